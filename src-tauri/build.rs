@@ -13,7 +13,10 @@ fn main() {
         // 检查 models 资源是否存在
         check_models_resources(&manifest_path);
     }
-    tauri_build::build()
+
+    // 无界面构建（未启用 desktop 特性）不需要 Tauri 的资源和上下文
+    #[cfg(feature = "desktop")]
+    tauri_build::build();
 }
 
 /// 检查 models 资源目录是否存在
@@ -178,12 +178,21 @@ impl NativeAgent {
             model: model.clone(),
             messages,
             stream: false,
+            stream_options: None,
             temperature: self.config.temperature,
             max_tokens: self.config.max_tokens,
             top_p: None,
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         // 对于自定义 Provider，使用 provider 特定路由
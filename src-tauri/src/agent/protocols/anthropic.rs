@@ -88,8 +88,8 @@ impl AnthropicProtocol {
                             "properties": {}
                         })),
                     }),
-                    // WebSearch 工具不支持转换为 Anthropic 格式，跳过
-                    Tool::WebSearch | Tool::WebSearch20250305 => None,
+                    // WebSearch、代码执行工具不支持转换为 Anthropic 格式，跳过
+                    Tool::WebSearch | Tool::WebSearch20250305 | Tool::CodeExecution => None,
                 })
                 .collect()
         })
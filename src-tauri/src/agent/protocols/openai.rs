@@ -374,6 +374,7 @@ impl Protocol for OpenAIProtocol {
             model: model.to_string(),
             messages: chat_messages,
             stream: true,
+            stream_options: None,
             temperature: config.temperature,
             max_tokens: config.max_tokens,
             top_p: None,
@@ -384,6 +385,14 @@ impl Protocol for OpenAIProtocol {
                 None
             },
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let url = format!("{}{}", base_url, self.endpoint());
@@ -450,6 +459,7 @@ impl Protocol for OpenAIProtocol {
             model: model.to_string(),
             messages: chat_messages,
             stream: true,
+            stream_options: None,
             temperature: config.temperature,
             max_tokens: config.max_tokens,
             top_p: None,
@@ -460,6 +470,14 @@ impl Protocol for OpenAIProtocol {
                 None
             },
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let url = format!("{}{}", base_url, self.endpoint());
@@ -5,6 +5,7 @@
 
 use super::registry::Tool;
 use super::types::{JsonSchema, PropertySchema, ToolDefinition, ToolError, ToolResult};
+use crate::app::FrontendHandle;
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -67,7 +68,7 @@ pub struct TermScrollbackTool {
     /// 默认超时时间（秒）
     timeout_secs: u64,
     /// Tauri AppHandle（用于发送事件）
-    app_handle: Arc<RwLock<Option<tauri::AppHandle>>>,
+    app_handle: Arc<RwLock<Option<FrontendHandle>>>,
 }
 
 impl TermScrollbackTool {
@@ -87,7 +88,7 @@ impl TermScrollbackTool {
     }
 
     /// 设置 Tauri AppHandle
-    pub fn set_app_handle(&self, handle: tauri::AppHandle) {
+    pub fn set_app_handle(&self, handle: FrontendHandle) {
         let mut app_handle = self.app_handle.write();
         *app_handle = Some(handle);
         eprintln!("[TermScrollbackTool] AppHandle 已设置");
@@ -161,6 +162,7 @@ impl TermScrollbackTool {
             );
 
             if let Some(handle) = app_handle.as_ref() {
+                #[cfg(feature = "desktop")]
                 use tauri::Emitter;
                 eprintln!("[TermScrollbackTool] 尝试发送事件到前端: {}", request_id);
                 if let Err(e) = handle.emit("term_get_scrollback_request", &request) {
@@ -320,7 +322,7 @@ pub fn get_term_scrollback_tool() -> Arc<TermScrollbackTool> {
 }
 
 /// 设置全局 TermScrollbackTool 的 AppHandle
-pub fn set_term_scrollback_tool_app_handle(handle: tauri::AppHandle) {
+pub fn set_term_scrollback_tool_app_handle(handle: FrontendHandle) {
     eprintln!("[TermScrollbackTool] 设置全局 AppHandle");
     tracing::info!("[TermScrollbackTool] 设置全局 AppHandle");
 
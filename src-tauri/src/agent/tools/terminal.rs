@@ -12,6 +12,7 @@
 
 use super::registry::Tool;
 use super::types::{JsonSchema, PropertySchema, ToolDefinition, ToolError, ToolResult};
+use crate::app::FrontendHandle;
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -101,7 +102,7 @@ pub struct TerminalTool {
     /// 默认超时时间（秒）
     timeout_secs: u64,
     /// Tauri AppHandle（用于发送事件）
-    app_handle: Arc<RwLock<Option<tauri::AppHandle>>>,
+    app_handle: Arc<RwLock<Option<FrontendHandle>>>,
 }
 
 impl TerminalTool {
@@ -122,7 +123,7 @@ impl TerminalTool {
     }
 
     /// 设置 Tauri AppHandle
-    pub fn set_app_handle(&self, handle: tauri::AppHandle) {
+    pub fn set_app_handle(&self, handle: FrontendHandle) {
         let mut app_handle = self.app_handle.write();
         *app_handle = Some(handle);
         eprintln!("[TerminalTool] AppHandle 已设置");
@@ -234,6 +235,7 @@ impl TerminalTool {
             );
 
             if let Some(handle) = app_handle.as_ref() {
+                #[cfg(feature = "desktop")]
                 use tauri::Emitter;
                 eprintln!("[TerminalTool] 尝试发送事件到前端: {}", request_id);
                 if let Err(e) = handle.emit("terminal_command_request", &request) {
@@ -419,7 +421,7 @@ pub fn get_terminal_tool() -> Arc<TerminalTool> {
 }
 
 /// 设置全局 TerminalTool 的 AppHandle
-pub fn set_terminal_tool_app_handle(handle: tauri::AppHandle) {
+pub fn set_terminal_tool_app_handle(handle: FrontendHandle) {
     eprintln!("[TerminalTool] 设置全局 AppHandle");
     tracing::info!("[TerminalTool] 设置全局 AppHandle");
 
@@ -2,54 +2,61 @@
 //!
 //! 包含配置验证、状态初始化等启动逻辑。
 
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-
-use crate::agent::NativeAgentState;
-use crate::commands::api_key_provider_cmd::ApiKeyProviderServiceState;
-use crate::commands::connect_cmd::ConnectStateWrapper;
-use crate::commands::flow_monitor_cmd::{
-    BatchOperationsState, BookmarkManagerState, EnhancedStatsServiceState, FlowInterceptorState,
-    FlowMonitorState, FlowQueryServiceState, FlowReplayerState, QuickFilterManagerState,
-    SessionManagerState,
-};
-use crate::commands::machine_id_cmd::MachineIdState;
-use crate::commands::model_registry_cmd::ModelRegistryState;
-use crate::commands::orchestrator_cmd::OrchestratorState;
-use crate::commands::plugin_cmd::PluginManagerState;
-use crate::commands::plugin_install_cmd::PluginInstallerState;
-use crate::commands::provider_pool_cmd::{CredentialSyncServiceState, ProviderPoolServiceState};
-use crate::commands::resilience_cmd::ResilienceConfigState;
-use crate::commands::session_files_cmd::SessionFilesState;
-use crate::commands::skill_cmd::SkillServiceState;
-use crate::commands::terminal_cmd::TerminalManagerState;
-use crate::commands::webview_cmd::{WebviewManagerState, WebviewManagerWrapper};
-use crate::config::{self, Config, ConfigManager, GlobalConfigManager, GlobalConfigManagerState};
-use crate::database::{self, DbConnection};
-use crate::flow_monitor::{
-    BatchOperations, BookmarkManager, EnhancedStatsService, FlowFileStore, FlowInterceptor,
-    FlowMonitor, FlowMonitorConfig, FlowQueryService, FlowReplayer, InterceptConfig,
-    QuickFilterManager, RotationConfig, SessionManager,
-};
-use crate::logger;
-use crate::plugin;
-use crate::server;
-use crate::services::api_key_provider_service::ApiKeyProviderService;
-use crate::services::provider_pool_service::ProviderPoolService;
-use crate::services::skill_service::SkillService;
-use crate::services::token_cache_service::TokenCacheService;
-use crate::services::update_check_service::UpdateCheckServiceState;
+
+use crate::config::{self, Config, ConfigManager};
 use crate::telemetry;
 
-use super::types::{AppState, LogState, TokenCacheServiceState};
-use super::utils::{generate_api_key, is_non_local_bind, is_valid_bind_host};
+use super::utils::generate_api_key;
+
+// 桌面应用的状态初始化（`init_states`）依赖 Tauri 命令模块中的状态类型
+#[cfg(feature = "desktop")]
+use {
+    super::types::{AppState, LogState, TokenCacheServiceState},
+    crate::agent::NativeAgentState,
+    crate::commands::api_key_provider_cmd::ApiKeyProviderServiceState,
+    crate::commands::connect_cmd::ConnectStateWrapper,
+    crate::commands::flow_monitor_cmd::{
+        BatchOperationsState, BookmarkManagerState, EnhancedStatsServiceState,
+        FlowInterceptorState, FlowMonitorState, FlowQueryServiceState, FlowReplayerState,
+        QuickFilterManagerState, SessionManagerState,
+    },
+    crate::commands::machine_id_cmd::MachineIdState,
+    crate::commands::model_registry_cmd::ModelRegistryState,
+    crate::commands::orchestrator_cmd::OrchestratorState,
+    crate::commands::plugin_cmd::PluginManagerState,
+    crate::commands::plugin_install_cmd::PluginInstallerState,
+    crate::commands::provider_pool_cmd::{CredentialSyncServiceState, ProviderPoolServiceState},
+    crate::commands::resilience_cmd::ResilienceConfigState,
+    crate::commands::session_files_cmd::SessionFilesState,
+    crate::commands::skill_cmd::SkillServiceState,
+    crate::commands::terminal_cmd::TerminalManagerState,
+    crate::commands::webview_cmd::{WebviewManagerState, WebviewManagerWrapper},
+    crate::config::{GlobalConfigManager, GlobalConfigManagerState},
+    crate::database::{self, DbConnection},
+    crate::flow_monitor::{
+        BatchOperations, BookmarkManager, EnhancedStatsService, FlowFileStore, FlowInterceptor,
+        FlowMonitor, FlowMonitorConfig, FlowQueryService, FlowReplayer, InterceptConfig,
+        QuickFilterManager, RotationConfig, SessionManager,
+    },
+    crate::logger,
+    crate::plugin,
+    crate::server,
+    crate::services::api_key_provider_service::ApiKeyProviderService,
+    crate::services::provider_pool_service::ProviderPoolService,
+    crate::services::skill_service::SkillService,
+    crate::services::token_cache_service::TokenCacheService,
+    crate::services::update_check_service::UpdateCheckServiceState,
+    tokio::sync::RwLock,
+};
 
 /// 配置验证错误
 #[derive(Debug)]
 pub enum ConfigError {
     LoadFailed(String),
     SaveFailed(String),
-    InvalidHost,
+    InvalidHost(String),
     DefaultApiKeyWithNonLocalBind,
     TlsNotSupported,
     RemoteManagementNotSupported,
@@ -60,12 +67,7 @@ impl std::fmt::Display for ConfigError {
         match self {
             ConfigError::LoadFailed(e) => write!(f, "配置加载失败: {}", e),
             ConfigError::SaveFailed(e) => write!(f, "配置保存失败: {}", e),
-            ConfigError::InvalidHost => {
-                write!(
-                    f,
-                    "无效的监听地址。允许的地址：127.0.0.1、localhost、::1、0.0.0.0、::"
-                )
-            }
+            ConfigError::InvalidHost(e) => write!(f, "{}", e),
             ConfigError::DefaultApiKeyWithNonLocalBind => write!(
                 f,
                 "监听所有网络接口 (0.0.0.0 或 ::) 时，必须设置非默认的 API Key"
@@ -82,11 +84,6 @@ impl std::fmt::Display for ConfigError {
 pub fn load_and_validate_config() -> Result<Config, ConfigError> {
     let mut config = config::load_config().map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
 
-    // 验证主机地址
-    if !is_valid_bind_host(&config.server.host) {
-        return Err(ConfigError::InvalidHost);
-    }
-
     // 如果使用默认 API key，自动生成新密钥
     if config.server.api_key == config::DEFAULT_API_KEY {
         let new_key = generate_api_key();
@@ -95,6 +92,46 @@ pub fn load_and_validate_config() -> Result<Config, ConfigError> {
         tracing::info!("检测到默认 API key，已自动生成并保存新密钥");
     }
 
+    validate_config(&config)?;
+    Ok(config)
+}
+
+/// 加载并验证指定路径的配置（无界面模式）
+///
+/// 文件不存在时使用默认配置；使用默认 API key 时自动生成新密钥并写回该文件。
+pub fn load_and_validate_config_from(path: &Path) -> Result<Config, ConfigError> {
+    let mut manager =
+        ConfigManager::load(path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+
+    if manager.config().server.api_key == config::DEFAULT_API_KEY {
+        manager.config_mut().server.api_key = generate_api_key();
+        manager
+            .save()
+            .map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        tracing::info!("检测到默认 API key，已自动生成并保存到 {}", path.display());
+    }
+
+    let config = manager.config().clone();
+    validate_config(&config)?;
+    Ok(config)
+}
+
+/// 验证配置中启动服务器所需的部分
+fn validate_config(config: &Config) -> Result<(), ConfigError> {
+    // 验证主机地址（包括主端口的额外地址和额外监听端口）
+    let hosts = std::iter::once(config.server.host.as_str())
+        .chain(config.server.additional_hosts.iter().map(String::as_str))
+        .chain(
+            config
+                .server
+                .listeners
+                .iter()
+                .map(|listener| listener.host_or(&config.server.host)),
+        );
+    for host in hosts {
+        config::validate_bind_host(host).map_err(ConfigError::InvalidHost)?;
+    }
+
     // 检查 TLS 配置
     if config.server.tls.enable {
         return Err(ConfigError::TlsNotSupported);
@@ -105,10 +142,11 @@ pub fn load_and_validate_config() -> Result<Config, ConfigError> {
         return Err(ConfigError::RemoteManagementNotSupported);
     }
 
-    Ok(config)
+    Ok(())
 }
 
 /// 应用状态集合
+#[cfg(feature = "desktop")]
 pub struct AppStates {
     pub state: AppState,
     pub logs: LogState,
@@ -152,6 +190,7 @@ pub struct AppStates {
 }
 
 /// 初始化所有应用状态
+#[cfg(feature = "desktop")]
 pub fn init_states(config: &Config) -> Result<AppStates, String> {
     // 核心状态
     let state: AppState = Arc::new(RwLock::new(server::ServerState::new(config.clone())));
@@ -159,6 +198,7 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
 
     // 数据库
     let db = database::init_database().map_err(|e| format!("数据库初始化失败: {}", e))?;
+    database::init_credential_storage(&config.credential_storage);
 
     // 服务状态
     let skill_service =
@@ -194,7 +234,13 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     let plugin_rpc_manager_state = crate::commands::plugin_rpc_cmd::PluginRpcManagerState::new();
 
     // 遥测系统
-    let (telemetry_state, shared_stats, shared_tokens, shared_logger) = init_telemetry(config)?;
+    let (shared_stats, shared_tokens, shared_logger) = init_telemetry(config)?;
+    let telemetry_state = crate::commands::telemetry_cmd::TelemetryState::with_shared(
+        shared_stats.clone(),
+        shared_tokens.clone(),
+        Some(shared_logger.clone()),
+    )
+    .map_err(|e| format!("TelemetryState 初始化失败: {}", e))?;
 
     // Flow Monitor 系统（根据插件安装状态启用/禁用）
     let (
@@ -293,6 +339,7 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
 }
 
 /// 初始化插件安装器
+#[cfg(feature = "desktop")]
 fn init_plugin_installer() -> Result<PluginInstallerState, String> {
     let db_path = database::get_db_path().map_err(|e| format!("获取数据库路径失败: {}", e))?;
     let plugins_dir = dirs::data_dir()
@@ -331,12 +378,11 @@ fn init_plugin_installer() -> Result<PluginInstallerState, String> {
     }
 }
 
-/// 初始化遥测系统
-fn init_telemetry(
+/// 初始化遥测系统（统计聚合器、Token 追踪器和请求日志，由服务器与前端命令共享）
+pub(super) fn init_telemetry(
     config: &Config,
 ) -> Result<
     (
-        crate::commands::telemetry_cmd::TelemetryState,
         Arc<parking_lot::RwLock<telemetry::StatsAggregator>>,
         Arc<parking_lot::RwLock<telemetry::TokenTracker>>,
         Arc<telemetry::RequestLogger>,
@@ -360,19 +406,13 @@ fn init_telemetry(
             .map_err(|e| format!("RequestLogger 初始化失败: {}", e))?,
     );
 
-    let telemetry_state = crate::commands::telemetry_cmd::TelemetryState::with_shared(
-        shared_stats.clone(),
-        shared_tokens.clone(),
-        Some(shared_logger.clone()),
-    )
-    .map_err(|e| format!("TelemetryState 初始化失败: {}", e))?;
-
-    Ok((telemetry_state, shared_stats, shared_tokens, shared_logger))
+    Ok((shared_stats, shared_tokens, shared_logger))
 }
 
 /// 初始化 Flow Monitor 系统
 ///
 /// 如果 flow-monitor 插件已安装，则启用监控功能；否则禁用。
+#[cfg(feature = "desktop")]
 #[allow(clippy::type_complexity)]
 fn init_flow_monitor(
     provider_pool_service_state: &ProviderPoolServiceState,
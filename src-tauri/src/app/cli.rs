@@ -0,0 +1,390 @@
+//! 命令行子命令
+//!
+//! - `serve`：无界面模式运行 HTTP 服务器
+//! - `install` / `uninstall`：安装 / 卸载系统服务
+//! - `status` / `logs`：通过管理 API 查看运行状态和实时日志
+//!
+//! 不带子命令时启动桌面应用；`proxycast-server`（不含 Tauri 的无界面构建）
+//! 不带子命令时等同于 `serve`。
+
+use std::path::{Path, PathBuf};
+
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::config::{Config, ConfigManager};
+use crate::logger::LogEntry;
+
+use super::{headless, service};
+
+const USAGE: &str = "用法: proxycast <command> [options]
+
+命令:
+  serve      无界面模式运行 API 服务器
+  install    安装为系统服务（开机自动运行 serve）
+  uninstall  卸载系统服务
+  status     查看服务运行状态
+  logs       查看实时日志（Ctrl+C 退出）
+
+选项:
+  -c, --config <path>  配置文件路径（默认使用桌面版的配置文件）
+  -n, --lines <n>      logs: 先输出的最近日志条数（默认 50）
+      --level <level>  logs: 最低日志级别（debug / info / warn / error）
+  -h, --help           显示帮助信息";
+
+/// 子命令列表
+const COMMANDS: &[&str] = &["serve", "install", "uninstall", "status", "logs"];
+
+/// `logs` 默认先输出的最近日志条数
+const DEFAULT_LOG_LINES: usize = 50;
+
+/// 子命令选项
+#[derive(Debug, PartialEq, Eq)]
+pub struct CliOptions {
+    /// 配置文件路径（为空时使用默认路径）
+    pub config_path: Option<PathBuf>,
+    pub help: bool,
+    /// `logs` 先输出的最近日志条数
+    pub lines: usize,
+    /// `logs` 最低日志级别
+    pub level: Option<String>,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        Self {
+            config_path: None,
+            help: false,
+            lines: DEFAULT_LOG_LINES,
+            level: None,
+        }
+    }
+}
+
+/// 第一个参数是否为子命令（含帮助参数）
+pub fn is_subcommand(args: &[String]) -> bool {
+    args.first().is_some_and(|command| {
+        matches!(command.as_str(), "-h" | "--help" | "help") || COMMANDS.contains(&command.as_str())
+    })
+}
+
+/// 连接到启动进程的控制台
+///
+/// Windows release 构建使用 GUI 子系统，不会自动分配控制台；
+/// 从终端执行子命令时需要连接父进程的控制台，否则输出不可见。
+pub fn attach_parent_console() {
+    #[cfg(windows)]
+    unsafe {
+        winapi::um::wincon::AttachConsole(winapi::um::wincon::ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// 执行命令行子命令，返回进程退出码
+///
+/// 第一个参数不是子命令时返回 None（启动桌面应用）。
+pub fn dispatch(args: &[String]) -> Option<i32> {
+    if !is_subcommand(args) {
+        return None;
+    }
+    let command = args[0].as_str();
+    if matches!(command, "-h" | "--help" | "help") {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    let options = match parse_options(command, &args[1..]) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return Some(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    let config_path = resolve_config_path(options.config_path.clone());
+    let result = match command {
+        "serve" => return Some(headless::serve(config_path)),
+        "install" => service::install(&config_path).map_err(CliError::from),
+        "uninstall" => service::uninstall().map_err(CliError::from),
+        "status" => block_on(status(&config_path)),
+        _ => block_on(logs(&config_path, &options)),
+    };
+    Some(match result {
+        Ok(message) => {
+            if !message.is_empty() {
+                println!("{}", message);
+            }
+            0
+        }
+        Err(CliError::NotRunning(message)) => {
+            eprintln!("{}", message);
+            3
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("{}", message);
+            1
+        }
+    })
+}
+
+/// 子命令错误
+#[derive(Debug)]
+pub enum CliError {
+    /// 服务未运行（退出码 3，与 `systemctl status` 一致）
+    NotRunning(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+/// 解析子命令选项（不含子命令本身）
+pub fn parse_options(command: &str, args: &[String]) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| format!("{} 缺少参数值", name))
+        };
+        match name {
+            "-h" | "--help" => options.help = true,
+            "-c" | "--config" => options.config_path = Some(PathBuf::from(value()?)),
+            "-n" | "--lines" if command == "logs" => {
+                let raw = value()?;
+                options.lines = raw
+                    .parse()
+                    .map_err(|_| format!("无效的日志条数: {}", raw))?;
+            }
+            "--level" if command == "logs" => options.level = Some(value()?),
+            other => return Err(format!("{} 不支持参数: {}", command, other)),
+        }
+    }
+    Ok(options)
+}
+
+/// 解析配置文件路径（展开 `~`，相对路径转换为绝对路径，为空时使用默认路径）
+pub fn resolve_config_path(path: Option<PathBuf>) -> PathBuf {
+    match path {
+        Some(path) => {
+            let path = crate::config::expand_tilde(path);
+            if path.is_relative() {
+                std::env::current_dir()
+                    .map(|dir| dir.join(&path))
+                    .unwrap_or(path)
+            } else {
+                path
+            }
+        }
+        None => ConfigManager::default_config_path(),
+    }
+}
+
+fn block_on<F: std::future::Future<Output = Result<String, CliError>>>(
+    future: F,
+) -> Result<String, CliError> {
+    tokio::runtime::Runtime::new()
+        .map_err(|e| format!("创建异步运行时失败: {}", e))?
+        .block_on(future)
+}
+
+/// 管理 API 客户端
+struct ManagementClient {
+    base_url: String,
+    secret_key: String,
+    client: reqwest::Client,
+}
+
+impl ManagementClient {
+    fn from_config(config: &Config) -> Result<Self, CliError> {
+        let secret_key = config
+            .remote_management
+            .secret_key
+            .clone()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                "管理 API 未启用：请在配置文件中设置 remote_management.secret_key".to_string()
+            })?;
+        Ok(Self {
+            base_url: format!(
+                "{}{}",
+                local_base_url(&config.server.host, config.server.port),
+                config.server.normalized_base_path()
+            ),
+            secret_key,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    async fn get(&self, path: &str) -> Result<reqwest::Response, CliError> {
+        let url = format!("{}{}", self.base_url, path);
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(&self.secret_key)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_connect() {
+                    CliError::NotRunning(format!("ProxyCast 未运行（无法连接 {}）", self.base_url))
+                } else {
+                    CliError::Failed(format!("请求 {} 失败: {}", url, e))
+                }
+            })?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("请求 {} 失败: {} {}", url, status, body).into());
+        }
+        Ok(resp)
+    }
+}
+
+/// 本机访问服务器的地址（监听所有接口时使用回环地址）
+fn local_base_url(host: &str, port: u16) -> String {
+    let host = match host {
+        "0.0.0.0" | "" => "127.0.0.1",
+        "::" => "::1",
+        host => host,
+    };
+    if host.contains(':') && !host.starts_with('[') {
+        format!("http://[{}]:{}", host, port)
+    } else {
+        format!("http://{}:{}", host, port)
+    }
+}
+
+/// 读取配置（只读，不生成或写回配置文件）
+fn load_config(config_path: &Path) -> Result<Config, CliError> {
+    if !config_path.exists() {
+        return Err(format!("配置文件不存在: {}", config_path.display()).into());
+    }
+    ConfigManager::load(config_path)
+        .map(|manager| manager.config().clone())
+        .map_err(|e| CliError::Failed(format!("配置加载失败: {}", e)))
+}
+
+/// `status`：查询管理 API 状态
+async fn status(config_path: &Path) -> Result<String, CliError> {
+    let config = load_config(config_path)?;
+    let client = ManagementClient::from_config(&config)?;
+    let status: Value = client
+        .get("/v0/management/status")
+        .await?
+        .json()
+        .await
+        .map_err(|e| format!("解析状态响应失败: {}", e))?;
+    Ok(format!(
+        "ProxyCast 运行中\n  地址: {}\n  版本: {}\n  默认 Provider: {}\n  请求数: {}",
+        client.base_url,
+        status["version"].as_str().unwrap_or("-"),
+        status["default_provider"].as_str().unwrap_or("-"),
+        status["requests"].as_u64().unwrap_or(0)
+    ))
+}
+
+/// `logs`：订阅管理 API 的实时日志
+async fn logs(config_path: &Path, options: &CliOptions) -> Result<String, CliError> {
+    let config = load_config(config_path)?;
+    let client = ManagementClient::from_config(&config)?;
+    let mut path = format!("/v0/management/logs/stream?backlog={}", options.lines);
+    if let Some(level) = &options.level {
+        path.push_str(&format!("&level={}", urlencoding::encode(level)));
+    }
+    let mut stream = client.get(&path).await?.bytes_stream();
+
+    let mut buffer = String::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("读取日志流失败: {}", e))?;
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(pos) = buffer.find('\n') {
+            let line: String = buffer.drain(..=pos).collect();
+            if let Some(output) = format_log_line(line.trim_end()) {
+                println!("{}", output);
+            }
+        }
+    }
+    Err(CliError::NotRunning("日志流已断开".to_string()))
+}
+
+/// 格式化 SSE 数据行（`lagged` 事件的数据为跳过的日志条数）
+fn format_log_line(line: &str) -> Option<String> {
+    let data = line.strip_prefix("data:")?.trim_start();
+    if let Ok(entry) = serde_json::from_str::<LogEntry>(data) {
+        return Some(format!(
+            "{} [{}] {}",
+            entry.timestamp,
+            entry.level.to_uppercase(),
+            entry.message
+        ));
+    }
+    data.parse::<u64>()
+        .ok()
+        .map(|skipped| format!("... 输出过慢，跳过 {} 条日志", skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse_options("serve", &[]).unwrap(), CliOptions::default());
+        assert_eq!(
+            parse_options("serve", &args(&["--config", "/etc/proxycast.yaml"]))
+                .unwrap()
+                .config_path,
+            Some(PathBuf::from("/etc/proxycast.yaml"))
+        );
+        let logs = parse_options(
+            "logs",
+            &args(&["--config=a.yaml", "-n", "10", "--level=warn"]),
+        )
+        .unwrap();
+        assert_eq!(logs.config_path, Some(PathBuf::from("a.yaml")));
+        assert_eq!(logs.lines, 10);
+        assert_eq!(logs.level.as_deref(), Some("warn"));
+        assert!(parse_options("status", &args(&["-h"])).unwrap().help);
+        assert!(parse_options("serve", &args(&["--config"])).is_err());
+        assert!(parse_options("serve", &args(&["--level", "warn"])).is_err());
+        assert!(parse_options("logs", &args(&["-n", "many"])).is_err());
+        assert!(dispatch(&args(&["--minimized"])).is_none());
+        assert!(is_subcommand(&args(&["status"])));
+        assert!(is_subcommand(&args(&["--help"])));
+        assert!(!is_subcommand(&args(&[])));
+    }
+
+    #[test]
+    fn test_local_base_url_and_log_line() {
+        assert_eq!(local_base_url("0.0.0.0", 8999), "http://127.0.0.1:8999");
+        assert_eq!(local_base_url("::", 8999), "http://[::1]:8999");
+        assert_eq!(local_base_url("localhost", 80), "http://localhost:80");
+
+        assert_eq!(
+            format_log_line(
+                r#"data: {"timestamp":"2026-01-01T00:00:00Z","level":"warn","message":"hi"}"#
+            )
+            .as_deref(),
+            Some("2026-01-01T00:00:00Z [WARN] hi")
+        );
+        assert!(format_log_line("data: 3").unwrap().contains('3'));
+        assert!(format_log_line("event: log").is_none());
+    }
+}
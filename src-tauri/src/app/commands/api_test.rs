@@ -94,7 +94,11 @@ pub async fn check_api_compatibility(
             ("claude-sonnet-4-5", "tool_call"),
         ],
         ProviderType::IFlow => vec![("gpt-4o", "basic"), ("gpt-4o", "tool_call")],
-        ProviderType::OpenAI | ProviderType::Claude => vec![],
+        ProviderType::OpenAI
+        | ProviderType::Claude
+        | ProviderType::OpenRouter
+        | ProviderType::Mistral
+        | ProviderType::DeepSeek => vec![],
         // API Key Provider 类型 - 暂不支持自动测试
         ProviderType::Anthropic
         | ProviderType::AzureOpenai
@@ -124,6 +128,7 @@ pub async fn check_api_compatibility(
                     max_tokens: Some(100),
                     top_p: None,
                     stream: false,
+                    stream_options: None,
                     tools: Some(vec![crate::models::openai::Tool::Function {
                         function: crate::models::openai::FunctionDef {
                             name: "calculator".to_string(),
@@ -142,6 +147,14 @@ pub async fn check_api_compatibility(
                     }]),
                     tool_choice: None,
                     reasoning_effort: None,
+                    stop: None,
+                    presence_penalty: None,
+                    frequency_penalty: None,
+                    top_k: None,
+                    user: None,
+                    n: None,
+                    logprobs: None,
+                    top_logprobs: None,
                 }
             }
             _ => {
@@ -160,9 +173,18 @@ pub async fn check_api_compatibility(
                     max_tokens: Some(10),
                     top_p: None,
                     stream: false,
+                    stream_options: None,
                     tools: None,
                     tool_choice: None,
                     reasoning_effort: None,
+                    stop: None,
+                    presence_penalty: None,
+                    frequency_penalty: None,
+                    top_k: None,
+                    user: None,
+                    n: None,
+                    logprobs: None,
+                    top_logprobs: None,
                 }
             }
         };
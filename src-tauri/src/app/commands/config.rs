@@ -3,7 +3,7 @@
 //! 包含配置读取、保存、Provider 设置等命令。
 
 use crate::app::types::{AppState, LogState};
-use crate::app::utils::{is_non_local_bind, is_valid_bind_host};
+use crate::app::utils::is_non_local_bind;
 use crate::config::{
     self,
     observer::{ConfigChangeEvent, RoutingChangeEvent},
@@ -17,6 +17,30 @@ pub async fn get_config(state: tauri::State<'_, AppState>) -> Result<config::Con
     Ok(s.config.clone())
 }
 
+/// 验证响应签名
+///
+/// 未指定 `key` 时使用当前配置的响应签名密钥。
+#[tauri::command]
+pub async fn verify_response_signature(
+    state: tauri::State<'_, AppState>,
+    body: String,
+    signature: String,
+    key: Option<String>,
+) -> Result<bool, String> {
+    let key = match key.filter(|k| !k.is_empty()) {
+        Some(key) => key,
+        None => state.read().await.config.response_signing.key.clone(),
+    };
+    if key.is_empty() {
+        return Err("未配置响应签名密钥".to_string());
+    }
+    Ok(crate::middleware::response_signing::verify(
+        key.as_bytes(),
+        body.as_bytes(),
+        &signature,
+    ))
+}
+
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
@@ -28,11 +52,11 @@ pub async fn save_config(
     tracing::info!("[CONFIG] 保存配置请求: host={}, port={}", host, config.server.port);
 
     // 验证绑定地址
-    if !is_valid_bind_host(&host) {
-        tracing::warn!("[CONFIG] 无效的监听地址: {}", host);
-        return Err(
-            "无效的监听地址。允许的地址：127.0.0.1、localhost、::1、0.0.0.0、:: 或局域网 IP".to_string(),
-        );
+    for host in std::iter::once(&host).chain(&config.server.additional_hosts) {
+        if let Err(e) = config::validate_bind_host(host) {
+            tracing::warn!("[CONFIG] {}", e);
+            return Err(e);
+        }
     }
 
     // 禁止开启远程管理
@@ -56,6 +80,26 @@ pub async fn save_config(
     }
 }
 
+/// 立即重新加载配置文件（不依赖文件监控事件）
+#[tauri::command]
+pub async fn reload_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<config::ReloadReport, String> {
+    let reloader = state
+        .read()
+        .await
+        .config_reloader_ref
+        .read()
+        .clone()
+        .ok_or("服务器未运行，无法重新加载配置")?;
+
+    let result = reloader.reload().await;
+    if let config::ReloadResult::Success { .. } = result {
+        state.write().await.config = reloader.config();
+    }
+    Ok(result.report())
+}
+
 /// 获取默认 Provider
 #[tauri::command]
 pub async fn get_default_provider(state: tauri::State<'_, AppState>) -> Result<String, String> {
@@ -82,12 +126,9 @@ pub async fn set_default_provider(
         *dp = provider.clone();
     }
 
-    // 同时更新运行中服务器的 router（如果服务器正在运行）
-    if let Some(router_ref) = &s.router_ref {
-        if let Ok(provider_type) = provider.parse::<crate::ProviderType>() {
-            let mut router = router_ref.write().await;
-            router.set_default_provider(provider_type);
-        }
+    // 同时更新运行中服务器的配置快照（如果服务器正在运行）
+    if let Some(snapshot_ref) = &s.snapshot_ref {
+        snapshot_ref.rcu(|current| current.with_default_provider(&provider));
     }
 
     // 保存配置
@@ -83,7 +83,7 @@ pub async fn get_kiro_credentials(
     state: tauri::State<'_, AppState>,
 ) -> Result<KiroCredentialStatus, String> {
     let s = state.read().await;
-    let creds = &s.kiro_provider.credentials;
+    let creds = s.kiro_provider.credentials();
     let path = providers::kiro::KiroProvider::default_creds_path();
 
     Ok(KiroCredentialStatus {
@@ -103,7 +103,7 @@ pub async fn get_env_variables(
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<EnvVariable>, String> {
     let s = state.read().await;
-    let creds = &s.kiro_provider.credentials;
+    let creds = s.kiro_provider.credentials();
     let mut vars = Vec::new();
 
     // P0 安全修复：不再返回明文敏感凭证，仅返回 masked 版本
@@ -1,6 +1,6 @@
 //! 日志命令
 //!
-//! 包含日志查询和清理命令。
+//! 包含日志查询、清理和 tracing 过滤指令调整命令。
 
 use crate::app::types::LogState;
 use crate::logger;
@@ -17,3 +17,15 @@ pub async fn clear_logs(logs: tauri::State<'_, LogState>) -> Result<(), String>
     logs.write().await.clear();
     Ok(())
 }
+
+/// 获取当前 tracing 过滤指令
+#[tauri::command]
+pub async fn get_tracing_filter() -> Result<Option<String>, String> {
+    Ok(logger::tracing_filter())
+}
+
+/// 设置 tracing 过滤指令（如 `info,proxycast_lib::server=debug`）
+#[tauri::command]
+pub async fn set_tracing_filter(directives: String) -> Result<String, String> {
+    logger::set_tracing_filter(&directives)
+}
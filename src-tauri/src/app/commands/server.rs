@@ -66,3 +66,18 @@ pub async fn get_server_status(
 
     Ok(status)
 }
+
+/// 获取 WebSocket 连接统计（服务器未运行时返回空统计）
+#[tauri::command]
+pub async fn get_websocket_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<crate::websocket::WsStatsReport, String> {
+    let s = state.read().await;
+    Ok(s.ws_manager_ref
+        .as_ref()
+        .map(|manager| manager.report())
+        .unwrap_or_else(|| crate::websocket::WsStatsReport {
+            stats: Default::default(),
+            connections: Vec::new(),
+        }))
+}
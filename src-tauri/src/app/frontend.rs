@@ -0,0 +1,24 @@
+//! 前端事件句柄
+//!
+//! 桌面构建中即 `tauri::AppHandle`。无界面构建（未启用 `desktop` 特性）没有前端，
+//! 句柄类型不可构造，持有 `Option<FrontendHandle>` 的组件始终按"前端未连接"处理。
+
+#[cfg(feature = "desktop")]
+pub use tauri::AppHandle as FrontendHandle;
+
+/// 无界面构建中的前端句柄（不可构造）
+#[cfg(not(feature = "desktop"))]
+#[derive(Debug, Clone)]
+pub enum FrontendHandle {}
+
+#[cfg(not(feature = "desktop"))]
+impl FrontendHandle {
+    /// 与 `tauri::Emitter::emit` 的调用方式一致
+    pub fn emit<S: serde::Serialize + Clone>(
+        &self,
+        _event: &str,
+        _payload: S,
+    ) -> Result<(), String> {
+        match *self {}
+    }
+}
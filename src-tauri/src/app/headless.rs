@@ -0,0 +1,119 @@
+//! 无界面模式
+//!
+//! `proxycast serve [--config <path>]` 不创建 Tauri 应用和窗口，只启动 HTTP 服务器
+//! （包含配置热重载、遥测和管理 API），用于在服务器上部署。
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::database;
+use crate::logger;
+use crate::server::ServerState;
+use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::token_cache_service::TokenCacheService;
+
+use super::bootstrap;
+
+/// 运行 `serve` 子命令，返回进程退出码
+pub fn serve(config_path: PathBuf) -> i32 {
+    let config = match bootstrap::load_and_validate_config_from(&config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+
+    logger::init_tracing(&config.logging.level);
+    logger::set_pii_masking(&config.logging.pii_masking);
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("创建异步运行时失败: {}", err);
+            return 1;
+        }
+    };
+    match runtime.block_on(run(config, config_path)) {
+        Ok(()) => 0,
+        Err(err) => {
+            tracing::error!("[HEADLESS] {}", err);
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+/// 启动服务器并等待退出信号
+async fn run(config: crate::config::Config, config_path: PathBuf) -> Result<(), String> {
+    let db = database::init_database().map_err(|e| format!("数据库初始化失败: {}", e))?;
+    database::init_credential_storage(&config.credential_storage);
+    let logs = Arc::new(RwLock::new(logger::LogStore::with_config(&config.logging)));
+    let pool_service = Arc::new(ProviderPoolService::new());
+    let token_cache = Arc::new(TokenCacheService::new());
+    let (shared_stats, shared_tokens, shared_logger) = bootstrap::init_telemetry(&config)?;
+
+    match pool_service.get_overview(&db) {
+        Ok(overview) => {
+            let total: usize = overview.iter().map(|p| p.stats.total_count).sum();
+            tracing::info!("[HEADLESS] 凭证池已加载: 共 {} 个凭证", total);
+        }
+        Err(e) => tracing::warn!("[HEADLESS] 获取凭证池信息失败: {}", e),
+    }
+
+    tracing::info!("[HEADLESS] 使用配置文件: {}", config_path.display());
+    let mut state = ServerState::new(config);
+    state.config_path = config_path;
+    state
+        .start_with_telemetry(
+            logs,
+            pool_service,
+            token_cache,
+            Some(db),
+            Some(shared_stats),
+            Some(shared_tokens),
+            Some(shared_logger),
+        )
+        .await
+        .map_err(|e| format!("服务器启动失败: {}", e))?;
+
+    let status = state.status();
+    tracing::info!("[HEADLESS] 服务器已启动: {}:{}", status.host, status.port);
+    println!("ProxyCast 服务器已启动: {}:{}", status.host, status.port);
+
+    shutdown_signal().await;
+    tracing::info!("[HEADLESS] 收到退出信号，正在停止服务器");
+    state.stop().await;
+    Ok(())
+}
+
+/// 等待 Ctrl+C（Unix 上同时监听 SIGTERM）
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("[HEADLESS] 监听 Ctrl+C 失败: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("[HEADLESS] 监听 SIGTERM 失败: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
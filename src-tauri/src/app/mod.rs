@@ -10,17 +10,35 @@
 //! - `utils` - 辅助函数
 //! - `bootstrap` - 应用启动引导（配置验证、状态初始化）
 //! - `runner` - 应用运行器（Tauri Builder 配置和命令注册）
+//! - `cli` - 命令行子命令（serve / install / uninstall / status / logs）
+//! - `headless` - 无界面模式（`proxycast serve`，只启动 HTTP 服务器）
+//! - `service` - 系统服务安装和卸载（systemd / launchd / Windows 计划任务）
+//! - `frontend` - 前端事件句柄（无界面构建中不可构造）
+//!
+//! `commands`、`runner`、`setup`、`state` 只在启用 `desktop` 特性时编译。
 
 pub mod bootstrap;
+pub mod cli;
+#[cfg(feature = "desktop")]
 pub mod commands;
+mod frontend;
+pub mod headless;
+#[cfg(feature = "desktop")]
 pub mod runner;
+pub mod service;
+#[cfg(feature = "desktop")]
 mod setup;
+#[cfg(feature = "desktop")]
 mod state;
 mod types;
 mod utils;
 
+pub use frontend::FrontendHandle;
+#[cfg(feature = "desktop")]
 pub use runner::run;
+#[cfg(feature = "desktop")]
 pub use setup::setup_app;
+#[cfg(feature = "desktop")]
 pub use state::*;
 pub use types::*;
 pub use utils::*;
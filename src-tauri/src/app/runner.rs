@@ -35,6 +35,10 @@ pub fn run() {
         }
     };
 
+    // 初始化 tracing（过滤指令可在运行时通过管理 API / 命令调整）
+    crate::logger::init_tracing(&config.logging.level);
+    crate::logger::set_pii_masking(&config.logging.pii_masking);
+
     // 初始化所有应用状态
     let states = match bootstrap::init_states(&config) {
         Ok(s) => s,
@@ -196,6 +200,9 @@ pub fn run() {
             crate::agent::tools::set_term_scrollback_tool_app_handle(app.handle().clone());
             tracing::info!("[启动] TermScrollbackTool AppHandle 已设置");
 
+            // 设置告警服务的 AppHandle（用于桌面通知）
+            crate::services::alert_service::set_alert_app_handle(app.handle().clone());
+
             // 初始化托盘管理器
             // Requirements 1.4: 应用启动时显示停止状态图标
             match TrayManager::new(app.handle()) {
@@ -304,7 +311,7 @@ pub fn run() {
 
                 tauri::async_runtime::spawn(async move {
                     // 创建 ModelRegistryService
-                    let mut service = crate::services::model_registry_service::ModelRegistryService::new(db_clone);
+                    let mut service = crate::services::model_registry_service::ModelRegistryService::new(db_clone.clone());
                     // 设置资源目录路径
                     service.set_resource_dir(resource_dir);
 
@@ -318,6 +325,12 @@ pub fn run() {
                             {
                                 let mut guard = state.write().await;
                                 *guard = Some(service);
+
+                                // 启动 OpenRouter 模型目录定期同步
+                                crate::commands::model_registry_cmd::start_background_openrouter_sync(
+                                    state.inner().clone(),
+                                    db_clone,
+                                );
                             }
                         }
                         Err(e) => {
@@ -467,6 +480,9 @@ pub fn run() {
                                             "codex" => "Codex",
                                             "claude_oauth" => "Claude OAuth",
                                             "iflow" => "iFlow",
+                                            "openrouter" => "OpenRouter",
+                                            "mistral" => "Mistral",
+                                            "deepseek" => "DeepSeek",
                                             _ => &provider_overview.provider_type,
                                         };
                                     loaded_types.push(format!("{} ({} 个)", provider_name, count));
@@ -628,14 +644,17 @@ pub fn run() {
             app_commands::start_server,
             app_commands::stop_server,
             app_commands::get_server_status,
+            app_commands::get_websocket_stats,
             // Config commands (from app::commands)
             app_commands::get_config,
             app_commands::save_config,
+            app_commands::reload_config,
             app_commands::get_default_provider,
             app_commands::set_default_provider,
             app_commands::get_endpoint_providers,
             app_commands::set_endpoint_provider,
             app_commands::update_provider_env_vars,
+            app_commands::verify_response_signature,
             // Unified OAuth commands (new)
             commands::oauth_cmd::get_oauth_credentials,
             commands::oauth_cmd::reload_oauth_credentials,
@@ -674,6 +693,8 @@ pub fn run() {
             // Log commands (from app::commands)
             app_commands::get_logs,
             app_commands::clear_logs,
+            app_commands::get_tracing_filter,
+            app_commands::set_tracing_filter,
             // API test commands (from app::commands)
             app_commands::test_api,
             app_commands::get_available_models,
@@ -752,12 +773,21 @@ pub fn run() {
             commands::provider_pool_cmd::reset_provider_pool_health,
             commands::provider_pool_cmd::check_provider_pool_credential_health,
             commands::provider_pool_cmd::check_provider_pool_type_health,
+            commands::provider_pool_cmd::duplicate_provider_pool_credential,
+            commands::provider_pool_cmd::bulk_set_provider_pool_disabled,
+            commands::provider_pool_cmd::bulk_delete_unhealthy_provider_pool_credentials,
+            commands::provider_pool_cmd::bulk_check_provider_pool_health,
+            commands::provider_pool_cmd::detect_importable_credentials,
+            commands::provider_pool_cmd::import_detected_credentials,
             commands::provider_pool_cmd::add_kiro_oauth_credential,
             commands::provider_pool_cmd::add_kiro_from_json,
             commands::provider_pool_cmd::add_gemini_oauth_credential,
             commands::provider_pool_cmd::add_qwen_oauth_credential,
             commands::provider_pool_cmd::add_antigravity_oauth_credential,
             commands::provider_pool_cmd::add_openai_key_credential,
+            commands::provider_pool_cmd::add_openrouter_key_credential,
+            commands::provider_pool_cmd::add_mistral_key_credential,
+            commands::provider_pool_cmd::add_deepseek_key_credential,
             commands::provider_pool_cmd::add_claude_key_credential,
             commands::provider_pool_cmd::add_gemini_api_key_credential,
             commands::provider_pool_cmd::add_codex_oauth_credential,
@@ -827,6 +857,7 @@ pub fn run() {
             // Route commands
             commands::route_cmd::get_available_routes,
             commands::route_cmd::get_route_curl_examples,
+            commands::route_cmd::get_route_client_bundle,
             // Resilience config commands
             commands::resilience_cmd::get_retry_config,
             commands::resilience_cmd::update_retry_config,
@@ -844,7 +875,11 @@ pub fn run() {
             commands::telemetry_cmd::get_token_summary,
             commands::telemetry_cmd::get_token_stats_by_provider,
             commands::telemetry_cmd::get_token_stats_by_model,
+            commands::telemetry_cmd::get_token_stats_by_user,
             commands::telemetry_cmd::get_token_stats_by_day,
+            commands::telemetry_cmd::get_telemetry_history,
+            commands::telemetry_cmd::get_request_log_history,
+            commands::telemetry_cmd::get_user_usage_history,
             // Injection commands
             commands::injection_cmd::get_injection_config,
             commands::injection_cmd::set_injection_enabled,
@@ -854,6 +889,7 @@ pub fn run() {
             commands::injection_cmd::update_injection_rule,
             // Usage commands
             commands::usage_cmd::get_kiro_usage,
+            commands::usage_cmd::get_credential_quota,
             // Tray commands
             commands::tray_cmd::sync_tray_state,
             commands::tray_cmd::update_tray_server_status,
@@ -1161,6 +1197,7 @@ pub fn run() {
             commands::model_registry_cmd::get_models_by_tier,
             commands::model_registry_cmd::get_provider_alias_config,
             commands::model_registry_cmd::get_all_alias_configs,
+            commands::model_registry_cmd::sync_openrouter_models,
             // Model Management commands (动态模型列表)
             commands::model_cmd::get_credential_models,
             commands::model_cmd::refresh_credential_models,
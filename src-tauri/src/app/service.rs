@@ -0,0 +1,325 @@
+//! 系统服务管理
+//!
+//! 将 `proxycast serve` 注册为开机自动运行的后台服务：
+//! - Linux：systemd unit（root 用户安装为系统服务，否则安装为用户服务）
+//! - macOS：launchd LaunchAgent
+//! - Windows：登录时运行的计划任务
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// systemd 服务名称
+const SYSTEMD_UNIT: &str = "proxycast.service";
+/// launchd 服务标签
+const LAUNCHD_LABEL: &str = "com.proxycast.server";
+/// Windows 计划任务名称
+const WINDOWS_TASK: &str = "ProxyCast";
+
+/// 安装系统服务并立即启动，返回提示信息
+pub fn install(config_path: &Path) -> Result<String, String> {
+    // 提前验证配置（并在首次使用时生成配置文件），避免服务启动后反复失败
+    super::bootstrap::load_and_validate_config_from(config_path).map_err(|e| e.to_string())?;
+
+    let exe = std::env::current_exe().map_err(|e| format!("获取程序路径失败: {}", e))?;
+    match std::env::consts::OS {
+        "linux" => install_systemd(&exe, config_path),
+        "macos" => install_launchd(&exe, config_path),
+        "windows" => install_windows_task(&exe, config_path),
+        os => Err(format!("不支持在 {} 上安装系统服务", os)),
+    }
+}
+
+/// 停止并卸载系统服务，返回提示信息
+pub fn uninstall() -> Result<String, String> {
+    match std::env::consts::OS {
+        "linux" => uninstall_systemd(),
+        "macos" => uninstall_launchd(),
+        "windows" => uninstall_windows_task(),
+        os => Err(format!("不支持在 {} 上卸载系统服务", os)),
+    }
+}
+
+/// 运行外部命令，失败时返回命令输出
+fn run_command(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("执行 {} 失败: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} {} 失败: {}{}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim(),
+            String::from_utf8_lossy(&output.stdout).trim()
+        ))
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("创建目录 {} 失败: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))
+}
+
+// ============ systemd ============
+
+/// 是否以 root 身份安装（安装为系统服务）
+fn systemd_system_wide() -> bool {
+    whoami::username() == "root"
+}
+
+fn systemd_unit_path(system_wide: bool) -> Result<PathBuf, String> {
+    if system_wide {
+        return Ok(PathBuf::from("/etc/systemd/system").join(SYSTEMD_UNIT));
+    }
+    dirs::config_dir()
+        .map(|dir| dir.join("systemd").join("user").join(SYSTEMD_UNIT))
+        .ok_or_else(|| "无法确定用户配置目录".to_string())
+}
+
+fn systemctl(system_wide: bool, args: &[&str]) -> Result<(), String> {
+    let mut full_args = Vec::with_capacity(args.len() + 1);
+    if !system_wide {
+        full_args.push("--user");
+    }
+    full_args.extend_from_slice(args);
+    run_command("systemctl", &full_args)
+}
+
+/// systemd ExecStart 参数转义（加引号，`%` 需要写成 `%%`）
+fn systemd_quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+    )
+}
+
+/// 生成 systemd unit 文件内容
+pub fn render_systemd_unit(exe: &Path, config_path: &Path, system_wide: bool) -> String {
+    format!(
+        "[Unit]
+Description=ProxyCast AI API proxy
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart={} serve --config {}
+Restart=on-failure
+RestartSec=5
+
+[Install]
+WantedBy={}
+",
+        systemd_quote(&exe.to_string_lossy()),
+        systemd_quote(&config_path.to_string_lossy()),
+        if system_wide {
+            "multi-user.target"
+        } else {
+            "default.target"
+        }
+    )
+}
+
+fn install_systemd(exe: &Path, config_path: &Path) -> Result<String, String> {
+    let system_wide = systemd_system_wide();
+    let unit_path = systemd_unit_path(system_wide)?;
+    write_file(
+        &unit_path,
+        &render_systemd_unit(exe, config_path, system_wide),
+    )?;
+    systemctl(system_wide, &["daemon-reload"])?;
+    systemctl(system_wide, &["enable", "--now", SYSTEMD_UNIT])?;
+
+    let mut message = format!("已安装并启动 systemd 服务: {}", unit_path.display());
+    if !system_wide {
+        message.push_str("\n用户服务在注销后会停止，如需常驻请执行: loginctl enable-linger $USER");
+    }
+    Ok(message)
+}
+
+fn uninstall_systemd() -> Result<String, String> {
+    let system_wide = systemd_system_wide();
+    let unit_path = systemd_unit_path(system_wide)?;
+    if !unit_path.exists() {
+        return Err(format!("未安装 systemd 服务: {}", unit_path.display()));
+    }
+    if let Err(e) = systemctl(system_wide, &["disable", "--now", SYSTEMD_UNIT]) {
+        eprintln!("停止服务失败: {}", e);
+    }
+    std::fs::remove_file(&unit_path)
+        .map_err(|e| format!("删除 {} 失败: {}", unit_path.display(), e))?;
+    systemctl(system_wide, &["daemon-reload"])?;
+    Ok(format!("已卸载 systemd 服务: {}", unit_path.display()))
+}
+
+// ============ launchd ============
+
+fn launchd_plist_path() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|home| {
+            home.join("Library")
+                .join("LaunchAgents")
+                .join(format!("{}.plist", LAUNCHD_LABEL))
+        })
+        .ok_or_else(|| "无法确定用户主目录".to_string())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 生成 launchd plist 内容
+pub fn render_launchd_plist(exe: &Path, config_path: &Path, log_path: &Path) -> String {
+    let exe = xml_escape(&exe.to_string_lossy());
+    let config_path = xml_escape(&config_path.to_string_lossy());
+    let log_path = xml_escape(&log_path.to_string_lossy());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+        <string>serve</string>
+        <string>--config</string>
+        <string>{config_path}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log_path}</string>
+    <key>StandardErrorPath</key>
+    <string>{log_path}</string>
+</dict>
+</plist>
+"#
+    )
+}
+
+fn install_launchd(exe: &Path, config_path: &Path) -> Result<String, String> {
+    let plist_path = launchd_plist_path()?;
+    let log_path = dirs::home_dir()
+        .unwrap_or_default()
+        .join("Library")
+        .join("Logs")
+        .join("ProxyCast")
+        .join("server.log");
+    if let Some(parent) = log_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if plist_path.exists() {
+        // 重新安装：先卸载旧的服务定义
+        let _ = run_command("launchctl", &["unload", &plist_path.to_string_lossy()]);
+    }
+    write_file(
+        &plist_path,
+        &render_launchd_plist(exe, config_path, &log_path),
+    )?;
+    run_command("launchctl", &["load", "-w", &plist_path.to_string_lossy()])?;
+    Ok(format!(
+        "已安装并启动 launchd 服务: {}\n日志文件: {}",
+        plist_path.display(),
+        log_path.display()
+    ))
+}
+
+fn uninstall_launchd() -> Result<String, String> {
+    let plist_path = launchd_plist_path()?;
+    if !plist_path.exists() {
+        return Err(format!("未安装 launchd 服务: {}", plist_path.display()));
+    }
+    if let Err(e) = run_command(
+        "launchctl",
+        &["unload", "-w", &plist_path.to_string_lossy()],
+    ) {
+        eprintln!("停止服务失败: {}", e);
+    }
+    std::fs::remove_file(&plist_path)
+        .map_err(|e| format!("删除 {} 失败: {}", plist_path.display(), e))?;
+    Ok(format!("已卸载 launchd 服务: {}", plist_path.display()))
+}
+
+// ============ Windows ============
+
+fn install_windows_task(exe: &Path, config_path: &Path) -> Result<String, String> {
+    let task_command = format!(
+        "\"{}\" serve --config \"{}\"",
+        exe.display(),
+        config_path.display()
+    );
+    run_command(
+        "schtasks",
+        &[
+            "/Create",
+            "/F",
+            "/TN",
+            WINDOWS_TASK,
+            "/SC",
+            "ONLOGON",
+            "/RL",
+            "LIMITED",
+            "/TR",
+            &task_command,
+        ],
+    )?;
+    run_command("schtasks", &["/Run", "/TN", WINDOWS_TASK])?;
+    Ok(format!(
+        "已创建并启动计划任务: {}（登录时自动运行）",
+        WINDOWS_TASK
+    ))
+}
+
+fn uninstall_windows_task() -> Result<String, String> {
+    let _ = run_command("schtasks", &["/End", "/TN", WINDOWS_TASK]);
+    run_command("schtasks", &["/Delete", "/F", "/TN", WINDOWS_TASK])?;
+    Ok(format!("已删除计划任务: {}", WINDOWS_TASK))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_service_definitions() {
+        let exe = Path::new("/opt/proxy cast/proxycast");
+        let config = Path::new("/etc/proxycast/100%.yaml");
+
+        let unit = render_systemd_unit(exe, config, false);
+        assert!(unit.contains(
+            "ExecStart=\"/opt/proxy cast/proxycast\" serve --config \"/etc/proxycast/100%%.yaml\""
+        ));
+        assert!(unit.contains("WantedBy=default.target"));
+        assert!(render_systemd_unit(exe, config, true).contains("WantedBy=multi-user.target"));
+
+        let plist = render_launchd_plist(
+            exe,
+            Path::new("/Users/a&b/config.yaml"),
+            Path::new("/tmp/server.log"),
+        );
+        assert!(plist.contains("<string>com.proxycast.server</string>"));
+        assert!(plist.contains("<string>/opt/proxy cast/proxycast</string>"));
+        assert!(plist.contains("<string>/Users/a&amp;b/config.yaml</string>"));
+        assert!(plist.contains("<string>serve</string>"));
+    }
+}
@@ -131,6 +131,9 @@ async fn start_server_async(
                             "codex" => "Codex",
                             "claude_oauth" => "Claude OAuth",
                             "iflow" => "iFlow",
+                            "openrouter" => "OpenRouter",
+                            "mistral" => "Mistral",
+                            "deepseek" => "DeepSeek",
                             _ => &provider_overview.provider_type,
                         };
                         loaded_types.push(format!("{} ({} 个)", provider_name, count));
@@ -4,12 +4,14 @@
 
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+#[cfg(feature = "desktop")]
 use tauri::Runtime;
 use tokio::sync::RwLock;
 
 use crate::logger;
 use crate::server;
 use crate::services::token_cache_service::TokenCacheService;
+#[cfg(feature = "desktop")]
 use crate::tray::TrayManager;
 
 /// Provider 类型枚举
@@ -38,6 +40,11 @@ pub enum ProviderType {
     #[serde(rename = "aws_bedrock")]
     AwsBedrock,
     Ollama,
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+    Mistral,
+    #[serde(rename = "deepseek")]
+    DeepSeek,
 }
 
 impl std::fmt::Display for ProviderType {
@@ -58,6 +65,9 @@ impl std::fmt::Display for ProviderType {
             ProviderType::AzureOpenai => write!(f, "azure_openai"),
             ProviderType::AwsBedrock => write!(f, "aws_bedrock"),
             ProviderType::Ollama => write!(f, "ollama"),
+            ProviderType::OpenRouter => write!(f, "openrouter"),
+            ProviderType::Mistral => write!(f, "mistral"),
+            ProviderType::DeepSeek => write!(f, "deepseek"),
         }
     }
 }
@@ -82,6 +92,9 @@ impl std::str::FromStr for ProviderType {
             "azure_openai" | "azure-openai" => Ok(ProviderType::AzureOpenai),
             "aws_bedrock" | "aws-bedrock" => Ok(ProviderType::AwsBedrock),
             "ollama" => Ok(ProviderType::Ollama),
+            "openrouter" => Ok(ProviderType::OpenRouter),
+            "mistral" => Ok(ProviderType::Mistral),
+            "deepseek" => Ok(ProviderType::DeepSeek),
             _ => Err(format!("Invalid provider: {s}")),
         }
     }
@@ -97,6 +110,7 @@ pub type LogState = Arc<RwLock<logger::LogStore>>;
 pub struct TokenCacheServiceState(pub Arc<TokenCacheService>);
 
 /// TrayManager 状态封装
+#[cfg(feature = "desktop")]
 pub struct TrayManagerState<R: Runtime>(pub Arc<tokio::sync::RwLock<Option<TrayManager<R>>>>);
 
 #[cfg(test)]
@@ -148,6 +162,9 @@ mod tests {
         assert_eq!(ProviderType::Claude.to_string(), "claude");
         assert_eq!(ProviderType::Vertex.to_string(), "vertex");
         assert_eq!(ProviderType::GeminiApiKey.to_string(), "gemini_api_key");
+        assert_eq!(ProviderType::OpenRouter.to_string(), "openrouter");
+        assert_eq!(ProviderType::Mistral.to_string(), "mistral");
+        assert_eq!(ProviderType::DeepSeek.to_string(), "deepseek");
     }
 
     #[test]
@@ -11,57 +11,18 @@ pub fn generate_api_key() -> String {
 
 /// 检查是否为回环地址
 pub fn is_loopback_host(host: &str) -> bool {
-    if host == "localhost" {
-        return true;
-    }
-    match host.parse::<std::net::IpAddr>() {
-        Ok(addr) => addr.is_loopback(),
-        Err(_) => false,
-    }
+    config::is_loopback_bind(host)
 }
 
 /// 检查是否为有效的绑定地址
-/// 允许回环地址、0.0.0.0 和私有网络地址
+/// 允许回环地址、所有接口（0.0.0.0、::）和局域网地址
 pub fn is_valid_bind_host(host: &str) -> bool {
-    if is_loopback_host(host) {
-        return true;
-    }
-    // 允许 0.0.0.0 和 :: （监听所有接口）
-    if host == "0.0.0.0" || host == "::" {
-        return true;
-    }
-
-    // 允许私有网络地址
-    if let Ok(addr) = host.parse::<std::net::IpAddr>() {
-        if let std::net::IpAddr::V4(ipv4) = addr {
-            // 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16
-            let octets = ipv4.octets();
-            return octets[0] == 10
-                || (octets[0] == 172 && (octets[1] >= 16 && octets[1] <= 31))
-                || (octets[0] == 192 && octets[1] == 168);
-        }
-    }
-
-    false
+    config::validate_bind_host(host).is_ok()
 }
 
 /// 检查是否为非本地绑定地址（需要强 API Key）
 pub fn is_non_local_bind(host: &str) -> bool {
-    if host == "0.0.0.0" || host == "::" {
-        return true;
-    }
-
-    // 私有网络地址也算非本地绑定
-    if let Ok(addr) = host.parse::<std::net::IpAddr>() {
-        if let std::net::IpAddr::V4(ipv4) = addr {
-            let octets = ipv4.octets();
-            return octets[0] == 10
-                || (octets[0] == 172 && (octets[1] >= 16 && octets[1] <= 31))
-                || (octets[0] == 192 && octets[1] == 168);
-        }
-    }
-
-    false
+    config::is_non_local_bind(host)
 }
 
 /// 掩码敏感 Token
@@ -0,0 +1,22 @@
+//! 无界面服务器
+//!
+//! 不链接 Tauri / WebView，适用于没有图形环境的服务器：
+//!
+//! ```bash
+//! cargo build --release --no-default-features --bin proxycast-server
+//! ```
+//!
+//! 支持与桌面版相同的子命令，不带子命令时等同于 `serve`。
+
+use proxycast_lib::app::cli;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = cli::dispatch(&args).unwrap_or_else(|| {
+        let serve: Vec<String> = std::iter::once("serve".to_string())
+            .chain(args.iter().cloned())
+            .collect();
+        cli::dispatch(&serve).unwrap_or(2)
+    });
+    std::process::exit(code);
+}
@@ -209,7 +209,7 @@ async fn set_default_provider_internal(
     provider: String,
 ) -> Result<(), String> {
     // 验证provider
-    let provider_type: ProviderType = provider.parse().map_err(|e: String| e)?;
+    provider.parse::<ProviderType>()?;
 
     let mut s = state.write().await;
     s.config.default_provider = provider.clone();
@@ -220,11 +220,10 @@ async fn set_default_provider_internal(
         *dp = provider.clone();
     }
 
-    // 同时更新运行中服务器的 router（如果服务器正在运行）
-    if let Some(router_ref) = &s.router_ref {
-        let mut router = router_ref.write().await;
-        router.set_default_provider(provider_type);
-        tracing::info!("[AUTO_FIX] 动态更新 Router 默认 Provider: {}", provider);
+    // 同时更新运行中服务器的配置快照（如果服务器正在运行）
+    if let Some(snapshot_ref) = &s.snapshot_ref {
+        snapshot_ref.rcu(|current| current.with_default_provider(&provider));
+        tracing::info!("[AUTO_FIX] 动态更新默认 Provider: {}", provider);
     }
 
     config::save_config(&s.config).map_err(|e| e.to_string())?;
@@ -1,9 +1,10 @@
 //! 参数注入相关命令
 
 use crate::config::{save_config, InjectionRuleConfig, InjectionSettings};
-use crate::injection::{InjectionMode, InjectionRule};
+use crate::injection::{InjectionConditions, InjectionMode, InjectionRule};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -27,6 +28,12 @@ pub struct InjectionRuleResponse {
     pub mode: InjectionMode,
     pub priority: i32,
     pub enabled: bool,
+    #[serde(default)]
+    pub conditions: InjectionConditions,
+    #[serde(default)]
+    pub remove: Vec<String>,
+    #[serde(default)]
+    pub caps: BTreeMap<String, f64>,
 }
 
 impl From<&InjectionRuleConfig> for InjectionRuleResponse {
@@ -38,6 +45,9 @@ impl From<&InjectionRuleConfig> for InjectionRuleResponse {
             mode: config.mode,
             priority: config.priority,
             enabled: config.enabled,
+            conditions: config.conditions.clone(),
+            remove: config.remove.clone(),
+            caps: config.caps.clone(),
         }
     }
 }
@@ -51,6 +61,9 @@ impl From<&InjectionRule> for InjectionRuleResponse {
             mode: rule.mode,
             priority: rule.priority,
             enabled: rule.enabled,
+            conditions: rule.conditions.clone(),
+            remove: rule.remove.clone(),
+            caps: rule.caps.clone(),
         }
     }
 }
@@ -119,6 +132,9 @@ pub async fn add_injection_rule(
         mode: rule.mode,
         priority: rule.priority,
         enabled: rule.enabled,
+        conditions: rule.conditions,
+        remove: rule.remove,
+        caps: rule.caps,
     };
 
     s.config.injection.rules.push(config_rule);
@@ -171,6 +187,9 @@ pub async fn update_injection_rule(
         mode: rule.mode,
         priority: rule.priority,
         enabled: rule.enabled,
+        conditions: rule.conditions,
+        remove: rule.remove,
+        caps: rule.caps,
     };
 
     save_config(&s.config).map_err(|e| e.to_string())?;
@@ -1,5 +1,6 @@
 //! 模型管理相关命令
 
+use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::services::model_service::ModelService;
@@ -25,6 +26,7 @@ pub fn get_credential_models(
 #[tauri::command]
 pub async fn refresh_credential_models(
     db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
     credential_uuid: String,
 ) -> Result<Vec<String>, String> {
     tracing::info!("[REFRESH_CREDENTIAL_MODELS] ========== 开始刷新凭证模型列表 ==========");
@@ -64,6 +66,7 @@ pub async fn refresh_credential_models(
     // 更新到数据库
     tracing::info!("[REFRESH_CREDENTIAL_MODELS] 更新模型列表到数据库...");
     model_service.update_credential_models(&db, &credential_uuid, models.clone())?;
+    pool_service.0.model_catalog().invalidate();
 
     tracing::info!("[REFRESH_CREDENTIAL_MODELS] ========== 刷新完成 ==========");
 
@@ -94,6 +97,7 @@ pub fn get_all_available_models(db: State<'_, DbConnection>) -> Result<Vec<Strin
 #[tauri::command]
 pub async fn refresh_all_credential_models(
     db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
 ) -> Result<HashMap<String, Result<Vec<String>, String>>, String> {
     tracing::info!("[REFRESH_ALL_CREDENTIAL_MODELS] 批量刷新所有凭证的模型列表");
 
@@ -141,6 +145,7 @@ pub async fn refresh_all_credential_models(
 
         results.insert(credential.uuid.clone(), result);
     }
+    pool_service.0.model_catalog().invalidate();
 
     Ok(results)
 }
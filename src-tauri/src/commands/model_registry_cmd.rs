@@ -2,9 +2,13 @@
 //!
 //! 提供模型注册表相关的前端 API
 
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
 use crate::models::model_registry::{
     EnhancedModelMetadata, ModelSyncState, ModelTier, ProviderAliasConfig, UserModelPreference,
 };
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+use crate::providers::openrouter::OPENROUTER_CATALOG_SYNC_INTERVAL;
 use crate::services::model_registry_service::ModelRegistryService;
 use std::sync::Arc;
 use tauri::State;
@@ -180,3 +184,63 @@ pub async fn refresh_model_registry(state: State<'_, ModelRegistryState>) -> Res
 
     service.force_reload().await
 }
+
+/// 从凭证池中查找第一个可用的 OpenRouter 凭证，返回 (api_key, base_url)
+fn find_openrouter_credential(db: &DbConnection) -> Option<(String, Option<String>)> {
+    let conn = db.lock().ok()?;
+    let credentials = ProviderPoolDao::get_by_type(&conn, &PoolProviderType::OpenRouter).ok()?;
+    credentials
+        .into_iter()
+        .filter(|c| !c.is_disabled)
+        .find_map(|c| match c.credential {
+            CredentialData::OpenRouterKey { api_key, base_url } => Some((api_key, base_url)),
+            _ => None,
+        })
+}
+
+/// 手动同步 OpenRouter 模型目录
+///
+/// 使用凭证池中的 OpenRouter 凭证；未配置凭证时以匿名方式拉取公开目录
+#[tauri::command]
+pub async fn sync_openrouter_models(
+    state: State<'_, ModelRegistryState>,
+    db: State<'_, DbConnection>,
+) -> Result<u32, String> {
+    let (api_key, base_url) = match find_openrouter_credential(&db) {
+        Some((api_key, base_url)) => (Some(api_key), base_url),
+        None => (None, None),
+    };
+
+    let guard = state.read().await;
+    let service = guard
+        .as_ref()
+        .ok_or_else(|| "模型注册服务未初始化".to_string())?;
+
+    service.sync_openrouter_catalog(api_key, base_url).await
+}
+
+/// 启动 OpenRouter 模型目录后台同步任务
+///
+/// 仅在凭证池中存在可用的 OpenRouter 凭证时同步，按固定间隔刷新目录
+pub fn start_background_openrouter_sync(registry: ModelRegistryState, db: DbConnection) {
+    tokio::spawn(async move {
+        // 延迟 60 秒后开始第一次同步，避免影响启动性能
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+
+        loop {
+            if let Some((api_key, base_url)) = find_openrouter_credential(&db) {
+                let guard = registry.read().await;
+                if let Some(service) = guard.as_ref() {
+                    if let Err(e) = service
+                        .sync_openrouter_catalog(Some(api_key), base_url)
+                        .await
+                    {
+                        tracing::warn!("[ModelRegistry] {}", e);
+                    }
+                }
+            }
+
+            tokio::time::sleep(OPENROUTER_CATALOG_SYNC_INTERVAL).await;
+        }
+    });
+}
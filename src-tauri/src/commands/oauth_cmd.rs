@@ -99,7 +99,7 @@ pub async fn get_oauth_credentials(
 
     match provider_type {
         OAuthProvider::Kiro => {
-            let creds = &s.kiro_provider.credentials;
+            let creds = s.kiro_provider.credentials();
             Ok(OAuthCredentialStatus {
                 provider: provider.clone(),
                 loaded: creds.access_token.is_some() || creds.refresh_token.is_some(),
@@ -234,7 +234,7 @@ pub async fn get_oauth_env_variables(
 
     match provider_type {
         OAuthProvider::Kiro => {
-            let creds = &s.kiro_provider.credentials;
+            let creds = s.kiro_provider.credentials();
             // P0 安全修复：不返回明文敏感凭证
             if let Some(token) = &creds.access_token {
                 vars.push(EnvVariable {
@@ -443,7 +443,7 @@ pub async fn get_all_oauth_credentials(
     let mut results = Vec::new();
 
     // Kiro
-    let kiro_creds = &s.kiro_provider.credentials;
+    let kiro_creds = s.kiro_provider.credentials();
     let kiro_path = providers::kiro::KiroProvider::default_creds_path();
     results.push(OAuthCredentialStatus {
         provider: "kiro".to_string(),
@@ -184,6 +184,17 @@ fn extract_supported_models(
             // iFlow 是 DeepSeek 的代理服务
             vec!["deepseek-chat".to_string(), "deepseek-reasoner".to_string()]
         }
+        CredentialData::DeepSeekKey { .. } => {
+            vec!["deepseek-chat".to_string(), "deepseek-reasoner".to_string()]
+        }
+        CredentialData::MistralKey { .. } => {
+            vec![
+                "mistral-large-latest".to_string(),
+                "mistral-medium-latest".to_string(),
+                "codestral-latest".to_string(),
+                "mistral-small-latest".to_string(),
+            ]
+        }
         _ => vec![],
     }
 }
@@ -198,7 +209,9 @@ fn map_pool_provider_type(pool_type: &str) -> ProviderType {
         "codex" => ProviderType::OpenAI,
         "qwen" => ProviderType::Custom,
         "antigravity" => ProviderType::Antigravity,
-        "iflow" | "deepseek" => ProviderType::Custom, // DeepSeek 及其代理 iFlow
+        "deepseek" => ProviderType::DeepSeek,
+        "iflow" => ProviderType::Custom, // DeepSeek 代理服务
+        "mistral" => ProviderType::Mistral,
         _ => ProviderType::Custom,
     }
 }
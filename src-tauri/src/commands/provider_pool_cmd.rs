@@ -6,224 +6,29 @@ use crate::credential::CredentialSyncService;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    AddCredentialRequest, CredentialData, CredentialDisplay, HealthCheckResult, OAuthStatus,
+    AddCredentialRequest, BulkCredentialFilter, BulkOperationResult, CredentialData,
+    CredentialDisplay, DuplicateCredentialRequest, HealthCheckResult, OAuthStatus,
     PoolProviderType, ProviderCredential, ProviderPoolOverview, UpdateCredentialRequest,
 };
+use crate::oauth::kiro as kiro_oauth;
+use crate::services::credential_file_service::{
+    copy_and_rename_credential_file, create_kiro_credential_from_json, get_credentials_dir,
+};
+use crate::services::credential_import_service::{
+    self, CredentialImportResult, DetectedCredential,
+};
 use crate::services::provider_pool_service::ProviderPoolService;
 use chrono::Utc;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{Emitter, State};
-use uuid::Uuid;
 
 pub struct ProviderPoolServiceState(pub Arc<ProviderPoolService>);
 
 /// 凭证同步服务状态封装
 pub struct CredentialSyncServiceState(pub Option<Arc<CredentialSyncService>>);
 
-/// 展开路径中的 ~ 为用户主目录
-fn expand_tilde(path: &str) -> String {
-    if let Some(stripped) = path.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(stripped).to_string_lossy().to_string();
-        }
-    }
-    path.to_string()
-}
-
-/// 获取应用凭证存储目录
-fn get_credentials_dir() -> Result<PathBuf, String> {
-    let app_data_dir = dirs::data_dir()
-        .ok_or_else(|| "无法获取应用数据目录".to_string())?
-        .join("proxycast")
-        .join("credentials");
-
-    // 确保目录存在
-    if !app_data_dir.exists() {
-        fs::create_dir_all(&app_data_dir).map_err(|e| format!("创建凭证存储目录失败: {}", e))?;
-    }
-
-    Ok(app_data_dir)
-}
-
-/// 复制并重命名 OAuth 凭证文件
-///
-/// 对于 Kiro 凭证，会自动合并 clientIdHash 文件中的 client_id/client_secret，
-/// 使副本文件完全独立，支持多账号场景。
-fn copy_and_rename_credential_file(
-    source_path: &str,
-    provider_type: &str,
-) -> Result<String, String> {
-    let expanded_source = expand_tilde(source_path);
-    let source = Path::new(&expanded_source);
-
-    // 验证源文件存在
-    if !source.exists() {
-        return Err(format!("凭证文件不存在: {}", expanded_source));
-    }
-
-    // 生成新的文件名：{provider_type}_{uuid}_{timestamp}.json
-    let uuid = Uuid::new_v4().to_string();
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let new_filename = format!(
-        "{}_{}_{}_{}.json",
-        provider_type,
-        &uuid[..8], // 使用 UUID 前8位
-        timestamp,
-        provider_type
-    );
-
-    // 获取目标目录
-    let credentials_dir = get_credentials_dir()?;
-    let target_path = credentials_dir.join(&new_filename);
-
-    // 对于 Kiro 凭证，需要合并 clientIdHash 文件中的 client_id/client_secret
-    if provider_type == "kiro" {
-        let content = fs::read_to_string(source).map_err(|e| format!("读取凭证文件失败: {}", e))?;
-        let mut creds: serde_json::Value =
-            serde_json::from_str(&content).map_err(|e| format!("解析凭证文件失败: {}", e))?;
-
-        // 检测 refreshToken 是否被截断（仅记录警告，不阻止添加）
-        // 正常的 refreshToken 长度应该在 500+ 字符，如果小于 100 字符则可能被截断
-        // 注意：即使 refreshToken 被截断，也允许添加凭证，在刷新时才会提示错误
-        if let Some(refresh_token) = creds.get("refreshToken").and_then(|v| v.as_str()) {
-            let token_len = refresh_token.len();
-
-            // 检测常见的截断模式
-            let is_truncated =
-                token_len < 100 || refresh_token.ends_with("...") || refresh_token.contains("...");
-
-            if is_truncated {
-                // 安全地截取前 50 个字符（避免 UTF-8 边界 panic）
-                let preview: String = refresh_token.chars().take(50).collect();
-                tracing::warn!(
-                    "[KIRO] 检测到 refreshToken 可能被截断！长度: {}, 内容: {}... (仍允许添加，刷新时会提示)",
-                    token_len,
-                    preview
-                );
-                // 不再阻止添加，只记录警告
-                // 在刷新 Token 时会检测并提示用户
-            } else {
-                tracing::info!("[KIRO] refreshToken 长度检查通过: {} 字符", token_len);
-            }
-        } else {
-            tracing::warn!("[KIRO] 凭证文件中没有 refreshToken 字段");
-        }
-
-        let aws_sso_cache_dir = dirs::home_dir()
-            .ok_or_else(|| "无法获取用户主目录".to_string())?
-            .join(".aws")
-            .join("sso")
-            .join("cache");
-
-        // 尝试从 clientIdHash 文件或扫描目录获取 client_id/client_secret
-        let mut found_credentials = false;
-
-        // 方式1：如果有 clientIdHash，读取对应文件
-        if let Some(hash) = creds.get("clientIdHash").and_then(|v| v.as_str()) {
-            let hash_file_path = aws_sso_cache_dir.join(format!("{}.json", hash));
-
-            if hash_file_path.exists() {
-                if let Ok(hash_content) = fs::read_to_string(&hash_file_path) {
-                    if let Ok(hash_json) = serde_json::from_str::<serde_json::Value>(&hash_content)
-                    {
-                        if let Some(client_id) = hash_json.get("clientId") {
-                            creds["clientId"] = client_id.clone();
-                        }
-                        if let Some(client_secret) = hash_json.get("clientSecret") {
-                            creds["clientSecret"] = client_secret.clone();
-                        }
-                        if creds.get("clientId").is_some() && creds.get("clientSecret").is_some() {
-                            found_credentials = true;
-                            tracing::info!(
-                                "[KIRO] 已从 clientIdHash 文件合并 client_id/client_secret 到副本"
-                            );
-                        }
-                    }
-                }
-            }
-        }
-
-        // 方式2：如果没有 clientIdHash 或未找到，扫描目录中的其他 JSON 文件
-        if !found_credentials && aws_sso_cache_dir.exists() {
-            tracing::info!(
-                "[KIRO] 没有 clientIdHash 或未找到，扫描目录查找 client_id/client_secret"
-            );
-            if let Ok(entries) = fs::read_dir(&aws_sso_cache_dir) {
-                for entry in entries.flatten() {
-                    let file_path = entry.path();
-                    // 跳过主凭证文件和备份文件
-                    if file_path.extension().map(|e| e == "json").unwrap_or(false) {
-                        let file_name =
-                            file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                        if file_name.starts_with("kiro-auth-token") {
-                            continue;
-                        }
-                        if let Ok(file_content) = fs::read_to_string(&file_path) {
-                            if let Ok(file_json) =
-                                serde_json::from_str::<serde_json::Value>(&file_content)
-                            {
-                                let has_client_id =
-                                    file_json.get("clientId").and_then(|v| v.as_str()).is_some();
-                                let has_client_secret = file_json
-                                    .get("clientSecret")
-                                    .and_then(|v| v.as_str())
-                                    .is_some();
-                                if has_client_id && has_client_secret {
-                                    creds["clientId"] = file_json["clientId"].clone();
-                                    creds["clientSecret"] = file_json["clientSecret"].clone();
-                                    found_credentials = true;
-                                    tracing::info!(
-                                        "[KIRO] 已从 {} 合并 client_id/client_secret 到副本",
-                                        file_name
-                                    );
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        if !found_credentials {
-            // 检查认证方式
-            let auth_method = creds
-                .get("authMethod")
-                .and_then(|v| v.as_str())
-                .unwrap_or("social");
-
-            if auth_method.to_lowercase() == "idc" {
-                // IdC 认证必须有 clientId/clientSecret
-                tracing::error!(
-                    "[KIRO] IdC 认证方式缺少 clientId/clientSecret，无法创建有效的凭证副本"
-                );
-                return Err(
-                    "IdC 认证凭证不完整：缺少 clientId/clientSecret。\n\n💡 解决方案：\n1. 确保 ~/.aws/sso/cache/ 目录下有对应的 clientIdHash 文件\n2. 如果使用 AWS IAM Identity Center，请确保已完成完整的 SSO 登录流程\n3. 或者尝试使用 Social 认证方式的凭证".to_string()
-                );
-            } else {
-                tracing::warn!("[KIRO] 未找到 client_id/client_secret，将使用 social 认证方式");
-            }
-        }
-
-        // 写入合并后的凭证到副本文件
-        let merged_content =
-            serde_json::to_string_pretty(&creds).map_err(|e| format!("序列化凭证失败: {}", e))?;
-        fs::write(&target_path, merged_content).map_err(|e| format!("写入凭证文件失败: {}", e))?;
-    } else {
-        // 其他类型直接复制
-        fs::copy(source, &target_path).map_err(|e| format!("复制凭证文件失败: {}", e))?;
-    }
-
-    // 返回新的文件路径
-    Ok(target_path.to_string_lossy().to_string())
-}
-
 /// 删除凭证文件（如果在应用存储目录中）
 fn cleanup_credential_file(file_path: &str) -> Result<(), String> {
     let path = Path::new(file_path);
@@ -407,7 +212,19 @@ pub fn update_provider_pool_credential(
             };
         }
         if let Some(not_supported_models) = request.not_supported_models {
-            updated_cred.not_supported_models = not_supported_models;
+            updated_cred.set_not_supported_models(not_supported_models);
+        }
+        if let Some(tier) = request.tier {
+            updated_cred.tier = tier;
+        }
+        if let Some(schedule) = request.schedule {
+            updated_cred.apply_schedule(schedule)?;
+        }
+        if let Some(tags) = request.tags {
+            updated_cred.set_tags(tags);
+        }
+        if let Some(model) = request.pinned_model {
+            updated_cred.set_pinned_model(model);
         }
 
         updated_cred.updated_at = Utc::now();
@@ -425,7 +242,10 @@ pub fn update_provider_pool_credential(
 
         // 更新 api_key 和 base_url
         match &mut current_credential.credential {
-            CredentialData::OpenAIKey { api_key, base_url } => {
+            CredentialData::OpenAIKey { api_key, base_url }
+            | CredentialData::OpenRouterKey { api_key, base_url }
+            | CredentialData::MistralKey { api_key, base_url }
+            | CredentialData::DeepSeekKey { api_key, base_url } => {
                 if let Some(new_key) = request.new_api_key {
                     if !new_key.is_empty() {
                         *api_key = new_key;
@@ -478,7 +298,19 @@ pub fn update_provider_pool_credential(
             };
         }
         if let Some(not_supported_models) = request.not_supported_models {
-            current_credential.not_supported_models = not_supported_models;
+            current_credential.set_not_supported_models(not_supported_models);
+        }
+        if let Some(tier) = request.tier {
+            current_credential.tier = tier;
+        }
+        if let Some(schedule) = request.schedule {
+            current_credential.apply_schedule(schedule)?;
+        }
+        if let Some(tags) = request.tags {
+            current_credential.set_tags(tags);
+        }
+        if let Some(model) = request.pinned_model {
+            current_credential.set_pinned_model(model);
         }
 
         current_credential.updated_at = Utc::now();
@@ -498,6 +330,10 @@ pub fn update_provider_pool_credential(
             request.check_model_name,
             request.not_supported_models,
             request.new_proxy_url,
+            request.tier,
+            request.schedule,
+            request.tags,
+            request.pinned_model,
         )?
     };
 
@@ -551,9 +387,20 @@ pub fn toggle_provider_pool_credential(
     uuid: String,
     is_disabled: bool,
 ) -> Result<ProviderCredential, String> {
-    pool_service
-        .0
-        .update_credential(&db, &uuid, None, Some(is_disabled), None, None, None, None)
+    pool_service.0.update_credential(
+        &db,
+        &uuid,
+        None,
+        Some(is_disabled),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
 }
 
 /// 重置凭证计数器
@@ -597,13 +444,127 @@ pub async fn check_provider_pool_credential_health(
 }
 
 /// 执行指定类型的所有凭证健康检查
+///
+/// 并发执行，每完成一个凭证发送 `provider-pool-health-progress` 事件；
+/// 未指定 `concurrency` 时使用配置中的 `health_check.concurrency`
 #[tauri::command]
 pub async fn check_provider_pool_type_health(
+    app: tauri::AppHandle,
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
     provider_type: String,
+    concurrency: Option<usize>,
 ) -> Result<Vec<HealthCheckResult>, String> {
-    pool_service.0.check_type_health(&db, &provider_type).await
+    let concurrency = pool_service.0.health_check_concurrency(concurrency);
+    pool_service
+        .0
+        .check_type_health_with_progress(&db, &provider_type, concurrency, |progress| {
+            let _ = app.emit("provider-pool-health-progress", progress);
+        })
+        .await
+}
+
+/// 复制凭证（未指定的字段沿用原凭证）
+#[tauri::command]
+pub fn duplicate_provider_pool_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    sync_service: State<'_, CredentialSyncServiceState>,
+    uuid: String,
+    overrides: Option<DuplicateCredentialRequest>,
+) -> Result<ProviderCredential, String> {
+    let credential =
+        pool_service
+            .0
+            .duplicate_credential(&db, &uuid, overrides.unwrap_or_default())?;
+
+    // 同步到 YAML 配置（如果同步服务可用）
+    if let Some(ref sync) = sync_service.0 {
+        if let Err(e) = sync.add_credential(&credential) {
+            tracing::warn!("同步凭证到 YAML 失败: {}", e);
+        }
+    }
+
+    Ok(credential)
+}
+
+/// 按 Provider 类型或标签批量启用 / 禁用凭证
+#[tauri::command]
+pub fn bulk_set_provider_pool_disabled(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    filter: BulkCredentialFilter,
+    is_disabled: bool,
+) -> Result<Vec<BulkOperationResult>, String> {
+    pool_service.0.bulk_set_disabled(&db, &filter, is_disabled)
+}
+
+/// 批量删除不健康的凭证
+#[tauri::command]
+pub fn bulk_delete_unhealthy_provider_pool_credentials(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    sync_service: State<'_, CredentialSyncServiceState>,
+    filter: Option<BulkCredentialFilter>,
+) -> Result<Vec<BulkOperationResult>, String> {
+    let results = pool_service
+        .0
+        .bulk_delete_unhealthy(&db, &filter.unwrap_or_default())?;
+
+    // 同步到 YAML 配置（如果同步服务可用）
+    if let Some(ref sync) = sync_service.0 {
+        for result in results.iter().filter(|r| r.success) {
+            if let Ok(pool_type) = result.provider_type.parse::<PoolProviderType>() {
+                if let Err(e) = sync.remove_credential(pool_type, &result.uuid) {
+                    tracing::warn!("从 YAML 删除凭证失败: {}", e);
+                }
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// 批量重新检查凭证健康状态
+#[tauri::command]
+pub async fn bulk_check_provider_pool_health(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    filter: Option<BulkCredentialFilter>,
+    concurrency: Option<usize>,
+) -> Result<Vec<HealthCheckResult>, String> {
+    let concurrency = pool_service.0.health_check_concurrency(concurrency);
+    pool_service
+        .0
+        .bulk_check_health(&db, &filter.unwrap_or_default(), concurrency)
+        .await
+}
+
+/// 扫描本机 CLI 工具中可导入的 OAuth 凭证
+#[tauri::command]
+pub fn detect_importable_credentials(
+    db: State<'_, DbConnection>,
+) -> Result<Vec<DetectedCredential>, String> {
+    credential_import_service::detect_credentials(&db)
+}
+
+/// 批量导入扫描到的凭证
+///
+/// `paths` 为空时导入所有未导入的凭证
+#[tauri::command]
+pub fn import_detected_credentials(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    paths: Option<Vec<String>>,
+) -> Result<Vec<CredentialImportResult>, String> {
+    let detected = credential_import_service::detect_credentials(&db)?;
+    Ok(credential_import_service::import_detected(
+        &db,
+        &pool_service.0,
+        detected,
+        paths.as_deref(),
+        copy_and_rename_credential_file,
+    ))
 }
 
 /// 添加 Kiro OAuth 凭证（通过文件路径）
@@ -649,158 +610,6 @@ pub fn add_kiro_oauth_credential(
     }
 }
 
-/// 从 JSON 内容创建 Kiro 凭证文件并添加到凭证池
-///
-/// 直接粘贴 JSON 内容，无需选择文件
-fn create_kiro_credential_from_json(json_content: &str) -> Result<String, String> {
-    // 验证 JSON 格式
-    let creds: serde_json::Value =
-        serde_json::from_str(json_content).map_err(|e| format!("JSON 格式无效: {}", e))?;
-
-    // 验证必要字段
-    if creds.get("refreshToken").is_none() {
-        return Err("凭证 JSON 缺少 refreshToken 字段".to_string());
-    }
-
-    // 检测 refreshToken 是否被截断
-    if let Some(refresh_token) = creds.get("refreshToken").and_then(|v| v.as_str()) {
-        let token_len = refresh_token.len();
-        let is_truncated =
-            token_len < 100 || refresh_token.ends_with("...") || refresh_token.contains("...");
-
-        if is_truncated {
-            tracing::warn!(
-                "[KIRO] 检测到 refreshToken 可能被截断！长度: {} (仍允许添加，刷新时会提示)",
-                token_len
-            );
-        }
-    }
-
-    // 生成新的文件名
-    let uuid = Uuid::new_v4().to_string();
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let new_filename = format!("kiro_{}_{}_{}.json", &uuid[..8], timestamp, "kiro");
-
-    // 获取目标目录
-    let credentials_dir = get_credentials_dir()?;
-    let target_path = credentials_dir.join(&new_filename);
-
-    // 尝试合并 clientId/clientSecret（如果凭证中没有）
-    let mut merged_creds = creds.clone();
-
-    // 检查是否需要从外部文件获取 clientId/clientSecret
-    let has_client_id = merged_creds.get("clientId").is_some();
-    let has_client_secret = merged_creds.get("clientSecret").is_some();
-
-    if !has_client_id || !has_client_secret {
-        let aws_sso_cache_dir = dirs::home_dir()
-            .ok_or_else(|| "无法获取用户主目录".to_string())?
-            .join(".aws")
-            .join("sso")
-            .join("cache");
-
-        let mut found_credentials = false;
-
-        // 方式1：如果有 clientIdHash，读取对应文件
-        if let Some(hash) = merged_creds.get("clientIdHash").and_then(|v| v.as_str()) {
-            let hash_file_path = aws_sso_cache_dir.join(format!("{}.json", hash));
-
-            if hash_file_path.exists() {
-                if let Ok(hash_content) = fs::read_to_string(&hash_file_path) {
-                    if let Ok(hash_json) = serde_json::from_str::<serde_json::Value>(&hash_content)
-                    {
-                        if let Some(client_id) = hash_json.get("clientId") {
-                            merged_creds["clientId"] = client_id.clone();
-                        }
-                        if let Some(client_secret) = hash_json.get("clientSecret") {
-                            merged_creds["clientSecret"] = client_secret.clone();
-                        }
-                        if merged_creds.get("clientId").is_some()
-                            && merged_creds.get("clientSecret").is_some()
-                        {
-                            found_credentials = true;
-                            tracing::info!(
-                                "[KIRO] 已从 clientIdHash 文件合并 client_id/client_secret"
-                            );
-                        }
-                    }
-                }
-            }
-        }
-
-        // 方式2：扫描目录中的其他 JSON 文件
-        if !found_credentials && aws_sso_cache_dir.exists() {
-            tracing::info!("[KIRO] 扫描目录查找 client_id/client_secret");
-            if let Ok(entries) = fs::read_dir(&aws_sso_cache_dir) {
-                for entry in entries.flatten() {
-                    let file_path = entry.path();
-                    if file_path.extension().map(|e| e == "json").unwrap_or(false) {
-                        let file_name =
-                            file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                        if file_name.starts_with("kiro-auth-token") {
-                            continue;
-                        }
-                        if let Ok(file_content) = fs::read_to_string(&file_path) {
-                            if let Ok(file_json) =
-                                serde_json::from_str::<serde_json::Value>(&file_content)
-                            {
-                                let has_cid =
-                                    file_json.get("clientId").and_then(|v| v.as_str()).is_some();
-                                let has_csec = file_json
-                                    .get("clientSecret")
-                                    .and_then(|v| v.as_str())
-                                    .is_some();
-                                if has_cid && has_csec {
-                                    merged_creds["clientId"] = file_json["clientId"].clone();
-                                    merged_creds["clientSecret"] =
-                                        file_json["clientSecret"].clone();
-                                    found_credentials = true;
-                                    tracing::info!(
-                                        "[KIRO] 从 {} 合并 client_id/client_secret",
-                                        file_name
-                                    );
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        if !found_credentials {
-            let auth_method = merged_creds
-                .get("authMethod")
-                .and_then(|v| v.as_str())
-                .unwrap_or("social");
-
-            if auth_method.to_lowercase() == "idc" {
-                tracing::error!(
-                    "[KIRO] IdC 认证方式缺少 clientId/clientSecret，无法创建有效的凭证"
-                );
-                return Err(
-                    "IdC 认证凭证不完整：缺少 clientId/clientSecret。\n\n💡 解决方案：\n1. 确保 ~/.aws/sso/cache/ 目录下有对应的 clientIdHash 文件\n2. 如果使用 AWS IAM Identity Center，请确保已完成完整的 SSO 登录流程\n3. 或者尝试使用 Social 认证方式的凭证".to_string()
-                );
-            } else {
-                tracing::warn!("[KIRO] 未找到 client_id/client_secret，将使用 social 认证方式");
-            }
-        }
-    }
-
-    // 写入凭证文件
-    let merged_content = serde_json::to_string_pretty(&merged_creds)
-        .map_err(|e| format!("序列化凭证失败: {}", e))?;
-    fs::write(&target_path, merged_content).map_err(|e| format!("写入凭证文件失败: {}", e))?;
-
-    tracing::info!("[KIRO] 凭证文件已创建: {:?}", target_path);
-
-    Ok(target_path.to_string_lossy().to_string())
-}
-
 /// 添加 Kiro OAuth 凭证（通过 JSON 内容）
 ///
 /// 直接粘贴凭证 JSON 内容，无需选择文件
@@ -941,6 +750,63 @@ pub fn add_openai_key_credential(
     )
 }
 
+/// 添加 OpenRouter API Key 凭证
+#[tauri::command]
+pub fn add_openrouter_key_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    api_key: String,
+    base_url: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.add_credential(
+        &db,
+        "openrouter",
+        CredentialData::OpenRouterKey { api_key, base_url },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加 Mistral API Key 凭证
+#[tauri::command]
+pub fn add_mistral_key_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    api_key: String,
+    base_url: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.add_credential(
+        &db,
+        "mistral",
+        CredentialData::MistralKey { api_key, base_url },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加 DeepSeek API Key 凭证
+#[tauri::command]
+pub fn add_deepseek_key_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    api_key: String,
+    base_url: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.add_credential(
+        &db,
+        "deepseek",
+        CredentialData::DeepSeekKey { api_key, base_url },
+        name,
+        Some(true),
+        None,
+    )
+}
+
 /// 添加 Claude API Key 凭证
 #[tauri::command]
 pub fn add_claude_key_credential(
@@ -1121,19 +987,19 @@ pub async fn debug_kiro_credentials() -> Result<String, String> {
             result.push_str("✅ 凭证加载成功!\n");
             result.push_str(&format!(
                 "📄 认证方式: {:?}\n",
-                provider.credentials.auth_method
+                provider.credentials().auth_method
             ));
             result.push_str(&format!(
                 "🔑 有 client_id: {}\n",
-                provider.credentials.client_id.is_some()
+                provider.credentials().client_id.is_some()
             ));
             result.push_str(&format!(
                 "🔒 有 client_secret: {}\n",
-                provider.credentials.client_secret.is_some()
+                provider.credentials().client_secret.is_some()
             ));
             result.push_str(&format!(
                 "🏷️  有 clientIdHash: {}\n",
-                provider.credentials.client_id_hash.is_some()
+                provider.credentials().client_id_hash.is_some()
             ));
 
             // P0 安全修复：不再输出敏感信息（clientIdHash、token 前缀等）
@@ -1237,15 +1103,15 @@ pub async fn test_user_credentials() -> Result<String, String> {
                             result.push_str("✅ KiroProvider 加载成功!\n");
                             result.push_str(&format!(
                                 "📄 最终认证方式: {:?}\n",
-                                provider.credentials.auth_method
+                                provider.credentials().auth_method
                             ));
                             result.push_str(&format!(
                                 "🔑 最终有 client_id: {}\n",
-                                provider.credentials.client_id.is_some()
+                                provider.credentials().client_id.is_some()
                             ));
                             result.push_str(&format!(
                                 "🔒 最终有 client_secret: {}\n",
-                                provider.credentials.client_secret.is_some()
+                                provider.credentials().client_secret.is_some()
                             ));
 
                             let detected_method = provider.detect_auth_method();
@@ -1987,18 +1853,11 @@ pub async fn get_kiro_credential_fingerprint(
         .map_err(|e| format!("加载凭证失败: {}", e))?;
 
     // 确定指纹来源
-    let (source, profile_arn, client_id) = if provider.credentials.profile_arn.is_some() {
-        (
-            "profileArn".to_string(),
-            provider.credentials.profile_arn.as_deref(),
-            None,
-        )
-    } else if provider.credentials.client_id.is_some() {
-        (
-            "clientId".to_string(),
-            None,
-            provider.credentials.client_id.as_deref(),
-        )
+    let creds = provider.credentials();
+    let (source, profile_arn, client_id) = if creds.profile_arn.is_some() {
+        ("profileArn".to_string(), creds.profile_arn.as_deref(), None)
+    } else if creds.client_id.is_some() {
+        ("clientId".to_string(), None, creds.client_id.as_deref())
     } else {
         ("system".to_string(), None, None)
     };
@@ -2009,8 +1868,7 @@ pub async fn get_kiro_credential_fingerprint(
     let machine_id_short: String = machine_id.chars().take(16).collect();
 
     // 获取认证方式
-    let auth_method = provider
-        .credentials
+    let auth_method = creds
         .auth_method
         .clone()
         .unwrap_or_else(|| "social".to_string());
@@ -2195,22 +2053,10 @@ pub async fn start_gemini_oauth_login(
 /// Kiro Builder ID 登录状态
 #[derive(Debug, Clone)]
 struct KiroBuilderIdLoginState {
-    /// OIDC 客户端 ID
-    client_id: String,
-    /// OIDC 客户端密钥
-    client_secret: String,
-    /// 设备码
-    device_code: String,
-    /// 用户码
-    user_code: String,
-    /// 验证 URI
-    verification_uri: String,
-    /// 轮询间隔（秒）
-    interval: i64,
+    /// 设备授权信息
+    authorization: kiro_oauth::DeviceAuthorization,
     /// 过期时间戳
     expires_at: i64,
-    /// 区域
-    region: String,
 }
 
 /// 全局 Builder ID 登录状态存储
@@ -2257,145 +2103,46 @@ pub struct KiroBuilderIdPollResponse {
 pub async fn start_kiro_builder_id_login(
     region: Option<String>,
 ) -> Result<KiroBuilderIdLoginResponse, String> {
-    let region = region.unwrap_or_else(|| "us-east-1".to_string());
-    let oidc_base = format!("https://oidc.{}.amazonaws.com", region);
-    let start_url = "https://view.awsapps.com/start";
-    let scopes = vec![
-        "codewhisperer:completions",
-        "codewhisperer:analysis",
-        "codewhisperer:conversations",
-        "codewhisperer:transformations",
-        "codewhisperer:taskassist",
-    ];
-
+    let region = region.unwrap_or_else(|| kiro_oauth::DEFAULT_REGION.to_string());
     tracing::info!("[Kiro Builder ID] 开始登录流程，区域: {}", region);
 
-    // Step 1: 注册 OIDC 客户端
-    tracing::info!("[Kiro Builder ID] Step 1: 注册 OIDC 客户端...");
-    let client = reqwest::Client::new();
-
-    let reg_body = serde_json::json!({
-        "clientName": "ProxyCast Kiro Manager",
-        "clientType": "public",
-        "scopes": scopes,
-        "grantTypes": ["urn:ietf:params:oauth:grant-type:device_code", "refresh_token"],
-        "issuerUrl": start_url
-    });
-
-    let reg_res = client
-        .post(format!("{}/client/register", oidc_base))
-        .header("Content-Type", "application/json")
-        .json(&reg_body)
-        .send()
-        .await
-        .map_err(|e| format!("注册客户端请求失败: {}", e))?;
-
-    if !reg_res.status().is_success() {
-        let err_text = reg_res.text().await.unwrap_or_default();
-        return Ok(KiroBuilderIdLoginResponse {
-            success: false,
-            user_code: None,
-            verification_uri: None,
-            expires_in: None,
-            interval: None,
-            error: Some(format!("注册客户端失败: {}", err_text)),
-        });
-    }
-
-    let reg_data: serde_json::Value = reg_res
-        .json()
-        .await
-        .map_err(|e| format!("解析注册响应失败: {}", e))?;
-
-    let client_id = reg_data["clientId"]
-        .as_str()
-        .ok_or("响应中缺少 clientId")?
-        .to_string();
-    let client_secret = reg_data["clientSecret"]
-        .as_str()
-        .ok_or("响应中缺少 clientSecret")?
-        .to_string();
+    let authorization =
+        match kiro_oauth::start_device_authorization(&reqwest::Client::new(), &region).await {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                return Ok(KiroBuilderIdLoginResponse {
+                    success: false,
+                    user_code: None,
+                    verification_uri: None,
+                    expires_in: None,
+                    interval: None,
+                    error: Some(e),
+                })
+            }
+        };
 
     tracing::info!(
-        "[Kiro Builder ID] 客户端注册成功: {}...",
-        client_id.chars().take(30).collect::<String>()
+        "[Kiro Builder ID] 设备码获取成功，user_code: {}",
+        authorization.user_code
     );
 
-    // Step 2: 发起设备授权
-    tracing::info!("[Kiro Builder ID] Step 2: 发起设备授权...");
-    let auth_body = serde_json::json!({
-        "clientId": client_id,
-        "clientSecret": client_secret,
-        "startUrl": start_url
-    });
-
-    let auth_res = client
-        .post(format!("{}/device_authorization", oidc_base))
-        .header("Content-Type", "application/json")
-        .json(&auth_body)
-        .send()
-        .await
-        .map_err(|e| format!("设备授权请求失败: {}", e))?;
-
-    if !auth_res.status().is_success() {
-        let err_text = auth_res.text().await.unwrap_or_default();
-        return Ok(KiroBuilderIdLoginResponse {
-            success: false,
-            user_code: None,
-            verification_uri: None,
-            expires_in: None,
-            interval: None,
-            error: Some(format!("设备授权失败: {}", err_text)),
-        });
-    }
-
-    let auth_data: serde_json::Value = auth_res
-        .json()
-        .await
-        .map_err(|e| format!("解析授权响应失败: {}", e))?;
-
-    let device_code = auth_data["deviceCode"]
-        .as_str()
-        .ok_or("响应中缺少 deviceCode")?
-        .to_string();
-    let user_code = auth_data["userCode"]
-        .as_str()
-        .ok_or("响应中缺少 userCode")?
-        .to_string();
-    let verification_uri = auth_data["verificationUriComplete"]
-        .as_str()
-        .or_else(|| auth_data["verificationUri"].as_str())
-        .ok_or("响应中缺少 verificationUri")?
-        .to_string();
-    let interval = auth_data["interval"].as_i64().unwrap_or(5);
-    let expires_in = auth_data["expiresIn"].as_i64().unwrap_or(600);
-
-    tracing::info!("[Kiro Builder ID] 设备码获取成功，user_code: {}", user_code);
+    let response = KiroBuilderIdLoginResponse {
+        success: true,
+        user_code: Some(authorization.user_code.clone()),
+        verification_uri: Some(authorization.verification_uri.clone()),
+        expires_in: Some(authorization.expires_in),
+        interval: Some(authorization.interval),
+        error: None,
+    };
 
     // 保存登录状态
-    let expires_at = chrono::Utc::now().timestamp() + expires_in;
-    {
-        let mut state = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-        *state = Some(KiroBuilderIdLoginState {
-            client_id,
-            client_secret,
-            device_code,
-            user_code: user_code.clone(),
-            verification_uri: verification_uri.clone(),
-            interval,
-            expires_at,
-            region,
-        });
-    }
+    let expires_at = chrono::Utc::now().timestamp() + authorization.expires_in;
+    *KIRO_BUILDER_ID_LOGIN_STATE.write().await = Some(KiroBuilderIdLoginState {
+        authorization,
+        expires_at,
+    });
 
-    Ok(KiroBuilderIdLoginResponse {
-        success: true,
-        user_code: Some(user_code),
-        verification_uri: Some(verification_uri),
-        expires_in: Some(expires_in),
-        interval: Some(interval),
-        error: None,
-    })
+    Ok(response)
 }
 
 /// 轮询 Kiro Builder ID 授权状态
@@ -2418,11 +2165,7 @@ pub async fn poll_kiro_builder_id_auth() -> Result<KiroBuilderIdPollResponse, St
 
     // 检查是否过期
     if chrono::Utc::now().timestamp() > state.expires_at {
-        // 清除状态
-        {
-            let mut state_guard = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-            *state_guard = None;
-        }
+        *KIRO_BUILDER_ID_LOGIN_STATE.write().await = None;
         return Ok(KiroBuilderIdPollResponse {
             success: false,
             completed: false,
@@ -2431,150 +2174,62 @@ pub async fn poll_kiro_builder_id_auth() -> Result<KiroBuilderIdPollResponse, St
         });
     }
 
-    let oidc_base = format!("https://oidc.{}.amazonaws.com", state.region);
-    let client = reqwest::Client::new();
-
-    let token_body = serde_json::json!({
-        "clientId": state.client_id,
-        "clientSecret": state.client_secret,
-        "grantType": "urn:ietf:params:oauth:grant-type:device_code",
-        "deviceCode": state.device_code
-    });
-
-    let token_res = client
-        .post(format!("{}/token", oidc_base))
-        .header("Content-Type", "application/json")
-        .json(&token_body)
-        .send()
-        .await
-        .map_err(|e| format!("Token 请求失败: {}", e))?;
-
-    let status = token_res.status();
-
-    if status.is_success() {
-        // 授权成功
-        let token_data: serde_json::Value = token_res
-            .json()
-            .await
-            .map_err(|e| format!("解析 Token 响应失败: {}", e))?;
-
-        tracing::info!("[Kiro Builder ID] 授权成功！");
-
-        // 保存凭证到文件
-        let access_token = token_data["accessToken"].as_str().unwrap_or("").to_string();
-        let refresh_token = token_data["refreshToken"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
-        let expires_in = token_data["expiresIn"].as_i64().unwrap_or(3600);
-
-        // 创建凭证 JSON
-        let creds_json = serde_json::json!({
-            "accessToken": access_token,
-            "refreshToken": refresh_token,
-            "clientId": state.client_id,
-            "clientSecret": state.client_secret,
-            "region": state.region,
-            "authMethod": "idc",
-            "expiresAt": chrono::Utc::now().timestamp() + expires_in
-        });
-
-        // 保存到临时状态，等待 add_kiro_from_builder_id_auth 调用
-        // 这里我们把凭证 JSON 存储到一个临时位置
-        {
-            let mut sessions = KIRO_BUILDER_ID_CREDENTIALS.write().await;
-            sessions.insert("pending".to_string(), creds_json);
+    let poll = match kiro_oauth::poll_token(&reqwest::Client::new(), &state.authorization).await {
+        Ok(poll) => poll,
+        Err(e) => {
+            return Ok(KiroBuilderIdPollResponse {
+                success: false,
+                completed: false,
+                status: None,
+                error: Some(e),
+            })
         }
-
-        // 清除登录状态
-        {
-            let mut state_guard = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-            *state_guard = None;
+    };
+    match poll {
+        kiro_oauth::TokenPoll::Authorized(creds_json) => {
+            tracing::info!("[Kiro Builder ID] 授权成功！");
+
+            // 保存到临时状态，等待 add_kiro_from_builder_id_auth 调用
+            KIRO_BUILDER_ID_CREDENTIALS
+                .write()
+                .await
+                .insert("pending".to_string(), creds_json);
+            *KIRO_BUILDER_ID_LOGIN_STATE.write().await = None;
+
+            Ok(KiroBuilderIdPollResponse {
+                success: true,
+                completed: true,
+                status: None,
+                error: None,
+            })
         }
-
-        Ok(KiroBuilderIdPollResponse {
+        kiro_oauth::TokenPoll::Pending => Ok(KiroBuilderIdPollResponse {
             success: true,
-            completed: true,
-            status: None,
+            completed: false,
+            status: Some("pending".to_string()),
             error: None,
-        })
-    } else if status.as_u16() == 400 {
-        let err_data: serde_json::Value = token_res
-            .json()
-            .await
-            .map_err(|e| format!("解析错误响应失败: {}", e))?;
-
-        let error = err_data["error"].as_str().unwrap_or("unknown");
-
-        match error {
-            "authorization_pending" => Ok(KiroBuilderIdPollResponse {
+        }),
+        kiro_oauth::TokenPoll::SlowDown => {
+            // 增加轮询间隔
+            if let Some(ref mut s) = *KIRO_BUILDER_ID_LOGIN_STATE.write().await {
+                s.authorization.interval += 5;
+            }
+            Ok(KiroBuilderIdPollResponse {
                 success: true,
                 completed: false,
-                status: Some("pending".to_string()),
+                status: Some("slow_down".to_string()),
                 error: None,
-            }),
-            "slow_down" => {
-                // 增加轮询间隔
-                {
-                    let mut state_guard = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-                    if let Some(ref mut s) = *state_guard {
-                        s.interval += 5;
-                    }
-                }
-                Ok(KiroBuilderIdPollResponse {
-                    success: true,
-                    completed: false,
-                    status: Some("slow_down".to_string()),
-                    error: None,
-                })
-            }
-            "expired_token" => {
-                // 清除状态
-                {
-                    let mut state_guard = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-                    *state_guard = None;
-                }
-                Ok(KiroBuilderIdPollResponse {
-                    success: false,
-                    completed: false,
-                    status: None,
-                    error: Some("设备码已过期".to_string()),
-                })
-            }
-            "access_denied" => {
-                // 清除状态
-                {
-                    let mut state_guard = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-                    *state_guard = None;
-                }
-                Ok(KiroBuilderIdPollResponse {
-                    success: false,
-                    completed: false,
-                    status: None,
-                    error: Some("用户拒绝授权".to_string()),
-                })
-            }
-            _ => {
-                // 清除状态
-                {
-                    let mut state_guard = KIRO_BUILDER_ID_LOGIN_STATE.write().await;
-                    *state_guard = None;
-                }
-                Ok(KiroBuilderIdPollResponse {
-                    success: false,
-                    completed: false,
-                    status: None,
-                    error: Some(format!("授权错误: {}", error)),
-                })
-            }
+            })
+        }
+        kiro_oauth::TokenPoll::Failed(error) => {
+            *KIRO_BUILDER_ID_LOGIN_STATE.write().await = None;
+            Ok(KiroBuilderIdPollResponse {
+                success: false,
+                completed: false,
+                status: None,
+                error: Some(error),
+            })
         }
-    } else {
-        Ok(KiroBuilderIdPollResponse {
-            success: false,
-            completed: false,
-            status: None,
-            error: Some(format!("未知响应: {}", status)),
-        })
     }
 }
 
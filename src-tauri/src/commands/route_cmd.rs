@@ -4,14 +4,21 @@ use crate::commands::network_cmd::get_accessible_url;
 use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::config;
 use crate::database::DbConnection;
-use crate::models::route_model::{RouteInfo, RouteListResponse};
+use crate::models::route_model::{RouteBundle, RouteInfo, RouteListResponse, API_KEY_PLACEHOLDER};
+use crate::services::provider_pool_service::ProviderPoolService;
 
 /// 获取可访问的服务器地址
 ///
-/// 使用 `get_accessible_url` 函数生成可访问的 URL。
-/// 对于 `0.0.0.0`，会转换为局域网 IP 或 `127.0.0.1`。
+/// 配置了对外访问地址时直接使用；否则使用 `get_accessible_url` 函数生成可访问的 URL
+/// （对于 `0.0.0.0`，会转换为局域网 IP 或 `127.0.0.1`），并附加路由路径前缀。
 fn get_valid_base_url(config: &config::Config) -> String {
-    get_accessible_url(&config.server.host, config.server.port)
+    config.server.normalized_public_url().unwrap_or_else(|| {
+        format!(
+            "{}{}",
+            get_accessible_url(&config.server.host, config.server.port),
+            config.server.normalized_base_path()
+        )
+    })
 }
 
 /// 获取所有可用的路由端点
@@ -20,14 +27,49 @@ pub async fn get_available_routes(
     db: tauri::State<'_, DbConnection>,
     pool_service: tauri::State<'_, ProviderPoolServiceState>,
 ) -> Result<RouteListResponse, String> {
-    // 获取配置中的服务器地址和默认 Provider
     let config = config::load_config().unwrap_or_default();
-    let base_url = get_valid_base_url(&config);
+    route_list(&config, db.inner(), &pool_service.0)
+}
+
+/// 生成所有可用路由的客户端配置包（Claude Code、Cline、Zed、环境变量）
+///
+/// 默认使用 API Key 占位符，`include_api_key` 为 true 时嵌入路由专属或全局 API Key
+#[tauri::command]
+pub async fn get_route_client_bundle(
+    include_api_key: Option<bool>,
+    db: tauri::State<'_, DbConnection>,
+    pool_service: tauri::State<'_, ProviderPoolServiceState>,
+) -> Result<RouteBundle, String> {
+    let config = config::load_config().unwrap_or_default();
+    let routes = route_list(&config, db.inner(), &pool_service.0)?;
+    let include_api_key = include_api_key.unwrap_or(false);
+
+    Ok(routes.client_bundle(|selector| {
+        if !include_api_key {
+            return API_KEY_PLACEHOLDER.to_string();
+        }
+        config
+            .routing
+            .route_api_keys
+            .iter()
+            .find(|(route, _)| route.eq_ignore_ascii_case(selector))
+            .map(|(_, key)| key.clone())
+            .unwrap_or_else(|| config.server.api_key.clone())
+    }))
+}
+
+/// 可用路由列表（含默认路由）
+fn route_list(
+    config: &config::Config,
+    db: &DbConnection,
+    pool_service: &ProviderPoolService,
+) -> Result<RouteListResponse, String> {
+    // 获取配置中的服务器地址和默认 Provider
+    let base_url = get_valid_base_url(config);
     let default_provider = config.default_provider.clone();
 
     let routes = pool_service
-        .0
-        .get_available_routes(db.inner(), &base_url)
+        .get_available_routes(db, &base_url)
         .map_err(|e| e.to_string())?;
 
     // 添加默认路由，使用配置中的默认 Provider
@@ -79,7 +121,7 @@ pub async fn get_route_curl_examples(
     let route = routes.iter().find(|r| r.selector == selector);
 
     // P0 安全修复：curl 示例使用占位符，不暴露真实 API Key
-    let api_key = "${PROXYCAST_API_KEY}";
+    let api_key = API_KEY_PLACEHOLDER;
 
     match route {
         Some(r) => Ok(r.generate_curl_examples(api_key)),
@@ -2,9 +2,13 @@
 //!
 //! 提供请求日志、统计数据和 Token 追踪的 Tauri 命令
 
+use crate::database::dao::telemetry::{
+    RollupGranularity, RollupQuery, TelemetryDao, TelemetryRollup, UserUsage,
+};
 use crate::telemetry::{
     ModelStats, ModelTokenStats, ProviderStats, ProviderTokenStats, RequestLog, RequestLogger,
     RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary, TokenTracker,
+    UserTokenStats,
 };
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
@@ -275,6 +279,26 @@ pub async fn get_token_stats_by_model(
     Ok(tokens.by_model(start, end))
 }
 
+/// 按终端用户分组 Token 统计
+#[tauri::command]
+pub async fn get_token_stats_by_user(
+    state: tauri::State<'_, TelemetryState>,
+    time_range: Option<TimeRangeParam>,
+) -> Result<HashMap<String, UserTokenStats>, String> {
+    let (start, end) = match time_range {
+        Some(r) => {
+            let range = r.to_time_range()?;
+            match range {
+                Some(tr) => (Some(tr.start), Some(tr.end)),
+                None => (None, None),
+            }
+        }
+        None => (None, None),
+    };
+    let tokens = state.tokens.read();
+    Ok(tokens.by_user(start, end))
+}
+
 /// 按天汇总 Token 统计
 #[tauri::command]
 pub async fn get_token_stats_by_day(
@@ -284,3 +308,39 @@ pub async fn get_token_stats_by_day(
     let tokens = state.tokens.read();
     Ok(tokens.by_day(days.unwrap_or(7)))
 }
+
+/// 查询持久化的汇总统计（按小时 / 按天）
+#[tauri::command]
+pub async fn get_telemetry_history(
+    db: tauri::State<'_, crate::database::DbConnection>,
+    granularity: RollupGranularity,
+    query: Option<RollupQuery>,
+) -> Result<Vec<TelemetryRollup>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    TelemetryDao::query_rollups(&conn, granularity, &query.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// 查询持久化的历史请求日志
+#[tauri::command]
+pub async fn get_request_log_history(
+    db: tauri::State<'_, crate::database::DbConnection>,
+    query: Option<RollupQuery>,
+    limit: Option<usize>,
+) -> Result<Vec<RequestLog>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    TelemetryDao::query_request_logs(&conn, &query.unwrap_or_default(), limit.unwrap_or(100))
+        .map_err(|e| e.to_string())
+}
+
+/// 查询持久化的终端用户用量
+#[tauri::command]
+pub async fn get_user_usage_history(
+    db: tauri::State<'_, crate::database::DbConnection>,
+    query: Option<RollupQuery>,
+    limit: Option<usize>,
+) -> Result<Vec<UserUsage>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    TelemetryDao::query_usage_by_user(&conn, &query.unwrap_or_default(), limit.unwrap_or(100))
+        .map_err(|e| e.to_string())
+}
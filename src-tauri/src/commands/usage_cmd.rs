@@ -1,11 +1,13 @@
 //! Usage Tauri 命令
 //!
-//! 提供 Kiro 用量查询的 Tauri 命令接口。
+//! 提供 Kiro 用量查询和凭证额度查询的 Tauri 命令接口。
 
+use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
 use crate::services::usage_service::{self, UsageInfo};
+use crate::usage::CredentialQuota;
 use crate::TokenCacheServiceState;
 use tauri::State;
 
@@ -85,6 +87,27 @@ pub async fn get_kiro_usage(
     Ok(usage_info)
 }
 
+/// 查询凭证剩余额度
+///
+/// 结果会缓存并随凭证列表展示，`refresh` 为 true 时忽略缓存重新查询。
+#[tauri::command]
+pub async fn get_credential_quota(
+    credential_uuid: String,
+    refresh: Option<bool>,
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    token_cache: State<'_, TokenCacheServiceState>,
+) -> Result<CredentialQuota, String> {
+    crate::usage::get_quota_by_uuid(
+        &pool_service.0,
+        &token_cache.0,
+        &db,
+        &credential_uuid,
+        refresh.unwrap_or(false),
+    )
+    .await
+}
+
 /// 从 Kiro 凭证文件读取 auth_method 和 profile_arn
 fn read_kiro_credential_info(creds_file_path: &str) -> Result<(String, Option<String>), String> {
     // 展开 ~ 路径
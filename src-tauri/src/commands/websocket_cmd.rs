@@ -56,12 +56,7 @@ impl WsServiceState {
     pub fn new() -> Self {
         Self {
             enabled: Arc::new(RwLock::new(false)),
-            stats: Arc::new(RwLock::new(WsStatsSnapshot {
-                total_connections: 0,
-                active_connections: 0,
-                total_messages: 0,
-                total_errors: 0,
-            })),
+            stats: Arc::new(RwLock::new(WsStatsSnapshot::default())),
             connections: Arc::new(RwLock::new(Vec::new())),
         }
     }
@@ -0,0 +1,332 @@
+//! 服务端对话压缩
+//!
+//! 上下文过长时把较早的消息交给廉价模型总结，摘要拼接到保留的第一条用户消息前面：
+//! - 只在轮次边界切分：保留部分从不含 `tool_result` 的用户消息开始，不拆开工具调用和结果
+//! - OpenAI 格式开头的 system / developer 消息原样保留
+//! - 摘要请求由调用方发送，本模块只负责切分、渲染和拼接
+
+use crate::session_files::TranscriptFormat;
+use serde_json::{json, Value};
+
+/// 开启压缩的请求头（值为 `auto`、`true` 或 `1`）
+pub const COMPACT_HEADER: &str = "x-proxycast-compact";
+
+/// 渲染摘要输入时单个工具调用 / 结果保留的最大字符数
+const MAX_TOOL_CHARS: usize = 2000;
+
+/// 至少总结的消息数（太少时压缩不划算）
+const MIN_COMPACTED_MESSAGES: usize = 2;
+
+/// 生成摘要的系统提示词
+pub const SUMMARY_INSTRUCTIONS: &str = "You compress the earlier part of a conversation between a user and an AI assistant so the assistant can continue the task without the original messages.
+Write a structured summary with these sections, omitting any that are empty:
+## Goal
+## Key decisions and constraints
+## Files, code and commands
+## Tool results worth keeping
+## Current state and open tasks
+Preserve exact identifiers, file paths, error messages and user preferences. Do not add commentary or answer the conversation.";
+
+/// 请求头是否开启压缩
+pub fn header_enabled(value: Option<&str>) -> bool {
+    matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("auto" | "true" | "1")
+    )
+}
+
+/// 压缩计划
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionPlan {
+    /// 开头原样保留的 system / developer 消息数（仅 OpenAI 格式）
+    pub leading: usize,
+    /// 保留原文的第一条消息下标，`leading..split` 之间的消息被总结
+    pub split: usize,
+}
+
+impl CompactionPlan {
+    /// 被总结的消息数
+    pub fn compacted(&self) -> usize {
+        self.split - self.leading
+    }
+}
+
+fn role(message: &Value) -> &str {
+    message.get("role").and_then(|r| r.as_str()).unwrap_or("")
+}
+
+/// 是否为新一轮对话的开始（用户消息，且不是工具结果）
+fn is_turn_start(format: TranscriptFormat, message: &Value) -> bool {
+    if role(message) != "user" {
+        return false;
+    }
+    match format {
+        TranscriptFormat::Anthropic => !message
+            .get("content")
+            .and_then(|c| c.as_array())
+            .is_some_and(|blocks| {
+                blocks
+                    .iter()
+                    .any(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+            }),
+        TranscriptFormat::OpenAI => true,
+    }
+}
+
+/// 计算切分位置：保留最近 `keep_recent` 条消息（不足一轮时向前扩展到轮次边界）
+///
+/// 没有可总结的消息时返回 None。
+pub fn plan(
+    format: TranscriptFormat,
+    messages: &[Value],
+    keep_recent: usize,
+) -> Option<CompactionPlan> {
+    let leading = match format {
+        TranscriptFormat::OpenAI => messages
+            .iter()
+            .take_while(|m| matches!(role(m), "system" | "developer"))
+            .count(),
+        TranscriptFormat::Anthropic => 0,
+    };
+    let latest = messages.len().checked_sub(keep_recent.max(1))?;
+    (leading + MIN_COMPACTED_MESSAGES..=latest)
+        .rev()
+        .find(|&i| is_turn_start(format, &messages[i]))
+        .map(|split| CompactionPlan { leading, split })
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_TOOL_CHARS {
+        let head: String = text.chars().take(MAX_TOOL_CHARS).collect();
+        format!("{}...", head)
+    } else {
+        text.to_string()
+    }
+}
+
+/// 内容（字符串或内容块数组）中的文本
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 渲染单个内容块（思考过程不计入摘要输入）
+fn render_block(block: &Value) -> Option<String> {
+    let text_of = |key: &str| block.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    match block.get("type").and_then(|t| t.as_str())? {
+        "text" => Some(text_of("text").to_string()),
+        "tool_use" => Some(format!(
+            "[tool call] {}({})",
+            text_of("name"),
+            truncate(
+                &block
+                    .get("input")
+                    .map(|i| i.to_string())
+                    .unwrap_or_default()
+            )
+        )),
+        "tool_result" => Some(format!(
+            "[tool result] {}",
+            truncate(&block.get("content").map(content_text).unwrap_or_default())
+        )),
+        "image" | "image_url" => Some("[image]".to_string()),
+        "document" | "file" => Some("[document]".to_string()),
+        _ => None,
+    }
+}
+
+/// 把消息渲染为纯文本对话记录，作为摘要模型的输入
+pub fn render_transcript(messages: &[Value]) -> String {
+    let mut sections = Vec::with_capacity(messages.len());
+    for message in messages {
+        let mut parts = Vec::new();
+        match message.get("content") {
+            Some(Value::String(text)) => parts.push(text.clone()),
+            Some(Value::Array(blocks)) => parts.extend(blocks.iter().filter_map(render_block)),
+            _ => {}
+        }
+        // OpenAI 格式的工具调用
+        if let Some(calls) = message.get("tool_calls").and_then(|c| c.as_array()) {
+            for call in calls {
+                let function = &call["function"];
+                parts.push(format!(
+                    "[tool call] {}({})",
+                    function["name"].as_str().unwrap_or(""),
+                    truncate(function["arguments"].as_str().unwrap_or(""))
+                ));
+            }
+        }
+        let label = match role(message) {
+            "tool" => {
+                // OpenAI 格式的工具结果
+                let text = parts.join("\n");
+                parts = vec![format!("[tool result] {}", truncate(&text))];
+                "TOOL".to_string()
+            }
+            other => other.to_uppercase(),
+        };
+        let text = parts.join("\n");
+        if !text.trim().is_empty() {
+            sections.push(format!("{}: {}", label, text.trim()));
+        }
+    }
+    sections.join("\n\n")
+}
+
+/// 用摘要替换被总结的消息，摘要拼接到保留的第一条用户消息前面
+pub fn splice_summary(messages: &mut Vec<Value>, plan: &CompactionPlan, summary: &str) {
+    let note = format!(
+        "<conversation_summary>\n{}\n</conversation_summary>\n\n\
+         The earlier part of this conversation was compacted into the summary above. \
+         Continue from the messages below.",
+        summary.trim()
+    );
+    messages.drain(plan.leading..plan.split);
+    let Some(first) = messages.get_mut(plan.leading) else {
+        return;
+    };
+    match first.get_mut("content") {
+        Some(Value::Array(blocks)) => blocks.insert(0, json!({"type": "text", "text": note})),
+        Some(Value::String(text)) => *text = format!("{}\n\n{}", note, text),
+        _ => first["content"] = json!(note),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anthropic_conversation() -> Vec<Value> {
+        vec![
+            json!({"role": "user", "content": "Fix the failing test in src/lib.rs"}),
+            json!({"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "..."},
+                {"type": "tool_use", "id": "t1", "name": "read_file", "input": {"path": "src/lib.rs"}}
+            ]}),
+            json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": "fn add() {}"}
+            ]}),
+            json!({"role": "assistant", "content": [{"type": "text", "text": "The test expects add(1, 2) == 3."}]}),
+            json!({"role": "user", "content": [{"type": "text", "text": "Now run cargo test"}]}),
+            json!({"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t2", "name": "bash", "input": {"command": "cargo test"}}
+            ]}),
+            json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t2", "content": [{"type": "text", "text": "ok"}]}
+            ]}),
+        ]
+    }
+
+    #[test]
+    fn test_header_enabled() {
+        assert!(header_enabled(Some("auto")));
+        assert!(header_enabled(Some(" TRUE ")));
+        assert!(!header_enabled(Some("off")));
+        assert!(!header_enabled(None));
+    }
+
+    #[test]
+    fn test_plan_respects_tool_results() {
+        let messages = anthropic_conversation();
+        // 最近 2 条是工具调用和结果，向前扩展到 "Now run cargo test"
+        let plan = plan(TranscriptFormat::Anthropic, &messages, 2).unwrap();
+        assert_eq!(
+            plan,
+            CompactionPlan {
+                leading: 0,
+                split: 4
+            }
+        );
+        assert_eq!(plan.compacted(), 4);
+
+        // 可总结的消息太少
+        assert_eq!(
+            super::plan(TranscriptFormat::Anthropic, &messages[..3], 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_plan_keeps_openai_system_messages() {
+        let messages = vec![
+            json!({"role": "system", "content": "You are helpful"}),
+            json!({"role": "user", "content": "a"}),
+            json!({"role": "assistant", "content": "b"}),
+            json!({"role": "user", "content": "c"}),
+            json!({"role": "assistant", "content": null, "tool_calls": [
+                {"id": "c1", "type": "function", "function": {"name": "ls", "arguments": "{}"}}
+            ]}),
+            json!({"role": "tool", "tool_call_id": "c1", "content": "README.md"}),
+        ];
+        let plan = plan(TranscriptFormat::OpenAI, &messages, 2).unwrap();
+        assert_eq!(
+            plan,
+            CompactionPlan {
+                leading: 1,
+                split: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_transcript() {
+        let messages = anthropic_conversation();
+        let transcript = render_transcript(&messages[..4]);
+        assert_eq!(
+            transcript,
+            "USER: Fix the failing test in src/lib.rs\n\n\
+             ASSISTANT: [tool call] read_file({\"path\":\"src/lib.rs\"})\n\n\
+             USER: [tool result] fn add() {}\n\n\
+             ASSISTANT: The test expects add(1, 2) == 3."
+        );
+
+        let openai = vec![
+            json!({"role": "assistant", "content": null, "tool_calls": [
+                {"id": "c1", "type": "function", "function": {"name": "ls", "arguments": "{}"}}
+            ]}),
+            json!({"role": "tool", "tool_call_id": "c1", "content": "README.md"}),
+        ];
+        assert_eq!(
+            render_transcript(&openai),
+            "ASSISTANT: [tool call] ls({})\n\nTOOL: [tool result] README.md"
+        );
+    }
+
+    #[test]
+    fn test_splice_summary() {
+        let mut messages = anthropic_conversation();
+        let plan = plan(TranscriptFormat::Anthropic, &messages, 2).unwrap();
+        splice_summary(&mut messages, &plan, "## Goal\nFix the test");
+
+        assert_eq!(messages.len(), 3);
+        let blocks = messages[0]["content"].as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("<conversation_summary>\n## Goal\nFix the test\n</conversation_summary>"));
+        assert_eq!(blocks[1]["text"], "Now run cargo test");
+
+        let mut messages = vec![
+            json!({"role": "system", "content": "sys"}),
+            json!({"role": "user", "content": "a"}),
+            json!({"role": "assistant", "content": "b"}),
+            json!({"role": "user", "content": "c"}),
+        ];
+        let plan = CompactionPlan {
+            leading: 1,
+            split: 3,
+        };
+        splice_summary(&mut messages, &plan, "summary");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["content"], "sys");
+        assert!(messages[1]["content"].as_str().unwrap().ends_with("\n\nc"));
+    }
+}
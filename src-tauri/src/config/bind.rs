@@ -0,0 +1,188 @@
+//! 监听地址验证和解析
+//!
+//! 允许的监听地址：
+//! - `localhost`（同时监听 `127.0.0.1` 和 `::1`）
+//! - 回环地址（`127.0.0.0/8`、`::1`）
+//! - 所有接口（`0.0.0.0`、`::`）
+//! - 局域网地址（`10.0.0.0/8`、`172.16.0.0/12`、`192.168.0.0/16`、IPv6 唯一本地地址 `fc00::/7`）
+//!
+//! IPv6 地址可以带方括号（如 `[::1]`）。
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// 去掉 IPv6 地址的方括号
+fn strip_brackets(host: &str) -> &str {
+    let host = host.trim();
+    host.strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+/// 解析为 IP 地址（`localhost` 返回 None）
+fn parse_ip(host: &str) -> Result<Option<IpAddr>, String> {
+    let host = strip_brackets(host);
+    if host.eq_ignore_ascii_case("localhost") {
+        return Ok(None);
+    }
+    if host.is_empty() {
+        return Err("监听地址不能为空".to_string());
+    }
+    if host.contains('%') {
+        return Err(format!(
+            "监听地址 {} 无效：不支持带网卡标识（%）的 IPv6 链路本地地址，请改用 :: 或唯一本地地址",
+            host
+        ));
+    }
+    host.parse::<IpAddr>().map(Some).map_err(|_| {
+        format!(
+            "监听地址 {} 无效：必须是 IP 地址或 localhost，不支持主机名",
+            host
+        )
+    })
+}
+
+/// IPv4 映射的 IPv6 地址（`::ffff:a.b.c.d`）按 IPv4 处理
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        ip => ip,
+    }
+}
+
+fn is_private_ip(ip: IpAddr) -> bool {
+    match canonical_ip(ip) {
+        IpAddr::V4(v4) => v4.is_private(),
+        // fc00::/7 唯一本地地址
+        IpAddr::V6(v6) => (v6.segments()[0] & 0xfe00) == 0xfc00,
+    }
+}
+
+/// 验证监听地址，无效时返回说明原因的错误信息
+pub fn validate_bind_host(host: &str) -> Result<(), String> {
+    let Some(ip) = parse_ip(host)? else {
+        return Ok(());
+    };
+    let ip = canonical_ip(ip);
+    if ip.is_loopback() || ip.is_unspecified() || is_private_ip(ip) {
+        return Ok(());
+    }
+    let reason = match ip {
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xffc0) == 0xfe80 => {
+            "IPv6 链路本地地址需要网卡标识，请改用 :: 或唯一本地地址（fc00::/7）"
+        }
+        IpAddr::V4(v4) if v4.is_link_local() => "不允许链路本地地址（169.254.0.0/16）",
+        _ if ip.is_multicast() => "不允许组播地址",
+        _ => "出于安全考虑不允许监听公网地址",
+    };
+    Err(format!(
+        "监听地址 {} 无效：{}。允许的地址：localhost、回环地址（127.0.0.1、::1）、所有接口（0.0.0.0、::）或局域网地址",
+        strip_brackets(host),
+        reason
+    ))
+}
+
+/// 是否只监听本机（localhost 或回环地址）
+pub fn is_loopback_bind(host: &str) -> bool {
+    match parse_ip(host) {
+        Ok(None) => true,
+        Ok(Some(ip)) => canonical_ip(ip).is_loopback(),
+        Err(_) => false,
+    }
+}
+
+/// 是否为非本地监听地址（所有接口或局域网地址，需要强 API Key）
+pub fn is_non_local_bind(host: &str) -> bool {
+    match parse_ip(host) {
+        Ok(Some(ip)) => {
+            let ip = canonical_ip(ip);
+            ip.is_unspecified() || is_private_ip(ip)
+        }
+        _ => false,
+    }
+}
+
+/// 解析实际要绑定的套接字地址（`localhost` 同时绑定 IPv4 和 IPv6 回环地址）
+pub fn resolve_bind_addrs(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    validate_bind_host(host)?;
+    Ok(match parse_ip(host)? {
+        Some(ip) => vec![SocketAddr::new(ip, port)],
+        None => vec![
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), port),
+        ],
+    })
+}
+
+/// 用于 URL 的主机名（IPv6 地址加方括号）
+pub fn url_host(host: &str) -> String {
+    let host = strip_brackets(host);
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_bind_host() {
+        for host in [
+            "localhost",
+            "127.0.0.1",
+            "::1",
+            "[::1]",
+            "0.0.0.0",
+            "::",
+            "192.168.1.10",
+            "172.16.0.1",
+            "fd12:3456::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(validate_bind_host(host).is_ok(), "{}", host);
+        }
+
+        for (host, reason) in [
+            ("8.8.8.8", "公网地址"),
+            ("2001:db8::1", "公网地址"),
+            ("fe80::1", "链路本地"),
+            ("fe80::1%eth0", "网卡标识"),
+            ("example.com", "主机名"),
+            ("", "不能为空"),
+        ] {
+            let err = validate_bind_host(host).unwrap_err();
+            assert!(err.contains(reason), "{}: {}", host, err);
+        }
+    }
+
+    #[test]
+    fn test_resolve_bind_addrs_and_url_host() {
+        assert_eq!(
+            resolve_bind_addrs("localhost", 8999).unwrap(),
+            vec![
+                "127.0.0.1:8999".parse().unwrap(),
+                "[::1]:8999".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            resolve_bind_addrs("[::]", 80).unwrap(),
+            vec!["[::]:80".parse().unwrap()]
+        );
+        assert!(resolve_bind_addrs("1.1.1.1", 80).is_err());
+
+        assert_eq!(url_host("::1"), "[::1]");
+        assert_eq!(url_host("[::1]"), "[::1]");
+        assert_eq!(url_host("127.0.0.1"), "127.0.0.1");
+
+        assert!(is_loopback_bind("[::1]"));
+        assert!(!is_loopback_bind("::"));
+        assert!(is_non_local_bind("::"));
+        assert!(is_non_local_bind("fd00::1"));
+        assert!(!is_non_local_bind("localhost"));
+    }
+}
@@ -178,6 +178,16 @@ impl From<ConfigError> for ExportError {
 /// 脱敏占位符
 pub const REDACTED_PLACEHOLDER: &str = "***REDACTED***";
 
+/// 以映射键保存的密钥使用的占位符（`***REDACTED***#1`、`***REDACTED***#2`……）
+fn redacted_key(index: usize) -> String {
+    format!("{}#{}", REDACTED_PLACEHOLDER, index + 1)
+}
+
+/// 是否为脱敏占位符（包括带序号的映射键占位符）
+pub fn is_redacted(value: &str) -> bool {
+    value.starts_with(REDACTED_PLACEHOLDER)
+}
+
 /// 导出服务
 ///
 /// 提供配置和凭证的统一导出功能
@@ -301,6 +311,46 @@ impl ExportService {
         // 脱敏凭证池中的 API Key
         redacted.credential_pool = Self::redact_credential_pool(&config.credential_pool);
 
+        // 脱敏路由专属 API Key
+        for key in redacted.routing.route_api_keys.values_mut() {
+            *key = REDACTED_PLACEHOLDER.to_string();
+        }
+        for entry in &mut redacted.routing.api_key_default_providers {
+            entry.api_key = REDACTED_PLACEHOLDER.to_string();
+        }
+
+        // 脱敏额外监听端口和远程管理密钥
+        for listener in &mut redacted.server.listeners {
+            if listener.api_key.is_some() {
+                listener.api_key = Some(REDACTED_PLACEHOLDER.to_string());
+            }
+        }
+        if redacted.remote_management.secret_key.is_some() {
+            redacted.remote_management.secret_key = Some(REDACTED_PLACEHOLDER.to_string());
+        }
+        if !redacted.response_signing.key.is_empty() {
+            redacted.response_signing.key = REDACTED_PLACEHOLDER.to_string();
+        }
+
+        // Webhook 地址本身即为凭据（如 Slack / Discord Webhook）
+        for webhook in redacted
+            .alerts
+            .webhooks
+            .iter_mut()
+            .chain(redacted.reports.webhooks.iter_mut())
+        {
+            webhook.url = REDACTED_PLACEHOLDER.to_string();
+        }
+
+        // 护栏策略以入站 API Key 为键，用带序号的占位符保留条目数量
+        redacted.guardrails.api_key_policies = config
+            .guardrails
+            .api_key_policies
+            .values()
+            .enumerate()
+            .map(|(i, policy)| (redacted_key(i), policy.clone()))
+            .collect();
+
         redacted
     }
 
@@ -332,8 +382,24 @@ impl ExportService {
                     proxy_url: entry.proxy_url.clone(),
                 })
                 .collect(),
-            gemini_api_keys: pool.gemini_api_keys.clone(),
-            vertex_api_keys: pool.vertex_api_keys.clone(),
+            gemini_api_keys: pool
+                .gemini_api_keys
+                .iter()
+                .map(|entry| {
+                    let mut entry = entry.clone();
+                    entry.api_key = REDACTED_PLACEHOLDER.to_string();
+                    entry
+                })
+                .collect(),
+            vertex_api_keys: pool
+                .vertex_api_keys
+                .iter()
+                .map(|entry| {
+                    let mut entry = entry.clone();
+                    entry.api_key = REDACTED_PLACEHOLDER.to_string();
+                    entry
+                })
+                .collect(),
             codex: pool.codex.clone(),
             iflow: pool.iflow.clone(),
         }
@@ -372,6 +438,68 @@ impl ExportService {
             }
         }
 
+        // 检查路由专属 API Key
+        if config
+            .routing
+            .route_api_keys
+            .values()
+            .any(|key| !key.is_empty() && key != REDACTED_PLACEHOLDER)
+        {
+            return true;
+        }
+        if config
+            .routing
+            .api_key_default_providers
+            .iter()
+            .any(|entry| !entry.api_key.is_empty() && entry.api_key != REDACTED_PLACEHOLDER)
+        {
+            return true;
+        }
+
+        // 检查 Gemini / Vertex API Key
+        let pool = &config.credential_pool;
+        if pool
+            .gemini_api_keys
+            .iter()
+            .map(|entry| &entry.api_key)
+            .chain(pool.vertex_api_keys.iter().map(|entry| &entry.api_key))
+            .any(|key| !key.is_empty() && key != REDACTED_PLACEHOLDER)
+        {
+            return true;
+        }
+
+        // 检查监听端口、远程管理密钥、响应签名密钥和护栏策略
+        if config
+            .server
+            .listeners
+            .iter()
+            .filter_map(|listener| listener.api_key.as_deref())
+            .chain(config.remote_management.secret_key.as_deref())
+            .chain(Some(config.response_signing.key.as_str()))
+            .any(|key| !key.is_empty() && key != REDACTED_PLACEHOLDER)
+        {
+            return true;
+        }
+        if config
+            .guardrails
+            .api_key_policies
+            .keys()
+            .any(|key| !is_redacted(key))
+        {
+            return true;
+        }
+
+        // 检查告警和报告 Webhook 地址
+        if config
+            .alerts
+            .webhooks
+            .iter()
+            .chain(&config.reports.webhooks)
+            .any(|webhook| !webhook.url.is_empty() && webhook.url != REDACTED_PLACEHOLDER)
+        {
+            return true;
+        }
+
         false
     }
 
@@ -651,6 +779,73 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn test_redact_config_covers_all_secrets() {
+        use crate::config::{AlertWebhookConfig, GeminiApiKeyEntry, ListenerConfig};
+
+        let mut config = Config::default();
+        config.server.api_key = "secret-key".to_string();
+        config.server.listeners.push(ListenerConfig {
+            name: None,
+            host: None,
+            port: 9000,
+            api_key: Some("listener-secret".to_string()),
+            routes: Vec::new(),
+        });
+        config.remote_management.secret_key = Some("management-secret".to_string());
+        config.response_signing.key = "signing-secret".to_string();
+        config
+            .credential_pool
+            .gemini_api_keys
+            .push(GeminiApiKeyEntry {
+                id: "gemini-1".to_string(),
+                api_key: "AIza-gemini-secret".to_string(),
+                base_url: None,
+                proxy_url: None,
+                excluded_models: Vec::new(),
+                disabled: false,
+            });
+        config
+            .guardrails
+            .api_key_policies
+            .insert("guardrail-secret".to_string(), "strict".to_string());
+        config.alerts.webhooks.push(AlertWebhookConfig {
+            url: "https://hooks.slack.com/services/alert-secret".to_string(),
+            format: Default::default(),
+        });
+        config.reports.webhooks.push(AlertWebhookConfig {
+            url: "https://discord.com/api/webhooks/report-secret".to_string(),
+            format: Default::default(),
+        });
+        assert!(ExportService::contains_secrets(&config));
+
+        let redacted = ExportService::redact_config(&config);
+        assert!(!ExportService::contains_secrets(&redacted));
+        let yaml = serde_yaml::to_string(&redacted).unwrap();
+        for secret in [
+            "listener-secret",
+            "management-secret",
+            "signing-secret",
+            "AIza-gemini-secret",
+            "guardrail-secret",
+            "alert-secret",
+            "report-secret",
+        ] {
+            assert!(!yaml.contains(secret), "{secret} 未脱敏");
+        }
+        assert_eq!(
+            redacted.guardrails.api_key_policies.get(&redacted_key(0)),
+            Some(&"strict".to_string())
+        );
+        assert_eq!(redacted.alerts.webhooks[0].url, REDACTED_PLACEHOLDER);
+        assert_eq!(redacted.reports.webhooks[0].url, REDACTED_PLACEHOLDER);
+
+        // 只有 Webhook 地址未脱敏时同样视为包含敏感信息
+        let mut partial = redacted.clone();
+        partial.reports.webhooks[0].url = "https://example.com/hook".to_string();
+        assert!(ExportService::contains_secrets(&partial));
+    }
+
     #[test]
     fn test_contains_secrets() {
         let mut config = Config::default();
@@ -7,6 +7,7 @@
 #![allow(dead_code)]
 //! - 失败时自动回滚到之前的配置
 
+use super::bind::validate_bind_host;
 use super::types::{is_default_api_key, Config};
 use super::yaml::ConfigManager;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
@@ -55,6 +56,8 @@ pub enum ReloadResult {
     Success {
         /// 重载时间戳
         timestamp: Instant,
+        /// 发生变化的配置项路径（如 `routing.default_provider`）
+        changes: Vec<String>,
     },
     /// 重载失败，已回滚
     RolledBack {
@@ -74,6 +77,99 @@ pub enum ReloadResult {
     },
 }
 
+impl ReloadResult {
+    /// 转换为可序列化的重载报告
+    pub fn report(&self) -> ReloadReport {
+        match self {
+            ReloadResult::Success { changes, .. } => ReloadReport {
+                status: "success",
+                changes: changes.clone(),
+                error: None,
+                rolled_back: false,
+                rollback_error: None,
+            },
+            ReloadResult::RolledBack { error, .. } => ReloadReport {
+                status: "rolled_back",
+                changes: Vec::new(),
+                error: Some(error.clone()),
+                rolled_back: true,
+                rollback_error: None,
+            },
+            ReloadResult::Failed {
+                error,
+                rollback_error,
+                ..
+            } => ReloadReport {
+                status: "failed",
+                changes: Vec::new(),
+                error: Some(error.clone()),
+                rolled_back: false,
+                rollback_error: rollback_error.clone(),
+            },
+        }
+    }
+}
+
+/// 重载报告（管理接口返回）
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ReloadReport {
+    /// `success` / `rolled_back` / `failed`
+    pub status: &'static str,
+    /// 发生变化的配置项路径
+    pub changes: Vec<String>,
+    /// 错误信息
+    pub error: Option<String>,
+    /// 是否已保留重载前的配置
+    pub rolled_back: bool,
+    /// 回滚错误
+    pub rollback_error: Option<String>,
+}
+
+/// 比较两份配置，返回发生变化的配置项路径（按字母顺序）
+///
+/// 对象逐层比较，数组整体比较；只返回路径，不包含配置值（避免泄露密钥）。
+pub fn config_changes(old: &Config, new: &Config) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut changes = Vec::new();
+    collect_changes("", &old, &new, &mut changes);
+    changes.sort();
+    changes
+}
+
+fn collect_changes(
+    path: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    changes: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                collect_changes(
+                    &child,
+                    old.get(key).unwrap_or(&Value::Null),
+                    new.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        _ => changes.push(path.to_string()),
+    }
+}
+
 /// 配置变更事件
 #[derive(Debug, Clone)]
 pub struct ConfigChangeEvent {
@@ -304,10 +400,10 @@ impl HotReloadManager {
         let now = Instant::now();
 
         // 1. 备份当前配置
+        let previous = self.current_config.read().clone();
         {
-            let current = self.current_config.read().clone();
             let mut backup = self.backup_config.write();
-            *backup = Some(current);
+            *backup = Some(previous.clone());
         }
 
         // 2. 尝试加载新配置
@@ -336,6 +432,7 @@ impl HotReloadManager {
         }
 
         // 4. 原子性地应用新配置
+        let changes = config_changes(&previous, &new_config);
         {
             let mut current = self.current_config.write();
             *current = new_config;
@@ -353,8 +450,11 @@ impl HotReloadManager {
             *backup = None;
         }
 
-        tracing::info!("配置热重载成功");
-        ReloadResult::Success { timestamp: now }
+        tracing::info!("配置热重载成功，{} 项配置变化", changes.len());
+        ReloadResult::Success {
+            timestamp: now,
+            changes,
+        }
     }
 
     /// 从文件加载配置
@@ -374,10 +474,6 @@ impl HotReloadManager {
 
     /// 验证配置
     fn validate_config(&self, config: &Config) -> Result<(), HotReloadError> {
-        let is_localhost = is_localhost_host(&config.server.host);
-        let is_valid_host = is_valid_bind_host(&config.server.host);
-        let is_non_local = is_non_local_bind(&config.server.host);
-
         // 验证端口范围
         if config.server.port == 0 {
             return Err(HotReloadError::ValidationError(
@@ -385,11 +481,9 @@ impl HotReloadManager {
             ));
         }
 
-        // 验证绑定地址
-        if !is_valid_host {
-            return Err(HotReloadError::ValidationError(
-                "无效的监听地址。允许的地址：127.0.0.1、localhost、::1、0.0.0.0、::".to_string(),
-            ));
+        // 验证绑定地址（包括同一端口的额外地址）
+        for host in std::iter::once(&config.server.host).chain(&config.server.additional_hosts) {
+            validate_bind_host(host).map_err(HotReloadError::ValidationError)?;
         }
 
         // 验证重试配置
@@ -430,6 +524,98 @@ impl HotReloadManager {
             ));
         }
 
+        // 验证额外监听端口
+        let mut ports = std::collections::HashSet::from([config.server.port]);
+        for listener in &config.server.listeners {
+            if !ports.insert(listener.port) || listener.port == 0 {
+                return Err(HotReloadError::ValidationError(format!(
+                    "额外监听端口 {} 无效（不能为 0 或与其他端口重复）",
+                    listener.port
+                )));
+            }
+            if let Err(e) = validate_bind_host(listener.host_or(&config.server.host)) {
+                return Err(HotReloadError::ValidationError(format!(
+                    "额外监听端口 {} 的{}",
+                    listener.port, e
+                )));
+            }
+            if listener
+                .api_key
+                .as_ref()
+                .is_some_and(|key| key.trim().is_empty())
+            {
+                return Err(HotReloadError::ValidationError(format!(
+                    "额外监听端口 {} 的 API Key 不能为空",
+                    listener.port
+                )));
+            }
+        }
+
+        // 验证路由专属 API Key
+        if let Some(selector) = config
+            .routing
+            .route_api_keys
+            .iter()
+            .find(|(_, key)| key.trim().is_empty())
+            .map(|(selector, _)| selector)
+        {
+            return Err(HotReloadError::ValidationError(format!(
+                "路由 '{}' 的专属 API Key 不能为空",
+                selector
+            )));
+        }
+
+        // 验证命名空间 / API Key 默认 Provider
+        if let Some(namespace) = config
+            .routing
+            .namespace_default_providers
+            .iter()
+            .find(|(_, provider)| provider.trim().is_empty())
+            .map(|(namespace, _)| namespace)
+        {
+            return Err(HotReloadError::ValidationError(format!(
+                "命名空间 '{}' 的默认 Provider 不能为空",
+                namespace
+            )));
+        }
+        if config
+            .routing
+            .api_key_default_providers
+            .iter()
+            .any(|entry| entry.api_key.trim().is_empty() || entry.provider.trim().is_empty())
+        {
+            return Err(HotReloadError::ValidationError(
+                "API Key 默认 Provider 的 api_key 和 provider 不能为空".to_string(),
+            ));
+        }
+
+        // 验证模型别名规则
+        crate::router::validate_alias_rules(&config.routing.model_alias_rules)
+            .map_err(HotReloadError::ValidationError)?;
+
+        // 验证请求优先级通道
+        if !(0.0..=1.0).contains(&config.priority_lanes.interactive_reserve_ratio) {
+            return Err(HotReloadError::ValidationError(
+                "交互式请求保留比例必须在 0.0 到 1.0 之间".to_string(),
+            ));
+        }
+
+        // 验证上游连通性负缓存
+        let reachability = &config.upstream_reachability;
+        if reachability.enabled
+            && (reachability.negative_ttl_secs == 0
+                || reachability.probe_interval_secs == 0
+                || reachability.probe_timeout_secs == 0)
+        {
+            return Err(HotReloadError::ValidationError(
+                "上游连通性负缓存的有效期、探测间隔和探测超时必须大于 0".to_string(),
+            ));
+        }
+
+        // 验证上游 TLS 配置（CA 证书文件、公钥固定值）
+        crate::providers::tls::validate_upstream_tls_config(&config.upstream_tls)
+            .map_err(HotReloadError::ValidationError)?;
+
         Ok(())
     }
 
@@ -470,59 +656,6 @@ impl HotReloadManager {
     }
 }
 
-fn is_localhost_host(host: &str) -> bool {
-    if host == "localhost" {
-        return true;
-    }
-    host.parse::<std::net::IpAddr>()
-        .map(|addr| addr.is_loopback())
-        .unwrap_or(false)
-}
-
-/// 检查是否为有效的绑定地址
-/// 允许回环地址、0.0.0.0 和私有网络地址
-fn is_valid_bind_host(host: &str) -> bool {
-    if is_localhost_host(host) {
-        return true;
-    }
-    // 允许 0.0.0.0 和 :: （监听所有接口）
-    if host == "0.0.0.0" || host == "::" {
-        return true;
-    }
-
-    // 允许私有网络地址
-    if let Ok(addr) = host.parse::<std::net::IpAddr>() {
-        if let std::net::IpAddr::V4(ipv4) = addr {
-            // 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16
-            let octets = ipv4.octets();
-            return octets[0] == 10
-                || (octets[0] == 172 && (octets[1] >= 16 && octets[1] <= 31))
-                || (octets[0] == 192 && octets[1] == 168);
-        }
-    }
-
-    false
-}
-
-/// 检查是否为非本地绑定地址（需要强 API Key）
-fn is_non_local_bind(host: &str) -> bool {
-    if host == "0.0.0.0" || host == "::" {
-        return true;
-    }
-
-    // 私有网络地址也算非本地绑定
-    if let Ok(addr) = host.parse::<std::net::IpAddr>() {
-        if let std::net::IpAddr::V4(ipv4) = addr {
-            let octets = ipv4.octets();
-            return octets[0] == 10
-                || (octets[0] == 172 && (octets[1] >= 16 && octets[1] <= 31))
-                || (octets[0] == 192 && octets[1] == 168);
-        }
-    }
-
-    false
-}
-
 /// 热重载状态
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct HotReloadStatus {
@@ -608,16 +741,60 @@ logging:
 
         let result = manager.reload();
         match result {
-            ReloadResult::Success { .. } => {
+            ReloadResult::Success { ref changes, .. } => {
                 let new_config = manager.config();
                 assert_eq!(new_config.server.port, 9000);
                 assert_eq!(new_config.retry.max_retries, 5);
                 assert_eq!(new_config.logging.level, "debug");
+                assert!(changes.contains(&"server.port".to_string()));
+                assert!(changes.contains(&"retry.max_retries".to_string()));
+                assert_eq!(result.report().status, "success");
             }
             _ => panic!("Expected Success result"),
         }
     }
 
+    #[test]
+    fn test_config_changes() {
+        let old = Config::default();
+        assert!(config_changes(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.server.api_key = "sk-changed".to_string();
+        new.routing
+            .model_aliases
+            .insert("gpt-4".to_string(), "claude-sonnet-4-5".to_string());
+        let changes = config_changes(&old, &new);
+        assert_eq!(
+            changes,
+            vec!["routing.model_aliases.gpt-4", "server.api_key"]
+        );
+
+        // 只返回路径，不包含配置值
+        assert!(!changes.iter().any(|c| c.contains("sk-changed")));
+    }
+
+    #[test]
+    fn test_reload_report() {
+        let report = ReloadResult::Failed {
+            error: "boom".to_string(),
+            rollback_error: Some("no backup".to_string()),
+            timestamp: Instant::now(),
+        }
+        .report();
+        assert_eq!(report.status, "failed");
+        assert!(!report.rolled_back);
+        assert_eq!(report.rollback_error.as_deref(), Some("no backup"));
+
+        let report = ReloadResult::RolledBack {
+            error: "invalid".to_string(),
+            timestamp: Instant::now(),
+        }
+        .report();
+        assert_eq!(report.status, "rolled_back");
+        assert!(report.rolled_back);
+    }
+
     #[test]
     fn test_hot_reload_manager_reload_invalid_yaml() {
         // 创建临时配置文件（无效 YAML）
@@ -663,6 +840,27 @@ server:
         }
     }
 
+    #[test]
+    fn test_hot_reload_manager_rejects_duplicate_listener_port() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let yaml_content = r#"
+server:
+  host: "127.0.0.1"
+  port: 8999
+  api_key: "test-key"
+  listeners:
+    - port: 8999
+      routes: [anthropic]
+"#;
+        temp_file.write_all(yaml_content.as_bytes()).unwrap();
+
+        let manager = HotReloadManager::new(Config::default(), temp_file.path().to_path_buf());
+        match manager.reload() {
+            ReloadResult::RolledBack { error, .. } => assert!(error.contains("8999")),
+            _ => panic!("Expected RolledBack result"),
+        }
+    }
+
     #[test]
     fn test_config_change_kind_eq() {
         assert_eq!(ConfigChangeKind::Modified, ConfigChangeKind::Modified);
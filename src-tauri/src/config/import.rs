@@ -8,7 +8,7 @@
 //! - 导入验证（格式、版本、脱敏状态）
 //! - 合并和替换模式
 
-use super::export::{base64_decode, ExportBundle, REDACTED_PLACEHOLDER};
+use super::export::{base64_decode, is_redacted, ExportBundle, REDACTED_PLACEHOLDER};
 use super::path_utils::expand_tilde;
 use super::types::{ApiKeyEntry, Config, CredentialEntry, CredentialPoolConfig};
 use super::yaml::{ConfigError, ConfigManager, YamlService};
@@ -497,6 +497,53 @@ impl ImportService {
             config.providers.claude.api_key = None;
         }
 
+        // 清理脱敏的路由专属 API Key
+        config
+            .routing
+            .route_api_keys
+            .retain(|_, key| key != REDACTED_PLACEHOLDER);
+        config
+            .routing
+            .api_key_default_providers
+            .retain(|entry| entry.api_key != REDACTED_PLACEHOLDER);
+
+        // 清理脱敏的 Gemini / Vertex API Key
+        config
+            .credential_pool
+            .gemini_api_keys
+            .retain(|e| e.api_key != REDACTED_PLACEHOLDER);
+        config
+            .credential_pool
+            .vertex_api_keys
+            .retain(|e| e.api_key != REDACTED_PLACEHOLDER);
+
+        // 清理脱敏的监听端口密钥、远程管理密钥、响应签名密钥和护栏策略
+        for listener in &mut config.server.listeners {
+            if listener.api_key.as_deref() == Some(REDACTED_PLACEHOLDER) {
+                listener.api_key = None;
+            }
+        }
+        if config.remote_management.secret_key.as_deref() == Some(REDACTED_PLACEHOLDER) {
+            config.remote_management.secret_key = None;
+        }
+        if config.response_signing.key == REDACTED_PLACEHOLDER {
+            config.response_signing.key.clear();
+        }
+        config
+            .guardrails
+            .api_key_policies
+            .retain(|key, _| !is_redacted(key));
+
+        // 清理脱敏的告警和报告 Webhook
+        config
+            .alerts
+            .webhooks
+            .retain(|webhook| webhook.url != REDACTED_PLACEHOLDER);
+        config
+            .reports
+            .webhooks
+            .retain(|webhook| webhook.url != REDACTED_PLACEHOLDER);
+
         // 清理服务器 API 密钥（如果是脱敏的，清空并提示手动设置）
         if config.server.api_key == REDACTED_PLACEHOLDER {
             config.server.api_key = String::new();
@@ -766,6 +813,13 @@ credential_pool:
             disabled: false,
             proxy_url: None,
         });
+        config
+            .alerts
+            .webhooks
+            .push(crate::config::AlertWebhookConfig {
+                url: REDACTED_PLACEHOLDER.to_string(),
+                format: Default::default(),
+            });
 
         let server_key_cleared = ImportService::clean_redacted_credentials(&mut config);
 
@@ -777,6 +831,8 @@ credential_pool:
         // 凭证池中脱敏的条目应被移除
         assert_eq!(config.credential_pool.openai.len(), 1);
         assert_eq!(config.credential_pool.openai[0].id, "real");
+        // 脱敏的 Webhook 应被移除
+        assert!(config.alerts.webhooks.is_empty());
     }
 
     #[test]
@@ -5,6 +5,7 @@
 
 #![allow(unused_imports)]
 
+mod bind;
 mod export;
 mod hot_reload;
 mod import;
@@ -13,21 +14,37 @@ mod path_utils;
 mod types;
 mod yaml;
 
+pub use bind::{
+    is_loopback_bind, is_non_local_bind, resolve_bind_addrs, url_host, validate_bind_host,
+};
 pub use export::{ExportBundle, ExportOptions, ExportService, REDACTED_PLACEHOLDER};
 pub use hot_reload::{
-    ConfigChangeEvent as FileChangeEvent, ConfigChangeKind, FileWatcher, HotReloadManager,
-    ReloadResult,
+    config_changes, ConfigChangeEvent as FileChangeEvent, ConfigChangeKind, FileWatcher,
+    HotReloadManager, ReloadReport, ReloadResult,
 };
 pub use import::{ImportOptions, ImportService, ValidationResult};
 pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
 pub use types::{
-    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, Config, CredentialEntry,
-    CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig, ExperimentalFeatures,
-    GeminiApiKeyEntry, IFlowCredentialEntry, InjectionRuleConfig, InjectionSettings, LoggingConfig,
-    ModelInfo, ModelsConfig, NativeAgentConfig, ProviderConfig, ProviderModelsConfig,
-    ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
-    ScreenshotChatConfig, ServerConfig, TlsConfig, VertexApiKeyEntry, VertexModelAlias,
-    DEFAULT_API_KEY,
+    generate_secure_api_key, AlertCondition, AlertRuleConfig, AlertWebhookConfig,
+    AlertWebhookFormat, AlertsConfig, AmpConfig, AmpModelMapping, ApiKeyDefaultProvider,
+    ApiKeyEntry, BodyLimitsConfig, ChaosConfig, ChaosRule, ClusterConfig, CompactionConfig, Config,
+    ContextOverflowAction, ContextOverflowConfig, CredentialEntry, CredentialExpiryConfig,
+    CredentialPoolConfig, CredentialSelectionStrategy, CredentialStorageBackend,
+    CredentialStorageConfig, CredentialTiersConfig, CustomProviderConfig, DedupeConfig,
+    EndpointProvidersConfig, ExperimentalFeatures, GeminiApiKeyEntry, GuardrailAction,
+    GuardrailPatternConfig, GuardrailPolicyConfig, GuardrailsConfig, HeaderPassthroughConfig,
+    HealthCheckConfig, HeartbeatConfig, IFlowCredentialEntry, InjectionRuleConfig,
+    InjectionSettings, ListenerConfig, ListenerRouteSet, LoggingConfig, MaintenanceConfig,
+    MaintenanceWindowConfig, MockProviderConfig, ModelAliasRule, ModelAliasRuleKind,
+    ModelBlacklistConfig, ModelInfo, ModelsConfig, NativeAgentConfig, PiiDetector,
+    PiiMaskingConfig, PriorityLanesConfig, ProviderConfig, ProviderHeaderPolicy,
+    ProviderModelsConfig, ProviderTlsConfig, ProvidersConfig, QuotaExceededConfig, RecordingConfig,
+    RecordingMode, RemoteManagementConfig, ReportsConfig, RequestIdConfig,
+    ResponseInfoHeadersConfig, ResponseProcessingConfig, ResponseRuleConfig, ResponseSigningConfig,
+    ResponseValidationConfig, RetrySettings, RoutingConfig, ScreenshotChatConfig, ServerConfig,
+    SessionPersistenceConfig, SignatureStoreConfig, SseResumeConfig, StreamWatchdogConfig,
+    TelemetryRetentionConfig, TlsConfig, TokenRefreshConfig, UpstreamReachabilityConfig,
+    UpstreamTlsConfig, VertexApiKeyEntry, VertexModelAlias, DEFAULT_API_KEY,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
 
@@ -3,17 +3,13 @@
 //! 整合配置主题、热重载和观察者管理
 
 use super::events::ConfigChangeSource;
-use super::observers::{
-    DefaultProviderRefObserver, EndpointObserver, InjectorObserver, LoggingObserver,
-    RouterObserver, TauriObserver,
-};
+use super::observers::{DefaultProviderRefObserver, EndpointObserver, TauriObserver};
 use super::subject::ConfigSubject;
 use super::traits::ConfigObserver;
+use crate::app::FrontendHandle as AppHandle;
 use crate::config::{Config, EndpointProvidersConfig, HotReloadManager, ReloadResult};
-use crate::processor::RequestProcessor;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::AppHandle;
 use tokio::sync::RwLock;
 
 /// 全局配置管理器
@@ -69,26 +65,6 @@ impl GlobalConfigManager {
         self.subject.unregister(name);
     }
 
-    /// 注册 RequestProcessor 相关的观察者
-    pub fn register_processor_observers(&self, processor: &RequestProcessor) {
-        // 路由器观察者
-        let router_observer = Arc::new(RouterObserver::new(
-            processor.router.clone(),
-            processor.mapper.clone(),
-        ));
-        self.subject.register(router_observer);
-
-        // 注入器观察者
-        let injector_observer = Arc::new(InjectorObserver::new(processor.injector.clone()));
-        self.subject.register(injector_observer);
-
-        // 日志观察者
-        let logging_observer = Arc::new(LoggingObserver);
-        self.subject.register(logging_observer);
-
-        tracing::info!("[GlobalConfigManager] 已注册 RequestProcessor 观察者");
-    }
-
     /// 注册端点 Provider 观察者
     pub fn register_endpoint_observer(
         &self,
@@ -208,6 +184,7 @@ impl std::ops::Deref for GlobalConfigManagerState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::observer::LoggingObserver;
 
     #[tokio::test]
     async fn test_global_config_manager_creation() {
@@ -1,6 +1,8 @@
 //! 配置观察者模块
 //!
-//! 提供基于观察者模式的全局配置管理系统
+//! 提供基于观察者模式的全局配置管理系统（仅桌面应用使用）
+
+#![cfg_attr(not(feature = "desktop"), allow(dead_code))]
 
 mod events;
 mod manager;
@@ -14,9 +16,6 @@ pub use events::{
     NativeAgentChangeEvent, RetryChangeEvent, RoutingChangeEvent, ServerChangeEvent,
 };
 pub use manager::{GlobalConfigManager, GlobalConfigManagerState};
-pub use observers::{
-    DefaultProviderRefObserver, EndpointObserver, InjectorObserver, LoggingObserver,
-    RouterObserver, TauriObserver,
-};
+pub use observers::{DefaultProviderRefObserver, EndpointObserver, LoggingObserver, TauriObserver};
 pub use subject::{ConfigSubject, CONFIG_CHANGED_EVENT, CONFIG_RELOAD_EVENT};
 pub use traits::{ConfigObserver, FnObserver, SyncConfigObserver, SyncObserverWrapper};
@@ -4,132 +4,14 @@
 
 use super::events::ConfigChangeEvent;
 use super::traits::ConfigObserver;
+use crate::app::FrontendHandle as AppHandle;
 use crate::config::{Config, EndpointProvidersConfig};
-use crate::injection::Injector;
-use crate::router::{ModelMapper, Router};
 use async_trait::async_trait;
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+#[cfg(feature = "desktop")]
+use tauri::Emitter;
 use tokio::sync::RwLock;
 
-/// 路由器观察者
-///
-/// 监听路由配置变更，更新 Router 和 ModelMapper
-pub struct RouterObserver {
-    router: Arc<RwLock<Router>>,
-    mapper: Arc<RwLock<ModelMapper>>,
-}
-
-impl RouterObserver {
-    pub fn new(router: Arc<RwLock<Router>>, mapper: Arc<RwLock<ModelMapper>>) -> Self {
-        Self { router, mapper }
-    }
-}
-
-#[async_trait]
-impl ConfigObserver for RouterObserver {
-    fn name(&self) -> &str {
-        "RouterObserver"
-    }
-
-    fn priority(&self) -> i32 {
-        10 // 高优先级，路由器需要先更新
-    }
-
-    fn is_interested_in(&self, event: &ConfigChangeEvent) -> bool {
-        matches!(
-            event,
-            ConfigChangeEvent::FullReload(_) | ConfigChangeEvent::RoutingChanged(_)
-        )
-    }
-
-    async fn on_config_changed(
-        &self,
-        _event: &ConfigChangeEvent,
-        config: &Config,
-    ) -> Result<(), String> {
-        // 更新默认 Provider
-        if let Ok(provider_type) = config
-            .routing
-            .default_provider
-            .parse::<crate::ProviderType>()
-        {
-            let mut router = self.router.write().await;
-            router.set_default_provider(provider_type);
-            tracing::info!(
-                "[RouterObserver] 更新默认 Provider: {}",
-                config.routing.default_provider
-            );
-        }
-
-        // 更新模型别名
-        {
-            let mut mapper = self.mapper.write().await;
-            mapper.clear();
-            for (alias, model) in &config.routing.model_aliases {
-                mapper.add_alias(alias, model);
-            }
-            tracing::debug!(
-                "[RouterObserver] 更新模型别名: {} 个",
-                config.routing.model_aliases.len()
-            );
-        }
-
-        Ok(())
-    }
-}
-
-/// 注入器观察者
-///
-/// 监听注入配置变更，更新 Injector
-pub struct InjectorObserver {
-    injector: Arc<RwLock<Injector>>,
-}
-
-impl InjectorObserver {
-    pub fn new(injector: Arc<RwLock<Injector>>) -> Self {
-        Self { injector }
-    }
-}
-
-#[async_trait]
-impl ConfigObserver for InjectorObserver {
-    fn name(&self) -> &str {
-        "InjectorObserver"
-    }
-
-    fn priority(&self) -> i32 {
-        20
-    }
-
-    fn is_interested_in(&self, event: &ConfigChangeEvent) -> bool {
-        matches!(
-            event,
-            ConfigChangeEvent::FullReload(_) | ConfigChangeEvent::InjectionChanged(_)
-        )
-    }
-
-    async fn on_config_changed(
-        &self,
-        _event: &ConfigChangeEvent,
-        config: &Config,
-    ) -> Result<(), String> {
-        let mut injector = self.injector.write().await;
-        injector.clear();
-
-        for rule in &config.injection.rules {
-            injector.add_rule(rule.clone().into());
-        }
-
-        tracing::info!(
-            "[InjectorObserver] 更新注入规则: {} 条",
-            config.injection.rules.len()
-        );
-
-        Ok(())
-    }
-}
-
 /// 端点 Provider 观察者
 ///
 /// 监听端点 Provider 配置变更
@@ -206,6 +88,7 @@ impl ConfigObserver for LoggingObserver {
             config.logging.enabled,
             config.logging.level
         );
+        crate::logger::set_pii_masking(&config.logging.pii_masking);
 
         Ok(())
     }
@@ -303,32 +186,3 @@ impl ConfigObserver for DefaultProviderRefObserver {
         Ok(())
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::observer::events::{ConfigChangeSource, FullReloadEvent};
-
-    #[tokio::test]
-    async fn test_router_observer_priority() {
-        let router = Arc::new(RwLock::new(Router::default()));
-        let mapper = Arc::new(RwLock::new(ModelMapper::new()));
-        let observer = RouterObserver::new(router, mapper);
-
-        assert_eq!(observer.name(), "RouterObserver");
-        assert_eq!(observer.priority(), 10);
-    }
-
-    #[tokio::test]
-    async fn test_injector_observer_interest() {
-        let injector = Arc::new(RwLock::new(Injector::new()));
-        let observer = InjectorObserver::new(injector);
-
-        let full_reload = ConfigChangeEvent::FullReload(FullReloadEvent {
-            timestamp_ms: 0,
-            source: ConfigChangeSource::ApiCall,
-        });
-
-        assert!(observer.is_interested_in(&full_reload));
-    }
-}
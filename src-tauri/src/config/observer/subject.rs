@@ -4,12 +4,14 @@
 
 use super::events::{ConfigChangeEvent, ConfigChangeSource, FullReloadEvent};
 use super::traits::ConfigObserver;
+use crate::app::FrontendHandle as AppHandle;
 use crate::config::Config;
 use parking_lot::RwLock;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+#[cfg(feature = "desktop")]
+use tauri::Emitter;
 use tokio::sync::broadcast;
 
 /// Tauri 事件名称常量
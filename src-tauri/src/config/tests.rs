@@ -38,6 +38,12 @@ fn arb_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        body_limits: crate::config::BodyLimitsConfig::default(),
+        dedupe: crate::config::DedupeConfig::default(),
+        additional_hosts: Vec::new(),
+        listeners: Vec::new(),
+        base_path: String::new(),
+        public_url: None,
     })
 }
 
@@ -116,6 +122,12 @@ fn arb_routing_config() -> impl Strategy<Value = RoutingConfig> {
         .prop_map(|(default_provider, model_aliases)| RoutingConfig {
             default_provider,
             model_aliases,
+            model_alias_rules: Vec::new(),
+            route_api_keys: std::collections::HashMap::new(),
+            namespace_default_providers: std::collections::HashMap::new(),
+            api_key_default_providers: Vec::new(),
+            model_max_tokens: std::collections::HashMap::new(),
+            credential_strategy: crate::config::CredentialSelectionStrategy::default(),
         })
 }
 
@@ -156,6 +168,8 @@ fn arb_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                telemetry: crate::config::TelemetryRetentionConfig::default(),
+                pii_masking: crate::config::PiiMaskingConfig::default(),
             },
         )
 }
@@ -189,6 +203,36 @@ fn arb_config() -> impl Strategy<Value = Config> {
             agent: crate::config::NativeAgentConfig::default(),
             language: "zh".to_string(),
             experimental: crate::config::ExperimentalFeatures::default(),
+            alerts: crate::config::AlertsConfig::default(),
+            reports: crate::config::ReportsConfig::default(),
+            token_refresh: crate::config::TokenRefreshConfig::default(),
+            credential_expiry: crate::config::CredentialExpiryConfig::default(),
+            credential_tiers: crate::config::CredentialTiersConfig::default(),
+            model_blacklist: crate::config::ModelBlacklistConfig::default(),
+            health_check: crate::config::HealthCheckConfig::default(),
+            priority_lanes: crate::config::PriorityLanesConfig::default(),
+            response_info_headers: crate::config::ResponseInfoHeadersConfig::default(),
+            response_signing: crate::config::ResponseSigningConfig::default(),
+            stream_watchdog: crate::config::StreamWatchdogConfig::default(),
+            response_validation: crate::config::ResponseValidationConfig::default(),
+            upstream_reachability: crate::config::UpstreamReachabilityConfig::default(),
+            upstream_tls: crate::config::UpstreamTlsConfig::default(),
+            session_persistence: crate::config::SessionPersistenceConfig::default(),
+            signature_store: crate::config::SignatureStoreConfig::default(),
+            request_id: crate::config::RequestIdConfig::default(),
+            heartbeat: crate::config::HeartbeatConfig::default(),
+            guardrails: crate::config::GuardrailsConfig::default(),
+            response_processing: crate::config::ResponseProcessingConfig::default(),
+            header_passthrough: crate::config::HeaderPassthroughConfig::default(),
+            recording: crate::config::RecordingConfig::default(),
+            mock_provider: crate::config::MockProviderConfig::default(),
+            chaos: crate::config::ChaosConfig::default(),
+            compaction: crate::config::CompactionConfig::default(),
+            context_overflow: crate::config::ContextOverflowConfig::default(),
+            credential_storage: crate::config::CredentialStorageConfig::default(),
+            cluster: crate::config::ClusterConfig::default(),
+            maintenance: crate::config::MaintenanceConfig::default(),
+            sse_resume: crate::config::SseResumeConfig::default(),
         })
 }
 
@@ -354,6 +398,12 @@ fn arb_valid_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        body_limits: crate::config::BodyLimitsConfig::default(),
+        dedupe: crate::config::DedupeConfig::default(),
+        additional_hosts: Vec::new(),
+        listeners: Vec::new(),
+        base_path: String::new(),
+        public_url: None,
     })
 }
 
@@ -394,6 +444,8 @@ fn arb_valid_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                telemetry: crate::config::TelemetryRetentionConfig::default(),
+                pii_masking: crate::config::PiiMaskingConfig::default(),
             },
         )
 }
@@ -427,6 +479,36 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             agent: crate::config::NativeAgentConfig::default(),
             language: "zh".to_string(),
             experimental: crate::config::ExperimentalFeatures::default(),
+            alerts: crate::config::AlertsConfig::default(),
+            reports: crate::config::ReportsConfig::default(),
+            token_refresh: crate::config::TokenRefreshConfig::default(),
+            credential_expiry: crate::config::CredentialExpiryConfig::default(),
+            credential_tiers: crate::config::CredentialTiersConfig::default(),
+            model_blacklist: crate::config::ModelBlacklistConfig::default(),
+            health_check: crate::config::HealthCheckConfig::default(),
+            priority_lanes: crate::config::PriorityLanesConfig::default(),
+            response_info_headers: crate::config::ResponseInfoHeadersConfig::default(),
+            response_signing: crate::config::ResponseSigningConfig::default(),
+            stream_watchdog: crate::config::StreamWatchdogConfig::default(),
+            response_validation: crate::config::ResponseValidationConfig::default(),
+            upstream_reachability: crate::config::UpstreamReachabilityConfig::default(),
+            upstream_tls: crate::config::UpstreamTlsConfig::default(),
+            session_persistence: crate::config::SessionPersistenceConfig::default(),
+            signature_store: crate::config::SignatureStoreConfig::default(),
+            request_id: crate::config::RequestIdConfig::default(),
+            heartbeat: crate::config::HeartbeatConfig::default(),
+            guardrails: crate::config::GuardrailsConfig::default(),
+            response_processing: crate::config::ResponseProcessingConfig::default(),
+            header_passthrough: crate::config::HeaderPassthroughConfig::default(),
+            recording: crate::config::RecordingConfig::default(),
+            mock_provider: crate::config::MockProviderConfig::default(),
+            chaos: crate::config::ChaosConfig::default(),
+            compaction: crate::config::CompactionConfig::default(),
+            context_overflow: crate::config::ContextOverflowConfig::default(),
+            credential_storage: crate::config::CredentialStorageConfig::default(),
+            cluster: crate::config::ClusterConfig::default(),
+            maintenance: crate::config::MaintenanceConfig::default(),
+            sse_resume: crate::config::SseResumeConfig::default(),
         })
 }
 
@@ -476,6 +558,36 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     agent: crate::config::NativeAgentConfig::default(),
                     language: "zh".to_string(),
                     experimental: crate::config::ExperimentalFeatures::default(),
+                    alerts: crate::config::AlertsConfig::default(),
+                    reports: crate::config::ReportsConfig::default(),
+                    token_refresh: crate::config::TokenRefreshConfig::default(),
+                    credential_expiry: crate::config::CredentialExpiryConfig::default(),
+                    credential_tiers: crate::config::CredentialTiersConfig::default(),
+                    model_blacklist: crate::config::ModelBlacklistConfig::default(),
+                    health_check: crate::config::HealthCheckConfig::default(),
+                    priority_lanes: crate::config::PriorityLanesConfig::default(),
+                    response_info_headers: crate::config::ResponseInfoHeadersConfig::default(),
+                    response_signing: crate::config::ResponseSigningConfig::default(),
+                    stream_watchdog: crate::config::StreamWatchdogConfig::default(),
+                    response_validation: crate::config::ResponseValidationConfig::default(),
+                    upstream_reachability: crate::config::UpstreamReachabilityConfig::default(),
+                    upstream_tls: crate::config::UpstreamTlsConfig::default(),
+                    session_persistence: crate::config::SessionPersistenceConfig::default(),
+                    signature_store: crate::config::SignatureStoreConfig::default(),
+                    request_id: crate::config::RequestIdConfig::default(),
+                    heartbeat: crate::config::HeartbeatConfig::default(),
+                    guardrails: crate::config::GuardrailsConfig::default(),
+                    response_processing: crate::config::ResponseProcessingConfig::default(),
+                    header_passthrough: crate::config::HeaderPassthroughConfig::default(),
+                    recording: crate::config::RecordingConfig::default(),
+                    mock_provider: crate::config::MockProviderConfig::default(),
+                    chaos: crate::config::ChaosConfig::default(),
+                    compaction: crate::config::CompactionConfig::default(),
+                    context_overflow: crate::config::ContextOverflowConfig::default(),
+                    credential_storage: crate::config::CredentialStorageConfig::default(),
+                    cluster: crate::config::ClusterConfig::default(),
+                    maintenance: crate::config::MaintenanceConfig::default(),
+                    sse_resume: crate::config::SseResumeConfig::default(),
                 };
                 // 根据类型使配置无效
                 match invalid_type {
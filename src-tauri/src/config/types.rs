@@ -3,9 +3,9 @@
 //! 定义 ProxyCast 的配置结构，支持 YAML 和 JSON 序列化/反序列化
 //! 保持与旧版 JSON 配置的向后兼容性
 
-use crate::injection::{InjectionMode, InjectionRule};
+use crate::injection::{InjectionConditions, InjectionMode, InjectionRule};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // ============ 凭证池配置类型 ============
 
@@ -323,6 +323,96 @@ pub struct Config {
     /// 实验室功能配置
     #[serde(default)]
     pub experimental: ExperimentalFeatures,
+    /// 告警配置
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    /// 每日用量报告配置
+    #[serde(default)]
+    pub reports: ReportsConfig,
+    /// OAuth Token 主动刷新配置
+    #[serde(default)]
+    pub token_refresh: TokenRefreshConfig,
+    /// 凭证过期预警配置
+    #[serde(default)]
+    pub credential_expiry: CredentialExpiryConfig,
+    /// 凭证优先级分层配置
+    #[serde(default)]
+    pub credential_tiers: CredentialTiersConfig,
+    /// 模型黑名单自动学习配置
+    #[serde(default)]
+    pub model_blacklist: ModelBlacklistConfig,
+    /// 凭证健康检查配置
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+    /// 请求优先级通道配置
+    #[serde(default)]
+    pub priority_lanes: PriorityLanesConfig,
+    /// 响应信息头配置
+    #[serde(default)]
+    pub response_info_headers: ResponseInfoHeadersConfig,
+    /// 响应签名配置
+    #[serde(default)]
+    pub response_signing: ResponseSigningConfig,
+    /// 上游流式响应看门狗配置
+    #[serde(default)]
+    pub stream_watchdog: StreamWatchdogConfig,
+    /// 上游响应结构校验配置
+    #[serde(default)]
+    pub response_validation: ResponseValidationConfig,
+    /// 上游连通性负缓存配置
+    #[serde(default)]
+    pub upstream_reachability: UpstreamReachabilityConfig,
+    /// 上游 TLS 配置（自定义 CA、证书公钥固定）
+    #[serde(default)]
+    pub upstream_tls: UpstreamTlsConfig,
+    /// 会话对话记录持久化配置
+    #[serde(default)]
+    pub session_persistence: SessionPersistenceConfig,
+    /// thoughtSignature 缓存配置
+    #[serde(default)]
+    pub signature_store: SignatureStoreConfig,
+    /// 请求 ID 配置
+    #[serde(default)]
+    pub request_id: RequestIdConfig,
+    /// 流式响应与 WebSocket 心跳配置
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    /// 请求内容防护配置
+    #[serde(default)]
+    pub guardrails: GuardrailsConfig,
+    /// 响应后处理配置
+    #[serde(default)]
+    pub response_processing: ResponseProcessingConfig,
+    /// 客户端请求头透传配置
+    #[serde(default)]
+    pub header_passthrough: HeaderPassthroughConfig,
+    /// 流量录制与回放配置
+    #[serde(default)]
+    pub recording: RecordingConfig,
+    /// 内置 mock Provider 配置
+    #[serde(default)]
+    pub mock_provider: MockProviderConfig,
+    /// 故障注入配置
+    #[serde(default)]
+    pub chaos: ChaosConfig,
+    /// 服务端对话压缩配置
+    #[serde(default)]
+    pub compaction: CompactionConfig,
+    /// 上下文窗口预检配置
+    #[serde(default)]
+    pub context_overflow: ContextOverflowConfig,
+    /// 凭证池存储后端配置
+    #[serde(default)]
+    pub credential_storage: CredentialStorageConfig,
+    /// 集群模式配置
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    /// Provider / 凭证维护窗口配置
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    /// SSE 断点续传配置
+    #[serde(default)]
+    pub sse_resume: SseResumeConfig,
 }
 
 // ============ Native Agent 配置类型 ============
@@ -528,6 +618,169 @@ pub struct ServerConfig {
     /// TLS 配置
     #[serde(default)]
     pub tls: TlsConfig,
+    /// 请求体大小限制（按路由组）
+    #[serde(default)]
+    pub body_limits: BodyLimitsConfig,
+    /// 重复请求合并（客户端重试风暴时只向上游发送一次）
+    #[serde(default)]
+    pub dedupe: DedupeConfig,
+    /// 主端口额外监听的地址（如 `host: 127.0.0.1` 时加上 `::1` 实现双栈监听）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_hosts: Vec<String>,
+    /// 额外的监听端口（共享凭证池和遥测，认证和开放的接口独立配置）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listeners: Vec<ListenerConfig>,
+    /// 路由路径前缀（部署在反向代理的子路径下时使用，如 `/proxycast`）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub base_path: String,
+    /// 对外访问地址（如 `https://example.com/proxycast`），用于 `/v1/routes` 返回的端点地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_url: Option<String>,
+}
+
+impl ServerConfig {
+    /// 规范化的路由路径前缀（以 `/` 开头、不以 `/` 结尾，未配置时为空字符串）
+    pub fn normalized_base_path(&self) -> String {
+        let path = self.base_path.trim().trim_matches('/');
+        if path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", path)
+        }
+    }
+
+    /// 对外访问地址（去掉末尾的 `/`，未配置时为 None）
+    pub fn normalized_public_url(&self) -> Option<String> {
+        self.public_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+    }
+}
+
+/// 额外监听端口配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ListenerConfig {
+    /// 名称（用于日志）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 监听地址（为空时使用 `server.host`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// 监听端口
+    pub port: u16,
+    /// API 密钥（为空时使用 `server.api_key`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// 开放的接口（为空时开放全部接口）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<ListenerRouteSet>,
+}
+
+/// 监听端口可开放的接口分组
+///
+/// `/health`、`/v1/models` 等只读接口始终开放。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerRouteSet {
+    /// OpenAI 格式（`/v1/chat/completions`、图像生成、音频、文件，包括多供应商和 Amp provider 路由）
+    OpenAI,
+    /// Anthropic 格式（`/v1/messages`、`/v1/messages/count_tokens`，包括多供应商和 Amp provider 路由）
+    Anthropic,
+    /// Gemini 原生协议（`/v1/gemini/*`）
+    Gemini,
+    /// WebSocket（`/v1/ws`、`/ws`）
+    WebSocket,
+    /// Amp CLI 管理代理（`/api/auth/*`、`/api/user/*`）
+    Amp,
+    /// 管理 API、Kiro 凭证 API 和凭证 API
+    Management,
+}
+
+impl ListenerConfig {
+    /// 实际监听地址
+    pub fn host_or<'a>(&'a self, server_host: &'a str) -> &'a str {
+        self.host.as_deref().unwrap_or(server_host)
+    }
+}
+
+/// 请求体大小限制（单位 MB）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BodyLimitsConfig {
+    /// 模型 API（`/v1/*`、多供应商路由、Amp provider 路由）
+    #[serde(default = "default_api_body_limit_mb")]
+    pub api_mb: u64,
+    /// 管理 API 及凭证 API
+    #[serde(default = "default_management_body_limit_mb")]
+    pub management_mb: u64,
+    /// Amp CLI 管理代理（`/api/auth/*`、`/api/user/*`）
+    #[serde(default = "default_amp_proxy_body_limit_mb")]
+    pub amp_proxy_mb: u64,
+    /// 音频 API（`/v1/audio/*`，与 OpenAI 上传限制一致）
+    #[serde(default = "default_audio_body_limit_mb")]
+    pub audio_mb: u64,
+}
+
+fn default_api_body_limit_mb() -> u64 {
+    100
+}
+
+fn default_management_body_limit_mb() -> u64 {
+    1
+}
+
+fn default_amp_proxy_body_limit_mb() -> u64 {
+    10
+}
+
+fn default_audio_body_limit_mb() -> u64 {
+    25
+}
+
+impl Default for BodyLimitsConfig {
+    fn default() -> Self {
+        Self {
+            api_mb: default_api_body_limit_mb(),
+            management_mb: default_management_body_limit_mb(),
+            amp_proxy_mb: default_amp_proxy_body_limit_mb(),
+            audio_mb: default_audio_body_limit_mb(),
+        }
+    }
+}
+
+impl BodyLimitsConfig {
+    /// MB 转换为字节
+    pub fn bytes(mb: u64) -> usize {
+        (mb.max(1) as usize).saturating_mul(1024 * 1024)
+    }
+}
+
+/// 重复请求合并配置
+///
+/// 同一 API Key 在窗口期内发送的相同请求（方法、路径和请求体均相同）只转发一次上游，
+/// 响应分发给所有等待的客户端，避免客户端超时重试时重复消耗额度。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DedupeConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 合并窗口（毫秒）：请求完成后该时间内到达的相同请求直接复用响应，为 0 时只合并进行中的请求
+    #[serde(default = "default_dedupe_window_ms")]
+    pub window_ms: u64,
+}
+
+fn default_dedupe_window_ms() -> u64 {
+    2000
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_ms: default_dedupe_window_ms(),
+        }
+    }
 }
 
 /// TLS 配置
@@ -603,16 +856,19 @@ impl Default for QuotaExceededConfig {
 /// Amp CLI 模型映射
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AmpModelMapping {
-    /// 源模型名称
+    /// 源模型名称，支持 `*` 通配符（如 `claude-opus-*`，匹配规则同凭证的模型排除列表）
     pub from: String,
     /// 目标模型名称
     pub to: String,
+    /// 仅对指定 provider 的路由生效（如 `anthropic`），为空时对所有 provider 生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
 }
 
 /// Amp CLI 配置
 ///
 /// 用于 Amp CLI 集成
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AmpConfig {
     /// 上游 URL
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -623,245 +879,1769 @@ pub struct AmpConfig {
     /// 是否限制管理端点只能从 localhost 访问
     #[serde(default)]
     pub restrict_management_to_localhost: bool,
+    /// 管理代理等待上游响应头的超时（秒），不限制流式响应体的传输时长
+    #[serde(default = "default_amp_upstream_timeout_secs")]
+    pub upstream_timeout_secs: u64,
+    /// 管理代理的重试次数：GET / HEAD / OPTIONS 在连接失败、超时或 502/503/504 时重试，
+    /// 其他方法只在连接失败（请求未发出）时重试
+    #[serde(default = "default_amp_upstream_max_retries")]
+    pub upstream_max_retries: u32,
+}
+
+fn default_amp_upstream_timeout_secs() -> u64 {
+    30
+}
+
+fn default_amp_upstream_max_retries() -> u32 {
+    1
+}
+
+impl Default for AmpConfig {
+    fn default() -> Self {
+        Self {
+            upstream_url: None,
+            model_mappings: Vec::new(),
+            restrict_management_to_localhost: false,
+            upstream_timeout_secs: default_amp_upstream_timeout_secs(),
+            upstream_max_retries: default_amp_upstream_max_retries(),
+        }
+    }
 }
 
 fn default_host() -> String {
     "127.0.0.1".to_string()
 }
 
-fn default_port() -> u16 {
-    8999
+fn default_port() -> u16 {
+    8999
+}
+
+pub const DEFAULT_API_KEY: &str = "proxy_cast";
+
+fn default_api_key() -> String {
+    DEFAULT_API_KEY.to_string()
+}
+
+/// 生成安全 API Key（32 字节随机）
+pub fn generate_secure_api_key() -> String {
+    use rand::distributions::Alphanumeric;
+    use rand::Rng;
+
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    format!("pc_{token}")
+}
+
+/// 是否为默认 API Key
+pub fn is_default_api_key(api_key: &str) -> bool {
+    api_key == DEFAULT_API_KEY
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            port: default_port(),
+            api_key: default_api_key(),
+            tls: TlsConfig::default(),
+            body_limits: BodyLimitsConfig::default(),
+            dedupe: DedupeConfig::default(),
+            additional_hosts: Vec::new(),
+            listeners: Vec::new(),
+            base_path: String::new(),
+            public_url: None,
+        }
+    }
+}
+
+/// Provider 配置集合
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProvidersConfig {
+    /// Kiro Provider 配置
+    #[serde(default)]
+    pub kiro: ProviderConfig,
+    /// Gemini Provider 配置
+    #[serde(default)]
+    pub gemini: ProviderConfig,
+    /// Qwen Provider 配置
+    #[serde(default)]
+    pub qwen: ProviderConfig,
+    /// OpenAI 自定义 Provider 配置
+    #[serde(default)]
+    pub openai: CustomProviderConfig,
+    /// Claude 自定义 Provider 配置
+    #[serde(default)]
+    pub claude: CustomProviderConfig,
+}
+
+impl Default for ProvidersConfig {
+    fn default() -> Self {
+        Self {
+            kiro: ProviderConfig {
+                enabled: true,
+                credentials_path: Some("~/.aws/sso/cache/kiro-auth-token.json".to_string()),
+                region: Some("us-east-1".to_string()),
+                project_id: None,
+            },
+            gemini: ProviderConfig {
+                enabled: false,
+                credentials_path: Some("~/.gemini/oauth_creds.json".to_string()),
+                region: None,
+                project_id: None,
+            },
+            qwen: ProviderConfig {
+                enabled: false,
+                credentials_path: Some("~/.qwen/oauth_creds.json".to_string()),
+                region: None,
+                project_id: None,
+            },
+            openai: CustomProviderConfig {
+                enabled: false,
+                api_key: None,
+                base_url: Some("https://api.openai.com/v1".to_string()),
+            },
+            claude: CustomProviderConfig {
+                enabled: false,
+                api_key: None,
+                base_url: Some("https://api.anthropic.com".to_string()),
+            },
+        }
+    }
+}
+
+/// OAuth Provider 配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProviderConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 凭证文件路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials_path: Option<String>,
+    /// 区域
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// 项目 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+}
+
+/// 自定义 Provider 配置（API Key 方式）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CustomProviderConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// API 密钥
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// 基础 URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+}
+
+/// 路由配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoutingConfig {
+    /// 默认 Provider
+    #[serde(default = "default_provider")]
+    pub default_provider: String,
+    /// 模型别名映射
+    #[serde(default)]
+    pub model_aliases: HashMap<String, String>,
+    /// 模型别名规则（通配符 / 正则），在精确别名未命中时按优先级匹配
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub model_alias_rules: Vec<ModelAliasRule>,
+    /// 命名空间路由的专属入站 API Key（路由选择器 -> API Key）
+    ///
+    /// 选择器为凭证名称或 UUID。专属 Key 只能访问 `/{selector}/v1/...` 路由，
+    /// 全局 `server.api_key` 仍可访问所有路由。
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub route_api_keys: HashMap<String, String>,
+    /// 命名空间默认 Provider（路由选择器 -> Provider）
+    ///
+    /// 选择器不是凭证名称、UUID 或 Provider 类型时，从该 Provider 的凭证池选择凭证。
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub namespace_default_providers: HashMap<String, String>,
+    /// 按入站 API Key 指定的默认 Provider
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_key_default_providers: Vec<ApiKeyDefaultProvider>,
+    /// 模型输出 Token 上限（模型名前缀 -> 最大输出 Token 数），覆盖内置上限
+    ///
+    /// 请求的 `max_tokens` 超过上限时自动钳制，并通过 `x-proxycast-max-tokens-adjusted` 响应头告知客户端。
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_max_tokens: HashMap<String, u32>,
+    /// 凭证池中有多个可用凭证时的选择策略
+    #[serde(default)]
+    pub credential_strategy: CredentialSelectionStrategy,
+}
+
+/// 凭证选择策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSelectionStrategy {
+    /// 按健康状态、使用次数、错误率和最近使用时间综合评分（默认）
+    #[default]
+    Weighted,
+    /// 优先选择滑动窗口内 P95 延迟最低的凭证（没有延迟样本的凭证优先试用）
+    LeastLatency,
+}
+
+/// 模型别名规则的匹配方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelAliasRuleKind {
+    /// 通配符（`*` 匹配任意字符，`?` 匹配单个字符）
+    #[default]
+    Glob,
+    /// 正则表达式（需完整匹配模型名）
+    Regex,
+}
+
+/// 模型别名规则
+///
+/// 例如 `gpt-4*` -> `claude-sonnet-4-5`，或 `*-preview` -> `*`（去掉 `-preview` 后缀）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelAliasRule {
+    /// 匹配模式
+    pub pattern: String,
+    /// 目标模型：glob 规则中的 `*` 依次替换为匹配到的内容，
+    /// regex 规则支持 `$1` / `${name}` 引用捕获组
+    pub target: String,
+    /// 匹配方式
+    #[serde(default)]
+    pub kind: ModelAliasRuleKind,
+    /// 优先级，数值越大越先匹配；相同优先级按配置顺序
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// 按入站 API Key 指定的默认 Provider
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyDefaultProvider {
+    /// 入站 API Key（全局 Key、监听端口 Key 或路由专属 Key）
+    pub api_key: String,
+    /// 默认 Provider（Provider 类型或自定义 Provider ID）
+    pub provider: String,
+}
+
+fn default_provider() -> String {
+    "kiro".to_string()
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            default_provider: default_provider(),
+            model_aliases: HashMap::new(),
+            model_alias_rules: Vec::new(),
+            route_api_keys: HashMap::new(),
+            namespace_default_providers: HashMap::new(),
+            api_key_default_providers: Vec::new(),
+            model_max_tokens: HashMap::new(),
+            credential_strategy: CredentialSelectionStrategy::default(),
+        }
+    }
+}
+
+/// 重试配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetrySettings {
+    /// 最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// 基础延迟（毫秒）
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    /// 最大延迟（毫秒）
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// 是否自动切换 Provider
+    #[serde(default = "default_auto_switch")]
+    pub auto_switch_provider: bool,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_base_delay_ms() -> u64 {
+    1000
+}
+
+fn default_max_delay_ms() -> u64 {
+    30000
+}
+
+fn default_auto_switch() -> bool {
+    true
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            base_delay_ms: default_base_delay_ms(),
+            max_delay_ms: default_max_delay_ms(),
+            auto_switch_provider: default_auto_switch(),
+        }
+    }
+}
+
+/// 日志配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoggingConfig {
+    /// 是否启用日志
+    #[serde(default = "default_logging_enabled")]
+    pub enabled: bool,
+    /// 日志级别
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// 日志保留天数
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+    /// 是否包含请求体
+    #[serde(default)]
+    pub include_request_body: bool,
+    /// 遥测历史（SQLite）保留配置
+    #[serde(default)]
+    pub telemetry: TelemetryRetentionConfig,
+    /// 日志和遥测中的个人信息脱敏
+    #[serde(default)]
+    pub pii_masking: PiiMaskingConfig,
+}
+
+/// 个人信息脱敏配置
+///
+/// 作用于应用日志、原始响应转储和遥测错误信息。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PiiMaskingConfig {
+    /// 是否启用
+    #[serde(default = "default_pii_masking_enabled")]
+    pub enabled: bool,
+    /// 启用的检测器
+    #[serde(default = "default_pii_detectors")]
+    pub detectors: Vec<PiiDetector>,
+}
+
+/// 个人信息检测器
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiDetector {
+    /// 电子邮件地址
+    Email,
+    /// 类似 API Key 的字符串（sk-、AKIA、ghp_、AIza、xox 等前缀）
+    ApiKey,
+    /// 电话号码
+    Phone,
+}
+
+fn default_pii_masking_enabled() -> bool {
+    true
+}
+
+fn default_pii_detectors() -> Vec<PiiDetector> {
+    vec![PiiDetector::Email, PiiDetector::ApiKey, PiiDetector::Phone]
+}
+
+impl Default for PiiMaskingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_pii_masking_enabled(),
+            detectors: default_pii_detectors(),
+        }
+    }
+}
+
+/// 遥测历史保留配置
+///
+/// 原始请求日志保留时间较短，按小时 / 按天的汇总数据保留更久。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TelemetryRetentionConfig {
+    /// 是否持久化遥测数据到 SQLite
+    #[serde(default = "default_telemetry_persist")]
+    pub persist: bool,
+    /// 原始请求日志保留天数
+    #[serde(default = "default_telemetry_raw_days")]
+    pub raw_days: u32,
+    /// 按小时汇总保留天数
+    #[serde(default = "default_telemetry_hourly_days")]
+    pub hourly_days: u32,
+    /// 按天汇总保留天数
+    #[serde(default = "default_telemetry_daily_days")]
+    pub daily_days: u32,
+}
+
+fn default_telemetry_persist() -> bool {
+    true
+}
+
+fn default_telemetry_raw_days() -> u32 {
+    7
+}
+
+fn default_telemetry_hourly_days() -> u32 {
+    30
+}
+
+fn default_telemetry_daily_days() -> u32 {
+    365
+}
+
+impl Default for TelemetryRetentionConfig {
+    fn default() -> Self {
+        Self {
+            persist: default_telemetry_persist(),
+            raw_days: default_telemetry_raw_days(),
+            hourly_days: default_telemetry_hourly_days(),
+            daily_days: default_telemetry_daily_days(),
+        }
+    }
+}
+
+fn default_logging_enabled() -> bool {
+    true
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_retention_days() -> u32 {
+    7
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_logging_enabled(),
+            level: default_log_level(),
+            retention_days: default_retention_days(),
+            include_request_body: false,
+            telemetry: TelemetryRetentionConfig::default(),
+            pii_masking: PiiMaskingConfig::default(),
+        }
+    }
+}
+
+// ============ 告警配置类型 ============
+
+/// 告警配置
+///
+/// 定期基于遥测数据评估告警规则，触发时发送 Webhook 和桌面通知。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertsConfig {
+    /// 是否启用告警
+    #[serde(default)]
+    pub enabled: bool,
+    /// 评估间隔（秒）
+    #[serde(default = "default_alert_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 同一规则重复告警的冷却时间（秒）
+    #[serde(default = "default_alert_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// 是否发送桌面通知
+    #[serde(default = "default_alert_desktop_notification")]
+    pub desktop_notification: bool,
+    /// 告警规则
+    #[serde(default)]
+    pub rules: Vec<AlertRuleConfig>,
+    /// Webhook 列表
+    #[serde(default)]
+    pub webhooks: Vec<AlertWebhookConfig>,
+}
+
+fn default_alert_check_interval_secs() -> u64 {
+    60
+}
+
+fn default_alert_cooldown_secs() -> u64 {
+    900
+}
+
+fn default_alert_desktop_notification() -> bool {
+    true
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_interval_secs: default_alert_check_interval_secs(),
+            cooldown_secs: default_alert_cooldown_secs(),
+            desktop_notification: default_alert_desktop_notification(),
+            rules: Vec::new(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// 告警规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertRuleConfig {
+    /// 规则名称（用于去重和通知标题）
+    pub name: String,
+    /// 是否启用
+    #[serde(default = "default_alert_rule_enabled")]
+    pub enabled: bool,
+    /// 触发条件
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+}
+
+fn default_alert_rule_enabled() -> bool {
+    true
+}
+
+/// 告警触发条件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// 窗口内错误率超过阈值
+    ErrorRate {
+        /// 错误率阈值（百分比，0-100）
+        threshold_percent: f64,
+        /// 统计窗口（分钟）
+        #[serde(default = "default_alert_window_minutes")]
+        window_minutes: i64,
+        /// 最少请求数（低于该值不评估，避免样本过少误报）
+        #[serde(default = "default_alert_min_requests")]
+        min_requests: u64,
+    },
+    /// 所有凭证都不可用
+    AllCredentialsUnhealthy {
+        /// 限定 Provider 类型，为空时检查每个有凭证的 Provider
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
+    },
+    /// 磁盘剩余空间低于阈值
+    LowDiskSpace {
+        /// 最小剩余空间（MB）
+        #[serde(default = "default_alert_min_free_mb")]
+        min_free_mb: u64,
+        /// 检查的路径，默认为应用数据目录
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+    /// 窗口内 Token 用量超过预算
+    TokenBudget {
+        /// Token 预算（输入 + 输出）
+        max_tokens: u64,
+        /// 统计窗口（小时）
+        #[serde(default = "default_alert_budget_window_hours")]
+        window_hours: i64,
+    },
+}
+
+fn default_alert_window_minutes() -> i64 {
+    5
+}
+
+fn default_alert_min_requests() -> u64 {
+    10
+}
+
+fn default_alert_min_free_mb() -> u64 {
+    1024
+}
+
+fn default_alert_budget_window_hours() -> i64 {
+    24
+}
+
+/// 告警 Webhook
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertWebhookConfig {
+    /// Webhook 地址
+    pub url: String,
+    /// 消息格式
+    #[serde(default)]
+    pub format: AlertWebhookFormat,
+}
+
+/// 告警 Webhook 消息格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertWebhookFormat {
+    /// 通用 JSON
+    #[default]
+    Generic,
+    /// Slack Incoming Webhook
+    Slack,
+    /// Discord Webhook
+    Discord,
+}
+
+// ============ 用量报告配置类型 ============
+
+/// 每日用量报告配置
+///
+/// 每天在指定时间（UTC）生成前一天的用量报告，保存到 `~/.proxycast/reports/`。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReportsConfig {
+    /// 是否启用定时报告
+    #[serde(default)]
+    pub enabled: bool,
+    /// 生成时间（UTC 小时，0-23）
+    #[serde(default = "default_report_hour_utc")]
+    pub hour_utc: u32,
+    /// 报告中列出的模型数量上限
+    #[serde(default = "default_report_top_models")]
+    pub top_models: usize,
+    /// 生成后推送的 Webhook
+    #[serde(default)]
+    pub webhooks: Vec<AlertWebhookConfig>,
+}
+
+fn default_report_hour_utc() -> u32 {
+    1
+}
+
+fn default_report_top_models() -> usize {
+    10
+}
+
+impl Default for ReportsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hour_utc: default_report_hour_utc(),
+            top_models: default_report_top_models(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// OAuth Token 主动刷新配置
+///
+/// 后台定期扫描凭证池，在 Token 过期前提前刷新，避免请求时等待刷新。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenRefreshConfig {
+    /// 是否启用主动刷新
+    #[serde(default = "default_token_refresh_enabled")]
+    pub enabled: bool,
+    /// 扫描间隔（秒）
+    #[serde(default = "default_token_refresh_interval_secs")]
+    pub interval_secs: u64,
+    /// 提前刷新时间（分钟）
+    #[serde(default = "default_token_refresh_lead_minutes")]
+    pub lead_minutes: i64,
+    /// 随机抖动上限（秒），按凭证分散刷新时间
+    #[serde(default = "default_token_refresh_jitter_secs")]
+    pub jitter_secs: u64,
+    /// 每个 Provider 每轮最多刷新的凭证数
+    #[serde(default = "default_token_refresh_max_per_provider")]
+    pub max_per_provider: usize,
+}
+
+fn default_token_refresh_enabled() -> bool {
+    true
+}
+
+fn default_token_refresh_interval_secs() -> u64 {
+    60
+}
+
+fn default_token_refresh_lead_minutes() -> i64 {
+    10
+}
+
+fn default_token_refresh_jitter_secs() -> u64 {
+    120
+}
+
+fn default_token_refresh_max_per_provider() -> usize {
+    3
+}
+
+impl Default for TokenRefreshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_token_refresh_enabled(),
+            interval_secs: default_token_refresh_interval_secs(),
+            lead_minutes: default_token_refresh_lead_minutes(),
+            jitter_secs: default_token_refresh_jitter_secs(),
+            max_per_provider: default_token_refresh_max_per_provider(),
+        }
+    }
+}
+
+/// 凭证过期预警配置
+///
+/// 跟踪 OAuth Refresh Token 的过期时间和刷新失败情况，在凭证失效前通过日志和 WebSocket 发出预警。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CredentialExpiryConfig {
+    /// 是否启用预警
+    #[serde(default = "default_credential_expiry_enabled")]
+    pub enabled: bool,
+    /// 提前预警天数
+    #[serde(default = "default_credential_expiry_warn_days")]
+    pub warn_days: u32,
+    /// 检查间隔（秒）
+    #[serde(default = "default_credential_expiry_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 连续刷新失败达到该次数视为"刷新失败"状态
+    #[serde(default = "default_credential_expiry_refresh_failure_threshold")]
+    pub refresh_failure_threshold: u32,
+    /// 各 Provider 的 Refresh Token 有效期（天，从最近一次成功刷新起算）
+    ///
+    /// 凭证文件中带有明确过期时间时以文件为准，例如 `kiro: 90`
+    #[serde(default)]
+    pub refresh_token_lifetime_days: HashMap<String, u32>,
+}
+
+fn default_credential_expiry_enabled() -> bool {
+    true
+}
+
+fn default_credential_expiry_warn_days() -> u32 {
+    3
+}
+
+fn default_credential_expiry_check_interval_secs() -> u64 {
+    3600
+}
+
+fn default_credential_expiry_refresh_failure_threshold() -> u32 {
+    2
+}
+
+impl Default for CredentialExpiryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_credential_expiry_enabled(),
+            warn_days: default_credential_expiry_warn_days(),
+            check_interval_secs: default_credential_expiry_check_interval_secs(),
+            refresh_failure_threshold: default_credential_expiry_refresh_failure_threshold(),
+            refresh_token_lifetime_days: HashMap::new(),
+        }
+    }
+}
+
+/// 凭证优先级分层配置
+///
+/// 凭证可标记为 `primary`（主力，默认）或 `overflow`（溢出）。启用后，
+/// 只有主力凭证全部不可用或近期被限流时，调度器才会使用溢出凭证。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CredentialTiersConfig {
+    /// 是否启用分层调度
+    #[serde(default = "default_credential_tiers_enabled")]
+    pub enabled: bool,
+    /// 主力凭证被限流后的回避时间（秒），期间优先使用溢出凭证
+    #[serde(default = "default_credential_tiers_rate_limit_cooldown_secs")]
+    pub rate_limit_cooldown_secs: u64,
+    /// 按 Provider 类型覆盖是否启用，例如 `kiro: false`
+    #[serde(default)]
+    pub providers: HashMap<String, bool>,
+}
+
+fn default_credential_tiers_enabled() -> bool {
+    true
+}
+
+fn default_credential_tiers_rate_limit_cooldown_secs() -> u64 {
+    60
+}
+
+impl Default for CredentialTiersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_credential_tiers_enabled(),
+            rate_limit_cooldown_secs: default_credential_tiers_rate_limit_cooldown_secs(),
+            providers: HashMap::new(),
+        }
+    }
+}
+
+impl CredentialTiersConfig {
+    /// 指定 Provider 类型是否启用分层调度
+    pub fn is_enabled_for(&self, provider_type: &str) -> bool {
+        self.providers
+            .get(&provider_type.to_lowercase())
+            .copied()
+            .unwrap_or(self.enabled)
+    }
+}
+
+/// 模型黑名单自动学习配置
+///
+/// 凭证对同一模型连续返回"模型不存在/不支持"类错误达到阈值后，自动加入该凭证的
+/// `not_supported_models`；到期后重新尝试该模型，成功则移除。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelBlacklistConfig {
+    /// 是否启用自动学习
+    #[serde(default = "default_model_blacklist_enabled")]
+    pub enabled: bool,
+    /// 连续失败次数阈值
+    #[serde(default = "default_model_blacklist_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 自动加入的黑名单有效期（秒），到期后重新尝试
+    #[serde(default = "default_model_blacklist_expiry_secs")]
+    pub expiry_secs: u64,
+}
+
+fn default_model_blacklist_enabled() -> bool {
+    true
+}
+
+fn default_model_blacklist_failure_threshold() -> u32 {
+    3
+}
+
+fn default_model_blacklist_expiry_secs() -> u64 {
+    86400
+}
+
+impl Default for ModelBlacklistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_model_blacklist_enabled(),
+            failure_threshold: default_model_blacklist_failure_threshold(),
+            expiry_secs: default_model_blacklist_expiry_secs(),
+        }
+    }
+}
+
+/// 凭证健康检查配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthCheckConfig {
+    /// 批量健康检查的并发数（请求未指定并发数时使用）
+    #[serde(default = "default_health_check_concurrency")]
+    pub concurrency: usize,
+}
+
+fn default_health_check_concurrency() -> usize {
+    5
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: default_health_check_concurrency(),
+        }
+    }
+}
+
+/// 请求优先级通道配置
+///
+/// 将请求分为交互式（流式、编码助手）和批处理两类，按比例为交互式请求保留一部分健康凭证，
+/// 长时间运行的批处理任务只能使用其余凭证，不会占满整个凭证池。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PriorityLanesConfig {
+    /// 是否启用（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 为交互式请求保留的健康凭证比例（0.0-1.0），批处理请求至少保留一个可用凭证
+    #[serde(default = "default_priority_lanes_interactive_reserve_ratio")]
+    pub interactive_reserve_ratio: f64,
+    /// 显式指定通道的请求头（值为 `interactive` 或 `batch`）
+    #[serde(default = "default_priority_lanes_header")]
+    pub header: String,
+    /// 请求体达到该大小（KB）的非流式请求视为批处理，0 表示不按大小判断
+    #[serde(default = "default_priority_lanes_batch_min_body_kb")]
+    pub batch_min_body_kb: u64,
+}
+
+fn default_priority_lanes_interactive_reserve_ratio() -> f64 {
+    0.25
+}
+
+fn default_priority_lanes_header() -> String {
+    "x-proxycast-priority".to_string()
+}
+
+fn default_priority_lanes_batch_min_body_kb() -> u64 {
+    256
+}
+
+impl Default for PriorityLanesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interactive_reserve_ratio: default_priority_lanes_interactive_reserve_ratio(),
+            header: default_priority_lanes_header(),
+            batch_min_body_kb: default_priority_lanes_batch_min_body_kb(),
+        }
+    }
+}
+
+/// 响应信息头配置
+///
+/// 启用后在代理响应上添加 `x-proxycast-*` 响应头（Provider、凭证、重试次数、等待时间、
+/// 估算 Token 数、凭证剩余请求次数）。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseInfoHeadersConfig {
+    /// 是否启用（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 凭证只输出 UUID 前 8 位，不输出凭证名称
+    #[serde(default = "default_response_info_headers_mask_credential")]
+    pub mask_credential: bool,
+}
+
+fn default_response_info_headers_mask_credential() -> bool {
+    true
+}
+
+impl Default for ResponseInfoHeadersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mask_credential: default_response_info_headers_mask_credential(),
+        }
+    }
+}
+
+/// 响应签名配置（修改后需重启服务器）
+///
+/// 启用后对非流式代理响应体计算 HMAC-SHA256，写入 `x-proxycast-signature` 响应头，
+/// 下游可用同一密钥验证响应确实经过 proxycast。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseSigningConfig {
+    /// 是否启用（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 签名密钥
+    #[serde(default)]
+    pub key: String,
+    /// 签名的响应体大小上限（MB），超过时原样转发、不签名
+    #[serde(default = "default_response_signing_max_body_mb")]
+    pub max_body_mb: u64,
+}
+
+fn default_response_signing_max_body_mb() -> u64 {
+    10
+}
+
+impl Default for ResponseSigningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key: String::new(),
+            max_body_mb: default_response_signing_max_body_mb(),
+        }
+    }
+}
+
+/// 上游流式响应看门狗配置
+///
+/// 部分 Provider 会在流式响应中途停止输出但不关闭连接，
+/// 空闲超时后中断上游并向客户端返回错误事件。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamWatchdogConfig {
+    /// 上游两次输出之间的最长空闲时间（秒），0 表示不启用
+    #[serde(default = "default_stream_watchdog_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// 超时后是否将凭证标记为可疑（错误计数加一，连续达到阈值后标记为不健康）
+    #[serde(default = "default_stream_watchdog_mark_credential")]
+    pub mark_credential: bool,
+}
+
+fn default_stream_watchdog_idle_timeout_secs() -> u64 {
+    120
+}
+
+fn default_stream_watchdog_mark_credential() -> bool {
+    true
+}
+
+impl Default for StreamWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_secs: default_stream_watchdog_idle_timeout_secs(),
+            mark_credential: default_stream_watchdog_mark_credential(),
+        }
+    }
+}
+
+/// 上游连通性负缓存配置
+///
+/// 上游主机连接失败（DNS 解析失败、连接被拒绝、连接超时）后短时间内直接判定为不可达，
+/// 请求立即失败并由故障转移切换到其他凭证，不再等待完整的连接超时；
+/// 后台定期探测主机，恢复后立即移除。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpstreamReachabilityConfig {
+    /// 是否启用
+    #[serde(default = "default_upstream_reachability_enabled")]
+    pub enabled: bool,
+    /// 不可达记录的有效期（秒）
+    #[serde(default = "default_upstream_reachability_negative_ttl_secs")]
+    pub negative_ttl_secs: u64,
+    /// 后台探测间隔（秒）
+    #[serde(default = "default_upstream_reachability_probe_interval_secs")]
+    pub probe_interval_secs: u64,
+    /// 单次探测的连接超时（秒）
+    #[serde(default = "default_upstream_reachability_probe_timeout_secs")]
+    pub probe_timeout_secs: u64,
+}
+
+fn default_upstream_reachability_enabled() -> bool {
+    true
+}
+
+fn default_upstream_reachability_negative_ttl_secs() -> u64 {
+    30
+}
+
+fn default_upstream_reachability_probe_interval_secs() -> u64 {
+    5
+}
+
+fn default_upstream_reachability_probe_timeout_secs() -> u64 {
+    3
+}
+
+impl Default for UpstreamReachabilityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_upstream_reachability_enabled(),
+            negative_ttl_secs: default_upstream_reachability_negative_ttl_secs(),
+            probe_interval_secs: default_upstream_reachability_probe_interval_secs(),
+            probe_timeout_secs: default_upstream_reachability_probe_timeout_secs(),
+        }
+    }
+}
+
+/// 上游 TLS 配置
+///
+/// 按 Provider 为上游 HTTPS 请求配置额外的 CA 证书（如企业 TLS 拦截代理）和证书公钥固定。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UpstreamTlsConfig {
+    /// Provider 类型 -> TLS 选项（`*` 匹配未单独配置的 Provider）
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderTlsConfig>,
+}
+
+/// 单个 Provider 的上游 TLS 选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProviderTlsConfig {
+    /// 额外信任的 CA 证书文件（PEM，可包含多个证书）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+    /// 不信任内置根证书，只信任 `ca_file` 中的证书
+    #[serde(default)]
+    pub disable_system_roots: bool,
+    /// 固定的证书公钥（SubjectPublicKeyInfo 的 SHA-256，Base64 编码），
+    /// 证书链中任一证书匹配即可，为空时不校验
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_spki_sha256: Vec<String>,
+}
+
+/// 上游响应结构校验配置
+///
+/// 非流式成功响应不符合预期的 JSON 结构时，不再原样转发给客户端，
+/// 而是将原始响应体写入隔离目录并返回结构化错误。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseValidationConfig {
+    /// 是否启用
+    #[serde(default = "default_response_validation_enabled")]
+    pub enabled: bool,
+    /// 隔离目录（支持 ~ 展开）
+    #[serde(default = "default_quarantine_dir")]
+    pub quarantine_dir: String,
+    /// 隔离目录中最多保留的文件数（超出时删除最早的文件），0 表示不写入文件
+    #[serde(default = "default_quarantine_max_files")]
+    pub max_quarantine_files: usize,
+    /// 是否将凭证标记为可疑（错误计数加一，连续达到阈值后标记为不健康）
+    #[serde(default = "default_response_validation_mark_credential")]
+    pub mark_credential: bool,
+}
+
+fn default_response_validation_enabled() -> bool {
+    true
+}
+
+fn default_response_validation_mark_credential() -> bool {
+    true
+}
+
+fn default_quarantine_dir() -> String {
+    "~/.proxycast/quarantine".to_string()
+}
+
+fn default_quarantine_max_files() -> usize {
+    200
+}
+
+impl Default for ResponseValidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_response_validation_enabled(),
+            quarantine_dir: default_quarantine_dir(),
+            max_quarantine_files: default_quarantine_max_files(),
+            mark_credential: default_response_validation_mark_credential(),
+        }
+    }
+}
+
+/// 会话对话记录持久化配置
+///
+/// 启用后按 SessionId 保存每个会话的完整对话（请求上下文 + 最后一次回复），
+/// 客户端重连后可通过 `/v1/sessions/:id` 获取或续接对话。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionPersistenceConfig {
+    /// 是否启用（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 未携带 `X-Session-Id` 请求头时，是否按请求内容指纹生成 SessionId 并保存
+    #[serde(default = "default_session_persistence_fingerprint")]
+    pub fingerprint_sessions: bool,
+}
+
+fn default_session_persistence_fingerprint() -> bool {
+    true
+}
+
+impl Default for SessionPersistenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fingerprint_sessions: default_session_persistence_fingerprint(),
+        }
+    }
+}
+
+/// thoughtSignature 缓存配置
+///
+/// 签名按工具调用 ID 缓存，超过有效期或条目上限时淘汰最旧的条目。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignatureStoreConfig {
+    /// 是否持久化到数据库（重启后恢复）
+    #[serde(default = "default_signature_store_persist")]
+    pub persist: bool,
+    /// 签名有效期（秒）
+    #[serde(default = "default_signature_store_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最大缓存条目数
+    #[serde(default = "default_signature_store_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_signature_store_persist() -> bool {
+    true
+}
+
+fn default_signature_store_ttl_secs() -> u64 {
+    86400
+}
+
+fn default_signature_store_max_entries() -> usize {
+    10000
+}
+
+impl Default for SignatureStoreConfig {
+    fn default() -> Self {
+        Self {
+            persist: default_signature_store_persist(),
+            ttl_secs: default_signature_store_ttl_secs(),
+            max_entries: default_signature_store_max_entries(),
+        }
+    }
+}
+
+/// 请求 ID 配置
+///
+/// 请求 ID 会写入日志、遥测记录和响应头，便于与已有的链路追踪系统关联。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestIdConfig {
+    /// 请求头名称（入站读取、响应回写、上游透传均使用该名称）
+    #[serde(default = "default_request_id_header")]
+    pub header: String,
+    /// 是否沿用客户端传入的请求 ID（关闭时总是生成新的 ID）
+    #[serde(default = "default_request_id_trust_inbound")]
+    pub trust_inbound: bool,
+    /// 是否透传给上游（仅 Claude / OpenAI 兼容的 API Key Provider）
+    #[serde(default = "default_request_id_propagate_upstream")]
+    pub propagate_upstream: bool,
+}
+
+fn default_request_id_header() -> String {
+    "x-request-id".to_string()
+}
+
+fn default_request_id_trust_inbound() -> bool {
+    true
+}
+
+fn default_request_id_propagate_upstream() -> bool {
+    true
+}
+
+impl Default for RequestIdConfig {
+    fn default() -> Self {
+        Self {
+            header: default_request_id_header(),
+            trust_inbound: default_request_id_trust_inbound(),
+            propagate_upstream: default_request_id_propagate_upstream(),
+        }
+    }
+}
+
+/// 客户端请求头透传配置
+///
+/// 默认只向上游发送 Provider 所需的固定请求头。按 Provider 配置允许透传的客户端请求头
+/// （如 `anthropic-beta`），以及固定覆盖的请求头，便于客户端通过代理使用上游 Beta 功能。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HeaderPassthroughConfig {
+    /// 是否启用
+    #[serde(default = "default_header_passthrough_enabled")]
+    pub enabled: bool,
+    /// Provider 名称（`claude` / `openai` / `kiro`）-> 请求头策略
+    #[serde(default = "default_header_passthrough_providers")]
+    pub providers: HashMap<String, ProviderHeaderPolicy>,
+}
+
+/// 单个 Provider 的请求头策略
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProviderHeaderPolicy {
+    /// 允许透传的客户端请求头（不区分大小写；认证相关请求头始终不透传）
+    #[serde(default)]
+    pub passthrough: Vec<String>,
+    /// 固定覆盖的请求头（优先于客户端请求头和 Provider 默认值）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+}
+
+fn default_header_passthrough_enabled() -> bool {
+    true
+}
+
+fn default_header_passthrough_providers() -> HashMap<String, ProviderHeaderPolicy> {
+    HashMap::from([
+        (
+            "claude".to_string(),
+            ProviderHeaderPolicy {
+                passthrough: vec![
+                    "anthropic-beta".to_string(),
+                    "anthropic-version".to_string(),
+                ],
+                overrides: BTreeMap::new(),
+            },
+        ),
+        (
+            "openai".to_string(),
+            ProviderHeaderPolicy {
+                passthrough: vec![
+                    "openai-organization".to_string(),
+                    "openai-project".to_string(),
+                ],
+                overrides: BTreeMap::new(),
+            },
+        ),
+        (
+            // 不转发为上游请求头，由 Kiro Provider 读取为 agent mode 和 origin
+            "kiro".to_string(),
+            ProviderHeaderPolicy {
+                passthrough: vec![
+                    "x-amzn-kiro-agent-mode".to_string(),
+                    "x-kiro-origin".to_string(),
+                ],
+                overrides: BTreeMap::new(),
+            },
+        ),
+    ])
+}
+
+impl Default for HeaderPassthroughConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_header_passthrough_enabled(),
+            providers: default_header_passthrough_providers(),
+        }
+    }
+}
+
+/// 流量录制模式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingMode {
+    /// 不录制
+    #[default]
+    Off,
+    /// 转发请求并将请求和响应录制为 fixture
+    Record,
+    /// 不访问上游，直接返回匹配的 fixture
+    Replay,
+}
+
+/// 流量录制与回放配置
+///
+/// 录制模式下将 API 请求、处理请求的 Provider 和响应保存为 fixture 文件；
+/// 回放模式下按请求内容匹配 fixture 并直接返回录制的响应，用于离线测试客户端集成。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordingConfig {
+    /// 录制模式
+    #[serde(default)]
+    pub mode: RecordingMode,
+    /// fixture 目录（支持 ~ 展开）
+    #[serde(default = "default_recording_dir")]
+    pub dir: String,
+    /// 匹配请求时忽略的请求体顶层字段（如每次请求都会变化的 `metadata`）
+    #[serde(default = "default_recording_ignore_fields")]
+    pub ignore_fields: Vec<String>,
+}
+
+fn default_recording_dir() -> String {
+    "~/.proxycast/recordings".to_string()
+}
+
+fn default_recording_ignore_fields() -> Vec<String> {
+    vec!["metadata".to_string(), "user".to_string()]
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            mode: RecordingMode::default(),
+            dir: default_recording_dir(),
+            ignore_fields: default_recording_ignore_fields(),
+        }
+    }
+}
+
+/// 内置 mock Provider 配置
+///
+/// mock Provider 不访问上游，返回确定性的模拟响应，用于测试客户端集成和路由规则。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MockProviderConfig {
+    /// 响应前的延迟（毫秒）
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// 流式响应每个事件之间的延迟（毫秒）
+    #[serde(default)]
+    pub chunk_delay_ms: u64,
+    /// 固定回复文本（为空时回显最后一条用户消息）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply: Option<String>,
+}
+
+/// 故障注入配置
+///
+/// 按 Provider 以指定概率在调用上游之前注入故障，用于验证重试、降级和凭证切换配置。
+/// 默认只在服务监听本机地址时生效，避免误用于生产环境。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ChaosConfig {
+    /// 是否启用（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 允许在非本机监听地址上启用
+    #[serde(default)]
+    pub allow_remote: bool,
+    /// Provider 类型 -> 故障规则（`*` 匹配未单独配置的 Provider）
+    #[serde(default)]
+    pub providers: HashMap<String, ChaosRule>,
+}
+
+/// 单个 Provider 的故障规则（概率取值 0.0 ~ 1.0）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChaosRule {
+    /// 返回 429 的概率
+    #[serde(default)]
+    pub rate_limit_rate: f64,
+    /// 返回 500 的概率
+    #[serde(default)]
+    pub server_error_rate: f64,
+    /// 模拟超时的概率
+    #[serde(default)]
+    pub timeout_rate: f64,
+    /// 模拟超时时等待的时长（毫秒），之后返回 504
+    #[serde(default = "default_chaos_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 额外随机延迟上限（毫秒）
+    #[serde(default)]
+    pub latency_jitter_ms: u64,
+}
+
+fn default_chaos_timeout_ms() -> u64 {
+    30_000
+}
+
+impl Default for ChaosRule {
+    fn default() -> Self {
+        Self {
+            rate_limit_rate: 0.0,
+            server_error_rate: 0.0,
+            timeout_rate: 0.0,
+            timeout_ms: default_chaos_timeout_ms(),
+            latency_jitter_ms: 0,
+        }
+    }
 }
 
-pub const DEFAULT_API_KEY: &str = "proxy_cast";
+/// 服务端对话压缩配置
+///
+/// 请求带 `x-proxycast-compact` 请求头且估算的输入 Token 超过阈值时，
+/// 用廉价模型把较早的消息总结为结构化摘要，替换原消息后再转发。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactionConfig {
+    /// 是否启用（默认关闭，启用后仍需请求头开启）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 触发压缩的输入 Token 阈值（估算值）
+    #[serde(default = "default_compaction_threshold_tokens")]
+    pub threshold_tokens: u32,
+    /// 保留原文的最近消息数
+    #[serde(default = "default_compaction_keep_recent_messages")]
+    pub keep_recent_messages: usize,
+    /// 生成摘要使用的凭证池 Provider 类型（为空时使用当前请求选择的 Provider）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub provider: String,
+    /// 生成摘要使用的模型
+    #[serde(default = "default_compaction_model")]
+    pub model: String,
+    /// 摘要的最大输出 Token 数
+    #[serde(default = "default_compaction_max_summary_tokens")]
+    pub max_summary_tokens: u32,
+}
 
-fn default_api_key() -> String {
-    DEFAULT_API_KEY.to_string()
+fn default_compaction_threshold_tokens() -> u32 {
+    100_000
 }
 
-/// 生成安全 API Key（32 字节随机）
-pub fn generate_secure_api_key() -> String {
-    use rand::distributions::Alphanumeric;
-    use rand::Rng;
+fn default_compaction_keep_recent_messages() -> usize {
+    6
+}
 
-    let token: String = rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(32)
-        .map(char::from)
-        .collect();
-    format!("pc_{token}")
+fn default_compaction_model() -> String {
+    "claude-haiku-4-5".to_string()
 }
 
-/// 是否为默认 API Key
-pub fn is_default_api_key(api_key: &str) -> bool {
-    api_key == DEFAULT_API_KEY
+fn default_compaction_max_summary_tokens() -> u32 {
+    2048
 }
 
-impl Default for ServerConfig {
+impl Default for CompactionConfig {
     fn default() -> Self {
         Self {
-            host: default_host(),
-            port: default_port(),
-            api_key: default_api_key(),
-            tls: TlsConfig::default(),
+            enabled: false,
+            threshold_tokens: default_compaction_threshold_tokens(),
+            keep_recent_messages: default_compaction_keep_recent_messages(),
+            provider: String::new(),
+            model: default_compaction_model(),
+            max_summary_tokens: default_compaction_max_summary_tokens(),
         }
     }
 }
 
-/// Provider 配置集合
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct ProvidersConfig {
-    /// Kiro Provider 配置
-    #[serde(default)]
-    pub kiro: ProviderConfig,
-    /// Gemini Provider 配置
-    #[serde(default)]
-    pub gemini: ProviderConfig,
-    /// Qwen Provider 配置
-    #[serde(default)]
-    pub qwen: ProviderConfig,
-    /// OpenAI 自定义 Provider 配置
+/// 超出上下文窗口时的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextOverflowAction {
+    /// 直接返回 `context_length_exceeded` 错误
+    #[default]
+    Reject,
+    /// 改用同一模型家族中上下文更大的模型，没有合适的模型时返回错误
+    Upgrade,
+}
+
+/// 上下文窗口预检配置
+///
+/// 转发前按本地估算的输入 Token 数检查目标模型的上下文窗口，
+/// 超出时不再请求上游，直接返回错误或改用上下文更大的模型。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ContextOverflowConfig {
+    /// 是否启用（默认关闭）
     #[serde(default)]
-    pub openai: CustomProviderConfig,
-    /// Claude 自定义 Provider 配置
+    pub enabled: bool,
+    /// 超出上下文窗口时的处理方式
     #[serde(default)]
-    pub claude: CustomProviderConfig,
+    pub action: ContextOverflowAction,
+    /// 可升级到的候选模型，按上下文窗口从小到大选择能容纳请求的第一个同家族模型
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upgrade_models: Vec<String>,
+    /// 模型上下文窗口（模型名前缀 -> Token 数），覆盖内置值
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub context_windows: HashMap<String, u32>,
 }
 
-impl Default for ProvidersConfig {
-    fn default() -> Self {
-        Self {
-            kiro: ProviderConfig {
-                enabled: true,
-                credentials_path: Some("~/.aws/sso/cache/kiro-auth-token.json".to_string()),
-                region: Some("us-east-1".to_string()),
-                project_id: None,
-            },
-            gemini: ProviderConfig {
-                enabled: false,
-                credentials_path: Some("~/.gemini/oauth_creds.json".to_string()),
-                region: None,
-                project_id: None,
-            },
-            qwen: ProviderConfig {
-                enabled: false,
-                credentials_path: Some("~/.qwen/oauth_creds.json".to_string()),
-                region: None,
-                project_id: None,
-            },
-            openai: CustomProviderConfig {
-                enabled: false,
-                api_key: None,
-                base_url: Some("https://api.openai.com/v1".to_string()),
-            },
-            claude: CustomProviderConfig {
-                enabled: false,
-                api_key: None,
-                base_url: Some("https://api.anthropic.com".to_string()),
-            },
-        }
-    }
+/// 凭证池存储后端
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialStorageBackend {
+    /// 本地 SQLite 数据库
+    #[default]
+    Sqlite,
+    /// 进程内存（不持久化，重启后清空，用于测试和临时实例）
+    Memory,
 }
 
-/// OAuth Provider 配置
+/// 凭证池存储配置（修改后需重启）
+///
+/// 其他后端（PostgreSQL、etcd、Redis 等）实现 `CredentialStorage` trait 后注册，
+/// 多个实例即可共享同一个凭证池。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
-pub struct ProviderConfig {
-    /// 是否启用
+pub struct CredentialStorageConfig {
+    /// 存储后端
     #[serde(default)]
-    pub enabled: bool,
-    /// 凭证文件路径
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub credentials_path: Option<String>,
-    /// 区域
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub region: Option<String>,
-    /// 项目 ID
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub project_id: Option<String>,
+    pub backend: CredentialStorageBackend,
 }
 
-/// 自定义 Provider 配置（API Key 方式）
+/// 维护窗口
+///
+/// `provider` 和 `credential` 必须且只能指定一个。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
-pub struct CustomProviderConfig {
-    /// 是否启用
+pub struct MaintenanceWindowConfig {
+    /// Provider 类型（如 `kiro`）
     #[serde(default)]
-    pub enabled: bool,
-    /// API 密钥
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub api_key: Option<String>,
-    /// 基础 URL
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub base_url: Option<String>,
+    pub provider: Option<String>,
+    /// 凭证 UUID 或名称
+    #[serde(default)]
+    pub credential: Option<String>,
+    /// 开始时间（RFC 3339），为空表示立即开始
+    #[serde(default)]
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    /// 结束时间（RFC 3339），为空表示直到移除
+    #[serde(default)]
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    /// 维护原因
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
-/// 路由配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct RoutingConfig {
-    /// 默认 Provider
-    #[serde(default = "default_provider")]
-    pub default_provider: String,
-    /// 模型别名映射
+/// 维护窗口配置（支持热重载）
+///
+/// 维护期间凭证池不选择对应的 Provider 或凭证，`/health` 将其报告为计划内维护。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct MaintenanceConfig {
     #[serde(default)]
-    pub model_aliases: HashMap<String, String>,
+    pub windows: Vec<MaintenanceWindowConfig>,
 }
 
-fn default_provider() -> String {
-    "kiro".to_string()
+fn default_cluster_lease_ttl_secs() -> u64 {
+    30
 }
 
-impl Default for RoutingConfig {
+/// 集群模式配置（修改后需重启）
+///
+/// 多台机器上的实例通过共享的凭证池存储后端共享凭证健康和限流状态，
+/// 并通过存储后端的租约选出 leader，只有 leader 执行后台维护任务。
+/// 集群模式在注册了共享存储后端时自动启用，内置的 SQLite 和内存后端不可共享，
+/// 此时只有定期健康检查和备份生效。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClusterConfig {
+    /// 实例 ID，未设置时启动时随机生成
+    #[serde(default)]
+    pub instance_id: Option<String>,
+    /// leader 租约有效期（秒），leader 每 1/3 有效期续约一次
+    #[serde(default = "default_cluster_lease_ttl_secs")]
+    pub lease_ttl_secs: u64,
+    /// leader 定期检查凭证健康的间隔（秒），0 表示不检查
+    #[serde(default)]
+    pub health_check_interval_secs: u64,
+    /// leader 定期备份数据库的间隔（小时），0 表示不备份
+    #[serde(default)]
+    pub backup_interval_hours: u64,
+}
+
+impl Default for ClusterConfig {
     fn default() -> Self {
         Self {
-            default_provider: default_provider(),
-            model_aliases: HashMap::new(),
+            instance_id: None,
+            lease_ttl_secs: default_cluster_lease_ttl_secs(),
+            health_check_interval_secs: 0,
+            backup_interval_hours: 0,
         }
     }
 }
 
-/// 重试配置
+/// 心跳配置
+///
+/// 长时间无输出的流式响应可能被中间网络设备按空闲超时断开，定期发送心跳保持连接活跃。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct RetrySettings {
-    /// 最大重试次数
-    #[serde(default = "default_max_retries")]
-    pub max_retries: u32,
-    /// 基础延迟（毫秒）
-    #[serde(default = "default_base_delay_ms")]
-    pub base_delay_ms: u64,
-    /// 最大延迟（毫秒）
-    #[serde(default = "default_max_delay_ms")]
-    pub max_delay_ms: u64,
-    /// 是否自动切换 Provider
-    #[serde(default = "default_auto_switch")]
-    pub auto_switch_provider: bool,
+pub struct HeartbeatConfig {
+    /// SSE 流空闲多少秒后发送心跳（0 表示关闭）
+    ///
+    /// `/v1/messages` 发送 Anthropic `ping` 事件，`/v1/chat/completions` 发送 SSE 注释行
+    #[serde(default = "default_heartbeat_sse_interval_secs")]
+    pub sse_interval_secs: u64,
+    /// WebSocket 服务端 Ping 间隔（秒，0 表示关闭）
+    #[serde(default = "default_heartbeat_ws_ping_interval_secs")]
+    pub ws_ping_interval_secs: u64,
+    /// WebSocket 连续多少次 Ping 未收到 Pong 后断开连接（0 表示不检测）
+    #[serde(default = "default_heartbeat_ws_max_missed_pongs")]
+    pub ws_max_missed_pongs: u32,
+    /// WebSocket 连接多少秒未收到客户端消息后断开（Ping/Pong 帧不计，0 表示不限制）
+    #[serde(default)]
+    pub ws_idle_timeout_secs: u64,
 }
 
-fn default_max_retries() -> u32 {
-    3
+fn default_heartbeat_sse_interval_secs() -> u64 {
+    15
 }
 
-fn default_base_delay_ms() -> u64 {
-    1000
+fn default_heartbeat_ws_ping_interval_secs() -> u64 {
+    30
 }
 
-fn default_max_delay_ms() -> u64 {
-    30000
+fn default_heartbeat_ws_max_missed_pongs() -> u32 {
+    2
 }
 
-fn default_auto_switch() -> bool {
-    true
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            sse_interval_secs: default_heartbeat_sse_interval_secs(),
+            ws_ping_interval_secs: default_heartbeat_ws_ping_interval_secs(),
+            ws_max_missed_pongs: default_heartbeat_ws_max_missed_pongs(),
+            ws_idle_timeout_secs: 0,
+        }
+    }
 }
 
-impl Default for RetrySettings {
+/// SSE 断点续传配置
+///
+/// 启用后流式响应在服务端缓冲，事件带 `id` 字段，客户端网络中断后可带 `Last-Event-ID`
+/// 重新发送请求从断点继续接收，而不是重新生成。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SseResumeConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 流结束后缓冲保留时间（秒）
+    #[serde(default = "default_sse_resume_retention_secs")]
+    pub retention_secs: u64,
+}
+
+fn default_sse_resume_retention_secs() -> u64 {
+    300
+}
+
+impl Default for SseResumeConfig {
     fn default() -> Self {
         Self {
-            max_retries: default_max_retries(),
-            base_delay_ms: default_base_delay_ms(),
-            max_delay_ms: default_max_delay_ms(),
-            auto_switch_provider: default_auto_switch(),
+            enabled: false,
+            retention_secs: default_sse_resume_retention_secs(),
         }
     }
 }
 
-/// 日志配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct LoggingConfig {
-    /// 是否启用日志
-    #[serde(default = "default_logging_enabled")]
+/// 请求内容防护配置
+///
+/// 检查入站消息（system、messages）是否命中拒绝规则（密钥、内部主机名等），
+/// 命中时按策略拒绝请求或脱敏后转发。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GuardrailsConfig {
+    /// 是否启用
+    #[serde(default)]
     pub enabled: bool,
-    /// 日志级别
-    #[serde(default = "default_log_level")]
-    pub level: String,
-    /// 日志保留天数
-    #[serde(default = "default_retention_days")]
-    pub retention_days: u32,
-    /// 是否包含请求体
+    /// 未单独分配策略的 API Key 使用的策略（为空时不检查）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_policy: Option<String>,
+    /// 策略列表
     #[serde(default)]
-    pub include_request_body: bool,
+    pub policies: Vec<GuardrailPolicyConfig>,
+    /// 入站 API Key -> 策略名称
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub api_key_policies: HashMap<String, String>,
 }
 
-fn default_logging_enabled() -> bool {
-    true
+/// 命中规则时的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardrailAction {
+    /// 拒绝请求
+    #[default]
+    Block,
+    /// 将命中内容替换后转发
+    Redact,
 }
 
-fn default_log_level() -> String {
-    "info".to_string()
+/// 防护策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GuardrailPolicyConfig {
+    /// 策略名称
+    pub name: String,
+    /// 命中规则时的处理方式
+    #[serde(default)]
+    pub action: GuardrailAction,
+    /// 是否启用内置的密钥检测规则（AWS、GitHub、OpenAI、Slack、Google、私钥）
+    #[serde(default)]
+    pub builtin_secrets: bool,
+    /// 自定义拒绝规则
+    #[serde(default)]
+    pub patterns: Vec<GuardrailPatternConfig>,
+    /// 脱敏替换文本
+    #[serde(default = "default_guardrail_replacement")]
+    pub replacement: String,
 }
 
-fn default_retention_days() -> u32 {
-    7
+fn default_guardrail_replacement() -> String {
+    "[REDACTED]".to_string()
 }
 
-impl Default for LoggingConfig {
+/// 拒绝规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GuardrailPatternConfig {
+    /// 规则名称（用于日志和错误信息）
+    pub name: String,
+    /// 正则表达式
+    pub regex: String,
+}
+
+/// 响应后处理配置
+///
+/// 与参数注入相对，在返回客户端之前改写模型输出的文本（OpenAI 和 Anthropic 格式，包括流式响应）：
+/// 在上游忽略的停止序列处截断、移除 Provider 输出残留、按规则替换文本。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseProcessingConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 在客户端指定的停止序列（`stop` / `stop_sequences`）处截断
+    #[serde(default = "default_response_processing_builtin")]
+    pub enforce_stop_sequences: bool,
+    /// 移除内置识别的 Provider 输出残留（如 `[Called xxx with args: {...}]`、泄露的特殊 token）
+    #[serde(default = "default_response_processing_builtin")]
+    pub strip_artifacts: bool,
+    /// 规范化空白：去掉首尾空白和行尾空格，连续空行合并为一个
+    #[serde(default)]
+    pub normalize_whitespace: bool,
+    /// 文本替换规则
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ResponseRuleConfig>,
+}
+
+fn default_response_processing_builtin() -> bool {
+    true
+}
+
+impl Default for ResponseProcessingConfig {
     fn default() -> Self {
         Self {
-            enabled: default_logging_enabled(),
-            level: default_log_level(),
-            retention_days: default_retention_days(),
-            include_request_body: false,
+            enabled: false,
+            enforce_stop_sequences: default_response_processing_builtin(),
+            strip_artifacts: default_response_processing_builtin(),
+            normalize_whitespace: false,
+            rules: Vec::new(),
         }
     }
 }
 
+/// 响应文本替换规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseRuleConfig {
+    /// 规则 ID（用于日志）
+    pub id: String,
+    /// 模型匹配模式（支持通配符，默认匹配全部模型）
+    #[serde(default = "default_response_rule_pattern")]
+    pub pattern: String,
+    /// 正则表达式（流式响应按行匹配，不能跨行）
+    pub regex: String,
+    /// 替换文本（为空时删除命中内容）
+    #[serde(default)]
+    pub replacement: String,
+    /// 是否启用
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+}
+
+fn default_response_rule_pattern() -> String {
+    "*".to_string()
+}
+
 // ============ 模型配置类型 ============
 
 /// 模型信息
@@ -1211,6 +2991,15 @@ pub struct InjectionRuleConfig {
     /// 是否启用
     #[serde(default = "default_rule_enabled")]
     pub enabled: bool,
+    /// 生效条件（Provider、客户端类型、请求路径、请求头、消息内容正则）
+    #[serde(default, skip_serializing_if = "InjectionConditions::is_empty")]
+    pub conditions: InjectionConditions,
+    /// 要移除的参数
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+    /// 参数上限
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub caps: BTreeMap<String, f64>,
 }
 
 fn default_rule_enabled() -> bool {
@@ -1227,6 +3016,9 @@ impl From<InjectionRuleConfig> for InjectionRule {
         rule.mode = config.mode;
         rule.priority = config.priority;
         rule.enabled = config.enabled;
+        rule.conditions = config.conditions;
+        rule.remove = config.remove;
+        rule.caps = config.caps;
         rule
     }
 }
@@ -1240,6 +3032,9 @@ impl From<&InjectionRule> for InjectionRuleConfig {
             mode: rule.mode,
             priority: rule.priority,
             enabled: rule.enabled,
+            conditions: rule.conditions.clone(),
+            remove: rule.remove.clone(),
+            caps: rule.caps.clone(),
         }
     }
 }
@@ -1266,6 +3061,36 @@ impl Default for Config {
             models: ModelsConfig::default(),
             agent: NativeAgentConfig::default(),
             experimental: ExperimentalFeatures::default(),
+            alerts: AlertsConfig::default(),
+            reports: ReportsConfig::default(),
+            token_refresh: TokenRefreshConfig::default(),
+            credential_expiry: CredentialExpiryConfig::default(),
+            credential_tiers: CredentialTiersConfig::default(),
+            model_blacklist: ModelBlacklistConfig::default(),
+            health_check: HealthCheckConfig::default(),
+            priority_lanes: PriorityLanesConfig::default(),
+            response_info_headers: ResponseInfoHeadersConfig::default(),
+            response_signing: ResponseSigningConfig::default(),
+            stream_watchdog: StreamWatchdogConfig::default(),
+            response_validation: ResponseValidationConfig::default(),
+            upstream_reachability: UpstreamReachabilityConfig::default(),
+            upstream_tls: UpstreamTlsConfig::default(),
+            session_persistence: SessionPersistenceConfig::default(),
+            signature_store: SignatureStoreConfig::default(),
+            request_id: RequestIdConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            guardrails: GuardrailsConfig::default(),
+            response_processing: ResponseProcessingConfig::default(),
+            header_passthrough: HeaderPassthroughConfig::default(),
+            recording: RecordingConfig::default(),
+            mock_provider: MockProviderConfig::default(),
+            chaos: ChaosConfig::default(),
+            compaction: CompactionConfig::default(),
+            context_overflow: ContextOverflowConfig::default(),
+            credential_storage: CredentialStorageConfig::default(),
+            cluster: ClusterConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            sse_resume: SseResumeConfig::default(),
         }
     }
 }
@@ -1587,4 +3412,24 @@ mod unit_tests {
             "CommandOrControl+Shift+S"
         );
     }
+
+    #[test]
+    fn test_server_base_path_and_public_url() {
+        let mut server = ServerConfig::default();
+        assert_eq!(server.normalized_base_path(), "");
+        assert_eq!(server.normalized_public_url(), None);
+
+        for base_path in ["proxycast", "/proxycast/", " /proxycast "] {
+            server.base_path = base_path.to_string();
+            assert_eq!(server.normalized_base_path(), "/proxycast");
+        }
+        server.base_path = "/".to_string();
+        assert_eq!(server.normalized_base_path(), "");
+
+        server.public_url = Some("https://example.com/proxycast/".to_string());
+        assert_eq!(
+            server.normalized_public_url().as_deref(),
+            Some("https://example.com/proxycast")
+        );
+    }
 }
@@ -86,7 +86,11 @@ impl ConfigManager {
 
     /// 从 YAML 字符串解析配置
     pub fn parse_yaml(yaml: &str) -> Result<Config, ConfigError> {
-        serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))
+        let config: Config =
+            serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        crate::router::validate_alias_rules(&config.routing.model_alias_rules)
+            .map_err(ConfigError::ValidationError)?;
+        Ok(config)
     }
 
     /// 将配置序列化为 YAML 字符串
@@ -213,6 +217,18 @@ impl ConfigManager {
                 .model_aliases
                 .extend(other.routing.model_aliases);
         }
+        if !other.routing.model_alias_rules.is_empty() {
+            self.config
+                .routing
+                .model_alias_rules
+                .extend(other.routing.model_alias_rules);
+        }
+        if !other.routing.model_max_tokens.is_empty() {
+            self.config
+                .routing
+                .model_max_tokens
+                .extend(other.routing.model_max_tokens);
+        }
         if other.routing.default_provider != "kiro" {
             self.config.routing.default_provider = other.routing.default_provider;
         }
@@ -801,6 +817,40 @@ logging:
         );
     }
 
+    #[test]
+    fn test_parse_yaml_model_alias_rules() {
+        let yaml = r#"
+routing:
+  default_provider: "kiro"
+  model_alias_rules:
+    - pattern: "gpt-4*"
+      target: "claude-sonnet-4-5"
+      priority: 10
+    - pattern: "(.*)-preview"
+      target: "$1"
+      kind: regex
+"#;
+        let config = ConfigManager::parse_yaml(yaml).unwrap();
+        let rules = &config.routing.model_alias_rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].kind, crate::config::ModelAliasRuleKind::Glob);
+        assert_eq!(rules[0].priority, 10);
+        assert_eq!(rules[1].kind, crate::config::ModelAliasRuleKind::Regex);
+
+        // 无效正则在加载时拒绝
+        let invalid = r#"
+routing:
+  model_alias_rules:
+    - pattern: "gpt-("
+      target: "x"
+      kind: regex
+"#;
+        assert!(matches!(
+            ConfigManager::parse_yaml(invalid),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
     #[test]
     fn test_to_yaml_roundtrip() {
         let config = Config::default();
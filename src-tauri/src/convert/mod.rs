@@ -0,0 +1,266 @@
+//! 请求 / 响应格式归一化
+//!
+//! 用生产环境的转换代码把客户端请求转换为各 Provider 的上游请求体，把上游响应转换回 OpenAI 格式，
+//! 供黄金文件测试（`tests/fixtures/conversion`）比对，转换模块的回归可以被机械地发现：
+//! - 请求：`normalize(source, target, request)`
+//! - 响应：`normalize_response(target, response, model)`
+//!
+//! 随机生成或与时间相关的字段（会话 ID、请求 ID、时间戳等）替换为 [`VOLATILE`]。
+
+use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::converter::cw_to_openai::convert_cw_event_to_openai_chunk;
+use crate::converter::openai_to_antigravity::{
+    convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
+};
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::codewhisperer::CWStreamEvent;
+use crate::models::openai::ChatCompletionRequest;
+use crate::providers::claude_custom::ClaudeCustomProvider;
+use crate::providers::qwen::QwenProvider;
+use crate::translator::kiro::anthropic::request::convert_anthropic_to_codewhisperer;
+use crate::translator::kiro::openai::request::convert_openai_to_codewhisperer;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// 易变字段的占位值
+pub const VOLATILE: &str = "<volatile>";
+
+/// 请求中的易变字段（任意层级）
+const VOLATILE_REQUEST_KEYS: &[&str] = &["conversationId", "requestId", "sessionId"];
+
+/// 响应中的易变字段（仅顶层 / 每个 chunk 的顶层）
+const VOLATILE_RESPONSE_KEYS: &[&str] = &["id", "created"];
+
+/// 客户端请求格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    /// OpenAI Chat Completions
+    OpenAI,
+    /// Anthropic Messages
+    Anthropic,
+}
+
+impl FromStr for SourceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAI),
+            "anthropic" | "claude" => Ok(Self::Anthropic),
+            _ => Err(format!("Unknown source format: {s}")),
+        }
+    }
+}
+
+/// 上游 Provider（名称与 fixture 目录名一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetProvider {
+    Kiro,
+    Gemini,
+    OpenAI,
+    Claude,
+    Qwen,
+    Antigravity,
+}
+
+impl TargetProvider {
+    pub const ALL: [TargetProvider; 6] = [
+        Self::Kiro,
+        Self::Gemini,
+        Self::OpenAI,
+        Self::Claude,
+        Self::Qwen,
+        Self::Antigravity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Kiro => "kiro",
+            Self::Gemini => "gemini",
+            Self::OpenAI => "openai",
+            Self::Claude => "claude",
+            Self::Qwen => "qwen",
+            Self::Antigravity => "antigravity",
+        }
+    }
+}
+
+impl FromStr for TargetProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|provider| provider.as_str() == s.to_lowercase())
+            .ok_or_else(|| format!("Unknown target provider: {s}"))
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: &Value) -> Result<T, String> {
+    serde_json::from_value(value.clone()).map_err(|e| format!("Invalid request: {e}"))
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to serialize: {e}"))
+}
+
+/// 将客户端请求转换为目标 Provider 的上游请求体
+pub fn normalize(
+    source: SourceFormat,
+    target: TargetProvider,
+    request: &Value,
+) -> Result<Value, String> {
+    let openai_request = || -> Result<ChatCompletionRequest, String> {
+        match source {
+            SourceFormat::OpenAI => parse(request),
+            SourceFormat::Anthropic => {
+                let anthropic: AnthropicMessagesRequest = parse(request)?;
+                Ok(convert_anthropic_to_openai(&anthropic))
+            }
+        }
+    };
+
+    let mut body = match (target, source) {
+        (TargetProvider::OpenAI, _) => to_value(&openai_request()?)?,
+        (TargetProvider::Qwen, _) => QwenProvider::prepare_request(&to_value(&openai_request()?)?),
+        // Anthropic 请求原样发送给 Claude
+        (TargetProvider::Claude, SourceFormat::Anthropic) => {
+            to_value(&parse::<AnthropicMessagesRequest>(request)?)?
+        }
+        (TargetProvider::Claude, SourceFormat::OpenAI) => {
+            ClaudeCustomProvider::build_anthropic_body(&openai_request()?)
+        }
+        (TargetProvider::Kiro, SourceFormat::Anthropic) => {
+            to_value(&convert_anthropic_to_codewhisperer(&parse(request)?, None))?
+        }
+        (TargetProvider::Kiro, SourceFormat::OpenAI) => {
+            to_value(&convert_openai_to_codewhisperer(&openai_request()?, None))?
+        }
+        (TargetProvider::Antigravity, _) => {
+            convert_openai_to_antigravity_with_context(&openai_request()?, "")
+        }
+        // Gemini 原生请求体即 Antigravity 请求中的 request 字段（不含会话 ID）
+        (TargetProvider::Gemini, _) => {
+            let mut wrapped = convert_openai_to_antigravity_with_context(&openai_request()?, "");
+            let mut inner = wrapped
+                .get_mut("request")
+                .map(Value::take)
+                .unwrap_or_default();
+            if let Some(obj) = inner.as_object_mut() {
+                obj.remove("sessionId");
+            }
+            inner
+        }
+    };
+
+    mask_request(&mut body);
+    Ok(body)
+}
+
+/// 将目标 Provider 的上游响应转换为 OpenAI 格式
+///
+/// Kiro 的响应为 CodeWhisperer 流式事件数组，转换结果为 OpenAI chunk 数组。
+pub fn normalize_response(
+    target: TargetProvider,
+    response: &Value,
+    model: &str,
+) -> Result<Value, String> {
+    let mut body = match target {
+        TargetProvider::OpenAI | TargetProvider::Qwen => response.clone(),
+        TargetProvider::Claude => {
+            ClaudeCustomProvider::openai_response_from_anthropic(response, model)
+        }
+        TargetProvider::Gemini | TargetProvider::Antigravity => {
+            convert_antigravity_to_openai_response(response, model)
+        }
+        TargetProvider::Kiro => {
+            let events: Vec<CWStreamEvent> = parse(response)?;
+            let chunks: Vec<_> = events
+                .iter()
+                .filter_map(|event| convert_cw_event_to_openai_chunk(event, model, VOLATILE))
+                .collect();
+            to_value(&chunks)?
+        }
+    };
+
+    match &mut body {
+        Value::Array(chunks) => chunks.iter_mut().for_each(mask_response),
+        other => mask_response(other),
+    }
+    Ok(body)
+}
+
+fn mask_request(value: &mut Value) {
+    match value {
+        Value::Object(obj) => {
+            for (key, field) in obj.iter_mut() {
+                if VOLATILE_REQUEST_KEYS.contains(&key.as_str()) {
+                    *field = Value::String(VOLATILE.to_string());
+                } else {
+                    mask_request(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_request),
+        _ => {}
+    }
+}
+
+fn mask_response(value: &mut Value) {
+    if let Some(obj) = value.as_object_mut() {
+        for key in VOLATILE_RESPONSE_KEYS {
+            if let Some(field) = obj.get_mut(*key) {
+                *field = Value::String(VOLATILE.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!("OpenAI".parse(), Ok(SourceFormat::OpenAI));
+        assert_eq!("anthropic".parse(), Ok(SourceFormat::Anthropic));
+        for provider in TargetProvider::ALL {
+            assert_eq!(provider.as_str().parse(), Ok(provider));
+        }
+        assert!("bedrock".parse::<TargetProvider>().is_err());
+    }
+
+    #[test]
+    fn test_mask_volatile_fields() {
+        let mut request = json!({
+            "conversationState": {"conversationId": "abc", "history": [{"sessionId": "1"}]},
+            "id": "kept"
+        });
+        mask_request(&mut request);
+        assert_eq!(request["conversationState"]["conversationId"], VOLATILE);
+        assert_eq!(
+            request["conversationState"]["history"][0]["sessionId"],
+            VOLATILE
+        );
+        assert_eq!(request["id"], "kept");
+
+        // 只替换顶层字段，工具调用 ID 保留
+        let mut response = json!({"id": "chatcmpl-1", "created": 1, "choices": [{"id": "call_1"}]});
+        mask_response(&mut response);
+        assert_eq!(response["id"], VOLATILE);
+        assert_eq!(response["created"], VOLATILE);
+        assert_eq!(response["choices"][0]["id"], "call_1");
+    }
+
+    #[test]
+    fn test_invalid_request() {
+        let err = normalize(
+            SourceFormat::OpenAI,
+            TargetProvider::OpenAI,
+            &json!({"model": 1}),
+        )
+        .unwrap_err();
+        assert!(err.starts_with("Invalid request"));
+    }
+}
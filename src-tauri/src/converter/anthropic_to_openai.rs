@@ -1,4 +1,6 @@
 //! Anthropic 格式转换为 OpenAI 格式 (支持 Claude Code)
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
 use crate::models::anthropic::*;
 use crate::models::openai::*;
 use uuid::Uuid;
@@ -7,17 +9,25 @@ use uuid::Uuid;
 pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCompletionRequest {
     let mut openai_messages: Vec<ChatMessage> = Vec::new();
 
-    // 处理 system prompt
-    if let Some(system) = &request.system {
-        let system_text = extract_system_text(system);
-        if !system_text.is_empty() {
-            openai_messages.push(ChatMessage {
-                role: "system".to_string(),
-                content: Some(MessageContent::Text(system_text)),
-                tool_calls: None,
-                tool_call_id: None,
-            });
-        }
+    // 处理 system prompt：多个块时转为多个文本 part，保留块边界
+    let mut system_blocks = request.system_blocks();
+    let system_content = match system_blocks.len() {
+        0 => None,
+        1 => Some(MessageContent::Text(system_blocks.remove(0).text)),
+        _ => Some(MessageContent::Parts(
+            system_blocks
+                .into_iter()
+                .map(|b| ContentPart::Text { text: b.text })
+                .collect(),
+        )),
+    };
+    if let Some(content) = system_content {
+        openai_messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: Some(content),
+            tool_calls: None,
+            tool_call_id: None,
+        });
     }
 
     // 转换消息
@@ -40,36 +50,36 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
             .collect()
     });
 
+    // top_k 不属于 OpenAI 参数，单独保留供后续转换为 Gemini/Antigravity 时使用
+    let params = SamplingParams::from_anthropic(request);
+    let top_k = params.top_k;
+    let sampling = SamplingParams {
+        top_k: None,
+        ..params
+    }
+    .map_to(Protocol::OpenAI);
+
     ChatCompletionRequest {
         model: request.model.clone(),
         messages: openai_messages,
-        temperature: request.temperature,
+        temperature: sampling.temperature,
         max_tokens: request.max_tokens,
-        top_p: None,
+        top_p: sampling.top_p,
         stream: request.stream,
+        stream_options: None,
         tools,
         tool_choice: request.tool_choice.clone(),
         reasoning_effort: None,
-    }
-}
-
-fn extract_system_text(system: &serde_json::Value) -> String {
-    match system {
-        serde_json::Value::String(s) => s.clone(),
-        serde_json::Value::Array(arr) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
+        stop: sampling
+            .stop_sequences()
+            .map(|stop| serde_json::json!(stop)),
+        presence_penalty: None,
+        frequency_penalty: None,
+        top_k,
+        user: request.user_id().map(str::to_string),
+        n: None,
+        logprobs: None,
+        top_logprobs: None,
     }
 }
 
@@ -197,3 +207,139 @@ fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
         _ => String::new(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with_system(system: serde_json::Value) -> AnthropicMessagesRequest {
+        AnthropicMessagesRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: vec![AnthropicMessage {
+                role: "user".to_string(),
+                content: serde_json::json!("Hello"),
+            }],
+            max_tokens: Some(1024),
+            system: Some(system),
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            stop_sequences: None,
+            stream: false,
+            tools: None,
+            tool_choice: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_system_string() {
+        let request = convert_anthropic_to_openai(&request_with_system(serde_json::json!(
+            "You are helpful."
+        )));
+        assert_eq!(request.messages[0].role, "system");
+        assert!(matches!(
+            &request.messages[0].content,
+            Some(MessageContent::Text(t)) if t == "You are helpful."
+        ));
+    }
+
+    #[test]
+    fn test_system_blocks_preserved() {
+        let request = convert_anthropic_to_openai(&request_with_system(serde_json::json!([
+            {"type": "text", "text": "You are Claude Code."},
+            {"type": "text", "text": "Project rules...", "cache_control": {"type": "ephemeral"}},
+            {"type": "text", "text": ""}
+        ])));
+
+        let system = &request.messages[0];
+        assert_eq!(system.role, "system");
+        assert_eq!(
+            system.get_text_blocks(),
+            vec!["You are Claude Code.", "Project rules..."]
+        );
+        assert_eq!(request.messages[1].role, "user");
+    }
+
+    #[test]
+    fn test_parse_system_blocks_skips_non_text() {
+        let blocks = parse_system_blocks(Some(&serde_json::json!([
+            {"type": "text", "text": "a"},
+            {"type": "image", "source": {}},
+            {"type": "text", "text": "b", "cache_control": {"type": "ephemeral"}}
+        ])));
+        assert_eq!(
+            blocks,
+            vec![
+                AnthropicSystemBlock {
+                    text: "a".to_string()
+                },
+                AnthropicSystemBlock {
+                    text: "b".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sampling_params_mapped() {
+        let mut anthropic = request_with_system(serde_json::json!("sys"));
+        anthropic.temperature = Some(0.7);
+        anthropic.top_p = Some(0.9);
+        anthropic.top_k = Some(40);
+        anthropic.stop_sequences = Some(vec!["\n\nHuman:".to_string()]);
+
+        let request = convert_anthropic_to_openai(&anthropic);
+        assert_eq!(request.temperature, Some(0.7));
+        assert_eq!(request.top_p, Some(0.9));
+        assert_eq!(request.top_k, Some(40));
+        assert_eq!(request.stop, Some(serde_json::json!(["\n\nHuman:"])));
+    }
+
+    #[test]
+    fn test_server_tools() {
+        let mut anthropic = request_with_system(serde_json::json!("sys"));
+        anthropic.tools = serde_json::from_value(serde_json::json!([
+            {"type": "web_search_20250305", "name": "web_search", "max_uses": 5},
+            {"type": "computer_20250124", "name": "computer", "display_width_px": 1024},
+            {"name": "get_weather", "input_schema": {"type": "object"}}
+        ]))
+        .unwrap();
+
+        let families: Vec<_> = anthropic
+            .server_tools()
+            .iter()
+            .filter_map(|t| t.server_tool_family())
+            .collect();
+        assert_eq!(families, vec!["web_search", "computer"]);
+
+        // 服务端工具参数原样保留
+        let json = serde_json::to_value(&anthropic).unwrap();
+        assert_eq!(json["tools"][0]["max_uses"], 5);
+        assert_eq!(json["tools"][1]["type"], "computer_20250124");
+        assert!(json["tools"][2].get("type").is_none());
+
+        // 转换为 OpenAI 格式后是无参数定义的同名函数
+        let request = convert_anthropic_to_openai(&anthropic);
+        assert!(matches!(
+            &request.tools.as_ref().unwrap()[0],
+            Tool::Function { function } if function.name == "web_search" && function.parameters.is_none()
+        ));
+    }
+
+    #[test]
+    fn test_metadata_user_id() {
+        let mut anthropic = request_with_system(serde_json::json!("You are helpful."));
+        assert!(anthropic.user_id().is_none());
+        assert!(serde_json::to_value(&anthropic)
+            .unwrap()
+            .get("metadata")
+            .is_none());
+
+        anthropic.metadata = Some(serde_json::json!({"user_id": "user-42"}));
+        assert_eq!(anthropic.user_id(), Some("user-42"));
+        let request = convert_anthropic_to_openai(&anthropic);
+        assert_eq!(request.user.as_deref(), Some("user-42"));
+        assert_eq!(serde_json::to_value(&request).unwrap()["user"], "user-42");
+    }
+}
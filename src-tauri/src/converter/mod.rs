@@ -3,6 +3,7 @@ pub mod cw_to_openai;
 pub mod openai_to_antigravity;
 pub mod openai_to_cw;
 pub mod protocol_selector;
+pub mod sampling;
 
 #[allow(unused_imports)]
 pub use anthropic_to_openai::*;
@@ -8,14 +8,21 @@
 //! - 工具定义转换（parameters → parametersJsonSchema）
 //! - 安全设置自动附加
 //! - 思维链配置（reasoning_effort）
+//! - 联网搜索、代码执行工具映射为 Gemini 原生 `googleSearch` / `codeExecution`，
+//!   响应中的 groundingMetadata 转换为引用
 
 #![allow(dead_code)]
 //!
 //! ## 更新日志
 //! - 2025-12-28: 修复请求格式，对齐 CLIProxyAPI 实现
 
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
 use crate::models::openai::*;
-use crate::session::{get_thought_signature, SessionManager};
+use crate::session::{
+    get_thought_signature, get_tool_call_signature, store_tool_call_signature, SessionManager,
+};
+use crate::stream::StopReason;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -127,6 +134,10 @@ pub struct GeminiGenerationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_sequences: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub candidate_count: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking_config: Option<ThinkingConfig>,
@@ -336,19 +347,28 @@ pub fn convert_openai_to_antigravity_with_context(
         match msg.role.as_str() {
             "system" => {
                 // system 消息只有在有其他消息时才作为 systemInstruction
+                // 多个 system 消息 / 文本块依次追加为独立 part，保留块边界
                 if messages_len > 1 {
-                    let text = msg.get_content_text();
-                    if !text.is_empty() {
-                        system_instruction = Some(GeminiContent {
-                            role: "user".to_string(),
-                            parts: vec![GeminiPart {
-                                text: Some(text),
-                                inline_data: None,
-                                function_call: None,
-                                function_response: None,
-                                thought_signature: None,
-                            }],
-                        });
+                    let parts: Vec<GeminiPart> = msg
+                        .get_text_blocks()
+                        .into_iter()
+                        .filter(|text| !text.is_empty())
+                        .map(|text| GeminiPart {
+                            text: Some(text),
+                            inline_data: None,
+                            function_call: None,
+                            function_response: None,
+                            thought_signature: None,
+                        })
+                        .collect();
+                    if !parts.is_empty() {
+                        system_instruction
+                            .get_or_insert_with(|| GeminiContent {
+                                role: "user".to_string(),
+                                parts: Vec::new(),
+                            })
+                            .parts
+                            .extend(parts);
                     }
                 } else {
                     // 只有 system 消息时，作为 user 消息
@@ -411,13 +431,17 @@ pub fn convert_openai_to_antigravity_with_context(
 
                     // 获取全局存储的 thoughtSignature（如果有）
                     let global_sig = get_thought_signature();
-                    let thought_sig = global_sig.unwrap_or_else(|| {
-                        // 如果没有缓存的签名，使用跳过验证的标记
-                        // 注意：Vertex AI 不接受此标记，但 Cloud Code API 接受
-                        GEMINI_CLI_FUNCTION_THOUGHT_SIGNATURE.to_string()
-                    });
 
                     for tc in tool_calls {
+                        // 优先使用该工具调用对应的签名
+                        let thought_sig = get_tool_call_signature(&tc.id)
+                            .or_else(|| global_sig.clone())
+                            .unwrap_or_else(|| {
+                                // 如果没有缓存的签名，使用跳过验证的标记
+                                // 注意：Vertex AI 不接受此标记，但 Cloud Code API 接受
+                                GEMINI_CLI_FUNCTION_THOUGHT_SIGNATURE.to_string()
+                            });
+
                         let args: serde_json::Value = serde_json::from_str(&tc.function.arguments)
                             .unwrap_or(serde_json::json!({}));
 
@@ -430,7 +454,7 @@ pub fn convert_openai_to_antigravity_with_context(
                                 args, // 直接使用 args，不要包装
                             }),
                             function_response: None,
-                            thought_signature: Some(thought_sig.clone()),
+                            thought_signature: Some(thought_sig),
                         });
 
                         function_ids.push(tc.id.clone());
@@ -556,13 +580,16 @@ pub fn convert_openai_to_antigravity_with_context(
         }
     }
 
-    // 构建生成配置
+    // 构建生成配置（采样参数按 Gemini 规则映射）
+    let sampling = SamplingParams::from_openai(request).map_to(Protocol::Antigravity);
     let mut generation_config = GeminiGenerationConfig {
-        temperature: request.temperature,
+        temperature: sampling.temperature,
         max_output_tokens: request.max_tokens.map(|t| t as i32),
-        top_p: request.top_p,
-        top_k: None,
-        stop_sequences: None,
+        top_p: sampling.top_p,
+        top_k: sampling.top_k.map(|k| k as i32),
+        stop_sequences: sampling.stop_sequences(),
+        presence_penalty: sampling.presence_penalty,
+        frequency_penalty: sampling.frequency_penalty,
         candidate_count: None,
         thinking_config: None,
         response_modalities: None,
@@ -623,11 +650,19 @@ pub fn convert_openai_to_antigravity_with_context(
     // 转换工具定义
     // 注意：Antigravity API 统一使用 functionDeclarations 格式
     // Claude 和 Gemini 模型都使用相同的结构，但字段名可能不同
+    let mut builtin_tools: Vec<GeminiBuiltinTool> = Vec::new();
     let tools: Option<serde_json::Value> = request.tools.as_ref().and_then(|tools| {
         let is_claude = is_claude_model(actual_model);
         let mut function_declarations: Vec<serde_json::Value> = Vec::new();
 
         for t in tools {
+            // Gemini 模型使用原生联网搜索、代码执行工具
+            if let Some(builtin) = gemini_builtin_tool(t).filter(|_| !is_claude) {
+                if !builtin_tools.contains(&builtin) {
+                    builtin_tools.push(builtin);
+                }
+                continue;
+            }
             match t {
                 Tool::Function { function } => {
                     let params_schema = function
@@ -662,23 +697,34 @@ pub fn convert_openai_to_antigravity_with_context(
                         }));
                     }
                 }
-                Tool::WebSearch | Tool::WebSearch20250305 => {
-                    // web_search 工具不转换
+                Tool::WebSearch | Tool::WebSearch20250305 | Tool::CodeExecution => {
+                    // Claude 模型不支持内置工具，不转换
                 }
             }
         }
 
-        if function_declarations.is_empty() {
+        let has_functions = !function_declarations.is_empty();
+        let mut gemini_tools: Vec<serde_json::Value> = Vec::new();
+        if has_functions {
+            gemini_tools.push(serde_json::json!({
+                "functionDeclarations": function_declarations
+            }));
+        }
+        gemini_tools.extend(builtin_tools.iter().map(|tool| tool.to_json()));
+
+        if gemini_tools.is_empty() {
             None
         } else {
-            Some(serde_json::json!([{
-                "functionDeclarations": function_declarations
-            }]))
+            Some(serde_json::Value::Array(gemini_tools))
         }
     });
+    let has_function_declarations = tools.as_ref().and_then(|t| t.as_array()).is_some_and(|t| {
+        t.iter()
+            .any(|tool| tool.get("functionDeclarations").is_some())
+    });
 
-    // 构建 toolConfig（如果有工具定义）
-    let tool_config: Option<serde_json::Value> = if tools.is_some() {
+    // 构建 toolConfig（仅函数调用需要）
+    let tool_config: Option<serde_json::Value> = if has_function_declarations {
         Some(serde_json::json!({
             "functionCallingConfig": {
                 "mode": "AUTO"
@@ -708,6 +754,8 @@ pub fn convert_openai_to_antigravity_with_context(
     // - "image_gen": 图片生成请求
     let request_type = if is_image_generation_model(actual_model) {
         "image_gen"
+    } else if builtin_tools.contains(&GeminiBuiltinTool::GoogleSearch) {
+        "web_search"
     } else {
         "agent"
     };
@@ -735,6 +783,170 @@ pub fn convert_openai_to_antigravity_with_context(
 // 辅助转换函数
 // ============================================================================
 
+/// Gemini 原生内置工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GeminiBuiltinTool {
+    GoogleSearch,
+    CodeExecution,
+}
+
+impl GeminiBuiltinTool {
+    fn to_json(self) -> serde_json::Value {
+        match self {
+            GeminiBuiltinTool::GoogleSearch => serde_json::json!({"googleSearch": {}}),
+            GeminiBuiltinTool::CodeExecution => serde_json::json!({"codeExecution": {}}),
+        }
+    }
+}
+
+/// 客户端请求的联网搜索、代码执行工具对应的 Gemini 原生工具
+///
+/// Anthropic 服务端工具（`web_search_20250305`、`code_execution_20250522`）转换为 OpenAI 格式后
+/// 是没有参数定义的同名函数，按名称识别。
+fn gemini_builtin_tool(tool: &Tool) -> Option<GeminiBuiltinTool> {
+    match tool {
+        Tool::WebSearch | Tool::WebSearch20250305 => Some(GeminiBuiltinTool::GoogleSearch),
+        Tool::CodeExecution => Some(GeminiBuiltinTool::CodeExecution),
+        Tool::Function { function } if function.parameters.is_none() => {
+            match function.name.as_str() {
+                "web_search" | "web_search_20250305" => Some(GeminiBuiltinTool::GoogleSearch),
+                "code_execution" => Some(GeminiBuiltinTool::CodeExecution),
+                _ => None,
+            }
+        }
+        Tool::Function { .. } => None,
+    }
+}
+
+/// Gemini 代码执行结果转换为 Markdown 文本
+fn code_execution_text(part: &serde_json::Value) -> Option<String> {
+    if let Some(code) = part.get("executableCode") {
+        let language = code
+            .get("language")
+            .and_then(|l| l.as_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let source = code.get("code").and_then(|c| c.as_str()).unwrap_or("");
+        return Some(format!("```{}\n{}\n```", language, source.trim_end()));
+    }
+    let result = part.get("codeExecutionResult")?;
+    let output = result.get("output").and_then(|o| o.as_str()).unwrap_or("");
+    Some(format!("```\n{}\n```", output.trim_end()))
+}
+
+/// 将 groundingMetadata 转换为 OpenAI `url_citation` 注释
+///
+/// Gemini 的 segment 下标按 UTF-8 字节计算，转换为字符下标；
+/// 没有 groundingSupports 时每个来源引用整段文本。
+fn grounding_annotations(metadata: &serde_json::Value, content: &str) -> Vec<serde_json::Value> {
+    let chunks: Vec<(String, String)> = metadata
+        .get("groundingChunks")
+        .and_then(|c| c.as_array())
+        .map(|chunks| {
+            chunks
+                .iter()
+                .map(|chunk| {
+                    let web = chunk.get("web").unwrap_or(chunk);
+                    let field = |name: &str| {
+                        web.get(name)
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    (field("uri"), field("title"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if chunks.is_empty() {
+        return Vec::new();
+    }
+
+    let char_index = |byte_index: u64| -> usize {
+        let mut end = (byte_index as usize).min(content.len());
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        content[..end].chars().count()
+    };
+    let annotation = |chunk: &(String, String), start: usize, end: usize| {
+        serde_json::json!({
+            "type": "url_citation",
+            "url_citation": {
+                "url": chunk.0,
+                "title": chunk.1,
+                "start_index": start,
+                "end_index": end
+            }
+        })
+    };
+
+    let supports = metadata
+        .get("groundingSupports")
+        .and_then(|s| s.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if supports.is_empty() {
+        let end = content.chars().count();
+        return chunks
+            .iter()
+            .map(|chunk| annotation(chunk, 0, end))
+            .collect();
+    }
+
+    let mut annotations = Vec::new();
+    for support in &supports {
+        let segment = support.get("segment");
+        let index = |name: &str| {
+            segment
+                .and_then(|s| s.get(name))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        };
+        let (start, end) = (
+            char_index(index("startIndex")),
+            char_index(index("endIndex")),
+        );
+        let chunk_indices = support
+            .get("groundingChunkIndices")
+            .and_then(|i| i.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|i| i.as_u64());
+        for i in chunk_indices {
+            if let Some(chunk) = chunks.get(i as usize) {
+                annotations.push(annotation(chunk, start, end));
+            }
+        }
+    }
+    annotations
+}
+
+/// 提取 Antigravity 响应的文本，附加引用来源列表
+///
+/// 用于只能输出纯文本的响应格式（如 Anthropic 格式）。
+pub fn antigravity_text_with_citations(antigravity_resp: &serde_json::Value) -> String {
+    let response = convert_antigravity_to_openai_response(antigravity_resp, "");
+    let message = &response["choices"][0]["message"];
+    let mut text = message["content"].as_str().unwrap_or_default().to_string();
+
+    let mut sources: Vec<(&str, &str)> = Vec::new();
+    for annotation in message["annotations"].as_array().into_iter().flatten() {
+        let citation = &annotation["url_citation"];
+        let url = citation["url"].as_str().unwrap_or_default();
+        if !url.is_empty() && !sources.iter().any(|(u, _)| *u == url) {
+            sources.push((url, citation["title"].as_str().unwrap_or(url)));
+        }
+    }
+    if !sources.is_empty() {
+        text.push_str("\n\nSources:");
+        for (i, (url, title)) in sources.iter().enumerate() {
+            text.push_str(&format!("\n{}. [{}]({})", i + 1, title, url));
+        }
+    }
+    text
+}
+
 /// 清理参数中不需要的字段
 fn clean_parameters(params: Option<serde_json::Value>) -> Option<serde_json::Value> {
     params.map(clean_value)
@@ -891,7 +1103,9 @@ pub fn convert_antigravity_to_openai_response(
 
                     let has_content = part.get("text").is_some()
                         || part.get("functionCall").is_some()
-                        || part.get("inlineData").is_some();
+                        || part.get("inlineData").is_some()
+                        || part.get("executableCode").is_some()
+                        || part.get("codeExecutionResult").is_some();
 
                     if has_thought_signature && !has_content {
                         continue;
@@ -910,6 +1124,15 @@ pub fn convert_antigravity_to_openai_response(
                         }
                     }
 
+                    // 代码执行：生成的代码和执行结果以代码块输出
+                    if let Some(block) = code_execution_text(part) {
+                        if !content.is_empty() && !content.ends_with('\n') {
+                            content.push('\n');
+                        }
+                        content.push_str(&block);
+                        content.push('\n');
+                    }
+
                     if let Some(fc) = part.get("functionCall") {
                         // 优先使用响应中的 id，否则生成新的
                         let call_id = fc
@@ -920,6 +1143,15 @@ pub fn convert_antigravity_to_openai_response(
                                 format!("call_{}", &uuid::Uuid::new_v4().to_string()[..8])
                             });
 
+                        // 缓存该工具调用的签名，客户端回传工具结果时注入
+                        if let Some(sig) = part
+                            .get("thoughtSignature")
+                            .or_else(|| part.get("thought_signature"))
+                            .and_then(|s| s.as_str())
+                        {
+                            store_tool_call_signature(&call_id, sig);
+                        }
+
                         let default_args = serde_json::json!({});
                         let args = fc.get("args").unwrap_or(&default_args);
                         let args_str = if args.is_string() {
@@ -960,27 +1192,30 @@ pub fn convert_antigravity_to_openai_response(
                 }
             }
 
+            // Gemini 发起函数调用时 finishReason 仍为 STOP
             let finish_reason = candidate
                 .get("finishReason")
                 .and_then(|r| r.as_str())
-                .map(|r| match r.to_uppercase().as_str() {
-                    "STOP" => "stop",
-                    "MAX_TOKENS" => "length",
-                    "SAFETY" => "content_filter",
-                    "RECITATION" => "content_filter",
-                    _ => "stop",
-                })
-                .unwrap_or(if !tool_calls.is_empty() {
-                    "tool_calls"
-                } else {
-                    "stop"
-                });
+                .map(StopReason::from_gemini_str)
+                .unwrap_or_default()
+                .with_tool_calls(!tool_calls.is_empty())
+                .to_openai_str();
+
+            // 联网搜索引用
+            let annotations = candidate
+                .get("groundingMetadata")
+                .map(|metadata| grounding_annotations(metadata, &content))
+                .unwrap_or_default();
 
             let mut message = serde_json::json!({
                 "role": "assistant",
                 "content": if content.is_empty() { serde_json::Value::Null } else { serde_json::Value::String(content) }
             });
 
+            if !annotations.is_empty() {
+                message["annotations"] = serde_json::json!(annotations);
+            }
+
             if let Some(ref rc) = reasoning_content {
                 message["reasoning_content"] = serde_json::Value::String(rc.clone());
             }
@@ -1388,6 +1623,112 @@ mod image_tests {
         assert_eq!(result.data.len(), 1);
         assert_eq!(result.data[0].b64_json, Some("testdata".to_string()));
     }
+
+    #[test]
+    fn test_builtin_tools_mapped_to_gemini_native_tools() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "tools": [
+                {"type": "function", "function": {"name": "read_file", "parameters": {"type": "object", "properties": {}}}},
+                {"type": "function", "function": {"name": "web_search"}},
+                {"type": "web_search_20250305"},
+                {"type": "code_interpreter"}
+            ]
+        }))
+        .unwrap();
+
+        let result = convert_openai_to_antigravity(&request);
+        let tools = result["request"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 3);
+        assert_eq!(
+            tools[0]["functionDeclarations"][0]["name"],
+            serde_json::json!("read_file")
+        );
+        assert_eq!(tools[1], serde_json::json!({"googleSearch": {}}));
+        assert_eq!(tools[2], serde_json::json!({"codeExecution": {}}));
+        assert_eq!(result["requestType"], "web_search");
+        assert!(result["request"]["toolConfig"].is_object());
+
+        // Claude 模型不使用 Gemini 内置工具
+        let mut claude_request = request.clone();
+        claude_request.model = "claude-sonnet-4-5".to_string();
+        let result = convert_openai_to_antigravity(&claude_request);
+        let tools = result["request"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(result["requestType"], "agent");
+    }
+
+    #[test]
+    fn test_grounding_and_code_execution_response() {
+        let resp = serde_json::json!({
+            "response": {
+                "candidates": [{
+                    "content": {"parts": [
+                        {"text": "Rust 1.0 发布于 2015 年。"},
+                        {"executableCode": {"language": "PYTHON", "code": "print(2025 - 2015)"}},
+                        {"codeExecutionResult": {"outcome": "OUTCOME_OK", "output": "10\n"}}
+                    ]},
+                    "finishReason": "STOP",
+                    "groundingMetadata": {
+                        "webSearchQueries": ["rust 1.0 release"],
+                        "groundingChunks": [
+                            {"web": {"uri": "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html", "title": "rust-lang.org"}}
+                        ],
+                        "groundingSupports": [{
+                            "segment": {"startIndex": 0, "endIndex": 30, "text": "Rust 1.0 发布于 2015 年。"},
+                            "groundingChunkIndices": [0]
+                        }]
+                    }
+                }]
+            }
+        });
+
+        let result = convert_antigravity_to_openai_response(&resp, "gemini-2.5-flash");
+        let message = &result["choices"][0]["message"];
+        assert_eq!(
+            message["content"],
+            "Rust 1.0 发布于 2015 年。\n```python\nprint(2025 - 2015)\n```\n```\n10\n```\n"
+        );
+        let citation = &message["annotations"][0];
+        assert_eq!(citation["type"], "url_citation");
+        assert_eq!(
+            citation["url_citation"]["url"],
+            "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html"
+        );
+        // 字节下标 30 对应字符下标 20（“发布于”“年。”为多字节字符）
+        assert_eq!(citation["url_citation"]["start_index"], 0);
+        assert_eq!(citation["url_citation"]["end_index"], 20);
+
+        let text = antigravity_text_with_citations(&resp);
+        assert!(text.ends_with(
+            "\n\nSources:\n1. [rust-lang.org](https://blog.rust-lang.org/2015/05/15/Rust-1.0.html)"
+        ));
+    }
+
+    #[test]
+    fn test_finish_reason_mapping() {
+        let cases = [
+            ("STOP", false, "stop"),
+            ("STOP", true, "tool_calls"),
+            ("MAX_TOKENS", true, "length"),
+            ("SAFETY", false, "content_filter"),
+            ("PROHIBITED_CONTENT", false, "content_filter"),
+            ("MALFORMED_FUNCTION_CALL", false, "stop"),
+        ];
+        for (gemini, with_tool, expected) in cases {
+            let mut parts = vec![serde_json::json!({"text": "hi"})];
+            if with_tool {
+                parts
+                    .push(serde_json::json!({"functionCall": {"name": "get_weather", "args": {}}}));
+            }
+            let resp = serde_json::json!({
+                "candidates": [{"content": {"parts": parts}, "finishReason": gemini}]
+            });
+            let result = convert_antigravity_to_openai_response(&resp, "gemini-2.5-flash");
+            assert_eq!(result["choices"][0]["finish_reason"], expected, "{gemini}");
+        }
+    }
 }
 
 // ============================================================================
@@ -1676,4 +2017,25 @@ mod image_property_tests {
             }
         }
     }
+    #[test]
+    fn test_sampling_params_mapped_to_generation_config() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "temperature": 0.5,
+            "top_k": 20,
+            "stop": "END",
+            "presence_penalty": 0.3,
+            "frequency_penalty": -3.0
+        }))
+        .unwrap();
+
+        let result = convert_openai_to_antigravity(&request);
+        let gen_config = &result["request"]["generationConfig"];
+        assert_eq!(gen_config["temperature"], 0.5);
+        assert_eq!(gen_config["topK"], 20);
+        assert_eq!(gen_config["stopSequences"], serde_json::json!(["END"]));
+        assert!((gen_config["presencePenalty"].as_f64().unwrap() - 0.3).abs() < 1e-6);
+        assert_eq!(gen_config["frequencyPenalty"], -2.0);
+    }
 }
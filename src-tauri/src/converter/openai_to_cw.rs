@@ -367,6 +367,10 @@ pub fn convert_openai_to_codewhisperer(
                         tool_type: "web_search".to_string(),
                     }));
                 }
+                // 代码执行工具 CodeWhisperer 不支持
+                Tool::CodeExecution => {
+                    tracing::warn!("[CW_TOOLS] 不支持 code_execution 工具，已忽略");
+                }
             }
         }
 
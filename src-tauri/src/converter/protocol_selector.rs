@@ -11,6 +11,8 @@ use crate::models::provider_pool_model::PoolProviderType;
 pub enum Protocol {
     /// OpenAI Chat Completions API
     OpenAI,
+    /// Codex Responses API
+    Codex,
     /// Anthropic Messages API (Claude)
     Anthropic,
     /// CodeWhisperer API (Kiro)
@@ -25,6 +27,7 @@ impl Protocol {
     pub fn as_str(&self) -> &'static str {
         match self {
             Protocol::OpenAI => "openai",
+            Protocol::Codex => "codex",
             Protocol::Anthropic => "anthropic",
             Protocol::CodeWhisperer => "codewhisperer",
             Protocol::Gemini => "gemini",
@@ -61,7 +64,7 @@ impl ProtocolSelector {
             PoolProviderType::Antigravity => Protocol::Antigravity,
             PoolProviderType::Vertex => Protocol::Gemini, // Vertex AI uses Gemini protocol
             PoolProviderType::GeminiApiKey => Protocol::Gemini, // Gemini API Key uses Gemini protocol
-            PoolProviderType::Codex => Protocol::OpenAI,        // Codex uses OpenAI protocol
+            PoolProviderType::Codex => Protocol::Codex,         // Codex uses OpenAI Responses API
             PoolProviderType::ClaudeOAuth => Protocol::Anthropic, // Claude OAuth uses Anthropic protocol
             PoolProviderType::IFlow => Protocol::OpenAI,          // iFlow uses OpenAI protocol
             // API Key Provider 类型
@@ -69,6 +72,9 @@ impl ProtocolSelector {
             PoolProviderType::AzureOpenai => Protocol::OpenAI,
             PoolProviderType::AwsBedrock => Protocol::Anthropic,
             PoolProviderType::Ollama => Protocol::OpenAI,
+            PoolProviderType::OpenRouter => Protocol::OpenAI,
+            PoolProviderType::Mistral => Protocol::OpenAI,
+            PoolProviderType::DeepSeek => Protocol::OpenAI,
         }
     }
 
@@ -107,6 +113,10 @@ impl ProtocolSelector {
             (Protocol::OpenAI, Protocol::Anthropic) => 3,
             (Protocol::Anthropic, Protocol::OpenAI) => 3,
 
+            // OpenAI <-> Codex: 低复杂度（Responses API 与 Chat Completions 字段相近）
+            (Protocol::OpenAI, Protocol::Codex) => 2,
+            (Protocol::Codex, Protocol::OpenAI) => 2,
+
             // OpenAI <-> CodeWhisperer: 较高复杂度（需要处理历史格式）
             (Protocol::OpenAI, Protocol::CodeWhisperer) => 5,
             (Protocol::CodeWhisperer, Protocol::OpenAI) => 5,
@@ -142,6 +152,8 @@ impl ProtocolSelector {
             (source, target),
             (Protocol::OpenAI, Protocol::Anthropic)
                 | (Protocol::Anthropic, Protocol::OpenAI)
+                | (Protocol::OpenAI, Protocol::Codex)
+                | (Protocol::Codex, Protocol::OpenAI)
                 | (Protocol::OpenAI, Protocol::CodeWhisperer)
                 | (Protocol::CodeWhisperer, Protocol::OpenAI)
                 | (Protocol::OpenAI, Protocol::Gemini)
@@ -0,0 +1,349 @@
+//! 采样参数映射矩阵
+//!
+//! 跨格式转换时按目标协议映射采样参数：
+//!
+//! | 参数 | OpenAI | Codex | Anthropic | Gemini / Antigravity | CodeWhisperer |
+//! |------|--------|-------|-----------|----------------------|---------------|
+//! | temperature | 0–2 | 0–2 | 0–1 | 0–2 | 不支持 |
+//! | top_p | 0–1 | 0–1 | 0–1 | 0–1 | 不支持 |
+//! | top_k | 不支持 | 不支持 | ≥1 | ≥1 | 不支持 |
+//! | stop | `stop`，最多 4 个 | 不支持 | `stop_sequences` | `stopSequences`，最多 5 个 | 不支持 |
+//! | presence_penalty | -2–2 | 不支持 | 不支持 | -2–2 | 不支持 |
+//! | frequency_penalty | -2–2 | 不支持 | 不支持 | -2–2 | 不支持 |
+//!
+//! 超出范围的值钳制到边界，超出数量的停止序列截断，
+//! 目标协议不支持的参数丢弃；以上情况均记录警告日志。
+
+use crate::converter::protocol_selector::Protocol;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+
+/// OpenAI 停止序列上限
+const OPENAI_MAX_STOP_SEQUENCES: usize = 4;
+/// Gemini 停止序列上限
+const GEMINI_MAX_STOP_SEQUENCES: usize = 5;
+
+/// 与协议无关的采样参数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SamplingParams {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub stop: Vec<String>,
+    pub presence_penalty: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+}
+
+impl SamplingParams {
+    /// 从 OpenAI 请求提取
+    pub fn from_openai(request: &ChatCompletionRequest) -> Self {
+        Self {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            top_k: request.top_k,
+            stop: parse_stop(request.stop.as_ref()),
+            presence_penalty: request.presence_penalty,
+            frequency_penalty: request.frequency_penalty,
+        }
+    }
+
+    /// 从 OpenAI 风格的 JSON 请求体提取（Chat Completions 与 Responses 字段名相同）
+    pub fn from_openai_value(request: &serde_json::Value) -> Self {
+        let float = |key: &str| request.get(key).and_then(|v| v.as_f64()).map(|v| v as f32);
+        Self {
+            temperature: float("temperature"),
+            top_p: float("top_p"),
+            top_k: request
+                .get("top_k")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32),
+            stop: parse_stop(request.get("stop")),
+            presence_penalty: float("presence_penalty"),
+            frequency_penalty: float("frequency_penalty"),
+        }
+    }
+
+    /// 从 Anthropic 请求提取
+    pub fn from_anthropic(request: &AnthropicMessagesRequest) -> Self {
+        Self {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            top_k: request.top_k,
+            stop: request.stop_sequences.clone().unwrap_or_default(),
+            presence_penalty: None,
+            frequency_penalty: None,
+        }
+    }
+
+    /// 按目标协议映射：钳制取值范围，丢弃不支持的参数
+    pub fn map_to(self, target: Protocol) -> Self {
+        let name = target.as_str();
+        match target {
+            Protocol::OpenAI => {
+                drop_unsupported(name, "top_k", self.top_k.is_some());
+                Self {
+                    temperature: clamp(name, "temperature", self.temperature, 0.0, 2.0),
+                    top_p: clamp(name, "top_p", self.top_p, 0.0, 1.0),
+                    top_k: None,
+                    stop: truncate_stop(name, self.stop, OPENAI_MAX_STOP_SEQUENCES),
+                    presence_penalty: clamp(
+                        name,
+                        "presence_penalty",
+                        self.presence_penalty,
+                        -2.0,
+                        2.0,
+                    ),
+                    frequency_penalty: clamp(
+                        name,
+                        "frequency_penalty",
+                        self.frequency_penalty,
+                        -2.0,
+                        2.0,
+                    ),
+                }
+            }
+            Protocol::Codex => {
+                drop_unsupported(name, "top_k", self.top_k.is_some());
+                drop_unsupported(name, "stop", !self.stop.is_empty());
+                drop_unsupported(name, "presence_penalty", self.presence_penalty.is_some());
+                drop_unsupported(name, "frequency_penalty", self.frequency_penalty.is_some());
+                Self {
+                    temperature: clamp(name, "temperature", self.temperature, 0.0, 2.0),
+                    top_p: clamp(name, "top_p", self.top_p, 0.0, 1.0),
+                    ..Self::default()
+                }
+            }
+            Protocol::Anthropic => {
+                drop_unsupported(name, "presence_penalty", self.presence_penalty.is_some());
+                drop_unsupported(name, "frequency_penalty", self.frequency_penalty.is_some());
+                Self {
+                    temperature: clamp(name, "temperature", self.temperature, 0.0, 1.0),
+                    top_p: clamp(name, "top_p", self.top_p, 0.0, 1.0),
+                    top_k: clamp_top_k(name, self.top_k),
+                    stop: self.stop,
+                    presence_penalty: None,
+                    frequency_penalty: None,
+                }
+            }
+            Protocol::Gemini | Protocol::Antigravity => Self {
+                temperature: clamp(name, "temperature", self.temperature, 0.0, 2.0),
+                top_p: clamp(name, "top_p", self.top_p, 0.0, 1.0),
+                top_k: clamp_top_k(name, self.top_k),
+                stop: truncate_stop(name, self.stop, GEMINI_MAX_STOP_SEQUENCES),
+                presence_penalty: clamp(name, "presence_penalty", self.presence_penalty, -2.0, 2.0),
+                frequency_penalty: clamp(
+                    name,
+                    "frequency_penalty",
+                    self.frequency_penalty,
+                    -2.0,
+                    2.0,
+                ),
+            },
+            Protocol::CodeWhisperer => {
+                drop_unsupported(name, "temperature", self.temperature.is_some());
+                drop_unsupported(name, "top_p", self.top_p.is_some());
+                drop_unsupported(name, "top_k", self.top_k.is_some());
+                drop_unsupported(name, "stop", !self.stop.is_empty());
+                drop_unsupported(name, "presence_penalty", self.presence_penalty.is_some());
+                drop_unsupported(name, "frequency_penalty", self.frequency_penalty.is_some());
+                Self::default()
+            }
+        }
+    }
+
+    /// 停止序列（为空时返回 None）
+    pub fn stop_sequences(&self) -> Option<Vec<String>> {
+        (!self.stop.is_empty()).then(|| self.stop.clone())
+    }
+
+    /// 写回 OpenAI 风格的 JSON 请求体，已丢弃的参数从请求体中移除
+    ///
+    /// `top_k` 不是 OpenAI 字段，不写入。
+    pub fn write_openai_fields(&self, request: &mut serde_json::Value) {
+        let Some(obj) = request.as_object_mut() else {
+            return;
+        };
+        let fields = [
+            ("temperature", self.temperature.map(f32_to_json)),
+            ("top_p", self.top_p.map(f32_to_json)),
+            ("stop", self.stop_sequences().map(serde_json::Value::from)),
+            ("presence_penalty", self.presence_penalty.map(f32_to_json)),
+            ("frequency_penalty", self.frequency_penalty.map(f32_to_json)),
+        ];
+        for (key, value) in fields {
+            match value {
+                Some(value) => {
+                    obj.insert(key.to_string(), value);
+                }
+                None => {
+                    obj.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// f32 转为 JSON 数字，按最短十进制表示转换，避免 0.7 变成 0.699999988079071
+fn f32_to_json(value: f32) -> serde_json::Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map(serde_json::Value::from)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// 解析 OpenAI `stop` 参数（字符串或字符串数组）
+pub fn parse_stop(stop: Option<&serde_json::Value>) -> Vec<String> {
+    match stop {
+        Some(serde_json::Value::String(s)) if !s.is_empty() => vec![s.clone()],
+        Some(serde_json::Value::Array(arr)) => arr
+            .iter()
+            .filter_map(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn clamp(target: &str, param: &str, value: Option<f32>, min: f32, max: f32) -> Option<f32> {
+    let value = value?;
+    if value.is_nan() {
+        tracing::warn!("[SAMPLING] {} 参数 {} 不是有效数值，已忽略", target, param);
+        return None;
+    }
+    let clamped = value.clamp(min, max);
+    if clamped != value {
+        tracing::warn!(
+            "[SAMPLING] {} 参数 {}={} 超出范围 [{}, {}]，已钳制为 {}",
+            target,
+            param,
+            value,
+            min,
+            max,
+            clamped
+        );
+    }
+    Some(clamped)
+}
+
+fn clamp_top_k(target: &str, top_k: Option<u32>) -> Option<u32> {
+    match top_k {
+        Some(0) => {
+            tracing::warn!("[SAMPLING] {} 参数 top_k=0 无效，已钳制为 1", target);
+            Some(1)
+        }
+        other => other,
+    }
+}
+
+fn truncate_stop(target: &str, mut stop: Vec<String>, max: usize) -> Vec<String> {
+    if stop.len() > max {
+        tracing::warn!(
+            "[SAMPLING] {} 最多支持 {} 个停止序列，已丢弃多余的 {} 个",
+            target,
+            max,
+            stop.len() - max
+        );
+        stop.truncate(max);
+    }
+    stop
+}
+
+fn drop_unsupported(target: &str, param: &str, present: bool) {
+    if present {
+        tracing::warn!("[SAMPLING] {} 不支持参数 {}，已忽略", target, param);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> SamplingParams {
+        SamplingParams {
+            temperature: Some(1.5),
+            top_p: Some(0.9),
+            top_k: Some(40),
+            stop: vec!["a", "b", "c", "d", "e", "f"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            presence_penalty: Some(0.5),
+            frequency_penalty: Some(3.0),
+        }
+    }
+
+    #[test]
+    fn test_map_to_anthropic() {
+        let mapped = params().map_to(Protocol::Anthropic);
+        assert_eq!(mapped.temperature, Some(1.0));
+        assert_eq!(mapped.top_k, Some(40));
+        assert_eq!(mapped.stop.len(), 6);
+        assert_eq!(mapped.presence_penalty, None);
+        assert_eq!(mapped.frequency_penalty, None);
+    }
+
+    #[test]
+    fn test_map_to_gemini() {
+        let mapped = params().map_to(Protocol::Antigravity);
+        assert_eq!(mapped.temperature, Some(1.5));
+        assert_eq!(mapped.stop.len(), GEMINI_MAX_STOP_SEQUENCES);
+        assert_eq!(mapped.presence_penalty, Some(0.5));
+        assert_eq!(mapped.frequency_penalty, Some(2.0));
+    }
+
+    #[test]
+    fn test_map_to_openai() {
+        let mapped = params().map_to(Protocol::OpenAI);
+        assert_eq!(mapped.stop.len(), OPENAI_MAX_STOP_SEQUENCES);
+        assert_eq!(mapped.top_k, None);
+    }
+
+    #[test]
+    fn test_map_to_codex() {
+        let mapped = params().map_to(Protocol::Codex);
+        assert_eq!(
+            mapped,
+            SamplingParams {
+                temperature: Some(1.5),
+                top_p: Some(0.9),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_openai_value_round_trip() {
+        let mut body = serde_json::json!({
+            "temperature": 3.0,
+            "top_p": 0.7,
+            "stop": "END",
+            "presence_penalty": 1.0
+        });
+        let mapped = SamplingParams::from_openai_value(&body).map_to(Protocol::Codex);
+        mapped.write_openai_fields(&mut body);
+        assert_eq!(body, serde_json::json!({"temperature": 2.0, "top_p": 0.7}));
+    }
+
+    #[test]
+    fn test_map_to_codewhisperer() {
+        assert_eq!(
+            params().map_to(Protocol::CodeWhisperer),
+            SamplingParams::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_stop() {
+        assert_eq!(
+            parse_stop(Some(&serde_json::json!("END"))),
+            vec!["END".to_string()]
+        );
+        assert_eq!(
+            parse_stop(Some(&serde_json::json!(["a", "", "b"]))),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert!(parse_stop(None).is_empty());
+    }
+}
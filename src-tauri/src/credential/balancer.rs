@@ -25,6 +25,8 @@ pub enum BalanceStrategy {
     LeastUsed,
     /// 随机策略
     Random,
+    /// 最低延迟策略（按滑动窗口内的 P95 延迟）
+    LeastLatency,
 }
 
 /// 冷却信息
@@ -57,6 +59,8 @@ pub struct LoadBalancer {
     health_checker: HealthChecker,
     /// 代理客户端工厂
     proxy_factory: ProxyClientFactory,
+    /// 凭证延迟提示（凭证 ID -> P95 延迟毫秒，由遥测统计定期更新）
+    latency_hints: DashMap<String, u64>,
 }
 
 impl LoadBalancer {
@@ -68,6 +72,7 @@ impl LoadBalancer {
             round_robin_indices: DashMap::new(),
             health_checker: HealthChecker::with_defaults(),
             proxy_factory: ProxyClientFactory::new(),
+            latency_hints: DashMap::new(),
         }
     }
 
@@ -84,6 +89,7 @@ impl LoadBalancer {
             round_robin_indices: DashMap::new(),
             health_checker: HealthChecker::new(health_config),
             proxy_factory: ProxyClientFactory::new(),
+            latency_hints: DashMap::new(),
         }
     }
 
@@ -157,9 +163,33 @@ impl LoadBalancer {
             BalanceStrategy::RoundRobin => self.select_round_robin(&pool, provider),
             BalanceStrategy::LeastUsed => self.select_least_used(&pool),
             BalanceStrategy::Random => self.select_random(&pool),
+            BalanceStrategy::LeastLatency => self.select_least_latency(&pool),
         }
     }
 
+    /// 替换 Provider 的凭证延迟提示（供最低延迟策略使用）
+    ///
+    /// 窗口内没有样本的凭证清除旧提示，避免过期的高延迟一直压制该凭证。
+    pub fn update_latency_hints(
+        &self,
+        provider: ProviderType,
+        hints: impl IntoIterator<Item = (String, u64)>,
+    ) {
+        if let Some(pool) = self.get_pool(provider) {
+            for credential in pool.all() {
+                self.latency_hints.remove(&credential.id);
+            }
+        }
+        for (credential_id, latency_ms) in hints {
+            self.latency_hints.insert(credential_id, latency_ms);
+        }
+    }
+
+    /// 获取凭证的延迟提示
+    pub fn latency_hint(&self, credential_id: &str) -> Option<u64> {
+        self.latency_hints.get(credential_id).map(|r| *r.value())
+    }
+
     /// 选择下一个可用凭证并创建配置了代理的 HTTP 客户端
     ///
     /// 代理选择逻辑：
@@ -340,6 +370,21 @@ impl LoadBalancer {
             .ok_or(PoolError::NoAvailableCredential)
     }
 
+    /// 最低延迟选择凭证
+    ///
+    /// 优先使用遥测统计的 P95 延迟，没有时回退到凭证的平均延迟；
+    /// 尚无成功请求的凭证视为 0 延迟，以便尽快获得样本。
+    fn select_least_latency(&self, pool: &CredentialPool) -> Result<Credential, PoolError> {
+        pool.all()
+            .into_iter()
+            .filter(|c| c.is_available())
+            .min_by_key(|c| {
+                self.latency_hint(&c.id)
+                    .unwrap_or(c.stats.avg_latency_ms as u64)
+            })
+            .ok_or(PoolError::NoAvailableCredential)
+    }
+
     /// 随机选择凭证
     fn select_random(&self, pool: &CredentialPool) -> Result<Credential, PoolError> {
         let active_creds: Vec<Credential> = pool
@@ -525,6 +570,34 @@ mod balancer_tests {
         assert!(matches!(result, Err(PoolError::EmptyPool)));
     }
 
+    #[test]
+    fn test_load_balancer_select_least_latency() {
+        let lb = LoadBalancer::new(BalanceStrategy::LeastLatency);
+        let pool = Arc::new(CredentialPool::new(ProviderType::Kiro));
+        pool.add(create_test_credential("cred-1", ProviderType::Kiro))
+            .unwrap();
+        pool.add(create_test_credential("cred-2", ProviderType::Kiro))
+            .unwrap();
+        lb.register_pool(pool);
+
+        lb.update_latency_hints(
+            ProviderType::Kiro,
+            [("cred-1".to_string(), 800), ("cred-2".to_string(), 200)],
+        );
+        assert_eq!(lb.select(ProviderType::Kiro).unwrap().id, "cred-2");
+
+        lb.update_latency_hints(
+            ProviderType::Kiro,
+            [("cred-1".to_string(), 800), ("cred-2".to_string(), 1500)],
+        );
+        assert_eq!(lb.latency_hint("cred-2"), Some(1500));
+        assert_eq!(lb.select(ProviderType::Kiro).unwrap().id, "cred-1");
+
+        // 窗口内没有样本的凭证清除旧提示
+        lb.update_latency_hints(ProviderType::Kiro, [("cred-2".to_string(), 1500)]);
+        assert_eq!(lb.latency_hint("cred-1"), None);
+    }
+
     #[test]
     fn test_load_balancer_cooldown() {
         let lb = LoadBalancer::round_robin();
@@ -0,0 +1,298 @@
+//! Provider / 凭证维护窗口
+//!
+//! 维护窗口来自配置文件（`maintenance.windows`，支持热重载）或管理 API（仅保存在内存中，
+//! 重启后清空）。窗口生效期间凭证池选择凭证时排除对应的 Provider 或凭证，
+//! `/health` 将其报告为计划内维护，而不是故障。
+
+use crate::config::{MaintenanceConfig, MaintenanceWindowConfig};
+use crate::models::provider_pool_model::{PoolProviderType, ProviderCredential};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+
+static MAINTENANCE_REGISTRY: OnceLock<Arc<MaintenanceRegistry>> = OnceLock::new();
+
+/// 全局维护窗口注册表
+pub fn maintenance_registry() -> &'static Arc<MaintenanceRegistry> {
+    MAINTENANCE_REGISTRY.get_or_init(|| Arc::new(MaintenanceRegistry::default()))
+}
+
+/// 维护窗口来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceSource {
+    /// 配置文件
+    Config,
+    /// 管理 API
+    Api,
+}
+
+/// 维护窗口
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceWindow {
+    pub id: String,
+    /// Provider 类型
+    pub provider: Option<PoolProviderType>,
+    /// 凭证 UUID 或名称
+    pub credential: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub source: MaintenanceSource,
+}
+
+impl MaintenanceWindow {
+    /// 校验配置并创建维护窗口
+    pub fn new(
+        id: String,
+        source: MaintenanceSource,
+        config: &MaintenanceWindowConfig,
+    ) -> Result<Self, String> {
+        let provider = config
+            .provider
+            .as_deref()
+            .filter(|p| !p.is_empty())
+            .map(|p| p.parse::<PoolProviderType>())
+            .transpose()?;
+        let credential = config.credential.clone().filter(|c| !c.is_empty());
+        if provider.is_some() == credential.is_some() {
+            return Err("必须且只能指定 provider 或 credential 之一".to_string());
+        }
+        if let (Some(start), Some(end)) = (config.start, config.end) {
+            if end <= start {
+                return Err("维护结束时间必须晚于开始时间".to_string());
+            }
+        }
+        Ok(Self {
+            id,
+            provider,
+            credential,
+            start: config.start,
+            end: config.end,
+            reason: config.reason.clone(),
+            source,
+        })
+    }
+
+    /// 当前是否处于维护中
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.start.is_none_or(|start| start <= now) && !self.is_expired(now)
+    }
+
+    /// 维护是否已结束
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.end.is_some_and(|end| end <= now)
+    }
+
+    /// 是否覆盖指定凭证
+    pub fn matches(&self, credential: &ProviderCredential) -> bool {
+        if let Some(provider) = self.provider {
+            return credential.provider_type == provider;
+        }
+        self.credential
+            .as_deref()
+            .is_some_and(|c| credential.uuid == c || credential.name.as_deref() == Some(c))
+    }
+}
+
+/// 维护窗口注册表
+#[derive(Debug, Default)]
+pub struct MaintenanceRegistry {
+    /// 配置文件中的窗口（热重载时整体替换）
+    configured: RwLock<Vec<MaintenanceWindow>>,
+    /// 管理 API 添加的窗口
+    runtime: RwLock<Vec<MaintenanceWindow>>,
+}
+
+impl MaintenanceRegistry {
+    /// 更新配置文件中的维护窗口（无效条目跳过并记录警告）
+    pub fn set_config(&self, config: &MaintenanceConfig) {
+        let windows = config
+            .windows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, window)| {
+                MaintenanceWindow::new(format!("config-{}", i), MaintenanceSource::Config, window)
+                    .map_err(|e| tracing::warn!("[MAINTENANCE] 忽略第 {} 个维护窗口: {}", i, e))
+                    .ok()
+            })
+            .collect();
+        *self.configured.write() = windows;
+    }
+
+    /// 添加维护窗口
+    pub fn add(&self, config: &MaintenanceWindowConfig) -> Result<MaintenanceWindow, String> {
+        let window = MaintenanceWindow::new(
+            uuid::Uuid::new_v4().to_string(),
+            MaintenanceSource::Api,
+            config,
+        )?;
+        if window.is_expired(Utc::now()) {
+            return Err("维护结束时间已过".to_string());
+        }
+        tracing::info!(
+            "[MAINTENANCE] 添加维护窗口 {}: {}",
+            window.id,
+            window
+                .provider
+                .map(|p| p.to_string())
+                .or_else(|| window.credential.clone())
+                .unwrap_or_default()
+        );
+        self.runtime.write().push(window.clone());
+        Ok(window)
+    }
+
+    /// 移除管理 API 添加的维护窗口（配置文件中的窗口需修改配置移除）
+    pub fn remove(&self, id: &str) -> bool {
+        let mut runtime = self.runtime.write();
+        let before = runtime.len();
+        runtime.retain(|w| w.id != id);
+        runtime.len() != before
+    }
+
+    /// 所有未结束的维护窗口（同时清理已结束的管理 API 窗口）
+    pub fn list(&self, now: DateTime<Utc>) -> Vec<MaintenanceWindow> {
+        self.runtime.write().retain(|w| !w.is_expired(now));
+        self.configured
+            .read()
+            .iter()
+            .chain(self.runtime.read().iter())
+            .filter(|w| !w.is_expired(now))
+            .cloned()
+            .collect()
+    }
+
+    /// 当前生效的维护窗口
+    pub fn active(&self, now: DateTime<Utc>) -> Vec<MaintenanceWindow> {
+        self.list(now)
+            .into_iter()
+            .filter(|w| w.is_active(now))
+            .collect()
+    }
+
+    /// 凭证当前是否处于维护中
+    pub fn is_in_maintenance(&self, credential: &ProviderCredential, now: DateTime<Utc>) -> bool {
+        self.configured
+            .read()
+            .iter()
+            .chain(self.runtime.read().iter())
+            .any(|w| w.is_active(now) && w.matches(credential))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::CredentialData;
+    use chrono::Duration;
+
+    fn credential(name: &str) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        cred.name = Some(name.to_string());
+        cred
+    }
+
+    fn window(provider: Option<&str>, credential: Option<&str>) -> MaintenanceWindowConfig {
+        MaintenanceWindowConfig {
+            provider: provider.map(String::from),
+            credential: credential.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_window_validation() {
+        assert!(
+            MaintenanceWindow::new("a".into(), MaintenanceSource::Api, &window(None, None))
+                .is_err()
+        );
+        assert!(MaintenanceWindow::new(
+            "a".into(),
+            MaintenanceSource::Api,
+            &window(Some("claude"), Some("main"))
+        )
+        .is_err());
+        assert!(MaintenanceWindow::new(
+            "a".into(),
+            MaintenanceSource::Api,
+            &window(Some("not-a-provider"), None)
+        )
+        .is_err());
+
+        let now = Utc::now();
+        let reversed = MaintenanceWindowConfig {
+            start: Some(now),
+            end: Some(now - Duration::hours(1)),
+            ..window(Some("claude"), None)
+        };
+        assert!(MaintenanceWindow::new("a".into(), MaintenanceSource::Api, &reversed).is_err());
+    }
+
+    #[test]
+    fn test_window_time_range_and_matching() {
+        let now = Utc::now();
+        let scheduled = MaintenanceWindow::new(
+            "a".into(),
+            MaintenanceSource::Config,
+            &MaintenanceWindowConfig {
+                start: Some(now + Duration::hours(1)),
+                end: Some(now + Duration::hours(2)),
+                ..window(None, Some("main"))
+            },
+        )
+        .unwrap();
+        assert!(!scheduled.is_active(now));
+        assert!(scheduled.is_active(now + Duration::minutes(90)));
+        assert!(!scheduled.is_active(now + Duration::hours(2)));
+
+        let main = credential("main");
+        assert!(scheduled.matches(&main));
+        assert!(!scheduled.matches(&credential("backup")));
+
+        let by_provider = MaintenanceWindow::new(
+            "b".into(),
+            MaintenanceSource::Api,
+            &window(Some("claude"), None),
+        )
+        .unwrap();
+        assert!(by_provider.matches(&main));
+    }
+
+    #[test]
+    fn test_registry_add_remove_and_config() {
+        let registry = MaintenanceRegistry::default();
+        let now = Utc::now();
+        let main = credential("main");
+        let backup = credential("backup");
+
+        let added = registry.add(&window(None, Some(&main.uuid))).unwrap();
+        assert!(registry.is_in_maintenance(&main, now));
+        assert!(!registry.is_in_maintenance(&backup, now));
+
+        registry.set_config(&MaintenanceConfig {
+            windows: vec![window(None, Some("backup")), window(None, None)],
+        });
+        // 无效的配置条目被跳过
+        assert_eq!(registry.list(now).len(), 2);
+        assert!(registry.is_in_maintenance(&backup, now));
+
+        // 配置中的窗口不能通过 API 移除
+        assert!(!registry.remove("config-0"));
+        assert!(registry.remove(&added.id));
+        assert!(!registry.is_in_maintenance(&main, now));
+
+        let expired = MaintenanceWindowConfig {
+            end: Some(now - Duration::minutes(1)),
+            ..window(Some("claude"), None)
+        };
+        assert!(registry.add(&expired).is_err());
+    }
+}
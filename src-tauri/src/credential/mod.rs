@@ -8,6 +8,7 @@
 //! - `pool` - 凭证池管理
 //! - `balancer` - 负载均衡策略
 //! - `health` - 健康检查
+//! - `maintenance` - Provider / 凭证维护窗口
 //! - `quota` - 配额管理
 //! - `sync` - 数据库同步
 //! - `plugin` - OAuth Provider 插件 Trait
@@ -19,6 +20,7 @@
 
 mod balancer;
 mod health;
+mod maintenance;
 pub mod oauth_plugin_loader;
 pub mod plugin;
 mod pool;
@@ -32,6 +34,9 @@ mod unified;
 
 pub use balancer::{BalanceStrategy, CooldownInfo, CredentialSelection, LoadBalancer};
 pub use health::{HealthCheckConfig, HealthCheckResult, HealthChecker, HealthStatus};
+pub use maintenance::{
+    maintenance_registry, MaintenanceRegistry, MaintenanceSource, MaintenanceWindow,
+};
 pub use oauth_plugin_loader::{
     BinaryManifest, ExternalOAuthPlugin, OAuthPluginLoader, OAuthPluginManifest, ProviderManifest,
     UiManifest,
@@ -237,6 +237,15 @@ impl CredentialSyncService {
                     "iFlow Cookie 凭证暂不支持同步到配置".to_string(),
                 ));
             }
+            CredentialData::OpenRouterKey { .. }
+            | CredentialData::MistralKey { .. }
+            | CredentialData::DeepSeekKey { .. } => {
+                // OpenAI 兼容的第三方 API Key 暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(format!(
+                    "{} 凭证暂不支持同步到配置",
+                    credential.provider_type
+                )));
+            }
             CredentialData::AnthropicKey { api_key, base_url } => {
                 // Anthropic API Key 保存到 claude 配置（使用相同的 API 格式）
                 let entry = ApiKeyEntry {
@@ -413,7 +422,10 @@ impl CredentialSyncService {
             PoolProviderType::Anthropic
             | PoolProviderType::AzureOpenai
             | PoolProviderType::AwsBedrock
-            | PoolProviderType::Ollama => {
+            | PoolProviderType::Ollama
+            | PoolProviderType::OpenRouter
+            | PoolProviderType::Mistral
+            | PoolProviderType::DeepSeek => {
                 return Err(SyncError::InvalidCredentialType(
                     "API Key Provider 凭证不支持同步到配置".to_string(),
                 ));
@@ -593,6 +605,15 @@ impl CredentialSyncService {
                     "iFlow Cookie 凭证暂不支持同步到配置".to_string(),
                 ));
             }
+            CredentialData::OpenRouterKey { .. }
+            | CredentialData::MistralKey { .. }
+            | CredentialData::DeepSeekKey { .. } => {
+                // OpenAI 兼容的第三方 API Key 暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(format!(
+                    "{} 凭证暂不支持同步到配置",
+                    credential.provider_type
+                )));
+            }
             CredentialData::AnthropicKey { api_key, base_url } => {
                 // Anthropic API Key 更新到 claude 配置
                 if let Some(entry) = config
@@ -13,6 +13,7 @@ use super::pool::{CredentialPool, PoolError};
 use super::risk::{CooldownConfig, RateLimitEvent, RiskController, RiskLevel};
 use super::types::{Credential, CredentialData};
 use crate::orchestrator::get_global_orchestrator;
+use crate::telemetry::StatsAggregator;
 use crate::ProviderType;
 use chrono::Duration;
 use std::sync::Arc;
@@ -76,6 +77,19 @@ impl UnifiedCredentialManager {
         self.load_balancer.register_pool(pool);
     }
 
+    /// 从遥测统计同步各凭证的 P95 延迟到负载均衡器
+    pub fn sync_latency_hints(&self, stats: &StatsAggregator, window: Duration) {
+        for provider in self.load_balancer.providers() {
+            let latencies = stats.credential_latencies(provider, window);
+            self.load_balancer.update_latency_hints(
+                provider,
+                latencies
+                    .into_iter()
+                    .map(|(credential_id, p)| (credential_id, p.p95_ms)),
+            );
+        }
+    }
+
     /// 选择凭证（带风控检查）
     ///
     /// # 参数
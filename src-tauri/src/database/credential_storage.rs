@@ -0,0 +1,579 @@
+//! 凭证池存储后端抽象
+//!
+//! `ProviderPoolDao` 默认直接读写本地 SQLite。通过 `ProviderPoolDao::set_storage`
+//! 注册 `CredentialStorage` 实现后，所有凭证池读写都转发到该后端，
+//! 多个 proxycast 实例可以共享同一个凭证池（PostgreSQL、etcd、Redis 等）。
+//!
+//! 后端只需实现按 UUID 的增删改查，健康状态、使用统计、Token 缓存等操作
+//! 由 trait 的默认方法以“读取 - 修改 - 写回”实现（后写覆盖先写），
+//! 支持原子更新的后端可以覆盖对应方法。
+
+use crate::models::provider_pool_model::{
+    CachedTokenInfo, CredentialExpiryInfo, PoolProviderType, ProviderCredential,
+};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// 存储后端错误
+#[derive(Debug, Clone)]
+pub struct StorageError(pub String);
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "凭证存储错误: {}", self.0)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<StorageError> for rusqlite::Error {
+    fn from(e: StorageError) -> Self {
+        rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+    }
+}
+
+/// 存储后端中的一条凭证记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCredential {
+    pub credential: ProviderCredential,
+    /// Token 缓存（对应 SQLite 中的 cached_* 列）
+    #[serde(default)]
+    pub token_cache: Option<CachedTokenInfo>,
+    /// 过期跟踪信息
+    #[serde(default)]
+    pub expiry: CredentialExpiryInfo,
+}
+
+impl StoredCredential {
+    pub fn new(credential: ProviderCredential) -> Self {
+        Self {
+            credential,
+            token_cache: None,
+            expiry: CredentialExpiryInfo::default(),
+        }
+    }
+}
+
+/// 凭证池存储后端
+pub trait CredentialStorage: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &str;
+
+    /// 列出所有记录
+    fn list(&self) -> Result<Vec<StoredCredential>, StorageError>;
+
+    /// 按 UUID 获取记录
+    fn get(&self, uuid: &str) -> Result<Option<StoredCredential>, StorageError>;
+
+    /// 插入新记录（UUID 已存在时返回错误）
+    fn insert(&self, record: StoredCredential) -> Result<(), StorageError>;
+
+    /// 覆盖已有记录，记录不存在时返回 `Ok(false)`
+    fn update(&self, record: StoredCredential) -> Result<bool, StorageError>;
+
+    /// 删除记录
+    fn remove(&self, uuid: &str) -> Result<bool, StorageError>;
+
+    /// 后端数据版本，任一实例写入后变化
+    ///
+    /// 用于让本地缓存感知其他实例的修改；返回 `None` 表示无法感知，
+    /// 此时每次读取都会回源。
+    fn revision(&self) -> Option<u64> {
+        None
+    }
+
+    /// 后端是否可被多个实例共享
+    ///
+    /// 集群模式只能使用共享后端；进程内的后端（如内存后端）返回 `false`。
+    fn is_shared(&self) -> bool {
+        false
+    }
+
+    /// 尝试获取或续约租约（用于集群选主）
+    ///
+    /// 租约未被持有、已过期或已由 `holder` 持有时成功，有效期从当前时间起算。
+    /// 不支持租约的后端视为单实例部署，始终返回 `true`。
+    fn try_acquire_lease(
+        &self,
+        _name: &str,
+        _holder: &str,
+        _ttl: Duration,
+    ) -> Result<bool, StorageError> {
+        Ok(true)
+    }
+
+    /// 释放由 `holder` 持有的租约
+    fn release_lease(&self, _name: &str, _holder: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    // ==================== 基于基础操作的默认实现 ====================
+
+    /// 读取 - 修改 - 写回单条记录，记录不存在时不做任何操作
+    fn modify(
+        &self,
+        uuid: &str,
+        f: &mut dyn FnMut(&mut StoredCredential),
+    ) -> Result<bool, StorageError> {
+        let Some(mut record) = self.get(uuid)? else {
+            return Ok(false);
+        };
+        f(&mut record);
+        self.update(record)
+    }
+
+    /// 获取所有凭证（按类型、创建时间排序）
+    fn get_all(&self) -> Result<Vec<ProviderCredential>, StorageError> {
+        let mut creds: Vec<ProviderCredential> =
+            self.list()?.into_iter().map(|r| r.credential).collect();
+        creds.sort_by(|a, b| {
+            a.provider_type
+                .to_string()
+                .cmp(&b.provider_type.to_string())
+                .then(a.created_at.cmp(&b.created_at))
+        });
+        Ok(creds)
+    }
+
+    /// 获取指定类型的凭证
+    fn get_by_type(
+        &self,
+        provider_type: &PoolProviderType,
+    ) -> Result<Vec<ProviderCredential>, StorageError> {
+        Ok(self
+            .get_all()?
+            .into_iter()
+            .filter(|c| c.provider_type == *provider_type)
+            .collect())
+    }
+
+    /// 根据名称获取凭证
+    fn get_by_name(&self, name: &str) -> Result<Option<ProviderCredential>, StorageError> {
+        Ok(self
+            .get_all()?
+            .into_iter()
+            .find(|c| c.name.as_deref() == Some(name)))
+    }
+
+    /// 更新凭证（保留创建时间、来源和 Token 缓存，与 SQL UPDATE 一致）
+    fn update_credential(&self, cred: &ProviderCredential) -> Result<(), StorageError> {
+        self.modify(&cred.uuid, &mut |record| {
+            let created_at = record.credential.created_at;
+            let source = record.credential.source;
+            record.credential = cred.clone();
+            record.credential.created_at = created_at;
+            record.credential.source = source;
+            record.credential.cached_token = None;
+        })?;
+        Ok(())
+    }
+
+    /// 更新健康状态
+    #[allow(clippy::too_many_arguments)]
+    fn update_health_status(
+        &self,
+        uuid: &str,
+        is_healthy: bool,
+        error_count: u32,
+        last_error_time: Option<DateTime<Utc>>,
+        last_error_message: Option<&str>,
+        last_health_check_time: Option<DateTime<Utc>>,
+        last_health_check_model: Option<&str>,
+    ) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            let c = &mut record.credential;
+            c.is_healthy = is_healthy;
+            c.error_count = error_count;
+            c.last_error_time = last_error_time;
+            c.last_error_message = last_error_message.map(String::from);
+            c.last_health_check_time = last_health_check_time;
+            c.last_health_check_model = last_health_check_model.map(String::from);
+            c.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 更新使用统计
+    fn update_usage(
+        &self,
+        uuid: &str,
+        usage_count: u64,
+        daily_usage_count: u64,
+        daily_usage_reset_at: DateTime<Utc>,
+        last_used: DateTime<Utc>,
+    ) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            let c = &mut record.credential;
+            c.usage_count = usage_count;
+            c.daily_usage_count = daily_usage_count;
+            c.daily_usage_reset_at = Some(daily_usage_reset_at);
+            c.last_used = Some(last_used);
+            c.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 更新支持的模型列表
+    fn update_supported_models(&self, uuid: &str, models: &[String]) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            record.credential.supported_models = models.to_vec();
+            record.credential.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 重置凭证的当日请求计数
+    fn reset_daily_usage(&self, uuid: &str, reset_at: DateTime<Utc>) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            record.credential.daily_usage_count = 0;
+            record.credential.daily_usage_reset_at = Some(reset_at);
+        })?;
+        Ok(())
+    }
+
+    /// 重置凭证计数器
+    fn reset_counters(&self, uuid: &str) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            let c = &mut record.credential;
+            c.usage_count = 0;
+            c.error_count = 0;
+            c.is_healthy = true;
+            c.last_error_time = None;
+            c.last_error_message = None;
+            c.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 重置指定类型的所有凭证健康状态
+    fn reset_health_by_type(
+        &self,
+        provider_type: &PoolProviderType,
+    ) -> Result<usize, StorageError> {
+        let mut affected = 0;
+        for cred in self.get_by_type(provider_type)? {
+            let changed = self.modify(&cred.uuid, &mut |record| {
+                let c = &mut record.credential;
+                c.is_healthy = true;
+                c.error_count = 0;
+                c.last_error_time = None;
+                c.last_error_message = None;
+                c.updated_at = Utc::now();
+            })?;
+            if changed {
+                affected += 1;
+            }
+        }
+        Ok(affected)
+    }
+
+    /// 获取凭证的 Token 缓存信息
+    fn get_token_cache(&self, uuid: &str) -> Result<Option<CachedTokenInfo>, StorageError> {
+        Ok(self
+            .get(uuid)?
+            .and_then(|r| r.token_cache)
+            .filter(|t| t.access_token.is_some()))
+    }
+
+    /// 更新凭证的 Token 缓存
+    fn update_token_cache(
+        &self,
+        uuid: &str,
+        token_info: &CachedTokenInfo,
+    ) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            record.token_cache = Some(token_info.clone());
+            record.credential.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 记录一次成功刷新（Refresh Token 过期时间未知时保留原值）
+    fn record_refresh_success(
+        &self,
+        uuid: &str,
+        refresh_token_expiry: Option<DateTime<Utc>>,
+    ) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            record.expiry.last_successful_refresh = Some(Utc::now());
+            if refresh_token_expiry.is_some() {
+                record.expiry.refresh_token_expiry = refresh_token_expiry;
+            }
+        })?;
+        Ok(())
+    }
+
+    /// 获取所有凭证的过期跟踪信息
+    fn get_all_expiry_info(&self) -> Result<HashMap<String, CredentialExpiryInfo>, StorageError> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .map(|r| (r.credential.uuid, r.expiry))
+            .collect())
+    }
+
+    /// 清除凭证的 Token 缓存
+    fn clear_token_cache(&self, uuid: &str) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            record.token_cache = None;
+            record.credential.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 记录 Token 刷新错误
+    fn record_token_refresh_error(
+        &self,
+        uuid: &str,
+        error_message: &str,
+    ) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            let cache = record.token_cache.get_or_insert_with(Default::default);
+            cache.refresh_error_count += 1;
+            cache.last_refresh_error = Some(error_message.to_string());
+            record.credential.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+
+    /// 重置 Token 刷新错误计数
+    fn reset_token_refresh_errors(&self, uuid: &str) -> Result<(), StorageError> {
+        self.modify(uuid, &mut |record| {
+            if let Some(cache) = record.token_cache.as_mut() {
+                cache.refresh_error_count = 0;
+                cache.last_refresh_error = None;
+            }
+            record.credential.updated_at = Utc::now();
+        })?;
+        Ok(())
+    }
+}
+
+/// 内存存储后端（不持久化，用于测试和临时实例）
+#[derive(Default)]
+pub struct MemoryCredentialStorage {
+    records: RwLock<BTreeMap<String, StoredCredential>>,
+    revision: AtomicU64,
+    /// 租约名称 -> (持有者, 到期时间)
+    leases: parking_lot::Mutex<HashMap<String, (String, DateTime<Utc>)>>,
+}
+
+impl MemoryCredentialStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bump(&self) {
+        self.revision.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl CredentialStorage for MemoryCredentialStorage {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn list(&self) -> Result<Vec<StoredCredential>, StorageError> {
+        Ok(self.records.read().values().cloned().collect())
+    }
+
+    fn get(&self, uuid: &str) -> Result<Option<StoredCredential>, StorageError> {
+        Ok(self.records.read().get(uuid).cloned())
+    }
+
+    fn insert(&self, mut record: StoredCredential) -> Result<(), StorageError> {
+        let mut records = self.records.write();
+        if records.contains_key(&record.credential.uuid) {
+            return Err(StorageError(format!(
+                "凭证已存在: {}",
+                record.credential.uuid
+            )));
+        }
+        record.credential.cached_token = None;
+        records.insert(record.credential.uuid.clone(), record);
+        drop(records);
+        self.bump();
+        Ok(())
+    }
+
+    fn update(&self, record: StoredCredential) -> Result<bool, StorageError> {
+        let mut records = self.records.write();
+        let Some(slot) = records.get_mut(&record.credential.uuid) else {
+            return Ok(false);
+        };
+        *slot = record;
+        drop(records);
+        self.bump();
+        Ok(true)
+    }
+
+    fn remove(&self, uuid: &str) -> Result<bool, StorageError> {
+        let removed = self.records.write().remove(uuid).is_some();
+        if removed {
+            self.bump();
+        }
+        Ok(removed)
+    }
+
+    fn revision(&self) -> Option<u64> {
+        Some(self.revision.load(Ordering::SeqCst))
+    }
+
+    fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> Result<bool, StorageError> {
+        let ttl = chrono::Duration::from_std(ttl).map_err(|e| StorageError(e.to_string()))?;
+        let now = Utc::now();
+        let mut leases = self.leases.lock();
+        if let Some((current, expires_at)) = leases.get(name) {
+            if current != holder && *expires_at > now {
+                return Ok(false);
+            }
+        }
+        leases.insert(name.to_string(), (holder.to_string(), now + ttl));
+        Ok(true)
+    }
+
+    fn release_lease(&self, name: &str, holder: &str) -> Result<(), StorageError> {
+        let mut leases = self.leases.lock();
+        if leases
+            .get(name)
+            .is_some_and(|(current, _)| current == holder)
+        {
+            leases.remove(name);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::CredentialData;
+
+    fn cred(provider_type: PoolProviderType, name: &str) -> ProviderCredential {
+        let mut c = ProviderCredential::new(
+            provider_type,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        c.name = Some(name.to_string());
+        c
+    }
+
+    #[test]
+    fn test_memory_storage_crud() {
+        let storage = MemoryCredentialStorage::new();
+        let a = cred(PoolProviderType::OpenAI, "a");
+        let b = cred(PoolProviderType::Claude, "b");
+        storage.insert(StoredCredential::new(a.clone())).unwrap();
+        storage.insert(StoredCredential::new(b.clone())).unwrap();
+        assert!(storage.insert(StoredCredential::new(a.clone())).is_err());
+
+        assert_eq!(storage.get_all().unwrap().len(), 2);
+        assert_eq!(
+            storage
+                .get_by_type(&PoolProviderType::OpenAI)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(storage.get_by_name("b").unwrap().unwrap().uuid, b.uuid);
+
+        let rev = storage.revision().unwrap();
+        let mut renamed = a.clone();
+        renamed.name = Some("renamed".to_string());
+        storage.update_credential(&renamed).unwrap();
+        assert_eq!(storage.revision().unwrap(), rev + 1);
+        assert!(storage.get_by_name("renamed").unwrap().is_some());
+
+        assert!(storage.remove(&a.uuid).unwrap());
+        assert!(!storage.remove(&a.uuid).unwrap());
+        assert_eq!(storage.get_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_memory_storage_health_and_token_cache() {
+        let storage = MemoryCredentialStorage::new();
+        let a = cred(PoolProviderType::OpenAI, "a");
+        storage.insert(StoredCredential::new(a.clone())).unwrap();
+
+        storage
+            .update_health_status(
+                &a.uuid,
+                false,
+                3,
+                Some(Utc::now()),
+                Some("boom"),
+                None,
+                None,
+            )
+            .unwrap();
+        let stored = storage.get(&a.uuid).unwrap().unwrap().credential;
+        assert!(!stored.is_healthy);
+        assert_eq!(stored.error_count, 3);
+        assert_eq!(
+            storage
+                .reset_health_by_type(&PoolProviderType::OpenAI)
+                .unwrap(),
+            1
+        );
+        assert!(storage.get(&a.uuid).unwrap().unwrap().credential.is_healthy);
+
+        // 没有 access_token 时视为无缓存
+        storage
+            .record_token_refresh_error(&a.uuid, "expired")
+            .unwrap();
+        assert!(storage.get_token_cache(&a.uuid).unwrap().is_none());
+
+        let token = CachedTokenInfo {
+            access_token: Some("at".to_string()),
+            ..Default::default()
+        };
+        storage.update_token_cache(&a.uuid, &token).unwrap();
+        // 更新凭证不覆盖 Token 缓存
+        storage.update_credential(&a).unwrap();
+        let cached = storage.get_token_cache(&a.uuid).unwrap().unwrap();
+        assert_eq!(cached.access_token.as_deref(), Some("at"));
+
+        storage.record_refresh_success(&a.uuid, None).unwrap();
+        let expiry = storage.get_all_expiry_info().unwrap();
+        assert!(expiry[&a.uuid].last_successful_refresh.is_some());
+
+        storage.clear_token_cache(&a.uuid).unwrap();
+        assert!(storage.get_token_cache(&a.uuid).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_memory_storage_lease() {
+        let storage = MemoryCredentialStorage::new();
+        let ttl = Duration::from_secs(60);
+        assert!(storage.try_acquire_lease("leader", "a", ttl).unwrap());
+        // 持有者续约成功，其他实例在租约有效期内无法获取
+        assert!(storage.try_acquire_lease("leader", "a", ttl).unwrap());
+        assert!(!storage.try_acquire_lease("leader", "b", ttl).unwrap());
+        // 其他实例不能释放不属于自己的租约
+        storage.release_lease("leader", "b").unwrap();
+        assert!(!storage.try_acquire_lease("leader", "b", ttl).unwrap());
+        storage.release_lease("leader", "a").unwrap();
+        assert!(storage.try_acquire_lease("leader", "b", ttl).unwrap());
+
+        // 租约过期后可被接管
+        assert!(storage
+            .try_acquire_lease("other", "a", Duration::ZERO)
+            .unwrap());
+        assert!(storage.try_acquire_lease("other", "b", ttl).unwrap());
+
+        // 内存后端只属于本进程，不能用于集群模式
+        assert!(!storage.is_shared());
+    }
+}
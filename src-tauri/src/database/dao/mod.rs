@@ -8,3 +8,5 @@ pub mod prompts;
 pub mod provider_pool;
 pub mod providers;
 pub mod skills;
+pub mod telemetry;
+pub mod thought_signature;
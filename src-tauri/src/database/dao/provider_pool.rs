@@ -1,24 +1,98 @@
 //! Provider Pool 数据访问对象
 //!
 //! 提供凭证池的 CRUD 操作。
+//!
+//! 默认读写本地 SQLite；注册了存储后端（见 `credential_storage`）时，
+//! 所有操作转发到该后端，`conn` 参数被忽略。
 
+use crate::database::credential_storage::{CredentialStorage, StoredCredential};
 use crate::models::provider_pool_model::{
-    CachedTokenInfo, CredentialData, CredentialSource, PoolProviderType, ProviderCredential,
-    ProviderPools,
+    CachedTokenInfo, CredentialData, CredentialExpiryInfo, CredentialSource, CredentialTier,
+    PoolProviderType, ProviderCredential, ProviderPools,
 };
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// 凭证池写入代数，每次写操作后递增，用于使内存缓存失效
+static WRITE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 已注册的存储后端（未注册时使用 SQLite）
+static STORAGE: parking_lot::RwLock<Option<Arc<dyn CredentialStorage>>> =
+    parking_lot::RwLock::new(None);
+
+fn bump_generation() {
+    WRITE_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+fn storage() -> Option<Arc<dyn CredentialStorage>> {
+    STORAGE.read().clone()
+}
 
 pub struct ProviderPoolDao;
 
 impl ProviderPoolDao {
+    /// 注册凭证池存储后端，传入 `None` 恢复使用 SQLite
+    pub fn set_storage(storage: Option<Arc<dyn CredentialStorage>>) {
+        if let Some(s) = &storage {
+            tracing::info!("[凭证池] 使用存储后端: {}", s.name());
+        }
+        *STORAGE.write() = storage;
+        bump_generation();
+    }
+
+    /// 当前凭证池存储后端名称
+    pub fn storage_name() -> String {
+        storage().map_or_else(|| "sqlite".to_string(), |s| s.name().to_string())
+    }
+
+    /// 当前凭证池存储后端是否可被多个实例共享（本地 SQLite 不可共享）
+    pub fn is_shared_storage() -> bool {
+        storage().is_some_and(|s| s.is_shared())
+    }
+
+    /// 尝试获取或续约租约（未注册存储后端时视为单实例部署，始终成功）
+    pub fn try_acquire_lease(
+        name: &str,
+        holder: &str,
+        ttl: std::time::Duration,
+    ) -> Result<bool, rusqlite::Error> {
+        match storage() {
+            Some(storage) => Ok(storage.try_acquire_lease(name, holder, ttl)?),
+            None => Ok(true),
+        }
+    }
+
+    /// 当前写入代数
+    ///
+    /// 所有写操作都经过本 DAO，代数未变化说明凭证池数据未被修改。
+    /// 使用外部存储后端时叠加后端的数据版本，以感知其他实例的写入；
+    /// 后端无法提供版本时每次返回新值，使缓存始终回源。
+    pub fn generation() -> u64 {
+        let local = WRITE_GENERATION.load(Ordering::SeqCst);
+        match storage() {
+            None => local,
+            Some(s) => match s.revision() {
+                Some(revision) => local.wrapping_add(revision),
+                None => WRITE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1,
+            },
+        }
+    }
+
     /// 获取所有凭证
     pub fn get_all(conn: &Connection) -> Result<Vec<ProviderCredential>, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.get_all()?);
+        }
         let mut stmt = conn.prepare(
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    schedule, daily_usage_count, daily_usage_reset_at, learned_unsupported_models,
+                    tags, pinned_model
              FROM provider_pool_credentials
              ORDER BY provider_type, created_at ASC",
         )?;
@@ -37,11 +111,16 @@ impl ProviderPoolDao {
         conn: &Connection,
         provider_type: &PoolProviderType,
     ) -> Result<Vec<ProviderCredential>, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.get_by_type(provider_type)?);
+        }
         let mut stmt = conn.prepare(
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    schedule, daily_usage_count, daily_usage_reset_at, learned_unsupported_models,
+                    tags, pinned_model
              FROM provider_pool_credentials
              WHERE provider_type = ?1
              ORDER BY created_at ASC",
@@ -63,11 +142,16 @@ impl ProviderPoolDao {
         conn: &Connection,
         uuid: &str,
     ) -> Result<Option<ProviderCredential>, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.get(uuid)?.map(|r| r.credential));
+        }
         let mut stmt = conn.prepare(
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    schedule, daily_usage_count, daily_usage_reset_at, learned_unsupported_models,
+                    tags, pinned_model
              FROM provider_pool_credentials
              WHERE uuid = ?1",
         )?;
@@ -85,11 +169,16 @@ impl ProviderPoolDao {
         conn: &Connection,
         name: &str,
     ) -> Result<Option<ProviderCredential>, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.get_by_name(name)?);
+        }
         let mut stmt = conn.prepare(
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    schedule, daily_usage_count, daily_usage_reset_at, learned_unsupported_models,
+                    tags, pinned_model
              FROM provider_pool_credentials
              WHERE name = ?1",
         )?;
@@ -116,12 +205,22 @@ impl ProviderPoolDao {
 
     /// 插入新凭证
     pub fn insert(conn: &Connection, cred: &ProviderCredential) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.insert(StoredCredential::new(cred.clone()))?);
+        }
         let credential_json =
             serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string());
         let not_supported_models_json =
             serde_json::to_string(&cred.not_supported_models).unwrap_or_else(|_| "[]".to_string());
         let supported_models_json =
             serde_json::to_string(&cred.supported_models).unwrap_or_else(|_| "[]".to_string());
+        let schedule_json = cred
+            .schedule
+            .as_ref()
+            .and_then(|s| serde_json::to_string(s).ok());
+        let learned_models_json = serde_json::to_string(&cred.learned_unsupported_models)
+            .unwrap_or_else(|_| "{}".to_string());
+        let tags_json = serde_json::to_string(&cred.tags).unwrap_or_else(|_| "[]".to_string());
         let source_str = match cred.source {
             CredentialSource::Manual => "manual",
             CredentialSource::Imported => "imported",
@@ -133,8 +232,9 @@ impl ProviderPoolDao {
              (uuid, provider_type, credential_data, name, is_healthy, is_disabled,
               check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
               last_used, last_error_time, last_error_message, last_health_check_time,
-              last_health_check_model, created_at, updated_at, source, proxy_url)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+              last_health_check_model, created_at, updated_at, source, proxy_url, tier, schedule,
+              learned_unsupported_models, tags, pinned_model)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26)",
             params![
                 cred.uuid,
                 cred.provider_type.to_string(),
@@ -157,19 +257,35 @@ impl ProviderPoolDao {
                 cred.updated_at.timestamp(),
                 source_str,
                 cred.proxy_url,
+                cred.tier.as_str(),
+                schedule_json,
+                learned_models_json,
+                tags_json,
+                cred.pinned_model,
             ],
         )?;
+        bump_generation();
         Ok(())
     }
 
     /// 更新凭证
     pub fn update(conn: &Connection, cred: &ProviderCredential) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.update_credential(cred)?);
+        }
         let credential_json =
             serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string());
         let not_supported_models_json =
             serde_json::to_string(&cred.not_supported_models).unwrap_or_else(|_| "[]".to_string());
         let supported_models_json =
             serde_json::to_string(&cred.supported_models).unwrap_or_else(|_| "[]".to_string());
+        let schedule_json = cred
+            .schedule
+            .as_ref()
+            .and_then(|s| serde_json::to_string(s).ok());
+        let learned_models_json = serde_json::to_string(&cred.learned_unsupported_models)
+            .unwrap_or_else(|_| "{}".to_string());
+        let tags_json = serde_json::to_string(&cred.tags).unwrap_or_else(|_| "[]".to_string());
 
         conn.execute(
             "UPDATE provider_pool_credentials SET
@@ -177,7 +293,9 @@ impl ProviderPoolDao {
              is_disabled = ?6, check_health = ?7, check_model_name = ?8,
              not_supported_models = ?9, supported_models = ?10, usage_count = ?11, error_count = ?12,
              last_used = ?13, last_error_time = ?14, last_error_message = ?15,
-             last_health_check_time = ?16, last_health_check_model = ?17, updated_at = ?18, proxy_url = ?19
+             last_health_check_time = ?16, last_health_check_model = ?17, updated_at = ?18, proxy_url = ?19,
+             tier = ?20, schedule = ?21,
+             learned_unsupported_models = ?22, tags = ?23, pinned_model = ?24
              WHERE uuid = ?1",
             params![
                 cred.uuid,
@@ -199,17 +317,27 @@ impl ProviderPoolDao {
                 cred.last_health_check_model,
                 cred.updated_at.timestamp(),
                 cred.proxy_url,
+                cred.tier.as_str(),
+                schedule_json,
+                learned_models_json,
+                tags_json,
+                cred.pinned_model,
             ],
         )?;
+        bump_generation();
         Ok(())
     }
 
     /// 删除凭证
     pub fn delete(conn: &Connection, uuid: &str) -> Result<bool, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.remove(uuid)?);
+        }
         let affected = conn.execute(
             "DELETE FROM provider_pool_credentials WHERE uuid = ?1",
             [uuid],
         )?;
+        bump_generation();
         Ok(affected > 0)
     }
 
@@ -224,6 +352,17 @@ impl ProviderPoolDao {
         last_health_check_time: Option<DateTime<Utc>>,
         last_health_check_model: Option<&str>,
     ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.update_health_status(
+                uuid,
+                is_healthy,
+                error_count,
+                last_error_time,
+                last_error_message,
+                last_health_check_time,
+                last_health_check_model,
+            )?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
              is_healthy = ?2, error_count = ?3, last_error_time = ?4,
@@ -241,6 +380,7 @@ impl ProviderPoolDao {
                 Utc::now().timestamp(),
             ],
         )?;
+        bump_generation();
         Ok(())
     }
 
@@ -249,24 +389,80 @@ impl ProviderPoolDao {
         conn: &Connection,
         uuid: &str,
         usage_count: u64,
+        daily_usage_count: u64,
+        daily_usage_reset_at: DateTime<Utc>,
         last_used: DateTime<Utc>,
     ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.update_usage(
+                uuid,
+                usage_count,
+                daily_usage_count,
+                daily_usage_reset_at,
+                last_used,
+            )?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
-             usage_count = ?2, last_used = ?3, updated_at = ?4
+             usage_count = ?2, daily_usage_count = ?3, daily_usage_reset_at = ?4,
+             last_used = ?5, updated_at = ?6
              WHERE uuid = ?1",
             params![
                 uuid,
                 usage_count,
+                daily_usage_count,
+                daily_usage_reset_at.timestamp(),
                 last_used.timestamp(),
                 Utc::now().timestamp()
             ],
         )?;
+        bump_generation();
+        Ok(())
+    }
+
+    /// 更新支持的模型列表
+    pub fn update_supported_models(
+        conn: &Connection,
+        uuid: &str,
+        models: &[String],
+    ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.update_supported_models(uuid, models)?);
+        }
+        let models_json = serde_json::to_string(models).unwrap_or_else(|_| "[]".to_string());
+        conn.execute(
+            "UPDATE provider_pool_credentials SET supported_models = ?2, updated_at = ?3
+             WHERE uuid = ?1",
+            params![uuid, models_json, Utc::now().timestamp()],
+        )?;
+        bump_generation();
+        Ok(())
+    }
+
+    /// 重置凭证的当日请求计数
+    pub fn reset_daily_usage(
+        conn: &Connection,
+        uuid: &str,
+        reset_at: DateTime<Utc>,
+    ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.reset_daily_usage(uuid, reset_at)?);
+        }
+        conn.execute(
+            "UPDATE provider_pool_credentials SET
+             daily_usage_count = 0, daily_usage_reset_at = ?2
+             WHERE uuid = ?1",
+            params![uuid, reset_at.timestamp()],
+        )?;
+        bump_generation();
         Ok(())
     }
 
     /// 重置凭证计数器
     pub fn reset_counters(conn: &Connection, uuid: &str) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.reset_counters(uuid)?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
              usage_count = 0, error_count = 0, is_healthy = 1,
@@ -274,6 +470,7 @@ impl ProviderPoolDao {
              WHERE uuid = ?1",
             params![uuid, Utc::now().timestamp()],
         )?;
+        bump_generation();
         Ok(())
     }
 
@@ -282,6 +479,9 @@ impl ProviderPoolDao {
         conn: &Connection,
         provider_type: &PoolProviderType,
     ) -> Result<usize, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.reset_health_by_type(provider_type)?);
+        }
         let affected = conn.execute(
             "UPDATE provider_pool_credentials SET
              is_healthy = 1, error_count = 0, last_error_time = NULL,
@@ -289,6 +489,7 @@ impl ProviderPoolDao {
              WHERE provider_type = ?1",
             params![provider_type.to_string(), Utc::now().timestamp()],
         )?;
+        bump_generation();
         Ok(affected)
     }
 
@@ -315,6 +516,13 @@ impl ProviderPoolDao {
         let updated_at_ts: i64 = row.get(18)?;
         let source_str: Option<String> = row.get(19).ok();
         let proxy_url: Option<String> = row.get(20).ok();
+        let tier_str: Option<String> = row.get(21).ok();
+        let schedule_json: Option<String> = row.get(22).ok().flatten();
+        let daily_usage_count: Option<i64> = row.get(23).ok().flatten();
+        let daily_usage_reset_at_ts: Option<i64> = row.get(24).ok().flatten();
+        let learned_models_json: Option<String> = row.get(25).ok().flatten();
+        let tags_json: Option<String> = row.get(26).ok().flatten();
+        let pinned_model: Option<String> = row.get(27).ok().flatten();
 
         let provider_type: PoolProviderType =
             provider_type_str.parse().unwrap_or(PoolProviderType::Kiro);
@@ -336,6 +544,9 @@ impl ProviderPoolDao {
             Some("private") => CredentialSource::Private,
             _ => CredentialSource::Manual,
         };
+        let tier = tier_str
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(CredentialTier::Primary);
 
         Ok(ProviderCredential {
             uuid,
@@ -367,6 +578,18 @@ impl ProviderPoolDao {
             cached_token: None, // 从 get_token_cache 单独获取
             source,
             proxy_url,
+            tier,
+            schedule: schedule_json.and_then(|s| serde_json::from_str(&s).ok()),
+            daily_usage_count: daily_usage_count.unwrap_or(0) as u64,
+            daily_usage_reset_at: daily_usage_reset_at_ts
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+            learned_unsupported_models: learned_models_json
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            tags: tags_json
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            pinned_model,
         })
     }
 
@@ -377,6 +600,9 @@ impl ProviderPoolDao {
         conn: &Connection,
         uuid: &str,
     ) -> Result<Option<CachedTokenInfo>, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.get_token_cache(uuid)?);
+        }
         let mut stmt = conn.prepare(
             "SELECT cached_access_token, cached_refresh_token, token_expiry_time,
                     last_refresh_time, refresh_error_count, last_refresh_error
@@ -425,6 +651,9 @@ impl ProviderPoolDao {
         uuid: &str,
         token_info: &CachedTokenInfo,
     ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.update_token_cache(uuid, token_info)?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
              cached_access_token = ?2,
@@ -446,11 +675,66 @@ impl ProviderPoolDao {
                 Utc::now().timestamp(),
             ],
         )?;
+        bump_generation();
         Ok(())
     }
 
+    /// 记录一次成功刷新（Refresh Token 过期时间未知时保留原值）
+    pub fn record_refresh_success(
+        conn: &Connection,
+        uuid: &str,
+        refresh_token_expiry: Option<DateTime<Utc>>,
+    ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.record_refresh_success(uuid, refresh_token_expiry)?);
+        }
+        conn.execute(
+            "UPDATE provider_pool_credentials SET
+             last_successful_refresh = ?2,
+             refresh_token_expiry = COALESCE(?3, refresh_token_expiry)
+             WHERE uuid = ?1",
+            params![
+                uuid,
+                Utc::now().to_rfc3339(),
+                refresh_token_expiry.map(|t| t.to_rfc3339()),
+            ],
+        )?;
+        bump_generation();
+        Ok(())
+    }
+
+    /// 获取所有凭证的过期跟踪信息
+    pub fn get_all_expiry_info(
+        conn: &Connection,
+    ) -> Result<HashMap<String, CredentialExpiryInfo>, rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.get_all_expiry_info()?);
+        }
+        let mut stmt = conn.prepare(
+            "SELECT uuid, last_successful_refresh, refresh_token_expiry
+             FROM provider_pool_credentials",
+        )?;
+        let parse = |s: Option<String>| {
+            s.and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+        };
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                CredentialExpiryInfo {
+                    last_successful_refresh: parse(row.get(1)?),
+                    refresh_token_expiry: parse(row.get(2)?),
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+
     /// 清除凭证的 Token 缓存
     pub fn clear_token_cache(conn: &Connection, uuid: &str) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.clear_token_cache(uuid)?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
              cached_access_token = NULL,
@@ -463,6 +747,7 @@ impl ProviderPoolDao {
              WHERE uuid = ?1",
             params![uuid, Utc::now().timestamp()],
         )?;
+        bump_generation();
         Ok(())
     }
 
@@ -472,6 +757,9 @@ impl ProviderPoolDao {
         uuid: &str,
         error_message: &str,
     ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.record_token_refresh_error(uuid, error_message)?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
              refresh_error_count = COALESCE(refresh_error_count, 0) + 1,
@@ -480,6 +768,7 @@ impl ProviderPoolDao {
              WHERE uuid = ?1",
             params![uuid, error_message, Utc::now().timestamp()],
         )?;
+        bump_generation();
         Ok(())
     }
 
@@ -489,6 +778,9 @@ impl ProviderPoolDao {
         conn: &Connection,
         uuid: &str,
     ) -> Result<(), rusqlite::Error> {
+        if let Some(storage) = storage() {
+            return Ok(storage.reset_token_refresh_errors(uuid)?);
+        }
         conn.execute(
             "UPDATE provider_pool_credentials SET
              refresh_error_count = 0,
@@ -497,6 +789,7 @@ impl ProviderPoolDao {
              WHERE uuid = ?1",
             params![uuid, Utc::now().timestamp()],
         )?;
+        bump_generation();
         Ok(())
     }
 }
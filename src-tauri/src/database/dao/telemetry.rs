@@ -0,0 +1,687 @@
+//! 遥测数据持久化
+//!
+//! 原始请求日志 / Token 使用记录写入 SQLite，同时增量更新按小时、按天的汇总表，
+//! 重启后仍可查询历史统计。
+
+use crate::config::TelemetryRetentionConfig;
+use crate::telemetry::{RequestLog, RequestStatus, TokenUsageRecord};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 汇总粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupGranularity {
+    Hour,
+    Day,
+}
+
+impl RollupGranularity {
+    pub const ALL: [RollupGranularity; 2] = [RollupGranularity::Hour, RollupGranularity::Day];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupGranularity::Hour => "hour",
+            RollupGranularity::Day => "day",
+        }
+    }
+
+    /// 时间戳所在桶的起始时间（秒，UTC）
+    pub fn bucket_start(&self, timestamp: &DateTime<Utc>) -> i64 {
+        let secs = timestamp.timestamp();
+        let size = match self {
+            RollupGranularity::Hour => 3600,
+            RollupGranularity::Day => 86400,
+        };
+        secs - secs.rem_euclid(size)
+    }
+}
+
+impl std::str::FromStr for RollupGranularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hour" | "hourly" => Ok(RollupGranularity::Hour),
+            "day" | "daily" => Ok(RollupGranularity::Day),
+            _ => Err(format!("Invalid granularity: {s}")),
+        }
+    }
+}
+
+/// 汇总记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryRollup {
+    pub granularity: RollupGranularity,
+    /// 桶起始时间（秒，UTC）
+    pub bucket_start: i64,
+    pub provider: String,
+    pub model: String,
+    pub request_count: u64,
+    pub success_count: u64,
+    pub failed_count: u64,
+    pub timeout_count: u64,
+    pub total_duration_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// 汇总查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RollupQuery {
+    /// 起始时间（秒，含）
+    pub start: Option<i64>,
+    /// 结束时间（秒，不含）
+    pub end: Option<i64>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+/// 终端用户用量（原始记录聚合，受原始数据保留期限制）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserUsage {
+    pub user_id: String,
+    pub request_count: u64,
+    pub success_count: u64,
+    pub failed_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// 清理结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TelemetryCleanupResult {
+    pub requests: usize,
+    pub token_usage: usize,
+    pub hourly_rollups: usize,
+    pub daily_rollups: usize,
+}
+
+/// 单条记录对汇总表的增量
+struct RollupDelta<'a> {
+    provider: &'a str,
+    model: &'a str,
+    timestamp: &'a DateTime<Utc>,
+    request_count: u64,
+    success_count: u64,
+    failed_count: u64,
+    timeout_count: u64,
+    duration_ms: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+pub struct TelemetryDao;
+
+impl TelemetryDao {
+    /// 写入请求日志并更新汇总
+    pub fn insert_request_log(conn: &Connection, log: &RequestLog) -> Result<(), rusqlite::Error> {
+        let tx = conn.unchecked_transaction()?;
+        Self::write_request_log(&tx, log)?;
+        tx.commit()
+    }
+
+    /// 写入 Token 使用记录并更新汇总
+    pub fn insert_token_usage(
+        conn: &Connection,
+        record: &TokenUsageRecord,
+    ) -> Result<(), rusqlite::Error> {
+        let tx = conn.unchecked_transaction()?;
+        Self::write_token_usage(&tx, record)?;
+        tx.commit()
+    }
+
+    /// 在同一个事务中批量写入请求日志和 Token 使用记录
+    pub fn insert_batch(
+        conn: &Connection,
+        logs: &[RequestLog],
+        usages: &[TokenUsageRecord],
+    ) -> Result<(), rusqlite::Error> {
+        let tx = conn.unchecked_transaction()?;
+        for log in logs {
+            Self::write_request_log(&tx, log)?;
+        }
+        for record in usages {
+            Self::write_token_usage(&tx, record)?;
+        }
+        tx.commit()
+    }
+
+    fn write_request_log(conn: &Connection, log: &RequestLog) -> Result<(), rusqlite::Error> {
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO telemetry_requests
+             (id, timestamp, provider, model, status, duration_ms, http_status, is_streaming,
+              credential_id, retry_count, error_message, user_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                log.id,
+                log.timestamp.timestamp_millis(),
+                log.provider.to_string(),
+                log.model,
+                log.status.to_string(),
+                log.duration_ms as i64,
+                log.http_status,
+                log.is_streaming,
+                log.credential_id,
+                log.retry_count,
+                log.error_message,
+                log.user_id,
+            ],
+        )?;
+
+        // 重复的请求 ID 不重复计入汇总
+        if inserted > 0 {
+            let provider = log.provider.to_string();
+            Self::upsert_rollups(
+                conn,
+                &RollupDelta {
+                    provider: &provider,
+                    model: &log.model,
+                    timestamp: &log.timestamp,
+                    request_count: 1,
+                    success_count: (log.status == RequestStatus::Success) as u64,
+                    failed_count: (log.status == RequestStatus::Failed) as u64,
+                    timeout_count: (log.status == RequestStatus::Timeout) as u64,
+                    duration_ms: log.duration_ms,
+                    input_tokens: 0,
+                    output_tokens: 0,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_token_usage(
+        conn: &Connection,
+        record: &TokenUsageRecord,
+    ) -> Result<(), rusqlite::Error> {
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO telemetry_token_usage
+             (id, request_id, timestamp, provider, model, input_tokens, output_tokens, source,
+              user_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.id,
+                record.request_id,
+                record.timestamp.timestamp_millis(),
+                record.provider.to_string(),
+                record.model,
+                record.input_tokens,
+                record.output_tokens,
+                record.source.to_string(),
+                record.user_id,
+            ],
+        )?;
+
+        if inserted > 0 {
+            let provider = record.provider.to_string();
+            Self::upsert_rollups(
+                conn,
+                &RollupDelta {
+                    provider: &provider,
+                    model: &record.model,
+                    timestamp: &record.timestamp,
+                    request_count: 0,
+                    success_count: 0,
+                    failed_count: 0,
+                    timeout_count: 0,
+                    duration_ms: 0,
+                    input_tokens: record.input_tokens as u64,
+                    output_tokens: record.output_tokens as u64,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn upsert_rollups(conn: &Connection, delta: &RollupDelta) -> Result<(), rusqlite::Error> {
+        for granularity in RollupGranularity::ALL {
+            conn.execute(
+                "INSERT INTO telemetry_rollups
+                 (granularity, bucket_start, provider, model, request_count, success_count,
+                  failed_count, timeout_count, total_duration_ms, input_tokens, output_tokens)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT(granularity, bucket_start, provider, model) DO UPDATE SET
+                    request_count = request_count + excluded.request_count,
+                    success_count = success_count + excluded.success_count,
+                    failed_count = failed_count + excluded.failed_count,
+                    timeout_count = timeout_count + excluded.timeout_count,
+                    total_duration_ms = total_duration_ms + excluded.total_duration_ms,
+                    input_tokens = input_tokens + excluded.input_tokens,
+                    output_tokens = output_tokens + excluded.output_tokens",
+                params![
+                    granularity.as_str(),
+                    granularity.bucket_start(delta.timestamp),
+                    delta.provider,
+                    delta.model,
+                    delta.request_count as i64,
+                    delta.success_count as i64,
+                    delta.failed_count as i64,
+                    delta.timeout_count as i64,
+                    delta.duration_ms as i64,
+                    delta.input_tokens as i64,
+                    delta.output_tokens as i64,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 查询汇总数据（按桶起始时间升序）
+    pub fn query_rollups(
+        conn: &Connection,
+        granularity: RollupGranularity,
+        query: &RollupQuery,
+    ) -> Result<Vec<TelemetryRollup>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT bucket_start, provider, model, request_count, success_count, failed_count,
+                    timeout_count, total_duration_ms, input_tokens, output_tokens
+             FROM telemetry_rollups
+             WHERE granularity = ?1
+               AND (?2 IS NULL OR bucket_start >= ?2)
+               AND (?3 IS NULL OR bucket_start < ?3)
+               AND (?4 IS NULL OR provider = ?4)
+               AND (?5 IS NULL OR model = ?5)
+             ORDER BY bucket_start, provider, model",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                granularity.as_str(),
+                query.start,
+                query.end,
+                query.provider,
+                query.model
+            ],
+            |row| {
+                Ok(TelemetryRollup {
+                    granularity,
+                    bucket_start: row.get(0)?,
+                    provider: row.get(1)?,
+                    model: row.get(2)?,
+                    request_count: row.get::<_, i64>(3)? as u64,
+                    success_count: row.get::<_, i64>(4)? as u64,
+                    failed_count: row.get::<_, i64>(5)? as u64,
+                    timeout_count: row.get::<_, i64>(6)? as u64,
+                    total_duration_ms: row.get::<_, i64>(7)? as u64,
+                    input_tokens: row.get::<_, i64>(8)? as u64,
+                    output_tokens: row.get::<_, i64>(9)? as u64,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// 查询原始请求日志（按时间倒序，Provider 无法解析的记录跳过并记录警告）
+    pub fn query_request_logs(
+        conn: &Connection,
+        query: &RollupQuery,
+        limit: usize,
+    ) -> Result<Vec<RequestLog>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, provider, model, status, duration_ms, http_status,
+                    is_streaming, credential_id, retry_count, error_message, user_id
+             FROM telemetry_requests
+             WHERE (?1 IS NULL OR timestamp >= ?1 * 1000)
+               AND (?2 IS NULL OR timestamp < ?2 * 1000)
+               AND (?3 IS NULL OR provider = ?3)
+               AND (?4 IS NULL OR model = ?4)
+             ORDER BY timestamp DESC
+             LIMIT ?5",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                query.start,
+                query.end,
+                query.provider,
+                query.model,
+                limit as i64
+            ],
+            |row| {
+                let id: String = row.get(0)?;
+                let timestamp: i64 = row.get(1)?;
+                let provider: String = row.get(2)?;
+                let status: String = row.get(4)?;
+                let Ok(provider) = provider.parse::<crate::ProviderType>() else {
+                    tracing::warn!(
+                        "[TELEMETRY] 请求日志 {} 的 Provider \"{}\" 无法解析，已跳过",
+                        id,
+                        provider
+                    );
+                    return Ok(None);
+                };
+                Ok(Some(RequestLog {
+                    id,
+                    timestamp: Utc
+                        .timestamp_millis_opt(timestamp)
+                        .single()
+                        .unwrap_or_default(),
+                    provider,
+                    model: row.get(3)?,
+                    duration_ms: row.get::<_, i64>(5)? as u64,
+                    status: parse_status(&status),
+                    http_status: row.get(6)?,
+                    input_tokens: None,
+                    output_tokens: None,
+                    total_tokens: None,
+                    error_message: row.get(10)?,
+                    is_streaming: row.get(7)?,
+                    credential_id: row.get(8)?,
+                    retry_count: row.get(9)?,
+                    context_usage_percentage: None,
+                    user_id: row.get(11)?,
+                }))
+            },
+        )?;
+
+        rows.filter_map(Result::transpose).collect()
+    }
+
+    /// 按终端用户聚合请求数与 Token 用量（按总 Token 数倒序，未携带用户 ID 的记录不计入）
+    pub fn query_usage_by_user(
+        conn: &Connection,
+        query: &RollupQuery,
+        limit: usize,
+    ) -> Result<Vec<UserUsage>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT user_id, SUM(request_count), SUM(success_count), SUM(failed_count),
+                    SUM(input_tokens), SUM(output_tokens)
+             FROM (
+                SELECT user_id, timestamp, provider, model, 1 AS request_count,
+                       status = 'success' AS success_count, status = 'failed' AS failed_count,
+                       0 AS input_tokens, 0 AS output_tokens
+                FROM telemetry_requests
+                UNION ALL
+                SELECT user_id, timestamp, provider, model, 0, 0, 0, input_tokens, output_tokens
+                FROM telemetry_token_usage
+             )
+             WHERE user_id IS NOT NULL
+               AND (?1 IS NULL OR timestamp >= ?1 * 1000)
+               AND (?2 IS NULL OR timestamp < ?2 * 1000)
+               AND (?3 IS NULL OR provider = ?3)
+               AND (?4 IS NULL OR model = ?4)
+             GROUP BY user_id
+             ORDER BY SUM(input_tokens) + SUM(output_tokens) DESC, user_id
+             LIMIT ?5",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                query.start,
+                query.end,
+                query.provider,
+                query.model,
+                limit as i64
+            ],
+            |row| {
+                Ok(UserUsage {
+                    user_id: row.get(0)?,
+                    request_count: row.get::<_, i64>(1)? as u64,
+                    success_count: row.get::<_, i64>(2)? as u64,
+                    failed_count: row.get::<_, i64>(3)? as u64,
+                    input_tokens: row.get::<_, i64>(4)? as u64,
+                    output_tokens: row.get::<_, i64>(5)? as u64,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// 按保留配置清理过期数据
+    pub fn cleanup(
+        conn: &Connection,
+        retention: &TelemetryRetentionConfig,
+        now: DateTime<Utc>,
+    ) -> Result<TelemetryCleanupResult, rusqlite::Error> {
+        let cutoff = |days: u32| now.timestamp() - days as i64 * 86400;
+
+        let raw_cutoff_ms = cutoff(retention.raw_days) * 1000;
+        Ok(TelemetryCleanupResult {
+            requests: conn.execute(
+                "DELETE FROM telemetry_requests WHERE timestamp < ?1",
+                [raw_cutoff_ms],
+            )?,
+            token_usage: conn.execute(
+                "DELETE FROM telemetry_token_usage WHERE timestamp < ?1",
+                [raw_cutoff_ms],
+            )?,
+            hourly_rollups: conn.execute(
+                "DELETE FROM telemetry_rollups WHERE granularity = 'hour' AND bucket_start < ?1",
+                [cutoff(retention.hourly_days)],
+            )?,
+            daily_rollups: conn.execute(
+                "DELETE FROM telemetry_rollups WHERE granularity = 'day' AND bucket_start < ?1",
+                [cutoff(retention.daily_days)],
+            )?,
+        })
+    }
+}
+
+fn parse_status(status: &str) -> RequestStatus {
+    match status {
+        "success" => RequestStatus::Success,
+        "failed" => RequestStatus::Failed,
+        "timeout" => RequestStatus::Timeout,
+        "cancelled" => RequestStatus::Cancelled,
+        _ => RequestStatus::Retrying,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::TokenSource;
+    use crate::ProviderType;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn log_at(id: &str, timestamp: DateTime<Utc>, status: RequestStatus) -> RequestLog {
+        let mut log = RequestLog::new(
+            id.to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+            false,
+        );
+        log.timestamp = timestamp;
+        log.status = status;
+        log.duration_ms = 100;
+        log
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        let ts = Utc.with_ymd_and_hms(2026, 1, 2, 13, 45, 10).unwrap();
+        assert_eq!(
+            RollupGranularity::Hour.bucket_start(&ts),
+            Utc.with_ymd_and_hms(2026, 1, 2, 13, 0, 0)
+                .unwrap()
+                .timestamp()
+        );
+        assert_eq!(
+            RollupGranularity::Day.bucket_start(&ts),
+            Utc.with_ymd_and_hms(2026, 1, 2, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        );
+    }
+
+    #[test]
+    fn test_rollups_accumulate() {
+        let conn = create_test_connection();
+        let ts = Utc.with_ymd_and_hms(2026, 1, 2, 13, 5, 0).unwrap();
+
+        TelemetryDao::insert_request_log(&conn, &log_at("a", ts, RequestStatus::Success)).unwrap();
+        TelemetryDao::insert_request_log(&conn, &log_at("b", ts, RequestStatus::Failed)).unwrap();
+        // 重复 ID 不重复计数
+        TelemetryDao::insert_request_log(&conn, &log_at("a", ts, RequestStatus::Success)).unwrap();
+
+        let mut usage = TokenUsageRecord::new(
+            "t1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+            10,
+            20,
+            TokenSource::Actual,
+        );
+        usage.timestamp = ts;
+        TelemetryDao::insert_token_usage(&conn, &usage).unwrap();
+
+        let hourly =
+            TelemetryDao::query_rollups(&conn, RollupGranularity::Hour, &RollupQuery::default())
+                .unwrap();
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(hourly[0].request_count, 2);
+        assert_eq!(hourly[0].success_count, 1);
+        assert_eq!(hourly[0].failed_count, 1);
+        assert_eq!(hourly[0].total_duration_ms, 200);
+        assert_eq!(hourly[0].input_tokens, 10);
+        assert_eq!(hourly[0].output_tokens, 20);
+
+        let daily =
+            TelemetryDao::query_rollups(&conn, RollupGranularity::Day, &RollupQuery::default())
+                .unwrap();
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].request_count, 2);
+
+        let logs = TelemetryDao::query_request_logs(&conn, &RollupQuery::default(), 10).unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].provider, ProviderType::Kiro);
+    }
+
+    #[test]
+    fn test_query_request_logs_skips_unknown_provider() {
+        let conn = create_test_connection();
+        let ts = Utc.with_ymd_and_hms(2026, 1, 2, 13, 5, 0).unwrap();
+        TelemetryDao::insert_request_log(&conn, &log_at("known", ts, RequestStatus::Success))
+            .unwrap();
+        TelemetryDao::insert_request_log(&conn, &log_at("unknown", ts, RequestStatus::Success))
+            .unwrap();
+        conn.execute(
+            "UPDATE telemetry_requests SET provider = 'retired-provider' WHERE id = 'unknown'",
+            [],
+        )
+        .unwrap();
+
+        let logs = TelemetryDao::query_request_logs(&conn, &RollupQuery::default(), 10).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].id, "known");
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let conn = create_test_connection();
+        let ts = Utc.with_ymd_and_hms(2026, 1, 2, 13, 5, 0).unwrap();
+
+        let mut usage = TokenUsageRecord::new(
+            "t1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+            10,
+            20,
+            TokenSource::Actual,
+        );
+        usage.timestamp = ts;
+        let logs = [
+            log_at("a", ts, RequestStatus::Success),
+            log_at("b", ts, RequestStatus::Failed),
+            // 批内重复 ID 不重复计数
+            log_at("a", ts, RequestStatus::Success),
+        ];
+        TelemetryDao::insert_batch(&conn, &logs, &[usage]).unwrap();
+
+        let hourly =
+            TelemetryDao::query_rollups(&conn, RollupGranularity::Hour, &RollupQuery::default())
+                .unwrap();
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(hourly[0].request_count, 2);
+        assert_eq!(hourly[0].input_tokens, 10);
+        assert_eq!(hourly[0].output_tokens, 20);
+    }
+
+    #[test]
+    fn test_usage_by_user() {
+        let conn = create_test_connection();
+        let ts = Utc.with_ymd_and_hms(2026, 1, 2, 13, 5, 0).unwrap();
+
+        for (id, user_id, status) in [
+            ("a", Some("alice"), RequestStatus::Success),
+            ("b", Some("alice"), RequestStatus::Failed),
+            ("c", Some("bob"), RequestStatus::Success),
+            ("d", None, RequestStatus::Success),
+        ] {
+            let mut log = log_at(id, ts, status);
+            log.user_id = user_id.map(str::to_string);
+            TelemetryDao::insert_request_log(&conn, &log).unwrap();
+
+            let mut usage = TokenUsageRecord::new(
+                format!("t-{id}"),
+                ProviderType::Kiro,
+                "claude-sonnet-4-5".to_string(),
+                10,
+                20,
+                TokenSource::Actual,
+            )
+            .with_user_id(user_id.map(str::to_string));
+            usage.timestamp = ts;
+            TelemetryDao::insert_token_usage(&conn, &usage).unwrap();
+        }
+
+        let users = TelemetryDao::query_usage_by_user(&conn, &RollupQuery::default(), 10).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(
+            users[0],
+            UserUsage {
+                user_id: "alice".to_string(),
+                request_count: 2,
+                success_count: 1,
+                failed_count: 1,
+                input_tokens: 20,
+                output_tokens: 40,
+            }
+        );
+        assert_eq!(users[1].user_id, "bob");
+        assert_eq!(users[1].request_count, 1);
+
+        let logs = TelemetryDao::query_request_logs(&conn, &RollupQuery::default(), 10).unwrap();
+        assert!(logs
+            .iter()
+            .any(|log| log.user_id.as_deref() == Some("alice")));
+    }
+
+    #[test]
+    fn test_cleanup_by_retention() {
+        let conn = create_test_connection();
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+
+        let old = now - chrono::Duration::days(10);
+        let recent = now - chrono::Duration::hours(1);
+        TelemetryDao::insert_request_log(&conn, &log_at("old", old, RequestStatus::Success))
+            .unwrap();
+        TelemetryDao::insert_request_log(&conn, &log_at("new", recent, RequestStatus::Success))
+            .unwrap();
+
+        let retention = TelemetryRetentionConfig {
+            persist: true,
+            raw_days: 7,
+            hourly_days: 7,
+            daily_days: 30,
+        };
+        let result = TelemetryDao::cleanup(&conn, &retention, now).unwrap();
+        assert_eq!(result.requests, 1);
+        assert_eq!(result.hourly_rollups, 1);
+        assert_eq!(result.daily_rollups, 0);
+
+        let daily =
+            TelemetryDao::query_rollups(&conn, RollupGranularity::Day, &RollupQuery::default())
+                .unwrap();
+        assert_eq!(daily.len(), 2);
+    }
+}
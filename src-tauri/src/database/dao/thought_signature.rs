@@ -0,0 +1,113 @@
+//! thoughtSignature 缓存持久化
+//!
+//! 按工具调用 ID 保存签名，启动时加载未过期的签名，淘汰时同步删除。
+
+use rusqlite::{params, Connection};
+
+/// 持久化的签名记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSignature {
+    pub key: String,
+    pub signature: String,
+    /// 写入时间（秒，UTC）
+    pub stored_at: i64,
+}
+
+pub struct ThoughtSignatureDao;
+
+impl ThoughtSignatureDao {
+    /// 写入或覆盖签名
+    pub fn upsert(conn: &Connection, record: &StoredSignature) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO thought_signatures (key, signature, stored_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET
+                signature = excluded.signature,
+                stored_at = excluded.stored_at",
+            params![record.key, record.signature, record.stored_at],
+        )
+        .map(|_| ())
+    }
+
+    /// 删除签名
+    pub fn delete(conn: &Connection, keys: &[String]) -> Result<(), rusqlite::Error> {
+        let mut stmt = conn.prepare("DELETE FROM thought_signatures WHERE key = ?1")?;
+        for key in keys {
+            stmt.execute([key])?;
+        }
+        Ok(())
+    }
+
+    /// 删除过期签名，并只保留最新的 `max_entries` 条，返回删除数量
+    pub fn prune(
+        conn: &Connection,
+        expired_before: i64,
+        max_entries: usize,
+    ) -> Result<usize, rusqlite::Error> {
+        let expired = conn.execute(
+            "DELETE FROM thought_signatures WHERE stored_at < ?1",
+            [expired_before],
+        )?;
+        let overflow = conn.execute(
+            "DELETE FROM thought_signatures WHERE key NOT IN (
+                SELECT key FROM thought_signatures ORDER BY stored_at DESC LIMIT ?1
+            )",
+            [max_entries as i64],
+        )?;
+        Ok(expired + overflow)
+    }
+
+    /// 加载全部签名（按写入时间升序）
+    pub fn load_all(conn: &Connection) -> Result<Vec<StoredSignature>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT key, signature, stored_at FROM thought_signatures ORDER BY stored_at ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(StoredSignature {
+                key: row.get(0)?,
+                signature: row.get(1)?,
+                stored_at: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn record(key: &str, stored_at: i64) -> StoredSignature {
+        StoredSignature {
+            key: key.to_string(),
+            signature: format!("sig-{}", key),
+            stored_at,
+        }
+    }
+
+    #[test]
+    fn test_upsert_and_prune() {
+        let conn = create_test_connection();
+        for (key, stored_at) in [("a", 100), ("b", 200), ("c", 300), ("d", 400)] {
+            ThoughtSignatureDao::upsert(&conn, &record(key, stored_at)).unwrap();
+        }
+        ThoughtSignatureDao::upsert(&conn, &record("a", 500)).unwrap();
+
+        // b 过期，剩余 c/d/a 中只保留最新的 2 条
+        assert_eq!(ThoughtSignatureDao::prune(&conn, 150, 2).unwrap(), 2);
+        let keys: Vec<String> = ThoughtSignatureDao::load_all(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.key)
+            .collect();
+        assert_eq!(keys, vec!["d".to_string(), "a".to_string()]);
+
+        ThoughtSignatureDao::delete(&conn, &["d".to_string()]).unwrap();
+        assert_eq!(ThoughtSignatureDao::load_all(&conn).unwrap().len(), 1);
+    }
+}
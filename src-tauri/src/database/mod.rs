@@ -1,6 +1,8 @@
+pub mod credential_storage;
 pub mod dao;
 pub mod migration;
 pub mod schema;
+pub mod schema_migrations;
 pub mod system_providers;
 
 use rusqlite::Connection;
@@ -27,8 +29,15 @@ pub fn init_database() -> Result<DbConnection, String> {
     conn.busy_timeout(std::time::Duration::from_secs(5))
         .map_err(|e| format!("设置 busy_timeout 失败: {}", e))?;
 
-    // 创建表结构
-    schema::create_tables(&conn).map_err(|e| e.to_string())?;
+    // 按版本升级表结构（数据库版本高于程序支持的版本时拒绝启动）
+    let applied = schema_migrations::run_migrations(&conn)?;
+    if applied > 0 {
+        tracing::info!(
+            "[数据库] 表结构已升级到 v{}（本次应用 {} 个迁移）",
+            schema_migrations::latest_version(),
+            applied
+        );
+    }
     migration::migrate_from_json(&conn)?;
 
     // 执行 API Keys 到 Provider Pool 的迁移
@@ -57,3 +66,15 @@ pub fn init_database() -> Result<DbConnection, String> {
 
     Ok(Arc::new(Mutex::new(conn)))
 }
+
+/// 按配置注册凭证池存储后端（修改后需重启）
+pub fn init_credential_storage(config: &crate::config::CredentialStorageConfig) {
+    use crate::config::CredentialStorageBackend;
+    use credential_storage::{CredentialStorage, MemoryCredentialStorage};
+
+    let storage: Option<Arc<dyn CredentialStorage>> = match config.backend {
+        CredentialStorageBackend::Sqlite => None,
+        CredentialStorageBackend::Memory => Some(Arc::new(MemoryCredentialStorage::new())),
+    };
+    dao::provider_pool::ProviderPoolDao::set_storage(storage);
+}
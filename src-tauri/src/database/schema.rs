@@ -591,6 +591,72 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // ============================================================================
+    // 遥测相关表
+    // ============================================================================
+
+    // 原始请求日志表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS telemetry_requests (
+            id TEXT PRIMARY KEY,
+            timestamp INTEGER NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            status TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            http_status INTEGER,
+            is_streaming INTEGER NOT NULL DEFAULT 0,
+            credential_id TEXT,
+            retry_count INTEGER NOT NULL DEFAULT 0,
+            error_message TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_telemetry_requests_timestamp ON telemetry_requests(timestamp)",
+        [],
+    )?;
+
+    // 原始 Token 使用记录表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS telemetry_token_usage (
+            id TEXT PRIMARY KEY,
+            request_id TEXT,
+            timestamp INTEGER NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            source TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_telemetry_token_usage_timestamp ON telemetry_token_usage(timestamp)",
+        [],
+    )?;
+
+    // 按小时 / 按天汇总表（写入原始记录时增量更新）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS telemetry_rollups (
+            granularity TEXT NOT NULL,
+            bucket_start INTEGER NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            request_count INTEGER NOT NULL DEFAULT 0,
+            success_count INTEGER NOT NULL DEFAULT 0,
+            failed_count INTEGER NOT NULL DEFAULT 0,
+            timeout_count INTEGER NOT NULL DEFAULT 0,
+            total_duration_ms INTEGER NOT NULL DEFAULT 0,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (granularity, bucket_start, provider, model)
+        )",
+        [],
+    )?;
+
     Ok(())
 }
 
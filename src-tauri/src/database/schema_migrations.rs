@@ -0,0 +1,372 @@
+//! 数据库版本化迁移
+//!
+//! - `schema_version` 表记录已应用的迁移版本
+//! - 启动时按版本号顺序执行未应用的迁移
+//! - 数据库版本高于当前程序支持的版本时拒绝启动，避免旧版本程序破坏新版本的数据
+//!
+//! 新增表或修改表结构时，在 `MIGRATIONS` 末尾追加迁移；已发布的迁移不可修改。
+
+use super::schema;
+use rusqlite::{params, Connection};
+
+/// 单个迁移
+pub struct Migration {
+    /// 版本号（严格递增）
+    pub version: u32,
+    /// 迁移名称（记录到 schema_version 表）
+    pub name: &'static str,
+    /// 是否在事务中执行（自行管理事务的迁移需设为 false）
+    pub transactional: bool,
+    /// 迁移内容
+    pub up: fn(&Connection) -> Result<(), rusqlite::Error>,
+}
+
+/// 已注册的迁移
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        // create_tables 中的历史迁移会自行开启事务
+        transactional: false,
+        up: schema::create_tables,
+    },
+    Migration {
+        version: 2,
+        name: "credential_expiry_tracking",
+        transactional: true,
+        up: add_credential_expiry_columns,
+    },
+    Migration {
+        version: 3,
+        name: "thought_signatures",
+        transactional: true,
+        up: create_thought_signatures_table,
+    },
+    Migration {
+        version: 4,
+        name: "credential_tiers",
+        transactional: true,
+        up: add_credential_tier_column,
+    },
+    Migration {
+        version: 5,
+        name: "credential_schedule",
+        transactional: true,
+        up: add_credential_schedule_columns,
+    },
+    Migration {
+        version: 6,
+        name: "learned_unsupported_models",
+        transactional: true,
+        up: add_learned_unsupported_models_column,
+    },
+    Migration {
+        version: 7,
+        name: "credential_tags",
+        transactional: true,
+        up: add_credential_tags_column,
+    },
+    Migration {
+        version: 8,
+        name: "credential_pinned_model",
+        transactional: true,
+        up: add_credential_pinned_model_column,
+    },
+    Migration {
+        version: 9,
+        name: "telemetry_user_id",
+        transactional: true,
+        up: add_telemetry_user_id_columns,
+    },
+];
+
+/// v2：记录最近一次成功刷新时间和 Refresh Token 过期时间
+fn add_credential_expiry_columns(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN last_successful_refresh TEXT;
+         ALTER TABLE provider_pool_credentials ADD COLUMN refresh_token_expiry TEXT;",
+    )
+}
+
+/// v3：thoughtSignature 缓存（按工具调用 ID）
+fn create_thought_signatures_table(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS thought_signatures (
+            key TEXT PRIMARY KEY,
+            signature TEXT NOT NULL,
+            stored_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_thought_signatures_stored_at ON thought_signatures(stored_at);",
+    )
+}
+
+/// v4：凭证优先级分层（主力/溢出）
+fn add_credential_tier_column(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN tier TEXT DEFAULT 'primary'",
+        [],
+    )
+    .map(|_| ())
+}
+
+/// v5：凭证调度规则和当日请求计数
+fn add_credential_schedule_columns(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN schedule TEXT;
+         ALTER TABLE provider_pool_credentials ADD COLUMN daily_usage_count INTEGER DEFAULT 0;
+         ALTER TABLE provider_pool_credentials ADD COLUMN daily_usage_reset_at INTEGER;",
+    )
+}
+
+/// v6：根据上游错误自动学习的不支持模型
+fn add_learned_unsupported_models_column(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN learned_unsupported_models TEXT",
+        [],
+    )
+    .map(|_| ())
+}
+
+/// v7：凭证标签
+fn add_credential_tags_column(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN tags TEXT",
+        [],
+    )
+    .map(|_| ())
+}
+
+/// v8：凭证固定模型
+fn add_credential_pinned_model_column(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN pinned_model TEXT",
+        [],
+    )
+    .map(|_| ())
+}
+
+/// v9：终端用户 ID（按下游用户统计用量）
+fn add_telemetry_user_id_columns(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE telemetry_requests ADD COLUMN user_id TEXT;
+         ALTER TABLE telemetry_token_usage ADD COLUMN user_id TEXT;
+         CREATE INDEX IF NOT EXISTS idx_telemetry_token_usage_user ON telemetry_token_usage(user_id);",
+    )
+}
+
+/// 当前程序支持的最高版本
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// 读取数据库当前版本（未迁移过的数据库为 0）
+pub fn current_version(conn: &Connection) -> Result<u32, String> {
+    ensure_version_table(conn)?;
+    conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+        row.get::<_, Option<u32>>(0)
+    })
+    .map(|v| v.unwrap_or(0))
+    .map_err(|e| format!("读取数据库版本失败: {}", e))
+}
+
+/// 执行所有未应用的迁移，返回本次应用的迁移数量
+pub fn run_migrations(conn: &Connection) -> Result<usize, String> {
+    run_migrations_with(conn, MIGRATIONS)
+}
+
+/// 按给定的迁移列表升级数据库
+pub fn run_migrations_with(conn: &Connection, migrations: &[Migration]) -> Result<usize, String> {
+    if let Some(pair) = migrations.windows(2).find(|w| w[0].version >= w[1].version) {
+        return Err(format!(
+            "迁移版本号必须严格递增: {} -> {}",
+            pair[0].version, pair[1].version
+        ));
+    }
+
+    let current = current_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(format!(
+            "数据库版本 ({}) 高于当前程序支持的版本 ({})，请升级 ProxyCast 后再启动",
+            current, latest
+        ));
+    }
+
+    let mut applied = 0;
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        apply(conn, migration).map_err(|e| {
+            format!(
+                "数据库迁移 v{} ({}) 失败: {}",
+                migration.version, migration.name, e
+            )
+        })?;
+        tracing::info!(
+            "[数据库] 已应用迁移 v{} ({})",
+            migration.version,
+            migration.name
+        );
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+fn ensure_version_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("创建 schema_version 表失败: {}", e))
+}
+
+fn apply(conn: &Connection, migration: &Migration) -> Result<(), rusqlite::Error> {
+    let record = |conn: &Connection| {
+        conn.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, ?2, ?3)",
+            params![
+                migration.version,
+                migration.name,
+                chrono::Utc::now().timestamp()
+            ],
+        )
+    };
+
+    if migration.transactional {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        record(&tx)?;
+        tx.commit()
+    } else {
+        (migration.up)(conn)?;
+        record(conn).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_a(conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute("CREATE TABLE a (id INTEGER PRIMARY KEY)", [])
+            .map(|_| ())
+    }
+
+    fn create_b(conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute("CREATE TABLE b (id INTEGER PRIMARY KEY)", [])
+            .map(|_| ())
+    }
+
+    fn create_c_then_fail(conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute("CREATE TABLE c (id INTEGER PRIMARY KEY)", [])?;
+        conn.execute("INSERT INTO missing_table VALUES (1)", [])
+            .map(|_| ())
+    }
+
+    /// 表中是否存在指定列
+    fn has_column(conn: &Connection, table: &str, column: &str) -> bool {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap();
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        columns.iter().any(|c| c == column)
+    }
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [name],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    const V1: Migration = Migration {
+        version: 1,
+        name: "create_a",
+        transactional: true,
+        up: create_a,
+    };
+    const V2: Migration = Migration {
+        version: 2,
+        name: "create_b",
+        transactional: true,
+        up: create_b,
+    };
+
+    #[test]
+    fn test_builtin_migrations_on_fresh_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        assert_eq!(run_migrations(&conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert!(table_exists(&conn, "provider_pool_credentials"));
+        assert!(has_column(&conn, "telemetry_token_usage", "user_id"));
+        // 再次启动不会重复执行
+        assert_eq!(run_migrations(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_telemetry_user_id_migration_upgrades_v8_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_version_table(&conn).unwrap();
+        let v8: Vec<_> = MIGRATIONS.iter().filter(|m| m.version <= 8).collect();
+        for migration in &v8 {
+            apply(&conn, migration).unwrap();
+        }
+        assert!(!has_column(&conn, "telemetry_requests", "user_id"));
+
+        assert_eq!(run_migrations(&conn).unwrap(), MIGRATIONS.len() - v8.len());
+        assert!(has_column(&conn, "telemetry_requests", "user_id"));
+        assert!(has_column(&conn, "telemetry_token_usage", "user_id"));
+    }
+
+    #[test]
+    fn test_applies_pending_migrations_in_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(run_migrations_with(&conn, &[V1]).unwrap(), 1);
+        assert!(table_exists(&conn, "a"));
+        assert!(!table_exists(&conn, "b"));
+
+        assert_eq!(run_migrations_with(&conn, &[V1, V2]).unwrap(), 1);
+        assert!(table_exists(&conn, "b"));
+        assert_eq!(current_version(&conn).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_downgrade_guard() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations_with(&conn, &[V1, V2]).unwrap();
+        let err = run_migrations_with(&conn, &[V1]).unwrap_err();
+        assert!(err.contains("高于当前程序支持的版本"));
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        let failing = Migration {
+            version: 2,
+            name: "create_c_then_fail",
+            transactional: true,
+            up: create_c_then_fail,
+        };
+        assert!(run_migrations_with(&conn, &[V1, failing]).is_err());
+        assert!(!table_exists(&conn, "c"));
+        assert_eq!(current_version(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_rejects_unordered_migrations() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(run_migrations_with(&conn, &[V2, V1]).is_err());
+        assert_eq!(current_version(&conn).unwrap(), 0);
+    }
+}
@@ -31,14 +31,14 @@ pub async fn handle_command(
         }
 
         "get_default_provider" => {
-            let provider = state.default_provider.read().await.clone();
+            let provider = state.processor.snapshot().default_provider.clone();
             // 直接返回字符串值，不是对象
             Ok(serde_json::json!(provider))
         }
 
         "get_endpoint_providers" => {
-            let providers = state.endpoint_providers.read().await;
-            Ok(serde_json::to_value(&*providers)?)
+            let snapshot = state.processor.snapshot();
+            Ok(serde_json::to_value(&snapshot.endpoint_providers)?)
         }
 
         // ========== P0 - 服务器状态 ==========
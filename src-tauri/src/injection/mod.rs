@@ -2,12 +2,17 @@
 //!
 //! 提供请求参数注入功能，支持：
 //! - 模型通配符匹配规则
-//! - merge 和 override 两种注入模式
+//! - Provider、客户端类型、请求路径、请求头和消息内容正则等附加条件
+//! - merge 和 override 两种注入模式，以及移除参数、数值上限
 //! - 规则优先级排序
 
 mod types;
 
-pub use types::{InjectionConfig, InjectionMode, InjectionResult, InjectionRule, Injector};
+pub(crate) use types::pattern_matches;
+pub use types::{
+    InjectionConditions, InjectionConfig, InjectionContext, InjectionMode, InjectionResult,
+    InjectionRule, Injector,
+};
 
 #[cfg(test)]
 mod tests;
@@ -224,3 +224,107 @@ mod injector_tests {
         assert!(matches.iter().any(|r| r.id == "r3"));
     }
 }
+
+#[cfg(test)]
+mod condition_tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn payload() -> serde_json::Value {
+        json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 64000,
+            "temperature": 1.0,
+            "messages": [
+                {"role": "user", "content": [{"type": "text", "text": "请帮我 review 这段代码"}]}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_conditions_match_request_context() {
+        let conditions = InjectionConditions {
+            providers: vec!["kiro".to_string()],
+            app_types: vec!["claude_code".to_string()],
+            paths: vec!["*/messages".to_string()],
+            headers: BTreeMap::from([("X-Team".to_string(), "infra-*".to_string())]),
+            message_regex: Some("(?i)review".to_string()),
+        };
+        let injector = Injector::with_rules(vec![InjectionRule::new(
+            "r1",
+            "claude-*",
+            json!({"top_p": 0.9}),
+        )
+        .with_conditions(conditions)]);
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-team", "infra-core".parse().unwrap());
+        let ctx = InjectionContext::new("claude-sonnet-4-5")
+            .with_provider("kiro")
+            .with_app_type("claude_code")
+            .with_path("/v1/messages")
+            .with_headers(&headers);
+
+        let mut request = payload();
+        let result = injector.inject_with_context(&ctx, &mut request);
+        assert_eq!(result.applied_rules, vec!["r1".to_string()]);
+        assert_eq!(request["top_p"], 0.9);
+
+        // 任一条件不满足时规则不生效
+        for ctx in [
+            ctx.clone().with_provider("gemini"),
+            ctx.clone().with_path("/v1/chat/completions"),
+            ctx.clone().with_headers(&axum::http::HeaderMap::new()),
+            InjectionContext::new("claude-sonnet-4-5"),
+        ] {
+            let mut request = payload();
+            assert!(!injector
+                .inject_with_context(&ctx, &mut request)
+                .has_injections());
+        }
+
+        let mut request = payload();
+        request["messages"][0]["content"] = json!("你好");
+        assert!(!injector
+            .inject_with_context(&ctx, &mut request)
+            .has_injections());
+    }
+
+    #[test]
+    fn test_remove_and_cap_parameters() {
+        let injector = Injector::with_rules(vec![InjectionRule::new("r1", "*", json!({}))
+            .with_remove(vec!["temperature".to_string(), "messages".to_string()])
+            .with_cap("max_tokens", 32000.0)
+            .with_cap("top_p", 0.5)]);
+
+        let mut request = payload();
+        let result = injector.inject("claude-sonnet-4-5", &mut request);
+
+        assert!(request.get("temperature").is_none());
+        // 核心参数不允许移除
+        assert!(request.get("messages").is_some());
+        // 整数参数保持整数类型；不存在的参数不受影响
+        assert_eq!(request["max_tokens"], json!(32000));
+        assert!(request.get("top_p").is_none());
+        assert_eq!(result.removed_params, vec!["temperature".to_string()]);
+        assert_eq!(result.capped_params, vec!["max_tokens".to_string()]);
+        assert!(result.has_injections());
+    }
+
+    #[test]
+    fn test_invalid_message_regex_disables_rule() {
+        let conditions = InjectionConditions {
+            message_regex: Some("(unclosed".to_string()),
+            ..Default::default()
+        };
+        let mut injector = Injector::new();
+        injector.add_rule(
+            InjectionRule::new("r1", "*", json!({"top_k": 40})).with_conditions(conditions),
+        );
+
+        let mut request = payload();
+        assert!(!injector
+            .inject("claude-sonnet-4-5", &mut request)
+            .has_injections());
+    }
+}
@@ -3,6 +3,7 @@
 //! 定义注入规则、注入模式和注入器
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// 允许注入的参数白名单
 /// 这些参数是安全的，不会影响请求的核心行为
@@ -39,6 +40,113 @@ pub enum InjectionMode {
     Override,
 }
 
+/// 规则生效条件
+///
+/// 所有已配置的条件同时满足时规则才生效；列表类条件满足任一项即可。
+/// 请求缺少条件所需的信息（如 WebSocket 请求没有请求头）时视为不满足。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct InjectionConditions {
+    /// Provider（支持通配符）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    /// 客户端类型（cursor / claude_code / codex / windsurf / kiro / other）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub app_types: Vec<String>,
+    /// 请求路径（支持通配符）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// 请求头名称 -> 值（名称不区分大小写，值支持通配符，需全部满足）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// 消息内容正则（任一消息文本匹配即可）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_regex: Option<String>,
+}
+
+impl InjectionConditions {
+    /// 是否未配置任何条件
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+            && self.app_types.is_empty()
+            && self.paths.is_empty()
+            && self.headers.is_empty()
+            && self.message_regex.is_none()
+    }
+
+    /// 检查请求上下文是否满足条件（不含消息内容正则）
+    fn matches_context(&self, ctx: &InjectionContext) -> bool {
+        let any_matches = |patterns: &[String], value: Option<&str>| {
+            patterns.is_empty()
+                || value.is_some_and(|v| patterns.iter().any(|p| pattern_matches(p, v)))
+        };
+        any_matches(&self.providers, ctx.provider.as_deref())
+            && any_matches(&self.app_types, ctx.app_type.as_deref())
+            && any_matches(&self.paths, ctx.path.as_deref())
+            && self.headers.iter().all(|(name, pattern)| {
+                ctx.headers
+                    .get(&name.to_lowercase())
+                    .is_some_and(|v| pattern_matches(pattern, v))
+            })
+    }
+}
+
+/// 注入上下文：规则条件匹配所需的请求信息
+#[derive(Debug, Clone, Default)]
+pub struct InjectionContext {
+    /// 模型名称
+    pub model: String,
+    /// Provider
+    pub provider: Option<String>,
+    /// 客户端类型
+    pub app_type: Option<String>,
+    /// 请求路径
+    pub path: Option<String>,
+    /// 请求头（名称为小写）
+    pub headers: HashMap<String, String>,
+}
+
+impl InjectionContext {
+    /// 创建只包含模型名称的上下文
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// 设置 Provider
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    /// 设置客户端类型
+    pub fn with_app_type(mut self, app_type: impl Into<String>) -> Self {
+        self.app_type = Some(app_type.into());
+        self
+    }
+
+    /// 设置请求路径
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// 设置请求头（忽略非 UTF-8 的值）
+    pub fn with_headers(mut self, headers: &axum::http::HeaderMap) -> Self {
+        self.headers = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (name.as_str().to_string(), v.to_string()))
+            })
+            .collect();
+        self
+    }
+}
+
 /// 注入规则
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InjectionRule {
@@ -57,6 +165,15 @@ pub struct InjectionRule {
     /// 是否启用
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// 生效条件（模型之外的附加条件）
+    #[serde(default, skip_serializing_if = "InjectionConditions::is_empty")]
+    pub conditions: InjectionConditions,
+    /// 要移除的参数
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+    /// 参数上限：数值参数超过上限时截断为上限
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub caps: BTreeMap<String, f64>,
 }
 
 fn default_priority() -> i32 {
@@ -77,6 +194,9 @@ impl InjectionRule {
             mode: InjectionMode::Merge,
             priority: default_priority(),
             enabled: true,
+            conditions: InjectionConditions::default(),
+            remove: Vec::new(),
+            caps: BTreeMap::new(),
         }
     }
 
@@ -92,6 +212,24 @@ impl InjectionRule {
         self
     }
 
+    /// 设置生效条件
+    pub fn with_conditions(mut self, conditions: InjectionConditions) -> Self {
+        self.conditions = conditions;
+        self
+    }
+
+    /// 设置要移除的参数
+    pub fn with_remove(mut self, params: Vec<String>) -> Self {
+        self.remove = params;
+        self
+    }
+
+    /// 设置参数上限
+    pub fn with_cap(mut self, param: &str, max: f64) -> Self {
+        self.caps.insert(param.to_string(), max);
+        self
+    }
+
     /// 检查模型是否匹配此规则
     ///
     /// 支持的通配符模式：
@@ -139,6 +277,12 @@ pub struct InjectionResult {
     pub applied_rules: Vec<String>,
     /// 注入的参数名列表
     pub injected_params: Vec<String>,
+    /// 移除的参数名列表
+    #[serde(default)]
+    pub removed_params: Vec<String>,
+    /// 被截断到上限的参数名列表
+    #[serde(default)]
+    pub capped_params: Vec<String>,
 }
 
 impl InjectionResult {
@@ -147,9 +291,11 @@ impl InjectionResult {
         Self::default()
     }
 
-    /// 检查是否有注入
+    /// 检查是否修改了请求
     pub fn has_injections(&self) -> bool {
         !self.injected_params.is_empty()
+            || !self.removed_params.is_empty()
+            || !self.capped_params.is_empty()
     }
 }
 
@@ -169,22 +315,30 @@ pub struct InjectionConfig {
 pub struct Injector {
     /// 注入规则列表（已排序）
     rules: Vec<InjectionRule>,
+    /// 规则 ID -> 已编译的消息内容正则（正则无效时为 None，规则不生效）
+    message_patterns: HashMap<String, Option<regex::Regex>>,
 }
 
 impl Injector {
     /// 创建新的注入器
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self::default()
     }
 
     /// 从规则列表创建注入器
-    pub fn with_rules(mut rules: Vec<InjectionRule>) -> Self {
-        rules.sort();
-        Self { rules }
+    pub fn with_rules(rules: Vec<InjectionRule>) -> Self {
+        let mut injector = Self::new();
+        for rule in rules {
+            injector.compile_message_pattern(&rule);
+            injector.rules.push(rule);
+        }
+        injector.rules.sort();
+        injector
     }
 
     /// 添加规则
     pub fn add_rule(&mut self, rule: InjectionRule) {
+        self.compile_message_pattern(&rule);
         self.rules.push(rule);
         self.rules.sort();
     }
@@ -192,18 +346,36 @@ impl Injector {
     /// 移除规则
     pub fn remove_rule(&mut self, id: &str) -> Option<InjectionRule> {
         if let Some(pos) = self.rules.iter().position(|r| r.id == id) {
+            self.message_patterns.remove(id);
             Some(self.rules.remove(pos))
         } else {
             None
         }
     }
 
+    fn compile_message_pattern(&mut self, rule: &InjectionRule) {
+        let Some(pattern) = &rule.conditions.message_regex else {
+            self.message_patterns.remove(&rule.id);
+            return;
+        };
+        let compiled = regex::Regex::new(pattern)
+            .map_err(|e| {
+                tracing::warn!(
+                    "[INJECTION] 规则 {} 的消息正则无效，规则不会生效: {}",
+                    rule.id,
+                    e
+                )
+            })
+            .ok();
+        self.message_patterns.insert(rule.id.clone(), compiled);
+    }
+
     /// 获取所有规则
     pub fn rules(&self) -> &[InjectionRule] {
         &self.rules
     }
 
-    /// 获取匹配的规则
+    /// 获取匹配的规则（只按模型匹配，不检查附加条件）
     pub fn matching_rules(&self, model: &str) -> Vec<&InjectionRule> {
         self.rules.iter().filter(|r| r.matches(model)).collect()
     }
@@ -211,16 +383,50 @@ impl Injector {
     /// 清空所有规则
     pub fn clear(&mut self) {
         self.rules.clear();
+        self.message_patterns.clear();
     }
 
-    /// 注入参数到请求
-    ///
-    /// 按规则优先级顺序应用注入：
-    /// - Merge 模式：不覆盖已有参数
-    /// - Override 模式：覆盖已有参数
+    /// 检查规则是否适用于请求（模型、附加条件和消息内容）
+    fn rule_applies(
+        &self,
+        rule: &InjectionRule,
+        ctx: &InjectionContext,
+        payload: &serde_json::Value,
+    ) -> bool {
+        if !rule.matches(&ctx.model) || !rule.conditions.matches_context(ctx) {
+            return false;
+        }
+        match self.message_patterns.get(&rule.id) {
+            Some(Some(re)) => message_texts(payload).iter().any(|text| re.is_match(text)),
+            Some(None) => false,
+            None => true,
+        }
+    }
+
+    /// 注入参数到请求（只按模型匹配规则）
     pub fn inject(&self, model: &str, payload: &mut serde_json::Value) -> InjectionResult {
+        self.inject_with_context(&InjectionContext::new(model), payload)
+    }
+
+    /// 按请求上下文注入参数
+    ///
+    /// 按规则优先级顺序应用，每条规则依次：
+    /// - 移除 `remove` 中的参数
+    /// - 注入参数：Merge 模式不覆盖已有参数，Override 模式覆盖已有参数
+    /// - 将超过 `caps` 上限的数值参数截断为上限
+    pub fn inject_with_context(
+        &self,
+        ctx: &InjectionContext,
+        payload: &mut serde_json::Value,
+    ) -> InjectionResult {
         let mut result = InjectionResult::new();
 
+        let rules: Vec<&InjectionRule> = self
+            .rules
+            .iter()
+            .filter(|r| self.rule_applies(r, ctx, payload))
+            .collect();
+
         // 确保 payload 是对象
         let obj = match payload.as_object_mut() {
             Some(obj) => obj,
@@ -228,14 +434,25 @@ impl Injector {
         };
 
         // 按优先级顺序应用匹配的规则
-        for rule in self.matching_rules(model) {
-            let params = match rule.parameters.as_object() {
-                Some(params) => params,
-                None => continue,
-            };
-
+        for rule in rules {
             let mut rule_applied = false;
 
+            for key in &rule.remove {
+                if BLOCKED_OVERRIDE_PARAMS.contains(&key.as_str()) {
+                    tracing::warn!("[INJECTION] 参数 {} 禁止移除", key);
+                    continue;
+                }
+                if obj.remove(key).is_some() {
+                    if !result.removed_params.contains(key) {
+                        result.removed_params.push(key.clone());
+                    }
+                    rule_applied = true;
+                }
+            }
+
+            let empty = serde_json::Map::new();
+            let params = rule.parameters.as_object().unwrap_or(&empty);
+
             for (key, value) in params {
                 // 安全修复：检查参数是否在白名单中
                 if !ALLOWED_INJECTION_PARAMS.contains(&key.as_str()) {
@@ -265,6 +482,29 @@ impl Injector {
                 }
             }
 
+            for (key, max) in &rule.caps {
+                if !ALLOWED_INJECTION_PARAMS.contains(&key.as_str()) {
+                    tracing::warn!("[INJECTION] 参数 {} 不在白名单中，跳过上限限制", key);
+                    continue;
+                }
+                let Some(current) = obj.get(key).and_then(|v| v.as_f64()) else {
+                    continue;
+                };
+                if current > *max {
+                    // 整数参数（如 max_tokens）保持整数类型
+                    let capped = if obj[key].is_f64() {
+                        serde_json::json!(max)
+                    } else {
+                        serde_json::json!(max.floor() as i64)
+                    };
+                    obj.insert(key.clone(), capped);
+                    if !result.capped_params.contains(key) {
+                        result.capped_params.push(key.clone());
+                    }
+                    rule_applied = true;
+                }
+            }
+
             if rule_applied {
                 result.applied_rules.push(rule.id.clone());
             }
@@ -274,6 +514,36 @@ impl Injector {
     }
 }
 
+/// 提取请求中的消息文本（system、messages、prompt）
+fn message_texts(payload: &serde_json::Value) -> Vec<&str> {
+    fn collect<'a>(content: &'a serde_json::Value, texts: &mut Vec<&'a str>) {
+        match content {
+            serde_json::Value::String(s) => texts.push(s),
+            serde_json::Value::Array(parts) => texts.extend(
+                parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(|t| t.as_str())),
+            ),
+            _ => {}
+        }
+    }
+
+    let mut texts = Vec::new();
+    for key in ["system", "prompt"] {
+        if let Some(content) = payload.get(key) {
+            collect(content, &mut texts);
+        }
+    }
+    if let Some(messages) = payload.get("messages").and_then(|m| m.as_array()) {
+        for message in messages {
+            if let Some(content) = message.get("content") {
+                collect(content, &mut texts);
+            }
+        }
+    }
+    texts
+}
+
 /// 检查模式是否匹配模型名
 ///
 /// 支持的通配符模式：
@@ -281,7 +551,7 @@ impl Injector {
 /// - 前缀匹配: `claude-*`
 /// - 后缀匹配: `*-preview`
 /// - 包含匹配: `*flash*`
-fn pattern_matches(pattern: &str, model: &str) -> bool {
+pub(crate) fn pattern_matches(pattern: &str, model: &str) -> bool {
     if !pattern.contains('*') {
         return pattern == model;
     }
@@ -1,6 +1,7 @@
 //! ProxyCast - AI API 代理服务
 //!
 //! 这是一个 Tauri 应用，提供 AI API 的代理和管理功能。
+//! 关闭默认的 `desktop` 特性时不编译窗口、托盘和前端命令，只保留无界面服务器。
 
 // 抑制 objc crate 宏内部的 unexpected_cfgs 警告
 // 该警告来自 cocoa/objc 依赖的 msg_send! 宏，是已知的 issue
@@ -11,18 +12,24 @@ pub mod agent;
 pub mod app;
 pub mod backends;
 pub mod browser_interceptor;
+pub mod compaction;
 pub mod connect;
+pub mod convert;
 pub mod credential;
 pub mod database;
 pub mod flow_monitor;
+pub mod guardrails;
 pub mod injection;
 pub mod middleware;
 pub mod orchestrator;
 pub mod plugin;
+pub mod postprocess;
 pub mod processor;
 pub mod proxy;
+pub mod recording;
 pub mod resilience;
 pub mod router;
+#[cfg(feature = "desktop")]
 pub mod screenshot;
 pub mod services;
 pub mod session;
@@ -30,12 +37,16 @@ pub mod session_files;
 pub mod stream;
 pub mod streaming;
 pub mod telemetry;
+#[cfg(feature = "desktop")]
 pub mod terminal;
 pub mod translator;
+#[cfg(feature = "desktop")]
 pub mod tray;
+pub mod usage;
 pub mod websocket;
 
 // 内部模块
+#[cfg(feature = "desktop")]
 mod commands;
 mod config;
 mod converter;
@@ -44,13 +55,17 @@ mod data;
 mod dev_bridge;
 mod logger;
 mod models;
+mod oauth;
 mod providers;
 mod server;
 mod server_utils;
 
 // 重新导出核心类型以保持向后兼容
-pub use app::{AppState, LogState, ProviderType, TokenCacheServiceState, TrayManagerState};
+#[cfg(feature = "desktop")]
+pub use app::TrayManagerState;
+pub use app::{AppState, LogState, ProviderType, TokenCacheServiceState};
 pub use services::provider_pool_service::ProviderPoolService;
 
 // 重新导出 run 函数
+#[cfg(feature = "desktop")]
 pub use app::run;
@@ -1,7 +1,9 @@
 //! 日志管理模块
+use crate::config::{PiiDetector, PiiMaskingConfig};
 use chrono::{Duration, Local, Utc};
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use once_cell::sync::{Lazy, OnceCell};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -9,7 +11,12 @@ use std::fs::{self, OpenOptions};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
 
 #[derive(Debug, Clone)]
 pub struct LogStoreConfig {
@@ -35,6 +42,59 @@ pub struct LogEntry {
     pub timestamp: String,
     pub level: String,
     pub message: String,
+    /// 所属请求 ID（在代理请求处理过程中记录时）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// 原始响应转储文件名中的请求 ID（客户端传入的 ID 可能包含路径分隔符）
+fn raw_response_file_id(request_id: &str) -> String {
+    request_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 日志级别排序（debug < info < warn < error），未知级别视为 info
+fn level_rank(level: &str) -> u8 {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" | "debug" => 0,
+        "warn" | "warning" => 2,
+        "error" => 3,
+        _ => 1,
+    }
+}
+
+/// 日志过滤条件（实时日志流使用）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    /// 最低日志级别（debug / info / warn / error）
+    pub level: Option<String>,
+    /// 关键字（不区分大小写）
+    pub keyword: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(level) = &self.level {
+            if level_rank(&entry.level) < level_rank(level) {
+                return false;
+            }
+        }
+        match &self.keyword {
+            Some(keyword) if !keyword.is_empty() => entry
+                .message
+                .to_lowercase()
+                .contains(&keyword.to_lowercase()),
+            _ => true,
+        }
+    }
 }
 
 pub struct LogStore {
@@ -42,6 +102,8 @@ pub struct LogStore {
     max_logs: usize,
     config: LogStoreConfig,
     log_file_path: Option<PathBuf>,
+    /// 新日志广播（实时日志流订阅）
+    sender: broadcast::Sender<LogEntry>,
 }
 
 impl Default for LogStore {
@@ -58,12 +120,14 @@ impl Default for LogStore {
         let log_file = log_dir.join("proxycast.log");
 
         let config = LogStoreConfig::default();
+        let (sender, _) = broadcast::channel(256);
 
         Self {
             logs: VecDeque::new(),
             max_logs: config.max_logs,
             config,
             log_file_path: Some(log_file),
+            sender,
         }
     }
 }
@@ -82,22 +146,34 @@ impl LogStore {
     }
 
     pub fn add(&mut self, level: &str, message: &str) {
-        let sanitized = sanitize_log_message(message);
+        let sanitized = mask_pii(&sanitize_log_message(message));
         let now = Utc::now();
         let entry = LogEntry {
             timestamp: now.to_rfc3339(),
             level: level.to_string(),
             message: sanitized.clone(),
+            request_id: crate::middleware::current_request_id(),
         };
 
         self.logs.push_back(entry.clone());
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.sender.send(entry.clone());
 
         // 写入日志文件
         if self.config.enable_file_logging {
             if let Some(ref path) = self.log_file_path {
                 self.rotate_log_file_if_needed(path);
                 let local_time = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-                let log_line = format!("{} [{}] {}\n", local_time, level.to_uppercase(), sanitized);
+                let log_line = match &entry.request_id {
+                    Some(request_id) => format!(
+                        "{} [{}] [{}] {}\n",
+                        local_time,
+                        level.to_uppercase(),
+                        request_id,
+                        sanitized
+                    ),
+                    None => format!("{} [{}] {}\n", local_time, level.to_uppercase(), sanitized),
+                };
 
                 if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
                     let _ = file.write_all(log_line.as_bytes());
@@ -116,8 +192,11 @@ impl LogStore {
     pub fn log_raw_response(&self, request_id: &str, body: &str) {
         if let Some(ref log_path) = self.log_file_path {
             let log_dir = log_path.parent().unwrap_or(std::path::Path::new("."));
-            let raw_file = log_dir.join(format!("raw_response_{request_id}.txt"));
-            let sanitized = sanitize_log_message(body);
+            let raw_file = log_dir.join(format!(
+                "raw_response_{}.txt",
+                raw_response_file_id(request_id)
+            ));
+            let sanitized = mask_pii(&sanitize_log_message(body));
 
             if let Ok(mut file) = OpenOptions::new()
                 .create(true)
@@ -134,6 +213,11 @@ impl LogStore {
         self.logs.iter().cloned().collect()
     }
 
+    /// 订阅新写入的日志
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.sender.subscribe()
+    }
+
     pub fn clear(&mut self) {
         self.logs.clear();
     }
@@ -263,6 +347,133 @@ impl LogStore {
 #[allow(dead_code)]
 pub type SharedLogStore = Arc<RwLock<LogStore>>;
 
+type TracingFilterHandle = reload::Handle<Targets, Registry>;
+
+/// 运行时 tracing 过滤器句柄及当前指令
+static TRACING_FILTER: OnceCell<(TracingFilterHandle, parking_lot::Mutex<String>)> =
+    OnceCell::new();
+
+/// 解析 tracing 过滤指令（如 `info,proxycast_lib::server=debug`）
+pub fn parse_tracing_filter(directives: &str) -> Result<Targets, String> {
+    let directives = directives.trim();
+    if directives.is_empty() {
+        return Err("过滤指令不能为空".to_string());
+    }
+    directives
+        .parse::<Targets>()
+        .map_err(|e| format!("无效的过滤指令 '{}': {}", directives, e))
+}
+
+/// 初始化 tracing 订阅者（可运行时重载过滤器）
+///
+/// 优先使用 `RUST_LOG`，否则使用配置中的日志级别；重复调用时忽略。
+pub fn init_tracing(default_directives: &str) {
+    let directives = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|d| parse_tracing_filter(d).is_ok())
+        .unwrap_or_else(|| default_directives.to_string());
+    let (directives, targets) = match parse_tracing_filter(&directives) {
+        Ok(targets) => (directives, targets),
+        Err(_) => ("info".to_string(), Targets::new().with_default(Level::INFO)),
+    };
+
+    let (filter, handle) = reload::Layer::new(targets);
+    let initialized = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .try_init()
+        .is_ok();
+    if initialized {
+        let _ = TRACING_FILTER.set((handle, parking_lot::Mutex::new(directives)));
+    }
+}
+
+/// 获取当前 tracing 过滤指令（未初始化时为 None）
+pub fn tracing_filter() -> Option<String> {
+    TRACING_FILTER
+        .get()
+        .map(|(_, directives)| directives.lock().clone())
+}
+
+/// 运行时替换 tracing 过滤指令，无需重启
+pub fn set_tracing_filter(directives: &str) -> Result<String, String> {
+    let targets = parse_tracing_filter(directives)?;
+    let (handle, current) = TRACING_FILTER
+        .get()
+        .ok_or_else(|| "tracing 订阅者未初始化".to_string())?;
+    handle
+        .reload(targets)
+        .map_err(|e| format!("重载过滤器失败: {}", e))?;
+    let directives = directives.trim().to_string();
+    *current.lock() = directives.clone();
+    tracing::info!("[LOGGER] tracing 过滤指令已更新: {}", directives);
+    Ok(directives)
+}
+
+/// 当前个人信息脱敏配置
+static PII_MASKING: Lazy<parking_lot::RwLock<PiiMaskingConfig>> =
+    Lazy::new(|| parking_lot::RwLock::new(PiiMaskingConfig::default()));
+
+static EMAIL_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+});
+
+static API_KEY_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}",
+        r"|\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+        r"|\bgh[pousr]_[A-Za-z0-9]{36,}",
+        r"|\bAIza[0-9A-Za-z_-]{35}",
+        r"|\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    ))
+    .unwrap()
+});
+
+static PHONE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        // 国际格式：+86 138 0013 8000、+1-415-555-0100
+        r"\+\d{1,3}[\s-]?\d{1,4}(?:[\s-]?\d{2,4}){2,3}\b",
+        // 北美格式：(415) 555-0100、415.555.0100
+        r"|\(?\b\d{3}\)?[\s.-]\d{3}[\s.-]\d{4}\b",
+        // 中国大陆手机号
+        r"|\b1[3-9]\d{9}\b",
+    ))
+    .unwrap()
+});
+
+/// 更新个人信息脱敏配置（启动和配置热更新时调用）
+pub fn set_pii_masking(config: &PiiMaskingConfig) {
+    *PII_MASKING.write() = config.clone();
+}
+
+/// 按当前配置脱敏个人信息（电子邮件、API Key、电话号码）
+pub fn mask_pii(message: &str) -> String {
+    let config = PII_MASKING.read();
+    if !config.enabled {
+        return message.to_string();
+    }
+    mask_pii_with(message, &config.detectors)
+}
+
+fn mask_pii_with(message: &str, detectors: &[PiiDetector]) -> String {
+    let mut masked = message.to_string();
+    // API Key 先于电话号码处理，避免 Key 中的数字片段被误判
+    for detector in [PiiDetector::ApiKey, PiiDetector::Email, PiiDetector::Phone] {
+        if !detectors.contains(&detector) {
+            continue;
+        }
+        let (pattern, replacement) = match detector {
+            PiiDetector::Email => (&*EMAIL_PATTERN, "[EMAIL]"),
+            PiiDetector::ApiKey => (&*API_KEY_PATTERN, "[API_KEY]"),
+            PiiDetector::Phone => (&*PHONE_PATTERN, "[PHONE]"),
+        };
+        if let std::borrow::Cow::Owned(replaced) = pattern.replace_all(&masked, replacement) {
+            masked = replaced;
+        }
+    }
+    masked
+}
+
 /// P2 安全修复：扩展日志脱敏规则，覆盖更多敏感字段
 pub fn sanitize_log_message(message: &str) -> String {
     let patterns = [
@@ -275,6 +486,8 @@ pub fn sanitize_log_message(message: &str) -> String {
         ),
         // 通用 token
         (r#"token["']?\s*[:=]\s*["']?[A-Za-z0-9._-]+"#, "token: ***"),
+        // WebSocket 子协议中的 token（proxycast.token.<api_key>）
+        (r"proxycast\.token\.[A-Za-z0-9._-]+", "proxycast.token.***"),
         // P2 新增：access_token
         (
             r#"access[_-]?token["']?\s*[:=]\s*["']?[A-Za-z0-9._-]+"#,
@@ -315,7 +528,17 @@ pub fn sanitize_log_message(message: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_log_message;
+    use super::{
+        mask_pii_with, parse_tracing_filter, raw_response_file_id, sanitize_log_message, LogEntry,
+        LogFilter, LogStore,
+    };
+    use crate::config::PiiDetector;
+
+    #[test]
+    fn test_raw_response_file_id() {
+        assert_eq!(raw_response_file_id("trace-123.1"), "trace-123.1");
+        assert_eq!(raw_response_file_id("../etc/passwd"), ".._etc_passwd");
+    }
 
     #[test]
     fn test_sanitize_bearer_token() {
@@ -358,6 +581,18 @@ mod tests {
         assert!(!output.contains("cs_SeCreT"));
     }
 
+    #[test]
+    fn test_sanitize_ws_credentials() {
+        let output = sanitize_log_message("GET /v1/ws?api_key=sk-query_1&token=tk_2");
+        assert!(!output.contains("sk-query_1"));
+        assert!(!output.contains("tk_2"));
+
+        let output =
+            sanitize_log_message("Sec-WebSocket-Protocol: proxycast, proxycast.token.sk-sub_3");
+        assert!(output.contains("proxycast.token.***"));
+        assert!(!output.contains("sk-sub_3"));
+    }
+
     #[test]
     fn test_sanitize_password() {
         let input = r#"{"password":"p@ssW0rd!"}"#;
@@ -366,6 +601,67 @@ mod tests {
         assert!(!output.contains("p@ssW0rd!"));
     }
 
+    #[test]
+    fn test_log_filter() {
+        let entry = |level: &str, message: &str| LogEntry {
+            timestamp: String::new(),
+            level: level.to_string(),
+            message: message.to_string(),
+            request_id: None,
+        };
+        let filter = LogFilter {
+            level: Some("warn".to_string()),
+            keyword: Some("kiro".to_string()),
+        };
+        assert!(filter.matches(&entry("error", "[KIRO] refresh failed")));
+        assert!(filter.matches(&entry("warn", "Kiro quota low")));
+        assert!(!filter.matches(&entry("info", "[KIRO] request ok")));
+        assert!(!filter.matches(&entry("error", "[GEMINI] refresh failed")));
+        assert!(LogFilter::default().matches(&entry("debug", "anything")));
+    }
+
+    #[test]
+    fn test_parse_tracing_filter() {
+        assert!(parse_tracing_filter("info").is_ok());
+        assert!(parse_tracing_filter("warn,proxycast_lib::server=debug").is_ok());
+        assert!(parse_tracing_filter("  ").is_err());
+        assert!(parse_tracing_filter("proxycast_lib=loud").is_err());
+    }
+
+    #[test]
+    fn test_subscribe_receives_new_logs() {
+        let mut store = LogStore::new();
+        store.config.enable_file_logging = false;
+        let mut receiver = store.subscribe();
+        store.add("info", "hello");
+        let entry = receiver.try_recv().unwrap();
+        assert_eq!(entry.level, "info");
+        assert_eq!(entry.message, "hello");
+    }
+
+    #[test]
+    fn test_mask_pii() {
+        let all = [PiiDetector::Email, PiiDetector::ApiKey, PiiDetector::Phone];
+        let input =
+            "user alice.w@example.co.uk key sk-proj-abcdefghijklmnop1234 tel +86 138 0013 8000";
+        assert_eq!(
+            mask_pii_with(input, &all),
+            "user [EMAIL] key [API_KEY] tel [PHONE]"
+        );
+        assert_eq!(
+            mask_pii_with("call (415) 555-0100 or 13800138000", &all),
+            "call [PHONE] or [PHONE]"
+        );
+        // 只启用部分检测器
+        assert_eq!(
+            mask_pii_with(input, &[PiiDetector::Email]),
+            "user [EMAIL] key sk-proj-abcdefghijklmnop1234 tel +86 138 0013 8000"
+        );
+        // 普通数字、时间戳、请求 ID 不受影响
+        let plain = "request_id=7f3c2a10 duration_ms=1532 tokens=128000 at 2025-01-15 10:30:00";
+        assert_eq!(mask_pii_with(plain, &all), plain);
+    }
+
     #[test]
     fn test_plain_text_unchanged() {
         let input = "这是一段普通日志，不包含任何敏感字段。";
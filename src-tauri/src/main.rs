@@ -1,5 +1,15 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use proxycast_lib::app::cli;
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::is_subcommand(&args) {
+        // release 构建没有控制台，子命令输出需要连接到启动它的终端
+        cli::attach_parent_console();
+    }
+    if let Some(code) = cli::dispatch(&args) {
+        std::process::exit(code);
+    }
     proxycast_lib::run()
 }
@@ -0,0 +1,138 @@
+//! 请求体大小限制中间件
+//!
+//! 在 `DefaultBodyLimit` 的基础上：
+//! - 根据 `Content-Length` 提前拒绝超限请求，无需读取请求体
+//! - 将分块传输时提取器返回的 413 统一为结构化错误，说明限制和实际大小
+
+use axum::{
+    extract::{DefaultBodyLimit, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+
+/// 为路由组设置请求体大小限制（字节）
+pub fn with_body_limit<S>(router: Router<S>, limit: usize) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(axum::middleware::from_fn_with_state(
+            limit,
+            enforce_body_limit,
+        ))
+        .layer(DefaultBodyLimit::max(limit))
+}
+
+/// 请求体大小检查
+pub async fn enforce_body_limit(
+    State(limit): State<usize>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if let Some(actual) = content_length {
+        if actual > limit as u64 {
+            return payload_too_large(limit, Some(actual));
+        }
+    }
+
+    let response = next.run(request).await;
+
+    // 分块传输的请求体在提取时超限，axum 返回纯文本 413，这里替换为结构化错误
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return payload_too_large(limit, content_length);
+    }
+    response
+}
+
+/// 构建 413 响应（同时兼容 OpenAI 和 Anthropic 的错误结构）
+pub fn payload_too_large(limit: usize, actual: Option<u64>) -> Response {
+    let message = match actual {
+        Some(actual) => format!(
+            "Request body is {} bytes, which exceeds the limit of {} bytes",
+            actual, limit
+        ),
+        None => format!("Request body exceeds the limit of {} bytes", limit),
+    };
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "request_too_large",
+                "code": "request_too_large",
+                "message": message,
+                "limit_bytes": limit,
+                "actual_bytes": actual,
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post};
+    use tower::ServiceExt;
+
+    fn app(limit: usize) -> Router {
+        with_body_limit(
+            Router::new().route("/echo", post(|body: axum::body::Bytes| async move { body })),
+            limit,
+        )
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rejects_by_content_length() {
+        let request = Request::post("/echo")
+            .header(header::CONTENT_LENGTH, "20")
+            .body(Body::from(vec![b'a'; 20]))
+            .unwrap();
+        let response = app(10).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let json = json_body(response).await;
+        assert_eq!(json["error"]["type"], "request_too_large");
+        assert_eq!(json["error"]["limit_bytes"], 10);
+        assert_eq!(json["error"]["actual_bytes"], 20);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_streamed_body() {
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> =
+            vec![Ok(vec![b'a'; 8]), Ok(vec![b'b'; 8])];
+        let request = Request::post("/echo")
+            .body(Body::from_stream(futures::stream::iter(chunks)))
+            .unwrap();
+        let response = app(10).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let json = json_body(response).await;
+        assert_eq!(json["error"]["limit_bytes"], 10);
+        assert!(json["error"]["actual_bytes"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_allows_body_within_limit() {
+        let request = Request::post("/echo").body(Body::from("hello")).unwrap();
+        let response = app(10).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
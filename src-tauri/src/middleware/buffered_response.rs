@@ -0,0 +1,178 @@
+//! 服务端缓冲的响应
+//!
+//! 上游响应在后台任务中逐块写入缓冲，多个客户端各自从指定位置读取。
+//! 供重复请求合并（[`super::dedupe`]）和 SSE 断点续传（[`super::sse_resume`]）共用。
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, StatusCode},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// 缓冲状态
+#[derive(Default)]
+pub struct BufferedState {
+    /// 响应状态码和响应头（上游响应头到达前为空）
+    pub head: Option<(StatusCode, HeaderMap)>,
+    pub chunks: Vec<Bytes>,
+    pub finished_at: Option<Instant>,
+    pub failed: bool,
+}
+
+/// 读取缓冲的进度
+enum Progress {
+    Chunks(usize, Vec<Bytes>),
+    Finished,
+    Failed,
+}
+
+/// 缓冲的响应（上游响应逐块写入，所有客户端从中读取）
+#[derive(Default)]
+pub struct BufferedResponse {
+    state: Mutex<BufferedState>,
+    notify: Notify,
+}
+
+impl BufferedResponse {
+    /// 创建已有响应头的缓冲
+    pub fn with_head(status: StatusCode, headers: HeaderMap) -> Self {
+        Self {
+            state: Mutex::new(BufferedState {
+                head: Some((status, headers)),
+                ..Default::default()
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut BufferedState)) {
+        f(&mut self.state.lock().unwrap());
+        self.notify.notify_waiters();
+    }
+
+    /// 上游响应完整结束
+    pub fn finish(&self) {
+        self.update(|state| state.finished_at = Some(Instant::now()));
+    }
+
+    /// 上游响应中断
+    pub fn fail(&self) {
+        self.update(|state| {
+            state.failed = true;
+            state.finished_at = Some(Instant::now());
+        });
+    }
+
+    /// 等待直到 `f` 返回 Some
+    pub async fn wait_for<T>(&self, mut f: impl FnMut(&BufferedState) -> Option<T>) -> T {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let value = f(&self.state.lock().unwrap());
+            if let Some(value) = value {
+                return value;
+            }
+            notified.await;
+        }
+    }
+
+    /// 等待响应头（上游请求失败时返回 None）
+    pub async fn head(&self) -> Option<(StatusCode, HeaderMap)> {
+        self.wait_for(|state| match &state.head {
+            Some(head) => Some(Some(head.clone())),
+            None if state.failed => Some(None),
+            None => None,
+        })
+        .await
+    }
+
+    /// 从缓冲构建响应体
+    ///
+    /// `start` 根据已缓冲的块计算起始下标，返回值超出已缓冲长度时等待更多数据后重新计算。
+    pub fn body(
+        self: Arc<Self>,
+        start: impl Fn(&[Bytes]) -> usize + Send + Sync + 'static,
+    ) -> Body {
+        let stream = async_stream::stream! {
+            let mut index = None;
+            loop {
+                let progress = self
+                    .wait_for(|state| {
+                        let from = index.unwrap_or_else(|| start(&state.chunks));
+                        if state.chunks.len() > from {
+                            Some(Progress::Chunks(from, state.chunks[from..].to_vec()))
+                        } else if state.failed {
+                            Some(Progress::Failed)
+                        } else if state.finished_at.is_some() {
+                            Some(Progress::Finished)
+                        } else {
+                            None
+                        }
+                    })
+                    .await;
+                match progress {
+                    Progress::Chunks(from, chunks) => {
+                        index = Some(from + chunks.len());
+                        for chunk in chunks {
+                            yield Ok::<_, axum::Error>(chunk);
+                        }
+                    }
+                    Progress::Finished => break,
+                    Progress::Failed => {
+                        yield Err(axum::Error::new("upstream response interrupted"));
+                        break;
+                    }
+                }
+            }
+        };
+        Body::from_stream(stream)
+    }
+
+    fn is_expired(&self, now: Instant, retention: Duration) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .finished_at
+            .is_some_and(|at| now.duration_since(at) >= retention)
+    }
+}
+
+/// 进行中和保留期内的缓冲响应
+pub struct BufferStore {
+    retention: Duration,
+    entries: Mutex<HashMap<String, Arc<BufferedResponse>>>,
+}
+
+impl BufferStore {
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            retention,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 响应结束后的保留时长
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// 清理过期条目后返回全部条目
+    pub fn entries(&self) -> MutexGuard<'_, HashMap<String, Arc<BufferedResponse>>> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, buffered| !buffered.is_expired(now, self.retention));
+        entries
+    }
+
+    /// 移除条目（只在仍是同一缓冲时移除）
+    pub fn remove(&self, key: &str, buffered: &Arc<BufferedResponse>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.get(key).is_some_and(|e| Arc::ptr_eq(e, buffered)) {
+            entries.remove(key);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! 重复请求合并中间件
+//!
+//! 客户端超时重试时，上游可能仍在处理第一次请求。启用后：
+//! - 同一监听端口上同一 API Key 的相同 POST 请求（方法、路径、请求体均相同）只转发一次上游
+//! - 响应（包括流式响应）分发给所有等待的客户端，被合并的请求带 `x-proxycast-deduplicated: true` 响应头
+//! - 请求成功完成后的合并窗口内到达的相同请求直接复用响应
+//!
+//! 上游请求在后台任务中执行，最先发起请求的客户端断开不会中断其他客户端的响应。
+
+use super::buffered_response::{BufferStore, BufferedResponse};
+use super::listener_routes::ListenerAddr;
+use super::request_id::in_current_request;
+use crate::compaction::COMPACT_HEADER;
+use crate::config::DedupeConfig;
+use crate::session_files::transcript::SESSION_ID_HEADER;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{header, request::Parts, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 被合并请求的响应头
+pub const DEDUPED_HEADER: &str = "x-proxycast-deduplicated";
+
+/// 参与计算请求指纹的请求头（认证、路由及影响上游行为的请求头）
+const KEY_HEADERS: &[&str] = &[
+    "authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "anthropic-version",
+    "anthropic-beta",
+    // 指定 Provider 凭证
+    "x-provider-id",
+    // 按客户端类型选择 Provider
+    "user-agent",
+    // 默认的请求优先级通道请求头
+    "x-proxycast-priority",
+    // SSE 断点续传请求（否则会与原始请求合并，从头重放）
+    "last-event-id",
+    SESSION_ID_HEADER,
+    COMPACT_HEADER,
+];
+
+/// 进行中和窗口期内的请求
+pub struct DedupeStore {
+    buffers: BufferStore,
+}
+
+impl DedupeStore {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            buffers: BufferStore::new(window),
+        }
+    }
+
+    /// 加入相同的请求，没有时创建新条目（第二个返回值为 true 时需要发起上游请求）
+    fn join(&self, key: &str) -> (Arc<BufferedResponse>, bool) {
+        let mut entries = self.buffers.entries();
+        if let Some(shared) = entries.get(key) {
+            return (shared.clone(), false);
+        }
+        let shared = Arc::new(BufferedResponse::default());
+        entries.insert(key.to_string(), shared.clone());
+        (shared, true)
+    }
+}
+
+/// 为路由组添加重复请求合并中间件（未启用时不添加）
+pub fn with_request_dedupe<S>(router: Router<S>, config: &DedupeConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if !config.enabled {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(DedupeStore::new(Duration::from_millis(config.window_ms))),
+        dedupe_requests,
+    ))
+}
+
+/// 合并相同的请求
+pub async fn dedupe_requests(
+    State(store): State<Arc<DedupeStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !is_dedupable(&request) {
+        return next.run(request).await;
+    }
+
+    // 请求体大小已由外层的 Content-Length 检查限制
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                format!("Failed to read request body: {}", e),
+            )
+        }
+    };
+
+    let key = request_key(&parts, &body);
+    let (shared, is_leader) = store.join(&key);
+    if is_leader {
+        let request = Request::from_parts(parts, Body::from(body));
+        tokio::spawn(in_current_request(produce(
+            store,
+            key,
+            shared.clone(),
+            next,
+            request,
+        )));
+    } else {
+        tracing::info!(
+            "[DEDUPE] 合并重复请求: {} {}",
+            parts.method,
+            parts.uri.path()
+        );
+    }
+    respond(shared, !is_leader).await
+}
+
+/// 只合并带 Content-Length 的普通 POST 请求
+fn is_dedupable(request: &Request) -> bool {
+    request.method() == Method::POST
+        && request.headers().contains_key(header::CONTENT_LENGTH)
+        && !request.headers().contains_key(header::UPGRADE)
+}
+
+/// 请求指纹：监听地址、认证及路由请求头、方法、路径（含查询参数）和请求体的 SHA-256
+///
+/// 各监听端口的 API Key 和开放接口不同，不同监听端口的请求不合并。
+fn request_key(parts: &Parts, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    if let Some(ConnectInfo(ListenerAddr(Some(addr)))) =
+        parts.extensions.get::<ConnectInfo<ListenerAddr>>()
+    {
+        hasher.update(format!("listener: {}\n", addr));
+    }
+    for name in KEY_HEADERS {
+        for value in parts.headers.get_all(*name) {
+            hasher.update(name.as_bytes());
+            hasher.update(b": ");
+            hasher.update(value.as_bytes());
+            hasher.update(b"\n");
+        }
+    }
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    hasher.update(format!("{} {}\n\n", parts.method, path));
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// 上游请求中断时标记失败并移除条目（包括任务 panic）
+struct FailGuard {
+    store: Arc<DedupeStore>,
+    key: String,
+    shared: Arc<BufferedResponse>,
+    armed: bool,
+}
+
+impl Drop for FailGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        self.shared.fail();
+        self.store.buffers.remove(&self.key, &self.shared);
+    }
+}
+
+/// 执行上游请求并写入共享响应
+async fn produce(
+    store: Arc<DedupeStore>,
+    key: String,
+    shared: Arc<BufferedResponse>,
+    next: Next,
+    request: Request,
+) {
+    let mut guard = FailGuard {
+        store: store.clone(),
+        key: key.clone(),
+        shared: shared.clone(),
+        armed: true,
+    };
+
+    let (parts, body) = next.run(request).await.into_parts();
+    // 只复用成功响应，错误响应允许客户端立即重试
+    let reusable = parts.status.is_success() && !store.buffers.retention().is_zero();
+    shared.update(|state| state.head = Some((parts.status, parts.headers)));
+
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => shared.update(|state| state.chunks.push(chunk)),
+            Err(e) => {
+                tracing::warn!("[DEDUPE] 上游响应中断: {}", e);
+                return;
+            }
+        }
+    }
+
+    guard.armed = false;
+    shared.finish();
+    if !reusable {
+        store.buffers.remove(&key, &shared);
+    }
+}
+
+/// 从共享响应构建客户端响应
+async fn respond(shared: Arc<BufferedResponse>, deduplicated: bool) -> Response {
+    let Some((status, headers)) = shared.head().await else {
+        return error_response(
+            StatusCode::BAD_GATEWAY,
+            "api_error",
+            "Upstream request failed".to_string(),
+        );
+    };
+
+    let mut response = Response::new(shared.body(|_| 0));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    if deduplicated {
+        response
+            .headers_mut()
+            .insert(DEDUPED_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// 构建错误响应（同时兼容 OpenAI 和 Anthropic 的错误结构）
+fn error_response(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": error_type,
+                "code": error_type,
+                "message": message,
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::routing::post;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(window_ms: u64, calls: Arc<AtomicUsize>) -> Router {
+        let handler = move |body: Bytes| {
+            let calls = calls.clone();
+            async move {
+                let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                format!("{}:{}", n, String::from_utf8_lossy(&body))
+            }
+        };
+        with_request_dedupe(
+            Router::new().route("/v1/messages", post(handler)),
+            &DedupeConfig {
+                enabled: true,
+                window_ms,
+            },
+        )
+    }
+
+    fn request(api_key: &str, body: &'static str) -> Request {
+        Request::post("/v1/messages")
+            .header("x-api-key", api_key)
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn send(app: &Router, request: Request) -> (bool, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let deduplicated = response.headers().contains_key(DEDUPED_HEADER);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (deduplicated, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_identical_requests_share_response() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(0, calls.clone());
+
+        let (a, b) = tokio::join!(
+            send(&app, request("sk-a", "hello")),
+            send(&app, request("sk-a", "hello"))
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(a.1, "1:hello");
+        assert_eq!(a.1, b.1);
+        assert!(a.0 ^ b.0);
+
+        // 不同 API Key 或请求体不合并
+        let (c, d) = tokio::join!(
+            send(&app, request("sk-b", "hello")),
+            send(&app, request("sk-a", "world"))
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(!c.0 && !d.0);
+
+        // 窗口为 0 时完成后不再复用
+        let e = send(&app, request("sk-a", "hello")).await;
+        assert_eq!(e, (false, "4:hello".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_routing_headers_not_merged() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(60_000, calls.clone());
+        let with_header = |name: &str, value: &str| {
+            let mut request = request("sk-a", "hello");
+            request.headers_mut().insert(
+                name.parse::<header::HeaderName>().unwrap(),
+                value.parse().unwrap(),
+            );
+            request
+        };
+
+        send(&app, with_header("x-provider-id", "cred-1")).await;
+        let (deduplicated, _) = send(&app, with_header("x-provider-id", "cred-2")).await;
+        assert!(!deduplicated);
+        let (deduplicated, _) = send(&app, with_header("user-agent", "claude-cli/1.0")).await;
+        assert!(!deduplicated);
+        let (deduplicated, _) = send(&app, with_header("x-provider-id", "cred-1")).await;
+        assert!(deduplicated);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_finished_response_reused_within_window() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(60_000, calls.clone());
+
+        let first = send(&app, request("sk-a", "hello")).await;
+        let second = send(&app, request("sk-a", "hello")).await;
+        assert_eq!(first, (false, "1:hello".to_string()));
+        assert_eq!(second, (true, "1:hello".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_listeners_not_merged() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        // 所有监听端口共用同一路由（及去重存储），按连接的本地地址区分
+        let routes = app(60_000, calls.clone());
+        let listener = |port: u16| {
+            let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+            routes
+                .clone()
+                .layer(axum::Extension(ConnectInfo(ListenerAddr(Some(addr)))))
+        };
+        let (main, extra) = (listener(8999), listener(9001));
+
+        let first = send(&main, request("sk-a", "hello")).await;
+        let other = send(&extra, request("sk-a", "hello")).await;
+        assert_eq!(first, (false, "1:hello".to_string()));
+        assert_eq!(other, (false, "2:hello".to_string()));
+
+        // 同一监听端口仍然合并
+        let again = send(&extra, request("sk-a", "hello")).await;
+        assert_eq!(again, (true, "2:hello".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
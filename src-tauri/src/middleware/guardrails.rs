@@ -0,0 +1,281 @@
+//! 内容防护中间件
+//!
+//! 对所有入站对话请求统一执行防护策略（`/v1/chat/completions`、`/v1/messages`、
+//! 选择器路由、Amp CLI 路由和 Gemini 原生协议路由），不依赖各处理器单独调用：
+//! - `block` 策略命中时直接返回 403，错误格式与路由协议一致
+//! - `redact` 策略命中时改写请求体后继续转发
+
+use super::body_limit::payload_too_large;
+use crate::processor::{ProcessError, RequestContext, RequestProcessor};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// 请求协议（决定错误响应格式）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChatProtocol {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+impl ChatProtocol {
+    fn from_path(path: &str) -> Option<Self> {
+        if path.ends_with("/chat/completions") {
+            Some(Self::OpenAI)
+        } else if path.ends_with("/messages") {
+            Some(Self::Anthropic)
+        } else if path.starts_with("/v1/gemini/") {
+            Some(Self::Gemini)
+        } else {
+            None
+        }
+    }
+}
+
+/// 为模型 API 路由添加内容防护中间件
+///
+/// `body_limit` 为路由组的请求体大小限制，检查时读取的请求体不超过该大小。
+pub fn with_guardrails<S>(
+    router: Router<S>,
+    processor: Arc<RequestProcessor>,
+    body_limit: usize,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(axum::middleware::from_fn_with_state(
+        (processor, body_limit),
+        guard_request,
+    ))
+}
+
+/// 提取客户端 API Key（Authorization Bearer、x-api-key 或 x-goog-api-key）
+pub fn client_api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")
+        .or_else(|| headers.get("x-api-key"))
+        .or_else(|| headers.get("x-goog-api-key"))
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.strip_prefix("Bearer ").unwrap_or(s))
+}
+
+/// 按入站 API Key 对应的防护策略检查请求体
+async fn guard_request(
+    State((processor, body_limit)): State<(Arc<RequestProcessor>, usize)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let protocol = match ChatProtocol::from_path(request.uri().path()) {
+        Some(protocol) if request.method() == Method::POST => protocol,
+        _ => return next.run(request).await,
+    };
+    if !processor.guardrails.read().await.is_enabled() {
+        return next.run(request).await;
+    }
+
+    // 外层只检查声明了 Content-Length 的请求，分块传输的请求体在这里限制大小
+    let (mut parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, body_limit).await {
+        Ok(bytes) => bytes,
+        Err(e) if exceeds_limit(&e) => return payload_too_large(body_limit, None),
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Failed to read request body: {}", e),
+            )
+                .into_response()
+        }
+    };
+    // 无法解析的请求体交给处理器的校验报错
+    let Ok(mut payload) = serde_json::from_slice::<Value>(&bytes) else {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+
+    let model = payload
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let ctx = RequestContext::new(model);
+    match processor
+        .apply_guardrails(&ctx, client_api_key(&parts.headers), &mut payload)
+        .await
+    {
+        Ok(None) => {
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+        Ok(Some(_)) => {
+            let body = serde_json::to_vec(&payload).unwrap_or_else(|_| bytes.to_vec());
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Err(e) => violation_response(protocol, &e),
+    }
+}
+
+/// 读取请求体的错误是否为超出大小限制
+fn exceeds_limit(e: &axum::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(e);
+    while let Some(e) = source {
+        if e.is::<http_body_util::LengthLimitError>() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// 按路由协议构建 403 响应
+fn violation_response(protocol: ChatProtocol, e: &ProcessError) -> Response {
+    let message = e.to_string();
+    let body = match protocol {
+        ChatProtocol::OpenAI => json!({
+            "error": {
+                "message": message,
+                "type": e.error_type(),
+                "param": null,
+                "code": "content_policy_violation"
+            }
+        }),
+        ChatProtocol::Anthropic => json!({
+            "type": "error",
+            "error": {
+                "type": "permission_error",
+                "message": message
+            }
+        }),
+        ChatProtocol::Gemini => json!({
+            "error": {
+                "code": 403,
+                "message": message,
+                "status": "PERMISSION_DENIED"
+            }
+        }),
+    };
+    (StatusCode::FORBIDDEN, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        GuardrailAction, GuardrailPatternConfig, GuardrailPolicyConfig, GuardrailsConfig,
+    };
+    use crate::guardrails::Guardrails;
+    use crate::services::provider_pool_service::ProviderPoolService;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    async fn app(action: GuardrailAction) -> Router {
+        let processor = Arc::new(RequestProcessor::with_defaults(Arc::new(
+            ProviderPoolService::new(),
+        )));
+        *processor.guardrails.write().await = Guardrails::from_config(&GuardrailsConfig {
+            enabled: true,
+            default_policy: Some("strict".to_string()),
+            policies: vec![GuardrailPolicyConfig {
+                name: "strict".to_string(),
+                action,
+                builtin_secrets: false,
+                patterns: vec![GuardrailPatternConfig {
+                    name: "internal_host".to_string(),
+                    regex: r"\b[\w.-]+\.corp\.example\.com\b".to_string(),
+                }],
+                replacement: "[REDACTED]".to_string(),
+            }],
+            ..Default::default()
+        });
+        // 处理器原样返回收到的请求体
+        let echo = |body: String| async move { body };
+        with_guardrails(
+            Router::new()
+                .route("/:selector/v1/chat/completions", post(echo))
+                .route("/:selector/v1/messages", post(echo))
+                .route("/v1/gemini/*path", post(echo)),
+            processor,
+            1024,
+        )
+    }
+
+    async fn send(app: &Router, path: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::post(path)
+            .header("authorization", "Bearer sk-client")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_block_on_selector_routes() {
+        let app = app(GuardrailAction::Block).await;
+        let messages =
+            json!({"messages": [{"role": "user", "content": "连接 db1.corp.example.com"}]});
+
+        let (status, body) = send(&app, "/kiro/v1/chat/completions", messages.clone()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["code"], "content_policy_violation");
+
+        let (status, body) = send(&app, "/tag:prod/v1/messages", messages).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["type"], "permission_error");
+
+        let gemini =
+            json!({"contents": [{"role": "user", "parts": [{"text": "db1.corp.example.com"}]}]});
+        let (status, body) = send(&app, "/v1/gemini/gemini-2.5-pro:generateContent", gemini).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["status"], "PERMISSION_DENIED");
+
+        // 未命中时放行
+        let clean = json!({"messages": [{"role": "user", "content": "hello"}]});
+        let (status, body) = send(&app, "/kiro/v1/chat/completions", clean.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, clean);
+    }
+
+    #[tokio::test]
+    async fn test_redact_rewrites_body() {
+        let app = app(GuardrailAction::Redact).await;
+        let (status, body) = send(
+            &app,
+            "/kiro/v1/chat/completions",
+            json!({"messages": [{"role": "user", "content": "连接 db1.corp.example.com"}]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["messages"][0]["content"], "连接 [REDACTED]");
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_over_limit_rejected() {
+        let app = app(GuardrailAction::Block).await;
+        let chunks = futures::stream::iter(
+            (0..2).map(|_| Ok::<_, std::io::Error>(axum::body::Bytes::from(vec![b' '; 800]))),
+        );
+        let request = Request::post("/kiro/v1/chat/completions")
+            .header("authorization", "Bearer sk-client")
+            .body(Body::from_stream(chunks))
+            .unwrap();
+        assert!(!request.headers().contains_key(header::CONTENT_LENGTH));
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,297 @@
+//! 客户端请求头透传中间件
+//!
+//! - 按配置收集入站请求中允许透传的请求头（如 `anthropic-beta`、`OpenAI-Organization`）
+//! - 请求处理期间通过 task-local 暴露，上游请求通过 [`HeaderPassthroughExt`] 按 Provider 附加
+//! - 固定覆盖的请求头优先于客户端请求头和 Provider 默认值
+//!
+//! 认证、连接相关的请求头始终不透传。
+
+use crate::config::HeaderPassthroughConfig;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 不允许透传或覆盖的请求头
+const BLOCKED_HEADERS: &[&str] = &[
+    "authorization",
+    "x-api-key",
+    "cookie",
+    "host",
+    "content-length",
+    "content-type",
+    "transfer-encoding",
+    "connection",
+];
+
+/// 单个 Provider 的请求头策略（启动时解析请求头名称）
+#[derive(Debug, Clone, Default)]
+struct CompiledPolicy {
+    passthrough: Vec<HeaderName>,
+    overrides: HeaderMap,
+}
+
+/// 中间件设置
+#[derive(Debug, Clone, Default)]
+pub struct HeaderPassthroughSettings {
+    policies: HashMap<String, CompiledPolicy>,
+}
+
+impl From<&HeaderPassthroughConfig> for HeaderPassthroughSettings {
+    fn from(config: &HeaderPassthroughConfig) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        let policies = config
+            .providers
+            .iter()
+            .map(|(provider, policy)| {
+                let passthrough = policy
+                    .passthrough
+                    .iter()
+                    .filter_map(|name| parse_header_name(provider, name))
+                    .collect();
+                let mut overrides = HeaderMap::new();
+                for (name, value) in &policy.overrides {
+                    let Some(name) = parse_header_name(provider, name) else {
+                        continue;
+                    };
+                    match HeaderValue::from_str(value) {
+                        Ok(value) => {
+                            overrides.insert(name, value);
+                        }
+                        Err(_) => tracing::warn!(
+                            "[HEADER_PASSTHROUGH] Provider {} 的请求头 {} 值无效，已忽略",
+                            provider,
+                            name
+                        ),
+                    }
+                }
+                (
+                    provider.to_lowercase(),
+                    CompiledPolicy {
+                        passthrough,
+                        overrides,
+                    },
+                )
+            })
+            .collect();
+        Self { policies }
+    }
+}
+
+/// 解析请求头名称（无效或禁止透传时记录警告并返回 None）
+fn parse_header_name(provider: &str, name: &str) -> Option<HeaderName> {
+    let Ok(header) = HeaderName::try_from(name.trim()) else {
+        tracing::warn!(
+            "[HEADER_PASSTHROUGH] Provider {} 的请求头名称 '{}' 无效，已忽略",
+            provider,
+            name
+        );
+        return None;
+    };
+    if BLOCKED_HEADERS.contains(&header.as_str()) {
+        tracing::warn!(
+            "[HEADER_PASSTHROUGH] Provider {} 的请求头 {} 不允许透传或覆盖，已忽略",
+            provider,
+            header
+        );
+        return None;
+    }
+    Some(header)
+}
+
+impl HeaderPassthroughSettings {
+    fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    /// 从入站请求头中收集任一 Provider 允许透传的请求头
+    fn capture(&self, headers: &HeaderMap) -> HeaderMap {
+        let mut captured = HeaderMap::new();
+        for name in self.policies.values().flat_map(|p| p.passthrough.iter()) {
+            if captured.contains_key(name) {
+                continue;
+            }
+            for value in headers.get_all(name) {
+                captured.append(name.clone(), value.clone());
+            }
+        }
+        captured
+    }
+
+    /// 指定 Provider 的上游请求头
+    fn resolve(&self, provider: &str, captured: &HeaderMap) -> HeaderMap {
+        let Some(policy) = self.policies.get(&provider.to_lowercase()) else {
+            return HeaderMap::new();
+        };
+        let mut headers = HeaderMap::new();
+        for name in &policy.passthrough {
+            for value in captured.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+        for (name, value) in &policy.overrides {
+            headers.insert(name.clone(), value.clone());
+        }
+        headers
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CurrentHeaders {
+    captured: HeaderMap,
+    settings: Arc<HeaderPassthroughSettings>,
+}
+
+tokio::task_local! {
+    static CURRENT_HEADERS: CurrentHeaders;
+}
+
+/// 为路由组添加请求头透传中间件（未配置任何策略时不启用）
+pub fn with_header_passthrough<S>(router: Router<S>, config: &HeaderPassthroughConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let settings = HeaderPassthroughSettings::from(config);
+    if settings.is_empty() {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(settings),
+        capture_passthrough_headers,
+    ))
+}
+
+/// 收集入站请求中允许透传的请求头
+pub async fn capture_passthrough_headers(
+    State(settings): State<Arc<HeaderPassthroughSettings>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let current = CurrentHeaders {
+        captured: settings.capture(request.headers()),
+        settings,
+    };
+    CURRENT_HEADERS.scope(current, next.run(request)).await
+}
+
+/// 当前请求发往指定 Provider 时附加的请求头（不在请求处理上下文中时为空）
+pub fn passthrough_headers(provider: &str) -> HeaderMap {
+    CURRENT_HEADERS
+        .try_with(|current| current.settings.resolve(provider, &current.captured))
+        .unwrap_or_default()
+}
+
+/// 为上游请求附加透传和覆盖的请求头
+///
+/// 同名请求头会替换之前设置的值，需在 Provider 默认请求头之后调用。
+pub trait HeaderPassthroughExt {
+    fn with_passthrough_headers(self, provider: &str) -> Self;
+}
+
+impl HeaderPassthroughExt for reqwest::RequestBuilder {
+    fn with_passthrough_headers(self, provider: &str) -> Self {
+        let headers = passthrough_headers(provider);
+        if headers.is_empty() {
+            self
+        } else {
+            self.headers(headers)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderHeaderPolicy;
+    use axum::{body::Body, routing::get};
+    use tower::ServiceExt;
+
+    fn app(config: HeaderPassthroughConfig) -> Router {
+        with_header_passthrough(
+            Router::new().route(
+                "/headers/:provider",
+                get(
+                    |axum::extract::Path(provider): axum::extract::Path<String>| async move {
+                        let headers = passthrough_headers(&provider);
+                        let mut lines: Vec<String> = headers
+                            .iter()
+                            .map(|(k, v)| format!("{}={}", k, v.to_str().unwrap()))
+                            .collect();
+                        lines.sort();
+                        lines.join("\n")
+                    },
+                ),
+            ),
+            &config,
+        )
+    }
+
+    async fn resolved(config: HeaderPassthroughConfig, provider: &str) -> String {
+        let request = Request::get(format!("/headers/{}", provider))
+            .header("anthropic-beta", "prompt-caching-2024-07-31")
+            .header("anthropic-beta", "output-128k-2025-02-19")
+            .header("OpenAI-Organization", "org-123")
+            .header("x-api-key", "client-key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(config).oneshot(request).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_default_policies() {
+        assert_eq!(
+            resolved(HeaderPassthroughConfig::default(), "claude").await,
+            "anthropic-beta=output-128k-2025-02-19\nanthropic-beta=prompt-caching-2024-07-31"
+        );
+        assert_eq!(
+            resolved(HeaderPassthroughConfig::default(), "openai").await,
+            "openai-organization=org-123"
+        );
+        assert_eq!(
+            resolved(HeaderPassthroughConfig::default(), "kiro").await,
+            ""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overrides_and_blocked_headers() {
+        let mut config = HeaderPassthroughConfig::default();
+        config.providers.insert(
+            "claude".to_string(),
+            ProviderHeaderPolicy {
+                passthrough: vec!["x-api-key".to_string(), "anthropic-beta".to_string()],
+                overrides: [
+                    ("anthropic-beta".to_string(), "fixed-beta".to_string()),
+                    ("anthropic-version".to_string(), "2023-06-01".to_string()),
+                ]
+                .into(),
+            },
+        );
+        assert_eq!(
+            resolved(config, "Claude").await,
+            "anthropic-beta=fixed-beta\nanthropic-version=2023-06-01"
+        );
+
+        let disabled = HeaderPassthroughConfig {
+            enabled: false,
+            ..HeaderPassthroughConfig::default()
+        };
+        assert_eq!(resolved(disabled, "claude").await, "");
+    }
+
+    #[test]
+    fn test_passthrough_headers_outside_request() {
+        assert!(passthrough_headers("claude").is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+//! 监听端口接口过滤中间件
+//!
+//! 额外监听端口只开放配置的接口分组，其他接口返回 404（与接口不存在时一致）。
+//! `/health`、`/v1/models` 等只读接口不属于任何分组，始终开放。
+
+use crate::config::ListenerRouteSet;
+use axum::{
+    extract::{connect_info::Connected, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    serve::IncomingStream,
+    Router,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// 接收请求的本地监听地址
+///
+/// 作为连接信息（`ConnectInfo<ListenerAddr>`）注入请求，
+/// 供按监听端口隔离状态的中间件区分来自不同监听端口的请求。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerAddr(pub Option<SocketAddr>);
+
+impl Connected<IncomingStream<'_>> for ListenerAddr {
+    fn connect_info(target: IncomingStream<'_>) -> Self {
+        Self(target.local_addr().ok())
+    }
+}
+
+/// 为路由添加接口过滤（分组列表为空时不过滤）
+///
+/// `base_path` 为路由路径前缀，按去掉前缀后的路径判断接口分组。
+pub fn with_listener_routes<S>(
+    router: Router<S>,
+    routes: &[ListenerRouteSet],
+    base_path: &str,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if routes.is_empty() {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(ListenerFilter {
+            allowed: routes.to_vec(),
+            base_path: base_path.to_string(),
+        }),
+        filter_listener_routes,
+    ))
+}
+
+struct ListenerFilter {
+    allowed: Vec<ListenerRouteSet>,
+    base_path: String,
+}
+
+async fn filter_listener_routes(
+    State(filter): State<Arc<ListenerFilter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    let path = path.strip_prefix(filter.base_path.as_str()).unwrap_or(path);
+    match route_set_for_path(path) {
+        Some(route_set) if !filter.allowed.contains(&route_set) => {
+            tracing::debug!("[LISTENER] {} 未在当前监听端口开放", request.uri().path());
+            StatusCode::NOT_FOUND.into_response()
+        }
+        _ => next.run(request).await,
+    }
+}
+
+/// 请求路径所属的接口分组（始终开放的接口返回 None）
+pub fn route_set_for_path(path: &str) -> Option<ListenerRouteSet> {
+    let path = path.trim_end_matches('/');
+    if path.starts_with("/v0/management/")
+        || path.starts_with("/api/kiro/")
+        || path.starts_with("/v1/credentials/")
+    {
+        Some(ListenerRouteSet::Management)
+    } else if path.starts_with("/api/auth/") || path.starts_with("/api/user/") {
+        Some(ListenerRouteSet::Amp)
+    } else if path.starts_with("/v1/gemini/") {
+        Some(ListenerRouteSet::Gemini)
+    } else if path == "/v1/ws" || path == "/ws" {
+        Some(ListenerRouteSet::WebSocket)
+    } else if path.ends_with("/v1/messages") || path.ends_with("/v1/messages/count_tokens") {
+        Some(ListenerRouteSet::Anthropic)
+    } else if path.ends_with("/v1/chat/completions")
+        || path == "/v1/images/generations"
+        || path.starts_with("/v1/audio/")
+        || path == "/v1/files"
+        || path.starts_with("/v1/files/")
+    {
+        Some(ListenerRouteSet::OpenAI)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get};
+    use tower::ServiceExt;
+
+    async fn status(app: &Router, path: &str) -> StatusCode {
+        app.clone()
+            .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_filter_with_base_path() {
+        let routes = Router::new()
+            .route("/v0/management/status", get(|| async { "ok" }))
+            .route("/v1/chat/completions", get(|| async { "ok" }));
+        let app = with_listener_routes(
+            Router::new().nest("/proxycast", routes),
+            &[ListenerRouteSet::OpenAI],
+            "/proxycast",
+        );
+
+        assert_eq!(
+            status(&app, "/proxycast/v1/chat/completions").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, "/proxycast/v0/management/status").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_route_set_for_path() {
+        let cases = [
+            ("/v1/chat/completions", Some(ListenerRouteSet::OpenAI)),
+            ("/kiro/v1/chat/completions", Some(ListenerRouteSet::OpenAI)),
+            (
+                "/api/provider/openai/v1/chat/completions",
+                Some(ListenerRouteSet::OpenAI),
+            ),
+            ("/v1/images/generations", Some(ListenerRouteSet::OpenAI)),
+            ("/v1/audio/transcriptions", Some(ListenerRouteSet::OpenAI)),
+            ("/v1/audio/speech", Some(ListenerRouteSet::OpenAI)),
+            ("/v1/files", Some(ListenerRouteSet::OpenAI)),
+            ("/v1/files/file-01/content", Some(ListenerRouteSet::OpenAI)),
+            ("/v1/messages", Some(ListenerRouteSet::Anthropic)),
+            ("/claude/v1/messages/", Some(ListenerRouteSet::Anthropic)),
+            (
+                "/v1/messages/count_tokens",
+                Some(ListenerRouteSet::Anthropic),
+            ),
+            (
+                "/v1/gemini/gemini-2.5-pro:generateContent",
+                Some(ListenerRouteSet::Gemini),
+            ),
+            ("/ws", Some(ListenerRouteSet::WebSocket)),
+            ("/api/user/profile", Some(ListenerRouteSet::Amp)),
+            ("/v0/management/status", Some(ListenerRouteSet::Management)),
+            ("/v1/credentials/select", Some(ListenerRouteSet::Management)),
+            ("/health", None),
+            ("/health/live", None),
+            ("/health/ready", None),
+            ("/v1/models", None),
+            ("/v1/models/claude-sonnet-4-5", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(route_set_for_path(path), expected, "{}", path);
+        }
+    }
+}
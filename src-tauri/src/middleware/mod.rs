@@ -2,9 +2,35 @@
 //!
 //! 提供 HTTP 请求处理的中间件组件
 
+pub mod body_limit;
+pub mod buffered_response;
+pub mod dedupe;
+pub mod guardrails;
+pub mod header_passthrough;
+pub mod listener_routes;
 pub mod management_auth;
+pub mod priority_lane;
+pub mod request_id;
+pub mod response_info;
+pub mod response_signing;
+pub mod sse_heartbeat;
+pub mod sse_resume;
 
 #[cfg(test)]
 mod tests;
 
+pub use body_limit::with_body_limit;
+pub use dedupe::with_request_dedupe;
+pub use guardrails::with_guardrails;
+pub use header_passthrough::{passthrough_headers, with_header_passthrough, HeaderPassthroughExt};
+pub use listener_routes::{with_listener_routes, ListenerAddr};
 pub use management_auth::{ManagementAuthLayer, ManagementAuthService};
+pub use priority_lane::{current_lane, with_priority_lanes, TrafficLane};
+pub use request_id::{
+    current_or_new_request_id, current_request_id, new_request_id, response_id, with_request_id,
+    RequestIdExt,
+};
+pub use response_info::with_response_info_headers;
+pub use response_signing::with_response_signing;
+pub use sse_heartbeat::with_sse_heartbeat;
+pub use sse_resume::with_sse_resume;
@@ -0,0 +1,258 @@
+//! 请求优先级通道中间件
+//!
+//! 将请求分为交互式和批处理两类，请求处理期间通过 task-local 暴露，
+//! 凭证池据此为交互式请求保留一部分健康凭证：
+//! - 请求头（默认 `x-proxycast-priority`）显式指定 `interactive` 或 `batch` 时以请求头为准
+//! - 流式请求（请求体 `"stream": true` 或 Gemini `streamGenerateContent`）为交互式
+//! - Content-Length 达到 `batch_min_body_kb` 的非流式请求为批处理
+//! - 其余请求为交互式
+//!
+//! 不在请求上下文中的凭证选择（健康检查、后台任务）按交互式处理。
+
+use crate::config::PriorityLanesConfig;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderName, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// 请求流量类别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrafficLane {
+    /// 交互式（流式、编码助手）
+    #[default]
+    Interactive,
+    /// 批处理
+    Batch,
+}
+
+impl TrafficLane {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Batch => "batch",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "interactive" => Some(Self::Interactive),
+            "batch" => Some(Self::Batch),
+            _ => None,
+        }
+    }
+}
+
+/// 中间件设置（启动时解析请求头名称）
+#[derive(Debug, Clone)]
+pub struct PriorityLaneSettings {
+    header: HeaderName,
+    /// 批处理请求体大小阈值（字节），0 表示不按大小判断
+    batch_min_body_bytes: u64,
+}
+
+impl From<&PriorityLanesConfig> for PriorityLaneSettings {
+    fn from(config: &PriorityLanesConfig) -> Self {
+        let header = HeaderName::try_from(config.header.trim()).unwrap_or_else(|_| {
+            tracing::warn!(
+                "[PRIORITY_LANE] 无效的请求头名称 '{}'，使用 x-proxycast-priority",
+                config.header
+            );
+            HeaderName::from_static("x-proxycast-priority")
+        });
+        Self {
+            header,
+            batch_min_body_bytes: config.batch_min_body_kb.saturating_mul(1024),
+        }
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_LANE: TrafficLane;
+}
+
+/// 当前请求的流量类别（不在请求上下文中时为交互式）
+pub fn current_lane() -> TrafficLane {
+    CURRENT_LANE.try_with(|lane| *lane).unwrap_or_default()
+}
+
+/// 在指定流量类别下执行同步代码
+pub fn with_lane<R>(lane: TrafficLane, f: impl FnOnce() -> R) -> R {
+    CURRENT_LANE.sync_scope(lane, f)
+}
+
+/// 为路由组添加优先级通道中间件（未启用时不添加）
+pub fn with_priority_lanes<S>(router: Router<S>, config: &PriorityLanesConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if !config.enabled {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(PriorityLaneSettings::from(config)),
+        assign_priority_lane,
+    ))
+}
+
+/// 判断请求的流量类别
+pub async fn assign_priority_lane(
+    State(settings): State<Arc<PriorityLaneSettings>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(lane) = request
+        .headers()
+        .get(&settings.header)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TrafficLane::parse)
+    {
+        return CURRENT_LANE.scope(lane, next.run(request)).await;
+    }
+
+    if request.uri().path().contains("streamGenerateContent") || !is_large(&settings, &request) {
+        return CURRENT_LANE
+            .scope(TrafficLane::Interactive, next.run(request))
+            .await;
+    }
+
+    // 大请求需要读取请求体判断是否为流式请求（请求体大小已由外层的 Content-Length 检查限制）
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": format!("Failed to read request body: {}", e)
+                    }
+                })),
+            )
+                .into_response()
+        }
+    };
+    let lane = if is_streaming_body(&body) {
+        TrafficLane::Interactive
+    } else {
+        TrafficLane::Batch
+    };
+    let request = Request::from_parts(parts, Body::from(body));
+    CURRENT_LANE.scope(lane, next.run(request)).await
+}
+
+/// Content-Length 是否达到批处理阈值
+fn is_large(settings: &PriorityLaneSettings, request: &Request) -> bool {
+    settings.batch_min_body_bytes > 0
+        && request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .is_some_and(|len| len >= settings.batch_min_body_bytes)
+}
+
+#[derive(Deserialize)]
+struct StreamFlag {
+    #[serde(default)]
+    stream: bool,
+}
+
+/// 请求体是否为流式请求
+fn is_streaming_body(body: &[u8]) -> bool {
+    serde_json::from_slice::<StreamFlag>(body).is_ok_and(|flag| flag.stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        let config = PriorityLanesConfig {
+            enabled: true,
+            batch_min_body_kb: 1,
+            ..PriorityLanesConfig::default()
+        };
+        with_priority_lanes(
+            Router::new()
+                .route("/v1/messages", post(|| async { current_lane().as_str() }))
+                .route(
+                    "/v1beta/models/*action",
+                    post(|| async { current_lane().as_str() }),
+                ),
+            &config,
+        )
+    }
+
+    async fn lane(path: &str, priority: Option<&str>, body: String) -> String {
+        let mut request = Request::post(path).header(header::CONTENT_LENGTH, body.len());
+        if let Some(priority) = priority {
+            request = request.header("x-proxycast-priority", priority);
+        }
+        let response = app()
+            .oneshot(request.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn body(stream: bool, size: usize) -> String {
+        serde_json::json!({ "stream": stream, "prompt": "x".repeat(size) }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_classifies_requests() {
+        assert_eq!(
+            lane("/v1/messages", None, body(false, 10)).await,
+            "interactive"
+        );
+        assert_eq!(lane("/v1/messages", None, body(false, 2048)).await, "batch");
+        assert_eq!(
+            lane("/v1/messages", None, body(true, 2048)).await,
+            "interactive"
+        );
+        assert_eq!(
+            lane(
+                "/v1beta/models/gemini-2.5-pro:streamGenerateContent",
+                None,
+                body(false, 2048)
+            )
+            .await,
+            "interactive"
+        );
+
+        // 请求头优先
+        assert_eq!(
+            lane("/v1/messages", Some("batch"), body(true, 10)).await,
+            "batch"
+        );
+        assert_eq!(
+            lane("/v1/messages", Some("Interactive"), body(false, 2048)).await,
+            "interactive"
+        );
+        assert_eq!(
+            lane("/v1/messages", Some("other"), body(false, 2048)).await,
+            "batch"
+        );
+    }
+
+    #[test]
+    fn test_current_lane_outside_request() {
+        assert_eq!(current_lane(), TrafficLane::Interactive);
+        assert_eq!(
+            with_lane(TrafficLane::Batch, current_lane),
+            TrafficLane::Batch
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! 请求 ID 中间件
+//!
+//! - 读取入站请求头（默认 `x-request-id`）中的请求 ID，缺失、无效或不信任入站 ID 时生成 UUID
+//! - 请求处理期间通过 task-local 暴露当前请求 ID，供请求上下文、响应体、原始响应转储、遥测和日志使用
+//! - 处理器内的 tracing 日志挂在带 `request_id` 字段的 span 下
+//! - 响应头回写同名请求头
+//! - 每次上游请求（包括重试和故障转移）使用子 ID `<请求 ID>.<序号>`，序号从 1 开始递增
+
+use crate::config::RequestIdConfig;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tracing::Instrument;
+
+/// 入站请求 ID 最大长度
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// 中间件设置（启动时解析请求头名称）
+#[derive(Debug, Clone)]
+pub struct RequestIdSettings {
+    header: HeaderName,
+    trust_inbound: bool,
+    propagate_upstream: bool,
+}
+
+impl From<&RequestIdConfig> for RequestIdSettings {
+    fn from(config: &RequestIdConfig) -> Self {
+        let header = HeaderName::try_from(config.header.trim()).unwrap_or_else(|_| {
+            tracing::warn!(
+                "[REQUEST_ID] 无效的请求头名称 '{}'，使用 x-request-id",
+                config.header
+            );
+            HeaderName::from_static("x-request-id")
+        });
+        Self {
+            header,
+            trust_inbound: config.trust_inbound,
+            propagate_upstream: config.propagate_upstream,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CurrentRequest {
+    id: String,
+    settings: Arc<RequestIdSettings>,
+    /// 已发出的上游请求数（派生任务共享）
+    hops: Arc<AtomicU32>,
+}
+
+tokio::task_local! {
+    static CURRENT_REQUEST: CurrentRequest;
+}
+
+/// 为路由组添加请求 ID 中间件
+pub fn with_request_id<S>(router: Router<S>, config: &RequestIdConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(RequestIdSettings::from(config)),
+        assign_request_id,
+    ))
+}
+
+/// 分配请求 ID 并在响应头中回写
+pub async fn assign_request_id(
+    State(settings): State<Arc<RequestIdSettings>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let inbound = request
+        .headers()
+        .get(&settings.header)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| settings.trust_inbound)
+        .filter(|id| is_valid_request_id(id));
+    let id = inbound
+        .map(|id| id.to_string())
+        .unwrap_or_else(new_request_id);
+
+    // 请求 ID 只包含可见 ASCII 字符，一定是合法的请求头值
+    let header_value = HeaderValue::from_str(&id).expect("valid request id");
+    request
+        .headers_mut()
+        .insert(settings.header.clone(), header_value.clone());
+
+    let span = tracing::info_span!("request", request_id = %id);
+    let current = CurrentRequest {
+        id,
+        settings: settings.clone(),
+        hops: Arc::new(AtomicU32::new(0)),
+    };
+    let mut response = CURRENT_REQUEST
+        .scope(current, next.run(request).instrument(span))
+        .await;
+    response
+        .headers_mut()
+        .insert(settings.header.clone(), header_value);
+    response
+}
+
+/// 入站请求 ID 校验：非空、不超过最大长度、仅包含可见 ASCII 字符
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// 生成新的请求 ID（所有请求 ID 的唯一来源）
+pub fn new_request_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// 子请求 ID：`<父 ID>.<序号>`
+pub fn child_request_id(parent: &str, hop: u32) -> String {
+    format!("{parent}.{hop}")
+}
+
+/// 当前请求 ID（不在请求处理上下文中时为 None）
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST.try_with(|current| current.id.clone()).ok()
+}
+
+/// 当前请求 ID，不在请求处理上下文中（如 WebSocket 消息）时生成新 ID
+pub fn current_or_new_request_id() -> String {
+    current_request_id().unwrap_or_else(new_request_id)
+}
+
+/// 响应体中的 ID（如 `chatcmpl-<请求 ID>`、`msg_<请求 ID>`）
+pub fn response_id(prefix: &str) -> String {
+    format!("{prefix}{}", current_or_new_request_id())
+}
+
+/// 在当前请求上下文（请求 ID 和 tracing span）中运行 future，用于派生的后台任务
+pub fn in_current_request<F>(future: F) -> impl std::future::Future<Output = F::Output>
+where
+    F: std::future::Future,
+{
+    let current = CURRENT_REQUEST.try_with(|current| current.clone()).ok();
+    let future = future.in_current_span();
+    async move {
+        match current {
+            Some(current) => CURRENT_REQUEST.scope(current, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// 为上游请求附加当前请求 ID
+pub trait RequestIdExt {
+    fn with_request_id(self) -> Self;
+}
+
+impl RequestIdExt for reqwest::RequestBuilder {
+    fn with_request_id(self) -> Self {
+        let header = CURRENT_REQUEST
+            .try_with(|current| {
+                current.settings.propagate_upstream.then(|| {
+                    let hop = current.hops.fetch_add(1, Ordering::Relaxed) + 1;
+                    (
+                        current.settings.header.clone(),
+                        child_request_id(&current.id, hop),
+                    )
+                })
+            })
+            .ok()
+            .flatten();
+        match header {
+            Some((name, id)) => {
+                tracing::debug!("[REQUEST_ID] 上游请求 ID: {}", id);
+                self.header(name.as_str(), id)
+            }
+            None => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get};
+    use tower::ServiceExt;
+
+    fn app(config: RequestIdConfig) -> Router {
+        with_request_id(
+            Router::new().route(
+                "/id",
+                get(|| async { current_request_id().unwrap_or_default() }),
+            ),
+            &config,
+        )
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_propagates_inbound_request_id() {
+        let config = RequestIdConfig {
+            header: "X-Correlation-Id".to_string(),
+            ..RequestIdConfig::default()
+        };
+        let request = Request::get("/id")
+            .header("x-correlation-id", "trace-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(config).oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["x-correlation-id"], "trace-123");
+        assert_eq!(body_text(response).await, "trace-123");
+    }
+
+    #[tokio::test]
+    async fn test_generates_request_id_when_untrusted_or_invalid() {
+        let untrusted = RequestIdConfig {
+            trust_inbound: false,
+            ..RequestIdConfig::default()
+        };
+        let request = Request::get("/id")
+            .header("x-request-id", "client-id")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(untrusted).oneshot(request).await.unwrap();
+        let id = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_ne!(id, "client-id");
+        assert_eq!(body_text(response).await, id);
+
+        let request = Request::get("/id")
+            .header("x-request-id", "a".repeat(MAX_REQUEST_ID_LENGTH + 1))
+            .body(Body::empty())
+            .unwrap();
+        let response = app(RequestIdConfig::default())
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-request-id"].len(), 36);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_requests_use_child_ids() {
+        let app = with_request_id(
+            Router::new().route(
+                "/hops",
+                get(|| async {
+                    let client = reqwest::Client::new();
+                    let ids: Vec<String> = (0..2)
+                        .map(|_| {
+                            let request = client
+                                .post("http://upstream.invalid")
+                                .with_request_id()
+                                .build()
+                                .unwrap();
+                            request.headers()["x-request-id"]
+                                .to_str()
+                                .unwrap()
+                                .to_string()
+                        })
+                        .collect();
+                    ids.join(",")
+                }),
+            ),
+            &RequestIdConfig::default(),
+        );
+        let request = Request::get("/hops")
+            .header("x-request-id", "trace-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(body_text(response).await, "trace-123.1,trace-123.2");
+    }
+
+    #[tokio::test]
+    async fn test_context_and_response_ids_use_request_id() {
+        let app = with_request_id(
+            Router::new().route(
+                "/ids",
+                get(|| async {
+                    let ctx = crate::processor::RequestContext::new("model".to_string());
+                    format!("{},{}", ctx.request_id, response_id("msg_"))
+                }),
+            ),
+            &RequestIdConfig::default(),
+        );
+        let request = Request::get("/ids")
+            .header("x-request-id", "trace-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(body_text(response).await, "trace-123,msg_trace-123");
+    }
+
+    #[test]
+    fn test_current_request_id_outside_request() {
+        assert_eq!(current_request_id(), None);
+        assert_eq!(response_id("chatcmpl-").len(), "chatcmpl-".len() + 36);
+        assert_eq!(child_request_id("abc", 2), "abc.2");
+    }
+}
@@ -0,0 +1,233 @@
+//! 响应信息头中间件
+//!
+//! 启用后在代理响应上添加 `x-proxycast-*` 响应头，说明本次请求的调度情况：
+//! - `x-proxycast-provider`：选择的 Provider
+//! - `x-proxycast-credential`：使用的凭证名称（`mask_credential` 时为凭证 UUID 前 8 位）
+//! - `x-proxycast-retries`：重新选择凭证的次数
+//! - `x-proxycast-queue-wait-ms`：从开始处理请求到选定凭证的耗时
+//! - `x-proxycast-estimated-tokens`：本地估算的输入 Token 数
+//! - `x-proxycast-budget-remaining`：凭证当日剩余请求次数（仅配置了每日请求上限的凭证）
+//!
+//! 请求期间凭证池和处理函数通过 task-local 记录信息，未记录的项不输出。
+
+use crate::config::ResponseInfoHeadersConfig;
+use crate::models::provider_pool_model::ProviderCredential;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Instant;
+
+pub const PROVIDER_HEADER: &str = "x-proxycast-provider";
+pub const CREDENTIAL_HEADER: &str = "x-proxycast-credential";
+pub const RETRIES_HEADER: &str = "x-proxycast-retries";
+pub const QUEUE_WAIT_HEADER: &str = "x-proxycast-queue-wait-ms";
+pub const ESTIMATED_TOKENS_HEADER: &str = "x-proxycast-estimated-tokens";
+pub const BUDGET_REMAINING_HEADER: &str = "x-proxycast-budget-remaining";
+
+/// 请求期间记录的调度信息
+#[derive(Debug)]
+struct ResponseInfo {
+    started: Instant,
+    provider: Option<String>,
+    credential_name: Option<String>,
+    credential_uuid: Option<String>,
+    /// 凭证选择次数
+    selections: u32,
+    queue_wait_ms: Option<u64>,
+    estimated_tokens: Option<u32>,
+    budget_remaining: Option<u64>,
+}
+
+impl ResponseInfo {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            provider: None,
+            credential_name: None,
+            credential_uuid: None,
+            selections: 0,
+            queue_wait_ms: None,
+            estimated_tokens: None,
+            budget_remaining: None,
+        }
+    }
+
+    fn record_credential(&mut self, credential: &ProviderCredential) {
+        let now = chrono::Utc::now();
+        self.selections += 1;
+        self.queue_wait_ms
+            .get_or_insert_with(|| self.started.elapsed().as_millis() as u64);
+        self.provider = Some(credential.provider_type.to_string());
+        self.credential_name = Some(
+            credential
+                .name
+                .clone()
+                .unwrap_or_else(|| credential.credential.display_name()),
+        );
+        self.credential_uuid = Some(credential.uuid.clone());
+        // 本次请求计入后的剩余次数
+        self.budget_remaining = credential
+            .schedule
+            .as_ref()
+            .and_then(|schedule| schedule.daily_request_limit)
+            .map(|limit| limit.saturating_sub(credential.current_daily_usage(now) + 1));
+    }
+
+    fn apply(&self, headers: &mut HeaderMap, mask_credential: bool) {
+        let mut insert = |name: &'static str, value: String| {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        };
+        if let Some(provider) = &self.provider {
+            insert(PROVIDER_HEADER, provider.clone());
+        }
+        let credential = if mask_credential {
+            self.credential_uuid
+                .as_ref()
+                .map(|uuid| uuid.chars().take(8).collect())
+        } else {
+            self.credential_name
+                .as_ref()
+                .map(|name| urlencoding::encode(name).into_owned())
+        };
+        if let Some(credential) = credential {
+            insert(CREDENTIAL_HEADER, credential);
+        }
+        if self.selections > 0 {
+            insert(RETRIES_HEADER, (self.selections - 1).to_string());
+        }
+        if let Some(wait) = self.queue_wait_ms {
+            insert(QUEUE_WAIT_HEADER, wait.to_string());
+        }
+        if let Some(tokens) = self.estimated_tokens {
+            insert(ESTIMATED_TOKENS_HEADER, tokens.to_string());
+        }
+        if let Some(remaining) = self.budget_remaining {
+            insert(BUDGET_REMAINING_HEADER, remaining.to_string());
+        }
+    }
+}
+
+tokio::task_local! {
+    static RESPONSE_INFO: Arc<Mutex<ResponseInfo>>;
+}
+
+/// 记录当前请求选择的凭证（不在启用了响应信息头的请求中时忽略）
+pub fn record_credential(credential: &ProviderCredential) {
+    let _ = RESPONSE_INFO.try_with(|info| info.lock().record_credential(credential));
+}
+
+/// 记录当前请求的估算输入 Token 数（只在需要时计算）
+pub fn record_estimated_tokens(estimate: impl FnOnce() -> u32) {
+    let _ = RESPONSE_INFO.try_with(|info| info.lock().estimated_tokens = Some(estimate()));
+}
+
+/// 为路由组添加响应信息头中间件（未启用时不添加）
+pub fn with_response_info_headers<S>(
+    router: Router<S>,
+    config: &ResponseInfoHeadersConfig,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if !config.enabled {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        config.mask_credential,
+        add_response_info_headers,
+    ))
+}
+
+/// 请求期间收集调度信息，写入响应头
+pub async fn add_response_info_headers(
+    State(mask_credential): State<bool>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let info = Arc::new(Mutex::new(ResponseInfo::new()));
+    let mut response = RESPONSE_INFO.scope(info.clone(), next.run(request)).await;
+    info.lock().apply(response.headers_mut(), mask_credential);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::{
+        CredentialData, CredentialSchedule, PoolProviderType,
+    };
+    use axum::{body::Body, routing::post};
+    use tower::ServiceExt;
+
+    fn credential(name: &str, daily_request_limit: Option<u64>) -> ProviderCredential {
+        let mut credential = ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        credential.name = Some(name.to_string());
+        credential.schedule = daily_request_limit.map(|limit| CredentialSchedule {
+            windows: Vec::new(),
+            daily_request_limit: Some(limit),
+            reset_time: "00:00".to_string(),
+        });
+        credential
+    }
+
+    async fn response_headers(mask_credential: bool) -> HeaderMap {
+        let config = ResponseInfoHeadersConfig {
+            enabled: true,
+            mask_credential,
+        };
+        let app = with_response_info_headers(
+            Router::new().route(
+                "/v1/messages",
+                post(|| async {
+                    record_estimated_tokens(|| 42);
+                    record_credential(&credential("主力 key", None));
+                    record_credential(&credential("backup", Some(10)));
+                    "ok"
+                }),
+            ),
+            &config,
+        );
+        let response = app
+            .oneshot(Request::post("/v1/messages").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.headers().clone()
+    }
+
+    #[tokio::test]
+    async fn test_adds_response_info_headers() {
+        let headers = response_headers(false).await;
+        assert_eq!(headers.get(PROVIDER_HEADER).unwrap(), "claude");
+        assert_eq!(headers.get(CREDENTIAL_HEADER).unwrap(), "backup");
+        assert_eq!(headers.get(RETRIES_HEADER).unwrap(), "1");
+        assert!(headers.contains_key(QUEUE_WAIT_HEADER));
+        assert_eq!(headers.get(ESTIMATED_TOKENS_HEADER).unwrap(), "42");
+        assert_eq!(headers.get(BUDGET_REMAINING_HEADER).unwrap(), "9");
+
+        // 掩码时只输出 UUID 前 8 位
+        let headers = response_headers(true).await;
+        let masked = headers.get(CREDENTIAL_HEADER).unwrap().to_str().unwrap();
+        assert_eq!(masked.len(), 8);
+        assert_ne!(masked, "backup");
+    }
+
+    #[test]
+    fn test_record_outside_request_is_ignored() {
+        record_credential(&credential("backup", None));
+        record_estimated_tokens(|| unreachable!());
+    }
+}
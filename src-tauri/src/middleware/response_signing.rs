@@ -0,0 +1,260 @@
+//! 响应签名中间件
+//!
+//! 启用后对代理响应体计算 HMAC-SHA256，写入 `x-proxycast-signature: sha256=<hex>` 响应头。
+//! 下游用同一密钥对收到的响应体重新计算并比较，即可确认响应经过 proxycast 且未被篡改。
+//!
+//! SSE 流式响应在发出响应头时正文尚未生成，不签名；超过大小上限的响应体原样转发，不签名。
+
+use crate::config::{BodyLimitsConfig, ResponseSigningConfig};
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+    Router,
+};
+use bytes::BytesMut;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+
+pub const SIGNATURE_HEADER: &str = "x-proxycast-signature";
+
+/// 签名值前缀（标明算法）
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(key: &[u8], body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(body);
+    mac
+}
+
+/// 计算响应体签名（`sha256=<hex>`）
+pub fn sign(key: &[u8], body: &[u8]) -> String {
+    format!(
+        "{}{}",
+        SIGNATURE_PREFIX,
+        hex::encode(mac(key, body).finalize().into_bytes())
+    )
+}
+
+/// 验证响应体签名（常量时间比较，签名可省略 `sha256=` 前缀）
+pub fn verify(key: &[u8], body: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let signature = signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .unwrap_or(signature);
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    mac(key, body).verify_slice(&expected).is_ok()
+}
+
+/// 为路由组添加响应签名中间件（未启用或未配置密钥时不添加）
+pub fn with_response_signing<S>(router: Router<S>, config: &ResponseSigningConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if !config.enabled {
+        return router;
+    }
+    if config.key.is_empty() {
+        tracing::warn!("[SIGNING] 已启用响应签名但未配置密钥，响应不签名");
+        return router;
+    }
+    let signer = Arc::new(Signer {
+        key: config.key.as_bytes().into(),
+        max_body_bytes: BodyLimitsConfig::bytes(config.max_body_mb),
+    });
+    router.layer(axum::middleware::from_fn_with_state(signer, sign_response))
+}
+
+/// 签名密钥及签名的响应体大小上限
+pub struct Signer {
+    key: Vec<u8>,
+    max_body_bytes: usize,
+}
+
+/// 读取完整响应体并写入签名头
+pub async fn sign_response(
+    State(signer): State<Arc<Signer>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let is_event_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if is_event_stream {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    if body.size_hint().lower() > signer.max_body_bytes as u64 {
+        tracing::debug!("[SIGNING] 响应体超过签名大小上限，不签名");
+        return Response::from_parts(parts, body);
+    }
+
+    // 逐块读取，超过上限时把已读取的部分和剩余部分原样转发
+    let mut stream = body.into_data_stream();
+    let mut buffered = BytesMut::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::warn!("[SIGNING] 读取响应体失败: {}", e);
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    Json(serde_json::json!({
+                        "type": "error",
+                        "error": {
+                            "type": "api_error",
+                            "message": format!("Failed to read response body: {}", e),
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+        };
+        buffered.extend_from_slice(&chunk);
+        if buffered.len() > signer.max_body_bytes {
+            tracing::debug!("[SIGNING] 响应体超过签名大小上限，不签名");
+            let head =
+                futures::stream::once(async move { Ok::<_, axum::Error>(buffered.freeze()) });
+            return Response::from_parts(parts, Body::from_stream(head.chain(stream)));
+        }
+    }
+
+    let body = buffered.freeze();
+    if let Ok(value) = HeaderValue::from_str(&sign(&signer.key, &body)) {
+        parts.headers.insert(SIGNATURE_HEADER, value);
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use tower::ServiceExt;
+
+    fn config(key: &str) -> ResponseSigningConfig {
+        ResponseSigningConfig {
+            enabled: true,
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signature = sign(b"secret", b"{\"ok\":true}");
+        assert!(signature.starts_with(SIGNATURE_PREFIX));
+        assert!(verify(b"secret", b"{\"ok\":true}", &signature));
+        // 省略前缀也可以验证
+        assert!(verify(
+            b"secret",
+            b"{\"ok\":true}",
+            signature.trim_start_matches(SIGNATURE_PREFIX)
+        ));
+        assert!(!verify(b"other", b"{\"ok\":true}", &signature));
+        assert!(!verify(b"secret", b"{\"ok\":false}", &signature));
+        assert!(!verify(b"secret", b"{\"ok\":true}", "sha256=not-hex"));
+    }
+
+    #[tokio::test]
+    async fn test_signs_response_body() {
+        let app = with_response_signing(
+            Router::new()
+                .route("/v1/messages", post(|| async { "{\"id\":\"msg_1\"}" }))
+                .route(
+                    "/v1/stream",
+                    get(|| async {
+                        (
+                            [(header::CONTENT_TYPE, "text/event-stream")],
+                            "data: {}\n\n",
+                        )
+                    }),
+                ),
+            &config("secret"),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(Request::post("/v1/messages").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let signature = response.headers()[SIGNATURE_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"{\"id\":\"msg_1\"}");
+        assert!(verify(b"secret", &body, &signature));
+
+        // 流式响应不签名
+        let response = app
+            .oneshot(Request::get("/v1/stream").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(SIGNATURE_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_empty_key_disables_signing() {
+        let app = with_response_signing(
+            Router::new().route("/v1/messages", post(|| async { "ok" })),
+            &config(""),
+        );
+        let response = app
+            .oneshot(Request::post("/v1/messages").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(SIGNATURE_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_passes_through_unsigned() {
+        const LIMIT: usize = 1024 * 1024;
+        let app = with_response_signing(
+            Router::new()
+                .route("/v1/sized", post(|| async { vec![b'a'; LIMIT + 1] }))
+                .route(
+                    "/v1/chunked",
+                    post(|| async {
+                        // 未知长度的响应体，读取超过上限后转发剩余部分
+                        let chunks = (0..4).map(|_| {
+                            Ok::<_, std::io::Error>(axum::body::Bytes::from(vec![b'b'; LIMIT / 2]))
+                        });
+                        Body::from_stream(futures::stream::iter(chunks))
+                    }),
+                ),
+            &ResponseSigningConfig {
+                max_body_mb: 1,
+                ..config("secret")
+            },
+        );
+
+        for (path, len) in [("/v1/sized", LIMIT + 1), ("/v1/chunked", LIMIT * 2)] {
+            let response = app
+                .clone()
+                .oneshot(Request::post(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(!response.headers().contains_key(SIGNATURE_HEADER));
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body.len(), len);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! SSE 心跳中间件
+//!
+//! 流式响应空闲超过指定时间时插入心跳，避免被代理、负载均衡等中间设备按空闲超时断开：
+//! - `/v1/messages` 发送 Anthropic `ping` 事件（客户端 SDK 会忽略）
+//! - `/v1/chat/completions` 发送 SSE 注释行（`: ping`）
+//!
+//! 心跳只在完整事件之后插入，不会打断上游正在输出的事件。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+
+/// Anthropic 格式心跳事件
+const ANTHROPIC_PING: &[u8] = b"event: ping\ndata: {\"type\": \"ping\"}\n\n";
+
+/// OpenAI 格式心跳（SSE 注释行）
+const COMMENT_PING: &[u8] = b": ping\n\n";
+
+/// 为路由组添加 SSE 心跳（间隔为 0 时不启用）
+pub fn with_sse_heartbeat<S>(router: Router<S>, interval_secs: u64) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if interval_secs == 0 {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Duration::from_secs(interval_secs),
+        inject_sse_heartbeat,
+    ))
+}
+
+/// 为 SSE 响应体注入心跳
+pub async fn inject_sse_heartbeat(
+    State(interval): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ping = heartbeat_for_path(request.uri().path());
+    let response = next.run(request).await;
+    let Some(ping) = ping else {
+        return response;
+    };
+
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !is_sse {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let stream = with_heartbeat(body.into_data_stream(), interval, ping);
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 根据请求路径选择心跳格式
+fn heartbeat_for_path(path: &str) -> Option<&'static [u8]> {
+    if path.ends_with("/chat/completions") {
+        Some(COMMENT_PING)
+    } else if path.ends_with("/messages") {
+        Some(ANTHROPIC_PING)
+    } else {
+        None
+    }
+}
+
+/// 包装数据流：空闲超过 `interval` 且处于事件边界时输出心跳
+fn with_heartbeat<S>(
+    mut inner: S,
+    interval: Duration,
+    ping: &'static [u8],
+) -> impl Stream<Item = Result<Bytes, axum::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin + Send + 'static,
+{
+    async_stream::stream! {
+        let mut at_boundary = true;
+        loop {
+            match tokio::time::timeout(interval, inner.next()).await {
+                Ok(Some(Ok(chunk))) => {
+                    if !chunk.is_empty() {
+                        at_boundary = chunk.ends_with(b"\n\n") || chunk.ends_with(b"\r\n\r\n");
+                    }
+                    yield Ok(chunk);
+                }
+                Ok(Some(Err(e))) => {
+                    yield Err(e);
+                    break;
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    if at_boundary {
+                        yield Ok(Bytes::from_static(ping));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn collect(chunks: Vec<(u64, &'static str)>, ping: &'static [u8]) -> String {
+        let inner = futures::stream::iter(chunks)
+            .then(|(delay_ms, chunk)| async move {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                Ok::<_, axum::Error>(Bytes::from_static(chunk.as_bytes()))
+            })
+            .boxed();
+        let output: Vec<Bytes> = with_heartbeat(inner, Duration::from_millis(40), ping)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        String::from_utf8(output.concat()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_between_events() {
+        let output = collect(
+            vec![(0, "data: {\"a\":1}\n\n"), (100, "data: [DONE]\n\n")],
+            COMMENT_PING,
+        )
+        .await;
+        assert!(output.starts_with("data: {\"a\":1}\n\n: ping\n\n"));
+        assert!(output.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_no_heartbeat_inside_event() {
+        let output = collect(
+            vec![(0, "event: content_block_delta\n"), (100, "data: {}\n\n")],
+            ANTHROPIC_PING,
+        )
+        .await;
+        assert_eq!(output, "event: content_block_delta\ndata: {}\n\n");
+    }
+
+    #[test]
+    fn test_heartbeat_for_path() {
+        assert_eq!(heartbeat_for_path("/v1/messages"), Some(ANTHROPIC_PING));
+        assert_eq!(
+            heartbeat_for_path("/kiro/v1/chat/completions"),
+            Some(COMMENT_PING)
+        );
+        assert_eq!(heartbeat_for_path("/v1/messages/count_tokens"), None);
+    }
+}
@@ -0,0 +1,388 @@
+//! SSE 断点续传中间件
+//!
+//! 启用后 `/v1/messages`、`/v1/chat/completions` 的流式响应在服务端缓冲：
+//! - 每个事件带 `id: <stream_id>:<序号>` 字段，响应头 `x-proxycast-stream-id` 给出流 ID
+//! - 上游响应在后台任务中读取，客户端断开不会中断生成
+//! - 客户端带 `Last-Event-ID` 请求头重新发送请求时，从该事件之后继续输出（先补发已缓冲的事件，
+//!   再跟随实时输出），不再请求上游
+//!
+//! 流结束后缓冲保留 `retention_secs` 秒。找不到对应的流（已过期、流 ID 错误或 API Key 不同）时
+//! 按新请求处理，新响应的事件 ID 带新的流 ID，客户端可据此判断未能续传。
+
+use super::buffered_response::{BufferStore, BufferedResponse};
+use super::request_id::in_current_request;
+use crate::config::SseResumeConfig;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 流 ID 响应头
+pub const STREAM_ID_HEADER: &str = "x-proxycast-stream-id";
+
+/// 续传请求头
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// 续传时校验的认证请求头（只有同一 API Key 可以续传）
+const AUTH_HEADERS: &[&str] = &["authorization", "x-api-key", "x-goog-api-key"];
+
+/// 缓冲中和保留期内的流
+pub struct SseResumeStore {
+    buffers: BufferStore,
+}
+
+impl SseResumeStore {
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            buffers: BufferStore::new(retention),
+        }
+    }
+
+    fn insert(&self, stream_id: &str, owner: &str, stream: Arc<BufferedResponse>) {
+        self.buffers
+            .entries()
+            .insert(entry_key(stream_id, owner), stream);
+    }
+
+    /// 查找流（只返回同一认证请求头创建的流）
+    fn get(&self, stream_id: &str, owner: &str) -> Option<Arc<BufferedResponse>> {
+        self.buffers
+            .entries()
+            .get(&entry_key(stream_id, owner))
+            .cloned()
+    }
+}
+
+/// 缓冲条目的键（认证请求头指纹 + 流 ID）
+fn entry_key(stream_id: &str, owner: &str) -> String {
+    format!("{}/{}", owner, stream_id)
+}
+
+/// 为路由组添加 SSE 断点续传中间件（未启用时不添加）
+pub fn with_sse_resume<S>(router: Router<S>, config: &SseResumeConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if !config.enabled {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(SseResumeStore::new(Duration::from_secs(
+            config.retention_secs,
+        ))),
+        resume_sse,
+    ))
+}
+
+/// 缓冲流式响应，处理带 `Last-Event-ID` 的续传请求
+pub async fn resume_sse(
+    State(store): State<Arc<SseResumeStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::POST || !is_resumable_path(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let owner = owner_key(request.headers());
+    let last_event_id = request
+        .headers()
+        .get(LAST_EVENT_ID)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_event_id);
+    if let Some((stream_id, seq)) = last_event_id {
+        match store.get(stream_id, &owner) {
+            Some(stream) => {
+                tracing::info!("[SSE_RESUME] 续传流 {}，从事件 {} 之后开始", stream_id, seq);
+                return respond(stream_id, stream, seq.saturating_add(1)).await;
+            }
+            None => tracing::info!("[SSE_RESUME] 流 {} 不存在或已过期，按新请求处理", stream_id),
+        }
+    }
+
+    let response = next.run(request).await;
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !response.status().is_success() || !is_sse {
+        return response;
+    }
+
+    let stream_id = uuid::Uuid::new_v4().simple().to_string();
+    let (parts, body) = response.into_parts();
+    let stream = Arc::new(BufferedResponse::with_head(parts.status, parts.headers));
+    store.insert(&stream_id, &owner, stream.clone());
+    tokio::spawn(in_current_request(buffer_events(
+        stream_id.clone(),
+        stream.clone(),
+        body,
+    )));
+    respond(&stream_id, stream, 0).await
+}
+
+/// 只缓冲生成类接口的流式响应
+fn is_resumable_path(path: &str) -> bool {
+    path.ends_with("/chat/completions") || path.ends_with("/messages")
+}
+
+/// 解析 `<stream_id>:<序号>`
+fn parse_event_id(value: &str) -> Option<(&str, usize)> {
+    let (stream_id, seq) = value.trim().rsplit_once(':')?;
+    if stream_id.is_empty() {
+        return None;
+    }
+    Some((stream_id, seq.parse().ok()?))
+}
+
+/// 认证请求头的 SHA-256
+fn owner_key(headers: &HeaderMap) -> String {
+    let mut hasher = Sha256::new();
+    for name in AUTH_HEADERS {
+        for value in headers.get_all(*name) {
+            hasher.update(name.as_bytes());
+            hasher.update(b": ");
+            hasher.update(value.as_bytes());
+            hasher.update(b"\n");
+        }
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// 查找事件结束位置（返回分隔符之后的下标）
+fn event_end(buf: &[u8]) -> Option<usize> {
+    let lf = buf.windows(2).position(|w| w == b"\n\n").map(|i| i + 2);
+    let crlf = buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4);
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 为完整事件加上 `id` 字段（去掉上游原有的 `id` 字段，只有注释行的事件不加）
+fn tag_event(event: &[u8], stream_id: &str, seq: usize) -> Bytes {
+    let text = String::from_utf8_lossy(event);
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.is_empty() && *line != "id" && !line.starts_with("id:"))
+        .collect();
+    let mut tagged = if lines.iter().all(|line| line.starts_with(':')) {
+        String::new()
+    } else {
+        format!("id: {}:{}\n", stream_id, seq)
+    };
+    for line in lines {
+        tagged.push_str(line);
+        tagged.push('\n');
+    }
+    if !tagged.is_empty() {
+        tagged.push('\n');
+    }
+    Bytes::from(tagged)
+}
+
+/// 事件是否带 `id` 字段（由 [`tag_event`] 添加）
+fn has_id(event: &[u8]) -> bool {
+    event.starts_with(b"id: ")
+}
+
+/// 读取上游响应体，按事件切分并写入缓冲
+async fn buffer_events(stream_id: String, stream: Arc<BufferedResponse>, body: Body) {
+    let mut data = body.into_data_stream();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut seq = 0;
+    while let Some(chunk) = data.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::warn!("[SSE_RESUME] 上游响应中断: {}", e);
+                stream.fail();
+                return;
+            }
+        };
+        pending.extend_from_slice(&chunk);
+        let mut events = Vec::new();
+        while let Some(end) = event_end(&pending) {
+            let event: Vec<u8> = pending.drain(..end).collect();
+            let event = tag_event(&event, &stream_id, seq);
+            if has_id(&event) {
+                seq += 1;
+            }
+            events.push(event);
+        }
+        if !events.is_empty() {
+            stream.update(|state| state.chunks.extend(events));
+        }
+    }
+    if !pending.is_empty() {
+        stream.update(|state| state.chunks.push(Bytes::from(pending)));
+    }
+    stream.finish();
+}
+
+/// 从缓冲流的第 `from` 个事件开始构建客户端响应
+async fn respond(stream_id: &str, stream: Arc<BufferedResponse>, from: usize) -> Response {
+    let (status, headers) = stream.head().await.unwrap_or_default();
+    let mut response = Response::new(stream.body(move |events| skip_events(events, from)));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response.headers_mut().remove(header::CONTENT_LENGTH);
+    if let Ok(value) = HeaderValue::from_str(stream_id) {
+        response.headers_mut().insert(STREAM_ID_HEADER, value);
+    }
+    response
+}
+
+/// 跳过前 `count` 个带 `id` 的事件及紧随其后的注释事件，返回缓冲中的下标
+fn skip_events(events: &[Bytes], count: usize) -> usize {
+    if count == 0 {
+        return 0;
+    }
+    let mut skipped = 0;
+    for (i, event) in events.iter().enumerate() {
+        if has_id(event) {
+            if skipped == count {
+                return i;
+            }
+            skipped += 1;
+        }
+    }
+    if skipped == count {
+        events.len()
+    } else {
+        // 请求的事件尚未生成，等待更多事件
+        usize::MAX
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn routes(calls: Arc<AtomicUsize>) -> Router {
+        let handler = move || {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream")],
+                    "data: {\"a\":1}\n\n: ping\n\ndata: {\"a\":2}\n\ndata: [DONE]\n\n",
+                )
+            }
+        };
+        Router::new().route("/v1/chat/completions", post(handler))
+    }
+
+    fn app(calls: Arc<AtomicUsize>) -> Router {
+        with_sse_resume(
+            routes(calls),
+            &SseResumeConfig {
+                enabled: true,
+                retention_secs: 60,
+            },
+        )
+    }
+
+    fn request(api_key: &str, last_event_id: Option<&str>) -> Request {
+        let mut builder = Request::post("/v1/chat/completions")
+            .header("x-api-key", api_key)
+            .header(header::CONTENT_LENGTH, 0);
+        if let Some(id) = last_event_id {
+            builder = builder.header("last-event-id", id);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn send(app: &Router, request: Request) -> (String, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stream_id = response.headers()[STREAM_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (stream_id, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_tag_event() {
+        assert_eq!(
+            tag_event(b"event: ping\nid: upstream\ndata: {}\n\n", "s", 3),
+            Bytes::from("id: s:3\nevent: ping\ndata: {}\n\n")
+        );
+        assert_eq!(tag_event(b": ping\n\n", "s", 3), Bytes::from(": ping\n\n"));
+        assert_eq!(parse_event_id("abc:12"), Some(("abc", 12)));
+        assert_eq!(parse_event_id("abc"), None);
+        assert_eq!(event_end(b"data: 1\r\n\r\ndata: 2\n\n"), Some(11));
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_last_event_id() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(calls.clone());
+
+        let (stream_id, body) = send(&app, request("sk-a", None)).await;
+        assert_eq!(
+            body,
+            format!(
+                "id: {0}:0\ndata: {{\"a\":1}}\n\n: ping\n\nid: {0}:1\ndata: {{\"a\":2}}\n\nid: {0}:2\ndata: [DONE]\n\n",
+                stream_id
+            )
+        );
+
+        let last_event_id = format!("{}:0", stream_id);
+        let (resumed_id, resumed) = send(&app, request("sk-a", Some(&last_event_id))).await;
+        assert_eq!(resumed_id, stream_id);
+        assert_eq!(
+            resumed,
+            format!(
+                "id: {0}:1\ndata: {{\"a\":2}}\n\nid: {0}:2\ndata: [DONE]\n\n",
+                stream_id
+            )
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 其他 API Key 不能续传，按新请求处理
+        let (other_id, _) = send(&app, request("sk-b", Some(&last_event_id))).await;
+        assert_ne!(other_id, stream_id);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resume_not_merged_by_request_dedupe() {
+        use crate::config::DedupeConfig;
+        use crate::middleware::with_request_dedupe;
+
+        // 与 build_router 相同的顺序：去重位于续传之外
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = with_request_dedupe(
+            app(calls.clone()),
+            &DedupeConfig {
+                enabled: true,
+                window_ms: 60_000,
+            },
+        );
+
+        let (stream_id, _) = send(&app, request("sk-a", None)).await;
+        let last_event_id = format!("{}:1", stream_id);
+        let (resumed_id, resumed) = send(&app, request("sk-a", Some(&last_event_id))).await;
+        assert_eq!(resumed_id, stream_id);
+        assert_eq!(resumed, format!("id: {}:2\ndata: [DONE]\n\n", stream_id));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -28,6 +28,40 @@ pub enum AnthropicContentBlock {
     },
 }
 
+/// 最早支持 Messages API 响应格式的 `anthropic-version`
+pub const ANTHROPIC_VERSION_CURRENT: &str = "2023-06-01";
+
+/// 客户端请求的 Anthropic API 版本（`anthropic-version` 请求头）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnthropicVersion {
+    /// 早于 2023-06-01：文本内容为字符串，SSE 事件不带 `event:` 行
+    Legacy,
+    /// 2023-06-01 及之后（未携带或无法识别时的默认值）
+    #[default]
+    Current,
+}
+
+impl AnthropicVersion {
+    /// 解析 `anthropic-version` 请求头的值（`YYYY-MM-DD`）
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some(v) if is_version_date(v) && v < ANTHROPIC_VERSION_CURRENT => Self::Legacy,
+            _ => Self::Current,
+        }
+    }
+}
+
+fn is_version_date(value: &str) -> bool {
+    value.len() == 10
+        && value.bytes().enumerate().all(|(i, b)| {
+            if i == 4 || i == 7 {
+                b == b'-'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageSource {
     #[serde(rename = "type")]
@@ -49,6 +83,31 @@ pub struct AnthropicTool {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub input_schema: Option<serde_json::Value>,
+    /// 工具类型：自定义工具为空或 `custom`，服务端工具为带版本的类型（如 `web_search_20250305`）
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<String>,
+    /// 服务端工具的其他参数（如 `max_uses`、`display_width_px`），原样透传
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AnthropicTool {
+    /// 是否为 Anthropic 服务端工具（web_search、computer、text_editor、bash、code_execution 等）
+    pub fn is_server_tool(&self) -> bool {
+        self.tool_type.as_deref().is_some_and(|t| t != "custom")
+    }
+
+    /// 服务端工具类型去掉版本后缀（如 `web_search_20250305` -> `web_search`）
+    pub fn server_tool_family(&self) -> Option<&str> {
+        if !self.is_server_tool() {
+            return None;
+        }
+        let tool_type = self.tool_type.as_deref()?;
+        match tool_type.rsplit_once('_') {
+            Some((family, version)) if version.bytes().all(|b| b.is_ascii_digit()) => Some(family),
+            _ => Some(tool_type),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,12 +120,85 @@ pub struct AnthropicMessagesRequest {
     pub system: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
     #[serde(default)]
     pub stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<AnthropicTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<serde_json::Value>,
+    /// 请求元数据（`user_id` 标识下游终端用户）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// system prompt 文本块
+///
+/// 不含 `cache_control`：转发给 Claude 时 system 原样透传，其他协议不支持提示缓存。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnthropicSystemBlock {
+    pub text: String,
+}
+
+/// 解析 system prompt 为文本块列表
+///
+/// 字符串视为单个块；数组保留块边界，忽略非文本块和空块。
+pub fn parse_system_blocks(system: Option<&serde_json::Value>) -> Vec<AnthropicSystemBlock> {
+    match system {
+        Some(serde_json::Value::String(s)) if !s.is_empty() => {
+            vec![AnthropicSystemBlock { text: s.clone() }]
+        }
+        Some(serde_json::Value::Array(arr)) => arr
+            .iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|item| {
+                let text = item.get("text").and_then(|t| t.as_str())?;
+                (!text.is_empty()).then(|| AnthropicSystemBlock {
+                    text: text.to_string(),
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl AnthropicMessagesRequest {
+    /// system prompt 文本块
+    pub fn system_blocks(&self) -> Vec<AnthropicSystemBlock> {
+        parse_system_blocks(self.system.as_ref())
+    }
+
+    /// 请求中的服务端工具
+    pub fn server_tools(&self) -> Vec<&AnthropicTool> {
+        self.tools
+            .iter()
+            .flatten()
+            .filter(|t| t.is_server_tool())
+            .collect()
+    }
+
+    /// 合并后的 system prompt（块之间以换行分隔），用于不支持多块的 Provider
+    pub fn system_text(&self) -> String {
+        self.system_blocks()
+            .into_iter()
+            .map(|b| b.text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `metadata.user_id`（空字符串视为未设置）
+    pub fn user_id(&self) -> Option<&str> {
+        self.metadata
+            .as_ref()?
+            .get("user_id")?
+            .as_str()
+            .filter(|id| !id.trim().is_empty())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
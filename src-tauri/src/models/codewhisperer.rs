@@ -15,6 +15,19 @@ pub struct CodeWhispererRequest {
     pub profile_arn: Option<String>,
 }
 
+impl CodeWhispererRequest {
+    /// 设置当前消息和历史用户消息的 origin
+    pub fn set_origin(&mut self, origin: &str) {
+        let state = &mut self.conversation_state;
+        state.current_message.user_input_message.origin = origin.to_string();
+        for item in state.history.iter_mut().flatten() {
+            if let HistoryItem::User(user) = item {
+                user.user_input_message.origin = origin.to_string();
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConversationState {
@@ -79,6 +79,21 @@ impl ChatMessage {
         }
     }
 
+    /// 按内容块提取文本（保留块边界，忽略图片）
+    pub fn get_text_blocks(&self) -> Vec<String> {
+        match &self.content {
+            Some(MessageContent::Text(s)) => vec![s.clone()],
+            Some(MessageContent::Parts(parts)) => parts
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text } => Some(text.clone()),
+                    _ => None,
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// 提取消息中的图片 URL 列表
     /// 返回 (format, base64_data) 元组列表
     pub fn get_images(&self) -> Vec<(String, String)> {
@@ -130,6 +145,7 @@ pub struct FunctionDef {
 /// - `function`: 标准函数调用工具，包含 function 字段
 /// - `web_search`: 联网搜索工具，无需额外字段
 /// - `web_search_20250305`: Claude Code 的联网搜索工具类型
+/// - `code_execution`: 代码执行工具（也接受 `code_interpreter`）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Tool {
@@ -142,6 +158,9 @@ pub enum Tool {
     /// 联网搜索工具（Claude Code 格式）
     #[serde(rename = "web_search_20250305")]
     WebSearch20250305,
+    /// 代码执行工具
+    #[serde(rename = "code_execution", alias = "code_interpreter")]
+    CodeExecution,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -156,6 +175,9 @@ pub struct ChatCompletionRequest {
     pub top_p: Option<f32>,
     #[serde(default)]
     pub stream: bool,
+    /// 流式选项（仅 `stream: true` 时有效）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -163,6 +185,57 @@ pub struct ChatCompletionRequest {
     /// 思维链强度：none, low, medium, high
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<String>,
+    /// 停止序列：字符串或字符串数组
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    /// 非 OpenAI 标准参数，仅用于跨格式转换时保留 Anthropic/Gemini 的 top_k，不发送给 OpenAI 上游
+    #[serde(default, skip_serializing)]
+    pub top_k: Option<u32>,
+    /// 下游终端用户标识
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// 生成的候选数量（默认 1）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    /// 是否返回输出 token 的对数概率
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// 每个位置返回的候选 token 数（需同时开启 `logprobs`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u32>,
+}
+
+impl ChatCompletionRequest {
+    /// 流式响应是否需要在最后附带用量 chunk
+    pub fn include_usage(&self) -> bool {
+        self.stream
+            && self
+                .stream_options
+                .as_ref()
+                .is_some_and(|options| options.include_usage)
+    }
+
+    /// 请求的候选数量（未设置时为 1）
+    pub fn choice_count(&self) -> u32 {
+        self.n.unwrap_or(1).max(1)
+    }
+
+    /// 是否请求了 logprobs
+    pub fn wants_logprobs(&self) -> bool {
+        self.logprobs == Some(true) || self.top_logprobs.is_some_and(|n| n > 0)
+    }
+}
+
+/// 流式选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamOptions {
+    /// 在 `[DONE]` 之前发送 `choices` 为空、包含 `usage` 的 chunk
+    #[serde(default)]
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
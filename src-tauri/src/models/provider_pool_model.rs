@@ -2,7 +2,7 @@
 //!
 //! 支持多凭证池管理，包括健康检测、负载均衡、故障转移等功能。
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -23,6 +23,127 @@ pub enum CredentialSource {
     Private,
 }
 
+/// 凭证优先级分层
+///
+/// 溢出凭证仅在同类型主力凭证全部不可用或近期被限流时使用（见 `CredentialTiersConfig`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialTier {
+    /// 主力凭证
+    #[default]
+    Primary,
+    /// 溢出凭证
+    Overflow,
+}
+
+impl CredentialTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CredentialTier::Primary => "primary",
+            CredentialTier::Overflow => "overflow",
+        }
+    }
+}
+
+impl std::str::FromStr for CredentialTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "primary" => Ok(CredentialTier::Primary),
+            "overflow" => Ok(CredentialTier::Overflow),
+            _ => Err(format!("无效的凭证分层: {}", s)),
+        }
+    }
+}
+
+/// 凭证调度时间窗口（UTC，`HH:MM`，不含结束时间；结束早于开始表示跨越午夜）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleWindow {
+    pub start: String,
+    pub end: String,
+}
+
+impl ScheduleWindow {
+    /// 指定时间是否在窗口内（开始等于结束表示全天）
+    fn contains(&self, time: NaiveTime) -> bool {
+        let (Some(start), Some(end)) = (parse_hhmm(&self.start), parse_hhmm(&self.end)) else {
+            return false;
+        };
+        if start <= end {
+            start == end || (start <= time && time < end)
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+/// 凭证调度规则
+///
+/// 限制凭证只在指定时间窗口内使用，或限制每日请求次数（按 `reset_time` 重置）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialSchedule {
+    /// 允许使用的时间窗口（为空表示不限时间）
+    #[serde(default)]
+    pub windows: Vec<ScheduleWindow>,
+    /// 每日最大请求次数
+    #[serde(default)]
+    pub daily_request_limit: Option<u64>,
+    /// 每日计数重置时间（UTC，`HH:MM`）
+    #[serde(default = "default_schedule_reset_time")]
+    pub reset_time: String,
+}
+
+fn default_schedule_reset_time() -> String {
+    "00:00".to_string()
+}
+
+fn parse_hhmm(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+impl CredentialSchedule {
+    /// 是否没有任何限制
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty() && self.daily_request_limit.is_none()
+    }
+
+    /// 校验时间格式
+    pub fn validate(&self) -> Result<(), String> {
+        let times = self
+            .windows
+            .iter()
+            .flat_map(|w| [&w.start, &w.end])
+            .chain(std::iter::once(&self.reset_time));
+        for time in times {
+            if parse_hhmm(time).is_none() {
+                return Err(format!("无效的时间格式（应为 HH:MM）: {}", time));
+            }
+        }
+        Ok(())
+    }
+
+    /// 指定时间是否在允许的时间窗口内
+    pub fn in_window(&self, now: DateTime<Utc>) -> bool {
+        self.windows.is_empty() || self.windows.iter().any(|w| w.contains(now.time()))
+    }
+
+    /// 最近一次每日计数重置的时间点
+    pub fn last_reset(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        daily_reset_boundary(parse_hhmm(&self.reset_time).unwrap_or(NaiveTime::MIN), now)
+    }
+}
+
+/// `now` 之前（含）最近的每日重置时间点
+fn daily_reset_boundary(reset: NaiveTime, now: DateTime<Utc>) -> DateTime<Utc> {
+    let today = now.date_naive().and_time(reset).and_utc();
+    if now >= today {
+        today
+    } else {
+        today - chrono::Duration::days(1)
+    }
+}
+
 /// Provider 类型别名
 ///
 /// 为了向后兼容，PoolProviderType 是 crate::ProviderType 的类型别名。
@@ -91,6 +212,21 @@ pub enum CredentialData {
         api_key: String,
         base_url: Option<String>,
     },
+    /// OpenRouter API Key 凭证（OpenAI 兼容协议）
+    OpenRouterKey {
+        api_key: String,
+        base_url: Option<String>,
+    },
+    /// Mistral API Key 凭证（OpenAI 兼容协议）
+    MistralKey {
+        api_key: String,
+        base_url: Option<String>,
+    },
+    /// DeepSeek API Key 凭证（OpenAI 兼容协议）
+    DeepSeekKey {
+        api_key: String,
+        base_url: Option<String>,
+    },
 }
 
 impl CredentialData {
@@ -142,6 +278,15 @@ impl CredentialData {
             CredentialData::AnthropicKey { api_key, .. } => {
                 format!("Anthropic: {}", mask_key(api_key))
             }
+            CredentialData::OpenRouterKey { api_key, .. } => {
+                format!("OpenRouter: {}", mask_key(api_key))
+            }
+            CredentialData::MistralKey { api_key, .. } => {
+                format!("Mistral: {}", mask_key(api_key))
+            }
+            CredentialData::DeepSeekKey { api_key, .. } => {
+                format!("DeepSeek: {}", mask_key(api_key))
+            }
         }
     }
 
@@ -161,6 +306,28 @@ impl CredentialData {
             CredentialData::IFlowOAuth { .. } => PoolProviderType::IFlow,
             CredentialData::IFlowCookie { .. } => PoolProviderType::IFlow,
             CredentialData::AnthropicKey { .. } => PoolProviderType::Anthropic,
+            CredentialData::OpenRouterKey { .. } => PoolProviderType::OpenRouter,
+            CredentialData::MistralKey { .. } => PoolProviderType::Mistral,
+            CredentialData::DeepSeekKey { .. } => PoolProviderType::DeepSeek,
+        }
+    }
+
+    /// 获取 OpenAI 兼容凭证的默认 base_url
+    ///
+    /// 用户未配置 base_url 时，OpenAI 兼容的第三方 Provider 需要使用各自的默认地址，
+    /// 而不是回退到 api.openai.com。
+    pub fn default_base_url(&self) -> Option<String> {
+        match self {
+            CredentialData::OpenRouterKey { .. } => {
+                Some(crate::providers::openrouter::OPENROUTER_BASE_URL.to_string())
+            }
+            CredentialData::MistralKey { .. } => {
+                Some(crate::providers::mistral::MISTRAL_BASE_URL.to_string())
+            }
+            CredentialData::DeepSeekKey { .. } => {
+                Some(crate::providers::deepseek::DEEPSEEK_BASE_URL.to_string())
+            }
+            _ => None,
         }
     }
 }
@@ -244,12 +411,56 @@ pub struct ProviderCredential {
     pub source: CredentialSource,
     /// 代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 优先级分层（主力/溢出）
+    #[serde(default)]
+    pub tier: CredentialTier,
+    /// 调度规则（时间窗口、每日请求次数）
+    #[serde(default)]
+    pub schedule: Option<CredentialSchedule>,
+    /// 当日请求次数（自 `daily_usage_reset_at` 起）
+    #[serde(default)]
+    pub daily_usage_count: u64,
+    /// 当日请求计数的重置时间
+    #[serde(default)]
+    pub daily_usage_reset_at: Option<DateTime<Utc>>,
+    /// 根据上游错误自动加入 `not_supported_models` 的模型及其到期时间
+    #[serde(default)]
+    pub learned_unsupported_models: HashMap<String, DateTime<Utc>>,
+    /// 标签（用于 `tag:<name>` 路由选择器）
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 固定模型：设置后无论请求哪个模型，都改用该模型请求上游
+    #[serde(default)]
+    pub pinned_model: Option<String>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// 规范化标签：去除首尾空白、转小写、去掉空标签和重复标签
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// 标签选择器前缀（`tag:<name>`）
+pub const TAG_SELECTOR_PREFIX: &str = "tag:";
+
+/// 解析标签选择器，不是 `tag:<name>` 形式时返回 None
+pub fn parse_tag_selector(selector: &str) -> Option<&str> {
+    selector
+        .strip_prefix(TAG_SELECTOR_PREFIX)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+}
+
 impl ProviderCredential {
     /// 创建新凭证
     pub fn new(provider_type: PoolProviderType, credential: CredentialData) -> Self {
@@ -277,6 +488,13 @@ impl ProviderCredential {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         }
     }
 
@@ -296,6 +514,128 @@ impl ProviderCredential {
         self.is_healthy && !self.is_disabled
     }
 
+    /// 设置标签（规范化后保存）
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = normalize_tags(tags);
+    }
+
+    /// 是否带有指定标签（不区分大小写）
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim();
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// 设置固定模型（空字符串表示清除）
+    pub fn set_pinned_model(&mut self, model: String) {
+        let model = model.trim();
+        self.pinned_model = (!model.is_empty()).then(|| model.to_string());
+    }
+
+    /// 请求模型需要改写时返回固定模型（与请求模型相同时返回 None）
+    pub fn pinned_model_for(&self, model: &str) -> Option<&str> {
+        self.pinned_model
+            .as_deref()
+            .filter(|pinned| *pinned != model)
+    }
+
+    /// 设置调度规则（没有任何限制时清除）
+    pub fn apply_schedule(&mut self, schedule: CredentialSchedule) -> Result<(), String> {
+        schedule.validate()?;
+        self.schedule = (!schedule.is_empty()).then_some(schedule);
+        Ok(())
+    }
+
+    /// 最近一次每日计数重置的时间点（未配置调度规则时为 UTC 0 点）
+    pub fn last_daily_reset(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match &self.schedule {
+            Some(schedule) => schedule.last_reset(now),
+            None => daily_reset_boundary(NaiveTime::MIN, now),
+        }
+    }
+
+    /// 当日计数是否仍然有效（自最近的重置时间点之后开始计数）
+    fn daily_usage_is_current(&self, now: DateTime<Utc>) -> bool {
+        self.daily_usage_reset_at
+            .is_some_and(|reset_at| reset_at >= self.last_daily_reset(now))
+    }
+
+    /// 当日请求次数（计数已过期时为 0）
+    pub fn current_daily_usage(&self, now: DateTime<Utc>) -> u64 {
+        if self.daily_usage_is_current(now) {
+            self.daily_usage_count
+        } else {
+            0
+        }
+    }
+
+    /// 记录一次请求后的当日计数和计数开始时间
+    pub fn next_daily_usage(&self, now: DateTime<Utc>) -> (u64, DateTime<Utc>) {
+        match self.daily_usage_reset_at {
+            Some(reset_at) if self.daily_usage_is_current(now) => {
+                (self.daily_usage_count + 1, reset_at)
+            }
+            _ => (1, now),
+        }
+    }
+
+    /// 当日计数是否已过期需要重置
+    pub fn needs_daily_reset(&self, now: DateTime<Utc>) -> bool {
+        self.daily_usage_count > 0 && !self.daily_usage_is_current(now)
+    }
+
+    /// 按调度规则当前是否可以使用（在时间窗口内且未达到每日请求上限）
+    pub fn is_schedulable(&self, now: DateTime<Utc>) -> bool {
+        let Some(schedule) = &self.schedule else {
+            return true;
+        };
+        schedule.in_window(now)
+            && schedule
+                .daily_request_limit
+                .is_none_or(|limit| self.current_daily_usage(now) < limit)
+    }
+
+    /// 最近一次错误是否为限流且仍在回避时间内
+    pub fn is_recently_rate_limited(&self, now: DateTime<Utc>, cooldown_secs: u64) -> bool {
+        let (Some(time), Some(message)) = (self.last_error_time, &self.last_error_message) else {
+            return false;
+        };
+        (now - time).num_seconds() < cooldown_secs as i64
+            && (message.contains("429")
+                || crate::resilience::Failover::is_quota_exceeded(None, message))
+    }
+
+    /// 自动学习的不支持模型是否已到期
+    pub fn is_learned_model_expired(&self, model: &str, now: DateTime<Utc>) -> bool {
+        self.learned_unsupported_models
+            .get(model)
+            .is_some_and(|expires_at| *expires_at <= now)
+    }
+
+    /// 将模型加入不支持列表（自动学习）
+    pub fn learn_unsupported_model(&mut self, model: &str, expires_at: DateTime<Utc>) {
+        if !self.not_supported_models.iter().any(|m| m == model) {
+            self.not_supported_models.push(model.to_string());
+        }
+        self.learned_unsupported_models
+            .insert(model.to_string(), expires_at);
+    }
+
+    /// 手动设置不支持模型列表（移除列表中已不存在的自动学习条目）
+    pub fn set_not_supported_models(&mut self, models: Vec<String>) {
+        self.learned_unsupported_models
+            .retain(|model, _| models.contains(model));
+        self.not_supported_models = models;
+    }
+
+    /// 移除自动学习的不支持模型，返回是否存在
+    pub fn forget_learned_model(&mut self, model: &str) -> bool {
+        if self.learned_unsupported_models.remove(model).is_none() {
+            return false;
+        }
+        self.not_supported_models.retain(|m| m != model);
+        true
+    }
+
     /// 是否支持指定模型
     ///
     /// 检查两个来源的排除列表：
@@ -303,8 +643,10 @@ impl ProviderCredential {
     /// 2. `excluded_models` - 来自 CredentialData::GeminiApiKey 的排除列表（支持通配符）
     /// 3. Antigravity 凭证只支持特定的模型列表
     pub fn supports_model(&self, model: &str) -> bool {
-        // 检查通用的不支持模型列表（精确匹配）
-        if self.not_supported_models.contains(&model.to_string()) {
+        // 检查通用的不支持模型列表（精确匹配，自动学习的条目到期后重新尝试）
+        if self.not_supported_models.iter().any(|m| m == model)
+            && !self.is_learned_model_expired(model, Utc::now())
+        {
             return false;
         }
 
@@ -378,6 +720,9 @@ pub struct PoolStats {
     pub healthy_count: usize,
     /// 禁用凭证数
     pub disabled_count: usize,
+    /// 溢出凭证数
+    #[serde(default)]
+    pub overflow_count: usize,
     /// 总使用次数
     pub total_usage: u64,
     /// 总错误次数
@@ -392,6 +737,10 @@ impl PoolStats {
             total_count: credentials.len(),
             healthy_count: credentials.iter().filter(|c| c.is_healthy).count(),
             disabled_count: credentials.iter().filter(|c| c.is_disabled).count(),
+            overflow_count: credentials
+                .iter()
+                .filter(|c| c.tier == CredentialTier::Overflow)
+                .count(),
             total_usage: credentials.iter().map(|c| c.usage_count).sum(),
             total_errors: credentials.iter().map(|c| c.error_count as u64).sum(),
             last_update: Utc::now(),
@@ -409,6 +758,18 @@ pub struct HealthCheckResult {
     pub duration_ms: u64,
 }
 
+/// 批量健康检查进度（每完成一个凭证推送一次）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckProgress {
+    pub provider_type: String,
+    /// 已完成数量
+    pub completed: usize,
+    /// 待检查总数
+    pub total: usize,
+    /// 本次完成的凭证结果
+    pub result: HealthCheckResult,
+}
+
 /// OAuth 凭证状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthStatus {
@@ -443,6 +804,41 @@ pub struct TokenCacheStatus {
     pub last_refresh_error: Option<String>,
 }
 
+/// 凭证过期跟踪信息
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CredentialExpiryInfo {
+    /// 最近一次成功刷新时间
+    pub last_successful_refresh: Option<DateTime<Utc>>,
+    /// Refresh Token 过期时间（凭证文件中明确给出时记录）
+    pub refresh_token_expiry: Option<DateTime<Utc>>,
+}
+
+/// 凭证过期状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryState {
+    /// 正常
+    Ok,
+    /// Refresh Token 即将过期
+    ExpiringSoon,
+    /// Refresh Token 已过期
+    Expired,
+    /// 连续刷新失败
+    RefreshFailing,
+}
+
+/// 凭证过期状态（用于前端展示）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialExpiryStatus {
+    pub state: ExpiryState,
+    /// 最近一次成功刷新时间
+    pub last_successful_refresh: Option<String>,
+    /// Refresh Token 过期时间（明确给出或按配置的有效期推算）
+    pub refresh_token_expiry: Option<String>,
+    /// 距离过期的天数（已过期为负数）
+    pub days_until_expiry: Option<i64>,
+}
+
 /// Token 缓存信息
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CachedTokenInfo {
@@ -522,6 +918,9 @@ pub fn get_default_check_model(provider_type: PoolProviderType) -> &'static str
         PoolProviderType::AzureOpenai => "gpt-4o-mini",
         PoolProviderType::AwsBedrock => "claude-sonnet-4-5-20250929",
         PoolProviderType::Ollama => "llama3.2",
+        PoolProviderType::OpenRouter => "openai/gpt-4o-mini",
+        PoolProviderType::Mistral => "mistral-small-latest",
+        PoolProviderType::DeepSeek => "deepseek-chat",
     }
 }
 
@@ -548,6 +947,8 @@ pub struct CredentialDisplay {
     pub last_health_check_model: Option<String>,
     pub oauth_status: Option<OAuthStatus>,
     pub token_cache_status: Option<TokenCacheStatus>,
+    /// 凭证过期状态（仅 OAuth 凭证）
+    pub expiry_status: Option<CredentialExpiryStatus>,
     pub created_at: String,
     pub updated_at: String,
     /// 凭证来源（手动添加/导入/私有）
@@ -558,6 +959,20 @@ pub struct CredentialDisplay {
     pub api_key: Option<String>,
     /// 凭证级代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 优先级分层（主力/溢出）
+    pub tier: CredentialTier,
+    /// 调度规则
+    pub schedule: Option<CredentialSchedule>,
+    /// 当日请求次数
+    pub daily_usage_count: u64,
+    /// 自动学习的不支持模型及其到期时间
+    pub learned_unsupported_models: HashMap<String, String>,
+    /// 标签
+    pub tags: Vec<String>,
+    /// 固定模型
+    pub pinned_model: Option<String>,
+    /// 最近一次额度查询结果
+    pub quota: Option<crate::usage::CredentialQuota>,
 }
 
 /// 获取凭证类型字符串
@@ -576,6 +991,9 @@ fn get_credential_type(cred: &CredentialData) -> String {
         CredentialData::IFlowOAuth { .. } => "iflow_oauth".to_string(),
         CredentialData::IFlowCookie { .. } => "iflow_cookie".to_string(),
         CredentialData::AnthropicKey { .. } => "anthropic_key".to_string(),
+        CredentialData::OpenRouterKey { .. } => "openrouter_key".to_string(),
+        CredentialData::MistralKey { .. } => "mistral_key".to_string(),
+        CredentialData::DeepSeekKey { .. } => "deepseek_key".to_string(),
     }
 }
 
@@ -606,6 +1024,9 @@ fn get_base_url(cred: &CredentialData) -> Option<String> {
         CredentialData::OpenAIKey { base_url, .. } => base_url.clone(),
         CredentialData::ClaudeKey { base_url, .. } => base_url.clone(),
         CredentialData::AnthropicKey { base_url, .. } => base_url.clone(),
+        CredentialData::OpenRouterKey { base_url, .. } => base_url.clone(),
+        CredentialData::MistralKey { base_url, .. } => base_url.clone(),
+        CredentialData::DeepSeekKey { base_url, .. } => base_url.clone(),
         _ => None,
     }
 }
@@ -616,6 +1037,9 @@ fn get_api_key(cred: &CredentialData) -> Option<String> {
         CredentialData::OpenAIKey { api_key, .. } => Some(api_key.clone()),
         CredentialData::ClaudeKey { api_key, .. } => Some(api_key.clone()),
         CredentialData::AnthropicKey { api_key, .. } => Some(api_key.clone()),
+        CredentialData::OpenRouterKey { api_key, .. } => Some(api_key.clone()),
+        CredentialData::MistralKey { api_key, .. } => Some(api_key.clone()),
+        CredentialData::DeepSeekKey { api_key, .. } => Some(api_key.clone()),
         _ => None,
     }
 }
@@ -654,12 +1078,24 @@ impl From<&ProviderCredential> for CredentialDisplay {
             last_health_check_model: cred.last_health_check_model.clone(),
             oauth_status: None, // 需要单独调用获取
             token_cache_status,
+            expiry_status: None, // 由 ProviderPoolService 按配置计算
             created_at: cred.created_at.to_rfc3339(),
             updated_at: cred.updated_at.to_rfc3339(),
             source: cred.source,
             base_url: get_base_url(&cred.credential),
             api_key: get_api_key(&cred.credential),
             proxy_url: cred.proxy_url.clone(),
+            tier: cred.tier,
+            schedule: cred.schedule.clone(),
+            daily_usage_count: cred.current_daily_usage(Utc::now()),
+            learned_unsupported_models: cred
+                .learned_unsupported_models
+                .iter()
+                .map(|(model, expires_at)| (model.clone(), expires_at.to_rfc3339()))
+                .collect(),
+            tags: cred.tags.clone(),
+            pinned_model: cred.pinned_model.clone(),
+            quota: None, // 由 ProviderPoolService 从额度缓存填充
         }
     }
 }
@@ -692,6 +1128,7 @@ fn mask_key(key: &str) -> String {
 }
 
 /// 添加凭证的请求结构
+#[cfg(feature = "desktop")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddCredentialRequest {
     pub provider_type: String,
@@ -702,6 +1139,7 @@ pub struct AddCredentialRequest {
 }
 
 /// 更新凭证请求
+#[cfg(feature = "desktop")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateCredentialRequest {
     pub name: Option<String>,
@@ -719,6 +1157,91 @@ pub struct UpdateCredentialRequest {
     pub new_api_key: Option<String>,
     /// 新的代理 URL（可覆盖全局代理设置）
     pub new_proxy_url: Option<String>,
+    /// 新的优先级分层
+    #[serde(default)]
+    pub tier: Option<CredentialTier>,
+    /// 新的调度规则（无时间窗口且无次数上限表示清除）
+    #[serde(default)]
+    pub schedule: Option<CredentialSchedule>,
+    /// 新的标签列表（空列表表示清除）
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// 新的固定模型（空字符串表示清除）
+    #[serde(default)]
+    pub pinned_model: Option<String>,
+}
+
+/// 复制凭证请求（未指定的字段沿用原凭证）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicateCredentialRequest {
+    /// 副本名称（未指定时为"原名称 (copy)"，空字符串表示不设置名称）
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub is_disabled: Option<bool>,
+    #[serde(default)]
+    pub check_health: Option<bool>,
+    #[serde(default)]
+    pub check_model_name: Option<String>,
+    #[serde(default)]
+    pub not_supported_models: Option<Vec<String>>,
+    /// 代理 URL（空字符串表示清除）
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    #[serde(default)]
+    pub tier: Option<CredentialTier>,
+    #[serde(default)]
+    pub schedule: Option<CredentialSchedule>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// 固定模型（空字符串表示清除）
+    #[serde(default)]
+    pub pinned_model: Option<String>,
+}
+
+/// 批量操作的凭证筛选条件
+///
+/// 同时指定 Provider 类型和标签时需同时满足；都不指定时匹配所有凭证。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkCredentialFilter {
+    #[serde(default)]
+    pub provider_type: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+impl BulkCredentialFilter {
+    /// 凭证是否满足筛选条件
+    pub fn matches(&self, cred: &ProviderCredential) -> bool {
+        let type_matches = self
+            .provider_type
+            .as_deref()
+            .is_none_or(|pt| pt.parse::<PoolProviderType>() == Ok(cred.provider_type));
+        let tag_matches = self.tag.as_deref().is_none_or(|tag| cred.has_tag(tag));
+        type_matches && tag_matches
+    }
+}
+
+/// 批量操作中单个凭证的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOperationResult {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub provider_type: String,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+impl BulkOperationResult {
+    pub fn new(cred: &ProviderCredential, result: Result<(), String>) -> Self {
+        Self {
+            uuid: cred.uuid.clone(),
+            name: cred.name.clone(),
+            provider_type: cred.provider_type.to_string(),
+            success: result.is_ok(),
+            message: result.err(),
+        }
+    }
 }
 
 pub type ProviderPools = HashMap<PoolProviderType, Vec<ProviderCredential>>;
@@ -788,6 +1311,13 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         };
 
         assert!(!cred.supports_model("claude-opus"));
@@ -823,6 +1353,13 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         };
 
         // Exact match exclusion
@@ -860,6 +1397,13 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         };
 
         // Prefix wildcard exclusion
@@ -901,6 +1445,13 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         };
 
         // Contains wildcard exclusion
@@ -939,6 +1490,13 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         };
 
         // Excluded by not_supported_models (exact match)
@@ -978,6 +1536,13 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         };
 
         // All models should be supported since not_supported_models is empty
@@ -985,6 +1550,105 @@ mod tests {
         assert!(cred.supports_model("claude-opus"));
     }
 
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_credential_schedule_windows_and_daily_limit() {
+        let window = |start: &str, end: &str| ScheduleWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+        };
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/path/to/creds".to_string(),
+            },
+        );
+        cred.apply_schedule(CredentialSchedule {
+            windows: vec![window("22:00", "02:00"), window("08:00", "12:00")],
+            daily_request_limit: Some(2),
+            reset_time: "08:00".to_string(),
+        })
+        .unwrap();
+
+        assert!(cred.is_schedulable(utc("2026-01-01T23:30:00Z")));
+        assert!(cred.is_schedulable(utc("2026-01-01T01:59:00Z")));
+        assert!(!cred.is_schedulable(utc("2026-01-01T12:00:00Z")));
+
+        // 达到每日上限后不可用，到重置时间后恢复
+        let now = utc("2026-01-01T09:00:00Z");
+        for _ in 0..2 {
+            let (count, since) = cred.next_daily_usage(now);
+            cred.daily_usage_count = count;
+            cred.daily_usage_reset_at = Some(since);
+        }
+        assert_eq!(cred.current_daily_usage(now), 2);
+        assert!(!cred.is_schedulable(now));
+        assert!(!cred.needs_daily_reset(utc("2026-01-02T07:59:00Z")));
+        let next_day = utc("2026-01-02T08:00:00Z");
+        assert!(cred.needs_daily_reset(next_day));
+        assert_eq!(cred.current_daily_usage(next_day), 0);
+        assert!(cred.is_schedulable(next_day));
+        assert_eq!(cred.next_daily_usage(next_day), (1, next_day));
+
+        // 无效时间格式；空规则表示清除
+        let mut invalid = cred.schedule.clone().unwrap();
+        invalid.reset_time = "25:00".to_string();
+        assert!(cred.apply_schedule(invalid).is_err());
+        cred.apply_schedule(CredentialSchedule {
+            windows: vec![],
+            daily_request_limit: None,
+            reset_time: "00:00".to_string(),
+        })
+        .unwrap();
+        assert!(cred.schedule.is_none());
+    }
+
+    #[test]
+    fn test_credential_tags() {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/path/to/creds".to_string(),
+            },
+        );
+        cred.set_tags(vec![
+            " Work ".to_string(),
+            "work".to_string(),
+            "".to_string(),
+            "night".to_string(),
+        ]);
+        assert_eq!(cred.tags, vec!["work", "night"]);
+        assert!(cred.has_tag("WORK"));
+        assert!(!cred.has_tag("personal"));
+
+        assert_eq!(parse_tag_selector("tag:work"), Some("work"));
+        assert_eq!(parse_tag_selector("tag:"), None);
+        assert_eq!(parse_tag_selector("kiro"), None);
+    }
+
+    #[test]
+    fn test_credential_pinned_model() {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-cheap".to_string(),
+                base_url: None,
+            },
+        );
+        assert_eq!(cred.pinned_model_for("gpt-4o"), None);
+
+        cred.set_pinned_model(" gpt-4o-mini ".to_string());
+        assert_eq!(cred.pinned_model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(cred.pinned_model_for("gpt-4o"), Some("gpt-4o-mini"));
+        assert_eq!(cred.pinned_model_for("gpt-4o-mini"), None);
+
+        cred.set_pinned_model("  ".to_string());
+        assert!(cred.pinned_model.is_none());
+    }
+
     // ========================================================================
     // Property-Based Tests for Token Expiration Check
     // ========================================================================
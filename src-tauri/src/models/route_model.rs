@@ -52,6 +52,74 @@ pub struct CurlExample {
     pub command: String,
 }
 
+/// 未嵌入真实 API Key 时使用的占位符
+pub const API_KEY_PLACEHOLDER: &str = "${PROXYCAST_API_KEY}";
+
+/// 客户端配置片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientConfig {
+    /// 客户端（`claude_code`、`cline`、`zed`、`env`）
+    pub client: String,
+    /// 配置位置说明（如 `~/.claude/settings.json`）
+    pub target: String,
+    /// 可直接粘贴的配置内容
+    pub content: String,
+}
+
+/// 单个路由的客户端配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteClientConfigs {
+    /// 路由选择器
+    pub selector: String,
+    /// Provider 类型
+    pub provider_type: String,
+    /// 客户端配置列表
+    pub configs: Vec<ClientConfig>,
+}
+
+/// 路由配置包（所有已注册路由的客户端配置）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteBundle {
+    /// 服务器基础 URL
+    pub base_url: String,
+    /// 默认 Provider
+    pub default_provider: String,
+    /// 各路由的客户端配置
+    pub routes: Vec<RouteClientConfigs>,
+}
+
+impl RouteListResponse {
+    /// 生成路由配置包
+    ///
+    /// `api_key_for` 返回路由使用的 API Key（路由专属 Key 或全局 Key，也可以是占位符）
+    pub fn client_bundle(&self, api_key_for: impl Fn(&str) -> String) -> RouteBundle {
+        RouteBundle {
+            base_url: self.base_url.clone(),
+            default_provider: self.default_provider.clone(),
+            routes: self
+                .routes
+                .iter()
+                .filter(|route| route.enabled)
+                .map(|route| RouteClientConfigs {
+                    selector: route.selector.clone(),
+                    provider_type: route.provider_type.clone(),
+                    configs: route.client_configs(&api_key_for(&route.selector)),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Provider 类型对应的示例模型
+fn example_model(provider_type: &str) -> &'static str {
+    match provider_type {
+        "gemini" => "gemini-2.5-flash",
+        "qwen" => "qwen3-coder-plus",
+        "openai" => "gpt-4",
+        _ => "claude-sonnet-4-5",
+    }
+}
+
 impl RouteInfo {
     /// 创建新的路由信息
     pub fn new(selector: String, provider_type: String) -> Self {
@@ -80,6 +148,80 @@ impl RouteInfo {
         });
     }
 
+    /// 指定协议端点的基础 URL（去掉 `/v1/...` 后缀）
+    fn endpoint_base(&self, protocol: &str) -> Option<&str> {
+        let endpoint = self.endpoints.iter().find(|e| e.protocol == protocol)?;
+        let suffix = match protocol {
+            "claude" => "/v1/messages",
+            _ => "/v1/chat/completions",
+        };
+        endpoint.url.strip_suffix(suffix)
+    }
+
+    /// 生成客户端配置（Claude Code、Cline、Zed、环境变量）
+    pub fn client_configs(&self, api_key: &str) -> Vec<ClientConfig> {
+        let model = example_model(&self.provider_type);
+        let anthropic_base = self.endpoint_base("claude");
+        let openai_base = self
+            .endpoint_base("openai")
+            .map(|base| format!("{}/v1", base));
+        let pretty =
+            |value: serde_json::Value| serde_json::to_string_pretty(&value).unwrap_or_default();
+
+        let mut configs = Vec::new();
+        let mut env = Vec::new();
+
+        if let Some(base) = anthropic_base {
+            configs.push(ClientConfig {
+                client: "claude_code".to_string(),
+                target: "~/.claude/settings.json".to_string(),
+                content: pretty(serde_json::json!({
+                    "env": {
+                        "ANTHROPIC_BASE_URL": base,
+                        "ANTHROPIC_AUTH_TOKEN": api_key,
+                    }
+                })),
+            });
+            configs.push(ClientConfig {
+                client: "zed".to_string(),
+                target: "~/.config/zed/settings.json".to_string(),
+                content: pretty(serde_json::json!({
+                    "language_models": {
+                        "anthropic": { "api_url": base }
+                    }
+                })),
+            });
+            env.push(format!("export ANTHROPIC_BASE_URL=\"{}\"", base));
+            env.push(format!("export ANTHROPIC_AUTH_TOKEN=\"{}\"", api_key));
+            env.push(format!("export ANTHROPIC_API_KEY=\"{}\"", api_key));
+        }
+
+        if let Some(base) = &openai_base {
+            configs.push(ClientConfig {
+                client: "cline".to_string(),
+                target: "Cline 设置 → API Provider: OpenAI Compatible".to_string(),
+                content: pretty(serde_json::json!({
+                    "apiProvider": "openai",
+                    "openAiBaseUrl": base,
+                    "openAiApiKey": api_key,
+                    "openAiModelId": model,
+                })),
+            });
+            env.push(format!("export OPENAI_BASE_URL=\"{}\"", base));
+            env.push(format!("export OPENAI_API_KEY=\"{}\"", api_key));
+        }
+
+        if !env.is_empty() {
+            configs.push(ClientConfig {
+                client: "env".to_string(),
+                target: "~/.bashrc / ~/.zshrc".to_string(),
+                content: env.join("\n"),
+            });
+        }
+
+        configs
+    }
+
     /// 生成 curl 示例
     pub fn generate_curl_examples(&self, api_key: &str) -> Vec<CurlExample> {
         let mut examples = Vec::new();
@@ -87,13 +229,7 @@ impl RouteInfo {
         for endpoint in &self.endpoints {
             let (_model, body) = match endpoint.protocol.as_str() {
                 "claude" => {
-                    let model = match self.provider_type.as_str() {
-                        "kiro" | "claude" => "claude-sonnet-4-5",
-                        "gemini" => "gemini-2.5-flash",
-                        "qwen" => "qwen3-coder-plus",
-                        "openai" => "gpt-4",
-                        _ => "claude-sonnet-4-5",
-                    };
+                    let model = example_model(&self.provider_type);
                     (
                         model,
                         format!(
@@ -107,13 +243,7 @@ impl RouteInfo {
                     )
                 }
                 "openai" => {
-                    let model = match self.provider_type.as_str() {
-                        "kiro" | "claude" => "claude-sonnet-4-5",
-                        "gemini" => "gemini-2.5-flash",
-                        "qwen" => "qwen3-coder-plus",
-                        "openai" => "gpt-4",
-                        _ => "claude-sonnet-4-5",
-                    };
+                    let model = example_model(&self.provider_type);
                     (
                         model,
                         format!(
@@ -145,3 +275,43 @@ impl RouteInfo {
         examples
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_bundle() {
+        let mut route = RouteInfo::new("work".to_string(), "gemini".to_string());
+        route.add_endpoint("http://127.0.0.1:8999", "claude");
+        route.add_endpoint("http://127.0.0.1:8999", "openai");
+        let mut disabled = RouteInfo::new("off".to_string(), "kiro".to_string());
+        disabled.enabled = false;
+        let list = RouteListResponse {
+            base_url: "http://127.0.0.1:8999".to_string(),
+            default_provider: "kiro".to_string(),
+            routes: vec![route, disabled],
+        };
+
+        let bundle = list.client_bundle(|selector| format!("key-{}", selector));
+        assert_eq!(bundle.routes.len(), 1);
+        let configs = &bundle.routes[0].configs;
+        let clients: Vec<&str> = configs.iter().map(|c| c.client.as_str()).collect();
+        assert_eq!(clients, vec!["claude_code", "zed", "cline", "env"]);
+
+        let claude_code: serde_json::Value = serde_json::from_str(&configs[0].content).unwrap();
+        assert_eq!(
+            claude_code["env"]["ANTHROPIC_BASE_URL"],
+            "http://127.0.0.1:8999/work"
+        );
+        assert_eq!(claude_code["env"]["ANTHROPIC_AUTH_TOKEN"], "key-work");
+
+        let cline: serde_json::Value = serde_json::from_str(&configs[2].content).unwrap();
+        assert_eq!(cline["openAiBaseUrl"], "http://127.0.0.1:8999/work/v1");
+        assert_eq!(cline["openAiModelId"], "gemini-2.5-flash");
+
+        assert!(configs[3]
+            .content
+            .contains("export OPENAI_BASE_URL=\"http://127.0.0.1:8999/work/v1\""));
+    }
+}
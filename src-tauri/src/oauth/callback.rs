@@ -0,0 +1,185 @@
+//! 本地 OAuth 回调监听器
+//!
+//! 绑定在 127.0.0.1 上的一次性 HTTP 服务，接收授权服务器重定向回来的授权码。
+//! 收到第一次 state 匹配的回调后优雅关闭（state 不匹配的请求被忽略）；等待中的 future 被丢弃时监听端口随之释放。
+
+use axum::{extract::Query, response::Html, routing::get, Router};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// 回调路径
+pub const CALLBACK_PATH: &str = "/oauth2callback";
+
+const SUCCESS_HTML: &str =
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>ProxyCast</title></head>\
+<body><h2>授权成功</h2><p>可以关闭此页面并返回 ProxyCast。</p></body></html>";
+
+const ERROR_HTML: &str =
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>ProxyCast</title></head>\
+<body><h2>授权失败</h2><p>ERROR_PLACEHOLDER</p></body></html>";
+
+/// 本地回调监听器
+pub struct CallbackListener {
+    listener: TcpListener,
+    port: u16,
+}
+
+impl CallbackListener {
+    /// 依次尝试绑定候选端口，均失败时使用系统分配的端口
+    pub async fn bind(ports: &[u16]) -> Result<Self, String> {
+        for &port in ports.iter().chain(std::iter::once(&0)) {
+            match TcpListener::bind(("127.0.0.1", port)).await {
+                Ok(listener) => {
+                    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+                    return Ok(Self { listener, port });
+                }
+                Err(e) => tracing::debug!("[OAUTH] 回调端口 {} 绑定失败: {}", port, e),
+            }
+        }
+        Err("无法绑定 OAuth 回调端口".to_string())
+    }
+
+    /// 授权请求中使用的 redirect_uri
+    pub fn redirect_uri(&self) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, CALLBACK_PATH)
+    }
+
+    /// 等待回调并返回授权码（校验 state）
+    pub async fn wait_for_code(self, expected_state: &str) -> Result<String, String> {
+        let slot: Arc<parking_lot::Mutex<Option<Result<String, String>>>> = Arc::default();
+        let received = Arc::new(Notify::new());
+
+        let handler = {
+            let slot = slot.clone();
+            let received = received.clone();
+            let expected_state = expected_state.to_string();
+            move |Query(params): Query<HashMap<String, String>>| async move {
+                // state 不匹配的请求（如其他页面伪造的回调）直接忽略，不结束登录流程
+                if !state_matches(&params, &expected_state) {
+                    return Html(error_page("State 验证失败"));
+                }
+                let result = parse_callback(&params, &expected_state);
+                let page = match &result {
+                    Ok(_) => SUCCESS_HTML.to_string(),
+                    Err(e) => error_page(e),
+                };
+                let mut slot = slot.lock();
+                if slot.is_none() {
+                    *slot = Some(result);
+                    received.notify_one();
+                }
+                Html(page)
+            }
+        };
+
+        let app = Router::new().route(CALLBACK_PATH, get(handler));
+        axum::serve(self.listener, app)
+            .with_graceful_shutdown(async move { received.notified().await })
+            .await
+            .map_err(|e| format!("OAuth 回调服务异常: {}", e))?;
+
+        let result = slot.lock().take();
+        result.unwrap_or_else(|| Err("OAuth 回调服务已关闭".to_string()))
+    }
+}
+
+/// 回调参数中的 state 是否与授权请求一致
+fn state_matches(params: &HashMap<String, String>, expected_state: &str) -> bool {
+    params.get("state").map(|s| s.as_str()) == Some(expected_state)
+}
+
+/// 构建错误页面（错误信息来自回调参数，需转义）
+fn error_page(message: &str) -> String {
+    let escaped = message
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;");
+    ERROR_HTML.replace("ERROR_PLACEHOLDER", &escaped)
+}
+
+/// 解析回调参数
+pub fn parse_callback(
+    params: &HashMap<String, String>,
+    expected_state: &str,
+) -> Result<String, String> {
+    if !state_matches(params, expected_state) {
+        return Err("State 验证失败".to_string());
+    }
+    if let Some(error) = params.get("error") {
+        let description = params
+            .get("error_description")
+            .map(|s| s.as_str())
+            .unwrap_or("未知错误");
+        return Err(format!("{}: {}", error, description));
+    }
+    params
+        .get("code")
+        .filter(|code| !code.is_empty())
+        .cloned()
+        .ok_or_else(|| "未收到授权码".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_callback() {
+        assert_eq!(
+            parse_callback(&params(&[("code", "abc"), ("state", "s1")]), "s1"),
+            Ok("abc".to_string())
+        );
+        assert!(parse_callback(&params(&[("code", "abc"), ("state", "other")]), "s1").is_err());
+        assert!(parse_callback(&params(&[("state", "s1")]), "s1").is_err());
+        let err = parse_callback(
+            &params(&[("error", "access_denied"), ("state", "s1")]),
+            "s1",
+        )
+        .unwrap_err();
+        assert!(err.starts_with("access_denied"));
+        // state 不匹配时不采信错误参数
+        assert_eq!(
+            parse_callback(&params(&[("error", "access_denied")]), "s1"),
+            Err("State 验证失败".to_string())
+        );
+    }
+
+    #[test]
+    fn test_error_page_escapes_message() {
+        let page = error_page("<script>alert('x')</script>");
+        assert!(!page.contains("<script>"));
+        assert!(page.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+    }
+
+    #[tokio::test]
+    async fn test_receives_code() {
+        let listener = CallbackListener::bind(&[]).await.unwrap();
+        let redirect_uri = listener.redirect_uri();
+        assert!(redirect_uri.ends_with(CALLBACK_PATH));
+        let waiter = tokio::spawn(async move { listener.wait_for_code("s1").await });
+
+        // state 不匹配的请求被忽略，不结束登录
+        let response = reqwest::get(format!("{}?code=evil&state=other", redirect_uri))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert!(!waiter.is_finished());
+
+        let response = reqwest::get(format!("{}?code=abc&state=s1", redirect_uri))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(waiter.await.unwrap(), Ok("abc".to_string()));
+    }
+}
@@ -0,0 +1,47 @@
+//! Gemini 登录（授权码 + PKCE，本地回调接收授权码）
+
+use super::callback::CallbackListener;
+use super::{LoginPrompt, PendingLogin};
+use crate::models::provider_pool_model::CredentialData;
+use crate::providers::gemini;
+
+/// 回调监听端口（均被占用时使用随机端口）
+const CALLBACK_PORTS: &[u16] = &[11451, 11452, 11453, 11454, 11455];
+
+/// 等待用户授权的时长（秒）
+const AUTHORIZATION_TIMEOUT_SECS: u64 = 600;
+
+/// 发起 Gemini 授权
+pub(crate) async fn start() -> Result<PendingLogin, String> {
+    let listener = CallbackListener::bind(CALLBACK_PORTS).await?;
+    let redirect_uri = listener.redirect_uri();
+    let (code_verifier, code_challenge) = gemini::generate_pkce();
+    let state = uuid::Uuid::new_v4().to_string();
+    let auth_url =
+        gemini::generate_gemini_auth_url_with_redirect(&state, &code_challenge, &redirect_uri);
+
+    let completion = async move {
+        let code = listener.wait_for_code(&state).await?;
+        let result = gemini::exchange_gemini_code_and_create_credentials_with_redirect(
+            &code,
+            &code_verifier,
+            &redirect_uri,
+        )
+        .await
+        .map_err(|e| format!("交换授权码失败: {}", e))?;
+        Ok(CredentialData::GeminiOAuth {
+            creds_file_path: result.creds_file_path,
+            // 项目 ID 会在健康检查时自动获取
+            project_id: None,
+        })
+    };
+
+    Ok(PendingLogin {
+        prompt: LoginPrompt {
+            verification_url: auth_url,
+            user_code: None,
+            expires_in: AUTHORIZATION_TIMEOUT_SECS,
+        },
+        completion: Box::pin(completion),
+    })
+}
@@ -0,0 +1,286 @@
+//! Kiro 登录（AWS Builder ID Device Authorization Flow）
+//!
+//! 注册 OIDC 公共客户端 → 发起设备授权 → 轮询 Token，
+//! 授权成功后生成 IdC 格式的凭证 JSON（包含 clientId/clientSecret，用于后续刷新）。
+
+use super::{LoginPrompt, PendingLogin};
+use crate::models::provider_pool_model::CredentialData;
+use reqwest::Client;
+
+/// 默认区域
+pub(crate) const DEFAULT_REGION: &str = "us-east-1";
+
+const START_URL: &str = "https://view.awsapps.com/start";
+const CLIENT_NAME: &str = "ProxyCast Kiro Manager";
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const SCOPES: &[&str] = &[
+    "codewhisperer:completions",
+    "codewhisperer:analysis",
+    "codewhisperer:conversations",
+    "codewhisperer:transformations",
+    "codewhisperer:taskassist",
+];
+
+/// 设备授权信息
+#[derive(Debug, Clone)]
+pub(crate) struct DeviceAuthorization {
+    /// OIDC 客户端 ID
+    pub client_id: String,
+    /// OIDC 客户端密钥
+    pub client_secret: String,
+    /// 设备码
+    pub device_code: String,
+    /// 用户码
+    pub user_code: String,
+    /// 验证 URI（优先使用包含用户码的完整地址）
+    pub verification_uri: String,
+    /// 轮询间隔（秒）
+    pub interval: i64,
+    /// 有效期（秒）
+    pub expires_in: i64,
+    /// 区域
+    pub region: String,
+}
+
+/// 单次轮询结果
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenPoll {
+    /// 授权成功，返回凭证 JSON
+    Authorized(serde_json::Value),
+    /// 用户尚未完成授权
+    Pending,
+    /// 轮询过快，需要增加间隔
+    SlowDown,
+    /// 授权失败（过期、拒绝或其他错误）
+    Failed(String),
+}
+
+fn oidc_base(region: &str) -> String {
+    format!("https://oidc.{}.amazonaws.com", region)
+}
+
+/// 注册 OIDC 客户端并发起设备授权
+pub(crate) async fn start_device_authorization(
+    client: &Client,
+    region: &str,
+) -> Result<DeviceAuthorization, String> {
+    let oidc_base = oidc_base(region);
+
+    let reg_res = client
+        .post(format!("{}/client/register", oidc_base))
+        .json(&serde_json::json!({
+            "clientName": CLIENT_NAME,
+            "clientType": "public",
+            "scopes": SCOPES,
+            "grantTypes": [DEVICE_GRANT_TYPE, "refresh_token"],
+            "issuerUrl": START_URL
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("注册客户端请求失败: {}", e))?;
+    if !reg_res.status().is_success() {
+        let err_text = reg_res.text().await.unwrap_or_default();
+        return Err(format!("注册客户端失败: {}", err_text));
+    }
+    let reg_data: serde_json::Value = reg_res
+        .json()
+        .await
+        .map_err(|e| format!("解析注册响应失败: {}", e))?;
+    let client_id = json_str(&reg_data, "clientId")?;
+    let client_secret = json_str(&reg_data, "clientSecret")?;
+
+    let auth_res = client
+        .post(format!("{}/device_authorization", oidc_base))
+        .json(&serde_json::json!({
+            "clientId": client_id,
+            "clientSecret": client_secret,
+            "startUrl": START_URL
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("设备授权请求失败: {}", e))?;
+    if !auth_res.status().is_success() {
+        let err_text = auth_res.text().await.unwrap_or_default();
+        return Err(format!("设备授权失败: {}", err_text));
+    }
+    let auth_data: serde_json::Value = auth_res
+        .json()
+        .await
+        .map_err(|e| format!("解析授权响应失败: {}", e))?;
+
+    let verification_uri = auth_data["verificationUriComplete"]
+        .as_str()
+        .or_else(|| auth_data["verificationUri"].as_str())
+        .ok_or("响应中缺少 verificationUri")?
+        .to_string();
+
+    Ok(DeviceAuthorization {
+        client_id,
+        client_secret,
+        device_code: json_str(&auth_data, "deviceCode")?,
+        user_code: json_str(&auth_data, "userCode")?,
+        verification_uri,
+        interval: auth_data["interval"].as_i64().unwrap_or(5),
+        expires_in: auth_data["expiresIn"].as_i64().unwrap_or(600),
+        region: region.to_string(),
+    })
+}
+
+/// 轮询一次 Token 端点
+pub(crate) async fn poll_token(
+    client: &Client,
+    authorization: &DeviceAuthorization,
+) -> Result<TokenPoll, String> {
+    let token_res = client
+        .post(format!("{}/token", oidc_base(&authorization.region)))
+        .json(&serde_json::json!({
+            "clientId": authorization.client_id,
+            "clientSecret": authorization.client_secret,
+            "grantType": DEVICE_GRANT_TYPE,
+            "deviceCode": authorization.device_code
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Token 请求失败: {}", e))?;
+
+    let status = token_res.status();
+    if status.is_success() {
+        let token_data: serde_json::Value = token_res
+            .json()
+            .await
+            .map_err(|e| format!("解析 Token 响应失败: {}", e))?;
+        return Ok(TokenPoll::Authorized(credentials_json(
+            &token_data,
+            authorization,
+        )));
+    }
+    if status.as_u16() != 400 {
+        return Err(format!("未知响应: {}", status));
+    }
+
+    let err_data: serde_json::Value = token_res
+        .json()
+        .await
+        .map_err(|e| format!("解析错误响应失败: {}", e))?;
+    Ok(classify_token_error(
+        err_data["error"].as_str().unwrap_or("unknown"),
+    ))
+}
+
+/// 将 Token 端点的 400 错误码映射为轮询结果
+pub(crate) fn classify_token_error(error: &str) -> TokenPoll {
+    match error {
+        "authorization_pending" => TokenPoll::Pending,
+        "slow_down" => TokenPoll::SlowDown,
+        "expired_token" => TokenPoll::Failed("设备码已过期".to_string()),
+        "access_denied" => TokenPoll::Failed("用户拒绝授权".to_string()),
+        other => TokenPoll::Failed(format!("授权错误: {}", other)),
+    }
+}
+
+/// 构建 IdC 格式的凭证 JSON
+pub(crate) fn credentials_json(
+    token_data: &serde_json::Value,
+    authorization: &DeviceAuthorization,
+) -> serde_json::Value {
+    let expires_in = token_data["expiresIn"].as_i64().unwrap_or(3600);
+    serde_json::json!({
+        "accessToken": token_data["accessToken"].as_str().unwrap_or(""),
+        "refreshToken": token_data["refreshToken"].as_str().unwrap_or(""),
+        "clientId": authorization.client_id,
+        "clientSecret": authorization.client_secret,
+        "region": authorization.region,
+        "authMethod": "idc",
+        "expiresAt": chrono::Utc::now().timestamp() + expires_in
+    })
+}
+
+fn json_str(value: &serde_json::Value, key: &str) -> Result<String, String> {
+    value[key]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| format!("响应中缺少 {}", key))
+}
+
+/// 发起 Kiro Builder ID 登录
+pub(crate) async fn start(region: Option<&str>) -> Result<PendingLogin, String> {
+    let client = Client::new();
+    let mut authorization =
+        start_device_authorization(&client, region.unwrap_or(DEFAULT_REGION)).await?;
+    tracing::info!(
+        "[OAUTH] Kiro 设备授权已发起，user_code: {}",
+        authorization.user_code
+    );
+
+    let prompt = LoginPrompt {
+        verification_url: authorization.verification_uri.clone(),
+        user_code: Some(authorization.user_code.clone()),
+        expires_in: authorization.expires_in.max(0) as u64,
+    };
+
+    let completion = async move {
+        loop {
+            let interval = authorization.interval.max(1) as u64;
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+            match poll_token(&client, &authorization).await? {
+                TokenPoll::Authorized(creds) => {
+                    let json_content = serde_json::to_string_pretty(&creds)
+                        .map_err(|e| format!("序列化凭证失败: {}", e))?;
+                    let creds_file_path =
+                        crate::services::credential_file_service::create_kiro_credential_from_json(
+                            &json_content,
+                        )?;
+                    return Ok(CredentialData::KiroOAuth { creds_file_path });
+                }
+                TokenPoll::Pending => {}
+                TokenPoll::SlowDown => authorization.interval += 5,
+                TokenPoll::Failed(message) => return Err(message),
+            }
+        }
+    };
+
+    Ok(PendingLogin {
+        prompt,
+        completion: Box::pin(completion),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_token_error() {
+        assert_eq!(
+            classify_token_error("authorization_pending"),
+            TokenPoll::Pending
+        );
+        assert_eq!(classify_token_error("slow_down"), TokenPoll::SlowDown);
+        assert!(matches!(
+            classify_token_error("access_denied"),
+            TokenPoll::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn test_credentials_json_is_idc_format() {
+        let authorization = DeviceAuthorization {
+            client_id: "cid".to_string(),
+            client_secret: "secret".to_string(),
+            device_code: "dc".to_string(),
+            user_code: "ABCD-EFGH".to_string(),
+            verification_uri: "https://device.sso".to_string(),
+            interval: 5,
+            expires_in: 600,
+            region: "eu-west-1".to_string(),
+        };
+        let creds = credentials_json(
+            &serde_json::json!({"accessToken": "at", "refreshToken": "rt", "expiresIn": 60}),
+            &authorization,
+        );
+        assert_eq!(creds["refreshToken"], "rt");
+        assert_eq!(creds["clientId"], "cid");
+        assert_eq!(creds["region"], "eu-west-1");
+        assert_eq!(creds["authMethod"], "idc");
+    }
+}
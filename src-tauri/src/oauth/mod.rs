@@ -0,0 +1,350 @@
+//! 内置 OAuth 登录
+//!
+//! 在代理内直接完成 OAuth 授权并把凭证写入凭证池，无需在外部生成凭证文件：
+//! - `gemini` - 授权码 + PKCE，由本地回调监听器（`callback`）接收授权码
+//! - `qwen` - Device Code Flow
+//! - `kiro` - AWS Builder ID Device Authorization Flow
+//!
+//! 登录以会话形式异步进行：`start` 返回需要用户打开的地址（及用户码），
+//! 后台等待授权完成后添加凭证，调用方轮询会话状态。
+//! Gemini 的回调监听在本机 127.0.0.1 上，远程使用时请选择 Device Flow 的 Provider。
+
+mod callback;
+mod gemini;
+pub(crate) mod kiro;
+mod qwen;
+
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::CredentialData;
+use crate::services::provider_pool_service::ProviderPoolService;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::task::AbortHandle;
+
+/// 已结束的会话保留时长（分钟）
+const FINISHED_SESSION_RETENTION_MINUTES: i64 = 60;
+
+/// 支持内置登录的 Provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoginProvider {
+    Gemini,
+    Qwen,
+    Kiro,
+}
+
+impl LoginProvider {
+    /// 对应的凭证池 Provider 类型
+    pub fn pool_type(&self) -> &'static str {
+        match self {
+            LoginProvider::Gemini => "gemini",
+            LoginProvider::Qwen => "qwen",
+            LoginProvider::Kiro => "kiro",
+        }
+    }
+}
+
+impl FromStr for LoginProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gemini" => Ok(LoginProvider::Gemini),
+            "qwen" => Ok(LoginProvider::Qwen),
+            "kiro" => Ok(LoginProvider::Kiro),
+            _ => Err(format!("不支持内置登录的 Provider: {}", s)),
+        }
+    }
+}
+
+/// 登录选项
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoginOptions {
+    /// 凭证名称
+    pub name: Option<String>,
+    /// 区域（仅 Kiro，默认 us-east-1）
+    pub region: Option<String>,
+}
+
+/// 需要用户完成的授权步骤
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoginPrompt {
+    /// 需要在浏览器中打开的地址
+    pub verification_url: String,
+    /// Device Flow 的用户码（授权码流程为 None）
+    pub user_code: Option<String>,
+    /// 授权有效期（秒）
+    pub expires_in: u64,
+}
+
+/// 登录状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LoginStatus {
+    Pending,
+    Completed { credential_uuid: String },
+    Failed { message: String },
+    Cancelled,
+}
+
+/// 登录会话
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginSession {
+    pub id: String,
+    pub provider: LoginProvider,
+    #[serde(flatten)]
+    pub prompt: LoginPrompt,
+    #[serde(flatten)]
+    pub status: LoginStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 进行中的授权：提示信息 + 授权完成后返回凭证数据的 future
+pub(crate) struct PendingLogin {
+    pub prompt: LoginPrompt,
+    pub completion: BoxFuture<'static, Result<CredentialData, String>>,
+}
+
+struct SessionEntry {
+    session: LoginSession,
+    task: Option<AbortHandle>,
+}
+
+/// 登录会话管理器
+#[derive(Default)]
+pub struct OAuthLoginManager {
+    sessions: Arc<parking_lot::Mutex<HashMap<String, SessionEntry>>>,
+}
+
+impl OAuthLoginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 发起登录，授权完成后自动添加到凭证池
+    pub async fn start(
+        &self,
+        provider: LoginProvider,
+        options: LoginOptions,
+        db: DbConnection,
+        pool_service: Arc<ProviderPoolService>,
+    ) -> Result<LoginSession, String> {
+        let pending = match provider {
+            LoginProvider::Gemini => gemini::start().await?,
+            LoginProvider::Qwen => qwen::start().await?,
+            LoginProvider::Kiro => kiro::start(options.region.as_deref()).await?,
+        };
+        let name = options.name;
+        Ok(self.spawn(provider, pending, move |credential| {
+            pool_service
+                .add_credential(
+                    &db,
+                    provider.pool_type(),
+                    credential,
+                    name,
+                    Some(true),
+                    None,
+                )
+                .map(|cred| cred.uuid)
+        }))
+    }
+
+    /// 查询会话
+    pub fn get(&self, id: &str) -> Option<LoginSession> {
+        self.sessions.lock().get(id).map(|e| e.session.clone())
+    }
+
+    /// 取消进行中的登录（已结束的会话原样返回）
+    pub fn cancel(&self, id: &str) -> Option<LoginSession> {
+        let mut sessions = self.sessions.lock();
+        let entry = sessions.get_mut(id)?;
+        if entry.session.status == LoginStatus::Pending {
+            if let Some(task) = entry.task.take() {
+                task.abort();
+            }
+            entry.session.status = LoginStatus::Cancelled;
+            tracing::info!(
+                "[OAUTH] 已取消 {:?} 登录会话 {}",
+                entry.session.provider,
+                id
+            );
+        }
+        Some(entry.session.clone())
+    }
+
+    fn spawn<F>(&self, provider: LoginProvider, pending: PendingLogin, register: F) -> LoginSession
+    where
+        F: FnOnce(CredentialData) -> Result<String, String> + Send + 'static,
+    {
+        self.prune();
+
+        let session = LoginSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            provider,
+            prompt: pending.prompt,
+            status: LoginStatus::Pending,
+            created_at: Utc::now(),
+        };
+        // 先登记会话，避免授权任务在登记前结束
+        self.sessions.lock().insert(
+            session.id.clone(),
+            SessionEntry {
+                session: session.clone(),
+                task: None,
+            },
+        );
+
+        let sessions = self.sessions.clone();
+        let id = session.id.clone();
+        let timeout = std::time::Duration::from_secs(session.prompt.expires_in);
+        let completion = pending.completion;
+        let task = tokio::spawn(async move {
+            let status = match tokio::time::timeout(timeout, completion).await {
+                Ok(Ok(credential)) => match register(credential) {
+                    Ok(credential_uuid) => LoginStatus::Completed { credential_uuid },
+                    Err(message) => LoginStatus::Failed { message },
+                },
+                Ok(Err(message)) => LoginStatus::Failed { message },
+                Err(_) => LoginStatus::Failed {
+                    message: "授权超时".to_string(),
+                },
+            };
+            match &status {
+                LoginStatus::Completed { credential_uuid } => {
+                    tracing::info!("[OAUTH] {:?} 登录成功，凭证: {}", provider, credential_uuid)
+                }
+                LoginStatus::Failed { message } => {
+                    tracing::warn!("[OAUTH] {:?} 登录失败: {}", provider, message)
+                }
+                _ => {}
+            }
+            if let Some(entry) = sessions.lock().get_mut(&id) {
+                entry.session.status = status;
+                entry.task = None;
+            }
+        });
+
+        if let Some(entry) = self.sessions.lock().get_mut(&session.id) {
+            if entry.session.status == LoginStatus::Pending {
+                entry.task = Some(task.abort_handle());
+            }
+        }
+        session
+    }
+
+    /// 清理过期的已结束会话
+    fn prune(&self) {
+        let cutoff = Utc::now() - chrono::Duration::minutes(FINISHED_SESSION_RETENTION_MINUTES);
+        self.sessions.lock().retain(|_, entry| {
+            entry.session.status == LoginStatus::Pending || entry.session.created_at > cutoff
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn pending(
+        expires_in: u64,
+        completion: impl std::future::Future<Output = Result<CredentialData, String>> + Send + 'static,
+    ) -> PendingLogin {
+        PendingLogin {
+            prompt: LoginPrompt {
+                verification_url: "https://example.com/device".to_string(),
+                user_code: Some("ABCD".to_string()),
+                expires_in,
+            },
+            completion: Box::pin(completion),
+        }
+    }
+
+    async fn wait_finished(manager: &OAuthLoginManager, id: &str) -> LoginStatus {
+        for _ in 0..100 {
+            let status = manager.get(id).unwrap().status;
+            if status != LoginStatus::Pending {
+                return status;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        panic!("login session did not finish");
+    }
+
+    fn qwen_credential() -> CredentialData {
+        CredentialData::QwenOAuth {
+            creds_file_path: "/tmp/qwen.json".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completed_login_registers_credential() {
+        let manager = OAuthLoginManager::new();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let session = manager.spawn(
+            LoginProvider::Qwen,
+            pending(60, async move { rx.await.map_err(|e| e.to_string()) }),
+            |credential| {
+                assert!(matches!(credential, CredentialData::QwenOAuth { .. }));
+                Ok("cred-1".to_string())
+            },
+        );
+        assert_eq!(session.status, LoginStatus::Pending);
+
+        tx.send(qwen_credential()).unwrap();
+        assert_eq!(
+            wait_finished(&manager, &session.id).await,
+            LoginStatus::Completed {
+                credential_uuid: "cred-1".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_pending_login() {
+        let manager = OAuthLoginManager::new();
+        let registered = Arc::new(AtomicBool::new(false));
+        let flag = registered.clone();
+        let session = manager.spawn(
+            LoginProvider::Kiro,
+            pending(60, futures::future::pending()),
+            move |_| {
+                flag.store(true, Ordering::SeqCst);
+                Ok("never".to_string())
+            },
+        );
+
+        let cancelled = manager.cancel(&session.id).unwrap();
+        assert_eq!(cancelled.status, LoginStatus::Cancelled);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(
+            manager.get(&session.id).unwrap().status,
+            LoginStatus::Cancelled
+        );
+        assert!(!registered.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_login_times_out() {
+        let manager = OAuthLoginManager::new();
+        let session = manager.spawn(
+            LoginProvider::Gemini,
+            pending(0, futures::future::pending()),
+            |_| Ok("never".to_string()),
+        );
+        assert!(matches!(
+            wait_finished(&manager, &session.id).await,
+            LoginStatus::Failed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_provider_from_str() {
+        assert_eq!("Kiro".parse::<LoginProvider>(), Ok(LoginProvider::Kiro));
+        assert!("claude".parse::<LoginProvider>().is_err());
+    }
+}
@@ -0,0 +1,32 @@
+//! Qwen 登录（Device Code Flow + PKCE）
+
+use super::{LoginPrompt, PendingLogin};
+use crate::models::provider_pool_model::CredentialData;
+use crate::providers::qwen;
+
+/// 发起 Qwen 设备授权
+pub(crate) async fn start() -> Result<PendingLogin, String> {
+    let (device, wait) = qwen::start_qwen_device_code_and_get_info()
+        .await
+        .map_err(|e| format!("发起 Qwen 设备授权失败: {}", e))?;
+
+    let prompt = LoginPrompt {
+        verification_url: device
+            .verification_uri_complete
+            .unwrap_or(device.verification_uri),
+        user_code: Some(device.user_code),
+        expires_in: device.expires_in.max(0) as u64,
+    };
+
+    let completion = async move {
+        let result = wait.await.map_err(|e| format!("Qwen 授权失败: {}", e))?;
+        Ok(CredentialData::QwenOAuth {
+            creds_file_path: result.creds_file_path,
+        })
+    };
+
+    Ok(PendingLogin {
+        prompt,
+        completion: Box::pin(completion),
+    })
+}
@@ -17,6 +17,8 @@ pub enum ProviderType {
     Azure,
     Bedrock,
     Antigravity,
+    Mistral,
+    DeepSeek,
     Custom,
 }
 
@@ -31,6 +33,8 @@ impl ProviderType {
             "azure" => Some(ProviderType::Azure),
             "bedrock" => Some(ProviderType::Bedrock),
             "antigravity" => Some(ProviderType::Antigravity),
+            "mistral" => Some(ProviderType::Mistral),
+            "deepseek" => Some(ProviderType::DeepSeek),
             _ => Some(ProviderType::Custom),
         }
     }
@@ -45,6 +49,8 @@ impl ProviderType {
             ProviderType::Azure => "Azure",
             ProviderType::Bedrock => "Bedrock",
             ProviderType::Antigravity => "Antigravity",
+            ProviderType::Mistral => "Mistral",
+            ProviderType::DeepSeek => "DeepSeek",
             ProviderType::Custom => "Custom",
         }
     }
@@ -284,6 +290,58 @@ pub fn builtin_provider_definitions() -> Vec<ProviderDefinition> {
             ],
             default_base_url: None,
         },
+        // Mistral
+        ProviderDefinition {
+            provider_type: ProviderType::Mistral,
+            display_name: "Mistral".to_string(),
+            families: vec![
+                ModelFamily {
+                    name: "large".to_string(),
+                    pattern: "mistral-large*".to_string(),
+                    tier: 3,
+                    description: Some("Mistral Large - 旗舰模型".to_string()),
+                },
+                ModelFamily {
+                    name: "medium".to_string(),
+                    pattern: "mistral-medium*".to_string(),
+                    tier: 2,
+                    description: Some("Mistral Medium - 均衡选择".to_string()),
+                },
+                ModelFamily {
+                    name: "codestral".to_string(),
+                    pattern: "codestral*".to_string(),
+                    tier: 2,
+                    description: Some("Codestral - 代码专用".to_string()),
+                },
+                ModelFamily {
+                    name: "small".to_string(),
+                    pattern: "mistral-small*".to_string(),
+                    tier: 1,
+                    description: Some("Mistral Small - 快速响应".to_string()),
+                },
+            ],
+            default_base_url: Some("https://api.mistral.ai".to_string()),
+        },
+        // DeepSeek
+        ProviderDefinition {
+            provider_type: ProviderType::DeepSeek,
+            display_name: "DeepSeek".to_string(),
+            families: vec![
+                ModelFamily {
+                    name: "reasoner".to_string(),
+                    pattern: "deepseek-reasoner*".to_string(),
+                    tier: 3,
+                    description: Some("DeepSeek Reasoner - 深度推理".to_string()),
+                },
+                ModelFamily {
+                    name: "chat".to_string(),
+                    pattern: "deepseek-chat*".to_string(),
+                    tier: 2,
+                    description: Some("DeepSeek Chat - 通用对话".to_string()),
+                },
+            ],
+            default_base_url: Some("https://api.deepseek.com".to_string()),
+        },
     ]
 }
 
@@ -450,6 +508,86 @@ pub fn builtin_model_metadata() -> Vec<ModelMetadata> {
             release_date: Some("2024-05-14".to_string()),
             is_latest: true,
         },
+        // Mistral Models
+        ModelMetadata {
+            id: "mistral-large-latest".to_string(),
+            display_name: "Mistral Large".to_string(),
+            provider_type: ProviderType::Mistral,
+            family: Some("large".to_string()),
+            context_length: Some(262144),
+            supports_vision: true,
+            supports_tools: true,
+            input_cost_per_million: Some(0.5),
+            output_cost_per_million: Some(1.5),
+            release_date: Some("2025-12-02".to_string()),
+            is_latest: true,
+        },
+        ModelMetadata {
+            id: "mistral-medium-latest".to_string(),
+            display_name: "Mistral Medium".to_string(),
+            provider_type: ProviderType::Mistral,
+            family: Some("medium".to_string()),
+            context_length: Some(131072),
+            supports_vision: true,
+            supports_tools: true,
+            input_cost_per_million: Some(0.4),
+            output_cost_per_million: Some(2.0),
+            release_date: Some("2025-08-12".to_string()),
+            is_latest: true,
+        },
+        ModelMetadata {
+            id: "codestral-latest".to_string(),
+            display_name: "Codestral".to_string(),
+            provider_type: ProviderType::Mistral,
+            family: Some("codestral".to_string()),
+            context_length: Some(262144),
+            supports_vision: false,
+            supports_tools: true,
+            input_cost_per_million: Some(0.3),
+            output_cost_per_million: Some(0.9),
+            release_date: Some("2025-07-30".to_string()),
+            is_latest: true,
+        },
+        ModelMetadata {
+            id: "mistral-small-latest".to_string(),
+            display_name: "Mistral Small".to_string(),
+            provider_type: ProviderType::Mistral,
+            family: Some("small".to_string()),
+            context_length: Some(131072),
+            supports_vision: true,
+            supports_tools: true,
+            input_cost_per_million: Some(0.1),
+            output_cost_per_million: Some(0.3),
+            release_date: Some("2025-06-20".to_string()),
+            is_latest: true,
+        },
+        // DeepSeek Models
+        ModelMetadata {
+            id: "deepseek-chat".to_string(),
+            display_name: "DeepSeek Chat".to_string(),
+            provider_type: ProviderType::DeepSeek,
+            family: Some("chat".to_string()),
+            context_length: Some(131072),
+            supports_vision: false,
+            supports_tools: true,
+            input_cost_per_million: Some(0.28),
+            output_cost_per_million: Some(0.42),
+            release_date: Some("2025-12-01".to_string()),
+            is_latest: true,
+        },
+        ModelMetadata {
+            id: "deepseek-reasoner".to_string(),
+            display_name: "DeepSeek Reasoner".to_string(),
+            provider_type: ProviderType::DeepSeek,
+            family: Some("reasoner".to_string()),
+            context_length: Some(131072),
+            supports_vision: false,
+            supports_tools: true,
+            input_cost_per_million: Some(0.28),
+            output_cost_per_million: Some(0.42),
+            release_date: Some("2025-12-01".to_string()),
+            is_latest: true,
+        },
     ]
 }
 
@@ -653,6 +791,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deepseek_and_mistral_tiers() {
+        let definitions = builtin_provider_definitions();
+        let deepseek = definitions
+            .iter()
+            .find(|d| d.provider_type == ProviderType::DeepSeek)
+            .unwrap();
+        assert_eq!(
+            deepseek.get_tier("deepseek-reasoner"),
+            Some(ServiceTier::Max)
+        );
+        assert_eq!(deepseek.get_tier("deepseek-chat"), Some(ServiceTier::Pro));
+
+        let mistral = definitions
+            .iter()
+            .find(|d| d.provider_type == ProviderType::Mistral)
+            .unwrap();
+        assert_eq!(
+            mistral.get_tier("mistral-large-latest"),
+            Some(ServiceTier::Max)
+        );
+        assert_eq!(
+            mistral.get_tier("mistral-small-latest"),
+            Some(ServiceTier::Mini)
+        );
+    }
+
     #[test]
     fn test_dynamic_pool_builder() {
         let builder = DynamicPoolBuilder::new();
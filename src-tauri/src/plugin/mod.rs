@@ -16,6 +16,7 @@ mod loader;
 mod manager;
 mod types;
 pub mod ui_builder;
+#[cfg(feature = "desktop")]
 pub mod ui_events;
 pub mod ui_trait;
 pub mod ui_types;
@@ -27,6 +28,7 @@ pub use types::{
     BinaryComponentStatus, BinaryManifest, HookResult, PlatformBinaries, Plugin, PluginConfig,
     PluginContext, PluginError, PluginInfo, PluginManifest, PluginState, PluginStatus, PluginType,
 };
+#[cfg(feature = "desktop")]
 pub use ui_events::{PluginUIEmitter, PluginUIEmitterState, PluginUIEventPayload};
 pub use ui_trait::{NoUI, PluginUI};
 pub use ui_types::{
@@ -0,0 +1,444 @@
+//! 响应后处理
+//!
+//! 与参数注入相对，在返回客户端之前改写模型输出的文本：
+//! - 在客户端指定、但上游忽略的停止序列处截断
+//! - 移除 Provider 输出残留（如 `[Called xxx with args: {...}]`、泄露的特殊 token）
+//! - 按规则替换文本（如脱敏）
+//! - 规范化空白
+//!
+//! 作用于 OpenAI（`/chat/completions`）和 Anthropic（`/messages`）格式的成功响应，包括流式响应。
+//! 配置了替换规则或规范化空白时，流式响应按行处理，未完成的行会暂缓输出。
+
+mod stream;
+
+use crate::config::ResponseProcessingConfig;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 内置的 Provider 输出残留（名称, 正则）
+const BUILTIN_ARTIFACTS: &[(&str, &str)] = &[
+    ("called_remnant", r"\[Called\s+\w+\s+with\s+args:[^\n]*\]"),
+    (
+        "special_token",
+        r"<\|(?:endoftext|im_start|im_end|eot_id|end_of_turn|user|bot)\|>",
+    ),
+];
+
+/// 流式响应中单行最多暂缓输出的字节数
+const MAX_PENDING_LINE: usize = 4096;
+
+/// 响应格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    OpenAI,
+    Anthropic,
+}
+
+impl ResponseFormat {
+    /// 根据请求路径判断响应格式
+    pub fn from_path(path: &str) -> Option<Self> {
+        if path.ends_with("/chat/completions") {
+            Some(Self::OpenAI)
+        } else if path.ends_with("/messages") {
+            Some(Self::Anthropic)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    id: String,
+    pattern: String,
+    regex: Regex,
+    replacement: String,
+}
+
+/// 响应后处理器
+#[derive(Debug, Clone, Default)]
+pub struct ResponseProcessor {
+    enabled: bool,
+    enforce_stop_sequences: bool,
+    normalize_whitespace: bool,
+    rules: Vec<CompiledRule>,
+}
+
+impl ResponseProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从配置构建（无效的正则会被忽略并记录警告）
+    pub fn from_config(config: &ResponseProcessingConfig) -> Self {
+        let mut rules = Vec::new();
+        if config.strip_artifacts {
+            for (id, regex) in BUILTIN_ARTIFACTS {
+                rules.push(CompiledRule {
+                    id: id.to_string(),
+                    pattern: "*".to_string(),
+                    regex: Regex::new(regex).expect("valid builtin"),
+                    replacement: String::new(),
+                });
+            }
+        }
+        for rule in config.rules.iter().filter(|r| r.enabled) {
+            match Regex::new(&rule.regex) {
+                Ok(regex) => rules.push(CompiledRule {
+                    id: rule.id.clone(),
+                    pattern: rule.pattern.clone(),
+                    regex,
+                    replacement: rule.replacement.clone(),
+                }),
+                Err(e) => tracing::warn!("[POSTPROCESS] 规则 {} 正则无效，已忽略: {}", rule.id, e),
+            }
+        }
+        Self {
+            enabled: config.enabled,
+            enforce_stop_sequences: config.enforce_stop_sequences,
+            normalize_whitespace: config.normalize_whitespace,
+            rules,
+        }
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+            && (self.enforce_stop_sequences || self.normalize_whitespace || !self.rules.is_empty())
+    }
+
+    /// 为请求创建文本过滤器（没有需要处理的内容时返回 None）
+    pub fn filter(&self, model: &str, stop_sequences: Vec<String>) -> Option<TextFilter> {
+        if !self.enabled {
+            return None;
+        }
+        let stop_sequences: Vec<String> = if self.enforce_stop_sequences {
+            stop_sequences
+                .into_iter()
+                .filter(|s| !s.is_empty())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let rules: Vec<CompiledRule> = self
+            .rules
+            .iter()
+            .filter(|rule| crate::injection::pattern_matches(&rule.pattern, model))
+            .cloned()
+            .collect();
+        if stop_sequences.is_empty() && rules.is_empty() && !self.normalize_whitespace {
+            return None;
+        }
+        Some(TextFilter {
+            stop_sequences,
+            rules,
+            normalize_whitespace: self.normalize_whitespace,
+            pending: String::new(),
+            started: false,
+            stopped: None,
+        })
+    }
+}
+
+/// 单段输出文本（一个 choice 或内容块）的过滤器
+///
+/// 上游文本分块写入，返回可以输出给客户端的文本；可能命中停止序列的尾部和未完成的行会暂缓输出。
+#[derive(Debug, Clone)]
+pub struct TextFilter {
+    stop_sequences: Vec<String>,
+    rules: Vec<CompiledRule>,
+    normalize_whitespace: bool,
+    pending: String,
+    started: bool,
+    stopped: Option<String>,
+}
+
+impl TextFilter {
+    /// 写入上游文本，返回可以输出的文本（命中停止序列后忽略后续文本）
+    pub fn push(&mut self, text: &str) -> String {
+        if self.stopped.is_some() {
+            return String::new();
+        }
+        self.pending.push_str(text);
+        if let Some((index, stop)) = self.find_stop() {
+            self.pending.truncate(index);
+            self.stopped = Some(stop);
+            return self.drain(self.pending.len(), true);
+        }
+        let boundary = self.safe_boundary();
+        self.drain(boundary, false)
+    }
+
+    /// 上游文本结束，返回暂缓输出的剩余文本
+    pub fn finish(&mut self) -> String {
+        self.drain(self.pending.len(), true)
+    }
+
+    /// 处理完整文本
+    pub fn apply(&mut self, text: &str) -> String {
+        let mut output = self.push(text);
+        output.push_str(&self.finish());
+        output
+    }
+
+    /// 命中的停止序列
+    pub fn stop_sequence(&self) -> Option<&str> {
+        self.stopped.as_deref()
+    }
+
+    /// 最早出现的停止序列（位置, 停止序列）
+    fn find_stop(&self) -> Option<(usize, String)> {
+        self.stop_sequences
+            .iter()
+            .filter_map(|stop| self.pending.find(stop.as_str()).map(|i| (i, stop.clone())))
+            .min_by_key(|(i, _)| *i)
+    }
+
+    /// 可以处理的前缀长度：保留可能是停止序列开头的尾部，
+    /// 有替换规则或规范化空白时只处理到最后一个换行
+    fn safe_boundary(&self) -> usize {
+        let max_stop = self
+            .stop_sequences
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or(0);
+        let mut boundary = self
+            .pending
+            .len()
+            .saturating_sub(max_stop.saturating_sub(1));
+        while !self.pending.is_char_boundary(boundary) {
+            boundary -= 1;
+        }
+        if self.rules.is_empty() && !self.normalize_whitespace {
+            return boundary;
+        }
+        match self.pending[..boundary].rfind('\n') {
+            Some(i) => i + 1,
+            None if boundary > MAX_PENDING_LINE => boundary,
+            None => 0,
+        }
+    }
+
+    fn drain(&mut self, boundary: usize, last: bool) -> String {
+        let mut text: String = self.pending.drain(..boundary).collect();
+        for rule in &self.rules {
+            if let Cow::Owned(replaced) = rule
+                .regex
+                .replace_all(&text, regex::NoExpand(&rule.replacement))
+            {
+                tracing::debug!("[POSTPROCESS] 规则 {} 已替换输出文本", rule.id);
+                text = replaced;
+            }
+        }
+        if self.normalize_whitespace {
+            text = normalize_lines(&text, self.started);
+            if !self.started {
+                text = text.trim_start().to_string();
+            }
+            let kept = text.trim_end().len();
+            if !last {
+                // 末尾空白暂缓输出，与后续文本一起规范化
+                self.pending.insert_str(0, &text[kept..]);
+            }
+            text.truncate(kept);
+        }
+        if !text.is_empty() {
+            self.started = true;
+        }
+        text
+    }
+}
+
+/// 去掉行尾空格，连续空行合并为一个
+///
+/// `continuation` 表示第一行接在已输出的非空文本之后，不算空行。
+fn normalize_lines(text: &str, continuation: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    let mut lines = text.split('\n').enumerate().peekable();
+    while let Some((i, line)) = lines.next() {
+        if lines.peek().is_none() {
+            output.push_str(line);
+            break;
+        }
+        let line = line.trim_end_matches([' ', '\t', '\r']);
+        if line.is_empty() && !(i == 0 && continuation) {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+/// 请求中的模型名称和停止序列
+fn request_options(format: ResponseFormat, body: &[u8]) -> (String, Vec<String>) {
+    let Ok(request) = serde_json::from_slice::<Value>(body) else {
+        return (String::new(), Vec::new());
+    };
+    let model = request["model"].as_str().unwrap_or_default().to_string();
+    let stop = match format {
+        ResponseFormat::OpenAI => &request["stop"],
+        ResponseFormat::Anthropic => &request["stop_sequences"],
+    };
+    let stop_sequences = match stop {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|s| s.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    (model, stop_sequences)
+}
+
+/// 改写 OpenAI `chat.completion` 响应
+pub fn rewrite_openai_completion(completion: &mut Value, template: &TextFilter) {
+    let Some(choices) = completion["choices"].as_array_mut() else {
+        return;
+    };
+    for choice in choices {
+        let Some(content) = choice["message"]["content"].as_str() else {
+            continue;
+        };
+        let mut filter = template.clone();
+        let text = filter.apply(content);
+        choice["message"]["content"] = Value::String(text);
+        if filter.stop_sequence().is_some() {
+            if let Some(message) = choice["message"].as_object_mut() {
+                message.remove("tool_calls");
+            }
+            choice["finish_reason"] = Value::from("stop");
+        }
+    }
+}
+
+/// 改写 Anthropic `message` 响应（命中停止序列后丢弃后续内容块）
+pub fn rewrite_anthropic_message(message: &mut Value, template: &TextFilter) {
+    let Some(blocks) = message["content"].as_array_mut() else {
+        return;
+    };
+    let mut stopped = None;
+    let mut kept = 0;
+    for block in blocks.iter_mut() {
+        if stopped.is_some() {
+            break;
+        }
+        kept += 1;
+        if block["type"] != "text" {
+            continue;
+        }
+        let Some(text) = block["text"].as_str() else {
+            continue;
+        };
+        let mut filter = template.clone();
+        block["text"] = Value::String(filter.apply(text));
+        stopped = filter.stop_sequence().map(str::to_string);
+    }
+    blocks.truncate(kept);
+    if let Some(stop) = stopped {
+        message["stop_reason"] = Value::from("stop_sequence");
+        message["stop_sequence"] = Value::from(stop);
+    }
+}
+
+/// 为路由组添加响应后处理中间件（配置变更时通过共享的处理器生效）
+pub fn with_response_processing<S>(
+    router: Router<S>,
+    processor: Arc<RwLock<ResponseProcessor>>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(axum::middleware::from_fn_with_state(
+        processor,
+        process_response,
+    ))
+}
+
+/// 改写模型输出文本
+pub async fn process_response(
+    State(processor): State<Arc<RwLock<ResponseProcessor>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let format = ResponseFormat::from_path(request.uri().path());
+    let Some(format) = format.filter(|_| request.method() == Method::POST) else {
+        return next.run(request).await;
+    };
+    if !processor.read().await.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, format!("读取请求体失败: {}", e)).into_response()
+        }
+    };
+    let (model, stop_sequences) = request_options(format, &bytes);
+    let filter = processor.read().await.filter(&model, stop_sequences);
+    let Some(filter) = filter else {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if content_type.starts_with("text/event-stream") {
+        let (parts, body) = response.into_parts();
+        let stream = stream::rewrite_sse(body.into_data_stream(), format, filter);
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+    if !content_type.starts_with("application/json") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (StatusCode::BAD_GATEWAY, format!("读取响应体失败: {}", e)).into_response()
+        }
+    };
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    match format {
+        ResponseFormat::OpenAI => rewrite_openai_completion(&mut value, &filter),
+        ResponseFormat::Anthropic => rewrite_anthropic_message(&mut value, &filter),
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests;
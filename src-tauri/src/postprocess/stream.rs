@@ -0,0 +1,309 @@
+//! 流式响应（SSE）后处理
+//!
+//! 按事件解析上游 SSE，只改写文本增量，其他事件原样输出：
+//! - OpenAI：每个 choice 的 `delta.content` 单独过滤，`finish_reason` 事件中补发暂缓的文本
+//! - Anthropic：每个 text 内容块的 `text_delta` 单独过滤，`content_block_stop` 前补发暂缓的文本；
+//!   命中停止序列后丢弃后续内容块，`message_delta` 的 `stop_reason` 改为 `stop_sequence`
+
+use super::{ResponseFormat, TextFilter};
+use axum::body::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// 改写 SSE 数据流
+pub(super) fn rewrite_sse<S>(
+    mut inner: S,
+    format: ResponseFormat,
+    template: TextFilter,
+) -> impl Stream<Item = Result<Bytes, axum::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin + Send + 'static,
+{
+    let mut rewriter = match format {
+        ResponseFormat::OpenAI => Rewriter::OpenAI(OpenAIStream::new(template)),
+        ResponseFormat::Anthropic => Rewriter::Anthropic(AnthropicStream::new(template)),
+    };
+    async_stream::stream! {
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = inner.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    buffer.extend_from_slice(&chunk);
+                    let mut output = String::new();
+                    while let Some((end, next)) = find_event_end(&buffer) {
+                        let event: Vec<u8> = buffer.drain(..next).collect();
+                        output.push_str(&rewriter.rewrite(&String::from_utf8_lossy(&event[..end])));
+                    }
+                    if !output.is_empty() {
+                        yield Ok(Bytes::from(output));
+                    }
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+        let mut output = String::new();
+        if !buffer.iter().all(u8::is_ascii_whitespace) {
+            output.push_str(&rewriter.rewrite(&String::from_utf8_lossy(&buffer)));
+        }
+        output.push_str(&rewriter.finish());
+        if !output.is_empty() {
+            yield Ok(Bytes::from(output));
+        }
+    }
+}
+
+/// 事件结束位置（事件内容结束位置, 下一个事件开始位置）
+fn find_event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    let lf = buffer
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .map(|i| (i, i + 2));
+    let crlf = buffer
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| (i, i + 4));
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(if a.0 <= b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 解析事件（事件名称, data）
+fn parse_event(event: &str) -> (Option<&str>, Option<String>) {
+    let mut name = None;
+    let mut data: Option<String> = None;
+    for line in event.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            name = Some(value.trim());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match &mut data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => data = Some(value.to_string()),
+            }
+        }
+    }
+    (name, data)
+}
+
+fn format_event(name: Option<&str>, data: &Value) -> String {
+    match name {
+        Some(name) => format!("event: {}\ndata: {}\n\n", name, data),
+        None => format!("data: {}\n\n", data),
+    }
+}
+
+enum Rewriter {
+    OpenAI(OpenAIStream),
+    Anthropic(AnthropicStream),
+}
+
+impl Rewriter {
+    /// 改写单个事件，返回要输出的事件文本
+    fn rewrite(&mut self, event: &str) -> String {
+        let (name, data) = parse_event(event);
+        let parsed = data
+            .as_deref()
+            .and_then(|data| serde_json::from_str::<Value>(data).ok());
+        let rewritten = match (self, parsed) {
+            (Rewriter::OpenAI(stream), Some(chunk)) => stream.rewrite(chunk),
+            (Rewriter::OpenAI(stream), None) if data.as_deref() == Some("[DONE]") => {
+                let mut output = stream.finish();
+                output.push_str("data: [DONE]\n\n");
+                return output;
+            }
+            (Rewriter::Anthropic(stream), Some(data)) => stream.rewrite(name, data),
+            _ => None,
+        };
+        rewritten.unwrap_or_else(|| format!("{}\n\n", event))
+    }
+
+    /// 数据流结束，返回补发的事件
+    fn finish(&mut self) -> String {
+        match self {
+            Rewriter::OpenAI(stream) => stream.finish(),
+            Rewriter::Anthropic(_) => String::new(),
+        }
+    }
+}
+
+/// OpenAI `chat.completion.chunk` 数据流
+struct OpenAIStream {
+    template: TextFilter,
+    choices: HashMap<u64, TextFilter>,
+    /// 最近的数据块（补发文本时沿用 id、model 等字段）
+    last_chunk: Option<Value>,
+}
+
+impl OpenAIStream {
+    fn new(template: TextFilter) -> Self {
+        Self {
+            template,
+            choices: HashMap::new(),
+            last_chunk: None,
+        }
+    }
+
+    /// 改写数据块（没有 choices 时返回 None 表示原样输出）
+    fn rewrite(&mut self, mut chunk: Value) -> Option<String> {
+        let choices = chunk["choices"].as_array_mut()?;
+        for choice in choices.iter_mut() {
+            let index = choice["index"].as_u64().unwrap_or(0);
+            let filter = self
+                .choices
+                .entry(index)
+                .or_insert_with(|| self.template.clone());
+            let mut text = match choice["delta"]["content"].as_str() {
+                Some(content) => filter.push(content),
+                None => String::new(),
+            };
+            let finished = !choice["finish_reason"].is_null();
+            if finished {
+                text.push_str(&filter.finish());
+            }
+            if choice["delta"]["content"].is_string() || !text.is_empty() {
+                choice["delta"]["content"] = Value::String(text);
+            }
+            if filter.stop_sequence().is_some() {
+                if let Some(delta) = choice["delta"].as_object_mut() {
+                    delta.remove("tool_calls");
+                }
+                if finished {
+                    choice["finish_reason"] = Value::from("stop");
+                }
+            }
+            if finished {
+                self.choices.remove(&index);
+            }
+        }
+        let output = format_event(None, &chunk);
+        self.last_chunk = Some(chunk);
+        Some(output)
+    }
+
+    /// 补发未结束的 choice 中暂缓的文本
+    fn finish(&mut self) -> String {
+        let mut output = String::new();
+        let mut indexes: Vec<u64> = self.choices.keys().copied().collect();
+        indexes.sort_unstable();
+        for index in indexes {
+            let Some(mut filter) = self.choices.remove(&index) else {
+                continue;
+            };
+            let text = filter.finish();
+            if text.is_empty() {
+                continue;
+            }
+            let mut chunk = self
+                .last_chunk
+                .clone()
+                .unwrap_or_else(|| serde_json::json!({"object": "chat.completion.chunk"}));
+            if let Some(obj) = chunk.as_object_mut() {
+                obj.remove("usage");
+            }
+            chunk["choices"] = serde_json::json!([{
+                "index": index,
+                "delta": {"content": text},
+                "finish_reason": null
+            }]);
+            output.push_str(&format_event(None, &chunk));
+        }
+        output
+    }
+}
+
+/// Anthropic Messages 数据流
+struct AnthropicStream {
+    template: TextFilter,
+    blocks: HashMap<u64, TextFilter>,
+    /// 命中停止序列后丢弃的内容块
+    dropped: HashSet<u64>,
+    stopped: Option<String>,
+}
+
+impl AnthropicStream {
+    fn new(template: TextFilter) -> Self {
+        Self {
+            template,
+            blocks: HashMap::new(),
+            dropped: HashSet::new(),
+            stopped: None,
+        }
+    }
+
+    /// 改写事件（返回 None 表示原样输出）
+    fn rewrite(&mut self, name: Option<&str>, mut data: Value) -> Option<String> {
+        let event_type = data["type"].as_str().unwrap_or_default().to_string();
+        let index = data["index"].as_u64().unwrap_or(0);
+        match event_type.as_str() {
+            "content_block_start" => {
+                if self.stopped.is_some() {
+                    self.dropped.insert(index);
+                    return Some(String::new());
+                }
+                if data["content_block"]["type"] != "text" {
+                    return None;
+                }
+                let mut filter = self.template.clone();
+                let text = data["content_block"]["text"]
+                    .as_str()
+                    .map(|t| filter.push(t));
+                self.stopped = filter.stop_sequence().map(str::to_string);
+                self.blocks.insert(index, filter);
+                let text = text?;
+                data["content_block"]["text"] = Value::String(text);
+                Some(format_event(name, &data))
+            }
+            "content_block_delta" => {
+                if self.dropped.contains(&index) {
+                    return Some(String::new());
+                }
+                let filter = self.blocks.get_mut(&index)?;
+                let text = data["delta"]["text"].as_str()?;
+                let text = filter.push(text);
+                if self.stopped.is_none() {
+                    self.stopped = filter.stop_sequence().map(str::to_string);
+                }
+                if text.is_empty() {
+                    return Some(String::new());
+                }
+                data["delta"]["text"] = Value::String(text);
+                Some(format_event(name, &data))
+            }
+            "content_block_stop" => {
+                if self.dropped.remove(&index) {
+                    return Some(String::new());
+                }
+                let mut filter = self.blocks.remove(&index)?;
+                let text = filter.finish();
+                if text.is_empty() {
+                    return None;
+                }
+                let delta = serde_json::json!({
+                    "type": "content_block_delta",
+                    "index": index,
+                    "delta": {"type": "text_delta", "text": text}
+                });
+                Some(format!(
+                    "{}{}",
+                    format_event(Some("content_block_delta"), &delta),
+                    format_event(name, &data)
+                ))
+            }
+            "message_delta" => {
+                let stop = self.stopped.clone()?;
+                data["delta"]["stop_reason"] = Value::from("stop_sequence");
+                data["delta"]["stop_sequence"] = Value::from(stop);
+                Some(format_event(name, &data))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! 响应后处理测试
+
+use super::*;
+use crate::config::ResponseRuleConfig;
+use axum::body::Bytes;
+use futures::StreamExt;
+use serde_json::json;
+
+fn processor(normalize_whitespace: bool) -> ResponseProcessor {
+    ResponseProcessor::from_config(&ResponseProcessingConfig {
+        enabled: true,
+        normalize_whitespace,
+        rules: vec![
+            ResponseRuleConfig {
+                id: "internal_host".to_string(),
+                pattern: "claude-*".to_string(),
+                regex: r"\b[\w.-]+\.corp\.example\.com\b".to_string(),
+                replacement: "[REDACTED]".to_string(),
+                enabled: true,
+            },
+            ResponseRuleConfig {
+                id: "invalid".to_string(),
+                pattern: "*".to_string(),
+                regex: "(".to_string(),
+                replacement: String::new(),
+                enabled: true,
+            },
+        ],
+        ..ResponseProcessingConfig::default()
+    })
+}
+
+fn push_all(filter: &mut TextFilter, chunks: &[&str]) -> String {
+    let mut output: String = chunks.iter().map(|chunk| filter.push(chunk)).collect();
+    output.push_str(&filter.finish());
+    output
+}
+
+async fn rewrite(format: ResponseFormat, filter: TextFilter, events: &[&'static str]) -> String {
+    let chunks: Vec<Result<Bytes, axum::Error>> = events
+        .iter()
+        .map(|event| Ok(Bytes::from_static(event.as_bytes())))
+        .collect();
+    let inner = futures::stream::iter(chunks);
+    let output: Vec<Bytes> = stream::rewrite_sse(inner, format, filter)
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+    String::from_utf8(output.concat()).unwrap()
+}
+
+#[test]
+fn test_filter_disabled_or_nothing_to_do() {
+    assert!(ResponseProcessor::new()
+        .filter("gpt-4o", vec!["END".into()])
+        .is_none());
+    let processor = ResponseProcessor::from_config(&ResponseProcessingConfig {
+        enabled: true,
+        strip_artifacts: false,
+        ..ResponseProcessingConfig::default()
+    });
+    assert!(processor.is_enabled());
+    assert!(processor.filter("gpt-4o", Vec::new()).is_none());
+    assert!(processor.filter("gpt-4o", vec!["END".into()]).is_some());
+}
+
+#[test]
+fn test_stop_sequence_across_chunks() {
+    let mut filter = processor(false)
+        .filter("gpt-4o", vec!["\n\nHuman:".into()])
+        .unwrap();
+    let output = push_all(
+        &mut filter,
+        &["Hello", " world\n", "\nHum", "an: next", " turn"],
+    );
+    assert_eq!(output, "Hello world");
+    assert_eq!(filter.stop_sequence(), Some("\n\nHuman:"));
+
+    let mut filter = processor(false)
+        .filter("gpt-4o", vec!["END".into()])
+        .unwrap();
+    assert_eq!(push_all(&mut filter, &["EN", "D", "ING? no"]), "");
+
+    // 未命中时输出完整文本
+    let mut filter = processor(false)
+        .filter("gpt-4o", vec!["STOP".into()])
+        .unwrap();
+    assert_eq!(push_all(&mut filter, &["ST", "OR", "Y"]), "STORY");
+    assert_eq!(filter.stop_sequence(), None);
+}
+
+#[test]
+fn test_artifacts_and_rules_by_model() {
+    let text = "Done [Called read_file with args: {\"path\": \"a.rs\"}]<|im_end|>\nsee db1.corp.example.com\n";
+    let mut filter = processor(false)
+        .filter("claude-sonnet-4-5", Vec::new())
+        .unwrap();
+    assert_eq!(
+        push_all(&mut filter, &[&text[..20], &text[20..]]),
+        "Done \nsee [REDACTED]\n"
+    );
+
+    // 规则只作用于匹配的模型
+    let mut filter = processor(false).filter("gpt-4o", Vec::new()).unwrap();
+    assert_eq!(filter.apply(text), "Done \nsee db1.corp.example.com\n");
+}
+
+#[test]
+fn test_normalize_whitespace() {
+    let mut filter = processor(true).filter("gpt-4o", Vec::new()).unwrap();
+    let output = push_all(
+        &mut filter,
+        &["\n  Hello  ", "\n", "\n\n\nworld", "\n\n", "next \n", "\n"],
+    );
+    assert_eq!(output, "Hello\n\nworld\n\nnext");
+}
+
+#[test]
+fn test_rewrite_json_responses() {
+    let filter = processor(false)
+        .filter("gpt-4o", vec!["END".into()])
+        .unwrap();
+    let mut completion = json!({
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": "answer END ignored", "tool_calls": []},
+            "finish_reason": "tool_calls"
+        }]
+    });
+    rewrite_openai_completion(&mut completion, &filter);
+    assert_eq!(completion["choices"][0]["message"]["content"], "answer ");
+    assert_eq!(completion["choices"][0]["finish_reason"], "stop");
+    assert!(completion["choices"][0]["message"]
+        .get("tool_calls")
+        .is_none());
+
+    let mut message = json!({
+        "content": [
+            {"type": "text", "text": "first END rest"},
+            {"type": "tool_use", "id": "t1", "name": "x", "input": {}}
+        ],
+        "stop_reason": "tool_use",
+        "stop_sequence": null
+    });
+    rewrite_anthropic_message(&mut message, &filter);
+    assert_eq!(
+        message["content"],
+        json!([{"type": "text", "text": "first "}])
+    );
+    assert_eq!(message["stop_reason"], "stop_sequence");
+    assert_eq!(message["stop_sequence"], "END");
+}
+
+#[tokio::test]
+async fn test_rewrite_openai_stream() {
+    let filter = processor(false)
+        .filter("gpt-4o", vec!["END".into()])
+        .unwrap();
+    let output = rewrite(
+        ResponseFormat::OpenAI,
+        filter,
+        &[
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi E\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"ND more\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"length\"}]}\n\ndata: [DONE]\n\n",
+        ],
+    )
+    .await;
+    let contents: Vec<String> = output
+        .split("\n\n")
+        .filter_map(|event| event.strip_prefix("data: "))
+        .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+        .map(|chunk| {
+            chunk["choices"][0]["delta"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(contents.concat(), "Hi ");
+    assert!(output.contains("\"finish_reason\":\"stop\""));
+    assert!(output.ends_with("data: [DONE]\n\n"));
+}
+
+#[tokio::test]
+async fn test_rewrite_anthropic_stream() {
+    let filter = processor(false)
+        .filter("claude-sonnet-4-5", vec!["END".into()])
+        .unwrap();
+    let output = rewrite(
+        ResponseFormat::Anthropic,
+        filter,
+        &[
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello E\"}}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"ND tail\"}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"x\",\"input\":{}}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        ],
+    )
+    .await;
+    let text: String = output
+        .split("\n\n")
+        .filter_map(|event| event.split_once("data: ").map(|(_, data)| data))
+        .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+        .filter_map(|event| event["delta"]["text"].as_str().map(str::to_string))
+        .collect();
+    assert_eq!(text, "Hello ");
+    assert!(!output.contains("tool_use\",\"id\""));
+    assert!(output.contains("\"stop_reason\":\"stop_sequence\""));
+    assert!(output.contains("\"stop_sequence\":\"END\""));
+    assert!(output.contains("event: ping"));
+    assert!(output.ends_with("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+}
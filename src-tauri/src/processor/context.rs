@@ -2,11 +2,15 @@
 //!
 //! 定义请求处理过程中的上下文信息
 
+use crate::models::anthropic::AnthropicVersion;
 use crate::plugin::PluginContext;
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
 use std::time::Instant;
 
+/// 终端用户 ID 最大长度（字符），超出部分截断，避免统计维度被超长值污染
+pub const MAX_USER_ID_LEN: usize = 256;
+
 /// 请求上下文
 ///
 /// 在请求处理管道中传递的上下文信息
@@ -30,6 +34,10 @@ pub struct RequestContext {
     pub retry_count: u32,
     /// 是否为流式请求
     pub is_stream: bool,
+    /// 下游终端用户 ID（Anthropic `metadata.user_id` / OpenAI `user`）
+    pub user_id: Option<String>,
+    /// 客户端请求的 `anthropic-version`
+    pub anthropic_version: Option<String>,
     /// 插件上下文
     pub plugin_ctx: Option<PluginContext>,
     /// 元数据
@@ -37,11 +45,10 @@ pub struct RequestContext {
 }
 
 impl RequestContext {
-    /// 创建新的请求上下文
+    /// 创建新的请求上下文（在请求处理上下文中沿用中间件分配的请求 ID）
     pub fn new(model: String) -> Self {
-        let request_id = uuid::Uuid::new_v4().to_string();
         Self {
-            request_id: request_id.clone(),
+            request_id: crate::middleware::current_or_new_request_id(),
             start_time: Instant::now(),
             timestamp: Utc::now(),
             original_model: model.clone(),
@@ -50,6 +57,8 @@ impl RequestContext {
             credential_id: None,
             retry_count: 0,
             is_stream: false,
+            user_id: None,
+            anthropic_version: None,
             plugin_ctx: None,
             metadata: std::collections::HashMap::new(),
         }
@@ -71,6 +80,27 @@ impl RequestContext {
         self.credential_id = Some(credential_id);
     }
 
+    /// 设置终端用户 ID（去除首尾空白，空值忽略，超长截断）
+    pub fn set_user_id(&mut self, user_id: Option<&str>) {
+        self.user_id = user_id
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| id.chars().take(MAX_USER_ID_LEN).collect());
+    }
+
+    /// 记录客户端请求的 `anthropic-version`（空值忽略）
+    pub fn set_anthropic_version(&mut self, version: Option<&str>) {
+        self.anthropic_version = version
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+    }
+
+    /// 客户端请求的 Anthropic API 版本
+    pub fn anthropic_api_version(&self) -> AnthropicVersion {
+        AnthropicVersion::parse(self.anthropic_version.as_deref())
+    }
+
     /// 设置解析后的模型名称
     pub fn set_resolved_model(&mut self, model: String) {
         self.resolved_model = model;
@@ -166,4 +196,33 @@ mod tests {
         assert!(value.is_some());
         assert_eq!(value.unwrap(), &serde_json::json!("value"));
     }
+
+    #[test]
+    fn test_request_context_set_user_id() {
+        let mut ctx = RequestContext::new("model".to_string());
+        ctx.set_user_id(Some("  user-1 "));
+        assert_eq!(ctx.user_id.as_deref(), Some("user-1"));
+
+        ctx.set_user_id(Some("   "));
+        assert!(ctx.user_id.is_none());
+
+        ctx.set_user_id(Some(&"x".repeat(MAX_USER_ID_LEN + 10)));
+        assert_eq!(ctx.user_id.unwrap().len(), MAX_USER_ID_LEN);
+    }
+
+    #[test]
+    fn test_request_context_anthropic_version() {
+        let mut ctx = RequestContext::new("model".to_string());
+        assert_eq!(ctx.anthropic_api_version(), AnthropicVersion::Current);
+
+        ctx.set_anthropic_version(Some(" 2023-01-01 "));
+        assert_eq!(ctx.anthropic_version.as_deref(), Some("2023-01-01"));
+        assert_eq!(ctx.anthropic_api_version(), AnthropicVersion::Legacy);
+
+        ctx.set_anthropic_version(Some("2023-06-01"));
+        assert_eq!(ctx.anthropic_api_version(), AnthropicVersion::Current);
+
+        ctx.set_anthropic_version(Some("not-a-date"));
+        assert_eq!(ctx.anthropic_api_version(), AnthropicVersion::Current);
+    }
 }
@@ -42,6 +42,10 @@ pub enum ProcessError {
     #[error("参数注入错误: {0}")]
     InjectionError(String),
 
+    /// 请求内容违反防护策略
+    #[error("请求被防护策略 {policy} 拒绝 (规则: {rule})")]
+    PolicyViolation { policy: String, rule: String },
+
     /// 凭证池错误
     #[error("凭证池错误: {0}")]
     CredentialPoolError(String),
@@ -71,6 +75,7 @@ impl ProcessError {
             ProcessError::StreamIdleTimeout { .. } => 408,
             ProcessError::PluginError { .. } => 500,
             ProcessError::InjectionError(_) => 400,
+            ProcessError::PolicyViolation { .. } => 403,
             ProcessError::CredentialPoolError(_) => 503,
             ProcessError::ConfigError(_) => 500,
             ProcessError::InternalError(_) => 500,
@@ -120,6 +125,7 @@ impl ProcessError {
             ProcessError::StreamIdleTimeout { .. } => "stream_idle_timeout",
             ProcessError::PluginError { .. } => "plugin_error",
             ProcessError::InjectionError(_) => "injection_error",
+            ProcessError::PolicyViolation { .. } => "policy_violation",
             ProcessError::CredentialPoolError(_) => "credential_pool_error",
             ProcessError::ConfigError(_) => "config_error",
             ProcessError::InternalError(_) => "internal_error",
@@ -169,6 +175,14 @@ mod tests {
             ProcessError::Timeout { timeout_ms: 5000 }.status_code(),
             408
         );
+        assert_eq!(
+            ProcessError::PolicyViolation {
+                policy: "strict".to_string(),
+                rule: "aws_access_key".to_string()
+            }
+            .status_code(),
+            403
+        );
         assert_eq!(ProcessError::Cancelled.status_code(), 499);
     }
 
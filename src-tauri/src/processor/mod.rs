@@ -6,28 +6,34 @@
 //!
 //! 请求处理流程：
 //! 1. 认证 (AuthStep)
-//! 2. 参数注入 (InjectionStep)
-//! 3. 路由解析 (RoutingStep)
-//! 4. 插件前置钩子 (PluginPreStep)
-//! 5. Provider 调用 (ProviderStep) - 包含重试和故障转移
-//! 6. 插件后置钩子 (PluginPostStep)
-//! 7. 统计记录 (TelemetryStep)
+//! 2. 内容防护 (Guardrails，可选)
+//! 3. 参数注入 (InjectionStep)
+//! 4. 路由解析 (RoutingStep)
+//! 5. 插件前置钩子 (PluginPreStep)
+//! 6. Provider 调用 (ProviderStep) - 包含重试和故障转移
+//! 7. 插件后置钩子 (PluginPostStep)
+//! 8. 统计记录 (TelemetryStep)
 
 mod context;
 mod error;
+pub mod snapshot;
 mod steps;
 
 pub use context::RequestContext;
 pub use error::ProcessError;
+pub use snapshot::{ConfigSnapshot, SharedConfigSnapshot};
 pub use steps::{
     AuthStep, InjectionStep, PipelineStep, PluginPostStep, PluginPreStep, ProviderStep,
     RoutingStep, TelemetryStep,
 };
 
-use crate::injection::Injector;
+use crate::config::{CompactionConfig, ResponseValidationConfig, StreamWatchdogConfig};
+use crate::guardrails::{GuardrailRedaction, Guardrails};
 use crate::plugin::PluginManager;
-use crate::resilience::{Failover, Retrier, TimeoutController};
-use crate::router::{ModelMapper, Router};
+use crate::postprocess::ResponseProcessor;
+use crate::providers::mock::MockProvider;
+use crate::resilience::{ChaosInjector, Failover, Retrier, TimeoutController};
+use crate::router::{MaxTokensAdjustment, ModelContextWindows, ModelTokenLimits};
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::telemetry::{StatsAggregator, TokenTracker};
 use parking_lot::RwLock as ParkingLotRwLock;
@@ -38,12 +44,8 @@ use tokio::sync::RwLock;
 ///
 /// 集成所有功能模块，提供完整的请求处理管道
 pub struct RequestProcessor {
-    /// 路由器
-    pub router: Arc<RwLock<Router>>,
-    /// 模型映射器
-    pub mapper: Arc<RwLock<ModelMapper>>,
-    /// 参数注入器
-    pub injector: Arc<RwLock<Injector>>,
+    /// 配置快照（路由器、模型映射器、注入器等，热重载时整体替换）
+    pub snapshot: SharedConfigSnapshot,
     /// 重试器
     pub retrier: Arc<Retrier>,
     /// 故障转移器
@@ -60,14 +62,30 @@ pub struct RequestProcessor {
     pub pool_service: Arc<ProviderPoolService>,
     /// 热重载协调锁（避免配置更新期间请求读取不一致的配置）
     pub reload_lock: Arc<RwLock<()>>,
+    /// 模型输出 Token 上限
+    pub token_limits: Arc<RwLock<ModelTokenLimits>>,
+    /// 模型上下文窗口预检
+    pub context_windows: Arc<RwLock<ModelContextWindows>>,
+    /// 请求内容防护策略
+    pub guardrails: Arc<RwLock<Guardrails>>,
+    /// 响应后处理规则
+    pub response_processor: Arc<RwLock<ResponseProcessor>>,
+    /// 内置 mock Provider
+    pub mock_provider: Arc<RwLock<MockProvider>>,
+    /// 故障注入器
+    pub chaos: Arc<RwLock<ChaosInjector>>,
+    /// 对话压缩配置
+    pub compaction: Arc<RwLock<CompactionConfig>>,
+    /// 上游流式响应看门狗配置
+    pub stream_watchdog: Arc<RwLock<StreamWatchdogConfig>>,
+    /// 上游响应结构校验配置
+    pub response_validation: Arc<RwLock<ResponseValidationConfig>>,
 }
 
 impl RequestProcessor {
     /// 创建新的请求处理器
     pub fn new(
-        router: Arc<RwLock<Router>>,
-        mapper: Arc<RwLock<ModelMapper>>,
-        injector: Arc<RwLock<Injector>>,
+        snapshot: SharedConfigSnapshot,
         retrier: Arc<Retrier>,
         failover: Arc<Failover>,
         timeout: Arc<TimeoutController>,
@@ -77,9 +95,7 @@ impl RequestProcessor {
         pool_service: Arc<ProviderPoolService>,
     ) -> Self {
         Self {
-            router,
-            mapper,
-            injector,
+            snapshot,
             retrier,
             failover,
             timeout,
@@ -88,15 +104,22 @@ impl RequestProcessor {
             tokens,
             pool_service,
             reload_lock: Arc::new(RwLock::new(())),
+            token_limits: Arc::new(RwLock::new(ModelTokenLimits::new())),
+            context_windows: Arc::new(RwLock::new(ModelContextWindows::new())),
+            guardrails: Arc::new(RwLock::new(Guardrails::new())),
+            response_processor: Arc::new(RwLock::new(ResponseProcessor::new())),
+            mock_provider: Arc::new(RwLock::new(MockProvider::default())),
+            chaos: Arc::new(RwLock::new(ChaosInjector::new())),
+            compaction: Arc::new(RwLock::new(CompactionConfig::default())),
+            stream_watchdog: Arc::new(RwLock::new(StreamWatchdogConfig::default())),
+            response_validation: Arc::new(RwLock::new(ResponseValidationConfig::default())),
         }
     }
 
     /// 使用默认配置创建请求处理器
     pub fn with_defaults(pool_service: Arc<ProviderPoolService>) -> Self {
         Self {
-            router: Arc::new(RwLock::new(Self::create_router_with_defaults())),
-            mapper: Arc::new(RwLock::new(ModelMapper::new())),
-            injector: Arc::new(RwLock::new(Injector::new())),
+            snapshot: Self::create_default_snapshot(),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
             timeout: Arc::new(TimeoutController::with_defaults()),
@@ -105,21 +128,62 @@ impl RequestProcessor {
             tokens: Arc::new(ParkingLotRwLock::new(TokenTracker::with_defaults())),
             pool_service,
             reload_lock: Arc::new(RwLock::new(())),
+            token_limits: Arc::new(RwLock::new(ModelTokenLimits::new())),
+            context_windows: Arc::new(RwLock::new(ModelContextWindows::new())),
+            guardrails: Arc::new(RwLock::new(Guardrails::new())),
+            response_processor: Arc::new(RwLock::new(ResponseProcessor::new())),
+            mock_provider: Arc::new(RwLock::new(MockProvider::default())),
+            chaos: Arc::new(RwLock::new(ChaosInjector::new())),
+            compaction: Arc::new(RwLock::new(CompactionConfig::default())),
+            stream_watchdog: Arc::new(RwLock::new(StreamWatchdogConfig::default())),
+            response_validation: Arc::new(RwLock::new(ResponseValidationConfig::default())),
         }
     }
 
-    /// 创建带默认路由规则的路由器
+    /// 创建默认配置快照
     ///
     /// 注意：不再添加硬编码的路由规则，让用户设置的默认 Provider 生效
     /// 用户可以通过 UI 或配置文件自定义路由规则
-    fn create_router_with_defaults() -> Router {
-        // 创建空的路由器，默认 Provider 会在启动时从配置中设置
+    fn create_default_snapshot() -> SharedConfigSnapshot {
+        // 路由器为空，默认 Provider 会在启动时从配置中设置
         // 不要硬编码任何 Provider，避免与用户配置冲突
-        let router = Router::new_empty();
-
         tracing::info!("[ROUTER] 初始化空路由器，等待从配置加载默认 Provider");
 
-        router
+        snapshot::shared(ConfigSnapshot::default())
+    }
+
+    /// 当前配置快照
+    ///
+    /// 一次原子加载；热重载不会影响已取得的快照。
+    pub fn snapshot(&self) -> Arc<ConfigSnapshot> {
+        self.snapshot.load_full()
+    }
+
+    /// 解析请求的默认 Provider
+    ///
+    /// 优先级：命名空间 > API Key > 客户端端点配置 > 全局默认 Provider
+    pub fn resolve_default_provider(
+        &self,
+        namespace: Option<&str>,
+        api_key: Option<&str>,
+        client_key: Option<&str>,
+    ) -> String {
+        self.snapshot
+            .load()
+            .resolve_default_provider(namespace, api_key, client_key)
+            .to_string()
+    }
+
+    /// 命名空间或 API Key 显式配置的默认 Provider（不回退到全局默认）
+    pub fn scoped_default_provider(
+        &self,
+        namespace: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Option<String> {
+        self.snapshot
+            .load()
+            .scoped_default_provider(namespace, api_key)
+            .map(str::to_string)
     }
 
     /// 使用共享的统计和 Token 追踪器创建请求处理器
@@ -132,9 +196,7 @@ impl RequestProcessor {
         tokens: Arc<ParkingLotRwLock<TokenTracker>>,
     ) -> Self {
         Self {
-            router: Arc::new(RwLock::new(Self::create_router_with_defaults())),
-            mapper: Arc::new(RwLock::new(ModelMapper::new())),
-            injector: Arc::new(RwLock::new(Injector::new())),
+            snapshot: Self::create_default_snapshot(),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
             timeout: Arc::new(TimeoutController::with_defaults()),
@@ -143,6 +205,15 @@ impl RequestProcessor {
             tokens,
             pool_service,
             reload_lock: Arc::new(RwLock::new(())),
+            token_limits: Arc::new(RwLock::new(ModelTokenLimits::new())),
+            context_windows: Arc::new(RwLock::new(ModelContextWindows::new())),
+            guardrails: Arc::new(RwLock::new(Guardrails::new())),
+            response_processor: Arc::new(RwLock::new(ResponseProcessor::new())),
+            mock_provider: Arc::new(RwLock::new(MockProvider::default())),
+            chaos: Arc::new(RwLock::new(ChaosInjector::new())),
+            compaction: Arc::new(RwLock::new(CompactionConfig::default())),
+            stream_watchdog: Arc::new(RwLock::new(StreamWatchdogConfig::default())),
+            response_validation: Arc::new(RwLock::new(ResponseValidationConfig::default())),
         }
     }
 
@@ -156,8 +227,7 @@ impl RequestProcessor {
     /// # Returns
     /// 解析后的实际模型名称
     pub async fn resolve_model(&self, model: &str) -> String {
-        let mapper = self.mapper.read().await;
-        mapper.resolve(model)
+        self.snapshot.load().mapper.resolve(model)
     }
 
     /// 解析模型别名并更新请求上下文
@@ -191,8 +261,7 @@ impl RequestProcessor {
     /// # Returns
     /// 选择的 Provider 类型（如果设置了）和是否使用默认 Provider
     pub async fn route_model(&self, model: &str) -> (Option<crate::ProviderType>, bool) {
-        let router = self.router.read().await;
-        let result = router.route(model);
+        let result = self.snapshot.load().router.route(model);
         (result.provider, result.is_default)
     }
 
@@ -226,6 +295,67 @@ impl RequestProcessor {
         provider
     }
 
+    /// 按目标模型的输出上限钳制 `max_tokens`
+    ///
+    /// 先解析模型别名再查询上限；发生调整时返回调整记录，由调用方写入响应头。
+    pub async fn clamp_max_tokens(
+        &self,
+        model: &str,
+        max_tokens: &mut Option<u32>,
+    ) -> Option<MaxTokensAdjustment> {
+        let resolved = self.resolve_model(model).await;
+        let adjustment = self.token_limits.read().await.clamp(&resolved, max_tokens);
+        if let Some(adj) = &adjustment {
+            tracing::warn!(
+                "[MAX_TOKENS] model={} max_tokens {} 超过模型上限，已调整为 {}",
+                resolved,
+                adj.requested,
+                adj.adjusted
+            );
+        }
+        adjustment
+    }
+
+    /// 按入站 API Key 对应的防护策略检查请求内容
+    ///
+    /// `block` 策略命中时返回 `PolicyViolation`；`redact` 策略命中时改写 `payload` 并返回脱敏记录。
+    /// 日志只记录策略和规则名称，不记录命中内容和 API Key。
+    pub async fn apply_guardrails(
+        &self,
+        ctx: &RequestContext,
+        api_key: Option<&str>,
+        payload: &mut serde_json::Value,
+    ) -> Result<Option<GuardrailRedaction>, ProcessError> {
+        let guardrails = self.guardrails.read().await;
+        if !guardrails.is_enabled() {
+            return Ok(None);
+        }
+        match guardrails.check(api_key, payload) {
+            Ok(Some(redaction)) => {
+                tracing::warn!(
+                    "[GUARDRAIL] request_id={} policy={} 已脱敏: {}",
+                    ctx.request_id,
+                    redaction.policy,
+                    redaction.rules.join(",")
+                );
+                Ok(Some(redaction))
+            }
+            Ok(None) => Ok(None),
+            Err(violation) => {
+                tracing::warn!(
+                    "[GUARDRAIL] request_id={} policy={} rule={} 请求已拦截",
+                    ctx.request_id,
+                    violation.policy,
+                    violation.rule
+                );
+                Err(ProcessError::PolicyViolation {
+                    policy: violation.policy,
+                    rule: violation.rule,
+                })
+            }
+        }
+    }
+
     /// 执行完整的路由解析流程
     ///
     /// 包括模型别名解析和 Provider 选择
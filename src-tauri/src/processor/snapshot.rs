@@ -0,0 +1,224 @@
+//! 配置快照
+//!
+//! 每个请求都要读取的路由配置（默认 Provider、路由器、模型映射器、注入器、端点 Provider、
+//! 命名空间 / API Key 默认 Provider）
+//! 组成一个不可变快照，通过 `ArcSwap` 共享：请求只需一次原子加载，
+//! 热重载或运行时修改时构建新快照整体替换，正在处理的请求继续使用旧快照。
+
+use crate::config::{Config, EndpointProvidersConfig};
+use crate::injection::Injector;
+use crate::router::{ModelMapper, Router};
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 可原子替换的共享配置快照
+pub type SharedConfigSnapshot = Arc<ArcSwap<ConfigSnapshot>>;
+
+/// 请求热路径使用的不可变配置快照
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSnapshot {
+    /// 默认 Provider（可能是自定义 Provider ID）
+    pub default_provider: String,
+    /// 路由器
+    pub router: Router,
+    /// 模型映射器
+    pub mapper: ModelMapper,
+    /// 参数注入器
+    pub injector: Injector,
+    /// 是否启用参数注入
+    pub injection_enabled: bool,
+    /// 端点 Provider 配置
+    pub endpoint_providers: EndpointProvidersConfig,
+    /// 命名空间默认 Provider（路由选择器 -> Provider）
+    pub namespace_default_providers: HashMap<String, String>,
+    /// API Key 默认 Provider（API Key -> Provider）
+    pub api_key_default_providers: HashMap<String, String>,
+}
+
+impl ConfigSnapshot {
+    /// 从配置构建快照
+    pub fn from_config(config: &Config) -> Self {
+        let mut router = Router::new_empty();
+        match config
+            .routing
+            .default_provider
+            .parse::<crate::ProviderType>()
+        {
+            Ok(provider_type) => router.set_default_provider(provider_type),
+            // 自定义 Provider ID：路由器保持空状态，请求直接使用 provider_id 查找凭证
+            Err(_) => tracing::warn!(
+                "[SNAPSHOT] 默认 Provider '{}' 不是标准 Provider 类型，可能是自定义 Provider ID",
+                config.routing.default_provider
+            ),
+        }
+
+        let mut mapper = ModelMapper::new();
+        for (alias, model) in &config.routing.model_aliases {
+            mapper.add_alias(alias, model);
+        }
+        if let Err(e) = mapper.set_rules(&config.routing.model_alias_rules) {
+            tracing::warn!("[SNAPSHOT] 模型别名规则无效，已忽略: {}", e);
+        }
+
+        Self {
+            default_provider: config.default_provider.clone(),
+            router,
+            mapper,
+            injector: Injector::with_rules(
+                config
+                    .injection
+                    .rules
+                    .iter()
+                    .map(|r| r.clone().into())
+                    .collect(),
+            ),
+            injection_enabled: config.injection.enabled,
+            endpoint_providers: config.endpoint_providers.clone(),
+            namespace_default_providers: config.routing.namespace_default_providers.clone(),
+            api_key_default_providers: config
+                .routing
+                .api_key_default_providers
+                .iter()
+                .map(|entry| (entry.api_key.clone(), entry.provider.clone()))
+                .collect(),
+        }
+    }
+
+    /// 命名空间或 API Key 配置的默认 Provider（命名空间优先），均未配置时为 None
+    pub fn scoped_default_provider(
+        &self,
+        namespace: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Option<&str> {
+        namespace
+            .and_then(|namespace| self.namespace_default_providers.get(namespace))
+            .or_else(|| api_key.and_then(|key| self.api_key_default_providers.get(key)))
+            .map(String::as_str)
+    }
+
+    /// 解析默认 Provider
+    ///
+    /// 优先级：命名空间 > API Key > 客户端端点配置 > 全局默认 Provider
+    pub fn resolve_default_provider(
+        &self,
+        namespace: Option<&str>,
+        api_key: Option<&str>,
+        client_key: Option<&str>,
+    ) -> &str {
+        self.scoped_default_provider(namespace, api_key)
+            .or_else(|| {
+                client_key
+                    .and_then(|key| self.endpoint_providers.get_provider(key))
+                    .map(String::as_str)
+            })
+            .unwrap_or(&self.default_provider)
+    }
+
+    /// 切换默认 Provider（标准 Provider 类型同时更新路由器）
+    pub fn with_default_provider(&self, provider: &str) -> Self {
+        let mut next = self.clone();
+        next.default_provider = provider.to_string();
+        if let Ok(provider_type) = provider.parse::<crate::ProviderType>() {
+            next.router.set_default_provider(provider_type);
+        }
+        next
+    }
+}
+
+/// 创建共享快照
+pub fn shared(snapshot: ConfigSnapshot) -> SharedConfigSnapshot {
+    Arc::new(ArcSwap::from_pointee(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProviderType;
+
+    #[test]
+    fn test_from_config() {
+        let mut config = Config::default();
+        config.default_provider = "gemini".to_string();
+        config.routing.default_provider = "gemini".to_string();
+        config
+            .routing
+            .model_aliases
+            .insert("gpt-4".to_string(), "claude-sonnet-4-5".to_string());
+        config.injection.enabled = true;
+
+        let snapshot = ConfigSnapshot::from_config(&config);
+        assert_eq!(snapshot.default_provider, "gemini");
+        assert_eq!(
+            snapshot.router.default_provider(),
+            Some(ProviderType::Gemini)
+        );
+        assert_eq!(snapshot.mapper.resolve("gpt-4"), "claude-sonnet-4-5");
+        assert!(snapshot.injection_enabled);
+
+        // 自定义 Provider ID 不设置路由器默认值
+        config.routing.default_provider = "custom-123".to_string();
+        let snapshot = ConfigSnapshot::from_config(&config);
+        assert_eq!(snapshot.router.default_provider(), None);
+    }
+
+    #[test]
+    fn test_resolve_default_provider_precedence() {
+        let mut config = Config::default();
+        config.default_provider = "kiro".to_string();
+        config.endpoint_providers.cursor = Some("gemini".to_string());
+        config
+            .routing
+            .namespace_default_providers
+            .insert("team-a".to_string(), "claude".to_string());
+        config
+            .routing
+            .api_key_default_providers
+            .push(crate::config::ApiKeyDefaultProvider {
+                api_key: "sk-team-b".to_string(),
+                provider: "openai".to_string(),
+            });
+        let snapshot = ConfigSnapshot::from_config(&config);
+
+        let resolve = |namespace, api_key, client_key| {
+            snapshot
+                .resolve_default_provider(namespace, api_key, client_key)
+                .to_string()
+        };
+        assert_eq!(
+            resolve(Some("team-a"), Some("sk-team-b"), Some("cursor")),
+            "claude"
+        );
+        assert_eq!(
+            resolve(Some("unknown"), Some("sk-team-b"), Some("cursor")),
+            "openai"
+        );
+        assert_eq!(resolve(None, Some("sk-other"), Some("cursor")), "gemini");
+        assert_eq!(resolve(None, None, Some("codex")), "kiro");
+        assert_eq!(
+            snapshot.scoped_default_provider(Some("unknown"), None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_swap_keeps_loaded_snapshot() {
+        let snapshot = shared(ConfigSnapshot::default());
+        let before = snapshot.load_full();
+
+        snapshot.rcu(|current| current.with_default_provider("claude"));
+
+        // 已加载的快照不受替换影响
+        assert_eq!(before.default_provider, "");
+        assert_eq!(before.router.default_provider(), None);
+        let after = snapshot.load_full();
+        assert_eq!(after.default_provider, "claude");
+        assert_eq!(after.router.default_provider(), Some(ProviderType::Claude));
+
+        // 自定义 Provider ID 只更新默认 Provider
+        snapshot.rcu(|current| current.with_default_provider("custom-123"));
+        let custom = snapshot.load_full();
+        assert_eq!(custom.default_provider, "custom-123");
+        assert_eq!(custom.router.default_provider(), Some(ProviderType::Claude));
+    }
+}
@@ -3,7 +3,7 @@
 //! 根据配置的规则注入请求参数
 
 use super::traits::{PipelineStep, StepError};
-use crate::injection::Injector;
+use crate::injection::{InjectionContext, Injector};
 use crate::processor::RequestContext;
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -51,7 +51,11 @@ impl PipelineStep for InjectionStep {
         }
 
         let injector = self.injector.read().await;
-        let result = injector.inject(&ctx.resolved_model, payload);
+        let mut injection_ctx = InjectionContext::new(&ctx.resolved_model);
+        if let Some(provider) = &ctx.provider {
+            injection_ctx = injection_ctx.with_provider(provider.to_string());
+        }
+        let result = injector.inject_with_context(&injection_ctx, payload);
 
         if result.has_injections() {
             tracing::info!(
@@ -66,7 +70,9 @@ impl PipelineStep for InjectionStep {
                 "injection_result",
                 serde_json::json!({
                     "applied_rules": result.applied_rules,
-                    "injected_params": result.injected_params
+                    "injected_params": result.injected_params,
+                    "removed_params": result.removed_params,
+                    "capped_params": result.capped_params
                 }),
             );
         }
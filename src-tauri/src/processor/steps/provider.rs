@@ -5,7 +5,7 @@
 use super::traits::{PipelineStep, StepError};
 use crate::processor::RequestContext;
 use crate::resilience::{
-    Failover, FailoverConfig, FailoverManager, Retrier, RetryConfig, TimeoutConfig,
+    Failover, FailoverConfig, FailoverManager, Retrier, RetryConfig, RetryHint, TimeoutConfig,
     TimeoutController, TimeoutError,
 };
 use crate::services::provider_pool_service::ProviderPoolService;
@@ -13,6 +13,7 @@ use crate::ProviderType;
 use async_trait::async_trait;
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Provider 调用结果
 #[derive(Debug, Clone)]
@@ -38,6 +39,10 @@ pub struct ProviderCallError {
     pub retryable: bool,
     /// 是否应触发故障转移
     pub should_failover: bool,
+    /// 上游给出的重试等待时间（Retry-After / retryDelay）
+    pub retry_after: Option<Duration>,
+    /// 使用的凭证 ID
+    pub credential_id: Option<String>,
 }
 
 impl ProviderCallError {
@@ -48,6 +53,8 @@ impl ProviderCallError {
             status_code,
             retryable: true,
             should_failover: false,
+            retry_after: None,
+            credential_id: None,
         }
     }
 
@@ -58,6 +65,8 @@ impl ProviderCallError {
             status_code,
             retryable: false,
             should_failover: true,
+            retry_after: None,
+            credential_id: None,
         }
     }
 
@@ -68,9 +77,27 @@ impl ProviderCallError {
             status_code,
             retryable: false,
             should_failover: false,
+            retry_after: None,
+            credential_id: None,
         }
     }
 
+    /// 从上游响应提取重试等待时间（Retry-After 响应头或响应体中的 retryDelay）
+    pub fn with_response_hint(
+        mut self,
+        headers: Option<&reqwest::header::HeaderMap>,
+        body: Option<&serde_json::Value>,
+    ) -> Self {
+        self.retry_after = RetryHint::from_response(headers, body).retry_after;
+        self
+    }
+
+    /// 设置使用的凭证 ID
+    pub fn with_credential(mut self, credential_id: impl Into<String>) -> Self {
+        self.credential_id = Some(credential_id.into());
+        self
+    }
+
     /// 检查是否为配额超限错误
     pub fn is_quota_exceeded(&self) -> bool {
         Failover::is_quota_exceeded(self.status_code, &self.message)
@@ -178,7 +205,10 @@ impl ProviderStep {
             attempts += 1;
 
             match operation().await {
-                Ok(result) => return Ok(result),
+                Ok(result) => {
+                    self.record_success(&result);
+                    return Ok(result);
+                }
                 Err(err) => {
                     // 增加重试计数
                     ctx.increment_retry();
@@ -205,23 +235,51 @@ impl ProviderStep {
 
                     let should_failover = err.should_failover || err.is_quota_exceeded();
 
-                    if !should_retry || attempts > max_retries {
+                    let delay = self.retry_delay(attempts - 1, &err);
+                    if !should_retry || attempts > max_retries || delay.is_none() {
                         return Err(ProviderCallError {
-                            message: err.message,
-                            status_code: err.status_code,
                             retryable: false,
-                            should_failover,
+                            should_failover: should_failover || delay.is_none(),
+                            ..err
                         });
                     }
 
                     // 等待退避时间
-                    let delay = self.retrier.backoff_delay(attempts - 1);
-                    tokio::time::sleep(delay).await;
+                    if let Some(delay) = delay {
+                        tokio::time::sleep(delay).await;
+                    }
                 }
             }
         }
     }
 
+    /// 计算第 N 次重试的退避时间
+    ///
+    /// 429 错误计入凭证的限流历史，退避时间参考上游给出的等待时间和凭证的限流记录；
+    /// 需要等待的时间超过最大延迟时返回 None（不再重试，改为故障转移）。
+    fn retry_delay(&self, attempt: u32, err: &ProviderCallError) -> Option<Duration> {
+        let credential_id = err.credential_id.as_deref();
+        if let (Some(429), Some(id)) = (err.status_code, credential_id) {
+            self.retrier.record_rate_limit(id, err.retry_after);
+        }
+        let hint = self.retrier.hint_for(credential_id, err.retry_after);
+        let delay = self.retrier.adaptive_backoff_delay(attempt, &hint);
+        if delay.is_none() {
+            tracing::warn!(
+                "[RETRY] 凭证 {:?} 需要等待的时间超过最大重试延迟，不再重试",
+                credential_id
+            );
+        }
+        delay
+    }
+
+    /// 请求成功后清除凭证的限流历史
+    fn record_success(&self, result: &ProviderCallResult) {
+        if let Some(id) = &result.credential_id {
+            self.retrier.record_success(id);
+        }
+    }
+
     /// 带超时执行 Provider 调用
     ///
     /// 使用 TimeoutController 包装 Provider 调用，自动处理超时
@@ -258,12 +316,10 @@ impl ProviderStep {
                     timeout_ms
                 );
 
-                Err(ProviderCallError {
-                    message: timeout_err.to_string(),
-                    status_code: Some(408),
-                    retryable: true,
-                    should_failover: false,
-                })
+                Err(ProviderCallError::retryable(
+                    timeout_err.to_string(),
+                    Some(408),
+                ))
             }
         }
     }
@@ -365,7 +421,10 @@ impl ProviderStep {
                     .await;
 
                 match call_result {
-                    Ok(result) => break Ok(result),
+                    Ok(result) => {
+                        self.record_success(&result);
+                        break Ok(result);
+                    }
                     Err(err) => {
                         ctx.increment_retry();
 
@@ -391,18 +450,19 @@ impl ProviderStep {
 
                         let should_failover = err.should_failover || err.is_quota_exceeded();
 
-                        if !should_retry || retry_attempts > max_retries {
+                        let delay = self.retry_delay(retry_attempts - 1, &err);
+                        if !should_retry || retry_attempts > max_retries || delay.is_none() {
                             break Err(ProviderCallError {
-                                message: err.message,
-                                status_code: err.status_code,
                                 retryable: false,
-                                should_failover,
+                                should_failover: should_failover || delay.is_none(),
+                                ..err
                             });
                         }
 
                         // 等待退避时间
-                        let delay = self.retrier.backoff_delay(retry_attempts - 1);
-                        tokio::time::sleep(delay).await;
+                        if let Some(delay) = delay {
+                            tokio::time::sleep(delay).await;
+                        }
                     }
                 }
             };
@@ -541,6 +601,61 @@ mod tests {
         assert_eq!(step.timeout().config().request_timeout_ms, 60000);
     }
 
+    #[tokio::test]
+    async fn test_execute_with_retry_respects_retry_after() {
+        let pool_service = Arc::new(ProviderPoolService::new());
+        let step = ProviderStep::with_defaults(pool_service);
+        let mut ctx = RequestContext::new("claude-sonnet-4-5".to_string());
+        let calls = std::sync::atomic::AtomicU32::new(0);
+
+        let result = step
+            .execute_with_retry(&mut ctx, || {
+                let n = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async move {
+                    if n == 0 {
+                        let mut headers = reqwest::header::HeaderMap::new();
+                        headers.insert("retry-after", "0.01".parse().unwrap());
+                        Err(ProviderCallError::retryable("rate limited", Some(429))
+                            .with_response_hint(Some(&headers), None)
+                            .with_credential("cred-1"))
+                    } else {
+                        Ok(ProviderCallResult {
+                            response: serde_json::json!({}),
+                            status_code: 200,
+                            latency_ms: 1,
+                            credential_id: Some("cred-1".to_string()),
+                        })
+                    }
+                }
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        // 成功后清除限流历史
+        assert!(step
+            .retrier()
+            .rate_limit_tracker()
+            .get_rate_limit("cred-1")
+            .is_none());
+
+        // 上游要求的等待时间超过最大延迟时不再重试，改为故障转移
+        let err = step
+            .execute_with_retry(&mut ctx, || async {
+                let mut err = ProviderCallError::retryable("rate limited", Some(429))
+                    .with_credential("cred-2");
+                err.retry_after = Some(Duration::from_secs(3600));
+                Err(err)
+            })
+            .await
+            .unwrap_err();
+        assert!(err.should_failover);
+        assert!(!err.retryable);
+        assert!(step
+            .retrier()
+            .rate_limit_tracker()
+            .is_rate_limited("cred-2"));
+    }
+
     #[test]
     fn test_provider_call_error_retryable() {
         let err = ProviderCallError::retryable("Connection timeout", Some(408));
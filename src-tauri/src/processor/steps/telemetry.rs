@@ -66,6 +66,7 @@ impl TelemetryStep {
 
         // 设置重试次数
         log.retry_count = ctx.retry_count;
+        log.user_id = ctx.user_id.clone();
 
         // 使用 parking_lot::RwLock 的同步写锁
         let stats = self.stats.write();
@@ -95,7 +96,8 @@ impl TelemetryStep {
                 output_tokens.unwrap_or(0),
                 source,
             )
-            .with_request_id(ctx.request_id.clone());
+            .with_request_id(ctx.request_id.clone())
+            .with_user_id(ctx.user_id.clone());
 
             // 使用 parking_lot::RwLock 的同步写锁
             let tokens = self.tokens.write();
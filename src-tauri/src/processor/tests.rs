@@ -10,9 +10,7 @@ fn test_request_processor_new() {
     let processor = RequestProcessor::with_defaults(pool_service);
 
     // 验证所有组件都已初始化
-    assert!(Arc::strong_count(&processor.router) >= 1);
-    assert!(Arc::strong_count(&processor.mapper) >= 1);
-    assert!(Arc::strong_count(&processor.injector) >= 1);
+    assert!(Arc::strong_count(&processor.snapshot) >= 1);
     assert!(Arc::strong_count(&processor.retrier) >= 1);
     assert!(Arc::strong_count(&processor.failover) >= 1);
     assert!(Arc::strong_count(&processor.timeout) >= 1);
@@ -27,25 +25,13 @@ async fn test_request_processor_components() {
     let pool_service = Arc::new(ProviderPoolService::new());
     let processor = RequestProcessor::with_defaults(pool_service);
 
-    // 验证路由器可以正常使用
-    {
-        let router = processor.router.read().await;
-        // 默认使用 Kiro
-        assert_eq!(router.default_provider(), Some(ProviderType::Kiro));
-    }
-
-    // 验证映射器可以正常使用
-    {
-        let mapper = processor.mapper.read().await;
-        // resolve 返回原值如果没有别名
-        assert_eq!(mapper.resolve("unknown"), "unknown");
-    }
-
-    // 验证注入器可以正常使用
-    {
-        let injector = processor.injector.read().await;
-        assert!(injector.rules().is_empty());
-    }
+    // 验证路由器、映射器和注入器可以正常使用
+    let snapshot = processor.snapshot();
+    // 默认使用 Kiro
+    assert_eq!(snapshot.router.default_provider(), Some(ProviderType::Kiro));
+    // resolve 返回原值如果没有别名
+    assert_eq!(snapshot.mapper.resolve("unknown"), "unknown");
+    assert!(snapshot.injector.rules().is_empty());
 
     // 验证统计聚合器可以正常使用（使用 parking_lot::RwLock）
     {
@@ -68,11 +54,12 @@ async fn test_resolve_model_with_alias() {
     let processor = RequestProcessor::with_defaults(pool_service);
 
     // 添加别名映射
-    {
-        let mut mapper = processor.mapper.write().await;
-        mapper.add_alias("gpt-4", "claude-sonnet-4-5");
-        mapper.add_alias("gpt-3.5-turbo", "claude-3-haiku");
-    }
+    processor.snapshot.rcu(|current| {
+        let mut next = ConfigSnapshot::clone(current);
+        next.mapper.add_alias("gpt-4", "claude-sonnet-4-5");
+        next.mapper.add_alias("gpt-3.5-turbo", "claude-3-haiku");
+        next
+    });
 
     // 测试别名解析
     let resolved = processor.resolve_model("gpt-4").await;
@@ -86,16 +73,42 @@ async fn test_resolve_model_with_alias() {
     assert_eq!(resolved, "claude-sonnet-4-5");
 }
 
+#[tokio::test]
+async fn test_clamp_max_tokens_uses_resolved_model() {
+    let pool_service = Arc::new(ProviderPoolService::new());
+    let processor = RequestProcessor::with_defaults(pool_service);
+
+    processor.snapshot.rcu(|current| {
+        let mut next = ConfigSnapshot::clone(current);
+        next.mapper.add_alias("gpt-4o", "claude-3-5-haiku");
+        next
+    });
+
+    // gpt-4o 别名指向 claude-3-5-haiku，按后者的上限钳制
+    let mut max_tokens = Some(16000);
+    let adjustment = processor.clamp_max_tokens("gpt-4o", &mut max_tokens).await;
+    assert_eq!(max_tokens, Some(8192));
+    assert_eq!(adjustment.map(|a| a.requested), Some(16000));
+
+    let mut max_tokens = Some(4096);
+    assert!(processor
+        .clamp_max_tokens("gpt-4o", &mut max_tokens)
+        .await
+        .is_none());
+    assert_eq!(max_tokens, Some(4096));
+}
+
 #[tokio::test]
 async fn test_resolve_model_for_context() {
     let pool_service = Arc::new(ProviderPoolService::new());
     let processor = RequestProcessor::with_defaults(pool_service);
 
     // 添加别名映射
-    {
-        let mut mapper = processor.mapper.write().await;
-        mapper.add_alias("gpt-4", "claude-sonnet-4-5");
-    }
+    processor.snapshot.rcu(|current| {
+        let mut next = ConfigSnapshot::clone(current);
+        next.mapper.add_alias("gpt-4", "claude-sonnet-4-5");
+        next
+    });
 
     // 创建请求上下文
     let mut ctx = RequestContext::new("gpt-4".to_string());
@@ -149,10 +162,11 @@ async fn test_resolve_and_route() {
     let processor = RequestProcessor::with_defaults(pool_service);
 
     // 添加别名映射
-    {
-        let mut mapper = processor.mapper.write().await;
-        mapper.add_alias("gpt-4", "claude-sonnet-4-5");
-    }
+    processor.snapshot.rcu(|current| {
+        let mut next = ConfigSnapshot::clone(current);
+        next.mapper.add_alias("gpt-4", "claude-sonnet-4-5");
+        next
+    });
 
     // 测试完整的解析和路由流程
     let mut ctx = RequestContext::new("gpt-4".to_string());
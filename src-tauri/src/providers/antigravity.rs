@@ -372,7 +372,7 @@ impl Default for AntigravityProvider {
         Self {
             credentials: AntigravityCredentials::default(),
             project_id: None,
-            client: Client::builder()
+            client: super::tls::client_builder(crate::ProviderType::Antigravity)
                 .timeout(std::time::Duration::from_secs(120))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
@@ -2095,8 +2095,8 @@ impl CredentialProvider for AntigravityProvider {
         AntigravityProvider::refresh_token(self).await
     }
 
-    fn get_access_token(&self) -> Option<&str> {
-        self.credentials.access_token.as_deref()
+    fn get_access_token(&self) -> Option<String> {
+        self.credentials.access_token.clone()
     }
 
     fn provider_type(&self) -> &'static str {
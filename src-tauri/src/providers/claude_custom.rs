@@ -1,6 +1,10 @@
 //! Claude Custom Provider (自定义 Claude API)
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
+use crate::middleware::{HeaderPassthroughExt, RequestIdExt};
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
+use crate::stream::StopReason;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -25,7 +29,7 @@ pub struct ClaudeCustomProvider {
 /// - timeout: 总超时 10 分钟（流式响应可能很长）
 /// - 不设置 pool_idle_timeout 以保持连接活跃
 fn create_http_client() -> Client {
-    Client::builder()
+    super::tls::client_builder(crate::ProviderType::Claude)
         .connect_timeout(Duration::from_secs(30))
         .timeout(Duration::from_secs(600)) // 10 分钟总超时，支持长时间流式响应
         .tcp_keepalive(Duration::from_secs(60)) // TCP keepalive 保持连接活跃
@@ -88,6 +92,34 @@ impl ClaudeCustomProvider {
         }
     }
 
+    /// 构建 Anthropic `system` 字段
+    ///
+    /// 单个文本块保持字符串形式；多个文本块以数组发送，保留块边界。
+    fn build_system_field(mut blocks: Vec<serde_json::Value>) -> Option<serde_json::Value> {
+        match blocks.len() {
+            0 => None,
+            1 => blocks.remove(0).get("text").cloned(),
+            _ => Some(serde_json::Value::Array(blocks)),
+        }
+    }
+
+    /// 按 Anthropic 规则写入采样参数（不支持的参数丢弃并记录警告）
+    fn apply_sampling_params(body: &mut serde_json::Value, request: &ChatCompletionRequest) {
+        let sampling = SamplingParams::from_openai(request).map_to(Protocol::Anthropic);
+        if let Some(temperature) = sampling.temperature {
+            body["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(top_p) = sampling.top_p {
+            body["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(top_k) = sampling.top_k {
+            body["top_k"] = serde_json::json!(top_k);
+        }
+        if let Some(stop) = sampling.stop_sequences() {
+            body["stop_sequences"] = serde_json::json!(stop);
+        }
+    }
+
     /// 将 OpenAI 图片 URL 格式转换为 Claude 图片格式
     ///
     /// 支持两种格式：
@@ -158,6 +190,8 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("claude")
             .json(request)
             .send()
             .await?;
@@ -172,14 +206,44 @@ impl ClaudeCustomProvider {
         Ok(resp)
     }
 
-    /// 调用 OpenAI 格式的 API（内部转换为 Anthropic 格式）
-    pub async fn call_openai_api(
+    /// 调用 Anthropic API（透传原始请求体，不做序列化）
+    pub async fn call_api_raw(
         &self,
-        request: &ChatCompletionRequest,
-    ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
+        body: bytes::Bytes,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("Claude API key not configured")?;
+
+        let url = self.build_url("messages");
+        tracing::info!(
+            "[CLAUDE_API] 透传请求: url={} body_bytes={}",
+            url,
+            body.len()
+        );
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("claude")
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
+    /// 将 OpenAI 请求转换为 Anthropic Messages 请求体（非流式）
+    pub fn build_anthropic_body(request: &ChatCompletionRequest) -> serde_json::Value {
         // 手动转换 OpenAI 请求为 Anthropic 格式
         let mut anthropic_messages = Vec::new();
-        let mut system_content = None;
+        let mut system_blocks: Vec<serde_json::Value> = Vec::new();
 
         for msg in &request.messages {
             let role = &msg.role;
@@ -215,13 +279,12 @@ impl ClaudeCustomProvider {
             };
 
             if role == "system" {
-                // system 消息只提取文本
-                let text = content_blocks
-                    .iter()
-                    .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
-                    .collect::<Vec<_>>()
-                    .join("");
-                system_content = Some(text);
+                // system 消息只提取文本块，保留块边界
+                system_blocks.extend(
+                    content_blocks
+                        .into_iter()
+                        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text")),
+                );
             } else if !content_blocks.is_empty() {
                 let anthropic_role = if role == "assistant" {
                     "assistant"
@@ -241,9 +304,65 @@ impl ClaudeCustomProvider {
             "messages": anthropic_messages
         });
 
-        if let Some(sys) = system_content {
-            anthropic_body["system"] = serde_json::json!(sys);
+        if let Some(sys) = Self::build_system_field(system_blocks) {
+            anthropic_body["system"] = sys;
         }
+        Self::apply_sampling_params(&mut anthropic_body, request);
+        anthropic_body
+    }
+
+    /// 将 Anthropic Messages 响应转换为 OpenAI 格式
+    pub fn openai_response_from_anthropic(
+        anthropic_resp: &serde_json::Value,
+        model: &str,
+    ) -> serde_json::Value {
+        // 转换回 OpenAI 格式
+        let content = anthropic_resp["content"]
+            .as_array()
+            .and_then(|arr| arr.first())
+            .and_then(|block| block["text"].as_str())
+            .unwrap_or("");
+        // 仅转换文本块，tool_use 结束原因回落为 stop；
+        // 命中的停止序列按 vLLM 约定放在 choice 的 stop_reason 字段回显
+        let finish_reason = match anthropic_resp["stop_reason"]
+            .as_str()
+            .map(StopReason::from_anthropic_str)
+        {
+            Some(StopReason::ToolUse) | None => StopReason::EndTurn,
+            Some(reason) => reason,
+        };
+
+        serde_json::json!({
+            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+            "object": "chat.completion",
+            "created": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": content
+                },
+                "finish_reason": finish_reason.to_openai_str(),
+                "stop_reason": anthropic_resp["stop_sequence"]
+            }],
+            "usage": {
+                "prompt_tokens": anthropic_resp["usage"]["input_tokens"].as_u64().unwrap_or(0),
+                "completion_tokens": anthropic_resp["usage"]["output_tokens"].as_u64().unwrap_or(0),
+                "total_tokens": 0
+            }
+        })
+    }
+
+    /// 调用 OpenAI 格式的 API（内部转换为 Anthropic 格式）
+    pub async fn call_openai_api(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
+        let anthropic_body = Self::build_anthropic_body(request);
 
         let api_key = self
             .config
@@ -267,6 +386,8 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("claude")
             .json(&anthropic_body)
             .send()
             .await?;
@@ -285,36 +406,10 @@ impl ClaudeCustomProvider {
         }
 
         let anthropic_resp: serde_json::Value = resp.json().await?;
-
-        // 转换回 OpenAI 格式
-        let content = anthropic_resp["content"]
-            .as_array()
-            .and_then(|arr| arr.first())
-            .and_then(|block| block["text"].as_str())
-            .unwrap_or("");
-
-        Ok(serde_json::json!({
-            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
-            "object": "chat.completion",
-            "created": std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
-            "model": request.model,
-            "choices": [{
-                "index": 0,
-                "message": {
-                    "role": "assistant",
-                    "content": content
-                },
-                "finish_reason": "stop"
-            }],
-            "usage": {
-                "prompt_tokens": anthropic_resp["usage"]["input_tokens"].as_u64().unwrap_or(0),
-                "completion_tokens": anthropic_resp["usage"]["output_tokens"].as_u64().unwrap_or(0),
-                "total_tokens": 0
-            }
-        }))
+        Ok(Self::openai_response_from_anthropic(
+            &anthropic_resp,
+            &request.model,
+        ))
     }
 
     pub async fn messages(
@@ -351,6 +446,8 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("claude")
             .json(request)
             .send()
             .await?;
@@ -383,6 +480,8 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("claude")
             .json(request)
             .send()
             .await?;
@@ -427,7 +526,7 @@ impl StreamingProvider for ClaudeCustomProvider {
 
         // 转换 OpenAI 请求为 Anthropic 格式
         let mut anthropic_messages = Vec::new();
-        let mut system_content = None;
+        let mut system_blocks: Vec<serde_json::Value> = Vec::new();
         // 收集 tool 角色消息的 tool_result，稍后合并到 user 消息中
         let mut pending_tool_results: Vec<serde_json::Value> = Vec::new();
 
@@ -504,13 +603,12 @@ impl StreamingProvider for ClaudeCustomProvider {
             }
 
             if role == "system" {
-                // system 消息只提取文本
-                let text = content_blocks
-                    .iter()
-                    .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
-                    .collect::<Vec<_>>()
-                    .join("");
-                system_content = Some(text);
+                // system 消息只提取文本块，保留块边界
+                system_blocks.extend(
+                    content_blocks
+                        .into_iter()
+                        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text")),
+                );
             } else if !content_blocks.is_empty() {
                 let anthropic_role = if role == "assistant" {
                     "assistant"
@@ -539,9 +637,10 @@ impl StreamingProvider for ClaudeCustomProvider {
             "stream": true
         });
 
-        if let Some(sys) = system_content {
-            anthropic_body["system"] = serde_json::json!(sys);
+        if let Some(sys) = Self::build_system_field(system_blocks) {
+            anthropic_body["system"] = sys;
         }
+        Self::apply_sampling_params(&mut anthropic_body, request);
 
         // 转换 tools: OpenAI 格式 -> Anthropic 格式
         if let Some(ref tools) = request.tools {
@@ -627,6 +726,8 @@ impl StreamingProvider for ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("claude")
             .header("Accept", "text/event-stream")
             .json(&anthropic_body)
             .send()
@@ -125,7 +125,7 @@ impl Default for ClaudeOAuthProvider {
     fn default() -> Self {
         Self {
             credentials: ClaudeOAuthCredentials::default(),
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::ClaudeOAuth),
             creds_path: None,
         }
     }
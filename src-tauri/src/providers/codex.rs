@@ -8,6 +8,8 @@
 use super::error::{
     create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
 };
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -112,6 +114,26 @@ impl Default for CodexCredentials {
     }
 }
 
+impl CodexCredentials {
+    /// 解析凭证文件
+    ///
+    /// Codex CLI 的 `auth.json` 把 OAuth Token 放在 `tokens` 对象下，解析前提升到顶层（顶层已有的字段优先）。
+    pub fn from_json(content: &str) -> serde_json::Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(content)?;
+        if let Some(obj) = value.as_object_mut() {
+            if let Some(serde_json::Value::Object(tokens)) = obj.remove("tokens") {
+                for (key, token) in tokens {
+                    let slot = obj.entry(key).or_insert(serde_json::Value::Null);
+                    if slot.is_null() {
+                        *slot = token;
+                    }
+                }
+            }
+        }
+        serde_json::from_value(value)
+    }
+}
+
 /// PKCE codes for OAuth2 authorization
 #[derive(Debug, Clone)]
 pub struct PKCECodes {
@@ -394,7 +416,7 @@ impl Default for CodexProvider {
     fn default() -> Self {
         Self {
             credentials: CodexCredentials::default(),
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::Codex),
             creds_path: None,
             callback_port: DEFAULT_CALLBACK_PORT,
         }
@@ -503,7 +525,7 @@ impl CodexProvider {
             let content = tokio::fs::read_to_string(&path).await?;
 
             // 尝试解析凭证文件
-            let creds: CodexCredentials = serde_json::from_str(&content).map_err(|e| {
+            let creds = CodexCredentials::from_json(&content).map_err(|e| {
                 tracing::error!("[CODEX] 凭证文件解析失败: {}. 文件路径: {:?}", e, path);
                 format!("凭证文件格式错误: {}", e)
             })?;
@@ -516,7 +538,7 @@ impl CodexProvider {
                 .unwrap_or(false);
             if creds.refresh_token.is_none() && !has_api_key {
                 tracing::warn!(
-                    "[CODEX] 凭证文件缺少 refresh_token/api_key 字段。支持的字段名: refresh_token, refreshToken, api_key, apiKey"
+                    "[CODEX] 凭证文件缺少 refresh_token/api_key 字段。支持的字段名: refresh_token, refreshToken, tokens.refresh_token, api_key, apiKey, OPENAI_API_KEY"
                 );
                 // 打印文件中的顶级字段名，帮助调试
                 if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&content) {
@@ -1399,14 +1421,27 @@ fn transform_to_codex_format(
             match role {
                 "system" => {
                     // System messages 转换为 user message（Codex 使用 instructions 而不是 system role）
-                    if let Some(text) = content.as_str() {
-                        if !text.is_empty() {
-                            input.push(serde_json::json!({
-                                "type": "message",
-                                "role": "user",
-                                "content": [{"type": "input_text", "text": text}]
-                            }));
-                        }
+                    // 数组形式的 system 内容按文本块拆分为多个 input_text，保留块边界
+                    let texts: Vec<&str> = if let Some(text) = content.as_str() {
+                        vec![text]
+                    } else if let Some(arr) = content.as_array() {
+                        arr.iter()
+                            .filter_map(|part| part["text"].as_str())
+                            .collect()
+                    } else {
+                        vec![]
+                    };
+                    let content_parts: Vec<serde_json::Value> = texts
+                        .into_iter()
+                        .filter(|text| !text.is_empty())
+                        .map(|text| serde_json::json!({"type": "input_text", "text": text}))
+                        .collect();
+                    if !content_parts.is_empty() {
+                        input.push(serde_json::json!({
+                            "type": "message",
+                            "role": "user",
+                            "content": content_parts
+                        }));
                     }
                 }
                 "user" => {
@@ -1560,6 +1595,18 @@ fn transform_to_codex_format(
         }
     }
 
+    // 采样参数按 Responses API 规则映射
+    SamplingParams::from_openai_value(request)
+        .map_to(Protocol::Codex)
+        .write_openai_fields(&mut codex_request);
+    if let Some(max_tokens) = request
+        .get("max_completion_tokens")
+        .or_else(|| request.get("max_tokens"))
+        .filter(|v| v.is_u64())
+    {
+        codex_request["max_output_tokens"] = max_tokens.clone();
+    }
+
     // Handle reasoning effort
     if let Some(reasoning_effort) = request["reasoning_effort"].as_str() {
         codex_request["reasoning"]["effort"] = serde_json::json!(reasoning_effort);
@@ -1631,6 +1678,29 @@ mod tests {
         assert_eq!(parsed.email, creds.email);
     }
 
+    #[test]
+    fn test_codex_cli_auth_json() {
+        let creds = CodexCredentials::from_json(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/credential_import/codex_auth.json"
+        )))
+        .unwrap();
+        assert_eq!(
+            creds.refresh_token.as_deref(),
+            Some("rt_test-codex-refresh-token")
+        );
+        assert!(creds.access_token.is_some());
+        assert_eq!(
+            creds.account_id.as_deref(),
+            Some("00000000-0000-0000-0000-000000000000")
+        );
+        assert!(creds.api_key.is_none());
+
+        let creds =
+            CodexCredentials::from_json(r#"{"OPENAI_API_KEY":"sk-codex","tokens":null}"#).unwrap();
+        assert_eq!(creds.api_key.as_deref(), Some("sk-codex"));
+    }
+
     #[test]
     fn test_codex_credentials_camel_case_alias() {
         // 测试 camelCase 字段名的支持（Codex CLI 官方格式）
@@ -1954,6 +2024,23 @@ mod tests {
         assert_eq!(result["top_p"], 0.9);
     }
 
+    #[test]
+    fn test_transform_to_codex_format_drops_unsupported_sampling() {
+        let request = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "temperature": 3.0,
+            "stop": ["END"],
+            "presence_penalty": 0.5
+        });
+
+        let result = transform_to_codex_format(&request).unwrap();
+
+        assert_eq!(result["temperature"], 2.0);
+        assert!(result.get("stop").is_none());
+        assert!(result.get("presence_penalty").is_none());
+    }
+
     #[tokio::test]
     async fn test_refresh_token_with_only_access_token() {
         // 场景：只有 access_token（无 refresh_token 和 api_key）
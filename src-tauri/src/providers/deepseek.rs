@@ -0,0 +1,19 @@
+//! DeepSeek Provider
+//!
+//! DeepSeek 使用 OpenAI 兼容协议，聊天请求复用 `OpenAICustomProvider`，
+//! 本模块负责默认地址以及发送前的请求适配。
+//!
+//! deepseek-reasoner 的思维链通过响应中的 `reasoning_content` 字段返回，
+//! 由调用方在转换为 Anthropic 格式时处理。
+
+use crate::models::openai::ChatCompletionRequest;
+
+/// DeepSeek 默认 API 地址（不含 /v1，由调用方按 OpenAI 规则拼接）
+pub const DEEPSEEK_BASE_URL: &str = "https://api.deepseek.com";
+
+/// 适配 DeepSeek 请求
+///
+/// - 不支持 `reasoning_effort` 参数，推理由模型（deepseek-reasoner）决定
+pub fn adapt_request(request: &mut ChatCompletionRequest) {
+    request.reasoning_effort = None;
+}
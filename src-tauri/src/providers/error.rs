@@ -154,8 +154,9 @@ impl ProviderError {
         }
     }
 
-    /// 从 reqwest 错误创建
+    /// 从 reqwest 错误创建（连接失败同时记入上游连通性负缓存）
     pub fn from_reqwest_error(err: &reqwest::Error) -> Self {
+        crate::resilience::record_error(err);
         if err.is_timeout() {
             ProviderError::NetworkError("请求超时".to_string())
         } else if err.is_connect() {
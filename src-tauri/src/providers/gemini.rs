@@ -191,7 +191,7 @@ impl Default for GeminiProvider {
         Self {
             credentials: GeminiCredentials::default(),
             project_id: None,
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::Gemini),
         }
     }
 }
@@ -584,7 +584,7 @@ impl GeminiApiKeyProvider {
     /// Create a new Gemini API Key provider
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::GeminiApiKey),
         }
     }
 
@@ -782,7 +782,7 @@ pub struct GeminiOAuthResult {
 }
 
 /// 生成 PKCE code_verifier 和 code_challenge
-fn generate_pkce() -> (String, String) {
+pub(crate) fn generate_pkce() -> (String, String) {
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
     use sha2::{Digest, Sha256};
 
@@ -806,6 +806,15 @@ fn generate_pkce() -> (String, String) {
 
 /// 生成 OAuth 授权 URL（使用 PKCE）
 pub fn generate_gemini_auth_url(state: &str, code_challenge: &str) -> String {
+    generate_gemini_auth_url_with_redirect(state, code_challenge, GEMINI_OAUTH_REDIRECT_URI)
+}
+
+/// 生成 OAuth 授权 URL（指定回调地址，用于本地回调监听）
+pub fn generate_gemini_auth_url_with_redirect(
+    state: &str,
+    code_challenge: &str,
+    redirect_uri: &str,
+) -> String {
     let scopes = GEMINI_OAUTH_SCOPES.join(" ");
 
     let params = [
@@ -814,7 +823,7 @@ pub fn generate_gemini_auth_url(state: &str, code_challenge: &str) -> String {
         ("code_challenge", code_challenge),
         ("code_challenge_method", "S256"),
         ("prompt", "select_account"),
-        ("redirect_uri", GEMINI_OAUTH_REDIRECT_URI),
+        ("redirect_uri", redirect_uri),
         ("response_type", "code"),
         ("scope", &scopes),
         ("state", state),
@@ -870,6 +879,20 @@ pub fn generate_gemini_auth_url_with_session() -> (String, GeminiOAuthSession) {
 pub async fn exchange_gemini_code_and_create_credentials(
     code: &str,
     code_verifier: &str,
+) -> Result<GeminiOAuthResult, Box<dyn std::error::Error + Send + Sync>> {
+    exchange_gemini_code_and_create_credentials_with_redirect(
+        code,
+        code_verifier,
+        GEMINI_OAUTH_REDIRECT_URI,
+    )
+    .await
+}
+
+/// 用授权码交换 Token 并创建凭证（指定授权时使用的回调地址）
+pub async fn exchange_gemini_code_and_create_credentials_with_redirect(
+    code: &str,
+    code_verifier: &str,
+    redirect_uri: &str,
 ) -> Result<GeminiOAuthResult, Box<dyn std::error::Error + Send + Sync>> {
     let client = Client::builder()
         .timeout(std::time::Duration::from_secs(30))
@@ -878,7 +901,9 @@ pub async fn exchange_gemini_code_and_create_credentials(
     tracing::info!("[Gemini OAuth] 正在用授权码交换 Token...");
 
     // 交换 Token
-    let token_data = exchange_gemini_code_for_token(&client, code, code_verifier).await?;
+    let token_data =
+        exchange_gemini_code_for_token_with_redirect(&client, code, code_verifier, redirect_uri)
+            .await?;
 
     let access_token = token_data["access_token"]
         .as_str()
@@ -936,13 +961,29 @@ pub async fn exchange_gemini_code_for_token(
     client: &Client,
     code: &str,
     code_verifier: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    exchange_gemini_code_for_token_with_redirect(
+        client,
+        code,
+        code_verifier,
+        GEMINI_OAUTH_REDIRECT_URI,
+    )
+    .await
+}
+
+/// 用授权码交换 Token（redirect_uri 须与授权请求一致）
+pub async fn exchange_gemini_code_for_token_with_redirect(
+    client: &Client,
+    code: &str,
+    code_verifier: &str,
+    redirect_uri: &str,
 ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
     let params = [
         ("code", code),
         ("client_id", GEMINI_OAUTH_CLIENT_ID),
         ("client_secret", GEMINI_OAUTH_CLIENT_SECRET),
         ("code_verifier", code_verifier),
-        ("redirect_uri", GEMINI_OAUTH_REDIRECT_URI),
+        ("redirect_uri", redirect_uri),
         ("grant_type", "authorization_code"),
     ];
 
@@ -1408,8 +1449,8 @@ impl CredentialProvider for GeminiProvider {
         GeminiProvider::refresh_token(self).await
     }
 
-    fn get_access_token(&self) -> Option<&str> {
-        self.credentials.access_token.as_deref()
+    fn get_access_token(&self) -> Option<String> {
+        self.credentials.access_token.clone()
     }
 
     fn provider_type(&self) -> &'static str {
@@ -470,7 +470,7 @@ impl Default for IFlowProvider {
     fn default() -> Self {
         Self {
             credentials: IFlowCredentials::default(),
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::IFlow),
             creds_path: None,
             callback_port: DEFAULT_CALLBACK_PORT,
         }
@@ -3,16 +3,36 @@
 #![allow(dead_code)]
 
 // 使用新的 translator 模块替代旧的 converter
+use crate::middleware::passthrough_headers;
 use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::codewhisperer::CodeWhispererRequest;
 use crate::models::openai::*;
+use crate::models::provider_pool_model::pattern_matches;
 use crate::providers::traits::{CredentialProvider, ProviderResult};
 use crate::translator::kiro::anthropic::request::convert_anthropic_to_codewhisperer;
 use crate::translator::kiro::openai::request::convert_openai_to_codewhisperer;
 use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// 请求级 agent mode 请求头（同时作为上游请求头名称）
+pub const KIRO_AGENT_MODE_HEADER: &str = "x-amzn-kiro-agent-mode";
+/// 请求级 origin 请求头
+pub const KIRO_ORIGIN_HEADER: &str = "x-kiro-origin";
+const DEFAULT_AGENT_MODE: &str = "vibe";
+const DEFAULT_ORIGIN: &str = "AI_EDITOR";
+const DEFAULT_REGION: &str = "us-east-1";
+/// 区域连续失败多少次后暂时跳过
+const REGION_FAILURE_THRESHOLD: u32 = 3;
+/// 区域被跳过的时长
+const REGION_COOLDOWN: Duration = Duration::from_secs(60);
 
 /// 根据凭证信息生成唯一的 Machine ID（与 AIClient-2-API 保持一致）
 ///
@@ -162,7 +182,7 @@ fn get_raw_machine_id() -> Option<String> {
 /// 获取 Kiro IDE 版本号
 ///
 /// 尝试从 Kiro.app 的 Info.plist 读取实际版本，失败时使用默认值
-fn get_kiro_version() -> String {
+pub fn get_kiro_version() -> String {
     use std::process::Command;
 
     if cfg!(target_os = "macos") {
@@ -219,6 +239,86 @@ pub struct KiroCredentials {
     /// 凭证类型标识
     #[serde(default = "default_kiro_type", rename = "type")]
     pub cred_type: String,
+    /// `x-amzn-kiro-agent-mode` 请求头（默认 vibe）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_mode: Option<String>,
+    /// 请求消息的 origin（默认 AI_EDITOR）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// 额外的 profile / 区域（与 `profileArn` + `region` 共用同一 Token）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<KiroProfile>,
+}
+
+/// Kiro profile（profile ARN + CodeWhisperer 区域）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KiroProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_arn: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// 该 profile 可用的模型（支持 `*` 通配符，为空表示全部）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+}
+
+impl KiroProfile {
+    pub fn region(&self) -> &str {
+        self.region.as_deref().unwrap_or(DEFAULT_REGION)
+    }
+
+    pub fn supports_model(&self, model: &str) -> bool {
+        self.models.is_empty() || self.models.iter().any(|p| pattern_matches(p, model))
+    }
+}
+
+#[derive(Debug, Default)]
+struct RegionHealth {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+}
+
+/// CodeWhisperer 区域健康状态（所有凭证共享）
+static REGION_HEALTH: Lazy<Mutex<HashMap<String, RegionHealth>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 区域当前是否可用
+pub fn is_region_healthy(region: &str) -> bool {
+    REGION_HEALTH
+        .lock()
+        .get(region)
+        .and_then(|h| h.unhealthy_until)
+        .is_none_or(|until| Instant::now() >= until)
+}
+
+/// 按响应状态记录区域请求结果（仅 5xx 视为区域故障，其他错误与区域无关）
+fn record_region_result_for_status(region: &str, status: reqwest::StatusCode) {
+    if status.is_server_error() {
+        record_region_result(region, false);
+    } else if status.is_success() {
+        record_region_result(region, true);
+    }
+}
+
+/// 记录区域请求结果：连续失败达到阈值后在冷却期内跳过该区域，成功后恢复
+pub fn record_region_result(region: &str, success: bool) {
+    let mut health = REGION_HEALTH.lock();
+    if success {
+        health.remove(region);
+        return;
+    }
+    let entry = health.entry(region.to_string()).or_default();
+    entry.consecutive_failures += 1;
+    if entry.consecutive_failures >= REGION_FAILURE_THRESHOLD {
+        tracing::warn!(
+            "[KIRO] 区域 {} 连续失败 {} 次，{} 秒内跳过",
+            region,
+            entry.consecutive_failures,
+            REGION_COOLDOWN.as_secs()
+        );
+        entry.unhealthy_until = Some(Instant::now() + REGION_COOLDOWN);
+    }
 }
 
 fn default_kiro_type() -> String {
@@ -240,12 +340,48 @@ impl Default for KiroCredentials {
             client_id_hash: None,
             last_refresh: None,
             cred_type: default_kiro_type(),
+            agent_mode: None,
+            origin: None,
+            profiles: Vec::new(),
         }
     }
 }
 
+/// 共享的 Kiro HTTP 客户端（上游 TLS 配置版本变化后重建）
+static SHARED_CLIENT: Lazy<Mutex<Option<(u64, Client)>>> = Lazy::new(|| Mutex::new(None));
+
+fn shared_client() -> Client {
+    let generation = super::tls::generation();
+    let mut shared = SHARED_CLIENT.lock();
+    match shared.as_ref() {
+        Some((built, client)) if *built == generation => client.clone(),
+        _ => {
+            // 创建带超时配置的 HTTP 客户端
+            // 参考 AIClient-2-API: AXIOS_TIMEOUT: 300000 (5分钟)
+            let client = super::tls::client_builder(crate::ProviderType::Kiro)
+                .connect_timeout(std::time::Duration::from_secs(30)) // 连接超时 30 秒
+                .timeout(std::time::Duration::from_secs(300)) // 总超时 5 分钟
+                .build()
+                .unwrap_or_else(|_| Client::new());
+            *shared = Some((generation, client.clone()));
+            client
+        }
+    }
+}
+
+/// 按凭证文件缓存的 Provider 及加载时的文件修改时间
+type PooledProviders = HashMap<PathBuf, (Option<SystemTime>, KiroProvider)>;
+
+/// 凭证池中按凭证文件共享的 Provider
+static POOLED_PROVIDERS: Lazy<Mutex<PooledProviders>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Kiro Provider
+///
+/// 克隆的实例共享同一份凭证状态（刷新后的 Token 对所有克隆可见）和同一个 HTTP 客户端。
+/// 凭证锁不能跨 `.await` 持有，异步方法先复制需要的字段。
+#[derive(Clone)]
 pub struct KiroProvider {
-    pub credentials: KiroCredentials,
+    pub credentials: Arc<RwLock<KiroCredentials>>,
     pub client: Client,
     /// 当前加载的凭证文件路径
     pub creds_path: Option<PathBuf>,
@@ -253,17 +389,9 @@ pub struct KiroProvider {
 
 impl Default for KiroProvider {
     fn default() -> Self {
-        // 创建带超时配置的 HTTP 客户端
-        // 参考 AIClient-2-API: AXIOS_TIMEOUT: 300000 (5分钟)
-        let client = Client::builder()
-            .connect_timeout(std::time::Duration::from_secs(30)) // 连接超时 30 秒
-            .timeout(std::time::Duration::from_secs(300)) // 总超时 5 分钟
-            .build()
-            .unwrap_or_else(|_| Client::new());
-
         Self {
-            credentials: KiroCredentials::default(),
-            client,
+            credentials: Arc::new(RwLock::new(KiroCredentials::default())),
+            client: shared_client(),
             creds_path: None,
         }
     }
@@ -274,6 +402,48 @@ impl KiroProvider {
         Self::default()
     }
 
+    /// 凭证池中指定凭证文件对应的共享 Provider
+    ///
+    /// 同一凭证文件的请求复用同一份凭证状态（刷新后的 Token 对后续请求可见），
+    /// 凭证文件被修改（重新登录、外部刷新）后重新加载。
+    pub async fn pooled(path: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let key = PathBuf::from(path);
+        let modified = tokio::fs::metadata(&key)
+            .await
+            .and_then(|m| m.modified())
+            .ok();
+        let cached = POOLED_PROVIDERS
+            .lock()
+            .get(&key)
+            .filter(|(loaded, _)| *loaded == modified)
+            .map(|(_, provider)| provider.clone());
+        if let Some(provider) = cached {
+            return Ok(provider);
+        }
+
+        let mut provider = Self::new();
+        provider.load_credentials_from_path(path).await?;
+        POOLED_PROVIDERS
+            .lock()
+            .insert(key, (modified, provider.clone()));
+        Ok(provider)
+    }
+
+    /// 当前凭证的快照
+    pub fn credentials(&self) -> KiroCredentials {
+        self.credentials.read().clone()
+    }
+
+    /// 当前 access_token
+    pub fn access_token(&self) -> Option<String> {
+        self.credentials.read().access_token.clone()
+    }
+
+    /// 更新 access_token（所有克隆可见）
+    pub fn set_access_token(&self, token: Option<String>) {
+        self.credentials.write().access_token = token;
+    }
+
     pub fn default_creds_path() -> PathBuf {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -358,12 +528,13 @@ impl KiroProvider {
             merged.auth_method
         );
 
-        self.credentials = merged;
+        *self.credentials.write() = merged;
         self.creds_path = Some(path);
 
         // 加载完成后，智能检测并更新认证方式（如果需要）
         let detected_auth_method = self.detect_auth_method();
-        if self.credentials.auth_method.as_deref().unwrap_or("social") != detected_auth_method {
+        let current_auth_method = self.credentials.read().auth_method.clone();
+        if current_auth_method.as_deref().unwrap_or("social") != detected_auth_method {
             tracing::info!(
                 "[KIRO] 加载后检测到需要调整认证方式为: {}",
                 detected_auth_method
@@ -522,12 +693,13 @@ impl KiroProvider {
             merged.auth_method
         );
 
-        self.credentials = merged;
+        *self.credentials.write() = merged;
         self.creds_path = Some(path);
 
         // 加载完成后，智能检测并更新认证方式（如果需要）
         let detected_auth_method = self.detect_auth_method();
-        if self.credentials.auth_method.as_deref().unwrap_or("social") != detected_auth_method {
+        let current_auth_method = self.credentials.read().auth_method.clone();
+        if current_auth_method.as_deref().unwrap_or("social") != detected_auth_method {
             tracing::info!(
                 "[KIRO] 从路径加载后检测到需要调整认证方式为: {}",
                 detected_auth_method
@@ -539,14 +711,20 @@ impl KiroProvider {
     }
 
     pub fn get_base_url(&self) -> String {
-        let region = self.credentials.region.as_deref().unwrap_or("us-east-1");
+        let creds = self.credentials.read();
+        let region = creds.region.as_deref().unwrap_or(DEFAULT_REGION);
+        Self::build_api_url(region)
+    }
+
+    /// 指定区域的 CodeWhisperer 端点
+    pub fn build_api_url(region: &str) -> String {
         format!("https://codewhisperer.{region}.amazonaws.com/generateAssistantResponse")
     }
 
     pub fn get_refresh_url(&self) -> String {
-        let region = self.credentials.region.as_deref().unwrap_or("us-east-1");
-        let auth_method = self
-            .credentials
+        let creds = self.credentials.read();
+        let region = creds.region.as_deref().unwrap_or("us-east-1");
+        let auth_method = creds
             .auth_method
             .as_deref()
             .unwrap_or("social")
@@ -586,8 +764,9 @@ impl KiroProvider {
     /// - RFC3339 格式（新格式，与 CLIProxyAPI 兼容）
     /// - 时间戳格式（旧格式）
     pub fn is_token_expired(&self) -> bool {
+        let creds = self.credentials.read();
         // 优先检查 RFC3339 格式的过期时间（新格式）
-        if let Some(expire_str) = &self.credentials.expire {
+        if let Some(expire_str) = &creds.expire {
             if let Ok(expires) = chrono::DateTime::parse_from_rfc3339(expire_str) {
                 let now = chrono::Utc::now();
                 // 提前5分钟判断为过期，避免边界情况
@@ -596,7 +775,7 @@ impl KiroProvider {
         }
 
         // 兼容旧的时间戳格式
-        if let Some(expires_str) = &self.credentials.expires_at {
+        if let Some(expires_str) = &creds.expires_at {
             if let Ok(expires_timestamp) = expires_str.parse::<i64>() {
                 let now = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
@@ -614,7 +793,8 @@ impl KiroProvider {
 
     /// 验证 refresh_token 的基本有效性
     pub fn validate_refresh_token(&self) -> Result<(), String> {
-        let refresh_token = self.credentials.refresh_token.as_ref()
+        let creds = self.credentials.read();
+        let refresh_token = creds.refresh_token.as_ref()
             .ok_or("缺少 refresh_token。\n💡 解决方案：\n1. 重新添加 OAuth 凭证\n2. 确保凭证文件包含完整的认证信息")?;
 
         // 基本格式验证
@@ -652,22 +832,25 @@ impl KiroProvider {
     /// 不能将 IdC 的 refreshToken 用于 Social 端点。
     pub fn detect_auth_method(&self) -> String {
         // 直接返回配置中的认证方式，不做降级
-        let auth_method = self.credentials.auth_method.as_deref().unwrap_or("social");
+        let creds = self.credentials.read();
+        let auth_method = creds.auth_method.as_deref().unwrap_or("social");
         tracing::debug!("[KIRO] 使用配置的认证方式: {}", auth_method);
         auth_method.to_lowercase()
     }
 
     /// 检查 IdC 认证配置是否完整
     pub fn is_idc_config_complete(&self) -> bool {
-        self.credentials.client_id.is_some() && self.credentials.client_secret.is_some()
+        let creds = self.credentials.read();
+        creds.client_id.is_some() && creds.client_secret.is_some()
     }
 
     /// 更新认证方式到凭证中（仅在内存中，需要调用 save_credentials 持久化）
-    pub fn set_auth_method(&mut self, method: &str) {
-        let old_method = self.credentials.auth_method.as_deref().unwrap_or("social");
+    pub fn set_auth_method(&self, method: &str) {
+        let mut creds = self.credentials.write();
+        let old_method = creds.auth_method.as_deref().unwrap_or("social");
         if old_method != method {
             tracing::info!("[KIRO] 认证方式从 {} 切换到 {}", old_method, method);
-            self.credentials.auth_method = Some(method.to_string());
+            creds.auth_method = Some(method.to_string());
         }
     }
 
@@ -675,17 +858,18 @@ impl KiroProvider {
         // 首先验证 refresh_token 的有效性
         self.validate_refresh_token()?;
 
+        // 复制当前凭证，刷新请求期间不持有锁
+        let creds = self.credentials();
+
         tracing::info!("[KIRO] 开始 Token 刷新流程");
         tracing::info!(
             "[KIRO] 当前凭证状态: has_client_id={}, has_client_secret={}, auth_method={:?}",
-            self.credentials.client_id.is_some(),
-            self.credentials.client_secret.is_some(),
-            self.credentials.auth_method
+            creds.client_id.is_some(),
+            creds.client_secret.is_some(),
+            creds.auth_method
         );
 
-        // 先克隆必要的值，避免借用冲突
-        let refresh_token = self
-            .credentials
+        let refresh_token = creds
             .refresh_token
             .as_ref()
             .ok_or("No refresh token")?
@@ -697,8 +881,8 @@ impl KiroProvider {
 
         // 检查 IdC 认证是否有完整配置
         if auth_method == "idc" && !self.is_idc_config_complete() {
-            let has_client_id = self.credentials.client_id.is_some();
-            let has_client_secret = self.credentials.client_secret.is_some();
+            let has_client_id = creds.client_id.is_some();
+            let has_client_secret = creds.client_secret.is_some();
 
             // IdC 认证缺少必要凭证，返回明确错误（不能降级到 social，因为 refreshToken 不兼容）
             let missing = match (has_client_id, has_client_secret) {
@@ -722,27 +906,25 @@ impl KiroProvider {
         );
         tracing::debug!(
             "[KIRO] has_client_id={}, has_client_secret={}",
-            self.credentials.client_id.is_some(),
-            self.credentials.client_secret.is_some()
+            creds.client_id.is_some(),
+            creds.client_secret.is_some()
         );
 
         // 获取设备指纹和版本号（用于 Social 认证的 User-Agent）
         // 使用基于凭证的 Machine ID，确保每个账号有独立的指纹
         let machine_id = generate_machine_id_from_credentials(
-            self.credentials.profile_arn.as_deref(),
-            self.credentials.client_id.as_deref(),
+            creds.profile_arn.as_deref(),
+            creds.client_id.as_deref(),
         );
         let kiro_version = get_kiro_version();
 
         let resp = if auth_method == "idc" {
             // IdC 认证使用 JSON 格式（参考 Kir-Manager 实现）
-            let client_id = self
-                .credentials
+            let client_id = creds
                 .client_id
                 .as_ref()
                 .ok_or("IdC 认证配置错误：缺少 client_id。建议删除后重新添加 OAuth 凭证")?;
-            let client_secret = self
-                .credentials
+            let client_secret = creds
                 .client_secret
                 .as_ref()
                 .ok_or("IdC 认证配置错误：缺少 client_secret。建议删除后重新添加 OAuth 凭证")?;
@@ -828,32 +1010,35 @@ impl KiroProvider {
             .or_else(|| data["access_token"].as_str())
             .ok_or("No access token in response")?;
 
-        self.credentials.access_token = Some(new_token.to_string());
-
-        // Handle both camelCase and snake_case response formats
-        if let Some(rt) = data["refreshToken"]
-            .as_str()
-            .or_else(|| data["refresh_token"].as_str())
         {
-            self.credentials.refresh_token = Some(rt.to_string());
-        }
-        if let Some(arn) = data["profileArn"].as_str() {
-            self.credentials.profile_arn = Some(arn.to_string());
-        }
+            let mut creds = self.credentials.write();
+            creds.access_token = Some(new_token.to_string());
 
-        // 更新过期时间（如果响应中包含）
-        if let Some(expires_in) = data["expiresIn"]
-            .as_i64()
-            .or_else(|| data["expires_in"].as_i64())
-        {
-            let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expires_in);
-            self.credentials.expire = Some(expires_at.to_rfc3339());
-            // 同时更新旧格式以保持兼容
-            self.credentials.expires_at = Some(expires_at.timestamp().to_string());
-        }
+            // Handle both camelCase and snake_case response formats
+            if let Some(rt) = data["refreshToken"]
+                .as_str()
+                .or_else(|| data["refresh_token"].as_str())
+            {
+                creds.refresh_token = Some(rt.to_string());
+            }
+            if let Some(arn) = data["profileArn"].as_str() {
+                creds.profile_arn = Some(arn.to_string());
+            }
 
-        // 更新最后刷新时间（RFC3339 格式）
-        self.credentials.last_refresh = Some(chrono::Utc::now().to_rfc3339());
+            // 更新过期时间（如果响应中包含）
+            if let Some(expires_in) = data["expiresIn"]
+                .as_i64()
+                .or_else(|| data["expires_in"].as_i64())
+            {
+                let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expires_in);
+                creds.expire = Some(expires_at.to_rfc3339());
+                // 同时更新旧格式以保持兼容
+                creds.expires_at = Some(expires_at.timestamp().to_string());
+            }
+
+            // 更新最后刷新时间（RFC3339 格式）
+            creds.last_refresh = Some(chrono::Utc::now().to_rfc3339());
+        }
 
         // 保存更新后的凭证到文件
         self.save_credentials().await?;
@@ -862,6 +1047,7 @@ impl KiroProvider {
     }
 
     pub async fn save_credentials(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let creds = self.credentials();
         // 使用加载时的路径或默认路径
         let path = self
             .creds_path
@@ -878,22 +1064,22 @@ impl KiroProvider {
         };
 
         // 更新字段
-        if let Some(token) = &self.credentials.access_token {
+        if let Some(token) = &creds.access_token {
             existing["accessToken"] = serde_json::json!(token);
         }
-        if let Some(token) = &self.credentials.refresh_token {
+        if let Some(token) = &creds.refresh_token {
             existing["refreshToken"] = serde_json::json!(token);
         }
-        if let Some(arn) = &self.credentials.profile_arn {
+        if let Some(arn) = &creds.profile_arn {
             existing["profileArn"] = serde_json::json!(arn);
         }
 
         // 添加统一凭证格式字段（与 CLIProxyAPI 兼容）
-        existing["type"] = serde_json::json!(self.credentials.cred_type);
-        if let Some(expire) = &self.credentials.expire {
+        existing["type"] = serde_json::json!(creds.cred_type);
+        if let Some(expire) = &creds.expire {
             existing["expire"] = serde_json::json!(expire);
         }
-        if let Some(last_refresh) = &self.credentials.last_refresh {
+        if let Some(last_refresh) = &creds.last_refresh {
             existing["lastRefresh"] = serde_json::json!(last_refresh);
         }
 
@@ -910,8 +1096,9 @@ impl KiroProvider {
     /// - RFC3339 格式（新格式，与 CLIProxyAPI 兼容）
     /// - 时间戳格式（旧格式）
     pub fn is_token_expiring_soon(&self) -> bool {
+        let creds = self.credentials.read();
         // 优先检查 RFC3339 格式的过期时间（新格式）
-        if let Some(expire_str) = &self.credentials.expire {
+        if let Some(expire_str) = &creds.expire {
             if let Ok(expiry) = chrono::DateTime::parse_from_rfc3339(expire_str) {
                 let now = chrono::Utc::now();
                 let threshold = now + chrono::Duration::minutes(10);
@@ -920,7 +1107,7 @@ impl KiroProvider {
         }
 
         // 兼容旧格式（expires_at 可能是 RFC3339 或时间戳）
-        if let Some(expires_at) = &self.credentials.expires_at {
+        if let Some(expires_at) = &creds.expires_at {
             // 尝试解析为 RFC3339
             if let Ok(expiry) = chrono::DateTime::parse_from_rfc3339(expires_at) {
                 let now = chrono::Utc::now();
@@ -940,24 +1127,117 @@ impl KiroProvider {
         false
     }
 
+    /// 候选 profile：配置了 `profiles` 时按顺序使用，否则使用凭证的 `profileArn` + `region`
+    pub fn candidate_profiles(&self) -> Vec<KiroProfile> {
+        let creds = self.credentials.read();
+        if !creds.profiles.is_empty() {
+            return creds.profiles.clone();
+        }
+        vec![KiroProfile {
+            profile_arn: creds.profile_arn.clone(),
+            region: creds.region.clone(),
+            models: Vec::new(),
+        }]
+    }
+
+    /// 为模型选择 profile
+    ///
+    /// 在支持该模型的 profile 中选择第一个区域健康的；都不健康时使用第一个，
+    /// 没有 profile 支持该模型时使用第一个候选。
+    pub fn select_profile(&self, model: &str) -> KiroProfile {
+        let candidates = self.candidate_profiles();
+        let supported: Vec<&KiroProfile> = candidates
+            .iter()
+            .filter(|p| p.supports_model(model))
+            .collect();
+        if supported.is_empty() {
+            tracing::warn!(
+                "[KIRO] 没有 profile 声明支持模型 {}，使用第一个 profile",
+                model
+            );
+            return candidates[0].clone();
+        }
+        supported
+            .iter()
+            .copied()
+            .find(|p| is_region_healthy(p.region()))
+            .unwrap_or(supported[0])
+            .clone()
+    }
+
+    /// social 登录方式才需要携带 profile_arn
+    fn request_profile_arn(&self, profile: &KiroProfile) -> Option<String> {
+        if self.credentials.read().auth_method.as_deref() == Some("social") {
+            profile.profile_arn.clone()
+        } else {
+            None
+        }
+    }
+
+    /// 本次请求的 agent mode 和 origin
+    ///
+    /// 优先级：请求头（经请求头透传策略 `kiro` 收集，含配置的固定值）> 凭证配置 > 默认值
+    pub fn request_mode(&self) -> (String, String) {
+        let headers = passthrough_headers("kiro");
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let creds = self.credentials.read();
+        let agent_mode = header(KIRO_AGENT_MODE_HEADER)
+            .or_else(|| creds.agent_mode.clone())
+            .unwrap_or_else(|| DEFAULT_AGENT_MODE.to_string());
+        let origin = header(KIRO_ORIGIN_HEADER)
+            .or_else(|| creds.origin.clone())
+            .unwrap_or_else(|| DEFAULT_ORIGIN.to_string());
+        (agent_mode, origin)
+    }
+
     pub async fn call_api(
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let token = self
-            .credentials
-            .access_token
-            .as_ref()
-            .ok_or("No access token")?;
+        let profile = self.select_profile(&request.model);
+        let profile_arn = self.request_profile_arn(&profile);
+        let cw_request = convert_openai_to_codewhisperer(request, profile_arn.clone());
+        self.send_cw_request(cw_request, profile_arn.as_deref(), profile.region())
+            .await
+    }
 
-        let profile_arn = if self.credentials.auth_method.as_deref() == Some("social") {
-            self.credentials.profile_arn.clone()
-        } else {
-            None
+    /// 非流式 Anthropic 请求，直接转换为 CodeWhisperer 格式
+    ///
+    /// 保留 tool_result 的错误状态和图片等 OpenAI 中间格式无法表达的信息。
+    pub async fn call_api_anthropic(
+        &self,
+        request: &AnthropicMessagesRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let profile = self.select_profile(&request.model);
+        let profile_arn = self.request_profile_arn(&profile);
+        let cw_request = convert_anthropic_to_codewhisperer(request, profile_arn.clone());
+        self.send_cw_request(cw_request, profile_arn.as_deref(), profile.region())
+            .await
+    }
+
+    async fn send_cw_request(
+        &self,
+        mut cw_request: CodeWhispererRequest,
+        profile_arn: Option<&str>,
+        region: &str,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let (agent_mode, origin) = self.request_mode();
+        cw_request.set_origin(&origin);
+
+        let (token, client_id) = {
+            let creds = self.credentials.read();
+            let token = creds.access_token.clone().ok_or("No access token")?;
+            (token, creds.client_id.clone())
         };
 
-        let cw_request = convert_openai_to_codewhisperer(request, profile_arn.clone());
-        let url = self.get_base_url();
+        let url = Self::build_api_url(region);
 
         // 安全修复：仅在 PROXYCAST_DEBUG=1 时写入请求调试文件，避免泄露敏感信息
         let debug_enabled = std::env::var("PROXYCAST_DEBUG")
@@ -1003,10 +1283,7 @@ impl KiroProvider {
         );
 
         // 生成基于凭证的唯一 Machine ID（关键改进：每个账号独立指纹）
-        let machine_id = generate_machine_id_from_credentials(
-            profile_arn.as_deref(),
-            self.credentials.client_id.as_deref(),
-        );
+        let machine_id = generate_machine_id_from_credentials(profile_arn, client_id.as_deref());
         let kiro_version = get_kiro_version();
         let (os_name, node_version) = get_system_runtime_info();
 
@@ -1014,7 +1291,7 @@ impl KiroProvider {
             "[KIRO_FINGERPRINT] machine_id={} (based on profile_arn={}, client_id={})",
             &machine_id[..16],
             profile_arn.is_some(),
-            self.credentials.client_id.is_some()
+            client_id.is_some()
         );
 
         let resp = self
@@ -1025,7 +1302,7 @@ impl KiroProvider {
             .header("Accept", "application/json")
             .header("amz-sdk-invocation-id", uuid::Uuid::new_v4().to_string())
             .header("amz-sdk-request", "attempt=1; max=1")
-            .header("x-amzn-kiro-agent-mode", "vibe")
+            .header(KIRO_AGENT_MODE_HEADER, agent_mode)
             // 关键指纹头：使用基于凭证的唯一 Machine ID
             .header(
                 "x-amz-user-agent",
@@ -1041,7 +1318,9 @@ impl KiroProvider {
             .header("Connection", "close")
             .json(&cw_request)
             .send()
-            .await?;
+            .await
+            .inspect_err(|_| record_region_result(region, false))?;
+        record_region_result_for_status(region, resp.status());
 
         Ok(resp)
     }
@@ -1081,6 +1360,15 @@ fn merge_credentials(target: &mut KiroCredentials, source: &KiroCredentials) {
     if source.last_refresh.is_some() {
         target.last_refresh = source.last_refresh.clone();
     }
+    if source.agent_mode.is_some() {
+        target.agent_mode = source.agent_mode.clone();
+    }
+    if source.origin.is_some() {
+        target.origin = source.origin.clone();
+    }
+    if !source.profiles.is_empty() {
+        target.profiles = source.profiles.clone();
+    }
     // cred_type 使用默认值，不需要合并
 }
 
@@ -1111,8 +1399,8 @@ impl CredentialProvider for KiroProvider {
         KiroProvider::refresh_token(self).await
     }
 
-    fn get_access_token(&self) -> Option<&str> {
-        self.credentials.access_token.as_deref()
+    fn get_access_token(&self) -> Option<String> {
+        self.access_token()
     }
 
     fn provider_type(&self) -> &'static str {
@@ -1142,26 +1430,26 @@ impl StreamingProvider for KiroProvider {
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<StreamResponse, ProviderError> {
-        let token = self
-            .credentials
-            .access_token
-            .as_ref()
-            .ok_or_else(|| ProviderError::AuthenticationError("No access token".to_string()))?;
-
-        let profile_arn = if self.credentials.auth_method.as_deref() == Some("social") {
-            self.credentials.profile_arn.clone()
-        } else {
-            None
+        let (token, client_id) = {
+            let creds = self.credentials.read();
+            let token = creds
+                .access_token
+                .clone()
+                .ok_or_else(|| ProviderError::AuthenticationError("No access token".to_string()))?;
+            (token, creds.client_id.clone())
         };
 
-        let cw_request = convert_openai_to_codewhisperer(request, profile_arn.clone());
-        let url = self.get_base_url();
+        let profile = self.select_profile(&request.model);
+        let profile_arn = self.request_profile_arn(&profile);
+
+        let mut cw_request = convert_openai_to_codewhisperer(request, profile_arn.clone());
+        let (agent_mode, origin) = self.request_mode();
+        cw_request.set_origin(&origin);
+        let url = Self::build_api_url(profile.region());
 
         // 生成基于凭证的唯一 Machine ID
-        let machine_id = generate_machine_id_from_credentials(
-            profile_arn.as_deref(),
-            self.credentials.client_id.as_deref(),
-        );
+        let machine_id =
+            generate_machine_id_from_credentials(profile_arn.as_deref(), client_id.as_deref());
         let kiro_version = get_kiro_version();
         let (os_name, node_version) = get_system_runtime_info();
 
@@ -1179,7 +1467,7 @@ impl StreamingProvider for KiroProvider {
             .header("Accept", "application/vnd.amazon.eventstream")
             .header("amz-sdk-invocation-id", uuid::Uuid::new_v4().to_string())
             .header("amz-sdk-request", "attempt=1; max=1")
-            .header("x-amzn-kiro-agent-mode", "vibe")
+            .header(KIRO_AGENT_MODE_HEADER, &agent_mode)
             .header(
                 "x-amz-user-agent",
                 format!("aws-sdk-js/1.0.0 KiroIDE-{kiro_version}-{machine_id}"),
@@ -1196,8 +1484,10 @@ impl StreamingProvider for KiroProvider {
             .await
             .map_err(|e| {
                 tracing::error!("[KIRO_STREAM] 请求发送失败: {}", e);
+                record_region_result(profile.region(), false);
                 ProviderError::from_reqwest_error(&e)
             })?;
+        record_region_result_for_status(profile.region(), resp.status());
 
         tracing::info!("[KIRO_STREAM] 收到响应: status={}", resp.status());
 
@@ -1241,27 +1531,27 @@ impl KiroProvider {
         &self,
         request: &AnthropicMessagesRequest,
     ) -> Result<StreamResponse, ProviderError> {
-        let token = self
-            .credentials
-            .access_token
-            .as_ref()
-            .ok_or_else(|| ProviderError::AuthenticationError("No access token".to_string()))?;
-
-        let profile_arn = if self.credentials.auth_method.as_deref() == Some("social") {
-            self.credentials.profile_arn.clone()
-        } else {
-            None
+        let (token, client_id) = {
+            let creds = self.credentials.read();
+            let token = creds
+                .access_token
+                .clone()
+                .ok_or_else(|| ProviderError::AuthenticationError("No access token".to_string()))?;
+            (token, creds.client_id.clone())
         };
 
+        let profile = self.select_profile(&request.model);
+        let profile_arn = self.request_profile_arn(&profile);
+
         // 直接转换 Anthropic → CodeWhisperer（不经过 OpenAI）
-        let cw_request = convert_anthropic_to_codewhisperer(request, profile_arn.clone());
-        let url = self.get_base_url();
+        let mut cw_request = convert_anthropic_to_codewhisperer(request, profile_arn.clone());
+        let (agent_mode, origin) = self.request_mode();
+        cw_request.set_origin(&origin);
+        let url = Self::build_api_url(profile.region());
 
         // 生成基于凭证的唯一 Machine ID
-        let machine_id = generate_machine_id_from_credentials(
-            profile_arn.as_deref(),
-            self.credentials.client_id.as_deref(),
-        );
+        let machine_id =
+            generate_machine_id_from_credentials(profile_arn.as_deref(), client_id.as_deref());
         let kiro_version = get_kiro_version();
         let (os_name, node_version) = get_system_runtime_info();
 
@@ -1279,7 +1569,7 @@ impl KiroProvider {
             .header("Accept", "application/vnd.amazon.eventstream")
             .header("amz-sdk-invocation-id", uuid::Uuid::new_v4().to_string())
             .header("amz-sdk-request", "attempt=1; max=1")
-            .header("x-amzn-kiro-agent-mode", "vibe")
+            .header(KIRO_AGENT_MODE_HEADER, &agent_mode)
             .header(
                 "x-amz-user-agent",
                 format!("aws-sdk-js/1.0.0 KiroIDE-{kiro_version}-{machine_id}"),
@@ -1295,8 +1585,10 @@ impl KiroProvider {
             .await
             .map_err(|e| {
                 tracing::error!("[KIRO_STREAM_ANTHROPIC] 请求发送失败: {}", e);
+                record_region_result(profile.region(), false);
                 ProviderError::from_reqwest_error(&e)
             })?;
+        record_region_result_for_status(profile.region(), resp.status());
 
         tracing::info!("[KIRO_STREAM_ANTHROPIC] 收到响应: status={}", resp.status());
 
@@ -0,0 +1,75 @@
+//! Mistral Provider
+//!
+//! Mistral 使用 OpenAI 兼容协议，聊天请求复用 `OpenAICustomProvider`，
+//! 本模块负责默认地址以及发送前的请求适配。
+
+use crate::models::openai::ChatCompletionRequest;
+
+/// Mistral 默认 API 地址（不含 /v1，由调用方按 OpenAI 规则拼接）
+pub const MISTRAL_BASE_URL: &str = "https://api.mistral.ai";
+
+/// Mistral 要求的 tool_call id 长度
+const MISTRAL_TOOL_CALL_ID_LEN: usize = 9;
+
+const ID_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// 将任意 tool_call id 转换为 Mistral 接受的格式（9 位字母数字）
+///
+/// 使用稳定的 FNV-1a 哈希，保证同一 id 在多轮对话中映射结果一致，
+/// assistant 的 tool_calls 与 tool 消息的 tool_call_id 才能对应上。
+pub fn normalize_tool_call_id(id: &str) -> String {
+    if id.len() == MISTRAL_TOOL_CALL_ID_LEN && id.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return id.to_string();
+    }
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in id.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    (0..MISTRAL_TOOL_CALL_ID_LEN)
+        .map(|_| {
+            let c = ID_ALPHABET[(hash % ID_ALPHABET.len() as u64) as usize] as char;
+            hash /= ID_ALPHABET.len() as u64;
+            c
+        })
+        .collect()
+}
+
+/// 适配 Mistral 请求
+///
+/// - tool_call id 必须为 9 位字母数字（Claude Code 的 `toolu_xxx` 会被拒绝）
+/// - 不支持 `reasoning_effort` 参数
+pub fn adapt_request(request: &mut ChatCompletionRequest) {
+    request.reasoning_effort = None;
+
+    for message in &mut request.messages {
+        if let Some(tool_calls) = &mut message.tool_calls {
+            for call in tool_calls {
+                call.id = normalize_tool_call_id(&call.id);
+            }
+        }
+        if let Some(id) = &mut message.tool_call_id {
+            *id = normalize_tool_call_id(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tool_call_id() {
+        // 已符合格式的 id 保持不变
+        assert_eq!(normalize_tool_call_id("abc123XYZ"), "abc123XYZ");
+
+        let id = normalize_tool_call_id("toolu_01A09q90qw90lq917835lq9");
+        assert_eq!(id.len(), MISTRAL_TOOL_CALL_ID_LEN);
+        assert!(id.bytes().all(|b| b.is_ascii_alphanumeric()));
+        // 映射结果稳定
+        assert_eq!(id, normalize_tool_call_id("toolu_01A09q90qw90lq917835lq9"));
+        assert_ne!(id, normalize_tool_call_id("toolu_01A09q90qw90lq917835lq8"));
+    }
+}
@@ -0,0 +1,530 @@
+//! 内置 mock Provider
+//!
+//! 不访问上游，按请求返回确定性的模拟响应，用于测试客户端集成和路由规则：
+//! - 默认回显最后一条用户消息（或返回配置的固定回复）
+//! - 模型名包含 `tool` 且请求定义了工具时，调用第一个工具（参数按 schema 的必填字段生成占位值）
+//! - 模型名包含 `error-<状态码>`（如 `mock-error-429`）时返回对应状态码的错误
+//!
+//! 通过 `default_provider`、端点 Provider、`X-Provider-Id: mock` 或 `/mock/v1/...` 路由选择。
+
+use crate::config::MockProviderConfig;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// mock Provider 的路由名称
+pub const MOCK_PROVIDER_ID: &str = "mock";
+
+/// 未配置回复且没有用户消息时的回复
+const DEFAULT_REPLY: &str = "This is a mock response from ProxyCast.";
+
+/// 是否选中了 mock Provider
+pub fn is_mock_provider(provider: &str) -> bool {
+    provider.eq_ignore_ascii_case(MOCK_PROVIDER_ID)
+}
+
+/// 模拟场景（由模型名决定）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockScenario {
+    /// 文本回复
+    Text,
+    /// 调用第一个工具
+    ToolCall,
+    /// 返回指定状态码的错误
+    Error(u16),
+}
+
+impl MockScenario {
+    pub fn from_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        let status = model.split("error-").nth(1).and_then(|rest| {
+            rest.get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .filter(|code| (400..600).contains(code))
+        });
+        if let Some(status) = status {
+            Self::Error(status)
+        } else if model.contains("tool") {
+            Self::ToolCall
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// 模拟的工具调用
+struct MockToolCall {
+    name: String,
+    arguments: Value,
+}
+
+/// 内置 mock Provider
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    config: MockProviderConfig,
+}
+
+impl MockProvider {
+    pub fn new(config: MockProviderConfig) -> Self {
+        Self { config }
+    }
+
+    /// OpenAI Chat Completions 模拟响应
+    pub async fn chat_completions(&self, request: &ChatCompletionRequest) -> Response {
+        self.wait_latency().await;
+        let scenario = MockScenario::from_model(&request.model);
+        if let MockScenario::Error(status) = scenario {
+            return openai_error(status);
+        }
+
+        let tools: Vec<(String, Option<Value>)> = request
+            .tools
+            .iter()
+            .flatten()
+            .filter_map(|tool| match tool {
+                crate::models::openai::Tool::Function { function } => {
+                    Some((function.name.clone(), function.parameters.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        let tool_call = self.tool_call(scenario, &tools);
+        let last_user = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| m.get_content_text())
+            .unwrap_or_default();
+        let text = self.reply_text(&last_user);
+        let prompt_tokens =
+            estimate_tokens(&serde_json::to_string(&request.messages).unwrap_or_default());
+
+        let message = match &tool_call {
+            Some(call) => json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_mock_0",
+                    "type": "function",
+                    "function": {"name": call.name, "arguments": call.arguments.to_string()}
+                }]
+            }),
+            None => json!({"role": "assistant", "content": text}),
+        };
+        let completion_tokens = match &tool_call {
+            Some(call) => estimate_tokens(&call.arguments.to_string()),
+            None => estimate_tokens(&text),
+        };
+        let completion = json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": chrono::Utc::now().timestamp(),
+            "model": request.model,
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "finish_reason": if tool_call.is_some() { "tool_calls" } else { "stop" }
+            }],
+            "usage": {
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens
+            }
+        });
+
+        if request.stream {
+            let events = crate::server_utils::openai_completion_to_chunks(
+                &completion,
+                request.include_usage(),
+            );
+            self.sse_response(events)
+        } else {
+            Json(completion).into_response()
+        }
+    }
+
+    /// Anthropic Messages 模拟响应
+    pub async fn anthropic_messages(&self, request: &AnthropicMessagesRequest) -> Response {
+        self.wait_latency().await;
+        let scenario = MockScenario::from_model(&request.model);
+        if let MockScenario::Error(status) = scenario {
+            return anthropic_error(status);
+        }
+
+        let tools: Vec<(String, Option<Value>)> = request
+            .tools
+            .iter()
+            .flatten()
+            .filter(|tool| tool.tool_type.as_deref().is_none_or(|t| t == "custom"))
+            .map(|tool| (tool.name.clone(), tool.input_schema.clone()))
+            .collect();
+        let tool_call = self.tool_call(scenario, &tools);
+        let last_user = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| anthropic_content_text(&m.content))
+            .unwrap_or_default();
+        let text = self.reply_text(&last_user);
+        let input_tokens =
+            estimate_tokens(&serde_json::to_string(&request.messages).unwrap_or_default());
+
+        let (content_block, delta, stop_reason, output_tokens) = match &tool_call {
+            Some(call) => (
+                json!({
+                    "type": "tool_use",
+                    "id": "toolu_mock_0",
+                    "name": call.name,
+                    "input": call.arguments
+                }),
+                json!({"type": "input_json_delta", "partial_json": call.arguments.to_string()}),
+                "tool_use",
+                estimate_tokens(&call.arguments.to_string()),
+            ),
+            None => (
+                json!({"type": "text", "text": text}),
+                json!({"type": "text_delta", "text": text}),
+                "end_turn",
+                estimate_tokens(&text),
+            ),
+        };
+
+        if !request.stream {
+            return Json(json!({
+                "id": "msg_mock",
+                "type": "message",
+                "role": "assistant",
+                "model": request.model,
+                "content": [content_block],
+                "stop_reason": stop_reason,
+                "stop_sequence": null,
+                "usage": {"input_tokens": input_tokens, "output_tokens": output_tokens}
+            }))
+            .into_response();
+        }
+
+        // 流式响应中内容块以空内容开始，由 delta 事件补全
+        let start_block = match &tool_call {
+            Some(call) => {
+                json!({"type": "tool_use", "id": "toolu_mock_0", "name": call.name, "input": {}})
+            }
+            None => json!({"type": "text", "text": ""}),
+        };
+        let events = [
+            (
+                "message_start",
+                json!({
+                    "type": "message_start",
+                    "message": {
+                        "id": "msg_mock",
+                        "type": "message",
+                        "role": "assistant",
+                        "model": request.model,
+                        "content": [],
+                        "stop_reason": null,
+                        "stop_sequence": null,
+                        "usage": {"input_tokens": input_tokens, "output_tokens": 0}
+                    }
+                }),
+            ),
+            (
+                "content_block_start",
+                json!({"type": "content_block_start", "index": 0, "content_block": start_block}),
+            ),
+            (
+                "content_block_delta",
+                json!({"type": "content_block_delta", "index": 0, "delta": delta}),
+            ),
+            (
+                "content_block_stop",
+                json!({"type": "content_block_stop", "index": 0}),
+            ),
+            (
+                "message_delta",
+                json!({
+                    "type": "message_delta",
+                    "delta": {"stop_reason": stop_reason, "stop_sequence": null},
+                    "usage": {"output_tokens": output_tokens}
+                }),
+            ),
+            ("message_stop", json!({"type": "message_stop"})),
+        ]
+        .into_iter()
+        .map(|(event, data)| format!("event: {}\ndata: {}\n\n", event, data))
+        .collect();
+        self.sse_response(events)
+    }
+
+    async fn wait_latency(&self) {
+        if self.config.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.latency_ms)).await;
+        }
+    }
+
+    /// 回复文本：优先使用配置的固定回复，否则回显用户消息
+    fn reply_text(&self, last_user: &str) -> String {
+        match &self.config.reply {
+            Some(reply) => reply.clone(),
+            None if !last_user.trim().is_empty() => last_user.to_string(),
+            None => DEFAULT_REPLY.to_string(),
+        }
+    }
+
+    /// 工具调用场景下调用第一个工具（请求未定义工具时返回文本）
+    fn tool_call(
+        &self,
+        scenario: MockScenario,
+        tools: &[(String, Option<Value>)],
+    ) -> Option<MockToolCall> {
+        if scenario != MockScenario::ToolCall {
+            return None;
+        }
+        tools.first().map(|(name, schema)| MockToolCall {
+            name: name.clone(),
+            arguments: placeholder_arguments(schema.as_ref()),
+        })
+    }
+
+    /// 按配置的间隔依次输出 SSE 事件
+    fn sse_response(&self, events: Vec<String>) -> Response {
+        let delay = Duration::from_millis(self.config.chunk_delay_ms);
+        let stream = futures::stream::iter(events.into_iter().enumerate()).then(
+            move |(index, event)| async move {
+                if index > 0 && !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                Ok::<_, std::convert::Infallible>(Bytes::from(event))
+            },
+        );
+        let mut response = Response::new(Body::from_stream(stream));
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        response
+    }
+}
+
+/// 按 JSON Schema 的必填字段生成占位参数
+fn placeholder_arguments(schema: Option<&Value>) -> Value {
+    let Some(schema) = schema else {
+        return json!({});
+    };
+    let mut arguments = serde_json::Map::new();
+    for name in schema["required"].as_array().into_iter().flatten() {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let value = match schema["properties"][name]["type"].as_str() {
+            Some("integer") | Some("number") => json!(0),
+            Some("boolean") => json!(false),
+            Some("array") => json!([]),
+            Some("object") => json!({}),
+            _ => json!("mock"),
+        };
+        arguments.insert(name.to_string(), value);
+    }
+    Value::Object(arguments)
+}
+
+/// Anthropic 消息内容中的文本（字符串或 text 内容块）
+fn anthropic_content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 估算 Token 数（约 4 字符 = 1 token，至少为 1）
+fn estimate_tokens(text: &str) -> u32 {
+    (text.len() / 4).max(1) as u32
+}
+
+/// 状态码对应的错误类型
+fn error_type(status: u16) -> &'static str {
+    match status {
+        400 | 413 | 422 => "invalid_request_error",
+        401 => "authentication_error",
+        403 => "permission_error",
+        404 => "not_found_error",
+        429 => "rate_limit_error",
+        529 => "overloaded_error",
+        _ => "api_error",
+    }
+}
+
+/// 错误响应（429 附带 `retry-after`，便于测试客户端重试）
+fn error_response(status: u16, body: Value) -> Response {
+    let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut response = (status, Json(body)).into_response();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    }
+    response
+}
+
+fn openai_error(status: u16) -> Response {
+    error_response(
+        status,
+        json!({
+            "error": {
+                "message": format!("Mock error (HTTP {})", status),
+                "type": error_type(status),
+                "code": status
+            }
+        }),
+    )
+}
+
+fn anthropic_error(status: u16) -> Response {
+    error_response(
+        status,
+        json!({
+            "type": "error",
+            "error": {
+                "type": error_type(status),
+                "message": format!("Mock error (HTTP {})", status)
+            }
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_json(response: Response) -> Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_scenario_from_model() {
+        assert_eq!(MockScenario::from_model("mock"), MockScenario::Text);
+        assert_eq!(
+            MockScenario::from_model("mock-tools"),
+            MockScenario::ToolCall
+        );
+        assert_eq!(
+            MockScenario::from_model("mock-error-429"),
+            MockScenario::Error(429)
+        );
+        assert_eq!(
+            MockScenario::from_model("mock-error-abc"),
+            MockScenario::Text
+        );
+        assert!(is_mock_provider("Mock"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_echo_and_tool_call() {
+        let provider = MockProvider::default();
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "mock",
+            "messages": [{"role": "user", "content": "hello mock"}]
+        }))
+        .unwrap();
+        let response = body_json(provider.chat_completions(&request).await).await;
+        assert_eq!(response["choices"][0]["message"]["content"], "hello mock");
+        assert_eq!(response["choices"][0]["finish_reason"], "stop");
+
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "mock-tool",
+            "stream": true,
+            "messages": [{"role": "user", "content": "weather?"}],
+            "tools": [{"type": "function", "function": {
+                "name": "get_weather",
+                "parameters": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}, "days": {"type": "integer"}},
+                    "required": ["city", "days"]
+                }
+            }}]
+        }))
+        .unwrap();
+        let response = provider.chat_completions(&request).await;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let text = body_text(response).await;
+        assert!(text.contains("get_weather"));
+        assert!(text.contains(r#"{\"city\":\"mock\",\"days\":0}"#));
+        assert!(text.contains("\"finish_reason\":\"tool_calls\""));
+        assert!(text.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_reply_stream_and_error() {
+        let provider = MockProvider::new(MockProviderConfig {
+            reply: Some("fixed".to_string()),
+            ..MockProviderConfig::default()
+        });
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "mock",
+            "max_tokens": 16,
+            "stream": true,
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "hi"}]}]
+        }))
+        .unwrap();
+        let text = body_text(provider.anthropic_messages(&request).await).await;
+        let events: Vec<&str> = text
+            .split("\n\n")
+            .filter_map(|event| event.lines().next()?.strip_prefix("event: "))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+        assert!(text.contains(r#""text":"fixed""#));
+
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "mock-error-429",
+            "max_tokens": 16,
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap();
+        let response = provider.anthropic_messages(&request).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        assert_eq!(
+            body_json(response).await["error"]["type"],
+            "rate_limit_error"
+        );
+    }
+}
@@ -2,12 +2,17 @@ pub mod antigravity;
 pub mod claude_custom;
 pub mod claude_oauth;
 pub mod codex;
+pub mod deepseek;
 pub mod error;
 pub mod gemini;
 pub mod iflow;
 pub mod kiro;
+pub mod mistral;
+pub mod mock;
 pub mod openai_custom;
+pub mod openrouter;
 pub mod qwen;
+pub mod tls;
 pub mod traits;
 pub mod vertex;
 
@@ -41,6 +46,8 @@ pub use kiro::KiroProvider;
 #[allow(unused_imports)]
 pub use openai_custom::OpenAICustomProvider;
 #[allow(unused_imports)]
+pub use openrouter::OpenRouterProvider;
+#[allow(unused_imports)]
 pub use qwen::QwenProvider;
 #[allow(unused_imports)]
 pub use vertex::VertexProvider;
@@ -1,4 +1,5 @@
 //! OpenAI Custom Provider (自定义 OpenAI 兼容 API)
+use crate::middleware::{HeaderPassthroughExt, RequestIdExt};
 use crate::models::openai::ChatCompletionRequest;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -19,7 +20,7 @@ pub struct OpenAICustomProvider {
 
 /// 创建配置好的 HTTP 客户端
 fn create_http_client() -> Client {
-    Client::builder()
+    super::tls::client_builder(crate::ProviderType::OpenAI)
         .connect_timeout(Duration::from_secs(30))
         .timeout(Duration::from_secs(600)) // 10 分钟总超时
         .tcp_keepalive(Duration::from_secs(60))
@@ -118,6 +119,8 @@ impl OpenAICustomProvider {
             .post(&url)
             .header("Authorization", format!("Bearer {api_key}"))
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("openai")
             .json(request)
             .send()
             .await?;
@@ -125,6 +128,62 @@ impl OpenAICustomProvider {
         Ok(resp)
     }
 
+    /// 透传原始请求体（不做序列化）
+    pub async fn call_api_raw(
+        &self,
+        body: bytes::Bytes,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+
+        let url = self.build_url("chat/completions");
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("openai")
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
+    /// 透传音频请求（`audio/transcriptions` 为 multipart，`audio/speech` 为 JSON）
+    pub async fn call_audio_raw(
+        &self,
+        endpoint: &str,
+        content_type: &str,
+        body: bytes::Bytes,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+
+        let url = self.build_url(endpoint);
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("Content-Type", content_type)
+            .with_request_id()
+            .with_passthrough_headers("openai")
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
     pub async fn chat_completions(
         &self,
         request: &serde_json::Value,
@@ -148,6 +207,8 @@ impl OpenAICustomProvider {
             .post(&url)
             .header("Authorization", format!("Bearer {api_key}"))
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("openai")
             .json(request)
             .send()
             .await?;
@@ -229,6 +290,8 @@ impl StreamingProvider for OpenAICustomProvider {
             .post(&url)
             .header("Authorization", format!("Bearer {api_key}"))
             .header("Content-Type", "application/json")
+            .with_request_id()
+            .with_passthrough_headers("openai")
             .header("Accept", "text/event-stream")
             .json(&stream_request)
             .send()
@@ -0,0 +1,259 @@
+//! OpenRouter Provider
+//!
+//! OpenRouter 使用 OpenAI 兼容协议，聊天请求复用 `OpenAICustomProvider`，
+//! 本模块负责默认地址和模型目录（含价格、上下文长度）的拉取与转换。
+
+use crate::models::model_registry::{
+    EnhancedModelMetadata, ModelCapabilities, ModelLimits, ModelPricing, ModelSource,
+};
+use reqwest::Client;
+use serde::Deserialize;
+use std::error::Error;
+use std::time::Duration;
+
+/// OpenRouter 默认 API 地址（不含 /v1，由调用方按 OpenAI 规则拼接）
+pub const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api";
+
+/// 模型注册表中 OpenRouter 的 Provider ID
+pub const OPENROUTER_PROVIDER_ID: &str = "openrouter";
+
+/// 模型目录同步间隔（6 小时）
+#[cfg(feature = "desktop")]
+pub const OPENROUTER_CATALOG_SYNC_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// `/models` 接口响应
+#[derive(Debug, Deserialize)]
+struct OpenRouterModelList {
+    data: Vec<OpenRouterModel>,
+}
+
+/// OpenRouter 模型目录条目
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenRouterModel {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u32>,
+    #[serde(default)]
+    pub pricing: Option<OpenRouterPricing>,
+    #[serde(default)]
+    pub top_provider: Option<OpenRouterTopProvider>,
+    #[serde(default)]
+    pub architecture: Option<OpenRouterArchitecture>,
+    #[serde(default)]
+    pub supported_parameters: Vec<String>,
+}
+
+/// OpenRouter 价格（字符串形式的每 token 美元价格）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpenRouterPricing {
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub completion: Option<String>,
+    #[serde(default)]
+    pub input_cache_read: Option<String>,
+    #[serde(default)]
+    pub input_cache_write: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpenRouterTopProvider {
+    #[serde(default)]
+    pub max_completion_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpenRouterArchitecture {
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
+}
+
+/// 将每 token 价格转换为每百万 token 价格
+fn per_million(price: &Option<String>) -> Option<f64> {
+    price
+        .as_deref()
+        .and_then(|p| p.parse::<f64>().ok())
+        .filter(|p| *p >= 0.0)
+        .map(|p| p * 1_000_000.0)
+}
+
+impl OpenRouterModel {
+    fn supports_parameter(&self, name: &str) -> bool {
+        self.supported_parameters.iter().any(|p| p == name)
+    }
+
+    /// 转换为模型注册表的元数据
+    pub fn to_model_metadata(&self, now: i64) -> EnhancedModelMetadata {
+        let vision = self
+            .architecture
+            .as_ref()
+            .map(|a| a.input_modalities.iter().any(|m| m == "image"))
+            .unwrap_or(false);
+        let tools = self.supports_parameter("tools");
+
+        let pricing = self.pricing.as_ref().map(|p| ModelPricing {
+            input_per_million: per_million(&p.prompt),
+            output_per_million: per_million(&p.completion),
+            cache_read_per_million: per_million(&p.input_cache_read),
+            cache_write_per_million: per_million(&p.input_cache_write),
+            currency: "USD".to_string(),
+        });
+
+        // OpenRouter 模型 ID 形如 "anthropic/claude-sonnet-4.5"，斜杠前为模型家族来源
+        let family = self
+            .id
+            .split_once('/')
+            .map(|(vendor, _)| vendor.to_string());
+
+        EnhancedModelMetadata {
+            id: self.id.clone(),
+            display_name: self.name.clone().unwrap_or_else(|| self.id.clone()),
+            provider_id: OPENROUTER_PROVIDER_ID.to_string(),
+            provider_name: "OpenRouter".to_string(),
+            family,
+            tier: Default::default(),
+            capabilities: ModelCapabilities {
+                vision,
+                tools,
+                streaming: true,
+                json_mode: self.supports_parameter("response_format"),
+                function_calling: tools,
+                reasoning: self.supports_parameter("reasoning"),
+            },
+            pricing,
+            limits: ModelLimits {
+                context_length: self.context_length,
+                max_output_tokens: self
+                    .top_provider
+                    .as_ref()
+                    .and_then(|t| t.max_completion_tokens),
+                requests_per_minute: None,
+                tokens_per_minute: None,
+            },
+            status: Default::default(),
+            release_date: None,
+            is_latest: false,
+            description: self.description.clone(),
+            source: ModelSource::Remote,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// OpenRouter Provider
+pub struct OpenRouterProvider {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub client: Client,
+}
+
+impl OpenRouterProvider {
+    pub fn new(api_key: Option<String>, base_url: Option<String>) -> Self {
+        Self {
+            api_key,
+            base_url,
+            client: super::tls::client_builder(crate::ProviderType::OpenRouter)
+                .connect_timeout(Duration::from_secs(30))
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+        }
+    }
+
+    pub fn get_base_url(&self) -> String {
+        self.base_url
+            .clone()
+            .unwrap_or_else(|| OPENROUTER_BASE_URL.to_string())
+    }
+
+    /// 构建模型目录 URL，兼容带 /v1 和不带 /v1 的 base_url
+    fn models_url(&self) -> String {
+        let base = self.get_base_url();
+        let base = base.trim_end_matches('/');
+        if base.ends_with("/v1") {
+            format!("{}/models", base)
+        } else {
+            format!("{}/v1/models", base)
+        }
+    }
+
+    /// 拉取 OpenRouter 模型目录
+    ///
+    /// `/models` 是公开接口，有 API Key 时附带认证以获取账号可见的模型。
+    pub async fn fetch_model_catalog(
+        &self,
+    ) -> Result<Vec<OpenRouterModel>, Box<dyn Error + Send + Sync>> {
+        let url = self.models_url();
+        tracing::debug!("[OPENROUTER] 拉取模型目录: {}", url);
+
+        let mut req = self.client.get(&url);
+        if let Some(key) = &self.api_key {
+            req = req.header("Authorization", format!("Bearer {key}"));
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("Failed to fetch OpenRouter models: {status} - {body}").into());
+        }
+
+        let list: OpenRouterModelList = resp.json().await?;
+        Ok(list.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_models_url() {
+        let provider = OpenRouterProvider::new(None, None);
+        assert_eq!(provider.models_url(), "https://openrouter.ai/api/v1/models");
+
+        let provider =
+            OpenRouterProvider::new(None, Some("https://openrouter.ai/api/v1/".to_string()));
+        assert_eq!(provider.models_url(), "https://openrouter.ai/api/v1/models");
+    }
+
+    #[test]
+    fn test_to_model_metadata() {
+        let json = serde_json::json!({
+            "id": "anthropic/claude-sonnet-4.5",
+            "name": "Anthropic: Claude Sonnet 4.5",
+            "context_length": 200000,
+            "pricing": {"prompt": "0.000003", "completion": "0.000015"},
+            "top_provider": {"max_completion_tokens": 64000},
+            "architecture": {"input_modalities": ["text", "image"]},
+            "supported_parameters": ["tools", "reasoning", "max_tokens"]
+        });
+        let model: OpenRouterModel = serde_json::from_value(json).unwrap();
+        let meta = model.to_model_metadata(0);
+
+        assert_eq!(meta.provider_id, OPENROUTER_PROVIDER_ID);
+        assert_eq!(meta.family.as_deref(), Some("anthropic"));
+        assert!(meta.capabilities.vision);
+        assert!(meta.capabilities.tools);
+        assert!(meta.capabilities.reasoning);
+        assert!(!meta.capabilities.json_mode);
+        assert_eq!(meta.limits.context_length, Some(200000));
+        assert_eq!(meta.limits.max_output_tokens, Some(64000));
+
+        let pricing = meta.pricing.unwrap();
+        assert!((pricing.input_per_million.unwrap() - 3.0).abs() < 1e-9);
+        assert!((pricing.output_per_million.unwrap() - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_negative_price_is_ignored() {
+        // OpenRouter 对可变价格的路由模型（如 openrouter/auto）返回 "-1"
+        assert_eq!(per_million(&Some("-1".to_string())), None);
+        assert_eq!(per_million(&None), None);
+    }
+}
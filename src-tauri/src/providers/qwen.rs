@@ -79,7 +79,7 @@ impl Default for QwenProvider {
     fn default() -> Self {
         Self {
             credentials: QwenCredentials::default(),
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::Qwen),
         }
     }
 }
@@ -287,6 +287,17 @@ impl QwenProvider {
         }
     }
 
+    /// 生成发送给 Qwen 的请求体（不支持的模型替换为默认模型）
+    pub fn prepare_request(request: &serde_json::Value) -> serde_json::Value {
+        let mut req_body = request.clone();
+        if let Some(model) = req_body.get("model").and_then(|m| m.as_str()) {
+            if !QWEN_MODELS.contains(&model) {
+                req_body["model"] = serde_json::json!(QWEN_MODELS[0]);
+            }
+        }
+        req_body
+    }
+
     pub async fn chat_completions(
         &self,
         request: &serde_json::Value,
@@ -300,13 +311,7 @@ impl QwenProvider {
         let base_url = self.get_base_url();
         let url = format!("{base_url}/chat/completions");
 
-        // Ensure model is valid
-        let mut req_body = request.clone();
-        if let Some(model) = req_body.get("model").and_then(|m| m.as_str()) {
-            if !QWEN_MODELS.contains(&model) {
-                req_body["model"] = serde_json::json!(QWEN_MODELS[0]);
-            }
-        }
+        let req_body = Self::prepare_request(request);
 
         let resp = self
             .client
@@ -545,6 +550,7 @@ pub async fn poll_for_token(
 }
 
 /// 启动 Qwen Device Code Flow 登录
+#[cfg(feature = "desktop")]
 pub async fn start_qwen_device_code_login() -> Result<QwenOAuthResult, Box<dyn Error + Send + Sync>>
 {
     let client = Client::builder()
@@ -697,8 +703,8 @@ impl CredentialProvider for QwenProvider {
         QwenProvider::refresh_token(self).await
     }
 
-    fn get_access_token(&self) -> Option<&str> {
-        self.credentials.access_token.as_deref()
+    fn get_access_token(&self) -> Option<String> {
+        self.credentials.access_token.clone()
     }
 
     fn provider_type(&self) -> &'static str {
@@ -999,4 +999,100 @@ mod unit_tests {
             "Token expiring in 2 mins should need refresh with 5 min lead time"
         );
     }
+
+    #[test]
+    fn test_kiro_request_mode() {
+        use crate::providers::kiro::{KiroCredentials, KiroProvider};
+
+        let provider = KiroProvider::new();
+        assert_eq!(
+            provider.request_mode(),
+            ("vibe".to_string(), "AI_EDITOR".to_string())
+        );
+
+        // 凭证文件中的配置
+        *provider.credentials.write() = serde_json::from_str::<KiroCredentials>(
+            r#"{"accessToken":"t","agentMode":"spec","origin":"CLI"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            provider.request_mode(),
+            ("spec".to_string(), "CLI".to_string())
+        );
+    }
+
+    #[test]
+    fn test_kiro_select_profile() {
+        use crate::providers::kiro::{record_region_result, KiroCredentials, KiroProvider};
+
+        let provider = KiroProvider::new();
+        *provider.credentials.write() = serde_json::from_str::<KiroCredentials>(
+            r#"{"accessToken":"t","profileArn":"arn:primary","region":"test-primary-1"}"#,
+        )
+        .unwrap();
+        let profile = provider.select_profile("claude-sonnet-4-5");
+        assert_eq!(profile.profile_arn.as_deref(), Some("arn:primary"));
+        assert_eq!(profile.region(), "test-primary-1");
+
+        provider.credentials.write().profiles = serde_json::from_str(
+            r#"[
+                {"profileArn":"arn:a","region":"test-west-1","models":["claude-sonnet-4-*"]},
+                {"profileArn":"arn:b","region":"test-east-1"}
+            ]"#,
+        )
+        .unwrap();
+        let select = |model: &str| provider.select_profile(model).profile_arn.unwrap();
+        assert_eq!(select("claude-sonnet-4-5"), "arn:a");
+        assert_eq!(select("claude-opus-4-5"), "arn:b");
+
+        // 区域连续失败后切换到其他支持该模型的 profile，成功后恢复
+        for _ in 0..3 {
+            record_region_result("test-west-1", false);
+        }
+        assert_eq!(select("claude-sonnet-4-5"), "arn:b");
+        record_region_result("test-west-1", true);
+        assert_eq!(select("claude-sonnet-4-5"), "arn:a");
+    }
+
+    #[test]
+    fn test_kiro_clones_share_credentials() {
+        use crate::providers::kiro::KiroProvider;
+
+        let provider = KiroProvider::new();
+        let clone = provider.clone();
+        assert!(clone.access_token().is_none());
+
+        // 刷新后的 Token 对所有克隆可见
+        provider.set_access_token(Some("refreshed".to_string()));
+        assert_eq!(clone.access_token().as_deref(), Some("refreshed"));
+        clone.set_auth_method("idc");
+        assert_eq!(provider.detect_auth_method(), "idc");
+    }
+
+    #[tokio::test]
+    async fn test_kiro_pooled_provider_shared_per_file() {
+        use crate::providers::kiro::KiroProvider;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kiro.json");
+        std::fs::write(&path, r#"{"accessToken":"a","refreshToken":"r"}"#).unwrap();
+        let path_str = path.to_str().unwrap();
+
+        // 同一凭证文件的请求共享凭证状态
+        let first = KiroProvider::pooled(path_str).await.unwrap();
+        let second = KiroProvider::pooled(path_str).await.unwrap();
+        first.set_access_token(Some("refreshed".to_string()));
+        assert_eq!(second.access_token().as_deref(), Some("refreshed"));
+
+        // 凭证文件被修改后重新加载
+        std::fs::write(&path, r#"{"accessToken":"b","refreshToken":"r"}"#).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+        let reloaded = KiroProvider::pooled(path_str).await.unwrap();
+        assert_eq!(reloaded.access_token().as_deref(), Some("b"));
+    }
 }
@@ -0,0 +1,476 @@
+//! 上游 TLS 配置
+//!
+//! 按 Provider 为上游 HTTPS 请求定制证书校验（`upstream_tls.providers`，`*` 匹配未单独配置的 Provider）：
+//! - `ca_file`：额外信任的 CA 证书（企业 TLS 拦截代理）
+//! - `disable_system_roots`：不信任内置根证书，只信任 `ca_file`
+//! - `pinned_spki_sha256`：证书公钥固定，校验通过的证书链中任一证书的 SPKI SHA-256 匹配才允许连接
+//!
+//! Provider 的 HTTP 客户端通过 [`client_builder`] 创建，配置变化后新建的客户端生效；
+//! 缓存客户端的 Provider 通过 [`generation`] 判断是否需要重建。
+
+use crate::config::{expand_tilde, ProviderTlsConfig, UpstreamTlsConfig};
+use crate::ProviderType;
+use base64::Engine;
+use parking_lot::RwLock;
+use reqwest::Client;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+static UPSTREAM_TLS: OnceLock<RwLock<HashMap<String, ClientConfig>>> = OnceLock::new();
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn registry() -> &'static RwLock<HashMap<String, ClientConfig>> {
+    UPSTREAM_TLS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// 应用上游 TLS 配置（构建失败的 Provider 记录错误后使用默认 TLS 设置）
+pub fn set_upstream_tls_config(config: &UpstreamTlsConfig) {
+    let mut configs = HashMap::new();
+    for (provider, options) in &config.providers {
+        if !is_customized(options) {
+            continue;
+        }
+        match build_client_config(options) {
+            Ok(tls) => {
+                configs.insert(provider.clone(), tls);
+            }
+            Err(e) => tracing::error!(
+                "[UPSTREAM_TLS] Provider {} 的 TLS 配置无效: {}",
+                provider,
+                e
+            ),
+        }
+    }
+    *registry().write() = configs;
+    GENERATION.fetch_add(1, Ordering::Release);
+}
+
+/// 上游 TLS 配置版本，每次应用配置后递增
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// 校验上游 TLS 配置
+pub fn validate_upstream_tls_config(config: &UpstreamTlsConfig) -> Result<(), String> {
+    for (provider, options) in &config.providers {
+        if is_customized(options) {
+            build_client_config(options).map_err(|e| format!("Provider {}: {}", provider, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 创建 Provider 的 HTTP 客户端构建器（带上游连通性负缓存和 TLS 配置）
+pub fn client_builder(provider: ProviderType) -> reqwest::ClientBuilder {
+    let builder = crate::resilience::client_builder();
+    let registry = registry().read();
+    match registry
+        .get(&provider.to_string())
+        .or_else(|| registry.get("*"))
+    {
+        Some(tls) => builder.use_preconfigured_tls(tls.clone()),
+        None => builder,
+    }
+}
+
+/// 创建 Provider 的默认 HTTP 客户端
+pub fn default_client(provider: ProviderType) -> Client {
+    client_builder(provider)
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+fn is_customized(options: &ProviderTlsConfig) -> bool {
+    options.ca_file.is_some()
+        || options.disable_system_roots
+        || !options.pinned_spki_sha256.is_empty()
+}
+
+/// 构建 rustls 客户端配置
+fn build_client_config(options: &ProviderTlsConfig) -> Result<ClientConfig, String> {
+    let mut roots = RootCertStore::empty();
+    if !options.disable_system_roots {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    }
+    if let Some(path) = &options.ca_file {
+        let path = expand_tilde(path);
+        let pem = std::fs::read(&path)
+            .map_err(|e| format!("读取 CA 证书文件 {} 失败: {}", path.display(), e))?;
+        let certs = rustls_pemfile::certs(&mut pem.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("解析 CA 证书文件 {} 失败: {}", path.display(), e))?;
+        if certs.is_empty() {
+            return Err(format!("CA 证书文件 {} 中没有证书", path.display()));
+        }
+        for cert in certs {
+            roots
+                .add(cert)
+                .map_err(|e| format!("CA 证书文件 {} 中的证书无效: {}", path.display(), e))?;
+        }
+    }
+    if roots.is_empty() {
+        return Err("禁用内置根证书时必须配置 ca_file".to_string());
+    }
+
+    let pins = options
+        .pinned_spki_sha256
+        .iter()
+        .map(|pin| parse_pin(pin))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = PinnedServerVerifier::new(roots, pins, &provider)?;
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("创建 TLS 配置失败: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Ok(config)
+}
+
+/// 解析 Base64 编码的 SPKI SHA-256
+fn parse_pin(pin: &str) -> Result<[u8; 32], String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(pin.trim().trim_start_matches("sha256/"))
+        .map_err(|_| format!("无效的公钥固定值: {}", pin))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("公钥固定值必须是 32 字节的 SHA-256: {}", pin))
+}
+
+/// SPKI（DER 编码）的 SHA-256
+fn spki_sha256(spki: &[u8]) -> [u8; 32] {
+    Sha256::digest(spki).into()
+}
+
+/// 为 DER 内容补上 SEQUENCE 头部（信任锚只保存 SPKI 的内容部分）
+fn der_sequence(content: &[u8]) -> Vec<u8> {
+    let mut der = vec![0x30];
+    if content.len() < 0x80 {
+        der.push(content.len() as u8);
+    } else {
+        let len: Vec<u8> = content
+            .len()
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        der.push(0x80 | len.len() as u8);
+        der.extend(len);
+    }
+    der.extend_from_slice(content);
+    der
+}
+
+/// 在标准证书链校验之后检查公钥固定
+#[derive(Debug)]
+struct PinnedServerVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    roots: Arc<RootCertStore>,
+    algorithms: WebPkiSupportedAlgorithms,
+    pins: Vec<[u8; 32]>,
+}
+
+impl PinnedServerVerifier {
+    fn new(
+        roots: RootCertStore,
+        pins: Vec<[u8; 32]>,
+        provider: &Arc<CryptoProvider>,
+    ) -> Result<Self, String> {
+        let roots = Arc::new(roots);
+        let inner = WebPkiServerVerifier::builder_with_provider(roots.clone(), provider.clone())
+            .build()
+            .map_err(|e| format!("创建证书校验器失败: {}", e))?;
+        Ok(Self {
+            inner,
+            roots,
+            algorithms: provider.signature_verification_algorithms,
+            pins,
+        })
+    }
+
+    /// 证书链中是否有证书匹配固定值
+    ///
+    /// 只检查 webpki 实际校验通过的证书链，对端附带的其他证书（例如与证书链无关的固定中间证书）不参与匹配。
+    fn matches_pin(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> bool {
+        let Ok(cert) = webpki::EndEntityCert::try_from(end_entity) else {
+            return false;
+        };
+        let pinned = |spki: &[u8]| self.pins.contains(&spki_sha256(spki));
+        let check_path = |path: &webpki::VerifiedPath<'_>| {
+            let matched = pinned(path.end_entity().subject_public_key_info().as_ref())
+                || path
+                    .intermediate_certificates()
+                    .any(|cert| pinned(cert.subject_public_key_info().as_ref()))
+                || pinned(&der_sequence(
+                    path.anchor().subject_public_key_info.as_ref(),
+                ));
+            if matched {
+                Ok(())
+            } else {
+                Err(webpki::Error::UnknownIssuer)
+            }
+        };
+        cert.verify_for_usage(
+            self.algorithms.all,
+            &self.roots.roots,
+            intermediates,
+            now,
+            webpki::KeyUsage::server_auth(),
+            None,
+            Some(&check_path),
+        )
+        .is_ok()
+    }
+}
+
+impl ServerCertVerifier for PinnedServerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        if self.pins.is_empty() {
+            return Ok(verified);
+        }
+        if self.matches_pin(end_entity, intermediates, now) {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::General(format!(
+                "{} 的证书公钥与固定值不匹配",
+                server_name.to_str()
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBkDCCATWgAwIBAgIUQsBN7SFMCiZkPUy5CY9pH/zJdeowCgYIKoZIzj0EAwIw
+HDEaMBgGA1UEAwwRUHJveHlDYXN0IFRlc3QgQ0EwIBcNMjYxMDE2MTkxMDE0WhgP
+MjEyNjA5MjIxOTEwMTRaMBwxGjAYBgNVBAMMEVByb3h5Q2FzdCBUZXN0IENBMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAECoBF6EtBq4qlrsHwBAeJgAdvwRCgj7kV
+VK2JxDz0/IMtF7f/FqNa3ps8oAdbygcPIoJUkG4Xw/pIJ7PgVQ5BlaNTMFEwHQYD
+VR0OBBYEFIaNv7rgnKBse+3hP7RGZmMpRvIJMB8GA1UdIwQYMBaAFIaNv7rgnKBs
+e+3hP7RGZmMpRvIJMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSQAwRgIh
+AP8Bf6vU7oCffk7//OKlsIWR3U3NC6TaNGOK18h+QKRkAiEAqjagT7vvU3ee2mxx
+M6SpupRadj5V2c74k6DL41PEuC0=
+-----END CERTIFICATE-----
+";
+    const TEST_CA_PIN: &str = "1JhP9RPyBJvkRdnY6QN2fpLn7PGGynCQGk5HN55y20s=";
+
+    // 根证书 -> 中间证书 -> example.com 证书；ROGUE_LEAF 由根证书直接签发（不经过中间证书）
+    const CHAIN_ROOT: &str = "-----BEGIN CERTIFICATE-----
+MIIBojCCAUmgAwIBAgIUQQbato0SFfdIz7VLV3vVHZH/DZowCgYIKoZIzj0EAwIw
+HjEcMBoGA1UEAwwTUHJveHlDYXN0IFRlc3QgUm9vdDAgFw0yNjEwMTcwMTA3MTla
+GA8yMTI2MDkyMzAxMDcxOVowHjEcMBoGA1UEAwwTUHJveHlDYXN0IFRlc3QgUm9v
+dDBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABA7fgFO2GJT+8rGGK93FGsMTEcPv
+llkEO3ObViIfnVF1UN9/X9KF/ZJnR59ZdaYlHpmxGlPLfovZLH/3TD2QQBGjYzBh
+MB0GA1UdDgQWBBR8xKKXQY0rYRfeyCZRdL2IYpE54DAfBgNVHSMEGDAWgBR8xKKX
+QY0rYRfeyCZRdL2IYpE54DAPBgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwIB
+BjAKBggqhkjOPQQDAgNHADBEAiAzxrTa4paTyiQmbGxzC25dPG6Zua9EUgOkvEQf
+86ZtxgIgXMOsaXgCgzxU9PC+7OZ0V8x77C8Xx0BWuxHrXPvUtd0=
+-----END CERTIFICATE-----
+";
+    const CHAIN_INTERMEDIATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBqjCCAVGgAwIBAgIUMkFijkkTFFpUCkk/8jXHE9gn98cwCgYIKoZIzj0EAwIw
+HjEcMBoGA1UEAwwTUHJveHlDYXN0IFRlc3QgUm9vdDAgFw0yNjEwMTcwMTA3MTla
+GA8yMTI2MDkyMzAxMDcxOVowJjEkMCIGA1UEAwwbUHJveHlDYXN0IFRlc3QgSW50
+ZXJtZWRpYXRlMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEj3Axlax/+pi0CBa+
+dyGelKVbLnSt8UeSeveOasTp9lntKcZZqVCStjNa0eG7dbkIu7jrcW4v632P5R+d
+60wcnqNjMGEwDwYDVR0TAQH/BAUwAwEB/zAOBgNVHQ8BAf8EBAMCAQYwHQYDVR0O
+BBYEFLl6LkEEYTuOppb7QwB3jqfhRcDKMB8GA1UdIwQYMBaAFHzEopdBjSthF97I
+JlF0vYhikTngMAoGCCqGSM49BAMCA0cAMEQCIDp7SWV/JLh2eX6BE+6Ab2NGfsqW
+tiHBzSsvCCKjQDXbAiBj5MZBwkoXj+JI7FlpTeN7b7C7wfZ6da2PD0BB3hxoUQ==
+-----END CERTIFICATE-----
+";
+    const CHAIN_LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBzjCCAXWgAwIBAgIUWkXi9MgwQxVkD1rb9Wg76yfFwUIwCgYIKoZIzj0EAwIw
+JjEkMCIGA1UEAwwbUHJveHlDYXN0IFRlc3QgSW50ZXJtZWRpYXRlMCAXDTI2MTAx
+NzAxMDcxOVoYDzIxMjYwOTIzMDEwNzE5WjAWMRQwEgYDVQQDDAtleGFtcGxlLmNv
+bTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABCJXAesLx98ad0TB/UCRW7Ua7OjH
+YfloB6jVbqwIgLWeKANRq6RCNI0kAqQ0T2vpTvUAwZj8WnkbogOOefZFhiCjgY4w
+gYswDAYDVR0TAQH/BAIwADAOBgNVHQ8BAf8EBAMCB4AwEwYDVR0lBAwwCgYIKwYB
+BQUHAwEwFgYDVR0RBA8wDYILZXhhbXBsZS5jb20wHQYDVR0OBBYEFBTCjTnmXBNt
+LjAY4ixxvd+9z5SfMB8GA1UdIwQYMBaAFLl6LkEEYTuOppb7QwB3jqfhRcDKMAoG
+CCqGSM49BAMCA0cAMEQCIErN6n3z5jaZOqpyMJtKk3VCdWc2Xti0eChx48byNGkb
+AiBIxAfDvd36jAPznQ3249aYbvwbTc44Y3cnz4iUwvwlbA==
+-----END CERTIFICATE-----
+";
+    const ROGUE_LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBxzCCAW2gAwIBAgIUMkFijkkTFFpUCkk/8jXHE9gn98gwCgYIKoZIzj0EAwIw
+HjEcMBoGA1UEAwwTUHJveHlDYXN0IFRlc3QgUm9vdDAgFw0yNjEwMTcwMTA3MTla
+GA8yMTI2MDkyMzAxMDcxOVowFjEUMBIGA1UEAwwLZXhhbXBsZS5jb20wWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAARBhnlSmySziYhLv+8MGwk8LEwZaaOVZ6ZUgii4
+bt3Z+Dc7JGoKky3YDj74omGKcCUU9EXo1n1iGLU0i1v0z3Doo4GOMIGLMAwGA1Ud
+EwEB/wQCMAAwDgYDVR0PAQH/BAQDAgeAMBMGA1UdJQQMMAoGCCsGAQUFBwMBMBYG
+A1UdEQQPMA2CC2V4YW1wbGUuY29tMB0GA1UdDgQWBBSnt3XAVxuD45ydBQFc4v8M
+WW2ajjAfBgNVHSMEGDAWgBR8xKKXQY0rYRfeyCZRdL2IYpE54DAKBggqhkjOPQQD
+AgNIADBFAiAFe/+ding94hjFOMwr3n6nbk3HEVHq6UfyzHRIySWMAgIhAIfCaz3u
+r1gXOJWGlkjYhkNCwd3Hx3VGk3y0br1dRC2+
+-----END CERTIFICATE-----
+";
+    const CHAIN_ROOT_PIN: &str = "RUGv8xIps3NtkX1Scw9MtYTlIaI+NnG+SwtkKXDQiDA=";
+    const CHAIN_INTERMEDIATE_PIN: &str = "M4ELnaeESyH4uQ6YC630bn6S+XP6efGD8FZ72naS27U=";
+    const CHAIN_LEAF_PIN: &str = "JiLuiQAMg80x5p20grEZXOa5sb25KQNLy4F1uRQxry0=";
+
+    fn ca_file() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(TEST_CA.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_spki_pin_matches_certificate() {
+        let cert = rustls_pemfile::certs(&mut TEST_CA.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+        let parsed = webpki::EndEntityCert::try_from(&cert).unwrap();
+        assert_eq!(
+            spki_sha256(parsed.subject_public_key_info().as_ref()),
+            parse_pin(TEST_CA_PIN).unwrap()
+        );
+        // 信任锚只保存 SPKI 内容，补上 SEQUENCE 头部后应得到相同的固定值
+        let anchor = webpki::anchor_from_trusted_cert(&cert).unwrap();
+        assert_eq!(
+            spki_sha256(&der_sequence(anchor.subject_public_key_info.as_ref())),
+            parse_pin(TEST_CA_PIN).unwrap()
+        );
+        assert_eq!(
+            parse_pin(&format!("sha256/{}", TEST_CA_PIN)).unwrap(),
+            parse_pin(TEST_CA_PIN).unwrap()
+        );
+        assert!(parse_pin("not-base64!").is_err());
+        assert!(parse_pin("AAAA").is_err());
+    }
+
+    #[test]
+    fn test_validate_provider_options() {
+        let file = ca_file();
+        let mut config = UpstreamTlsConfig::default();
+        config.providers.insert(
+            "claude".to_string(),
+            ProviderTlsConfig {
+                ca_file: Some(file.path().to_string_lossy().to_string()),
+                disable_system_roots: true,
+                pinned_spki_sha256: vec![TEST_CA_PIN.to_string()],
+            },
+        );
+        assert!(validate_upstream_tls_config(&config).is_ok());
+
+        // 禁用内置根证书但未配置 CA
+        config.providers.insert(
+            "openai".to_string(),
+            ProviderTlsConfig {
+                disable_system_roots: true,
+                ..ProviderTlsConfig::default()
+            },
+        );
+        assert!(validate_upstream_tls_config(&config).is_err());
+
+        // CA 文件不存在
+        config.providers.insert(
+            "openai".to_string(),
+            ProviderTlsConfig {
+                ca_file: Some("/nonexistent/ca.pem".to_string()),
+                ..ProviderTlsConfig::default()
+            },
+        );
+        let err = validate_upstream_tls_config(&config).unwrap_err();
+        assert!(err.starts_with("Provider openai"));
+    }
+
+    fn pem_cert(pem: &str) -> CertificateDer<'static> {
+        rustls_pemfile::certs(&mut pem.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap()
+    }
+
+    fn verify(pin: &str, end_entity: &str, intermediates: &[&str]) -> bool {
+        let mut roots = RootCertStore::empty();
+        roots.add(pem_cert(CHAIN_ROOT)).unwrap();
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier =
+            PinnedServerVerifier::new(roots, vec![parse_pin(pin).unwrap()], &provider).unwrap();
+        let intermediates: Vec<_> = intermediates.iter().map(|pem| pem_cert(pem)).collect();
+        verifier
+            .verify_server_cert(
+                &pem_cert(end_entity),
+                &intermediates,
+                &ServerName::try_from("example.com").unwrap(),
+                &[],
+                UnixTime::now(),
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn test_pin_matches_verified_chain() {
+        for pin in [CHAIN_LEAF_PIN, CHAIN_INTERMEDIATE_PIN, CHAIN_ROOT_PIN] {
+            assert!(verify(pin, CHAIN_LEAF, &[CHAIN_INTERMEDIATE]), "{}", pin);
+        }
+        assert!(!verify(TEST_CA_PIN, CHAIN_LEAF, &[CHAIN_INTERMEDIATE]));
+    }
+
+    #[test]
+    fn test_pin_ignores_unrelated_intermediate() {
+        // 证书链有效，但附带的固定中间证书不在校验通过的证书链中
+        assert!(!verify(
+            CHAIN_INTERMEDIATE_PIN,
+            ROGUE_LEAF,
+            &[CHAIN_INTERMEDIATE]
+        ));
+        assert!(verify(CHAIN_ROOT_PIN, ROGUE_LEAF, &[CHAIN_INTERMEDIATE]));
+    }
+}
@@ -33,7 +33,7 @@ pub trait CredentialProvider: Send + Sync {
     async fn refresh_token(&mut self) -> ProviderResult<String>;
 
     /// 获取当前 access_token
-    fn get_access_token(&self) -> Option<&str>;
+    fn get_access_token(&self) -> Option<String>;
 
     /// 获取 Provider 类型名称
     fn provider_type(&self) -> &'static str;
@@ -84,7 +84,6 @@ pub trait TokenManager: CredentialProvider {
             self.refresh_token().await
         } else {
             self.get_access_token()
-                .map(|s| s.to_string())
                 .ok_or_else(|| "No access token available".into())
         }
     }
@@ -131,8 +130,8 @@ mod tests {
             Ok(self.token.clone().unwrap())
         }
 
-        fn get_access_token(&self) -> Option<&str> {
-            self.token.as_deref()
+        fn get_access_token(&self) -> Option<String> {
+            self.token.clone()
         }
 
         fn provider_type(&self) -> &'static str {
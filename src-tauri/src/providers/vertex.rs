@@ -6,6 +6,8 @@
 #![allow(dead_code)]
 
 use crate::config::VertexApiKeyEntry;
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -53,7 +55,7 @@ impl Default for VertexProvider {
     fn default() -> Self {
         Self {
             config: VertexConfig::default(),
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::Vertex),
         }
     }
 }
@@ -74,7 +76,7 @@ impl VertexProvider {
                 model_aliases: HashMap::new(),
                 proxy_url: None,
             },
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::Vertex),
         }
     }
 
@@ -93,7 +95,7 @@ impl VertexProvider {
                 model_aliases,
                 proxy_url: entry.proxy_url.clone(),
             },
-            client: Client::new(),
+            client: super::tls::default_client(crate::ProviderType::Vertex),
         }
     }
 
@@ -157,6 +159,19 @@ impl VertexProvider {
         &self.config.model_aliases
     }
 
+    /// Resolve model alias and map sampling parameters to Gemini ranges
+    fn prepare_request(&self, request: &serde_json::Value) -> serde_json::Value {
+        let mut request = request.clone();
+        if let Some(model) = request.get("model").and_then(|m| m.as_str()) {
+            let resolved_model = self.resolve_model_alias(model);
+            request["model"] = serde_json::json!(resolved_model);
+        }
+        SamplingParams::from_openai_value(&request)
+            .map_to(Protocol::Gemini)
+            .write_openai_fields(&mut request);
+        request
+    }
+
     /// Call the Vertex AI chat completions API
     ///
     /// Automatically injects the x-goog-api-key header and resolves model aliases.
@@ -170,12 +185,7 @@ impl VertexProvider {
             .as_ref()
             .ok_or("Vertex AI API key not configured")?;
 
-        // Resolve model alias if present
-        let mut request = request.clone();
-        if let Some(model) = request.get("model").and_then(|m| m.as_str()) {
-            let resolved_model = self.resolve_model_alias(model);
-            request["model"] = serde_json::json!(resolved_model);
-        }
+        let request = self.prepare_request(request);
 
         let base_url = self.get_base_url();
         let model = request
@@ -209,12 +219,7 @@ impl VertexProvider {
             .as_ref()
             .ok_or("Vertex AI API key not configured")?;
 
-        // Resolve model alias if present
-        let mut request = request.clone();
-        if let Some(model) = request.get("model").and_then(|m| m.as_str()) {
-            let resolved_model = self.resolve_model_alias(model);
-            request["model"] = serde_json::json!(resolved_model);
-        }
+        let request = self.prepare_request(request);
 
         let base_url = self.get_base_url();
         let model = request
@@ -344,6 +349,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prepare_request_maps_sampling_to_gemini() {
+        let provider = VertexProvider::new().with_model_alias("fast", "gemini-2.0-flash");
+        let request = provider.prepare_request(&serde_json::json!({
+            "model": "fast",
+            "temperature": 2.5,
+            "stop": ["a", "b", "c", "d", "e", "f"]
+        }));
+        assert_eq!(request["model"], "gemini-2.0-flash");
+        assert_eq!(request["temperature"], 2.0);
+        assert_eq!(request["stop"].as_array().unwrap().len(), 5);
+    }
+
     #[test]
     fn test_is_alias() {
         let provider = VertexProvider::with_config("test-key".to_string(), None)
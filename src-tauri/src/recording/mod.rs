@@ -0,0 +1,548 @@
+//! 流量录制与回放
+//!
+//! - 录制模式：正常转发请求，同时将（请求、Provider、响应）保存为 fixture 文件，流式响应按 SSE 事件保存
+//! - 回放模式：不访问上游，按请求匹配 fixture 并返回录制的响应，未匹配时返回 404
+//!
+//! fixture 以 `方法 + 路径 + 规范化请求体` 的哈希命名，配置忽略的请求体顶层字段不参与匹配。
+//! 不录制请求头，避免保存客户端 API Key。
+
+use crate::config::{expand_tilde, RecordingConfig, RecordingMode};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// 响应头：本次请求对应的 fixture 键
+pub const RECORDING_KEY_HEADER: &str = "x-proxycast-recording-key";
+
+/// 录制的请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// 路径（含查询参数）
+    pub path: String,
+    /// 请求体（JSON 或文本，为空时为 null）
+    #[serde(default)]
+    pub body: Value,
+}
+
+/// 录制的响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// 非流式响应体（JSON 或文本）
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub body: Value,
+    /// 流式响应的 SSE 事件（不含事件之间的空行）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+}
+
+impl IntoResponse for RecordedResponse {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        let body = if self.events.is_empty() {
+            Body::from(value_to_bytes(self.body))
+        } else {
+            let chunks = self
+                .events
+                .into_iter()
+                .map(|event| Ok::<_, std::convert::Infallible>(Bytes::from(event + "\n\n")));
+            Body::from_stream(futures::stream::iter(chunks))
+        };
+        let mut response = Response::new(body);
+        *response.status_mut() = status;
+        if let Some(value) = self
+            .content_type
+            .and_then(|v| HeaderValue::from_str(&v).ok())
+        {
+            response.headers_mut().insert(header::CONTENT_TYPE, value);
+        }
+        response
+    }
+}
+
+/// 一次录制的请求和响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub key: String,
+    pub recorded_at: DateTime<Utc>,
+    pub request: RecordedRequest,
+    /// 处理请求的 Provider（未经过凭证池时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub response: RecordedResponse,
+}
+
+/// fixture 存储
+#[derive(Debug, Clone)]
+pub struct RecordingStore {
+    mode: RecordingMode,
+    dir: PathBuf,
+    ignore_fields: Vec<String>,
+}
+
+impl From<&RecordingConfig> for RecordingStore {
+    fn from(config: &RecordingConfig) -> Self {
+        Self {
+            mode: config.mode,
+            dir: expand_tilde(&config.dir),
+            ignore_fields: config.ignore_fields.clone(),
+        }
+    }
+}
+
+impl RecordingStore {
+    /// 计算请求的匹配键
+    pub fn key(&self, method: &Method, uri: &Uri, body: &[u8]) -> String {
+        let body = match serde_json::from_slice::<Value>(body) {
+            Ok(mut value) => {
+                if let Some(obj) = value.as_object_mut() {
+                    for field in &self.ignore_fields {
+                        obj.remove(field);
+                    }
+                }
+                canonical_json(&value)
+            }
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        };
+        let path = uri.path_and_query().map_or(uri.path(), |p| p.as_str());
+        let digest = Sha256::digest(format!("{}\n{}\n{}", method, path, body).as_bytes());
+        hex::encode(digest)[..16].to_string()
+    }
+
+    /// fixture 文件路径（文件名包含路径便于识别）
+    fn fixture_path(&self, path: &str, key: &str) -> PathBuf {
+        let slug: String = path
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let slug = slug.trim_matches('-');
+        if slug.is_empty() {
+            self.dir.join(format!("{}.json", key))
+        } else {
+            self.dir.join(format!("{}-{}.json", slug, key))
+        }
+    }
+
+    /// 保存 fixture（相同请求覆盖之前的录制）
+    pub fn save(&self, fixture: &Fixture) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("无法创建录制目录 {:?}: {}", self.dir, e))?;
+        let path = self.fixture_path(&fixture.request.path, &fixture.key);
+        let json = serde_json::to_string_pretty(fixture).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("写入 fixture {:?} 失败: {}", path, e))?;
+        Ok(path)
+    }
+
+    /// 读取 fixture（不存在时返回 None）
+    pub fn load(&self, path: &str, key: &str) -> Result<Option<Fixture>, String> {
+        let file = self.fixture_path(path, key);
+        if !file.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("读取 fixture {:?} 失败: {}", file, e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("解析 fixture {:?} 失败: {}", file, e))
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_PROVIDER: Arc<Mutex<Option<String>>>;
+}
+
+/// 记录当前请求实际使用的 Provider（不在录制上下文中时忽略）
+pub fn note_provider(provider: &str) {
+    let _ = CURRENT_PROVIDER.try_with(|current| {
+        if let Ok(mut current) = current.lock() {
+            *current = Some(provider.to_string());
+        }
+    });
+}
+
+/// 为路由组添加流量录制中间件（`off` 模式时不启用）
+pub fn with_recording<S>(router: Router<S>, config: &RecordingConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if config.mode == RecordingMode::Off {
+        return router;
+    }
+    let store = RecordingStore::from(config);
+    tracing::info!(
+        "[RECORDING] 已启用 {:?} 模式，fixture 目录: {:?}",
+        store.mode,
+        store.dir
+    );
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(store),
+        handle_recording,
+    ))
+}
+
+/// 录制或回放请求（WebSocket 升级请求直接放行）
+pub async fn handle_recording(
+    State(store): State<Arc<RecordingStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.headers().contains_key(header::UPGRADE) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, format!("读取请求体失败: {}", e)).into_response()
+        }
+    };
+    let key = store.key(&parts.method, &parts.uri, &bytes);
+    let recorded_request = RecordedRequest {
+        method: parts.method.to_string(),
+        path: parts
+            .uri
+            .path_and_query()
+            .map_or_else(|| parts.uri.path().to_string(), |p| p.to_string()),
+        body: bytes_to_value(&bytes),
+    };
+
+    let mut response = if store.mode == RecordingMode::Replay {
+        replay(&store, &recorded_request.path, &key)
+    } else {
+        let provider = Arc::new(Mutex::new(None));
+        let request = Request::from_parts(parts, Body::from(bytes));
+        let response = CURRENT_PROVIDER
+            .scope(provider.clone(), next.run(request))
+            .await;
+        let provider = provider.lock().ok().and_then(|p| p.clone());
+        record(store, key.clone(), recorded_request, provider, response).await
+    };
+    if let Ok(value) = HeaderValue::from_str(&key) {
+        response.headers_mut().insert(RECORDING_KEY_HEADER, value);
+    }
+    response
+}
+
+/// 返回匹配的 fixture
+fn replay(store: &RecordingStore, path: &str, key: &str) -> Response {
+    let error = |status: StatusCode, error_type: &str, message: String| {
+        (
+            status,
+            Json(serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": error_type,
+                    "code": error_type,
+                    "message": message,
+                    "recording_key": key,
+                }
+            })),
+        )
+            .into_response()
+    };
+    match store.load(path, key) {
+        Ok(Some(fixture)) => fixture.response.into_response(),
+        Ok(None) => error(
+            StatusCode::NOT_FOUND,
+            "recording_not_found",
+            format!("No recording matches {} (key: {})", path, key),
+        ),
+        Err(e) => {
+            tracing::warn!("[RECORDING] {}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "recording_invalid", e)
+        }
+    }
+}
+
+/// 待保存的 fixture（响应体读取完成后保存）
+struct PendingFixture {
+    store: Arc<RecordingStore>,
+    key: String,
+    request: RecordedRequest,
+    provider: Option<String>,
+    status: u16,
+    content_type: Option<String>,
+}
+
+impl PendingFixture {
+    fn finish(self, body: Value, events: Vec<String>) {
+        let fixture = Fixture {
+            key: self.key,
+            recorded_at: Utc::now(),
+            request: self.request,
+            provider: self.provider,
+            response: RecordedResponse {
+                status: self.status,
+                content_type: self.content_type,
+                body,
+                events,
+            },
+        };
+        match self.store.save(&fixture) {
+            Ok(path) => tracing::debug!("[RECORDING] 已录制 {:?}", path),
+            Err(e) => tracing::warn!("[RECORDING] {}", e),
+        }
+    }
+}
+
+/// 转发响应并录制，流式响应在流正常结束后保存
+async fn record(
+    store: Arc<RecordingStore>,
+    key: String,
+    request: RecordedRequest,
+    provider: Option<String>,
+    response: Response,
+) -> Response {
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let is_sse = content_type
+        .as_deref()
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    let pending = PendingFixture {
+        store,
+        key,
+        request,
+        provider,
+        status: response.status().as_u16(),
+        content_type,
+    };
+
+    let (parts, body) = response.into_parts();
+    if is_sse {
+        let stream = record_stream(body.into_data_stream(), pending);
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    pending.finish(bytes_to_value(&bytes), Vec::new());
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// 包装 SSE 数据流：原样输出，流结束后按事件保存（客户端提前断开时不保存）
+fn record_stream<S>(
+    mut inner: S,
+    pending: PendingFixture,
+) -> impl Stream<Item = Result<Bytes, axum::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin + Send + 'static,
+{
+    async_stream::stream! {
+        let mut buffer = Vec::new();
+        while let Some(chunk) = inner.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    buffer.extend_from_slice(&chunk);
+                    yield Ok(chunk);
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+        pending.finish(Value::Null, split_sse_events(&String::from_utf8_lossy(&buffer)));
+    }
+}
+
+/// 按空行拆分 SSE 事件
+fn split_sse_events(text: &str) -> Vec<String> {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|event| !event.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 请求体 / 响应体转为 JSON 值（非 JSON 时保存为文本）
+fn bytes_to_value(bytes: &[u8]) -> Value {
+    if bytes.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+fn value_to_bytes(value: Value) -> Bytes {
+    match value {
+        Value::Null => Bytes::new(),
+        Value::String(text) => Bytes::from(text),
+        other => Bytes::from(other.to_string()),
+    }
+}
+
+/// 键按字典序排列的 JSON 文本，使字段顺序不影响匹配
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(obj) => {
+            let mut entries: Vec<_> = obj.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| format!("{}:{}", Value::String(k.clone()), canonical_json(v)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use std::path::Path;
+    use tower::ServiceExt;
+
+    fn config(mode: RecordingMode, dir: &Path) -> RecordingConfig {
+        RecordingConfig {
+            mode,
+            dir: dir.to_string_lossy().into_owned(),
+            ..RecordingConfig::default()
+        }
+    }
+
+    fn upstream() -> Router {
+        Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async {
+                    note_provider("openai");
+                    Json(serde_json::json!({"id": "chatcmpl-1", "choices": []}))
+                }),
+            )
+            .route(
+                "/v1/messages",
+                post(|| async {
+                    note_provider("claude");
+                    (
+                        [(header::CONTENT_TYPE, "text/event-stream")],
+                        "event: message_start\ndata: {}\n\nevent: message_stop\ndata: {}\n\n",
+                    )
+                }),
+            )
+    }
+
+    async fn send(app: Router, path: &str, body: &str) -> (StatusCode, Option<String>, String) {
+        let request = Request::post(path)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            content_type,
+            String::from_utf8(bytes.to_vec()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_key_ignores_field_order_and_ignored_fields() {
+        let store = RecordingStore::from(&RecordingConfig::default());
+        let uri: Uri = "/v1/messages".parse().unwrap();
+        let key = store.key(
+            &Method::POST,
+            &uri,
+            br#"{"model":"m","messages":[],"metadata":{"user_id":"a"}}"#,
+        );
+        assert_eq!(
+            key,
+            store.key(
+                &Method::POST,
+                &uri,
+                br#"{"messages":[],"model":"m","metadata":{"user_id":"b"}}"#
+            )
+        );
+        assert_ne!(
+            key,
+            store.key(&Method::POST, &uri, br#"{"model":"other","messages":[]}"#)
+        );
+        let other_path: Uri = "/v1/chat/completions".parse().unwrap();
+        assert_ne!(
+            key,
+            store.key(
+                &Method::POST,
+                &other_path,
+                br#"{"model":"m","messages":[]}"#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = with_recording(upstream(), &config(RecordingMode::Record, dir.path()));
+        let body = r#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}"#;
+        let recorded = send(recorder.clone(), "/v1/chat/completions", body).await;
+        let streamed = send(recorder, "/v1/messages", body).await;
+        assert_eq!(recorded.0, StatusCode::OK);
+
+        let store = RecordingStore::from(&config(RecordingMode::Record, dir.path()));
+        let key = store.key(
+            &Method::POST,
+            &"/v1/messages".parse().unwrap(),
+            body.as_bytes(),
+        );
+        let fixture = store.load("/v1/messages", &key).unwrap().unwrap();
+        assert_eq!(fixture.provider.as_deref(), Some("claude"));
+        assert_eq!(fixture.request.body["model"], "gpt-4o");
+        assert_eq!(
+            fixture.response.events,
+            vec![
+                "event: message_start\ndata: {}".to_string(),
+                "event: message_stop\ndata: {}".to_string()
+            ]
+        );
+
+        // 回放时不访问上游
+        let replayer = with_recording(
+            Router::new().fallback(|| async { StatusCode::BAD_GATEWAY }),
+            &config(RecordingMode::Replay, dir.path()),
+        );
+        let replayed = send(replayer.clone(), "/v1/chat/completions", body).await;
+        assert_eq!((replayed.0, &replayed.1), (recorded.0, &recorded.1));
+        assert_eq!(
+            serde_json::from_str::<Value>(&replayed.2).unwrap(),
+            serde_json::from_str::<Value>(&recorded.2).unwrap()
+        );
+        assert_eq!(send(replayer.clone(), "/v1/messages", body).await, streamed);
+
+        let (status, _, missing) = send(replayer, "/v1/messages", r#"{"model":"x"}"#).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(missing.contains("recording_not_found"));
+    }
+}
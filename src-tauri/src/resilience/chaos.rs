@@ -0,0 +1,217 @@
+//! 故障注入
+//!
+//! 按 Provider 以指定概率在调用上游之前注入故障，用于验证重试、降级和凭证切换配置：
+//! - 429 限流 / 500 服务端错误
+//! - 超时（等待指定时长后返回 504）
+//! - 额外的随机延迟
+//!
+//! 默认只在服务监听本机地址时生效，避免误用于生产环境。
+
+use crate::config::{ChaosConfig, ChaosRule};
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 匹配所有 Provider 的规则名称
+const WILDCARD_PROVIDER: &str = "*";
+
+/// 注入的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChaosFault {
+    /// 429 限流
+    RateLimit,
+    /// 500 服务端错误
+    ServerError,
+    /// 等待指定时长后超时
+    Timeout(Duration),
+}
+
+impl ChaosFault {
+    /// 返回给客户端的状态码
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::RateLimit => 429,
+            Self::ServerError => 500,
+            Self::Timeout(_) => 504,
+        }
+    }
+
+    /// 错误信息（包含状态码，与上游错误一样参与限流和健康状态判断）
+    pub fn message(&self) -> String {
+        match self {
+            Self::RateLimit => "[CHAOS] Injected fault: 429 Too Many Requests".to_string(),
+            Self::ServerError => "[CHAOS] Injected fault: 500 Internal Server Error".to_string(),
+            Self::Timeout(wait) => format!(
+                "[CHAOS] Injected fault: upstream timed out after {}ms",
+                wait.as_millis()
+            ),
+        }
+    }
+}
+
+/// 单次请求的注入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChaosOutcome {
+    /// 调用上游前的额外延迟
+    pub delay: Duration,
+    /// 注入的故障（为空时正常调用上游）
+    pub fault: Option<ChaosFault>,
+}
+
+/// 故障注入器
+#[derive(Debug, Clone, Default)]
+pub struct ChaosInjector {
+    rules: HashMap<String, ChaosRule>,
+}
+
+impl ChaosInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从配置构建（未启用，或监听非本机地址且未允许时不注入任何故障）
+    pub fn from_config(config: &ChaosConfig, host: &str) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        if !config.allow_remote && !crate::config::is_loopback_bind(host) {
+            tracing::warn!(
+                "[CHAOS] 服务监听非本机地址 {}，故障注入未生效（如需启用请设置 allow_remote）",
+                host
+            );
+            return Self::default();
+        }
+        tracing::warn!("[CHAOS] 故障注入已启用: {} 条规则", config.providers.len());
+        Self {
+            rules: config
+                .providers
+                .iter()
+                .map(|(provider, rule)| (provider.to_lowercase(), rule.clone()))
+                .collect(),
+        }
+    }
+
+    /// 是否启用
+    pub fn is_active(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// 为发往指定 Provider 的请求抽取注入结果（没有适用规则时返回 None）
+    pub fn roll(&self, provider: &str) -> Option<ChaosOutcome> {
+        let rule = self
+            .rules
+            .get(&provider.to_lowercase())
+            .or_else(|| self.rules.get(WILDCARD_PROVIDER))?;
+        Some(roll_rule(rule, &mut rand::thread_rng()))
+    }
+}
+
+/// 按规则抽取延迟和故障（各故障概率依次累加，总和超过 1 时后面的故障概率被截断）
+fn roll_rule(rule: &ChaosRule, rng: &mut impl Rng) -> ChaosOutcome {
+    let delay = if rule.latency_jitter_ms > 0 {
+        Duration::from_millis(rng.gen_range(0..=rule.latency_jitter_ms))
+    } else {
+        Duration::ZERO
+    };
+
+    let sample: f64 = rng.gen();
+    let mut threshold = 0.0;
+    let mut fault = None;
+    for (rate, candidate) in [
+        (rule.rate_limit_rate, ChaosFault::RateLimit),
+        (rule.server_error_rate, ChaosFault::ServerError),
+        (
+            rule.timeout_rate,
+            ChaosFault::Timeout(Duration::from_millis(rule.timeout_ms)),
+        ),
+    ] {
+        threshold += rate.clamp(0.0, 1.0);
+        if sample < threshold {
+            fault = Some(candidate);
+            break;
+        }
+    }
+    ChaosOutcome { delay, fault }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn config(rule: ChaosRule) -> ChaosConfig {
+        ChaosConfig {
+            enabled: true,
+            allow_remote: false,
+            providers: HashMap::from([("Kiro".to_string(), rule)]),
+        }
+    }
+
+    #[test]
+    fn test_injector_requires_local_host() {
+        let config = config(ChaosRule::default());
+        assert!(ChaosInjector::from_config(&config, "127.0.0.1").is_active());
+        assert!(ChaosInjector::from_config(&config, "localhost").is_active());
+        assert!(ChaosInjector::from_config(&config, "::1").is_active());
+        assert!(!ChaosInjector::from_config(&config, "0.0.0.0").is_active());
+
+        let remote = ChaosConfig {
+            allow_remote: true,
+            ..config.clone()
+        };
+        assert!(ChaosInjector::from_config(&remote, "0.0.0.0").is_active());
+
+        let disabled = ChaosConfig {
+            enabled: false,
+            ..config
+        };
+        assert!(!ChaosInjector::from_config(&disabled, "127.0.0.1").is_active());
+    }
+
+    #[test]
+    fn test_roll_matches_provider_and_rates() {
+        let injector = ChaosInjector::from_config(
+            &config(ChaosRule {
+                rate_limit_rate: 1.0,
+                ..ChaosRule::default()
+            }),
+            "127.0.0.1",
+        );
+        assert_eq!(
+            injector.roll("kiro").unwrap().fault,
+            Some(ChaosFault::RateLimit)
+        );
+        assert!(injector.roll("claude").is_none());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let rule = ChaosRule {
+            server_error_rate: 0.5,
+            timeout_rate: 0.5,
+            timeout_ms: 10,
+            latency_jitter_ms: 100,
+            ..ChaosRule::default()
+        };
+        let outcomes: Vec<ChaosOutcome> = (0..200).map(|_| roll_rule(&rule, &mut rng)).collect();
+        assert!(outcomes.iter().all(|o| o.fault.is_some()));
+        assert!(outcomes
+            .iter()
+            .any(|o| o.fault == Some(ChaosFault::ServerError)));
+        assert!(outcomes
+            .iter()
+            .any(|o| o.fault == Some(ChaosFault::Timeout(Duration::from_millis(10)))));
+        assert!(outcomes
+            .iter()
+            .all(|o| o.delay <= Duration::from_millis(100)));
+
+        let none = roll_rule(&ChaosRule::default(), &mut rng);
+        assert_eq!(
+            none,
+            ChaosOutcome {
+                delay: Duration::ZERO,
+                fault: None
+            }
+        );
+        assert!(ChaosFault::RateLimit.message().contains("429"));
+    }
+}
@@ -1,16 +1,22 @@
 //! 容错机制模块
 //!
-//! 提供重试、故障转移和超时控制功能
+//! 提供重试、故障转移、超时控制、故障注入和上游连通性负缓存功能
 
+mod chaos;
 mod failover;
+mod reachability;
 mod retry;
 mod timeout;
 
+pub use chaos::{ChaosFault, ChaosInjector, ChaosOutcome};
 pub use failover::{
     Failover, FailoverConfig, FailoverManager, FailoverResult, FailureType, SwitchEvent,
     QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES,
 };
-pub use retry::{Retrier, RetryConfig, RetryError};
+pub use reachability::{
+    client_builder, reachability_cache, record_error, ReachabilityCache, ReachabilityResolver,
+};
+pub use retry::{Retrier, RetryConfig, RetryError, RetryHint};
 pub use timeout::{
     CancellationToken, StreamIdleDetector, StreamWithIdleTimeout, TimeoutConfig, TimeoutController,
     TimeoutError,
@@ -0,0 +1,337 @@
+//! 上游连通性负缓存
+//!
+//! 上游主机连接失败（DNS 解析失败、连接被拒绝、连接超时）后记为不可达：
+//! - 有效期内对该主机的请求在 DNS 解析阶段立即失败，不再等待完整的连接超时，
+//!   由故障转移切换到其他凭证或 Provider
+//! - 后台按 `probe_interval_secs` 探测该主机（DNS 解析 + TCP 连接），成功后立即移除
+//! - 有效期内仍有请求命中时，探测失败会延长有效期；无请求命中时到期自动移除
+//!
+//! 通过 [`client_builder`] 创建的 HTTP 客户端使用带负缓存的 DNS 解析器，
+//! 连接失败由 `ProviderError` / `StreamError` 的 reqwest 错误转换统一记录。
+//! 使用代理时解析器只解析代理主机，负缓存对目标主机不生效。
+
+use crate::config::UpstreamReachabilityConfig;
+use parking_lot::{Mutex, RwLock};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+static REACHABILITY_CACHE: OnceLock<Arc<ReachabilityCache>> = OnceLock::new();
+
+/// 全局上游连通性负缓存
+pub fn reachability_cache() -> &'static Arc<ReachabilityCache> {
+    REACHABILITY_CACHE.get_or_init(|| Arc::new(ReachabilityCache::default()))
+}
+
+/// 创建使用负缓存 DNS 解析器的 HTTP 客户端构建器
+pub fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder().dns_resolver(Arc::new(ReachabilityResolver))
+}
+
+/// 记录 reqwest 请求错误（仅连接失败会将主机记为不可达）
+pub fn record_error(err: &reqwest::Error) {
+    if !err.is_connect() {
+        return;
+    }
+    let Some(url) = err.url() else {
+        return;
+    };
+    if let Some(host) = url.host_str() {
+        reachability_cache().record_failure(host, url.port_or_known_default(), root_cause(err));
+    }
+}
+
+/// 错误链最底层的原因
+fn root_cause(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut cause = err;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+/// 主机名归一化（小写，去掉 IPv6 字面量的方括号）
+fn normalize_host(host: &str) -> String {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase()
+}
+
+/// 不可达主机记录
+#[derive(Debug, Clone)]
+struct UnreachableHost {
+    /// 探测端口（仅 DNS 解析失败时未知，此时只探测解析）
+    port: Option<u16>,
+    /// 有效期截止时间
+    until: Instant,
+    /// 最近一次被请求命中的时间
+    last_hit: Instant,
+    /// 失败原因
+    reason: String,
+}
+
+/// 上游连通性负缓存
+#[derive(Debug, Default)]
+pub struct ReachabilityCache {
+    config: RwLock<UpstreamReachabilityConfig>,
+    hosts: Mutex<HashMap<String, UnreachableHost>>,
+}
+
+impl ReachabilityCache {
+    pub fn new(config: UpstreamReachabilityConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 更新配置（禁用时清空记录）
+    pub fn set_config(&self, config: UpstreamReachabilityConfig) {
+        if !config.enabled {
+            self.hosts.lock().clear();
+        }
+        *self.config.write() = config;
+    }
+
+    /// 检查主机是否可用，有效期内的不可达主机返回错误
+    pub fn check(&self, host: &str) -> Result<(), String> {
+        if !self.config.read().enabled {
+            return Ok(());
+        }
+        let key = normalize_host(host);
+        let now = Instant::now();
+        let mut hosts = self.hosts.lock();
+        let expired = match hosts.get_mut(&key) {
+            Some(entry) if entry.until > now => {
+                entry.last_hit = now;
+                return Err(format!("上游主机 {} 暂时不可达: {}", key, entry.reason));
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            hosts.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// 将主机记为不可达，新记录会启动后台探测
+    ///
+    /// 已有记录时只补充探测端口，不延长有效期。返回是否新增记录。
+    pub fn record_failure(self: &Arc<Self>, host: &str, port: Option<u16>, reason: String) -> bool {
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return false;
+        }
+        let key = normalize_host(host);
+        let now = Instant::now();
+        {
+            let mut hosts = self.hosts.lock();
+            if let Some(entry) = hosts.get_mut(&key) {
+                entry.port = entry.port.or(port);
+                return false;
+            }
+            hosts.insert(
+                key.clone(),
+                UnreachableHost {
+                    port,
+                    until: now + Duration::from_secs(config.negative_ttl_secs),
+                    last_hit: now,
+                    reason: reason.clone(),
+                },
+            );
+        }
+        tracing::warn!(
+            "[REACHABILITY] 上游主机 {} 不可达，{} 秒内的请求将立即失败: {}",
+            key,
+            config.negative_ttl_secs,
+            reason
+        );
+
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(self.clone().probe_loop(key));
+        }
+        true
+    }
+
+    /// 后台探测循环，记录移除后退出
+    async fn probe_loop(self: Arc<Self>, host: String) {
+        loop {
+            let config = self.config.read().clone();
+            tokio::time::sleep(Duration::from_secs(config.probe_interval_secs.max(1))).await;
+            let port = match self.hosts.lock().get(&host) {
+                Some(entry) => entry.port,
+                None => return,
+            };
+            let result = probe(
+                &host,
+                port,
+                Duration::from_secs(config.probe_timeout_secs.max(1)),
+            )
+            .await;
+            if !self.finish_probe(&host, result) {
+                return;
+            }
+        }
+    }
+
+    /// 处理探测结果，返回是否继续探测
+    fn finish_probe(&self, host: &str, result: Result<(), String>) -> bool {
+        let ttl = Duration::from_secs(self.config.read().negative_ttl_secs);
+        let now = Instant::now();
+        let mut hosts = self.hosts.lock();
+        let Some(entry) = hosts.get_mut(host) else {
+            return false;
+        };
+        match result {
+            Ok(()) => {
+                hosts.remove(host);
+                tracing::info!("[REACHABILITY] 上游主机 {} 已恢复", host);
+                false
+            }
+            Err(reason) => {
+                entry.reason = reason;
+                if now.duration_since(entry.last_hit) < ttl {
+                    entry.until = now + ttl;
+                    true
+                } else if entry.until > now {
+                    true
+                } else {
+                    hosts.remove(host);
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// 探测主机：DNS 解析，端口已知时再尝试 TCP 连接
+async fn probe(host: &str, port: Option<u16>, timeout: Duration) -> Result<(), String> {
+    let attempt = async {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port.unwrap_or(0)))
+            .await
+            .map_err(|e| format!("DNS 解析失败: {}", e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err("DNS 解析无结果".to_string());
+        }
+        if port.is_none() {
+            return Ok(());
+        }
+        let mut last_error = String::new();
+        for addr in addrs {
+            match tokio::net::TcpStream::connect(addr).await {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+        Err(last_error)
+    };
+    tokio::time::timeout(timeout, attempt)
+        .await
+        .unwrap_or_else(|_| Err("探测超时".to_string()))
+}
+
+/// 带负缓存的 DNS 解析器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReachabilityResolver;
+
+impl Resolve for ReachabilityResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let cache = reachability_cache();
+            cache.check(&host)?;
+            match tokio::net::lookup_host((host.as_str(), 0)).await {
+                Ok(addrs) => Ok(Box::new(addrs.collect::<Vec<_>>().into_iter()) as Addrs),
+                Err(e) => {
+                    cache.record_failure(&host, None, format!("DNS 解析失败: {}", e));
+                    Err(e.into())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(ttl: u64) -> UpstreamReachabilityConfig {
+        UpstreamReachabilityConfig {
+            negative_ttl_secs: ttl,
+            probe_interval_secs: 3600,
+            ..UpstreamReachabilityConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_failed_host_fails_fast_until_expired() {
+        let cache = Arc::new(ReachabilityCache::new(config(30)));
+        assert!(cache.check("api.example.com").is_ok());
+
+        assert!(cache.record_failure("API.example.com", Some(443), "refused".to_string()));
+        assert!(!cache.record_failure("api.example.com", Some(443), "refused".to_string()));
+        let err = cache.check("api.example.com").unwrap_err();
+        assert!(err.contains("refused"));
+        assert!(cache.check("other.example.com").is_ok());
+
+        // 到期后放行
+        cache.hosts.lock().get_mut("api.example.com").unwrap().until = Instant::now();
+        assert!(cache.check("api.example.com").is_ok());
+        assert!(cache.hosts.lock().is_empty());
+    }
+
+    #[test]
+    fn test_probe_result_updates_entry() {
+        let cache = Arc::new(ReachabilityCache::new(config(30)));
+        cache.record_failure("api.example.com", None, "dns".to_string());
+
+        // 探测失败且仍有请求命中：延长有效期并继续探测
+        assert!(cache.finish_probe("api.example.com", Err("timeout".to_string())));
+        assert!(cache.check("api.example.com").is_err());
+
+        // 探测成功：立即移除
+        assert!(!cache.finish_probe("api.example.com", Ok(())));
+        assert!(cache.check("api.example.com").is_ok());
+
+        // 无请求命中且已到期：移除并停止探测
+        cache.record_failure("api.example.com", Some(443), "refused".to_string());
+        {
+            let mut hosts = cache.hosts.lock();
+            let entry = hosts.get_mut("api.example.com").unwrap();
+            entry.last_hit = Instant::now() - Duration::from_secs(60);
+            entry.until = Instant::now();
+        }
+        assert!(!cache.finish_probe("api.example.com", Err("refused".to_string())));
+        assert!(cache.hosts.lock().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_cache_ignores_failures() {
+        let cache = Arc::new(ReachabilityCache::new(config(30)));
+        cache.record_failure("api.example.com", Some(443), "refused".to_string());
+        cache.set_config(UpstreamReachabilityConfig {
+            enabled: false,
+            ..config(30)
+        });
+        assert!(cache.check("api.example.com").is_ok());
+        assert!(!cache.record_failure("api.example.com", Some(443), "refused".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_probe_connects_to_listening_port() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(probe("127.0.0.1", Some(port), Duration::from_secs(1))
+            .await
+            .is_ok());
+
+        drop(listener);
+        assert!(probe("127.0.0.1", Some(port), Duration::from_secs(1))
+            .await
+            .is_err());
+    }
+}
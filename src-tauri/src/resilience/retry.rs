@@ -1,9 +1,12 @@
 //! 重试机制实现
 //!
-//! 提供带指数退避和抖动的重试逻辑
+//! 提供带指数退避和抖动的重试逻辑。上游返回 429 时，退避时间优先采用上游给出的
+//! Retry-After / retryDelay，并参考凭证的限流历史（连续限流次数越多，退避起点越高）。
 
+use crate::session::{extract_retry_delay, RateLimitReason, RateLimitRecord, RateLimitTracker};
 use serde::{Deserialize, Serialize};
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// 可重试的 HTTP 状态码
@@ -78,16 +81,62 @@ impl std::fmt::Display for RetryError {
 
 impl std::error::Error for RetryError {}
 
+/// 退避提示（来自上游 429 响应和凭证的限流记录）
+#[derive(Debug, Clone, Default)]
+pub struct RetryHint {
+    /// 上游给出的等待时间（Retry-After 响应头或响应体中的 retryDelay）
+    pub retry_after: Option<Duration>,
+    /// 凭证的限流记录
+    pub rate_limit: Option<RateLimitRecord>,
+}
+
+impl RetryHint {
+    /// 从上游响应提取等待时间
+    pub fn from_response(
+        headers: Option<&reqwest::header::HeaderMap>,
+        body: Option<&serde_json::Value>,
+    ) -> Self {
+        Self {
+            retry_after: extract_retry_delay(headers, body).and_then(|d| d.to_std().ok()),
+            rate_limit: None,
+        }
+    }
+
+    /// 限流记录中剩余的等待时间
+    fn remaining_wait(&self) -> Option<Duration> {
+        self.rate_limit
+            .as_ref()
+            .and_then(|record| (record.reset_at - chrono::Utc::now()).to_std().ok())
+            .filter(|wait| !wait.is_zero())
+    }
+}
+
 /// 重试器
 #[derive(Debug, Clone)]
 pub struct Retrier {
     config: RetryConfig,
+    /// 凭证限流历史
+    rate_limits: Arc<RateLimitTracker>,
 }
 
 impl Retrier {
     /// 创建新的重试器
     pub fn new(config: RetryConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            rate_limits: Arc::new(RateLimitTracker::default()),
+        }
+    }
+
+    /// 使用指定的限流追踪器（与其他组件共享凭证限流历史）
+    pub fn with_rate_limit_tracker(mut self, tracker: Arc<RateLimitTracker>) -> Self {
+        self.rate_limits = tracker;
+        self
+    }
+
+    /// 获取限流追踪器
+    pub fn rate_limit_tracker(&self) -> &Arc<RateLimitTracker> {
+        &self.rate_limits
     }
 
     /// 使用默认配置创建重试器
@@ -127,6 +176,70 @@ impl Retrier {
         Duration::from_millis(delay as u64)
     }
 
+    /// 结合退避提示计算第 N 次重试的退避时间
+    ///
+    /// - 上游给出等待时间时直接采用
+    /// - 否则按指数退避计算，凭证连续限流 K 次时至少从第 K-1 次重试的退避时间开始，
+    ///   且不短于限流记录中剩余的等待时间
+    ///
+    /// 需要等待的时间超过 `max_delay_ms` 时返回 None，此时不应继续重试同一凭证。
+    pub fn adaptive_backoff_delay(&self, attempt: u32, hint: &RetryHint) -> Option<Duration> {
+        self.adaptive_backoff_delay_with_jitter(attempt, hint, rand_jitter_factor())
+    }
+
+    /// 结合退避提示计算退避时间（可指定抖动因子，用于测试）
+    pub fn adaptive_backoff_delay_with_jitter(
+        &self,
+        attempt: u32,
+        hint: &RetryHint,
+        jitter_factor: f64,
+    ) -> Option<Duration> {
+        let max = Duration::from_millis(self.config.max_delay_ms);
+        let required = match hint.retry_after {
+            Some(retry_after) => retry_after,
+            None => {
+                let attempt = hint.rate_limit.as_ref().map_or(attempt, |r| {
+                    attempt.max(r.consecutive_failures.saturating_sub(1))
+                });
+                let exponential = self.backoff_delay_with_jitter(attempt, jitter_factor);
+                hint.remaining_wait()
+                    .map_or(exponential, |wait| wait.max(exponential))
+            }
+        };
+        (required <= max).then_some(required)
+    }
+
+    /// 获取凭证的退避提示（合并上游给出的等待时间和凭证的限流记录）
+    pub fn hint_for(
+        &self,
+        credential_id: Option<&str>,
+        retry_after: Option<Duration>,
+    ) -> RetryHint {
+        RetryHint {
+            retry_after,
+            rate_limit: credential_id.and_then(|id| self.rate_limits.get_rate_limit(id)),
+        }
+    }
+
+    /// 记录凭证被限流（429）
+    pub fn record_rate_limit(
+        &self,
+        credential_id: &str,
+        retry_after: Option<Duration>,
+    ) -> RateLimitRecord {
+        self.rate_limits.mark_rate_limited(
+            credential_id,
+            RateLimitReason::RateLimitExceeded,
+            retry_after.and_then(|d| chrono::Duration::from_std(d).ok()),
+            None,
+        )
+    }
+
+    /// 记录凭证请求成功（清除限流记录）
+    pub fn record_success(&self, credential_id: &str) {
+        self.rate_limits.clear_rate_limit(credential_id);
+    }
+
     /// 带重试执行异步操作
     ///
     /// 操作函数返回 `Result<T, (String, Option<u16>)>`，
@@ -307,6 +420,84 @@ mod unit_tests {
         assert_eq!(sequence[2], Duration::from_millis(4000));
     }
 
+    #[test]
+    fn test_adaptive_backoff_respects_retry_after() {
+        let retrier = Retrier::new(RetryConfig::new(3, 1000, 30000));
+
+        // 上游给出的等待时间优先于指数退避
+        let hint = RetryHint {
+            retry_after: Some(Duration::from_millis(300)),
+            rate_limit: None,
+        };
+        assert_eq!(
+            retrier.adaptive_backoff_delay_with_jitter(2, &hint, 0.0),
+            Some(Duration::from_millis(300))
+        );
+
+        // 超过最大延迟时不再重试
+        let hint = RetryHint {
+            retry_after: Some(Duration::from_secs(60)),
+            rate_limit: None,
+        };
+        assert_eq!(
+            retrier.adaptive_backoff_delay_with_jitter(0, &hint, 0.0),
+            None
+        );
+
+        // 没有提示时与普通指数退避相同
+        assert_eq!(
+            retrier.adaptive_backoff_delay_with_jitter(1, &RetryHint::default(), 0.0),
+            Some(Duration::from_millis(2000))
+        );
+    }
+
+    #[test]
+    fn test_adaptive_backoff_uses_rate_limit_history() {
+        let retrier = Retrier::new(RetryConfig::new(3, 1000, 30000));
+
+        // 连续限流 3 次：从第 2 次重试的退避时间开始
+        for _ in 0..3 {
+            retrier.record_rate_limit("cred-1", Some(Duration::from_millis(1)));
+        }
+        let hint = retrier.hint_for(Some("cred-1"), None);
+        assert_eq!(hint.rate_limit.as_ref().unwrap().consecutive_failures, 3);
+        assert_eq!(
+            retrier.adaptive_backoff_delay_with_jitter(0, &hint, 0.0),
+            Some(Duration::from_millis(4000))
+        );
+
+        // 限流记录中剩余的等待时间更长时按剩余时间等待
+        retrier.record_rate_limit("cred-2", Some(Duration::from_secs(10)));
+        let hint = retrier.hint_for(Some("cred-2"), None);
+        let delay = retrier
+            .adaptive_backoff_delay_with_jitter(0, &hint, 0.0)
+            .unwrap();
+        assert!(delay > Duration::from_secs(9) && delay <= Duration::from_secs(10));
+
+        // 请求成功后清除限流历史
+        retrier.record_success("cred-1");
+        assert!(retrier.hint_for(Some("cred-1"), None).rate_limit.is_none());
+    }
+
+    #[test]
+    fn test_retry_hint_from_response() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("retry-after", "2".parse().unwrap());
+        assert_eq!(
+            RetryHint::from_response(Some(&headers), None).retry_after,
+            Some(Duration::from_secs(2))
+        );
+
+        let body = serde_json::json!({
+            "error": {"details": [{"retryDelay": "1.5s"}]}
+        });
+        assert_eq!(
+            RetryHint::from_response(None, Some(&body)).retry_after,
+            Some(Duration::from_millis(1500))
+        );
+        assert!(RetryHint::from_response(None, None).retry_after.is_none());
+    }
+
     #[tokio::test]
     async fn test_execute_success_first_try() {
         let retrier = Retrier::with_defaults();
@@ -5,7 +5,7 @@
 //! # 功能
 //!
 //! - 解析 Amp CLI 请求路径
-//! - 应用模型映射（将不可用模型映射到可用替代）
+//! - 应用模型映射（将不可用模型映射到可用替代），支持按 provider 覆盖和 `*` 通配符
 //! - 识别管理路由（/api/auth/*, /api/user/*）
 //!
 //! # 示例
@@ -23,6 +23,55 @@
 //! ```
 
 use crate::config::{AmpConfig, AmpModelMapping};
+use crate::models::provider_pool_model::pattern_matches;
+use serde::Serialize;
+use std::time::Duration;
+
+/// 逐跳（hop-by-hop）头部，代理转发时不应透传（RFC 7230 §6.1）
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// 判断头部是否应在代理转发时丢弃
+///
+/// 除标准逐跳头部外，还包括 `Connection` 头中声明的头部，
+/// 以及由 HTTP 客户端 / 服务器重新计算的 `host` 和 `content-length`。
+pub fn is_hop_by_hop_header(name: &str, connection: Option<&str>) -> bool {
+    let name = name.to_ascii_lowercase();
+    HOP_BY_HOP_HEADERS.contains(&name.as_str())
+        || name == "host"
+        || name == "content-length"
+        || connection.is_some_and(|c| c.split(',').any(|t| t.trim().eq_ignore_ascii_case(&name)))
+}
+
+/// 生效的模型映射（按匹配优先级排列）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveAmpMapping {
+    /// 优先级（越小越先匹配）
+    pub priority: usize,
+    pub provider: Option<String>,
+    pub from: String,
+    pub to: String,
+    pub wildcard: bool,
+}
+
+/// 映射的匹配优先级：provider 专属精确 > provider 专属通配 > 全局精确 > 全局通配
+fn mapping_rank(mapping: &AmpModelMapping) -> u8 {
+    match (mapping.provider.is_some(), mapping.from.contains('*')) {
+        (true, false) => 0,
+        (true, true) => 1,
+        (false, false) => 2,
+        (false, true) => 3,
+    }
+}
 
 /// Amp 路由解析结果
 #[derive(Debug, Clone, PartialEq)]
@@ -65,6 +114,10 @@ pub struct AmpRouter {
     model_mappings: Vec<AmpModelMapping>,
     /// 是否限制管理端点只能从 localhost 访问
     restrict_management_to_localhost: bool,
+    /// 管理代理等待上游响应头的超时
+    upstream_timeout: Duration,
+    /// 管理代理重试次数
+    upstream_max_retries: u32,
 }
 
 impl AmpRouter {
@@ -74,6 +127,8 @@ impl AmpRouter {
             upstream_url: config.upstream_url,
             model_mappings: config.model_mappings,
             restrict_management_to_localhost: config.restrict_management_to_localhost,
+            upstream_timeout: Duration::from_secs(config.upstream_timeout_secs.max(1)),
+            upstream_max_retries: config.upstream_max_retries,
         }
     }
 
@@ -83,11 +138,12 @@ impl AmpRouter {
         model_mappings: Vec<AmpModelMapping>,
         restrict_management_to_localhost: bool,
     ) -> Self {
-        Self {
+        Self::new(AmpConfig {
             upstream_url,
             model_mappings,
             restrict_management_to_localhost,
-        }
+            ..AmpConfig::default()
+        })
     }
 
     /// 获取上游 URL
@@ -100,6 +156,16 @@ impl AmpRouter {
         self.restrict_management_to_localhost
     }
 
+    /// 管理代理等待上游响应头的超时
+    pub fn upstream_timeout(&self) -> Duration {
+        self.upstream_timeout
+    }
+
+    /// 管理代理重试次数
+    pub fn upstream_max_retries(&self) -> u32 {
+        self.upstream_max_retries
+    }
+
     /// 解析 provider 路由
     ///
     /// 支持的路径格式：
@@ -157,12 +223,52 @@ impl AmpRouter {
     /// assert_eq!(mapped, "claude-sonnet-4");
     /// ```
     pub fn apply_model_mapping(&self, model: &str) -> String {
-        for mapping in &self.model_mappings {
-            if mapping.from == model {
-                return mapping.to.clone();
-            }
-        }
-        model.to_string()
+        self.apply_model_mapping_for(None, model)
+    }
+
+    /// 按 provider 应用模型映射
+    ///
+    /// 匹配顺序：provider 专属精确 > provider 专属通配 > 全局精确 > 全局通配，
+    /// 同一优先级内按配置顺序匹配。
+    pub fn apply_model_mapping_for(&self, provider: Option<&str>, model: &str) -> String {
+        self.resolve_model_mapping(provider, model)
+            .map(|mapping| mapping.to.clone())
+            .unwrap_or_else(|| model.to_string())
+    }
+
+    /// 查找对模型生效的映射规则
+    pub fn resolve_model_mapping(
+        &self,
+        provider: Option<&str>,
+        model: &str,
+    ) -> Option<&AmpModelMapping> {
+        self.model_mappings
+            .iter()
+            .filter(|mapping| match (&mapping.provider, provider) {
+                (None, _) => true,
+                (Some(p), Some(provider)) => p.eq_ignore_ascii_case(provider),
+                (Some(_), None) => false,
+            })
+            .filter(|mapping| pattern_matches(&mapping.from, model))
+            .min_by_key(|mapping| mapping_rank(mapping))
+    }
+
+    /// 按匹配优先级列出所有映射规则
+    pub fn effective_mappings(&self) -> Vec<EffectiveAmpMapping> {
+        let mut mappings: Vec<&AmpModelMapping> = self.model_mappings.iter().collect();
+        // 稳定排序，同一优先级保持配置顺序
+        mappings.sort_by_key(|mapping| mapping_rank(mapping));
+        mappings
+            .into_iter()
+            .enumerate()
+            .map(|(priority, mapping)| EffectiveAmpMapping {
+                priority,
+                provider: mapping.provider.clone(),
+                from: mapping.from.clone(),
+                to: mapping.to.clone(),
+                wildcard: mapping.from.contains('*'),
+            })
+            .collect()
     }
 
     /// 转换请求体中的模型名称
@@ -285,9 +391,9 @@ impl AmpRouter {
             .collect()
     }
 
-    /// 检查是否有模型映射
+    /// 检查是否有模型映射（仅全局映射）
     pub fn has_model_mapping(&self, model: &str) -> bool {
-        self.model_mappings.iter().any(|m| m.from == model)
+        self.resolve_model_mapping(None, model).is_some()
     }
 
     /// 获取所有模型映射
@@ -300,6 +406,7 @@ impl AmpRouter {
         self.model_mappings.push(AmpModelMapping {
             from: from.to_string(),
             to: to.to_string(),
+            provider: None,
         });
     }
 
@@ -356,13 +463,16 @@ mod tests {
                 AmpModelMapping {
                     from: "claude-opus-4.5".to_string(),
                     to: "claude-sonnet-4".to_string(),
+                    provider: None,
                 },
                 AmpModelMapping {
                     from: "gpt-5".to_string(),
                     to: "gemini-2.5-pro".to_string(),
+                    provider: None,
                 },
             ],
             restrict_management_to_localhost: false,
+            ..AmpConfig::default()
         };
         AmpRouter::new(config)
     }
@@ -654,6 +764,63 @@ mod tests {
         assert!(empty.is_empty());
     }
 
+    #[test]
+    fn test_per_provider_and_wildcard_mappings() {
+        let config: AmpConfig = serde_yaml::from_str(
+            r#"
+model_mappings:
+  - from: "claude-opus-*"
+    to: claude-sonnet-4-5
+  - from: claude-opus-4-5
+    to: claude-haiku-4-5
+  - from: "claude-opus-*"
+    to: gemini-2.5-pro
+    provider: google
+  - from: claude-opus-4-5
+    to: kiro-opus
+    provider: anthropic
+"#,
+        )
+        .unwrap();
+        let router = AmpRouter::new(config);
+
+        // 全局：精确优先于通配
+        assert_eq!(
+            router.apply_model_mapping("claude-opus-4-5"),
+            "claude-haiku-4-5"
+        );
+        assert_eq!(
+            router.apply_model_mapping("claude-opus-4-1"),
+            "claude-sonnet-4-5"
+        );
+
+        // provider 专属优先于全局
+        assert_eq!(
+            router.apply_model_mapping_for(Some("anthropic"), "claude-opus-4-5"),
+            "kiro-opus"
+        );
+        assert_eq!(
+            router.apply_model_mapping_for(Some("Google"), "claude-opus-4-5"),
+            "gemini-2.5-pro"
+        );
+        // 其他 provider 回退到全局映射
+        assert_eq!(
+            router.apply_model_mapping_for(Some("openai"), "claude-opus-4-1"),
+            "claude-sonnet-4-5"
+        );
+        assert_eq!(
+            router.apply_model_mapping_for(Some("openai"), "gpt-5"),
+            "gpt-5"
+        );
+
+        let effective = router.effective_mappings();
+        assert_eq!(effective.len(), 4);
+        assert_eq!(effective[0].to, "kiro-opus");
+        assert_eq!(effective[1].to, "gemini-2.5-pro");
+        assert_eq!(effective[2].to, "claude-haiku-4-5");
+        assert!(effective[3].wildcard);
+    }
+
     #[test]
     fn test_default_router() {
         let router = AmpRouter::default();
@@ -669,9 +836,37 @@ mod tests {
             upstream_url: None,
             model_mappings: vec![],
             restrict_management_to_localhost: true,
+            ..AmpConfig::default()
         };
         let router = AmpRouter::new(config);
 
         assert!(router.restrict_management_to_localhost());
     }
+
+    #[test]
+    fn test_upstream_settings() {
+        let router = AmpRouter::default();
+        assert_eq!(router.upstream_timeout(), Duration::from_secs(30));
+        assert_eq!(router.upstream_max_retries(), 1);
+
+        let config: AmpConfig =
+            serde_yaml::from_str("upstream_timeout_secs: 5\nupstream_max_retries: 3").unwrap();
+        let router = AmpRouter::new(config);
+        assert_eq!(router.upstream_timeout(), Duration::from_secs(5));
+        assert_eq!(router.upstream_max_retries(), 3);
+    }
+
+    #[test]
+    fn test_is_hop_by_hop_header() {
+        assert!(is_hop_by_hop_header("Connection", None));
+        assert!(is_hop_by_hop_header("transfer-encoding", None));
+        assert!(is_hop_by_hop_header("Host", None));
+        assert!(is_hop_by_hop_header(
+            "x-custom-hop",
+            Some("keep-alive, X-Custom-Hop")
+        ));
+        assert!(!is_hop_by_hop_header("authorization", None));
+        assert!(!is_hop_by_hop_header("set-cookie", Some("keep-alive")));
+        assert!(!is_hop_by_hop_header("content-type", None));
+    }
 }
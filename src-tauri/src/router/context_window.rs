@@ -0,0 +1,244 @@
+//! 模型上下文窗口预检
+//!
+//! 转发前按本地估算的输入 Token 数检查目标模型的上下文窗口，避免把注定失败的请求发给上游。
+//! 超出时按配置直接拒绝，或改用同一模型家族中上下文更大的模型。
+//!
+//! 匹配规则与输出上限相同：按模型名前缀匹配，最长前缀优先；配置中的值优先于内置值。
+
+use crate::config::{ContextOverflowAction, ContextOverflowConfig};
+use std::collections::HashMap;
+
+/// 内置的模型上下文窗口（模型名前缀 -> Token 数）
+const BUILTIN_WINDOWS: &[(&str, u32)] = &[
+    // Anthropic
+    ("claude", 200000),
+    // OpenAI
+    ("gpt-5", 400000),
+    ("gpt-4.1", 1047576),
+    ("gpt-4o", 128000),
+    ("gpt-4-turbo", 128000),
+    ("gpt-4", 8192),
+    ("gpt-3.5-turbo", 16385),
+    ("o1", 200000),
+    ("o3", 200000),
+    ("o4-mini", 200000),
+    // Google
+    ("gemini-1.5-pro", 2097152),
+    ("gemini", 1048576),
+    // 其他
+    ("deepseek", 128000),
+    ("qwen3-coder", 262144),
+];
+
+/// 上下文窗口检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextCheck {
+    /// 未超出（或模型未知、预检未启用）
+    Fits,
+    /// 超出，改用上下文更大的模型
+    Upgrade { model: String, context_window: u32 },
+    /// 超出且没有可升级的模型
+    Exceeded { context_window: u32 },
+}
+
+/// 模型家族（升级只在同一家族内进行）
+pub fn model_family(model: &str) -> &str {
+    let model = model.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+    if model.starts_with("claude") {
+        "claude"
+    } else if ["gpt", "chatgpt", "o1", "o3", "o4"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+    {
+        "openai"
+    } else {
+        model.split(['-', '.', '/']).next().unwrap_or(model)
+    }
+}
+
+/// 模型上下文窗口注册表
+#[derive(Debug, Clone, Default)]
+pub struct ModelContextWindows {
+    enabled: bool,
+    action: ContextOverflowAction,
+    /// 可升级到的候选模型
+    upgrade_models: Vec<String>,
+    /// 配置中的上下文窗口（模型名前缀 -> Token 数）
+    overrides: HashMap<String, u32>,
+}
+
+impl ModelContextWindows {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从配置创建（值为 0 的条目忽略）
+    pub fn from_config(config: &ContextOverflowConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            action: config.action,
+            upgrade_models: config
+                .upgrade_models
+                .iter()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect(),
+            overrides: config
+                .context_windows
+                .iter()
+                .filter(|(_, window)| **window > 0)
+                .map(|(model, window)| (model.to_lowercase(), *window))
+                .collect(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 查询模型的上下文窗口，未知模型返回 None
+    pub fn window_for(&self, model: &str) -> Option<u32> {
+        let model = model.to_lowercase();
+        longest_prefix(self.overrides.iter().map(|(k, v)| (k.as_str(), *v)), &model)
+            .or_else(|| longest_prefix(BUILTIN_WINDOWS.iter().copied(), &model))
+    }
+
+    /// 检查输入 Token 数是否超出模型的上下文窗口
+    pub fn check(&self, model: &str, input_tokens: u32) -> ContextCheck {
+        if !self.enabled {
+            return ContextCheck::Fits;
+        }
+        let Some(context_window) = self.window_for(model) else {
+            return ContextCheck::Fits;
+        };
+        if input_tokens <= context_window {
+            return ContextCheck::Fits;
+        }
+        if self.action == ContextOverflowAction::Upgrade {
+            let family = model_family(model);
+            let upgrade = self
+                .upgrade_models
+                .iter()
+                .filter(|candidate| model_family(candidate) == family)
+                .filter_map(|candidate| Some((candidate, self.window_for(candidate)?)))
+                .filter(|(_, window)| *window >= input_tokens)
+                .min_by_key(|(_, window)| *window);
+            if let Some((model, context_window)) = upgrade {
+                return ContextCheck::Upgrade {
+                    model: model.clone(),
+                    context_window,
+                };
+            }
+        }
+        ContextCheck::Exceeded { context_window }
+    }
+}
+
+fn longest_prefix<'a>(entries: impl Iterator<Item = (&'a str, u32)>, model: &str) -> Option<u32> {
+    entries
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(action: ContextOverflowAction) -> ContextOverflowConfig {
+        let mut context_windows = HashMap::new();
+        context_windows.insert("claude-sonnet-4-5[1m]".to_string(), 1000000);
+        ContextOverflowConfig {
+            enabled: true,
+            action,
+            upgrade_models: vec![
+                "gpt-4.1".to_string(),
+                "claude-sonnet-4-5[1m]".to_string(),
+                "gpt-5".to_string(),
+            ],
+            context_windows,
+        }
+    }
+
+    #[test]
+    fn test_model_family() {
+        assert_eq!(model_family("claude-opus-4-5"), "claude");
+        assert_eq!(model_family("gpt-4o-mini"), "openai");
+        assert_eq!(model_family("o3-mini"), "openai");
+        assert_eq!(model_family("gemini-2.5-pro"), "gemini");
+        assert_eq!(model_family("deepseek-chat"), "deepseek");
+    }
+
+    #[test]
+    fn test_window_for() {
+        let windows = ModelContextWindows::from_config(&config(ContextOverflowAction::Reject));
+        assert_eq!(windows.window_for("claude-sonnet-4-5"), Some(200000));
+        assert_eq!(windows.window_for("claude-sonnet-4-5[1m]"), Some(1000000));
+        assert_eq!(windows.window_for("gpt-4-0613"), Some(8192));
+        assert_eq!(windows.window_for("gpt-4o-mini"), Some(128000));
+        assert_eq!(windows.window_for("gemini-1.5-pro-002"), Some(2097152));
+        assert_eq!(windows.window_for("unknown-model"), None);
+    }
+
+    #[test]
+    fn test_check_reject() {
+        let windows = ModelContextWindows::from_config(&config(ContextOverflowAction::Reject));
+        assert_eq!(
+            windows.check("claude-sonnet-4-5", 150000),
+            ContextCheck::Fits
+        );
+        assert_eq!(windows.check("unknown-model", 5000000), ContextCheck::Fits);
+        assert_eq!(
+            windows.check("claude-sonnet-4-5", 250000),
+            ContextCheck::Exceeded {
+                context_window: 200000
+            }
+        );
+
+        // 未启用时不检查
+        assert_eq!(
+            ModelContextWindows::new().check("gpt-4", 100000),
+            ContextCheck::Fits
+        );
+    }
+
+    #[test]
+    fn test_check_upgrade_within_family() {
+        let windows = ModelContextWindows::from_config(&config(ContextOverflowAction::Upgrade));
+        assert_eq!(
+            windows.check("claude-sonnet-4-5", 250000),
+            ContextCheck::Upgrade {
+                model: "claude-sonnet-4-5[1m]".to_string(),
+                context_window: 1000000
+            }
+        );
+        // 选择能容纳请求的最小窗口
+        assert_eq!(
+            windows.check("gpt-4o", 300000),
+            ContextCheck::Upgrade {
+                model: "gpt-5".to_string(),
+                context_window: 400000
+            }
+        );
+        assert_eq!(
+            windows.check("gpt-4o", 500000),
+            ContextCheck::Upgrade {
+                model: "gpt-4.1".to_string(),
+                context_window: 1047576
+            }
+        );
+        // 同家族没有足够大的模型
+        assert_eq!(
+            windows.check("claude-opus-4-5", 2000000),
+            ContextCheck::Exceeded {
+                context_window: 200000
+            }
+        );
+        assert_eq!(
+            windows.check("deepseek-chat", 200000),
+            ContextCheck::Exceeded {
+                context_window: 128000
+            }
+        );
+    }
+}
@@ -1,7 +1,11 @@
 //! 模型映射器
 //!
 //! 提供模型别名映射和解析功能
+//!
+//! 解析顺序：精确别名优先，未命中时按优先级依次尝试通配符 / 正则规则。
 
+use crate::config::{ModelAliasRule, ModelAliasRuleKind};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -16,11 +20,97 @@ pub struct ModelInfo {
     pub actual_model: Option<String>,
 }
 
+/// 编译后的别名规则
+#[derive(Debug, Clone)]
+struct CompiledAliasRule {
+    regex: Regex,
+    target: String,
+    kind: ModelAliasRuleKind,
+    priority: i32,
+}
+
+impl CompiledAliasRule {
+    fn compile(rule: &ModelAliasRule) -> Result<Self, String> {
+        if rule.pattern.is_empty() {
+            return Err("模型别名规则的 pattern 不能为空".to_string());
+        }
+        if rule.target.is_empty() {
+            return Err(format!(
+                "模型别名规则 '{}' 的 target 不能为空",
+                rule.pattern
+            ));
+        }
+
+        let source = match rule.kind {
+            ModelAliasRuleKind::Glob => glob_to_regex(&rule.pattern),
+            ModelAliasRuleKind::Regex => format!("^(?:{})$", rule.pattern),
+        };
+        let regex = Regex::new(&source)
+            .map_err(|e| format!("模型别名规则 '{}' 无效: {}", rule.pattern, e))?;
+
+        Ok(Self {
+            regex,
+            target: rule.target.clone(),
+            kind: rule.kind,
+            priority: rule.priority,
+        })
+    }
+
+    /// 尝试匹配模型名，命中时返回映射后的模型名
+    fn apply(&self, model: &str) -> Option<String> {
+        let caps = self.regex.captures(model)?;
+        let resolved = match self.kind {
+            ModelAliasRuleKind::Glob => {
+                // target 中的 `*` 依次替换为通配符匹配到的内容
+                let mut groups = caps.iter().skip(1).flatten().map(|m| m.as_str());
+                let mut out = String::with_capacity(self.target.len());
+                for c in self.target.chars() {
+                    if c == '*' {
+                        out.push_str(groups.next().unwrap_or(""));
+                    } else {
+                        out.push(c);
+                    }
+                }
+                out
+            }
+            ModelAliasRuleKind::Regex => {
+                let mut out = String::new();
+                caps.expand(&self.target, &mut out);
+                out
+            }
+        };
+        (!resolved.is_empty()).then_some(resolved)
+    }
+}
+
+/// 将 glob 模式转换为完整匹配的正则表达式，`*` / `?` 转为捕获组
+fn glob_to_regex(pattern: &str) -> String {
+    let mut source = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => source.push_str("(.*)"),
+            '?' => source.push_str("(.)"),
+            _ => source.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    source.push('$');
+    source
+}
+
+/// 校验模型别名规则（配置加载时调用）
+pub fn validate_alias_rules(rules: &[ModelAliasRule]) -> Result<(), String> {
+    rules
+        .iter()
+        .try_for_each(|rule| CompiledAliasRule::compile(rule).map(|_| ()))
+}
+
 /// 模型映射器 - 管理模型别名映射
 #[derive(Debug, Clone, Default)]
 pub struct ModelMapper {
     /// 别名到实际模型的映射 (alias -> actual)
     aliases: HashMap<String, String>,
+    /// 通配符 / 正则规则（按优先级降序）
+    rules: Vec<CompiledAliasRule>,
 }
 
 impl ModelMapper {
@@ -28,24 +118,49 @@ impl ModelMapper {
     pub fn new() -> Self {
         Self {
             aliases: HashMap::new(),
+            rules: Vec::new(),
         }
     }
 
     /// 从别名映射创建模型映射器
     pub fn from_aliases(aliases: HashMap<String, String>) -> Self {
-        Self { aliases }
+        Self {
+            aliases,
+            rules: Vec::new(),
+        }
     }
 
     /// 解析模型名（别名 -> 实际名）
     ///
-    /// 如果模型名是别名，返回实际模型名；否则返回原模型名
+    /// 精确别名优先；未命中时按优先级尝试别名规则；都未命中则返回原模型名
     pub fn resolve(&self, model: &str) -> String {
-        self.aliases
-            .get(model)
-            .cloned()
+        if let Some(actual) = self.aliases.get(model) {
+            return actual.clone();
+        }
+
+        self.rules
+            .iter()
+            .find_map(|rule| rule.apply(model))
             .unwrap_or_else(|| model.to_string())
     }
 
+    /// 设置别名规则（全部校验通过才会替换现有规则）
+    pub fn set_rules(&mut self, rules: &[ModelAliasRule]) -> Result<(), String> {
+        let mut compiled = rules
+            .iter()
+            .map(CompiledAliasRule::compile)
+            .collect::<Result<Vec<_>, _>>()?;
+        // 稳定排序：相同优先级保持配置顺序
+        compiled.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        self.rules = compiled;
+        Ok(())
+    }
+
+    /// 获取别名规则数量
+    pub fn rules_len(&self) -> usize {
+        self.rules.len()
+    }
+
     /// 添加别名映射
     pub fn add_alias(&mut self, alias: &str, actual: &str) {
         self.aliases.insert(alias.to_string(), actual.to_string());
@@ -108,9 +223,10 @@ impl ModelMapper {
         models
     }
 
-    /// 清空所有别名
+    /// 清空所有别名和别名规则
     pub fn clear(&mut self) {
         self.aliases.clear();
+        self.rules.clear();
     }
 }
 
@@ -159,6 +275,88 @@ mod mapper_tests {
         assert!(!mapper.has_alias("gpt-4"));
     }
 
+    fn rule(
+        pattern: &str,
+        target: &str,
+        kind: ModelAliasRuleKind,
+        priority: i32,
+    ) -> ModelAliasRule {
+        ModelAliasRule {
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+            kind,
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_glob_rules() {
+        let mut mapper = ModelMapper::new();
+        mapper
+            .set_rules(&[
+                rule("gpt-4*", "claude-sonnet-4-5", ModelAliasRuleKind::Glob, 0),
+                rule("*-preview", "*", ModelAliasRuleKind::Glob, 0),
+            ])
+            .unwrap();
+
+        assert_eq!(mapper.resolve("gpt-4o"), "claude-sonnet-4-5");
+        assert_eq!(mapper.resolve("gemini-3-pro-preview"), "gemini-3-pro");
+        // 未命中任何规则
+        assert_eq!(mapper.resolve("qwen3-coder-plus"), "qwen3-coder-plus");
+    }
+
+    #[test]
+    fn test_regex_rules_and_priority() {
+        let mut mapper = ModelMapper::new();
+        mapper
+            .set_rules(&[
+                rule(r"gpt-(.*)", "openai/gpt-$1", ModelAliasRuleKind::Regex, 0),
+                rule("gpt-4*", "claude-sonnet-4-5", ModelAliasRuleKind::Glob, 10),
+            ])
+            .unwrap();
+
+        // 高优先级规则先匹配
+        assert_eq!(mapper.resolve("gpt-4o"), "claude-sonnet-4-5");
+        assert_eq!(mapper.resolve("gpt-5"), "openai/gpt-5");
+        // 正则需要完整匹配
+        assert_eq!(mapper.resolve("my-gpt-5"), "my-gpt-5");
+    }
+
+    #[test]
+    fn test_exact_alias_takes_precedence() {
+        let mut mapper = ModelMapper::new();
+        mapper.add_alias("gpt-4o", "claude-opus-4-5");
+        mapper
+            .set_rules(&[rule(
+                "gpt-4*",
+                "claude-sonnet-4-5",
+                ModelAliasRuleKind::Glob,
+                0,
+            )])
+            .unwrap();
+
+        assert_eq!(mapper.resolve("gpt-4o"), "claude-opus-4-5");
+    }
+
+    #[test]
+    fn test_invalid_rule_rejected() {
+        let mut mapper = ModelMapper::new();
+        mapper
+            .set_rules(&[rule(
+                "gpt-4*",
+                "claude-sonnet-4-5",
+                ModelAliasRuleKind::Glob,
+                0,
+            )])
+            .unwrap();
+
+        let invalid = [rule("gpt-(", "x", ModelAliasRuleKind::Regex, 0)];
+        assert!(validate_alias_rules(&invalid).is_err());
+        assert!(mapper.set_rules(&invalid).is_err());
+        // 校验失败时保留原有规则
+        assert_eq!(mapper.rules_len(), 1);
+    }
+
     #[test]
     fn test_available_models() {
         let mut mapper = ModelMapper::new();
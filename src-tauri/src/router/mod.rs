@@ -10,15 +10,22 @@
 //!
 //! 模型映射：
 //! - 支持模型别名映射（如 `gpt-4` -> `claude-sonnet-4-5-20250514`）
+//! - 支持通配符 / 正则别名规则（如 `gpt-4*` -> `claude-sonnet-4-5`）
+//! - 按模型输出上限自动钳制 `max_tokens`
+//! - 按模型上下文窗口预检输入长度，超出时拒绝或升级到上下文更大的模型
 
 mod amp_router;
+mod context_window;
 mod mapper;
 mod provider_router;
 mod route_registry;
 mod rules;
+mod token_limits;
 
-pub use amp_router::{AmpRouteMatch, AmpRouter};
-pub use mapper::{ModelInfo, ModelMapper};
+pub use amp_router::{is_hop_by_hop_header, AmpRouteMatch, AmpRouter, EffectiveAmpMapping};
+pub use context_window::{ContextCheck, ModelContextWindows};
+pub use mapper::{validate_alias_rules, ModelInfo, ModelMapper};
 pub use provider_router::ProviderRouter;
-pub use route_registry::{RegisteredRoute, RouteRegistry, RouteType};
+pub use route_registry::{validate_route_name, RegisteredRoute, RouteRegistry, RouteType};
 pub use rules::{RouteResult, Router};
+pub use token_limits::{MaxTokensAdjustment, ModelTokenLimits, MAX_TOKENS_ADJUSTED_HEADER};
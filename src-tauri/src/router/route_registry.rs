@@ -35,6 +35,9 @@ pub struct RegisteredRoute {
     pub enabled: bool,
     /// 优先级（数字越小优先级越高）
     pub priority: u32,
+    /// 是否通过管理 API 动态注册
+    #[serde(default)]
+    pub dynamic: bool,
 }
 
 impl RegisteredRoute {
@@ -57,6 +60,7 @@ impl RegisteredRoute {
             protocols: vec!["openai".to_string(), "claude".to_string()],
             enabled: true,
             priority: 10,
+            dynamic: false,
         }
     }
 
@@ -71,6 +75,7 @@ impl RegisteredRoute {
             protocols: vec!["openai".to_string(), "claude".to_string()],
             enabled: true,
             priority: 20,
+            dynamic: false,
         }
     }
 
@@ -85,9 +90,17 @@ impl RegisteredRoute {
             protocols: vec!["openai".to_string(), "claude".to_string()],
             enabled: true,
             priority: 100,
+            dynamic: false,
         }
     }
 
+    /// 创建动态注册的命名路由（绑定到指定凭证）
+    pub fn dynamic_route(name: &str, provider_type: &str, credential_uuid: &str) -> Self {
+        let mut route = Self::provider_namespace(provider_type, credential_uuid, Some(name));
+        route.dynamic = true;
+        route
+    }
+
     /// 生成路由名称
     fn generate_route_name(provider_type: &str, credential_name: Option<&str>) -> String {
         if let Some(name) = credential_name {
@@ -114,6 +127,26 @@ impl RegisteredRoute {
     }
 }
 
+/// 与内置端点冲突的保留路由名称
+const RESERVED_ROUTE_NAMES: &[&str] = &["v0", "v1", "api", "ws", "health", "default"];
+
+/// 校验动态路由名称（仅允许字母、数字、`-`、`_`）
+pub fn validate_route_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("路由名称不能为空".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("路由名称 '{}' 只能包含字母、数字、- 和 _", name));
+    }
+    if RESERVED_ROUTE_NAMES.contains(&name.to_lowercase().as_str()) {
+        return Err(format!("路由名称 '{}' 为保留名称", name));
+    }
+    Ok(())
+}
+
 /// 路由注册表
 #[derive(Debug, Default)]
 pub struct RouteRegistry {
@@ -123,6 +156,10 @@ pub struct RouteRegistry {
     name_index: HashMap<String, usize>,
     /// UUID 到索引的映射
     uuid_index: HashMap<String, usize>,
+    /// 路由专属入站 API Key（小写选择器 -> API Key）
+    api_keys: HashMap<String, String>,
+    /// 通过管理 API 设置的路由专属 API Key（配置替换 Key 和重建注册表时保留）
+    runtime_api_keys: HashMap<String, String>,
 }
 
 impl RouteRegistry {
@@ -211,6 +248,106 @@ impl RouteRegistry {
         self.uuid_index.clear();
     }
 
+    /// 按选择器启用 / 禁用路由，返回是否找到路由
+    ///
+    /// 同一凭证的名称路由和 UUID 路由共享启用状态；动态路由单独启用 / 禁用。
+    pub fn set_enabled(&mut self, selector: &str, enabled: bool) -> bool {
+        let Some(route) = self.find_by_selector(selector) else {
+            return false;
+        };
+
+        if route.dynamic {
+            let name = route.credential_name.clone();
+            for r in self
+                .routes
+                .iter_mut()
+                .filter(|r| r.dynamic && r.credential_name == name)
+            {
+                r.enabled = enabled;
+            }
+        } else {
+            let uuid = route.credential_uuid.clone();
+            self.set_credential_enabled(uuid.as_deref(), enabled);
+        }
+        true
+    }
+
+    /// 设置凭证的名称路由和 UUID 路由的启用状态
+    fn set_credential_enabled(&mut self, credential_uuid: Option<&str>, enabled: bool) {
+        for route in self
+            .routes
+            .iter_mut()
+            .filter(|r| !r.dynamic && r.credential_uuid.as_deref() == credential_uuid)
+        {
+            route.enabled = enabled;
+        }
+    }
+
+    /// 从旧注册表继承运行时状态（动态路由、禁用状态和路由专属 API Key）
+    ///
+    /// 重建注册表（如配置热重载）后调用，避免丢失通过管理 API 所做的修改。
+    pub fn inherit_runtime_state(&mut self, previous: &RouteRegistry) {
+        for route in &previous.routes {
+            if route.dynamic {
+                let name = route.credential_name.as_deref().unwrap_or_default();
+                if self.find_by_name(name).is_none() {
+                    self.register(route.clone());
+                }
+            } else if !route.enabled {
+                // 按凭证继承，凭证改名后名称路由的路径变化也不影响
+                self.set_credential_enabled(route.credential_uuid.as_deref(), false);
+            }
+        }
+        for (selector, api_key) in &previous.runtime_api_keys {
+            self.set_api_key(selector, api_key);
+        }
+    }
+
+    /// 设置路由专属 API Key（选择器为凭证名称或 UUID，通过管理 API 设置）
+    pub fn set_api_key(&mut self, selector: &str, api_key: &str) {
+        let selector = selector.to_lowercase();
+        self.api_keys.insert(selector.clone(), api_key.to_string());
+        self.runtime_api_keys.insert(selector, api_key.to_string());
+    }
+
+    /// 移除路由专属 API Key
+    pub fn remove_api_key(&mut self, selector: &str) -> bool {
+        let selector = selector.to_lowercase();
+        self.runtime_api_keys.remove(&selector);
+        self.api_keys.remove(&selector).is_some()
+    }
+
+    /// 用配置替换路由专属 API Key（保留通过管理 API 设置的 Key）
+    pub fn set_api_keys(&mut self, api_keys: &HashMap<String, String>) {
+        self.api_keys = api_keys
+            .iter()
+            .filter(|(_, key)| !key.is_empty())
+            .map(|(selector, key)| (selector.to_lowercase(), key.clone()))
+            .chain(self.runtime_api_keys.clone())
+            .collect();
+    }
+
+    /// 获取选择器对应的专属 API Key
+    ///
+    /// 同一凭证的名称路由和 UUID 路由共享专属 Key；动态路由和已禁用路由的 Key 不共享给其他选择器。
+    pub fn api_key_for(&self, selector: &str) -> Option<&str> {
+        let lookup = |s: &str| self.api_keys.get(&s.to_lowercase()).map(String::as_str);
+
+        if let Some(key) = lookup(selector) {
+            return Some(key);
+        }
+
+        let uuid = self
+            .find_by_selector(selector)?
+            .credential_uuid
+            .as_deref()?;
+        self.routes
+            .iter()
+            .filter(|r| !r.dynamic && r.enabled && r.credential_uuid.as_deref() == Some(uuid))
+            .find_map(|r| r.credential_name.as_deref().and_then(lookup))
+            .or_else(|| lookup(uuid))
+    }
+
     /// 按优先级排序
     fn sort_by_priority(&mut self) {
         self.routes.sort_by_key(|r| r.priority);
@@ -263,4 +400,155 @@ mod tests {
         assert!(registry.find_by_name("my-kiro-account").is_none());
         assert!(registry.find_by_uuid("uuid-123").is_none());
     }
+
+    #[test]
+    fn test_dynamic_route_and_disable() {
+        let mut registry = RouteRegistry::new();
+        registry.register(RegisteredRoute::provider_namespace(
+            "kiro",
+            "uuid-123",
+            Some("my-kiro"),
+        ));
+        registry.register(RegisteredRoute::dynamic_route("team-a", "kiro", "uuid-123"));
+
+        let route = registry.find_by_selector("team-a").unwrap();
+        assert!(route.dynamic);
+        assert_eq!(route.credential_uuid.as_deref(), Some("uuid-123"));
+        assert_eq!(route.path_pattern, "/team-a/v1/{endpoint}");
+
+        assert!(registry.set_enabled("team-a", false));
+        assert!(!registry.find_by_selector("team-a").unwrap().enabled);
+        assert!(registry.find_by_selector("my-kiro").unwrap().enabled);
+        assert!(!registry.set_enabled("missing", false));
+
+        // 重建后保留动态路由和禁用状态
+        let mut rebuilt = RouteRegistry::new();
+        rebuilt.register(RegisteredRoute::provider_namespace(
+            "kiro",
+            "uuid-123",
+            Some("my-kiro"),
+        ));
+        rebuilt.inherit_runtime_state(&registry);
+        let route = rebuilt.find_by_selector("team-a").unwrap();
+        assert!(route.dynamic);
+        assert!(!route.enabled);
+    }
+
+    #[test]
+    fn test_disable_applies_to_name_and_uuid_routes() {
+        let mut registry = RouteRegistry::new();
+        registry.register(RegisteredRoute::provider_namespace(
+            "kiro",
+            "uuid-123",
+            Some("my-kiro"),
+        ));
+        registry.register(RegisteredRoute::credential_selector("uuid-123", "kiro"));
+        registry.register(RegisteredRoute::dynamic_route("team-a", "kiro", "uuid-123"));
+
+        assert!(registry.set_enabled("my-kiro", false));
+        assert!(!registry.find_by_selector("uuid-123").unwrap().enabled);
+        assert!(registry.find_by_selector("team-a").unwrap().enabled);
+
+        // 重建后按凭证保留禁用状态（凭证改名不影响）
+        let mut rebuilt = RouteRegistry::new();
+        rebuilt.register(RegisteredRoute::provider_namespace(
+            "kiro",
+            "uuid-123",
+            Some("renamed"),
+        ));
+        rebuilt.register(RegisteredRoute::credential_selector("uuid-123", "kiro"));
+        rebuilt.inherit_runtime_state(&registry);
+        assert!(!rebuilt.find_by_selector("renamed").unwrap().enabled);
+        assert!(!rebuilt.find_by_selector("uuid-123").unwrap().enabled);
+
+        assert!(rebuilt.set_enabled("uuid-123", true));
+        assert!(rebuilt.find_by_selector("renamed").unwrap().enabled);
+    }
+
+    #[test]
+    fn test_runtime_api_keys_survive_rebuild() {
+        let mut registry = RouteRegistry::new();
+        registry.register(RegisteredRoute::dynamic_route("team-a", "kiro", "uuid-123"));
+        registry.set_api_key("team-a", "runtime-key");
+
+        // 配置替换 Key 时保留运行时设置的 Key
+        let mut keys = HashMap::new();
+        keys.insert("other".to_string(), "config-key".to_string());
+        registry.set_api_keys(&keys);
+        assert_eq!(registry.api_key_for("team-a"), Some("runtime-key"));
+
+        // 热重载重建注册表后同样保留
+        let mut rebuilt = RouteRegistry::new();
+        rebuilt.set_api_keys(&keys);
+        rebuilt.inherit_runtime_state(&registry);
+        assert_eq!(rebuilt.api_key_for("team-a"), Some("runtime-key"));
+        assert_eq!(rebuilt.api_key_for("other"), Some("config-key"));
+
+        assert!(rebuilt.remove_api_key("team-a"));
+        rebuilt.set_api_keys(&keys);
+        assert_eq!(rebuilt.api_key_for("team-a"), None);
+    }
+
+    #[test]
+    fn test_validate_route_name() {
+        assert!(validate_route_name("team-a_1").is_ok());
+        assert!(validate_route_name("").is_err());
+        assert!(validate_route_name("team a").is_err());
+        assert!(validate_route_name("a/b").is_err());
+        assert!(validate_route_name("V1").is_err());
+    }
+
+    #[test]
+    fn test_route_api_key_shared_by_name_and_uuid() {
+        let mut registry = RouteRegistry::new();
+        registry.register(RegisteredRoute::provider_namespace(
+            "kiro",
+            "uuid-123",
+            Some("My-Kiro"),
+        ));
+        registry.register(RegisteredRoute::credential_selector("uuid-123", "kiro"));
+        registry.register(RegisteredRoute::provider_namespace(
+            "gemini",
+            "uuid-456",
+            Some("my-gemini"),
+        ));
+
+        let mut keys = HashMap::new();
+        keys.insert("my-kiro".to_string(), "kiro-team-key".to_string());
+        registry.set_api_keys(&keys);
+
+        assert_eq!(registry.api_key_for("my-kiro"), Some("kiro-team-key"));
+        assert_eq!(registry.api_key_for("uuid-123"), Some("kiro-team-key"));
+        assert_eq!(registry.api_key_for("my-gemini"), None);
+        assert_eq!(registry.api_key_for("unknown"), None);
+
+        // 按 UUID 配置同样对名称路由生效
+        registry.set_api_key("uuid-456", "gemini-key");
+        assert_eq!(registry.api_key_for("my-gemini"), Some("gemini-key"));
+
+        assert!(registry.remove_api_key("uuid-456"));
+        assert_eq!(registry.api_key_for("my-gemini"), None);
+    }
+
+    #[test]
+    fn test_dynamic_route_api_key_not_shared() {
+        let mut registry = RouteRegistry::new();
+        registry.register(RegisteredRoute::provider_namespace(
+            "kiro",
+            "uuid-123",
+            Some("my-kiro"),
+        ));
+        registry.register(RegisteredRoute::credential_selector("uuid-123", "kiro"));
+        registry.register(RegisteredRoute::dynamic_route("team-a", "kiro", "uuid-123"));
+        registry.set_api_key("team-a", "team-key");
+
+        assert_eq!(registry.api_key_for("team-a"), Some("team-key"));
+        assert_eq!(registry.api_key_for("my-kiro"), None);
+        assert_eq!(registry.api_key_for("uuid-123"), None);
+
+        // 禁用动态路由后同样不会授权凭证的名称路由和 UUID 路由
+        assert!(registry.set_enabled("team-a", false));
+        assert_eq!(registry.api_key_for("my-kiro"), None);
+        assert_eq!(registry.api_key_for("uuid-123"), None);
+    }
 }
@@ -0,0 +1,185 @@
+//! 模型输出 Token 上限
+//!
+//! 客户端请求的 `max_tokens` 超过目标模型支持的上限时（常见于把 gpt-4 请求改路由到
+//! 较小的模型），上游会直接报错。这里维护各模型的输出上限，并在转发前自动钳制。
+//!
+//! 匹配规则：按模型名前缀匹配，最长前缀优先；配置中的上限优先于内置上限。
+
+use axum::http::{HeaderMap, HeaderValue};
+use std::collections::HashMap;
+
+/// 记录 `max_tokens` 调整的响应头，值为 `请求值->调整后值`
+pub const MAX_TOKENS_ADJUSTED_HEADER: &str = "x-proxycast-max-tokens-adjusted";
+
+/// 内置的模型输出上限（模型名前缀 -> 最大输出 Token 数）
+const BUILTIN_LIMITS: &[(&str, u32)] = &[
+    // Anthropic
+    ("claude-opus-4-5", 64000),
+    ("claude-opus-4", 32000),
+    ("claude-sonnet-4", 64000),
+    ("claude-haiku-4", 64000),
+    ("claude-3-7-sonnet", 64000),
+    ("claude-3-5-sonnet", 8192),
+    ("claude-3-5-haiku", 8192),
+    ("claude-3-opus", 4096),
+    ("claude-3-haiku", 4096),
+    // OpenAI
+    ("gpt-5", 128000),
+    ("gpt-4.1", 32768),
+    ("gpt-4o", 16384),
+    ("gpt-4-turbo", 4096),
+    ("gpt-4", 8192),
+    ("gpt-3.5-turbo", 4096),
+    ("o1", 100000),
+    ("o3", 100000),
+    ("o4-mini", 100000),
+    // Google
+    ("gemini-3", 65536),
+    ("gemini-2.5", 65536),
+    ("gemini-2.0", 8192),
+    ("gemini-1.5", 8192),
+    // 其他
+    ("deepseek-chat", 8192),
+    ("deepseek-reasoner", 65536),
+    ("qwen3-coder", 65536),
+];
+
+/// 一次 `max_tokens` 调整
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxTokensAdjustment {
+    /// 客户端请求的值
+    pub requested: u32,
+    /// 调整后的值（即模型上限）
+    pub adjusted: u32,
+}
+
+impl MaxTokensAdjustment {
+    /// 写入响应头
+    pub fn apply_header(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&format!("{}->{}", self.requested, self.adjusted))
+        {
+            headers.insert(MAX_TOKENS_ADJUSTED_HEADER, value);
+        }
+    }
+}
+
+/// 模型输出上限注册表
+#[derive(Debug, Clone, Default)]
+pub struct ModelTokenLimits {
+    /// 配置中的上限（模型名前缀 -> 最大输出 Token 数）
+    overrides: HashMap<String, u32>,
+}
+
+impl ModelTokenLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 替换配置中的上限（值为 0 的条目忽略）
+    pub fn set_overrides(&mut self, limits: &HashMap<String, u32>) {
+        self.overrides = limits
+            .iter()
+            .filter(|(_, limit)| **limit > 0)
+            .map(|(model, limit)| (model.to_lowercase(), *limit))
+            .collect();
+    }
+
+    /// 配置中的上限数量
+    pub fn overrides_len(&self) -> usize {
+        self.overrides.len()
+    }
+
+    /// 查询模型的输出上限，未知模型返回 None
+    pub fn limit_for(&self, model: &str) -> Option<u32> {
+        let model = model.to_lowercase();
+        longest_prefix(self.overrides.iter().map(|(k, v)| (k.as_str(), *v)), &model)
+            .or_else(|| longest_prefix(BUILTIN_LIMITS.iter().copied(), &model))
+    }
+
+    /// 按模型上限钳制 `max_tokens`，发生调整时返回调整记录
+    pub fn clamp(&self, model: &str, max_tokens: &mut Option<u32>) -> Option<MaxTokensAdjustment> {
+        let requested = (*max_tokens)?;
+        let limit = self.limit_for(model)?;
+        if requested <= limit {
+            return None;
+        }
+        *max_tokens = Some(limit);
+        Some(MaxTokensAdjustment {
+            requested,
+            adjusted: limit,
+        })
+    }
+}
+
+fn longest_prefix<'a>(entries: impl Iterator<Item = (&'a str, u32)>, model: &str) -> Option<u32> {
+    entries
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, limit)| limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_longest_prefix() {
+        let limits = ModelTokenLimits::new();
+        assert_eq!(limits.limit_for("claude-opus-4-5-20251101"), Some(64000));
+        assert_eq!(limits.limit_for("claude-opus-4-1"), Some(32000));
+        assert_eq!(limits.limit_for("gpt-4o-mini"), Some(16384));
+        assert_eq!(limits.limit_for("gpt-4-0613"), Some(8192));
+        assert_eq!(limits.limit_for("unknown-model"), None);
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let mut limits = ModelTokenLimits::new();
+        let mut overrides = HashMap::new();
+        overrides.insert("claude-sonnet-4".to_string(), 8192);
+        overrides.insert("my-model".to_string(), 0);
+        limits.set_overrides(&overrides);
+
+        assert_eq!(limits.overrides_len(), 1);
+        assert_eq!(limits.limit_for("claude-sonnet-4-5"), Some(8192));
+        assert_eq!(limits.limit_for("my-model"), None);
+    }
+
+    #[test]
+    fn test_clamp() {
+        let limits = ModelTokenLimits::new();
+
+        let mut max_tokens = Some(100000);
+        let adjustment = limits.clamp("claude-3-5-haiku", &mut max_tokens);
+        assert_eq!(max_tokens, Some(8192));
+        assert_eq!(
+            adjustment,
+            Some(MaxTokensAdjustment {
+                requested: 100000,
+                adjusted: 8192
+            })
+        );
+
+        let mut max_tokens = Some(1024);
+        assert!(limits.clamp("claude-3-5-haiku", &mut max_tokens).is_none());
+        assert_eq!(max_tokens, Some(1024));
+
+        let mut max_tokens = None;
+        assert!(limits.clamp("claude-3-5-haiku", &mut max_tokens).is_none());
+        assert_eq!(max_tokens, None);
+    }
+
+    #[test]
+    fn test_apply_header() {
+        let mut headers = HeaderMap::new();
+        MaxTokensAdjustment {
+            requested: 32000,
+            adjusted: 8192,
+        }
+        .apply_header(&mut headers);
+        assert_eq!(
+            headers.get(MAX_TOKENS_ADJUSTED_HEADER).unwrap(),
+            "32000->8192"
+        );
+    }
+}
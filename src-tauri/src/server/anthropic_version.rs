@@ -0,0 +1,215 @@
+//! Anthropic API 版本兼容
+//!
+//! `/v1/messages` 按请求头 `anthropic-version` 调整响应格式，兼容锁定了旧版本的客户端：
+//! - 2023-06-01 及之后（默认）：原样返回
+//! - 更早的版本：
+//!   - 非流式响应中只包含文本块的 `content` 合并为字符串
+//!   - SSE 事件去掉 `event:` 行只保留 `data:` 行，并丢弃 `ping` 事件
+//!
+//! 失败响应不做调整。
+
+use crate::models::anthropic::AnthropicVersion;
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap},
+    response::Response,
+};
+use futures::{Stream, StreamExt};
+
+/// 请求头中的 Anthropic API 版本
+pub fn version_from_headers(headers: &HeaderMap) -> AnthropicVersion {
+    AnthropicVersion::parse(
+        headers
+            .get("anthropic-version")
+            .and_then(|v| v.to_str().ok()),
+    )
+}
+
+/// 按客户端请求的版本调整响应格式
+pub async fn apply_version_shim(version: AnthropicVersion, response: Response) -> Response {
+    if version == AnthropicVersion::Current || !response.status().is_success() {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let (mut parts, body) = response.into_parts();
+    if content_type.starts_with("text/event-stream") {
+        let stream = legacy_sse_stream(body.into_data_stream());
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+    if !content_type.starts_with("application/json") {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[ANTHROPIC_VERSION] 读取响应体失败: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if !legacy_message_body(&mut value) {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+/// 旧版响应体：只包含文本块的 `content` 合并为字符串，返回是否修改
+fn legacy_message_body(value: &mut serde_json::Value) -> bool {
+    if value.get("type").and_then(|t| t.as_str()) != Some("message") {
+        return false;
+    }
+    let Some(blocks) = value.get("content").and_then(|c| c.as_array()) else {
+        return false;
+    };
+    let text: Option<String> = blocks
+        .iter()
+        .map(|block| match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => block.get("text").and_then(|t| t.as_str()),
+            _ => None,
+        })
+        .collect();
+    match text {
+        Some(text) => {
+            value["content"] = serde_json::Value::String(text);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 旧版 SSE 事件：去掉 `event:` 行，`ping` 事件返回 None
+fn legacy_sse_event(event: &str) -> Option<String> {
+    let is_ping = event.lines().any(|line| {
+        line.strip_prefix("event:").map(str::trim) == Some("ping")
+            || line
+                .strip_prefix("data:")
+                .and_then(|data| serde_json::from_str::<serde_json::Value>(data.trim()).ok())
+                .is_some_and(|data| data.get("type").and_then(|t| t.as_str()) == Some("ping"))
+    });
+    if is_ping {
+        return None;
+    }
+    let lines: Vec<&str> = event
+        .lines()
+        .filter(|line| !line.starts_with("event:"))
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!("{}\n\n", lines.join("\n")))
+}
+
+/// 按完整事件改写 SSE 数据流
+fn legacy_sse_stream<S>(mut inner: S) -> impl Stream<Item = Result<Bytes, axum::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin + Send + 'static,
+{
+    async_stream::stream! {
+        let mut buffer = String::new();
+        while let Some(chunk) = inner.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    buffer.push_str(&String::from_utf8_lossy(&chunk));
+                    let mut output = String::new();
+                    while let Some(end) = buffer.find("\n\n") {
+                        let event: String = buffer.drain(..end + 2).collect();
+                        if let Some(event) = legacy_sse_event(event.trim_end_matches('\n')) {
+                            output.push_str(&event);
+                        }
+                    }
+                    if !output.is_empty() {
+                        yield Ok(Bytes::from(output));
+                    }
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+        if !buffer.is_empty() {
+            yield Ok(Bytes::from(buffer));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn test_legacy_json_content_is_flattened() {
+        let body = serde_json::json!({
+            "type": "message",
+            "content": [{"type": "text", "text": "Hello"}, {"type": "text", "text": " world"}]
+        });
+        let response = axum::Json(body.clone()).into_response();
+
+        let current = apply_version_shim(AnthropicVersion::Current, response).await;
+        let bytes = axum::body::to_bytes(current.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(),
+            body
+        );
+
+        let legacy =
+            apply_version_shim(AnthropicVersion::Legacy, axum::Json(body).into_response()).await;
+        let bytes = axum::body::to_bytes(legacy.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["content"], "Hello world");
+
+        // 包含工具调用时保留内容块
+        let mut with_tool = serde_json::json!({
+            "type": "message",
+            "content": [{"type": "text", "text": "x"}, {"type": "tool_use", "id": "t"}]
+        });
+        assert!(!legacy_message_body(&mut with_tool));
+
+        // 失败响应不调整
+        let error = (
+            StatusCode::BAD_REQUEST,
+            axum::Json(serde_json::json!({"type": "message", "content": []})),
+        )
+            .into_response();
+        let error = apply_version_shim(AnthropicVersion::Legacy, error).await;
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_sse_drops_event_lines_and_pings() {
+        let chunks = vec![
+            "event: message_start\ndata: {\"type\":\"message_start\"}\n\nevent: pi",
+            "ng\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\"}\n\n",
+        ];
+        let inner = futures::stream::iter(
+            chunks
+                .into_iter()
+                .map(|chunk| Ok::<_, axum::Error>(Bytes::from_static(chunk.as_bytes()))),
+        );
+        let output: Vec<Bytes> = legacy_sse_stream(inner)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            String::from_utf8(output.concat()).unwrap(),
+            "data: {\"type\":\"message_start\"}\n\ndata: {\"type\":\"content_block_delta\"}\n\n"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! 配置重载
+//!
+//! 文件监控事件和手动触发（`POST /v0/management/config/reload`、`reload_config` 命令）
+//! 共用同一套重载流程：重新读取配置文件，成功后更新请求处理器、同步凭证池、
+//! 重建路由注册表和 Amp 路由器，并清除模型目录缓存。
+
+use super::readiness::Readiness;
+use super::{build_route_registry, sync_credential_pool_from_config, update_processor_config};
+use crate::config::{Config, ConfigManager, HotReloadManager, ReloadResult};
+use crate::database::DbConnection;
+use crate::logger::LogStore;
+use crate::processor::RequestProcessor;
+use crate::router::{AmpRouter, RouteRegistry};
+use crate::services::model_catalog_service::ModelCatalogService;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 运行中服务器的配置重载器（服务器未运行时为 None）
+pub type ConfigReloaderSlot = Arc<parking_lot::RwLock<Option<Arc<ConfigReloader>>>>;
+
+/// 配置重载器
+pub struct ConfigReloader {
+    pub(super) manager: Arc<HotReloadManager>,
+    pub(super) processor: Arc<RequestProcessor>,
+    pub(super) logs: Arc<RwLock<LogStore>>,
+    pub(super) db: Option<DbConnection>,
+    pub(super) config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
+    pub(super) route_registry: Arc<RwLock<RouteRegistry>>,
+    pub(super) amp_router: Arc<parking_lot::RwLock<AmpRouter>>,
+    pub(super) readiness: Arc<Readiness>,
+    pub(super) model_catalog: Arc<ModelCatalogService>,
+}
+
+impl ConfigReloader {
+    /// 当前生效的配置
+    pub fn config(&self) -> Config {
+        self.manager.config()
+    }
+
+    /// 重新加载配置文件并应用到运行中的服务器
+    ///
+    /// 热重载及凭证池同步期间持有 `reload_lock` 写锁，就绪检查报告未就绪。
+    pub async fn reload(&self) -> ReloadResult {
+        let _reload_guard = self.processor.reload_lock.write().await;
+        let result = self.manager.reload();
+        match &result {
+            ReloadResult::Success { changes, .. } => {
+                self.log(
+                    "info",
+                    format!("[HOT_RELOAD] 配置热重载成功，{} 项配置变化", changes.len()),
+                )
+                .await;
+                self.apply().await;
+            }
+            ReloadResult::RolledBack { error, .. } => {
+                self.log(
+                    "warn",
+                    format!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error),
+                )
+                .await;
+            }
+            ReloadResult::Failed {
+                error,
+                rollback_error,
+                ..
+            } => {
+                self.log(
+                    "error",
+                    format!(
+                        "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
+                        error, rollback_error
+                    ),
+                )
+                .await;
+            }
+        }
+        result
+    }
+
+    /// 将重载后的配置应用到各组件
+    async fn apply(&self) {
+        let new_config = self.manager.config();
+        update_processor_config(&self.processor, &new_config).await;
+
+        // 同步凭证池
+        if let (Some(db), Some(cfg_manager)) = (&self.db, &self.config_manager) {
+            match sync_credential_pool_from_config(db, cfg_manager, &self.logs).await {
+                Ok(count) => {
+                    self.readiness.mark_credentials_synced();
+                    self.log(
+                        "info",
+                        format!("[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证", count),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    self.log("warn", format!("[HOT_RELOAD] 凭证池同步失败: {}", e))
+                        .await;
+                }
+            }
+        }
+
+        // 重建路由注册表（凭证变化及路由专属 API Key），保留运行时修改
+        let mut rebuilt =
+            build_route_registry(self.db.as_ref(), &new_config.routing.route_api_keys);
+        let mut registry = self.route_registry.write().await;
+        rebuilt.inherit_runtime_state(&registry);
+        *registry = rebuilt;
+
+        // 重建 Amp 路由器（模型映射、上游设置）
+        *self.amp_router.write() = AmpRouter::new(new_config.ampcode.clone());
+
+        // 凭证和 Amp 模型映射都会影响模型目录
+        self.model_catalog.invalidate();
+    }
+
+    async fn log(&self, level: &str, message: String) {
+        match level {
+            "error" => tracing::error!("{}", message),
+            "warn" => tracing::warn!("{}", message),
+            _ => tracing::info!("{}", message),
+        }
+        self.logs.write().await.add(level, &message);
+    }
+}
@@ -16,9 +16,9 @@
 //! - 需求 5.3: 流中发生错误时发送错误事件并优雅关闭流
 
 use axum::{
-    body::Body,
-    extract::State,
-    http::{header, HeaderMap, StatusCode},
+    body::{Body, Bytes},
+    extract::{OriginalUri, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -32,19 +32,37 @@ use crate::flow_monitor::{
     LLMFlow, LLMRequest, LLMResponse, Message, MessageContent, MessageRole, RequestParameters,
     RoutingInfo, TokenUsage,
 };
+use crate::injection::InjectionContext;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
+use crate::models::provider_pool_model::CredentialData;
 use crate::processor::RequestContext;
+use crate::providers::mock::{is_mock_provider, MOCK_PROVIDER_ID};
+use crate::resilience::ChaosFault;
+use crate::router::{ContextCheck, MaxTokensAdjustment};
+use crate::server::anthropic_version::{apply_version_shim, version_from_headers};
 use crate::server::client_detector::ClientType;
+use crate::server::response_validation::{validate_upstream_response, ResponseSchema};
+use crate::server::stream_watchdog::record_and_watch;
+use crate::server::validation::{ErrorFormat, ValidateRequest, ValidatedJsonWithBody};
 use crate::server::{record_request_telemetry, record_token_usage, AppState};
 use crate::server_utils::{
-    build_anthropic_response, build_anthropic_stream_response, message_content_len,
-    parse_cw_response, safe_truncate,
+    build_anthropic_response, build_anthropic_stream_response, build_error_response_with_status,
+    context_usage_from_response, ensure_openai_stream, message_content_len, parse_cw_response,
+    safe_truncate, CONTEXT_USAGE_METADATA,
 };
+use crate::session_files::transcript::{resume_request, SESSION_ID_HEADER};
+use crate::session_files::{SessionFileStorage, SessionTranscript, TranscriptFormat};
+use crate::stream::StopReason;
 use crate::streaming::StreamFormat as StreamingFormat;
 use crate::ProviderType;
 
-use super::{call_provider_anthropic, call_provider_openai};
+use super::{
+    call_provider_anthropic, call_provider_openai, call_provider_openai_fanout,
+    call_provider_passthrough, supported_server_tools, supports_logprobs, supports_native_choices,
+    unsupported_server_tools, PassthroughFormat, PassthroughRequest, FANOUT_HEADER,
+    KIRO_SERVER_TOOLS, MAX_FANOUT_CHOICES,
+};
 
 // ============================================================================
 // Flow 捕获辅助函数
@@ -218,15 +236,7 @@ fn build_llm_request_from_anthropic(
         .collect();
 
     // 提取系统提示词
-    let system_prompt = request.system.as_ref().map(|s| match s {
-        serde_json::Value::String(text) => text.clone(),
-        serde_json::Value::Array(arr) => arr
-            .iter()
-            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
-    });
+    let system_prompt = request.system.as_ref().map(|_| request.system_text());
 
     // 构建请求参数
     let parameters = RequestParameters {
@@ -340,10 +350,10 @@ fn build_llm_response(status_code: u16, content: &str, usage: Option<(u32, u32)>
 ///
 /// **Validates: Requirements 1.3, 1.4, 3.4**
 ///
-/// 优先级：端点 Provider 配置 > 默认 Provider
+/// 优先级：API Key 默认 Provider > 端点 Provider 配置 > 默认 Provider
 ///
 /// # 参数
-/// - `headers`: HTTP 请求头，用于提取 User-Agent
+/// - `headers`: HTTP 请求头，用于提取 User-Agent 和 API Key
 /// - `state`: 应用状态，包含端点配置和默认 Provider
 ///
 /// # 返回
@@ -356,20 +366,256 @@ async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (S
         .unwrap_or("");
     let client_type = ClientType::from_user_agent(user_agent);
 
-    // 获取端点 Provider 配置
-    let endpoint_providers = state.endpoint_providers.read().await;
-    let endpoint_provider = endpoint_providers.get_provider(client_type.config_key());
+    let selected_provider = state.processor.resolve_default_provider(
+        None,
+        presented_api_key(headers),
+        Some(client_type.config_key()),
+    );
 
-    // 获取默认 Provider
-    let default_provider = state.default_provider.read().await.clone();
+    (selected_provider, client_type)
+}
+
+/// 请求携带的 API Key（`x-api-key` 或 `Authorization: Bearer`）
+pub fn presented_api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("x-api-key")
+        .or_else(|| headers.get("authorization"))
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.strip_prefix("Bearer ").unwrap_or(s))
+}
 
-    // 选择 Provider：端点配置优先，否则使用默认
-    let selected_provider = match endpoint_provider {
-        Some(provider) => provider.clone(),
-        None => default_provider,
+/// 按注入规则修改请求，请求被修改时返回 true
+async fn apply_injection<T>(
+    state: &AppState,
+    request_id: &str,
+    injection_ctx: &InjectionContext,
+    request: &mut T,
+) -> bool
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let snapshot = state.processor.snapshot();
+    if !snapshot.injection_enabled {
+        return false;
+    }
+    let mut payload = serde_json::to_value(&*request).unwrap_or_default();
+    let result = snapshot
+        .injector
+        .inject_with_context(injection_ctx, &mut payload);
+    if !result.has_injections() {
+        return false;
+    }
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[INJECT] request_id={} applied_rules={:?} injected_params={:?} removed_params={:?} capped_params={:?}",
+            request_id,
+            result.applied_rules,
+            result.injected_params,
+            result.removed_params,
+            result.capped_params
+        ),
+    );
+    // 更新请求
+    match serde_json::from_value(payload) {
+        Ok(updated) => {
+            *request = updated;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// 按对话压缩配置总结较早的消息，请求被修改时返回 true
+///
+/// 仅在配置启用、请求头 `x-proxycast-compact` 开启且估算输入超过阈值时生效；
+/// 摘要失败时记录警告并按原请求转发。
+async fn apply_compaction<T>(
+    state: &AppState,
+    request_id: &str,
+    headers: &HeaderMap,
+    default_provider: &str,
+    request: &mut T,
+) -> bool
+where
+    T: serde::Serialize + ValidateRequest,
+{
+    let config = state.processor.compaction.read().await.clone();
+    let header = headers
+        .get(crate::compaction::COMPACT_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if !config.enabled || !crate::compaction::header_enabled(header) {
+        return false;
+    }
+    let mut payload = serde_json::to_value(&*request).unwrap_or_default();
+    let input_tokens = estimate_input_tokens(&payload);
+    if input_tokens < config.threshold_tokens {
+        return false;
+    }
+    let format = match T::FORMAT {
+        ErrorFormat::OpenAI => TranscriptFormat::OpenAI,
+        ErrorFormat::Anthropic => TranscriptFormat::Anthropic,
+    };
+    let Some(messages) = payload.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+        return false;
+    };
+    let Some(plan) = crate::compaction::plan(format, messages, config.keep_recent_messages) else {
+        return false;
+    };
+    let transcript = crate::compaction::render_transcript(&messages[plan.leading..plan.split]);
+    let summary = match summarize_conversation(state, &config, default_provider, &transcript).await
+    {
+        Ok(summary) => summary,
+        Err(e) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[COMPACT] request_id={} 摘要失败，按原请求转发: {}",
+                    request_id, e
+                ),
+            );
+            return false;
+        }
     };
+    crate::compaction::splice_summary(messages, &plan, &summary);
+    let compacted_tokens = estimate_input_tokens(&payload);
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[COMPACT] request_id={} compacted_messages={} input_tokens={} -> {}",
+            request_id,
+            plan.compacted(),
+            input_tokens,
+            compacted_tokens
+        ),
+    );
+    match serde_json::from_value(payload) {
+        Ok(updated) => {
+            *request = updated;
+            true
+        }
+        Err(_) => false,
+    }
+}
 
-    (selected_provider, client_type)
+/// 按目标模型的上下文窗口预检请求
+///
+/// 超出且配置了升级时返回升级后的模型名；没有可升级的模型时返回对应格式的
+/// `context_length_exceeded` 错误（400）。
+async fn check_context_window<T>(
+    state: &AppState,
+    request_id: &str,
+    model: &str,
+    request: &T,
+) -> Result<Option<String>, Response>
+where
+    T: serde::Serialize + ValidateRequest,
+{
+    let windows = state.processor.context_windows.read().await;
+    if !windows.is_enabled() {
+        return Ok(None);
+    }
+    let payload = serde_json::to_value(request).unwrap_or_default();
+    let input_tokens = estimate_input_tokens(&payload);
+    match windows.check(model, input_tokens) {
+        ContextCheck::Fits => Ok(None),
+        ContextCheck::Upgrade {
+            model: upgraded,
+            context_window,
+        } => {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[CONTEXT] request_id={} input_tokens={} 超出 {} 的上下文窗口，改用 {} (context_window={})",
+                    request_id, input_tokens, model, upgraded, context_window
+                ),
+            );
+            Ok(Some(upgraded))
+        }
+        ContextCheck::Exceeded { context_window } => {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[CONTEXT] request_id={} input_tokens={} 超出 {} 的上下文窗口 {}，已拒绝",
+                    request_id, input_tokens, model, context_window
+                ),
+            );
+            let body = match T::FORMAT {
+                ErrorFormat::OpenAI => json!({
+                    "error": {
+                        "message": format!(
+                            "This model's maximum context length is {} tokens. However, your messages resulted in about {} tokens. Please reduce the length of the messages.",
+                            context_window, input_tokens
+                        ),
+                        "type": "invalid_request_error",
+                        "param": "messages",
+                        "code": "context_length_exceeded"
+                    }
+                }),
+                ErrorFormat::Anthropic => json!({
+                    "type": "error",
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": format!(
+                            "prompt is too long: {} tokens > {} maximum",
+                            input_tokens, context_window
+                        )
+                    }
+                }),
+            };
+            Err((StatusCode::BAD_REQUEST, Json(body)).into_response())
+        }
+    }
+}
+
+/// 使用凭证池中的模型生成对话摘要
+async fn summarize_conversation(
+    state: &AppState,
+    config: &crate::config::CompactionConfig,
+    default_provider: &str,
+    transcript: &str,
+) -> Result<String, String> {
+    let db = state.db.as_ref().ok_or("Database not available")?;
+    let provider = if config.provider.is_empty() {
+        default_provider
+    } else {
+        config.provider.as_str()
+    };
+    let credential = state
+        .pool_service
+        .select_credential(db, provider, Some(&config.model))?
+        .ok_or_else(|| format!("No available credentials for provider '{}'", provider))?;
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": config.model,
+        "messages": [
+            {"role": "system", "content": crate::compaction::SUMMARY_INSTRUCTIONS},
+            {"role": "user", "content": transcript}
+        ],
+        "max_tokens": config.max_summary_tokens,
+        "stream": false
+    }))
+    .map_err(|e| e.to_string())?;
+
+    let response = call_provider_openai(state, &credential, &request, None).await;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!(
+            "Upstream returned {}: {}",
+            status.as_u16(),
+            String::from_utf8_lossy(&bytes)
+        ));
+    }
+    let completion: serde_json::Value =
+        serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+    completion["choices"][0]["message"]["content"]
+        .as_str()
+        .map(str::trim)
+        .filter(|summary| !summary.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| "Empty summary".to_string())
 }
 
 // ============================================================================
@@ -620,10 +866,227 @@ pub async fn verify_api_key_anthropic(
     Ok(())
 }
 
+/// 在响应中标记 `max_tokens` 调整
+pub fn with_max_tokens_header(
+    mut response: Response,
+    adjustment: Option<MaxTokensAdjustment>,
+) -> Response {
+    if let Some(adjustment) = adjustment {
+        adjustment.apply_header(response.headers_mut());
+    }
+    response
+}
+
+/// 记录 Kiro 响应头中的上下文窗口使用百分比，供遥测使用
+fn capture_context_usage(ctx: &mut RequestContext, response: &Response) {
+    if let Some(percentage) = context_usage_from_response(response) {
+        ctx.set_metadata(CONTEXT_USAGE_METADATA, json!(percentage));
+    }
+}
+
+/// 上游返回"模型不存在/不支持"错误时记录到凭证池，用于自动学习模型黑名单
+///
+/// 仅读取 4xx 错误响应的响应体，读取后按原样重建响应。
+async fn learn_unsupported_model(
+    state: &AppState,
+    credential: &crate::models::provider_pool_model::ProviderCredential,
+    model: &str,
+    response: Response,
+) -> Response {
+    let status = response.status();
+    let Some(db) = &state.db else {
+        return response;
+    };
+    if !status.is_client_error() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    if crate::services::provider_pool_service::is_model_unsupported_error(
+        status.as_u16(),
+        &String::from_utf8_lossy(&bytes),
+    ) {
+        if let Err(e) = state
+            .pool_service
+            .record_model_failure(db, &credential.uuid, model)
+        {
+            tracing::warn!("[MODEL_BLACKLIST] 记录模型错误失败: {}", e);
+        }
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// 故障注入：按配置延迟请求，命中故障时计入凭证健康状态并返回模拟的上游错误
+pub async fn inject_chaos(
+    state: &AppState,
+    credential: &crate::models::provider_pool_model::ProviderCredential,
+) -> Option<Response> {
+    let outcome = state
+        .processor
+        .chaos
+        .read()
+        .await
+        .roll(&credential.provider_type.to_string())?;
+    if !outcome.delay.is_zero() {
+        tokio::time::sleep(outcome.delay).await;
+    }
+    let fault = outcome.fault?;
+    if let ChaosFault::Timeout(wait) = fault {
+        tokio::time::sleep(wait).await;
+    }
+
+    let message = fault.message();
+    tracing::warn!(
+        "[CHAOS] 凭证 {} ({}) {}",
+        &credential.uuid[..8.min(credential.uuid.len())],
+        credential.provider_type,
+        message
+    );
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_unhealthy(db, &credential.uuid, Some(&message));
+    }
+    Some(build_error_response_with_status(
+        fault.status_code(),
+        &message,
+    ))
+}
+
+/// 服务端工具只能透传给 Claude 凭证时建议的路由
+const SERVER_TOOLS_ROUTE: &str = "/claude/v1/messages";
+
+/// 所选凭证无法处理请求中的 Anthropic 服务端工具时返回的错误，附带建议路由
+pub fn server_tools_unsupported_response(provider: &str, tools: &[String]) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "capability_error",
+                "message": format!(
+                    "Provider '{}' does not support Anthropic server tools: {}. Use {} or set X-Provider-Id: claude to route to a Claude credential",
+                    provider,
+                    tools.join(", "),
+                    SERVER_TOOLS_ROUTE
+                ),
+                "unsupported_tools": tools,
+                "suggested_route": SERVER_TOOLS_ROUTE
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 检查凭证能否处理请求中的 `n` / `logprobs`，不能处理时返回 400
+///
+/// `credential` 为 None 表示旧的单凭证（Kiro）模式，两者都不支持。
+/// 上游不支持 `n > 1` 时由代理扇出，超过扇出上限或无凭证时拒绝。
+fn choices_unsupported_response(
+    credential: Option<&CredentialData>,
+    provider: &str,
+    request: &ChatCompletionRequest,
+) -> Option<Response> {
+    let (param, message) = if request.wants_logprobs() && !credential.is_some_and(supports_logprobs)
+    {
+        (
+            "logprobs",
+            format!("Provider '{}' does not support logprobs", provider),
+        )
+    } else if request.choice_count() > 1 && !credential.is_some_and(supports_native_choices) {
+        let n = request.choice_count();
+        if credential.is_none() {
+            (
+                "n",
+                format!(
+                    "Provider '{}' does not support n > 1 without a pool credential",
+                    provider
+                ),
+            )
+        } else if n > MAX_FANOUT_CHOICES {
+            (
+                "n",
+                format!(
+                    "Provider '{}' does not support n > 1 natively; fan-out is limited to n <= {}",
+                    provider, MAX_FANOUT_CHOICES
+                ),
+            )
+        } else {
+            return None;
+        }
+    } else {
+        return None;
+    };
+
+    Some(
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": {
+                    "message": message,
+                    "type": "invalid_request_error",
+                    "param": param,
+                    "code": "unsupported_parameter"
+                }
+            })),
+        )
+            .into_response(),
+    )
+}
+
 pub async fn chat_completions(
     State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    ValidatedJsonWithBody(mut request, raw_body): ValidatedJsonWithBody<ChatCompletionRequest>,
+) -> Response {
+    crate::middleware::response_info::record_estimated_tokens(|| {
+        serde_json::to_value(&request)
+            .map(|value| estimate_input_tokens(&value))
+            .unwrap_or_default()
+    });
+    // 按目标模型的输出上限钳制 max_tokens，调整时通过响应头告知客户端
+    let adjustment = state
+        .processor
+        .clamp_max_tokens(&request.model, &mut request.max_tokens)
+        .await;
+    // 请求被改写后原始请求体不再可用于透传
+    let raw_body = adjustment.is_none().then_some(raw_body);
+    let response = handle_chat_completions(state, headers, uri.path(), request, raw_body).await;
+    with_max_tokens_header(response, adjustment)
+}
+
+async fn handle_chat_completions(
+    state: AppState,
+    headers: HeaderMap,
+    path: &str,
+    request: ChatCompletionRequest,
+    raw_body: Option<Bytes>,
+) -> Response {
+    let stream = request.stream;
+    let include_usage = request.include_usage();
+    let response = dispatch_chat_completions(state, headers, path, request, raw_body).await;
+    if stream {
+        // 扇出时上游返回完整 JSON，转换为 SSE 后保留扇出响应头
+        let fanout = response.headers().get(FANOUT_HEADER).cloned();
+        let mut response = ensure_openai_stream(response, include_usage).await;
+        if let Some(fanout) = fanout {
+            response.headers_mut().insert(FANOUT_HEADER, fanout);
+        }
+        response
+    } else {
+        response
+    }
+}
+
+async fn dispatch_chat_completions(
+    state: AppState,
     headers: HeaderMap,
-    Json(mut request): Json<ChatCompletionRequest>,
+    path: &str,
+    mut request: ChatCompletionRequest,
+    mut raw_body: Option<Bytes>,
 ) -> Response {
     // ========== 详细日志：请求入口 ==========
     eprintln!("\n========== [CHAT_COMPLETIONS] 收到请求 ==========");
@@ -645,6 +1108,11 @@ pub async fn chat_completions(
 
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    ctx.set_user_id(request.user.as_deref());
+    // 沿用请求 ID 中间件分配的 ID，使日志、遥测与响应头一致
+    if let Some(request_id) = crate::middleware::current_request_id() {
+        ctx.request_id = request_id;
+    }
     eprintln!("[CHAT_COMPLETIONS] 请求ID: {}", ctx.request_id);
 
     state.logs.write().await.add(
@@ -667,6 +1135,7 @@ pub async fn chat_completions(
     // 更新请求中的模型名为解析后的模型
     if resolved_model != request.model {
         request.model = resolved_model.clone();
+        raw_body = None;
         state.logs.write().await.add(
             "info",
             &format!(
@@ -676,27 +1145,6 @@ pub async fn chat_completions(
         );
     }
 
-    // 应用参数注入
-    let injection_enabled = *state.injection_enabled.read().await;
-    if injection_enabled {
-        let injector = state.processor.injector.read().await;
-        let mut payload = serde_json::to_value(&request).unwrap_or_default();
-        let result = injector.inject(&request.model, &mut payload);
-        if result.has_injections() {
-            state.logs.write().await.add(
-                "info",
-                &format!(
-                    "[INJECT] request_id={} applied_rules={:?} injected_params={:?}",
-                    ctx.request_id, result.applied_rules, result.injected_params
-                ),
-            );
-            // 更新请求
-            if let Ok(updated) = serde_json::from_value(payload) {
-                request = updated;
-            }
-        }
-    }
-
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
@@ -729,6 +1177,47 @@ pub async fn chat_completions(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_lowercase());
 
+    // 应用参数注入
+    let injection_ctx = InjectionContext::new(&request.model)
+        .with_provider(provider_id_header.as_deref().unwrap_or(&selected_provider))
+        .with_app_type(client_type.config_key())
+        .with_path(path)
+        .with_headers(&headers);
+    if apply_injection(&state, &ctx.request_id, &injection_ctx, &mut request).await {
+        raw_body = None;
+    }
+
+    // 对话压缩：上下文过长时总结较早的消息
+    if apply_compaction(
+        &state,
+        &ctx.request_id,
+        &headers,
+        &selected_provider,
+        &mut request,
+    )
+    .await
+    {
+        raw_body = None;
+    }
+
+    // 上下文窗口预检：超出时拒绝或改用上下文更大的模型
+    match check_context_window(&state, &ctx.request_id, &request.model, &request).await {
+        Ok(Some(upgraded)) => {
+            request.model = upgraded.clone();
+            ctx.set_resolved_model(upgraded);
+            raw_body = None;
+        }
+        Ok(None) => {}
+        Err(response) => return response,
+    }
+
+    // 选中内置 mock Provider 时直接返回模拟响应，不选择凭证
+    if is_mock_provider(provider_id_header.as_deref().unwrap_or(&selected_provider)) {
+        crate::recording::note_provider(MOCK_PROVIDER_ID);
+        let mock = state.processor.mock_provider.read().await.clone();
+        return mock.chat_completions(&request).await;
+    }
+
     // 尝试从凭证池中选择凭证
     // 如果指定了 X-Provider-Id，优先使用它（不降级）
     // 否则使用 selected_provider
@@ -970,6 +1459,13 @@ pub async fn chat_completions(
             ),
         );
 
+        // 多候选 / logprobs：上游不支持 n > 1 时扇出，不支持 logprobs 时拒绝
+        if let Some(response) =
+            choices_unsupported_response(Some(&cred.credential), &selected_provider, &request)
+        {
+            return response;
+        }
+
         // 启动 Flow 捕获
         let llm_request = build_llm_request_from_openai(&request, "/v1/chat/completions", &headers);
 
@@ -1013,6 +1509,7 @@ pub async fn chat_completions(
                         // 从修改后的 LLMRequest 更新 ChatCompletionRequest
                         if let Ok(updated) = serde_json::from_value(modified.body.clone()) {
                             request = updated;
+                            raw_body = None;
                         }
                     }
                 }
@@ -1032,21 +1529,63 @@ pub async fn chat_completions(
         }
 
         eprintln!("[CHAT_COMPLETIONS] 调用 Provider: {}", cred.provider_type);
-        let response = call_provider_openai(&state, &cred, &request, flow_id.as_deref()).await;
+        // 同格式上游且请求未被改写时透传原始请求体，否则走转换路径
+        // 故障注入命中时不调用上游
+        let injected = inject_chaos(&state, &cred).await;
+        let passthrough = match &raw_body {
+            Some(raw) if injected.is_none() => {
+                call_provider_passthrough(
+                    &state,
+                    &ctx,
+                    &cred,
+                    PassthroughRequest {
+                        format: PassthroughFormat::OpenAI,
+                        raw_body: raw,
+                        model: &request.model,
+                        stream: request.stream,
+                    },
+                    flow_id.as_deref(),
+                )
+                .await
+            }
+            _ => None,
+        };
+        // 透传响应按上游 usage 记录 Token 用量，不再估算
+        let passthrough_used = passthrough.is_some();
+        let response = match injected.or(passthrough) {
+            Some(response) => response,
+            None if request.choice_count() > 1 && !supports_native_choices(&cred.credential) => {
+                call_provider_openai_fanout(&state, &cred, &request, flow_id.as_deref()).await
+            }
+            None => call_provider_openai(&state, &cred, &request, flow_id.as_deref()).await,
+        };
+        crate::recording::note_provider(&cred.provider_type.to_string());
+        let response = learn_unsupported_model(&state, &cred, &request.model, response).await;
+        let response = validate_upstream_response(
+            &state,
+            &ctx.request_id,
+            &cred,
+            ResponseSchema::OpenAiChat,
+            response,
+        )
+        .await;
         eprintln!(
             "[CHAT_COMPLETIONS] Provider 响应状态: {}",
             response.status()
         );
 
-        // 记录请求统计
+        // 记录请求统计（流式响应结束时记录，并监控上游空闲）
         let is_success = response.status().is_success();
         let status_code = response.status().as_u16();
-        let status = if is_success {
-            crate::telemetry::RequestStatus::Success
-        } else {
-            crate::telemetry::RequestStatus::Failed
-        };
-        record_request_telemetry(&state, &ctx, status, None);
+        capture_context_usage(&mut ctx, &response);
+        let response = record_and_watch(
+            &state,
+            &ctx,
+            &cred.uuid,
+            StreamingFormat::OpenAiSse,
+            response,
+        )
+        .await;
 
         // 如果成功且需要 Flow 捕获，提取响应体内容和响应头
         // 注意：非流式响应需要读取 body，所以必须在这里处理
@@ -1131,7 +1670,9 @@ pub async fn chat_completions(
                 content.len(), input_tokens, output_tokens);
 
             // 记录 Token 使用量
-            record_token_usage(&state, &ctx, Some(input_tokens), Some(output_tokens));
+            if !passthrough_used {
+                record_token_usage(&state, &ctx, Some(input_tokens), Some(output_tokens));
+            }
 
             // 完成 Flow 捕获并检查响应拦截
             // **Validates: Requirements 2.1, 2.5**
@@ -1169,7 +1710,7 @@ pub async fn chat_completions(
                     return (
                         StatusCode::OK,
                         Json(serde_json::json!({
-                            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                            "id": crate::middleware::response_id("chatcmpl-"),
                             "object": "chat.completion",
                             "created": std::time::SystemTime::now()
                                 .duration_since(std::time::UNIX_EPOCH)
@@ -1224,7 +1765,7 @@ pub async fn chat_completions(
                 .sum::<usize>() as u32;
             let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
 
-            if is_success {
+            if is_success && !passthrough_used {
                 record_token_usage(
                     &state,
                     &ctx,
@@ -1281,6 +1822,10 @@ pub async fn chat_completions(
         ),
     );
 
+    if let Some(response) = choices_unsupported_response(None, &selected_provider, &request) {
+        return response;
+    }
+
     // 启动 Flow 捕获（legacy mode）
     let llm_request = build_llm_request_from_openai(&request, "/v1/chat/completions", &headers);
 
@@ -1331,8 +1876,7 @@ pub async fn chat_completions(
     {
         let _guard = state.kiro_refresh_lock.lock().await;
         let mut kiro = state.kiro.write().await;
-        let needs_refresh =
-            kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
+        let needs_refresh = kiro.access_token().is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
             if let Err(e) = kiro.refresh_token().await {
                 state
@@ -1362,7 +1906,7 @@ pub async fn chat_completions(
         Ok(resp) => {
             let status = resp.status();
             if status.is_success() {
-                match resp.text().await {
+                match resp.bytes().await {
                     Ok(body) => {
                         let parsed = parse_cw_response(&body);
                         let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1416,7 +1960,7 @@ pub async fn chat_completions(
                             as u32;
 
                         let response = serde_json::json!({
-                            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                            "id": crate::middleware::response_id("chatcmpl-"),
                             "object": "chat.completion",
                             "created": std::time::SystemTime::now()
                                 .duration_since(std::time::UNIX_EPOCH)
@@ -1435,6 +1979,12 @@ pub async fn chat_completions(
                             }
                         });
                         // 记录成功请求统计
+                        if parsed.context_usage_percentage > 0.0 {
+                            ctx.set_metadata(
+                                CONTEXT_USAGE_METADATA,
+                                json!(parsed.context_usage_percentage),
+                            );
+                        }
                         record_request_telemetry(
                             &state,
                             &ctx,
@@ -1486,7 +2036,7 @@ pub async fn chat_completions(
                                 });
 
                                 let modified_json_response = serde_json::json!({
-                                    "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                                    "id": crate::middleware::response_id("chatcmpl-"),
                                     "object": "chat.completion",
                                     "created": std::time::SystemTime::now()
                                         .duration_since(std::time::UNIX_EPOCH)
@@ -1513,7 +2063,7 @@ pub async fn chat_completions(
                                 .complete_flow(fid, Some(llm_response))
                                 .await;
                         }
-                        Json(response).into_response()
+                        parsed.with_context_usage_header(Json(response).into_response())
                     }
                     Err(e) => {
                         // 记录失败请求统计
@@ -1569,7 +2119,7 @@ pub async fn chat_completions(
                         match kiro.call_api(&request).await {
                             Ok(retry_resp) => {
                                 if retry_resp.status().is_success() {
-                                    match retry_resp.text().await {
+                                    match retry_resp.bytes().await {
                                         Ok(body) => {
                                             let parsed = parse_cw_response(&body);
                                             let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1597,7 +2147,7 @@ pub async fn chat_completions(
                                             };
 
                                             let response = serde_json::json!({
-                                                "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                                                "id": crate::middleware::response_id("chatcmpl-"),
                                                 "object": "chat.completion",
                                                 "created": std::time::SystemTime::now()
                                                     .duration_since(std::time::UNIX_EPOCH)
@@ -1662,7 +2212,7 @@ pub async fn chat_completions(
                                                     });
 
                                                     let modified_json_response = serde_json::json!({
-                                                        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                                                        "id": crate::middleware::response_id("chatcmpl-"),
                                                         "object": "chat.completion",
                                                         "created": std::time::SystemTime::now()
                                                             .duration_since(std::time::UNIX_EPOCH)
@@ -1690,7 +2240,9 @@ pub async fn chat_completions(
                                                     .complete_flow(fid, Some(llm_response))
                                                     .await;
                                             }
-                                            return Json(response).into_response();
+                                            return parsed.with_context_usage_header(
+                                                Json(response).into_response(),
+                                            );
                                         }
                                         Err(e) => {
                                             // 标记 Flow 失败
@@ -1797,10 +2349,295 @@ pub async fn chat_completions(
     }
 }
 
+/// 本地 Token 估算器（tiktoken 初始化较慢，全局共享）
+static TOKEN_ESTIMATOR: once_cell::sync::Lazy<Option<crate::telemetry::TokenEstimator>> =
+    once_cell::sync::Lazy::new(|| crate::telemetry::TokenEstimator::new().ok());
+
+/// 上游 count_tokens 端点接受的字段（max_tokens、stream 等会被拒绝）
+const COUNT_TOKENS_FIELDS: &[&str] = &[
+    "model",
+    "messages",
+    "system",
+    "tools",
+    "tool_choice",
+    "thinking",
+    "mcp_servers",
+];
+
+/// 构建上游 count_tokens 请求体
+fn count_tokens_payload(request: &serde_json::Value) -> serde_json::Value {
+    let fields = request
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .filter(|(key, _)| COUNT_TOKENS_FIELDS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    serde_json::Value::Object(fields)
+}
+
+/// 本地估算输入 Token 数（估算器不可用时按 4 字符 1 Token 粗略计算）
+fn estimate_input_tokens(request: &serde_json::Value) -> u32 {
+    match TOKEN_ESTIMATOR.as_ref() {
+        Some(estimator) => estimator.estimate_anthropic_request(request),
+        None => (request.to_string().chars().count() / 4) as u32,
+    }
+}
+
+/// 按 /v1/messages 的规则选择凭证
+///
+/// 指定 X-Provider-Id 时只使用该 Provider（无可用凭证返回 503）；
+/// 否则按客户端类型选择 Provider，凭证池中没有可用凭证时降级到 API Key Provider。
+async fn select_messages_credential(
+    state: &AppState,
+    headers: &HeaderMap,
+    model: &str,
+) -> Result<Option<crate::models::provider_pool_model::ProviderCredential>, Response> {
+    let Some(db) = &state.db else {
+        return Ok(None);
+    };
+
+    if let Some(provider_id) = headers
+        .get("x-provider-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_lowercase())
+    {
+        return match state
+            .pool_service
+            .select_credential(db, &provider_id, Some(model))
+            .ok()
+            .flatten()
+        {
+            Some(cred) => Ok(Some(cred)),
+            None => Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "error": {
+                        "type": "provider_unavailable",
+                        "message": format!("No available credentials for provider '{}'", provider_id)
+                    }
+                })),
+            )
+                .into_response()),
+        };
+    }
+
+    let (selected_provider, _) = select_provider_for_client(headers, state).await;
+    if let Some(cred) = state
+        .pool_service
+        .select_credential(db, &selected_provider, Some(model))
+        .ok()
+        .flatten()
+    {
+        return Ok(Some(cred));
+    }
+    Ok(state
+        .api_key_service
+        .get_fallback_credential(
+            db,
+            &crate::models::provider_pool_model::PoolProviderType::Anthropic,
+            Some(&selected_provider),
+        )
+        .ok()
+        .flatten())
+}
+
+/// Anthropic count_tokens
+///
+/// 选中 Claude API Key 凭证时转发到上游 count_tokens 端点；
+/// 其他 Provider 没有计数接口（或上游调用失败）时使用本地估算。
+pub async fn count_tokens(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<serde_json::Value>,
+) -> Response {
+    use crate::models::provider_pool_model::CredentialData;
+    use crate::providers::claude_custom::ClaudeCustomProvider;
+
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+
+    let model = request
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let resolved_model = state.processor.resolve_model(&model).await;
+    if resolved_model != model {
+        request["model"] = json!(resolved_model);
+    }
+
+    let credential = match select_messages_credential(&state, &headers, &resolved_model).await {
+        Ok(credential) => credential,
+        Err(response) => return response,
+    };
+
+    if let Some(cred) = credential {
+        let upstream = match &cred.credential {
+            CredentialData::ClaudeKey { api_key, base_url }
+            | CredentialData::AnthropicKey { api_key, base_url } => Some(
+                ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone()),
+            ),
+            _ => None,
+        };
+        if let Some(claude) = upstream {
+            match claude.count_tokens(&count_tokens_payload(&request)).await {
+                Ok(result) => return Json(result).into_response(),
+                Err(e) => state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "[COUNT_TOKENS] 上游计数失败，使用本地估算: credential_uuid={} error={}",
+                        &cred.uuid[..8.min(cred.uuid.len())],
+                        e
+                    ),
+                ),
+            }
+        }
+    }
+
+    Json(json!({ "input_tokens": estimate_input_tokens(&request) })).into_response()
+}
+
+// ============================================================================
+// 会话对话记录
+// ============================================================================
+
+fn session_error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({"error": {"message": message.into()}}))).into_response()
+}
+
+/// 读取会话对话记录
+fn load_transcript(state: &AppState, session_id: &str) -> Result<SessionTranscript, Box<Response>> {
+    let recorder = state.session_recorder.as_ref().ok_or_else(|| {
+        session_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Session persistence is unavailable",
+        )
+    })?;
+    SessionFileStorage::validate_session_id(session_id)
+        .map_err(|e| session_error(StatusCode::BAD_REQUEST, e))?;
+    match recorder.storage().get_transcript(session_id) {
+        Ok(Some(transcript)) => Ok(transcript),
+        Ok(None) => Err(session_error(
+            StatusCode::NOT_FOUND,
+            format!("Session not found: {}", session_id),
+        )
+        .into()),
+        Err(e) => Err(session_error(StatusCode::INTERNAL_SERVER_ERROR, e).into()),
+    }
+}
+
+/// 获取会话对话记录
+pub async fn get_session_transcript(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    match load_transcript(&state, &session_id) {
+        Ok(transcript) => Json(transcript).into_response(),
+        Err(response) => *response,
+    }
+}
+
+/// 续接会话
+///
+/// 请求体只需包含新消息，历史消息从对话记录中补齐后按记录的格式转发，
+/// 回复继续记录到同一会话。
+pub async fn resume_session(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    Path(session_id): Path<String>,
+    mut headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    let transcript = match load_transcript(&state, &session_id) {
+        Ok(transcript) => transcript,
+        Err(response) => return *response,
+    };
+    let request = resume_request(&transcript, body);
+    if let Ok(value) = HeaderValue::from_str(&session_id) {
+        headers.insert(SESSION_ID_HEADER, value);
+    }
+
+    match transcript.format {
+        TranscriptFormat::Anthropic => {
+            let mut request: AnthropicMessagesRequest = match serde_json::from_value(request) {
+                Ok(request) => request,
+                Err(e) => {
+                    return session_error(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid request: {}", e),
+                    )
+                }
+            };
+            let adjustment = state
+                .processor
+                .clamp_max_tokens(&request.model, &mut request.max_tokens)
+                .await;
+            let version = version_from_headers(&headers);
+            let response =
+                handle_anthropic_messages(state, headers, uri.path(), request, None).await;
+            apply_version_shim(version, with_max_tokens_header(response, adjustment)).await
+        }
+        TranscriptFormat::OpenAI => {
+            let mut request: ChatCompletionRequest = match serde_json::from_value(request) {
+                Ok(request) => request,
+                Err(e) => {
+                    return session_error(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid request: {}", e),
+                    )
+                }
+            };
+            let adjustment = state
+                .processor
+                .clamp_max_tokens(&request.model, &mut request.max_tokens)
+                .await;
+            let response = handle_chat_completions(state, headers, uri.path(), request, None).await;
+            with_max_tokens_header(response, adjustment)
+        }
+    }
+}
+
 pub async fn anthropic_messages(
     State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    ValidatedJsonWithBody(mut request, raw_body): ValidatedJsonWithBody<AnthropicMessagesRequest>,
+) -> Response {
+    crate::middleware::response_info::record_estimated_tokens(|| {
+        serde_json::to_value(&request)
+            .map(|value| estimate_input_tokens(&value))
+            .unwrap_or_default()
+    });
+    // 按目标模型的输出上限钳制 max_tokens，调整时通过响应头告知客户端
+    let adjustment = state
+        .processor
+        .clamp_max_tokens(&request.model, &mut request.max_tokens)
+        .await;
+    // 请求被改写后原始请求体不再可用于透传
+    let raw_body = adjustment.is_none().then_some(raw_body);
+    // 旧版 anthropic-version 的客户端按旧版格式返回
+    let version = version_from_headers(&headers);
+    let response = handle_anthropic_messages(state, headers, uri.path(), request, raw_body).await;
+    apply_version_shim(version, with_max_tokens_header(response, adjustment)).await
+}
+
+async fn handle_anthropic_messages(
+    state: AppState,
     headers: HeaderMap,
-    Json(mut request): Json<AnthropicMessagesRequest>,
+    path: &str,
+    mut request: AnthropicMessagesRequest,
+    mut raw_body: Option<Bytes>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证（优先检查 x-api-key）
     if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
@@ -1814,6 +2651,16 @@ pub async fn anthropic_messages(
 
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    ctx.set_user_id(request.user_id());
+    ctx.set_anthropic_version(
+        headers
+            .get("anthropic-version")
+            .and_then(|v| v.to_str().ok()),
+    );
+    // 沿用请求 ID 中间件分配的 ID，使日志、遥测与响应头一致
+    if let Some(request_id) = crate::middleware::current_request_id() {
+        ctx.request_id = request_id;
+    }
 
     // 详细记录请求信息
     let msg_count = request.messages.len();
@@ -1834,6 +2681,7 @@ pub async fn anthropic_messages(
     // 更新请求中的模型名为解析后的模型
     if resolved_model != request.model {
         request.model = resolved_model.clone();
+        raw_body = None;
         state.logs.write().await.add(
             "info",
             &format!(
@@ -1869,27 +2717,6 @@ pub async fn anthropic_messages(
         );
     }
 
-    // 应用参数注入
-    let injection_enabled = *state.injection_enabled.read().await;
-    if injection_enabled {
-        let injector = state.processor.injector.read().await;
-        let mut payload = serde_json::to_value(&request).unwrap_or_default();
-        let result = injector.inject(&request.model, &mut payload);
-        if result.has_injections() {
-            state.logs.write().await.add(
-                "info",
-                &format!(
-                    "[INJECT] request_id={} applied_rules={:?} injected_params={:?}",
-                    ctx.request_id, result.applied_rules, result.injected_params
-                ),
-            );
-            // 更新请求
-            if let Ok(updated) = serde_json::from_value(payload) {
-                request = updated;
-            }
-        }
-    }
-
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
@@ -1918,6 +2745,47 @@ pub async fn anthropic_messages(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_lowercase());
 
+    // 应用参数注入
+    let injection_ctx = InjectionContext::new(&request.model)
+        .with_provider(provider_id_header.as_deref().unwrap_or(&selected_provider))
+        .with_app_type(client_type.config_key())
+        .with_path(path)
+        .with_headers(&headers);
+    if apply_injection(&state, &ctx.request_id, &injection_ctx, &mut request).await {
+        raw_body = None;
+    }
+
+    // 对话压缩：上下文过长时总结较早的消息
+    if apply_compaction(
+        &state,
+        &ctx.request_id,
+        &headers,
+        &selected_provider,
+        &mut request,
+    )
+    .await
+    {
+        raw_body = None;
+    }
+
+    // 上下文窗口预检：超出时拒绝或改用上下文更大的模型
+    match check_context_window(&state, &ctx.request_id, &request.model, &request).await {
+        Ok(Some(upgraded)) => {
+            request.model = upgraded.clone();
+            ctx.set_resolved_model(upgraded);
+            raw_body = None;
+        }
+        Ok(None) => {}
+        Err(response) => return response,
+    }
+
+    // 选中内置 mock Provider 时直接返回模拟响应，不选择凭证
+    if is_mock_provider(provider_id_header.as_deref().unwrap_or(&selected_provider)) {
+        crate::recording::note_provider(MOCK_PROVIDER_ID);
+        let mock = state.processor.mock_provider.read().await.clone();
+        return mock.anthropic_messages(&request).await;
+    }
+
     // 尝试从凭证池中选择凭证
     // 如果指定了 X-Provider-Id，优先使用它（不降级）
     // 否则使用 selected_provider
@@ -2048,6 +2916,22 @@ pub async fn anthropic_messages(
             ),
         );
 
+        // 服务端工具只透传给 Claude 凭证，其他凭证仅支持可转换的部分
+        let unsupported = unsupported_server_tools(
+            &request,
+            supported_server_tools(&cred.credential, &request.model),
+        );
+        if !unsupported.is_empty() {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[ROUTE] Credential type={} does not support server tools: {:?}",
+                    cred.provider_type, unsupported
+                ),
+            );
+            return server_tools_unsupported_response(&selected_provider, &unsupported);
+        }
+
         // 启动 Flow 捕获
         let llm_request = build_llm_request_from_anthropic(&request, "/v1/messages", &headers);
 
@@ -2092,6 +2976,7 @@ pub async fn anthropic_messages(
                         // 从修改后的 LLMRequest 更新 AnthropicMessagesRequest
                         if let Ok(updated) = serde_json::from_value(modified.body.clone()) {
                             request = updated;
+                            raw_body = None;
                         }
                     }
                 }
@@ -2114,16 +2999,55 @@ pub async fn anthropic_messages(
             }
         }
 
-        let response = call_provider_anthropic(&state, &cred, &request, flow_id.as_deref()).await;
+        // 同格式上游且请求未被改写时透传原始请求体，否则走转换路径
+        // 故障注入命中时不调用上游
+        let injected = inject_chaos(&state, &cred).await;
+        let passthrough = match &raw_body {
+            Some(raw) if injected.is_none() => {
+                call_provider_passthrough(
+                    &state,
+                    &ctx,
+                    &cred,
+                    PassthroughRequest {
+                        format: PassthroughFormat::Anthropic,
+                        raw_body: raw,
+                        model: &request.model,
+                        stream: request.stream,
+                    },
+                    flow_id.as_deref(),
+                )
+                .await
+            }
+            _ => None,
+        };
+        // 透传响应按上游 usage 记录 Token 用量，不再估算
+        let passthrough_used = passthrough.is_some();
+        let response = match injected.or(passthrough) {
+            Some(response) => response,
+            None => call_provider_anthropic(&state, &cred, &request, flow_id.as_deref()).await,
+        };
+        crate::recording::note_provider(&cred.provider_type.to_string());
+        let response = learn_unsupported_model(&state, &cred, &request.model, response).await;
+        let response = validate_upstream_response(
+            &state,
+            &ctx.request_id,
+            &cred,
+            ResponseSchema::AnthropicMessages,
+            response,
+        )
+        .await;
 
-        // 记录请求统计
+        // 记录请求统计（流式响应结束时记录，并监控上游空闲）
         let is_success = response.status().is_success();
-        let status = if is_success {
-            crate::telemetry::RequestStatus::Success
-        } else {
-            crate::telemetry::RequestStatus::Failed
-        };
-        record_request_telemetry(&state, &ctx, status, None);
+        capture_context_usage(&mut ctx, &response);
+        let response = record_and_watch(
+            &state,
+            &ctx,
+            &cred.uuid,
+            StreamingFormat::AnthropicSse,
+            response,
+        )
+        .await;
 
         // 估算 Token 使用量
         let estimated_input_tokens = request
@@ -2144,7 +3068,7 @@ pub async fn anthropic_messages(
             .sum::<usize>() as u32;
         let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
 
-        if is_success {
+        if is_success && !passthrough_used {
             record_token_usage(
                 &state,
                 &ctx,
@@ -2190,7 +3114,7 @@ pub async fn anthropic_messages(
                     return (
                         StatusCode::OK,
                         Json(serde_json::json!({
-                            "id": format!("msg_{}", uuid::Uuid::new_v4()),
+                            "id": crate::middleware::response_id("msg_"),
                             "type": "message",
                             "role": "assistant",
                             "content": [{
@@ -2258,6 +3182,11 @@ pub async fn anthropic_messages(
         ),
     );
 
+    let unsupported = unsupported_server_tools(&request, Some(KIRO_SERVER_TOOLS));
+    if !unsupported.is_empty() {
+        return server_tools_unsupported_response(&selected_provider, &unsupported);
+    }
+
     // 启动 Flow 捕获（legacy mode）
     let llm_request = build_llm_request_from_anthropic(&request, "/v1/messages", &headers);
 
@@ -2314,8 +3243,7 @@ pub async fn anthropic_messages(
     {
         let _guard = state.kiro_refresh_lock.lock().await;
         let mut kiro = state.kiro.write().await;
-        let needs_refresh =
-            kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
+        let needs_refresh = kiro.access_token().is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
             state.logs.write().await.add(
                 "info",
@@ -2387,7 +3315,7 @@ pub async fn anthropic_messages(
                         );
 
                         // 保存原始响应到文件用于调试
-                        let request_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
+                        let request_id = crate::middleware::current_or_new_request_id();
                         state.logs.read().await.log_raw_response(&request_id, &body);
                         state.logs.write().await.add(
                             "debug",
@@ -2403,7 +3331,7 @@ pub async fn anthropic_messages(
                             .await
                             .add("debug", &format!("[RESP] Body preview: {preview}"));
 
-                        let parsed = parse_cw_response(&body);
+                        let parsed = parse_cw_response(&bytes);
 
                         // 详细记录解析结果
                         state.logs.write().await.add(
@@ -2466,7 +3394,7 @@ pub async fn anthropic_messages(
                                     return (
                                         StatusCode::OK,
                                         Json(serde_json::json!({
-                                            "id": format!("msg_{}", uuid::Uuid::new_v4()),
+                                            "id": crate::middleware::response_id("msg_"),
                                             "type": "message",
                                             "role": "assistant",
                                             "content": [{
@@ -2529,7 +3457,7 @@ pub async fn anthropic_messages(
                                 return (
                                     StatusCode::OK,
                                     Json(serde_json::json!({
-                                        "id": format!("msg_{}", uuid::Uuid::new_v4()),
+                                        "id": crate::middleware::response_id("msg_"),
                                         "type": "message",
                                         "role": "assistant",
                                         "content": [{
@@ -2614,8 +3542,7 @@ pub async fn anthropic_messages(
                                 if retry_resp.status().is_success() {
                                     match retry_resp.bytes().await {
                                         Ok(bytes) => {
-                                            let body = String::from_utf8_lossy(&bytes).to_string();
-                                            let parsed = parse_cw_response(&body);
+                                            let parsed = parse_cw_response(&bytes);
                                             state.logs.write().await.add(
                                                 "info",
                                                 &format!(
@@ -2665,7 +3592,7 @@ pub async fn anthropic_messages(
                                                         return (
                                                             StatusCode::OK,
                                                             Json(serde_json::json!({
-                                                                "id": format!("msg_{}", uuid::Uuid::new_v4()),
+                                                                "id": crate::middleware::response_id("msg_"),
                                                                 "type": "message",
                                                                 "role": "assistant",
                                                                 "content": [{
@@ -2686,7 +3613,7 @@ pub async fn anthropic_messages(
                                                         return (
                                                             StatusCode::OK,
                                                             Json(serde_json::json!({
-                                                                "id": format!("msg_{}", uuid::Uuid::new_v4()),
+                                                                "id": crate::middleware::response_id("msg_"),
                                                                 "type": "message",
                                                                 "role": "assistant",
                                                                 "content": [{
@@ -2940,44 +3867,7 @@ fn build_stream_error_response(
     message: &str,
     target_format: StreamingFormat,
 ) -> Response {
-    let error_event = match target_format {
-        StreamingFormat::AnthropicSse => {
-            format!(
-                "event: error\ndata: {}\n\n",
-                serde_json::json!({
-                    "type": "error",
-                    "error": {
-                        "type": error_type,
-                        "message": message
-                    }
-                })
-            )
-        }
-        // TODO: 任务 6 完成后，添加 GeminiStream 分支
-        StreamingFormat::OpenAiSse => {
-            format!(
-                "data: {}\n\n",
-                serde_json::json!({
-                    "error": {
-                        "type": error_type,
-                        "message": message
-                    }
-                })
-            )
-        }
-        StreamingFormat::AwsEventStream => {
-            // AWS Event Stream 格式的错误（不太可能作为目标格式）
-            format!(
-                "data: {}\n\n",
-                serde_json::json!({
-                    "error": {
-                        "type": error_type,
-                        "message": message
-                    }
-                })
-            )
-        }
-    };
+    let error_event = crate::streaming::sse_error_event(target_format, error_type, message);
 
     Response::builder()
         .status(StatusCode::OK) // SSE 错误仍然返回 200
@@ -3119,6 +4009,10 @@ fn convert_openai_to_anthropic(request: &ChatCompletionRequest) -> serde_json::V
         result["system"] = serde_json::Value::String(system);
     }
 
+    if let Some(user) = &request.user {
+        result["metadata"] = serde_json::json!({ "user_id": user });
+    }
+
     if let Some(temp) = request.temperature {
         result["temperature"] = serde_json::Value::Number(
             serde_json::Number::from_f64(temp as f64).unwrap_or(serde_json::Number::from(1)),
@@ -3154,12 +4048,11 @@ fn convert_anthropic_response_to_openai(anthropic_resp: &serde_json::Value, mode
                 "role": "assistant",
                 "content": content
             },
-            "finish_reason": match anthropic_resp["stop_reason"].as_str() {
-                Some("end_turn") => "stop",
-                Some("max_tokens") => "length",
-                Some("tool_use") => "tool_calls",
-                _ => "stop"
-            }
+            "finish_reason": anthropic_resp["stop_reason"]
+                .as_str()
+                .map(StopReason::from_anthropic_str)
+                .unwrap_or_default()
+                .to_openai_str()
         }],
         "usage": usage
     });
@@ -0,0 +1,324 @@
+//! 音频 API 处理器
+//!
+//! 实现 OpenAI 兼容的 `/v1/audio/transcriptions`（语音转文字）和
+//! `/v1/audio/speech`（文字转语音）端点。
+//!
+//! # 功能
+//! - 请求体原样透传（转录为 multipart/form-data，语音合成为 JSON）
+//! - 从凭证池选择 OpenAI 凭证，更新凭证健康状态和使用次数
+//! - 上游响应（文本、JSON、SSE 或音频流）原样返回
+//! - 记录请求遥测
+//!
+//! 请求体大小受 `server.body_limits.audio_mb` 限制（默认 25 MB）。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::models::provider_pool_model::CredentialData;
+use crate::processor::RequestContext;
+use crate::providers::OpenAICustomProvider;
+use crate::server::handlers::verify_api_key;
+use crate::server::{record_request_telemetry, AppState};
+use crate::server_utils::multipart_text_field;
+use crate::telemetry::RequestStatus;
+use crate::ProviderType;
+
+/// 音频端点
+#[derive(Debug, Clone, Copy)]
+enum AudioEndpoint {
+    /// 语音转文字
+    Transcriptions,
+    /// 文字转语音
+    Speech,
+}
+
+impl AudioEndpoint {
+    /// 上游路径（相对于 `/v1`）
+    fn path(self) -> &'static str {
+        match self {
+            AudioEndpoint::Transcriptions => "audio/transcriptions",
+            AudioEndpoint::Speech => "audio/speech",
+        }
+    }
+
+    /// 日志中的名称
+    fn label(self) -> &'static str {
+        match self {
+            AudioEndpoint::Transcriptions => "语音转录",
+            AudioEndpoint::Speech => "语音合成",
+        }
+    }
+
+    /// 从请求体中读取模型名
+    fn model(self, content_type: &str, body: &[u8]) -> Option<String> {
+        match self {
+            AudioEndpoint::Transcriptions => multipart_text_field(body, content_type, "model"),
+            AudioEndpoint::Speech => serde_json::from_slice::<serde_json::Value>(body)
+                .ok()
+                .and_then(|v| v.get("model")?.as_str().map(|m| m.trim().to_string())),
+        }
+    }
+}
+
+/// 处理语音转录请求
+///
+/// # 端点
+/// `POST /v1/audio/transcriptions`
+///
+/// 请求体为 multipart/form-data（`file`、`model` 以及可选的 `language`、`prompt`、
+/// `response_format` 等字段），原样转发给上游。
+pub async fn handle_audio_transcription(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    forward_audio(state, headers, body, AudioEndpoint::Transcriptions).await
+}
+
+/// 处理语音合成请求
+///
+/// # 端点
+/// `POST /v1/audio/speech`
+///
+/// # 请求格式
+/// ```json
+/// {
+///   "model": "tts-1",
+///   "input": "你好",
+///   "voice": "alloy",
+///   "response_format": "mp3"
+/// }
+/// ```
+///
+/// 响应为上游返回的音频数据，边接收边返回。
+pub async fn handle_audio_speech(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    forward_audio(state, headers, body, AudioEndpoint::Speech).await
+}
+
+/// 构建 OpenAI 格式的错误响应
+fn audio_error(status: StatusCode, message: &str, error_type: &str, code: &str) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+async fn forward_audio(
+    state: AppState,
+    headers: HeaderMap,
+    body: Bytes,
+    endpoint: AudioEndpoint,
+) -> Response {
+    // 验证 API Key
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+
+    // 验证请求参数
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let expected_type = match endpoint {
+        AudioEndpoint::Transcriptions => "multipart/form-data",
+        AudioEndpoint::Speech => "application/json",
+    };
+    if !content_type.to_ascii_lowercase().starts_with(expected_type) {
+        return audio_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            &format!("Content-Type must be {}", expected_type),
+            "invalid_request_error",
+            "invalid_content_type",
+        );
+    }
+    let model = match endpoint
+        .model(&content_type, &body)
+        .filter(|m| !m.is_empty())
+    {
+        Some(model) => model,
+        None => {
+            return audio_error(
+                StatusCode::BAD_REQUEST,
+                "model is required and cannot be empty",
+                "invalid_request_error",
+                "invalid_model",
+            );
+        }
+    };
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AUDIO] 收到{}请求: model={}, size={} bytes",
+            endpoint.label(),
+            model,
+            body.len()
+        ),
+    );
+
+    let db = match &state.db {
+        Some(db) => db,
+        None => {
+            return audio_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database not available",
+                "server_error",
+                "database_unavailable",
+            );
+        }
+    };
+
+    // 从凭证池获取 OpenAI 凭证
+    let credential = match state
+        .pool_service
+        .select_credential(db, "openai", Some(&model))
+    {
+        Ok(Some(cred)) => cred,
+        Ok(None) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("error", "[AUDIO] 没有可用的 OpenAI 凭证");
+            return audio_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No OpenAI credentials available for audio requests",
+                "server_error",
+                "no_credentials",
+            );
+        }
+        Err(e) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("error", &format!("[AUDIO] 获取凭证失败: {}", e));
+            return audio_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to get credentials: {}", e),
+                "server_error",
+                "credential_error",
+            );
+        }
+    };
+
+    let (api_key, base_url) = match &credential.credential {
+        CredentialData::OpenAIKey { api_key, base_url } => (api_key.clone(), base_url.clone()),
+        _ => {
+            return audio_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Selected credential is not OpenAI type",
+                "server_error",
+                "credential_error",
+            );
+        }
+    };
+
+    let mut ctx = RequestContext::new(model.clone());
+    // 沿用请求 ID 中间件分配的 ID，使日志、遥测与响应头一致
+    if let Some(request_id) = crate::middleware::current_request_id() {
+        ctx.request_id = request_id;
+    }
+    ctx.set_provider(ProviderType::OpenAI);
+    ctx.set_credential_id(credential.uuid.clone());
+
+    let provider = OpenAICustomProvider::with_config(api_key, base_url);
+    let resp = match provider
+        .call_audio_raw(endpoint.path(), &content_type, body)
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            let message = format!("Audio request failed: {}", e);
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&message));
+            state
+                .logs
+                .write()
+                .await
+                .add("error", &format!("[AUDIO] 上游请求失败: {}", e));
+            record_request_telemetry(&state, &ctx, RequestStatus::Failed, Some(message.clone()));
+            return audio_error(
+                StatusCode::BAD_GATEWAY,
+                &message,
+                "server_error",
+                "api_error",
+            );
+        }
+    };
+
+    let status = resp.status();
+    let upstream_type = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .cloned()
+        .unwrap_or_else(|| header::HeaderValue::from_static("application/json"));
+
+    if !status.is_success() {
+        let error_body = resp.text().await.unwrap_or_default();
+        let message = format!("Upstream returned {}: {}", status.as_u16(), error_body);
+        // 请求参数错误不影响凭证健康状态
+        if status.is_server_error()
+            || status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+        {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&message));
+        }
+        state.logs.write().await.add(
+            "error",
+            &format!("[AUDIO] {}失败: {}", endpoint.label(), message),
+        );
+        record_request_telemetry(&state, &ctx, RequestStatus::Failed, Some(message));
+        return Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, upstream_type)
+            .body(Body::from(error_body))
+            .unwrap_or_else(|_| {
+                audio_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to build response",
+                    "server_error",
+                    "api_error",
+                )
+            });
+    }
+
+    let _ = state
+        .pool_service
+        .mark_healthy(db, &credential.uuid, Some(&model));
+    let _ = state.pool_service.record_usage(db, &credential.uuid);
+    record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, upstream_type)
+        .body(Body::from_stream(resp.bytes_stream()))
+        .unwrap_or_else(|_| {
+            audio_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to build response",
+                "server_error",
+                "api_error",
+            )
+        })
+}
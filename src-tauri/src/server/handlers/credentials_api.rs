@@ -322,6 +322,11 @@ fn get_oauth_base_url(provider_type: &PoolProviderType) -> String {
         PoolProviderType::Codex => "https://api.openai.com/v1".to_string(),
         PoolProviderType::ClaudeOAuth => "https://api.anthropic.com".to_string(),
         PoolProviderType::IFlow => "https://chat.iflyrec.com".to_string(),
+        PoolProviderType::OpenRouter => {
+            crate::providers::openrouter::OPENROUTER_BASE_URL.to_string()
+        }
+        PoolProviderType::Mistral => crate::providers::mistral::MISTRAL_BASE_URL.to_string(),
+        PoolProviderType::DeepSeek => crate::providers::deepseek::DEEPSEEK_BASE_URL.to_string(),
         _ => "https://api.openai.com/v1".to_string(),
     }
 }
@@ -0,0 +1,271 @@
+//! 文件 API 处理器
+//!
+//! 实现 OpenAI / Anthropic 兼容的 `/v1/files` 端点，文件保存在本地会话存储中。
+//! 响应格式按请求头判断：带 `anthropic-version` 时返回 Anthropic 文件对象，否则返回 OpenAI 文件对象。
+//!
+//! 消息中引用上传文件的内联转换见 [`crate::session_files::uploads`]。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::server::handlers::{verify_api_key, verify_api_key_anthropic};
+use crate::server::AppState;
+use crate::server_utils::parse_multipart;
+use crate::session_files::uploads::{
+    file_deleted_object, file_list_object, file_object, format_from_headers, guess_mime_type,
+};
+use crate::session_files::{SessionFileStorage, TranscriptFormat};
+
+/// 按格式构建错误响应
+fn files_error(
+    format: TranscriptFormat,
+    status: StatusCode,
+    message: impl Into<String>,
+) -> Response {
+    let message = message.into();
+    let body = match format {
+        TranscriptFormat::OpenAI => serde_json::json!({
+            "error": {
+                "message": message,
+                "type": "invalid_request_error",
+                "code": null
+            }
+        }),
+        TranscriptFormat::Anthropic => serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": if status == StatusCode::NOT_FOUND {
+                    "not_found_error"
+                } else {
+                    "invalid_request_error"
+                },
+                "message": message
+            }
+        }),
+    };
+    (status, Json(body)).into_response()
+}
+
+/// 用于 Content-Disposition 的文件名（去掉非 ASCII 字符和引号）
+fn ascii_filename(filename: &str) -> String {
+    filename
+        .chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control() && *c != '"')
+        .collect()
+}
+
+/// 验证 API Key 并获取文件存储
+async fn files_storage<'a>(
+    state: &'a AppState,
+    headers: &HeaderMap,
+    format: TranscriptFormat,
+) -> Result<&'a SessionFileStorage, Response> {
+    let verified = match format {
+        TranscriptFormat::OpenAI => verify_api_key(headers, &state.api_key).await,
+        TranscriptFormat::Anthropic => verify_api_key_anthropic(headers, &state.api_key).await,
+    };
+    verified.map_err(IntoResponse::into_response)?;
+    state
+        .session_recorder
+        .as_ref()
+        .map(|recorder| recorder.storage())
+        .ok_or_else(|| {
+            files_error(
+                format,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "File storage is unavailable",
+            )
+        })
+}
+
+/// 上传文件
+///
+/// # 端点
+/// `POST /v1/files`
+///
+/// 请求体为 multipart/form-data，`file` 字段为文件内容，可选 `purpose` 字段（OpenAI）。
+pub async fn upload_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let format = format_from_headers(&headers);
+    let storage = match files_storage(&state, &headers, format).await {
+        Ok(storage) => storage,
+        Err(response) => return response,
+    };
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let Some(fields) = parse_multipart(&body, content_type) else {
+        return files_error(
+            format,
+            StatusCode::BAD_REQUEST,
+            "Content-Type must be multipart/form-data",
+        );
+    };
+    let Some(file_field) = fields
+        .iter()
+        .find(|field| field.name == "file" && field.filename.is_some())
+    else {
+        return files_error(format, StatusCode::BAD_REQUEST, "file: Field required");
+    };
+    if file_field.data.is_empty() {
+        return files_error(format, StatusCode::BAD_REQUEST, "file: File is empty");
+    }
+    let purpose = fields
+        .iter()
+        .find(|field| field.name == "purpose" && field.filename.is_none())
+        .and_then(|field| std::str::from_utf8(field.data).ok())
+        .map(|purpose| purpose.trim().to_string())
+        .filter(|purpose| !purpose.is_empty());
+
+    let filename = file_field.filename.as_deref().unwrap_or_default();
+    let mime_type = file_field
+        .content_type
+        .as_deref()
+        .filter(|t| !t.is_empty() && *t != "application/octet-stream")
+        .unwrap_or_else(|| guess_mime_type(filename));
+
+    match storage.save_upload(filename, purpose, mime_type, file_field.data) {
+        Ok(file) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[FILES] 上传文件: {} -> {} ({} bytes)",
+                    file.filename, file.id, file.size
+                ),
+            );
+            Json(file_object(&file, format)).into_response()
+        }
+        Err(e) => files_error(format, StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// 列出上传的文件
+///
+/// # 端点
+/// `GET /v1/files`
+pub async fn list_files(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let format = format_from_headers(&headers);
+    let storage = match files_storage(&state, &headers, format).await {
+        Ok(storage) => storage,
+        Err(response) => return response,
+    };
+    match storage.list_uploads() {
+        Ok(files) => Json(file_list_object(&files, format)).into_response(),
+        Err(e) => files_error(format, StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// 获取文件对象
+///
+/// # 端点
+/// `GET /v1/files/:id`
+pub async fn get_file(
+    State(state): State<AppState>,
+    Path(file_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let format = format_from_headers(&headers);
+    let storage = match files_storage(&state, &headers, format).await {
+        Ok(storage) => storage,
+        Err(response) => return response,
+    };
+    match storage.get_upload(&file_id) {
+        Ok(Some(file)) => Json(file_object(&file, format)).into_response(),
+        Ok(None) => files_error(
+            format,
+            StatusCode::NOT_FOUND,
+            format!("File not found: {}", file_id),
+        ),
+        Err(e) => files_error(format, StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// 下载文件内容
+///
+/// # 端点
+/// `GET /v1/files/:id/content`
+pub async fn get_file_content(
+    State(state): State<AppState>,
+    Path(file_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let format = format_from_headers(&headers);
+    let storage = match files_storage(&state, &headers, format).await {
+        Ok(storage) => storage,
+        Err(response) => return response,
+    };
+    let file = match storage.get_upload(&file_id) {
+        Ok(Some(file)) => file,
+        Ok(None) => {
+            return files_error(
+                format,
+                StatusCode::NOT_FOUND,
+                format!("File not found: {}", file_id),
+            )
+        }
+        Err(e) => return files_error(format, StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    match storage.read_upload(&file_id) {
+        Ok(content) => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, file.mime_type.as_str())
+            .header(
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{}\"",
+                    ascii_filename(&file.filename)
+                ),
+            )
+            .body(Body::from(content))
+            .unwrap_or_else(|_| {
+                files_error(
+                    format,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to build response",
+                )
+            }),
+        Err(e) => files_error(format, StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// 删除文件
+///
+/// # 端点
+/// `DELETE /v1/files/:id`
+pub async fn delete_file(
+    State(state): State<AppState>,
+    Path(file_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let format = format_from_headers(&headers);
+    let storage = match files_storage(&state, &headers, format).await {
+        Ok(storage) => storage,
+        Err(response) => return response,
+    };
+    match storage.delete_upload(&file_id) {
+        Ok(true) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("info", &format!("[FILES] 删除文件: {}", file_id));
+            Json(file_deleted_object(&file_id, format)).into_response()
+        }
+        Ok(false) => files_error(
+            format,
+            StatusCode::NOT_FOUND,
+            format!("File not found: {}", file_id),
+        ),
+        Err(e) => files_error(format, StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
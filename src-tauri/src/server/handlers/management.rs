@@ -1,14 +1,31 @@
 //! Management API 处理器
 //!
-//! 提供服务器状态查询、凭证管理、配置管理等功能
+//! 提供服务器状态查询、凭证管理、配置管理、路由管理等功能
 
 #![allow(dead_code)]
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::config::{AmpModelMapping, ExportService, MaintenanceWindowConfig, ReloadResult};
 use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::dao::telemetry::{
+    RollupGranularity, RollupQuery, TelemetryDao, TelemetryRollup, UserUsage,
+};
+use crate::logger::LogFilter;
+use crate::models::provider_pool_model::{BulkCredentialFilter, DuplicateCredentialRequest};
+use crate::models::route_model::API_KEY_PLACEHOLDER;
+use crate::oauth::{LoginOptions, LoginProvider, LoginSession};
+use crate::router::{validate_route_name, EffectiveAmpMapping, RegisteredRoute};
 use crate::server::AppState;
+use crate::services::credential_import_service;
+use crate::telemetry::{LatencyStats, DEFAULT_LATENCY_WINDOW_MINUTES};
+use crate::usage::CredentialQuota;
 
 // ============ Types ============
 
@@ -31,6 +48,8 @@ pub struct ManagementStatusResponse {
     pub tls_enabled: bool,
     /// 默认 Provider
     pub default_provider: String,
+    /// thoughtSignature 缓存统计
+    pub signature_store: crate::session::SignatureStoreStats,
 }
 
 /// 凭证信息（用于列表显示）
@@ -149,11 +168,212 @@ pub struct UpdateConfigResponse {
     pub message: String,
 }
 
+/// 路由信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagementRouteInfo {
+    #[serde(flatten)]
+    pub route: RegisteredRoute,
+    /// 是否配置了路由专属 API Key
+    pub has_api_key: bool,
+}
+
+/// 路由列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutesListResponse {
+    pub routes: Vec<ManagementRouteInfo>,
+    pub total: usize,
+}
+
+/// 注册路由请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegisterRouteRequest {
+    /// 路由名称（用作 `/{name}/v1/...` 中的选择器）
+    pub name: String,
+    /// 绑定的凭证选择器（凭证名称、UUID 或已注册的路由名称）
+    pub selector: String,
+    /// 路由专属 API Key
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// 路由操作响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteActionResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+/// 遥测历史查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelemetryHistoryParams {
+    /// 汇总粒度（hour / day），默认 hour
+    #[serde(default)]
+    pub granularity: Option<String>,
+    /// 起始时间（Unix 秒，含）
+    pub start: Option<i64>,
+    /// 结束时间（Unix 秒，不含）
+    pub end: Option<i64>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+/// 终端用户用量查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserUsageParams {
+    /// 起始时间（Unix 秒，含）
+    pub start: Option<i64>,
+    /// 结束时间（Unix 秒，不含）
+    pub end: Option<i64>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// 最多返回的用户数，默认 100
+    pub limit: Option<usize>,
+}
+
+/// 延迟分位数查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct LatencyQueryParams {
+    /// 滑动窗口（分钟），默认 15
+    pub window_minutes: Option<i64>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub credential_id: Option<String>,
+}
+
+/// 延迟分位数响应
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyPercentilesResponse {
+    pub window_minutes: i64,
+    pub latencies: Vec<LatencyStats>,
+    pub total: usize,
+}
+
+/// 路由配置包查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RouteBundleParams {
+    /// 嵌入真实 API Key（默认使用占位符）
+    #[serde(default)]
+    pub include_api_key: bool,
+}
+
+/// 凭证额度查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuotaQueryParams {
+    /// 忽略缓存，重新查询上游
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// 凭证额度列表响应
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaListResponse {
+    pub quotas: Vec<CredentialQuota>,
+    pub total: usize,
+}
+
+/// 每日用量报告查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct DailyReportParams {
+    /// 报告日期（YYYY-MM-DD，UTC），默认当天
+    pub date: Option<String>,
+    /// 输出格式（json / markdown），默认 json
+    pub format: Option<String>,
+    /// 是否同时保存到 `~/.proxycast/reports/`
+    #[serde(default)]
+    pub save: bool,
+}
+
+/// 实时日志流参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogStreamParams {
+    /// 最低日志级别（debug / info / warn / error）
+    pub level: Option<String>,
+    /// 关键字（不区分大小写）
+    pub keyword: Option<String>,
+    /// 连接后先推送的最近日志条数，默认 0
+    #[serde(default)]
+    pub backlog: usize,
+}
+
+/// tracing 过滤指令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingFilterBody {
+    /// 过滤指令（如 `info,proxycast_lib::server=debug`）
+    pub directives: String,
+}
+
+/// 凭证导入请求
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImportCredentialsRequest {
+    /// 只导入指定路径的凭证（为空时导入所有未导入的凭证）
+    pub paths: Option<Vec<String>>,
+}
+
+/// 批量启用 / 禁用凭证请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkSetDisabledRequest {
+    #[serde(flatten)]
+    pub filter: BulkCredentialFilter,
+    pub disabled: bool,
+}
+
+/// 批量健康检查请求
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BulkCheckRequest {
+    #[serde(flatten)]
+    pub filter: BulkCredentialFilter,
+    /// 并发数（默认使用配置中的 `health_check.concurrency`）
+    pub concurrency: Option<usize>,
+}
+
+/// Amp 模型映射查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct AmpMappingParams {
+    /// 按 provider 解析（可选）
+    pub provider: Option<String>,
+    /// 解析指定模型的映射结果（可选）
+    pub model: Option<String>,
+}
+
+/// Amp 模型映射解析结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AmpMappingResolution {
+    pub provider: Option<String>,
+    pub model: String,
+    pub mapped_to: String,
+    /// 命中的映射规则（未命中时为 None）
+    pub rule: Option<AmpModelMapping>,
+}
+
+/// Amp 模型映射响应
+#[derive(Debug, Clone, Serialize)]
+pub struct AmpMappingsResponse {
+    pub mappings: Vec<EffectiveAmpMapping>,
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<AmpMappingResolution>,
+}
+
+/// 遥测历史响应
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryHistoryResponse {
+    pub granularity: RollupGranularity,
+    pub rollups: Vec<TelemetryRollup>,
+    pub total: usize,
+}
+
+/// 终端用户用量响应
+#[derive(Debug, Clone, Serialize)]
+pub struct UserUsageResponse {
+    pub users: Vec<UserUsage>,
+    pub total: usize,
+}
+
 // ============ Handlers ============
 
 /// GET /v0/management/status - 获取服务器状态
 pub async fn management_status(State(state): State<AppState>) -> impl IntoResponse {
-    let default_provider = state.default_provider.read().await.clone();
+    let default_provider = state.processor.snapshot().default_provider.clone();
 
     // 获取请求数量
     let requests = state.processor.stats.read().len() as u64;
@@ -167,6 +387,7 @@ pub async fn management_status(State(state): State<AppState>) -> impl IntoRespon
         version: env!("CARGO_PKG_VERSION").to_string(),
         tls_enabled: false,
         default_provider,
+        signature_store: crate::session::signature_store_stats(),
     };
 
     Json(response)
@@ -449,6 +670,28 @@ pub async fn management_add_credential(
                 );
             }
         }
+        // OpenAI 兼容的第三方 API Key（OpenRouter / Mistral / DeepSeek）
+        PoolProviderType::OpenRouter | PoolProviderType::Mistral | PoolProviderType::DeepSeek => {
+            if let Some(api_key) = request.api_key {
+                let base_url = request.base_url;
+                match provider_type {
+                    PoolProviderType::OpenRouter => {
+                        CredentialData::OpenRouterKey { api_key, base_url }
+                    }
+                    PoolProviderType::Mistral => CredentialData::MistralKey { api_key, base_url },
+                    _ => CredentialData::DeepSeekKey { api_key, base_url },
+                }
+            } else {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(AddCredentialResponse {
+                        success: false,
+                        message: format!("API key is required for {} provider", provider_type),
+                        id: None,
+                    }),
+                );
+            }
+        }
         // API Key Provider 类型 - 不支持通过此接口添加凭证
         PoolProviderType::AzureOpenai | PoolProviderType::AwsBedrock | PoolProviderType::Ollama => {
             return (
@@ -474,6 +717,7 @@ pub async fn management_add_credential(
         if let Ok(conn) = db.lock() {
             match ProviderPoolDao::insert(&conn, &credential) {
                 Ok(_) => {
+                    state.model_catalog.invalidate();
                     tracing::info!(
                         "[MANAGEMENT] Added credential: {} ({})",
                         request.id,
@@ -515,7 +759,7 @@ pub async fn management_add_credential(
 
 /// GET /v0/management/config - 获取配置
 pub async fn management_get_config(State(state): State<AppState>) -> impl IntoResponse {
-    let default_provider = state.default_provider.read().await.clone();
+    let default_provider = state.processor.snapshot().default_provider.clone();
 
     let response = ManagementConfigResponse {
         server: ManagementServerConfigInfo {
@@ -553,8 +797,10 @@ pub async fn management_update_config(
     if let Some(provider) = request.default_provider {
         // 验证 provider 类型
         if provider.parse::<crate::ProviderType>().is_ok() {
-            let mut dp = state.default_provider.write().await;
-            *dp = provider.clone();
+            state
+                .processor
+                .snapshot
+                .rcu(|current| current.with_default_provider(&provider));
             tracing::info!("[MANAGEMENT] Updated default_provider to: {}", provider);
             updated = true;
         } else {
@@ -586,3 +832,933 @@ pub async fn management_update_config(
         )
     }
 }
+
+/// POST /v0/management/config/reload - 立即重新加载配置文件
+///
+/// 不依赖文件监控事件（网络挂载或原子替换的配置文件可能收不到事件）。
+/// 重载失败时保留原配置，返回 422（已回滚）或 500（回滚失败）。
+pub async fn management_reload_config(State(state): State<AppState>) -> impl IntoResponse {
+    let Some(reloader) = &state.config_reloader else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "success": false,
+                "message": "Config file not available"
+            })),
+        )
+            .into_response();
+    };
+
+    let result = reloader.reload().await;
+    let status = match &result {
+        ReloadResult::Success { .. } => StatusCode::OK,
+        ReloadResult::RolledBack { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        ReloadResult::Failed { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(result.report())).into_response()
+}
+
+/// GET /v0/management/config/full - 获取完整的生效配置
+///
+/// 包含默认值在内的全部配置项，密钥按导出脱敏规则替换为占位符。
+pub async fn management_get_full_config(State(state): State<AppState>) -> impl IntoResponse {
+    let Some(reloader) = &state.config_reloader else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "success": false,
+                "message": "Config file not available"
+            })),
+        )
+            .into_response();
+    };
+
+    Json(ExportService::redact_config(&reloader.config())).into_response()
+}
+
+/// GET /v0/management/cluster - 获取集群状态
+pub async fn management_get_cluster_status() -> impl IntoResponse {
+    Json(serde_json::json!({
+        "success": true,
+        "cluster": crate::services::cluster_service::status()
+    }))
+}
+
+/// GET /v0/management/maintenance - 列出未结束的维护窗口
+pub async fn management_list_maintenance() -> impl IntoResponse {
+    let now = chrono::Utc::now();
+    let windows: Vec<serde_json::Value> = crate::credential::maintenance_registry()
+        .list(now)
+        .into_iter()
+        .map(|w| {
+            let active = w.is_active(now);
+            let mut value = serde_json::json!(w);
+            value["active"] = serde_json::json!(active);
+            value
+        })
+        .collect();
+    Json(serde_json::json!({
+        "success": true,
+        "windows": windows
+    }))
+}
+
+/// POST /v0/management/maintenance - 添加维护窗口（仅保存在内存中）
+pub async fn management_add_maintenance(
+    Json(request): Json<MaintenanceWindowConfig>,
+) -> impl IntoResponse {
+    match crate::credential::maintenance_registry().add(&request) {
+        Ok(window) => Json(serde_json::json!({
+            "success": true,
+            "window": window
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "success": false,
+                "message": e
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// DELETE /v0/management/maintenance/:id - 移除管理 API 添加的维护窗口
+pub async fn management_remove_maintenance(Path(id): Path<String>) -> impl IntoResponse {
+    if crate::credential::maintenance_registry().remove(&id) {
+        Json(serde_json::json!({
+            "success": true,
+            "message": format!("Maintenance window removed: {}", id)
+        }))
+        .into_response()
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "success": false,
+                "message": format!("Maintenance window not found: {}", id)
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// GET /v0/management/routes - 获取已注册的路由
+pub async fn management_list_routes(State(state): State<AppState>) -> impl IntoResponse {
+    let registry = state.route_registry.read().await;
+
+    let routes: Vec<ManagementRouteInfo> = registry
+        .all_routes()
+        .iter()
+        .map(|route| {
+            let selector = route
+                .credential_name
+                .as_deref()
+                .or(route.credential_uuid.as_deref());
+            ManagementRouteInfo {
+                has_api_key: selector.and_then(|s| registry.api_key_for(s)).is_some(),
+                route: route.clone(),
+            }
+        })
+        .collect();
+
+    let total = routes.len();
+    Json(RoutesListResponse { routes, total })
+}
+
+/// GET /v0/management/routes/bundle - 为所有可用路由生成客户端配置
+pub async fn management_route_bundle(
+    State(state): State<AppState>,
+    Query(params): Query<RouteBundleParams>,
+) -> impl IntoResponse {
+    let routes = crate::server::provider_dispatch::route_list(&state);
+    let registry = state.route_registry.read().await;
+    let bundle = routes.client_bundle(|selector| {
+        if params.include_api_key {
+            registry
+                .api_key_for(selector)
+                .unwrap_or(&state.api_key)
+                .to_string()
+        } else {
+            API_KEY_PLACEHOLDER.to_string()
+        }
+    });
+    Json(bundle)
+}
+
+/// POST /v0/management/routes - 动态注册命名路由
+pub async fn management_register_route(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterRouteRequest>,
+) -> impl IntoResponse {
+    let respond = |status: StatusCode, success: bool, message: String| {
+        (status, Json(RouteActionResponse { success, message }))
+    };
+
+    if let Err(e) = validate_route_name(&request.name) {
+        return respond(StatusCode::BAD_REQUEST, false, e);
+    }
+    if request
+        .api_key
+        .as_deref()
+        .is_some_and(|k| k.trim().is_empty())
+    {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            false,
+            "Route API key must not be empty".to_string(),
+        );
+    }
+
+    let Some(db) = &state.db else {
+        return respond(
+            StatusCode::SERVICE_UNAVAILABLE,
+            false,
+            "Database not available".to_string(),
+        );
+    };
+
+    // 已注册的路由名称解析为其绑定的凭证 UUID
+    let bound_uuid = state
+        .route_registry
+        .read()
+        .await
+        .find_by_selector(&request.selector)
+        .and_then(|r| r.credential_uuid.clone());
+
+    let credential = match bound_uuid {
+        Some(uuid) => state.pool_service.get_by_uuid(db, &uuid),
+        None => state
+            .pool_service
+            .get_by_name(db, &request.selector)
+            .and_then(|cred| match cred {
+                Some(cred) => Ok(Some(cred)),
+                None => state.pool_service.get_by_uuid(db, &request.selector),
+            }),
+    };
+    let credential = match credential {
+        Ok(Some(cred)) => cred,
+        Ok(None) => {
+            return respond(
+                StatusCode::NOT_FOUND,
+                false,
+                format!("Credential not found for selector: {}", request.selector),
+            );
+        }
+        Err(e) => {
+            return respond(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                false,
+                format!("Failed to resolve credential: {}", e),
+            );
+        }
+    };
+
+    let mut registry = state.route_registry.write().await;
+    if registry.find_by_selector(&request.name).is_some() {
+        return respond(
+            StatusCode::CONFLICT,
+            false,
+            format!("Route already exists: {}", request.name),
+        );
+    }
+
+    registry.register(RegisteredRoute::dynamic_route(
+        &request.name,
+        &credential.provider_type.to_string(),
+        &credential.uuid,
+    ));
+    if let Some(api_key) = &request.api_key {
+        registry.set_api_key(&request.name, api_key);
+    }
+
+    tracing::info!(
+        "[MANAGEMENT] Registered route: {} -> {} ({})",
+        request.name,
+        credential.uuid,
+        credential.provider_type
+    );
+    respond(
+        StatusCode::CREATED,
+        true,
+        format!("Route registered: /{}/v1/{{endpoint}}", request.name),
+    )
+}
+
+/// POST /v0/management/routes/:selector/enable - 启用路由
+pub async fn management_enable_route(
+    State(state): State<AppState>,
+    Path(selector): Path<String>,
+) -> impl IntoResponse {
+    set_route_enabled(&state, &selector, true).await
+}
+
+/// POST /v0/management/routes/:selector/disable - 禁用路由
+pub async fn management_disable_route(
+    State(state): State<AppState>,
+    Path(selector): Path<String>,
+) -> impl IntoResponse {
+    set_route_enabled(&state, &selector, false).await
+}
+
+async fn set_route_enabled(
+    state: &AppState,
+    selector: &str,
+    enabled: bool,
+) -> (StatusCode, Json<RouteActionResponse>) {
+    if !state
+        .route_registry
+        .write()
+        .await
+        .set_enabled(selector, enabled)
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(RouteActionResponse {
+                success: false,
+                message: format!("Route not found: {}", selector),
+            }),
+        );
+    }
+
+    let action = if enabled { "Enabled" } else { "Disabled" };
+    tracing::info!("[MANAGEMENT] {} route: {}", action, selector);
+    (
+        StatusCode::OK,
+        Json(RouteActionResponse {
+            success: true,
+            message: format!("{} route: {}", action, selector),
+        }),
+    )
+}
+
+/// GET /v0/management/telemetry/history - 查询持久化的遥测汇总
+pub async fn management_telemetry_history(
+    State(state): State<AppState>,
+    Query(params): Query<TelemetryHistoryParams>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(serde_json::json!({ "success": false, "message": message })),
+        )
+            .into_response()
+    };
+
+    let granularity = match params.granularity.as_deref() {
+        None => RollupGranularity::Hour,
+        Some(g) => match g.parse::<RollupGranularity>() {
+            Ok(g) => g,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e),
+        },
+    };
+
+    let Some(db) = &state.db else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Database not available".to_string(),
+        );
+    };
+
+    let query = RollupQuery {
+        start: params.start,
+        end: params.end,
+        provider: params.provider,
+        model: params.model,
+    };
+    let result = match db.lock() {
+        Ok(conn) => TelemetryDao::query_rollups(&conn, granularity, &query),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    match result {
+        Ok(rollups) => {
+            let total = rollups.len();
+            Json(TelemetryHistoryResponse {
+                granularity,
+                rollups,
+                total,
+            })
+            .into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /v0/management/telemetry/users - 按终端用户统计用量
+pub async fn management_telemetry_users(
+    State(state): State<AppState>,
+    Query(params): Query<UserUsageParams>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(serde_json::json!({ "success": false, "message": message })),
+        )
+            .into_response()
+    };
+
+    let Some(db) = &state.db else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Database not available".to_string(),
+        );
+    };
+
+    let query = RollupQuery {
+        start: params.start,
+        end: params.end,
+        provider: params.provider,
+        model: params.model,
+    };
+    let limit = params.limit.unwrap_or(100);
+    let result = match db.lock() {
+        Ok(conn) => TelemetryDao::query_usage_by_user(&conn, &query, limit),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    match result {
+        Ok(users) => {
+            let total = users.len();
+            Json(UserUsageResponse { users, total }).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /v0/management/reports/daily - 按需生成每日用量报告
+pub async fn management_daily_report(
+    State(state): State<AppState>,
+    Query(params): Query<DailyReportParams>,
+) -> impl IntoResponse {
+    use crate::services::usage_report_service;
+
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(serde_json::json!({ "success": false, "message": message })),
+        )
+            .into_response()
+    };
+
+    let date = match params.date.as_deref() {
+        None => chrono::Utc::now().date_naive(),
+        Some(d) => match chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(e) => return error(StatusCode::BAD_REQUEST, format!("Invalid date: {}", e)),
+        },
+    };
+    let markdown = match params.format.as_deref() {
+        None | Some("json") => false,
+        Some("markdown") | Some("md") => true,
+        Some(f) => return error(StatusCode::BAD_REQUEST, format!("Unknown format: {}", f)),
+    };
+
+    let Some(db) = &state.db else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Database not available".to_string(),
+        );
+    };
+    let top_models = state
+        .hot_reload_manager
+        .as_ref()
+        .map(|m| m.config().reports.top_models)
+        .unwrap_or_else(|| crate::config::ReportsConfig::default().top_models);
+
+    let result = match db.lock() {
+        Ok(conn) => usage_report_service::generate_daily_report(&conn, date, top_models),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+
+    if params.save {
+        if let Err(e) = usage_report_service::reports_dir()
+            .and_then(|dir| usage_report_service::save_report(&report, &dir))
+        {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e);
+        }
+    }
+
+    if markdown {
+        (
+            [(
+                axum::http::header::CONTENT_TYPE,
+                "text/markdown; charset=utf-8",
+            )],
+            report.to_markdown(),
+        )
+            .into_response()
+    } else {
+        Json(report).into_response()
+    }
+}
+
+/// GET /v0/management/credentials/import - 扫描本机 CLI 工具中可导入的 OAuth 凭证
+pub async fn management_detect_credentials(State(state): State<AppState>) -> impl IntoResponse {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match credential_import_service::detect_credentials(db) {
+        Ok(detected) => {
+            let total = detected.len();
+            Json(serde_json::json!({ "credentials": detected, "total": total })).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /v0/management/credentials/import - 批量导入扫描到的凭证（按指纹去重）
+pub async fn management_import_credentials(
+    State(state): State<AppState>,
+    body: Option<Json<ImportCredentialsRequest>>,
+) -> impl IntoResponse {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    let paths = body.and_then(|Json(body)| body.paths);
+    let detected = match credential_import_service::detect_credentials(db) {
+        Ok(detected) => detected,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "success": false, "message": e })),
+            )
+                .into_response()
+        }
+    };
+    let results = credential_import_service::import_detected(
+        db,
+        &state.pool_service,
+        detected,
+        paths.as_deref(),
+        crate::services::credential_file_service::copy_and_rename_credential_file,
+    );
+    let imported = results.iter().filter(|r| r.status == "imported").count();
+    Json(serde_json::json!({
+        "success": true,
+        "imported": imported,
+        "results": results,
+    }))
+    .into_response()
+}
+
+/// POST /v0/management/credentials/:uuid/duplicate - 复制凭证（请求体中的字段覆盖原凭证配置）
+pub async fn management_duplicate_credential(
+    State(state): State<AppState>,
+    Path(uuid): Path<String>,
+    body: Option<Json<DuplicateCredentialRequest>>,
+) -> impl IntoResponse {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    let overrides = body.map(|Json(body)| body).unwrap_or_default();
+    match state
+        .pool_service
+        .duplicate_credential(db, &uuid, overrides)
+    {
+        Ok(cred) => Json(serde_json::json!({
+            "success": true,
+            "credential_id": cred.uuid,
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /v0/management/credentials/bulk/disabled - 按 Provider 类型或标签批量启用 / 禁用凭证
+pub async fn management_bulk_set_disabled(
+    State(state): State<AppState>,
+    Json(request): Json<BulkSetDisabledRequest>,
+) -> impl IntoResponse {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    bulk_response(
+        state
+            .pool_service
+            .bulk_set_disabled(db, &request.filter, request.disabled),
+    )
+}
+
+/// POST /v0/management/credentials/bulk/delete-unhealthy - 批量删除不健康的凭证
+pub async fn management_bulk_delete_unhealthy(
+    State(state): State<AppState>,
+    body: Option<Json<BulkCredentialFilter>>,
+) -> impl IntoResponse {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    let filter = body.map(|Json(body)| body).unwrap_or_default();
+    bulk_response(state.pool_service.bulk_delete_unhealthy(db, &filter))
+}
+
+/// POST /v0/management/credentials/bulk/check - 批量重新检查凭证健康状态
+pub async fn management_bulk_check_health(
+    State(state): State<AppState>,
+    body: Option<Json<BulkCheckRequest>>,
+) -> impl IntoResponse {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    let request = body.map(|Json(body)| body).unwrap_or_default();
+    let concurrency = state
+        .pool_service
+        .health_check_concurrency(request.concurrency);
+    bulk_response(
+        state
+            .pool_service
+            .bulk_check_health(db, &request.filter, concurrency)
+            .await,
+    )
+}
+
+/// 批量操作响应（`results` 为每个凭证的结果）
+fn bulk_response<T: Serialize>(results: Result<Vec<T>, String>) -> axum::response::Response {
+    match results {
+        Ok(results) => Json(serde_json::json!({
+            "success": true,
+            "total": results.len(),
+            "results": results,
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /v0/management/oauth/:provider/login - 发起内置 OAuth 登录（gemini / qwen / kiro）
+///
+/// 返回需要在浏览器中打开的地址（Device Flow 另含用户码），授权完成后凭证自动加入凭证池
+pub async fn management_start_oauth_login(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    body: Option<Json<LoginOptions>>,
+) -> impl IntoResponse {
+    let provider: LoginProvider = match provider.parse() {
+        Ok(provider) => provider,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "success": false, "message": e })),
+            )
+                .into_response()
+        }
+    };
+    let Some(db) = state.db.clone() else {
+        return database_unavailable();
+    };
+    let options = body.map(|Json(options)| options).unwrap_or_default();
+
+    match state
+        .oauth_logins
+        .start(provider, options, db, state.pool_service.clone())
+        .await
+    {
+        Ok(session) => Json(session).into_response(),
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /v0/management/oauth/sessions/:id - 查询登录会话状态
+pub async fn management_get_oauth_login(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    oauth_session_response(state.oauth_logins.get(&id))
+}
+
+/// DELETE /v0/management/oauth/sessions/:id - 取消进行中的登录
+pub async fn management_cancel_oauth_login(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    oauth_session_response(state.oauth_logins.cancel(&id))
+}
+
+fn oauth_session_response(session: Option<LoginSession>) -> axum::response::Response {
+    match session {
+        Some(session) => Json(session).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "success": false, "message": "Login session not found" })),
+        )
+            .into_response(),
+    }
+}
+
+fn database_unavailable() -> axum::response::Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({ "success": false, "message": "Database not available" })),
+    )
+        .into_response()
+}
+
+/// GET /v0/management/amp/mappings - 查看生效的 Amp 模型映射（按匹配优先级）
+///
+/// 指定 `model`（及可选的 `provider`）时同时返回该模型的映射结果。
+pub async fn management_amp_mappings(
+    State(state): State<AppState>,
+    Query(params): Query<AmpMappingParams>,
+) -> impl IntoResponse {
+    let router = state.amp_router.read();
+    let mappings = router.effective_mappings();
+    let resolved = params.model.map(|model| {
+        let rule = router
+            .resolve_model_mapping(params.provider.as_deref(), &model)
+            .cloned();
+        AmpMappingResolution {
+            provider: params.provider.clone(),
+            mapped_to: rule
+                .as_ref()
+                .map(|r| r.to.clone())
+                .unwrap_or_else(|| model.clone()),
+            model,
+            rule,
+        }
+    });
+
+    let total = mappings.len();
+    Json(AmpMappingsResponse {
+        mappings,
+        total,
+        resolved,
+    })
+}
+
+/// GET /v0/management/websocket - 获取 WebSocket 连接统计和各连接详情
+pub async fn management_websocket_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.ws_manager.report())
+}
+
+/// GET /v0/management/logs/filter - 获取当前 tracing 过滤指令
+pub async fn management_get_log_filter() -> impl IntoResponse {
+    match crate::logger::tracing_filter() {
+        Some(directives) => Json(TracingFilterBody { directives }).into_response(),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "success": false,
+                "message": "Tracing subscriber not initialized"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// PUT /v0/management/logs/filter - 运行时替换 tracing 过滤指令
+pub async fn management_set_log_filter(Json(body): Json<TracingFilterBody>) -> impl IntoResponse {
+    match crate::logger::set_tracing_filter(&body.directives) {
+        Ok(directives) => Json(TracingFilterBody { directives }).into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /v0/management/logs/stream - 以 SSE 实时推送新日志
+///
+/// 每条日志作为一个 `log` 事件发送（data 为 JSON），订阅者落后过多时发送 `lagged` 事件。
+pub async fn management_logs_stream(
+    State(state): State<AppState>,
+    Query(params): Query<LogStreamParams>,
+) -> impl IntoResponse {
+    use axum::response::sse::{Event, KeepAlive, Sse};
+
+    let filter = LogFilter {
+        level: params.level,
+        keyword: params.keyword,
+    };
+    let (backlog, mut receiver) = {
+        let logs = state.logs.read().await;
+        let backlog: Vec<_> = logs
+            .get_logs()
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect();
+        let skip = backlog.len().saturating_sub(params.backlog);
+        (
+            backlog.into_iter().skip(skip).collect::<Vec<_>>(),
+            logs.subscribe(),
+        )
+    };
+
+    let stream = async_stream::stream! {
+        for entry in backlog {
+            yield Event::default().event("log").json_data(&entry);
+        }
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => {
+                    if filter.matches(&entry) {
+                        yield Event::default().event("log").json_data(&entry);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    yield Ok(Event::default().event("lagged").data(n.to_string()));
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// GET /v0/management/telemetry/latency - 查询延迟分位数（P50/P95/P99）
+pub async fn management_latency_percentiles(
+    State(state): State<AppState>,
+    Query(params): Query<LatencyQueryParams>,
+) -> impl IntoResponse {
+    let window_minutes = params
+        .window_minutes
+        .filter(|m| *m > 0)
+        .unwrap_or(DEFAULT_LATENCY_WINDOW_MINUTES);
+
+    let provider = match params.provider.as_deref() {
+        None => None,
+        Some(p) => match p.parse::<crate::ProviderType>() {
+            Ok(p) => Some(p),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "success": false, "message": e })),
+                )
+                    .into_response()
+            }
+        },
+    };
+
+    let latencies: Vec<LatencyStats> = state
+        .processor
+        .stats
+        .read()
+        .latency_percentiles(chrono::Duration::minutes(window_minutes))
+        .into_iter()
+        .filter(|s| provider.is_none_or(|p| s.provider == p))
+        .filter(|s| params.model.as_ref().is_none_or(|m| &s.model == m))
+        .filter(|s| {
+            params
+                .credential_id
+                .as_ref()
+                .is_none_or(|id| s.credential_id.as_ref() == Some(id))
+        })
+        .collect();
+
+    let total = latencies.len();
+    Json(LatencyPercentilesResponse {
+        window_minutes,
+        latencies,
+        total,
+    })
+    .into_response()
+}
+
+/// GET /v0/management/usage - 查询所有凭证的剩余额度（默认使用缓存）
+pub async fn management_list_quotas(
+    State(state): State<AppState>,
+    Query(params): Query<QuotaQueryParams>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(serde_json::json!({ "success": false, "message": message })),
+        )
+            .into_response()
+    };
+
+    let Some(db) = &state.db else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Database not available".to_string(),
+        );
+    };
+
+    match crate::usage::get_all_quotas(&state.pool_service, &state.token_cache, db, params.refresh)
+        .await
+    {
+        Ok(quotas) => {
+            let total = quotas.len();
+            Json(QuotaListResponse { quotas, total }).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// GET /v0/management/usage/:uuid - 查询单个凭证的剩余额度
+pub async fn management_get_quota(
+    State(state): State<AppState>,
+    Path(uuid): Path<String>,
+    Query(params): Query<QuotaQueryParams>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(serde_json::json!({ "success": false, "message": message })),
+        )
+            .into_response()
+    };
+
+    let Some(db) = &state.db else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Database not available".to_string(),
+        );
+    };
+
+    let credential = match db.lock() {
+        Ok(conn) => ProviderPoolDao::get_by_uuid(&conn, &uuid),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let credential = match credential {
+        Ok(Some(credential)) => credential,
+        Ok(None) => {
+            return error(
+                StatusCode::NOT_FOUND,
+                format!("Credential not found: {}", uuid),
+            )
+        }
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    if !crate::usage::supports_quota(&credential.credential) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Quota lookup is not supported for {} credentials",
+                credential.provider_type
+            ),
+        );
+    }
+
+    let quota = crate::usage::get_quota(
+        &state.pool_service,
+        &state.token_cache,
+        db,
+        &credential,
+        params.refresh,
+    )
+    .await;
+    Json(quota).into_response()
+}
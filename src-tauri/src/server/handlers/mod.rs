@@ -3,17 +3,23 @@
 //! 将 server 中的各类处理器拆分到独立文件
 
 pub mod api;
+pub mod audio_handler;
 pub mod credentials_api;
+pub mod files_api;
 pub mod image_handler;
 pub mod kiro_credential;
 pub mod management;
+pub mod models_api;
 pub mod provider_calls;
 pub mod websocket;
 
 pub use api::*;
+pub use audio_handler::*;
 pub use credentials_api::*;
+pub use files_api::*;
 pub use image_handler::*;
 pub use kiro_credential::*;
 pub use management::*;
+pub use models_api::*;
 pub use provider_calls::*;
 pub use websocket::*;
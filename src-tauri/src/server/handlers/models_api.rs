@@ -0,0 +1,179 @@
+//! 模型列表 API 处理器
+//!
+//! `/v1/models` 返回凭证池聚合后的模型列表，
+//! 凭证池为空时回退到内置的静态模型列表。
+//! 请求携带 `anthropic-version` 请求头（Claude SDK）时，`/v1/models` 和 `/v1/models/{id}`
+//! 返回 Anthropic 格式。
+//! `/v1/capabilities` 返回各凭证支持的功能，供客户端按能力调整请求。
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::server::handlers::verify_api_key;
+use crate::server::AppState;
+use crate::server_utils::{models, static_model, STATIC_MODELS};
+use crate::services::model_catalog_service::{
+    anthropic_models, AnthropicModel, AnthropicModelPage, AnthropicPageParams, CatalogModel,
+    ModelCatalogService,
+};
+
+/// 是否返回 Anthropic 格式
+fn wants_anthropic_format(headers: &HeaderMap) -> bool {
+    headers.contains_key("anthropic-version")
+}
+
+/// 凭证池聚合的模型列表（没有数据库或凭证池为空时为 None）
+async fn catalog_models(state: &AppState) -> Option<Vec<CatalogModel>> {
+    let db = state.db.as_ref()?;
+    let amp_mappings = state.amp_router.read().model_mappings().to_vec();
+    let catalog = state.model_catalog.list_models(db, &amp_mappings).await;
+    (!catalog.is_empty()).then_some(catalog)
+}
+
+/// Anthropic 格式的模型列表（凭证池为空时使用静态模型列表）
+fn anthropic_catalog(state: &AppState, catalog: Option<&[CatalogModel]>) -> Vec<AnthropicModel> {
+    let registry = state
+        .db
+        .as_ref()
+        .map(ModelCatalogService::registry_models)
+        .unwrap_or_default();
+    let now = chrono::Utc::now().timestamp();
+    match catalog {
+        Some(catalog) => {
+            let ids: Vec<(&str, Option<&str>)> = catalog
+                .iter()
+                .map(|m| (m.id.as_str(), m.alias_of.as_deref()))
+                .collect();
+            anthropic_models(&ids, &registry, now)
+        }
+        None => {
+            let ids: Vec<(&str, Option<&str>)> =
+                STATIC_MODELS.iter().map(|(id, _)| (*id, None)).collect();
+            anthropic_models(&ids, &registry, now)
+        }
+    }
+}
+
+/// GET /v1/models
+pub async fn list_models(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AnthropicPageParams>,
+) -> Response {
+    let catalog = catalog_models(&state).await;
+
+    if wants_anthropic_format(&headers) {
+        let models = anthropic_catalog(&state, catalog.as_deref());
+        return Json(AnthropicModelPage::paginate(&models, &params)).into_response();
+    }
+
+    let Some(catalog) = catalog else {
+        return models().await.into_response();
+    };
+
+    Json(serde_json::json!({
+        "object": "list",
+        "data": catalog,
+    }))
+    .into_response()
+}
+
+/// GET /v1/models/{id}
+pub async fn get_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let catalog = catalog_models(&state).await;
+
+    if wants_anthropic_format(&headers) {
+        return match anthropic_catalog(&state, catalog.as_deref())
+            .into_iter()
+            .find(|m| m.id == id)
+        {
+            Some(model) => Json(model).into_response(),
+            None => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "type": "error",
+                    "error": {
+                        "type": "not_found_error",
+                        "message": format!("model: {}", id)
+                    }
+                })),
+            )
+                .into_response(),
+        };
+    }
+
+    let model = match &catalog {
+        Some(catalog) => catalog
+            .iter()
+            .find(|m| m.id == id)
+            .and_then(|m| serde_json::to_value(m).ok()),
+        None => STATIC_MODELS
+            .iter()
+            .find(|(model_id, _)| *model_id == id)
+            .map(|(model_id, owned_by)| static_model(model_id, owned_by)),
+    };
+    match model {
+        Some(model) => Json(model).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": {
+                    "message": format!("The model '{}' does not exist", id),
+                    "type": "invalid_request_error",
+                    "code": "model_not_found"
+                }
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /v1/capabilities
+///
+/// 返回内容包含凭证 UUID 和名称（可用作路由选择器），需要 API Key。
+pub async fn list_capabilities(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    let capabilities = match &state.db {
+        Some(db) => state.model_catalog.list_capabilities(db).await,
+        None => Vec::new(),
+    };
+
+    Json(serde_json::json!({
+        "object": "list",
+        "data": capabilities,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_capabilities_requires_api_key() {
+        let state = AppState::for_test("secret");
+
+        let response = list_capabilities(State(state.clone()), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer wrong".parse().unwrap());
+        let response = list_capabilities(State(state.clone()), headers).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        let response = list_capabilities(State(state), headers).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -51,30 +51,91 @@ use futures::StreamExt;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
-    convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
+    antigravity_text_with_citations, convert_antigravity_to_openai_response,
+    convert_openai_to_antigravity_with_context,
 };
 use crate::flow_monitor::models::{FlowError, FlowErrorType};
 use crate::flow_monitor::stream_rebuilder::StreamFormat;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::processor::RequestContext;
 use crate::providers::{
-    AntigravityApiError, AntigravityProvider, ClaudeCustomProvider, CodexProvider, IFlowProvider,
-    KiroProvider, OpenAICustomProvider, VertexProvider,
+    AntigravityProvider, ClaudeCustomProvider, CodexProvider, IFlowProvider, KiroProvider,
+    OpenAICustomProvider, VertexProvider,
 };
+use crate::resilience::RetryHint;
 use crate::server::AppState;
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response,
-    build_error_response_with_status, parse_cw_response, safe_truncate, CWParsedResponse,
+    build_error_response_with_status, merge_fanout_completions, parse_cw_response, safe_truncate,
+    CWParsedResponse,
 };
 use crate::session::store_thought_signature;
-use crate::stream::{PipelineConfig, StreamPipeline};
+use crate::stream::{PipelineConfig, StopReason, StreamPipeline};
 use crate::streaming::traits::StreamingProvider;
 use crate::streaming::{
     StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat, StreamManager,
     StreamResponse,
 };
 
+/// 上游返回 429 时按退避提示重试同一凭证
+///
+/// 退避时间由请求处理器的重试器结合上游给出的等待时间（Retry-After / retryDelay）
+/// 和凭证的限流历史计算；重试次数耗尽或需要等待的时间超过最大重试延迟时，
+/// 返回最后一次 429 响应，由调用方按原逻辑处理。
+async fn send_with_rate_limit_retry<F, Fut>(
+    state: &AppState,
+    credential_uuid: &str,
+    mut send: F,
+) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<
+        Output = Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>>,
+    >,
+{
+    let retrier = &state.processor.retrier;
+    let mut attempt = 0;
+    loop {
+        let resp = send().await?;
+        let status = resp.status();
+        if status != StatusCode::TOO_MANY_REQUESTS {
+            if status.is_success() {
+                retrier.record_success(credential_uuid);
+            }
+            return Ok(resp);
+        }
+
+        // 429 响应体较小，读取后提取 retryDelay，不再重试时按原样重建响应
+        let headers = resp.headers().clone();
+        let body = resp.bytes().await.unwrap_or_default();
+        let json = serde_json::from_slice::<serde_json::Value>(&body).ok();
+        let retry_after = RetryHint::from_response(Some(&headers), json.as_ref()).retry_after;
+        retrier.record_rate_limit(credential_uuid, retry_after);
+        let hint = retrier.hint_for(Some(credential_uuid), retry_after);
+        match retrier.adaptive_backoff_delay(attempt, &hint) {
+            Some(delay) if attempt < retrier.config().max_retries => {
+                tracing::warn!(
+                    "[RETRY] 凭证 {} 被限流，{}ms 后重试 ({}/{})",
+                    safe_truncate(credential_uuid, 8),
+                    delay.as_millis(),
+                    attempt + 1,
+                    retrier.config().max_retries
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            _ => {
+                let mut rebuilt = axum::http::Response::new(body);
+                *rebuilt.status_mut() = status;
+                *rebuilt.headers_mut() = headers;
+                return Ok(reqwest::Response::from(rebuilt));
+            }
+        }
+    }
+}
+
 /// 根据凭证调用 Provider (Anthropic 格式)
 ///
 /// # 参数
@@ -88,6 +149,13 @@ pub async fn call_provider_anthropic(
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
 ) -> Response {
+    // 凭证固定了模型时改写请求模型（在别名解析之后）
+    let pinned = pin_credential_model(state, credential, request, &request.model, |r, m| {
+        r.model = m
+    })
+    .await;
+    let request = pinned.as_ref();
+
     // 如果是流式请求且有 flow_id，设置流式状态
     if request.stream {
         if let Some(fid) = flow_id {
@@ -132,20 +200,22 @@ pub async fn call_provider_anthropic(
                 Err(e) => {
                     tracing::warn!("[POOL] Token cache miss, loading from source: {}", e);
                     // 回退到从源文件加载
-                    let mut kiro = KiroProvider::new();
-                    if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                        // 记录凭证加载失败
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&format!("Failed to load credentials: {}", e)),
-                        );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                        )
-                            .into_response();
-                    }
+                    let mut kiro = match KiroProvider::pooled(creds_file_path).await {
+                        Ok(kiro) => kiro,
+                        Err(e) => {
+                            // 记录凭证加载失败
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&format!("Failed to load credentials: {}", e)),
+                            );
+                            return (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
+                            )
+                                .into_response();
+                        }
+                    };
                     if let Err(e) = kiro.refresh_token().await {
                         // 记录 Token 刷新失败
                         let _ = state.pool_service.mark_unhealthy(
@@ -159,18 +229,17 @@ pub async fn call_provider_anthropic(
                         )
                             .into_response();
                     }
-                    kiro.credentials.access_token.unwrap_or_default()
+                    kiro.access_token().unwrap_or_default()
                 }
             };
-            // 使用获取到的 token 创建 KiroProvider
-            let mut kiro = KiroProvider::new();
-            // 从源文件加载其他配置（region, profile_arn 等）
-            // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
-            let _ = kiro.load_credentials_from_path(creds_file_path).await;
+            // 复用该凭证文件的共享 Provider（region、profile_arn 等来自凭证文件）
+            let kiro = KiroProvider::pooled(creds_file_path)
+                .await
+                .unwrap_or_default();
             // 使用缓存的 token 覆盖文件中的 token（缓存的 token 更新）
-            kiro.credentials.access_token = Some(token);
-            let openai_request = convert_anthropic_to_openai(request);
-            let resp = match kiro.call_api(&openai_request).await {
+            kiro.set_access_token(Some(token));
+            // 直接转换 Anthropic → CodeWhisperer，保留 tool_result 的完整信息
+            let resp = match kiro.call_api_anthropic(request).await {
                 Ok(r) => r,
                 Err(e) => {
                     // 记录 API 调用失败
@@ -190,8 +259,7 @@ pub async fn call_provider_anthropic(
             if status.is_success() {
                 match resp.bytes().await {
                     Ok(bytes) => {
-                        let body = String::from_utf8_lossy(&bytes).to_string();
-                        let parsed = parse_cw_response(&body);
+                        let parsed = parse_cw_response(&bytes);
                         // 记录成功
                         let _ = state.pool_service.mark_healthy(
                             db,
@@ -243,14 +311,13 @@ pub async fn call_provider_anthropic(
                     }
                 };
                 // 使用新 token 重试
-                kiro.credentials.access_token = Some(new_token);
-                match kiro.call_api(&openai_request).await {
+                kiro.set_access_token(Some(new_token));
+                match kiro.call_api_anthropic(request).await {
                     Ok(retry_resp) => {
                         if retry_resp.status().is_success() {
                             match retry_resp.bytes().await {
                                 Ok(bytes) => {
-                                    let body = String::from_utf8_lossy(&bytes).to_string();
-                                    let parsed = parse_cw_response(&body);
+                                    let parsed = parse_cw_response(&bytes);
                                     // 记录重试成功
                                     let _ = state.pool_service.mark_healthy(
                                         db,
@@ -421,15 +488,17 @@ pub async fn call_provider_anthropic(
                 .await
             {
                 Ok(resp) => {
-                    // 转换为 OpenAI 格式，再构建 Anthropic 响应
-                    let content = resp["candidates"][0]["content"]["parts"][0]["text"]
-                        .as_str()
-                        .unwrap_or("");
+                    // 转换为 OpenAI 格式，再构建 Anthropic 响应（联网搜索引用附加在文本末尾）
+                    let content = antigravity_text_with_citations(&resp);
+                    let (stop_reason, stop_sequence) =
+                        openai_stop_reason(&convert_antigravity_to_openai_response(&resp, ""));
                     let parsed = CWParsedResponse {
-                        content: content.to_string(),
+                        content,
                         tool_calls: Vec::new(),
                         usage_credits: 0.0,
                         context_usage_percentage: 0.0,
+                        stop_reason,
+                        stop_sequence,
                     };
                     // 记录成功
                     if let Some(db) = &state.db {
@@ -461,10 +530,24 @@ pub async fn call_provider_anthropic(
                 }
             }
         }
-        CredentialData::OpenAIKey { api_key, base_url } => {
-            let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+        CredentialData::OpenAIKey { api_key, base_url }
+        | CredentialData::OpenRouterKey { api_key, base_url }
+        | CredentialData::MistralKey { api_key, base_url }
+        | CredentialData::DeepSeekKey { api_key, base_url } => {
+            let openai = OpenAICustomProvider::with_config(
+                api_key.clone(),
+                base_url
+                    .clone()
+                    .or_else(|| credential.credential.default_base_url()),
+            );
             let openai_request = convert_anthropic_to_openai(request);
-            match openai.call_api(&openai_request).await {
+            let openai_request =
+                adapt_openai_compatible_request(&credential.credential, &openai_request);
+            match send_with_rate_limit_retry(state, &credential.uuid, || {
+                openai.call_api(&openai_request)
+            })
+            .await
+            {
                 Ok(resp) => {
                     let status = resp.status();
                     if status.is_success() {
@@ -476,14 +559,25 @@ pub async fn call_provider_anthropic(
                                 if let Ok(openai_resp) =
                                     serde_json::from_str::<serde_json::Value>(&body)
                                 {
-                                    let content = openai_resp["choices"][0]["message"]["content"]
-                                        .as_str()
-                                        .unwrap_or("");
+                                    let message = &openai_resp["choices"][0]["message"];
+                                    let content = message["content"].as_str().unwrap_or("");
+                                    // DeepSeek 等推理模型通过 reasoning_content 返回思维链，
+                                    // 与 Antigravity 一致用 <thinking> 标签包裹并放在前面
+                                    let content = match message["reasoning_content"].as_str() {
+                                        Some(reasoning) if !reasoning.is_empty() => {
+                                            format!("<thinking>{}</thinking>\n\n{}", reasoning, content)
+                                        }
+                                        _ => content.to_string(),
+                                    };
+                                    let (stop_reason, stop_sequence) =
+                                        openai_stop_reason(&openai_resp);
                                     let parsed = CWParsedResponse {
-                                        content: content.to_string(),
+                                        content,
                                         tool_calls: Vec::new(),
                                         usage_credits: 0.0,
                                         context_usage_percentage: 0.0,
+                                        stop_reason,
+                                        stop_sequence,
                                     };
                                     // 记录成功
                                     if let Some(db) = &state.db {
@@ -594,7 +688,9 @@ pub async fn call_provider_anthropic(
                     &request_json.chars().take(500).collect::<String>()
                 ),
             );
-            match claude.call_api(request).await {
+            match send_with_rate_limit_retry(state, &credential.uuid, || claude.call_api(request))
+                .await
+            {
                 Ok(resp) => {
                     let status = resp.status();
                     // 打印响应状态
@@ -624,7 +720,7 @@ pub async fn call_provider_anthropic(
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
                         // 透传流式响应，保持 SSE 格式
-                        let stream = resp.bytes_stream();
+                        let stream = passthrough_sse_stream(resp, StreamingFormat::AnthropicSse);
                         return Response::builder()
                             .status(StatusCode::OK)
                             .header(header::CONTENT_TYPE, "text/event-stream")
@@ -811,7 +907,9 @@ pub async fn call_provider_anthropic(
                     request.stream
                 ),
             );
-            match claude.call_api(request).await {
+            match send_with_rate_limit_retry(state, &credential.uuid, || claude.call_api(request))
+                .await
+            {
                 Ok(resp) => {
                     let status = resp.status();
                     state.logs.write().await.add(
@@ -838,7 +936,7 @@ pub async fn call_provider_anthropic(
                             );
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
-                        let stream = resp.bytes_stream();
+                        let stream = passthrough_sse_stream(resp, StreamingFormat::AnthropicSse);
                         return Response::builder()
                             .status(StatusCode::OK)
                             .header(header::CONTENT_TYPE, "text/event-stream")
@@ -944,11 +1042,21 @@ pub async fn call_provider_openai(
 ) -> Response {
     let _start_time = std::time::Instant::now();
 
+    // 凭证固定了模型时改写请求模型（在别名解析之后）
+    let pinned = pin_credential_model(state, credential, request, &request.model, |r, m| {
+        r.model = m
+    })
+    .await;
+    let request = pinned.as_ref();
+
     // 调试：打印凭证类型
     let cred_type = match &credential.credential {
         CredentialData::KiroOAuth { .. } => "KiroOAuth",
         CredentialData::ClaudeKey { .. } => "ClaudeKey",
         CredentialData::OpenAIKey { .. } => "OpenAIKey",
+        CredentialData::OpenRouterKey { .. } => "OpenRouterKey",
+        CredentialData::MistralKey { .. } => "MistralKey",
+        CredentialData::DeepSeekKey { .. } => "DeepSeekKey",
         CredentialData::GeminiOAuth { .. } => "GeminiOAuth",
         CredentialData::GeminiApiKey { .. } => "GeminiApiKey",
         CredentialData::VertexKey { .. } => "VertexKey",
@@ -988,19 +1096,21 @@ pub async fn call_provider_openai(
                 Err(e) => {
                     tracing::warn!("[POOL] Token cache miss, loading from source: {}", e);
                     // 降级：从源文件加载并刷新
-                    let mut kiro = KiroProvider::new();
-                    if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&format!("Failed to load credentials: {}", e)),
-                        );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                        )
-                            .into_response();
-                    }
+                    let mut kiro = match KiroProvider::pooled(creds_file_path).await {
+                        Ok(kiro) => kiro,
+                        Err(e) => {
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&format!("Failed to load credentials: {}", e)),
+                            );
+                            return (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
+                            )
+                                .into_response();
+                        }
+                    };
                     if let Err(e) = kiro.refresh_token().await {
                         let _ = state.pool_service.mark_unhealthy(
                             db,
@@ -1013,17 +1123,16 @@ pub async fn call_provider_openai(
                         )
                             .into_response();
                     }
-                    kiro.credentials.access_token.unwrap_or_default()
+                    kiro.access_token().unwrap_or_default()
                 }
             };
 
-            // 使用获取到的 token 创建 KiroProvider
-            let mut kiro = KiroProvider::new();
-            // 从源文件加载其他配置（region, profile_arn 等）
-            // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
-            let _ = kiro.load_credentials_from_path(creds_file_path).await;
+            // 复用该凭证文件的共享 Provider（region、profile_arn 等来自凭证文件）
+            let kiro = KiroProvider::pooled(creds_file_path)
+                .await
+                .unwrap_or_default();
             // 使用缓存的 token 覆盖文件中的 token（缓存的 token 更新）
-            kiro.credentials.access_token = Some(token);
+            kiro.set_access_token(Some(token));
 
             tracing::info!("[CALL_PROVIDER_OPENAI] request.stream = {}, model = {}", request.stream, request.model);
 
@@ -1108,7 +1217,7 @@ pub async fn call_provider_openai(
                         let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
                             match result {
                                 Ok(event) => Ok(axum::body::Bytes::from(event)),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(StreamingFormat::OpenAiSse))),
                             }
                         });
 
@@ -1155,7 +1264,7 @@ pub async fn call_provider_openai(
                             let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
-                        match resp.text().await {
+                        match resp.bytes().await {
                             Ok(body) => {
                                 let parsed = parse_cw_response(&body);
                                 let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1180,8 +1289,8 @@ pub async fn call_provider_openai(
                                         "content": parsed.content
                                     })
                                 };
-                                Json(serde_json::json!({
-                                    "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                                let response = Json(serde_json::json!({
+                                    "id": crate::middleware::response_id("chatcmpl-"),
                                     "object": "chat.completion",
                                     "created": std::time::SystemTime::now()
                                         .duration_since(std::time::UNIX_EPOCH)
@@ -1199,7 +1308,8 @@ pub async fn call_provider_openai(
                                         "total_tokens": 0
                                     }
                                 }))
-                                .into_response()
+                                .into_response();
+                                parsed.with_context_usage_header(response)
                             }
                             Err(e) => (
                                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -1387,7 +1497,7 @@ pub async fn call_provider_openai(
 
                             // 将非流式响应转换为流式 SSE 格式
                             let model = request.model.clone();
-                            let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4());
+                            let chunk_id = crate::middleware::response_id("chatcmpl-");
                             let created = chrono::Utc::now().timestamp();
 
                             // 提取内容
@@ -1586,8 +1696,19 @@ pub async fn call_provider_openai(
                 }
             }
         }
-        CredentialData::OpenAIKey { api_key, base_url } => {
-            let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+        CredentialData::OpenAIKey { api_key, base_url }
+        | CredentialData::OpenRouterKey { api_key, base_url }
+        | CredentialData::MistralKey { api_key, base_url }
+        | CredentialData::DeepSeekKey { api_key, base_url } => {
+            let openai = OpenAICustomProvider::with_config(
+                api_key.clone(),
+                base_url
+                    .clone()
+                    .or_else(|| credential.credential.default_base_url()),
+            );
+
+            let request = adapt_openai_compatible_request(&credential.credential, request);
+            let request: &ChatCompletionRequest = &request;
 
             tracing::info!("[OPENAI_KEY] request.stream = {}, model = {}", request.stream, request.model);
 
@@ -1602,7 +1723,7 @@ pub async fn call_provider_openai(
                         let body_stream = stream_response.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
                             match result {
                                 Ok(bytes) => Ok(bytes),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(StreamingFormat::OpenAiSse))),
                             }
                         });
 
@@ -1635,7 +1756,9 @@ pub async fn call_provider_openai(
             }
 
             // 非流式请求处理
-            match openai.call_api(request).await {
+            match send_with_rate_limit_retry(state, &credential.uuid, || openai.call_api(request))
+                .await
+            {
                 Ok(resp) => {
                     if resp.status().is_success() {
                         match resp.text().await {
@@ -1741,7 +1864,7 @@ pub async fn call_provider_openai(
                         let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
                             match result {
                                 Ok(event) => Ok(axum::body::Bytes::from(event)),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(StreamingFormat::OpenAiSse))),
                             }
                         });
 
@@ -1832,7 +1955,11 @@ pub async fn call_provider_openai(
                         request.stream
                     ),
                 );
-                match openai.call_api(request).await {
+                match send_with_rate_limit_retry(state, &credential.uuid, || {
+                    openai.call_api(request)
+                })
+                .await
+                {
                     Ok(resp) => {
                         let status = resp.status();
                         state.logs.write().await.add(
@@ -1858,7 +1985,7 @@ pub async fn call_provider_openai(
                                 );
                                 let _ = state.pool_service.record_usage(db, &credential.uuid);
                             }
-                            let stream = resp.bytes_stream();
+                            let stream = passthrough_sse_stream(resp, StreamingFormat::OpenAiSse);
                             return Response::builder()
                                 .status(StatusCode::OK)
                                 .header(header::CONTENT_TYPE, "text/event-stream")
@@ -2283,6 +2410,490 @@ pub async fn call_provider_openai(
     }
 }
 
+/// 扇出调用 Provider (OpenAI 格式)
+///
+/// 上游不支持 `n > 1` 时并发发送 `n` 个单候选的非流式请求，合并为一个 `chat.completion`。
+/// 任一请求失败时返回该失败响应；流式请求由调用方将合并结果转换为 SSE。
+pub async fn call_provider_openai_fanout(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+) -> Response {
+    let n = request.choice_count();
+    let mut single = request.clone();
+    single.n = None;
+    single.stream = false;
+    single.stream_options = None;
+
+    // 每个候选都会重复计费 prompt token
+    tracing::warn!(
+        "[FANOUT] credential_uuid={} model={} n={} 上游不支持多候选，发送 {} 个请求",
+        &credential.uuid[..8],
+        request.model,
+        n,
+        n
+    );
+    state.logs.write().await.add(
+        "warn",
+        &format!(
+            "[FANOUT] Provider {} does not support n > 1, sending {} upstream requests for model {} (prompt tokens billed {} times)",
+            credential.provider_type, n, request.model, n
+        ),
+    );
+
+    let responses = futures::future::join_all(
+        (0..n).map(|_| call_provider_openai(state, credential, &single, flow_id)),
+    )
+    .await;
+
+    let mut completions = Vec::with_capacity(responses.len());
+    for response in responses {
+        if !response.status().is_success() {
+            return response;
+        }
+        let completion = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+            Ok(bytes) => serde_json::from_slice::<serde_json::Value>(&bytes).ok(),
+            Err(_) => None,
+        };
+        match completion {
+            Some(completion) if completion["choices"].is_array() => completions.push(completion),
+            _ => {
+                return build_error_response_with_status(
+                    502,
+                    "Invalid upstream response while merging choices",
+                )
+            }
+        }
+    }
+
+    let mut response = Json(merge_fanout_completions(completions)).into_response();
+    response
+        .headers_mut()
+        .insert(FANOUT_HEADER, header::HeaderValue::from(n));
+    response
+}
+
+/// 按凭证的固定模型改写请求
+///
+/// 凭证未固定模型、或固定模型与请求模型相同时借用原请求。
+async fn pin_credential_model<'a, T: Clone>(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &'a T,
+    requested: &str,
+    set_model: impl FnOnce(&mut T, String),
+) -> std::borrow::Cow<'a, T> {
+    let Some(pinned) = credential.pinned_model_for(requested) else {
+        return std::borrow::Cow::Borrowed(request);
+    };
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[PIN] credential_uuid={} model={} -> pinned={}",
+            &credential.uuid[..8.min(credential.uuid.len())],
+            requested,
+            pinned
+        ),
+    );
+    let mut pinned_request = request.clone();
+    set_model(&mut pinned_request, pinned.to_string());
+    std::borrow::Cow::Owned(pinned_request)
+}
+
+/// 按 Provider 适配 OpenAI 兼容请求
+///
+/// Mistral / DeepSeek 虽然兼容 OpenAI 协议，但对部分字段有额外限制，
+/// 发送前在副本上修正；其他凭证直接使用原请求。
+fn adapt_openai_compatible_request<'a>(
+    credential: &CredentialData,
+    request: &'a ChatCompletionRequest,
+) -> std::borrow::Cow<'a, ChatCompletionRequest> {
+    match credential {
+        CredentialData::MistralKey { .. } => {
+            let mut adapted = request.clone();
+            crate::providers::mistral::adapt_request(&mut adapted);
+            std::borrow::Cow::Owned(adapted)
+        }
+        CredentialData::DeepSeekKey { .. } => {
+            let mut adapted = request.clone();
+            crate::providers::deepseek::adapt_request(&mut adapted);
+            std::borrow::Cow::Owned(adapted)
+        }
+        _ => std::borrow::Cow::Borrowed(request),
+    }
+}
+
+// ============================================================================
+// 原始请求体透传
+// ============================================================================
+
+/// 透传请求的协议格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassthroughFormat {
+    /// Anthropic Messages（`/v1/messages`）
+    Anthropic,
+    /// OpenAI Chat Completions（`/v1/chat/completions`）
+    OpenAI,
+}
+
+/// 透传请求
+pub struct PassthroughRequest<'a> {
+    pub format: PassthroughFormat,
+    /// 客户端发送的原始请求体
+    pub raw_body: &'a axum::body::Bytes,
+    pub model: &'a str,
+    pub stream: bool,
+}
+
+/// 凭证是否支持以指定格式透传
+///
+/// 仅当上游格式与请求格式一致、且无需按 Provider 改写请求时支持。
+pub fn supports_passthrough(credential: &CredentialData, format: PassthroughFormat) -> bool {
+    matches!(
+        (credential, format),
+        (
+            CredentialData::ClaudeKey { .. },
+            PassthroughFormat::Anthropic
+        ) | (CredentialData::OpenAIKey { .. }, PassthroughFormat::OpenAI)
+    )
+}
+
+/// 扇出生成多个候选时的上游请求数上限（每个请求单独计费）
+pub const MAX_FANOUT_CHOICES: u32 = 8;
+
+/// 扇出响应头，值为实际发送的上游请求数
+pub const FANOUT_HEADER: &str = "x-proxycast-fanout";
+
+/// 上游是否原生支持 `n > 1`
+///
+/// 不支持的凭证由代理按候选数量并发发送单候选请求（见 `call_provider_openai_fanout`）。
+pub fn supports_native_choices(credential: &CredentialData) -> bool {
+    matches!(credential, CredentialData::OpenAIKey { .. })
+}
+
+/// 上游是否支持 `logprobs` / `top_logprobs`（随 OpenAI 格式请求原样发送）
+pub fn supports_logprobs(credential: &CredentialData) -> bool {
+    matches!(
+        credential,
+        CredentialData::OpenAIKey { .. }
+            | CredentialData::OpenRouterKey { .. }
+            | CredentialData::DeepSeekKey { .. }
+    )
+}
+
+/// Kiro 可处理的服务端工具
+pub const KIRO_SERVER_TOOLS: &[&str] = &["web_search"];
+
+/// Antigravity 可处理的服务端工具（映射为 Gemini 内置工具）
+const ANTIGRAVITY_SERVER_TOOLS: &[&str] = &["web_search", "code_execution"];
+
+/// 凭证可处理的 Anthropic 服务端工具
+///
+/// Claude 凭证原样透传所有服务端工具，返回 None；其他凭证返回可转换的工具类型（不含版本后缀）。
+pub fn supported_server_tools(
+    credential: &CredentialData,
+    model: &str,
+) -> Option<&'static [&'static str]> {
+    match credential {
+        CredentialData::ClaudeKey { .. }
+        | CredentialData::AnthropicKey { .. }
+        | CredentialData::ClaudeOAuth { .. } => None,
+        CredentialData::KiroOAuth { .. } => Some(KIRO_SERVER_TOOLS),
+        CredentialData::AntigravityOAuth { .. } if !model.to_lowercase().contains("claude") => {
+            Some(ANTIGRAVITY_SERVER_TOOLS)
+        }
+        _ => Some(&[]),
+    }
+}
+
+/// 请求中不在支持范围内的服务端工具类型
+pub fn unsupported_server_tools(
+    request: &AnthropicMessagesRequest,
+    supported: Option<&[&str]>,
+) -> Vec<String> {
+    let Some(supported) = supported else {
+        return Vec::new();
+    };
+    request
+        .server_tools()
+        .into_iter()
+        .filter(|t| {
+            t.server_tool_family()
+                .is_some_and(|family| !supported.contains(&family))
+        })
+        .filter_map(|t| t.tool_type.clone())
+        .collect()
+}
+
+/// 透传调用 Provider
+///
+/// 请求体原样发送到上游，响应体（含流式 SSE）原样返回，跳过 serde 的反序列化 / 序列化。
+/// 凭证不支持透传时返回 None，调用方应回退到 `call_provider_anthropic` / `call_provider_openai`。
+/// Token 用量按上游响应中的 usage 记录（流式响应在流结束时记录），调用方无需再估算。
+pub async fn call_provider_passthrough(
+    state: &AppState,
+    ctx: &RequestContext,
+    credential: &ProviderCredential,
+    request: PassthroughRequest<'_>,
+    flow_id: Option<&str>,
+) -> Option<Response> {
+    let PassthroughRequest {
+        format,
+        raw_body,
+        model,
+        stream,
+    } = request;
+
+    // 固定模型需要改写请求体，不能透传
+    if !supports_passthrough(&credential.credential, format)
+        || credential.pinned_model_for(model).is_some()
+    {
+        return None;
+    }
+
+    let result = match &credential.credential {
+        CredentialData::ClaudeKey { api_key, base_url } => {
+            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            send_with_rate_limit_retry(state, &credential.uuid, || {
+                claude.call_api_raw(raw_body.clone())
+            })
+            .await
+        }
+        CredentialData::OpenAIKey { api_key, base_url } => {
+            let openai = OpenAICustomProvider::with_config(
+                api_key.clone(),
+                base_url
+                    .clone()
+                    .or_else(|| credential.credential.default_base_url()),
+            );
+            send_with_rate_limit_retry(state, &credential.uuid, || {
+                openai.call_api_raw(raw_body.clone())
+            })
+            .await
+        }
+        _ => return None,
+    };
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[PASSTHROUGH] {:?} credential_uuid={} model={} stream={} body_bytes={}",
+            format,
+            &credential.uuid[..8],
+            model,
+            stream,
+            raw_body.len()
+        ),
+    );
+
+    let resp = match result {
+        Ok(resp) => resp,
+        Err(e) => {
+            if let Some(db) = &state.db {
+                let _ =
+                    state
+                        .pool_service
+                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            }
+            return Some(
+                (
+                    StatusCode::BAD_GATEWAY,
+                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                )
+                    .into_response(),
+            );
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        // 错误响应体较小，读取后用于记录凭证健康状态，并按上游原样返回
+        let content_type = resp.headers().get(header::CONTENT_TYPE).cloned();
+        let body = resp.bytes().await.unwrap_or_default();
+        let message = String::from_utf8_lossy(&body);
+        state.logs.write().await.add(
+            "error",
+            &format!(
+                "[PASSTHROUGH] 请求失败: status={} body={}",
+                status,
+                safe_truncate(&message, 200)
+            ),
+        );
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(message.as_ref()));
+        }
+        let mut builder = Response::builder().status(status);
+        if let Some(content_type) = content_type {
+            builder = builder.header(header::CONTENT_TYPE, content_type);
+        }
+        return Some(builder.body(Body::from(body)).unwrap_or_else(|_| {
+            build_error_response_with_status(status.as_u16(), "Failed to build response")
+        }));
+    }
+
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &credential.uuid, Some(model));
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+
+    let builder = Response::builder().status(status);
+    let response = if stream {
+        if let Some(fid) = flow_id {
+            let stream_format = match format {
+                PassthroughFormat::Anthropic => StreamFormat::Anthropic,
+                PassthroughFormat::OpenAI => StreamFormat::OpenAI,
+            };
+            state.flow_monitor.set_streaming(fid, stream_format).await;
+        }
+        let sse_format = match format {
+            PassthroughFormat::Anthropic => StreamingFormat::AnthropicSse,
+            PassthroughFormat::OpenAI => StreamingFormat::OpenAiSse,
+        };
+        let stream = record_stream_usage(
+            passthrough_sse_stream(resp, sse_format),
+            state.clone(),
+            ctx.clone(),
+        );
+        builder
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
+            .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
+            .body(Body::from_stream(stream))
+    } else {
+        let content_type = resp
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .cloned()
+            .unwrap_or_else(|| header::HeaderValue::from_static("application/json"));
+        // 非流式响应体需要完整读取才能取得 usage
+        let body = match resp.bytes().await {
+            Ok(body) => body,
+            Err(e) => {
+                return Some(build_error_response_with_status(
+                    502,
+                    &format!("Failed to read upstream response: {}", e),
+                ))
+            }
+        };
+        let mut usage = UsageScanner::default();
+        if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&body) {
+            usage.merge_json(&json);
+        }
+        usage.record(state, ctx);
+        builder
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+    };
+
+    Some(
+        response
+            .unwrap_or_else(|_| build_error_response_with_status(500, "Failed to build response")),
+    )
+}
+
+/// 透传上游 SSE 流
+///
+/// 读取上游失败时补发目标格式的错误事件后结束流，避免客户端只看到连接中断。
+fn passthrough_sse_stream(
+    resp: reqwest::Response,
+    format: StreamingFormat,
+) -> impl futures::Stream<Item = Result<axum::body::Bytes, std::io::Error>> + Send + 'static {
+    let mut upstream = Box::pin(resp.bytes_stream());
+    async_stream::stream! {
+        while let Some(chunk) = upstream.next().await {
+            match chunk {
+                Ok(bytes) => yield Ok(bytes),
+                Err(e) => {
+                    tracing::error!("[STREAM] 读取上游流失败: {}", e);
+                    let error = StreamError::from(e);
+                    yield Ok(axum::body::Bytes::from(error.to_sse_error_for(format)));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// 透传流结束时按 SSE 事件中的 usage 记录 Token 用量
+fn record_stream_usage<S>(
+    stream: S,
+    state: AppState,
+    ctx: RequestContext,
+) -> impl futures::Stream<Item = Result<axum::body::Bytes, std::io::Error>> + Send + 'static
+where
+    S: futures::Stream<Item = Result<axum::body::Bytes, std::io::Error>> + Send + 'static,
+{
+    async_stream::stream! {
+        let mut stream = Box::pin(stream);
+        let mut usage = UsageScanner::default();
+        while let Some(chunk) = stream.next().await {
+            if let Ok(bytes) = &chunk {
+                usage.feed_sse(bytes);
+            }
+            yield chunk;
+        }
+        usage.record(&state, &ctx);
+    }
+}
+
+/// 从上游响应中提取 Token 用量（兼容 OpenAI 与 Anthropic 的 usage 字段）
+#[derive(Debug, Default)]
+struct UsageScanner {
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+    /// 尚未读到换行的 SSE 数据
+    pending: Vec<u8>,
+}
+
+impl UsageScanner {
+    /// 合并响应体或 SSE 事件中的 `usage`（Anthropic `message_start` 为 `message.usage`）
+    fn merge_json(&mut self, json: &serde_json::Value) {
+        for usage in [json.get("usage"), json.pointer("/message/usage")]
+            .into_iter()
+            .flatten()
+        {
+            let tokens = |names: [&str; 2]| {
+                names
+                    .iter()
+                    .find_map(|name| usage.get(*name)?.as_u64())
+                    .map(|v| v as u32)
+            };
+            if let Some(input) = tokens(["prompt_tokens", "input_tokens"]) {
+                self.input_tokens = Some(input);
+            }
+            if let Some(output) = tokens(["completion_tokens", "output_tokens"]) {
+                self.output_tokens = Some(output);
+            }
+        }
+    }
+
+    /// 按行扫描 SSE 数据，只解析包含 usage 的事件
+    fn feed_sse(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        while let Some(pos) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let Some(data) = line.strip_prefix(b"data:") else {
+                continue;
+            };
+            if !data.windows(7).any(|w| w == b"\"usage\"") {
+                continue;
+            }
+            if let Ok(json) = serde_json::from_slice::<serde_json::Value>(data) {
+                self.merge_json(&json);
+            }
+        }
+    }
+
+    fn record(&self, state: &AppState, ctx: &RequestContext) {
+        crate::server::record_token_usage(state, ctx, self.input_tokens, self.output_tokens);
+    }
+}
+
 // ============================================================================
 // 流式传输支持
 // ============================================================================
@@ -2301,6 +2912,9 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
         CredentialData::KiroOAuth { .. } => StreamingFormat::AwsEventStream,
         CredentialData::ClaudeKey { .. } => StreamingFormat::AnthropicSse,
         CredentialData::OpenAIKey { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::OpenRouterKey { .. }
+        | CredentialData::MistralKey { .. }
+        | CredentialData::DeepSeekKey { .. } => StreamingFormat::OpenAiSse,
         // TODO: 任务 6 完成后，将这些改为 GeminiStream
         CredentialData::AntigravityOAuth { .. } => StreamingFormat::OpenAiSse,
         CredentialData::GeminiOAuth { .. } => StreamingFormat::OpenAiSse,
@@ -2387,10 +3001,10 @@ pub async fn handle_streaming_response(
         let stream = manager.handle_stream_with_callback(context, source_stream, on_chunk);
 
         // 转换为 Body 流
-        let body_stream = stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
+        let body_stream = stream.map(move |result| -> Result<axum::body::Bytes, std::io::Error> {
             match result {
                 Ok(event) => Ok(axum::body::Bytes::from(event)),
-                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(target_format))),
             }
         });
 
@@ -2399,10 +3013,10 @@ pub async fn handle_streaming_response(
         // 没有 flow_id，使用普通流式处理
         let stream = manager.handle_stream(context, source_stream);
 
-        let body_stream = stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
+        let body_stream = stream.map(move |result| -> Result<axum::body::Bytes, std::io::Error> {
             match result {
                 Ok(event) => Ok(axum::body::Bytes::from(event)),
-                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(target_format))),
             }
         });
 
@@ -2515,12 +3129,13 @@ pub async fn handle_streaming_response_with_timeout(
         };
 
     // 转换为 Body 流
-    let body_stream = timeout_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-        match result {
-            Ok(event) => Ok(axum::body::Bytes::from(event)),
-            Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-        }
-    });
+    let body_stream =
+        timeout_stream.map(move |result| -> Result<axum::body::Bytes, std::io::Error> {
+            match result {
+                Ok(event) => Ok(axum::body::Bytes::from(event)),
+                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(target_format))),
+            }
+        });
 
     // 构建 SSE 响应
     Response::builder()
@@ -2669,22 +3284,23 @@ pub async fn handle_streaming_with_disconnect_detection(
 
         // 转换为 Body 流
         let stream =
-            cancellable_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
+            cancellable_stream.map(move |result| -> Result<axum::body::Bytes, std::io::Error> {
                 match result {
                     Ok(event) => Ok(axum::body::Bytes::from(event)),
-                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(target_format))),
                 }
             });
 
         Body::from_stream(stream)
     } else {
         // 没有取消令牌，使用普通流
-        let stream = managed_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-            match result {
-                Ok(event) => Ok(axum::body::Bytes::from(event)),
-                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-            }
-        });
+        let stream =
+            managed_stream.map(move |result| -> Result<axum::body::Bytes, std::io::Error> {
+                match result {
+                    Ok(event) => Ok(axum::body::Bytes::from(event)),
+                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error_for(target_format))),
+                }
+            });
 
         Body::from_stream(stream)
     };
@@ -2863,19 +3479,21 @@ pub async fn handle_kiro_stream(
                 e
             );
             // 回退到从源文件加载
-            let mut kiro = KiroProvider::new();
-            if let Err(e) = kiro.load_credentials_from_path(&creds_file_path).await {
-                let _ = state.pool_service.mark_unhealthy(
-                    db,
-                    &credential.uuid,
-                    Some(&format!("Failed to load credentials: {}", e)),
-                );
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                )
-                    .into_response();
-            }
+            let mut kiro = match KiroProvider::pooled(&creds_file_path).await {
+                Ok(kiro) => kiro,
+                Err(e) => {
+                    let _ = state.pool_service.mark_unhealthy(
+                        db,
+                        &credential.uuid,
+                        Some(&format!("Failed to load credentials: {}", e)),
+                    );
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
+                    )
+                        .into_response();
+                }
+            };
             if let Err(e) = kiro.refresh_token().await {
                 let _ = state.pool_service.mark_unhealthy(
                     db,
@@ -2888,17 +3506,16 @@ pub async fn handle_kiro_stream(
                 )
                     .into_response();
             }
-            kiro.credentials.access_token.unwrap_or_default()
+            kiro.access_token().unwrap_or_default()
         }
     };
 
-    // 创建 KiroProvider 并设置 token
-    let mut kiro = KiroProvider::new();
-    // 从源文件加载其他配置（region, profile_arn 等）
-    // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
-    let _ = kiro.load_credentials_from_path(&creds_file_path).await;
+    // 复用该凭证文件的共享 Provider（region、profile_arn 等来自凭证文件）
+    let kiro = KiroProvider::pooled(&creds_file_path)
+        .await
+        .unwrap_or_default();
     // 使用缓存的 token 覆盖文件中的 token（缓存的 token 更新）
-    kiro.credentials.access_token = Some(token);
+    kiro.set_access_token(Some(token));
 
     tracing::info!("[KIRO_STREAM] 准备调用 call_api_stream_anthropic (直接转换)");
 
@@ -2951,7 +3568,7 @@ pub async fn handle_kiro_stream(
                 };
 
                 // 使用新 token 重试（需求 4.2）
-                kiro.credentials.access_token = Some(new_token);
+                kiro.set_access_token(Some(new_token));
                 match kiro.call_api_stream_anthropic(request).await {
                     Ok(stream) => stream,
                     Err(retry_err) => {
@@ -3166,7 +3783,9 @@ pub async fn handle_kiro_stream(
     let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
         match result {
             Ok(event) => Ok(axum::body::Bytes::from(event)),
-            Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+            Err(e) => Ok(axum::body::Bytes::from(
+                e.to_sse_error_for(StreamingFormat::AnthropicSse),
+            )),
         }
     });
 
@@ -3454,7 +4073,7 @@ fn build_sse_response(
 
     eprintln!("[ANTIGRAVITY_PARSE] 构建 SSE，内容长度: {}", content.len());
 
-    let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4());
+    let chunk_id = crate::middleware::response_id("chatcmpl-");
     let created = chrono::Utc::now().timestamp();
 
     let mut sse_output = String::new();
@@ -3549,13 +4168,7 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
     let finish_reason = candidate
         .get("finishReason")
         .and_then(|f| f.as_str())
-        .map(|r| match r {
-            "STOP" => "stop",
-            "MAX_TOKENS" => "length",
-            "SAFETY" => "content_filter",
-            "RECITATION" => "content_filter",
-            _ => "stop",
-        });
+        .map(|r| StopReason::from_gemini_str(r).to_openai_str());
 
     // 如果没有内容变化且没有 finish_reason，跳过
     if content_delta.is_none() && !has_image && finish_reason.is_none() {
@@ -3577,7 +4190,7 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
     }
 
     // 构建完整的 SSE 事件
-    let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4());
+    let chunk_id = crate::middleware::response_id("chatcmpl-");
     let created = chrono::Utc::now().timestamp();
 
     let response = serde_json::json!({
@@ -3595,6 +4208,18 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
     Some(format!("data: {}\n\n", response.to_string()))
 }
 
+/// 从 OpenAI 格式响应中提取停止原因与命中的停止序列
+///
+/// 调用方不转换工具调用块，`tool_calls` 结束原因回落为正常结束，
+/// 避免客户端等待不存在的 tool_use 块。
+fn openai_stop_reason(openai_resp: &serde_json::Value) -> (Option<StopReason>, Option<String>) {
+    match StopReason::from_openai_choice(&openai_resp["choices"][0]) {
+        Some((StopReason::ToolUse, _)) => (Some(StopReason::EndTurn), None),
+        Some((reason, stop_sequence)) => (Some(reason), stop_sequence),
+        None => (None, None),
+    }
+}
+
 /// 将 OpenAI ChatCompletionResponse 转换为 Anthropic MessagesResponse 格式
 fn convert_openai_response_to_anthropic(
     openai_resp: &crate::models::openai::ChatCompletionResponse,
@@ -3642,22 +4267,17 @@ fn convert_openai_response_to_anthropic(
     let stop_reason = openai_resp
         .choices
         .first()
-        .map(|c| match c.finish_reason.as_str() {
-            "stop" => "end_turn",
-            "length" => "max_tokens",
-            "tool_calls" => "tool_use",
-            _ => "end_turn",
-        })
-        .unwrap_or("end_turn");
+        .map(|c| StopReason::from_openai_str(&c.finish_reason))
+        .unwrap_or_default();
 
     // 构建 Anthropic 响应
     serde_json::json!({
-        "id": format!("msg_{}", uuid::Uuid::new_v4()),
+        "id": crate::middleware::response_id("msg_"),
         "type": "message",
         "role": "assistant",
         "content": content_array,
         "model": model,
-        "stop_reason": stop_reason,
+        "stop_reason": stop_reason.to_anthropic_str(),
         "stop_sequence": null,
         "usage": {
             "input_tokens": openai_resp.usage.prompt_tokens,
@@ -3666,6 +4286,17 @@ fn convert_openai_response_to_anthropic(
     })
 }
 
+/// Codex（Responses API）结束原因
+///
+/// `incomplete_details.reason` 区分输出截断与内容拦截，其余按是否包含工具调用判断。
+fn codex_stop_reason(response: &serde_json::Value, has_tool_calls: bool) -> StopReason {
+    match response["incomplete_details"]["reason"].as_str() {
+        Some("max_output_tokens") => StopReason::MaxTokens,
+        Some("content_filter") => StopReason::ContentFilter,
+        _ => StopReason::EndTurn.with_tool_calls(has_tool_calls),
+    }
+}
+
 /// 将 Codex response.completed 事件转换为 OpenAI Chat Completions 非流式响应格式
 /// 参考 CLIProxyAPI: internal/translator/codex/openai/chat-completions/codex_openai_response.go
 fn convert_codex_to_openai_non_stream(codex_response: &serde_json::Value) -> serde_json::Value {
@@ -3747,11 +4378,7 @@ fn convert_codex_to_openai_non_stream(codex_response: &serde_json::Value) -> ser
     }
 
     // 确定 finish_reason
-    let finish_reason = if !tool_calls.is_empty() {
-        "tool_calls"
-    } else {
-        "stop"
-    };
+    let finish_reason = codex_stop_reason(response, !tool_calls.is_empty()).to_openai_str();
 
     // 构建 message 对象
     let mut message = serde_json::json!({
@@ -3943,13 +4570,11 @@ fn convert_codex_event_to_openai_sse(
             });
             Some(chunk.to_string())
         }
-        "response.completed" => {
-            // 响应完成
-            let finish_reason = if *function_call_index != -1 {
-                "tool_calls"
-            } else {
-                "stop"
-            };
+        "response.completed" | "response.incomplete" => {
+            // 响应完成（incomplete 表示被截断或拦截）
+            let finish_reason =
+                codex_stop_reason(&codex_event["response"], *function_call_index != -1)
+                    .to_openai_str();
 
             // 提取 usage 信息
             let usage = &codex_event["response"]["usage"];
@@ -3981,3 +4606,112 @@ fn convert_codex_event_to_openai_sse(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_scanner_anthropic_sse() {
+        let mut usage = UsageScanner::default();
+        usage.feed_sse(b"event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n");
+        // 事件跨 chunk 拆分
+        usage.feed_sse(b"data: {\"type\":\"message_delta\",\"usage\":");
+        assert_eq!(usage.output_tokens, Some(1));
+        usage.feed_sse(b"{\"output_tokens\":34}}\n\n");
+        assert_eq!(usage.input_tokens, Some(12));
+        assert_eq!(usage.output_tokens, Some(34));
+    }
+
+    #[test]
+    fn test_usage_scanner_openai() {
+        let mut usage = UsageScanner::default();
+        usage.feed_sse(
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: [DONE]\n\n",
+        );
+        assert_eq!(usage.input_tokens, None);
+
+        usage.merge_json(&serde_json::json!({
+            "usage": {"prompt_tokens": 8, "completion_tokens": 3, "total_tokens": 11}
+        }));
+        assert_eq!(
+            (usage.input_tokens, usage.output_tokens),
+            (Some(8), Some(3))
+        );
+    }
+
+    /// 第一次返回带 Retry-After 的 429、之后返回 200 的上游
+    async fn rate_limited_upstream(
+        calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    ) -> String {
+        use std::sync::atomic::Ordering;
+
+        let app = axum::Router::new().route(
+            "/v1/chat/completions",
+            axum::routing::post(move || {
+                let calls = calls.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        (
+                            StatusCode::TOO_MANY_REQUESTS,
+                            [(header::RETRY_AFTER, "1")],
+                            "{\"error\":{\"message\":\"rate limited\"}}",
+                        )
+                            .into_response()
+                    } else {
+                        Json(serde_json::json!({"id": "chatcmpl-1", "choices": []})).into_response()
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_passthrough_honors_retry_after() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = std::sync::Arc::new(AtomicUsize::new(0));
+        let base_url = rate_limited_upstream(calls.clone()).await;
+        let state = AppState::for_test("secret");
+        let credential = ProviderCredential::new(
+            crate::ProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: Some(base_url),
+            },
+        );
+        let raw_body = axum::body::Bytes::from_static(b"{\"model\":\"gpt-4o\",\"messages\":[]}");
+
+        let started = std::time::Instant::now();
+        let response = call_provider_passthrough(
+            &state,
+            &RequestContext::new("gpt-4o".to_string()),
+            &credential,
+            PassthroughRequest {
+                format: PassthroughFormat::OpenAI,
+                raw_body: &raw_body,
+                model: "gpt-4o",
+                stream: false,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        // 按上游给出的 Retry-After 等待，而不是默认的指数退避起点
+        assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+        // 成功后清除凭证的限流记录
+        assert!(state
+            .processor
+            .retrier
+            .rate_limit_tracker()
+            .get_rate_limit(&credential.uuid)
+            .is_none());
+    }
+}
@@ -5,21 +5,25 @@
 use axum::{
     body::Body,
     extract::{
-        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        ws::{close_code, CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
         Query, State,
     },
     http::HeaderMap,
     response::IntoResponse,
 };
+use futures::stream::SplitSink;
 use futures::{SinkExt, StreamExt as FuturesStreamExt};
 use serde::Deserialize;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use subtle::ConstantTimeEq;
 use tokio::sync::Mutex;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use crate::injection::InjectionContext;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::ProviderCredential;
@@ -29,12 +33,21 @@ use crate::providers::{
 };
 use crate::server::AppState;
 use crate::server_utils::parse_cw_response;
+use crate::session_files::transcript::resume_request;
+use crate::session_files::TranscriptFormat;
 use crate::websocket::{
-    WsApiRequest, WsApiResponse, WsEndpoint, WsError, WsFlowEvent, WsMessage as WsProtoMessage,
+    WsApiRequest, WsApiResponse, WsConnectionManager, WsEndpoint, WsError, WsFlowEvent,
+    WsMessage as WsProtoMessage,
 };
 
+/// 浏览器通过子协议传递 Token 时使用的协议名（需同时声明，服务端会选择该协议）
+pub const WS_SUBPROTOCOL: &str = "proxycast";
+
+/// 携带 Token 的子协议前缀，如 `proxycast.token.<api_key>`
+const WS_TOKEN_SUBPROTOCOL_PREFIX: &str = "proxycast.token.";
+
 /// WebSocket 查询参数
-#[derive(Debug, Deserialize, Default)]
+#[derive(Deserialize, Default)]
 pub struct WsQueryParams {
     /// API 密钥（通过 URL 参数传递）
     pub api_key: Option<String>,
@@ -42,6 +55,27 @@ pub struct WsQueryParams {
     pub token: Option<String>,
 }
 
+impl std::fmt::Debug for WsQueryParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redact = |value: &Option<String>| value.as_ref().map(|_| "***");
+        f.debug_struct("WsQueryParams")
+            .field("api_key", &redact(&self.api_key))
+            .field("token", &redact(&self.token))
+            .finish()
+    }
+}
+
+/// 从 `Sec-WebSocket-Protocol` 请求头中提取 Token
+fn subprotocol_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all("sec-websocket-protocol")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .find_map(|protocol| protocol.trim().strip_prefix(WS_TOKEN_SUBPROTOCOL_PREFIX))
+        .filter(|token| !token.is_empty())
+}
+
 /// WebSocket 升级处理器
 pub async fn ws_upgrade_handler(
     ws: WebSocketUpgrade,
@@ -49,26 +83,30 @@ pub async fn ws_upgrade_handler(
     Query(params): Query<WsQueryParams>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    // 验证 API 密钥：优先从 header 获取，其次从 URL 参数获取
+    // 验证 API 密钥：优先从 header 获取，其次从子协议获取（浏览器无法设置请求头），最后从 URL 参数获取
     let auth = headers
         .get("authorization")
         .or_else(|| headers.get("x-api-key"))
         .and_then(|v| v.to_str().ok());
 
-    let key = match auth {
-        Some(s) if s.starts_with("Bearer ") => Some(&s[7..]),
-        Some(s) => Some(s),
-        None => {
-            // 尝试从 URL 参数获取
-            params.api_key.as_deref().or(params.token.as_deref())
-        }
+    let (key, source) = match auth {
+        Some(s) => (Some(s.strip_prefix("Bearer ").unwrap_or(s)), "header"),
+        None => match subprotocol_token(&headers) {
+            Some(token) => (Some(token), "subprotocol"),
+            None => (
+                params.api_key.as_deref().or(params.token.as_deref()),
+                "query",
+            ),
+        },
     };
 
     // 如果没有提供任何认证信息，允许连接（用于内部 Flow Monitor）
     // 但会在日志中记录
     let authenticated = match key {
-        Some(k) if k == state.api_key => true,
+        Some(k) if bool::from(k.as_bytes().ct_eq(state.api_key.as_bytes())) => true,
         Some(_) => {
+            // 只记录认证来源，不记录 Token 和 URL（URL 参数中可能包含 Token）
+            tracing::warn!("[WS] 认证失败（来源: {}）", source);
             return axum::http::Response::builder()
                 .status(401)
                 .body(Body::from("Invalid API key"))
@@ -88,7 +126,9 @@ pub async fn ws_upgrade_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, authenticated))
+    // 子协议认证时必须回应客户端声明的协议，否则浏览器会断开连接
+    ws.protocols([WS_SUBPROTOCOL])
+        .on_upgrade(move |socket| handle_websocket(socket, state, client_info, authenticated))
 }
 
 /// 处理 WebSocket 连接
@@ -123,13 +163,75 @@ pub async fn handle_websocket(
     );
 
     let (sender, mut receiver) = socket.split();
-    let sender = Arc::new(Mutex::new(sender));
+    let sender: WsSender = Arc::new(Mutex::new(sender));
 
     // Flow 事件订阅状态
     let flow_subscribed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    // Token 使用事件订阅状态
+    let token_subscribed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    // 启动凭证过期预警转发任务（无需订阅）
+    let expiry_sender = sender.clone();
+    let expiry_manager = state.ws_manager.clone();
+    let expiry_conn_id = conn_id.clone();
+    let mut expiry_receiver = state.expiry_monitor.subscribe();
+    let expiry_task = tokio::spawn(async move {
+        loop {
+            match expiry_receiver.recv().await {
+                Ok(warning) => {
+                    let ws_msg = WsProtoMessage::CredentialExpiryWarning(warning);
+                    if let Ok(msg_text) = serde_json::to_string(&ws_msg) {
+                        if !send_text(&expiry_sender, &expiry_manager, &expiry_conn_id, msg_text)
+                            .await
+                        {
+                            break;
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // 启动批量健康检查进度转发任务（无需订阅）
+    let health_sender = sender.clone();
+    let health_manager = state.ws_manager.clone();
+    let health_conn_id = conn_id.clone();
+    let mut health_receiver = state.pool_service.subscribe_health_progress();
+    let health_task = tokio::spawn(async move {
+        loop {
+            match health_receiver.recv().await {
+                Ok(progress) => {
+                    let ws_msg = WsProtoMessage::HealthCheckProgress(progress);
+                    if let Ok(msg_text) = serde_json::to_string(&ws_msg) {
+                        if !send_text(&health_sender, &health_manager, &health_conn_id, msg_text)
+                            .await
+                        {
+                            break;
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // 启动心跳任务：定期发送 Ping，未响应 Ping 或空闲超时时通知消息循环断开连接
+    let missed_pongs = Arc::new(AtomicU32::new(0));
+    let (close_tx, mut close_rx) = tokio::sync::oneshot::channel();
+    let heartbeat_task = tokio::spawn(run_heartbeat(
+        sender.clone(),
+        state.ws_manager.clone(),
+        conn_id.clone(),
+        missed_pongs.clone(),
+        close_tx,
+    ));
 
     // 启动 Flow 事件转发任务
     let flow_sender = sender.clone();
+    let flow_manager = state.ws_manager.clone();
     let flow_subscribed_clone = flow_subscribed.clone();
     let flow_monitor = state.flow_monitor.clone();
     let conn_id_clone = conn_id.clone();
@@ -151,12 +253,7 @@ pub async fn handle_websocket(
                     let ws_msg = WsProtoMessage::FlowEvent(ws_event);
 
                     if let Ok(msg_text) = serde_json::to_string(&ws_msg) {
-                        let mut sender_guard = flow_sender.lock().await;
-                        if sender_guard
-                            .send(WsMessage::Text(msg_text.into()))
-                            .await
-                            .is_err()
-                        {
+                        if !send_text(&flow_sender, &flow_manager, &conn_id_clone, msg_text).await {
                             tracing::debug!(
                                 "[WS] Flow event send failed for connection {}",
                                 &conn_id_clone[..8]
@@ -183,25 +280,81 @@ pub async fn handle_websocket(
         }
     });
 
+    // 启动 Token 使用事件转发任务
+    let token_sender = sender.clone();
+    let token_manager = state.ws_manager.clone();
+    let token_subscribed_clone = token_subscribed.clone();
+    let token_conn_id = conn_id.clone();
+    let mut token_receiver = state.processor.tokens.read().subscribe();
+    let token_task = tokio::spawn(async move {
+        loop {
+            match token_receiver.recv().await {
+                Ok(record) => {
+                    if !token_subscribed_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                        continue;
+                    }
+                    let ws_msg = WsProtoMessage::TokenUsage(record);
+                    if let Ok(msg_text) = serde_json::to_string(&ws_msg) {
+                        if !send_text(&token_sender, &token_manager, &token_conn_id, msg_text).await
+                        {
+                            break;
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!(
+                        "[WS] Token usage receiver lagged by {} messages for connection {}",
+                        n,
+                        &token_conn_id[..8]
+                    );
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
     // 消息处理循环
-    while let Some(msg) = receiver.next().await {
+    let mut heartbeat_stopped = false;
+    loop {
+        let msg = tokio::select! {
+            msg = receiver.next() => msg,
+            result = &mut close_rx, if !heartbeat_stopped => match result {
+                Ok(reason) => {
+                    close_connection(&state, &sender, &conn_id, reason).await;
+                    break;
+                }
+                // 心跳任务已退出（未启用心跳和空闲超时，或连接已关闭）
+                Err(_) => {
+                    heartbeat_stopped = true;
+                    continue;
+                }
+            },
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+        // 收到任何消息都说明连接存活
+        missed_pongs.store(0, Ordering::Relaxed);
+
         match msg {
             Ok(WsMessage::Text(text)) => {
                 state.ws_manager.on_message();
                 state.ws_manager.increment_request_count(&conn_id);
+                state.ws_manager.record_received(&conn_id, text.len());
 
                 match serde_json::from_str::<WsProtoMessage>(&text) {
                     Ok(ws_msg) => {
-                        let response =
-                            handle_ws_message(&state, &conn_id, ws_msg, &flow_subscribed).await;
+                        let response = handle_ws_message(
+                            &state,
+                            &conn_id,
+                            ws_msg,
+                            &flow_subscribed,
+                            &token_subscribed,
+                        )
+                        .await;
                         if let Some(resp) = response {
                             let resp_text = serde_json::to_string(&resp).unwrap_or_default();
-                            let mut sender_guard = sender.lock().await;
-                            if sender_guard
-                                .send(WsMessage::Text(resp_text.into()))
-                                .await
-                                .is_err()
-                            {
+                            if !send_text(&sender, &state.ws_manager, &conn_id, resp_text).await {
                                 break;
                             }
                         }
@@ -213,29 +366,20 @@ pub async fn handle_websocket(
                             e
                         )));
                         let error_text = serde_json::to_string(&error).unwrap_or_default();
-                        let mut sender_guard = sender.lock().await;
-                        if sender_guard
-                            .send(WsMessage::Text(error_text.into()))
-                            .await
-                            .is_err()
-                        {
+                        if !send_text(&sender, &state.ws_manager, &conn_id, error_text).await {
                             break;
                         }
                     }
                 }
             }
-            Ok(WsMessage::Binary(_)) => {
+            Ok(WsMessage::Binary(data)) => {
                 state.ws_manager.on_error();
+                state.ws_manager.record_received(&conn_id, data.len());
                 let error = WsProtoMessage::Error(WsError::invalid_message(
                     "Binary messages not supported",
                 ));
                 let error_text = serde_json::to_string(&error).unwrap_or_default();
-                let mut sender_guard = sender.lock().await;
-                if sender_guard
-                    .send(WsMessage::Text(error_text.into()))
-                    .await
-                    .is_err()
-                {
+                if !send_text(&sender, &state.ws_manager, &conn_id, error_text).await {
                     break;
                 }
             }
@@ -261,8 +405,12 @@ pub async fn handle_websocket(
         }
     }
 
-    // 取消 Flow 事件转发任务
+    // 取消事件转发任务
     flow_task.abort();
+    token_task.abort();
+    expiry_task.abort();
+    health_task.abort();
+    heartbeat_task.abort();
 
     // 清理连接
     state.ws_manager.unregister(&conn_id);
@@ -272,12 +420,136 @@ pub async fn handle_websocket(
     );
 }
 
+/// WebSocket 发送端（多个任务共享）
+type WsSender = Arc<Mutex<SplitSink<WebSocket, WsMessage>>>;
+
+/// 向客户端发送的消息（包括 Ping 和 Close 帧）超过该时间未写出时视为连接已断开
+const WS_SEND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// 发送文本消息并记录发送字节数，发送失败时返回 false
+async fn send_text(
+    sender: &WsSender,
+    manager: &WsConnectionManager,
+    conn_id: &str,
+    text: String,
+) -> bool {
+    let bytes = text.len();
+    let sent = sender
+        .lock()
+        .await
+        .send(WsMessage::Text(text))
+        .await
+        .is_ok();
+    if sent {
+        manager.record_sent(conn_id, bytes);
+    }
+    sent
+}
+
+/// 服务端主动断开连接的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseReason {
+    /// 连续多次 Ping 未收到 Pong（或 Ping 无法写出）
+    PingTimeout,
+    /// 空闲超时
+    IdleTimeout,
+}
+
+impl CloseReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            CloseReason::PingTimeout => "ping timeout",
+            CloseReason::IdleTimeout => "idle timeout",
+        }
+    }
+}
+
+/// 心跳任务：按间隔发送 Ping 并检查空闲超时，需要断开时通过 `close_tx` 通知消息循环
+async fn run_heartbeat(
+    sender: WsSender,
+    manager: Arc<WsConnectionManager>,
+    conn_id: String,
+    missed_pongs: Arc<AtomicU32>,
+    close_tx: tokio::sync::oneshot::Sender<CloseReason>,
+) {
+    let config = manager.config();
+    let ping_interval = config.heartbeat_interval_secs;
+    let max_missed_pongs = config.max_missed_pongs;
+    // 空闲超时按超时时间的 1/4 检查（至少 1 秒）
+    let period_secs = match (ping_interval, config.idle_timeout_secs) {
+        (0, 0) => return,
+        (ping, 0) => ping,
+        (0, idle) => (idle / 4).max(1),
+        (ping, idle) => ping.min((idle / 4).max(1)),
+    };
+    let period = std::time::Duration::from_secs(period_secs);
+    let ping_period = std::time::Duration::from_secs(ping_interval);
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let mut last_ping = tokio::time::Instant::now();
+
+    loop {
+        let now = interval.tick().await;
+        if manager.is_idle(&conn_id) {
+            let _ = close_tx.send(CloseReason::IdleTimeout);
+            return;
+        }
+        if ping_interval == 0 || now.duration_since(last_ping) < ping_period {
+            continue;
+        }
+        if max_missed_pongs > 0 && missed_pongs.load(Ordering::Relaxed) >= max_missed_pongs {
+            let _ = close_tx.send(CloseReason::PingTimeout);
+            return;
+        }
+        last_ping = now;
+        missed_pongs.fetch_add(1, Ordering::Relaxed);
+        let ping = async { sender.lock().await.send(WsMessage::Ping(Vec::new())).await };
+        match tokio::time::timeout(WS_SEND_TIMEOUT, ping).await {
+            Ok(Ok(())) => {}
+            // 连接已关闭，由消息循环处理
+            Ok(Err(_)) => return,
+            // 写缓冲区满且长时间无法写出，对端已不可达
+            Err(_) => {
+                let _ = close_tx.send(CloseReason::PingTimeout);
+                return;
+            }
+        }
+    }
+}
+
+/// 服务端主动断开连接（记录统计并尽力发送 Close 帧）
+async fn close_connection(state: &AppState, sender: &WsSender, conn_id: &str, reason: CloseReason) {
+    match reason {
+        CloseReason::PingTimeout => state.ws_manager.stats().on_ping_timeout(),
+        CloseReason::IdleTimeout => state.ws_manager.stats().on_idle_disconnect(),
+    }
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[WS] Closing connection {}: {}",
+            &conn_id[..8],
+            reason.as_str()
+        ),
+    );
+    let close = async {
+        sender
+            .lock()
+            .await
+            .send(WsMessage::Close(Some(CloseFrame {
+                code: close_code::AWAY,
+                reason: reason.as_str().into(),
+            })))
+            .await
+    };
+    let _ = tokio::time::timeout(WS_SEND_TIMEOUT, close).await;
+}
+
 /// 处理 WebSocket 消息
 async fn handle_ws_message(
     state: &AppState,
     conn_id: &str,
     msg: WsProtoMessage,
     flow_subscribed: &Arc<std::sync::atomic::AtomicBool>,
+    token_subscribed: &Arc<std::sync::atomic::AtomicBool>,
 ) -> Option<WsProtoMessage> {
     match msg {
         WsProtoMessage::Ping { timestamp } => Some(WsProtoMessage::Pong { timestamp }),
@@ -375,11 +647,110 @@ async fn handle_ws_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsProtoMessage::CredentialExpiryWarning(_) => Some(WsProtoMessage::Error(
+            WsError::invalid_message("CredentialExpiryWarning messages are server-to-client only"),
+        )),
+        WsProtoMessage::HealthCheckProgress(_) => Some(WsProtoMessage::Error(
+            WsError::invalid_message("HealthCheckProgress messages are server-to-client only"),
+        )),
+        WsProtoMessage::SubscribeTokenUsage => {
+            token_subscribed.store(true, std::sync::atomic::Ordering::Relaxed);
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[WS] Connection {} subscribed to token usage events",
+                    &conn_id[..8]
+                ),
+            );
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "subscribe_token_usage".to_string(),
+                payload: serde_json::json!({
+                    "status": "subscribed",
+                    "message": "Successfully subscribed to token usage events"
+                }),
+            }))
+        }
+        WsProtoMessage::UnsubscribeTokenUsage => {
+            token_subscribed.store(false, std::sync::atomic::Ordering::Relaxed);
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[WS] Connection {} unsubscribed from token usage events",
+                    &conn_id[..8]
+                ),
+            );
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "unsubscribe_token_usage".to_string(),
+                payload: serde_json::json!({
+                    "status": "unsubscribed",
+                    "message": "Successfully unsubscribed from token usage events"
+                }),
+            }))
+        }
+        WsProtoMessage::TokenUsage(_) => Some(WsProtoMessage::Error(WsError::invalid_message(
+            "TokenUsage messages are server-to-client only",
+        ))),
     }
 }
 
 /// 处理 WebSocket API 请求
+///
+/// 携带 `session_id` 且启用会话持久化时，先补齐已记录的历史消息，响应后更新对话记录。
 async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsProtoMessage {
+    let format = match request.endpoint {
+        WsEndpoint::ChatCompletions => Some(TranscriptFormat::OpenAI),
+        WsEndpoint::Messages => Some(TranscriptFormat::Anthropic),
+        WsEndpoint::Models => None,
+    };
+    let recorder = state
+        .session_recorder
+        .as_ref()
+        .filter(|recorder| recorder.enabled());
+    let (Some(session_id), Some(format), Some(recorder)) =
+        (request.session_id.as_ref(), format, recorder)
+    else {
+        return dispatch_ws_api_request(state, request).await;
+    };
+
+    let mut request = request.clone();
+    match recorder.storage().get_transcript(session_id) {
+        Ok(Some(transcript)) if transcript.format == format => {
+            request.payload = resume_request(&transcript, request.payload);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return WsProtoMessage::Error(WsError::invalid_request(
+                Some(request.request_id.clone()),
+                e,
+            ))
+        }
+    }
+
+    let response = dispatch_ws_api_request(state, &request).await;
+    if let WsProtoMessage::Response(api_response) = &response {
+        recorder.record(
+            Some(session_id.clone()),
+            format,
+            &request.payload,
+            &api_response.payload,
+            None,
+        );
+    }
+    response
+}
+
+async fn dispatch_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsProtoMessage {
+    // 内容防护：WebSocket 连接只能使用服务 API Key 认证，按该 Key 对应的策略检查
+    let payload = match request.endpoint {
+        WsEndpoint::Models => request.payload.clone(),
+        WsEndpoint::ChatCompletions | WsEndpoint::Messages => {
+            match apply_ws_guardrails(state, request).await {
+                Ok(payload) => payload,
+                Err(e) => return WsProtoMessage::Error(e),
+            }
+        }
+    };
+
     match request.endpoint {
         WsEndpoint::Models => {
             // 返回模型列表
@@ -401,7 +772,7 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
         }
         WsEndpoint::ChatCompletions => {
             // 解析 ChatCompletionRequest
-            match serde_json::from_value::<ChatCompletionRequest>(request.payload.clone()) {
+            match serde_json::from_value::<ChatCompletionRequest>(payload) {
                 Ok(chat_request) => {
                     handle_ws_chat_completions(state, &request.request_id, chat_request).await
                 }
@@ -413,7 +784,7 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
         }
         WsEndpoint::Messages => {
             // 解析 AnthropicMessagesRequest
-            match serde_json::from_value::<AnthropicMessagesRequest>(request.payload.clone()) {
+            match serde_json::from_value::<AnthropicMessagesRequest>(payload) {
                 Ok(messages_request) => {
                     handle_ws_anthropic_messages(state, &request.request_id, messages_request).await
                 }
@@ -426,6 +797,31 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
     }
 }
 
+/// 按防护策略检查 WebSocket API 请求，返回（可能已脱敏的）请求体
+async fn apply_ws_guardrails(
+    state: &AppState,
+    request: &WsApiRequest,
+) -> Result<serde_json::Value, WsError> {
+    let mut payload = request.payload.clone();
+    let model = payload
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let ctx = RequestContext::new(model);
+    match state
+        .processor
+        .apply_guardrails(&ctx, Some(&state.api_key), &mut payload)
+        .await
+    {
+        Ok(_) => Ok(payload),
+        Err(e) => Err(WsError::invalid_request(
+            Some(request.request_id.clone()),
+            e.to_string(),
+        )),
+    }
+}
+
 /// 处理 WebSocket chat completions 请求
 async fn handle_ws_chat_completions(
     state: &AppState,
@@ -434,6 +830,7 @@ async fn handle_ws_chat_completions(
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    ctx.set_user_id(request.user.as_deref());
 
     // 使用 RequestProcessor 解析模型别名和路由
     let _provider = state.processor.resolve_and_route(&mut ctx).await;
@@ -443,12 +840,18 @@ async fn handle_ws_chat_completions(
         request.model = ctx.resolved_model.clone();
     }
 
-    // 应用参数注入
-    let injection_enabled = *state.injection_enabled.read().await;
-    if injection_enabled {
-        let injector = state.processor.injector.read().await;
+    // 获取默认 provider
+    let snapshot = state.processor.snapshot();
+    let default_provider = snapshot.default_provider.clone();
+
+    // 应用参数注入（WebSocket 消息没有请求头）
+    if snapshot.injection_enabled {
         let mut payload = serde_json::to_value(&request).unwrap_or_default();
-        let result = injector.inject(&request.model, &mut payload);
+        let injection_ctx =
+            InjectionContext::new(&request.model).with_provider(default_provider.as_str());
+        let result = snapshot
+            .injector
+            .inject_with_context(&injection_ctx, &mut payload);
         if result.has_injections() {
             if let Ok(updated) = serde_json::from_value(payload) {
                 request = updated;
@@ -456,9 +859,6 @@ async fn handle_ws_chat_completions(
         }
     }
 
-    // 获取默认 provider
-    let default_provider = state.default_provider.read().await.clone();
-
     // 尝试从凭证池中选择凭证（不降级，指定什么就用什么）
     let credential = match &state.db {
         Some(db) => state
@@ -500,6 +900,7 @@ async fn handle_ws_anthropic_messages(
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    ctx.set_user_id(request.user_id());
 
     // 使用 RequestProcessor 解析模型别名和路由
     let _provider = state.processor.resolve_and_route(&mut ctx).await;
@@ -509,12 +910,18 @@ async fn handle_ws_anthropic_messages(
         request.model = ctx.resolved_model.clone();
     }
 
-    // 应用参数注入
-    let injection_enabled = *state.injection_enabled.read().await;
-    if injection_enabled {
-        let injector = state.processor.injector.read().await;
+    // 获取默认 provider
+    let snapshot = state.processor.snapshot();
+    let default_provider = snapshot.default_provider.clone();
+
+    // 应用参数注入（WebSocket 消息没有请求头）
+    if snapshot.injection_enabled {
         let mut payload = serde_json::to_value(&request).unwrap_or_default();
-        let result = injector.inject(&request.model, &mut payload);
+        let injection_ctx =
+            InjectionContext::new(&request.model).with_provider(default_provider.as_str());
+        let result = snapshot
+            .injector
+            .inject_with_context(&injection_ctx, &mut payload);
         if result.has_injections() {
             if let Ok(updated) = serde_json::from_value(payload) {
                 request = updated;
@@ -522,9 +929,6 @@ async fn handle_ws_anthropic_messages(
         }
     }
 
-    // 获取默认 provider
-    let default_provider = state.default_provider.read().await.clone();
-
     // 尝试从凭证池中选择凭证（带智能降级）
     let credential = match &state.db {
         Some(db) => state
@@ -566,17 +970,19 @@ pub async fn call_provider_openai_for_ws(
 
     match &credential.credential {
         CredentialData::KiroOAuth { creds_file_path } => {
-            let mut kiro = KiroProvider::new();
-            if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
-                        db,
-                        &credential.uuid,
-                        Some(&format!("Failed to load credentials: {}", e)),
-                    );
+            let mut kiro = match KiroProvider::pooled(creds_file_path).await {
+                Ok(kiro) => kiro,
+                Err(e) => {
+                    if let Some(db) = &state.db {
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&format!("Failed to load credentials: {}", e)),
+                        );
+                    }
+                    return Err(e.to_string());
                 }
-                return Err(e.to_string());
-            }
+            };
             if let Err(e) = kiro.refresh_token().await {
                 if let Some(db) = &state.db {
                     let _ = state.pool_service.mark_unhealthy(
@@ -602,7 +1008,7 @@ pub async fn call_provider_openai_for_ws(
                 }
             };
             if resp.status().is_success() {
-                let body = resp.text().await.map_err(|e| e.to_string())?;
+                let body = resp.bytes().await.map_err(|e| e.to_string())?;
                 let parsed = parse_cw_response(&body);
                 let has_tool_calls = !parsed.tool_calls.is_empty();
 
@@ -638,7 +1044,7 @@ pub async fn call_provider_openai_for_ws(
                 };
 
                 Ok(serde_json::json!({
-                    "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+                    "id": crate::middleware::response_id("chatcmpl-"),
                     "object": "chat.completion",
                     "created": std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
@@ -895,7 +1301,7 @@ pub async fn call_provider_anthropic_for_ws(
 
             // 转换响应为 Anthropic 格式
             Ok(serde_json::json!({
-                "id": format!("msg_{}", uuid::Uuid::new_v4()),
+                "id": crate::middleware::response_id("msg_"),
                 "type": "message",
                 "role": "assistant",
                 "content": [{
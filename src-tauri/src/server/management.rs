@@ -0,0 +1,465 @@
+//! 管理类路由
+//!
+//! 管理 API、Kiro 凭证管理、凭证 API（aster Agent 集成）和 Amp CLI 管理代理的路由表。
+//! 管理类路由使用管理 API 的请求体大小限制，Amp 管理代理使用单独的限制。
+
+use crate::config::{BodyLimitsConfig, RemoteManagementConfig};
+use crate::server::handlers;
+use crate::server::AppState;
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+
+/// 管理类路由表
+pub fn routes(
+    management_config: RemoteManagementConfig,
+    body_limits: &BodyLimitsConfig,
+) -> Router<AppState> {
+    let management_body_limit = BodyLimitsConfig::bytes(body_limits.management_mb);
+    let amp_proxy_body_limit = BodyLimitsConfig::bytes(body_limits.amp_proxy_mb);
+
+    // 管理 API 路由（带认证中间件）
+    let management_routes = Router::new()
+        .route("/v0/management/status", get(handlers::management_status))
+        .route(
+            "/v0/management/credentials",
+            get(handlers::management_list_credentials),
+        )
+        .route(
+            "/v0/management/credentials",
+            post(handlers::management_add_credential),
+        )
+        .route(
+            "/v0/management/credentials/import",
+            get(handlers::management_detect_credentials)
+                .post(handlers::management_import_credentials),
+        )
+        .route(
+            "/v0/management/credentials/:uuid/duplicate",
+            post(handlers::management_duplicate_credential),
+        )
+        .route(
+            "/v0/management/credentials/bulk/disabled",
+            post(handlers::management_bulk_set_disabled),
+        )
+        .route(
+            "/v0/management/credentials/bulk/delete-unhealthy",
+            post(handlers::management_bulk_delete_unhealthy),
+        )
+        .route(
+            "/v0/management/credentials/bulk/check",
+            post(handlers::management_bulk_check_health),
+        )
+        .route(
+            "/v0/management/oauth/:provider/login",
+            post(handlers::management_start_oauth_login),
+        )
+        .route(
+            "/v0/management/oauth/sessions/:id",
+            get(handlers::management_get_oauth_login)
+                .delete(handlers::management_cancel_oauth_login),
+        )
+        .route(
+            "/v0/management/config",
+            get(handlers::management_get_config),
+        )
+        .route(
+            "/v0/management/config",
+            axum::routing::put(handlers::management_update_config),
+        )
+        .route(
+            "/v0/management/config/reload",
+            post(handlers::management_reload_config),
+        )
+        .route(
+            "/v0/management/config/full",
+            get(handlers::management_get_full_config),
+        )
+        .route(
+            "/v0/management/cluster",
+            get(handlers::management_get_cluster_status),
+        )
+        .route(
+            "/v0/management/maintenance",
+            get(handlers::management_list_maintenance).post(handlers::management_add_maintenance),
+        )
+        .route(
+            "/v0/management/maintenance/:id",
+            axum::routing::delete(handlers::management_remove_maintenance),
+        )
+        .route(
+            "/v0/management/routes",
+            get(handlers::management_list_routes).post(handlers::management_register_route),
+        )
+        .route(
+            "/v0/management/routes/bundle",
+            get(handlers::management_route_bundle),
+        )
+        .route(
+            "/v0/management/routes/:selector/enable",
+            post(handlers::management_enable_route),
+        )
+        .route(
+            "/v0/management/routes/:selector/disable",
+            post(handlers::management_disable_route),
+        )
+        .route(
+            "/v0/management/telemetry/history",
+            get(handlers::management_telemetry_history),
+        )
+        .route(
+            "/v0/management/telemetry/users",
+            get(handlers::management_telemetry_users),
+        )
+        .route(
+            "/v0/management/telemetry/latency",
+            get(handlers::management_latency_percentiles),
+        )
+        .route(
+            "/v0/management/websocket",
+            get(handlers::management_websocket_stats),
+        )
+        .route(
+            "/v0/management/amp/mappings",
+            get(handlers::management_amp_mappings),
+        )
+        .route(
+            "/v0/management/logs/filter",
+            get(handlers::management_get_log_filter).put(handlers::management_set_log_filter),
+        )
+        .route(
+            "/v0/management/logs/stream",
+            get(handlers::management_logs_stream),
+        )
+        .route(
+            "/v0/management/reports/daily",
+            get(handlers::management_daily_report),
+        )
+        .route(
+            "/v0/management/usage",
+            get(handlers::management_list_quotas),
+        )
+        .route(
+            "/v0/management/usage/:uuid",
+            get(handlers::management_get_quota),
+        );
+    let management_routes =
+        crate::middleware::with_body_limit(management_routes, management_body_limit).layer(
+            crate::middleware::ManagementAuthLayer::new(management_config),
+        );
+
+    // Kiro凭证管理API路由
+    let kiro_api_routes = Router::new()
+        .route(
+            "/api/kiro/credentials/available",
+            get(handlers::get_available_credentials),
+        )
+        .route(
+            "/api/kiro/credentials/select",
+            post(handlers::select_credential),
+        )
+        .route(
+            "/api/kiro/credentials/:uuid/refresh",
+            axum::routing::put(handlers::refresh_credential),
+        )
+        .route(
+            "/api/kiro/credentials/:uuid/status",
+            get(handlers::get_credential_status),
+        );
+    let kiro_api_routes =
+        crate::middleware::with_body_limit(kiro_api_routes, management_body_limit);
+
+    // 凭证 API 路由（用于 aster Agent 集成）
+    let credentials_api_routes = Router::new()
+        .route("/v1/credentials/select", post(handlers::credentials_select))
+        .route(
+            "/v1/credentials/:uuid/token",
+            get(handlers::credentials_get_token),
+        );
+    let credentials_api_routes =
+        crate::middleware::with_body_limit(credentials_api_routes, management_body_limit);
+
+    // Amp CLI 管理代理路由
+    let amp_proxy_routes = Router::new()
+        .route(
+            "/api/auth/*path",
+            axum::routing::any(amp_management_proxy_auth),
+        )
+        .route(
+            "/api/user/*path",
+            axum::routing::any(amp_management_proxy_user),
+        );
+    let amp_proxy_routes =
+        crate::middleware::with_body_limit(amp_proxy_routes, amp_proxy_body_limit);
+
+    management_routes
+        // Amp CLI 管理代理路由
+        .merge(amp_proxy_routes)
+        // Kiro凭证管理API路由
+        .merge(kiro_api_routes)
+        // 凭证 API 路由（用于 aster Agent 集成）
+        .merge(credentials_api_routes)
+}
+
+/// Amp CLI 管理代理 - auth 路由
+///
+/// 处理 `/api/auth/*` 路由，将请求代理到上游 URL
+async fn amp_management_proxy_auth(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+    axum::extract::RawQuery(query): axum::extract::RawQuery,
+    headers: HeaderMap,
+    method: axum::http::Method,
+    body: axum::body::Bytes,
+) -> Response {
+    amp_management_proxy_internal(
+        state,
+        &format!("auth/{}", path),
+        query,
+        headers,
+        method,
+        body,
+    )
+    .await
+}
+
+/// Amp CLI 管理代理 - user 路由
+///
+/// 处理 `/api/user/*` 路由，将请求代理到上游 URL
+async fn amp_management_proxy_user(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+    axum::extract::RawQuery(query): axum::extract::RawQuery,
+    headers: HeaderMap,
+    method: axum::http::Method,
+    body: axum::body::Bytes,
+) -> Response {
+    amp_management_proxy_internal(
+        state,
+        &format!("user/{}", path),
+        query,
+        headers,
+        method,
+        body,
+    )
+    .await
+}
+
+/// 是否为幂等方法（重复发送不会产生额外副作用，可以安全重试）
+fn is_idempotent_method(method: &axum::http::Method) -> bool {
+    matches!(
+        *method,
+        axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+    )
+}
+
+/// Amp CLI 管理代理内部实现
+///
+/// 处理 `/api/auth/*` 和 `/api/user/*` 路由
+/// 将请求代理到上游 URL，响应以流式方式转发
+///
+/// # 参数
+/// - `path`: 请求路径（不含 /api/ 前缀，如 "auth/login" 或 "user/profile"）
+/// - `query`: 原始查询字符串（原样转发到上游）
+async fn amp_management_proxy_internal(
+    state: AppState,
+    path: &str,
+    query: Option<String>,
+    headers: HeaderMap,
+    method: axum::http::Method,
+    body: axum::body::Bytes,
+) -> Response {
+    let full_path = format!("/api/{}", path);
+
+    let amp_router = state.amp_router.read().clone();
+
+    // 检查是否是管理路由
+    if !amp_router.is_management_route(&full_path) {
+        state.logs.write().await.add(
+            "warn",
+            &format!("[AMP] Invalid management route: {}", full_path),
+        );
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": {"message": "Not found"}})),
+        )
+            .into_response();
+    }
+
+    // 检查 localhost 限制
+    if amp_router.restrict_management_to_localhost() {
+        // 从 headers 中获取客户端 IP
+        let client_ip = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.split(',').next().unwrap_or("").trim().to_string())
+            .or_else(|| {
+                headers
+                    .get("x-real-ip")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string())
+            });
+
+        if let Some(ip) = &client_ip {
+            let is_localhost = ip == "127.0.0.1" || ip == "::1" || ip == "localhost";
+            if !is_localhost {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!("[AMP] Management proxy blocked from non-localhost: {}", ip),
+                );
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(serde_json::json!({"error": {"message": "Management endpoints are restricted to localhost"}})),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    // 获取上游 URL
+    let upstream_url = match amp_router.get_management_upstream_path(&full_path) {
+        Some(url) => url,
+        None => {
+            state.logs.write().await.add(
+                "warn",
+                "[AMP] No upstream URL configured for management proxy",
+            );
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({"error": {"message": "Upstream URL not configured"}})),
+            )
+                .into_response();
+        }
+    };
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AMP] Proxying management request: {} {} -> {}",
+            method, full_path, upstream_url
+        ),
+    );
+
+    let upstream_url = match query.as_deref() {
+        Some(query) if !query.is_empty() => format!("{}?{}", upstream_url, query),
+        _ => upstream_url,
+    };
+
+    // 复制请求头（排除逐跳头部，host / content-length 由客户端重新计算）
+    let connection = headers
+        .get(axum::http::header::CONNECTION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let mut forward_headers = HeaderMap::new();
+    for (name, value) in headers.iter() {
+        if !crate::router::is_hop_by_hop_header(name.as_str(), connection.as_deref()) {
+            forward_headers.append(name.clone(), value.clone());
+        }
+    }
+
+    // 关闭自动解压，响应体按上游原样透传（包括 content-encoding）
+    let client = match reqwest::Client::builder()
+        .no_gzip()
+        .no_brotli()
+        .no_deflate()
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": {"message": format!("Failed to create HTTP client: {}", e)}})),
+            )
+                .into_response();
+        }
+    };
+
+    // 发送请求：超时只作用于等待响应头，流式响应体不受限制。
+    // 非幂等请求（POST / PUT / PATCH 等）上游可能已经处理，只在连接失败（请求未发出）时重试
+    let timeout = amp_router.upstream_timeout();
+    let max_retries = amp_router.upstream_max_retries();
+    let idempotent = is_idempotent_method(&method);
+    let mut attempt = 0;
+    let response = loop {
+        let mut request_builder = client
+            .request(method.clone(), &upstream_url)
+            .headers(forward_headers.clone());
+        if !body.is_empty() {
+            request_builder = request_builder.body(body.clone());
+        }
+
+        let outcome = match tokio::time::timeout(timeout, request_builder.send()).await {
+            Ok(Ok(response))
+                if attempt >= max_retries
+                    || !idempotent
+                    || !matches!(response.status().as_u16(), 502..=504) =>
+            {
+                break response;
+            }
+            Ok(Ok(response)) => (
+                true,
+                StatusCode::BAD_GATEWAY,
+                format!("Upstream returned {}", response.status()),
+            ),
+            Ok(Err(e)) => (
+                idempotent || e.is_connect(),
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to connect to upstream: {}", e),
+            ),
+            Err(_) => (
+                idempotent,
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Upstream did not respond within {}s", timeout.as_secs()),
+            ),
+        };
+        let (retryable, status, error) = outcome;
+
+        if !retryable || attempt >= max_retries {
+            state.logs.write().await.add(
+                "error",
+                &format!("[AMP] Failed to proxy request to upstream: {}", error),
+            );
+            return (
+                status,
+                Json(serde_json::json!({"error": {"message": error}})),
+            )
+                .into_response();
+        }
+
+        attempt += 1;
+        state.logs.write().await.add(
+            "warn",
+            &format!("[AMP] {} (retry {}/{})", error, attempt, max_retries),
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(500 * attempt as u64)).await;
+    };
+
+    // 复制响应头并流式转发响应体（支持 chunked / SSE）
+    let status = response.status();
+    let connection = response
+        .headers()
+        .get(reqwest::header::CONNECTION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let mut builder = Response::builder().status(status);
+    for (name, value) in response.headers().iter() {
+        if !crate::router::is_hop_by_hop_header(name.as_str(), connection.as_deref()) {
+            builder = builder.header(name, value);
+        }
+    }
+
+    builder
+        .body(Body::from_stream(response.bytes_stream()))
+        .unwrap_or_else(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
+            )
+                .into_response()
+        })
+}
@@ -1,46 +1,51 @@
 //! HTTP API 服务器
-
+//!
+//! - `handlers` - 各 API 的请求处理器
+//! - `provider_dispatch` - 按路由选择器 / Provider 分发的路由（`/:selector/v1/*`、`/api/provider/*`、Gemini 原生接口）
+//! - `management` - 管理 API、Kiro 凭证 API 与 Amp CLI 管理代理路由
+//! - `ws` - WebSocket 路由
+//! - `streaming` - 流式响应构建
+//! - `readiness` - 就绪检查（热重载及凭证池同步期间报告未就绪）
+//!
+//! 所有路由共享同一个 `AppState`，由 `build_router` 组装。
+
+pub mod anthropic_version;
 pub mod client_detector;
+pub mod config_reload;
+mod management;
+mod provider_dispatch;
+pub mod readiness;
+pub mod response_validation;
+pub mod stream_watchdog;
+pub mod streaming;
+pub mod validation;
+mod ws;
 
 use crate::config::{
-    Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig, FileChangeEvent, FileWatcher,
-    HotReloadManager, ReloadResult,
+    Config, ConfigChangeKind, ConfigManager, FileChangeEvent, FileWatcher, HotReloadManager,
 };
-use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::credential::CredentialSyncService;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::flow_monitor::{FlowInterceptor, FlowMonitor, FlowMonitorConfig};
-use crate::injection::Injector;
 use crate::logger::LogStore;
-use crate::models::anthropic::*;
-use crate::models::openai::*;
-use crate::models::provider_pool_model::CredentialData;
-use crate::models::route_model::{RouteInfo, RouteListResponse};
-use crate::processor::{RequestContext, RequestProcessor};
-use crate::providers::antigravity::AntigravityProvider;
+use crate::processor::{ConfigSnapshot, RequestContext, RequestProcessor, SharedConfigSnapshot};
 use crate::providers::claude_custom::ClaudeCustomProvider;
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::kiro::KiroProvider;
 use crate::providers::openai_custom::OpenAICustomProvider;
 use crate::providers::qwen::QwenProvider;
-use crate::server_utils::{
-    build_anthropic_response, build_anthropic_stream_response, build_error_response,
-    build_error_response_with_status, build_gemini_cli_request, build_gemini_native_request,
-    health, models, parse_cw_response,
-};
+use crate::router::{RegisteredRoute, RouteRegistry};
+use crate::server_utils::{health, health_live};
 use crate::services::kiro_event_service::KiroEventService;
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::services::token_cache_service::TokenCacheService;
-use crate::websocket::{WsConfig, WsConnectionManager, WsStats};
+use crate::websocket::{WsConnectionManager, WsStats};
 use axum::{
-    body::Body,
-    extract::{DefaultBodyLimit, Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Router,
 };
+use config_reload::{ConfigReloader, ConfigReloaderSlot};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -53,6 +58,27 @@ pub fn record_request_telemetry(
     status: crate::telemetry::RequestStatus,
     error_message: Option<String>,
 ) {
+    let log = build_request_log(ctx, status, error_message);
+
+    // 统计聚合器、请求日志和数据库由后台任务批量写入
+    state.telemetry.record_request(log);
+
+    tracing::info!(
+        "[TELEMETRY] request_id={} provider={:?} model={} status={:?} duration_ms={}",
+        ctx.request_id,
+        ctx.provider.unwrap_or(crate::ProviderType::Kiro),
+        ctx.resolved_model,
+        status,
+        ctx.elapsed_ms()
+    );
+}
+
+/// 根据请求上下文构建遥测请求日志
+pub fn build_request_log(
+    ctx: &RequestContext,
+    status: crate::telemetry::RequestStatus,
+    error_message: Option<String>,
+) -> crate::telemetry::RequestLog {
     use crate::telemetry::RequestLog;
 
     let provider = ctx.provider.unwrap_or(crate::ProviderType::Kiro);
@@ -69,7 +95,7 @@ pub fn record_request_telemetry(
         crate::telemetry::RequestStatus::Failed => log.mark_failed(
             ctx.elapsed_ms(),
             None,
-            error_message.clone().unwrap_or_default(),
+            error_message.unwrap_or_default(),
         ),
         crate::telemetry::RequestStatus::Timeout => log.mark_timeout(ctx.elapsed_ms()),
         crate::telemetry::RequestStatus::Cancelled => log.mark_cancelled(ctx.elapsed_ms()),
@@ -85,26 +111,12 @@ pub fn record_request_telemetry(
 
     // 设置重试次数
     log.retry_count = ctx.retry_count;
+    log.user_id = ctx.user_id.clone();
 
-    // 记录到统计聚合器
-    {
-        let stats = state.processor.stats.write();
-        stats.record(log.clone());
-    }
-
-    // 记录到请求日志记录器（用于前端日志列表显示）
-    if let Some(logger) = &state.request_logger {
-        let _ = logger.record(log.clone());
-    }
-
-    tracing::info!(
-        "[TELEMETRY] request_id={} provider={:?} model={} status={:?} duration_ms={}",
-        ctx.request_id,
-        provider,
-        ctx.resolved_model,
-        status,
-        ctx.elapsed_ms()
-    );
+    log.context_usage_percentage = ctx
+        .get_metadata(crate::server_utils::CONTEXT_USAGE_METADATA)
+        .and_then(|v| v.as_f64());
+    log
 }
 
 /// 记录 Token 使用量到遥测系统
@@ -130,13 +142,11 @@ pub fn record_token_usage(
         output_tokens.unwrap_or(0),
         TokenSource::Actual,
     )
-    .with_request_id(ctx.request_id.clone());
+    .with_request_id(ctx.request_id.clone())
+    .with_user_id(ctx.user_id.clone());
 
-    // 记录到 Token 追踪器
-    {
-        let tokens = state.processor.tokens.write();
-        tokens.record(record);
-    }
+    // Token 追踪器和数据库由后台任务批量写入
+    state.telemetry.record_token_usage(record);
 
     tracing::debug!(
         "[TOKEN] request_id={} input={} output={}",
@@ -157,6 +167,8 @@ pub struct ServerStatus {
 
 pub struct ServerState {
     pub config: Config,
+    /// 配置文件路径（热重载监控的文件）
+    pub config_path: std::path::PathBuf,
     pub running: bool,
     pub requests: u64,
     pub start_time: Option<std::time::Instant>,
@@ -166,8 +178,12 @@ pub struct ServerState {
     pub openai_custom_provider: OpenAICustomProvider,
     pub claude_custom_provider: ClaudeCustomProvider,
     pub default_provider_ref: Arc<RwLock<String>>,
-    /// 路由器引用（用于动态更新默认 Provider）
-    pub router_ref: Option<Arc<RwLock<crate::router::Router>>>,
+    /// 配置快照引用（服务器运行时有效，用于动态更新默认 Provider）
+    pub snapshot_ref: Option<SharedConfigSnapshot>,
+    /// WebSocket 连接管理器引用（服务器运行时有效）
+    pub ws_manager_ref: Option<Arc<WsConnectionManager>>,
+    /// 配置重载器引用（服务器运行且配置了配置文件时有效）
+    pub config_reloader_ref: ConfigReloaderSlot,
     shutdown_tx: Option<oneshot::Sender<()>>,
     /// 服务器运行时使用的 API key（启动时从配置复制）
     /// 用于 test_api 命令，确保测试使用的 API key 和服务器一致
@@ -187,6 +203,7 @@ impl ServerState {
 
         Self {
             config,
+            config_path: crate::config::ConfigManager::default_config_path(),
             running: false,
             requests: 0,
             start_time: None,
@@ -196,7 +213,9 @@ impl ServerState {
             openai_custom_provider: openai_custom,
             claude_custom_provider: claude_custom,
             default_provider_ref,
-            router_ref: None,
+            snapshot_ref: None,
+            ws_manager_ref: None,
+            config_reloader_ref: ConfigReloaderSlot::default(),
             shutdown_tx: None,
             running_api_key: None,
             running_host: None,
@@ -312,28 +331,16 @@ impl ServerState {
         let port = self.config.server.port;
         let api_key = self.config.server.api_key.clone();
         let api_key_for_state = api_key.clone(); // 用于保存到 running_api_key
-        let default_provider_ref = self.default_provider_ref.clone();
 
         // 重新加载凭证
         let _ = self.kiro_provider.load_credentials().await;
         let kiro = self.kiro_provider.clone();
 
-        // 创建参数注入器
-        let injection_enabled = self.config.injection.enabled;
-        let injector = Injector::with_rules(
-            self.config
-                .injection
-                .rules
-                .iter()
-                .map(|r| r.clone().into())
-                .collect(),
-        );
-
         // 获取配置和配置路径用于热重载
         let config = self.config.clone();
-        let config_path = crate::config::ConfigManager::default_config_path();
+        let config_path = self.config_path.clone();
 
-        // 创建请求处理器（在 spawn 之前创建，以便保存 router_ref）
+        // 创建请求处理器（在 spawn 之前创建，以便保存 snapshot_ref）
         let processor = match (&shared_stats, &shared_tokens) {
             (Some(stats), Some(tokens)) => Arc::new(RequestProcessor::with_shared_telemetry(
                 pool_service.clone(),
@@ -343,57 +350,31 @@ impl ServerState {
             _ => Arc::new(RequestProcessor::with_defaults(pool_service.clone())),
         };
 
-        // 从配置初始化 Router 的默认 Provider
-        {
-            let default_provider_str = &config.routing.default_provider;
-
-            // 尝试解析为 ProviderType 枚举
-            match default_provider_str.parse::<crate::ProviderType>() {
-                Ok(provider_type) => {
-                    let mut router = processor.router.write().await;
-                    router.set_default_provider(provider_type);
-                    tracing::info!(
-                        "[SERVER] 从配置初始化 Router 默认 Provider: {} (ProviderType)",
-                        default_provider_str
-                    );
-                }
-                Err(_) => {
-                    // 如果解析失败，可能是自定义 provider ID
-                    // 这种情况下，路由器保持空状态，请求会直接使用 provider_id 进行凭证查找
-                    tracing::warn!(
-                        "[SERVER] 配置的默认 Provider '{}' 不是有效的 ProviderType 枚举值，可能是自定义 Provider ID。\
-                        路由器将保持空状态，请求将直接使用 provider_id 进行凭证查找。",
-                        default_provider_str
-                    );
-                    eprintln!(
-                        "[SERVER] 警告：默认 Provider '{}' 不是标准 Provider 类型（kiro/openai/claude等），\
-                        可能是自定义 Provider ID。如果这是预期行为，请忽略此警告。",
-                        default_provider_str
-                    );
-                }
-            }
-        }
+        // 从配置构建配置快照，保存引用以便后续动态更新默认 Provider
+        processor
+            .snapshot
+            .store(Arc::new(ConfigSnapshot::from_config(&config)));
+        self.snapshot_ref = Some(processor.snapshot.clone());
 
-        // 保存 router_ref 以便后续动态更新
-        self.router_ref = Some(processor.router.clone());
+        // 创建 WebSocket 管理器（保存引用以便查询连接统计）
+        let ws_manager = ws::build_ws_manager(Some(&config));
+        self.ws_manager_ref = Some(ws_manager.clone());
 
         // 保存实际使用的 host（在移动到 spawn 之前克隆）
         let running_host = host.clone();
+        let config_reloader_slot = self.config_reloader_ref.clone();
 
         tokio::spawn(async move {
             if let Err(e) = run_server(
                 &host,
                 port,
                 &api_key,
-                default_provider_ref,
                 kiro,
                 logs,
                 rx,
                 pool_service,
                 token_cache,
                 db,
-                injector,
-                injection_enabled,
                 shared_stats,
                 shared_tokens,
                 shared_logger,
@@ -402,6 +383,8 @@ impl ServerState {
                 Some(config),
                 Some(config_path),
                 Some(processor),
+                Some(ws_manager),
+                config_reloader_slot,
             )
             .await
             {
@@ -426,17 +409,9 @@ impl ServerState {
         self.start_time = None;
         self.running_api_key = None;
         self.running_host = None;
-        self.router_ref = None;
-    }
-}
-
-impl Clone for KiroProvider {
-    fn clone(&self) -> Self {
-        Self {
-            credentials: self.credentials.clone(),
-            client: reqwest::Client::new(),
-            creds_path: self.creds_path.clone(),
-        }
+        self.snapshot_ref = None;
+        self.ws_manager_ref = None;
+        *self.config_reloader_ref.write() = None;
     }
 }
 
@@ -447,7 +422,8 @@ pub mod handlers;
 pub struct AppState {
     pub api_key: String,
     pub base_url: String,
-    pub default_provider: Arc<RwLock<String>>,
+    /// 对外访问地址（配置后 `/v1/routes` 直接使用该地址）
+    pub public_url: Option<String>,
     pub kiro: Arc<RwLock<KiroProvider>>,
     pub logs: Arc<RwLock<LogStore>>,
     pub kiro_refresh_lock: Arc<tokio::sync::Mutex<()>>,
@@ -456,11 +432,9 @@ pub struct AppState {
     pub pool_service: Arc<ProviderPoolService>,
     pub token_cache: Arc<TokenCacheService>,
     pub db: Option<DbConnection>,
-    /// 参数注入器
-    pub injector: Arc<RwLock<Injector>>,
-    /// 是否启用参数注入
-    pub injection_enabled: Arc<RwLock<bool>>,
-    /// 请求处理器
+    /// 遥测持久化数据库连接（未启用持久化时为 None）
+    pub telemetry_db: Option<DbConnection>,
+    /// 请求处理器（持有默认 Provider、注入器、端点 Provider 等配置快照）
     pub processor: Arc<RequestProcessor>,
     /// WebSocket 连接管理器
     pub ws_manager: Arc<WsConnectionManager>,
@@ -470,18 +444,162 @@ pub struct AppState {
     pub hot_reload_manager: Option<Arc<HotReloadManager>>,
     /// 请求日志记录器（与 TelemetryState 共享）
     pub request_logger: Option<Arc<crate::telemetry::RequestLogger>>,
-    /// Amp CLI 路由器
-    pub amp_router: Arc<crate::router::AmpRouter>,
+    /// 遥测记录器（后台批量写入统计聚合器、请求日志和数据库）
+    pub telemetry: crate::telemetry::TelemetryRecorder,
+    /// Amp CLI 路由器（`ampcode` 配置热重载时整体替换）
+    pub amp_router: Arc<parking_lot::RwLock<crate::router::AmpRouter>>,
     /// Flow 监控服务
     pub flow_monitor: Arc<FlowMonitor>,
     /// Flow 拦截器
     pub flow_interceptor: Arc<FlowInterceptor>,
-    /// 端点 Provider 配置
-    pub endpoint_providers: Arc<RwLock<EndpointProvidersConfig>>,
     /// Kiro 事件服务
     pub kiro_event_service: Arc<KiroEventService>,
     /// API Key Provider 服务（用于智能降级）
     pub api_key_service: Arc<crate::services::api_key_provider_service::ApiKeyProviderService>,
+    /// 模型目录聚合服务（/v1/models）
+    pub model_catalog: Arc<crate::services::model_catalog_service::ModelCatalogService>,
+    /// 路由注册表（命名空间路由及其专属 API Key）
+    pub route_registry: Arc<RwLock<RouteRegistry>>,
+    /// 内置 OAuth 登录会话
+    pub oauth_logins: Arc<crate::oauth::OAuthLoginManager>,
+    /// 凭证过期预警
+    pub expiry_monitor: Arc<crate::services::credential_expiry_service::CredentialExpiryMonitor>,
+    /// 会话对话记录（会话目录不可用时为 None）
+    pub session_recorder: Option<Arc<crate::session_files::SessionRecorder>>,
+    /// 就绪状态
+    pub readiness: Arc<readiness::Readiness>,
+    /// 配置重载器（未配置配置文件时为 None）
+    pub config_reloader: Option<Arc<ConfigReloader>>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// 测试用应用状态（无数据库，其余组件使用默认配置）
+    pub(crate) fn for_test(api_key: &str) -> Self {
+        let pool_service = Arc::new(ProviderPoolService::new());
+        let processor = Arc::new(RequestProcessor::with_defaults(pool_service.clone()));
+        let ws_manager = Arc::new(WsConnectionManager::with_defaults());
+        let telemetry =
+            crate::telemetry::TelemetryRecorder::spawn(crate::telemetry::TelemetrySinks {
+                stats: processor.stats.clone(),
+                tokens: processor.tokens.clone(),
+                request_logger: None,
+                db: None,
+            });
+        Self {
+            api_key: api_key.to_string(),
+            base_url: "http://127.0.0.1:8999".to_string(),
+            public_url: None,
+            kiro: Arc::new(RwLock::new(KiroProvider::new())),
+            logs: Arc::new(RwLock::new(LogStore::new())),
+            kiro_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            gemini_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            qwen_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            model_catalog: pool_service.model_catalog(),
+            pool_service,
+            token_cache: Arc::new(TokenCacheService::new()),
+            db: None,
+            telemetry_db: None,
+            processor,
+            ws_stats: ws_manager.stats().clone(),
+            ws_manager,
+            hot_reload_manager: None,
+            request_logger: None,
+            telemetry,
+            amp_router: Arc::new(parking_lot::RwLock::new(crate::router::AmpRouter::new(
+                Default::default(),
+            ))),
+            flow_monitor: Arc::new(FlowMonitor::new(FlowMonitorConfig::default(), None)),
+            flow_interceptor: Arc::new(FlowInterceptor::default()),
+            kiro_event_service: Arc::new(KiroEventService::new()),
+            api_key_service: Arc::new(
+                crate::services::api_key_provider_service::ApiKeyProviderService::new(),
+            ),
+            route_registry: Arc::new(RwLock::new(RouteRegistry::new())),
+            oauth_logins: Arc::new(crate::oauth::OAuthLoginManager::new()),
+            expiry_monitor: Arc::new(
+                crate::services::credential_expiry_service::CredentialExpiryMonitor::new(),
+            ),
+            session_recorder: None,
+            readiness: Arc::new(readiness::Readiness::new()),
+            config_reloader: None,
+        }
+    }
+}
+
+/// 服务器运行期间的后台任务
+///
+/// 服务器停止（包括启动失败）时中止，避免重新启动服务器后同一任务重复运行。
+#[derive(Default)]
+struct BackgroundTasks(Vec<tokio::task::JoinHandle<()>>);
+
+impl BackgroundTasks {
+    fn push(&mut self, task: tokio::task::JoinHandle<()>) {
+        self.0.push(task);
+    }
+}
+
+impl Extend<tokio::task::JoinHandle<()>> for BackgroundTasks {
+    fn extend<I: IntoIterator<Item = tokio::task::JoinHandle<()>>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl Drop for BackgroundTasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// 启动遥测数据定期清理任务（每小时按保留配置删除过期数据）
+fn spawn_telemetry_cleanup(
+    db: DbConnection,
+    retention: crate::config::TelemetryRetentionConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let result = match db.lock() {
+                Ok(conn) => crate::database::dao::telemetry::TelemetryDao::cleanup(
+                    &conn,
+                    &retention,
+                    chrono::Utc::now(),
+                ),
+                Err(_) => continue,
+            };
+            match result {
+                Ok(removed) => tracing::debug!("[TELEMETRY] 清理过期遥测数据: {:?}", removed),
+                Err(e) => tracing::warn!("[TELEMETRY] 清理过期遥测数据失败: {}", e),
+            }
+        }
+    })
+}
+
+/// 启动凭证延迟提示同步任务（每 30 秒把遥测统计的 P95 延迟同步给凭证池和负载均衡器）
+fn spawn_latency_hint_sync(
+    stats: Arc<parking_lot::RwLock<crate::telemetry::StatsAggregator>>,
+    pool_service: Arc<ProviderPoolService>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        let window = chrono::Duration::minutes(crate::telemetry::DEFAULT_LATENCY_WINDOW_MINUTES);
+        loop {
+            interval.tick().await;
+            let hints = stats
+                .read()
+                .all_credential_latencies(window)
+                .into_iter()
+                .map(|(credential_id, p)| (credential_id, p.p95_ms))
+                .collect();
+            pool_service.set_latency_hints(hints);
+            if let Some(manager) = crate::credential::get_global_unified_manager() {
+                manager.sync_latency_hints(&stats.read(), window);
+            }
+        }
+    })
 }
 
 /// 启动配置文件监控
@@ -495,13 +613,11 @@ pub struct AppState {
 /// - 使用 RwLock 进行原子性更新，不会阻塞正在处理的请求
 /// - 服务器继续运行，不需要重启
 /// - HTTP 和 WebSocket 连接保持活跃
+/// - 热重载及凭证池同步期间持有 `reload_lock` 写锁，就绪检查报告未就绪
 async fn start_config_watcher(
     config_path: PathBuf,
-    hot_reload_manager: Option<Arc<HotReloadManager>>,
-    processor: Arc<RequestProcessor>,
+    reloader: Arc<ConfigReloader>,
     logs: Arc<RwLock<LogStore>>,
-    db: Option<DbConnection>,
-    config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
 ) -> Option<FileWatcher> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FileChangeEvent>();
 
@@ -523,12 +639,6 @@ async fn start_config_watcher(
     tracing::info!("[HOT_RELOAD] 配置文件监控已启动: {:?}", config_path);
 
     // 启动事件处理任务
-    let hot_reload_manager_clone = hot_reload_manager.clone();
-    let processor_clone = processor.clone();
-    let logs_clone = logs.clone();
-    let db_clone = db.clone();
-    let config_manager_clone = config_manager.clone();
-
     tokio::spawn(async move {
         while let Some(event) = rx.recv().await {
             // 只处理修改事件
@@ -537,83 +647,13 @@ async fn start_config_watcher(
             }
 
             tracing::info!("[HOT_RELOAD] 检测到配置文件变更: {:?}", event.path);
-            logs_clone.write().await.add(
+            logs.write().await.add(
                 "info",
                 &format!("[HOT_RELOAD] 检测到配置文件变更: {:?}", event.path),
             );
 
             // 执行热重载
-            if let Some(ref manager) = hot_reload_manager_clone {
-                let result = manager.reload();
-                match &result {
-                    ReloadResult::Success { .. } => {
-                        tracing::info!("[HOT_RELOAD] 配置热重载成功");
-                        logs_clone
-                            .write()
-                            .await
-                            .add("info", "[HOT_RELOAD] 配置热重载成功");
-
-                        // 更新处理器中的组件
-                        let new_config = manager.config();
-                        update_processor_config(&processor_clone, &new_config).await;
-
-                        // 同步凭证池
-                        if let (Some(ref db), Some(ref cfg_manager)) =
-                            (&db_clone, &config_manager_clone)
-                        {
-                            match sync_credential_pool_from_config(db, cfg_manager, &logs_clone)
-                                .await
-                            {
-                                Ok(count) => {
-                                    tracing::info!(
-                                        "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                        count
-                                    );
-                                    logs_clone.write().await.add(
-                                        "info",
-                                        &format!(
-                                            "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                            count
-                                        ),
-                                    );
-                                }
-                                Err(e) => {
-                                    tracing::warn!("[HOT_RELOAD] 凭证池同步失败: {}", e);
-                                    logs_clone.write().await.add(
-                                        "warn",
-                                        &format!("[HOT_RELOAD] 凭证池同步失败: {}", e),
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    ReloadResult::RolledBack { error, .. } => {
-                        tracing::warn!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error);
-                        logs_clone.write().await.add(
-                            "warn",
-                            &format!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error),
-                        );
-                    }
-                    ReloadResult::Failed {
-                        error,
-                        rollback_error,
-                        ..
-                    } => {
-                        tracing::error!(
-                            "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
-                            error,
-                            rollback_error
-                        );
-                        logs_clone.write().await.add(
-                            "error",
-                            &format!(
-                                "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
-                                error, rollback_error
-                            ),
-                        );
-                    }
-                }
-            }
+            reloader.reload().await;
         }
     });
 
@@ -626,66 +666,96 @@ async fn start_config_watcher(
 ///
 /// # 原子性更新
 ///
-/// 每个组件的更新都是原子性的，使用 RwLock 确保：
+/// 配置快照整体替换，其余组件各自使用 RwLock 更新，确保：
 /// - 正在处理的请求不会看到部分更新的状态
 /// - 更新过程不会阻塞新请求的处理
 /// - 现有连接不受影响
 async fn update_processor_config(processor: &RequestProcessor, config: &Config) {
-    // 更新注入器规则
+    // 重建配置快照（默认 Provider、注入器、模型别名、端点 Provider）
     {
-        let mut injector = processor.injector.write().await;
-        injector.clear();
-        for rule in &config.injection.rules {
-            injector.add_rule(rule.clone().into());
-        }
+        let snapshot = ConfigSnapshot::from_config(config);
         tracing::debug!(
-            "[HOT_RELOAD] 注入器规则已更新: {} 条规则",
-            config.injection.rules.len()
+            "[HOT_RELOAD] 配置快照已更新: 默认 Provider {}, {} 条注入规则, {} 个别名, {} 条别名规则",
+            snapshot.default_provider,
+            config.injection.rules.len(),
+            config.routing.model_aliases.len(),
+            snapshot.mapper.rules_len()
         );
+        processor.snapshot.store(Arc::new(snapshot));
     }
 
-    // 更新路由器默认 Provider
+    // 更新模型输出上限
     {
-        let mut router = processor.router.write().await;
-
-        // 尝试解析为 ProviderType 枚举
-        match config
-            .routing
-            .default_provider
-            .parse::<crate::ProviderType>()
-        {
-            Ok(provider_type) => {
-                router.set_default_provider(provider_type);
-                tracing::debug!(
-                    "[HOT_RELOAD] 路由器默认 Provider 已更新: {} (ProviderType)",
-                    config.routing.default_provider
-                );
-            }
-            Err(_) => {
-                // 如果解析失败，可能是自定义 provider ID
-                // 清空路由器的默认 provider，让请求直接使用 provider_id
-                tracing::warn!(
-                    "[HOT_RELOAD] 配置的默认 Provider '{}' 不是有效的 ProviderType 枚举值，可能是自定义 Provider ID。\
-                    路由器默认 Provider 将被清空。",
-                    config.routing.default_provider
-                );
-            }
-        }
+        let mut token_limits = processor.token_limits.write().await;
+        token_limits.set_overrides(&config.routing.model_max_tokens);
+        tracing::debug!(
+            "[HOT_RELOAD] 模型输出上限已更新: {} 条配置",
+            token_limits.overrides_len()
+        );
     }
 
-    // 更新模型映射器
+    // 更新上下文窗口预检配置
+    *processor.context_windows.write().await =
+        crate::router::ModelContextWindows::from_config(&config.context_overflow);
+
+    // 更新内容防护策略
     {
-        let mut mapper = processor.mapper.write().await;
-        mapper.clear();
-        for (alias, model) in &config.routing.model_aliases {
-            mapper.add_alias(alias, model);
-        }
+        let mut guardrails = processor.guardrails.write().await;
+        *guardrails = crate::guardrails::Guardrails::from_config(&config.guardrails);
         tracing::debug!(
-            "[HOT_RELOAD] 模型别名已更新: {} 个别名",
-            config.routing.model_aliases.len()
+            "[HOT_RELOAD] 内容防护策略已更新: enabled={}, {} 个策略",
+            config.guardrails.enabled,
+            config.guardrails.policies.len()
         );
     }
 
+    // 更新响应后处理规则
+    *processor.response_processor.write().await =
+        crate::postprocess::ResponseProcessor::from_config(&config.response_processing);
+
+    // 更新内置 mock Provider 配置
+    *processor.mock_provider.write().await =
+        crate::providers::mock::MockProvider::new(config.mock_provider.clone());
+
+    // 更新故障注入规则
+    *processor.chaos.write().await =
+        crate::resilience::ChaosInjector::from_config(&config.chaos, &config.server.host);
+
+    // 更新对话压缩配置
+    *processor.compaction.write().await = config.compaction.clone();
+
+    // 更新上游流式响应看门狗配置
+    *processor.stream_watchdog.write().await = config.stream_watchdog.clone();
+
+    // 更新上游响应结构校验配置
+    *processor.response_validation.write().await = config.response_validation.clone();
+
+    // 更新上游连通性负缓存配置
+    crate::resilience::reachability_cache().set_config(config.upstream_reachability.clone());
+
+    // 更新维护窗口
+    crate::credential::maintenance_registry().set_config(&config.maintenance);
+
+    // 更新上游 TLS 配置（之后创建的 Provider 客户端生效）
+    crate::providers::tls::set_upstream_tls_config(&config.upstream_tls);
+
+    // 更新凭证优先级分层、模型黑名单自动学习、健康检查、请求优先级通道配置和凭证选择策略
+    processor
+        .pool_service
+        .set_tier_config(config.credential_tiers.clone());
+    processor
+        .pool_service
+        .set_model_blacklist_config(config.model_blacklist.clone());
+    processor
+        .pool_service
+        .set_health_check_config(config.health_check.clone());
+    processor
+        .pool_service
+        .set_priority_lanes_config(config.priority_lanes.clone());
+    processor
+        .pool_service
+        .set_selection_strategy(config.routing.credential_strategy);
+
     // 注意：重试配置目前不支持热更新，因为 Retrier 是不可变的
     // 如果需要更新重试配置，需要重启服务器
     tracing::debug!(
@@ -726,9 +796,17 @@ async fn sync_credential_pool_from_config(
         let existing =
             ProviderPoolDao::get_by_uuid(&conn, &cred.uuid).map_err(|e| e.to_string())?;
 
-        if existing.is_some() {
-            // 更新现有凭证
-            ProviderPoolDao::update(&conn, cred).map_err(|e| e.to_string())?;
+        if let Some(existing) = existing {
+            // 更新现有凭证（优先级分层、调度规则、标签和固定模型不保存在 YAML 中，保留数据库中的设置）
+            let mut cred = cred.clone();
+            cred.tier = existing.tier;
+            cred.schedule = existing.schedule;
+            cred.tags = existing.tags;
+            cred.pinned_model = existing.pinned_model;
+            for (model, expires_at) in existing.learned_unsupported_models {
+                cred.learn_unsupported_model(&model, expires_at);
+            }
+            ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
             tracing::debug!(
                 "[HOT_RELOAD] 更新凭证: {} ({})",
                 cred.uuid,
@@ -749,19 +827,45 @@ async fn sync_credential_pool_from_config(
     Ok(synced_count)
 }
 
+/// 根据凭证池构建路由注册表，并应用路由专属 API Key
+fn build_route_registry(
+    db: Option<&DbConnection>,
+    route_api_keys: &std::collections::HashMap<String, String>,
+) -> RouteRegistry {
+    let mut registry = RouteRegistry::new();
+
+    let credentials = db
+        .and_then(|db| db.lock().ok())
+        .map(|conn| ProviderPoolDao::get_all(&conn).unwrap_or_default())
+        .unwrap_or_default();
+
+    for cred in &credentials {
+        let provider_type = cred.provider_type.to_string();
+        registry.register(RegisteredRoute::provider_namespace(
+            &provider_type,
+            &cred.uuid,
+            cred.name.as_deref(),
+        ));
+        registry.register(RegisteredRoute::credential_selector(
+            &cred.uuid,
+            &provider_type,
+        ));
+    }
+    registry.set_api_keys(route_api_keys);
+
+    registry
+}
+
 async fn run_server(
     host: &str,
     port: u16,
     api_key: &str,
-    default_provider: Arc<RwLock<String>>,
     kiro: KiroProvider,
     logs: Arc<RwLock<LogStore>>,
     shutdown: oneshot::Receiver<()>,
     pool_service: Arc<ProviderPoolService>,
     token_cache: Arc<TokenCacheService>,
     db: Option<DbConnection>,
-    injector: Injector,
-    injection_enabled: bool,
     shared_stats: Option<Arc<parking_lot::RwLock<crate::telemetry::StatsAggregator>>>,
     shared_tokens: Option<Arc<parking_lot::RwLock<crate::telemetry::TokenTracker>>>,
     shared_logger: Option<Arc<crate::telemetry::RequestLogger>>,
@@ -770,61 +874,108 @@ async fn run_server(
     config: Option<Config>,
     config_path: Option<PathBuf>,
     processor: Option<Arc<RequestProcessor>>,
+    ws_manager: Option<Arc<WsConnectionManager>>,
+    config_reloader_slot: ConfigReloaderSlot,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let base_url = format!("http://{}:{}", host, port);
+    let base_path = config
+        .as_ref()
+        .map(|c| c.server.normalized_base_path())
+        .unwrap_or_default();
+    let base_url = format!(
+        "http://{}:{}{}",
+        crate::config::url_host(host),
+        port,
+        base_path
+    );
 
-    // 使用传入的 processor 或创建新的
+    // 使用传入的 processor（已加载配置快照）或创建新的
     let processor = match processor {
         Some(p) => p,
-        None => match (&shared_stats, &shared_tokens) {
-            (Some(stats), Some(tokens)) => Arc::new(RequestProcessor::with_shared_telemetry(
-                pool_service.clone(),
-                stats.clone(),
-                tokens.clone(),
-            )),
-            _ => Arc::new(RequestProcessor::with_defaults(pool_service.clone())),
-        },
-    };
-
-    // 将注入器规则同步到处理器
-    {
-        let mut proc_injector = processor.injector.write().await;
-        for rule in injector.rules() {
-            proc_injector.add_rule(rule.clone());
+        None => {
+            let processor = match (&shared_stats, &shared_tokens) {
+                (Some(stats), Some(tokens)) => Arc::new(RequestProcessor::with_shared_telemetry(
+                    pool_service.clone(),
+                    stats.clone(),
+                    tokens.clone(),
+                )),
+                _ => Arc::new(RequestProcessor::with_defaults(pool_service.clone())),
+            };
+            // 从配置构建配置快照（默认 Provider、注入器、模型别名、端点 Provider）
+            if let Some(cfg) = &config {
+                processor
+                    .snapshot
+                    .store(Arc::new(ConfigSnapshot::from_config(cfg)));
+            }
+            processor
         }
-    }
+    };
 
-    // 从配置初始化 Router 的默认 Provider
     if let Some(cfg) = &config {
-        let default_provider_str = &cfg.routing.default_provider;
-
-        // 尝试解析为 ProviderType 枚举
-        match default_provider_str.parse::<crate::ProviderType>() {
-            Ok(provider_type) => {
-                let mut router = processor.router.write().await;
-                router.set_default_provider(provider_type);
-                tracing::info!(
-                    "[SERVER] 从配置初始化 Router 默认 Provider: {} (ProviderType)",
-                    default_provider_str
-                );
-            }
-            Err(_) => {
-                // 如果解析失败，可能是自定义 provider ID
-                tracing::warn!(
-                    "[SERVER] 配置的默认 Provider '{}' 不是有效的 ProviderType 枚举值，可能是自定义 Provider ID。\
-                    路由器将保持空状态，请求将直接使用 provider_id 进行凭证查找。",
-                    default_provider_str
-                );
-                eprintln!(
-                    "[SERVER] 警告：默认 Provider '{}' 不是标准 Provider 类型，可能是自定义 Provider ID",
-                    default_provider_str
-                );
-            }
-        }
+        // 从配置初始化模型输出上限
+        processor
+            .token_limits
+            .write()
+            .await
+            .set_overrides(&cfg.routing.model_max_tokens);
+
+        // 从配置初始化上下文窗口预检
+        *processor.context_windows.write().await =
+            crate::router::ModelContextWindows::from_config(&cfg.context_overflow);
+
+        // 从配置初始化内容防护策略
+        *processor.guardrails.write().await =
+            crate::guardrails::Guardrails::from_config(&cfg.guardrails);
+
+        // 从配置初始化响应后处理规则
+        *processor.response_processor.write().await =
+            crate::postprocess::ResponseProcessor::from_config(&cfg.response_processing);
+
+        // 从配置初始化内置 mock Provider
+        *processor.mock_provider.write().await =
+            crate::providers::mock::MockProvider::new(cfg.mock_provider.clone());
+
+        // 从配置初始化故障注入规则
+        *processor.chaos.write().await =
+            crate::resilience::ChaosInjector::from_config(&cfg.chaos, host);
+
+        // 从配置初始化对话压缩
+        *processor.compaction.write().await = cfg.compaction.clone();
+
+        // 从配置初始化上游流式响应看门狗
+        *processor.stream_watchdog.write().await = cfg.stream_watchdog.clone();
+
+        // 从配置初始化上游响应结构校验
+        *processor.response_validation.write().await = cfg.response_validation.clone();
+
+        // 从配置初始化上游连通性负缓存
+        crate::resilience::reachability_cache().set_config(cfg.upstream_reachability.clone());
+
+        // 从配置初始化维护窗口
+        crate::credential::maintenance_registry().set_config(&cfg.maintenance);
+
+        // 从配置初始化上游 TLS
+        crate::providers::tls::set_upstream_tls_config(&cfg.upstream_tls);
+
+        // 从配置初始化凭证优先级分层、模型黑名单自动学习、健康检查、请求优先级通道和凭证选择策略
+        processor
+            .pool_service
+            .set_tier_config(cfg.credential_tiers.clone());
+        processor
+            .pool_service
+            .set_model_blacklist_config(cfg.model_blacklist.clone());
+        processor
+            .pool_service
+            .set_health_check_config(cfg.health_check.clone());
+        processor
+            .pool_service
+            .set_priority_lanes_config(cfg.priority_lanes.clone());
+        processor
+            .pool_service
+            .set_selection_strategy(cfg.routing.credential_strategy);
     }
 
-    // 初始化 WebSocket 管理器
-    let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
+    // 使用传入的 WebSocket 管理器或创建新的
+    let ws_manager = ws_manager.unwrap_or_else(|| ws::build_ws_manager(config.as_ref()));
     let ws_stats = ws_manager.stats().clone();
 
     // 初始化热重载管理器
@@ -843,15 +994,39 @@ async fn run_server(
         };
 
     let logs_clone = logs.clone();
-    let db_clone = db.clone();
+
+    // 启动后首次凭证池同步，成功前就绪检查报告未就绪
+    let readiness = Arc::new(readiness::Readiness::new());
+    match (db.clone(), config_manager.clone()) {
+        (Some(sync_db), Some(cfg_manager)) => {
+            let readiness = readiness.clone();
+            let logs = logs.clone();
+            tokio::spawn(async move {
+                match sync_credential_pool_from_config(&sync_db, &cfg_manager, &logs).await {
+                    Ok(count) => {
+                        tracing::info!("[SERVER] 启动凭证池同步完成，共 {} 个凭证", count);
+                        readiness.mark_credentials_synced();
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "[SERVER] 启动凭证池同步失败，下次同步成功前服务保持未就绪: {}",
+                            e
+                        );
+                    }
+                }
+            });
+        }
+        // 没有需要同步的凭证池
+        _ => readiness.mark_credentials_synced(),
+    }
 
     // 初始化 Amp CLI 路由器
-    let amp_router = Arc::new(crate::router::AmpRouter::new(
+    let amp_router = Arc::new(parking_lot::RwLock::new(crate::router::AmpRouter::new(
         config
             .as_ref()
             .map(|c| c.ampcode.clone())
             .unwrap_or_default(),
-    ));
+    )));
 
     // 使用共享的 Flow 监控服务，如果没有则创建新的
     let flow_monitor = shared_flow_monitor
@@ -861,14 +1036,6 @@ async fn run_server(
     let flow_interceptor =
         shared_flow_interceptor.unwrap_or_else(|| Arc::new(FlowInterceptor::default()));
 
-    // 初始化端点 Provider 配置
-    let endpoint_providers = Arc::new(RwLock::new(
-        config
-            .as_ref()
-            .map(|c| c.endpoint_providers.clone())
-            .unwrap_or_default(),
-    ));
-
     // 创建 Kiro 事件服务
     let kiro_event_service = Arc::new(KiroEventService::new());
 
@@ -876,10 +1043,197 @@ async fn run_server(
     let api_key_service =
         Arc::new(crate::services::api_key_provider_service::ApiKeyProviderService::new());
 
+    // 初始化路由注册表
+    let route_registry = Arc::new(RwLock::new(build_route_registry(
+        db.as_ref(),
+        &config
+            .as_ref()
+            .map(|c| c.routing.route_api_keys.clone())
+            .unwrap_or_default(),
+    )));
+
+    // 周期性后台任务，run_server 返回时中止
+    let mut background_tasks = BackgroundTasks::default();
+
+    // 遥测持久化及定期清理
+    let telemetry_retention = config
+        .as_ref()
+        .map(|c| c.logging.telemetry.clone())
+        .unwrap_or_default();
+    let telemetry_db = db.clone().filter(|_| telemetry_retention.persist);
+    if let Some(telemetry_db) = telemetry_db.clone() {
+        background_tasks.push(spawn_telemetry_cleanup(telemetry_db, telemetry_retention));
+    }
+    let telemetry = crate::telemetry::TelemetryRecorder::spawn(crate::telemetry::TelemetrySinks {
+        stats: processor.stats.clone(),
+        tokens: processor.tokens.clone(),
+        request_logger: shared_logger.clone(),
+        db: telemetry_db.clone(),
+    });
+    background_tasks.push(spawn_latency_hint_sync(
+        processor.stats.clone(),
+        processor.pool_service.clone(),
+    ));
+
+    // 告警规则定期评估（热重载后的告警配置在下一轮生效）
+    {
+        let initial_alerts = config
+            .as_ref()
+            .map(|c| c.alerts.clone())
+            .unwrap_or_default();
+        let reload_manager = hot_reload_manager.clone();
+        background_tasks.push(
+            Arc::new(crate::services::alert_service::AlertService::new()).spawn(
+                move || match &reload_manager {
+                    Some(manager) => manager.config().alerts,
+                    None => initial_alerts.clone(),
+                },
+                crate::services::alert_service::TelemetryAlertSource {
+                    stats: processor.stats.clone(),
+                    tokens: processor.tokens.clone(),
+                    db: db.clone(),
+                },
+            ),
+        );
+    }
+
+    // OAuth Token 主动刷新
+    if let Some(refresh_db) = db.clone() {
+        let initial_token_refresh = config
+            .as_ref()
+            .map(|c| c.token_refresh.clone())
+            .unwrap_or_default();
+        let reload_manager = hot_reload_manager.clone();
+        background_tasks.push(
+            crate::services::token_refresh_scheduler::spawn_token_refresh_scheduler(
+                token_cache.clone(),
+                refresh_db,
+                move || match &reload_manager {
+                    Some(manager) => manager.config().token_refresh,
+                    None => initial_token_refresh.clone(),
+                },
+            ),
+        );
+    }
+
+    // 凭证过期预警
+    let expiry_monitor =
+        Arc::new(crate::services::credential_expiry_service::CredentialExpiryMonitor::new());
+    if let Some(expiry_db) = db.clone() {
+        let initial_expiry = config
+            .as_ref()
+            .map(|c| c.credential_expiry.clone())
+            .unwrap_or_default();
+        let reload_manager = hot_reload_manager.clone();
+        background_tasks.push(
+            crate::services::credential_expiry_service::spawn_credential_expiry_monitor(
+                expiry_monitor.clone(),
+                pool_service.clone(),
+                expiry_db,
+                move || match &reload_manager {
+                    Some(manager) => manager.config().credential_expiry,
+                    None => initial_expiry.clone(),
+                },
+            ),
+        );
+    }
+
+    // 凭证每日请求计数重置
+    if let Some(schedule_db) = db.clone() {
+        background_tasks.push(
+            crate::services::provider_pool_service::spawn_daily_usage_reset_task(
+                pool_service.clone(),
+                schedule_db,
+            ),
+        );
+    }
+
+    // 集群模式 leader 选举及 leader 专属的定期任务（修改后需重启）
+    if let (Some(cluster_db), Some(cfg)) = (db.clone(), config.as_ref()) {
+        background_tasks.extend(crate::services::cluster_service::spawn_cluster(
+            &cfg.cluster,
+            pool_service.clone(),
+            cluster_db,
+        ));
+    }
+
+    // 每日用量报告（基于持久化的遥测汇总）
+    if let Some(report_db) = telemetry_db.clone() {
+        let initial_reports = config
+            .as_ref()
+            .map(|c| c.reports.clone())
+            .unwrap_or_default();
+        let reload_manager = hot_reload_manager.clone();
+        background_tasks.push(crate::services::usage_report_service::spawn_daily_reporter(
+            report_db,
+            move || match &reload_manager {
+                Some(manager) => manager.config().reports,
+                None => initial_reports.clone(),
+            },
+        ));
+    }
+
+    // thoughtSignature 缓存（持久化到主数据库）
+    crate::session::configure_signature_store(
+        config
+            .as_ref()
+            .map(|c| c.signature_store.clone())
+            .unwrap_or_default(),
+        db.clone(),
+    );
+
+    // 会话对话持久化（HTTP 请求依赖 Flow 监控记录）
+    let session_recorder = match crate::session_files::SessionFileStorage::new() {
+        Ok(storage) => {
+            let initial_persistence = config
+                .as_ref()
+                .map(|c| c.session_persistence.clone())
+                .unwrap_or_default();
+            let reload_manager = hot_reload_manager.clone();
+            let recorder = Arc::new(crate::session_files::SessionRecorder::new(
+                storage,
+                move || match &reload_manager {
+                    Some(manager) => manager.config().session_persistence,
+                    None => initial_persistence.clone(),
+                },
+            ));
+            background_tasks.push(crate::session_files::transcript::spawn_transcript_recorder(
+                recorder.clone(),
+                flow_monitor.clone(),
+            ));
+            Some(recorder)
+        }
+        Err(e) => {
+            tracing::warn!("[SESSION] 会话目录不可用，对话持久化已禁用: {}", e);
+            None
+        }
+    };
+
+    // 模型目录由凭证池服务持有，凭证增删改时清除缓存
+    let model_catalog = pool_service.model_catalog();
+
+    // 配置重载器（文件监控和管理接口共用），保存引用供 Tauri 命令使用
+    let config_reloader = hot_reload_manager.clone().map(|manager| {
+        Arc::new(ConfigReloader {
+            manager,
+            processor: processor.clone(),
+            logs: logs.clone(),
+            db: db.clone(),
+            config_manager: config_manager.clone(),
+            route_registry: route_registry.clone(),
+            amp_router: amp_router.clone(),
+            readiness: readiness.clone(),
+            model_catalog: model_catalog.clone(),
+        })
+    });
+    *config_reloader_slot.write() = config_reloader.clone();
+
     let state = AppState {
         api_key: api_key.to_string(),
         base_url,
-        default_provider,
+        public_url: config
+            .as_ref()
+            .and_then(|c| c.server.normalized_public_url()),
         kiro: Arc::new(RwLock::new(kiro)),
         logs,
         kiro_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
@@ -888,19 +1242,25 @@ async fn run_server(
         pool_service,
         token_cache,
         db,
-        injector: Arc::new(RwLock::new(injector)),
-        injection_enabled: Arc::new(RwLock::new(injection_enabled)),
+        telemetry_db,
         processor: processor.clone(),
         ws_manager,
         ws_stats,
         hot_reload_manager: hot_reload_manager.clone(),
         request_logger: shared_logger,
-        amp_router,
+        telemetry,
+        amp_router: amp_router.clone(),
         flow_monitor,
         flow_interceptor,
-        endpoint_providers,
         kiro_event_service,
         api_key_service,
+        model_catalog,
+        route_registry: route_registry.clone(),
+        oauth_logins: Arc::new(crate::oauth::OAuthLoginManager::new()),
+        expiry_monitor,
+        session_recorder,
+        readiness: readiness.clone(),
+        config_reloader: config_reloader.clone(),
     };
 
     // ========== 开发模式：启动独立的 HTTP 桥接服务器 ==========
@@ -926,1468 +1286,220 @@ async fn run_server(
     }
 
     // 启动配置文件监控
-    let _file_watcher = if let Some(path) = config_path {
-        start_config_watcher(
-            path,
-            hot_reload_manager,
-            processor,
-            logs_clone,
-            db_clone,
-            config_manager,
-        )
-        .await
-    } else {
-        None
+    let _file_watcher = match (config_path, config_reloader) {
+        (Some(path), Some(reloader)) => start_config_watcher(path, reloader, logs_clone).await,
+        _ => None,
     };
 
-    // 设置请求体大小限制为 100MB，支持大型上下文请求（如 Claude Code 的 /compact 命令）
-    let body_limit = 100 * 1024 * 1024; // 100MB
+    let routes = build_router(&state, config.as_ref(), &base_path);
 
-    // 创建管理 API 路由（带认证中间件）
-    let management_config = config
+    // 绑定所有监听端口（任一端口绑定失败时不启动）
+    let listeners = config
         .as_ref()
-        .map(|c| c.remote_management.clone())
+        .map(|c| c.server.listeners.clone())
         .unwrap_or_default();
+    let additional_hosts = config
+        .as_ref()
+        .map(|c| c.server.additional_hosts.clone())
+        .unwrap_or_default();
+    let mut servers = Vec::new();
+    let main_app = routes.clone().with_state(state.clone());
+    for bind_host in std::iter::once(host).chain(additional_hosts.iter().map(String::as_str)) {
+        for listener in bind_listener(bind_host, port).await? {
+            servers.push((listener, main_app.clone()));
+        }
+    }
+    for listener in &listeners {
+        let listener_host = listener.host_or(host);
+        let listener_state = AppState {
+            api_key: listener
+                .api_key
+                .clone()
+                .unwrap_or_else(|| api_key.to_string()),
+            base_url: format!(
+                "http://{}:{}{}",
+                crate::config::url_host(listener_host),
+                listener.port,
+                base_path
+            ),
+            public_url: None,
+            ..state.clone()
+        };
+        let app =
+            crate::middleware::with_listener_routes(routes.clone(), &listener.routes, &base_path)
+                .with_state(listener_state);
+        for tcp_listener in bind_listener(listener_host, listener.port).await? {
+            servers.push((tcp_listener, app.clone()));
+        }
+        tracing::info!(
+            "[SERVER] 额外监听端口 {} ({}:{})，开放接口: {:?}",
+            listener.name.as_deref().unwrap_or("-"),
+            listener_host,
+            listener.port,
+            listener.routes
+        );
+    }
 
-    let management_routes = Router::new()
-        .route("/v0/management/status", get(handlers::management_status))
-        .route(
-            "/v0/management/credentials",
-            get(handlers::management_list_credentials),
-        )
+    // 关闭信号广播到所有监听端口
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(());
+    tokio::spawn(async move {
+        let _ = shutdown.await;
+        let _ = shutdown_tx.send(());
+    });
+
+    futures::future::try_join_all(servers.into_iter().map(|(listener, app)| {
+        let mut shutdown_rx = shutdown_rx.clone();
+        async move {
+            // 注入监听地址，重复请求合并按监听端口隔离
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<crate::middleware::ListenerAddr>(),
+            )
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_rx.changed().await;
+            })
+            .await
+        }
+    }))
+    .await?;
+
+    Ok(())
+}
+
+/// 组装所有 HTTP 路由
+///
+/// 模型 API 路由经过响应处理中间件（内容防护、文件引用、录制、后处理、SSE 心跳、请求头透传、优先级通道、响应信息头、去重），
+/// 音频、文件和管理类路由使用各自的请求体大小限制。
+fn build_router(state: &AppState, config: Option<&Config>, base_path: &str) -> Router<AppState> {
+    // 请求体大小限制（按路由组）：模型 API 默认 100MB，支持大型上下文请求（如 Claude Code 的 /compact 命令）
+    let body_limits = config
+        .map(|c| c.server.body_limits.clone())
+        .unwrap_or_default();
+    let api_body_limit = crate::config::BodyLimitsConfig::bytes(body_limits.api_mb);
+    let audio_body_limit = crate::config::BodyLimitsConfig::bytes(body_limits.audio_mb);
+
+    // 音频 API 路由（请求体为音频文件，使用单独的大小限制，不经过响应后处理）
+    let audio_routes = Router::new()
         .route(
-            "/v0/management/credentials",
-            post(handlers::management_add_credential),
+            "/v1/audio/transcriptions",
+            post(handlers::handle_audio_transcription),
         )
+        .route("/v1/audio/speech", post(handlers::handle_audio_speech));
+    let audio_routes = crate::middleware::with_body_limit(audio_routes, audio_body_limit);
+
+    // 文件 API 路由
+    let files_routes = Router::new()
         .route(
-            "/v0/management/config",
-            get(handlers::management_get_config),
+            "/v1/files",
+            get(handlers::list_files).post(handlers::upload_file),
         )
         .route(
-            "/v0/management/config",
-            axum::routing::put(handlers::management_update_config),
+            "/v1/files/:id",
+            get(handlers::get_file).delete(handlers::delete_file),
         )
-        .layer(crate::middleware::ManagementAuthLayer::new(
-            management_config,
-        ));
+        .route("/v1/files/:id/content", get(handlers::get_file_content));
+    let files_routes = crate::middleware::with_body_limit(files_routes, api_body_limit);
 
-    // Kiro凭证管理API路由
-    let kiro_api_routes = Router::new()
-        .route(
-            "/api/kiro/credentials/available",
-            get(handlers::get_available_credentials),
-        )
-        .route(
-            "/api/kiro/credentials/select",
-            post(handlers::select_credential),
-        )
-        .route(
-            "/api/kiro/credentials/:uuid/refresh",
-            axum::routing::put(handlers::refresh_credential),
-        )
-        .route(
-            "/api/kiro/credentials/:uuid/status",
-            get(handlers::get_credential_status),
-        );
-
-    // 凭证 API 路由（用于 aster Agent 集成）
-    let credentials_api_routes = Router::new()
-        .route("/v1/credentials/select", post(handlers::credentials_select))
-        .route(
-            "/v1/credentials/:uuid/token",
-            get(handlers::credentials_get_token),
-        );
-
-    let app = Router::new()
-        .route("/health", get(health))
-        .route("/v1/models", get(models))
-        .route("/v1/routes", get(list_routes))
-        .route("/v1/chat/completions", post(handlers::chat_completions))
-        .route("/v1/messages", post(handlers::anthropic_messages))
-        .route("/v1/messages/count_tokens", post(count_tokens))
-        // 图像生成 API 路由
+    let api_routes = Router::new()
+        .route("/health", get(health))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(readiness::readiness))
+        .route("/v1/models", get(handlers::list_models))
+        .route("/v1/models/*id", get(handlers::get_model))
+        .route("/v1/capabilities", get(handlers::list_capabilities))
+        .route("/v1/chat/completions", post(handlers::chat_completions))
+        .route("/v1/messages", post(handlers::anthropic_messages))
+        .route("/v1/messages/count_tokens", post(handlers::count_tokens))
+        .route("/v1/sessions/:id", get(handlers::get_session_transcript))
+        .route("/v1/sessions/:id/resume", post(handlers::resume_session))
+        // 图像生成 API 路由
         .route(
             "/v1/images/generations",
             post(handlers::handle_image_generation),
         )
-        // Gemini 原生协议路由
-        .route("/v1/gemini/*path", post(gemini_generate_content))
         // WebSocket 路由
-        .route("/v1/ws", get(handlers::ws_upgrade_handler))
-        .route("/ws", get(handlers::ws_upgrade_handler))
-        // 多供应商路由
-        .route(
-            "/:selector/v1/messages",
-            post(anthropic_messages_with_selector),
-        )
-        .route(
-            "/:selector/v1/chat/completions",
-            post(chat_completions_with_selector),
-        )
-        // Amp CLI 路由
-        .route(
-            "/api/provider/:provider/v1/chat/completions",
-            post(amp_chat_completions),
-        )
-        .route("/api/provider/:provider/v1/messages", post(amp_messages))
-        // Amp CLI 管理代理路由
-        .route(
-            "/api/auth/*path",
-            axum::routing::any(amp_management_proxy_auth),
-        )
-        .route(
-            "/api/user/*path",
-            axum::routing::any(amp_management_proxy_user),
-        )
-        // 管理 API 路由
-        .merge(management_routes)
-        // Kiro凭证管理API路由
-        .merge(kiro_api_routes)
-        // 凭证 API 路由（用于 aster Agent 集成）
-        .merge(credentials_api_routes)
-        .layer(DefaultBodyLimit::max(body_limit))
-        .with_state(state);
-
-    let addr: std::net::SocketAddr = format!("{host}:{port}")
-        .parse()
-        .map_err(|e| format!("无效的监听地址 {}:{} - {}", host, port, e))?;
-    
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .map_err(|e| {
-            format!(
-                "无法绑定到 {}:{}，错误: {}。请检查地址是否有效或端口是否被占用。",
-                host, port, e
-            )
-        })?;
-
-    tracing::info!("Server listening on {}", addr);
-
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown.await;
-        })
-        .await?;
-
-    Ok(())
-}
-
-async fn count_tokens(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Json(_request): Json<serde_json::Value>,
-) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        return e.into_response();
-    }
-
-    // Claude Code 需要这个端点，返回估算值
-    Json(serde_json::json!({
-        "input_tokens": 100
-    }))
-    .into_response()
-}
-
-/// Gemini 原生协议处理
-/// 路由: POST /v1/gemini/{model}:{method}
-/// 例如: /v1/gemini/gemini-3-pro-preview:generateContent
-async fn gemini_generate_content(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Path(path): Path<String>,
-    Json(request): Json<serde_json::Value>,
-) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        return e.into_response();
-    }
-
-    // 解析路径: {model}:{method}
-    // 例如: gemini-3-pro-preview:generateContent
-    let parts: Vec<&str> = path.splitn(2, ':').collect();
-    if parts.len() != 2 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("无效的路径格式: {}，期望格式: model:method", path)
-                }
-            })),
-        )
-            .into_response();
-    }
-
-    let model = parts[0];
-    let method = parts[1];
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[GEMINI] POST /v1/gemini/{} model={} method={}",
-            path, model, method
-        ),
-    );
-
-    // 目前只支持 generateContent 方法
-    if method != "generateContent" && method != "streamGenerateContent" {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("不支持的方法: {}，目前只支持 generateContent", method)
-                }
-            })),
-        )
-            .into_response();
-    }
-
-    let is_stream = method == "streamGenerateContent";
-
-    // 获取默认 provider
-    let default_provider = state.default_provider.read().await.clone();
-
-    // 尝试从凭证池中选择凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => state
-            .pool_service
-            .select_credential(db, &default_provider, Some(model))
-            .ok()
-            .flatten(),
-        None => None,
-    };
-
-    let cred = match credential {
-        Some(c) => c,
-        None => {
-            return (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for provider '{}'. Please add credentials in the Provider Pool.", default_provider)
-                    }
-                })),
-            )
-                .into_response();
-        }
-    };
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[GEMINI] 使用凭证: type={} name={:?} uuid={}",
-            cred.provider_type,
-            cred.name,
-            &cred.uuid[..8]
-        ),
-    );
-
-    // 调用 Antigravity Provider
-    match &cred.credential {
-        CredentialData::AntigravityOAuth {
-            creds_file_path,
-            project_id,
-        } => {
-            let mut antigravity = AntigravityProvider::new();
-            if let Err(e) = antigravity
-                .load_credentials_from_path(creds_file_path)
-                .await
-            {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": format!("加载 Antigravity 凭证失败: {}", e)
-                        }
-                    })),
-                )
-                    .into_response();
-            }
-
-            // 使用新的 validate_token() 方法检查 Token 状态
-            let validation_result = antigravity.validate_token();
-            tracing::info!(
-                "[Antigravity Gemini] Token 验证结果: {:?}",
-                validation_result
-            );
-
-            // 根据验证结果决定是否刷新
-            if validation_result.needs_refresh() {
-                tracing::info!("[Antigravity Gemini] Token 需要刷新，开始刷新...");
-                match antigravity.refresh_token_with_retry(3).await {
-                    Ok(new_token) => {
-                        tracing::info!(
-                            "[Antigravity Gemini] Token 刷新成功，新 token 长度: {}",
-                            new_token.len()
-                        );
-                    }
-                    Err(refresh_error) => {
-                        tracing::error!("[Antigravity Gemini] Token 刷新失败: {:?}", refresh_error);
-
-                        // 根据错误类型返回不同的状态码和消息
-                        let (status, message) = if refresh_error.requires_reauth() {
-                            (StatusCode::UNAUTHORIZED, refresh_error.user_message())
-                        } else {
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                refresh_error.user_message(),
-                            )
-                        };
-
-                        return (
-                            status,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "message": message
-                                }
-                            })),
-                        )
-                            .into_response();
-                    }
-                }
-            }
-
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if antigravity.project_id.is_none() {
-                // 如果凭证中没有 project_id，尝试从 API 获取或生成随机 ID
-                if let Err(e) = antigravity.discover_project().await {
-                    tracing::warn!("[Antigravity] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
-                    // 生成随机项目 ID
-                    let uuid = uuid::Uuid::new_v4();
-                    let bytes = uuid.as_bytes();
-                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
-                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
-                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
-                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
-                    let random_part: String = uuid.to_string()[..5].to_lowercase();
-                    antigravity.project_id = Some(format!("{}-{}-{}", adj, noun, random_part));
-                }
-            }
-
-            let proj_id = antigravity.project_id.clone().unwrap_or_else(|| {
-                // 最后的后备：生成随机 ID
-                let uuid = uuid::Uuid::new_v4();
-                format!("proxycast-{}", &uuid.to_string()[..8])
-            });
-
-            state
-                .logs
-                .write()
-                .await
-                .add("debug", &format!("[GEMINI] 使用 project_id: {}", proj_id));
-
-            // 构建 Antigravity 请求体
-            // 直接使用用户传入的 Gemini 格式请求，只添加必要的字段
-            let antigravity_request = build_gemini_native_request(&request, model, &proj_id);
-
-            state.logs.write().await.add(
-                "debug",
-                &format!(
-                    "[GEMINI] 请求体: {}",
-                    serde_json::to_string(&antigravity_request).unwrap_or_default()
-                ),
-            );
-
-            if is_stream {
-                // 流式响应 - 暂不支持，返回错误
-                return (
-                    StatusCode::NOT_IMPLEMENTED,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": "流式响应暂不支持，请使用 generateContent"
-                        }
-                    })),
-                )
-                    .into_response();
-            }
-
-            // 非流式响应
-            match antigravity
-                .call_api("generateContent", &antigravity_request)
-                .await
-            {
-                Ok(resp) => {
-                    state.logs.write().await.add(
-                        "info",
-                        &format!(
-                            "[GEMINI] 响应成功: {}",
-                            serde_json::to_string(&resp)
-                                .unwrap_or_default()
-                                .chars()
-                                .take(200)
-                                .collect::<String>()
-                        ),
-                    );
-
-                    // 直接返回 Gemini 格式响应
-                    Json(resp).into_response()
-                }
-                Err(api_err) => {
-                    state.logs.write().await.add(
-                        "error",
-                        &format!(
-                            "[GEMINI] 请求失败 (HTTP {}): {}",
-                            api_err.status_code, api_err.message
-                        ),
-                    );
-
-                    // 直接使用 AntigravityApiError 的状态码构建响应
-                    build_error_response_with_status(api_err.status_code, &api_err.to_string())
-                }
-            }
-        }
-        CredentialData::GeminiOAuth {
-            creds_file_path,
-            project_id,
-        } => {
-            // 使用 GeminiProvider 处理 Gemini CLI OAuth 凭证
-            let mut gemini = GeminiProvider::new();
-            if let Err(e) = gemini.load_credentials_from_path(creds_file_path).await {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": format!("加载 Gemini 凭证失败: {}", e)
-                        }
-                    })),
-                )
-                    .into_response();
-            }
-
-            // 检查并刷新 Token
-            if !gemini.is_token_valid() {
-                tracing::info!("[Gemini CLI] Token 需要刷新，开始刷新...");
-                match gemini.refresh_token_with_retry(3).await {
-                    Ok(new_token) => {
-                        tracing::info!(
-                            "[Gemini CLI] Token 刷新成功，新 token 长度: {}",
-                            new_token.len()
-                        );
-                    }
-                    Err(refresh_error) => {
-                        tracing::error!("[Gemini CLI] Token 刷新失败: {:?}", refresh_error);
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "message": format!("Token 刷新失败: {}", refresh_error)
-                                }
-                            })),
-                        )
-                            .into_response();
-                    }
-                }
-            }
-
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                gemini.project_id = Some(pid.clone());
-            } else if gemini.project_id.is_none() {
-                // 尝试从 API 获取项目 ID
-                if let Err(e) = gemini.discover_project().await {
-                    tracing::warn!("[Gemini CLI] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
-                    let uuid = uuid::Uuid::new_v4();
-                    let bytes = uuid.as_bytes();
-                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
-                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
-                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
-                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
-                    let random_part: String = uuid.to_string()[..5].to_lowercase();
-                    gemini.project_id = Some(format!("{}-{}-{}", adj, noun, random_part));
-                }
-            }
-
-            let proj_id = gemini.project_id.clone().unwrap_or_else(|| {
-                let uuid = uuid::Uuid::new_v4();
-                format!("proxycast-{}", &uuid.to_string()[..8])
-            });
-
-            state.logs.write().await.add(
-                "debug",
-                &format!("[GEMINI CLI] 使用 project_id: {}", proj_id),
-            );
-
-            // 构建 Gemini CLI 请求体
-            // Gemini CLI 使用 Cloud Code Assist 端点，不做模型名称映射
-            let gemini_request = build_gemini_cli_request(&request, model, &proj_id);
-
-            state.logs.write().await.add(
-                "debug",
-                &format!(
-                    "[GEMINI CLI] 请求体: {}",
-                    serde_json::to_string(&gemini_request).unwrap_or_default()
-                ),
-            );
-
-            if is_stream {
-                // 流式响应 - 暂不支持
-                return (
-                    StatusCode::NOT_IMPLEMENTED,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": "Gemini CLI 流式响应暂不支持，请使用 generateContent"
-                        }
-                    })),
-                )
-                    .into_response();
-            }
-
-            // 非流式响应
-            match gemini.call_api("generateContent", &gemini_request).await {
-                Ok(resp) => {
-                    state.logs.write().await.add(
-                        "info",
-                        &format!(
-                            "[GEMINI CLI] 响应成功: {}",
-                            serde_json::to_string(&resp)
-                                .unwrap_or_default()
-                                .chars()
-                                .take(200)
-                                .collect::<String>()
-                        ),
-                    );
-
-                    // 直接返回 Gemini 格式响应
-                    Json(resp).into_response()
-                }
-                Err(api_err) => {
-                    state
-                        .logs
-                        .write()
-                        .await
-                        .add("error", &format!("[GEMINI CLI] 请求失败: {}", api_err));
-
-                    build_error_response(&api_err.to_string())
-                }
-            }
-        }
-        _ => (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": "Gemini 原生协议只支持 Antigravity 或 Gemini CLI OAuth 凭证"
-                }
-            })),
-        )
-            .into_response(),
-    }
-}
-
-/// 列出所有可用路由
-async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
-    // 处理 base_url：检查 IP 是否有效（在当前网卡列表中或是特殊地址）
-    let display_base_url = {
-        // 从 base_url 中提取 host 部分
-        let url_parts: Vec<&str> = state.base_url.split("://").collect();
-        let host_port = if url_parts.len() > 1 {
-            url_parts[1]
-        } else {
-            &state.base_url
-        };
-        let host = host_port.split(':').next().unwrap_or("localhost");
-
-        // 检查是否需要替换 IP
-        let should_replace = if host == "0.0.0.0" || host == "127.0.0.1" || host == "localhost" {
-            // 0.0.0.0 需要替换为局域网 IP，127.0.0.1 和 localhost 保持不变
-            host == "0.0.0.0"
-        } else {
-            // 检查 IP 是否在当前网卡列表中
-            if let Ok(network_info) = crate::commands::network_cmd::get_network_info() {
-                !network_info.all_ips.contains(&host.to_string())
-            } else {
-                false
-            }
-        };
-
-        if should_replace {
-            // 获取局域网 IP 进行替换
-            // 优先选择 192.168.x.x 或 10.x.x.x 开头的 IP（真正的局域网 IP）
-            if let Ok(network_info) = crate::commands::network_cmd::get_network_info() {
-                let new_ip = network_info
-                    .all_ips
-                    .iter()
-                    .find(|ip| ip.starts_with("192.168.") || ip.starts_with("10."))
-                    .or_else(|| network_info.lan_ip.as_ref())
-                    .or_else(|| network_info.all_ips.first())
-                    .cloned()
-                    .unwrap_or_else(|| "localhost".to_string());
-                state.base_url.replace(host, &new_ip)
-            } else {
-                state.base_url.replace(host, "localhost")
-            }
-        } else {
-            state.base_url.clone()
-        }
-    };
-
-    let routes = match &state.db {
-        Some(db) => state
-            .pool_service
-            .get_available_routes(db, &display_base_url)
-            .unwrap_or_default(),
-        None => Vec::new(),
-    };
-
-    // 获取默认 Provider
-    let default_provider = state.default_provider.read().await.clone();
-
-    // 添加默认路由
-    let mut all_routes = vec![RouteInfo {
-        selector: "default".to_string(),
-        provider_type: default_provider.clone(),
-        credential_count: 1,
-        endpoints: vec![
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/messages".to_string(),
-                protocol: "claude".to_string(),
-                url: format!("{}/v1/messages", display_base_url),
-            },
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/chat/completions".to_string(),
-                protocol: "openai".to_string(),
-                url: format!("{}/v1/chat/completions", display_base_url),
-            },
-        ],
-        tags: vec!["默认".to_string()],
-        enabled: true,
-    }];
-    all_routes.extend(routes);
-
-    let response = RouteListResponse {
-        base_url: display_base_url,
-        default_provider,
-        routes: all_routes,
-    };
-
-    Json(response)
-}
-
-/// 带选择器的 Anthropic messages 处理
-async fn anthropic_messages_with_selector(
-    State(state): State<AppState>,
-    Path(selector): Path<String>,
-    headers: HeaderMap,
-    Json(request): Json<AnthropicMessagesRequest>,
-) -> Response {
-    // 使用 Anthropic 格式的认证验证
-    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!("Unauthorized request to /{}/v1/messages", selector),
-        );
-        return e.into_response();
-    }
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[REQ] POST /{}/v1/messages model={} stream={}",
-            selector, request.model, request.stream
-        ),
-    );
-
-    // 尝试解析凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => {
-            // 首先尝试按名称查找
-            if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
-                Some(cred)
-            }
-            // 然后尝试按 UUID 查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
-                Some(cred)
-            }
-            // 最后尝试按 provider 类型选择（不降级）
-            else if let Ok(Some(cred)) =
-                state
-                    .pool_service
-                    .select_credential(db, &selector, Some(&request.model))
-            {
-                Some(cred)
-            } else {
-                None
-            }
-        }
-        None => None,
-    };
-
-    match credential {
-        Some(cred) => {
-            state.logs.write().await.add(
-                "info",
-                &format!(
-                    "[ROUTE] Using credential: type={} name={:?} uuid={}",
-                    cred.provider_type,
-                    cred.name,
-                    &cred.uuid[..8]
-                ),
-            );
-
-            // 根据凭证类型调用相应的 Provider
-            // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
-        }
-        None => {
-            // 不再回退到默认 provider，直接返回错误
-            state.logs.write().await.add(
-                "error",
-                &format!(
-                    "[ROUTE] No available credentials for selector '{}', refusing to fallback",
-                    selector
-                ),
-            );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "type": "provider_unavailable",
-                        "message": format!("No available credentials for selector '{}'", selector)
-                    }
-                })),
-            )
-                .into_response()
-        }
-    }
-}
-
-/// 带选择器的 OpenAI chat completions 处理
-async fn chat_completions_with_selector(
-    State(state): State<AppState>,
-    Path(selector): Path<String>,
-    headers: HeaderMap,
-    Json(request): Json<ChatCompletionRequest>,
-) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!("Unauthorized request to /{}/v1/chat/completions", selector),
-        );
-        return e.into_response();
-    }
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[REQ] POST /{}/v1/chat/completions model={} stream={}",
-            selector, request.model, request.stream
-        ),
-    );
-
-    // 尝试解析凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => {
-            if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
-                Some(cred)
-            } else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
-                Some(cred)
-            } else if let Ok(Some(cred)) =
-                state
-                    .pool_service
-                    .select_credential(db, &selector, Some(&request.model))
-            {
-                Some(cred)
-            } else {
-                None
-            }
-        }
-        None => None,
-    };
-
-    match credential {
-        Some(cred) => {
-            state.logs.write().await.add(
-                "info",
-                &format!(
-                    "[ROUTE] Using credential: type={} name={:?} uuid={}",
-                    cred.provider_type,
-                    cred.name,
-                    &cred.uuid[..8]
-                ),
-            );
-
-            // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
-        }
-        None => {
-            // 不再回退到默认 provider，直接返回错误
-            state.logs.write().await.add(
-                "error",
-                &format!(
-                    "[ROUTE] No available credentials for selector '{}', refusing to fallback",
-                    selector
-                ),
-            );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for selector '{}'", selector),
-                        "type": "provider_unavailable",
-                        "code": "no_credentials"
-                    }
-                })),
-            )
-                .into_response()
-        }
-    }
-}
-
-// ============ Amp CLI 路由处理 ============
-
-/// Amp CLI chat completions 处理
-///
-/// 处理 `/api/provider/:provider/v1/chat/completions` 路由
-/// 支持模型映射，将不可用模型映射到可用替代
-async fn amp_chat_completions(
-    State(state): State<AppState>,
-    Path(provider): Path<String>,
-    headers: HeaderMap,
-    Json(mut request): Json<ChatCompletionRequest>,
-) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!(
-                "Unauthorized request to /api/provider/{}/v1/chat/completions",
-                provider
-            ),
-        );
-        return e.into_response();
-    }
-
-    // 应用模型映射
-    let original_model = request.model.clone();
-    let mapped_model = state.amp_router.apply_model_mapping(&request.model);
-    if mapped_model != original_model {
-        state.logs.write().await.add(
-            "info",
-            &format!(
-                "[AMP] Model mapping applied: {} -> {}",
-                original_model, mapped_model
-            ),
-        );
-        request.model = mapped_model;
-    }
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[AMP] POST /api/provider/{}/v1/chat/completions model={} stream={}",
-            provider, request.model, request.stream
-        ),
+        .merge(ws::routes())
+        // 多供应商、Amp CLI、Gemini 原生协议路由
+        .merge(provider_dispatch::routes());
+    // 内容防护位于文件引用内联之内，检查内联后的完整内容
+    let api_routes =
+        crate::middleware::with_guardrails(api_routes, state.processor.clone(), api_body_limit);
+    let api_routes = crate::session_files::uploads::with_file_references(
+        api_routes,
+        state.session_recorder.clone(),
     );
-
-    // 尝试根据 provider 名称选择凭证
-    eprintln!(
-        "[AMP] 开始查找凭证: provider={}, model={}, db={}",
-        provider,
-        request.model,
-        state.db.is_some()
-    );
-    let credential = match &state.db {
-        Some(db) => {
-            eprintln!(
-                "[AMP] 使用 select_credential 查找凭证（Provider Pool）: provider={}",
-                provider
-            );
-            // 先尝试从 Provider Pool 查找
-            let pool_cred = if let Ok(Some(cred)) =
-                state
-                    .pool_service
-                    .select_credential(db, &provider, Some(&request.model))
-            {
-                eprintln!("[AMP] select_credential 找到凭证: {:?}", cred.name);
-                Some(cred)
-            }
-            // 然后尝试按名称查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &provider) {
-                eprintln!("[AMP] get_by_name 找到凭证: {:?}", cred.name);
-                Some(cred)
-            }
-            // 最后尝试按 UUID 查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &provider) {
-                eprintln!("[AMP] get_by_uuid 找到凭证: {:?}", cred.name);
-                Some(cred)
-            } else {
-                None
-            };
-
-            // 如果 Provider Pool 中没有找到，尝试从 API Key Provider 查找
-            if pool_cred.is_none() {
-                eprintln!(
-                    "[AMP] Provider Pool 中未找到凭证，尝试 API Key Provider: provider={}",
-                    provider
-                );
-
-                match state.api_key_service.get_fallback_credential(
-                    db,
-                    &crate::models::provider_pool_model::PoolProviderType::OpenAI,
-                    Some(&provider),
-                ) {
-                    Ok(Some(cred)) => {
-                        eprintln!(
-                            "[AMP] 通过 provider_id '{}' 找到 API Key Provider 凭证: name={:?}",
-                            provider, cred.name
-                        );
-                        Some(cred)
-                    }
-                    Ok(None) => {
-                        eprintln!("[AMP] 未找到任何凭证 for provider '{}'", provider);
-                        None
-                    }
-                    Err(e) => {
-                        eprintln!("[AMP] 查找 API Key Provider 凭证时出错: {}", e);
-                        None
-                    }
-                }
-            } else {
-                pool_cred
-            }
-        }
-        None => {
-            eprintln!("[AMP] 数据库未初始化");
-            None
-        }
-    };
-
-    match credential {
-        Some(cred) => {
-            state.logs.write().await.add(
-                "info",
-                &format!(
-                    "[AMP] Using credential: type={} name={:?} uuid={}",
-                    cred.provider_type,
-                    cred.name,
-                    &cred.uuid[..8]
-                ),
-            );
-            // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
-        }
-        None => {
-            // 不再回退到默认 provider，直接返回错误
-            state.logs.write().await.add(
-                "error",
-                &format!(
-                    "[AMP] No available credentials for provider '{}', refusing to fallback",
-                    provider
-                ),
-            );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for provider '{}'", provider),
-                        "type": "provider_unavailable",
-                        "code": "no_credentials"
-                    }
-                })),
-            )
-                .into_response()
-        }
-    }
-}
-
-/// Amp CLI messages 处理
-///
-/// 处理 `/api/provider/:provider/v1/messages` 路由
-/// 支持模型映射，将不可用模型映射到可用替代
-async fn amp_messages(
-    State(state): State<AppState>,
-    Path(provider): Path<String>,
-    headers: HeaderMap,
-    Json(mut request): Json<AnthropicMessagesRequest>,
-) -> Response {
-    // 使用 Anthropic 格式的认证验证
-    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!(
-                "Unauthorized request to /api/provider/{}/v1/messages",
-                provider
-            ),
-        );
-        return e.into_response();
-    }
-
-    // 应用模型映射
-    let original_model = request.model.clone();
-    let mapped_model = state.amp_router.apply_model_mapping(&request.model);
-    if mapped_model != original_model {
-        state.logs.write().await.add(
-            "info",
-            &format!(
-                "[AMP] Model mapping applied: {} -> {}",
-                original_model, mapped_model
-            ),
-        );
-        request.model = mapped_model;
-    }
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[AMP] POST /api/provider/{}/v1/messages model={} stream={}",
-            provider, request.model, request.stream
-        ),
+    let recording_config = config.map(|c| c.recording.clone()).unwrap_or_default();
+    let api_routes = crate::recording::with_recording(api_routes, &recording_config);
+    let api_routes = crate::postprocess::with_response_processing(
+        api_routes,
+        state.processor.response_processor.clone(),
     );
+    let heartbeat_config = config.map(|c| c.heartbeat.clone()).unwrap_or_default();
+    let api_routes =
+        crate::middleware::with_sse_heartbeat(api_routes, heartbeat_config.sse_interval_secs);
+    let header_passthrough_config = config
+        .map(|c| c.header_passthrough.clone())
+        .unwrap_or_default();
+    let api_routes =
+        crate::middleware::with_header_passthrough(api_routes, &header_passthrough_config);
+    let priority_lanes_config = config.map(|c| c.priority_lanes.clone()).unwrap_or_default();
+    let api_routes = crate::middleware::with_priority_lanes(api_routes, &priority_lanes_config);
+    let response_info_config = config
+        .map(|c| c.response_info_headers.clone())
+        .unwrap_or_default();
+    let api_routes =
+        crate::middleware::with_response_info_headers(api_routes, &response_info_config);
+    let sse_resume_config = config.map(|c| c.sse_resume.clone()).unwrap_or_default();
+    let api_routes = crate::middleware::with_sse_resume(api_routes, &sse_resume_config);
+    let dedupe_config = config.map(|c| c.server.dedupe.clone()).unwrap_or_default();
+    let api_routes = crate::middleware::with_request_dedupe(api_routes, &dedupe_config);
+    let response_signing_config = config
+        .map(|c| c.response_signing.clone())
+        .unwrap_or_default();
+    let api_routes = crate::middleware::with_response_signing(api_routes, &response_signing_config);
 
-    // 尝试根据 provider 名称选择凭证
-    let credential = match &state.db {
-        Some(db) => {
-            // 先尝试从 Provider Pool 查找
-            let pool_cred = if let Ok(Some(cred)) =
-                state
-                    .pool_service
-                    .select_credential(db, &provider, Some(&request.model))
-            {
-                Some(cred)
-            }
-            // 然后尝试按名称查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &provider) {
-                Some(cred)
-            }
-            // 最后尝试按 UUID 查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &provider) {
-                Some(cred)
-            } else {
-                None
-            };
-
-            // 如果 Provider Pool 中没有找到，尝试从 API Key Provider 查找
-            if pool_cred.is_none() {
-                eprintln!(
-                    "[AMP_MESSAGES] Provider Pool 中未找到凭证，尝试 API Key Provider: provider={}",
-                    provider
-                );
-
-                match state.api_key_service.get_fallback_credential(
-                    db,
-                    &crate::models::provider_pool_model::PoolProviderType::Anthropic,
-                    Some(&provider),
-                ) {
-                    Ok(Some(cred)) => {
-                        eprintln!(
-                            "[AMP_MESSAGES] 通过 provider_id '{}' 找到 API Key Provider 凭证: name={:?}",
-                            provider, cred.name
-                        );
-                        Some(cred)
-                    }
-                    Ok(None) => {
-                        eprintln!("[AMP_MESSAGES] 未找到任何凭证 for provider '{}'", provider);
-                        None
-                    }
-                    Err(e) => {
-                        eprintln!("[AMP_MESSAGES] 查找 API Key Provider 凭证时出错: {}", e);
-                        None
-                    }
-                }
-            } else {
-                pool_cred
-            }
-        }
-        None => None,
-    };
-
-    match credential {
-        Some(cred) => {
-            state.logs.write().await.add(
-                "info",
-                &format!(
-                    "[AMP] Using credential: type={} name={:?} uuid={}",
-                    cred.provider_type,
-                    cred.name,
-                    &cred.uuid[..8]
-                ),
-            );
-            // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
-        }
-        None => {
-            // 不再回退到默认 provider，直接返回错误
-            state.logs.write().await.add(
-                "error",
-                &format!(
-                    "[AMP] No available credentials for provider '{}', refusing to fallback",
-                    provider
-                ),
-            );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "type": "provider_unavailable",
-                        "message": format!("No available credentials for provider '{}'", provider)
-                    }
-                })),
-            )
-                .into_response()
-        }
+    let management_config = config
+        .map(|c| c.remote_management.clone())
+        .unwrap_or_default();
+    let routes = crate::middleware::with_body_limit(api_routes, api_body_limit)
+        // 音频 API 路由
+        .merge(audio_routes)
+        // 文件 API 路由
+        .merge(files_routes)
+        // 管理 API、Kiro 凭证管理、凭证 API 和 Amp CLI 管理代理路由
+        .merge(management::routes(management_config, &body_limits));
+    let request_id_config = config.map(|c| c.request_id.clone()).unwrap_or_default();
+    let routes = crate::middleware::with_request_id(routes, &request_id_config);
+    if base_path.is_empty() {
+        routes
+    } else {
+        tracing::info!("[SERVER] 路由路径前缀: {}", base_path);
+        Router::new().nest(base_path, routes)
     }
 }
 
-/// Amp CLI 管理代理 - auth 路由
-///
-/// 处理 `/api/auth/*` 路由，将请求代理到上游 URL
-async fn amp_management_proxy_auth(
-    State(state): State<AppState>,
-    Path(path): Path<String>,
-    headers: HeaderMap,
-    method: axum::http::Method,
-    body: axum::body::Bytes,
-) -> Response {
-    amp_management_proxy_internal(state, &format!("auth/{}", path), headers, method, body).await
-}
-
-/// Amp CLI 管理代理 - user 路由
-///
-/// 处理 `/api/user/*` 路由，将请求代理到上游 URL
-async fn amp_management_proxy_user(
-    State(state): State<AppState>,
-    Path(path): Path<String>,
-    headers: HeaderMap,
-    method: axum::http::Method,
-    body: axum::body::Bytes,
-) -> Response {
-    amp_management_proxy_internal(state, &format!("user/{}", path), headers, method, body).await
-}
-
-/// Amp CLI 管理代理内部实现
+/// 绑定监听地址
 ///
-/// 处理 `/api/auth/*` 和 `/api/user/*` 路由
-/// 将请求代理到上游 URL
-///
-/// # 参数
-/// - `path`: 请求路径（不含 /api/ 前缀，如 "auth/login" 或 "user/profile"）
-async fn amp_management_proxy_internal(
-    state: AppState,
-    path: &str,
-    headers: HeaderMap,
-    method: axum::http::Method,
-    body: axum::body::Bytes,
-) -> Response {
-    let full_path = format!("/api/{}", path);
-
-    // 检查是否是管理路由
-    if !state.amp_router.is_management_route(&full_path) {
-        state.logs.write().await.add(
-            "warn",
-            &format!("[AMP] Invalid management route: {}", full_path),
-        );
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": {"message": "Not found"}})),
-        )
-            .into_response();
-    }
-
-    // 检查 localhost 限制
-    if state.amp_router.restrict_management_to_localhost() {
-        // 从 headers 中获取客户端 IP
-        let client_ip = headers
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.split(',').next().unwrap_or("").trim().to_string())
-            .or_else(|| {
-                headers
-                    .get("x-real-ip")
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string())
-            });
-
-        if let Some(ip) = &client_ip {
-            let is_localhost = ip == "127.0.0.1" || ip == "::1" || ip == "localhost";
-            if !is_localhost {
-                state.logs.write().await.add(
-                    "warn",
-                    &format!("[AMP] Management proxy blocked from non-localhost: {}", ip),
-                );
-                return (
-                    StatusCode::FORBIDDEN,
-                    Json(serde_json::json!({"error": {"message": "Management endpoints are restricted to localhost"}})),
-                )
-                    .into_response();
+/// `localhost` 同时绑定 `127.0.0.1` 和 `::1`，系统未启用 IPv6 时只绑定 IPv4。
+async fn bind_listener(host: &str, port: u16) -> Result<Vec<tokio::net::TcpListener>, String> {
+    let addrs = crate::config::resolve_bind_addrs(host, port)?;
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => {
+                tracing::info!("Server listening on {}", addr);
+                listeners.push(listener);
             }
-        }
-    }
-
-    // 获取上游 URL
-    let upstream_url = match state.amp_router.get_management_upstream_path(&full_path) {
-        Some(url) => url,
-        None => {
-            state.logs.write().await.add(
-                "warn",
-                "[AMP] No upstream URL configured for management proxy",
-            );
-            return (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({"error": {"message": "Upstream URL not configured"}})),
-            )
-                .into_response();
-        }
-    };
-
-    state.logs.write().await.add(
-        "info",
-        &format!(
-            "[AMP] Proxying management request: {} {} -> {}",
-            method, full_path, upstream_url
-        ),
-    );
-
-    // 创建 HTTP 客户端
-    let client = reqwest::Client::new();
-
-    // 构建请求
-    let mut request_builder = match method {
-        axum::http::Method::GET => client.get(&upstream_url),
-        axum::http::Method::POST => client.post(&upstream_url),
-        axum::http::Method::PUT => client.put(&upstream_url),
-        axum::http::Method::DELETE => client.delete(&upstream_url),
-        axum::http::Method::PATCH => client.patch(&upstream_url),
-        axum::http::Method::HEAD => client.head(&upstream_url),
-        axum::http::Method::OPTIONS => client.request(reqwest::Method::OPTIONS, &upstream_url),
-        _ => {
-            return (
-                StatusCode::METHOD_NOT_ALLOWED,
-                Json(serde_json::json!({"error": {"message": "Method not allowed"}})),
-            )
-                .into_response();
-        }
-    };
-
-    // 复制请求头（排除 host 和 content-length）
-    for (name, value) in headers.iter() {
-        let name_str = name.as_str().to_lowercase();
-        if name_str != "host" && name_str != "content-length" {
-            if let Ok(value_str) = value.to_str() {
-                request_builder = request_builder.header(name.as_str(), value_str);
-            }
-        }
-    }
-
-    // 添加请求体
-    if !body.is_empty() {
-        request_builder = request_builder.body(body.to_vec());
-    }
-
-    // 发送请求
-    match request_builder.send().await {
-        Ok(response) => {
-            let status = response.status();
-            let response_headers = response.headers().clone();
-
-            match response.bytes().await {
-                Ok(response_body) => {
-                    let mut builder = Response::builder().status(status.as_u16());
-
-                    // 复制响应头
-                    for (name, value) in response_headers.iter() {
-                        let name_str = name.as_str().to_lowercase();
-                        // 排除 transfer-encoding 和 content-length（axum 会自动处理）
-                        if name_str != "transfer-encoding" && name_str != "content-length" {
-                            builder = builder.header(name.as_str(), value.to_str().unwrap_or(""));
-                        }
-                    }
-
-                    builder
-                        .body(Body::from(response_body.to_vec()))
-                        .unwrap_or_else(|_| {
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
-                            )
-                                .into_response()
-                        })
-                }
-                Err(e) => {
-                    state.logs.write().await.add(
-                        "error",
-                        &format!("[AMP] Failed to read upstream response: {}", e),
-                    );
-                    (
-                        StatusCode::BAD_GATEWAY,
-                        Json(serde_json::json!({"error": {"message": format!("Failed to read upstream response: {}", e)}})),
-                    )
-                        .into_response()
-                }
-            }
-        }
-        Err(e) => {
-            state.logs.write().await.add(
-                "error",
-                &format!("[AMP] Failed to proxy request to upstream: {}", e),
-            );
-            (
-                StatusCode::BAD_GATEWAY,
-                Json(serde_json::json!({"error": {"message": format!("Failed to connect to upstream: {}", e)}})),
-            )
-                .into_response()
-        }
-    }
-}
-
-/// 内部 Anthropic messages 处理 (使用默认 Kiro)
-/// 预留：用于内部直接调用 Kiro API
-#[allow(dead_code)]
-async fn anthropic_messages_internal(
-    state: &AppState,
-    request: &AnthropicMessagesRequest,
-) -> Response {
-    // 检查 token
-    {
-        let _guard = state.kiro_refresh_lock.lock().await;
-        let mut kiro = state.kiro.write().await;
-        let needs_refresh =
-            kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
-        if needs_refresh {
-            if let Err(e) = kiro.refresh_token().await {
-                state
-                    .logs
-                    .write()
-                    .await
-                    .add("error", &format!("[AUTH] Token refresh failed: {e}"));
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
-                )
-                    .into_response();
-            }
-        }
-    }
-
-    let openai_request = convert_anthropic_to_openai(request);
-    let kiro = state.kiro.read().await;
-
-    match kiro.call_api(&openai_request).await {
-        Ok(resp) => {
-            let status = resp.status();
-            if status.is_success() {
-                match resp.bytes().await {
-                    Ok(bytes) => {
-                        let body = String::from_utf8_lossy(&bytes).to_string();
-                        let parsed = parse_cw_response(&body);
-                        if request.stream {
-                            build_anthropic_stream_response(&request.model, &parsed)
-                        } else {
-                            build_anthropic_response(&request.model, &parsed)
-                        }
-                    }
-                    Err(e) => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response(),
-                }
-            } else {
-                let body = resp.text().await.unwrap_or_default();
-                (
-                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}})),
-                )
-                    .into_response()
-            }
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-        )
-            .into_response(),
-    }
-}
-
-/// 内部 OpenAI chat completions 处理 (使用默认 Kiro)
-/// 预留：用于内部直接调用 Kiro API
-#[allow(dead_code)]
-async fn chat_completions_internal(state: &AppState, request: &ChatCompletionRequest) -> Response {
-    {
-        let _guard = state.kiro_refresh_lock.lock().await;
-        let mut kiro = state.kiro.write().await;
-        let needs_refresh =
-            kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
-        if needs_refresh {
-            if let Err(e) = kiro.refresh_token().await {
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
-                )
-                    .into_response();
+            Err(e) if addr.is_ipv6() && !listeners.is_empty() => {
+                tracing::warn!("[SERVER] 跳过 IPv6 地址 {}：{}", addr, e);
             }
-        }
-    }
-
-    let kiro = state.kiro.read().await;
-    match kiro.call_api(request).await {
-        Ok(resp) => {
-            let status = resp.status();
-            if status.is_success() {
-                match resp.text().await {
-                    Ok(body) => {
-                        let parsed = parse_cw_response(&body);
-                        let has_tool_calls = !parsed.tool_calls.is_empty();
-
-                        let message = if has_tool_calls {
-                            serde_json::json!({
-                                "role": "assistant",
-                                "content": if parsed.content.is_empty() { serde_json::Value::Null } else { serde_json::json!(parsed.content) },
-                                "tool_calls": parsed.tool_calls.iter().map(|tc| {
-                                    serde_json::json!({
-                                        "id": tc.id,
-                                        "type": "function",
-                                        "function": {
-                                            "name": tc.function.name,
-                                            "arguments": tc.function.arguments
-                                        }
-                                    })
-                                }).collect::<Vec<_>>()
-                            })
-                        } else {
-                            serde_json::json!({
-                                "role": "assistant",
-                                "content": parsed.content
-                            })
-                        };
-
-                        let response = serde_json::json!({
-                            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
-                            "object": "chat.completion",
-                            "created": std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs(),
-                            "model": request.model,
-                            "choices": [{
-                                "index": 0,
-                                "message": message,
-                                "finish_reason": if has_tool_calls { "tool_calls" } else { "stop" }
-                            }],
-                            "usage": {
-                                "prompt_tokens": 0,
-                                "completion_tokens": 0,
-                                "total_tokens": 0
-                            }
-                        });
-                        Json(response).into_response()
-                    }
-                    Err(e) => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response(),
-                }
-            } else {
-                let body = resp.text().await.unwrap_or_default();
-                (
-                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}})),
-                )
-                    .into_response()
+            Err(e) => {
+                return Err(format!(
+                    "无法绑定到 {}，错误: {}。请检查地址是否有效或端口是否被占用。",
+                    addr, e
+                ));
             }
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-        )
-            .into_response(),
     }
+    Ok(listeners)
 }
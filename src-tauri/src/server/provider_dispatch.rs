@@ -0,0 +1,1363 @@
+//! Provider 分发路由
+//!
+//! 按路径中的选择器 / Provider 名称直接选择凭证并转发请求（不经过默认 Provider 路由）：
+//! - `/:selector/v1/*`：命名空间路由、凭证名称、UUID 或凭证标签（`tag:<name>`）
+//! - `/api/provider/:provider/v1/*`：Amp CLI 路由（支持模型映射）
+//! - `/v1/gemini/*`：Gemini 原生协议
+//! - `/v1/routes`：列出可用路由
+
+use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::middleware::request_id::current_request_id;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use crate::models::provider_pool_model::CredentialData;
+use crate::models::route_model::{RouteInfo, RouteListResponse};
+use crate::providers::antigravity::AntigravityProvider;
+use crate::providers::gemini::GeminiProvider;
+use crate::router::RegisteredRoute;
+use crate::server::handlers;
+use crate::server::response_validation::{validate_upstream_response, ResponseSchema};
+use crate::server::validation::ValidatedJson;
+use crate::server::AppState;
+use crate::server_utils::{
+    build_anthropic_response, build_anthropic_stream_response, build_error_response,
+    build_error_response_with_status, build_gemini_cli_request, build_gemini_native_request,
+    ensure_openai_stream, parse_cw_response,
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+
+/// Provider 分发路由表
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/routes", get(list_routes))
+        // Gemini 原生协议路由
+        .route("/v1/gemini/*path", post(gemini_generate_content))
+        // 多供应商路由
+        .route(
+            "/:selector/v1/messages",
+            post(anthropic_messages_with_selector),
+        )
+        .route(
+            "/:selector/v1/chat/completions",
+            post(chat_completions_with_selector),
+        )
+        // Amp CLI 路由
+        .route(
+            "/api/provider/:provider/v1/chat/completions",
+            post(amp_chat_completions),
+        )
+        .route("/api/provider/:provider/v1/messages", post(amp_messages))
+}
+
+/// Gemini 原生协议处理
+/// 路由: POST /v1/gemini/{model}:{method}
+/// 例如: /v1/gemini/gemini-3-pro-preview:generateContent
+async fn gemini_generate_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(path): Path<String>,
+    Json(request): Json<serde_json::Value>,
+) -> Response {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+
+    // 解析路径: {model}:{method}
+    // 例如: gemini-3-pro-preview:generateContent
+    let parts: Vec<&str> = path.splitn(2, ':').collect();
+    if parts.len() != 2 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": {
+                    "message": format!("无效的路径格式: {}，期望格式: model:method", path)
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    let model = parts[0];
+    let method = parts[1];
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[GEMINI] POST /v1/gemini/{} model={} method={}",
+            path, model, method
+        ),
+    );
+
+    // 目前只支持 generateContent 方法
+    if method != "generateContent" && method != "streamGenerateContent" {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": {
+                    "message": format!("不支持的方法: {}，目前只支持 generateContent", method)
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    let is_stream = method == "streamGenerateContent";
+
+    // 获取默认 provider（API Key 配置优先）
+    let default_provider =
+        state
+            .processor
+            .resolve_default_provider(None, handlers::presented_api_key(&headers), None);
+
+    // 尝试从凭证池中选择凭证（不降级，指定什么就用什么）
+    let credential = match &state.db {
+        Some(db) => state
+            .pool_service
+            .select_credential(db, &default_provider, Some(model))
+            .ok()
+            .flatten(),
+        None => None,
+    };
+
+    let cred = match credential {
+        Some(c) => c,
+        None => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("No available credentials for provider '{}'. Please add credentials in the Provider Pool.", default_provider)
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[GEMINI] 使用凭证: type={} name={:?} uuid={}",
+            cred.provider_type,
+            cred.name,
+            &cred.uuid[..8]
+        ),
+    );
+
+    // 调用 Antigravity Provider
+    match &cred.credential {
+        CredentialData::AntigravityOAuth {
+            creds_file_path,
+            project_id,
+        } => {
+            let mut antigravity = AntigravityProvider::new();
+            if let Err(e) = antigravity
+                .load_credentials_from_path(creds_file_path)
+                .await
+            {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": format!("加载 Antigravity 凭证失败: {}", e)
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+
+            // 使用新的 validate_token() 方法检查 Token 状态
+            let validation_result = antigravity.validate_token();
+            tracing::info!(
+                "[Antigravity Gemini] Token 验证结果: {:?}",
+                validation_result
+            );
+
+            // 根据验证结果决定是否刷新
+            if validation_result.needs_refresh() {
+                tracing::info!("[Antigravity Gemini] Token 需要刷新，开始刷新...");
+                match antigravity.refresh_token_with_retry(3).await {
+                    Ok(new_token) => {
+                        tracing::info!(
+                            "[Antigravity Gemini] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
+                    }
+                    Err(refresh_error) => {
+                        tracing::error!("[Antigravity Gemini] Token 刷新失败: {:?}", refresh_error);
+
+                        // 根据错误类型返回不同的状态码和消息
+                        let (status, message) = if refresh_error.requires_reauth() {
+                            (StatusCode::UNAUTHORIZED, refresh_error.user_message())
+                        } else {
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                refresh_error.user_message(),
+                            )
+                        };
+
+                        return (
+                            status,
+                            Json(serde_json::json!({
+                                "error": {
+                                    "message": message
+                                }
+                            })),
+                        )
+                            .into_response();
+                    }
+                }
+            }
+
+            // 设置项目 ID
+            if let Some(pid) = project_id {
+                antigravity.project_id = Some(pid.clone());
+            } else if antigravity.project_id.is_none() {
+                // 如果凭证中没有 project_id，尝试从 API 获取或生成随机 ID
+                if let Err(e) = antigravity.discover_project().await {
+                    tracing::warn!("[Antigravity] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
+                    // 生成随机项目 ID
+                    let uuid = uuid::Uuid::new_v4();
+                    let bytes = uuid.as_bytes();
+                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
+                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
+                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
+                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
+                    let random_part: String = uuid.to_string()[..5].to_lowercase();
+                    antigravity.project_id = Some(format!("{}-{}-{}", adj, noun, random_part));
+                }
+            }
+
+            let proj_id = antigravity.project_id.clone().unwrap_or_else(|| {
+                // 最后的后备：生成随机 ID
+                let uuid = uuid::Uuid::new_v4();
+                format!("proxycast-{}", &uuid.to_string()[..8])
+            });
+
+            state
+                .logs
+                .write()
+                .await
+                .add("debug", &format!("[GEMINI] 使用 project_id: {}", proj_id));
+
+            // 构建 Antigravity 请求体
+            // 直接使用用户传入的 Gemini 格式请求，只添加必要的字段
+            let antigravity_request = build_gemini_native_request(&request, model, &proj_id);
+
+            state.logs.write().await.add(
+                "debug",
+                &format!(
+                    "[GEMINI] 请求体: {}",
+                    serde_json::to_string(&antigravity_request).unwrap_or_default()
+                ),
+            );
+
+            if is_stream {
+                // 流式响应 - 暂不支持，返回错误
+                return (
+                    StatusCode::NOT_IMPLEMENTED,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": "流式响应暂不支持，请使用 generateContent"
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+
+            // 非流式响应
+            match antigravity
+                .call_api("generateContent", &antigravity_request)
+                .await
+            {
+                Ok(resp) => {
+                    state.logs.write().await.add(
+                        "info",
+                        &format!(
+                            "[GEMINI] 响应成功: {}",
+                            serde_json::to_string(&resp)
+                                .unwrap_or_default()
+                                .chars()
+                                .take(200)
+                                .collect::<String>()
+                        ),
+                    );
+
+                    // 直接返回 Gemini 格式响应
+                    Json(resp).into_response()
+                }
+                Err(api_err) => {
+                    state.logs.write().await.add(
+                        "error",
+                        &format!(
+                            "[GEMINI] 请求失败 (HTTP {}): {}",
+                            api_err.status_code, api_err.message
+                        ),
+                    );
+
+                    // 直接使用 AntigravityApiError 的状态码构建响应
+                    build_error_response_with_status(api_err.status_code, &api_err.to_string())
+                }
+            }
+        }
+        CredentialData::GeminiOAuth {
+            creds_file_path,
+            project_id,
+        } => {
+            // 使用 GeminiProvider 处理 Gemini CLI OAuth 凭证
+            let mut gemini = GeminiProvider::new();
+            if let Err(e) = gemini.load_credentials_from_path(creds_file_path).await {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": format!("加载 Gemini 凭证失败: {}", e)
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+
+            // 检查并刷新 Token
+            if !gemini.is_token_valid() {
+                tracing::info!("[Gemini CLI] Token 需要刷新，开始刷新...");
+                match gemini.refresh_token_with_retry(3).await {
+                    Ok(new_token) => {
+                        tracing::info!(
+                            "[Gemini CLI] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
+                    }
+                    Err(refresh_error) => {
+                        tracing::error!("[Gemini CLI] Token 刷新失败: {:?}", refresh_error);
+                        return (
+                            StatusCode::UNAUTHORIZED,
+                            Json(serde_json::json!({
+                                "error": {
+                                    "message": format!("Token 刷新失败: {}", refresh_error)
+                                }
+                            })),
+                        )
+                            .into_response();
+                    }
+                }
+            }
+
+            // 设置项目 ID
+            if let Some(pid) = project_id {
+                gemini.project_id = Some(pid.clone());
+            } else if gemini.project_id.is_none() {
+                // 尝试从 API 获取项目 ID
+                if let Err(e) = gemini.discover_project().await {
+                    tracing::warn!("[Gemini CLI] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
+                    let uuid = uuid::Uuid::new_v4();
+                    let bytes = uuid.as_bytes();
+                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
+                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
+                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
+                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
+                    let random_part: String = uuid.to_string()[..5].to_lowercase();
+                    gemini.project_id = Some(format!("{}-{}-{}", adj, noun, random_part));
+                }
+            }
+
+            let proj_id = gemini.project_id.clone().unwrap_or_else(|| {
+                let uuid = uuid::Uuid::new_v4();
+                format!("proxycast-{}", &uuid.to_string()[..8])
+            });
+
+            state.logs.write().await.add(
+                "debug",
+                &format!("[GEMINI CLI] 使用 project_id: {}", proj_id),
+            );
+
+            // 构建 Gemini CLI 请求体
+            // Gemini CLI 使用 Cloud Code Assist 端点，不做模型名称映射
+            let gemini_request = build_gemini_cli_request(&request, model, &proj_id);
+
+            state.logs.write().await.add(
+                "debug",
+                &format!(
+                    "[GEMINI CLI] 请求体: {}",
+                    serde_json::to_string(&gemini_request).unwrap_or_default()
+                ),
+            );
+
+            if is_stream {
+                // 流式响应 - 暂不支持
+                return (
+                    StatusCode::NOT_IMPLEMENTED,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": "Gemini CLI 流式响应暂不支持，请使用 generateContent"
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+
+            // 非流式响应
+            match gemini.call_api("generateContent", &gemini_request).await {
+                Ok(resp) => {
+                    state.logs.write().await.add(
+                        "info",
+                        &format!(
+                            "[GEMINI CLI] 响应成功: {}",
+                            serde_json::to_string(&resp)
+                                .unwrap_or_default()
+                                .chars()
+                                .take(200)
+                                .collect::<String>()
+                        ),
+                    );
+
+                    // 直接返回 Gemini 格式响应
+                    Json(resp).into_response()
+                }
+                Err(api_err) => {
+                    state
+                        .logs
+                        .write()
+                        .await
+                        .add("error", &format!("[GEMINI CLI] 请求失败: {}", api_err));
+
+                    build_error_response(&api_err.to_string())
+                }
+            }
+        }
+        _ => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": {
+                    "message": "Gemini 原生协议只支持 Antigravity 或 Gemini CLI OAuth 凭证"
+                }
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// 列出所有可用路由
+async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
+    Json(route_list(&state))
+}
+
+/// 当前可用路由列表（含默认路由）
+pub(super) fn route_list(state: &AppState) -> RouteListResponse {
+    // 处理 base_url：检查 IP 是否有效（在当前网卡列表中或是特殊地址）
+    let display_base_url = {
+        // 从 base_url 中提取 host 部分
+        let url_parts: Vec<&str> = state.base_url.split("://").collect();
+        let host_port = if url_parts.len() > 1 {
+            url_parts[1]
+        } else {
+            &state.base_url
+        };
+        // IPv6 地址带方括号（如 `[::1]:8999`）
+        let host = if host_port.starts_with('[') {
+            host_port.split_inclusive(']').next().unwrap_or(host_port)
+        } else {
+            host_port.split(':').next().unwrap_or("localhost")
+        };
+
+        // 检查是否需要替换 IP
+        let should_replace = if matches!(
+            host,
+            "0.0.0.0" | "[::]" | "127.0.0.1" | "[::1]" | "localhost"
+        ) {
+            // 0.0.0.0 / :: 需要替换为局域网 IP，回环地址和 localhost 保持不变
+            host == "0.0.0.0" || host == "[::]"
+        } else {
+            // 检查 IP 是否在当前网卡列表中
+            if let Ok(network_info) = crate::services::network_service::get_network_info() {
+                let ip = host.trim_start_matches('[').trim_end_matches(']');
+                !network_info.all_ips.iter().any(|addr| addr == ip)
+            } else {
+                false
+            }
+        };
+
+        if should_replace {
+            // 获取局域网 IP 进行替换
+            // 优先选择 192.168.x.x 或 10.x.x.x 开头的 IP（真正的局域网 IP）
+            if let Ok(network_info) = crate::services::network_service::get_network_info() {
+                let new_ip = network_info
+                    .all_ips
+                    .iter()
+                    .find(|ip| ip.starts_with("192.168.") || ip.starts_with("10."))
+                    .or(network_info.lan_ip.as_ref())
+                    .or_else(|| network_info.all_ips.first())
+                    .cloned()
+                    .unwrap_or_else(|| "localhost".to_string());
+                state.base_url.replace(host, &new_ip)
+            } else {
+                state.base_url.replace(host, "localhost")
+            }
+        } else {
+            state.base_url.clone()
+        }
+    };
+    // 配置了对外访问地址时直接使用
+    let display_base_url = state.public_url.clone().unwrap_or(display_base_url);
+
+    let routes = match &state.db {
+        Some(db) => state
+            .pool_service
+            .get_available_routes(db, &display_base_url)
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    // 获取默认 Provider
+    let default_provider = state.processor.snapshot().default_provider.clone();
+
+    // 添加默认路由
+    let mut all_routes = vec![RouteInfo {
+        selector: "default".to_string(),
+        provider_type: default_provider.clone(),
+        credential_count: 1,
+        endpoints: vec![
+            crate::models::route_model::RouteEndpoint {
+                path: "/v1/messages".to_string(),
+                protocol: "claude".to_string(),
+                url: format!("{}/v1/messages", display_base_url),
+            },
+            crate::models::route_model::RouteEndpoint {
+                path: "/v1/chat/completions".to_string(),
+                protocol: "openai".to_string(),
+                url: format!("{}/v1/chat/completions", display_base_url),
+            },
+        ],
+        tags: vec!["默认".to_string()],
+        enabled: true,
+    }];
+    all_routes.extend(routes);
+
+    RouteListResponse {
+        base_url: display_base_url,
+        default_provider,
+        routes: all_routes,
+    }
+}
+
+/// 动态路由绑定的凭证 UUID
+fn dynamic_route_credential(route: Option<&RegisteredRoute>) -> Option<&str> {
+    route
+        .filter(|r| r.dynamic)
+        .and_then(|r| r.credential_uuid.as_deref())
+}
+
+/// 路由已禁用的错误响应
+fn route_disabled_response(selector: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({
+            "error": {
+                "type": "route_disabled",
+                "message": format!("Route '{}' is disabled", selector)
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 带选择器的 Anthropic messages 处理
+async fn anthropic_messages_with_selector(
+    State(state): State<AppState>,
+    Path(selector): Path<String>,
+    headers: HeaderMap,
+    ValidatedJson(mut request): ValidatedJson<AnthropicMessagesRequest>,
+) -> Response {
+    // 使用 Anthropic 格式的认证验证（全局 Key 或路由专属 Key）
+    let route_key = state
+        .route_registry
+        .read()
+        .await
+        .api_key_for(&selector)
+        .map(str::to_string);
+    let route_authorized = match route_key.as_deref() {
+        Some(key) => handlers::verify_api_key_anthropic(&headers, key)
+            .await
+            .is_ok(),
+        None => false,
+    };
+    if !route_authorized {
+        if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
+            state.logs.write().await.add(
+                "warn",
+                &format!("Unauthorized request to /{}/v1/messages", selector),
+            );
+            return e.into_response();
+        }
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[REQ] POST /{}/v1/messages model={} stream={}",
+            selector, request.model, request.stream
+        ),
+    );
+
+    // 已禁用的路由直接拒绝
+    let route = state
+        .route_registry
+        .read()
+        .await
+        .find_by_selector(&selector)
+        .cloned();
+    if matches!(&route, Some(r) if !r.enabled) {
+        return route_disabled_response(&selector);
+    }
+
+    // 内置 mock Provider
+    if crate::providers::mock::is_mock_provider(&selector) {
+        crate::recording::note_provider(crate::providers::mock::MOCK_PROVIDER_ID);
+        let mock = state.processor.mock_provider.read().await.clone();
+        return mock.anthropic_messages(&request).await;
+    }
+
+    // 尝试解析凭证（不降级，指定什么就用什么）
+    let credential = match &state.db {
+        Some(db) => {
+            // 动态注册的路由绑定到指定凭证
+            if let Some(uuid) = dynamic_route_credential(route.as_ref()) {
+                state.pool_service.get_by_uuid(db, uuid).ok().flatten()
+            }
+            // 首先尝试按名称查找
+            else if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
+                Some(cred)
+            }
+            // 然后尝试按 UUID 查找
+            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
+                Some(cred)
+            }
+            // 最后尝试按 provider 类型或 `tag:<name>` 标签选择（不降级）
+            else if let Ok(Some(cred)) =
+                state
+                    .pool_service
+                    .select_credential(db, &selector, Some(&request.model))
+            {
+                Some(cred)
+            }
+            // 未绑定凭证的命名空间：使用命名空间 / API Key 配置的默认 Provider
+            else if let Some(provider) = state
+                .processor
+                .scoped_default_provider(Some(&selector), handlers::presented_api_key(&headers))
+            {
+                state
+                    .pool_service
+                    .select_credential(db, &provider, Some(&request.model))
+                    .ok()
+                    .flatten()
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+
+    match credential {
+        Some(cred) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[ROUTE] Using credential: type={} name={:?} uuid={}",
+                    cred.provider_type,
+                    cred.name,
+                    &cred.uuid[..8]
+                ),
+            );
+
+            let unsupported = handlers::unsupported_server_tools(
+                &request,
+                handlers::supported_server_tools(&cred.credential, &request.model),
+            );
+            if !unsupported.is_empty() {
+                return handlers::server_tools_unsupported_response(&selector, &unsupported);
+            }
+
+            let adjustment = state
+                .processor
+                .clamp_max_tokens(&request.model, &mut request.max_tokens)
+                .await;
+
+            // 根据凭证类型调用相应的 Provider
+            // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
+            let response = match handlers::inject_chaos(&state, &cred).await {
+                Some(response) => response,
+                None => handlers::call_provider_anthropic(&state, &cred, &request, None).await,
+            };
+            let response = validate_upstream_response(
+                &state,
+                &current_request_id().unwrap_or_default(),
+                &cred,
+                ResponseSchema::AnthropicMessages,
+                response,
+            )
+            .await;
+            handlers::with_max_tokens_header(response, adjustment)
+        }
+        None => {
+            // 不再回退到默认 provider，直接返回错误
+            state.logs.write().await.add(
+                "error",
+                &format!(
+                    "[ROUTE] No available credentials for selector '{}', refusing to fallback",
+                    selector
+                ),
+            );
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": {
+                        "type": "provider_unavailable",
+                        "message": format!("No available credentials for selector '{}'", selector)
+                    }
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 带选择器的 OpenAI chat completions 处理
+async fn chat_completions_with_selector(
+    State(state): State<AppState>,
+    Path(selector): Path<String>,
+    headers: HeaderMap,
+    ValidatedJson(mut request): ValidatedJson<ChatCompletionRequest>,
+) -> Response {
+    // 全局 Key 或路由专属 Key
+    let route_key = state
+        .route_registry
+        .read()
+        .await
+        .api_key_for(&selector)
+        .map(str::to_string);
+    let route_authorized = match route_key.as_deref() {
+        Some(key) => handlers::verify_api_key(&headers, key).await.is_ok(),
+        None => false,
+    };
+    if !route_authorized {
+        if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+            state.logs.write().await.add(
+                "warn",
+                &format!("Unauthorized request to /{}/v1/chat/completions", selector),
+            );
+            return e.into_response();
+        }
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[REQ] POST /{}/v1/chat/completions model={} stream={}",
+            selector, request.model, request.stream
+        ),
+    );
+
+    // 已禁用的路由直接拒绝
+    let route = state
+        .route_registry
+        .read()
+        .await
+        .find_by_selector(&selector)
+        .cloned();
+    if matches!(&route, Some(r) if !r.enabled) {
+        return route_disabled_response(&selector);
+    }
+
+    // 内置 mock Provider
+    if crate::providers::mock::is_mock_provider(&selector) {
+        crate::recording::note_provider(crate::providers::mock::MOCK_PROVIDER_ID);
+        let mock = state.processor.mock_provider.read().await.clone();
+        return mock.chat_completions(&request).await;
+    }
+
+    // 尝试解析凭证（不降级，指定什么就用什么）
+    let credential = match &state.db {
+        Some(db) => {
+            if let Some(uuid) = dynamic_route_credential(route.as_ref()) {
+                state.pool_service.get_by_uuid(db, uuid).ok().flatten()
+            } else if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
+                Some(cred)
+            } else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
+                Some(cred)
+            } else if let Ok(Some(cred)) =
+                state
+                    .pool_service
+                    .select_credential(db, &selector, Some(&request.model))
+            {
+                Some(cred)
+            }
+            // 未绑定凭证的命名空间：使用命名空间 / API Key 配置的默认 Provider
+            else if let Some(provider) = state
+                .processor
+                .scoped_default_provider(Some(&selector), handlers::presented_api_key(&headers))
+            {
+                state
+                    .pool_service
+                    .select_credential(db, &provider, Some(&request.model))
+                    .ok()
+                    .flatten()
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+
+    match credential {
+        Some(cred) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[ROUTE] Using credential: type={} name={:?} uuid={}",
+                    cred.provider_type,
+                    cred.name,
+                    &cred.uuid[..8]
+                ),
+            );
+
+            let adjustment = state
+                .processor
+                .clamp_max_tokens(&request.model, &mut request.max_tokens)
+                .await;
+
+            // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
+            let mut response = match handlers::inject_chaos(&state, &cred).await {
+                Some(response) => response,
+                None => handlers::call_provider_openai(&state, &cred, &request, None).await,
+            };
+            response = validate_upstream_response(
+                &state,
+                &current_request_id().unwrap_or_default(),
+                &cred,
+                ResponseSchema::OpenAiChat,
+                response,
+            )
+            .await;
+            if request.stream {
+                response = ensure_openai_stream(response, request.include_usage()).await;
+            }
+            handlers::with_max_tokens_header(response, adjustment)
+        }
+        None => {
+            // 不再回退到默认 provider，直接返回错误
+            state.logs.write().await.add(
+                "error",
+                &format!(
+                    "[ROUTE] No available credentials for selector '{}', refusing to fallback",
+                    selector
+                ),
+            );
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("No available credentials for selector '{}'", selector),
+                        "type": "provider_unavailable",
+                        "code": "no_credentials"
+                    }
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+// ============ Amp CLI 路由处理 ============
+
+/// Amp CLI chat completions 处理
+///
+/// 处理 `/api/provider/:provider/v1/chat/completions` 路由
+/// 支持模型映射，将不可用模型映射到可用替代
+async fn amp_chat_completions(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    ValidatedJson(mut request): ValidatedJson<ChatCompletionRequest>,
+) -> Response {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+        state.logs.write().await.add(
+            "warn",
+            &format!(
+                "Unauthorized request to /api/provider/{}/v1/chat/completions",
+                provider
+            ),
+        );
+        return e.into_response();
+    }
+
+    // 应用模型映射
+    let original_model = request.model.clone();
+    let mapped_model = state
+        .amp_router
+        .read()
+        .apply_model_mapping_for(Some(&provider), &request.model);
+    if mapped_model != original_model {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[AMP] Model mapping applied: {} -> {}",
+                original_model, mapped_model
+            ),
+        );
+        request.model = mapped_model;
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AMP] POST /api/provider/{}/v1/chat/completions model={} stream={}",
+            provider, request.model, request.stream
+        ),
+    );
+
+    // 尝试根据 provider 名称选择凭证
+    eprintln!(
+        "[AMP] 开始查找凭证: provider={}, model={}, db={}",
+        provider,
+        request.model,
+        state.db.is_some()
+    );
+    let credential = match &state.db {
+        Some(db) => {
+            eprintln!(
+                "[AMP] 使用 select_credential 查找凭证（Provider Pool）: provider={}",
+                provider
+            );
+            // 先尝试从 Provider Pool 查找
+            let pool_cred = if let Ok(Some(cred)) =
+                state
+                    .pool_service
+                    .select_credential(db, &provider, Some(&request.model))
+            {
+                eprintln!("[AMP] select_credential 找到凭证: {:?}", cred.name);
+                Some(cred)
+            }
+            // 然后尝试按名称查找
+            else if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &provider) {
+                eprintln!("[AMP] get_by_name 找到凭证: {:?}", cred.name);
+                Some(cred)
+            }
+            // 最后尝试按 UUID 查找
+            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &provider) {
+                eprintln!("[AMP] get_by_uuid 找到凭证: {:?}", cred.name);
+                Some(cred)
+            } else {
+                None
+            };
+
+            // 如果 Provider Pool 中没有找到，尝试从 API Key Provider 查找
+            if pool_cred.is_none() {
+                eprintln!(
+                    "[AMP] Provider Pool 中未找到凭证，尝试 API Key Provider: provider={}",
+                    provider
+                );
+
+                match state.api_key_service.get_fallback_credential(
+                    db,
+                    &crate::models::provider_pool_model::PoolProviderType::OpenAI,
+                    Some(&provider),
+                ) {
+                    Ok(Some(cred)) => {
+                        eprintln!(
+                            "[AMP] 通过 provider_id '{}' 找到 API Key Provider 凭证: name={:?}",
+                            provider, cred.name
+                        );
+                        Some(cred)
+                    }
+                    Ok(None) => {
+                        eprintln!("[AMP] 未找到任何凭证 for provider '{}'", provider);
+                        None
+                    }
+                    Err(e) => {
+                        eprintln!("[AMP] 查找 API Key Provider 凭证时出错: {}", e);
+                        None
+                    }
+                }
+            } else {
+                pool_cred
+            }
+        }
+        None => {
+            eprintln!("[AMP] 数据库未初始化");
+            None
+        }
+    };
+
+    match credential {
+        Some(cred) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[AMP] Using credential: type={} name={:?} uuid={}",
+                    cred.provider_type,
+                    cred.name,
+                    &cred.uuid[..8]
+                ),
+            );
+            // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
+            let response = match handlers::inject_chaos(&state, &cred).await {
+                Some(response) => response,
+                None => handlers::call_provider_openai(&state, &cred, &request, None).await,
+            };
+            let response = validate_upstream_response(
+                &state,
+                &current_request_id().unwrap_or_default(),
+                &cred,
+                ResponseSchema::OpenAiChat,
+                response,
+            )
+            .await;
+            if request.stream {
+                ensure_openai_stream(response, request.include_usage()).await
+            } else {
+                response
+            }
+        }
+        None => {
+            // 不再回退到默认 provider，直接返回错误
+            state.logs.write().await.add(
+                "error",
+                &format!(
+                    "[AMP] No available credentials for provider '{}', refusing to fallback",
+                    provider
+                ),
+            );
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("No available credentials for provider '{}'", provider),
+                        "type": "provider_unavailable",
+                        "code": "no_credentials"
+                    }
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Amp CLI messages 处理
+///
+/// 处理 `/api/provider/:provider/v1/messages` 路由
+/// 支持模型映射，将不可用模型映射到可用替代
+async fn amp_messages(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    ValidatedJson(mut request): ValidatedJson<AnthropicMessagesRequest>,
+) -> Response {
+    // 使用 Anthropic 格式的认证验证
+    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
+        state.logs.write().await.add(
+            "warn",
+            &format!(
+                "Unauthorized request to /api/provider/{}/v1/messages",
+                provider
+            ),
+        );
+        return e.into_response();
+    }
+
+    // 应用模型映射
+    let original_model = request.model.clone();
+    let mapped_model = state
+        .amp_router
+        .read()
+        .apply_model_mapping_for(Some(&provider), &request.model);
+    if mapped_model != original_model {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[AMP] Model mapping applied: {} -> {}",
+                original_model, mapped_model
+            ),
+        );
+        request.model = mapped_model;
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AMP] POST /api/provider/{}/v1/messages model={} stream={}",
+            provider, request.model, request.stream
+        ),
+    );
+
+    // 尝试根据 provider 名称选择凭证
+    let credential = match &state.db {
+        Some(db) => {
+            // 先尝试从 Provider Pool 查找
+            let pool_cred = if let Ok(Some(cred)) =
+                state
+                    .pool_service
+                    .select_credential(db, &provider, Some(&request.model))
+            {
+                Some(cred)
+            }
+            // 然后尝试按名称查找
+            else if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &provider) {
+                Some(cred)
+            }
+            // 最后尝试按 UUID 查找
+            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &provider) {
+                Some(cred)
+            } else {
+                None
+            };
+
+            // 如果 Provider Pool 中没有找到，尝试从 API Key Provider 查找
+            if pool_cred.is_none() {
+                eprintln!(
+                    "[AMP_MESSAGES] Provider Pool 中未找到凭证，尝试 API Key Provider: provider={}",
+                    provider
+                );
+
+                match state.api_key_service.get_fallback_credential(
+                    db,
+                    &crate::models::provider_pool_model::PoolProviderType::Anthropic,
+                    Some(&provider),
+                ) {
+                    Ok(Some(cred)) => {
+                        eprintln!(
+                            "[AMP_MESSAGES] 通过 provider_id '{}' 找到 API Key Provider 凭证: name={:?}",
+                            provider, cred.name
+                        );
+                        Some(cred)
+                    }
+                    Ok(None) => {
+                        eprintln!("[AMP_MESSAGES] 未找到任何凭证 for provider '{}'", provider);
+                        None
+                    }
+                    Err(e) => {
+                        eprintln!("[AMP_MESSAGES] 查找 API Key Provider 凭证时出错: {}", e);
+                        None
+                    }
+                }
+            } else {
+                pool_cred
+            }
+        }
+        None => None,
+    };
+
+    match credential {
+        Some(cred) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[AMP] Using credential: type={} name={:?} uuid={}",
+                    cred.provider_type,
+                    cred.name,
+                    &cred.uuid[..8]
+                ),
+            );
+            // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
+            let response = match handlers::inject_chaos(&state, &cred).await {
+                Some(response) => response,
+                None => handlers::call_provider_anthropic(&state, &cred, &request, None).await,
+            };
+            validate_upstream_response(
+                &state,
+                &current_request_id().unwrap_or_default(),
+                &cred,
+                ResponseSchema::AnthropicMessages,
+                response,
+            )
+            .await
+        }
+        None => {
+            // 不再回退到默认 provider，直接返回错误
+            state.logs.write().await.add(
+                "error",
+                &format!(
+                    "[AMP] No available credentials for provider '{}', refusing to fallback",
+                    provider
+                ),
+            );
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": {
+                        "type": "provider_unavailable",
+                        "message": format!("No available credentials for provider '{}'", provider)
+                    }
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 内部 Anthropic messages 处理 (使用默认 Kiro)
+/// 预留：用于内部直接调用 Kiro API
+#[allow(dead_code)]
+async fn anthropic_messages_internal(
+    state: &AppState,
+    request: &AnthropicMessagesRequest,
+) -> Response {
+    // 检查 token
+    {
+        let _guard = state.kiro_refresh_lock.lock().await;
+        let mut kiro = state.kiro.write().await;
+        let needs_refresh = kiro.access_token().is_none() || kiro.is_token_expiring_soon();
+        if needs_refresh {
+            if let Err(e) = kiro.refresh_token().await {
+                state
+                    .logs
+                    .write()
+                    .await
+                    .add("error", &format!("[AUTH] Token refresh failed: {e}"));
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    let openai_request = convert_anthropic_to_openai(request);
+    let kiro = state.kiro.read().await;
+
+    match kiro.call_api(&openai_request).await {
+        Ok(resp) => {
+            let status = resp.status();
+            if status.is_success() {
+                match resp.bytes().await {
+                    Ok(bytes) => {
+                        let parsed = parse_cw_response(&bytes);
+                        if request.stream {
+                            build_anthropic_stream_response(&request.model, &parsed)
+                        } else {
+                            build_anthropic_response(&request.model, &parsed)
+                        }
+                    }
+                    Err(e) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                    )
+                        .into_response(),
+                }
+            } else {
+                let body = resp.text().await.unwrap_or_default();
+                (
+                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}})),
+                )
+                    .into_response()
+            }
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+        )
+            .into_response(),
+    }
+}
+
+/// 内部 OpenAI chat completions 处理 (使用默认 Kiro)
+/// 预留：用于内部直接调用 Kiro API
+#[allow(dead_code)]
+async fn chat_completions_internal(state: &AppState, request: &ChatCompletionRequest) -> Response {
+    {
+        let _guard = state.kiro_refresh_lock.lock().await;
+        let mut kiro = state.kiro.write().await;
+        let needs_refresh = kiro.access_token().is_none() || kiro.is_token_expiring_soon();
+        if needs_refresh {
+            if let Err(e) = kiro.refresh_token().await {
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    let kiro = state.kiro.read().await;
+    match kiro.call_api(request).await {
+        Ok(resp) => {
+            let status = resp.status();
+            if status.is_success() {
+                match resp.bytes().await {
+                    Ok(body) => {
+                        let parsed = parse_cw_response(&body);
+                        let has_tool_calls = !parsed.tool_calls.is_empty();
+
+                        let message = if has_tool_calls {
+                            serde_json::json!({
+                                "role": "assistant",
+                                "content": if parsed.content.is_empty() { serde_json::Value::Null } else { serde_json::json!(parsed.content) },
+                                "tool_calls": parsed.tool_calls.iter().map(|tc| {
+                                    serde_json::json!({
+                                        "id": tc.id,
+                                        "type": "function",
+                                        "function": {
+                                            "name": tc.function.name,
+                                            "arguments": tc.function.arguments
+                                        }
+                                    })
+                                }).collect::<Vec<_>>()
+                            })
+                        } else {
+                            serde_json::json!({
+                                "role": "assistant",
+                                "content": parsed.content
+                            })
+                        };
+
+                        let response = serde_json::json!({
+                            "id": crate::middleware::response_id("chatcmpl-"),
+                            "object": "chat.completion",
+                            "created": std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs(),
+                            "model": request.model,
+                            "choices": [{
+                                "index": 0,
+                                "message": message,
+                                "finish_reason": if has_tool_calls { "tool_calls" } else { "stop" }
+                            }],
+                            "usage": {
+                                "prompt_tokens": 0,
+                                "completion_tokens": 0,
+                                "total_tokens": 0
+                            }
+                        });
+                        Json(response).into_response()
+                    }
+                    Err(e) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                    )
+                        .into_response(),
+                }
+            } else {
+                let body = resp.text().await.unwrap_or_default();
+                (
+                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}})),
+                )
+                    .into_response()
+            }
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,97 @@
+//! 就绪检查
+//!
+//! `GET /health/ready` 在以下情况返回 503，负载均衡或编排系统可据此暂停转发请求：
+//! - 启动后首次凭证池同步成功之前
+//! - 配置热重载及随后的凭证池同步进行中（热重载期间持有 `processor.reload_lock` 写锁）
+
+use crate::server::AppState;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 服务就绪状态
+#[derive(Debug, Default)]
+pub struct Readiness {
+    credentials_synced: AtomicBool,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 标记凭证池已完成同步
+    pub fn mark_credentials_synced(&self) {
+        self.credentials_synced.store(true, Ordering::Release);
+    }
+
+    /// 凭证池是否已完成过一次同步
+    pub fn credentials_synced(&self) -> bool {
+        self.credentials_synced.load(Ordering::Acquire)
+    }
+
+    /// 未就绪原因，就绪时返回 None
+    pub fn not_ready_reason(&self, reloading: bool) -> Option<&'static str> {
+        if reloading {
+            Some("config_reload_in_progress")
+        } else if !self.credentials_synced() {
+            Some("credential_sync_pending")
+        } else {
+            None
+        }
+    }
+}
+
+/// GET /health/ready - 就绪检查
+pub async fn readiness(State(state): State<AppState>) -> Response {
+    // 热重载期间写锁被占用
+    let reloading = state.processor.reload_lock.try_read().is_err();
+    match state.readiness.not_ready_reason(reloading) {
+        None => Json(serde_json::json!({ "status": "ready" })).into_response(),
+        Some(reason) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "not_ready", "reason": reason })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::RwLock;
+
+    #[test]
+    fn test_not_ready_until_credentials_synced() {
+        let readiness = Readiness::new();
+        assert_eq!(
+            readiness.not_ready_reason(false),
+            Some("credential_sync_pending")
+        );
+
+        readiness.mark_credentials_synced();
+        assert_eq!(readiness.not_ready_reason(false), None);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_during_reload() {
+        let readiness = Readiness::new();
+        readiness.mark_credentials_synced();
+        let reload_lock = RwLock::new(());
+
+        let guard = reload_lock.write().await;
+        let reloading = reload_lock.try_read().is_err();
+        assert_eq!(
+            readiness.not_ready_reason(reloading),
+            Some("config_reload_in_progress")
+        );
+
+        drop(guard);
+        let reloading = reload_lock.try_read().is_err();
+        assert_eq!(readiness.not_ready_reason(reloading), None);
+    }
+}
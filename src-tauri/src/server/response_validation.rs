@@ -0,0 +1,410 @@
+//! 上游响应结构校验
+//!
+//! 上游偶尔会返回结构损坏的响应体（截断的 JSON、HTML 错误页、缺少 `choices` / `content` 的对象等），
+//! 原样转发会让下游客户端解析失败。非流式成功响应不符合预期结构时：
+//! - 将原始响应体写入隔离目录（`response_validation.quarantine_dir`）
+//! - 将凭证标记为可疑（错误计数加一）
+//! - 向客户端返回 502 结构化错误
+//!
+//! 流式响应和失败响应不做校验。
+
+use super::AppState;
+use crate::config::{expand_tilde, ResponseValidationConfig};
+use crate::models::provider_pool_model::ProviderCredential;
+use axum::{
+    body::{Body, Bytes},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// 客户端收到的错误类型
+const INVALID_RESPONSE_ERROR_TYPE: &str = "upstream_invalid_response";
+
+/// 预期的响应结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseSchema {
+    /// OpenAI Chat Completions
+    OpenAiChat,
+    /// Anthropic Messages
+    AnthropicMessages,
+}
+
+impl ResponseSchema {
+    /// 校验响应体，返回第一处不符合预期结构的原因
+    pub fn validate(self, body: &Value) -> Result<(), String> {
+        let object = body
+            .as_object()
+            .ok_or_else(|| "response body is not a JSON object".to_string())?;
+        if object.contains_key("error") || body["type"] == "error" {
+            return Err("error object in a successful response".to_string());
+        }
+        match self {
+            ResponseSchema::OpenAiChat => validate_openai_chat(body),
+            ResponseSchema::AnthropicMessages => validate_anthropic_message(body),
+        }
+    }
+
+    /// 以对应协议的错误格式构建响应
+    fn error_response(self, message: &str) -> Response {
+        let body = match self {
+            ResponseSchema::OpenAiChat => json!({
+                "error": {
+                    "type": INVALID_RESPONSE_ERROR_TYPE,
+                    "code": INVALID_RESPONSE_ERROR_TYPE,
+                    "message": message,
+                }
+            }),
+            ResponseSchema::AnthropicMessages => json!({
+                "type": "error",
+                "error": {
+                    "type": INVALID_RESPONSE_ERROR_TYPE,
+                    "message": message,
+                }
+            }),
+        };
+        (StatusCode::BAD_GATEWAY, Json(body)).into_response()
+    }
+}
+
+fn validate_openai_chat(body: &Value) -> Result<(), String> {
+    let choices = body["choices"]
+        .as_array()
+        .ok_or_else(|| "missing `choices` array".to_string())?;
+    for (index, choice) in choices.iter().enumerate() {
+        let message = choice["message"]
+            .as_object()
+            .ok_or_else(|| format!("`choices[{}].message` is not an object", index))?;
+        if !matches!(
+            message.get("content"),
+            None | Some(Value::Null | Value::String(_) | Value::Array(_))
+        ) {
+            return Err(format!(
+                "`choices[{}].message.content` has an unexpected type",
+                index
+            ));
+        }
+        if !matches!(
+            message.get("tool_calls"),
+            None | Some(Value::Null | Value::Array(_))
+        ) {
+            return Err(format!(
+                "`choices[{}].message.tool_calls` is not an array",
+                index
+            ));
+        }
+    }
+    if !matches!(
+        body.get("usage"),
+        None | Some(Value::Null | Value::Object(_))
+    ) {
+        return Err("`usage` is not an object".to_string());
+    }
+    Ok(())
+}
+
+fn validate_anthropic_message(body: &Value) -> Result<(), String> {
+    if body.get("type").is_some_and(|t| *t != "message") {
+        return Err("`type` is not `message`".to_string());
+    }
+    if body["role"]
+        .as_str()
+        .is_some_and(|role| role != "assistant")
+    {
+        return Err("`role` is not `assistant`".to_string());
+    }
+    let content = body["content"]
+        .as_array()
+        .ok_or_else(|| "missing `content` array".to_string())?;
+    for (index, block) in content.iter().enumerate() {
+        if !block["type"].is_string() {
+            return Err(format!("`content[{}].type` is missing", index));
+        }
+    }
+    if !matches!(
+        body.get("usage"),
+        None | Some(Value::Null | Value::Object(_))
+    ) {
+        return Err("`usage` is not an object".to_string());
+    }
+    Ok(())
+}
+
+/// 校验上游非流式成功响应
+///
+/// 响应体符合预期结构（或未启用校验）时按原样重建响应；否则隔离原始响应体、
+/// 标记凭证并返回 502 结构化错误。
+pub async fn validate_upstream_response(
+    state: &AppState,
+    request_id: &str,
+    credential: &ProviderCredential,
+    schema: ResponseSchema,
+    response: Response,
+) -> Response {
+    let config = state.processor.response_validation.read().await.clone();
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !config.enabled || !response.status().is_success() || is_sse {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let (bytes, reason) = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => {
+            let reason = match serde_json::from_slice::<Value>(&bytes) {
+                Ok(value) => schema.validate(&value).err(),
+                Err(e) => Some(format!("invalid JSON: {}", e)),
+            };
+            (bytes, reason)
+        }
+        Err(e) => (Bytes::new(), Some(format!("failed to read body: {}", e))),
+    };
+    let Some(reason) = reason else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let uuid_prefix = &credential.uuid[..8.min(credential.uuid.len())];
+    tracing::warn!(
+        "[RESPONSE_VALIDATION] request_id={} 凭证 {} ({}) 返回了无效的响应: {}",
+        request_id,
+        uuid_prefix,
+        credential.provider_type,
+        reason
+    );
+    let quarantined = quarantine(
+        &config,
+        &QuarantineRecord {
+            request_id,
+            credential,
+            status: parts.status.as_u16(),
+            reason: &reason,
+            body: &bytes,
+        },
+    );
+    match &quarantined {
+        Ok(Some(path)) => tracing::warn!("[RESPONSE_VALIDATION] 原始响应体已隔离到 {:?}", path),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("[RESPONSE_VALIDATION] 隔离响应体失败: {}", e),
+    }
+
+    let message = format!("Upstream returned a malformed response: {}", reason);
+    if config.mark_credential {
+        if let Some(db) = &state.db {
+            if let Err(e) = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&message))
+            {
+                tracing::warn!("[RESPONSE_VALIDATION] 标记凭证 {} 失败: {}", uuid_prefix, e);
+            }
+        }
+    }
+    state.logs.write().await.add(
+        "warn",
+        &format!(
+            "[RESPONSE_VALIDATION] request_id={} credential_uuid={} reason={}",
+            request_id, uuid_prefix, reason
+        ),
+    );
+
+    schema.error_response(&message)
+}
+
+/// 隔离文件内容
+struct QuarantineRecord<'a> {
+    request_id: &'a str,
+    credential: &'a ProviderCredential,
+    status: u16,
+    reason: &'a str,
+    body: &'a [u8],
+}
+
+/// 将无效响应写入隔离目录，超出保留数量时删除最早的文件
+///
+/// `max_quarantine_files` 为 0 时不写入，返回 `Ok(None)`。
+fn quarantine(
+    config: &ResponseValidationConfig,
+    record: &QuarantineRecord<'_>,
+) -> Result<Option<PathBuf>, String> {
+    if config.max_quarantine_files == 0 {
+        return Ok(None);
+    }
+    let dir = expand_tilde(&config.quarantine_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建隔离目录 {:?} 失败: {}", dir, e))?;
+
+    let now = chrono::Utc::now();
+    let file_name = format!(
+        "{}-{}.json",
+        now.format("%Y%m%dT%H%M%S%.3fZ"),
+        sanitize_file_component(record.request_id)
+    );
+    let path = dir.join(file_name);
+    let content = json!({
+        "request_id": record.request_id,
+        "received_at": now.to_rfc3339(),
+        "credential_uuid": record.credential.uuid,
+        "provider_type": record.credential.provider_type.to_string(),
+        "status": record.status,
+        "reason": record.reason,
+        "body": String::from_utf8_lossy(record.body),
+    });
+    let content = serde_json::to_string_pretty(&content).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| format!("写入 {:?} 失败: {}", path, e))?;
+
+    prune_quarantine(&dir, config.max_quarantine_files);
+    Ok(Some(path))
+}
+
+/// 删除最早的隔离文件，只保留最近 `max_files` 个（文件名以时间戳开头，按名称排序即按时间排序）
+fn prune_quarantine(dir: &Path, max_files: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    if files.len() <= max_files {
+        return;
+    }
+    files.sort();
+    for path in &files[..files.len() - max_files] {
+        if let Err(e) = std::fs::remove_file(path) {
+            tracing::warn!("[RESPONSE_VALIDATION] 删除隔离文件 {:?} 失败: {}", path, e);
+        }
+    }
+}
+
+/// 文件名中只保留字母、数字、`-` 和 `_`
+fn sanitize_file_component(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+    #[test]
+    fn test_validate_openai_chat() {
+        let schema = ResponseSchema::OpenAiChat;
+        let valid = json!({
+            "id": "chatcmpl-1",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}}],
+            "usage": {"prompt_tokens": 1, "completion_tokens": 1}
+        });
+        assert!(schema.validate(&valid).is_ok());
+        let tool_call = json!({
+            "choices": [{"message": {"content": null, "tool_calls": [{"id": "call_1"}]}}]
+        });
+        assert!(schema.validate(&tool_call).is_ok());
+
+        assert!(schema.validate(&json!([])).is_err());
+        assert!(schema.validate(&json!({"id": "x"})).is_err());
+        assert!(schema
+            .validate(&json!({"choices": [{"text": "hi"}]}))
+            .is_err());
+        assert!(schema
+            .validate(&json!({"choices": [{"message": {"content": 42}}]}))
+            .is_err());
+        assert!(schema
+            .validate(&json!({"error": {"message": "overloaded"}}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_anthropic_message() {
+        let schema = ResponseSchema::AnthropicMessages;
+        let valid = json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [{"type": "text", "text": "hi"}],
+            "usage": {"input_tokens": 1, "output_tokens": 1}
+        });
+        assert!(schema.validate(&valid).is_ok());
+
+        assert!(schema
+            .validate(&json!({"type": "message", "content": "hi"}))
+            .is_err());
+        assert!(schema
+            .validate(&json!({"type": "message", "content": [{"text": "hi"}]}))
+            .is_err());
+        assert!(schema
+            .validate(&json!({"type": "completion", "content": []}))
+            .is_err());
+        assert!(schema
+            .validate(&json!({"type": "error", "error": {"type": "overloaded_error"}}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_quarantine_prunes_oldest_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = ResponseValidationConfig {
+            quarantine_dir: dir.path().to_string_lossy().to_string(),
+            max_quarantine_files: 2,
+            ..Default::default()
+        };
+        let credential = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+
+        let mut paths = Vec::new();
+        for i in 0..3 {
+            let request_id = format!("req/{}", i);
+            let path = quarantine(
+                &config,
+                &QuarantineRecord {
+                    request_id: &request_id,
+                    credential: &credential,
+                    status: 200,
+                    reason: "invalid JSON",
+                    body: b"<html>oops</html>",
+                },
+            )
+            .unwrap()
+            .unwrap();
+            paths.push(path);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        assert!(!paths[0].exists());
+        assert!(paths[1].exists() && paths[2].exists());
+        assert!(paths[2].to_string_lossy().ends_with("-req_2.json"));
+        let saved: Value =
+            serde_json::from_str(&std::fs::read_to_string(&paths[2]).unwrap()).unwrap();
+        assert_eq!(saved["body"], "<html>oops</html>");
+        assert_eq!(saved["credential_uuid"], credential.uuid.as_str());
+
+        let disabled = ResponseValidationConfig {
+            max_quarantine_files: 0,
+            ..config
+        };
+        let record = QuarantineRecord {
+            request_id: "req",
+            credential: &credential,
+            status: 200,
+            reason: "invalid JSON",
+            body: b"",
+        };
+        assert_eq!(quarantine(&disabled, &record).unwrap(), None);
+    }
+}
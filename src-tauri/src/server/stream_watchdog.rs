@@ -0,0 +1,207 @@
+//! 上游流式响应看门狗
+//!
+//! 部分 Provider 会在流式响应中途停止输出但不关闭连接。上游两次输出之间的空闲时间
+//! 超过 `stream_watchdog.idle_timeout_secs` 时：
+//! - 丢弃上游响应流（断开上游连接）
+//! - 向客户端发送错误事件后结束响应
+//! - 将凭证标记为可疑（错误计数加一）
+//! - 请求遥测状态记为 `Timeout`
+//!
+//! 被监控的流式请求在响应结束时才写入遥测记录。
+
+use super::{build_request_log, record_request_telemetry, AppState};
+use crate::processor::RequestContext;
+use crate::streaming::{sse_error_event, StreamFormat};
+use crate::telemetry::{RequestLog, RequestStatus, TelemetryRecorder};
+use axum::{
+    body::{Body, Bytes},
+    http::header,
+    response::Response,
+};
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+
+/// 客户端收到的错误类型
+const STALL_ERROR_TYPE: &str = "timeout_error";
+
+/// 记录请求遥测，并为成功的流式响应添加看门狗
+///
+/// 非流式响应、失败响应或未启用看门狗时立即记录遥测并原样返回响应。
+pub async fn record_and_watch(
+    state: &AppState,
+    ctx: &RequestContext,
+    credential_uuid: &str,
+    format: StreamFormat,
+    response: Response,
+) -> Response {
+    let status = if response.status().is_success() {
+        RequestStatus::Success
+    } else {
+        RequestStatus::Failed
+    };
+    let config = state.processor.stream_watchdog.read().await.clone();
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if status != RequestStatus::Success || !is_sse || config.idle_timeout_secs == 0 {
+        record_request_telemetry(state, ctx, status, None);
+        return response;
+    }
+
+    let deferred = DeferredRequestLog {
+        telemetry: state.telemetry.clone(),
+        log: Some(build_request_log(ctx, status, None)),
+    };
+    let pool_service = state.processor.pool_service.clone();
+    let db = state.db.clone().filter(|_| config.mark_credential);
+    let ctx = ctx.clone();
+    let credential_uuid = credential_uuid.to_string();
+    let on_stall = move |idle: Duration| {
+        tracing::warn!(
+            "[STREAM_WATCHDOG] request_id={} 凭证 {} 的上游流式响应空闲超过 {} 秒，已中断",
+            ctx.request_id,
+            credential_uuid,
+            idle.as_secs()
+        );
+        let message = format!("Upstream stream stalled: no data for {}s", idle.as_secs());
+        if let Some(db) = &db {
+            if let Err(e) = pool_service.mark_unhealthy(db, &credential_uuid, Some(&message)) {
+                tracing::warn!("[STREAM_WATCHDOG] 标记凭证 {} 失败: {}", credential_uuid, e);
+            }
+        }
+        deferred.timeout(ctx.elapsed_ms());
+        Bytes::from(sse_error_event(format, STALL_ERROR_TYPE, &message))
+    };
+
+    let (parts, body) = response.into_parts();
+    let stream = with_watchdog(
+        body.into_data_stream(),
+        Duration::from_secs(config.idle_timeout_secs),
+        on_stall,
+    );
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 延迟写入的遥测记录（响应结束或被丢弃时写入）
+struct DeferredRequestLog {
+    telemetry: TelemetryRecorder,
+    log: Option<RequestLog>,
+}
+
+impl DeferredRequestLog {
+    /// 以超时状态写入
+    fn timeout(mut self, duration_ms: u64) {
+        if let Some(mut log) = self.log.take() {
+            log.mark_timeout(duration_ms);
+            self.telemetry.record_request(log);
+        }
+    }
+}
+
+impl Drop for DeferredRequestLog {
+    fn drop(&mut self) {
+        if let Some(log) = self.log.take() {
+            self.telemetry.record_request(log);
+        }
+    }
+}
+
+/// 包装响应数据流：空闲超过 `idle` 时丢弃上游流，输出 `on_stall` 返回的错误事件后结束
+///
+/// 上游停在事件中间时先补齐事件分隔符，避免错误事件与未完成的事件拼接。
+fn with_watchdog<S, F>(
+    mut inner: S,
+    idle: Duration,
+    on_stall: F,
+) -> impl Stream<Item = Result<Bytes, axum::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin + Send + 'static,
+    F: FnOnce(Duration) -> Bytes + Send + 'static,
+{
+    async_stream::stream! {
+        let mut at_boundary = true;
+        let mut stalled = false;
+        loop {
+            match tokio::time::timeout(idle, inner.next()).await {
+                Ok(Some(Ok(chunk))) => {
+                    if !chunk.is_empty() {
+                        at_boundary = chunk.ends_with(b"\n\n") || chunk.ends_with(b"\r\n\r\n");
+                    }
+                    yield Ok(chunk);
+                }
+                Ok(Some(Err(e))) => {
+                    yield Err(e);
+                    break;
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    stalled = true;
+                    break;
+                }
+            }
+        }
+        if stalled {
+            drop(inner);
+            if !at_boundary {
+                yield Ok(Bytes::from_static(b"\n\n"));
+            }
+            yield Ok(on_stall(idle));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    async fn collect(chunks: Vec<(u64, &'static str)>) -> (String, bool) {
+        let inner = futures::stream::iter(chunks)
+            .then(|(delay_ms, chunk)| async move {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                Ok::<_, axum::Error>(Bytes::from_static(chunk.as_bytes()))
+            })
+            .boxed();
+        let stalled = Arc::new(AtomicBool::new(false));
+        let flag = stalled.clone();
+        let output: Vec<Bytes> = with_watchdog(inner, Duration::from_millis(50), move |_| {
+            flag.store(true, Ordering::SeqCst);
+            Bytes::from(sse_error_event(
+                StreamFormat::AnthropicSse,
+                STALL_ERROR_TYPE,
+                "stalled",
+            ))
+        })
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+        (
+            String::from_utf8(output.concat()).unwrap(),
+            stalled.load(Ordering::SeqCst),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_completed_stream_passes_through() {
+        let (output, stalled) =
+            collect(vec![(0, "data: {\"a\":1}\n\n"), (20, "data: [DONE]\n\n")]).await;
+        assert_eq!(output, "data: {\"a\":1}\n\ndata: [DONE]\n\n");
+        assert!(!stalled);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_emits_error() {
+        let (output, stalled) = collect(vec![
+            (0, "event: content_block_delta\n"),
+            (200, "data: {}\n\n"),
+        ])
+        .await;
+        assert!(stalled);
+        assert!(output.starts_with("event: content_block_delta\n\n\nevent: error\n"));
+        assert!(output.contains("\"type\":\"timeout_error\""));
+        assert!(!output.contains("data: {}"));
+    }
+}
@@ -0,0 +1,435 @@
+//! 流式响应构建
+//!
+//! 将完整响应转换为客户端要求的流式格式：
+//! - CodeWhisperer 解析结果 -> Anthropic 响应 / SSE 事件流
+//! - `chat.completion` -> `chat.completion.chunk` SSE 事件流（不支持流式输出的 Provider 路径）
+//! - `n > 1` 扇出结果合并
+
+use crate::server_utils::{build_error_response_with_status, CWParsedResponse};
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::stream;
+
+/// 构建 Anthropic 非流式响应
+pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Response {
+    let stop_reason = parsed.stop_reason();
+    let mut content_array: Vec<serde_json::Value> = Vec::new();
+
+    if !parsed.content.is_empty() {
+        content_array.push(serde_json::json!({
+            "type": "text",
+            "text": parsed.content
+        }));
+    }
+
+    for tc in &parsed.tool_calls {
+        let input: serde_json::Value =
+            serde_json::from_str(&tc.function.arguments).unwrap_or(serde_json::json!({}));
+        content_array.push(serde_json::json!({
+            "type": "tool_use",
+            "id": tc.id,
+            "name": tc.function.name,
+            "input": input
+        }));
+    }
+
+    if content_array.is_empty() {
+        content_array.push(serde_json::json!({"type": "text", "text": ""}));
+    }
+
+    // 估算 output tokens: 基于响应内容长度 (约 4 字符 = 1 token)
+    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
+    for tc in &parsed.tool_calls {
+        output_tokens += (tc.function.arguments.len() / 4) as u32;
+    }
+    // 从 context_usage_percentage 估算 input tokens
+    // 假设 100% = 200k tokens (Claude 的上下文窗口)
+    let input_tokens = ((parsed.context_usage_percentage / 100.0) * 200000.0) as u32;
+
+    let response = serde_json::json!({
+        "id": crate::middleware::response_id("msg_"),
+        "type": "message",
+        "role": "assistant",
+        "content": content_array,
+        "model": model,
+        "stop_reason": stop_reason.to_anthropic_str(),
+        "stop_sequence": parsed.stop_sequence,
+        "usage": {
+            "input_tokens": input_tokens,
+            "output_tokens": output_tokens
+        }
+    });
+    parsed.with_context_usage_header(Json(response).into_response())
+}
+
+/// 构建 Anthropic 流式响应 (SSE)
+pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -> Response {
+    let stop_reason = parsed.stop_reason();
+    let message_id = crate::middleware::response_id("msg_");
+    let model = model.to_string();
+    let content = parsed.content.clone();
+    let tool_calls = parsed.tool_calls.clone();
+
+    // 估算 output tokens: 基于响应内容长度 (约 4 字符 = 1 token)
+    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
+    for tc in &parsed.tool_calls {
+        output_tokens += (tc.function.arguments.len() / 4) as u32;
+    }
+    // 从 context_usage_percentage 估算 input tokens
+    let input_tokens = ((parsed.context_usage_percentage / 100.0) * 200000.0) as u32;
+
+    // 构建 SSE 事件流
+    let mut events: Vec<String> = Vec::new();
+
+    // 1. message_start
+    let message_start = serde_json::json!({
+        "type": "message_start",
+        "message": {
+            "id": message_id,
+            "type": "message",
+            "role": "assistant",
+            "model": model,
+            "content": [],
+            "stop_reason": null,
+            "stop_sequence": null,
+            "usage": {"input_tokens": input_tokens, "output_tokens": 0}
+        }
+    });
+    events.push(format!("event: message_start\ndata: {message_start}\n\n"));
+
+    let mut block_index = 0;
+
+    // 2. 文本内容块 - 即使为空也要发送，Claude Code 需要至少一个 content block
+    // content_block_start
+    let block_start = serde_json::json!({
+        "type": "content_block_start",
+        "index": block_index,
+        "content_block": {"type": "text", "text": ""}
+    });
+    events.push(format!(
+        "event: content_block_start\ndata: {block_start}\n\n"
+    ));
+
+    if !content.is_empty() {
+        // content_block_delta - 发送完整内容
+        let block_delta = serde_json::json!({
+            "type": "content_block_delta",
+            "index": block_index,
+            "delta": {"type": "text_delta", "text": content}
+        });
+        events.push(format!(
+            "event: content_block_delta\ndata: {block_delta}\n\n"
+        ));
+    }
+
+    // content_block_stop
+    let block_stop = serde_json::json!({
+        "type": "content_block_stop",
+        "index": block_index
+    });
+    events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
+
+    block_index += 1;
+
+    // 3. Tool use 块
+    for tc in &tool_calls {
+        // content_block_start
+        let block_start = serde_json::json!({
+            "type": "content_block_start",
+            "index": block_index,
+            "content_block": {
+                "type": "tool_use",
+                "id": tc.id,
+                "name": tc.function.name,
+                "input": {}
+            }
+        });
+        events.push(format!(
+            "event: content_block_start\ndata: {block_start}\n\n"
+        ));
+
+        // content_block_delta - input_json_delta
+        let partial_json = if tc.function.arguments.is_empty() {
+            "{}".to_string()
+        } else {
+            tc.function.arguments.clone()
+        };
+        let block_delta = serde_json::json!({
+            "type": "content_block_delta",
+            "index": block_index,
+            "delta": {
+                "type": "input_json_delta",
+                "partial_json": partial_json
+            }
+        });
+        events.push(format!(
+            "event: content_block_delta\ndata: {block_delta}\n\n"
+        ));
+
+        // content_block_stop
+        let block_stop = serde_json::json!({
+            "type": "content_block_stop",
+            "index": block_index
+        });
+        events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
+
+        block_index += 1;
+    }
+
+    // 4. message_delta
+    let message_delta = serde_json::json!({
+        "type": "message_delta",
+        "delta": {
+            "stop_reason": stop_reason.to_anthropic_str(),
+            "stop_sequence": parsed.stop_sequence
+        },
+        "usage": {"output_tokens": output_tokens}
+    });
+    events.push(format!("event: message_delta\ndata: {message_delta}\n\n"));
+
+    // 5. message_stop
+    let message_stop = serde_json::json!({"type": "message_stop"});
+    events.push(format!("event: message_stop\ndata: {message_stop}\n\n"));
+
+    // 创建 SSE 响应
+    let body_stream = stream::iter(events.into_iter().map(Ok::<_, std::convert::Infallible>));
+    let body = Body::from_stream(body_stream);
+
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .body(body)
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to build SSE response: {}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap_or_default()
+        });
+    parsed.with_context_usage_header(response)
+}
+
+/// 合并扇出得到的多个 `chat.completion` 响应
+///
+/// 以第一个响应为基础，依次收集所有 choice 并重新编号；
+/// usage 累加（每个上游请求都计费 prompt token）。
+pub fn merge_fanout_completions(completions: Vec<serde_json::Value>) -> serde_json::Value {
+    let mut iter = completions.into_iter();
+    let Some(mut merged) = iter.next() else {
+        return serde_json::json!({"object": "chat.completion", "choices": []});
+    };
+    let mut choices = merged["choices"].as_array().cloned().unwrap_or_default();
+    let mut usage = merged.get("usage").filter(|u| u.is_object()).cloned();
+
+    for completion in iter {
+        choices.extend(
+            completion["choices"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default(),
+        );
+        let Some(extra) = completion.get("usage").filter(|u| u.is_object()) else {
+            continue;
+        };
+        let total = usage.get_or_insert_with(|| serde_json::json!({}));
+        for key in ["prompt_tokens", "completion_tokens", "total_tokens"] {
+            let sum = total[key].as_u64().unwrap_or(0) + extra[key].as_u64().unwrap_or(0);
+            total[key] = serde_json::json!(sum);
+        }
+    }
+
+    for (i, choice) in choices.iter_mut().enumerate() {
+        choice["index"] = serde_json::json!(i);
+    }
+    merged["choices"] = serde_json::Value::Array(choices);
+    if let Some(usage) = usage {
+        merged["usage"] = usage;
+    }
+    merged
+}
+
+/// 将完整的 `chat.completion` 响应转换为 `chat.completion.chunk` 事件
+///
+/// 每个 choice 依次输出角色、推理内容、文本、工具调用和结束原因；
+/// `include_usage` 时在 `[DONE]` 之前追加 `choices` 为空的用量 chunk。
+pub fn openai_completion_to_chunks(
+    completion: &serde_json::Value,
+    include_usage: bool,
+) -> Vec<String> {
+    let id = completion["id"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| crate::middleware::response_id("chatcmpl-"));
+    let created = completion["created"].as_u64().unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    });
+    let model = completion["model"].clone();
+    let chunk = |choices: serde_json::Value| {
+        let mut chunk = serde_json::json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": choices,
+        });
+        if include_usage {
+            chunk["usage"] = serde_json::Value::Null;
+        }
+        chunk
+    };
+    let delta = |index: &serde_json::Value, delta: serde_json::Value| {
+        chunk(serde_json::json!([{"index": index, "delta": delta, "finish_reason": null}]))
+    };
+
+    let mut chunks = Vec::new();
+    let choices = completion["choices"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    for (i, choice) in choices.iter().enumerate() {
+        let index = choice
+            .get("index")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!(i));
+        let message = &choice["message"];
+
+        chunks.push(delta(
+            &index,
+            serde_json::json!({"role": "assistant", "content": ""}),
+        ));
+        if let Some(reasoning) = message["reasoning_content"]
+            .as_str()
+            .filter(|s| !s.is_empty())
+        {
+            chunks.push(delta(
+                &index,
+                serde_json::json!({"reasoning_content": reasoning}),
+            ));
+        }
+        if let Some(content) = message["content"].as_str().filter(|s| !s.is_empty()) {
+            chunks.push(delta(&index, serde_json::json!({"content": content})));
+        }
+        for (tc_index, tool_call) in message["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            // 先发送 ID 和函数名，再发送参数
+            chunks.push(delta(
+                &index,
+                serde_json::json!({"tool_calls": [{
+                    "index": tc_index,
+                    "id": tool_call["id"],
+                    "type": "function",
+                    "function": {"name": tool_call["function"]["name"], "arguments": ""}
+                }]}),
+            ));
+            if let Some(arguments) = tool_call["function"]["arguments"]
+                .as_str()
+                .filter(|s| !s.is_empty())
+            {
+                chunks.push(delta(
+                    &index,
+                    serde_json::json!({"tool_calls": [{
+                        "index": tc_index,
+                        "function": {"arguments": arguments}
+                    }]}),
+                ));
+            }
+        }
+
+        let finish_reason = choice["finish_reason"].as_str().unwrap_or(
+            if message["tool_calls"]
+                .as_array()
+                .is_some_and(|t| !t.is_empty())
+            {
+                "tool_calls"
+            } else {
+                "stop"
+            },
+        );
+        chunks.push(chunk(serde_json::json!([{
+            "index": index,
+            "delta": {},
+            "finish_reason": finish_reason
+        }])));
+    }
+
+    if include_usage {
+        let mut usage_chunk = chunk(serde_json::json!([]));
+        usage_chunk["usage"] = completion["usage"].clone();
+        chunks.push(usage_chunk);
+    }
+
+    let mut events: Vec<String> = chunks
+        .into_iter()
+        .map(|chunk| format!("data: {chunk}\n\n"))
+        .collect();
+    events.push("data: [DONE]\n\n".to_string());
+    events
+}
+
+/// 构建 OpenAI 流式响应 (SSE)
+pub fn build_openai_stream_response(
+    completion: &serde_json::Value,
+    include_usage: bool,
+) -> Response {
+    let events = openai_completion_to_chunks(completion, include_usage);
+    let body_stream = stream::iter(events.into_iter().map(Ok::<_, std::convert::Infallible>));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .body(Body::from_stream(body_stream))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to build SSE response: {}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap_or_default()
+        })
+}
+
+/// 流式请求收到完整 JSON 响应时转换为 SSE
+///
+/// 部分 Provider 路径不支持流式输出，只返回完整的 `chat.completion`。
+/// 已是 SSE 或失败的响应原样返回。
+pub async fn ensure_openai_stream(response: Response, include_usage: bool) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return build_error_response_with_status(
+                502,
+                &format!("Failed to read response body: {e}"),
+            )
+        }
+    };
+    match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(completion) if completion["choices"].is_array() => {
+            build_openai_stream_response(&completion, include_usage)
+        }
+        _ => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
@@ -0,0 +1,472 @@
+//! 请求体校验
+//!
+//! 替代 axum 的 `Json` 提取器：
+//! - 不要求 `Content-Type: application/json`，忽略未知字段和值为 null 的可选字段
+//! - 反序列化前校验必填字段和字段类型
+//! - 校验失败时按 OpenAI / Anthropic 的错误格式返回 400，并指出出错的字段
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+
+/// `n` 的上限（与 OpenAI 一致）
+const MAX_CHOICES: u64 = 128;
+
+/// `top_logprobs` 的上限（与 OpenAI 一致）
+const MAX_TOP_LOGPROBS: u64 = 20;
+
+/// 错误响应格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    OpenAI,
+    Anthropic,
+}
+
+/// 字段校验错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// 出错字段路径（如 `messages.0.role`），请求体整体错误时为 None
+    pub param: Option<String>,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(param: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            param: Some(param.into()),
+            message: message.into(),
+        }
+    }
+
+    pub fn body(message: impl Into<String>) -> Self {
+        Self {
+            param: None,
+            message: message.into(),
+        }
+    }
+
+    /// 按指定格式构建 400 响应
+    pub fn into_response(self, format: ErrorFormat) -> Response {
+        let message = match &self.param {
+            Some(param) => format!("{}: {}", param, self.message),
+            None => self.message.clone(),
+        };
+        let body = match format {
+            ErrorFormat::OpenAI => serde_json::json!({
+                "error": {
+                    "message": message,
+                    "type": "invalid_request_error",
+                    "param": self.param,
+                    "code": null
+                }
+            }),
+            ErrorFormat::Anthropic => serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": message
+                }
+            }),
+        };
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+/// 可校验的请求体
+pub trait ValidateRequest: DeserializeOwned {
+    /// 错误响应格式
+    const FORMAT: ErrorFormat;
+
+    /// 校验请求体结构（已去除顶层 null 字段）
+    fn validate(body: &Map<String, Value>) -> Result<(), FieldError>;
+}
+
+impl ValidateRequest for ChatCompletionRequest {
+    const FORMAT: ErrorFormat = ErrorFormat::OpenAI;
+
+    fn validate(body: &Map<String, Value>) -> Result<(), FieldError> {
+        validate_common(body)?;
+        for (i, msg) in messages(body)?.iter().enumerate() {
+            let msg = message_object(msg, i)?;
+            match msg.get("content") {
+                None | Some(Value::Null) | Some(Value::String(_)) | Some(Value::Array(_)) => {}
+                Some(_) => {
+                    return Err(FieldError::new(
+                        format!("messages.{i}.content"),
+                        "Input should be a string or an array of content parts",
+                    ))
+                }
+            }
+        }
+        check_number(body, "presence_penalty")?;
+        check_number(body, "frequency_penalty")?;
+        check_range(body, "n", 1, MAX_CHOICES)?;
+        check_bool(body, "logprobs")?;
+        check_range(body, "top_logprobs", 0, MAX_TOP_LOGPROBS)?;
+        if body.get("top_logprobs").is_some() && body.get("logprobs") != Some(&Value::Bool(true)) {
+            return Err(FieldError::new(
+                "top_logprobs",
+                "logprobs must be set to true when top_logprobs is used",
+            ));
+        }
+        match body.get("stop") {
+            None | Some(Value::String(_)) => {}
+            Some(Value::Array(items)) => check_string_items("stop", items)?,
+            Some(_) => {
+                return Err(FieldError::new(
+                    "stop",
+                    "Input should be a string or an array of strings",
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ValidateRequest for AnthropicMessagesRequest {
+    const FORMAT: ErrorFormat = ErrorFormat::Anthropic;
+
+    fn validate(body: &Map<String, Value>) -> Result<(), FieldError> {
+        validate_common(body)?;
+        for (i, msg) in messages(body)?.iter().enumerate() {
+            let msg = message_object(msg, i)?;
+            match msg.get("content") {
+                Some(Value::String(_)) | Some(Value::Array(_)) => {}
+                None | Some(Value::Null) => {
+                    return Err(FieldError::new(
+                        format!("messages.{i}.content"),
+                        "Field required",
+                    ))
+                }
+                Some(_) => {
+                    return Err(FieldError::new(
+                        format!("messages.{i}.content"),
+                        "Input should be a string or an array of content blocks",
+                    ))
+                }
+            }
+        }
+        match body.get("system") {
+            None | Some(Value::String(_)) | Some(Value::Array(_)) => {}
+            Some(_) => {
+                return Err(FieldError::new(
+                    "system",
+                    "Input should be a string or an array of text blocks",
+                ))
+            }
+        }
+        match body.get("stop_sequences") {
+            None => {}
+            Some(Value::Array(items)) => check_string_items("stop_sequences", items)?,
+            Some(_) => {
+                return Err(FieldError::new(
+                    "stop_sequences",
+                    "Input should be an array of strings",
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 两种格式共有的字段校验
+fn validate_common(body: &Map<String, Value>) -> Result<(), FieldError> {
+    match body.get("model") {
+        Some(Value::String(model)) if !model.trim().is_empty() => {}
+        Some(Value::String(_)) => return Err(FieldError::new("model", "Field must not be empty")),
+        Some(_) => return Err(FieldError::new("model", "Input should be a valid string")),
+        None => return Err(FieldError::new("model", "Field required")),
+    }
+    check_bool(body, "stream")?;
+    check_number(body, "temperature")?;
+    check_number(body, "top_p")?;
+    check_unsigned(body, "max_tokens")?;
+    check_unsigned(body, "top_k")?;
+    if let Some(tools) = body.get("tools") {
+        if !tools.is_array() {
+            return Err(FieldError::new("tools", "Input should be a valid array"));
+        }
+    }
+    Ok(())
+}
+
+fn messages(body: &Map<String, Value>) -> Result<&Vec<Value>, FieldError> {
+    match body.get("messages") {
+        Some(Value::Array(messages)) => Ok(messages),
+        Some(_) => Err(FieldError::new("messages", "Input should be a valid array")),
+        None => Err(FieldError::new("messages", "Field required")),
+    }
+}
+
+fn message_object(msg: &Value, index: usize) -> Result<&Map<String, Value>, FieldError> {
+    let msg = msg.as_object().ok_or_else(|| {
+        FieldError::new(
+            format!("messages.{index}"),
+            "Input should be a valid object",
+        )
+    })?;
+    match msg.get("role") {
+        Some(Value::String(_)) => Ok(msg),
+        Some(_) => Err(FieldError::new(
+            format!("messages.{index}.role"),
+            "Input should be a valid string",
+        )),
+        None => Err(FieldError::new(
+            format!("messages.{index}.role"),
+            "Field required",
+        )),
+    }
+}
+
+fn check_bool(body: &Map<String, Value>, field: &str) -> Result<(), FieldError> {
+    match body.get(field) {
+        None | Some(Value::Bool(_)) => Ok(()),
+        Some(_) => Err(FieldError::new(field, "Input should be a valid boolean")),
+    }
+}
+
+fn check_number(body: &Map<String, Value>, field: &str) -> Result<(), FieldError> {
+    match body.get(field) {
+        None | Some(Value::Number(_)) => Ok(()),
+        Some(_) => Err(FieldError::new(field, "Input should be a valid number")),
+    }
+}
+
+fn check_unsigned(body: &Map<String, Value>, field: &str) -> Result<(), FieldError> {
+    match body.get(field) {
+        None => Ok(()),
+        Some(Value::Number(n)) if n.as_u64().is_some_and(|v| v <= u32::MAX as u64) => Ok(()),
+        Some(_) => Err(FieldError::new(
+            field,
+            "Input should be a non-negative integer",
+        )),
+    }
+}
+
+fn check_range(
+    body: &Map<String, Value>,
+    field: &str,
+    min: u64,
+    max: u64,
+) -> Result<(), FieldError> {
+    match body.get(field) {
+        None => Ok(()),
+        Some(Value::Number(n)) if n.as_u64().is_some_and(|v| (min..=max).contains(&v)) => Ok(()),
+        Some(_) => Err(FieldError::new(
+            field,
+            format!("Input should be an integer between {min} and {max}"),
+        )),
+    }
+}
+
+fn check_string_items(field: &str, items: &[Value]) -> Result<(), FieldError> {
+    match items.iter().position(|v| !v.is_string()) {
+        Some(i) => Err(FieldError::new(
+            format!("{field}.{i}"),
+            "Input should be a valid string",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// 解析并校验请求体
+pub fn parse_request<T: ValidateRequest>(bytes: &[u8]) -> Result<T, FieldError> {
+    let value: Value = serde_json::from_slice(bytes)
+        .map_err(|e| FieldError::body(format!("Request body is not valid JSON: {}", e)))?;
+    let Value::Object(mut body) = value else {
+        return Err(FieldError::body("Request body must be a JSON object"));
+    };
+
+    // 顶层 null 视为未提供
+    body.retain(|_, v| !v.is_null());
+    T::validate(&body)?;
+
+    serde_json::from_value(Value::Object(body))
+        .map_err(|e| FieldError::body(format!("Invalid request body: {}", e)))
+}
+
+/// 带校验的 JSON 请求体提取器
+pub struct ValidatedJson<T>(pub T);
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: ValidateRequest,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        parse_request::<T>(&bytes)
+            .map(ValidatedJson)
+            .map_err(|e| e.into_response(T::FORMAT))
+    }
+}
+
+/// 带校验的 JSON 请求体提取器，同时保留原始请求体
+///
+/// 用于同格式透传：请求未被改写时可直接转发原始字节，无需重新序列化。
+pub struct ValidatedJsonWithBody<T>(pub T, pub Bytes);
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for ValidatedJsonWithBody<T>
+where
+    T: ValidateRequest,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let request = parse_request::<T>(&bytes).map_err(|e| e.into_response(T::FORMAT))?;
+        Ok(ValidatedJsonWithBody(request, bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_openai(body: Value) -> Result<ChatCompletionRequest, FieldError> {
+        parse_request(body.to_string().as_bytes())
+    }
+
+    fn parse_anthropic(body: Value) -> Result<AnthropicMessagesRequest, FieldError> {
+        parse_request(body.to_string().as_bytes())
+    }
+
+    #[test]
+    fn test_accepts_unknown_and_null_fields() {
+        let request = parse_openai(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hi"}],
+            "stream": null,
+            "user": "abc",
+            "unknown_field": {"nested": true}
+        }))
+        .unwrap();
+        assert!(!request.stream);
+    }
+
+    #[test]
+    fn test_missing_required_fields() {
+        let err = parse_openai(serde_json::json!({"messages": []})).unwrap_err();
+        assert_eq!(err.param.as_deref(), Some("model"));
+
+        let err = parse_anthropic(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user"}]
+        }))
+        .unwrap_err();
+        assert_eq!(err.param.as_deref(), Some("messages.0.content"));
+        assert_eq!(err.message, "Field required");
+    }
+
+    #[test]
+    fn test_invalid_field_types() {
+        let err = parse_openai(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [],
+            "max_tokens": -1
+        }))
+        .unwrap_err();
+        assert_eq!(err.param.as_deref(), Some("max_tokens"));
+
+        let err = parse_anthropic(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [],
+            "stop_sequences": ["a", 1]
+        }))
+        .unwrap_err();
+        assert_eq!(err.param.as_deref(), Some("stop_sequences.1"));
+    }
+
+    #[test]
+    fn test_choices_and_logprobs() {
+        let request = parse_openai(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [],
+            "n": 3,
+            "logprobs": true,
+            "top_logprobs": 5
+        }))
+        .unwrap();
+        assert_eq!(request.choice_count(), 3);
+        assert!(request.wants_logprobs());
+
+        for (body, param) in [
+            (serde_json::json!({"n": 0}), "n"),
+            (serde_json::json!({"n": 129}), "n"),
+            (serde_json::json!({"logprobs": "yes"}), "logprobs"),
+            (
+                serde_json::json!({"logprobs": true, "top_logprobs": 21}),
+                "top_logprobs",
+            ),
+            (serde_json::json!({"top_logprobs": 2}), "top_logprobs"),
+        ] {
+            let mut body = body.as_object().unwrap().clone();
+            body.insert("model".into(), "gpt-4o".into());
+            body.insert("messages".into(), serde_json::json!([]));
+            let err = parse_openai(Value::Object(body)).unwrap_err();
+            assert_eq!(err.param.as_deref(), Some(param));
+        }
+    }
+
+    #[test]
+    fn test_invalid_json_body() {
+        let err = parse_request::<ChatCompletionRequest>(b"{not json").unwrap_err();
+        assert!(err.param.is_none());
+        let err = parse_request::<ChatCompletionRequest>(b"[]").unwrap_err();
+        assert_eq!(err.message, "Request body must be a JSON object");
+    }
+
+    #[tokio::test]
+    async fn test_validated_json_with_body_keeps_raw_bytes() {
+        let raw = r#"{"model":"gpt-4o","messages":[],"x_custom":1}"#;
+        let req = Request::post("/")
+            .body(axum::body::Body::from(raw))
+            .unwrap();
+        let ValidatedJsonWithBody(request, bytes) =
+            ValidatedJsonWithBody::<ChatCompletionRequest>::from_request(req, &())
+                .await
+                .unwrap_or_else(|_| panic!("extract failed"));
+        assert_eq!(request.model, "gpt-4o");
+        assert_eq!(&bytes[..], raw.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_error_formats() {
+        let err = FieldError::new("model", "Field required");
+
+        let response = err.clone().into_response(ErrorFormat::OpenAI);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["param"], "model");
+        assert_eq!(json["error"]["type"], "invalid_request_error");
+
+        let response = err.into_response(ErrorFormat::Anthropic);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["error"]["message"], "model: Field required");
+    }
+}
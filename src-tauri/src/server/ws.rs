@@ -0,0 +1,28 @@
+//! WebSocket 路由
+//!
+//! WebSocket 连接管理器按配置中的心跳参数创建，每次启动服务器时重新创建。
+
+use crate::config::Config;
+use crate::server::handlers;
+use crate::server::AppState;
+use crate::websocket::{WsConfig, WsConnectionManager};
+use axum::{routing::get, Router};
+use std::sync::Arc;
+
+/// WebSocket 路由表
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/ws", get(handlers::ws_upgrade_handler))
+        .route("/ws", get(handlers::ws_upgrade_handler))
+}
+
+/// 按心跳配置创建 WebSocket 连接管理器
+pub fn build_ws_manager(config: Option<&Config>) -> Arc<WsConnectionManager> {
+    let heartbeat = config.map(|c| c.heartbeat.clone()).unwrap_or_default();
+    Arc::new(WsConnectionManager::new(WsConfig {
+        heartbeat_interval_secs: heartbeat.ws_ping_interval_secs,
+        max_missed_pongs: heartbeat.ws_max_missed_pongs,
+        idle_timeout_secs: heartbeat.ws_idle_timeout_secs,
+        ..WsConfig::default()
+    }))
+}
@@ -3,15 +3,21 @@
 //! 包含响应解析、字符串处理、响应构建等公共工具函数。
 
 use crate::models::openai::{ContentPart, FunctionCall, MessageContent, ToolCall};
+use crate::stream::parsers::EventStreamDecoder;
+use crate::stream::tool_args::repair_tool_arguments;
+use crate::stream::StopReason;
 use axum::{
-    body::Body,
-    http::{header, StatusCode},
+    http::{HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use futures::stream;
 use std::collections::HashMap;
 
+pub use crate::server::streaming::{
+    build_anthropic_response, build_anthropic_stream_response, ensure_openai_stream,
+    merge_fanout_completions, openai_completion_to_chunks,
+};
+
 /// 从错误信息中解析 HTTP 状态码
 ///
 /// 用于将上游 API 返回的错误状态码透传给客户端，而不是统一返回 500。
@@ -96,6 +102,22 @@ pub fn build_error_response_with_status(status_code: u16, error_message: &str) -
         .into_response()
 }
 
+/// Kiro 上下文窗口使用百分比响应头
+pub const CONTEXT_USAGE_HEADER: &str = "x-kiro-context-usage-percentage";
+/// 请求上下文中记录上下文窗口使用百分比的元数据键
+pub const CONTEXT_USAGE_METADATA: &str = "context_usage_percentage";
+
+/// 响应头中的上下文窗口使用百分比
+pub fn context_usage_from_response(response: &Response) -> Option<f64> {
+    response
+        .headers()
+        .get(CONTEXT_USAGE_HEADER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
 /// CodeWhisperer 响应解析结果
 #[derive(Debug, Default)]
 pub struct CWParsedResponse {
@@ -103,9 +125,23 @@ pub struct CWParsedResponse {
     pub tool_calls: Vec<ToolCall>,
     pub usage_credits: f64,
     pub context_usage_percentage: f64,
+    /// 上游返回的停止原因（未返回时按是否包含工具调用推断）
+    pub stop_reason: Option<StopReason>,
+    /// 命中的停止序列
+    pub stop_sequence: Option<String>,
 }
 
 impl CWParsedResponse {
+    /// 最终停止原因
+    ///
+    /// 上游未返回停止原因时默认为正常结束；包含工具调用时修正为 `ToolUse`。
+    pub fn stop_reason(&self) -> StopReason {
+        self.stop_reason
+            .clone()
+            .unwrap_or_default()
+            .with_tool_calls(!self.tool_calls.is_empty())
+    }
+
     /// 估算 Token 使用量
     ///
     /// 基于响应内容长度和上下文使用百分比估算 Token 数量：
@@ -127,6 +163,18 @@ impl CWParsedResponse {
 
         (input_tokens, output_tokens)
     }
+
+    /// 附加上下文窗口使用百分比响应头（上游未返回时不附加）
+    pub fn with_context_usage_header(&self, mut response: Response) -> Response {
+        if self.context_usage_percentage > 0.0 {
+            if let Ok(value) =
+                HeaderValue::from_str(&format!("{:.2}", self.context_usage_percentage))
+            {
+                response.headers_mut().insert(CONTEXT_USAGE_HEADER, value);
+            }
+        }
+        response
+    }
 }
 
 /// 安全截断字符串到指定字符数，避免 UTF-8 边界问题
@@ -158,18 +206,83 @@ pub fn message_content_len(content: &MessageContent) -> usize {
 
 /// 解析 CodeWhisperer AWS Event Stream 响应
 ///
-/// AWS Event Stream 是二进制格式，JSON payload 嵌入在二进制头部之间
-pub fn parse_cw_response(body: &str) -> CWParsedResponse {
+/// 按二进制帧（前导、头部、payload、CRC）解码，逐帧处理 JSON payload。
+/// 帧校验失败时（如响应体已被有损转换为文本），剩余数据回退到 JSON 模式扫描。
+pub fn parse_cw_response(body: &[u8]) -> CWParsedResponse {
     let mut result = CWParsedResponse::default();
     // 使用 HashMap 来跟踪多个并发的 tool calls
     // key: toolUseId, value: (name, input_accumulated)
     let mut tool_map: HashMap<String, (String, String)> = HashMap::new();
 
-    // 将字符串转换为字节，因为 AWS Event Stream 包含二进制数据
-    let bytes = body.as_bytes();
+    let mut decoder = EventStreamDecoder::new();
+    decoder.feed(body);
+    loop {
+        match decoder.next_frame() {
+            Ok(Some(frame)) => match frame.message_type() {
+                Some("event") | None => {
+                    match serde_json::from_slice::<serde_json::Value>(&frame.payload) {
+                        Ok(value) => apply_cw_event(&value, &mut result, &mut tool_map),
+                        Err(e) => tracing::warn!(
+                            "[CW_PARSE] 事件 payload 不是合法 JSON: event_type={:?} error={}",
+                            frame.event_type(),
+                            e
+                        ),
+                    }
+                }
+                Some(message_type) => tracing::warn!(
+                    "[CW_PARSE] 上游返回 {} 帧: type={:?} payload={}",
+                    message_type,
+                    frame
+                        .header_str(":exception-type")
+                        .or(frame.header_str(":error-code")),
+                    safe_truncate(&String::from_utf8_lossy(&frame.payload), 500)
+                ),
+            },
+            Ok(None) => {
+                if !decoder.remaining().is_empty() {
+                    tracing::warn!(
+                        "[CW_PARSE] 响应末尾存在不完整的帧 ({} 字节)，回退到 JSON 扫描",
+                        decoder.remaining().len()
+                    );
+                    scan_cw_json_events(decoder.remaining(), &mut result, &mut tool_map);
+                }
+                break;
+            }
+            Err(e) => {
+                tracing::debug!("[CW_PARSE] 帧解码失败，回退到 JSON 扫描: {}", e);
+                scan_cw_json_events(decoder.remaining(), &mut result, &mut tool_map);
+                break;
+            }
+        }
+    }
+
+    // 处理未完成的 tool calls（没有收到 stop 事件的）
+    for (id, (name, input)) in tool_map {
+        if !name.is_empty() {
+            let arguments = repair_tool_arguments(&id, &input);
+            result.tool_calls.push(ToolCall {
+                id,
+                call_type: "function".to_string(),
+                function: FunctionCall { name, arguments },
+            });
+        }
+    }
 
+    // 解析 bracket 格式的 tool calls: [Called xxx with args: {...}]
+    parse_bracket_tool_calls(&mut result);
+
+    result
+}
+
+/// 扫描字节中的 JSON 事件（非二进制帧格式的兼容路径）
+///
+/// AWS Event Stream 格式: [binary headers]{"content":"..."}[binary trailer]
+fn scan_cw_json_events(
+    bytes: &[u8],
+    result: &mut CWParsedResponse,
+    tool_map: &mut HashMap<String, (String, String)>,
+) {
     // 搜索所有 JSON 对象的模式
-    // AWS Event Stream 格式: [binary headers]{"content":"..."}[binary trailer]
     let json_patterns: &[&[u8]] = &[
         b"{\"content\":",
         b"{\"name\":",
@@ -203,95 +316,83 @@ pub fn parse_cw_response(body: &str) -> CWParsedResponse {
         // 从 start 位置提取完整的 JSON 对象
         if let Some(json_str) = extract_json_from_bytes(&bytes[start..]) {
             if let Ok(value) = serde_json::from_str::<serde_json::Value>(&json_str) {
-                // 处理 content 事件
-                if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
-                    // 跳过 followupPrompt
-                    if value.get("followupPrompt").is_none() {
-                        result.content.push_str(content);
-                    }
-                }
-                // 处理 tool use 事件 (包含 toolUseId)
-                else if let Some(tool_use_id) = value.get("toolUseId").and_then(|v| v.as_str()) {
-                    let name = value
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    let input_chunk = value
-                        .get("input")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    let is_stop = value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                    // 获取或创建 tool entry
-                    let entry = tool_map
-                        .entry(tool_use_id.to_string())
-                        .or_insert_with(|| (String::new(), String::new()));
-
-                    // 更新 name（如果有）
-                    if !name.is_empty() {
-                        entry.0 = name;
-                    }
-
-                    // 累积 input
-                    entry.1.push_str(&input_chunk);
-
-                    // 如果是 stop 事件，完成这个 tool call
-                    if is_stop {
-                        if let Some((name, input)) = tool_map.remove(tool_use_id) {
-                            if !name.is_empty() {
-                                result.tool_calls.push(ToolCall {
-                                    id: tool_use_id.to_string(),
-                                    call_type: "function".to_string(),
-                                    function: FunctionCall {
-                                        name,
-                                        arguments: input,
-                                    },
-                                });
-                            }
-                        }
-                    }
-                }
-                // 处理独立的 stop 事件（没有 toolUseId）- 这种情况不应该发生，但以防万一
-                else if value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false) {
-                    // no-op
-                }
-                // 处理 meteringEvent: {"unit":"credit","unitPlural":"credits","usage":0.34}
-                else if let Some(usage) = value.get("usage").and_then(|v| v.as_f64()) {
-                    result.usage_credits = usage;
-                }
-                // 处理 contextUsageEvent: {"contextUsagePercentage":54.36}
-                else if let Some(ctx_usage) =
-                    value.get("contextUsagePercentage").and_then(|v| v.as_f64())
-                {
-                    result.context_usage_percentage = ctx_usage;
-                }
+                apply_cw_event(&value, result, tool_map);
             }
             pos = start + json_str.len();
         } else {
             pos = start + 1;
         }
     }
+}
 
-    // 处理未完成的 tool calls（没有收到 stop 事件的）
-    for (id, (name, input)) in tool_map {
-        if !name.is_empty() {
-            result.tool_calls.push(ToolCall {
-                id,
-                call_type: "function".to_string(),
-                function: FunctionCall {
-                    name,
-                    arguments: input,
-                },
-            });
+/// 处理单个 CodeWhisperer 事件 payload
+fn apply_cw_event(
+    value: &serde_json::Value,
+    result: &mut CWParsedResponse,
+    tool_map: &mut HashMap<String, (String, String)>,
+) {
+    // 处理 content 事件
+    if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
+        // 跳过 followupPrompt
+        if value.get("followupPrompt").is_none() {
+            result.content.push_str(content);
         }
     }
+    // 处理 tool use 事件 (包含 toolUseId)
+    else if let Some(tool_use_id) = value.get("toolUseId").and_then(|v| v.as_str()) {
+        let name = value
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let input_chunk = value
+            .get("input")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let is_stop = value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        // 获取或创建 tool entry
+        let entry = tool_map
+            .entry(tool_use_id.to_string())
+            .or_insert_with(|| (String::new(), String::new()));
+
+        // 更新 name（如果有）
+        if !name.is_empty() {
+            entry.0 = name;
+        }
 
-    // 解析 bracket 格式的 tool calls: [Called xxx with args: {...}]
-    parse_bracket_tool_calls(&mut result);
-
-    result
+        // 累积 input
+        entry.1.push_str(&input_chunk);
+
+        // 如果是 stop 事件，完成这个 tool call
+        if is_stop {
+            if let Some((name, input)) = tool_map.remove(tool_use_id) {
+                if !name.is_empty() {
+                    result.tool_calls.push(ToolCall {
+                        id: tool_use_id.to_string(),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name,
+                            arguments: repair_tool_arguments(tool_use_id, &input),
+                        },
+                    });
+                }
+            }
+        }
+    }
+    // 处理独立的 stop 事件（没有 toolUseId）- 这种情况不应该发生，但以防万一
+    else if value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false) {
+        // no-op
+    }
+    // 处理 meteringEvent: {"unit":"credit","unitPlural":"credits","usage":0.34}
+    else if let Some(usage) = value.get("usage").and_then(|v| v.as_f64()) {
+        result.usage_credits = usage;
+    }
+    // 处理 contextUsageEvent: {"contextUsagePercentage":54.36}
+    else if let Some(ctx_usage) = value.get("contextUsagePercentage").and_then(|v| v.as_f64()) {
+        result.context_usage_percentage = ctx_usage;
+    }
 }
 
 /// 在字节数组中查找子序列
@@ -301,6 +402,96 @@ pub fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
         .position(|window| window == needle)
 }
 
+/// multipart/form-data 中的一个字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartField<'a> {
+    /// 字段名
+    pub name: String,
+    /// 文件名（仅文件字段）
+    pub filename: Option<String>,
+    /// 字段的 Content-Type
+    pub content_type: Option<String>,
+    /// 字段内容
+    pub data: &'a [u8],
+}
+
+/// 解析 multipart/form-data 请求体
+///
+/// 只做轻量扫描（不支持嵌套 multipart），找不到 boundary 时返回 None。
+pub fn parse_multipart<'a>(body: &'a [u8], content_type: &str) -> Option<Vec<MultipartField<'a>>> {
+    let boundary = content_type.split(';').find_map(|param| {
+        param
+            .trim()
+            .strip_prefix("boundary=")
+            .map(|b| b.trim_matches('"'))
+    })?;
+    if boundary.is_empty() {
+        return None;
+    }
+    let delimiter = format!("--{}", boundary);
+
+    let mut fields = Vec::new();
+    let mut rest = body;
+    while let Some(pos) = find_subsequence(rest, delimiter.as_bytes()) {
+        let part = &rest[..pos];
+        rest = &rest[pos + delimiter.len()..];
+
+        let Some(header_end) = find_subsequence(part, b"\r\n\r\n") else {
+            continue;
+        };
+        let headers = String::from_utf8_lossy(&part[..header_end]);
+        let mut name = None;
+        let mut filename = None;
+        let mut field_type = None;
+        for line in headers.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            if key.trim().eq_ignore_ascii_case("content-disposition") {
+                name = disposition_param(value, "name");
+                filename = disposition_param(value, "filename");
+            } else if key.trim().eq_ignore_ascii_case("content-type") {
+                field_type = Some(value.trim().to_string());
+            }
+        }
+        let Some(name) = name else {
+            continue;
+        };
+        let data = &part[header_end + 4..];
+        fields.push(MultipartField {
+            name,
+            filename,
+            content_type: field_type,
+            data: data.strip_suffix(b"\r\n").unwrap_or(data),
+        });
+    }
+    Some(fields)
+}
+
+/// 读取 Content-Disposition 中的参数（如 `name`、`filename`）
+fn disposition_param(value: &str, key: &str) -> Option<String> {
+    value.split(';').find_map(|param| {
+        let (k, v) = param.trim().split_once('=')?;
+        k.trim()
+            .eq_ignore_ascii_case(key)
+            .then(|| v.trim().trim_matches('"').to_string())
+    })
+}
+
+/// 读取 multipart/form-data 请求体中的文本字段（如音频转录请求的 `model`）
+///
+/// 文件字段（带 `filename`）和找不到 boundary 时返回 None。
+pub fn multipart_text_field(body: &[u8], content_type: &str, name: &str) -> Option<String> {
+    parse_multipart(body, content_type)?
+        .into_iter()
+        .find(|field| field.name == name && field.filename.is_none())
+        .and_then(|field| {
+            std::str::from_utf8(field.data)
+                .ok()
+                .map(|v| v.trim().to_string())
+        })
+}
+
 /// 从字节数组中提取 JSON 对象字符串
 pub fn extract_json_from_bytes(bytes: &[u8]) -> Option<String> {
     if bytes.is_empty() || bytes[0] != b'{' {
@@ -406,206 +597,6 @@ pub fn parse_bracket_tool_calls(result: &mut CWParsedResponse) {
     }
 }
 
-/// 构建 Anthropic 非流式响应
-pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Response {
-    let has_tool_calls = !parsed.tool_calls.is_empty();
-    let mut content_array: Vec<serde_json::Value> = Vec::new();
-
-    if !parsed.content.is_empty() {
-        content_array.push(serde_json::json!({
-            "type": "text",
-            "text": parsed.content
-        }));
-    }
-
-    for tc in &parsed.tool_calls {
-        let input: serde_json::Value =
-            serde_json::from_str(&tc.function.arguments).unwrap_or(serde_json::json!({}));
-        content_array.push(serde_json::json!({
-            "type": "tool_use",
-            "id": tc.id,
-            "name": tc.function.name,
-            "input": input
-        }));
-    }
-
-    if content_array.is_empty() {
-        content_array.push(serde_json::json!({"type": "text", "text": ""}));
-    }
-
-    // 估算 output tokens: 基于响应内容长度 (约 4 字符 = 1 token)
-    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
-    for tc in &parsed.tool_calls {
-        output_tokens += (tc.function.arguments.len() / 4) as u32;
-    }
-    // 从 context_usage_percentage 估算 input tokens
-    // 假设 100% = 200k tokens (Claude 的上下文窗口)
-    let input_tokens = ((parsed.context_usage_percentage / 100.0) * 200000.0) as u32;
-
-    let response = serde_json::json!({
-        "id": format!("msg_{}", uuid::Uuid::new_v4()),
-        "type": "message",
-        "role": "assistant",
-        "content": content_array,
-        "model": model,
-        "stop_reason": if has_tool_calls { "tool_use" } else { "end_turn" },
-        "stop_sequence": null,
-        "usage": {
-            "input_tokens": input_tokens,
-            "output_tokens": output_tokens
-        }
-    });
-    Json(response).into_response()
-}
-
-/// 构建 Anthropic 流式响应 (SSE)
-pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -> Response {
-    let has_tool_calls = !parsed.tool_calls.is_empty();
-    let message_id = format!("msg_{}", uuid::Uuid::new_v4());
-    let model = model.to_string();
-    let content = parsed.content.clone();
-    let tool_calls = parsed.tool_calls.clone();
-
-    // 估算 output tokens: 基于响应内容长度 (约 4 字符 = 1 token)
-    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
-    for tc in &parsed.tool_calls {
-        output_tokens += (tc.function.arguments.len() / 4) as u32;
-    }
-    // 从 context_usage_percentage 估算 input tokens
-    let input_tokens = ((parsed.context_usage_percentage / 100.0) * 200000.0) as u32;
-
-    // 构建 SSE 事件流
-    let mut events: Vec<String> = Vec::new();
-
-    // 1. message_start
-    let message_start = serde_json::json!({
-        "type": "message_start",
-        "message": {
-            "id": message_id,
-            "type": "message",
-            "role": "assistant",
-            "model": model,
-            "content": [],
-            "stop_reason": null,
-            "stop_sequence": null,
-            "usage": {"input_tokens": input_tokens, "output_tokens": 0}
-        }
-    });
-    events.push(format!("event: message_start\ndata: {message_start}\n\n"));
-
-    let mut block_index = 0;
-
-    // 2. 文本内容块 - 即使为空也要发送，Claude Code 需要至少一个 content block
-    // content_block_start
-    let block_start = serde_json::json!({
-        "type": "content_block_start",
-        "index": block_index,
-        "content_block": {"type": "text", "text": ""}
-    });
-    events.push(format!(
-        "event: content_block_start\ndata: {block_start}\n\n"
-    ));
-
-    if !content.is_empty() {
-        // content_block_delta - 发送完整内容
-        let block_delta = serde_json::json!({
-            "type": "content_block_delta",
-            "index": block_index,
-            "delta": {"type": "text_delta", "text": content}
-        });
-        events.push(format!(
-            "event: content_block_delta\ndata: {block_delta}\n\n"
-        ));
-    }
-
-    // content_block_stop
-    let block_stop = serde_json::json!({
-        "type": "content_block_stop",
-        "index": block_index
-    });
-    events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
-
-    block_index += 1;
-
-    // 3. Tool use 块
-    for tc in &tool_calls {
-        // content_block_start
-        let block_start = serde_json::json!({
-            "type": "content_block_start",
-            "index": block_index,
-            "content_block": {
-                "type": "tool_use",
-                "id": tc.id,
-                "name": tc.function.name,
-                "input": {}
-            }
-        });
-        events.push(format!(
-            "event: content_block_start\ndata: {block_start}\n\n"
-        ));
-
-        // content_block_delta - input_json_delta
-        let partial_json = if tc.function.arguments.is_empty() {
-            "{}".to_string()
-        } else {
-            tc.function.arguments.clone()
-        };
-        let block_delta = serde_json::json!({
-            "type": "content_block_delta",
-            "index": block_index,
-            "delta": {
-                "type": "input_json_delta",
-                "partial_json": partial_json
-            }
-        });
-        events.push(format!(
-            "event: content_block_delta\ndata: {block_delta}\n\n"
-        ));
-
-        // content_block_stop
-        let block_stop = serde_json::json!({
-            "type": "content_block_stop",
-            "index": block_index
-        });
-        events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
-
-        block_index += 1;
-    }
-
-    // 4. message_delta
-    let message_delta = serde_json::json!({
-        "type": "message_delta",
-        "delta": {
-            "stop_reason": if has_tool_calls { "tool_use" } else { "end_turn" },
-            "stop_sequence": null
-        },
-        "usage": {"output_tokens": output_tokens}
-    });
-    events.push(format!("event: message_delta\ndata: {message_delta}\n\n"));
-
-    // 5. message_stop
-    let message_stop = serde_json::json!({"type": "message_stop"});
-    events.push(format!("event: message_stop\ndata: {message_stop}\n\n"));
-
-    // 创建 SSE 响应
-    let body_stream = stream::iter(events.into_iter().map(Ok::<_, std::convert::Infallible>));
-    let body = Body::from_stream(body_stream);
-
-    Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "text/event-stream")
-        .header(header::CACHE_CONTROL, "no-cache")
-        .header(header::CONNECTION, "keep-alive")
-        .body(body)
-        .unwrap_or_else(|e| {
-            tracing::error!("Failed to build SSE response: {}", e);
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::empty())
-                .unwrap_or_default()
-        })
-}
-
 /// 构建 Gemini CLI OAuth 请求体
 ///
 /// 用于 Gemini OAuth 凭证（Cloud Code Assist API）
@@ -754,39 +745,72 @@ pub fn build_gemini_native_request(
 }
 
 /// 健康检查端点响应
+///
+/// 处于维护窗口的 Provider / 凭证列在 `maintenance` 中，属于计划内降级，整体状态仍为 healthy。
 pub async fn health() -> impl IntoResponse {
-    Json(serde_json::json!({
+    let maintenance = crate::credential::maintenance_registry().active(chrono::Utc::now());
+    let mut body = serde_json::json!({
         "status": "healthy",
         "version": env!("CARGO_PKG_VERSION")
-    }))
+    });
+    if !maintenance.is_empty() {
+        body["maintenance"] = serde_json::json!(maintenance
+            .iter()
+            .map(|w| serde_json::json!({
+                "provider": w.provider,
+                "credential": w.credential,
+                "status": "maintenance",
+                "until": w.end,
+                "reason": w.reason,
+            }))
+            .collect::<Vec<_>>());
+    }
+    Json(body)
+}
+
+/// 存活检查端点响应（不做任何检查，立即返回）
+pub async fn health_live() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "alive" }))
 }
 
 /// 模型列表端点响应（静态列表，用于不指定凭证的情况）
+/// 内置的静态模型列表（模型 ID，拥有者），凭证池为空时使用
+pub const STATIC_MODELS: &[(&str, &str)] = &[
+    // Kiro/Claude models
+    ("claude-sonnet-4-5", "anthropic"),
+    ("claude-sonnet-4-5-20250929", "anthropic"),
+    ("claude-3-7-sonnet-20250219", "anthropic"),
+    ("claude-3-5-sonnet-latest", "anthropic"),
+    // Gemini models
+    ("gemini-2.5-flash", "google"),
+    ("gemini-2.5-flash-lite", "google"),
+    ("gemini-2.5-pro", "google"),
+    ("gemini-2.5-pro-preview-06-05", "google"),
+    ("gemini-3-pro-preview", "google"),
+    ("gemini-3-pro-image-preview", "google"),
+    ("gemini-3-flash-preview", "google"),
+    ("gemini-2.5-computer-use-preview-10-2025", "google"),
+    ("gemini-claude-sonnet-4-5", "google"),
+    ("gemini-claude-sonnet-4-5-thinking", "google"),
+    ("gemini-claude-opus-4-5-thinking", "google"),
+    // Qwen models
+    ("qwen3-coder-plus", "alibaba"),
+    ("qwen3-coder-flash", "alibaba"),
+];
+
+/// 静态模型条目（OpenAI 格式）
+pub fn static_model(id: &str, owned_by: &str) -> serde_json::Value {
+    serde_json::json!({"id": id, "object": "model", "owned_by": owned_by})
+}
+
 pub async fn models() -> impl IntoResponse {
+    let data: Vec<serde_json::Value> = STATIC_MODELS
+        .iter()
+        .map(|(id, owned_by)| static_model(id, owned_by))
+        .collect();
     Json(serde_json::json!({
         "object": "list",
-        "data": [
-            // Kiro/Claude models
-            {"id": "claude-sonnet-4-5", "object": "model", "owned_by": "anthropic"},
-            {"id": "claude-sonnet-4-5-20250929", "object": "model", "owned_by": "anthropic"},
-            {"id": "claude-3-7-sonnet-20250219", "object": "model", "owned_by": "anthropic"},
-            {"id": "claude-3-5-sonnet-latest", "object": "model", "owned_by": "anthropic"},
-            // Gemini models
-            {"id": "gemini-2.5-flash", "object": "model", "owned_by": "google"},
-            {"id": "gemini-2.5-flash-lite", "object": "model", "owned_by": "google"},
-            {"id": "gemini-2.5-pro", "object": "model", "owned_by": "google"},
-            {"id": "gemini-2.5-pro-preview-06-05", "object": "model", "owned_by": "google"},
-            {"id": "gemini-3-pro-preview", "object": "model", "owned_by": "google"},
-            {"id": "gemini-3-pro-image-preview", "object": "model", "owned_by": "google"},
-            {"id": "gemini-3-flash-preview", "object": "model", "owned_by": "google"},
-            {"id": "gemini-2.5-computer-use-preview-10-2025", "object": "model", "owned_by": "google"},
-            {"id": "gemini-claude-sonnet-4-5", "object": "model", "owned_by": "google"},
-            {"id": "gemini-claude-sonnet-4-5-thinking", "object": "model", "owned_by": "google"},
-            {"id": "gemini-claude-opus-4-5-thinking", "object": "model", "owned_by": "google"},
-            // Qwen models
-            {"id": "qwen3-coder-plus", "object": "model", "owned_by": "alibaba"},
-            {"id": "qwen3-coder-flash", "object": "model", "owned_by": "alibaba"}
-        ]
+        "data": data
     }))
 }
 
@@ -801,6 +825,69 @@ mod tests {
         assert_eq!(safe_truncate("你好世界", 2), "你好");
     }
 
+    #[test]
+    fn test_context_usage_header() {
+        let parsed = CWParsedResponse {
+            content: "hi".to_string(),
+            context_usage_percentage: 54.361,
+            ..Default::default()
+        };
+        let response = build_anthropic_response("claude-sonnet-4-5", &parsed);
+        assert_eq!(
+            response.headers().get(CONTEXT_USAGE_HEADER).unwrap(),
+            "54.36"
+        );
+        assert_eq!(context_usage_from_response(&response), Some(54.36));
+
+        // 上游未返回时不附加
+        let response = build_anthropic_response("claude-sonnet-4-5", &CWParsedResponse::default());
+        assert_eq!(context_usage_from_response(&response), None);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_response_stop_reason() {
+        let cases = [
+            (None, None, false, "end_turn"),
+            (None, None, true, "tool_use"),
+            (Some(StopReason::MaxTokens), None, true, "max_tokens"),
+            (Some(StopReason::ContentFilter), None, false, "refusal"),
+            (
+                Some(StopReason::StopSequence),
+                Some("END".to_string()),
+                false,
+                "stop_sequence",
+            ),
+        ];
+        for (stop_reason, stop_sequence, with_tool, expected) in cases {
+            let tool_calls = if with_tool {
+                vec![ToolCall {
+                    id: "toolu_1".to_string(),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: "get_weather".to_string(),
+                        arguments: "{}".to_string(),
+                    },
+                }]
+            } else {
+                Vec::new()
+            };
+            let parsed = CWParsedResponse {
+                content: "hi".to_string(),
+                tool_calls,
+                stop_reason,
+                stop_sequence: stop_sequence.clone(),
+                ..Default::default()
+            };
+            let response = build_anthropic_response("claude-sonnet-4-5", &parsed);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["stop_reason"], expected);
+            assert_eq!(body["stop_sequence"], serde_json::json!(stop_sequence));
+        }
+    }
+
     #[test]
     fn test_find_subsequence() {
         let haystack = b"hello world";
@@ -808,6 +895,79 @@ mod tests {
         assert_eq!(find_subsequence(haystack, b"foo"), None);
     }
 
+    #[test]
+    fn test_parse_cw_response_repairs_tool_arguments() {
+        let body = concat!(
+            r#"{"name":"read_file","toolUseId":"t1","input":"{\"path\":\"/tmp/a"}"#,
+            r#"{"toolUseId":"t1","input":".txt\","}"#,
+            r#"{"toolUseId":"t1","stop":true}"#,
+        );
+        let parsed = parse_cw_response(body.as_bytes());
+        assert_eq!(parsed.tool_calls.len(), 1);
+        assert_eq!(
+            parsed.tool_calls[0].function.arguments,
+            r#"{"path":"/tmp/a.txt"}"#
+        );
+    }
+
+    fn cw_event(event_type: &str, payload: &str) -> Vec<u8> {
+        use crate::stream::parsers::{EventStreamFrame, EventStreamHeaderValue};
+        EventStreamFrame {
+            headers: vec![
+                (
+                    ":message-type".to_string(),
+                    EventStreamHeaderValue::String("event".to_string()),
+                ),
+                (
+                    ":event-type".to_string(),
+                    EventStreamHeaderValue::String(event_type.to_string()),
+                ),
+            ],
+            payload: payload.as_bytes().to_vec(),
+        }
+        .encode()
+    }
+
+    #[test]
+    fn test_parse_cw_response_binary_frames() {
+        let body: Vec<u8> = [
+            cw_event("assistantResponseEvent", r#"{"content":"读取 {文件}"}"#),
+            cw_event(
+                "toolUseEvent",
+                r#"{"name":"read_file","toolUseId":"t1","input":"{\"path\":"}"#,
+            ),
+            cw_event("toolUseEvent", r#"{"toolUseId":"t1","input":"\"a.txt\"}"}"#),
+            cw_event("toolUseEvent", r#"{"toolUseId":"t1","stop":true}"#),
+            cw_event("meteringEvent", r#"{"unit":"credit","usage":0.5}"#),
+            cw_event("contextUsageEvent", r#"{"contextUsagePercentage":12.5}"#),
+        ]
+        .concat();
+
+        let parsed = parse_cw_response(&body);
+        assert_eq!(parsed.content, "读取 {文件}");
+        assert_eq!(parsed.tool_calls.len(), 1);
+        assert_eq!(
+            parsed.tool_calls[0].function.arguments,
+            r#"{"path":"a.txt"}"#
+        );
+        assert_eq!(parsed.usage_credits, 0.5);
+        assert_eq!(parsed.context_usage_percentage, 12.5);
+
+        // 末尾帧被截断时，完整帧仍按帧解析
+        let truncated = parse_cw_response(&body[..body.len() - 3]);
+        assert_eq!(truncated.content, "读取 {文件}");
+        assert_eq!(truncated.usage_credits, 0.5);
+
+        // 有损转换为文本后回退到 JSON 扫描
+        let lossy = String::from_utf8_lossy(&body).to_string();
+        let fallback = parse_cw_response(lossy.as_bytes());
+        assert_eq!(fallback.content, parsed.content);
+        assert_eq!(
+            fallback.tool_calls[0].function.arguments,
+            r#"{"path":"a.txt"}"#
+        );
+    }
+
     #[test]
     fn test_extract_json_from_bytes() {
         let json = b"{\"key\":\"value\"}";
@@ -833,6 +993,7 @@ mod tests {
 #[cfg(test)]
 mod property_tests {
     use super::*;
+    use axum::http::header;
     use proptest::prelude::*;
 
     // 生成随机文本内容
@@ -892,6 +1053,7 @@ mod property_tests {
                     tool_calls,
                     usage_credits,
                     context_usage_percentage,
+                    ..Default::default()
                 },
             )
     }
@@ -1044,6 +1206,7 @@ mod property_tests {
                 tool_calls: Vec::new(),
                 usage_credits: 0.0,
                 context_usage_percentage: 0.0,
+                ..Default::default()
             };
 
             let response = build_anthropic_response(&model, &parsed);
@@ -1068,6 +1231,7 @@ mod property_tests {
                 tool_calls,
                 usage_credits: 0.0,
                 context_usage_percentage: 50.0,
+                ..Default::default()
             };
 
             let response = build_anthropic_response(&model, &parsed);
@@ -1092,6 +1256,7 @@ mod property_tests {
                 tool_calls: Vec::new(),
                 usage_credits: 0.0,
                 context_usage_percentage: context_percentage,
+                ..Default::default()
             };
 
             let (input_tokens, output_tokens) = parsed.estimate_tokens();
@@ -1408,4 +1573,128 @@ mod property_tests {
             );
         }
     }
+
+    #[test]
+    fn test_openai_completion_to_chunks() {
+        let completion = serde_json::json!({
+            "id": "chatcmpl-1",
+            "created": 1700000000,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Checking",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"NYC\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        });
+        let events = openai_completion_to_chunks(&completion, true);
+        assert_eq!(events.last().unwrap(), "data: [DONE]\n\n");
+        let chunks: Vec<serde_json::Value> = events[..events.len() - 1]
+            .iter()
+            .map(|e| serde_json::from_str(e.strip_prefix("data: ").unwrap().trim()).unwrap())
+            .collect();
+
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(chunks[1]["choices"][0]["delta"]["content"], "Checking");
+        assert_eq!(
+            chunks[2]["choices"][0]["delta"]["tool_calls"][0]["function"]["name"],
+            "get_weather"
+        );
+        assert_eq!(
+            chunks[3]["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"],
+            "{\"city\":\"NYC\"}"
+        );
+        assert_eq!(chunks[4]["choices"][0]["finish_reason"], "tool_calls");
+        assert!(chunks[4]["usage"].is_null());
+        assert_eq!(chunks[5]["choices"], serde_json::json!([]));
+        assert_eq!(chunks[5]["usage"]["total_tokens"], 15);
+        assert!(chunks
+            .iter()
+            .all(|c| c["object"] == "chat.completion.chunk"));
+
+        // 不需要用量时不输出用量 chunk
+        let events = openai_completion_to_chunks(&completion, false);
+        assert_eq!(events.len(), 6);
+        assert!(!events[0].contains("usage"));
+    }
+
+    #[test]
+    fn test_multipart_text_field() {
+        let content_type = "multipart/form-data; boundary=\"XyZ\"";
+        let body = b"--XyZ\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"model.mp3\"\r\n\
+Content-Type: audio/mpeg\r\n\r\n\
+\x00\xffname=\"model\"\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"model\"\r\n\r\n\
+whisper-1\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"language\"\r\n\r\n\
+zh\r\n\
+--XyZ--\r\n";
+
+        assert_eq!(
+            multipart_text_field(body, content_type, "model").as_deref(),
+            Some("whisper-1")
+        );
+        assert_eq!(
+            multipart_text_field(body, content_type, "language").as_deref(),
+            Some("zh")
+        );
+        assert_eq!(multipart_text_field(body, content_type, "file"), None);
+        assert_eq!(multipart_text_field(body, content_type, "prompt"), None);
+        assert_eq!(
+            multipart_text_field(body, "multipart/form-data", "model"),
+            None
+        );
+
+        let fields = parse_multipart(body, content_type).unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].name, "file");
+        assert_eq!(fields[0].filename.as_deref(), Some("model.mp3"));
+        assert_eq!(fields[0].content_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(fields[0].data, b"\x00\xffname=\"model\"");
+    }
+
+    #[test]
+    fn test_merge_fanout_completions() {
+        let completion = |content: &str| {
+            serde_json::json!({
+                "id": format!("chatcmpl-{content}"),
+                "object": "chat.completion",
+                "model": "claude-sonnet-4-5",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": content},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 10, "completion_tokens": 2, "total_tokens": 12}
+            })
+        };
+        let merged =
+            merge_fanout_completions(vec![completion("a"), completion("b"), completion("c")]);
+
+        assert_eq!(merged["id"], "chatcmpl-a");
+        let choices = merged["choices"].as_array().unwrap();
+        assert_eq!(choices.len(), 3);
+        for (i, content) in ["a", "b", "c"].iter().enumerate() {
+            assert_eq!(choices[i]["index"], i);
+            assert_eq!(choices[i]["message"]["content"], *content);
+        }
+        assert_eq!(merged["usage"]["prompt_tokens"], 30);
+        assert_eq!(merged["usage"]["completion_tokens"], 6);
+        assert_eq!(merged["usage"]["total_tokens"], 36);
+
+        // 合并结果可直接转换为多候选的流式事件
+        let events = openai_completion_to_chunks(&merged, false);
+        assert!(events.iter().any(|e| e.contains("\"index\":2")));
+    }
 }
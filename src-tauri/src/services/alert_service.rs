@@ -0,0 +1,537 @@
+//! 告警服务
+//!
+//! 定期基于遥测数据评估告警规则（错误率、凭证全部不可用、磁盘空间、Token 预算），
+//! 触发时发送 Webhook（通用 JSON / Slack / Discord）和桌面通知。
+//!
+//! 同一规则在冷却时间内只告警一次，避免持续异常时刷屏。
+
+use crate::app::FrontendHandle;
+use crate::config::{AlertCondition, AlertRuleConfig, AlertWebhookFormat, AlertsConfig};
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::telemetry::{StatsAggregator, TimeRange, TokenTracker};
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::OnceCell;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(feature = "desktop")]
+use tauri::Emitter;
+use tokio::sync::broadcast;
+
+/// 告警触发时发送给前端的事件名（前端据此弹出桌面通知）
+pub const ALERT_EVENT: &str = "alert-triggered";
+
+/// 桌面通知使用的 AppHandle
+static ALERT_APP_HANDLE: OnceCell<FrontendHandle> = OnceCell::new();
+
+/// 设置桌面通知使用的 AppHandle
+pub fn set_alert_app_handle(handle: FrontendHandle) {
+    let _ = ALERT_APP_HANDLE.set(handle);
+}
+
+/// 告警事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    /// 规则名称
+    pub rule: String,
+    /// 条件类型（error_rate / all_credentials_unhealthy / low_disk_space / token_budget）
+    pub kind: String,
+    /// 告警内容
+    pub message: String,
+    /// 触发时的观测值
+    pub value: f64,
+    /// 阈值
+    pub threshold: f64,
+    /// 触发时间
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// 告警评估所需的数据源
+pub trait AlertDataSource {
+    /// 窗口内的 (总请求数, 失败请求数)，失败包含超时
+    fn request_counts(&self, window: Duration) -> (u64, u64);
+    /// 所有凭证都不可用的 Provider 列表
+    fn unhealthy_providers(&self) -> Vec<String>;
+    /// 路径所在磁盘的剩余空间（MB）
+    fn free_disk_mb(&self, path: Option<&str>) -> Option<u64>;
+    /// 窗口内的 Token 用量（输入 + 输出）
+    fn token_usage(&self, window: Duration) -> u64;
+}
+
+/// 基于遥测统计和凭证池的数据源
+pub struct TelemetryAlertSource {
+    pub stats: Arc<RwLock<StatsAggregator>>,
+    pub tokens: Arc<RwLock<TokenTracker>>,
+    pub db: Option<DbConnection>,
+}
+
+impl AlertDataSource for TelemetryAlertSource {
+    fn request_counts(&self, window: Duration) -> (u64, u64) {
+        let now = Utc::now();
+        let summary = self
+            .stats
+            .read()
+            .summary(Some(TimeRange::new(now - window, now)));
+        (
+            summary.total_requests,
+            summary.failed_requests + summary.timeout_requests,
+        )
+    }
+
+    fn unhealthy_providers(&self) -> Vec<String> {
+        let Some(db) = &self.db else {
+            return Vec::new();
+        };
+        let credentials = match db.lock() {
+            Ok(conn) => ProviderPoolDao::get_all(&conn).unwrap_or_default(),
+            Err(_) => return Vec::new(),
+        };
+
+        // Provider -> (启用的凭证数, 可用的凭证数)
+        let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for cred in credentials.iter().filter(|c| !c.is_disabled) {
+            let entry = counts.entry(cred.provider_type.to_string()).or_default();
+            entry.0 += 1;
+            if cred.is_available() {
+                entry.1 += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .filter(|(_, (enabled, available))| *enabled > 0 && *available == 0)
+            .map(|(provider, _)| provider)
+            .collect()
+    }
+
+    fn free_disk_mb(&self, path: Option<&str>) -> Option<u64> {
+        let path = match path {
+            Some(p) => PathBuf::from(p),
+            None => crate::database::get_db_path().ok()?.parent()?.to_path_buf(),
+        };
+        fs2::available_space(&path)
+            .ok()
+            .map(|bytes| bytes / 1024 / 1024)
+    }
+
+    fn token_usage(&self, window: Duration) -> u64 {
+        let now = Utc::now();
+        self.tokens
+            .read()
+            .summary(Some(now - window), Some(now))
+            .total_tokens
+    }
+}
+
+/// 评估单条规则，触发时返回 (告警内容, 观测值, 阈值)
+pub fn evaluate_rule(
+    rule: &AlertRuleConfig,
+    source: &dyn AlertDataSource,
+) -> Option<(String, f64, f64)> {
+    match &rule.condition {
+        AlertCondition::ErrorRate {
+            threshold_percent,
+            window_minutes,
+            min_requests,
+        } => {
+            let (total, failed) = source.request_counts(Duration::minutes(*window_minutes));
+            if total == 0 || total < *min_requests {
+                return None;
+            }
+            let rate = failed as f64 / total as f64 * 100.0;
+            (rate > *threshold_percent).then(|| {
+                (
+                    format!(
+                        "最近 {} 分钟错误率 {:.1}%（{}/{}），超过阈值 {}%",
+                        window_minutes, rate, failed, total, threshold_percent
+                    ),
+                    rate,
+                    *threshold_percent,
+                )
+            })
+        }
+        AlertCondition::AllCredentialsUnhealthy { provider } => {
+            let unhealthy: Vec<String> = source
+                .unhealthy_providers()
+                .into_iter()
+                .filter(|p| {
+                    provider
+                        .as_ref()
+                        .is_none_or(|want| p.eq_ignore_ascii_case(want))
+                })
+                .collect();
+            (!unhealthy.is_empty()).then(|| {
+                (
+                    format!("以下 Provider 的凭证全部不可用: {}", unhealthy.join(", ")),
+                    unhealthy.len() as f64,
+                    0.0,
+                )
+            })
+        }
+        AlertCondition::LowDiskSpace { min_free_mb, path } => {
+            let free = source.free_disk_mb(path.as_deref())?;
+            (free < *min_free_mb).then(|| {
+                (
+                    format!("磁盘剩余空间 {} MB，低于 {} MB", free, min_free_mb),
+                    free as f64,
+                    *min_free_mb as f64,
+                )
+            })
+        }
+        AlertCondition::TokenBudget {
+            max_tokens,
+            window_hours,
+        } => {
+            let used = source.token_usage(Duration::hours(*window_hours));
+            (used > *max_tokens).then(|| {
+                (
+                    format!(
+                        "最近 {} 小时 Token 用量 {}，超过预算 {}",
+                        window_hours, used, max_tokens
+                    ),
+                    used as f64,
+                    *max_tokens as f64,
+                )
+            })
+        }
+    }
+}
+
+fn condition_kind(condition: &AlertCondition) -> &'static str {
+    match condition {
+        AlertCondition::ErrorRate { .. } => "error_rate",
+        AlertCondition::AllCredentialsUnhealthy { .. } => "all_credentials_unhealthy",
+        AlertCondition::LowDiskSpace { .. } => "low_disk_space",
+        AlertCondition::TokenBudget { .. } => "token_budget",
+    }
+}
+
+/// 按 Webhook 格式构建请求体
+pub fn webhook_payload(format: AlertWebhookFormat, event: &AlertEvent) -> serde_json::Value {
+    let text = format!("[ProxyCast] {}: {}", event.rule, event.message);
+    match format {
+        AlertWebhookFormat::Generic => serde_json::json!({
+            "source": "proxycast",
+            "alert": event,
+        }),
+        AlertWebhookFormat::Slack => serde_json::json!({ "text": text }),
+        AlertWebhookFormat::Discord => serde_json::json!({ "content": text }),
+    }
+}
+
+/// 告警服务
+pub struct AlertService {
+    client: reqwest::Client,
+    /// 规则名称 -> 上次触发时间
+    last_fired: Mutex<HashMap<String, DateTime<Utc>>>,
+    /// 告警事件广播
+    sender: broadcast::Sender<AlertEvent>,
+}
+
+impl Default for AlertService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlertService {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(64);
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            last_fired: Mutex::new(HashMap::new()),
+            sender,
+        }
+    }
+
+    /// 订阅告警事件
+    pub fn subscribe(&self) -> broadcast::Receiver<AlertEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 评估所有启用的规则，返回本轮需要发送的告警（已应用冷却）
+    pub fn evaluate(
+        &self,
+        config: &AlertsConfig,
+        source: &dyn AlertDataSource,
+        now: DateTime<Utc>,
+    ) -> Vec<AlertEvent> {
+        let cooldown = Duration::seconds(config.cooldown_secs as i64);
+        let mut last_fired = self.last_fired.lock();
+        let mut events = Vec::new();
+
+        for rule in config.rules.iter().filter(|r| r.enabled) {
+            let Some((message, value, threshold)) = evaluate_rule(rule, source) else {
+                continue;
+            };
+            if let Some(last) = last_fired.get(&rule.name) {
+                if now - *last < cooldown {
+                    continue;
+                }
+            }
+            last_fired.insert(rule.name.clone(), now);
+            events.push(AlertEvent {
+                rule: rule.name.clone(),
+                kind: condition_kind(&rule.condition).to_string(),
+                message,
+                value,
+                threshold,
+                triggered_at: now,
+            });
+        }
+        events
+    }
+
+    /// 发送告警（Webhook、桌面通知、事件广播）
+    pub async fn notify(&self, config: &AlertsConfig, events: &[AlertEvent]) {
+        for event in events {
+            tracing::warn!("[ALERT] {}: {}", event.rule, event.message);
+            let _ = self.sender.send(event.clone());
+
+            if config.desktop_notification {
+                if let Some(handle) = ALERT_APP_HANDLE.get() {
+                    if let Err(e) = handle.emit(ALERT_EVENT, event) {
+                        tracing::warn!("[ALERT] 发送桌面通知失败: {}", e);
+                    }
+                }
+            }
+
+            for webhook in &config.webhooks {
+                let result = self
+                    .client
+                    .post(&webhook.url)
+                    .json(&webhook_payload(webhook.format, event))
+                    .send()
+                    .await
+                    .and_then(|resp| resp.error_for_status());
+                if let Err(e) = result {
+                    tracing::warn!("[ALERT] Webhook 发送失败 ({}): {}", webhook.url, e);
+                }
+            }
+        }
+    }
+
+    /// 启动定期评估任务
+    ///
+    /// `config` 每轮调用一次，以便热重载后的配置立即生效。
+    pub fn spawn<F>(
+        self: Arc<Self>,
+        config: F,
+        source: TelemetryAlertSource,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn() -> AlertsConfig + Send + Sync + 'static,
+    {
+        tokio::spawn(async move {
+            loop {
+                let current = config();
+                let interval = current.check_interval_secs.max(10);
+                if current.enabled && !current.rules.is_empty() {
+                    let events = self.evaluate(&current, &source, Utc::now());
+                    if !events.is_empty() {
+                        self.notify(&current, &events).await;
+                    }
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockSource {
+        total: u64,
+        failed: u64,
+        unhealthy: Vec<String>,
+        free_mb: Option<u64>,
+        tokens: u64,
+    }
+
+    impl AlertDataSource for MockSource {
+        fn request_counts(&self, _window: Duration) -> (u64, u64) {
+            (self.total, self.failed)
+        }
+
+        fn unhealthy_providers(&self) -> Vec<String> {
+            self.unhealthy.clone()
+        }
+
+        fn free_disk_mb(&self, _path: Option<&str>) -> Option<u64> {
+            self.free_mb
+        }
+
+        fn token_usage(&self, _window: Duration) -> u64 {
+            self.tokens
+        }
+    }
+
+    fn rule(name: &str, condition: AlertCondition) -> AlertRuleConfig {
+        AlertRuleConfig {
+            name: name.to_string(),
+            enabled: true,
+            condition,
+        }
+    }
+
+    #[test]
+    fn test_error_rate_rule() {
+        let rule = rule(
+            "errors",
+            AlertCondition::ErrorRate {
+                threshold_percent: 20.0,
+                window_minutes: 5,
+                min_requests: 10,
+            },
+        );
+
+        let source = MockSource {
+            total: 20,
+            failed: 5,
+            ..Default::default()
+        };
+        let (_, value, threshold) = evaluate_rule(&rule, &source).unwrap();
+        assert_eq!(value, 25.0);
+        assert_eq!(threshold, 20.0);
+
+        // 样本不足不告警
+        let source = MockSource {
+            total: 5,
+            failed: 5,
+            ..Default::default()
+        };
+        assert!(evaluate_rule(&rule, &source).is_none());
+    }
+
+    #[test]
+    fn test_other_rules() {
+        let source = MockSource {
+            unhealthy: vec!["kiro".to_string()],
+            free_mb: Some(512),
+            tokens: 2000,
+            ..Default::default()
+        };
+
+        let unhealthy = rule(
+            "creds",
+            AlertCondition::AllCredentialsUnhealthy {
+                provider: Some("gemini".to_string()),
+            },
+        );
+        assert!(evaluate_rule(&unhealthy, &source).is_none());
+        let unhealthy = rule(
+            "creds",
+            AlertCondition::AllCredentialsUnhealthy { provider: None },
+        );
+        assert!(evaluate_rule(&unhealthy, &source).is_some());
+
+        let disk = rule(
+            "disk",
+            AlertCondition::LowDiskSpace {
+                min_free_mb: 1024,
+                path: None,
+            },
+        );
+        assert!(evaluate_rule(&disk, &source).is_some());
+
+        let budget = rule(
+            "budget",
+            AlertCondition::TokenBudget {
+                max_tokens: 5000,
+                window_hours: 24,
+            },
+        );
+        assert!(evaluate_rule(&budget, &source).is_none());
+    }
+
+    #[test]
+    fn test_evaluate_applies_cooldown() {
+        let service = AlertService::new();
+        let config = AlertsConfig {
+            enabled: true,
+            cooldown_secs: 600,
+            rules: vec![rule(
+                "disk",
+                AlertCondition::LowDiskSpace {
+                    min_free_mb: 1024,
+                    path: None,
+                },
+            )],
+            ..Default::default()
+        };
+        let source = MockSource {
+            free_mb: Some(100),
+            ..Default::default()
+        };
+
+        let now = Utc::now();
+        let events = service.evaluate(&config, &source, now);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, "low_disk_space");
+        assert!(service
+            .evaluate(&config, &source, now + Duration::seconds(60))
+            .is_empty());
+        assert_eq!(
+            service
+                .evaluate(&config, &source, now + Duration::seconds(601))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_webhook_payload_formats() {
+        let event = AlertEvent {
+            rule: "errors".to_string(),
+            kind: "error_rate".to_string(),
+            message: "too many errors".to_string(),
+            value: 50.0,
+            threshold: 20.0,
+            triggered_at: Utc::now(),
+        };
+
+        let generic = webhook_payload(AlertWebhookFormat::Generic, &event);
+        assert_eq!(generic["alert"]["rule"], "errors");
+        let slack = webhook_payload(AlertWebhookFormat::Slack, &event);
+        assert_eq!(slack["text"], "[ProxyCast] errors: too many errors");
+        let discord = webhook_payload(AlertWebhookFormat::Discord, &event);
+        assert_eq!(discord["content"], "[ProxyCast] errors: too many errors");
+    }
+
+    #[test]
+    fn test_alert_config_yaml() {
+        let yaml = r#"
+enabled: true
+rules:
+  - name: high-errors
+    type: error_rate
+    threshold_percent: 10
+  - name: budget
+    type: token_budget
+    max_tokens: 1000000
+webhooks:
+  - url: https://hooks.slack.com/services/x
+    format: slack
+"#;
+        let config: AlertsConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(
+            config.rules[0].condition,
+            AlertCondition::ErrorRate {
+                threshold_percent: 10.0,
+                window_minutes: 5,
+                min_requests: 10,
+            }
+        );
+        assert_eq!(config.webhooks[0].format, AlertWebhookFormat::Slack);
+        assert_eq!(config.check_interval_secs, 60);
+    }
+}
@@ -895,6 +895,9 @@ impl ApiKeyProviderService {
             PoolProviderType::AzureOpenai => Some(ApiProviderType::AzureOpenai),
             PoolProviderType::AwsBedrock => Some(ApiProviderType::AwsBedrock),
             PoolProviderType::Ollama => Some(ApiProviderType::Ollama),
+            PoolProviderType::OpenRouter => Some(ApiProviderType::Openai),
+            PoolProviderType::Mistral => Some(ApiProviderType::Openai),
+            PoolProviderType::DeepSeek => Some(ApiProviderType::Openai),
 
             // OAuth-only，无降级
             PoolProviderType::Kiro => None,
@@ -1111,6 +1114,13 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported,
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         })
     }
 
@@ -1169,6 +1179,13 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported, // 标记为导入来源
             proxy_url: None,
+            tier: CredentialTier::Primary,
+            schedule: None,
+            daily_usage_count: 0,
+            daily_usage_reset_at: None,
+            learned_unsupported_models: HashMap::new(),
+            tags: Vec::new(),
+            pinned_model: None,
         })
     }
 }
@@ -1186,5 +1203,5 @@ pub struct ImportResult {
 use serde::{Deserialize, Serialize};
 
 use crate::models::provider_pool_model::{
-    CredentialData, CredentialSource, PoolProviderType, ProviderCredential,
+    CredentialData, CredentialSource, CredentialTier, PoolProviderType, ProviderCredential,
 };
@@ -0,0 +1,188 @@
+//! 集群模式
+//!
+//! 多台机器上的 proxycast 实例通过共享的凭证池存储后端（见 `credential_storage`）
+//! 共享凭证健康状态和限流状态（健康标记、错误计数、最近的 429 错误都保存在凭证记录中），
+//! 并通过存储后端的租约选出 leader。后台维护任务（Token 主动刷新、凭证过期检查、
+//! 每日请求计数重置、每日用量报告、定期健康检查和备份）只在 leader 上执行，避免重复工作。
+//!
+//! 内置的存储后端（本地 SQLite、内存后端）都不能在实例间共享，集群模式只在通过
+//! `ProviderPoolDao::set_storage` 注册了共享后端（`is_shared()` 为 `true`）时自动启用；
+//! 其他情况下本实例按单机模式运行，始终为 leader。
+
+use crate::config::ClusterConfig;
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::BulkCredentialFilter;
+use crate::services::backup_service::BackupService;
+use crate::services::provider_pool_service::ProviderPoolService;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// leader 租约名称
+pub const LEADER_LEASE: &str = "proxycast-leader";
+
+/// 本实例是否为 leader
+static IS_LEADER: AtomicBool = AtomicBool::new(true);
+
+/// 集群模式下本实例的 ID（未启用集群模式时为空）
+static INSTANCE_ID: OnceCell<String> = OnceCell::new();
+
+/// 本实例是否为 leader（未启用集群模式时始终为 `true`）
+pub fn is_leader() -> bool {
+    IS_LEADER.load(Ordering::SeqCst)
+}
+
+/// 集群状态
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterStatus {
+    pub enabled: bool,
+    pub instance_id: Option<String>,
+    pub is_leader: bool,
+    /// 凭证池存储后端
+    pub storage_backend: String,
+}
+
+/// 获取本实例的集群状态
+pub fn status() -> ClusterStatus {
+    ClusterStatus {
+        enabled: INSTANCE_ID.get().is_some(),
+        instance_id: INSTANCE_ID.get().cloned(),
+        is_leader: is_leader(),
+        storage_backend: ProviderPoolDao::storage_name(),
+    }
+}
+
+/// 竞选 leader，返回本实例是否为 leader
+///
+/// 访问存储后端失败时放弃 leader 身份，宁可暂停后台任务也不让多个 leader 重复执行。
+fn campaign(instance_id: &str, ttl: Duration) -> bool {
+    let elected = match ProviderPoolDao::try_acquire_lease(LEADER_LEASE, instance_id, ttl) {
+        Ok(elected) => elected,
+        Err(e) => {
+            tracing::warn!("[CLUSTER] 续约 leader 租约失败: {}", e);
+            false
+        }
+    };
+    let was_leader = IS_LEADER.swap(elected, Ordering::SeqCst);
+    if elected != was_leader {
+        if elected {
+            tracing::info!("[CLUSTER] 实例 {} 成为 leader", instance_id);
+        } else {
+            tracing::info!("[CLUSTER] 实例 {} 不再是 leader", instance_id);
+        }
+    }
+    elected
+}
+
+/// 启动集群模式（存储后端可共享时）及 leader 专属的定期任务，返回启动的任务
+pub fn spawn_cluster(
+    config: &ClusterConfig,
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+) -> Vec<tokio::task::JoinHandle<()>> {
+    let mut tasks = Vec::new();
+    // 本地 SQLite 和内存后端的租约只在本进程内有效，不参与选举
+    if ProviderPoolDao::is_shared_storage() {
+        tasks.push(spawn_leader_election(config));
+    }
+    if config.health_check_interval_secs > 0 {
+        tasks.push(spawn_health_check_task(
+            pool_service,
+            db.clone(),
+            Duration::from_secs(config.health_check_interval_secs.max(60)),
+        ));
+    }
+    if config.backup_interval_hours > 0 {
+        tasks.push(spawn_backup_task(
+            db,
+            Duration::from_secs(config.backup_interval_hours * 3600),
+        ));
+    }
+    tasks
+}
+
+/// 启动 leader 选举任务（重复启动服务器时沿用已有的实例 ID）
+fn spawn_leader_election(config: &ClusterConfig) -> tokio::task::JoinHandle<()> {
+    let instance_id = INSTANCE_ID
+        .get_or_init(|| {
+            config
+                .instance_id
+                .clone()
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+        })
+        .clone();
+
+    // 当选前不执行后台维护任务
+    IS_LEADER.store(false, Ordering::SeqCst);
+    let ttl = Duration::from_secs(config.lease_ttl_secs.max(3));
+    tracing::info!(
+        "[CLUSTER] 集群模式已启用，实例 {}，存储后端 {}",
+        instance_id,
+        ProviderPoolDao::storage_name()
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ttl / 3);
+        loop {
+            interval.tick().await;
+            campaign(&instance_id, ttl);
+        }
+    })
+}
+
+/// 启动定期健康检查任务（仅 leader 执行）
+fn spawn_health_check_task(
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+    period: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        // 跳过启动时的立即触发
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            let concurrency = pool_service.health_check_concurrency(None);
+            match pool_service
+                .bulk_check_health(&db, &BulkCredentialFilter::default(), concurrency)
+                .await
+            {
+                Ok(results) => {
+                    let failed = results.iter().filter(|r| !r.success).count();
+                    tracing::info!(
+                        "[CLUSTER] 定期健康检查完成：{} 个凭证，{} 个失败",
+                        results.len(),
+                        failed
+                    );
+                }
+                Err(e) => tracing::warn!("[CLUSTER] 定期健康检查失败: {}", e),
+            }
+        }
+    })
+}
+
+/// 启动定期数据库备份任务（仅 leader 执行）
+fn spawn_backup_task(db: DbConnection, period: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            let result = BackupService::with_defaults()
+                .and_then(|service| service.backup_database_with_connection(&db));
+            match result {
+                Ok(path) => tracing::info!("[CLUSTER] 数据库已备份到 {:?}", path),
+                Err(e) => tracing::warn!("[CLUSTER] 数据库备份失败: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,422 @@
+//! 凭证过期预警
+//!
+//! 根据最近一次成功刷新时间、Refresh Token 过期时间和连续刷新失败次数计算 OAuth 凭证的过期状态，
+//! 后台定期检查，在 Refresh Token 失效前 `warn_days` 天起通过日志和 WebSocket 发出预警。
+//! 同一凭证的同一状态每 24 小时最多预警一次，状态变化时立即预警。
+
+use crate::config::CredentialExpiryConfig;
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{
+    get_oauth_creds_path, CachedTokenInfo, CredentialExpiryInfo, CredentialExpiryStatus,
+    ExpiryState,
+};
+use crate::services::provider_pool_service::ProviderPoolService;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// 同一状态重复预警的间隔（小时）
+const REPEAT_WARNING_HOURS: i64 = 24;
+
+/// 凭证文件中表示 Refresh Token 过期时间的字段（RFC3339 或 Unix 时间戳）
+const EXPIRES_AT_KEYS: &[&str] = &["refreshTokenExpiresAt", "refresh_token_expires_at"];
+
+/// 凭证文件中表示 Refresh Token 有效期的字段（秒）
+const EXPIRES_IN_KEYS: &[&str] = &["refreshTokenExpiresIn", "refresh_token_expires_in"];
+
+/// 从凭证 JSON 中解析 Refresh Token 过期时间
+///
+/// 只给出有效期（expires_in）时从 `now` 起算。
+pub fn refresh_token_expiry_from_json(
+    json: &serde_json::Value,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    for key in EXPIRES_AT_KEYS {
+        match &json[key] {
+            serde_json::Value::String(s) => {
+                if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+                    return Some(dt.with_timezone(&Utc));
+                }
+            }
+            serde_json::Value::Number(n) => {
+                if let Some(ts) = n.as_i64() {
+                    // 13 位以上视为毫秒
+                    let dt = if ts > 100_000_000_000 {
+                        Utc.timestamp_millis_opt(ts).single()
+                    } else {
+                        Utc.timestamp_opt(ts, 0).single()
+                    };
+                    if dt.is_some() {
+                        return dt;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    EXPIRES_IN_KEYS
+        .iter()
+        .find_map(|key| json[key].as_i64())
+        .filter(|secs| *secs > 0)
+        .map(|secs| now + Duration::seconds(secs))
+}
+
+/// 从凭证文件中读取 Refresh Token 过期时间
+pub fn refresh_token_expiry_from_file(path: &str) -> Option<DateTime<Utc>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    refresh_token_expiry_from_json(&json, Utc::now())
+}
+
+/// 计算凭证的过期状态
+///
+/// 优先级：已过期 > 刷新失败 > 即将过期 > 正常。
+/// 凭证文件未给出 Refresh Token 过期时间时，按配置的有效期从最近一次成功刷新起算。
+pub fn evaluate(
+    provider_type: &str,
+    cache: Option<&CachedTokenInfo>,
+    info: &CredentialExpiryInfo,
+    config: &CredentialExpiryConfig,
+    now: DateTime<Utc>,
+) -> CredentialExpiryStatus {
+    let expiry = info.refresh_token_expiry.or_else(|| {
+        let days = config.refresh_token_lifetime_days.get(provider_type)?;
+        info.last_successful_refresh
+            .map(|t| t + Duration::days(*days as i64))
+    });
+    let refresh_failing =
+        cache.is_some_and(|c| c.refresh_error_count >= config.refresh_failure_threshold.max(1));
+
+    let state = match expiry {
+        Some(expiry) if expiry <= now => ExpiryState::Expired,
+        _ if refresh_failing => ExpiryState::RefreshFailing,
+        Some(expiry) if expiry <= now + Duration::days(config.warn_days as i64) => {
+            ExpiryState::ExpiringSoon
+        }
+        _ => ExpiryState::Ok,
+    };
+
+    CredentialExpiryStatus {
+        state,
+        last_successful_refresh: info.last_successful_refresh.map(|t| t.to_rfc3339()),
+        refresh_token_expiry: expiry.map(|t| t.to_rfc3339()),
+        days_until_expiry: expiry.map(|t| (t - now).num_seconds().div_euclid(86400)),
+    }
+}
+
+/// 凭证过期预警
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialExpiryWarning {
+    pub uuid: String,
+    pub provider_type: String,
+    pub name: Option<String>,
+    pub state: ExpiryState,
+    pub refresh_token_expiry: Option<String>,
+    pub days_until_expiry: Option<i64>,
+    pub message: String,
+}
+
+impl CredentialExpiryWarning {
+    fn new(
+        uuid: &str,
+        provider_type: &str,
+        name: Option<String>,
+        status: &CredentialExpiryStatus,
+    ) -> Self {
+        let label = name
+            .clone()
+            .unwrap_or_else(|| uuid[..8.min(uuid.len())].to_string());
+        let message = match (status.state, status.days_until_expiry) {
+            (ExpiryState::Expired, _) => {
+                format!(
+                    "{} 凭证 {} 的 Refresh Token 已过期，请重新授权",
+                    provider_type, label
+                )
+            }
+            (ExpiryState::RefreshFailing, _) => {
+                format!(
+                    "{} 凭证 {} 连续刷新失败，可能需要重新授权",
+                    provider_type, label
+                )
+            }
+            (_, Some(days)) => format!(
+                "{} 凭证 {} 的 Refresh Token 将在 {} 天内过期",
+                provider_type,
+                label,
+                days.max(0)
+            ),
+            _ => format!("{} 凭证 {} 的 Refresh Token 即将过期", provider_type, label),
+        };
+        Self {
+            uuid: uuid.to_string(),
+            provider_type: provider_type.to_string(),
+            name,
+            state: status.state,
+            refresh_token_expiry: status.refresh_token_expiry.clone(),
+            days_until_expiry: status.days_until_expiry,
+            message,
+        }
+    }
+}
+
+/// 凭证过期监控
+pub struct CredentialExpiryMonitor {
+    sender: broadcast::Sender<CredentialExpiryWarning>,
+    /// 每个凭证最近一次预警的状态和时间
+    last_warned: parking_lot::Mutex<HashMap<String, (ExpiryState, DateTime<Utc>)>>,
+}
+
+impl Default for CredentialExpiryMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialExpiryMonitor {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(64);
+        Self {
+            sender,
+            last_warned: parking_lot::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 订阅预警
+    pub fn subscribe(&self) -> broadcast::Receiver<CredentialExpiryWarning> {
+        self.sender.subscribe()
+    }
+
+    /// 检查所有启用的 OAuth 凭证，返回本轮发出的预警
+    pub fn check(
+        &self,
+        db: &DbConnection,
+        config: &CredentialExpiryConfig,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<CredentialExpiryWarning>, String> {
+        let statuses = {
+            let conn = db.lock().map_err(|e| e.to_string())?;
+            let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+            let expiry_info = ProviderPoolDao::get_all_expiry_info(&conn).unwrap_or_default();
+
+            let mut statuses = Vec::new();
+            for cred in credentials {
+                if cred.is_disabled || get_oauth_creds_path(&cred.credential).is_none() {
+                    continue;
+                }
+                let cache = ProviderPoolDao::get_token_cache(&conn, &cred.uuid)
+                    .ok()
+                    .flatten();
+                let provider_type = cred.provider_type.to_string();
+                let info = expiry_info.get(&cred.uuid).cloned().unwrap_or_default();
+                let status = evaluate(&provider_type, cache.as_ref(), &info, config, now);
+                statuses.push((cred.uuid, provider_type, cred.name, status));
+            }
+            statuses
+        };
+
+        let warnings = self.collect_warnings(statuses, now);
+        for warning in &warnings {
+            tracing::warn!("[CREDENTIAL_EXPIRY] {}", warning.message);
+            let _ = self.sender.send(warning.clone());
+        }
+        Ok(warnings)
+    }
+
+    /// 按状态变化和重复间隔筛选需要发出的预警
+    fn collect_warnings(
+        &self,
+        statuses: Vec<(String, String, Option<String>, CredentialExpiryStatus)>,
+        now: DateTime<Utc>,
+    ) -> Vec<CredentialExpiryWarning> {
+        let mut last_warned = self.last_warned.lock();
+        let mut warnings = Vec::new();
+        for (uuid, provider_type, name, status) in statuses {
+            if status.state == ExpiryState::Ok {
+                last_warned.remove(&uuid);
+                continue;
+            }
+            let due = match last_warned.get(&uuid) {
+                Some((state, at)) => {
+                    *state != status.state || now - *at >= Duration::hours(REPEAT_WARNING_HOURS)
+                }
+                None => true,
+            };
+            if due {
+                last_warned.insert(uuid.clone(), (status.state, now));
+                warnings.push(CredentialExpiryWarning::new(
+                    &uuid,
+                    &provider_type,
+                    name,
+                    &status,
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// 启动后台过期检查任务（每次循环重新读取配置，支持热重载；集群模式下仅 leader 执行）
+pub fn spawn_credential_expiry_monitor<F>(
+    monitor: Arc<CredentialExpiryMonitor>,
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+    config: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> CredentialExpiryConfig + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let current = config();
+            pool_service.set_expiry_config(current.clone());
+            if current.enabled && crate::services::cluster_service::is_leader() {
+                if let Err(e) = monitor.check(&db, &current, Utc::now()) {
+                    tracing::warn!("[CREDENTIAL_EXPIRY] 检查凭证过期状态失败: {}", e);
+                }
+            }
+            let interval = current.check_interval_secs.max(60);
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CredentialExpiryConfig {
+        CredentialExpiryConfig {
+            refresh_token_lifetime_days: HashMap::from([("kiro".to_string(), 90)]),
+            ..CredentialExpiryConfig::default()
+        }
+    }
+
+    fn status(state: ExpiryState) -> CredentialExpiryStatus {
+        CredentialExpiryStatus {
+            state,
+            last_successful_refresh: None,
+            refresh_token_expiry: None,
+            days_until_expiry: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_parse_refresh_token_expiry() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let rfc = serde_json::json!({"refreshTokenExpiresAt": "2025-02-01T00:00:00Z"});
+        assert_eq!(
+            refresh_token_expiry_from_json(&rfc, now),
+            Some(Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap())
+        );
+        let millis = serde_json::json!({"refresh_token_expires_at": 1738368000000i64});
+        assert_eq!(
+            refresh_token_expiry_from_json(&millis, now),
+            Some(Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap())
+        );
+        let expires_in = serde_json::json!({"refresh_token_expires_in": 86400});
+        assert_eq!(
+            refresh_token_expiry_from_json(&expires_in, now),
+            Some(now + Duration::days(1))
+        );
+        assert_eq!(
+            refresh_token_expiry_from_json(&serde_json::json!({}), now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_evaluate_states() {
+        let now = Utc::now();
+        let config = config();
+
+        // 从最近一次成功刷新按配置的有效期推算
+        let info = CredentialExpiryInfo {
+            last_successful_refresh: Some(now - Duration::days(88)),
+            refresh_token_expiry: None,
+        };
+        let status = evaluate("kiro", None, &info, &config, now);
+        assert_eq!(status.state, ExpiryState::ExpiringSoon);
+        assert_eq!(status.days_until_expiry, Some(2));
+
+        // 未配置有效期的 Provider 无法推算
+        assert_eq!(
+            evaluate("gemini", None, &info, &config, now).state,
+            ExpiryState::Ok
+        );
+
+        let expired = CredentialExpiryInfo {
+            last_successful_refresh: None,
+            refresh_token_expiry: Some(now - Duration::hours(1)),
+        };
+        let status = evaluate("gemini", None, &expired, &config, now);
+        assert_eq!(status.state, ExpiryState::Expired);
+        assert_eq!(status.days_until_expiry, Some(-1));
+
+        let failing = CachedTokenInfo {
+            refresh_error_count: 2,
+            ..CachedTokenInfo::default()
+        };
+        assert_eq!(
+            evaluate(
+                "qwen",
+                Some(&failing),
+                &CredentialExpiryInfo::default(),
+                &config,
+                now
+            )
+            .state,
+            ExpiryState::RefreshFailing
+        );
+    }
+
+    #[test]
+    fn test_warnings_are_throttled() {
+        let monitor = CredentialExpiryMonitor::new();
+        let now = Utc::now();
+        let entry = |state| {
+            vec![(
+                "cred-1".to_string(),
+                "kiro".to_string(),
+                None,
+                status(state),
+            )]
+        };
+
+        assert_eq!(
+            monitor
+                .collect_warnings(entry(ExpiryState::ExpiringSoon), now)
+                .len(),
+            1
+        );
+        // 同一状态 24 小时内不重复预警
+        assert!(monitor
+            .collect_warnings(entry(ExpiryState::ExpiringSoon), now + Duration::hours(1))
+            .is_empty());
+        // 状态变化立即预警
+        assert_eq!(
+            monitor
+                .collect_warnings(entry(ExpiryState::Expired), now + Duration::hours(2))
+                .len(),
+            1
+        );
+        assert_eq!(
+            monitor
+                .collect_warnings(entry(ExpiryState::Expired), now + Duration::hours(27))
+                .len(),
+            1
+        );
+        // 恢复正常后清除记录
+        assert!(monitor
+            .collect_warnings(entry(ExpiryState::Ok), now + Duration::hours(28))
+            .is_empty());
+        assert_eq!(
+            monitor
+                .collect_warnings(entry(ExpiryState::ExpiringSoon), now + Duration::hours(29))
+                .len(),
+            1
+        );
+    }
+}
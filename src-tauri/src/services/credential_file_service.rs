@@ -0,0 +1,362 @@
+//! 凭证文件服务
+//!
+//! 管理应用凭证存储目录（`<data_dir>/proxycast/credentials`）中的 OAuth 凭证文件，
+//! 供 Tauri 命令、管理 API 和 OAuth 登录流程共用。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// 展开路径中的 ~ 为用户主目录
+pub fn expand_tilde(path: &str) -> String {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(stripped).to_string_lossy().to_string();
+        }
+    }
+    path.to_string()
+}
+
+/// 获取应用凭证存储目录
+pub fn get_credentials_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_dir()
+        .ok_or_else(|| "无法获取应用数据目录".to_string())?
+        .join("proxycast")
+        .join("credentials");
+
+    // 确保目录存在
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir).map_err(|e| format!("创建凭证存储目录失败: {}", e))?;
+    }
+
+    Ok(app_data_dir)
+}
+
+/// 复制并重命名 OAuth 凭证文件
+///
+/// 对于 Kiro 凭证，会自动合并 clientIdHash 文件中的 client_id/client_secret，
+/// 使副本文件完全独立，支持多账号场景。
+pub fn copy_and_rename_credential_file(
+    source_path: &str,
+    provider_type: &str,
+) -> Result<String, String> {
+    let expanded_source = expand_tilde(source_path);
+    let source = Path::new(&expanded_source);
+
+    // 验证源文件存在
+    if !source.exists() {
+        return Err(format!("凭证文件不存在: {}", expanded_source));
+    }
+
+    // 生成新的文件名：{provider_type}_{uuid}_{timestamp}.json
+    let uuid = Uuid::new_v4().to_string();
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let new_filename = format!(
+        "{}_{}_{}_{}.json",
+        provider_type,
+        &uuid[..8], // 使用 UUID 前8位
+        timestamp,
+        provider_type
+    );
+
+    // 获取目标目录
+    let credentials_dir = get_credentials_dir()?;
+    let target_path = credentials_dir.join(&new_filename);
+
+    // 对于 Kiro 凭证，需要合并 clientIdHash 文件中的 client_id/client_secret
+    if provider_type == "kiro" {
+        let content = fs::read_to_string(source).map_err(|e| format!("读取凭证文件失败: {}", e))?;
+        let mut creds: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| format!("解析凭证文件失败: {}", e))?;
+
+        // 检测 refreshToken 是否被截断（仅记录警告，不阻止添加）
+        // 正常的 refreshToken 长度应该在 500+ 字符，如果小于 100 字符则可能被截断
+        // 注意：即使 refreshToken 被截断，也允许添加凭证，在刷新时才会提示错误
+        if let Some(refresh_token) = creds.get("refreshToken").and_then(|v| v.as_str()) {
+            let token_len = refresh_token.len();
+
+            // 检测常见的截断模式
+            let is_truncated =
+                token_len < 100 || refresh_token.ends_with("...") || refresh_token.contains("...");
+
+            if is_truncated {
+                // 安全地截取前 50 个字符（避免 UTF-8 边界 panic）
+                let preview: String = refresh_token.chars().take(50).collect();
+                tracing::warn!(
+                    "[KIRO] 检测到 refreshToken 可能被截断！长度: {}, 内容: {}... (仍允许添加，刷新时会提示)",
+                    token_len,
+                    preview
+                );
+                // 不再阻止添加，只记录警告
+                // 在刷新 Token 时会检测并提示用户
+            } else {
+                tracing::info!("[KIRO] refreshToken 长度检查通过: {} 字符", token_len);
+            }
+        } else {
+            tracing::warn!("[KIRO] 凭证文件中没有 refreshToken 字段");
+        }
+
+        let aws_sso_cache_dir = dirs::home_dir()
+            .ok_or_else(|| "无法获取用户主目录".to_string())?
+            .join(".aws")
+            .join("sso")
+            .join("cache");
+
+        // 尝试从 clientIdHash 文件或扫描目录获取 client_id/client_secret
+        let mut found_credentials = false;
+
+        // 方式1：如果有 clientIdHash，读取对应文件
+        if let Some(hash) = creds.get("clientIdHash").and_then(|v| v.as_str()) {
+            let hash_file_path = aws_sso_cache_dir.join(format!("{}.json", hash));
+
+            if hash_file_path.exists() {
+                if let Ok(hash_content) = fs::read_to_string(&hash_file_path) {
+                    if let Ok(hash_json) = serde_json::from_str::<serde_json::Value>(&hash_content)
+                    {
+                        if let Some(client_id) = hash_json.get("clientId") {
+                            creds["clientId"] = client_id.clone();
+                        }
+                        if let Some(client_secret) = hash_json.get("clientSecret") {
+                            creds["clientSecret"] = client_secret.clone();
+                        }
+                        if creds.get("clientId").is_some() && creds.get("clientSecret").is_some() {
+                            found_credentials = true;
+                            tracing::info!(
+                                "[KIRO] 已从 clientIdHash 文件合并 client_id/client_secret 到副本"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        // 方式2：如果没有 clientIdHash 或未找到，扫描目录中的其他 JSON 文件
+        if !found_credentials && aws_sso_cache_dir.exists() {
+            tracing::info!(
+                "[KIRO] 没有 clientIdHash 或未找到，扫描目录查找 client_id/client_secret"
+            );
+            if let Ok(entries) = fs::read_dir(&aws_sso_cache_dir) {
+                for entry in entries.flatten() {
+                    let file_path = entry.path();
+                    // 跳过主凭证文件和备份文件
+                    if file_path.extension().map(|e| e == "json").unwrap_or(false) {
+                        let file_name =
+                            file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        if file_name.starts_with("kiro-auth-token") {
+                            continue;
+                        }
+                        if let Ok(file_content) = fs::read_to_string(&file_path) {
+                            if let Ok(file_json) =
+                                serde_json::from_str::<serde_json::Value>(&file_content)
+                            {
+                                let has_client_id =
+                                    file_json.get("clientId").and_then(|v| v.as_str()).is_some();
+                                let has_client_secret = file_json
+                                    .get("clientSecret")
+                                    .and_then(|v| v.as_str())
+                                    .is_some();
+                                if has_client_id && has_client_secret {
+                                    creds["clientId"] = file_json["clientId"].clone();
+                                    creds["clientSecret"] = file_json["clientSecret"].clone();
+                                    found_credentials = true;
+                                    tracing::info!(
+                                        "[KIRO] 已从 {} 合并 client_id/client_secret 到副本",
+                                        file_name
+                                    );
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !found_credentials {
+            // 检查认证方式
+            let auth_method = creds
+                .get("authMethod")
+                .and_then(|v| v.as_str())
+                .unwrap_or("social");
+
+            if auth_method.to_lowercase() == "idc" {
+                // IdC 认证必须有 clientId/clientSecret
+                tracing::error!(
+                    "[KIRO] IdC 认证方式缺少 clientId/clientSecret，无法创建有效的凭证副本"
+                );
+                return Err(
+                    "IdC 认证凭证不完整：缺少 clientId/clientSecret。\n\n💡 解决方案：\n1. 确保 ~/.aws/sso/cache/ 目录下有对应的 clientIdHash 文件\n2. 如果使用 AWS IAM Identity Center，请确保已完成完整的 SSO 登录流程\n3. 或者尝试使用 Social 认证方式的凭证".to_string()
+                );
+            } else {
+                tracing::warn!("[KIRO] 未找到 client_id/client_secret，将使用 social 认证方式");
+            }
+        }
+
+        // 写入合并后的凭证到副本文件
+        let merged_content =
+            serde_json::to_string_pretty(&creds).map_err(|e| format!("序列化凭证失败: {}", e))?;
+        fs::write(&target_path, merged_content).map_err(|e| format!("写入凭证文件失败: {}", e))?;
+    } else {
+        // 其他类型直接复制
+        fs::copy(source, &target_path).map_err(|e| format!("复制凭证文件失败: {}", e))?;
+    }
+
+    // 返回新的文件路径
+    Ok(target_path.to_string_lossy().to_string())
+}
+
+/// 从 JSON 内容创建 Kiro 凭证文件
+///
+/// 直接粘贴 JSON 内容，无需选择文件；返回新凭证文件的路径
+pub fn create_kiro_credential_from_json(json_content: &str) -> Result<String, String> {
+    // 验证 JSON 格式
+    let creds: serde_json::Value =
+        serde_json::from_str(json_content).map_err(|e| format!("JSON 格式无效: {}", e))?;
+
+    // 验证必要字段
+    if creds.get("refreshToken").is_none() {
+        return Err("凭证 JSON 缺少 refreshToken 字段".to_string());
+    }
+
+    // 检测 refreshToken 是否被截断
+    if let Some(refresh_token) = creds.get("refreshToken").and_then(|v| v.as_str()) {
+        let token_len = refresh_token.len();
+        let is_truncated =
+            token_len < 100 || refresh_token.ends_with("...") || refresh_token.contains("...");
+
+        if is_truncated {
+            tracing::warn!(
+                "[KIRO] 检测到 refreshToken 可能被截断！长度: {} (仍允许添加，刷新时会提示)",
+                token_len
+            );
+        }
+    }
+
+    // 生成新的文件名
+    let uuid = Uuid::new_v4().to_string();
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let new_filename = format!("kiro_{}_{}_{}.json", &uuid[..8], timestamp, "kiro");
+
+    // 获取目标目录
+    let credentials_dir = get_credentials_dir()?;
+    let target_path = credentials_dir.join(&new_filename);
+
+    // 尝试合并 clientId/clientSecret（如果凭证中没有）
+    let mut merged_creds = creds.clone();
+
+    // 检查是否需要从外部文件获取 clientId/clientSecret
+    let has_client_id = merged_creds.get("clientId").is_some();
+    let has_client_secret = merged_creds.get("clientSecret").is_some();
+
+    if !has_client_id || !has_client_secret {
+        let aws_sso_cache_dir = dirs::home_dir()
+            .ok_or_else(|| "无法获取用户主目录".to_string())?
+            .join(".aws")
+            .join("sso")
+            .join("cache");
+
+        let mut found_credentials = false;
+
+        // 方式1：如果有 clientIdHash，读取对应文件
+        if let Some(hash) = merged_creds.get("clientIdHash").and_then(|v| v.as_str()) {
+            let hash_file_path = aws_sso_cache_dir.join(format!("{}.json", hash));
+
+            if hash_file_path.exists() {
+                if let Ok(hash_content) = fs::read_to_string(&hash_file_path) {
+                    if let Ok(hash_json) = serde_json::from_str::<serde_json::Value>(&hash_content)
+                    {
+                        if let Some(client_id) = hash_json.get("clientId") {
+                            merged_creds["clientId"] = client_id.clone();
+                        }
+                        if let Some(client_secret) = hash_json.get("clientSecret") {
+                            merged_creds["clientSecret"] = client_secret.clone();
+                        }
+                        if merged_creds.get("clientId").is_some()
+                            && merged_creds.get("clientSecret").is_some()
+                        {
+                            found_credentials = true;
+                            tracing::info!(
+                                "[KIRO] 已从 clientIdHash 文件合并 client_id/client_secret"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        // 方式2：扫描目录中的其他 JSON 文件
+        if !found_credentials && aws_sso_cache_dir.exists() {
+            tracing::info!("[KIRO] 扫描目录查找 client_id/client_secret");
+            if let Ok(entries) = fs::read_dir(&aws_sso_cache_dir) {
+                for entry in entries.flatten() {
+                    let file_path = entry.path();
+                    if file_path.extension().map(|e| e == "json").unwrap_or(false) {
+                        let file_name =
+                            file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        if file_name.starts_with("kiro-auth-token") {
+                            continue;
+                        }
+                        if let Ok(file_content) = fs::read_to_string(&file_path) {
+                            if let Ok(file_json) =
+                                serde_json::from_str::<serde_json::Value>(&file_content)
+                            {
+                                let has_cid =
+                                    file_json.get("clientId").and_then(|v| v.as_str()).is_some();
+                                let has_csec = file_json
+                                    .get("clientSecret")
+                                    .and_then(|v| v.as_str())
+                                    .is_some();
+                                if has_cid && has_csec {
+                                    merged_creds["clientId"] = file_json["clientId"].clone();
+                                    merged_creds["clientSecret"] =
+                                        file_json["clientSecret"].clone();
+                                    found_credentials = true;
+                                    tracing::info!(
+                                        "[KIRO] 从 {} 合并 client_id/client_secret",
+                                        file_name
+                                    );
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !found_credentials {
+            let auth_method = merged_creds
+                .get("authMethod")
+                .and_then(|v| v.as_str())
+                .unwrap_or("social");
+
+            if auth_method.to_lowercase() == "idc" {
+                tracing::error!(
+                    "[KIRO] IdC 认证方式缺少 clientId/clientSecret，无法创建有效的凭证"
+                );
+                return Err(
+                    "IdC 认证凭证不完整：缺少 clientId/clientSecret。\n\n💡 解决方案：\n1. 确保 ~/.aws/sso/cache/ 目录下有对应的 clientIdHash 文件\n2. 如果使用 AWS IAM Identity Center，请确保已完成完整的 SSO 登录流程\n3. 或者尝试使用 Social 认证方式的凭证".to_string()
+                );
+            } else {
+                tracing::warn!("[KIRO] 未找到 client_id/client_secret，将使用 social 认证方式");
+            }
+        }
+    }
+
+    // 写入凭证文件
+    let merged_content = serde_json::to_string_pretty(&merged_creds)
+        .map_err(|e| format!("序列化凭证失败: {}", e))?;
+    fs::write(&target_path, merged_content).map_err(|e| format!("写入凭证文件失败: {}", e))?;
+
+    tracing::info!("[KIRO] 凭证文件已创建: {:?}", target_path);
+
+    Ok(target_path.to_string_lossy().to_string())
+}
@@ -0,0 +1,386 @@
+//! 凭证导入服务
+//!
+//! 扫描本机 CLI 工具的凭证文件（Kiro、Gemini CLI、Qwen Code、Claude、Codex CLI），
+//! 识别可用的 OAuth 凭证并批量导入凭证池。
+//! 按 refresh token（Codex 为 API Key）的指纹去重，已导入的凭证不会重复添加。
+
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{get_oauth_creds_path, CredentialData, CredentialSource};
+use crate::services::provider_pool_service::ProviderPoolService;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 已知的凭证文件位置
+struct KnownLocation {
+    provider_type: &'static str,
+    /// 相对用户主目录的路径
+    relative_path: &'static str,
+    /// 来源工具名称
+    source: &'static str,
+}
+
+const KNOWN_LOCATIONS: &[KnownLocation] = &[
+    KnownLocation {
+        provider_type: "kiro",
+        relative_path: ".aws/sso/cache/kiro-auth-token.json",
+        source: "Kiro",
+    },
+    KnownLocation {
+        provider_type: "gemini",
+        relative_path: ".gemini/oauth_creds.json",
+        source: "Gemini CLI",
+    },
+    KnownLocation {
+        provider_type: "qwen",
+        relative_path: ".qwen/oauth_creds.json",
+        source: "Qwen Code",
+    },
+    KnownLocation {
+        provider_type: "claude_oauth",
+        relative_path: ".claude/oauth_creds.json",
+        source: "Claude",
+    },
+    KnownLocation {
+        provider_type: "codex",
+        relative_path: ".codex/auth.json",
+        source: "Codex CLI",
+    },
+];
+
+/// 扫描到的凭证
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetectedCredential {
+    pub provider_type: String,
+    /// 来源工具名称
+    pub source: String,
+    /// 凭证文件路径
+    pub path: String,
+    /// 凭证指纹（用于去重）
+    pub fingerprint: String,
+    /// 凭证池中是否已存在
+    pub already_imported: bool,
+}
+
+/// 单个凭证的导入结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialImportResult {
+    pub provider_type: String,
+    pub path: String,
+    /// imported / skipped / failed
+    pub status: String,
+    /// 导入后的凭证 UUID
+    pub uuid: Option<String>,
+    pub message: Option<String>,
+}
+
+impl CredentialImportResult {
+    fn new(detected: &DetectedCredential, status: &str) -> Self {
+        Self {
+            provider_type: detected.provider_type.clone(),
+            path: detected.path.clone(),
+            status: status.to_string(),
+            uuid: None,
+            message: None,
+        }
+    }
+}
+
+/// 用于去重的密钥位置（JSON Pointer，按优先级排列）
+///
+/// Codex CLI 的 `auth.json` 把 refresh token 放在 `tokens` 下，API Key 登录时写入 `OPENAI_API_KEY`。
+const SECRET_POINTERS: &[&str] = &[
+    "/refresh_token",
+    "/refreshToken",
+    "/tokens/refresh_token",
+    "/api_key",
+    "/apiKey",
+    "/OPENAI_API_KEY",
+];
+
+/// 提取用于去重的密钥：refresh token，Codex 额外支持 API Key
+fn credential_secret(json: &serde_json::Value) -> Option<&str> {
+    SECRET_POINTERS.iter().find_map(|pointer| {
+        json.pointer(pointer)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+    })
+}
+
+/// 计算凭证文件的指纹，文件不可用时返回 None
+pub fn file_fingerprint(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let secret = credential_secret(&json)?;
+    let digest = Sha256::digest(secret.as_bytes());
+    Some(hex::encode(&digest[..8]))
+}
+
+/// 凭证池中已有 OAuth 凭证的指纹
+pub fn existing_fingerprints(db: &DbConnection) -> Result<HashSet<String>, String> {
+    let credentials = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+    };
+    Ok(credentials
+        .iter()
+        .filter_map(|c| get_oauth_creds_path(&c.credential))
+        .filter_map(|path| file_fingerprint(Path::new(&path)))
+        .collect())
+}
+
+/// 扫描主目录下的已知凭证位置
+pub fn scan_known_locations(home: &Path, existing: &HashSet<String>) -> Vec<DetectedCredential> {
+    KNOWN_LOCATIONS
+        .iter()
+        .filter_map(|location| {
+            let path = home.join(location.relative_path);
+            let fingerprint = file_fingerprint(&path)?;
+            Some(DetectedCredential {
+                provider_type: location.provider_type.to_string(),
+                source: location.source.to_string(),
+                path: path.to_string_lossy().to_string(),
+                already_imported: existing.contains(&fingerprint),
+                fingerprint,
+            })
+        })
+        .collect()
+}
+
+/// 扫描本机可导入的凭证
+pub fn detect_credentials(db: &DbConnection) -> Result<Vec<DetectedCredential>, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    Ok(scan_known_locations(&home, &existing_fingerprints(db)?))
+}
+
+/// 构建导入后的凭证数据
+fn build_credential_data(provider_type: &str, creds_file_path: String) -> Option<CredentialData> {
+    match provider_type {
+        "kiro" => Some(CredentialData::KiroOAuth { creds_file_path }),
+        "gemini" => Some(CredentialData::GeminiOAuth {
+            creds_file_path,
+            project_id: None,
+        }),
+        "qwen" => Some(CredentialData::QwenOAuth { creds_file_path }),
+        "claude_oauth" => Some(CredentialData::ClaudeOAuth { creds_file_path }),
+        "codex" => Some(CredentialData::CodexOAuth {
+            creds_file_path,
+            api_base_url: None,
+        }),
+        _ => None,
+    }
+}
+
+/// 批量导入扫描到的凭证
+///
+/// - `paths`: 只导入指定路径的凭证，为空时导入所有未导入的凭证
+/// - `copy_file`: 将源文件复制到应用凭证目录，返回副本路径
+pub fn import_detected<F>(
+    db: &DbConnection,
+    pool_service: &ProviderPoolService,
+    detected: Vec<DetectedCredential>,
+    paths: Option<&[String]>,
+    copy_file: F,
+) -> Vec<CredentialImportResult>
+where
+    F: Fn(&str, &str) -> Result<String, String>,
+{
+    let mut seen = HashSet::new();
+    let mut results = Vec::new();
+
+    for item in detected {
+        if paths.is_some_and(|paths| !paths.contains(&item.path)) {
+            continue;
+        }
+        if item.already_imported || !seen.insert(item.fingerprint.clone()) {
+            let mut result = CredentialImportResult::new(&item, "skipped");
+            result.message = Some("凭证已存在".to_string());
+            results.push(result);
+            continue;
+        }
+
+        let imported = copy_file(&item.path, &item.provider_type).and_then(|stored_path| {
+            let credential = build_credential_data(&item.provider_type, stored_path)
+                .ok_or_else(|| format!("不支持导入的凭证类型: {}", item.provider_type))?;
+            pool_service.add_credential_with_source(
+                db,
+                &item.provider_type,
+                credential,
+                Some(format!("{}（导入）", item.source)),
+                Some(true),
+                None,
+                CredentialSource::Imported,
+            )
+        });
+
+        let result = match imported {
+            Ok(cred) => {
+                tracing::info!(
+                    "[IMPORT] 已导入 {} 凭证: {} -> {}",
+                    item.provider_type,
+                    item.path,
+                    cred.uuid
+                );
+                CredentialImportResult {
+                    uuid: Some(cred.uuid),
+                    ..CredentialImportResult::new(&item, "imported")
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[IMPORT] 导入凭证失败: {}: {}", item.path, e);
+                CredentialImportResult {
+                    message: Some(e),
+                    ..CredentialImportResult::new(&item, "failed")
+                }
+            }
+        };
+        results.push(result);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn write_json(home: &Path, relative: &str, value: serde_json::Value) {
+        let path = home.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, value.to_string()).unwrap();
+    }
+
+    fn test_db() -> DbConnection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[test]
+    fn test_scan_detects_known_locations() {
+        let home = tempfile::tempdir().unwrap();
+        write_json(
+            home.path(),
+            ".aws/sso/cache/kiro-auth-token.json",
+            serde_json::json!({"refreshToken": "kiro-refresh", "accessToken": "a"}),
+        );
+        write_json(
+            home.path(),
+            ".gemini/oauth_creds.json",
+            serde_json::json!({"refresh_token": "gemini-refresh"}),
+        );
+        // 没有 refresh token 的文件不可用
+        write_json(
+            home.path(),
+            ".qwen/oauth_creds.json",
+            serde_json::json!({"access_token": "only-access"}),
+        );
+
+        let existing: HashSet<String> =
+            [file_fingerprint(&home.path().join(".gemini/oauth_creds.json")).unwrap()].into();
+        let detected = scan_known_locations(home.path(), &existing);
+        let kinds: Vec<_> = detected
+            .iter()
+            .map(|d| (d.provider_type.as_str(), d.already_imported))
+            .collect();
+        assert_eq!(kinds, vec![("kiro", false), ("gemini", true)]);
+    }
+
+    #[test]
+    fn test_codex_auth_json() {
+        let home = tempfile::tempdir().unwrap();
+        let codex: serde_json::Value = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/credential_import/codex_auth.json"
+        )))
+        .unwrap();
+        assert_eq!(
+            credential_secret(&codex),
+            Some("rt_test-codex-refresh-token")
+        );
+        write_json(home.path(), ".codex/auth.json", codex);
+        let detected = scan_known_locations(home.path(), &HashSet::new());
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].provider_type, "codex");
+
+        // API Key 登录
+        let api_key = serde_json::json!({"OPENAI_API_KEY": "sk-codex", "tokens": null});
+        assert_eq!(credential_secret(&api_key), Some("sk-codex"));
+        // 空字段不作为密钥
+        let empty = serde_json::json!({"refresh_token": "", "OPENAI_API_KEY": null});
+        assert_eq!(credential_secret(&empty), None);
+    }
+
+    #[test]
+    fn test_import_dedupes_and_reports() {
+        let home = tempfile::tempdir().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        write_json(
+            home.path(),
+            ".gemini/oauth_creds.json",
+            serde_json::json!({"refresh_token": "shared"}),
+        );
+        write_json(
+            home.path(),
+            ".qwen/oauth_creds.json",
+            serde_json::json!({"refresh_token": "qwen-refresh"}),
+        );
+
+        let db = test_db();
+        let service = ProviderPoolService::new();
+        let copy = |source: &str, provider_type: &str| -> Result<String, String> {
+            let target = store.path().join(format!("{provider_type}.json"));
+            std::fs::copy(source, &target).map_err(|e| e.to_string())?;
+            Ok(target.to_string_lossy().to_string())
+        };
+
+        let detected = scan_known_locations(home.path(), &existing_fingerprints(&db).unwrap());
+        let results = import_detected(&db, &service, detected, None, copy);
+        assert!(results.iter().all(|r| r.status == "imported"));
+        assert_eq!(results.len(), 2);
+
+        // 再次扫描时识别为已导入
+        let detected = scan_known_locations(home.path(), &existing_fingerprints(&db).unwrap());
+        assert!(detected.iter().all(|d| d.already_imported));
+        let results = import_detected(&db, &service, detected, None, copy);
+        assert!(results.iter().all(|r| r.status == "skipped"));
+        assert_eq!(
+            ProviderPoolDao::get_all(&db.lock().unwrap()).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_import_only_selected_paths() {
+        let home = tempfile::tempdir().unwrap();
+        write_json(
+            home.path(),
+            ".gemini/oauth_creds.json",
+            serde_json::json!({"refresh_token": "g"}),
+        );
+        write_json(
+            home.path(),
+            ".qwen/oauth_creds.json",
+            serde_json::json!({"refresh_token": "q"}),
+        );
+        let db = test_db();
+        let detected = scan_known_locations(home.path(), &HashSet::new());
+        let selected = vec![home
+            .path()
+            .join(".qwen/oauth_creds.json")
+            .to_string_lossy()
+            .to_string()];
+        let results = import_detected(
+            &db,
+            &ProviderPoolService::new(),
+            detected,
+            Some(&selected),
+            |source, _| Ok(source.to_string()),
+        );
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].provider_type, "qwen");
+        assert_eq!(results[0].status, "imported");
+    }
+}
@@ -1,20 +1,32 @@
+pub mod alert_service;
 pub mod api_key_provider_service;
 pub mod backup_service;
+pub mod cluster_service;
+pub mod credential_expiry_service;
+pub mod credential_file_service;
+pub mod credential_import_service;
+#[cfg(feature = "desktop")]
 pub mod file_browser_service;
 pub mod kiro_event_service;
 pub mod live_sync;
 pub mod machine_id_service;
 pub mod mcp_service;
 pub mod mcp_sync;
+pub mod model_catalog_service;
 pub mod model_registry_service;
 pub mod model_service;
+pub mod network_service;
 pub mod prompt_service;
 pub mod prompt_sync;
 pub mod provider_pool_service;
 pub mod skill_service;
 pub mod switch;
+#[cfg(feature = "desktop")]
 pub mod sysinfo_service;
 pub mod token_cache_service;
+pub mod token_refresh_scheduler;
 pub mod update_check_service;
+#[cfg(feature = "desktop")]
 pub mod update_window;
+pub mod usage_report_service;
 pub mod usage_service;
@@ -0,0 +1,824 @@
+//! 模型目录聚合服务
+//!
+//! 为 `/v1/models` 聚合凭证池中各凭证实际可用的模型：
+//! - API Key 凭证实时调用上游 `/models` 接口，失败时回退到默认模型列表
+//! - OAuth 凭证使用凭证记录的 `supported_models` 或默认模型列表
+//! - 合并 Vertex 凭证的模型别名与 Amp CLI 模型映射
+//!
+//! 聚合结果按 TTL 缓存，避免每次请求都访问上游；缓存失效时同一凭证并发的上游 `/models`
+//! 请求合并为一次。
+//!
+//! 同时为 `/v1/capabilities` 按凭证汇总模型能力（流式、工具、视觉、JSON 模式、上下文长度），
+//! 能力数据来自模型注册表。
+//!
+//! Claude SDK 请求 `/v1/models` 时返回 Anthropic 格式（显示名称和发布日期来自模型注册表，
+//! 按发布时间从新到旧排序，支持 `limit` / `after_id` / `before_id` 分页）。
+
+use crate::config::AmpModelMapping;
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::model_registry::EnhancedModelMetadata;
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::services::model_registry_service::load_registry_models;
+use crate::services::model_service::ModelService;
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, Utc};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
+
+/// 默认缓存时间（5 分钟）
+pub const DEFAULT_MODEL_CATALOG_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// 聚合后的模型条目（OpenAI `/v1/models` 格式的超集）
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CatalogModel {
+    pub id: String,
+    pub object: String,
+    /// 拥有者（首个提供该模型的 Provider 类型）
+    pub owned_by: String,
+    pub created: i64,
+    /// 提供该模型的 Provider 类型列表
+    pub providers: Vec<String>,
+    /// 是否至少有一个健康且未禁用的凭证可以服务该模型
+    pub available: bool,
+    /// 别名指向的上游模型（Vertex 别名 / Amp 映射）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias_of: Option<String>,
+}
+
+/// Anthropic 格式模型列表的默认分页大小
+const ANTHROPIC_DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Anthropic 格式模型列表的最大分页大小
+const ANTHROPIC_MAX_PAGE_SIZE: usize = 1000;
+
+/// Anthropic `/v1/models` 格式的模型条目
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AnthropicModel {
+    #[serde(rename = "type")]
+    pub object_type: &'static str,
+    pub id: String,
+    pub display_name: String,
+    /// 发布时间（RFC 3339）
+    pub created_at: String,
+}
+
+/// Anthropic 格式模型列表的分页参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AnthropicPageParams {
+    pub limit: Option<usize>,
+    pub after_id: Option<String>,
+    pub before_id: Option<String>,
+}
+
+/// Anthropic 格式的模型列表分页
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AnthropicModelPage {
+    pub data: Vec<AnthropicModel>,
+    pub has_more: bool,
+    pub first_id: Option<String>,
+    pub last_id: Option<String>,
+}
+
+impl AnthropicModelPage {
+    /// 分页：`before_id` 取该模型之前的 `limit` 个，否则取 `after_id` 之后（未指定时从头开始）的 `limit` 个；
+    /// 游标模型不存在时返回空页
+    pub fn paginate(models: &[AnthropicModel], params: &AnthropicPageParams) -> Self {
+        let limit = params
+            .limit
+            .unwrap_or(ANTHROPIC_DEFAULT_PAGE_SIZE)
+            .clamp(1, ANTHROPIC_MAX_PAGE_SIZE);
+        let position = |id: &str| models.iter().position(|m| m.id == id);
+
+        let (start, end, has_more) = if let Some(before_id) = &params.before_id {
+            let end = position(before_id).unwrap_or(0);
+            let start = end.saturating_sub(limit);
+            (start, end, start > 0)
+        } else {
+            let start = match &params.after_id {
+                Some(after_id) => position(after_id).map_or(models.len(), |i| i + 1),
+                None => 0,
+            };
+            let end = (start + limit).min(models.len());
+            (start, end, end < models.len())
+        };
+
+        let data = models[start..end].to_vec();
+        Self {
+            first_id: data.first().map(|m| m.id.clone()),
+            last_id: data.last().map(|m| m.id.clone()),
+            has_more,
+            data,
+        }
+    }
+}
+
+/// 同一模型在注册表中有多个条目时优先使用的 Provider
+const PREFERRED_REGISTRY_PROVIDERS: &[&str] =
+    &["anthropic", "openai", "google", "deepseek", "mistral"];
+
+/// 模型能力（来自模型注册表）
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ModelFeatures {
+    pub streaming: bool,
+    pub tools: bool,
+    pub vision: bool,
+    pub json_mode: bool,
+    pub reasoning: bool,
+    /// 最大上下文长度
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_context: Option<u32>,
+    /// 最大输出 token 数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+}
+
+impl ModelFeatures {
+    fn from_registry(model: &EnhancedModelMetadata) -> Self {
+        let caps = &model.capabilities;
+        Self {
+            streaming: caps.streaming,
+            tools: caps.tools || caps.function_calling,
+            vision: caps.vision,
+            json_mode: caps.json_mode,
+            reasoning: caps.reasoning,
+            max_context: model.limits.context_length,
+            max_output_tokens: model.limits.max_output_tokens,
+        }
+    }
+
+    /// 合并：任一模型支持即视为支持，长度限制取最大值
+    fn merge(&mut self, other: &Self) {
+        self.streaming |= other.streaming;
+        self.tools |= other.tools;
+        self.vision |= other.vision;
+        self.json_mode |= other.json_mode;
+        self.reasoning |= other.reasoning;
+        self.max_context = self.max_context.max(other.max_context);
+        self.max_output_tokens = self.max_output_tokens.max(other.max_output_tokens);
+    }
+}
+
+/// 单个模型的能力
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelCapabilityEntry {
+    pub id: String,
+    /// 模型注册表中没有该模型时为空
+    pub features: Option<ModelFeatures>,
+}
+
+/// 单个凭证的能力（`/v1/capabilities` 条目）
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CredentialCapabilities {
+    /// Provider 类型
+    pub provider: String,
+    pub credential_uuid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_name: Option<String>,
+    /// 凭证是否健康且未禁用
+    pub available: bool,
+    /// 凭证下已知模型的能力汇总（没有已知模型时为空）
+    pub features: Option<ModelFeatures>,
+    pub models: Vec<ModelCapabilityEntry>,
+}
+
+/// 单个凭证贡献的模型
+struct CredentialModels {
+    provider: String,
+    uuid: String,
+    name: Option<String>,
+    available: bool,
+    /// (模型 ID, 别名指向的上游模型)
+    models: Vec<(String, Option<String>)>,
+}
+
+/// 上游模型列表请求结果通道（请求完成前为 None）
+type FetchResult = watch::Receiver<Option<Result<Vec<String>, String>>>;
+
+/// 进行中的上游模型列表请求（发起方持有），结束或被取消时移除登记
+///
+/// 发起方被取消时通道随之关闭，等待者重新发起请求。
+struct FetchFlight {
+    inflight: Arc<DashMap<String, FetchResult>>,
+    uuid: String,
+    tx: watch::Sender<Option<Result<Vec<String>, String>>>,
+}
+
+impl FetchFlight {
+    /// 通知等待者请求结果
+    fn finish(self, result: &Result<Vec<String>, String>) {
+        let _ = self.tx.send(Some(result.clone()));
+    }
+}
+
+impl Drop for FetchFlight {
+    fn drop(&mut self) {
+        self.inflight.remove(&self.uuid);
+    }
+}
+
+/// 模型目录聚合服务
+pub struct ModelCatalogService {
+    model_service: ModelService,
+    ttl: Duration,
+    /// 缓存代数（[`Self::invalidate`] 时递增，代数不同的缓存视为失效）
+    epoch: AtomicU64,
+    cache: RwLock<Option<(Instant, u64, Vec<CatalogModel>)>>,
+    capabilities_cache: RwLock<Option<(Instant, u64, Vec<CredentialCapabilities>)>>,
+    /// 进行中的上游模型列表请求（按凭证 UUID），同一凭证的其他请求等待其结果
+    inflight: Arc<DashMap<String, FetchResult>>,
+}
+
+impl Default for ModelCatalogService {
+    fn default() -> Self {
+        Self::new(DEFAULT_MODEL_CATALOG_TTL)
+    }
+}
+
+impl ModelCatalogService {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            model_service: ModelService::new(),
+            ttl,
+            epoch: AtomicU64::new(0),
+            cache: RwLock::new(None),
+            capabilities_cache: RwLock::new(None),
+            inflight: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// 发起上游请求：没有进行中的请求时返回 Ok，否则返回进行中请求的结果通道
+    fn begin_fetch(&self, uuid: &str) -> Result<FetchFlight, FetchResult> {
+        match self.inflight.entry(uuid.to_string()) {
+            Entry::Occupied(entry) => Err(entry.get().clone()),
+            Entry::Vacant(entry) => {
+                let (tx, rx) = watch::channel(None);
+                entry.insert(rx);
+                Ok(FetchFlight {
+                    inflight: self.inflight.clone(),
+                    uuid: uuid.to_string(),
+                    tx,
+                })
+            }
+        }
+    }
+
+    /// 实时获取凭证的模型列表，同一凭证并发的请求合并为一次上游调用
+    async fn fetch_models(&self, credential: &ProviderCredential) -> Result<Vec<String>, String> {
+        loop {
+            match self.begin_fetch(&credential.uuid) {
+                Ok(flight) => {
+                    let result = self
+                        .model_service
+                        .fetch_models_for_credential(credential)
+                        .await;
+                    flight.finish(&result);
+                    return result;
+                }
+                Err(mut rx) => {
+                    if let Ok(result) = rx.wait_for(|result| result.is_some()).await {
+                        if let Some(result) = result.clone() {
+                            return result;
+                        }
+                    }
+                    // 发起方被取消，重新发起请求
+                }
+            }
+        }
+    }
+
+    /// 清除缓存，下次请求时重新聚合
+    ///
+    /// 凭证池增删改和配置重载后调用；正在进行的聚合结果也不会被复用。
+    pub fn invalidate(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 获取聚合后的模型列表（命中缓存时直接返回）
+    pub async fn list_models(
+        &self,
+        db: &DbConnection,
+        amp_mappings: &[AmpModelMapping],
+    ) -> Vec<CatalogModel> {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        if let Some((built_at, built_epoch, models)) = self.cache.read().await.as_ref() {
+            if *built_epoch == epoch && built_at.elapsed() < self.ttl {
+                return models.clone();
+            }
+        }
+
+        let models = self.build(db, amp_mappings).await;
+        *self.cache.write().await = Some((Instant::now(), epoch, models.clone()));
+        models
+    }
+
+    async fn build(
+        &self,
+        db: &DbConnection,
+        amp_mappings: &[AmpModelMapping],
+    ) -> Vec<CatalogModel> {
+        let credentials = match db.lock() {
+            Ok(conn) => ProviderPoolDao::get_all(&conn).unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("[MODEL_CATALOG] 获取数据库连接失败: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let per_credential = futures::future::join_all(
+            credentials
+                .iter()
+                .map(|cred| self.collect_credential_models(cred)),
+        )
+        .await;
+
+        merge_catalog(per_credential, amp_mappings, chrono::Utc::now().timestamp())
+    }
+
+    /// 获取各凭证的能力（命中缓存时直接返回）
+    pub async fn list_capabilities(&self, db: &DbConnection) -> Vec<CredentialCapabilities> {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        if let Some((built_at, built_epoch, capabilities)) =
+            self.capabilities_cache.read().await.as_ref()
+        {
+            if *built_epoch == epoch && built_at.elapsed() < self.ttl {
+                return capabilities.clone();
+            }
+        }
+
+        let (credentials, registry) = match db.lock() {
+            Ok(conn) => {
+                let registry = load_registry_models(&conn).unwrap_or_else(|e| {
+                    tracing::warn!("[MODEL_CATALOG] 读取模型注册表失败: {}", e);
+                    Vec::new()
+                });
+                (
+                    ProviderPoolDao::get_all(&conn).unwrap_or_default(),
+                    registry,
+                )
+            }
+            Err(e) => {
+                tracing::warn!("[MODEL_CATALOG] 获取数据库连接失败: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let per_credential = futures::future::join_all(
+            credentials
+                .iter()
+                .map(|cred| self.collect_credential_models(cred)),
+        )
+        .await;
+
+        let capabilities = build_capabilities(per_credential, &registry);
+        *self.capabilities_cache.write().await =
+            Some((Instant::now(), epoch, capabilities.clone()));
+        capabilities
+    }
+
+    /// 读取模型注册表（读取失败时为空）
+    pub fn registry_models(db: &DbConnection) -> Vec<EnhancedModelMetadata> {
+        match db.lock() {
+            Ok(conn) => load_registry_models(&conn).unwrap_or_else(|e| {
+                tracing::warn!("[MODEL_CATALOG] 读取模型注册表失败: {}", e);
+                Vec::new()
+            }),
+            Err(e) => {
+                tracing::warn!("[MODEL_CATALOG] 获取数据库连接失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 收集单个凭证可提供的模型
+    async fn collect_credential_models(&self, credential: &ProviderCredential) -> CredentialModels {
+        let available = credential.is_available();
+
+        let mut model_ids = if !credential.supported_models.is_empty() {
+            credential.supported_models.clone()
+        } else if available && is_live_listing_supported(&credential.credential) {
+            match self.fetch_models(credential).await {
+                Ok(models) if !models.is_empty() => models,
+                Ok(_) => self
+                    .model_service
+                    .get_default_models_for_provider(&credential.provider_type),
+                Err(e) => {
+                    tracing::warn!(
+                        "[MODEL_CATALOG] 实时获取模型列表失败，使用默认列表: uuid={}, error={}",
+                        credential.uuid,
+                        e
+                    );
+                    self.model_service
+                        .get_default_models_for_provider(&credential.provider_type)
+                }
+            }
+        } else {
+            self.model_service
+                .get_default_models_for_provider(&credential.provider_type)
+        };
+        model_ids.retain(|m| credential.supports_model(m));
+
+        let mut models: Vec<(String, Option<String>)> =
+            model_ids.into_iter().map(|m| (m, None)).collect();
+
+        // Vertex 凭证的别名对客户端可见
+        if let CredentialData::VertexKey { model_aliases, .. } = &credential.credential {
+            models.extend(
+                model_aliases
+                    .iter()
+                    .map(|(alias, upstream)| (alias.clone(), Some(upstream.clone()))),
+            );
+        }
+
+        CredentialModels {
+            provider: credential.provider_type.to_string(),
+            uuid: credential.uuid.clone(),
+            name: credential.name.clone(),
+            available,
+            models,
+        }
+    }
+}
+
+/// 是否支持实时调用上游 `/models` 接口
+fn is_live_listing_supported(credential: &CredentialData) -> bool {
+    matches!(
+        credential,
+        CredentialData::OpenAIKey { .. }
+            | CredentialData::ClaudeKey { .. }
+            | CredentialData::AnthropicKey { .. }
+            | CredentialData::GeminiApiKey { .. }
+            | CredentialData::OpenRouterKey { .. }
+            | CredentialData::MistralKey { .. }
+            | CredentialData::DeepSeekKey { .. }
+    )
+}
+
+/// 合并各凭证的模型并应用 Amp 映射，按模型 ID 排序
+fn merge_catalog(
+    per_credential: Vec<CredentialModels>,
+    amp_mappings: &[AmpModelMapping],
+    created: i64,
+) -> Vec<CatalogModel> {
+    let mut catalog: BTreeMap<String, CatalogModel> = BTreeMap::new();
+
+    for entry in per_credential {
+        for (id, alias_of) in entry.models {
+            let model = catalog.entry(id.clone()).or_insert_with(|| CatalogModel {
+                id,
+                object: "model".to_string(),
+                owned_by: entry.provider.clone(),
+                created,
+                providers: Vec::new(),
+                available: false,
+                alias_of,
+            });
+            if !model.providers.contains(&entry.provider) {
+                model.providers.push(entry.provider.clone());
+            }
+            model.available |= entry.available;
+        }
+    }
+
+    // Amp 映射：源模型继承目标模型的 Provider 与可用性（通配符和 provider 专属映射不列出）
+    for mapping in amp_mappings {
+        if mapping.provider.is_some()
+            || mapping.from.contains('*')
+            || catalog.contains_key(&mapping.from)
+        {
+            continue;
+        }
+        if let Some(target) = catalog.get(&mapping.to).cloned() {
+            catalog.insert(
+                mapping.from.clone(),
+                CatalogModel {
+                    id: mapping.from.clone(),
+                    alias_of: Some(mapping.to.clone()),
+                    ..target
+                },
+            );
+        }
+    }
+
+    catalog.into_values().collect()
+}
+
+/// 模型注册表查询键（忽略大小写，`.` 与 `-` 视为相同，如 `claude-sonnet-4.5`）
+fn registry_key(model: &str) -> String {
+    model.to_lowercase().replace('.', "-")
+}
+
+/// 按模型 ID 索引注册表，同一模型优先使用官方 Provider 的条目
+fn registry_index(registry: &[EnhancedModelMetadata]) -> HashMap<String, &EnhancedModelMetadata> {
+    let is_preferred =
+        |m: &EnhancedModelMetadata| PREFERRED_REGISTRY_PROVIDERS.contains(&m.provider_id.as_str());
+    let mut index: HashMap<String, &EnhancedModelMetadata> = HashMap::new();
+    for model in registry {
+        let existing = index.entry(registry_key(&model.id)).or_insert(model);
+        if !is_preferred(existing) && is_preferred(model) {
+            *existing = model;
+        }
+    }
+    index
+}
+
+/// 注册表中的发布日期（`YYYY-MM-DD`）
+fn parse_release_date(date: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// 转换为 Anthropic 格式，按发布时间从新到旧排序
+///
+/// `models` 为 (模型 ID, 别名指向的上游模型)；注册表中没有该模型时按上游模型查询，
+/// 仍然没有时显示名称使用模型 ID、发布时间使用 `created`（Unix 时间戳）。
+pub fn anthropic_models(
+    models: &[(&str, Option<&str>)],
+    registry: &[EnhancedModelMetadata],
+    created: i64,
+) -> Vec<AnthropicModel> {
+    let index = registry_index(registry);
+    let fallback = DateTime::from_timestamp(created, 0).unwrap_or_default();
+
+    let mut entries: Vec<(DateTime<Utc>, AnthropicModel)> = models
+        .iter()
+        .map(|(id, alias_of)| {
+            let metadata = index
+                .get(&registry_key(id))
+                .or_else(|| alias_of.and_then(|upstream| index.get(&registry_key(upstream))));
+            let released = metadata
+                .and_then(|m| m.release_date.as_deref())
+                .and_then(parse_release_date)
+                .unwrap_or(fallback);
+            let model = AnthropicModel {
+                object_type: "model",
+                id: id.to_string(),
+                display_name: metadata
+                    .map(|m| m.display_name.clone())
+                    .unwrap_or_else(|| id.to_string()),
+                created_at: released.to_rfc3339_opts(SecondsFormat::Secs, true),
+            };
+            (released, model)
+        })
+        .collect();
+    entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+    entries.into_iter().map(|(_, model)| model).collect()
+}
+
+/// 按凭证汇总模型能力，别名按其指向的上游模型查询
+fn build_capabilities(
+    per_credential: Vec<CredentialModels>,
+    registry: &[EnhancedModelMetadata],
+) -> Vec<CredentialCapabilities> {
+    let index = registry_index(registry);
+    per_credential
+        .into_iter()
+        .map(|entry| {
+            let mut summary: Option<ModelFeatures> = None;
+            let models = entry
+                .models
+                .into_iter()
+                .map(|(id, alias_of)| {
+                    let features = index
+                        .get(&registry_key(alias_of.as_deref().unwrap_or(&id)))
+                        .map(|m| ModelFeatures::from_registry(m));
+                    if let Some(features) = &features {
+                        summary.get_or_insert_with(Default::default).merge(features);
+                    }
+                    ModelCapabilityEntry { id, features }
+                })
+                .collect();
+            CredentialCapabilities {
+                provider: entry.provider,
+                credential_uuid: entry.uuid,
+                credential_name: entry.name,
+                available: entry.available,
+                features: summary,
+                models,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(provider: &str, available: bool, models: &[&str]) -> CredentialModels {
+        CredentialModels {
+            provider: provider.to_string(),
+            uuid: format!("{}-uuid", provider),
+            name: None,
+            available,
+            models: models.iter().map(|m| (m.to_string(), None)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_catalog_dedup_and_availability() {
+        let catalog = merge_catalog(
+            vec![
+                entry("kiro", false, &["claude-sonnet-4-5"]),
+                entry("claude", true, &["claude-sonnet-4-5", "claude-haiku-4-5"]),
+            ],
+            &[],
+            0,
+        );
+
+        assert_eq!(catalog.len(), 2);
+        let sonnet = catalog
+            .iter()
+            .find(|m| m.id == "claude-sonnet-4-5")
+            .unwrap();
+        assert_eq!(sonnet.owned_by, "kiro");
+        assert_eq!(sonnet.providers, vec!["kiro", "claude"]);
+        assert!(sonnet.available);
+    }
+
+    #[test]
+    fn test_merge_catalog_amp_mapping() {
+        let mappings = vec![
+            AmpModelMapping {
+                from: "claude-opus-4-5".to_string(),
+                to: "claude-sonnet-4-5".to_string(),
+                provider: None,
+            },
+            // 目标模型不存在时忽略
+            AmpModelMapping {
+                from: "gpt-5".to_string(),
+                to: "missing-model".to_string(),
+                provider: None,
+            },
+        ];
+        let catalog = merge_catalog(
+            vec![entry("kiro", true, &["claude-sonnet-4-5"])],
+            &mappings,
+            0,
+        );
+
+        assert_eq!(catalog.len(), 2);
+        let opus = catalog.iter().find(|m| m.id == "claude-opus-4-5").unwrap();
+        assert_eq!(opus.alias_of.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(opus.owned_by, "kiro");
+        assert!(opus.available);
+    }
+
+    fn registry_model(
+        id: &str,
+        provider: &str,
+        context: u32,
+        vision: bool,
+    ) -> EnhancedModelMetadata {
+        let mut model = EnhancedModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            provider.to_string(),
+            provider.to_string(),
+        );
+        model.capabilities.streaming = true;
+        model.capabilities.tools = true;
+        model.capabilities.vision = vision;
+        model.limits.context_length = Some(context);
+        model
+    }
+
+    #[test]
+    fn test_build_capabilities() {
+        let registry = vec![
+            registry_model("claude-sonnet-4-5", "openrouter", 100_000, false),
+            registry_model("claude-sonnet-4-5", "anthropic", 200_000, true),
+            registry_model("claude-haiku-4-5", "anthropic", 200_000, false),
+        ];
+        let mut vertex = entry("vertex", true, &["unknown-model"]);
+        vertex.models.push((
+            "my-sonnet".to_string(),
+            Some("claude-sonnet-4-5".to_string()),
+        ));
+
+        let capabilities = build_capabilities(
+            vec![
+                entry("kiro", true, &["claude-sonnet-4.5", "claude-haiku-4-5"]),
+                vertex,
+                entry("openai", false, &["unknown-model"]),
+            ],
+            &registry,
+        );
+
+        // 优先使用官方 Provider 的条目，`.` 与 `-` 视为相同
+        let kiro = &capabilities[0];
+        assert_eq!(kiro.credential_uuid, "kiro-uuid");
+        let sonnet = kiro.models[0].features.as_ref().unwrap();
+        assert!(sonnet.vision);
+        assert_eq!(sonnet.max_context, Some(200_000));
+        let summary = kiro.features.as_ref().unwrap();
+        assert!(summary.streaming && summary.tools && summary.vision);
+        assert!(!summary.json_mode);
+
+        // 别名按上游模型查询，未知模型没有能力数据
+        let vertex = &capabilities[1];
+        assert_eq!(vertex.models[0].features, None);
+        assert!(vertex.models[1].features.as_ref().unwrap().vision);
+
+        assert_eq!(capabilities[2].features, None);
+        assert!(!capabilities[2].available);
+    }
+
+    #[test]
+    fn test_anthropic_models() {
+        let mut sonnet = registry_model("claude-sonnet-4-5", "anthropic", 200_000, true);
+        sonnet.display_name = "Claude Sonnet 4.5".to_string();
+        sonnet.release_date = Some("2025-09-29".to_string());
+        let mut haiku = registry_model("claude-haiku-4-5", "anthropic", 200_000, false);
+        haiku.display_name = "Claude Haiku 4.5".to_string();
+        haiku.release_date = Some("2025-10-15".to_string());
+
+        let models = anthropic_models(
+            &[
+                ("claude-sonnet-4-5", None),
+                ("my-haiku", Some("claude-haiku-4-5")),
+                ("unknown-model", None),
+            ],
+            &[sonnet, haiku],
+            0,
+        );
+
+        // 按发布时间从新到旧，别名使用上游模型的元数据
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["my-haiku", "claude-sonnet-4-5", "unknown-model"]);
+        assert_eq!(models[0].display_name, "Claude Haiku 4.5");
+        assert_eq!(models[1].created_at, "2025-09-29T00:00:00Z");
+        assert_eq!(models[2].display_name, "unknown-model");
+        assert_eq!(models[2].created_at, "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_anthropic_model_page() {
+        let models = anthropic_models(
+            &[("a", None), ("b", None), ("c", None), ("d", None)],
+            &[],
+            0,
+        );
+        let page = |limit, after_id: Option<&str>, before_id: Option<&str>| {
+            AnthropicModelPage::paginate(
+                &models,
+                &AnthropicPageParams {
+                    limit: Some(limit),
+                    after_id: after_id.map(str::to_string),
+                    before_id: before_id.map(str::to_string),
+                },
+            )
+        };
+
+        let first = page(2, None, None);
+        assert_eq!(first.first_id.as_deref(), Some("a"));
+        assert_eq!(first.last_id.as_deref(), Some("b"));
+        assert!(first.has_more);
+
+        let last = page(2, Some("b"), None);
+        assert_eq!(last.first_id.as_deref(), Some("c"));
+        assert!(!last.has_more);
+
+        let before = page(2, None, Some("d"));
+        assert_eq!(before.first_id.as_deref(), Some("b"));
+        assert_eq!(before.last_id.as_deref(), Some("c"));
+        assert!(before.has_more);
+
+        assert!(page(2, Some("missing"), None).data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_share_result() {
+        let service = ModelCatalogService::default();
+        let uuid = "00000000-0000-0000-0000-000000000000";
+
+        let flight = service.begin_fetch(uuid).ok().unwrap();
+        let mut waiter = service.begin_fetch(uuid).err().unwrap();
+        flight.finish(&Ok(vec!["gpt-4o".to_string()]));
+        let result = waiter.wait_for(|result| result.is_some()).await.unwrap();
+        assert_eq!(result.clone(), Some(Ok(vec!["gpt-4o".to_string()])));
+        drop(result);
+
+        // 请求结束后可以再次发起
+        assert!(service.inflight.is_empty());
+        assert!(service.begin_fetch(uuid).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_fetch_releases_waiters() {
+        let service = ModelCatalogService::default();
+        let uuid = "00000000-0000-0000-0000-000000000000";
+
+        let flight = service.begin_fetch(uuid).ok().unwrap();
+        let mut waiter = service.begin_fetch(uuid).err().unwrap();
+        drop(flight);
+
+        assert!(waiter.wait_for(|result| result.is_some()).await.is_err());
+        assert!(service.begin_fetch(uuid).is_ok());
+    }
+}
@@ -286,7 +286,14 @@ impl ModelRegistryService {
         // 3. 加载别名配置
         let mut aliases = HashMap::new();
         let aliases_dir = models_dir.join("aliases");
-        let alias_files = ["kiro", "antigravity", "codex", "gemini"];
+        let alias_files = [
+            "kiro",
+            "antigravity",
+            "codex",
+            "gemini",
+            "mistral",
+            "deepseek",
+        ];
 
         for alias_name in alias_files {
             let alias_file = aliases_dir.join(format!("{}.json", alias_name));
@@ -379,46 +386,7 @@ impl ModelRegistryService {
         let (models, sync_rows) = {
             let conn = self.db.lock().map_err(|e| e.to_string())?;
 
-            let mut stmt = conn
-                .prepare(
-                    "SELECT id, display_name, provider_id, provider_name, family, tier,
-                            capabilities, pricing, limits, status, release_date, is_latest,
-                            description, source, created_at, updated_at
-                     FROM model_registry",
-                )
-                .map_err(|e| e.to_string())?;
-
-            let models = stmt
-                .query_map([], |row| {
-                    let capabilities_json: String = row.get(6)?;
-                    let pricing_json: Option<String> = row.get(7)?;
-                    let limits_json: String = row.get(8)?;
-                    let status_str: String = row.get(9)?;
-                    let tier_str: String = row.get(5)?;
-                    let source_str: String = row.get(13)?;
-
-                    Ok(EnhancedModelMetadata {
-                        id: row.get(0)?,
-                        display_name: row.get(1)?,
-                        provider_id: row.get(2)?,
-                        provider_name: row.get(3)?,
-                        family: row.get(4)?,
-                        tier: tier_str.parse().unwrap_or(ModelTier::Pro),
-                        capabilities: serde_json::from_str(&capabilities_json).unwrap_or_default(),
-                        pricing: pricing_json.and_then(|s| serde_json::from_str(&s).ok()),
-                        limits: serde_json::from_str(&limits_json).unwrap_or_default(),
-                        status: status_str.parse().unwrap_or(ModelStatus::Active),
-                        release_date: row.get(10)?,
-                        is_latest: row.get::<_, i32>(11)? != 0,
-                        description: row.get(12)?,
-                        source: source_str.parse().unwrap_or(ModelSource::Local),
-                        created_at: row.get(14)?,
-                        updated_at: row.get(15)?,
-                    })
-                })
-                .map_err(|e| e.to_string())?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| e.to_string())?;
+            let models = load_registry_models(&conn)?;
 
             // 加载同步状态数据
             let mut sync_stmt = conn
@@ -569,6 +537,65 @@ impl ModelRegistryService {
         Ok(model_count)
     }
 
+    /// 替换指定 Provider 的模型数据
+    ///
+    /// 用于远程同步的模型目录：移除该 Provider 的旧模型后写入新模型，
+    /// 其他 Provider 的内嵌数据保持不变。返回该 Provider 的模型数量。
+    pub async fn replace_provider_models(
+        &self,
+        provider_id: &str,
+        models: Vec<EnhancedModelMetadata>,
+    ) -> Result<u32, String> {
+        let count = models.len() as u32;
+        let snapshot = {
+            let mut cache = self.models_cache.write().await;
+            cache.retain(|m| m.provider_id != provider_id);
+            cache.extend(models);
+            cache.clone()
+        };
+
+        {
+            let mut state = self.sync_state.write().await;
+            state.model_count = snapshot.len() as u32;
+            state.last_sync_at = Some(chrono::Utc::now().timestamp());
+            state.last_error = None;
+        }
+
+        self.save_models_to_db(&snapshot).await?;
+        Ok(count)
+    }
+
+    /// 同步 OpenRouter 模型目录
+    ///
+    /// 拉取 OpenRouter `/models` 接口，将可用模型及价格写入注册表
+    pub async fn sync_openrouter_catalog(
+        &self,
+        api_key: Option<String>,
+        base_url: Option<String>,
+    ) -> Result<u32, String> {
+        use crate::providers::openrouter::{OpenRouterProvider, OPENROUTER_PROVIDER_ID};
+
+        let provider = OpenRouterProvider::new(api_key, base_url);
+        let catalog = match provider.fetch_model_catalog().await {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                let error = format!("同步 OpenRouter 模型目录失败: {}", e);
+                self.sync_state.write().await.last_error = Some(error.clone());
+                return Err(error);
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let models: Vec<EnhancedModelMetadata> =
+            catalog.iter().map(|m| m.to_model_metadata(now)).collect();
+
+        let count = self
+            .replace_provider_models(OPENROUTER_PROVIDER_ID, models)
+            .await?;
+        tracing::info!("[ModelRegistry] 已同步 {} 个 OpenRouter 模型", count);
+        Ok(count)
+    }
+
     /// 按 Provider 获取模型
     pub async fn get_models_by_provider(&self, provider_id: &str) -> Vec<EnhancedModelMetadata> {
         self.models_cache
@@ -811,3 +838,50 @@ impl ModelRegistryService {
         self.aliases_cache.read().await.clone()
     }
 }
+
+/// 从数据库读取模型注册表（注册服务初始化时写入）
+pub fn load_registry_models(
+    conn: &rusqlite::Connection,
+) -> Result<Vec<EnhancedModelMetadata>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, display_name, provider_id, provider_name, family, tier,
+                    capabilities, pricing, limits, status, release_date, is_latest,
+                    description, source, created_at, updated_at
+             FROM model_registry",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let models = stmt
+        .query_map([], |row| {
+            let capabilities_json: String = row.get(6)?;
+            let pricing_json: Option<String> = row.get(7)?;
+            let limits_json: String = row.get(8)?;
+            let status_str: String = row.get(9)?;
+            let tier_str: String = row.get(5)?;
+            let source_str: String = row.get(13)?;
+
+            Ok(EnhancedModelMetadata {
+                id: row.get(0)?,
+                display_name: row.get(1)?,
+                provider_id: row.get(2)?,
+                provider_name: row.get(3)?,
+                family: row.get(4)?,
+                tier: tier_str.parse().unwrap_or(ModelTier::Pro),
+                capabilities: serde_json::from_str(&capabilities_json).unwrap_or_default(),
+                pricing: pricing_json.and_then(|s| serde_json::from_str(&s).ok()),
+                limits: serde_json::from_str(&limits_json).unwrap_or_default(),
+                status: status_str.parse().unwrap_or(ModelStatus::Active),
+                release_date: row.get(10)?,
+                is_latest: row.get::<_, i32>(11)? != 0,
+                description: row.get(12)?,
+                source: source_str.parse().unwrap_or(ModelSource::Local),
+                created_at: row.get(14)?,
+                updated_at: row.get(15)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(models)
+}
@@ -97,6 +97,15 @@ impl ModelService {
                 tracing::info!("[MODEL_SERVICE] 使用 OpenAI API Key");
                 self.fetch_models_openai(base_url.as_deref(), api_key).await
             }
+            CredentialData::OpenRouterKey { base_url, api_key }
+            | CredentialData::MistralKey { base_url, api_key }
+            | CredentialData::DeepSeekKey { base_url, api_key } => {
+                tracing::info!("[MODEL_SERVICE] 使用 {} API Key", credential.provider_type);
+                let base_url = base_url
+                    .clone()
+                    .or_else(|| credential.credential.default_base_url());
+                self.fetch_models_openai(base_url.as_deref(), api_key).await
+            }
             CredentialData::ClaudeKey { base_url, api_key } => {
                 tracing::info!("[MODEL_SERVICE] 使用 Claude API Key");
                 self.fetch_models_claude(base_url.as_deref(), api_key).await
@@ -393,6 +402,15 @@ impl ModelService {
             PoolProviderType::GeminiApiKey => {
                 vec!["gemini-2.5-flash".to_string(), "gemini-2.5-pro".to_string()]
             }
+            PoolProviderType::Mistral => vec![
+                "mistral-large-latest".to_string(),
+                "mistral-medium-latest".to_string(),
+                "mistral-small-latest".to_string(),
+                "codestral-latest".to_string(),
+            ],
+            PoolProviderType::DeepSeek => {
+                vec!["deepseek-chat".to_string(), "deepseek-reasoner".to_string()]
+            }
             _ => vec![],
         }
     }
@@ -405,17 +423,8 @@ impl ModelService {
         models: Vec<String>,
     ) -> Result<(), String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
-
-        // 序列化模型列表为 JSON
-        let models_json = serde_json::to_string(&models).map_err(|e| e.to_string())?;
-
-        conn.execute(
-            "UPDATE provider_pool_credentials SET supported_models = ?1, updated_at = ?2 WHERE uuid = ?3",
-            rusqlite::params![models_json, chrono::Utc::now().timestamp(), credential_uuid],
-        )
-        .map_err(|e| e.to_string())?;
-
-        Ok(())
+        ProviderPoolDao::update_supported_models(&conn, credential_uuid, &models)
+            .map_err(|e| e.to_string())
     }
 
     /// 获取凭证的支持模型列表（从数据库）
@@ -425,17 +434,10 @@ impl ModelService {
         credential_uuid: &str,
     ) -> Result<Vec<String>, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
-
-        let mut stmt = conn
-            .prepare("SELECT supported_models FROM provider_pool_credentials WHERE uuid = ?1")
-            .map_err(|e| e.to_string())?;
-
-        let models_json: Option<String> = stmt.query_row([credential_uuid], |row| row.get(0)).ok();
-
-        match models_json {
-            Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
-            None => Ok(vec![]),
-        }
+        Ok(ProviderPoolDao::get_by_uuid(&conn, credential_uuid)
+            .map_err(|e| e.to_string())?
+            .map(|cred| cred.supported_models)
+            .unwrap_or_default())
     }
 
     /// 获取所有凭证的模型列表（按 Provider 类型分组）
@@ -500,4 +502,30 @@ mod tests {
         assert!(!gemini_models.is_empty());
         assert!(gemini_models.contains(&"gemini-2.5-flash".to_string()));
     }
+
+    #[test]
+    fn test_update_credential_models_bumps_generation() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        let cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        ProviderPoolDao::insert(&conn, &cred).unwrap();
+        let db: DbConnection = std::sync::Arc::new(std::sync::Mutex::new(conn));
+
+        let service = ModelService::new();
+        let before = ProviderPoolDao::generation();
+        service
+            .update_credential_models(&db, &cred.uuid, vec!["gpt-4o".to_string()])
+            .unwrap();
+        assert!(ProviderPoolDao::generation() > before);
+        assert_eq!(
+            service.get_credential_models(&db, &cred.uuid).unwrap(),
+            vec!["gpt-4o".to_string()]
+        );
+    }
 }
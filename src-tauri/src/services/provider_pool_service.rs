@@ -4,24 +4,40 @@
 
 #![allow(dead_code)]
 
+use crate::config::{
+    CredentialExpiryConfig, CredentialSelectionStrategy, CredentialTiersConfig, HealthCheckConfig,
+    ModelBlacklistConfig, PriorityLanesConfig,
+};
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
+use crate::middleware::priority_lane::{current_lane, TrafficLane};
 use crate::models::provider_pool_model::{
-    get_default_check_model, get_oauth_creds_path, CredentialData, CredentialDisplay,
-    HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats, ProviderCredential,
-    ProviderPoolOverview,
+    get_default_check_model, get_oauth_creds_path, parse_tag_selector, BulkCredentialFilter,
+    BulkOperationResult, CredentialData, CredentialDisplay, CredentialSchedule, CredentialTier,
+    DuplicateCredentialRequest, HealthCheckProgress, HealthCheckResult, OAuthStatus,
+    PoolProviderType, PoolStats, ProviderCredential, ProviderPoolOverview,
 };
 use crate::models::route_model::RouteInfo;
 use crate::providers::antigravity::TokenRefreshError;
 use crate::providers::kiro::KiroProvider;
 use crate::services::api_key_provider_service::ApiKeyProviderService;
+use crate::services::credential_expiry_service;
+use crate::services::model_catalog_service::ModelCatalogService;
+use crate::usage::CredentialQuota;
 use chrono::Utc;
+use futures::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// 为交互式请求保留的凭证数量（按比例向上取整，至少给批处理请求留一个凭证）
+pub fn reserved_interactive_count(total: usize, ratio: f64) -> usize {
+    ((total as f64 * ratio.clamp(0.0, 1.0)).ceil() as usize).min(total.saturating_sub(1))
+}
+
 /// 凭证健康信息
 /// Requirements: 3.1, 3.2
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +82,37 @@ pub struct ProviderPoolService {
     max_error_count: u32,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 按 Provider 类型缓存的凭证列表（选择凭证时不访问数据库）
+    credential_cache: std::sync::RwLock<HashMap<PoolProviderType, CachedCredentials>>,
+    /// 凭证过期预警配置（用于计算展示的过期状态）
+    expiry_config: std::sync::RwLock<CredentialExpiryConfig>,
+    /// 凭证额度查询结果（uuid -> 额度）
+    quota_cache: std::sync::RwLock<HashMap<String, CredentialQuota>>,
+    /// 凭证优先级分层配置
+    tier_config: std::sync::RwLock<CredentialTiersConfig>,
+    /// 模型黑名单自动学习配置
+    model_blacklist_config: std::sync::RwLock<ModelBlacklistConfig>,
+    /// 凭证健康检查配置
+    health_check_config: std::sync::RwLock<HealthCheckConfig>,
+    /// 批量健康检查进度广播（WebSocket 推送给所有连接）
+    health_progress: tokio::sync::broadcast::Sender<HealthCheckProgress>,
+    /// 凭证对各模型的连续"模型不支持"错误次数（(uuid, model) -> 次数）
+    model_failures: std::sync::RwLock<HashMap<(String, String), u32>>,
+    /// 请求优先级通道配置
+    priority_lanes_config: std::sync::RwLock<PriorityLanesConfig>,
+    /// 凭证选择策略
+    selection_strategy: std::sync::RwLock<CredentialSelectionStrategy>,
+    /// 凭证延迟提示（uuid -> 滑动窗口内的 P95 延迟毫秒，由遥测统计定期整体替换）
+    latency_hints: std::sync::RwLock<HashMap<String, u64>>,
+    /// 模型目录聚合服务（凭证增删改后清除其缓存）
+    model_catalog: Arc<ModelCatalogService>,
+}
+
+/// 凭证缓存条目
+struct CachedCredentials {
+    /// 加载时的凭证池写入代数（见 `ProviderPoolDao::generation`）
+    generation: u64,
+    credentials: Vec<ProviderCredential>,
 }
 
 impl Default for ProviderPoolService {
@@ -84,6 +131,382 @@ impl ProviderPoolService {
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
             max_error_count: 3,
             health_check_timeout: Duration::from_secs(30),
+            credential_cache: std::sync::RwLock::new(HashMap::new()),
+            expiry_config: std::sync::RwLock::new(CredentialExpiryConfig::default()),
+            quota_cache: std::sync::RwLock::new(HashMap::new()),
+            tier_config: std::sync::RwLock::new(CredentialTiersConfig::default()),
+            model_blacklist_config: std::sync::RwLock::new(ModelBlacklistConfig::default()),
+            health_check_config: std::sync::RwLock::new(HealthCheckConfig::default()),
+            health_progress: tokio::sync::broadcast::channel(256).0,
+            model_failures: std::sync::RwLock::new(HashMap::new()),
+            priority_lanes_config: std::sync::RwLock::new(PriorityLanesConfig::default()),
+            selection_strategy: std::sync::RwLock::new(CredentialSelectionStrategy::default()),
+            latency_hints: std::sync::RwLock::new(HashMap::new()),
+            model_catalog: Arc::new(ModelCatalogService::default()),
+        }
+    }
+
+    /// 模型目录聚合服务（`/v1/models`、`/v1/capabilities` 共用）
+    pub fn model_catalog(&self) -> Arc<ModelCatalogService> {
+        self.model_catalog.clone()
+    }
+
+    /// 更新过期预警配置
+    pub fn set_expiry_config(&self, config: CredentialExpiryConfig) {
+        if let Ok(mut current) = self.expiry_config.write() {
+            *current = config;
+        }
+    }
+
+    /// 更新凭证优先级分层配置
+    pub fn set_tier_config(&self, config: CredentialTiersConfig) {
+        if let Ok(mut current) = self.tier_config.write() {
+            *current = config;
+        }
+    }
+
+    /// 更新模型黑名单自动学习配置
+    pub fn set_model_blacklist_config(&self, config: ModelBlacklistConfig) {
+        if let Ok(mut current) = self.model_blacklist_config.write() {
+            *current = config;
+        }
+    }
+
+    /// 更新凭证健康检查配置
+    pub fn set_health_check_config(&self, config: HealthCheckConfig) {
+        if let Ok(mut current) = self.health_check_config.write() {
+            *current = config;
+        }
+    }
+
+    /// 批量健康检查的并发数（未指定时使用配置值）
+    pub fn health_check_concurrency(&self, requested: Option<usize>) -> usize {
+        requested.unwrap_or_else(|| {
+            self.health_check_config
+                .read()
+                .map(|c| c.concurrency)
+                .unwrap_or_else(|_| HealthCheckConfig::default().concurrency)
+        })
+    }
+
+    /// 订阅批量健康检查进度
+    pub fn subscribe_health_progress(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<HealthCheckProgress> {
+        self.health_progress.subscribe()
+    }
+
+    /// 更新请求优先级通道配置
+    pub fn set_priority_lanes_config(&self, config: PriorityLanesConfig) {
+        if let Ok(mut current) = self.priority_lanes_config.write() {
+            *current = config;
+        }
+    }
+
+    /// 更新凭证选择策略
+    pub fn set_selection_strategy(&self, strategy: CredentialSelectionStrategy) {
+        if let Ok(mut current) = self.selection_strategy.write() {
+            *current = strategy;
+        }
+    }
+
+    /// 替换凭证延迟提示（窗口内没有样本的凭证不保留旧值，避免过期的高延迟一直压制该凭证）
+    pub fn set_latency_hints(&self, hints: HashMap<String, u64>) {
+        if let Ok(mut current) = self.latency_hints.write() {
+            *current = hints;
+        }
+    }
+
+    /// 记录凭证请求指定模型时返回的"模型不存在/不支持"错误
+    ///
+    /// 连续失败达到阈值后将模型加入凭证的 `not_supported_models`（到期后重新尝试），返回是否已加入。
+    pub fn record_model_failure(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        model: &str,
+    ) -> Result<bool, String> {
+        let config = self
+            .model_blacklist_config
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        if !config.enabled {
+            return Ok(false);
+        }
+
+        let key = (uuid.to_string(), model.to_string());
+        let failures = {
+            let mut failures = self.model_failures.write().map_err(|e| e.to_string())?;
+            let count = failures.entry(key.clone()).or_insert(0);
+            *count += 1;
+            let count = *count;
+            if count >= config.failure_threshold {
+                failures.remove(&key);
+            }
+            count
+        };
+        if failures < config.failure_threshold {
+            return Ok(false);
+        }
+
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+        let expires_at = Utc::now() + chrono::Duration::seconds(config.expiry_secs as i64);
+        cred.learn_unsupported_model(model, expires_at);
+        ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+        self.model_catalog.invalidate();
+        tracing::warn!(
+            "[MODEL_BLACKLIST] 凭证 {} 连续 {} 次不支持模型 {}，已加入不支持列表（{} 后重新尝试）",
+            cred.name.as_deref().unwrap_or(uuid),
+            failures,
+            model,
+            expires_at.to_rfc3339()
+        );
+        Ok(true)
+    }
+
+    /// 凭证成功处理指定模型：清除失败计数，并移除到期后重新尝试成功的自动学习条目
+    fn record_model_success(
+        &self,
+        conn: &rusqlite::Connection,
+        uuid: &str,
+        model: &str,
+    ) -> Result<(), String> {
+        let key = (uuid.to_string(), model.to_string());
+        let has_failures = self
+            .model_failures
+            .read()
+            .is_ok_and(|failures| failures.contains_key(&key));
+        if has_failures {
+            if let Ok(mut failures) = self.model_failures.write() {
+                failures.remove(&key);
+            }
+        }
+        let learned = self.credential_cache.read().is_ok_and(|cache| {
+            cache
+                .values()
+                .flat_map(|entry| entry.credentials.iter())
+                .any(|c| c.uuid == uuid && c.learned_unsupported_models.contains_key(model))
+        });
+        if !learned {
+            return Ok(());
+        }
+        if let Some(mut cred) =
+            ProviderPoolDao::get_by_uuid(conn, uuid).map_err(|e| e.to_string())?
+        {
+            if cred.forget_learned_model(model) {
+                ProviderPoolDao::update(conn, &cred).map_err(|e| e.to_string())?;
+                self.model_catalog.invalidate();
+                tracing::info!(
+                    "[MODEL_BLACKLIST] 凭证 {} 重新尝试模型 {} 成功，已从不支持列表移除",
+                    cred.name.as_deref().unwrap_or(uuid),
+                    model
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 按请求优先级通道筛选候选凭证
+    ///
+    /// 批处理请求排除为交互式请求保留的凭证：候选凭证按 UUID 排序，
+    /// 前 `reserved_interactive_count` 个保留给交互式请求。
+    fn filter_by_lane(&self, mut available: Vec<ProviderCredential>) -> Vec<ProviderCredential> {
+        if current_lane() != TrafficLane::Batch {
+            return available;
+        }
+        let config = self
+            .priority_lanes_config
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        if !config.enabled {
+            return available;
+        }
+
+        let reserved =
+            reserved_interactive_count(available.len(), config.interactive_reserve_ratio);
+        if reserved == 0 {
+            return available;
+        }
+        available.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        tracing::debug!(
+            "[PRIORITY_LANE] 批处理请求跳过 {} 个为交互式请求保留的凭证，剩余 {} 个",
+            reserved,
+            available.len() - reserved
+        );
+        available.split_off(reserved)
+    }
+
+    /// 按优先级分层筛选候选凭证
+    ///
+    /// 存在可用且未被限流的主力凭证时只返回主力凭证，否则溢出到溢出凭证；
+    /// 没有可用的溢出凭证时仍返回全部凭证（包括被限流的主力凭证）。
+    fn filter_by_tier(
+        &self,
+        provider_type: PoolProviderType,
+        available: Vec<ProviderCredential>,
+    ) -> Vec<ProviderCredential> {
+        let config = self
+            .tier_config
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        if !config.is_enabled_for(&provider_type.to_string()) {
+            return available;
+        }
+
+        let now = Utc::now();
+        let (primary, overflow): (Vec<_>, Vec<_>) = available
+            .into_iter()
+            .partition(|c| c.tier == CredentialTier::Primary);
+        let (ready, rate_limited): (Vec<_>, Vec<_>) = primary
+            .into_iter()
+            .partition(|c| !c.is_recently_rate_limited(now, config.rate_limit_cooldown_secs));
+        if !ready.is_empty() {
+            return ready;
+        }
+        if !overflow.is_empty() {
+            tracing::info!(
+                "[CREDENTIAL_TIER] {} 主力凭证不可用（{} 个被限流），使用 {} 个溢出凭证",
+                provider_type,
+                rate_limited.len(),
+                overflow.len()
+            );
+            return overflow;
+        }
+        rate_limited
+    }
+
+    /// 重置已过期的当日请求计数，返回重置的凭证数量
+    pub fn reset_expired_daily_usage(&self, db: &DbConnection) -> Result<usize, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let now = Utc::now();
+        let expired: Vec<_> = ProviderPoolDao::get_all(&conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|c| c.needs_daily_reset(now))
+            .collect();
+        for cred in &expired {
+            ProviderPoolDao::reset_daily_usage(&conn, &cred.uuid, now)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(expired.len())
+    }
+
+    /// 缓存的凭证额度
+    pub fn cached_quota(&self, uuid: &str) -> Option<CredentialQuota> {
+        self.quota_cache
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(uuid).cloned())
+    }
+
+    /// 缓存凭证额度查询结果
+    pub fn cache_quota(&self, quota: CredentialQuota) {
+        if let Ok(mut cache) = self.quota_cache.write() {
+            cache.insert(quota.credential_uuid.clone(), quota);
+        }
+    }
+
+    /// 将凭证转换为展示信息，并为 OAuth 凭证计算过期状态
+    fn to_displays(
+        &self,
+        conn: &rusqlite::Connection,
+        credentials: &[ProviderCredential],
+    ) -> Vec<CredentialDisplay> {
+        let expiry_info = ProviderPoolDao::get_all_expiry_info(conn).unwrap_or_default();
+        let config = self
+            .expiry_config
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        let now = Utc::now();
+        credentials
+            .iter()
+            .map(|cred| {
+                let mut display = CredentialDisplay::from(cred);
+                display.quota = self.cached_quota(&cred.uuid);
+                if get_oauth_creds_path(&cred.credential).is_some() {
+                    let info = expiry_info.get(&cred.uuid).cloned().unwrap_or_default();
+                    display.expiry_status = Some(credential_expiry_service::evaluate(
+                        &display.provider_type,
+                        cred.cached_token.as_ref(),
+                        &info,
+                        &config,
+                        now,
+                    ));
+                }
+                display
+            })
+            .collect()
+    }
+
+    /// 读取指定类型的凭证，优先使用内存缓存
+    ///
+    /// 调用方需持有数据库锁，保证读取的写入代数与数据一致。
+    /// 任何经过 DAO 的写操作（增删改、热重载同步等）都会使缓存失效，下次读取时重新加载。
+    fn credentials_by_type(
+        &self,
+        conn: &rusqlite::Connection,
+        pt: PoolProviderType,
+    ) -> Result<Vec<ProviderCredential>, String> {
+        let generation = ProviderPoolDao::generation();
+        if let Ok(cache) = self.credential_cache.read() {
+            if let Some(entry) = cache.get(&pt) {
+                if entry.generation == generation {
+                    return Ok(entry.credentials.clone());
+                }
+            }
+        }
+
+        let credentials = ProviderPoolDao::get_by_type(conn, &pt).map_err(|e| e.to_string())?;
+        if let Ok(mut cache) = self.credential_cache.write() {
+            cache.insert(
+                pt,
+                CachedCredentials {
+                    generation,
+                    credentials: credentials.clone(),
+                },
+            );
+        }
+        Ok(credentials)
+    }
+
+    /// 将本服务刚完成的单条写操作同步到缓存
+    ///
+    /// 使用统计和健康状态在每次请求后都会更新，直接修改缓存避免频繁失效。
+    /// 仅当写入前后代数恰好相差 1（期间没有其他写操作）时才同步，否则保持失效。
+    fn patch_cached_credential(
+        &self,
+        generation_before: u64,
+        uuid: &str,
+        patch: impl Fn(&mut ProviderCredential),
+    ) {
+        let generation = ProviderPoolDao::generation();
+        if generation != generation_before + 1 {
+            return;
+        }
+        if let Ok(mut cache) = self.credential_cache.write() {
+            for entry in cache.values_mut() {
+                if entry.generation != generation_before {
+                    continue;
+                }
+                if let Some(cred) = entry.credentials.iter_mut().find(|c| c.uuid == uuid) {
+                    patch(cred);
+                }
+                entry.generation = generation;
+            }
+        }
+    }
+
+    /// 清空凭证缓存
+    pub fn invalidate_credential_cache(&self) {
+        if let Ok(mut cache) = self.credential_cache.write() {
+            cache.clear();
         }
     }
 
@@ -102,7 +525,7 @@ impl ProviderPoolService {
             }
 
             let stats = PoolStats::from_credentials(&credentials);
-            let displays: Vec<CredentialDisplay> = credentials.iter().map(|c| c.into()).collect();
+            let displays = self.to_displays(&conn, &credentials);
 
             overview.push(ProviderPoolOverview {
                 provider_type: provider_type.to_string(),
@@ -134,7 +557,7 @@ impl ProviderPoolService {
                 .flatten();
         }
 
-        Ok(credentials.iter().map(|c| c.into()).collect())
+        Ok(self.to_displays(&conn, &credentials))
     }
 
     /// 添加凭证
@@ -156,6 +579,7 @@ impl ProviderPoolService {
 
         let conn = db.lock().map_err(|e| e.to_string())?;
         ProviderPoolDao::insert(&conn, &cred).map_err(|e| e.to_string())?;
+        self.model_catalog.invalidate();
 
         Ok(cred)
     }
@@ -171,6 +595,10 @@ impl ProviderPoolService {
         check_model_name: Option<String>,
         not_supported_models: Option<Vec<String>>,
         proxy_url: Option<String>,
+        tier: Option<CredentialTier>,
+        schedule: Option<CredentialSchedule>,
+        tags: Option<Vec<String>>,
+        pinned_model: Option<String>,
     ) -> Result<ProviderCredential, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
         let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
@@ -192,22 +620,183 @@ impl ProviderPoolService {
             cred.check_model_name = if m.is_empty() { None } else { Some(m) };
         }
         if let Some(models) = not_supported_models {
-            cred.not_supported_models = models;
+            cred.set_not_supported_models(models);
         }
         // 处理 proxy_url：空字符串表示清除，None 表示不修改
         if let Some(p) = proxy_url {
             cred.proxy_url = if p.is_empty() { None } else { Some(p) };
         }
+        if let Some(t) = tier {
+            cred.tier = t;
+        }
+        if let Some(s) = schedule {
+            cred.apply_schedule(s)?;
+        }
+        if let Some(t) = tags {
+            cred.set_tags(t);
+        }
+        if let Some(m) = pinned_model {
+            cred.set_pinned_model(m);
+        }
         cred.updated_at = Utc::now();
 
         ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+        self.model_catalog.invalidate();
         Ok(cred)
     }
 
     /// 删除凭证
     pub fn delete_credential(&self, db: &DbConnection, uuid: &str) -> Result<bool, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
-        ProviderPoolDao::delete(&conn, uuid).map_err(|e| e.to_string())
+        let deleted = ProviderPoolDao::delete(&conn, uuid).map_err(|e| e.to_string())?;
+        self.model_catalog.invalidate();
+        Ok(deleted)
+    }
+
+    /// 复制凭证
+    ///
+    /// 副本使用新的 UUID，配置沿用原凭证（`overrides` 中指定的字段除外），使用统计和健康状态从零开始。
+    ///
+    /// 不支持复制 OAuth 凭证：复制凭证文件后两个凭证仍持有同一个 Refresh Token，
+    /// 一方刷新轮换后另一方的 Token 失效。需要同一账号的另一个凭证时应重新登录。
+    pub fn duplicate_credential(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        overrides: DuplicateCredentialRequest,
+    ) -> Result<ProviderCredential, String> {
+        let source = {
+            let conn = db.lock().map_err(|e| e.to_string())?;
+            ProviderPoolDao::get_by_uuid(&conn, uuid)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Credential not found: {}", uuid))?
+        };
+        if get_oauth_creds_path(&source.credential).is_some() {
+            return Err(format!(
+                "OAuth credentials cannot be duplicated (the copy would share the refresh token); \
+                 log in again to add another {} credential",
+                source.provider_type
+            ));
+        }
+
+        let mut cred = ProviderCredential::new(source.provider_type, source.credential.clone());
+        cred.name = match overrides.name {
+            Some(n) => (!n.is_empty()).then_some(n),
+            None => source.name.map(|n| format!("{} (copy)", n)),
+        };
+        cred.is_disabled = overrides.is_disabled.unwrap_or(source.is_disabled);
+        cred.check_health = overrides.check_health.unwrap_or(source.check_health);
+        cred.check_model_name = match overrides.check_model_name {
+            Some(m) => (!m.is_empty()).then_some(m),
+            None => source.check_model_name,
+        };
+        cred.not_supported_models = source.not_supported_models;
+        cred.learned_unsupported_models = source.learned_unsupported_models;
+        if let Some(models) = overrides.not_supported_models {
+            cred.set_not_supported_models(models);
+        }
+        cred.supported_models = source.supported_models;
+        cred.proxy_url = match overrides.proxy_url {
+            Some(p) => (!p.is_empty()).then_some(p),
+            None => source.proxy_url,
+        };
+        cred.tier = overrides.tier.unwrap_or(source.tier);
+        cred.schedule = source.schedule;
+        if let Some(schedule) = overrides.schedule {
+            cred.apply_schedule(schedule)?;
+        }
+        cred.tags = source.tags;
+        if let Some(tags) = overrides.tags {
+            cred.set_tags(tags);
+        }
+        cred.pinned_model = source.pinned_model;
+        if let Some(model) = overrides.pinned_model {
+            cred.set_pinned_model(model);
+        }
+
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::insert(&conn, &cred).map_err(|e| e.to_string())?;
+        self.model_catalog.invalidate();
+        Ok(cred)
+    }
+
+    /// 获取满足批量操作筛选条件的凭证
+    fn bulk_targets(
+        &self,
+        db: &DbConnection,
+        filter: &BulkCredentialFilter,
+    ) -> Result<Vec<ProviderCredential>, String> {
+        if let Some(pt) = &filter.provider_type {
+            pt.parse::<PoolProviderType>()?;
+        }
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        Ok(ProviderPoolDao::get_all(&conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|cred| filter.matches(cred))
+            .collect())
+    }
+
+    /// 批量启用 / 禁用凭证
+    pub fn bulk_set_disabled(
+        &self,
+        db: &DbConnection,
+        filter: &BulkCredentialFilter,
+        is_disabled: bool,
+    ) -> Result<Vec<BulkOperationResult>, String> {
+        let targets = self.bulk_targets(db, filter)?;
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let results = targets
+            .into_iter()
+            .map(|mut cred| {
+                cred.is_disabled = is_disabled;
+                cred.updated_at = Utc::now();
+                let result = ProviderPoolDao::update(&conn, &cred)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string());
+                BulkOperationResult::new(&cred, result)
+            })
+            .collect();
+        self.model_catalog.invalidate();
+        Ok(results)
+    }
+
+    /// 批量删除不健康的凭证
+    pub fn bulk_delete_unhealthy(
+        &self,
+        db: &DbConnection,
+        filter: &BulkCredentialFilter,
+    ) -> Result<Vec<BulkOperationResult>, String> {
+        let targets = self.bulk_targets(db, filter)?;
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let results = targets
+            .into_iter()
+            .filter(|cred| !cred.is_healthy)
+            .map(|cred| {
+                let result = match ProviderPoolDao::delete(&conn, &cred.uuid) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err("Credential already deleted".to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                BulkOperationResult::new(&cred, result)
+            })
+            .collect();
+        self.model_catalog.invalidate();
+        Ok(results)
+    }
+
+    /// 批量重新检查凭证健康状态（跳过已禁用和未开启健康检查的凭证）
+    pub async fn bulk_check_health(
+        &self,
+        db: &DbConnection,
+        filter: &BulkCredentialFilter,
+        concurrency: usize,
+    ) -> Result<Vec<HealthCheckResult>, String> {
+        let targets = self.bulk_targets(db, filter)?;
+        let label = filter.provider_type.as_deref().unwrap_or("all");
+        Ok(self
+            .check_credentials_health(db, label, targets, concurrency, |_| {})
+            .await)
     }
 
     /// 选择一个可用的凭证（智能轮换策略）
@@ -217,12 +806,18 @@ impl ProviderPoolService {
     /// - 使用频率：优先选择使用次数较少的凭证
     /// - 错误率：避免选择错误次数过多的凭证
     /// - 冷却时间：避免短时间内重复使用同一凭证
+    ///
+    /// `provider_type` 为 `tag:<name>` 时在所有 Provider 类型中按标签选择
     pub fn select_credential(
         &self,
         db: &DbConnection,
         provider_type: &str,
         model: Option<&str>,
     ) -> Result<Option<ProviderCredential>, String> {
+        if let Some(tag) = parse_tag_selector(provider_type) {
+            return self.select_credential_by_tag(db, tag, model);
+        }
+
         // 对于未知的 provider_type，直接返回 None（不是错误）
         // 这样可以让 select_credential_with_fallback 继续尝试智能降级
         let pt: PoolProviderType = match provider_type.parse() {
@@ -238,8 +833,7 @@ impl ProviderPoolService {
         let conn = db.lock().map_err(|e| e.to_string())?;
 
         // 获取凭证，对于 Anthropic 类型，也查找 Claude 类型的凭证
-        let mut credentials =
-            ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?;
+        let mut credentials = self.credentials_by_type(&conn, pt)?;
         eprintln!(
             "[SELECT_CREDENTIAL] provider_type={}, pt={:?}, initial_count={}",
             provider_type,
@@ -249,16 +843,14 @@ impl ProviderPoolService {
 
         // Anthropic 和 Claude 共享凭证（都使用 Anthropic API）
         if pt == PoolProviderType::Anthropic {
-            let claude_creds = ProviderPoolDao::get_by_type(&conn, &PoolProviderType::Claude)
-                .map_err(|e| e.to_string())?;
+            let claude_creds = self.credentials_by_type(&conn, PoolProviderType::Claude)?;
             eprintln!(
                 "[SELECT_CREDENTIAL] Anthropic: adding {} Claude credentials",
                 claude_creds.len()
             );
             credentials.extend(claude_creds);
         } else if pt == PoolProviderType::Claude {
-            let anthropic_creds = ProviderPoolDao::get_by_type(&conn, &PoolProviderType::Anthropic)
-                .map_err(|e| e.to_string())?;
+            let anthropic_creds = self.credentials_by_type(&conn, PoolProviderType::Anthropic)?;
             eprintln!(
                 "[SELECT_CREDENTIAL] Claude: adding {} Anthropic credentials",
                 anthropic_creds.len()
@@ -307,6 +899,33 @@ impl ProviderPoolService {
             available.len()
         );
 
+        // 调度规则：不在时间窗口内或已达到每日请求上限的凭证不参与选择
+        let now = Utc::now();
+        available.retain(|c| {
+            let schedulable = c.is_schedulable(now);
+            if !schedulable {
+                eprintln!(
+                    "[SELECT_CREDENTIAL] credential {} is outside its schedule (daily_usage={})",
+                    c.name.as_deref().unwrap_or("unnamed"),
+                    c.current_daily_usage(now)
+                );
+            }
+            schedulable
+        });
+
+        // 维护窗口：处于计划内维护的 Provider 或凭证不参与选择
+        let maintenance = crate::credential::maintenance_registry();
+        available.retain(|c| {
+            let in_maintenance = maintenance.is_in_maintenance(c, now);
+            if in_maintenance {
+                tracing::debug!(
+                    "[MAINTENANCE] 跳过维护中的凭证 {}",
+                    c.name.as_deref().unwrap_or(&c.uuid)
+                );
+            }
+            !in_maintenance
+        });
+
         // 如果指定了模型，进一步过滤支持该模型的凭证
         if let Some(m) = model {
             available.retain(|c| {
@@ -330,14 +949,61 @@ impl ProviderPoolService {
             return Ok(None);
         }
 
-        // 如果只有一个可用凭证，直接返回
-        if available.len() == 1 {
-            return Ok(Some(available.into_iter().next().unwrap()));
-        }
+        // 优先级通道：批处理请求不使用为交互式请求保留的凭证
+        let available = self.filter_by_lane(available);
+
+        // 优先级分层：主力凭证不可用或被限流时才使用溢出凭证
+        let available = self.filter_by_tier(pt, available);
+
+        // 如果只有一个可用凭证，直接返回；否则按选择策略选择最优凭证
+        let selected = if available.len() == 1 {
+            available.into_iter().next().unwrap()
+        } else {
+            self.select_by_strategy(&available)
+        };
+
+        crate::middleware::response_info::record_credential(&selected);
+        Ok(Some(selected))
+    }
+
+    /// 按标签选择凭证（不区分 Provider 类型）
+    ///
+    /// 与 `select_credential` 使用相同的可用性、调度规则、模型过滤和优先级通道，
+    /// 标签选择由调用方显式指定凭证范围，不做优先级分层。
+    pub fn select_credential_by_tag(
+        &self,
+        db: &DbConnection,
+        tag: &str,
+        model: Option<&str>,
+    ) -> Result<Option<ProviderCredential>, String> {
+        let credentials = {
+            let conn = db.lock().map_err(|e| e.to_string())?;
+            ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+        };
 
-        // 智能选择：基于权重分数选择最优凭证
-        let selected = self.select_best_credential_by_weight(&available);
+        let now = Utc::now();
+        let available: Vec<_> = credentials
+            .into_iter()
+            .filter(|c| c.has_tag(tag))
+            .filter(|c| c.is_available() && c.is_schedulable(now))
+            .filter(|c| !crate::credential::maintenance_registry().is_in_maintenance(c, now))
+            .filter(|c| model.is_none_or(|m| c.supports_model(m)))
+            .collect();
+        let available = self.filter_by_lane(available);
 
+        eprintln!(
+            "[SELECT_CREDENTIAL] tag={}, model={:?}, available={}",
+            tag,
+            model,
+            available.len()
+        );
+
+        let selected = match available.len() {
+            0 => return Ok(None),
+            1 => available.into_iter().next().unwrap(),
+            _ => self.select_by_strategy(&available),
+        };
+        crate::middleware::response_info::record_credential(&selected);
         Ok(Some(selected))
     }
 
@@ -405,6 +1071,39 @@ impl ProviderPoolService {
     }
 
     /// 基于权重分数选择最优凭证
+    /// 按选择策略从多个可用凭证中选择
+    ///
+    /// 最低延迟策略选择 P95 延迟最低的凭证，没有延迟样本的凭证视为最快（优先试用以获得样本），
+    /// 延迟相同时按权重分数选择。
+    fn select_by_strategy(&self, credentials: &[ProviderCredential]) -> ProviderCredential {
+        let strategy = self
+            .selection_strategy
+            .read()
+            .map(|s| *s)
+            .unwrap_or_default();
+        match strategy {
+            CredentialSelectionStrategy::Weighted => {
+                self.select_best_credential_by_weight(credentials)
+            }
+            CredentialSelectionStrategy::LeastLatency => {
+                let hints = self.latency_hints.read().ok();
+                let latency = |c: &ProviderCredential| {
+                    hints
+                        .as_ref()
+                        .and_then(|h| h.get(&c.uuid).copied())
+                        .unwrap_or(0)
+                };
+                let fastest = credentials.iter().map(latency).min().unwrap_or(0);
+                let candidates: Vec<_> = credentials
+                    .iter()
+                    .filter(|c| latency(c) == fastest)
+                    .cloned()
+                    .collect();
+                self.select_best_credential_by_weight(&candidates)
+            }
+        }
+    }
+
     fn select_best_credential_by_weight(
         &self,
         credentials: &[ProviderCredential],
@@ -489,8 +1188,27 @@ impl ProviderPoolService {
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("Credential not found: {}", uuid))?;
 
-        ProviderPoolDao::update_usage(&conn, uuid, cred.usage_count + 1, Utc::now())
-            .map_err(|e| e.to_string())
+        let generation = ProviderPoolDao::generation();
+        let usage_count = cred.usage_count + 1;
+        let last_used = Utc::now();
+        let (daily_usage_count, daily_usage_reset_at) = cred.next_daily_usage(last_used);
+        ProviderPoolDao::update_usage(
+            &conn,
+            uuid,
+            usage_count,
+            daily_usage_count,
+            daily_usage_reset_at,
+            last_used,
+        )
+        .map_err(|e| e.to_string())?;
+        self.patch_cached_credential(generation, uuid, |c| {
+            c.usage_count = usage_count;
+            c.daily_usage_count = daily_usage_count;
+            c.daily_usage_reset_at = Some(daily_usage_reset_at);
+            c.last_used = Some(last_used);
+            c.updated_at = last_used;
+        });
+        Ok(())
     }
 
     /// 标记凭证为健康
@@ -501,6 +1219,8 @@ impl ProviderPoolService {
         check_model: Option<&str>,
     ) -> Result<(), String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
+        let generation = ProviderPoolDao::generation();
+        let now = Utc::now();
         ProviderPoolDao::update_health_status(
             &conn,
             uuid,
@@ -508,10 +1228,23 @@ impl ProviderPoolService {
             0,
             None,
             None,
-            Some(Utc::now()),
+            Some(now),
             check_model,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+        self.patch_cached_credential(generation, uuid, |c| {
+            c.is_healthy = true;
+            c.error_count = 0;
+            c.last_error_time = None;
+            c.last_error_message = None;
+            c.last_health_check_time = Some(now);
+            c.last_health_check_model = check_model.map(|m| m.to_string());
+            c.updated_at = now;
+        });
+        if let Some(model) = check_model {
+            self.record_model_success(&conn, uuid, model)?;
+        }
+        Ok(())
     }
 
     /// 标记凭证为不健康
@@ -529,17 +1262,29 @@ impl ProviderPoolService {
         let new_error_count = cred.error_count + 1;
         let is_healthy = new_error_count < self.max_error_count;
 
+        let generation = ProviderPoolDao::generation();
+        let now = Utc::now();
         ProviderPoolDao::update_health_status(
             &conn,
             uuid,
             is_healthy,
             new_error_count,
-            Some(Utc::now()),
+            Some(now),
             error_message,
             None,
             None,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+        self.patch_cached_credential(generation, uuid, |c| {
+            c.is_healthy = is_healthy;
+            c.error_count = new_error_count;
+            c.last_error_time = Some(now);
+            c.last_error_message = error_message.map(|m| m.to_string());
+            c.last_health_check_time = None;
+            c.last_health_check_model = None;
+            c.updated_at = now;
+        });
+        Ok(())
     }
 
     /// 重置凭证计数器
@@ -889,23 +1634,97 @@ impl ProviderPoolService {
         db: &DbConnection,
         provider_type: &str,
     ) -> Result<Vec<HealthCheckResult>, String> {
+        self.check_type_health_with_progress(
+            db,
+            provider_type,
+            self.health_check_concurrency(None),
+            |_| {},
+        )
+        .await
+    }
+
+    /// 并发检查指定类型的所有凭证
+    ///
+    /// 同时进行的检查不超过 `concurrency` 个，每完成一个凭证回调一次进度。
+    /// 单个凭证检查出错时记为失败结果，不中断其余检查；结果按凭证原始顺序返回。
+    pub async fn check_type_health_with_progress<F>(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        concurrency: usize,
+        on_progress: F,
+    ) -> Result<Vec<HealthCheckResult>, String>
+    where
+        F: Fn(&HealthCheckProgress) + Sync,
+    {
         let pt: PoolProviderType = provider_type.parse().map_err(|e: String| e)?;
         let credentials = {
             let conn = db.lock().map_err(|e| e.to_string())?;
             ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?
         };
 
-        let mut results = Vec::new();
-        for cred in credentials {
-            if cred.is_disabled || !cred.check_health {
-                continue;
-            }
+        Ok(self
+            .check_credentials_health(db, provider_type, credentials, concurrency, on_progress)
+            .await)
+    }
+
+    /// 并发检查一组凭证（跳过已禁用和未开启健康检查的凭证）
+    ///
+    /// 进度同时回调 `on_progress` 并广播给健康检查进度的订阅者。
+    async fn check_credentials_health<F>(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        credentials: Vec<ProviderCredential>,
+        concurrency: usize,
+        on_progress: F,
+    ) -> Vec<HealthCheckResult>
+    where
+        F: Fn(&HealthCheckProgress) + Sync,
+    {
+        let credentials: Vec<ProviderCredential> = credentials
+            .into_iter()
+            .filter(|cred| !cred.is_disabled && cred.check_health)
+            .collect();
 
-            let result = self.check_credential_health(db, &cred.uuid).await?;
-            results.push(result);
+        let total = credentials.len();
+        // 每个检查持有自己的凭证 UUID 和数据库连接，future 不借用迭代器中的数据
+        let mut checks = futures::stream::iter(credentials.into_iter().enumerate())
+            .map(|(index, cred)| {
+                let db = db.clone();
+                async move {
+                    let start = std::time::Instant::now();
+                    let result = match self.check_credential_health(&db, &cred.uuid).await {
+                        Ok(result) => result,
+                        Err(e) => HealthCheckResult {
+                            uuid: cred.uuid,
+                            success: false,
+                            model: None,
+                            message: Some(e),
+                            duration_ms: start.elapsed().as_millis() as u64,
+                        },
+                    };
+                    (index, result)
+                }
+            })
+            .buffer_unordered(concurrency.max(1));
+
+        let mut results: Vec<Option<HealthCheckResult>> = vec![None; total];
+        let mut completed = 0;
+        while let Some((index, result)) = checks.next().await {
+            completed += 1;
+            let progress = HealthCheckProgress {
+                provider_type: provider_type.to_string(),
+                completed,
+                total,
+                result: result.clone(),
+            };
+            on_progress(&progress);
+            let _ = self.health_progress.send(progress);
+            results[index] = Some(result);
         }
 
-        Ok(results)
+        results.into_iter().flatten().collect()
     }
 
     /// 执行实际的健康检查请求
@@ -940,6 +1759,13 @@ impl ProviderPoolService {
                 self.check_openai_health(api_key, base_url.as_deref(), model)
                     .await
             }
+            CredentialData::OpenRouterKey { api_key, base_url }
+            | CredentialData::MistralKey { api_key, base_url }
+            | CredentialData::DeepSeekKey { api_key, base_url } => {
+                let base_url = base_url.clone().or_else(|| credential.default_base_url());
+                self.check_openai_health(api_key, base_url.as_deref(), model)
+                    .await
+            }
             CredentialData::ClaudeKey { api_key, base_url } => {
                 self.check_claude_health(api_key, base_url.as_deref(), model)
                     .await
@@ -1017,8 +1843,8 @@ impl ProviderPoolService {
                 self.format_user_friendly_error(&format!("加载凭证失败: {}", e), "Kiro")
             })?;
 
-        let access_token = provider
-            .credentials
+        let creds = provider.credentials();
+        let access_token = creds
             .access_token
             .as_ref()
             .ok_or_else(|| "凭证中缺少 access_token".to_string())?;
@@ -1055,8 +1881,8 @@ impl ProviderPoolService {
         });
 
         // 如果是 social 认证方式，需要添加 profileArn
-        if provider.credentials.auth_method.as_deref() == Some("social") {
-            if let Some(profile_arn) = &provider.credentials.profile_arn {
+        if creds.auth_method.as_deref() == Some("social") {
+            if let Some(profile_arn) = &creds.profile_arn {
                 request_body["profileArn"] = serde_json::json!(profile_arn);
             }
         }
@@ -1908,6 +2734,7 @@ impl ProviderPoolService {
 
         let conn = db.lock().map_err(|e| e.to_string())?;
         ProviderPoolDao::insert(&conn, &cred).map_err(|e| e.to_string())?;
+        self.model_catalog.invalidate();
 
         Ok(cred)
     }
@@ -2114,11 +2941,64 @@ pub struct MigrationResult {
     pub errors: Vec<String>,
 }
 
+/// 上游"模型不存在/不支持"错误中的关键词（需同时包含 "model"）
+const MODEL_UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "not found",
+    "not_found",
+    "does not exist",
+    "not supported",
+    "unsupported",
+    "not available",
+    "no such",
+    "unknown",
+    "does not have access",
+];
+
+/// 上游错误是否表示凭证不支持请求的模型
+pub fn is_model_unsupported_error(status: u16, message: &str) -> bool {
+    if !matches!(status, 400 | 403 | 404 | 422) {
+        return false;
+    }
+    let message = message.to_lowercase();
+    message.contains("model")
+        && MODEL_UNSUPPORTED_KEYWORDS
+            .iter()
+            .any(|keyword| message.contains(keyword))
+}
+
+/// 每日请求计数检查间隔
+const DAILY_USAGE_RESET_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 启动后台任务，定期重置到达重置时间的凭证当日请求计数（集群模式下仅 leader 执行）
+pub fn spawn_daily_usage_reset_task(
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if crate::services::cluster_service::is_leader() {
+                match pool_service.reset_expired_daily_usage(&db) {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!(
+                        "[CREDENTIAL_SCHEDULE] 已重置 {} 个凭证的当日请求计数",
+                        count
+                    ),
+                    Err(e) => {
+                        tracing::warn!("[CREDENTIAL_SCHEDULE] 重置当日请求计数失败: {}", e)
+                    }
+                }
+            }
+            tokio::time::sleep(DAILY_USAGE_RESET_INTERVAL).await;
+        }
+    })
+}
+
 // ==================== 测试模块 ====================
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::middleware::priority_lane::with_lane;
 
     // ==================== Property 3: 不健康凭证排除 ====================
     // Feature: antigravity-token-refresh, Property 3: 不健康凭证排除
@@ -2249,4 +3129,568 @@ mod tests {
         assert_eq!(deserialized.uuid, info.uuid);
         assert_eq!(deserialized.is_healthy, info.is_healthy);
     }
+
+    #[test]
+    fn test_bulk_check_health_future_is_send() {
+        // 管理 API 处理函数和集群后台任务都要求 future 为 Send
+        fn assert_send<T: Send>(_: T) {}
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let db: DbConnection = std::sync::Arc::new(std::sync::Mutex::new(conn));
+        let service = ProviderPoolService::new();
+        let filter = BulkCredentialFilter::default();
+        assert_send(service.bulk_check_health(&db, &filter, 2));
+        assert_send(service.check_type_health(&db, "openai"));
+    }
+
+    #[tokio::test]
+    async fn test_check_type_health_parallel_progress() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        let mut uuids = Vec::new();
+        for _ in 0..4 {
+            // 指向不可达地址，检查会很快失败
+            let cred = ProviderCredential::new(
+                PoolProviderType::OpenAI,
+                CredentialData::OpenAIKey {
+                    api_key: "sk-test".to_string(),
+                    base_url: Some("http://127.0.0.1:9".to_string()),
+                },
+            );
+            ProviderPoolDao::insert(&conn, &cred).unwrap();
+            uuids.push(cred.uuid);
+        }
+        let mut disabled = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-disabled".to_string(),
+                base_url: None,
+            },
+        );
+        disabled.is_disabled = true;
+        ProviderPoolDao::insert(&conn, &disabled).unwrap();
+        let db: DbConnection = std::sync::Arc::new(std::sync::Mutex::new(conn));
+
+        let service = ProviderPoolService::new();
+        let progress = std::sync::Mutex::new(Vec::new());
+        let results = service
+            .check_type_health_with_progress(&db, "openai", 2, |p| {
+                progress.lock().unwrap().push((p.completed, p.total));
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|r| !r.success));
+        let mut result_uuids: Vec<_> = results.iter().map(|r| r.uuid.clone()).collect();
+        let mut expected = uuids.clone();
+        result_uuids.sort();
+        expected.sort();
+        assert_eq!(result_uuids, expected);
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    fn pool_db_with(creds: &[ProviderCredential]) -> DbConnection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        for cred in creds {
+            ProviderPoolDao::insert(&conn, cred).unwrap();
+        }
+        std::sync::Arc::new(std::sync::Mutex::new(conn))
+    }
+
+    fn openai_credential(key: &str) -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: key.to_string(),
+                base_url: None,
+            },
+        )
+    }
+
+    fn cached_and_stored(
+        service: &ProviderPoolService,
+        db: &DbConnection,
+    ) -> (Vec<ProviderCredential>, Vec<ProviderCredential>) {
+        let conn = db.lock().unwrap();
+        let mut cached = service
+            .credentials_by_type(&conn, PoolProviderType::OpenAI)
+            .unwrap();
+        let mut stored = ProviderPoolDao::get_by_type(&conn, &PoolProviderType::OpenAI).unwrap();
+        cached.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        stored.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        (cached, stored)
+    }
+
+    #[test]
+    fn test_credential_cache_invalidated_on_dao_write() {
+        let cred = openai_credential("sk-a");
+        let db = pool_db_with(std::slice::from_ref(&cred));
+        let service = ProviderPoolService::new();
+
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(cred.uuid.clone()));
+
+        // 绕过服务直接通过 DAO 修改（如命令、热重载同步）
+        let mut disabled = cred.clone();
+        disabled.is_disabled = true;
+        ProviderPoolDao::update(&db.lock().unwrap(), &disabled).unwrap();
+        assert!(service
+            .select_credential(&db, "openai", None)
+            .unwrap()
+            .is_none());
+
+        let added = openai_credential("sk-b");
+        ProviderPoolDao::insert(&db.lock().unwrap(), &added).unwrap();
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(added.uuid));
+    }
+
+    #[tokio::test]
+    async fn test_model_catalog_invalidated_on_pool_change() {
+        let db = pool_db_with(&[]);
+        let service = ProviderPoolService::new();
+        let catalog = service.model_catalog();
+        assert!(catalog.list_models(&db, &[]).await.is_empty());
+
+        // OAuth 凭证使用默认模型列表，不访问上游
+        let cred = service
+            .add_credential(
+                &db,
+                "kiro",
+                CredentialData::KiroOAuth {
+                    creds_file_path: "/nonexistent/kiro.json".to_string(),
+                },
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(!catalog.list_models(&db, &[]).await.is_empty());
+
+        service.delete_credential(&db, &cred.uuid).unwrap();
+        assert!(catalog.list_models(&db, &[]).await.is_empty());
+    }
+
+    #[test]
+    fn test_credential_cache_tracks_health_updates() {
+        let a = openai_credential("sk-a");
+        let b = openai_credential("sk-b");
+        let db = pool_db_with(&[a.clone(), b.clone()]);
+        let service = ProviderPoolService::new();
+        assert!(service
+            .select_credential(&db, "openai", None)
+            .unwrap()
+            .is_some());
+
+        for _ in 0..service.max_error_count {
+            service.mark_unhealthy(&db, &a.uuid, Some("boom")).unwrap();
+        }
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(b.uuid.clone()));
+
+        service.mark_healthy(&db, &a.uuid, Some("gpt-4o")).unwrap();
+        let (cached, stored) = cached_and_stored(&service, &db);
+        let cached_a = cached.iter().find(|c| c.uuid == a.uuid).unwrap();
+        let stored_a = stored.iter().find(|c| c.uuid == a.uuid).unwrap();
+        assert!(cached_a.is_healthy && stored_a.is_healthy);
+        assert_eq!(cached_a.error_count, stored_a.error_count);
+        assert_eq!(
+            cached_a.last_health_check_model,
+            stored_a.last_health_check_model
+        );
+    }
+
+    #[test]
+    fn test_credential_cache_consistent_under_concurrent_updates() {
+        let creds: Vec<_> = (0..3)
+            .map(|i| openai_credential(&format!("sk-{i}")))
+            .collect();
+        let db = pool_db_with(&creds);
+        let service = std::sync::Arc::new(ProviderPoolService::new());
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let service = service.clone();
+                let db = db.clone();
+                let uuids: Vec<_> = creds.iter().map(|c| c.uuid.clone()).collect();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        let uuid = &uuids[(t + i) % uuids.len()];
+                        service.select_credential(&db, "openai", None).unwrap();
+                        service.record_usage(&db, uuid).unwrap();
+                        if i % 10 == 0 {
+                            service.mark_unhealthy(&db, uuid, Some("flaky")).unwrap();
+                            service.mark_healthy(&db, uuid, None).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let (cached, stored) = cached_and_stored(&service, &db);
+        assert_eq!(cached.len(), stored.len());
+        for (c, s) in cached.iter().zip(stored.iter()) {
+            assert_eq!(c.uuid, s.uuid);
+            assert_eq!(c.usage_count, s.usage_count);
+            assert_eq!(c.error_count, s.error_count);
+            assert_eq!(c.is_healthy, s.is_healthy);
+        }
+        assert_eq!(stored.iter().map(|c| c.usage_count).sum::<u64>(), 200);
+    }
+
+    #[test]
+    fn test_select_credential_spills_over_to_overflow_tier() {
+        let primary = openai_credential("sk-primary");
+        let mut overflow = openai_credential("sk-overflow");
+        overflow.tier = CredentialTier::Overflow;
+        let db = pool_db_with(&[primary.clone(), overflow.clone()]);
+        let service = ProviderPoolService::new();
+
+        for _ in 0..5 {
+            let selected = service.select_credential(&db, "openai", None).unwrap();
+            assert_eq!(selected.map(|c| c.uuid), Some(primary.uuid.clone()));
+        }
+
+        // 主力凭证被限流后溢出到溢出凭证
+        service
+            .mark_unhealthy(&db, &primary.uuid, Some("429 Too Many Requests"))
+            .unwrap();
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(overflow.uuid.clone()));
+
+        // 回避时间已过，恢复使用主力凭证
+        service.set_tier_config(CredentialTiersConfig {
+            rate_limit_cooldown_secs: 0,
+            ..CredentialTiersConfig::default()
+        });
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(primary.uuid.clone()));
+
+        // 主力凭证不健康时同样溢出
+        for _ in 0..service.max_error_count {
+            service
+                .mark_unhealthy(&db, &primary.uuid, Some("boom"))
+                .unwrap();
+        }
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(overflow.uuid));
+    }
+
+    #[test]
+    fn test_select_credential_least_latency() {
+        let fast = openai_credential("sk-fast");
+        let slow = openai_credential("sk-slow");
+        let db = pool_db_with(&[fast.clone(), slow.clone()]);
+        let service = ProviderPoolService::new();
+        service.set_selection_strategy(CredentialSelectionStrategy::LeastLatency);
+        service.set_latency_hints(HashMap::from([
+            (fast.uuid.clone(), 200),
+            (slow.uuid.clone(), 1500),
+        ]));
+
+        for _ in 0..3 {
+            let selected = service.select_credential(&db, "openai", None).unwrap();
+            assert_eq!(selected.map(|c| c.uuid), Some(fast.uuid.clone()));
+            service.record_usage(&db, &fast.uuid).unwrap();
+        }
+
+        // 同步时整体替换：窗口内没有样本的凭证清除旧的高延迟，重新参与选择
+        service.set_latency_hints(HashMap::from([(fast.uuid.clone(), 200)]));
+        let selected = service.select_credential(&db, "openai", None).unwrap();
+        assert_eq!(selected.map(|c| c.uuid), Some(slow.uuid));
+    }
+
+    #[test]
+    fn test_select_credential_respects_daily_request_limit() {
+        let mut limited = openai_credential("sk-limited");
+        limited
+            .apply_schedule(CredentialSchedule {
+                windows: vec![],
+                daily_request_limit: Some(2),
+                reset_time: "00:00".to_string(),
+            })
+            .unwrap();
+        let db = pool_db_with(std::slice::from_ref(&limited));
+        let service = ProviderPoolService::new();
+
+        for _ in 0..2 {
+            let selected = service.select_credential(&db, "openai", None).unwrap();
+            assert_eq!(selected.map(|c| c.uuid), Some(limited.uuid.clone()));
+            service.record_usage(&db, &limited.uuid).unwrap();
+        }
+        assert!(service
+            .select_credential(&db, "openai", None)
+            .unwrap()
+            .is_none());
+
+        // 计数未过期时后台任务不会重置
+        assert_eq!(service.reset_expired_daily_usage(&db).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_select_credential_by_tag() {
+        let mut work = openai_credential("sk-work");
+        work.set_tags(vec!["Work".to_string()]);
+        let mut work_kiro = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/path/to/creds".to_string(),
+            },
+        );
+        work_kiro.set_tags(vec!["work".to_string(), "night".to_string()]);
+        let personal = openai_credential("sk-personal");
+        let db = pool_db_with(&[work.clone(), work_kiro.clone(), personal]);
+        let service = ProviderPoolService::new();
+
+        // 跨 Provider 类型按标签选择，标签不区分大小写
+        let mut selected = Vec::new();
+        for _ in 0..4 {
+            let cred = service
+                .select_credential(&db, "tag:WORK", None)
+                .unwrap()
+                .unwrap();
+            assert!(cred.has_tag("work"));
+            service.record_usage(&db, &cred.uuid).unwrap();
+            selected.push(cred.uuid);
+        }
+        assert!(selected.contains(&work.uuid) && selected.contains(&work_kiro.uuid));
+
+        let night = service.select_credential(&db, "tag:night", None).unwrap();
+        assert_eq!(night.map(|c| c.uuid), Some(work_kiro.uuid.clone()));
+
+        // 禁用的凭证不参与选择
+        service
+            .update_credential(
+                &db,
+                &work_kiro.uuid,
+                None,
+                Some(true),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(service
+            .select_credential(&db, "tag:night", None)
+            .unwrap()
+            .is_none());
+        assert!(service
+            .select_credential(&db, "tag:unknown", None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_duplicate_credential_with_overrides() {
+        let mut source = openai_credential("sk-expensive");
+        source.name = Some("Main".to_string());
+        source.set_tags(vec!["work".to_string()]);
+        source.usage_count = 42;
+        let mut kiro = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/path/to/creds".to_string(),
+            },
+        );
+        kiro.is_healthy = false;
+        let db = pool_db_with(&[source.clone(), kiro.clone()]);
+        let service = ProviderPoolService::new();
+
+        let copy = service
+            .duplicate_credential(
+                &db,
+                &source.uuid,
+                DuplicateCredentialRequest {
+                    pinned_model: Some("gpt-4o-mini".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_ne!(copy.uuid, source.uuid);
+        assert_eq!(copy.name.as_deref(), Some("Main (copy)"));
+        assert_eq!(copy.tags, source.tags);
+        assert_eq!(copy.pinned_model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(copy.usage_count, 0);
+        assert!(service.get_by_uuid(&db, &copy.uuid).unwrap().is_some());
+
+        // OAuth 凭证的副本会共享 Refresh Token，拒绝复制
+        let err = service
+            .duplicate_credential(&db, &kiro.uuid, DuplicateCredentialRequest::default())
+            .unwrap_err();
+        assert!(err.contains("refresh token"));
+        assert_eq!(
+            ProviderPoolDao::get_all(&db.lock().unwrap()).unwrap().len(),
+            3
+        );
+
+        assert!(service
+            .duplicate_credential(&db, "missing", Default::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_bulk_credential_operations() {
+        let mut work = openai_credential("sk-work");
+        work.set_tags(vec!["work".to_string()]);
+        let mut broken = openai_credential("sk-broken");
+        broken.is_healthy = false;
+        let mut broken_kiro = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/path/to/creds".to_string(),
+            },
+        );
+        broken_kiro.is_healthy = false;
+        broken_kiro.set_tags(vec!["work".to_string()]);
+        let db = pool_db_with(&[work.clone(), broken.clone(), broken_kiro.clone()]);
+        let service = ProviderPoolService::new();
+
+        let by_tag = BulkCredentialFilter {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        let results = service.bulk_set_disabled(&db, &by_tag, true).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.success));
+        assert!(
+            service
+                .get_by_uuid(&db, &work.uuid)
+                .unwrap()
+                .unwrap()
+                .is_disabled
+        );
+        assert!(
+            !service
+                .get_by_uuid(&db, &broken.uuid)
+                .unwrap()
+                .unwrap()
+                .is_disabled
+        );
+
+        let openai = BulkCredentialFilter {
+            provider_type: Some("openai".to_string()),
+            ..Default::default()
+        };
+        let results = service.bulk_delete_unhealthy(&db, &openai).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].uuid, broken.uuid);
+        assert!(service.get_by_uuid(&db, &broken.uuid).unwrap().is_none());
+        assert!(service
+            .get_by_uuid(&db, &broken_kiro.uuid)
+            .unwrap()
+            .is_some());
+
+        let invalid = BulkCredentialFilter {
+            provider_type: Some("nope".to_string()),
+            ..Default::default()
+        };
+        assert!(service.bulk_set_disabled(&db, &invalid, false).is_err());
+    }
+
+    #[test]
+    fn test_batch_lane_skips_reserved_credentials() {
+        assert_eq!(reserved_interactive_count(0, 0.25), 0);
+        assert_eq!(reserved_interactive_count(1, 0.25), 0);
+        assert_eq!(reserved_interactive_count(4, 0.25), 1);
+        assert_eq!(reserved_interactive_count(5, 0.25), 2);
+        assert_eq!(reserved_interactive_count(4, 1.0), 3);
+
+        let creds: Vec<_> = (0..4)
+            .map(|i| openai_credential(&format!("sk-{}", i)))
+            .collect();
+        let db = pool_db_with(&creds);
+        let service = ProviderPoolService::new();
+        service.set_priority_lanes_config(PriorityLanesConfig {
+            enabled: true,
+            interactive_reserve_ratio: 0.5,
+            ..PriorityLanesConfig::default()
+        });
+        let mut uuids: Vec<_> = creds.iter().map(|c| c.uuid.clone()).collect();
+        uuids.sort();
+        let reserved = &uuids[..2];
+
+        let select = |lane| {
+            with_lane(lane, || {
+                let cred = service
+                    .select_credential(&db, "openai", None)
+                    .unwrap()
+                    .unwrap();
+                service.record_usage(&db, &cred.uuid).unwrap();
+                cred.uuid
+            })
+        };
+
+        // 批处理请求只使用未保留的凭证
+        for _ in 0..8 {
+            assert!(!reserved.contains(&select(TrafficLane::Batch)));
+        }
+        // 交互式请求可以使用全部凭证（优先选择使用次数较少的保留凭证）
+        let interactive: Vec<_> = (0..4).map(|_| select(TrafficLane::Interactive)).collect();
+        assert!(interactive.iter().any(|uuid| reserved.contains(uuid)));
+
+        // 未启用时不区分通道
+        service.set_priority_lanes_config(PriorityLanesConfig::default());
+        let batch: Vec<_> = (0..4).map(|_| select(TrafficLane::Batch)).collect();
+        assert!(batch.iter().any(|uuid| reserved.contains(uuid)));
+    }
+
+    #[test]
+    fn test_learn_unsupported_model_after_repeated_failures() {
+        let a = openai_credential("sk-a");
+        let b = openai_credential("sk-b");
+        let db = pool_db_with(&[a.clone(), b.clone()]);
+        let service = ProviderPoolService::new();
+
+        assert!(is_model_unsupported_error(
+            404,
+            r#"{"error":{"message":"The model `gpt-5` does not exist","code":"model_not_found"}}"#
+        ));
+        assert!(!is_model_unsupported_error(
+            429,
+            "model rate limit exceeded"
+        ));
+        assert!(!is_model_unsupported_error(400, "max_tokens is too large"));
+
+        // 成功会清除连续失败计数
+        for _ in 0..2 {
+            assert!(!service.record_model_failure(&db, &a.uuid, "gpt-5").unwrap());
+        }
+        service.mark_healthy(&db, &a.uuid, Some("gpt-5")).unwrap();
+        for _ in 0..2 {
+            assert!(!service.record_model_failure(&db, &a.uuid, "gpt-5").unwrap());
+        }
+        assert!(service.record_model_failure(&db, &a.uuid, "gpt-5").unwrap());
+        for _ in 0..3 {
+            let selected = service
+                .select_credential(&db, "openai", Some("gpt-5"))
+                .unwrap();
+            assert_eq!(selected.map(|c| c.uuid), Some(b.uuid.clone()));
+        }
+
+        // 到期后重新尝试，成功则移除
+        let mut expired = service.get_by_uuid(&db, &a.uuid).unwrap().unwrap();
+        assert_eq!(expired.not_supported_models, vec!["gpt-5".to_string()]);
+        expired.learned_unsupported_models.insert(
+            "gpt-5".to_string(),
+            Utc::now() - chrono::Duration::seconds(1),
+        );
+        ProviderPoolDao::update(&db.lock().unwrap(), &expired).unwrap();
+        assert!(service
+            .select_credential(&db, "openai", Some("gpt-5"))
+            .is_ok());
+        service.mark_healthy(&db, &a.uuid, Some("gpt-5")).unwrap();
+        let recovered = service.get_by_uuid(&db, &a.uuid).unwrap().unwrap();
+        assert!(recovered.not_supported_models.is_empty());
+        assert!(recovered.learned_unsupported_models.is_empty());
+    }
 }
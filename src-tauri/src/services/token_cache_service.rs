@@ -11,16 +11,19 @@
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    CachedTokenInfo, CredentialData, PoolProviderType, ProviderCredential,
+    get_oauth_creds_path, CachedTokenInfo, CredentialData, PoolProviderType, ProviderCredential,
 };
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::kiro::KiroProvider;
 use crate::providers::qwen::QwenProvider;
+use crate::services::credential_expiry_service;
 use crate::services::kiro_event_service::KiroEventService;
 use chrono::Utc;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 
 /// Token 刷新错误类型
 #[derive(Debug, Clone, PartialEq)]
@@ -54,10 +57,50 @@ pub struct RefreshErrorClassification {
     pub should_disable_credential: bool,
 }
 
+/// 刷新结果通道（刷新完成前为 None）
+type RefreshResult = watch::Receiver<Option<Result<String, String>>>;
+
+/// 进行中刷新的登记
+struct InflightRefresh {
+    /// 登记序号，刷新结束时只移除自己的登记
+    id: u64,
+    /// 是否为强制刷新
+    force: bool,
+    result: RefreshResult,
+}
+
 /// Token 缓存服务
 pub struct TokenCacheService {
     /// 每凭证一把锁，防止并发刷新
     locks: DashMap<String, Arc<Mutex<()>>>,
+    /// 进行中的刷新，同一凭证的其他请求等待其结果而不是再次刷新
+    inflight: Arc<DashMap<String, InflightRefresh>>,
+    /// 下一个刷新登记序号
+    next_flight_id: AtomicU64,
+}
+
+/// 进行中的刷新（发起方持有），结束或被取消时移除登记
+///
+/// 发起方被取消时通道随之关闭，等待者重新发起刷新。
+struct RefreshFlight {
+    inflight: Arc<DashMap<String, InflightRefresh>>,
+    uuid: String,
+    id: u64,
+    tx: watch::Sender<Option<Result<String, String>>>,
+}
+
+impl RefreshFlight {
+    /// 通知等待者刷新结果
+    fn finish(self, result: &Result<String, String>) {
+        let _ = self.tx.send(Some(result.clone()));
+    }
+}
+
+impl Drop for RefreshFlight {
+    fn drop(&mut self) {
+        self.inflight
+            .remove_if(&self.uuid, |_, flight| flight.id == self.id);
+    }
 }
 
 impl Default for TokenCacheService {
@@ -70,9 +113,37 @@ impl TokenCacheService {
     pub fn new() -> Self {
         Self {
             locks: DashMap::new(),
+            inflight: Arc::new(DashMap::new()),
+            next_flight_id: AtomicU64::new(0),
         }
     }
 
+    /// 发起刷新：没有进行中的刷新时返回 Ok，否则返回进行中刷新的结果通道
+    ///
+    /// 非强制刷新可能直接使用缓存中的 Token，强制刷新不加入非强制的刷新，而是另行发起
+    /// （实际刷新由每凭证的锁串行执行），之后到达的请求加入强制刷新。
+    fn begin_refresh(&self, uuid: &str, force: bool) -> Result<RefreshFlight, RefreshResult> {
+        let entry = match self.inflight.entry(uuid.to_string()) {
+            Entry::Occupied(entry) if entry.get().force || !force => {
+                return Err(entry.get().result.clone());
+            }
+            entry => entry,
+        };
+        let (tx, rx) = watch::channel(None);
+        let id = self.next_flight_id.fetch_add(1, Ordering::Relaxed);
+        entry.insert(InflightRefresh {
+            id,
+            force,
+            result: rx,
+        });
+        Ok(RefreshFlight {
+            inflight: self.inflight.clone(),
+            uuid: uuid.to_string(),
+            id,
+            tx,
+        })
+    }
+
     /// 获取有效的 Token（核心方法）
     ///
     /// 1. 检查数据库缓存是否有效
@@ -221,12 +292,46 @@ impl TokenCacheService {
     /// - kiro_event_service: 可选的事件服务，用于发送 Kiro 凭证刷新事件
     ///
     /// 优化说明：添加了随机延迟机制，避免多个凭证同时刷新造成请求过于集中
+    ///
+    /// 同一凭证同时只执行一次刷新，刷新期间到达的请求等待并共享其结果；强制刷新不共享非强制刷新的结果。
     pub async fn refresh_and_cache_with_events(
         &self,
         db: &DbConnection,
         uuid: &str,
         force: bool,
         kiro_event_service: Option<Arc<KiroEventService>>,
+    ) -> Result<String, String> {
+        loop {
+            match self.begin_refresh(uuid, force) {
+                Ok(flight) => {
+                    let result = self
+                        .refresh_and_cache_inner(db, uuid, force, kiro_event_service)
+                        .await;
+                    flight.finish(&result);
+                    return result;
+                }
+                Err(mut rx) => {
+                    tracing::debug!(
+                        "[TOKEN_CACHE] Waiting for in-flight refresh for {}",
+                        &uuid[..8]
+                    );
+                    if let Ok(result) = rx.wait_for(|result| result.is_some()).await {
+                        if let Some(result) = result.clone() {
+                            return result;
+                        }
+                    }
+                    // 发起方被取消，重新发起刷新
+                }
+            }
+        }
+    }
+
+    async fn refresh_and_cache_inner(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        force: bool,
+        kiro_event_service: Option<Arc<KiroEventService>>,
     ) -> Result<String, String> {
         // 添加随机延迟，避免多个凭证同时刷新
         // 基于凭证UUID生成0-30秒的随机延迟，确保同一凭证的延迟时间一致但不同凭证间分散
@@ -302,6 +407,16 @@ impl TokenCacheService {
                     let conn = db.lock().map_err(|e| e.to_string())?;
                     ProviderPoolDao::update_token_cache(&conn, uuid, &token_info)
                         .map_err(|e| e.to_string())?;
+                    // 过期跟踪信息仅用于预警，记录失败不影响刷新结果
+                    let refresh_token_expiry = get_oauth_creds_path(&credential.credential)
+                        .and_then(|path| {
+                            credential_expiry_service::refresh_token_expiry_from_file(&path)
+                        });
+                    if let Err(e) =
+                        ProviderPoolDao::record_refresh_success(&conn, uuid, refresh_token_expiry)
+                    {
+                        tracing::debug!("[TOKEN_CACHE] 记录刷新时间失败: {}", e);
+                    }
                 }
 
                 let token = token_info
@@ -438,7 +553,10 @@ impl TokenCacheService {
             CredentialData::AntigravityOAuth {
                 creds_file_path, ..
             } => self.refresh_antigravity(creds_file_path).await,
-            CredentialData::OpenAIKey { api_key, .. } => {
+            CredentialData::OpenAIKey { api_key, .. }
+            | CredentialData::OpenRouterKey { api_key, .. }
+            | CredentialData::MistralKey { api_key, .. }
+            | CredentialData::DeepSeekKey { api_key, .. } => {
                 // API Key 不需要刷新，直接返回
                 Ok(CachedTokenInfo {
                     access_token: Some(api_key.clone()),
@@ -526,7 +644,7 @@ impl TokenCacheService {
 
         Ok(CachedTokenInfo {
             access_token: Some(token),
-            refresh_token: provider.credentials.refresh_token.clone(),
+            refresh_token: provider.credentials().refresh_token,
             expiry_time: Some(expiry_time),
             last_refresh: Some(Utc::now()),
             refresh_error_count: 0,
@@ -943,7 +1061,10 @@ impl TokenCacheService {
                     last_refresh_error: None,
                 })
             }
-            CredentialData::OpenAIKey { api_key, .. } => Ok(CachedTokenInfo {
+            CredentialData::OpenAIKey { api_key, .. }
+            | CredentialData::OpenRouterKey { api_key, .. }
+            | CredentialData::MistralKey { api_key, .. }
+            | CredentialData::DeepSeekKey { api_key, .. } => Ok(CachedTokenInfo {
                 access_token: Some(api_key.clone()),
                 refresh_token: None,
                 expiry_time: None,
@@ -1287,4 +1408,96 @@ impl TokenCacheService {
         // 需要刷新（无缓存、已过期或即将过期）
         self.refresh_and_cache(db, uuid, false).await
     }
+
+    /// Token 在指定时间内过期时刷新（供后台主动刷新使用）
+    ///
+    /// 先在凭证锁内复查缓存，其他请求已完成刷新时跳过。
+    /// 返回是否执行了刷新。
+    pub async fn refresh_if_expiring_within(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        within: chrono::Duration,
+    ) -> Result<bool, String> {
+        let lock = self
+            .locks
+            .entry(uuid.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+        {
+            let _guard = lock.lock().await;
+            let cached = {
+                let conn = db.lock().map_err(|e| e.to_string())?;
+                ProviderPoolDao::get_token_cache(&conn, uuid).map_err(|e| e.to_string())?
+            };
+            let expiring = cached
+                .as_ref()
+                .and_then(|c| c.expiry_time)
+                .is_some_and(|expiry| expiry <= Utc::now() + within);
+            if !expiring {
+                return Ok(false);
+            }
+        }
+
+        self.refresh_and_cache(db, uuid, true).await.map(|_| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrent_refresh_shares_result() {
+        let service = TokenCacheService::new();
+        let uuid = "00000000-0000-0000-0000-000000000000";
+
+        let flight = service.begin_refresh(uuid, false).ok().unwrap();
+        let mut waiter = service.begin_refresh(uuid, false).err().unwrap();
+        assert!(waiter.borrow().is_none());
+
+        flight.finish(&Ok("token".to_string()));
+        let result = waiter.wait_for(|result| result.is_some()).await.unwrap();
+        assert_eq!(result.clone(), Some(Ok("token".to_string())));
+        drop(result);
+
+        // 刷新结束后可以再次发起
+        assert!(service.inflight.is_empty());
+        assert!(service.begin_refresh(uuid, false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_refresh_releases_waiters() {
+        let service = TokenCacheService::new();
+        let uuid = "00000000-0000-0000-0000-000000000000";
+
+        let flight = service.begin_refresh(uuid, false).ok().unwrap();
+        let mut waiter = service.begin_refresh(uuid, false).err().unwrap();
+        drop(flight);
+
+        assert!(waiter.wait_for(|result| result.is_some()).await.is_err());
+        assert!(service.begin_refresh(uuid, false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_forced_refresh_does_not_join_non_forced() {
+        let service = TokenCacheService::new();
+        let uuid = "00000000-0000-0000-0000-000000000000";
+
+        let flight = service.begin_refresh(uuid, false).ok().unwrap();
+        // 强制刷新另行发起，之后的请求（无论是否强制）加入强制刷新
+        let forced = service.begin_refresh(uuid, true).ok().unwrap();
+        let mut waiter = service.begin_refresh(uuid, true).err().unwrap();
+        assert!(service.begin_refresh(uuid, false).is_err());
+
+        // 非强制刷新结束不移除强制刷新的登记
+        flight.finish(&Ok("cached".to_string()));
+        assert!(service.begin_refresh(uuid, true).is_err());
+
+        forced.finish(&Ok("fresh".to_string()));
+        let result = waiter.wait_for(|result| result.is_some()).await.unwrap();
+        assert_eq!(result.clone(), Some(Ok("fresh".to_string())));
+        drop(result);
+        assert!(service.inflight.is_empty());
+    }
 }
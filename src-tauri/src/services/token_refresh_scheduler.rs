@@ -0,0 +1,234 @@
+//! OAuth Token 主动刷新调度
+//!
+//! 定期扫描凭证池中支持刷新的 OAuth 凭证，在 Token 过期前（提前量 + 按凭证分散的抖动）主动刷新，
+//! 请求不再需要等待刷新。每个 Provider 每轮最多刷新 `max_per_provider` 个凭证，
+//! 同一 Provider 内串行执行，避免集中请求上游。
+
+use crate::config::TokenRefreshConfig;
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::PoolProviderType;
+use crate::services::token_cache_service::TokenCacheService;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 连续刷新失败达到该次数的凭证不再主动刷新（等待用户处理或请求时按需刷新）
+const MAX_REFRESH_ERRORS: u32 = 3;
+
+/// 待检查的凭证
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshCandidate {
+    pub uuid: String,
+    pub provider_type: PoolProviderType,
+    pub expiry_time: DateTime<Utc>,
+}
+
+/// 按凭证计算的抖动（同一凭证每次相同，不同凭证分散）
+pub fn jitter_for(uuid: &str, max_secs: u64) -> Duration {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    if max_secs == 0 {
+        return Duration::zero();
+    }
+    let mut hasher = DefaultHasher::new();
+    uuid.hash(&mut hasher);
+    Duration::seconds((hasher.finish() % (max_secs + 1)) as i64)
+}
+
+/// 提前刷新的时间窗口：提前量 + 抖动
+pub fn refresh_window(uuid: &str, config: &TokenRefreshConfig) -> Duration {
+    Duration::minutes(config.lead_minutes.max(0)) + jitter_for(uuid, config.jitter_secs)
+}
+
+/// 选出本轮需要刷新的凭证
+///
+/// 按 Provider 分组，组内按过期时间升序，最多保留 `max_per_provider` 个。
+pub fn plan_refreshes(
+    candidates: Vec<RefreshCandidate>,
+    now: DateTime<Utc>,
+    config: &TokenRefreshConfig,
+) -> HashMap<PoolProviderType, Vec<RefreshCandidate>> {
+    let mut plan: HashMap<PoolProviderType, Vec<RefreshCandidate>> = HashMap::new();
+    for candidate in candidates {
+        if candidate.expiry_time <= now + refresh_window(&candidate.uuid, config) {
+            plan.entry(candidate.provider_type)
+                .or_default()
+                .push(candidate);
+        }
+    }
+    for group in plan.values_mut() {
+        group.sort_by_key(|c| c.expiry_time);
+        group.truncate(config.max_per_provider.max(1));
+    }
+    plan
+}
+
+/// 读取支持刷新且有过期时间的凭证
+fn load_candidates(db: &DbConnection) -> Result<Vec<RefreshCandidate>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+
+    let mut candidates = Vec::new();
+    for cred in credentials {
+        if cred.is_disabled || !TokenCacheService::supports_refresh(cred.provider_type) {
+            continue;
+        }
+        let Some(cache) =
+            ProviderPoolDao::get_token_cache(&conn, &cred.uuid).map_err(|e| e.to_string())?
+        else {
+            continue;
+        };
+        if cache.refresh_error_count >= MAX_REFRESH_ERRORS {
+            continue;
+        }
+        if let Some(expiry_time) = cache.expiry_time {
+            candidates.push(RefreshCandidate {
+                uuid: cred.uuid,
+                provider_type: cred.provider_type,
+                expiry_time,
+            });
+        }
+    }
+    Ok(candidates)
+}
+
+/// 执行一轮主动刷新，返回成功刷新的凭证数
+pub async fn refresh_due_tokens(
+    token_cache: &TokenCacheService,
+    db: &DbConnection,
+    config: &TokenRefreshConfig,
+) -> usize {
+    let candidates = match load_candidates(db) {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            tracing::warn!("[TOKEN_REFRESH] 读取凭证失败: {}", e);
+            return 0;
+        }
+    };
+
+    let plan = plan_refreshes(candidates, Utc::now(), config);
+    let groups = plan.into_values().map(|group| async move {
+        let mut refreshed = 0;
+        for candidate in group {
+            let window = refresh_window(&candidate.uuid, config);
+            match token_cache
+                .refresh_if_expiring_within(db, &candidate.uuid, window)
+                .await
+            {
+                Ok(true) => {
+                    tracing::info!(
+                        "[TOKEN_REFRESH] 已提前刷新 {} 凭证 {}（原过期时间 {}）",
+                        candidate.provider_type,
+                        &candidate.uuid[..8.min(candidate.uuid.len())],
+                        candidate.expiry_time
+                    );
+                    refreshed += 1;
+                }
+                Ok(false) => {}
+                // 失败次数由 refresh_and_cache 记录，达到上限后不再主动刷新
+                Err(e) => tracing::warn!(
+                    "[TOKEN_REFRESH] 凭证 {} 提前刷新失败: {}",
+                    &candidate.uuid[..8.min(candidate.uuid.len())],
+                    e
+                ),
+            }
+        }
+        refreshed
+    });
+
+    futures::future::join_all(groups).await.into_iter().sum()
+}
+
+/// 启动后台主动刷新任务（每次循环重新读取配置，支持热重载；集群模式下仅 leader 执行）
+pub fn spawn_token_refresh_scheduler<F>(
+    token_cache: Arc<TokenCacheService>,
+    db: DbConnection,
+    config: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> TokenRefreshConfig + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let current = config();
+            if current.enabled && crate::services::cluster_service::is_leader() {
+                refresh_due_tokens(&token_cache, &db, &current).await;
+            }
+            let interval = current.interval_secs.max(10);
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(uuid: &str, provider_type: PoolProviderType, minutes: i64) -> RefreshCandidate {
+        RefreshCandidate {
+            uuid: uuid.to_string(),
+            provider_type,
+            expiry_time: Utc::now() + Duration::minutes(minutes),
+        }
+    }
+
+    fn config() -> TokenRefreshConfig {
+        TokenRefreshConfig {
+            jitter_secs: 0,
+            ..TokenRefreshConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_jitter_is_stable_and_bounded() {
+        let a = jitter_for("credential-a", 120);
+        assert_eq!(a, jitter_for("credential-a", 120));
+        assert!(a >= Duration::zero() && a <= Duration::seconds(120));
+        assert_eq!(jitter_for("credential-a", 0), Duration::zero());
+    }
+
+    #[test]
+    fn test_plan_selects_only_expiring_tokens() {
+        let plan = plan_refreshes(
+            vec![
+                candidate("soon", PoolProviderType::Kiro, 5),
+                candidate("later", PoolProviderType::Kiro, 60),
+                candidate("expired", PoolProviderType::Qwen, -1),
+            ],
+            Utc::now(),
+            &config(),
+        );
+        let kiro: Vec<_> = plan[&PoolProviderType::Kiro]
+            .iter()
+            .map(|c| c.uuid.as_str())
+            .collect();
+        assert_eq!(kiro, vec!["soon"]);
+        assert_eq!(plan[&PoolProviderType::Qwen].len(), 1);
+    }
+
+    #[test]
+    fn test_plan_limits_per_provider() {
+        let config = TokenRefreshConfig {
+            max_per_provider: 2,
+            ..config()
+        };
+        let plan = plan_refreshes(
+            vec![
+                candidate("c", PoolProviderType::Gemini, 3),
+                candidate("a", PoolProviderType::Gemini, 1),
+                candidate("b", PoolProviderType::Gemini, 2),
+                candidate("k", PoolProviderType::Kiro, 1),
+            ],
+            Utc::now(),
+            &config,
+        );
+        let gemini: Vec<_> = plan[&PoolProviderType::Gemini]
+            .iter()
+            .map(|c| c.uuid.as_str())
+            .collect();
+        assert_eq!(gemini, vec!["a", "b"]);
+        assert_eq!(plan[&PoolProviderType::Kiro].len(), 1);
+    }
+}
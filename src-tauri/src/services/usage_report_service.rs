@@ -0,0 +1,418 @@
+//! 每日用量报告
+//!
+//! 基于按天汇总的遥测数据（`telemetry_rollups`）生成每日用量报告：
+//! - 请求数、成功 / 失败数、Token 用量
+//! - 按模型注册表定价估算的费用（按货币分别汇总）
+//! - 用量最高的模型
+//!
+//! 报告以 Markdown 和 JSON 两种格式保存到 `~/.proxycast/reports/`，
+//! 并可推送到配置的 Webhook（通用 JSON / Slack / Discord）。
+//! 没有内置 SMTP 客户端，邮件推送需通过 Webhook 转发服务实现。
+
+use crate::config::{AlertWebhookConfig, AlertWebhookFormat, ReportsConfig};
+use crate::database::dao::telemetry::{RollupGranularity, RollupQuery, TelemetryDao};
+use crate::database::DbConnection;
+use crate::models::model_registry::ModelPricing;
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// 单个模型的用量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    pub requests: u64,
+    pub failed: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 估算费用（模型无定价时为 None）
+    pub estimated_cost: Option<f64>,
+    pub currency: Option<String>,
+}
+
+impl ModelUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// 每日用量报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyUsageReport {
+    /// 报告日期（UTC）
+    pub date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub total_requests: u64,
+    pub success_count: u64,
+    pub failed_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 估算费用（货币 -> 金额）
+    pub estimated_cost: BTreeMap<String, f64>,
+    /// 用量最高的模型（按 Token 总量降序）
+    pub top_models: Vec<ModelUsage>,
+}
+
+impl DailyUsageReport {
+    /// 渲染为 Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# ProxyCast 用量报告 {}\n\n", self.date);
+        md.push_str("| 指标 | 数值 |\n|---|---|\n");
+        md.push_str(&format!("| 请求数 | {} |\n", self.total_requests));
+        md.push_str(&format!("| 成功 | {} |\n", self.success_count));
+        md.push_str(&format!("| 失败 | {} |\n", self.failed_count));
+        md.push_str(&format!("| 输入 Token | {} |\n", self.input_tokens));
+        md.push_str(&format!("| 输出 Token | {} |\n", self.output_tokens));
+        for (currency, cost) in &self.estimated_cost {
+            md.push_str(&format!("| 估算费用 ({}) | {:.4} |\n", currency, cost));
+        }
+
+        if !self.top_models.is_empty() {
+            md.push_str("\n## 模型用量\n\n");
+            md.push_str("| Provider | 模型 | 请求数 | 输入 Token | 输出 Token | 估算费用 |\n");
+            md.push_str("|---|---|---|---|---|---|\n");
+            for m in &self.top_models {
+                let cost = match (m.estimated_cost, &m.currency) {
+                    (Some(cost), Some(currency)) => format!("{:.4} {}", cost, currency),
+                    _ => "-".to_string(),
+                };
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} |\n",
+                    m.provider, m.model, m.requests, m.input_tokens, m.output_tokens, cost
+                ));
+            }
+        }
+
+        md.push_str(&format!(
+            "\n_生成时间: {}_\n",
+            self.generated_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+        md
+    }
+}
+
+/// 从模型注册表加载定价（模型 ID 小写 -> 定价）
+pub fn load_model_pricing(conn: &Connection) -> HashMap<String, ModelPricing> {
+    let mut pricing = HashMap::new();
+    let Ok(mut stmt) =
+        conn.prepare("SELECT id, pricing FROM model_registry WHERE pricing IS NOT NULL")
+    else {
+        return pricing;
+    };
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    });
+    if let Ok(rows) = rows {
+        for (id, json) in rows.flatten() {
+            if let Ok(p) = serde_json::from_str::<ModelPricing>(&json) {
+                pricing.insert(id.to_lowercase(), p);
+            }
+        }
+    }
+    pricing
+}
+
+/// 查找模型定价：精确匹配优先，否则取最长的前缀匹配（兼容带日期后缀的模型名）
+fn find_pricing<'a>(
+    pricing: &'a HashMap<String, ModelPricing>,
+    model: &str,
+) -> Option<&'a ModelPricing> {
+    let model = model.to_lowercase();
+    pricing.get(&model).or_else(|| {
+        pricing
+            .iter()
+            .filter(|(id, _)| model.starts_with(id.as_str()))
+            .max_by_key(|(id, _)| id.len())
+            .map(|(_, p)| p)
+    })
+}
+
+fn estimate_cost(pricing: &ModelPricing, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+    if pricing.input_per_million.is_none() && pricing.output_per_million.is_none() {
+        return None;
+    }
+    Some(
+        input_tokens as f64 / 1_000_000.0 * pricing.input_per_million.unwrap_or(0.0)
+            + output_tokens as f64 / 1_000_000.0 * pricing.output_per_million.unwrap_or(0.0),
+    )
+}
+
+/// 生成指定日期（UTC）的用量报告
+pub fn generate_daily_report(
+    conn: &Connection,
+    date: NaiveDate,
+    top_models: usize,
+) -> Result<DailyUsageReport, String> {
+    let start = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or_else(|| format!("Invalid date: {}", date))?
+        .and_utc()
+        .timestamp();
+    let rollups = TelemetryDao::query_rollups(
+        conn,
+        RollupGranularity::Day,
+        &RollupQuery {
+            start: Some(start),
+            end: Some(start + 86400),
+            provider: None,
+            model: None,
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    let pricing = load_model_pricing(conn);
+
+    let mut report = DailyUsageReport {
+        date,
+        generated_at: Utc::now(),
+        total_requests: 0,
+        success_count: 0,
+        failed_count: 0,
+        input_tokens: 0,
+        output_tokens: 0,
+        estimated_cost: BTreeMap::new(),
+        top_models: Vec::new(),
+    };
+
+    let mut models = Vec::with_capacity(rollups.len());
+    for r in rollups {
+        report.total_requests += r.request_count;
+        report.success_count += r.success_count;
+        report.failed_count += r.failed_count + r.timeout_count;
+        report.input_tokens += r.input_tokens;
+        report.output_tokens += r.output_tokens;
+
+        let model_pricing = find_pricing(&pricing, &r.model);
+        let cost = model_pricing.and_then(|p| estimate_cost(p, r.input_tokens, r.output_tokens));
+        let currency = cost.and(model_pricing).map(|p| p.currency.clone());
+        if let (Some(cost), Some(currency)) = (cost, &currency) {
+            *report.estimated_cost.entry(currency.clone()).or_default() += cost;
+        }
+
+        models.push(ModelUsage {
+            provider: r.provider,
+            model: r.model,
+            requests: r.request_count,
+            failed: r.failed_count + r.timeout_count,
+            input_tokens: r.input_tokens,
+            output_tokens: r.output_tokens,
+            estimated_cost: cost,
+            currency,
+        });
+    }
+
+    models.sort_by(|a, b| {
+        b.total_tokens()
+            .cmp(&a.total_tokens())
+            .then(b.requests.cmp(&a.requests))
+    });
+    models.truncate(top_models);
+    report.top_models = models;
+    Ok(report)
+}
+
+/// 报告目录 `~/.proxycast/reports/`
+pub fn reports_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取主目录".to_string())?;
+    Ok(home.join(".proxycast").join("reports"))
+}
+
+fn report_path(dir: &Path, date: NaiveDate, ext: &str) -> PathBuf {
+    dir.join(format!("usage-{}.{}", date, ext))
+}
+
+/// 保存报告（Markdown + JSON），返回两个文件路径
+pub fn save_report(report: &DailyUsageReport, dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("无法创建报告目录 {:?}: {}", dir, e))?;
+    let md_path = report_path(dir, report.date, "md");
+    let json_path = report_path(dir, report.date, "json");
+    std::fs::write(&md_path, report.to_markdown()).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    std::fs::write(&json_path, json).map_err(|e| e.to_string())?;
+    Ok((md_path, json_path))
+}
+
+/// 按 Webhook 格式构建报告推送请求体
+pub fn report_webhook_payload(
+    format: AlertWebhookFormat,
+    report: &DailyUsageReport,
+) -> serde_json::Value {
+    match format {
+        AlertWebhookFormat::Generic => serde_json::json!({
+            "source": "proxycast",
+            "report": report,
+        }),
+        AlertWebhookFormat::Slack => serde_json::json!({ "text": report.to_markdown() }),
+        AlertWebhookFormat::Discord => {
+            // Discord 消息内容上限 2000 字符
+            let content: String = report.to_markdown().chars().take(2000).collect();
+            serde_json::json!({ "content": content })
+        }
+    }
+}
+
+async fn push_report(webhooks: &[AlertWebhookConfig], report: &DailyUsageReport) {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+    for webhook in webhooks {
+        let result = client
+            .post(&webhook.url)
+            .json(&report_webhook_payload(webhook.format, report))
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status());
+        if let Err(e) = result {
+            tracing::warn!("[REPORT] Webhook 推送失败 ({}): {}", webhook.url, e);
+        }
+    }
+}
+
+/// 启动每日报告任务
+///
+/// 每 10 分钟检查一次：到达配置的生成时间且前一天的报告尚未生成时，生成、保存并推送。
+/// `config` 每轮调用一次，以便热重载后的配置立即生效。集群模式下仅 leader 执行。
+pub fn spawn_daily_reporter<F>(db: DbConnection, config: F) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> ReportsConfig + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let current = config();
+            let now = Utc::now();
+            if current.enabled
+                && now.hour() >= current.hour_utc
+                && crate::services::cluster_service::is_leader()
+            {
+                let date = (now - Duration::days(1)).date_naive();
+                match reports_dir() {
+                    Ok(dir) if !report_path(&dir, date, "json").exists() => {
+                        let report = match db.lock() {
+                            Ok(conn) => generate_daily_report(&conn, date, current.top_models),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        match report.and_then(|r| save_report(&r, &dir).map(|paths| (r, paths))) {
+                            Ok((report, (md_path, _))) => {
+                                tracing::info!("[REPORT] 已生成用量报告: {:?}", md_path);
+                                push_report(&current.webhooks, &report).await;
+                            }
+                            Err(e) => tracing::warn!("[REPORT] 生成用量报告失败: {}", e),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("[REPORT] {}", e),
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(600)).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{RequestLog, RequestStatus, TokenSource, TokenUsageRecord};
+    use crate::ProviderType;
+    use chrono::TimeZone;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema_migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn record(conn: &Connection, id: &str, model: &str, status: RequestStatus, tokens: (u32, u32)) {
+        let ts = Utc.with_ymd_and_hms(2026, 1, 2, 10, 0, 0).unwrap();
+        let mut log = RequestLog::new(
+            id.to_string(),
+            ProviderType::Claude,
+            model.to_string(),
+            false,
+        );
+        log.timestamp = ts;
+        log.status = status;
+        TelemetryDao::insert_request_log(conn, &log).unwrap();
+
+        let mut usage = TokenUsageRecord::new(
+            format!("usage-{}", id),
+            ProviderType::Claude,
+            model.to_string(),
+            tokens.0,
+            tokens.1,
+            TokenSource::Actual,
+        );
+        usage.timestamp = ts;
+        TelemetryDao::insert_token_usage(conn, &usage).unwrap();
+    }
+
+    #[test]
+    fn test_generate_daily_report() {
+        let conn = setup();
+        conn.execute(
+            "INSERT INTO model_registry (id, display_name, provider_id, provider_name, pricing, created_at, updated_at)
+             VALUES ('claude-sonnet-4-5', 'Claude Sonnet 4.5', 'anthropic', 'Anthropic', ?1, 0, 0)",
+            [r#"{"input_per_million":3.0,"output_per_million":15.0,"cache_read_per_million":null,"cache_write_per_million":null,"currency":"USD"}"#],
+        )
+        .unwrap();
+
+        record(
+            &conn,
+            "a",
+            "claude-sonnet-4-5-20250929",
+            RequestStatus::Success,
+            (1_000_000, 100_000),
+        );
+        record(
+            &conn,
+            "b",
+            "claude-sonnet-4-5-20250929",
+            RequestStatus::Failed,
+            (0, 0),
+        );
+        record(
+            &conn,
+            "c",
+            "unknown-model",
+            RequestStatus::Success,
+            (10, 10),
+        );
+
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        let report = generate_daily_report(&conn, date, 10).unwrap();
+        assert_eq!(report.total_requests, 3);
+        assert_eq!(report.success_count, 2);
+        assert_eq!(report.failed_count, 1);
+        assert_eq!(report.input_tokens, 1_000_010);
+        assert_eq!(report.top_models[0].model, "claude-sonnet-4-5-20250929");
+        assert!((report.estimated_cost["USD"] - 4.5).abs() < 1e-9);
+        assert_eq!(report.top_models[1].estimated_cost, None);
+
+        let md = report.to_markdown();
+        assert!(md.contains("# ProxyCast 用量报告 2026-01-02"));
+        assert!(md.contains("| 估算费用 (USD) | 4.5000 |"));
+
+        let top1 = generate_daily_report(&conn, date, 1).unwrap();
+        assert_eq!(top1.top_models.len(), 1);
+        assert_eq!(top1.total_requests, 3);
+
+        let empty = generate_daily_report(&conn, date.succ_opt().unwrap(), 10).unwrap();
+        assert_eq!(empty.total_requests, 0);
+    }
+
+    #[test]
+    fn test_save_report() {
+        let conn = setup();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        let report = generate_daily_report(&conn, date, 10).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let (md_path, json_path) = save_report(&report, dir.path()).unwrap();
+        assert!(md_path.ends_with("usage-2026-01-02.md"));
+        let saved: DailyUsageReport =
+            serde_json::from_str(&std::fs::read_to_string(json_path).unwrap()).unwrap();
+        assert_eq!(saved, report);
+    }
+}
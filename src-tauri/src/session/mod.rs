@@ -2,7 +2,7 @@
 //!
 //! 提供以下功能：
 //! - 稳定的 SessionId 生成（基于请求内容哈希）
-//! - thoughtSignature 全局缓存
+//! - thoughtSignature 缓存（按工具调用 ID，支持持久化和淘汰）
 //! - 会话粘性管理（会话与账号映射）
 //! - 调度模式配置
 //! - 增强的限流处理（Duration 解析、指数退避）
@@ -18,8 +18,9 @@ pub use rate_limit::{
 };
 pub use session_manager::SessionManager;
 pub use signature_store::{
-    clear_thought_signature, get_thought_signature, has_valid_signature, store_thought_signature,
-    take_thought_signature,
+    clear_thought_signature, configure_signature_store, get_thought_signature,
+    get_tool_call_signature, has_valid_signature, signature_store_stats, store_thought_signature,
+    store_tool_call_signature, take_thought_signature, SignatureStoreStats,
 };
 pub use sticky_config::{SchedulingMode, StickySessionConfig};
 pub use sticky_manager::{AccountInfo, StickySessionManager};
@@ -158,6 +158,13 @@ impl RateLimitTracker {
         self.get_remaining_wait(account_id) > 0
     }
 
+    /// 获取账号当前的限流记录（已过期的记录也返回，用于参考连续失败次数）
+    pub fn get_rate_limit(&self, account_id: &str) -> Option<RateLimitRecord> {
+        self.account_limits
+            .get(account_id)
+            .map(|record| record.value().clone())
+    }
+
     /// 检查特定模型是否被限流
     pub fn is_model_rate_limited(&self, account_id: &str, model: &str) -> bool {
         let key = format!("{}:{}", account_id, model);
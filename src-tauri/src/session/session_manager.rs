@@ -173,9 +173,18 @@ mod tests {
             max_tokens: None,
             top_p: None,
             stream: false,
+            stream_options: None,
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let sid1 = SessionManager::extract_session_id(&request);
@@ -204,9 +213,18 @@ mod tests {
             max_tokens: None,
             top_p: None,
             stream: false,
+            stream_options: None,
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let request2 = ChatCompletionRequest {
@@ -223,9 +241,18 @@ mod tests {
             max_tokens: None,
             top_p: None,
             stream: false,
+            stream_options: None,
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let sid1 = SessionManager::extract_session_id(&request1);
@@ -2,14 +2,205 @@
 //!
 //! 用于在流式响应中捕获 thoughtSignature，并在后续请求中注入。
 //! 这对于 Gemini 3 Pro 的 Tool Use 功能至关重要。
+//!
+//! 签名按工具调用 ID 缓存，另保留一个"最近签名"供无法按 ID 对应的请求使用。
+//! 超过有效期或条目上限时淘汰最旧的签名；启用持久化后写入数据库，重启后恢复。
 
+use crate::config::SignatureStoreConfig;
+use crate::database::dao::thought_signature::{StoredSignature, ThoughtSignatureDao};
+use crate::database::DbConnection;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 
 /// 最小有效签名长度
 const MIN_SIGNATURE_LENGTH: usize = 50;
 
+/// "最近签名"的缓存键（工具调用 ID 不会为空）
+const LATEST_KEY: &str = "";
+
 /// 全局 thoughtSignature 存储
-static THOUGHT_SIGNATURE: RwLock<Option<String>> = RwLock::new(None);
+static STORE: Lazy<RwLock<SignatureStore>> =
+    Lazy::new(|| RwLock::new(SignatureStore::new(SignatureStoreConfig::default())));
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static EVICTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// 签名缓存统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureStoreStats {
+    /// 当前缓存条目数
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// 因过期或超出上限被淘汰的条目数
+    pub evictions: u64,
+    /// 命中率（无查询时为 0）
+    pub hit_rate: f64,
+    /// 是否持久化到数据库
+    pub persisted: bool,
+}
+
+struct SignatureEntry {
+    signature: String,
+    /// 写入时间（秒，UTC）
+    stored_at: i64,
+}
+
+struct SignatureStore {
+    entries: HashMap<String, SignatureEntry>,
+    config: SignatureStoreConfig,
+    db: Option<DbConnection>,
+}
+
+impl SignatureStore {
+    fn new(config: SignatureStoreConfig) -> Self {
+        Self {
+            entries: HashMap::new(),
+            config,
+            db: None,
+        }
+    }
+
+    fn is_expired(&self, entry: &SignatureEntry, now: i64) -> bool {
+        now - entry.stored_at >= self.config.ttl_secs as i64
+    }
+
+    fn get(&self, key: &str, now: i64) -> Option<&str> {
+        self.entries
+            .get(key)
+            .filter(|entry| !self.is_expired(entry, now))
+            .map(|entry| entry.signature.as_str())
+    }
+
+    /// 写入签名，返回被淘汰的键
+    fn insert(&mut self, key: &str, signature: &str, now: i64) -> Vec<String> {
+        self.entries.insert(
+            key.to_string(),
+            SignatureEntry {
+                signature: signature.to_string(),
+                stored_at: now,
+            },
+        );
+        self.evict(now)
+    }
+
+    /// 超出条目上限时先淘汰过期签名，再按写入时间淘汰最旧的签名
+    fn evict(&mut self, now: i64) -> Vec<String> {
+        let max_entries = self.config.max_entries.max(1);
+        if self.entries.len() <= max_entries {
+            return Vec::new();
+        }
+
+        let mut evicted: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry, now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &evicted {
+            self.entries.remove(key);
+        }
+
+        if self.entries.len() > max_entries {
+            let mut by_age: Vec<(i64, String)> = self
+                .entries
+                .iter()
+                .map(|(key, entry)| (entry.stored_at, key.clone()))
+                .collect();
+            by_age.sort();
+            for (_, key) in by_age.into_iter().take(self.entries.len() - max_entries) {
+                self.entries.remove(&key);
+                evicted.push(key);
+            }
+        }
+        evicted
+    }
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// 同步写入/删除数据库中的签名（在释放缓存锁后调用）
+fn persist(db: Option<DbConnection>, stored: Option<StoredSignature>, removed: &[String]) {
+    let Some(db) = db else {
+        return;
+    };
+    let Ok(conn) = db.lock() else {
+        return;
+    };
+    let result = stored
+        .map_or(Ok(()), |record| ThoughtSignatureDao::upsert(&conn, &record))
+        .and_then(|_| ThoughtSignatureDao::delete(&conn, removed));
+    if let Err(e) = result {
+        tracing::warn!("[SignatureStore] Failed to persist signatures: {}", e);
+    }
+}
+
+fn record_lookup(found: bool) {
+    if found {
+        HITS.fetch_add(1, Ordering::Relaxed);
+    } else {
+        MISSES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn store_entry(key: &str, sig: &str) {
+    let now = now_secs();
+    let (evicted, db) = {
+        let mut store = STORE.write().unwrap();
+        (store.insert(key, sig, now), store.db.clone())
+    };
+    EVICTIONS.fetch_add(evicted.len() as u64, Ordering::Relaxed);
+    let stored = StoredSignature {
+        key: key.to_string(),
+        signature: sig.to_string(),
+        stored_at: now,
+    };
+    persist(db, Some(stored), &evicted);
+}
+
+/// 配置签名缓存
+///
+/// 启用持久化时清理数据库中过期/超限的签名并加载其余签名，之后的写入同步到数据库。
+pub fn configure_signature_store(config: SignatureStoreConfig, db: Option<DbConnection>) {
+    let db = db.filter(|_| config.persist);
+    let now = now_secs();
+
+    let loaded = match &db {
+        Some(db) => db.lock().ok().and_then(|conn| {
+            let expired_before = now - config.ttl_secs as i64;
+            ThoughtSignatureDao::prune(&conn, expired_before, config.max_entries.max(1))
+                .and_then(|_| ThoughtSignatureDao::load_all(&conn))
+                .map_err(|e| tracing::warn!("[SignatureStore] Failed to load signatures: {}", e))
+                .ok()
+        }),
+        None => None,
+    };
+
+    let mut store = STORE.write().unwrap();
+    store.config = config;
+    store.db = db;
+    let loaded_count = loaded.as_ref().map_or(0, |records| records.len());
+    for record in loaded.into_iter().flatten() {
+        store.entries.entry(record.key).or_insert(SignatureEntry {
+            signature: record.signature,
+            stored_at: record.stored_at,
+        });
+    }
+    let evicted = store.evict(now);
+    EVICTIONS.fetch_add(evicted.len() as u64, Ordering::Relaxed);
+    if loaded_count > 0 {
+        tracing::info!(
+            "[SignatureStore] Restored {} persisted signatures",
+            loaded_count
+        );
+    }
+}
 
 /// 存储 thoughtSignature 到全局存储
 ///
@@ -28,12 +219,13 @@ pub fn store_thought_signature(sig: &str) {
         return;
     }
 
-    let mut store = THOUGHT_SIGNATURE.write().unwrap();
-
     // 只有当新签名更长时才替换
-    let should_replace = match &*store {
-        Some(existing) => sig.len() > existing.len(),
-        None => true,
+    let should_replace = {
+        let store = STORE.read().unwrap();
+        match store.get(LATEST_KEY, now_secs()) {
+            Some(existing) => sig.len() > existing.len(),
+            None => true,
+        }
     };
 
     if should_replace {
@@ -41,8 +233,27 @@ pub fn store_thought_signature(sig: &str) {
             "[SignatureStore] Storing thought_signature (length: {})",
             sig.len()
         );
-        *store = Some(sig.to_string());
+        store_entry(LATEST_KEY, sig);
+    }
+}
+
+/// 按工具调用 ID 存储 thoughtSignature
+pub fn store_tool_call_signature(tool_call_id: &str, sig: &str) {
+    if tool_call_id.is_empty() || sig.len() < MIN_SIGNATURE_LENGTH {
+        return;
     }
+    store_entry(tool_call_id, sig);
+}
+
+/// 获取工具调用对应的 thoughtSignature
+pub fn get_tool_call_signature(tool_call_id: &str) -> Option<String> {
+    if tool_call_id.is_empty() {
+        return None;
+    }
+    let store = STORE.read().unwrap();
+    let sig = store.get(tool_call_id, now_secs()).map(|s| s.to_string());
+    record_lookup(sig.is_some());
+    sig
 }
 
 /// 获取存储的 thoughtSignature（不清除）
@@ -50,8 +261,10 @@ pub fn store_thought_signature(sig: &str) {
 /// # 返回
 /// 存储的签名，如果没有则返回 None
 pub fn get_thought_signature() -> Option<String> {
-    let store = THOUGHT_SIGNATURE.read().unwrap();
-    store.clone()
+    let store = STORE.read().unwrap();
+    let sig = store.get(LATEST_KEY, now_secs()).map(|s| s.to_string());
+    record_lookup(sig.is_some());
+    sig
 }
 
 /// 获取并清除存储的 thoughtSignature
@@ -59,26 +272,57 @@ pub fn get_thought_signature() -> Option<String> {
 /// # 返回
 /// 存储的签名，如果没有则返回 None
 pub fn take_thought_signature() -> Option<String> {
-    let mut store = THOUGHT_SIGNATURE.write().unwrap();
-    store.take()
+    let now = now_secs();
+    let (sig, db) = {
+        let mut store = STORE.write().unwrap();
+        let sig = store.get(LATEST_KEY, now).map(|s| s.to_string());
+        store.entries.remove(LATEST_KEY);
+        (sig, store.db.clone())
+    };
+    persist(db, None, &[LATEST_KEY.to_string()]);
+    sig
 }
 
 /// 清除存储的 thoughtSignature
 pub fn clear_thought_signature() {
-    let mut store = THOUGHT_SIGNATURE.write().unwrap();
-    *store = None;
+    let db = {
+        let mut store = STORE.write().unwrap();
+        store.entries.remove(LATEST_KEY);
+        store.db.clone()
+    };
+    persist(db, None, &[LATEST_KEY.to_string()]);
     tracing::debug!("[SignatureStore] Cleared thought_signature");
 }
 
 /// 检查是否有有效的 thoughtSignature
 pub fn has_valid_signature() -> bool {
-    let store = THOUGHT_SIGNATURE.read().unwrap();
+    let store = STORE.read().unwrap();
     store
-        .as_ref()
+        .get(LATEST_KEY, now_secs())
         .map(|s| s.len() >= MIN_SIGNATURE_LENGTH)
         .unwrap_or(false)
 }
 
+/// 获取签名缓存统计
+pub fn signature_store_stats() -> SignatureStoreStats {
+    let store = STORE.read().unwrap();
+    let hits = HITS.load(Ordering::Relaxed);
+    let misses = MISSES.load(Ordering::Relaxed);
+    let lookups = hits + misses;
+    SignatureStoreStats {
+        entries: store.entries.len(),
+        hits,
+        misses,
+        evictions: EVICTIONS.load(Ordering::Relaxed),
+        hit_rate: if lookups == 0 {
+            0.0
+        } else {
+            hits as f64 / lookups as f64
+        },
+        persisted: store.db.is_some(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,4 +358,33 @@ mod tests {
         assert_eq!(taken, Some(longer_sig));
         assert!(get_thought_signature().is_none());
     }
+
+    fn test_store(ttl_secs: u64, max_entries: usize) -> SignatureStore {
+        SignatureStore::new(SignatureStoreConfig {
+            persist: false,
+            ttl_secs,
+            max_entries,
+        })
+    }
+
+    #[test]
+    fn test_expired_signature_is_ignored() {
+        let mut store = test_store(60, 10);
+        store.insert("call_1", "sig", 1000);
+        assert_eq!(store.get("call_1", 1059), Some("sig"));
+        assert_eq!(store.get("call_1", 1060), None);
+    }
+
+    #[test]
+    fn test_evicts_expired_then_oldest() {
+        let mut store = test_store(60, 2);
+        store.insert("call_1", "sig1", 1000);
+        store.insert("call_2", "sig2", 1050);
+        // call_1 已过期，优先淘汰
+        assert_eq!(store.insert("call_3", "sig3", 1070), vec!["call_1"]);
+        // 均未过期时淘汰最旧的 call_2
+        assert_eq!(store.insert("call_4", "sig4", 1080), vec!["call_2"]);
+        assert_eq!(store.entries.len(), 2);
+        assert_eq!(store.get("call_4", 1080), Some("sig4"));
+    }
 }
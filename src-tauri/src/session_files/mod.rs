@@ -7,16 +7,23 @@
 //! ~/.proxycast/sessions/
 //! ├── {session-id}/
 //! │   ├── .meta.json          # 会话元数据
+//! │   ├── transcript.json     # 对话记录（启用会话持久化时）
 //! │   ├── files/              # 生成的文件
 //! │   │   ├── article.md
 //! │   │   ├── song-spec.md
 //! │   │   └── ...
 //! │   └── canvas/             # 画布状态快照
+//! ├── .uploads/               # 通过 /v1/files 上传的文件
+//! │   ├── {file-id}           # 文件内容
+//! │   └── {file-id}.json      # 文件元数据
 //! └── ...
 //! ```
 
 pub mod storage;
+pub mod transcript;
 pub mod types;
+pub mod uploads;
 
 pub use storage::SessionFileStorage;
+pub use transcript::SessionRecorder;
 pub use types::*;
@@ -7,7 +7,18 @@ use std::path::PathBuf;
 
 use chrono::Utc;
 
-use super::types::{SessionDetail, SessionFile, SessionMeta, SessionSummary};
+use super::types::{
+    SessionDetail, SessionFile, SessionMeta, SessionSummary, SessionTranscript, UploadedFile,
+};
+
+/// 对话记录文件名（位于会话目录下，不计入会话文件）
+const TRANSCRIPT_FILE: &str = "transcript.json";
+
+/// 上传文件目录（隐藏目录，不会被当作会话）
+const UPLOADS_DIR: &str = ".uploads";
+
+/// 上传文件 ID 前缀
+pub const UPLOAD_ID_PREFIX: &str = "file-";
 
 /// 会话文件存储服务
 pub struct SessionFileStorage {
@@ -52,6 +63,38 @@ impl SessionFileStorage {
         self.get_session_dir(session_id).join("files")
     }
 
+    /// 获取对话记录文件路径
+    fn get_transcript_path(&self, session_id: &str) -> PathBuf {
+        self.get_session_dir(session_id).join(TRANSCRIPT_FILE)
+    }
+
+    /// 获取上传文件目录路径
+    fn get_uploads_dir(&self) -> PathBuf {
+        self.base_dir.join(UPLOADS_DIR)
+    }
+
+    /// 是否为本地上传文件的 ID（同时防止路径穿越）
+    pub fn is_upload_id(file_id: &str) -> bool {
+        file_id
+            .strip_prefix(UPLOAD_ID_PREFIX)
+            .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+
+    /// 校验外部传入的会话 ID（防止路径穿越）
+    pub fn validate_session_id(session_id: &str) -> Result<(), String> {
+        let valid = !session_id.is_empty()
+            && session_id.len() <= 128
+            && !session_id.starts_with('.')
+            && session_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("无效的会话 ID: {}", session_id))
+        }
+    }
+
     // ========================================================================
     // 会话管理
     // ========================================================================
@@ -295,6 +338,146 @@ impl SessionFileStorage {
         Ok(SessionDetail { meta, files })
     }
 
+    // ========================================================================
+    // 对话记录
+    // ========================================================================
+
+    /// 保存对话记录（覆盖该会话之前的记录）
+    pub fn save_transcript(&self, transcript: &SessionTranscript) -> Result<(), String> {
+        let session_id = transcript.session_id.as_str();
+        Self::validate_session_id(session_id)?;
+        let mut meta = self.get_or_create_session(session_id)?;
+
+        let content =
+            serde_json::to_string(transcript).map_err(|e| format!("序列化对话记录失败: {}", e))?;
+        fs::write(self.get_transcript_path(session_id), content)
+            .map_err(|e| format!("写入对话记录失败: {}", e))?;
+
+        if meta.title.is_none() {
+            meta.title = transcript_title(transcript);
+        }
+        meta.updated_at = Utc::now().timestamp_millis();
+        self.save_meta(session_id, &meta)
+    }
+
+    /// 读取对话记录（不存在时返回 None）
+    pub fn get_transcript(&self, session_id: &str) -> Result<Option<SessionTranscript>, String> {
+        Self::validate_session_id(session_id)?;
+        let path = self.get_transcript_path(session_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).map_err(|e| format!("读取对话记录失败: {}", e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("解析对话记录失败: {}", e))
+    }
+
+    /// 检查会话是否有对话记录
+    pub fn has_transcript(&self, session_id: &str) -> bool {
+        self.get_transcript_path(session_id).exists()
+    }
+
+    // ========================================================================
+    // 上传文件
+    // ========================================================================
+
+    /// 保存上传文件，内容和元数据分别保存为 `{id}` 和 `{id}.json`
+    pub fn save_upload(
+        &self,
+        filename: &str,
+        purpose: Option<String>,
+        mime_type: &str,
+        content: &[u8],
+    ) -> Result<UploadedFile, String> {
+        let uploads_dir = self.get_uploads_dir();
+        fs::create_dir_all(&uploads_dir).map_err(|e| format!("创建上传目录失败: {}", e))?;
+
+        let file = UploadedFile {
+            id: format!("{}{}", UPLOAD_ID_PREFIX, uuid::Uuid::new_v4().simple()),
+            filename: filename.to_string(),
+            purpose,
+            mime_type: mime_type.to_string(),
+            size: content.len() as u64,
+            created_at: Utc::now().timestamp_millis(),
+        };
+        fs::write(uploads_dir.join(&file.id), content)
+            .map_err(|e| format!("写入上传文件失败: {}", e))?;
+        let meta =
+            serde_json::to_string_pretty(&file).map_err(|e| format!("序列化元数据失败: {}", e))?;
+        fs::write(uploads_dir.join(format!("{}.json", file.id)), meta)
+            .map_err(|e| format!("写入元数据失败: {}", e))?;
+
+        tracing::debug!(
+            "[SessionFileStorage] 保存上传文件: {} -> {}",
+            filename,
+            file.id
+        );
+        Ok(file)
+    }
+
+    /// 读取上传文件元数据（不存在时返回 None）
+    pub fn get_upload(&self, file_id: &str) -> Result<Option<UploadedFile>, String> {
+        if !Self::is_upload_id(file_id) {
+            return Ok(None);
+        }
+        let meta_path = self.get_uploads_dir().join(format!("{}.json", file_id));
+        if !meta_path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&meta_path).map_err(|e| format!("读取元数据失败: {}", e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("解析元数据失败: {}", e))
+    }
+
+    /// 读取上传文件内容
+    pub fn read_upload(&self, file_id: &str) -> Result<Vec<u8>, String> {
+        if !Self::is_upload_id(file_id) {
+            return Err(format!("无效的文件 ID: {}", file_id));
+        }
+        fs::read(self.get_uploads_dir().join(file_id)).map_err(|e| format!("读取文件失败: {}", e))
+    }
+
+    /// 列出上传文件（按创建时间倒序）
+    pub fn list_uploads(&self) -> Result<Vec<UploadedFile>, String> {
+        let uploads_dir = self.get_uploads_dir();
+        let mut files = Vec::new();
+        if !uploads_dir.exists() {
+            return Ok(files);
+        }
+
+        let entries = fs::read_dir(&uploads_dir).map_err(|e| format!("读取上传目录失败: {}", e))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(file_id) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+                continue;
+            };
+            if let Ok(Some(file)) = self.get_upload(file_id) {
+                files.push(file);
+            }
+        }
+
+        files.sort_by_key(|file| std::cmp::Reverse(file.created_at));
+        Ok(files)
+    }
+
+    /// 删除上传文件（不存在时返回 false）
+    pub fn delete_upload(&self, file_id: &str) -> Result<bool, String> {
+        if self.get_upload(file_id)?.is_none() {
+            return Ok(false);
+        }
+        let uploads_dir = self.get_uploads_dir();
+        let content_path = uploads_dir.join(file_id);
+        if content_path.exists() {
+            fs::remove_file(&content_path).map_err(|e| format!("删除文件失败: {}", e))?;
+        }
+        fs::remove_file(uploads_dir.join(format!("{}.json", file_id)))
+            .map_err(|e| format!("删除元数据失败: {}", e))?;
+        Ok(true)
+    }
+
     // ========================================================================
     // 清理功能
     // ========================================================================
@@ -323,10 +506,12 @@ impl SessionFileStorage {
 
         let sessions = self.list_sessions()?;
         for session in sessions {
-            if session.file_count == 0 {
-                if self.delete_session(&session.session_id).is_ok() {
-                    cleaned += 1;
-                }
+            // 只有对话记录的会话不视为空会话
+            if session.file_count == 0
+                && !self.has_transcript(&session.session_id)
+                && self.delete_session(&session.session_id).is_ok()
+            {
+                cleaned += 1;
             }
         }
 
@@ -365,6 +550,29 @@ impl SessionFileStorage {
     }
 }
 
+/// 以第一条用户消息生成会话标题
+fn transcript_title(transcript: &SessionTranscript) -> Option<String> {
+    let first_user = transcript
+        .messages
+        .iter()
+        .find(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))?;
+    let text = match first_user.get("content")? {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.chars().take(50).collect())
+    }
+}
+
 impl Default for SessionFileStorage {
     fn default() -> Self {
         Self::new().expect("创建默认会话文件存储失败")
@@ -420,6 +628,80 @@ mod tests {
         assert_eq!(files.len(), 2);
     }
 
+    #[test]
+    fn test_save_and_get_transcript() {
+        let (storage, _temp) = create_test_storage();
+        assert_eq!(storage.get_transcript("sid-1").unwrap(), None);
+
+        let transcript = SessionTranscript {
+            session_id: "sid-1".to_string(),
+            format: crate::session_files::TranscriptFormat::Anthropic,
+            model: "claude-sonnet-4-5".to_string(),
+            system: None,
+            messages: vec![
+                serde_json::json!({"role": "user", "content": "Write a haiku about rust"}),
+                serde_json::json!({"role": "assistant", "content": [{"type": "text", "text": "..."}]}),
+            ],
+            updated_at: 0,
+        };
+        storage.save_transcript(&transcript).unwrap();
+
+        assert_eq!(
+            storage.get_transcript("sid-1").unwrap(),
+            Some(transcript.clone())
+        );
+        let meta = storage.get_meta("sid-1").unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Write a haiku about rust"));
+        // 只有对话记录的会话不会被当作空会话清理
+        assert_eq!(storage.cleanup_empty().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_session_id() {
+        let (storage, _temp) = create_test_storage();
+        assert!(storage.get_transcript("../etc").is_err());
+        assert!(storage.get_transcript(".hidden").is_err());
+        assert!(SessionFileStorage::validate_session_id("sid-0123abcd_x.y").is_ok());
+    }
+
+    #[test]
+    fn test_upload_lifecycle() {
+        let (storage, _temp) = create_test_storage();
+        let file = storage
+            .save_upload(
+                "notes.txt",
+                Some("assistants".to_string()),
+                "text/plain",
+                b"hello",
+            )
+            .unwrap();
+        assert!(SessionFileStorage::is_upload_id(&file.id));
+        assert_eq!(file.size, 5);
+
+        assert_eq!(storage.get_upload(&file.id).unwrap(), Some(file.clone()));
+        assert_eq!(storage.read_upload(&file.id).unwrap(), b"hello");
+        assert_eq!(storage.list_uploads().unwrap(), vec![file.clone()]);
+        // 上传目录不会被当作会话
+        assert!(storage.list_sessions().unwrap().is_empty());
+
+        assert!(storage.delete_upload(&file.id).unwrap());
+        assert!(!storage.delete_upload(&file.id).unwrap());
+        assert_eq!(storage.get_upload(&file.id).unwrap(), None);
+        assert!(storage.list_uploads().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_invalid_upload_id() {
+        let (storage, _temp) = create_test_storage();
+        assert!(!SessionFileStorage::is_upload_id("file-../../etc"));
+        assert!(!SessionFileStorage::is_upload_id("file-"));
+        assert!(!SessionFileStorage::is_upload_id(
+            "file_011CNha8iCJcU1wXNR6q4V8w"
+        ));
+        assert_eq!(storage.get_upload("file-../x").unwrap(), None);
+        assert!(storage.read_upload("../secret").is_err());
+    }
+
     #[test]
     fn test_delete_session() {
         let (storage, _temp) = create_test_storage();
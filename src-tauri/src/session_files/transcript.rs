@@ -0,0 +1,360 @@
+//! 会话对话记录
+//!
+//! 按 SessionId 保存代理请求的完整上下文和回复，客户端重连后可获取或续接对话。
+//! HTTP 请求在 Flow 完成后由后台任务记录（依赖 Flow 监控），WebSocket 请求在响应后直接记录。
+
+use super::storage::SessionFileStorage;
+use super::types::{SessionTranscript, TranscriptFormat};
+use crate::config::SessionPersistenceConfig;
+use crate::flow_monitor::{FlowEvent, FlowMonitor, LLMFlow};
+use crate::session::SessionManager;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 指定会话 ID 的请求头
+pub const SESSION_ID_HEADER: &str = "x-session-id";
+
+impl TranscriptFormat {
+    /// 根据请求路径判断格式
+    pub fn from_path(path: &str) -> Option<Self> {
+        if path.ends_with("/chat/completions") {
+            Some(TranscriptFormat::OpenAI)
+        } else if path.ends_with("/messages") {
+            Some(TranscriptFormat::Anthropic)
+        } else {
+            None
+        }
+    }
+}
+
+/// 从请求头读取会话 ID（请求头名不区分大小写）
+pub fn session_id_from_headers(headers: &HashMap<String, String>) -> Option<String> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(SESSION_ID_HEADER))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 构建对话记录：请求中的消息 + 助手回复
+///
+/// 响应体中取不到回复（如无法识别的流式格式）时使用 `fallback_text`。
+pub fn build_transcript(
+    session_id: String,
+    format: TranscriptFormat,
+    request: &Value,
+    response: &Value,
+    fallback_text: Option<&str>,
+) -> Option<SessionTranscript> {
+    let mut messages = request.get("messages")?.as_array()?.clone();
+    if let Some(reply) = assistant_reply(format, response, fallback_text) {
+        messages.push(reply);
+    }
+    let system = match format {
+        TranscriptFormat::Anthropic => request.get("system").filter(|s| !s.is_null()).cloned(),
+        TranscriptFormat::OpenAI => None,
+    };
+    Some(SessionTranscript {
+        session_id,
+        format,
+        model: request
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        system,
+        messages,
+        updated_at: Utc::now().timestamp_millis(),
+    })
+}
+
+/// 从响应体提取助手消息
+fn assistant_reply(
+    format: TranscriptFormat,
+    response: &Value,
+    fallback_text: Option<&str>,
+) -> Option<Value> {
+    let reply = match format {
+        TranscriptFormat::Anthropic => response
+            .get("content")
+            .filter(|c| c.as_array().is_some_and(|blocks| !blocks.is_empty()))
+            .map(|content| json!({"role": "assistant", "content": content})),
+        TranscriptFormat::OpenAI => response
+            .pointer("/choices/0/message")
+            .filter(|m| m.is_object())
+            .cloned(),
+    };
+    reply.or_else(|| {
+        let text = fallback_text.filter(|t| !t.is_empty())?;
+        Some(match format {
+            TranscriptFormat::Anthropic => {
+                json!({"role": "assistant", "content": [{"type": "text", "text": text}]})
+            }
+            TranscriptFormat::OpenAI => json!({"role": "assistant", "content": text}),
+        })
+    })
+}
+
+/// 续接会话：把历史消息放在新消息之前
+///
+/// 请求体中的其他参数原样保留；未指定的模型和 system 沿用记录中的值。
+pub fn resume_request(transcript: &SessionTranscript, request: Value) -> Value {
+    let mut messages = transcript.messages.clone();
+    if let Some(new_messages) = request.get("messages").and_then(|m| m.as_array()) {
+        messages.extend(new_messages.iter().cloned());
+    }
+
+    let mut request = if request.is_object() {
+        request
+    } else {
+        json!({})
+    };
+    request["messages"] = Value::Array(messages);
+    if request.get("model").and_then(|m| m.as_str()).is_none() {
+        request["model"] = json!(transcript.model);
+    }
+    if let Some(system) = &transcript.system {
+        if request.get("system").is_none() {
+            request["system"] = system.clone();
+        }
+    }
+    request
+}
+
+/// 会话对话记录器
+pub struct SessionRecorder {
+    storage: SessionFileStorage,
+    config: Box<dyn Fn() -> SessionPersistenceConfig + Send + Sync>,
+}
+
+impl SessionRecorder {
+    /// 创建记录器（每次记录时读取配置，支持热重载）
+    pub fn new<F>(storage: SessionFileStorage, config: F) -> Self
+    where
+        F: Fn() -> SessionPersistenceConfig + Send + Sync + 'static,
+    {
+        Self {
+            storage,
+            config: Box::new(config),
+        }
+    }
+
+    pub fn storage(&self) -> &SessionFileStorage {
+        &self.storage
+    }
+
+    pub fn enabled(&self) -> bool {
+        (self.config)().enabled
+    }
+
+    /// 记录一次请求/响应
+    ///
+    /// 未指定会话 ID 时按配置决定是否使用请求内容指纹作为会话 ID。
+    pub fn record(
+        &self,
+        session_id: Option<String>,
+        format: TranscriptFormat,
+        request: &Value,
+        response: &Value,
+        fallback_text: Option<&str>,
+    ) {
+        let config = (self.config)();
+        if !config.enabled {
+            return;
+        }
+        let session_id = match session_id {
+            Some(id) => id,
+            None if config.fingerprint_sessions => {
+                let model = request
+                    .get("model")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or_default();
+                SessionManager::extract_session_id_from_json(request, model)
+            }
+            None => return,
+        };
+
+        let Some(transcript) =
+            build_transcript(session_id.clone(), format, request, response, fallback_text)
+        else {
+            return;
+        };
+        if let Err(e) = self.storage.save_transcript(&transcript) {
+            tracing::warn!(
+                "[SESSION] 保存对话记录失败: session_id={} error={}",
+                session_id,
+                e
+            );
+        }
+    }
+
+    /// 记录已完成的 Flow
+    pub fn record_flow(&self, flow: &LLMFlow) {
+        let Some(format) = TranscriptFormat::from_path(&flow.request.path) else {
+            return;
+        };
+        let Some(response) = &flow.response else {
+            return;
+        };
+        self.record(
+            session_id_from_headers(&flow.request.headers),
+            format,
+            &flow.request.body,
+            &response.body,
+            Some(&response.content),
+        );
+    }
+}
+
+/// 启动后台任务：Flow 完成后记录对话
+pub fn spawn_transcript_recorder(
+    recorder: Arc<SessionRecorder>,
+    flow_monitor: Arc<FlowMonitor>,
+) -> tokio::task::JoinHandle<()> {
+    let mut receiver = flow_monitor.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(FlowEvent::FlowCompleted { id, .. }) => {
+                    if !recorder.enabled() {
+                        continue;
+                    }
+                    let flow = {
+                        let store = flow_monitor.memory_store();
+                        let store = store.read().await;
+                        store
+                            .get(&id)
+                            .and_then(|flow| flow.read().ok().map(|flow| flow.clone()))
+                    };
+                    if let Some(flow) = flow {
+                        let recorder = recorder.clone();
+                        tokio::task::spawn_blocking(move || recorder.record_flow(&flow));
+                    }
+                }
+                Ok(_) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("[SESSION] 对话记录任务落后 {} 个 Flow 事件", n);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn anthropic_request() -> Value {
+        json!({
+            "model": "claude-sonnet-4-5",
+            "system": "You are terse.",
+            "max_tokens": 1024,
+            "messages": [{"role": "user", "content": "Hello there, assistant"}]
+        })
+    }
+
+    #[test]
+    fn test_build_transcript_appends_reply() {
+        let transcript = build_transcript(
+            "sid-1".to_string(),
+            TranscriptFormat::Anthropic,
+            &anthropic_request(),
+            &json!({"content": [{"type": "text", "text": "Hi"}]}),
+            None,
+        )
+        .unwrap();
+        assert_eq!(transcript.messages.len(), 2);
+        assert_eq!(transcript.messages[1]["role"], "assistant");
+        assert_eq!(transcript.system, Some(json!("You are terse.")));
+
+        let openai = build_transcript(
+            "sid-2".to_string(),
+            TranscriptFormat::OpenAI,
+            &json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}),
+            &json!({"content": "Hello!"}),
+            Some("Hello!"),
+        )
+        .unwrap();
+        assert_eq!(
+            openai.messages[1],
+            json!({"role": "assistant", "content": "Hello!"})
+        );
+    }
+
+    #[test]
+    fn test_resume_request_prepends_history() {
+        let transcript = build_transcript(
+            "sid-1".to_string(),
+            TranscriptFormat::Anthropic,
+            &anthropic_request(),
+            &json!({"content": [{"type": "text", "text": "Hi"}]}),
+            None,
+        )
+        .unwrap();
+        let resumed = resume_request(
+            &transcript,
+            json!({"max_tokens": 512, "messages": [{"role": "user", "content": "Continue"}]}),
+        );
+        let messages = resumed["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2]["content"], "Continue");
+        assert_eq!(resumed["model"], "claude-sonnet-4-5");
+        assert_eq!(resumed["system"], "You are terse.");
+        assert_eq!(resumed["max_tokens"], 512);
+    }
+
+    #[test]
+    fn test_recorder_respects_config() {
+        let temp = TempDir::new().unwrap();
+        let storage = SessionFileStorage::with_base_dir(temp.path().to_path_buf()).unwrap();
+        let disabled = SessionRecorder::new(storage, SessionPersistenceConfig::default);
+        let response = json!({"content": [{"type": "text", "text": "Hi"}]});
+        disabled.record(
+            Some("sid-1".to_string()),
+            TranscriptFormat::Anthropic,
+            &anthropic_request(),
+            &response,
+            None,
+        );
+        assert_eq!(disabled.storage().get_transcript("sid-1").unwrap(), None);
+
+        let storage = SessionFileStorage::with_base_dir(temp.path().to_path_buf()).unwrap();
+        let recorder = SessionRecorder::new(storage, || SessionPersistenceConfig {
+            enabled: true,
+            fingerprint_sessions: false,
+        });
+        recorder.record(
+            Some("sid-1".to_string()),
+            TranscriptFormat::Anthropic,
+            &anthropic_request(),
+            &response,
+            None,
+        );
+        assert!(recorder
+            .storage()
+            .get_transcript("sid-1")
+            .unwrap()
+            .is_some());
+
+        // 未携带会话 ID 且关闭指纹时不记录
+        recorder.record(
+            None,
+            TranscriptFormat::Anthropic,
+            &anthropic_request(),
+            &response,
+            None,
+        );
+        assert_eq!(recorder.storage().list_sessions().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_session_id_from_headers() {
+        let headers = HashMap::from([("X-Session-Id".to_string(), " abc ".to_string())]);
+        assert_eq!(session_id_from_headers(&headers), Some("abc".to_string()));
+        assert_eq!(session_id_from_headers(&HashMap::new()), None);
+    }
+}
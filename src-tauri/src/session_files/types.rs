@@ -84,3 +84,53 @@ pub struct SessionDetail {
     /// 文件列表
     pub files: Vec<SessionFile>,
 }
+
+/// 通过 `/v1/files` 上传的文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedFile {
+    /// 文件 ID（`file-` 前缀）
+    pub id: String,
+    /// 原始文件名
+    pub filename: String,
+    /// 用途（OpenAI 的 `purpose` 字段，Anthropic 上传时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    /// MIME 类型
+    pub mime_type: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 创建时间（Unix 时间戳，毫秒）
+    pub created_at: i64,
+}
+
+/// 对话记录的请求格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    /// Anthropic Messages（/v1/messages）
+    Anthropic,
+    /// OpenAI Chat Completions（/v1/chat/completions）
+    OpenAI,
+}
+
+/// 会话对话记录
+///
+/// 保存最近一次请求的完整上下文和对应回复，消息保持请求格式的原始 JSON。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTranscript {
+    /// 会话 ID
+    pub session_id: String,
+    /// 请求格式
+    pub format: TranscriptFormat,
+    /// 模型名称
+    pub model: String,
+    /// 系统提示词（仅 Anthropic 格式，OpenAI 格式在 messages 中）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<serde_json::Value>,
+    /// 消息列表（包含最后一次助手回复）
+    pub messages: Vec<serde_json::Value>,
+    /// 更新时间（Unix 时间戳，毫秒）
+    pub updated_at: i64,
+}
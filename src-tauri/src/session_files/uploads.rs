@@ -0,0 +1,467 @@
+//! 文件上传 API（`/v1/files`）
+//!
+//! - 上传的文件保存在会话存储目录下，返回 OpenAI / Anthropic 兼容的文件对象
+//! - 请求消息中按 ID 引用本地文件时，转发前替换为内联内容（上游不认识本地文件 ID）；
+//!   非本地 ID 原样转发，由支持文件 API 的上游自行处理
+
+use super::storage::SessionFileStorage;
+use super::transcript::SessionRecorder;
+use super::types::{TranscriptFormat, UploadedFile};
+use crate::server::validation::{ErrorFormat, FieldError};
+use crate::server_utils::find_subsequence;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{TimeZone, Utc};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// 上传文件未指定 `purpose` 时在 OpenAI 文件对象中使用的值
+const DEFAULT_PURPOSE: &str = "user_data";
+
+/// 按请求头判断客户端使用的文件 API 格式（带 `anthropic-version` 时为 Anthropic）
+pub fn format_from_headers(headers: &HeaderMap) -> TranscriptFormat {
+    if headers.contains_key("anthropic-version") || headers.contains_key("anthropic-beta") {
+        TranscriptFormat::Anthropic
+    } else {
+        TranscriptFormat::OpenAI
+    }
+}
+
+/// 根据文件扩展名推断 MIME 类型
+pub fn guess_mime_type(filename: &str) -> &'static str {
+    let ext = filename.rsplit('.').next().unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 是否按文本内联
+fn is_text_mime(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json" | "application/xml" | "application/x-yaml"
+        )
+}
+
+/// 构建文件对象
+pub fn file_object(file: &UploadedFile, format: TranscriptFormat) -> Value {
+    match format {
+        TranscriptFormat::OpenAI => json!({
+            "id": file.id,
+            "object": "file",
+            "bytes": file.size,
+            "created_at": file.created_at / 1000,
+            "filename": file.filename,
+            "purpose": file.purpose.as_deref().unwrap_or(DEFAULT_PURPOSE),
+            "status": "processed"
+        }),
+        TranscriptFormat::Anthropic => json!({
+            "id": file.id,
+            "type": "file",
+            "filename": file.filename,
+            "mime_type": file.mime_type,
+            "size_bytes": file.size,
+            "created_at": Utc
+                .timestamp_millis_opt(file.created_at)
+                .single()
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            "downloadable": true
+        }),
+    }
+}
+
+/// 构建文件列表
+pub fn file_list_object(files: &[UploadedFile], format: TranscriptFormat) -> Value {
+    let data: Vec<Value> = files.iter().map(|f| file_object(f, format)).collect();
+    match format {
+        TranscriptFormat::OpenAI => json!({
+            "object": "list",
+            "data": data,
+            "has_more": false
+        }),
+        TranscriptFormat::Anthropic => json!({
+            "data": data,
+            "has_more": false,
+            "first_id": files.first().map(|f| f.id.as_str()),
+            "last_id": files.last().map(|f| f.id.as_str())
+        }),
+    }
+}
+
+/// 构建删除结果
+pub fn file_deleted_object(file_id: &str, format: TranscriptFormat) -> Value {
+    match format {
+        TranscriptFormat::OpenAI => json!({
+            "id": file_id,
+            "object": "file",
+            "deleted": true
+        }),
+        TranscriptFormat::Anthropic => json!({
+            "id": file_id,
+            "type": "file_deleted"
+        }),
+    }
+}
+
+/// 内容块引用的文件 ID
+///
+/// - Anthropic：`{"type": "document" | "image", "source": {"type": "file", "file_id": ...}}`
+/// - OpenAI：`{"type": "file", "file": {"file_id": ...}}`
+fn referenced_file_id(format: TranscriptFormat, block: &Value) -> Option<&str> {
+    let block_type = block.get("type")?.as_str()?;
+    match format {
+        TranscriptFormat::Anthropic => {
+            if block_type != "document" && block_type != "image" {
+                return None;
+            }
+            let source = block.get("source")?;
+            if source.get("type")?.as_str()? != "file" {
+                return None;
+            }
+            source.get("file_id")?.as_str()
+        }
+        TranscriptFormat::OpenAI => {
+            if block_type != "file" {
+                return None;
+            }
+            block.get("file")?.get("file_id")?.as_str()
+        }
+    }
+}
+
+/// 把文件引用替换为内联内容块
+fn inline_block(
+    format: TranscriptFormat,
+    block: &Value,
+    file: &UploadedFile,
+    content: &[u8],
+) -> Result<Value, String> {
+    let mime_type = file.mime_type.as_str();
+    let text = if is_text_mime(mime_type) {
+        std::str::from_utf8(content).ok()
+    } else {
+        None
+    };
+    let is_image = mime_type.starts_with("image/");
+
+    match format {
+        TranscriptFormat::Anthropic => {
+            let mut inlined = block.clone();
+            let source = if is_image {
+                inlined["type"] = json!("image");
+                json!({"type": "base64", "media_type": mime_type, "data": BASE64.encode(content)})
+            } else if block["type"] == "image" {
+                return Err(format!("File {} is not an image ({})", file.id, mime_type));
+            } else if mime_type == "application/pdf" {
+                json!({"type": "base64", "media_type": mime_type, "data": BASE64.encode(content)})
+            } else if let Some(text) = text {
+                json!({"type": "text", "media_type": "text/plain", "data": text})
+            } else {
+                return Err(format!(
+                    "File {} ({}) cannot be inlined into a document block",
+                    file.id, mime_type
+                ));
+            };
+            inlined["source"] = source;
+            if !is_image && inlined.get("title").is_none() {
+                inlined["title"] = json!(file.filename);
+            }
+            Ok(inlined)
+        }
+        TranscriptFormat::OpenAI => {
+            if is_image {
+                Ok(json!({
+                    "type": "image_url",
+                    "image_url": {
+                        "url": format!("data:{};base64,{}", mime_type, BASE64.encode(content))
+                    }
+                }))
+            } else if let Some(text) = text {
+                Ok(json!({
+                    "type": "text",
+                    "text": format!("[{}]\n{}", file.filename, text)
+                }))
+            } else {
+                Err(format!(
+                    "File {} ({}) cannot be inlined into an OpenAI format request, \
+                     only text and image files are supported",
+                    file.id, mime_type
+                ))
+            }
+        }
+    }
+}
+
+/// 把消息中引用本地上传文件的内容块替换为内联内容，返回替换的数量
+///
+/// `load` 按文件 ID 读取元数据和内容，找不到时返回 None。
+pub fn inline_file_references<F>(
+    format: TranscriptFormat,
+    request: &mut Value,
+    load: F,
+) -> Result<usize, FieldError>
+where
+    F: Fn(&str) -> Option<(UploadedFile, Vec<u8>)>,
+{
+    let Some(messages) = request.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+        return Ok(0);
+    };
+
+    let mut inlined = 0;
+    for (i, message) in messages.iter_mut().enumerate() {
+        let Some(blocks) = message.get_mut("content").and_then(|c| c.as_array_mut()) else {
+            continue;
+        };
+        for (j, block) in blocks.iter_mut().enumerate() {
+            let Some(file_id) = referenced_file_id(format, block) else {
+                continue;
+            };
+            // 非本地文件 ID 原样转发
+            if !SessionFileStorage::is_upload_id(file_id) {
+                continue;
+            }
+            let param = format!("messages.{}.content.{}", i, j);
+            let (file, content) = load(file_id)
+                .ok_or_else(|| FieldError::new(&param, format!("File not found: {}", file_id)))?;
+            *block = inline_block(format, block, &file, &content)
+                .map_err(|message| FieldError::new(&param, message))?;
+            inlined += 1;
+        }
+    }
+    Ok(inlined)
+}
+
+/// 为模型 API 路由添加文件引用内联中间件（会话存储不可用时不添加）
+pub fn with_file_references<S>(
+    router: Router<S>,
+    recorder: Option<Arc<SessionRecorder>>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match recorder {
+        Some(recorder) => router.layer(axum::middleware::from_fn_with_state(
+            recorder,
+            inline_request_files,
+        )),
+        None => router,
+    }
+}
+
+/// 内联请求中引用的本地文件
+async fn inline_request_files(
+    State(recorder): State<Arc<SessionRecorder>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let format = match TranscriptFormat::from_path(request.uri().path()) {
+        Some(format) if request.method() == Method::POST => format,
+        _ => return next.run(request).await,
+    };
+    let error_format = match format {
+        TranscriptFormat::OpenAI => ErrorFormat::OpenAI,
+        TranscriptFormat::Anthropic => ErrorFormat::Anthropic,
+    };
+
+    // 请求体大小已由外层的 Content-Length 检查限制
+    let (mut parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return FieldError::body(format!("Failed to read request body: {}", e))
+                .into_response(error_format)
+        }
+    };
+    if find_subsequence(&bytes, b"\"file_id\"").is_none() {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    }
+    // 无法解析的请求体交给处理器的校验报错
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+
+    let storage = recorder.storage();
+    let result = inline_file_references(format, &mut value, |file_id| {
+        let file = storage.get_upload(file_id).ok()??;
+        let content = storage.read_upload(file_id).ok()?;
+        Some((file, content))
+    });
+    match result {
+        Ok(0) => {
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+        Ok(count) => {
+            tracing::debug!("[FILES] 内联 {} 个文件引用", count);
+            let body = serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec());
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Err(e) => e.into_response(error_format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uploaded(id: &str, filename: &str, mime_type: &str) -> UploadedFile {
+        UploadedFile {
+            id: id.to_string(),
+            filename: filename.to_string(),
+            purpose: None,
+            mime_type: mime_type.to_string(),
+            size: 5,
+            created_at: 1_767_225_600_000,
+        }
+    }
+
+    fn load(file_id: &str) -> Option<(UploadedFile, Vec<u8>)> {
+        match file_id {
+            "file-01" => Some((
+                uploaded("file-01", "notes.md", "text/markdown"),
+                b"hello".to_vec(),
+            )),
+            "file-02" => Some((
+                uploaded("file-02", "cat.png", "image/png"),
+                b"\x89PNG".to_vec(),
+            )),
+            "file-03" => Some((
+                uploaded("file-03", "paper.pdf", "application/pdf"),
+                b"%PDF-".to_vec(),
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_inline_anthropic_references() {
+        let mut request = json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "document", "source": {"type": "file", "file_id": "file-01"}},
+                    {"type": "document", "source": {"type": "file", "file_id": "file-03"}, "title": "Paper"},
+                    {"type": "image", "source": {"type": "file", "file_id": "file-02"}},
+                    {"type": "document", "source": {"type": "file", "file_id": "file_011CNha8"}},
+                    {"type": "text", "text": "Summarize"}
+                ]
+            }]
+        });
+        let count =
+            inline_file_references(TranscriptFormat::Anthropic, &mut request, load).unwrap();
+        assert_eq!(count, 3);
+
+        let content = &request["messages"][0]["content"];
+        assert_eq!(content[0]["source"]["type"], "text");
+        assert_eq!(content[0]["source"]["data"], "hello");
+        assert_eq!(content[0]["title"], "notes.md");
+        assert_eq!(content[1]["source"]["media_type"], "application/pdf");
+        assert_eq!(content[1]["source"]["data"], BASE64.encode(b"%PDF-"));
+        assert_eq!(content[1]["title"], "Paper");
+        assert_eq!(content[2]["source"]["type"], "base64");
+        assert_eq!(content[2]["source"]["media_type"], "image/png");
+        // 上游文件 ID 原样保留
+        assert_eq!(content[3]["source"]["file_id"], "file_011CNha8");
+    }
+
+    #[test]
+    fn test_inline_openai_references() {
+        let mut request = json!({
+            "model": "gpt-4o",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "file", "file": {"file_id": "file-01"}},
+                    {"type": "file", "file": {"file_id": "file-02"}}
+                ]
+            }]
+        });
+        let count = inline_file_references(TranscriptFormat::OpenAI, &mut request, load).unwrap();
+        assert_eq!(count, 2);
+
+        let content = &request["messages"][0]["content"];
+        assert_eq!(
+            content[0],
+            json!({"type": "text", "text": "[notes.md]\nhello"})
+        );
+        assert_eq!(content[1]["type"], "image_url");
+        assert!(content[1]["image_url"]["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("data:image/png;base64,"));
+
+        // PDF 无法转换为 OpenAI 格式的内容块
+        let mut request = json!({
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "hi"},
+                {"type": "file", "file": {"file_id": "file-03"}}
+            ]}]
+        });
+        let err = inline_file_references(TranscriptFormat::OpenAI, &mut request, load).unwrap_err();
+        assert_eq!(err.param.as_deref(), Some("messages.0.content.1"));
+
+        let mut request = json!({
+            "messages": [{"role": "user", "content": [
+                {"type": "file", "file": {"file_id": "file-99"}}
+            ]}]
+        });
+        let err = inline_file_references(TranscriptFormat::OpenAI, &mut request, load).unwrap_err();
+        assert_eq!(err.message, "File not found: file-99");
+    }
+
+    #[test]
+    fn test_file_objects() {
+        let file = uploaded("file-01", "notes.md", "text/markdown");
+
+        let openai = file_object(&file, TranscriptFormat::OpenAI);
+        assert_eq!(openai["object"], "file");
+        assert_eq!(openai["bytes"], 5);
+        assert_eq!(openai["created_at"], 1_767_225_600);
+        assert_eq!(openai["purpose"], DEFAULT_PURPOSE);
+
+        let anthropic = file_object(&file, TranscriptFormat::Anthropic);
+        assert_eq!(anthropic["type"], "file");
+        assert_eq!(anthropic["size_bytes"], 5);
+        assert_eq!(anthropic["created_at"], "2026-01-01T00:00:00+00:00");
+
+        let list = file_list_object(&[file], TranscriptFormat::Anthropic);
+        assert_eq!(list["first_id"], "file-01");
+        assert_eq!(list["last_id"], "file-01");
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(format_from_headers(&headers), TranscriptFormat::OpenAI);
+        headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+        assert_eq!(format_from_headers(&headers), TranscriptFormat::Anthropic);
+
+        assert_eq!(guess_mime_type("Report.PDF"), "application/pdf");
+        assert_eq!(guess_mime_type("data.bin"), "application/octet-stream");
+    }
+}
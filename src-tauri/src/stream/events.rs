@@ -146,6 +146,16 @@ pub enum ContentBlockType {
 }
 
 /// 停止原因
+///
+/// 各协议停止原因的映射矩阵：
+///
+/// | StopReason | OpenAI `finish_reason` | Anthropic `stop_reason` | Gemini `finishReason` |
+/// |---|---|---|---|
+/// | `EndTurn` | `stop` | `end_turn` | `STOP` |
+/// | `MaxTokens` | `length` | `max_tokens` | `MAX_TOKENS` |
+/// | `ToolUse` | `tool_calls` / `function_call` | `tool_use` | `STOP`（含 functionCall） |
+/// | `StopSequence` | `stop`（附带命中的序列） | `stop_sequence` | `STOP` |
+/// | `ContentFilter` | `content_filter` | `refusal` | `SAFETY` / `RECITATION` / `BLOCKLIST` 等 |
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StopReason {
     /// 正常结束
@@ -156,6 +166,8 @@ pub enum StopReason {
     ToolUse,
     /// 用户停止
     StopSequence,
+    /// 内容被安全策略拦截
+    ContentFilter,
     /// 其他原因
     Other(String),
 }
@@ -172,31 +184,114 @@ impl StopReason {
         match s.to_lowercase().as_str() {
             "end_turn" | "stop" => Self::EndTurn,
             "max_tokens" | "length" => Self::MaxTokens,
-            "tool_use" | "tool_calls" => Self::ToolUse,
+            "tool_use" | "tool_calls" | "function_call" => Self::ToolUse,
             "stop_sequence" => Self::StopSequence,
+            "content_filter" | "refusal" | "safety" => Self::ContentFilter,
             _ => Self::Other(s.to_string()),
         }
     }
 
+    /// 解析 OpenAI `finish_reason`
+    ///
+    /// OpenAI 命中停止序列时同样返回 `stop`，需结合 [`Self::from_openai_choice`] 区分。
+    pub fn from_openai_str(s: &str) -> Self {
+        match s {
+            "stop" => Self::EndTurn,
+            "length" => Self::MaxTokens,
+            "tool_calls" | "function_call" => Self::ToolUse,
+            "content_filter" => Self::ContentFilter,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// 解析 OpenAI choice 的结束原因及命中的停止序列
+    ///
+    /// vLLM 通过 `stop_reason`、SGLang 通过 `matched_stop` 回传命中的停止字符串
+    /// （为整数时是停止 token ID，忽略）；此时结束原因为 `StopSequence`。
+    /// `finish_reason` 缺失（流式中间 chunk）时返回 `None`。
+    pub fn from_openai_choice(choice: &serde_json::Value) -> Option<(Self, Option<String>)> {
+        let finish_reason = choice.get("finish_reason")?.as_str()?;
+        let reason = Self::from_openai_str(finish_reason);
+        if reason != Self::EndTurn {
+            return Some((reason, None));
+        }
+        let matched = ["matched_stop", "stop_reason"]
+            .iter()
+            .find_map(|key| choice.get(*key).and_then(|v| v.as_str()))
+            .map(str::to_string);
+        match matched {
+            Some(sequence) => Some((Self::StopSequence, Some(sequence))),
+            None => Some((Self::EndTurn, None)),
+        }
+    }
+
+    /// 解析 Anthropic `stop_reason`
+    pub fn from_anthropic_str(s: &str) -> Self {
+        match s {
+            "end_turn" => Self::EndTurn,
+            "max_tokens" => Self::MaxTokens,
+            "tool_use" => Self::ToolUse,
+            "stop_sequence" => Self::StopSequence,
+            "refusal" => Self::ContentFilter,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// 解析 Gemini `finishReason`
+    ///
+    /// Gemini 发起函数调用时仍返回 `STOP`，调用方需配合 [`Self::with_tool_calls`] 使用。
+    pub fn from_gemini_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "STOP" => Self::EndTurn,
+            "MAX_TOKENS" => Self::MaxTokens,
+            "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII"
+            | "IMAGE_SAFETY" | "LANGUAGE" => Self::ContentFilter,
+            "FUNCTION_CALL" => Self::ToolUse,
+            _ => Self::Other(s.to_string()),
+        }
+    }
+
+    /// 响应包含工具调用时，将正常结束修正为 `ToolUse`
+    pub fn with_tool_calls(self, has_tool_calls: bool) -> Self {
+        match self {
+            Self::EndTurn if has_tool_calls => Self::ToolUse,
+            other => other,
+        }
+    }
+
     /// 转换为 OpenAI 格式的字符串
-    pub fn to_openai_str(&self) -> &str {
+    pub fn to_openai_str(&self) -> &'static str {
         match self {
             Self::EndTurn => "stop",
             Self::MaxTokens => "length",
             Self::ToolUse => "tool_calls",
             Self::StopSequence => "stop",
+            Self::ContentFilter => "content_filter",
             Self::Other(_) => "stop",
         }
     }
 
     /// 转换为 Anthropic 格式的字符串
-    pub fn to_anthropic_str(&self) -> &str {
+    ///
+    /// 非 Anthropic 取值的 `Other` 统一回落为 `end_turn`，避免客户端收到未知枚举。
+    pub fn to_anthropic_str(&self) -> &'static str {
         match self {
             Self::EndTurn => "end_turn",
             Self::MaxTokens => "max_tokens",
             Self::ToolUse => "tool_use",
             Self::StopSequence => "stop_sequence",
-            Self::Other(s) => s,
+            Self::ContentFilter => "refusal",
+            Self::Other(s) if s == "pause_turn" => "pause_turn",
+            Self::Other(_) => "end_turn",
+        }
+    }
+
+    /// 转换为 Gemini 格式的字符串
+    pub fn to_gemini_str(&self) -> &'static str {
+        match self {
+            Self::MaxTokens => "MAX_TOKENS",
+            Self::ContentFilter => "SAFETY",
+            _ => "STOP",
         }
     }
 }
@@ -288,6 +383,93 @@ mod tests {
         assert_eq!(StopReason::ToolUse.to_anthropic_str(), "tool_use");
     }
 
+    #[test]
+    fn test_stop_reason_protocol_matrix() {
+        let cases = [
+            ("stop", "end_turn", "STOP", StopReason::EndTurn),
+            ("length", "max_tokens", "MAX_TOKENS", StopReason::MaxTokens),
+            (
+                "content_filter",
+                "refusal",
+                "SAFETY",
+                StopReason::ContentFilter,
+            ),
+        ];
+        for (openai, anthropic, gemini, expected) in cases {
+            assert_eq!(StopReason::from_openai_str(openai), expected);
+            assert_eq!(StopReason::from_anthropic_str(anthropic), expected);
+            assert_eq!(StopReason::from_gemini_str(gemini), expected);
+            assert_eq!(expected.to_openai_str(), openai);
+            assert_eq!(expected.to_anthropic_str(), anthropic);
+            assert_eq!(expected.to_gemini_str(), gemini);
+        }
+
+        assert_eq!(
+            StopReason::from_openai_str("tool_calls"),
+            StopReason::ToolUse
+        );
+        assert_eq!(
+            StopReason::from_openai_str("function_call"),
+            StopReason::ToolUse
+        );
+        assert_eq!(
+            StopReason::from_anthropic_str("stop_sequence"),
+            StopReason::StopSequence
+        );
+        assert_eq!(StopReason::StopSequence.to_openai_str(), "stop");
+        assert_eq!(
+            StopReason::from_gemini_str("RECITATION"),
+            StopReason::ContentFilter
+        );
+        assert_eq!(
+            StopReason::from_gemini_str("STOP").with_tool_calls(true),
+            StopReason::ToolUse
+        );
+        assert_eq!(
+            StopReason::MaxTokens.with_tool_calls(true),
+            StopReason::MaxTokens
+        );
+        assert_eq!(
+            StopReason::from_openai_str("error").to_anthropic_str(),
+            "end_turn"
+        );
+        assert_eq!(
+            StopReason::from_anthropic_str("pause_turn").to_anthropic_str(),
+            "pause_turn"
+        );
+    }
+
+    #[test]
+    fn test_stop_reason_from_openai_choice() {
+        let choice = serde_json::json!({"finish_reason": "stop", "stop_reason": "\n\nHuman:"});
+        assert_eq!(
+            StopReason::from_openai_choice(&choice),
+            Some((StopReason::StopSequence, Some("\n\nHuman:".to_string())))
+        );
+
+        let choice = serde_json::json!({"finish_reason": "stop", "matched_stop": "END"});
+        assert_eq!(
+            StopReason::from_openai_choice(&choice),
+            Some((StopReason::StopSequence, Some("END".to_string())))
+        );
+
+        // 停止 token ID 不是停止序列
+        let choice = serde_json::json!({"finish_reason": "stop", "stop_reason": 128009});
+        assert_eq!(
+            StopReason::from_openai_choice(&choice),
+            Some((StopReason::EndTurn, None))
+        );
+
+        let choice = serde_json::json!({"finish_reason": "length", "stop_reason": null});
+        assert_eq!(
+            StopReason::from_openai_choice(&choice),
+            Some((StopReason::MaxTokens, None))
+        );
+
+        let choice = serde_json::json!({"finish_reason": null});
+        assert_eq!(StopReason::from_openai_choice(&choice), None);
+    }
+
     #[test]
     fn test_stream_context_block_index() {
         let mut ctx = StreamContext::new();
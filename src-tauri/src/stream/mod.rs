@@ -23,14 +23,17 @@
 //! - `generators`: 前端流格式生成器
 //!   - `openai_sse`: OpenAI SSE 格式生成器
 //!   - `anthropic_sse`: Anthropic SSE 格式生成器
+//! - `tool_args`: 工具调用参数缓冲与 JSON 修复
 
 pub mod events;
 pub mod generators;
 pub mod parsers;
 pub mod pipeline;
+pub mod tool_args;
 
 // 重新导出核心类型
 pub use events::{ContentBlockType, StopReason, StreamContext, StreamEvent};
 pub use generators::{AnthropicSseGenerator, OpenAiSseGenerator};
 pub use parsers::{AwsEventStreamParser, ParserState};
 pub use pipeline::{create_sse_stream, BackendType, FrontendType, PipelineConfig, StreamPipeline};
+pub use tool_args::{repair_json, repair_tool_arguments, ToolArgumentBuffer};
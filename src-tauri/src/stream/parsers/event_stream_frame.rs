@@ -0,0 +1,455 @@
+//! AWS Event Stream 二进制帧解码
+//!
+//! # 帧格式
+//!
+//! ```text
+//! ┌──────────────┬───────────────┬─────────────┬─────────┬─────────┬─────────────┐
+//! │ total_length │ headers_length│ prelude_crc │ headers │ payload │ message_crc │
+//! │   u32 (BE)   │   u32 (BE)    │  u32 (BE)   │         │         │  u32 (BE)   │
+//! └──────────────┴───────────────┴─────────────┴─────────┴─────────┴─────────────┘
+//! ```
+//!
+//! - `prelude_crc` 为前 8 字节的 CRC32，`message_crc` 为除自身外整个帧的 CRC32
+//! - 头部依次为：名称长度 (u8)、名称、值类型 (u8)、值
+//!
+//! 解码器按字节增量输入，可用于完整响应体或流式分块。
+
+/// 前导部分长度（total_length + headers_length + prelude_crc）
+const PRELUDE_LENGTH: usize = 12;
+
+/// 最小帧长度（前导 + message_crc）
+const MIN_FRAME_LENGTH: usize = PRELUDE_LENGTH + 4;
+
+/// 最大帧长度 (16MB)
+pub const MAX_FRAME_LENGTH: usize = 16 * 1024 * 1024;
+
+/// CRC32 (IEEE) 查找表
+const CRC32_TABLE: [u32; 256] = build_crc32_table();
+
+const fn build_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// 计算 CRC32 (IEEE)
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// 头部值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventStreamHeaderValue {
+    Bool(bool),
+    Byte(i8),
+    Short(i16),
+    Integer(i32),
+    Long(i64),
+    Bytes(Vec<u8>),
+    String(String),
+    /// 毫秒时间戳
+    Timestamp(i64),
+    Uuid([u8; 16]),
+}
+
+/// 解码后的帧
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EventStreamFrame {
+    pub headers: Vec<(String, EventStreamHeaderValue)>,
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamFrame {
+    /// 获取字符串类型的头部值
+    pub fn header_str(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find_map(|(n, v)| match v {
+            EventStreamHeaderValue::String(s) if n == name => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// `:message-type` 头部（event / exception / error）
+    pub fn message_type(&self) -> Option<&str> {
+        self.header_str(":message-type")
+    }
+
+    /// `:event-type` 头部（如 assistantResponseEvent、toolUseEvent）
+    pub fn event_type(&self) -> Option<&str> {
+        self.header_str(":event-type")
+    }
+
+    /// 编码为二进制帧
+    pub fn encode(&self) -> Vec<u8> {
+        let mut headers = Vec::new();
+        for (name, value) in &self.headers {
+            headers.push(name.len() as u8);
+            headers.extend_from_slice(name.as_bytes());
+            match value {
+                EventStreamHeaderValue::Bool(true) => headers.push(0),
+                EventStreamHeaderValue::Bool(false) => headers.push(1),
+                EventStreamHeaderValue::Byte(v) => {
+                    headers.push(2);
+                    headers.extend_from_slice(&v.to_be_bytes());
+                }
+                EventStreamHeaderValue::Short(v) => {
+                    headers.push(3);
+                    headers.extend_from_slice(&v.to_be_bytes());
+                }
+                EventStreamHeaderValue::Integer(v) => {
+                    headers.push(4);
+                    headers.extend_from_slice(&v.to_be_bytes());
+                }
+                EventStreamHeaderValue::Long(v) => {
+                    headers.push(5);
+                    headers.extend_from_slice(&v.to_be_bytes());
+                }
+                EventStreamHeaderValue::Bytes(v) => {
+                    headers.push(6);
+                    headers.extend_from_slice(&(v.len() as u16).to_be_bytes());
+                    headers.extend_from_slice(v);
+                }
+                EventStreamHeaderValue::String(v) => {
+                    headers.push(7);
+                    headers.extend_from_slice(&(v.len() as u16).to_be_bytes());
+                    headers.extend_from_slice(v.as_bytes());
+                }
+                EventStreamHeaderValue::Timestamp(v) => {
+                    headers.push(8);
+                    headers.extend_from_slice(&v.to_be_bytes());
+                }
+                EventStreamHeaderValue::Uuid(v) => {
+                    headers.push(9);
+                    headers.extend_from_slice(v);
+                }
+            }
+        }
+
+        let total_length = MIN_FRAME_LENGTH + headers.len() + self.payload.len();
+        let mut frame = Vec::with_capacity(total_length);
+        frame.extend_from_slice(&(total_length as u32).to_be_bytes());
+        frame.extend_from_slice(&(headers.len() as u32).to_be_bytes());
+        let prelude_crc = crc32(&frame);
+        frame.extend_from_slice(&prelude_crc.to_be_bytes());
+        frame.extend_from_slice(&headers);
+        frame.extend_from_slice(&self.payload);
+        let message_crc = crc32(&frame);
+        frame.extend_from_slice(&message_crc.to_be_bytes());
+        frame
+    }
+}
+
+/// 增量帧解码器
+#[derive(Debug, Default)]
+pub struct EventStreamDecoder {
+    /// 未解码的字节
+    buffer: Vec<u8>,
+    /// 缓冲区中已解码部分的长度
+    offset: usize,
+}
+
+impl EventStreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加字节
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // 已解码部分超过一半时压缩缓冲区，避免大响应反复移动数据
+        if self.offset > 0 && self.offset * 2 >= self.buffer.len() {
+            self.buffer.drain(..self.offset);
+            self.offset = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// 尚未解码的字节（不完整的帧，或解码失败位置之后的数据）
+    pub fn remaining(&self) -> &[u8] {
+        &self.buffer[self.offset..]
+    }
+
+    /// 解码下一帧
+    ///
+    /// 数据不足一帧时返回 `Ok(None)`；帧格式或 CRC 错误时返回错误且不消耗数据。
+    pub fn next_frame(&mut self) -> Result<Option<EventStreamFrame>, String> {
+        let data = self.remaining();
+        if data.len() < PRELUDE_LENGTH {
+            return Ok(None);
+        }
+
+        let total_length = read_u32(data, 0) as usize;
+        let headers_length = read_u32(data, 4) as usize;
+        let prelude_crc = read_u32(data, 8);
+        if crc32(&data[..8]) != prelude_crc {
+            return Err("前导 CRC 校验失败".to_string());
+        }
+        if !(MIN_FRAME_LENGTH..=MAX_FRAME_LENGTH).contains(&total_length) {
+            return Err(format!("无效的帧长度: {}", total_length));
+        }
+        if headers_length > total_length - MIN_FRAME_LENGTH {
+            return Err(format!(
+                "头部长度 {} 超出帧长度 {}",
+                headers_length, total_length
+            ));
+        }
+        if data.len() < total_length {
+            return Ok(None);
+        }
+
+        let message_crc = read_u32(data, total_length - 4);
+        if crc32(&data[..total_length - 4]) != message_crc {
+            return Err("消息 CRC 校验失败".to_string());
+        }
+
+        let headers_end = PRELUDE_LENGTH + headers_length;
+        let headers = decode_headers(&data[PRELUDE_LENGTH..headers_end])?;
+        let payload = data[headers_end..total_length - 4].to_vec();
+        self.offset += total_length;
+        Ok(Some(EventStreamFrame { headers, payload }))
+    }
+
+    /// 重置解码器
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.offset = 0;
+    }
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// 解码头部
+fn decode_headers(mut data: &[u8]) -> Result<Vec<(String, EventStreamHeaderValue)>, String> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+        if data.len() < len {
+            return Err("头部数据不完整".to_string());
+        }
+        let slice: &'a [u8] = data;
+        let (head, tail) = slice.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
+
+    let mut headers = Vec::new();
+    while !data.is_empty() {
+        let name_len = take(&mut data, 1)?[0] as usize;
+        let name = std::str::from_utf8(take(&mut data, name_len)?)
+            .map_err(|_| "头部名称不是合法 UTF-8".to_string())?
+            .to_string();
+        let value_type = take(&mut data, 1)?[0];
+        let value = match value_type {
+            0 => EventStreamHeaderValue::Bool(true),
+            1 => EventStreamHeaderValue::Bool(false),
+            2 => EventStreamHeaderValue::Byte(take(&mut data, 1)?[0] as i8),
+            3 => {
+                let b = take(&mut data, 2)?;
+                EventStreamHeaderValue::Short(i16::from_be_bytes([b[0], b[1]]))
+            }
+            4 => {
+                let b = take(&mut data, 4)?;
+                EventStreamHeaderValue::Integer(i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            5 | 8 => {
+                let mut b = [0u8; 8];
+                b.copy_from_slice(take(&mut data, 8)?);
+                let v = i64::from_be_bytes(b);
+                if value_type == 5 {
+                    EventStreamHeaderValue::Long(v)
+                } else {
+                    EventStreamHeaderValue::Timestamp(v)
+                }
+            }
+            6 | 7 => {
+                let b = take(&mut data, 2)?;
+                let len = u16::from_be_bytes([b[0], b[1]]) as usize;
+                let bytes = take(&mut data, len)?;
+                if value_type == 6 {
+                    EventStreamHeaderValue::Bytes(bytes.to_vec())
+                } else {
+                    EventStreamHeaderValue::String(
+                        std::str::from_utf8(bytes)
+                            .map_err(|_| format!("头部 {} 的值不是合法 UTF-8", name))?
+                            .to_string(),
+                    )
+                }
+            }
+            9 => {
+                let mut b = [0u8; 16];
+                b.copy_from_slice(take(&mut data, 16)?);
+                EventStreamHeaderValue::Uuid(b)
+            }
+            other => return Err(format!("未知的头部值类型: {}", other)),
+        };
+        headers.push((name, value));
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn event_frame(event_type: &str, payload: &str) -> EventStreamFrame {
+        EventStreamFrame {
+            headers: vec![
+                (
+                    ":message-type".to_string(),
+                    EventStreamHeaderValue::String("event".to_string()),
+                ),
+                (
+                    ":event-type".to_string(),
+                    EventStreamHeaderValue::String(event_type.to_string()),
+                ),
+                (
+                    ":content-type".to_string(),
+                    EventStreamHeaderValue::String("application/json".to_string()),
+                ),
+            ],
+            payload: payload.as_bytes().to_vec(),
+        }
+    }
+
+    fn decode_all(bytes: &[u8]) -> Result<Vec<EventStreamFrame>, String> {
+        let mut decoder = EventStreamDecoder::new();
+        decoder.feed(bytes);
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.next_frame()? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_roundtrip_all_header_types() {
+        let frame = EventStreamFrame {
+            headers: vec![
+                ("t".to_string(), EventStreamHeaderValue::Bool(true)),
+                ("f".to_string(), EventStreamHeaderValue::Bool(false)),
+                ("b".to_string(), EventStreamHeaderValue::Byte(-1)),
+                ("s".to_string(), EventStreamHeaderValue::Short(-2)),
+                ("i".to_string(), EventStreamHeaderValue::Integer(3)),
+                ("l".to_string(), EventStreamHeaderValue::Long(-4)),
+                ("y".to_string(), EventStreamHeaderValue::Bytes(vec![0, 255])),
+                (
+                    "n".to_string(),
+                    EventStreamHeaderValue::String("值".to_string()),
+                ),
+                (
+                    "m".to_string(),
+                    EventStreamHeaderValue::Timestamp(1_700_000_000_000),
+                ),
+                ("u".to_string(), EventStreamHeaderValue::Uuid([7; 16])),
+            ],
+            payload: b"{\"content\":\"hi\"}".to_vec(),
+        };
+        assert_eq!(decode_all(&frame.encode()).unwrap(), vec![frame]);
+    }
+
+    #[test]
+    fn test_incremental_decoding() {
+        let frames = vec![
+            event_frame("assistantResponseEvent", r#"{"content":"Hello"}"#),
+            event_frame("assistantResponseEvent", r#"{"content":" world"}"#),
+        ];
+        let bytes: Vec<u8> = frames.iter().flat_map(|f| f.encode()).collect();
+
+        let mut decoder = EventStreamDecoder::new();
+        let mut decoded = Vec::new();
+        for chunk in bytes.chunks(5) {
+            decoder.feed(chunk);
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                decoded.push(frame);
+            }
+        }
+        assert_eq!(decoded, frames);
+        assert_eq!(decoded[0].event_type(), Some("assistantResponseEvent"));
+        assert_eq!(decoded[0].message_type(), Some("event"));
+        assert!(decoder.remaining().is_empty());
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let mut bytes = event_frame("toolUseEvent", r#"{"toolUseId":"t1"}"#).encode();
+        let last = bytes.len() - 5;
+        bytes[last] ^= 0xFF;
+        assert!(decode_all(&bytes).unwrap_err().contains("消息 CRC"));
+
+        bytes[0] ^= 0xFF;
+        assert!(decode_all(&bytes).unwrap_err().contains("前导 CRC"));
+
+        // 不是二进制帧（如已被转换为文本的响应）
+        assert!(decode_all(br#"{"content":"hi"}"#).is_err());
+    }
+
+    #[test]
+    fn test_fuzz_mutated_frames() {
+        let mut rng = StdRng::seed_from_u64(4124);
+        let frames: Vec<u8> = (0..8)
+            .flat_map(|i| {
+                event_frame(
+                    "assistantResponseEvent",
+                    &format!(r#"{{"content":"{}"}}"#, i),
+                )
+                .encode()
+            })
+            .collect();
+
+        for _ in 0..2000 {
+            let mut bytes = frames.clone();
+            match rng.gen_range(0..4) {
+                // 随机翻转比特
+                0 => {
+                    for _ in 0..rng.gen_range(1..4) {
+                        let i = rng.gen_range(0..bytes.len());
+                        bytes[i] ^= 1 << rng.gen_range(0..8);
+                    }
+                }
+                // 随机截断
+                1 => bytes.truncate(rng.gen_range(0..bytes.len())),
+                // 随机插入垃圾数据
+                2 => {
+                    let i = rng.gen_range(0..bytes.len());
+                    let garbage: Vec<u8> = (0..rng.gen_range(1..32)).map(|_| rng.gen()).collect();
+                    bytes.splice(i..i, garbage);
+                }
+                // 完全随机的数据
+                _ => bytes = (0..rng.gen_range(0..256)).map(|_| rng.gen()).collect(),
+            }
+
+            // 不得 panic；解码出的帧必须是原始帧之一
+            let mut decoder = EventStreamDecoder::new();
+            for chunk in bytes.chunks(rng.gen_range(1..64)) {
+                decoder.feed(chunk);
+                while let Ok(Some(frame)) = decoder.next_frame() {
+                    assert_eq!(frame.event_type(), Some("assistantResponseEvent"));
+                    assert!(frame.payload.starts_with(br#"{"content":""#));
+                }
+            }
+        }
+    }
+}
@@ -9,5 +9,7 @@
 //! - Anthropic SSE (待实现)
 
 pub mod aws_event_stream;
+pub mod event_stream_frame;
 
 pub use aws_event_stream::{AwsEventStreamParser, ParserState};
+pub use event_stream_frame::{EventStreamDecoder, EventStreamFrame, EventStreamHeaderValue};
@@ -17,6 +17,7 @@
 use crate::stream::events::StreamEvent;
 use crate::stream::generators::{AnthropicSseGenerator, OpenAiSseGenerator};
 use crate::stream::parsers::AwsEventStreamParser;
+use crate::stream::tool_args::ToolArgumentBuffer;
 use bytes::Bytes;
 use futures::{Stream, StreamExt};
 
@@ -106,6 +107,8 @@ pub struct StreamPipeline {
     aws_parser: Option<AwsEventStreamParser>,
     /// SSE 生成器
     generator: SseGenerator,
+    /// 工具调用参数缓冲（结束时输出修复后的参数）
+    tool_args: ToolArgumentBuffer,
 }
 
 impl StreamPipeline {
@@ -143,6 +146,7 @@ impl StreamPipeline {
             config,
             aws_parser,
             generator,
+            tool_args: ToolArgumentBuffer::new(),
         }
     }
 
@@ -162,24 +166,35 @@ impl StreamPipeline {
     ///
     /// 最终的 SSE 字符串列表
     pub fn finish(&mut self) -> Vec<String> {
-        let events = self.finish_parsing();
+        let mut events = self.finish_parsing();
+        events.extend(self.tool_args.flush());
         self.generate_sse(&events)
     }
 
     /// 解析字节为 StreamEvent
     fn parse_bytes(&mut self, bytes: &[u8]) -> Vec<StreamEvent> {
-        match &mut self.aws_parser {
+        let events = match &mut self.aws_parser {
             Some(parser) => parser.process(bytes),
             None => Vec::new(), // TODO: 支持其他后端格式的解析
-        }
+        };
+        self.repair_tool_args(events)
     }
 
     /// 完成解析
     fn finish_parsing(&mut self) -> Vec<StreamEvent> {
-        match &mut self.aws_parser {
+        let events = match &mut self.aws_parser {
             Some(parser) => parser.finish(),
             None => Vec::new(),
-        }
+        };
+        self.repair_tool_args(events)
+    }
+
+    /// 缓冲工具调用参数片段，结束时输出修复后的参数
+    fn repair_tool_args(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        events
+            .into_iter()
+            .flat_map(|event| self.tool_args.process(event))
+            .collect()
     }
 
     /// 将 StreamEvent 转换为 SSE 字符串
@@ -202,6 +217,7 @@ impl StreamPipeline {
         if let Some(ref mut parser) = self.aws_parser {
             parser.reset();
         }
+        self.tool_args.reset();
         self.generator = match self.config.frontend {
             FrontendType::Anthropic => {
                 SseGenerator::Anthropic(AnthropicSseGenerator::new(self.config.model.clone()))
@@ -299,14 +315,16 @@ mod tests {
         assert!(sse.iter().any(|s| s.contains("tool_use")));
         assert!(sse.iter().any(|s| s.contains("read_file")));
 
-        // 工具参数
+        // 工具参数（缓冲到工具调用结束）
         let bytes = br#"{"toolUseId":"tool_123","input":"{\"path\":"}"#;
         let sse = pipeline.process_chunk(bytes);
-        assert!(sse.iter().any(|s| s.contains("input_json_delta")));
+        assert!(!sse.iter().any(|s| s.contains("input_json_delta")));
 
-        // 工具结束
+        // 工具结束，输出修复后的参数
         let bytes = br#"{"toolUseId":"tool_123","stop":true}"#;
         let sse = pipeline.process_chunk(bytes);
+        assert!(sse.iter().any(|s| s.contains("input_json_delta")));
+        assert!(sse.iter().any(|s| s.contains(r#"{\"path\":null}"#)));
         assert!(sse.iter().any(|s| s.contains("content_block_stop")));
     }
 
@@ -0,0 +1,363 @@
+//! 工具调用参数修复
+//!
+//! 上游按片段输出工具调用参数，拼接后可能不是合法 JSON（截断、重复发送、多余的逗号等）。
+//! 本模块按工具调用 ID 缓冲参数片段，在工具调用结束时校验并修复，
+//! 再以单个增量事件输出；无法修复时原样输出并记录片段，便于排查上游问题。
+
+use crate::stream::events::StreamEvent;
+use std::collections::HashMap;
+
+/// 日志中单个片段的最大长度
+const MAX_LOGGED_FRAGMENT_CHARS: usize = 200;
+
+/// 修复不完整的 JSON
+///
+/// - 合法 JSON 原样返回
+/// - 空字符串视为空对象
+/// - 完整值之后的多余内容（如重复发送）被丢弃
+/// - 未闭合的字符串、对象、数组被补全，末尾多余的逗号被移除，缺失的值补为 `null`
+///
+/// 修复后仍不是合法 JSON 时返回 None。
+pub fn repair_json(input: &str) -> Option<String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Some("{}".to_string());
+    }
+    if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return Some(trimmed.to_string());
+    }
+
+    // 完整值之后有多余内容
+    let mut values = serde_json::Deserializer::from_str(trimmed).into_iter::<serde_json::Value>();
+    if let Some(Ok(value)) = values.next() {
+        if value.is_object() || value.is_array() {
+            return Some(value.to_string());
+        }
+    }
+
+    let repaired = close_json(trimmed);
+    serde_json::from_str::<serde_json::Value>(&repaired)
+        .ok()
+        .map(|_| repaired)
+}
+
+/// 容器状态
+enum Frame {
+    Object {
+        /// 下一个字符串是键
+        expecting_key: bool,
+        /// 已读取键，尚未读取冒号
+        after_key: bool,
+    },
+    Array,
+}
+
+/// 补全截断的 JSON
+fn close_json(input: &str) -> String {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut in_string = false;
+    let mut string_is_key = false;
+    let mut escaped = false;
+
+    for c in input.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+                end_string(&mut stack, string_is_key);
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                string_is_key = matches!(
+                    stack.last(),
+                    Some(Frame::Object {
+                        expecting_key: true,
+                        ..
+                    })
+                );
+            }
+            '{' => stack.push(Frame::Object {
+                expecting_key: true,
+                after_key: false,
+            }),
+            '[' => stack.push(Frame::Array),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ':' => {
+                if let Some(Frame::Object { after_key, .. }) = stack.last_mut() {
+                    *after_key = false;
+                }
+            }
+            ',' => {
+                if let Some(Frame::Object { expecting_key, .. }) = stack.last_mut() {
+                    *expecting_key = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut output = input.to_string();
+    if in_string {
+        // 丢弃未完成的转义序列
+        if escaped {
+            output.pop();
+        }
+        output.push('"');
+        end_string(&mut stack, string_is_key);
+    }
+
+    let trimmed_len = output.trim_end().len();
+    output.truncate(trimmed_len);
+    complete_literal(&mut output);
+    if output.ends_with(',') {
+        output.pop();
+    }
+    if output.ends_with(':') {
+        output.push_str("null");
+    } else if let Some(Frame::Object {
+        after_key: true, ..
+    }) = stack.last()
+    {
+        output.push_str(":null");
+    }
+
+    for frame in stack.iter().rev() {
+        output.push(match frame {
+            Frame::Object { .. } => '}',
+            Frame::Array => ']',
+        });
+    }
+    output
+}
+
+/// 字符串结束时更新容器状态
+fn end_string(stack: &mut [Frame], string_is_key: bool) {
+    if !string_is_key {
+        return;
+    }
+    if let Some(Frame::Object {
+        expecting_key,
+        after_key,
+    }) = stack.last_mut()
+    {
+        *expecting_key = false;
+        *after_key = true;
+    }
+}
+
+/// 补全截断的字面量和数字（如 `tru`、`1.`）
+fn complete_literal(output: &mut String) {
+    let token_start = output
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let token = output[token_start..].to_string();
+    if token.is_empty() {
+        return;
+    }
+    for literal in ["true", "false", "null"] {
+        if literal.starts_with(token.as_str()) {
+            output.truncate(token_start);
+            output.push_str(literal);
+            return;
+        }
+    }
+    if token.ends_with(['.', '-', '+', 'e', 'E']) {
+        output.push('0');
+    }
+}
+
+/// 校验并修复工具调用参数
+///
+/// 返回修复后的参数；无法修复时返回原始参数并记录错误日志。
+pub fn repair_tool_arguments(tool_id: &str, arguments: &str) -> String {
+    match repair_json(arguments) {
+        Some(repaired) => {
+            if repaired != arguments.trim() && !arguments.trim().is_empty() {
+                tracing::warn!(
+                    "[TOOL_ARGS] 工具调用参数不是合法 JSON，已修复: tool_id={} raw={} repaired={}",
+                    tool_id,
+                    truncate_for_log(arguments),
+                    truncate_for_log(&repaired)
+                );
+            }
+            repaired
+        }
+        None => {
+            tracing::error!(
+                "[TOOL_ARGS] 工具调用参数无法修复: tool_id={} raw={}",
+                tool_id,
+                truncate_for_log(arguments)
+            );
+            arguments.to_string()
+        }
+    }
+}
+
+fn truncate_for_log(s: &str) -> String {
+    if s.chars().count() > MAX_LOGGED_FRAGMENT_CHARS {
+        let truncated: String = s.chars().take(MAX_LOGGED_FRAGMENT_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        s.to_string()
+    }
+}
+
+/// 工具调用参数缓冲
+///
+/// 放在解析器和生成器之间：缓冲 `ToolUseInputDelta`，
+/// 在 `ToolUseStop` 或 `MessageStop` 时输出修复后的完整参数。
+#[derive(Debug, Default)]
+pub struct ToolArgumentBuffer {
+    /// 工具调用 ID -> 参数片段
+    fragments: HashMap<String, Vec<String>>,
+    /// 工具调用开始顺序（用于按顺序刷新）
+    order: Vec<String>,
+}
+
+impl ToolArgumentBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理事件
+    pub fn process(&mut self, event: StreamEvent) -> Vec<StreamEvent> {
+        match event {
+            StreamEvent::ToolUseStart { ref id, .. } => {
+                if !self.fragments.contains_key(id) {
+                    self.fragments.insert(id.clone(), Vec::new());
+                    self.order.push(id.clone());
+                }
+                vec![event]
+            }
+            StreamEvent::ToolUseInputDelta { id, partial_json } => {
+                if !self.fragments.contains_key(&id) {
+                    self.order.push(id.clone());
+                }
+                self.fragments.entry(id).or_default().push(partial_json);
+                Vec::new()
+            }
+            StreamEvent::ToolUseStop { ref id } => {
+                let mut events = self.take(id).into_iter().collect::<Vec<_>>();
+                events.push(event);
+                events
+            }
+            StreamEvent::MessageStop { .. } => {
+                let mut events = self.flush();
+                events.push(event);
+                events
+            }
+            other => vec![other],
+        }
+    }
+
+    /// 输出所有未结束的工具调用参数
+    pub fn flush(&mut self) -> Vec<StreamEvent> {
+        let ids = self.order.clone();
+        ids.iter().filter_map(|id| self.take(id)).collect()
+    }
+
+    /// 重置状态
+    pub fn reset(&mut self) {
+        self.fragments.clear();
+        self.order.clear();
+    }
+
+    /// 取出并修复指定工具调用的参数
+    fn take(&mut self, id: &str) -> Option<StreamEvent> {
+        self.order.retain(|existing| existing != id);
+        let fragments = self.fragments.remove(id)?;
+        let arguments = fragments.concat();
+        let repaired = repair_tool_arguments(id, &arguments);
+        if repaired != arguments.trim() && fragments.len() > 1 {
+            let logged: Vec<String> = fragments.iter().map(|f| truncate_for_log(f)).collect();
+            tracing::warn!(
+                "[TOOL_ARGS] 工具调用参数片段: tool_id={} fragments={:?}",
+                id,
+                logged
+            );
+        }
+        Some(StreamEvent::ToolUseInputDelta {
+            id: id.to_string(),
+            partial_json: repaired,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::events::StopReason;
+
+    #[test]
+    fn test_repair_json() {
+        assert_eq!(repair_json(r#"{"a":1}"#).unwrap(), r#"{"a":1}"#);
+        assert_eq!(repair_json("").unwrap(), "{}");
+        assert_eq!(
+            repair_json(r#"{"path":"/tmp/a"#).unwrap(),
+            r#"{"path":"/tmp/a"}"#
+        );
+        assert_eq!(repair_json(r#"{"a":[1,2,"#).unwrap(), r#"{"a":[1,2]}"#);
+        assert_eq!(repair_json(r#"{"a":1,"b""#).unwrap(), r#"{"a":1,"b":null}"#);
+        assert_eq!(repair_json(r#"{"a":"#).unwrap(), r#"{"a":null}"#);
+        assert_eq!(repair_json(r#"{"a":tr"#).unwrap(), r#"{"a":true}"#);
+        assert_eq!(repair_json(r#"{"a":1.}"#), None);
+        assert_eq!(repair_json(r#"{"a":1."#).unwrap(), r#"{"a":1.0}"#);
+        assert_eq!(repair_json(r#"{"a":"x\"#).unwrap(), r#"{"a":"x"}"#);
+        assert_eq!(repair_json(r#"{"a":1}{"a":1}"#).unwrap(), r#"{"a":1}"#);
+        assert_eq!(repair_json("not json"), None);
+    }
+
+    #[test]
+    fn test_buffer_emits_repaired_arguments() {
+        let mut buffer = ToolArgumentBuffer::new();
+        let mut events = buffer.process(StreamEvent::ToolUseStart {
+            id: "t1".to_string(),
+            name: "read_file".to_string(),
+        });
+        for fragment in [r#"{"path":"#, r#""/tmp/a.txt","#] {
+            events.extend(buffer.process(StreamEvent::ToolUseInputDelta {
+                id: "t1".to_string(),
+                partial_json: fragment.to_string(),
+            }));
+        }
+        assert_eq!(events.len(), 1);
+
+        events.extend(buffer.process(StreamEvent::ToolUseStop {
+            id: "t1".to_string(),
+        }));
+        assert_eq!(
+            events[1],
+            StreamEvent::ToolUseInputDelta {
+                id: "t1".to_string(),
+                partial_json: r#"{"path":"/tmp/a.txt"}"#.to_string(),
+            }
+        );
+        assert!(matches!(events[2], StreamEvent::ToolUseStop { .. }));
+    }
+
+    #[test]
+    fn test_buffer_flushes_on_message_stop() {
+        let mut buffer = ToolArgumentBuffer::new();
+        buffer.process(StreamEvent::ToolUseInputDelta {
+            id: "t1".to_string(),
+            partial_json: r#"{"q":"rust"#.to_string(),
+        });
+        let events = buffer.process(StreamEvent::MessageStop {
+            stop_reason: StopReason::ToolUse,
+        });
+        assert_eq!(events.len(), 2);
+        assert!(
+            matches!(&events[0], StreamEvent::ToolUseInputDelta { partial_json, .. } if partial_json == r#"{"q":"rust"}"#)
+        );
+        assert!(buffer.flush().is_empty());
+    }
+}
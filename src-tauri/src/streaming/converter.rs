@@ -11,6 +11,7 @@
 //! - 需求 3.3: Anthropic SSE 到 OpenAI SSE 转换
 //! - 需求 3.5: 处理工具调用参数中的部分 JSON
 
+use crate::stream::StopReason;
 use crate::streaming::aws_parser::{AwsEvent, AwsEventStreamParser};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -164,6 +165,8 @@ pub struct StreamConverter {
     message_started: bool,
     /// 累积的内容（用于重建完整响应）
     accumulated_content: String,
+    /// 上游返回的停止原因（Anthropic SSE 的 message_delta）
+    stop_reason: Option<StopReason>,
 }
 
 impl StreamConverter {
@@ -186,6 +189,7 @@ impl StreamConverter {
             next_content_block_index: 0,
             message_started: false,
             accumulated_content: String::new(),
+            stop_reason: None,
         }
     }
 
@@ -222,6 +226,15 @@ impl StreamConverter {
         self.next_content_block_index = 0;
         self.message_started = false;
         self.accumulated_content.clear();
+        self.stop_reason = None;
+    }
+
+    /// 最终停止原因：上游未返回时按是否有工具调用推断
+    fn final_stop_reason(&self) -> StopReason {
+        self.stop_reason
+            .clone()
+            .unwrap_or_default()
+            .with_tool_calls(!self.tool_accumulators.is_empty())
     }
 
     /// 转换 chunk
@@ -554,8 +567,18 @@ impl StreamConverter {
                                     }
                                 }
                             }
+                            "message_delta" => {
+                                if let Some(reason) = event
+                                    .get("delta")
+                                    .and_then(|d| d.get("stop_reason"))
+                                    .and_then(|r| r.as_str())
+                                {
+                                    self.stop_reason = Some(StopReason::from_anthropic_str(reason));
+                                }
+                            }
                             "message_stop" => {
-                                sse_events.push(self.create_openai_finish_chunk("stop"));
+                                let finish_reason = self.final_stop_reason().to_openai_str();
+                                sse_events.push(self.create_openai_finish_chunk(finish_reason));
                                 sse_events.push("data: [DONE]\n\n".to_string());
                             }
                             _ => {}
@@ -588,11 +611,7 @@ impl StreamConverter {
                 events
             }
             StreamFormat::OpenAiSse => {
-                let finish_reason = if self.tool_accumulators.is_empty() {
-                    "stop"
-                } else {
-                    "tool_calls"
-                };
+                let finish_reason = self.final_stop_reason().to_openai_str();
                 vec![
                     self.create_openai_finish_chunk(finish_reason),
                     "data: [DONE]\n\n".to_string(),
@@ -695,7 +714,7 @@ impl StreamConverter {
         let event = serde_json::json!({
             "type": "message_delta",
             "delta": {
-                "stop_reason": "end_turn",
+                "stop_reason": self.final_stop_reason().to_anthropic_str(),
                 "stop_sequence": null
             },
             "usage": {
@@ -1084,6 +1103,30 @@ mod tests {
         assert_eq!(converter.state(), &ConverterState::Completed);
     }
 
+    #[test]
+    fn test_anthropic_to_openai_finish_reason() {
+        let cases = [
+            ("end_turn", "\"finish_reason\":\"stop\""),
+            ("max_tokens", "\"finish_reason\":\"length\""),
+            ("stop_sequence", "\"finish_reason\":\"stop\""),
+            ("refusal", "\"finish_reason\":\"content_filter\""),
+        ];
+        for (stop_reason, expected) in cases {
+            let mut converter = StreamConverter::with_model(
+                StreamFormat::AnthropicSse,
+                StreamFormat::OpenAiSse,
+                "test-model",
+            );
+            let delta = format!(
+                "event: message_delta\ndata: {{\"type\":\"message_delta\",\"delta\":{{\"stop_reason\":\"{stop_reason}\"}}}}\n\n"
+            );
+            assert!(converter.convert(delta.as_bytes()).is_empty());
+            let events =
+                converter.convert(b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n");
+            assert!(events[0].contains(expected), "{stop_reason}: {}", events[0]);
+        }
+    }
+
     #[test]
     fn test_partial_json_accumulator() {
         let mut acc = PartialJsonAccumulator::new();
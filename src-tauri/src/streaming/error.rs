@@ -8,6 +8,7 @@
 //! - 需求 6.2: 超时错误处理
 //! - 需求 6.3: Provider 错误转发
 
+use crate::streaming::converter::StreamFormat;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -91,6 +92,7 @@ impl From<std::io::Error> for StreamError {
 
 impl From<reqwest::Error> for StreamError {
     fn from(err: reqwest::Error) -> Self {
+        crate::resilience::record_error(&err);
         if err.is_timeout() {
             StreamError::Timeout
         } else if err.is_connect() {
@@ -190,6 +192,34 @@ impl StreamError {
         format!("event: error\ndata: {}\n\n", error_json)
     }
 
+    /// 按目标流式格式转换为 SSE 错误事件
+    ///
+    /// 流已开始后无法再返回 HTTP 错误，客户端 SDK 依赖格式一致的错误事件结束流。
+    pub fn to_sse_error_for(&self, format: StreamFormat) -> String {
+        let error_type = match format {
+            StreamFormat::AnthropicSse => self.anthropic_error_type(),
+            StreamFormat::OpenAiSse | StreamFormat::AwsEventStream => self.error_type_string(),
+        };
+        sse_error_event(format, error_type, &self.to_string())
+    }
+
+    /// 获取 Anthropic 错误类型
+    fn anthropic_error_type(&self) -> &'static str {
+        match self {
+            StreamError::ProviderError { status, .. } => match status {
+                400 => "invalid_request_error",
+                401 => "authentication_error",
+                403 => "permission_error",
+                404 => "not_found_error",
+                413 => "request_too_large",
+                429 => "rate_limit_error",
+                529 => "overloaded_error",
+                _ => "api_error",
+            },
+            _ => "api_error",
+        }
+    }
+
     /// 获取错误类型字符串
     fn error_type_string(&self) -> &'static str {
         match self {
@@ -204,6 +234,35 @@ impl StreamError {
     }
 }
 
+/// 构建 SSE 错误事件
+///
+/// - Anthropic：`event: error`，`data` 为 `{"type": "error", "error": {...}}`
+/// - OpenAI：无事件名，`data` 为 `{"error": {...}}`
+pub fn sse_error_event(format: StreamFormat, error_type: &str, message: &str) -> String {
+    match format {
+        StreamFormat::AnthropicSse => format!(
+            "event: error\ndata: {}\n\n",
+            serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": error_type,
+                    "message": message,
+                }
+            })
+        ),
+        StreamFormat::OpenAiSse | StreamFormat::AwsEventStream => format!(
+            "data: {}\n\n",
+            serde_json::json!({
+                "error": {
+                    "type": error_type,
+                    "code": error_type,
+                    "message": message,
+                }
+            })
+        ),
+    }
+}
+
 // ============================================================================
 // 测试模块
 // ============================================================================
@@ -278,4 +337,21 @@ mod tests {
         assert!(sse.starts_with("event: error\n"));
         assert!(sse.contains("timeout"));
     }
+
+    #[test]
+    fn test_stream_error_to_sse_error_for_format() {
+        let err = StreamError::provider_error(429, "rate limited");
+        let sse = err.to_sse_error_for(StreamFormat::AnthropicSse);
+        let data: serde_json::Value =
+            serde_json::from_str(sse.strip_prefix("event: error\ndata: ").unwrap().trim()).unwrap();
+        assert_eq!(data["type"], "error");
+        assert_eq!(data["error"]["type"], "rate_limit_error");
+
+        let sse = StreamError::Timeout.to_sse_error_for(StreamFormat::OpenAiSse);
+        assert!(sse.ends_with("\n\n"));
+        let data: serde_json::Value =
+            serde_json::from_str(sse.strip_prefix("data: ").unwrap().trim()).unwrap();
+        assert_eq!(data["error"]["type"], "timeout");
+        assert_eq!(data["error"]["message"], "流式响应超时");
+    }
 }
@@ -525,7 +525,7 @@ impl ManagedStream {
             "流式传输错误"
         );
 
-        error.to_sse_error()
+        error.to_sse_error_for(self.context.target_format)
     }
 
     /// 处理 Provider 错误
@@ -31,7 +31,7 @@ pub use converter::{
     extract_content_from_sse, extract_tool_calls_from_sse, ConverterState, PartialJsonAccumulator,
     StreamConverter, StreamFormat,
 };
-pub use error::StreamError;
+pub use error::{sse_error_event, StreamError};
 pub use manager::{
     collect_stream_content, create_flow_monitor_callback, with_timeout, FlowMonitorCallback,
     ManagedStream, ManagedStreamWithCallback, StreamConfig, StreamContext, StreamEvent,
@@ -1,19 +1,24 @@
 //! 监控与日志模块
 //!
-//! 提供请求日志记录、统计聚合和 Token 追踪功能
+//! 提供请求日志记录、统计聚合和 Token 追踪功能；请求处理器通过 `TelemetryRecorder` 异步写入
 
 mod logger;
+mod recorder;
 mod stats;
 mod tokens;
 mod types;
 
 pub use logger::{LogRotationConfig, LoggerError, RequestLogger};
-pub use stats::StatsAggregator;
+pub use recorder::{TelemetryRecorder, TelemetrySinks};
+pub use stats::{StatsAggregator, DEFAULT_LATENCY_WINDOW_MINUTES};
 pub use tokens::{
-    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenSource, TokenStatsSummary,
-    TokenTracker, TokenUsageRecord,
+    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenEstimator, TokenSource,
+    TokenStatsSummary, TokenTracker, TokenUsageRecord, UserTokenStats,
+};
+pub use types::{
+    LatencyPercentiles, LatencyStats, ModelStats, ProviderStats, RequestLog, RequestStatus,
+    StatsSummary, TimeRange,
 };
-pub use types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,225 @@
+//! 遥测异步写入
+//!
+//! 请求处理器只把记录投递到有界队列，由后台线程批量写入统计聚合器、Token 追踪器、
+//! 请求日志和 SQLite，遥测写入变慢时不会增加响应延迟。队列已满时丢弃记录并计数。
+
+use super::{RequestLog, RequestLogger, StatsAggregator, TokenTracker, TokenUsageRecord};
+use crate::database::dao::telemetry::TelemetryDao;
+use crate::database::DbConnection;
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+
+/// 队列容量
+const QUEUE_CAPACITY: usize = 4096;
+
+/// 单批最多写入的记录数
+const MAX_BATCH_SIZE: usize = 256;
+
+/// 遥测记录
+#[derive(Debug)]
+enum TelemetryEvent {
+    Request(RequestLog),
+    TokenUsage(TokenUsageRecord),
+}
+
+/// 遥测记录的写入目标
+pub struct TelemetrySinks {
+    pub stats: Arc<RwLock<StatsAggregator>>,
+    pub tokens: Arc<RwLock<TokenTracker>>,
+    /// 请求日志记录器（前端日志列表）
+    pub request_logger: Option<Arc<RequestLogger>>,
+    /// 遥测持久化数据库（未启用持久化时为 None）
+    pub db: Option<DbConnection>,
+}
+
+/// 遥测记录器
+///
+/// 所有克隆共享同一个队列；全部克隆释放后后台线程写完剩余记录后退出。
+#[derive(Clone)]
+pub struct TelemetryRecorder {
+    tx: SyncSender<TelemetryEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl TelemetryRecorder {
+    /// 创建记录器并启动后台写入线程
+    pub fn spawn(sinks: TelemetrySinks) -> Self {
+        let (tx, rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let spawned = std::thread::Builder::new()
+            .name("telemetry-writer".to_string())
+            .spawn(move || run_writer(rx, sinks));
+        if let Err(e) = spawned {
+            tracing::error!("[TELEMETRY] 启动遥测写入线程失败: {}", e);
+        }
+        Self {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// 记录请求日志（不阻塞）
+    pub fn record_request(&self, log: RequestLog) {
+        self.send(TelemetryEvent::Request(log));
+    }
+
+    /// 记录 Token 使用量（不阻塞）
+    pub fn record_token_usage(&self, record: TokenUsageRecord) {
+        self.send(TelemetryEvent::TokenUsage(record));
+    }
+
+    /// 因队列已满或写入线程退出而丢弃的记录数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, event: TelemetryEvent) {
+        match self.tx.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                // 避免队列持续积压时刷屏
+                if dropped.is_power_of_two() {
+                    tracing::warn!("[TELEMETRY] 遥测队列已满，已丢弃 {} 条记录", dropped);
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn run_writer(rx: Receiver<TelemetryEvent>, sinks: TelemetrySinks) {
+    while let Ok(first) = rx.recv() {
+        let mut batch = vec![first];
+        while batch.len() < MAX_BATCH_SIZE {
+            match rx.try_recv() {
+                Ok(event) => batch.push(event),
+                Err(_) => break,
+            }
+        }
+        write_batch(&sinks, batch);
+    }
+}
+
+fn write_batch(sinks: &TelemetrySinks, batch: Vec<TelemetryEvent>) {
+    let mut logs = Vec::new();
+    let mut usages = Vec::new();
+    for event in batch {
+        match event {
+            TelemetryEvent::Request(log) => logs.push(log),
+            TelemetryEvent::TokenUsage(record) => usages.push(record),
+        }
+    }
+
+    // 持久化到数据库（历史统计）
+    if let Some(db) = &sinks.db {
+        if let Ok(conn) = db.lock() {
+            if let Err(e) = TelemetryDao::insert_batch(&conn, &logs, &usages) {
+                tracing::warn!(
+                    "[TELEMETRY] 持久化遥测记录失败（{} 条请求日志，{} 条 Token 记录）: {}",
+                    logs.len(),
+                    usages.len(),
+                    e
+                );
+            }
+        }
+    }
+
+    // 记录到请求日志记录器（用于前端日志列表显示）
+    if let Some(logger) = &sinks.request_logger {
+        for log in &logs {
+            let _ = logger.record(log.clone());
+        }
+    }
+
+    if !logs.is_empty() {
+        let stats = sinks.stats.write();
+        for log in logs {
+            stats.record(log);
+        }
+    }
+
+    if !usages.is_empty() {
+        let tokens = sinks.tokens.write();
+        for record in usages {
+            tokens.record(record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::TokenSource;
+    use crate::ProviderType;
+    use std::time::{Duration, Instant};
+
+    fn sinks() -> TelemetrySinks {
+        TelemetrySinks {
+            stats: Arc::new(RwLock::new(StatsAggregator::with_defaults())),
+            tokens: Arc::new(RwLock::new(TokenTracker::with_defaults())),
+            request_logger: None,
+            db: None,
+        }
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "等待遥测写入超时");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_records_written_in_background() {
+        let sinks = sinks();
+        let stats = sinks.stats.clone();
+        let tokens = sinks.tokens.clone();
+        let recorder = TelemetryRecorder::spawn(sinks);
+
+        for i in 0..10 {
+            let mut log = RequestLog::new(
+                format!("req-{i}"),
+                ProviderType::Kiro,
+                "claude-sonnet-4-5".to_string(),
+                false,
+            );
+            log.mark_success(100, 200);
+            recorder.record_request(log);
+        }
+        recorder.record_token_usage(TokenUsageRecord::new(
+            "t1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+            10,
+            20,
+            TokenSource::Actual,
+        ));
+
+        wait_until(|| stats.read().len() == 10 && !tokens.read().is_empty());
+        assert_eq!(recorder.dropped(), 0);
+    }
+
+    #[test]
+    fn test_full_queue_drops_records() {
+        // 不启动写入线程，队列写满后丢弃
+        let (tx, _rx) = mpsc::sync_channel(1);
+        let recorder = TelemetryRecorder {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        for i in 0..3 {
+            recorder.record_request(RequestLog::new(
+                format!("req-{i}"),
+                ProviderType::Kiro,
+                "claude-sonnet-4-5".to_string(),
+                false,
+            ));
+        }
+        assert_eq!(recorder.dropped(), 2);
+    }
+}
@@ -3,13 +3,17 @@
 //! 提供请求统计的聚合、分组和查询功能
 
 use crate::telemetry::types::{
-    ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange,
+    LatencyPercentiles, LatencyStats, ModelStats, ProviderStats, RequestLog, RequestStatus,
+    StatsSummary, TimeRange,
 };
 use crate::ProviderType;
 use chrono::{Duration, Utc};
 use parking_lot::RwLock;
 use std::collections::{HashMap, VecDeque};
 
+/// 延迟分位数默认滑动窗口（分钟）
+pub const DEFAULT_LATENCY_WINDOW_MINUTES: i64 = 15;
+
 /// 统计聚合器
 ///
 /// 管理请求日志的统计聚合，支持按时间范围、Provider 和模型分组统计
@@ -260,3 +264,105 @@ impl StatsAggregator {
         ModelStats::from_logs(model.to_string(), &filtered)
     }
 }
+
+// ========== 延迟分位数 ==========
+
+impl StatsAggregator {
+    /// 计算滑动窗口内按 (Provider, 模型, 凭证) 分组的延迟分位数
+    ///
+    /// 只统计成功的请求，失败 / 超时请求的耗时不代表上游的正常响应延迟。
+    ///
+    /// # Arguments
+    /// * `window` - 滑动窗口时长（从当前时间往前）
+    pub fn latency_percentiles(&self, window: Duration) -> Vec<LatencyStats> {
+        let cutoff = Utc::now() - window;
+        let mut grouped: HashMap<(ProviderType, String, Option<String>), Vec<u64>> = HashMap::new();
+        {
+            let logs = self.logs.read();
+            for log in logs
+                .iter()
+                .filter(|l| l.timestamp >= cutoff && l.is_success())
+            {
+                grouped
+                    .entry((log.provider, log.model.clone(), log.credential_id.clone()))
+                    .or_default()
+                    .push(log.duration_ms);
+            }
+        }
+
+        let mut stats: Vec<LatencyStats> = grouped
+            .into_iter()
+            .filter_map(|((provider, model, credential_id), mut samples)| {
+                LatencyPercentiles::from_samples(&mut samples).map(|percentiles| LatencyStats {
+                    provider,
+                    model,
+                    credential_id,
+                    percentiles,
+                })
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            (a.provider.to_string(), &a.model, &a.credential_id).cmp(&(
+                b.provider.to_string(),
+                &b.model,
+                &b.credential_id,
+            ))
+        });
+        stats
+    }
+
+    /// 计算滑动窗口内指定 Provider 各凭证的延迟分位数（跨模型合并）
+    ///
+    /// 用于最低延迟负载均衡策略。
+    ///
+    /// # Arguments
+    /// * `provider` - Provider 类型
+    /// * `window` - 滑动窗口时长
+    pub fn credential_latencies(
+        &self,
+        provider: ProviderType,
+        window: Duration,
+    ) -> HashMap<String, LatencyPercentiles> {
+        self.latencies_by_credential(window, |p| p == provider)
+    }
+
+    /// 计算滑动窗口内所有凭证的延迟分位数（跨 Provider、模型合并）
+    ///
+    /// 窗口内没有成功请求的凭证不在结果中。
+    pub fn all_credential_latencies(
+        &self,
+        window: Duration,
+    ) -> HashMap<String, LatencyPercentiles> {
+        self.latencies_by_credential(window, |_| true)
+    }
+
+    fn latencies_by_credential(
+        &self,
+        window: Duration,
+        provider_filter: impl Fn(ProviderType) -> bool,
+    ) -> HashMap<String, LatencyPercentiles> {
+        let cutoff = Utc::now() - window;
+        let mut grouped: HashMap<String, Vec<u64>> = HashMap::new();
+        {
+            let logs = self.logs.read();
+            for log in logs
+                .iter()
+                .filter(|l| provider_filter(l.provider) && l.timestamp >= cutoff && l.is_success())
+            {
+                if let Some(credential_id) = &log.credential_id {
+                    grouped
+                        .entry(credential_id.clone())
+                        .or_default()
+                        .push(log.duration_ms);
+                }
+            }
+        }
+
+        grouped
+            .into_iter()
+            .filter_map(|(credential_id, mut samples)| {
+                LatencyPercentiles::from_samples(&mut samples).map(|p| (credential_id, p))
+            })
+            .collect()
+    }
+}
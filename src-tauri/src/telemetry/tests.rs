@@ -3,7 +3,8 @@
 //! 使用 proptest 进行属性测试
 
 use crate::telemetry::{
-    LogRotationConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator, TimeRange,
+    LatencyPercentiles, LogRotationConfig, RequestLog, RequestLogger, RequestStatus,
+    StatsAggregator, TimeRange,
 };
 use crate::ProviderType;
 use chrono::{Duration, Utc};
@@ -841,3 +842,80 @@ fn test_stats_aggregator_max_logs_limit() {
     // 验证日志数量不超过限制
     assert_eq!(aggregator.len(), 10);
 }
+
+// ========== 延迟分位数测试 ==========
+
+#[test]
+fn test_latency_percentiles_from_samples() {
+    let mut samples: Vec<u64> = (1..=100).rev().collect();
+    let p = LatencyPercentiles::from_samples(&mut samples).unwrap();
+    assert_eq!(p.count, 100);
+    assert_eq!(p.p50_ms, 50);
+    assert_eq!(p.p95_ms, 95);
+    assert_eq!(p.p99_ms, 99);
+
+    let mut single = vec![42];
+    let p = LatencyPercentiles::from_samples(&mut single).unwrap();
+    assert_eq!((p.p50_ms, p.p95_ms, p.p99_ms), (42, 42, 42));
+
+    assert!(LatencyPercentiles::from_samples(&mut []).is_none());
+}
+
+#[test]
+fn test_stats_aggregator_latency_percentiles() {
+    let aggregator = create_test_aggregator();
+
+    for (i, (credential, latency)) in [("a", 100), ("a", 300), ("b", 50)].iter().enumerate() {
+        let mut log = RequestLog::new(
+            format!("ok-{}", i),
+            ProviderType::Kiro,
+            "model".to_string(),
+            false,
+        );
+        log.set_credential_id(credential.to_string());
+        log.mark_success(*latency, 200);
+        aggregator.record(log);
+    }
+
+    // 失败请求和窗口外的请求不计入
+    let mut failed = RequestLog::new(
+        "failed".to_string(),
+        ProviderType::Kiro,
+        "model".to_string(),
+        false,
+    );
+    failed.set_credential_id("b".to_string());
+    failed.mark_failed(10000, Some(500), "error".to_string());
+    aggregator.record(failed);
+
+    let mut old = RequestLog::new(
+        "old".to_string(),
+        ProviderType::Kiro,
+        "model".to_string(),
+        false,
+    );
+    old.set_credential_id("b".to_string());
+    old.mark_success(9000, 200);
+    old.timestamp = Utc::now() - Duration::hours(2);
+    aggregator.record(old);
+
+    let stats = aggregator.latency_percentiles(Duration::hours(1));
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].credential_id.as_deref(), Some("a"));
+    assert_eq!(stats[0].percentiles.count, 2);
+    assert_eq!(stats[0].percentiles.p50_ms, 100);
+    assert_eq!(stats[0].percentiles.p99_ms, 300);
+    assert_eq!(stats[1].percentiles.count, 1);
+    assert_eq!(stats[1].percentiles.p95_ms, 50);
+
+    let by_credential = aggregator.credential_latencies(ProviderType::Kiro, Duration::hours(1));
+    assert_eq!(by_credential["a"].p95_ms, 300);
+    assert_eq!(by_credential["b"].p95_ms, 50);
+    assert!(aggregator
+        .credential_latencies(ProviderType::Gemini, Duration::hours(1))
+        .is_empty());
+    assert_eq!(
+        aggregator.all_credential_latencies(Duration::hours(1)),
+        by_credential
+    );
+}
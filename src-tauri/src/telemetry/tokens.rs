@@ -9,6 +9,10 @@ use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
+use tokio::sync::broadcast;
+
+/// 实时 Token 使用事件通道容量
+const TOKEN_EVENT_CAPACITY: usize = 1024;
 
 /// Token 使用记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +35,9 @@ pub struct TokenUsageRecord {
     pub source: TokenSource,
     /// 关联的请求 ID
     pub request_id: Option<String>,
+    /// 下游终端用户 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
 }
 
 impl TokenUsageRecord {
@@ -53,6 +60,7 @@ impl TokenUsageRecord {
             total_tokens: input_tokens + output_tokens,
             source,
             request_id: None,
+            user_id: None,
         }
     }
 
@@ -61,6 +69,12 @@ impl TokenUsageRecord {
         self.request_id = Some(request_id);
         self
     }
+
+    /// 设置终端用户 ID
+    pub fn with_user_id(mut self, user_id: Option<String>) -> Self {
+        self.user_id = user_id;
+        self
+    }
 }
 
 /// Token 来源
@@ -176,6 +190,26 @@ impl ModelTokenStats {
     }
 }
 
+/// 终端用户 Token 统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserTokenStats {
+    /// 终端用户 ID
+    pub user_id: String,
+    /// 统计摘要
+    #[serde(flatten)]
+    pub summary: TokenStatsSummary,
+}
+
+impl UserTokenStats {
+    /// 从记录列表计算终端用户 Token 统计
+    pub fn from_records(user_id: String, records: &[TokenUsageRecord]) -> Self {
+        Self {
+            user_id,
+            summary: TokenStatsSummary::from_records(records),
+        }
+    }
+}
+
 /// 时间段 Token 统计
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PeriodTokenStats {
@@ -198,6 +232,8 @@ pub struct TokenTracker {
     retention: Duration,
     /// 最大记录条数
     max_records: usize,
+    /// 实时 Token 使用事件
+    events: broadcast::Sender<TokenUsageRecord>,
 }
 
 impl TokenTracker {
@@ -207,13 +243,20 @@ impl TokenTracker {
     /// * `retention` - 记录保留时长
     /// * `max_records` - 最大记录条数
     pub fn new(retention: Duration, max_records: usize) -> Self {
+        let (events, _) = broadcast::channel(TOKEN_EVENT_CAPACITY);
         Self {
             records: RwLock::new(VecDeque::with_capacity(max_records)),
             retention,
             max_records,
+            events,
         }
     }
 
+    /// 订阅实时 Token 使用事件（每条记录写入时推送）
+    pub fn subscribe(&self) -> broadcast::Receiver<TokenUsageRecord> {
+        self.events.subscribe()
+    }
+
     /// 使用默认配置创建 Token 追踪器（保留 30 天，最多 50000 条）
     pub fn with_defaults() -> Self {
         Self::new(Duration::days(30), 50000)
@@ -221,6 +264,9 @@ impl TokenTracker {
 
     /// 记录 Token 使用
     pub fn record(&self, record: TokenUsageRecord) {
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.events.send(record.clone());
+
         let mut records = self.records.write();
         records.push_back(record);
 
@@ -389,6 +435,33 @@ impl TokenTracker {
             .collect()
     }
 
+    /// 按终端用户分组统计（未携带用户 ID 的记录不计入）
+    pub fn by_user(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> HashMap<String, UserTokenStats> {
+        let records = match (start, end) {
+            (Some(s), Some(e)) => self.get_by_time_range(s, e),
+            _ => self.get_all(),
+        };
+
+        let mut grouped: HashMap<String, Vec<TokenUsageRecord>> = HashMap::new();
+        for record in records {
+            if let Some(user_id) = record.user_id.clone() {
+                grouped.entry(user_id).or_default().push(record);
+            }
+        }
+
+        grouped
+            .into_iter()
+            .map(|(user_id, records)| {
+                let stats = UserTokenStats::from_records(user_id.clone(), &records);
+                (user_id, stats)
+            })
+            .collect()
+    }
+
     /// 按时间段汇总（按天）
     pub fn by_day(&self, days: i64) -> Vec<PeriodTokenStats> {
         let now = Utc::now();
@@ -437,6 +510,9 @@ impl Default for TokenTracker {
     }
 }
 
+/// 本地估算时每个图片/文档块计入的 Token 数
+const MEDIA_BLOCK_TOKENS: u32 = 1600;
+
 /// Token 估算器
 ///
 /// 使用 tiktoken 库估算文本的 Token 数量
@@ -497,6 +573,66 @@ impl TokenEstimator {
         total_tokens
     }
 
+    /// 估算 Anthropic Messages 请求的输入 Token 数量（用于本地 count_tokens）
+    ///
+    /// 统计 system、消息内容和工具定义；图片/文档按固定值计入。
+    pub fn estimate_anthropic_request(&self, request: &serde_json::Value) -> u32 {
+        let model = request.get("model").and_then(|m| m.as_str());
+        let mut total_tokens = 0u32;
+
+        if let Some(system) = request.get("system") {
+            total_tokens += self.estimate_anthropic_content(system, model);
+        }
+        if let Some(messages) = request.get("messages").and_then(|m| m.as_array()) {
+            for message in messages {
+                total_tokens += 4; // 消息格式化开销
+                if let Some(content) = message.get("content") {
+                    total_tokens += self.estimate_anthropic_content(content, model);
+                }
+            }
+        }
+        if let Some(tools) = request.get("tools").and_then(|t| t.as_array()) {
+            for tool in tools {
+                total_tokens += self.estimate(&tool.to_string(), model);
+            }
+        }
+
+        total_tokens
+    }
+
+    /// 估算 Anthropic 内容（字符串或内容块数组）
+    fn estimate_anthropic_content(&self, content: &serde_json::Value, model: Option<&str>) -> u32 {
+        match content {
+            serde_json::Value::String(text) => self.estimate(text, model),
+            serde_json::Value::Array(blocks) => blocks
+                .iter()
+                .map(|block| self.estimate_anthropic_block(block, model))
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    fn estimate_anthropic_block(&self, block: &serde_json::Value, model: Option<&str>) -> u32 {
+        let text_of = |key: &str| block.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => self.estimate(text_of("text"), model),
+            Some("thinking") => self.estimate(text_of("thinking"), model),
+            Some("tool_use") => {
+                let input = block
+                    .get("input")
+                    .map(|i| i.to_string())
+                    .unwrap_or_default();
+                self.estimate(text_of("name"), model) + self.estimate(&input, model)
+            }
+            Some("tool_result") => block
+                .get("content")
+                .map(|c| self.estimate_anthropic_content(c, model))
+                .unwrap_or(0),
+            Some("image") | Some("document") => MEDIA_BLOCK_TOKENS,
+            _ => 0,
+        }
+    }
+
     /// 根据模型名称选择合适的 BPE 编码器
     fn select_bpe(&self, model: Option<&str>) -> &tiktoken_rs::CoreBPE {
         match model {
@@ -661,6 +797,25 @@ mod token_tests {
         assert!(!tracker.is_empty());
     }
 
+    #[test]
+    fn test_token_tracker_subscribe() {
+        let tracker = TokenTracker::with_defaults();
+        let mut receiver = tracker.subscribe();
+
+        tracker.record_from_response(
+            "req-1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            Some(100),
+            Some(50),
+        );
+
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.total_tokens, 150);
+        assert_eq!(event.request_id, Some("req-1".to_string()));
+        assert!(receiver.try_recv().is_err());
+    }
+
     #[test]
     fn test_token_tracker_record_from_response() {
         let tracker = TokenTracker::with_defaults();
@@ -756,6 +911,37 @@ mod token_tests {
         assert_eq!(stats["model-b"].summary.record_count, 1);
     }
 
+    #[test]
+    fn test_token_tracker_by_user() {
+        let tracker = TokenTracker::with_defaults();
+
+        for (id, user_id, input) in [
+            ("1", Some("alice"), 100),
+            ("2", Some("bob"), 200),
+            ("3", Some("alice"), 150),
+            ("4", None, 300),
+        ] {
+            tracker.record(
+                TokenUsageRecord::new(
+                    id.to_string(),
+                    ProviderType::Kiro,
+                    "model-a".to_string(),
+                    input,
+                    50,
+                    TokenSource::Actual,
+                )
+                .with_user_id(user_id.map(str::to_string)),
+            );
+        }
+
+        let stats = tracker.by_user(None, None);
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["alice"].summary.record_count, 2);
+        assert_eq!(stats["alice"].summary.total_input_tokens, 250);
+        assert_eq!(stats["bob"].summary.record_count, 1);
+    }
+
     #[test]
     fn test_token_tracker_clear() {
         let tracker = TokenTracker::with_defaults();
@@ -881,6 +1067,38 @@ mod token_tests {
         assert!(tokens_with > tokens_without);
     }
 
+    #[test]
+    fn test_token_estimator_estimate_anthropic_request() {
+        let estimator = TokenEstimator::new().unwrap();
+
+        let base = serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "system": "You are a helpful assistant.",
+            "messages": [{"role": "user", "content": "Hello!"}]
+        });
+        let base_tokens = estimator.estimate_anthropic_request(&base);
+        assert!(base_tokens > 0);
+
+        let with_blocks = serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "system": "You are a helpful assistant.",
+            "messages": [
+                {"role": "user", "content": "Hello!"},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t1", "name": "read_file", "input": {"path": "a.rs"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "fn main() {}"},
+                    {"type": "image", "source": {"type": "base64", "data": "..."}}
+                ]}
+            ],
+            "tools": [{"name": "read_file", "input_schema": {"type": "object"}}]
+        });
+        assert!(
+            estimator.estimate_anthropic_request(&with_blocks) > base_tokens + MEDIA_BLOCK_TOKENS
+        );
+    }
+
     #[test]
     fn test_chat_message_new() {
         let msg = ChatMessage::new("user", "Hello!");
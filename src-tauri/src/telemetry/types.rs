@@ -67,6 +67,12 @@ pub struct RequestLog {
     pub credential_id: Option<String>,
     /// 重试次数
     pub retry_count: u32,
+    /// 上下文窗口使用百分比（Kiro 非流式响应返回）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_usage_percentage: Option<f64>,
+    /// 下游终端用户 ID（如果有）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
 }
 
 impl RequestLog {
@@ -87,6 +93,8 @@ impl RequestLog {
             is_streaming,
             credential_id: None,
             retry_count: 0,
+            context_usage_percentage: None,
+            user_id: None,
         }
     }
 
@@ -102,7 +110,8 @@ impl RequestLog {
         self.status = RequestStatus::Failed;
         self.duration_ms = duration_ms;
         self.http_status = http_status;
-        self.error_message = Some(error);
+        // 上游错误信息可能回显请求内容
+        self.error_message = Some(crate::logger::mask_pii(&error));
     }
 
     /// 标记请求超时
@@ -268,6 +277,53 @@ impl StatsSummary {
     }
 }
 
+/// 延迟分位数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    /// 样本数
+    pub count: u64,
+    /// P50 延迟（毫秒）
+    pub p50_ms: u64,
+    /// P95 延迟（毫秒）
+    pub p95_ms: u64,
+    /// P99 延迟（毫秒）
+    pub p99_ms: u64,
+}
+
+impl LatencyPercentiles {
+    /// 从延迟样本计算分位数（最近秩法），样本为空时返回 None
+    pub fn from_samples(samples: &mut [u64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = |p: f64| {
+            let index = ((p * samples.len() as f64).ceil() as usize).max(1) - 1;
+            samples[index.min(samples.len() - 1)]
+        };
+        Some(Self {
+            count: samples.len() as u64,
+            p50_ms: rank(0.50),
+            p95_ms: rank(0.95),
+            p99_ms: rank(0.99),
+        })
+    }
+}
+
+/// 按 (Provider, 模型, 凭证) 分组的延迟统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// Provider 类型
+    pub provider: ProviderType,
+    /// 模型名称
+    pub model: String,
+    /// 凭证 ID
+    pub credential_id: Option<String>,
+    /// 延迟分位数
+    #[serde(flatten)]
+    pub percentiles: LatencyPercentiles,
+}
+
 /// Provider 统计
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProviderStats {
@@ -3,6 +3,8 @@
 //! 直接将 Anthropic MessagesRequest 转换为 CodeWhisperer API 格式，
 //! 无需经过 OpenAI 中间格式，减少转换开销。
 
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
 use crate::models::anthropic::*;
 use crate::models::codewhisperer::*;
 use crate::translator::kiro::openai::request::{get_model_map, DEFAULT_MODEL};
@@ -54,6 +56,13 @@ impl RequestTranslator for AnthropicRequestTranslator {
 // 内部类型
 // ============================================================================
 
+/// 仅包含工具结果的 user 消息使用的占位内容
+const TOOL_RESULTS_PLACEHOLDER: &str = "Tool results provided.";
+
+/// 缺失工具结果时补充的错误内容
+const MISSING_TOOL_RESULT_TEXT: &str =
+    "Tool execution was interrupted before a result was returned.";
+
 #[derive(Debug, Clone)]
 struct ProcessedMessage {
     role: String,
@@ -80,6 +89,9 @@ pub fn convert_anthropic_to_codewhisperer(
 
     let conversation_id = Uuid::new_v4().to_string();
 
+    // CodeWhisperer 不支持采样参数，仅记录被丢弃的参数
+    SamplingParams::from_anthropic(request).map_to(Protocol::CodeWhisperer);
+
     // 提取 system prompt
     let mut system_prompt = extract_system_text(&request.system);
 
@@ -90,8 +102,9 @@ pub fn convert_anthropic_to_codewhisperer(
         tracing::info!("[KIRO_TRANSLATE] tool_choice=required detected in Anthropic request, injected tool instruction");
     }
 
-    // 预处理消息
-    let messages = preprocess_anthropic_messages(&request.messages);
+    // 预处理消息，并校正工具调用与结果的配对
+    let mut messages = preprocess_anthropic_messages(&request.messages);
+    reconcile_tool_results(&mut messages);
 
     // 构建历史记录
     let mut history: Vec<HistoryItem> = Vec::new();
@@ -133,7 +146,7 @@ pub fn convert_anthropic_to_codewhisperer(
             "user" => {
                 let content = if msg.content.is_empty() {
                     if msg.tool_results.is_some() {
-                        "Tool results provided.".to_string()
+                        TOOL_RESULTS_PLACEHOLDER.to_string()
                     } else {
                         "Continue".to_string()
                     }
@@ -189,7 +202,7 @@ pub fn convert_anthropic_to_codewhisperer(
             } else {
                 let content = if last_msg.content.is_empty() {
                     if last_msg.tool_results.is_some() {
-                        "Tool results provided.".to_string()
+                        TOOL_RESULTS_PLACEHOLDER.to_string()
                     } else {
                         "Continue".to_string()
                     }
@@ -250,24 +263,14 @@ pub fn convert_anthropic_to_codewhisperer(
 }
 
 /// 提取 system prompt 文本
+///
+/// CodeWhisperer 不支持多个 system 块，按换行合并。
 fn extract_system_text(system: &Option<serde_json::Value>) -> String {
-    match system {
-        Some(serde_json::Value::String(s)) => s.clone(),
-        Some(serde_json::Value::Array(arr)) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
-    }
+    parse_system_blocks(system.as_ref())
+        .into_iter()
+        .map(|b| b.text)
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// 预处理 Anthropic 消息
@@ -296,7 +299,7 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
                 merged.push(ProcessedMessage {
                     role: msg.role,
                     content: if msg.content.is_empty() && !pending_tool_results.is_empty() {
-                        "Tool results provided.".to_string()
+                        TOOL_RESULTS_PLACEHOLDER.to_string()
                     } else {
                         msg.content
                     },
@@ -318,7 +321,7 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
 
                 merged.push(ProcessedMessage {
                     role: "user".to_string(),
-                    content: "Tool results provided.".to_string(),
+                    content: TOOL_RESULTS_PLACEHOLDER.to_string(),
                     tool_uses: None,
                     tool_results: Some(pending_tool_results.clone()),
                     images: None,
@@ -336,7 +339,7 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
 
         merged.push(ProcessedMessage {
             role: "user".to_string(),
-            content: "Tool results provided.".to_string(),
+            content: TOOL_RESULTS_PLACEHOLDER.to_string(),
             tool_uses: None,
             tool_results: Some(pending_tool_results),
             images: None,
@@ -376,36 +379,8 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                         }
                     }
                     "image" => {
-                        // 处理 Anthropic 格式的图片
-                        // { "type": "image", "source": { "type": "base64", "media_type": "image/jpeg", "data": "..." } }
-                        if let Some(source) = part.get("source") {
-                            let source_type =
-                                source.get("type").and_then(|t| t.as_str()).unwrap_or("");
-                            if source_type == "base64" {
-                                let media_type = source
-                                    .get("media_type")
-                                    .and_then(|m| m.as_str())
-                                    .unwrap_or("image/jpeg");
-                                let data =
-                                    source.get("data").and_then(|d| d.as_str()).unwrap_or("");
-
-                                if !data.is_empty() {
-                                    // 从 media_type 提取格式 (image/jpeg -> jpeg)
-                                    let format =
-                                        media_type.split('/').nth(1).unwrap_or("jpeg").to_string();
-
-                                    images.push(CWImage {
-                                        format,
-                                        source: CWImageSource {
-                                            bytes: data.to_string(),
-                                        },
-                                    });
-                                    tracing::debug!(
-                                        "[KIRO_TRANSLATE] Converted image: media_type={}",
-                                        media_type
-                                    );
-                                }
-                            }
+                        if let Some(image) = convert_image_block(part) {
+                            images.push(image);
                         }
                     }
                     "tool_use" => {
@@ -428,7 +403,10 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                             .get("tool_use_id")
                             .and_then(|i| i.as_str())
                             .unwrap_or("");
-                        let content_text = extract_tool_result_content(part.get("content"));
+                        let (content_text, result_images) =
+                            extract_tool_result_content(part.get("content"));
+                        // 工具结果中的图片（如截图工具）随消息一起发送
+                        images.extend(result_images);
                         let is_error = part
                             .get("is_error")
                             .and_then(|e| e.as_bool())
@@ -497,24 +475,142 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
     result
 }
 
-/// 提取 tool_result 内容
-fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
+/// 转换 Anthropic 格式的图片块
+///
+/// `{ "type": "image", "source": { "type": "base64", "media_type": "image/jpeg", "data": "..." } }`
+fn convert_image_block(part: &serde_json::Value) -> Option<CWImage> {
+    let source = part.get("source")?;
+    if source.get("type").and_then(|t| t.as_str()) != Some("base64") {
+        return None;
+    }
+
+    let media_type = source
+        .get("media_type")
+        .and_then(|m| m.as_str())
+        .unwrap_or("image/jpeg");
+    let data = source.get("data").and_then(|d| d.as_str()).unwrap_or("");
+    if data.is_empty() {
+        return None;
+    }
+
+    tracing::debug!(
+        "[KIRO_TRANSLATE] Converted image: media_type={}",
+        media_type
+    );
+    // 从 media_type 提取格式 (image/jpeg -> jpeg)
+    Some(CWImage {
+        format: media_type.split('/').nth(1).unwrap_or("jpeg").to_string(),
+        source: CWImageSource {
+            bytes: data.to_string(),
+        },
+    })
+}
+
+/// 提取 tool_result 内容（文本和图片）
+fn extract_tool_result_content(content: Option<&serde_json::Value>) -> (String, Vec<CWImage>) {
     match content {
-        Some(serde_json::Value::String(s)) => s.clone(),
-        Some(serde_json::Value::Array(arr)) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
+        Some(serde_json::Value::String(s)) => (s.clone(), Vec::new()),
+        Some(serde_json::Value::Array(arr)) => {
+            let mut texts: Vec<&str> = Vec::new();
+            let mut images: Vec<CWImage> = Vec::new();
+            for item in arr {
+                match item.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
+                            texts.push(text);
+                        }
+                    }
+                    Some("image") => images.extend(convert_image_block(item)),
+                    _ => {}
                 }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
+            }
+            (texts.join("\n"), images)
+        }
+        _ => (String::new(), Vec::new()),
+    }
+}
+
+/// 校正工具调用与结果的配对
+///
+/// CodeWhisperer 要求 toolResults 与上一条 assistant 消息的 toolUses 一一对应，否则拒绝请求：
+/// - 找不到对应 toolUse 的结果（如上下文压缩后残留）转为文本附加到消息内容
+/// - 缺少结果的 toolUse（如用户中断了工具执行）补充错误状态的结果
+fn reconcile_tool_results(messages: &mut [ProcessedMessage]) {
+    let mut pending_ids: Vec<String> = Vec::new();
+
+    for msg in messages.iter_mut() {
+        if msg.role == "assistant" {
+            pending_ids = msg
+                .tool_uses
+                .as_ref()
+                .map(|uses| uses.iter().map(|u| u.tool_use_id.clone()).collect())
+                .unwrap_or_default();
+            continue;
+        }
+
+        let (mut matched, orphaned): (Vec<CWToolResult>, Vec<CWToolResult>) = msg
+            .tool_results
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|r| pending_ids.contains(&r.tool_use_id));
+
+        if !orphaned.is_empty() {
+            tracing::warn!(
+                "[KIRO_TRANSLATE] {} tool_result(s) without matching tool_use, converted to text",
+                orphaned.len()
+            );
+            let orphaned_text = orphaned
+                .iter()
+                .map(|r| {
+                    let text = r
+                        .content
+                        .iter()
+                        .map(|c| c.text.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    format!(
+                        "[Tool result for {} ({})]\n{}",
+                        r.tool_use_id, r.status, text
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+
+            msg.content = if msg.content.is_empty() || msg.content == TOOL_RESULTS_PLACEHOLDER {
+                orphaned_text
+            } else {
+                format!("{}\n\n{}", msg.content, orphaned_text)
+            };
+        }
+
+        for id in &pending_ids {
+            if !matched.iter().any(|r| &r.tool_use_id == id) {
+                tracing::warn!(
+                    "[KIRO_TRANSLATE] tool_use {} has no result, marked as error",
+                    id
+                );
+                matched.push(CWToolResult {
+                    content: vec![CWTextContent {
+                        text: MISSING_TOOL_RESULT_TEXT.to_string(),
+                    }],
+                    status: "error".to_string(),
+                    tool_use_id: id.clone(),
+                });
+            }
+        }
+        // 与 toolUses 顺序保持一致
+        matched.sort_by_key(|r| pending_ids.iter().position(|id| id == &r.tool_use_id));
+
+        if matched.is_empty() {
+            msg.tool_results = None;
+        } else {
+            if msg.content.is_empty() {
+                msg.content = TOOL_RESULTS_PLACEHOLDER.to_string();
+            }
+            msg.tool_results = Some(matched);
+        }
+        pending_ids.clear();
     }
 }
 
@@ -698,8 +794,12 @@ mod tests {
             max_tokens: Some(1024),
             stream: true,
             temperature: None,
+            top_p: None,
+            top_k: None,
+            stop_sequences: None,
             tools: None,
             tool_choice: None,
+            metadata: None,
         };
 
         let translator = AnthropicRequestTranslator::new();
@@ -717,6 +817,129 @@ mod tests {
         );
     }
 
+    fn tool_loop_request(messages: Vec<serde_json::Value>) -> AnthropicMessagesRequest {
+        AnthropicMessagesRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: messages
+                .into_iter()
+                .map(|m| serde_json::from_value(m).unwrap())
+                .collect(),
+            system: None,
+            max_tokens: Some(1024),
+            stream: true,
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_multi_turn_tool_loop() {
+        let request = tool_loop_request(vec![
+            serde_json::json!({"role": "user", "content": "List files then read main.rs"}),
+            serde_json::json!({"role": "assistant", "content": [
+                {"type": "text", "text": "Listing files."},
+                {"type": "tool_use", "id": "toolu_1", "name": "ls", "input": {"path": "."}}
+            ]}),
+            serde_json::json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                    {"type": "text", "text": "main.rs"}
+                ]}
+            ]}),
+            serde_json::json!({"role": "assistant", "content": [
+                {"type": "tool_use", "id": "toolu_2", "name": "read", "input": {"file": "main.rs"}},
+                {"type": "tool_use", "id": "toolu_3", "name": "read", "input": {"file": "lib.rs"}}
+            ]}),
+            serde_json::json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_3", "content": "not found", "is_error": true},
+                {"type": "tool_result", "tool_use_id": "toolu_2", "content": "fn main() {}"}
+            ]}),
+        ]);
+
+        let cw = convert_anthropic_to_codewhisperer(&request, None);
+        let history = cw.conversation_state.history.unwrap();
+
+        // 历史中的工具结果编码到 toolResults，而不是文本
+        let HistoryItem::User(second_user) = &history[2] else {
+            panic!("expected user history item");
+        };
+        let results = second_user
+            .user_input_message
+            .user_input_message_context
+            .as_ref()
+            .and_then(|ctx| ctx.tool_results.as_ref())
+            .unwrap();
+        assert_eq!(results[0].tool_use_id, "toolu_1");
+        assert_eq!(results[0].content[0].text, "main.rs");
+
+        // 当前消息的工具结果按 toolUses 顺序排列，并保留错误状态
+        let current = cw.conversation_state.current_message.user_input_message;
+        let results = current
+            .user_input_message_context
+            .and_then(|ctx| ctx.tool_results)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].tool_use_id, "toolu_2");
+        assert_eq!(results[0].status, "success");
+        assert_eq!(results[1].tool_use_id, "toolu_3");
+        assert_eq!(results[1].status, "error");
+        assert_eq!(current.content, TOOL_RESULTS_PLACEHOLDER);
+    }
+
+    #[test]
+    fn test_orphaned_and_missing_tool_results() {
+        let request = tool_loop_request(vec![
+            serde_json::json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_old", "content": "stale output"},
+                {"type": "text", "text": "Continue the task"}
+            ]}),
+            serde_json::json!({"role": "assistant", "content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "bash", "input": {"cmd": "ls"}}
+            ]}),
+            serde_json::json!({"role": "user", "content": "Stop, do something else"}),
+        ]);
+
+        let cw = convert_anthropic_to_codewhisperer(&request, None);
+        let history = cw.conversation_state.history.unwrap();
+
+        // 没有对应 tool_use 的结果转为文本
+        let HistoryItem::User(first_user) = &history[0] else {
+            panic!("expected user history item");
+        };
+        let content = &first_user.user_input_message.content;
+        assert!(content.starts_with("Continue the task"));
+        assert!(content.contains("[Tool result for toolu_old (success)]\nstale output"));
+        assert!(first_user
+            .user_input_message
+            .user_input_message_context
+            .is_none());
+
+        // 被中断的 tool_use 补充错误结果
+        let current = cw.conversation_state.current_message.user_input_message;
+        assert_eq!(current.content, "Stop, do something else");
+        let results = current
+            .user_input_message_context
+            .and_then(|ctx| ctx.tool_results)
+            .unwrap();
+        assert_eq!(results[0].tool_use_id, "toolu_1");
+        assert_eq!(results[0].status, "error");
+    }
+
+    #[test]
+    fn test_tool_result_images() {
+        let (text, images) = extract_tool_result_content(Some(&serde_json::json!([
+            {"type": "text", "text": "Screenshot taken"},
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBOR"}}
+        ])));
+        assert_eq!(text, "Screenshot taken");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].format, "png");
+    }
+
     #[test]
     fn test_extract_system_text_string() {
         let system = Some(serde_json::json!("You are a helpful assistant."));
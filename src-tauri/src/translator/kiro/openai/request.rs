@@ -9,6 +9,8 @@
 //! - claude-sonnet-4-20250514 → CLAUDE_SONNET_4_20250514_V1_0
 //! - claude-haiku-4-5 → claude-haiku-4.5
 
+use crate::converter::protocol_selector::Protocol;
+use crate::converter::sampling::SamplingParams;
 use crate::models::codewhisperer::*;
 use crate::models::openai::*;
 use crate::translator::traits::{RequestTranslator, TranslateError};
@@ -139,17 +141,21 @@ pub fn convert_openai_to_codewhisperer(
 
     let conversation_id = Uuid::new_v4().to_string();
 
-    // 提取 system prompt 和消息
-    let mut system_prompt = String::new();
+    // CodeWhisperer 不支持采样参数，仅记录被丢弃的参数
+    SamplingParams::from_openai(request).map_to(Protocol::CodeWhisperer);
+
+    // 提取 system prompt 和消息（多个 system 消息 / 文本块按换行合并）
+    let mut system_blocks: Vec<String> = Vec::new();
     let mut raw_messages: Vec<&ChatMessage> = Vec::new();
 
     for msg in &request.messages {
         if msg.role == "system" {
-            system_prompt = msg.get_content_text();
+            system_blocks.extend(msg.get_text_blocks());
         } else {
             raw_messages.push(msg);
         }
     }
+    let mut system_prompt = system_blocks.join("\n");
 
     // 调试日志：打印 tool_choice 和 tools 信息
     tracing::info!(
@@ -489,6 +495,8 @@ fn convert_tools(tools: &Option<Vec<Tool>>) -> Option<Vec<CWToolItem>> {
                         tool_type: "web_search".to_string(),
                     }));
                 }
+                // 代码执行工具 CodeWhisperer 不支持
+                Tool::CodeExecution => {}
             }
         }
 
@@ -637,11 +645,20 @@ mod tests {
             }],
             tools: None,
             stream: false,
+            stream_options: None,
             max_tokens: None,
             temperature: None,
             top_p: None,
             tool_choice: None,
             reasoning_effort: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            top_k: None,
+            user: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
         };
 
         let translator = OpenAiRequestTranslator::new();
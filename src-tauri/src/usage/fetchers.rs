@@ -0,0 +1,290 @@
+//! 各 Provider 的额度查询接口
+
+use super::{QuotaBucket, QuotaInfo};
+use crate::providers::gemini::GeminiProvider;
+use crate::providers::kiro::{
+    generate_machine_id_from_credentials, get_kiro_version, KiroProvider,
+};
+use crate::services::usage_service;
+use chrono::{Datelike, Utc};
+use serde_json::Value;
+use std::time::Duration;
+
+/// 额度查询超时
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// 发送 GET 请求并解析 JSON 响应
+async fn get_json(url: &str, api_key: &str) -> Result<Value, String> {
+    let resp = http_client()?
+        .get(url)
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .map_err(|e| format!("请求 {} 失败: {}", url, e))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("请求 {} 失败: {} - {}", url, status, body));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("解析响应失败: {}", e))
+}
+
+/// 拼接 OpenAI 风格的接口地址（base_url 未包含版本号时补充 /v1）
+fn versioned_url(base_url: &str, path: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    let has_version = base.rsplit('/').next().is_some_and(|segment| {
+        segment.len() >= 2
+            && segment.starts_with('v')
+            && segment[1..].chars().all(|c| c.is_ascii_digit())
+    });
+    if has_version {
+        format!("{}/{}", base, path)
+    } else {
+        format!("{}/v1/{}", base, path)
+    }
+}
+
+/// Kiro 计量额度
+pub(super) async fn fetch_kiro(
+    access_token: &str,
+    creds_file_path: &str,
+) -> Result<QuotaInfo, String> {
+    let mut kiro = KiroProvider::new();
+    kiro.load_credentials_from_path(creds_file_path)
+        .await
+        .map_err(|e| format!("加载 Kiro 凭证失败: {}", e))?;
+    let creds = kiro.credentials();
+    let auth_method = creds
+        .auth_method
+        .clone()
+        .unwrap_or_else(|| "social".to_string());
+    let profile_arn = kiro.candidate_profiles().remove(0).profile_arn;
+    let machine_id =
+        generate_machine_id_from_credentials(profile_arn.as_deref(), creds.client_id.as_deref());
+    let usage = usage_service::get_usage_limits(
+        access_token,
+        &auth_method,
+        profile_arn.as_deref(),
+        &machine_id,
+        &get_kiro_version(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut quota = QuotaInfo::from_totals(
+        "credits",
+        Some(usage.usage_limit),
+        Some(usage.current_usage),
+    );
+    quota.plan = Some(usage.subscription_title).filter(|s| !s.is_empty());
+    Ok(quota)
+}
+
+/// Gemini Code Assist 额度
+pub(super) async fn fetch_gemini(
+    access_token: &str,
+    creds_file_path: &str,
+    project_id: Option<String>,
+) -> Result<QuotaInfo, String> {
+    let mut gemini = GeminiProvider::new();
+    gemini
+        .load_credentials_from_path(creds_file_path)
+        .await
+        .map_err(|e| format!("加载 Gemini 凭证失败: {}", e))?;
+    gemini.credentials.access_token = Some(access_token.to_string());
+    gemini.project_id = project_id;
+    let project = gemini
+        .discover_project()
+        .await
+        .map_err(|e| format!("获取 Gemini 项目失败: {}", e))?;
+    let resp = gemini
+        .call_api(
+            "retrieveUserQuota",
+            &serde_json::json!({ "project": project }),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(parse_gemini_quota(&resp))
+}
+
+/// 解析 `retrieveUserQuota` 响应：总体剩余比例取各模型中的最小值
+fn parse_gemini_quota(resp: &Value) -> QuotaInfo {
+    let buckets: Vec<QuotaBucket> = resp["buckets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|bucket| QuotaBucket {
+            name: bucket["modelId"]
+                .as_str()
+                .or(bucket["tokenType"].as_str())
+                .unwrap_or("default")
+                .to_string(),
+            remaining_fraction: bucket["remainingFraction"].as_f64(),
+            reset_at: bucket["resetTime"].as_str().map(str::to_string),
+        })
+        .collect();
+    let lowest = buckets
+        .iter()
+        .filter(|b| b.remaining_fraction.is_some())
+        .min_by(|a, b| {
+            a.remaining_fraction
+                .partial_cmp(&b.remaining_fraction)
+                .unwrap()
+        });
+    QuotaInfo {
+        remaining_fraction: lowest.and_then(|b| b.remaining_fraction),
+        reset_at: lowest.and_then(|b| b.reset_at.clone()),
+        buckets,
+        ..QuotaInfo::default()
+    }
+}
+
+/// OpenAI billing 接口：订阅额度 + 本月用量
+pub(super) async fn fetch_openai(
+    api_key: &str,
+    base_url: Option<&str>,
+) -> Result<QuotaInfo, String> {
+    let base_url = base_url.unwrap_or("https://api.openai.com");
+    let subscription = get_json(
+        &versioned_url(base_url, "dashboard/billing/subscription"),
+        api_key,
+    )
+    .await?;
+
+    let today = Utc::now().date_naive();
+    let start = today.with_day(1).unwrap_or(today);
+    let end = today + chrono::Duration::days(1);
+    let usage = get_json(
+        &versioned_url(
+            base_url,
+            &format!(
+                "dashboard/billing/usage?start_date={}&end_date={}",
+                start.format("%Y-%m-%d"),
+                end.format("%Y-%m-%d")
+            ),
+        ),
+        api_key,
+    )
+    .await?;
+    Ok(parse_openai_billing(&subscription, &usage))
+}
+
+/// 解析 billing 响应（`total_usage` 单位为美分）
+fn parse_openai_billing(subscription: &Value, usage: &Value) -> QuotaInfo {
+    let limit = subscription["hard_limit_usd"].as_f64();
+    let used = usage["total_usage"].as_f64().map(|cents| cents / 100.0);
+    let mut quota = QuotaInfo::from_totals("USD", limit, used);
+    quota.plan = subscription["plan"]["title"].as_str().map(str::to_string);
+    quota
+}
+
+/// OpenRouter API Key 额度
+pub(super) async fn fetch_openrouter(
+    api_key: &str,
+    base_url: Option<&str>,
+) -> Result<QuotaInfo, String> {
+    let base_url = base_url.unwrap_or(crate::providers::openrouter::OPENROUTER_BASE_URL);
+    let resp = get_json(&versioned_url(base_url, "key"), api_key).await?;
+    Ok(parse_openrouter_key(&resp))
+}
+
+/// 解析 `/v1/key` 响应（`limit` 为空表示不限额）
+fn parse_openrouter_key(resp: &Value) -> QuotaInfo {
+    let data = &resp["data"];
+    let mut quota = QuotaInfo::from_totals("USD", data["limit"].as_f64(), data["usage"].as_f64());
+    if let Some(remaining) = data["limit_remaining"].as_f64() {
+        quota.remaining = Some(remaining);
+    }
+    if data["is_free_tier"].as_bool() == Some(true) {
+        quota.plan = Some("free".to_string());
+    }
+    quota
+}
+
+/// DeepSeek 余额
+pub(super) async fn fetch_deepseek(
+    api_key: &str,
+    base_url: Option<&str>,
+) -> Result<QuotaInfo, String> {
+    let base_url = base_url.unwrap_or(crate::providers::deepseek::DEEPSEEK_BASE_URL);
+    let base = base_url.trim_end_matches('/').trim_end_matches("/v1");
+    let resp = get_json(&format!("{}/user/balance", base), api_key).await?;
+    parse_deepseek_balance(&resp)
+}
+
+/// 解析 `/user/balance` 响应（余额为字符串，取第一个币种）
+fn parse_deepseek_balance(resp: &Value) -> Result<QuotaInfo, String> {
+    let info = resp["balance_infos"]
+        .as_array()
+        .and_then(|infos| infos.first())
+        .ok_or("响应中没有余额信息")?;
+    let amount = |key: &str| info[key].as_str().and_then(|s| s.parse::<f64>().ok());
+    Ok(QuotaInfo {
+        unit: info["currency"].as_str().map(str::to_string),
+        remaining: amount("total_balance"),
+        ..QuotaInfo::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_versioned_url() {
+        assert_eq!(
+            versioned_url("https://api.openai.com", "dashboard/billing/subscription"),
+            "https://api.openai.com/v1/dashboard/billing/subscription"
+        );
+        assert_eq!(
+            versioned_url("https://openrouter.ai/api/v1/", "key"),
+            "https://openrouter.ai/api/v1/key"
+        );
+    }
+
+    #[test]
+    fn test_parse_provider_quotas() {
+        let gemini = parse_gemini_quota(&json!({"buckets": [
+            {"modelId": "gemini-2.5-pro", "remainingFraction": 0.25, "resetTime": "2026-01-02T00:00:00Z"},
+            {"modelId": "gemini-2.5-flash", "remainingFraction": 0.9, "resetTime": "2026-01-01T00:00:00Z"}
+        ]}));
+        assert_eq!(gemini.remaining_fraction, Some(0.25));
+        assert_eq!(gemini.reset_at.as_deref(), Some("2026-01-02T00:00:00Z"));
+        assert_eq!(gemini.buckets.len(), 2);
+
+        let openai = parse_openai_billing(
+            &json!({"hard_limit_usd": 100.0, "plan": {"title": "Pay-as-you-go"}}),
+            &json!({"total_usage": 2500.0}),
+        );
+        assert_eq!(openai.used, Some(25.0));
+        assert_eq!(openai.remaining, Some(75.0));
+        assert_eq!(openai.remaining_fraction, Some(0.75));
+        assert_eq!(openai.plan.as_deref(), Some("Pay-as-you-go"));
+
+        // 不限额
+        let openrouter = parse_openrouter_key(
+            &json!({"data": {"usage": 1.5, "limit": null, "limit_remaining": null}}),
+        );
+        assert_eq!(openrouter.used, Some(1.5));
+        assert_eq!(openrouter.limit, None);
+        assert_eq!(openrouter.remaining, None);
+
+        let deepseek = parse_deepseek_balance(&json!({"is_available": true, "balance_infos": [
+            {"currency": "CNY", "total_balance": "110.00", "granted_balance": "10.00", "topped_up_balance": "100.00"}
+        ]}))
+        .unwrap();
+        assert_eq!(deepseek.unit.as_deref(), Some("CNY"));
+        assert_eq!(deepseek.remaining, Some(110.0));
+        assert!(parse_deepseek_balance(&json!({"balance_infos": []})).is_err());
+    }
+}
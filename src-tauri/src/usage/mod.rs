@@ -0,0 +1,216 @@
+//! 凭证额度查询
+//!
+//! 上游提供查询接口时，查询凭证的剩余额度：
+//! - Kiro：getUsageLimits 计量额度（credits）
+//! - Gemini OAuth：Code Assist `retrieveUserQuota`（按模型的剩余比例）
+//! - OpenAI API Key：`dashboard/billing` 订阅额度和本月用量（USD，兼容 one-api 等中转服务）
+//! - OpenRouter：`/v1/key` 额度和用量（USD）
+//! - DeepSeek：`/user/balance` 余额
+//!
+//! 查询结果缓存在 [`ProviderPoolService`] 中，随 `CredentialDisplay` 一起展示。
+
+mod fetchers;
+
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::token_cache_service::TokenCacheService;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 额度缓存有效期（秒）
+pub const QUOTA_CACHE_TTL_SECS: i64 = 600;
+
+/// 分项额度（如 Gemini 按模型的额度）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaBucket {
+    pub name: String,
+    /// 剩余比例（0.0 ~ 1.0）
+    pub remaining_fraction: Option<f64>,
+    pub reset_at: Option<String>,
+}
+
+/// 额度信息
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaInfo {
+    /// 额度单位（credits、USD、CNY 等，只有剩余比例时为空）
+    pub unit: Option<String>,
+    /// 订阅 / 套餐名称
+    pub plan: Option<String>,
+    /// 总额度（无上限时为空）
+    pub limit: Option<f64>,
+    pub used: Option<f64>,
+    pub remaining: Option<f64>,
+    /// 剩余比例（0.0 ~ 1.0）
+    pub remaining_fraction: Option<f64>,
+    pub reset_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buckets: Vec<QuotaBucket>,
+}
+
+impl QuotaInfo {
+    /// 由总额度和已用额度计算剩余额度
+    pub fn from_totals(unit: &str, limit: Option<f64>, used: Option<f64>) -> Self {
+        let remaining = match (limit, used) {
+            (Some(limit), Some(used)) => Some((limit - used).max(0.0)),
+            (Some(limit), None) => Some(limit),
+            _ => None,
+        };
+        let remaining_fraction = match (limit, remaining) {
+            (Some(limit), Some(remaining)) if limit > 0.0 => Some(remaining / limit),
+            _ => None,
+        };
+        Self {
+            unit: Some(unit.to_string()),
+            limit,
+            used,
+            remaining,
+            remaining_fraction,
+            ..Self::default()
+        }
+    }
+}
+
+/// 凭证额度查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialQuota {
+    pub credential_uuid: String,
+    pub provider_type: String,
+    /// 查询成功时的额度信息
+    pub quota: Option<QuotaInfo>,
+    /// 查询失败时的错误信息
+    pub error: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl CredentialQuota {
+    /// 缓存是否已过期
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        (now - self.fetched_at).num_seconds() >= QUOTA_CACHE_TTL_SECS
+    }
+}
+
+/// 凭证类型是否支持额度查询
+pub fn supports_quota(credential: &CredentialData) -> bool {
+    matches!(
+        credential,
+        CredentialData::KiroOAuth { .. }
+            | CredentialData::GeminiOAuth { .. }
+            | CredentialData::OpenAIKey { .. }
+            | CredentialData::OpenRouterKey { .. }
+            | CredentialData::DeepSeekKey { .. }
+    )
+}
+
+/// 查询凭证额度（不使用缓存）
+async fn fetch_quota(
+    token_cache: &TokenCacheService,
+    db: &DbConnection,
+    credential: &ProviderCredential,
+) -> Result<QuotaInfo, String> {
+    match &credential.credential {
+        CredentialData::KiroOAuth { creds_file_path } => {
+            let token = token_cache.get_valid_token(db, &credential.uuid).await?;
+            fetchers::fetch_kiro(&token, creds_file_path).await
+        }
+        CredentialData::GeminiOAuth {
+            creds_file_path,
+            project_id,
+        } => {
+            let token = token_cache.get_valid_token(db, &credential.uuid).await?;
+            fetchers::fetch_gemini(&token, creds_file_path, project_id.clone()).await
+        }
+        CredentialData::OpenAIKey { api_key, base_url } => {
+            fetchers::fetch_openai(api_key, base_url.as_deref()).await
+        }
+        CredentialData::OpenRouterKey { api_key, base_url } => {
+            let base_url = base_url
+                .clone()
+                .or_else(|| credential.credential.default_base_url());
+            fetchers::fetch_openrouter(api_key, base_url.as_deref()).await
+        }
+        CredentialData::DeepSeekKey { api_key, base_url } => {
+            let base_url = base_url
+                .clone()
+                .or_else(|| credential.credential.default_base_url());
+            fetchers::fetch_deepseek(api_key, base_url.as_deref()).await
+        }
+        _ => Err(format!("{} 凭证不支持额度查询", credential.provider_type)),
+    }
+}
+
+/// 查询凭证额度
+///
+/// 缓存未过期且未指定 `force` 时直接返回缓存；查询失败的结果同样缓存，避免频繁请求上游。
+pub async fn get_quota(
+    pool_service: &ProviderPoolService,
+    token_cache: &TokenCacheService,
+    db: &DbConnection,
+    credential: &ProviderCredential,
+    force: bool,
+) -> CredentialQuota {
+    if !force {
+        if let Some(cached) = pool_service.cached_quota(&credential.uuid) {
+            if !cached.is_stale(Utc::now()) {
+                return cached;
+            }
+        }
+    }
+
+    let result = fetch_quota(token_cache, db, credential).await;
+    if let Err(e) = &result {
+        tracing::warn!(
+            "[USAGE] 查询凭证 {} 额度失败: {}",
+            &credential.uuid[..8.min(credential.uuid.len())],
+            e
+        );
+    }
+    let quota = CredentialQuota {
+        credential_uuid: credential.uuid.clone(),
+        provider_type: credential.provider_type.to_string(),
+        error: result.as_ref().err().cloned(),
+        quota: result.ok(),
+        fetched_at: Utc::now(),
+    };
+    pool_service.cache_quota(quota.clone());
+    quota
+}
+
+/// 查询所有支持额度查询的凭证（跳过已禁用的凭证）
+pub async fn get_all_quotas(
+    pool_service: &ProviderPoolService,
+    token_cache: &TokenCacheService,
+    db: &DbConnection,
+    force: bool,
+) -> Result<Vec<CredentialQuota>, String> {
+    let credentials = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+    };
+    let queries = credentials
+        .iter()
+        .filter(|c| !c.is_disabled && supports_quota(&c.credential))
+        .map(|c| get_quota(pool_service, token_cache, db, c, force));
+    Ok(futures::future::join_all(queries).await)
+}
+
+/// 查询单个凭证额度
+pub async fn get_quota_by_uuid(
+    pool_service: &ProviderPoolService,
+    token_cache: &TokenCacheService,
+    db: &DbConnection,
+    uuid: &str,
+    force: bool,
+) -> Result<CredentialQuota, String> {
+    let credential = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("凭证不存在: {}", uuid))?
+    };
+    if !supports_quota(&credential.credential) {
+        return Err(format!("{} 凭证不支持额度查询", credential.provider_type));
+    }
+    Ok(get_quota(pool_service, token_cache, db, &credential, force).await)
+}
@@ -275,6 +275,22 @@ async fn handle_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsMessage::CredentialExpiryWarning(_) => Some(WsMessage::Error(WsError::invalid_message(
+            "CredentialExpiryWarning messages are server-to-client only",
+        ))),
+        WsMessage::HealthCheckProgress(_) => Some(WsMessage::Error(WsError::invalid_message(
+            "HealthCheckProgress messages are server-to-client only",
+        ))),
+        WsMessage::SubscribeTokenUsage | WsMessage::UnsubscribeTokenUsage => {
+            // Token 使用事件订阅在 server/handlers/websocket.rs 中处理
+            Some(WsMessage::Error(WsError::invalid_request(
+                None,
+                "Token usage subscription is not supported in this handler",
+            )))
+        }
+        WsMessage::TokenUsage(_) => Some(WsMessage::Error(WsError::invalid_message(
+            "TokenUsage messages are server-to-client only",
+        ))),
     }
 }
 
@@ -20,7 +20,7 @@ pub use processor::MessageProcessor;
 pub use stream::{BackpressureController, StreamForwarder};
 pub use types::{
     KiroTokenInfo, WsApiRequest, WsApiResponse, WsConfig, WsConnection, WsConnectionStatus,
-    WsEndpoint, WsError, WsErrorCode, WsFlowEvent, WsKiroEvent, WsMessage, WsStats,
+    WsEndpoint, WsError, WsErrorCode, WsFlowEvent, WsKiroEvent, WsMessage, WsStats, WsStatsReport,
     WsStatsSnapshot, WsStreamChunk, WsStreamEnd,
 };
 
@@ -93,6 +93,31 @@ impl WsConnectionManager {
         }
     }
 
+    /// 记录收到的消息（更新最后活动时间）
+    pub fn record_received(&self, id: &str, bytes: usize) {
+        if let Some(mut conn) = self.connections.get_mut(id) {
+            conn.record_received(bytes);
+        }
+        self.stats.on_bytes_received(bytes);
+    }
+
+    /// 记录发送的消息
+    pub fn record_sent(&self, id: &str, bytes: usize) {
+        if let Some(mut conn) = self.connections.get_mut(id) {
+            conn.record_sent(bytes);
+        }
+        self.stats.on_bytes_sent(bytes);
+    }
+
+    /// 连接是否空闲超时（未配置空闲超时或连接不存在时返回 false）
+    pub fn is_idle(&self, id: &str) -> bool {
+        self.config.idle_timeout_secs > 0
+            && self.connections.get(id).is_some_and(|conn| {
+                conn.idle_duration()
+                    >= std::time::Duration::from_secs(self.config.idle_timeout_secs)
+            })
+    }
+
     /// 获取活跃连接数
     pub fn active_count(&self) -> usize {
         self.connections.len()
@@ -108,6 +133,16 @@ impl WsConnectionManager {
         &self.stats
     }
 
+    /// 获取统计报告（包含各连接详情，按连接时间排序）
+    pub fn report(&self) -> WsStatsReport {
+        let mut connections = self.list_connections();
+        connections.sort_by_key(|conn| conn.connected_at);
+        WsStatsReport {
+            stats: self.stats.snapshot(),
+            connections,
+        }
+    }
+
     /// 获取配置
     pub fn config(&self) -> &WsConfig {
         &self.config
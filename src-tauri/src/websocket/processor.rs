@@ -173,6 +173,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "".to_string(),
             endpoint: WsEndpoint::Models,
+            session_id: None,
             payload: serde_json::json!({}),
         };
         let result = MessageProcessor::validate_request(&request);
@@ -184,6 +185,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::Models,
+            session_id: None,
             payload: serde_json::json!("not an object"),
         };
         let result = MessageProcessor::validate_request(&request);
@@ -195,6 +197,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::ChatCompletions,
+            session_id: None,
             payload: serde_json::json!({
                 "messages": [{"role": "user", "content": "hello"}]
             }),
@@ -208,6 +211,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::ChatCompletions,
+            session_id: None,
             payload: serde_json::json!({
                 "model": "gpt-4"
             }),
@@ -221,6 +225,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::ChatCompletions,
+            session_id: None,
             payload: serde_json::json!({
                 "model": "gpt-4",
                 "messages": [{"role": "user", "content": "hello"}]
@@ -235,6 +240,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::Messages,
+            session_id: None,
             payload: serde_json::json!({
                 "model": "claude-3",
                 "messages": [{"role": "user", "content": "hello"}]
@@ -249,6 +255,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::Messages,
+            session_id: None,
             payload: serde_json::json!({
                 "model": "claude-3",
                 "messages": [{"role": "user", "content": "hello"}],
@@ -264,6 +271,7 @@ mod tests {
         let request = WsApiRequest {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::Models,
+            session_id: None,
             payload: serde_json::json!({}),
         };
         let result = MessageProcessor::validate_request(&request);
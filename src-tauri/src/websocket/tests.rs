@@ -199,6 +199,38 @@ fn test_ws_connection_manager_increment_request_count() {
     assert_eq!(conn.request_count, 1);
 }
 
+#[test]
+fn test_ws_connection_manager_traffic_and_idle() {
+    let manager = WsConnectionManager::new(WsConfig {
+        idle_timeout_secs: 60,
+        ..Default::default()
+    });
+    manager.register("conn-1".to_string(), None).unwrap();
+
+    manager.record_received("conn-1", 10);
+    manager.record_sent("conn-1", 25);
+    manager.record_sent("conn-1", 5);
+
+    let report = manager.report();
+    assert_eq!(report.connections.len(), 1);
+    assert_eq!(report.connections[0].bytes_received, 10);
+    assert_eq!(report.connections[0].bytes_sent, 30);
+    assert_eq!(report.stats.total_bytes_received, 10);
+    assert_eq!(report.stats.total_bytes_sent, 30);
+    assert!(!manager.is_idle("conn-1"));
+
+    // 回拨最后活动时间模拟空闲
+    if let Some(mut conn) = manager.connections.get_mut("conn-1") {
+        conn.last_activity = chrono::Utc::now() - chrono::Duration::seconds(61);
+    }
+    assert!(manager.is_idle("conn-1"));
+
+    // 未配置空闲超时时不会判定为空闲
+    let unlimited = WsConnectionManager::with_defaults();
+    unlimited.register("conn-2".to_string(), None).unwrap();
+    assert!(!unlimited.is_idle("conn-2"));
+}
+
 #[test]
 fn test_ws_endpoint_serialization() {
     assert_eq!(
@@ -220,6 +252,7 @@ fn test_ws_api_request_serialization() {
     let request = WsApiRequest {
         request_id: "req-123".to_string(),
         endpoint: WsEndpoint::ChatCompletions,
+        session_id: None,
         payload: serde_json::json!({"model": "gpt-4", "messages": []}),
     };
 
@@ -288,6 +321,7 @@ fn arb_api_request() -> impl Strategy<Value = WsApiRequest> {
         .prop_map(|(request_id, endpoint, payload)| WsApiRequest {
             request_id,
             endpoint,
+            session_id: None,
             payload,
         })
 }
@@ -10,6 +10,9 @@ use crate::flow_monitor::models::FlowError;
 use crate::flow_monitor::monitor::{
     FlowEvent, FlowSummary, FlowUpdate, NotificationEvent, ThresholdCheckResult,
 };
+use crate::models::provider_pool_model::HealthCheckProgress;
+use crate::services::credential_expiry_service::CredentialExpiryWarning;
+use crate::telemetry::TokenUsageRecord;
 
 /// WebSocket 连接信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,17 +27,30 @@ pub struct WsConnection {
     pub request_count: u64,
     /// 连接状态
     pub status: WsConnectionStatus,
+    /// 收到的消息字节数
+    #[serde(default)]
+    pub bytes_received: u64,
+    /// 发送的消息字节数
+    #[serde(default)]
+    pub bytes_sent: u64,
+    /// 最后一次收到客户端消息的时间（Ping/Pong 帧不计）
+    #[serde(default = "Utc::now")]
+    pub last_activity: DateTime<Utc>,
 }
 
 impl WsConnection {
     /// 创建新连接
     pub fn new(id: String, client_info: Option<String>) -> Self {
+        let now = Utc::now();
         Self {
             id,
-            connected_at: Utc::now(),
+            connected_at: now,
             client_info,
             request_count: 0,
             status: WsConnectionStatus::Connected,
+            bytes_received: 0,
+            bytes_sent: 0,
+            last_activity: now,
         }
     }
 
@@ -42,6 +58,24 @@ impl WsConnection {
     pub fn increment_request_count(&mut self) {
         self.request_count += 1;
     }
+
+    /// 记录收到的消息
+    pub fn record_received(&mut self, bytes: usize) {
+        self.bytes_received += bytes as u64;
+        self.last_activity = Utc::now();
+    }
+
+    /// 记录发送的消息
+    pub fn record_sent(&mut self, bytes: usize) {
+        self.bytes_sent += bytes as u64;
+    }
+
+    /// 距最后一次收到客户端消息的时间
+    pub fn idle_duration(&self) -> std::time::Duration {
+        (Utc::now() - self.last_activity)
+            .to_std()
+            .unwrap_or_default()
+    }
 }
 
 /// WebSocket 连接状态
@@ -86,6 +120,16 @@ pub enum WsMessage {
     UnsubscribeKiroEvents,
     /// Kiro 凭证状态事件通知
     KiroCredentialEvent(WsKiroEvent),
+    /// 凭证过期预警（推送给所有连接）
+    CredentialExpiryWarning(CredentialExpiryWarning),
+    /// 批量健康检查进度（推送给所有连接）
+    HealthCheckProgress(HealthCheckProgress),
+    /// 订阅实时 Token 使用事件
+    SubscribeTokenUsage,
+    /// 取消订阅实时 Token 使用事件
+    UnsubscribeTokenUsage,
+    /// Token 使用事件通知（每条 Token 使用记录写入时推送）
+    TokenUsage(TokenUsageRecord),
 }
 
 /// WebSocket API 请求
@@ -97,6 +141,9 @@ pub struct WsApiRequest {
     pub endpoint: WsEndpoint,
     /// 请求体（JSON）
     pub payload: serde_json::Value,
+    /// 会话 ID：指定后请求体只需包含新消息，历史消息从对话记录中补齐
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
 }
 
 /// API 端点类型
@@ -222,12 +269,18 @@ pub struct WsConfig {
     /// 是否启用 WebSocket
     #[serde(default = "default_enabled")]
     pub enabled: bool,
-    /// 心跳间隔（秒）
+    /// 心跳间隔（秒，服务端按此间隔发送 Ping，0 表示不发送）
     #[serde(default = "default_heartbeat_interval")]
     pub heartbeat_interval_secs: u64,
     /// 心跳超时（秒）
     #[serde(default = "default_heartbeat_timeout")]
     pub heartbeat_timeout_secs: u64,
+    /// 连续多少次 Ping 未收到 Pong 后断开连接（0 表示不检测）
+    #[serde(default = "default_max_missed_pongs")]
+    pub max_missed_pongs: u32,
+    /// 空闲超时（秒，未收到客户端消息超过该时间后断开，0 表示不限制）
+    #[serde(default)]
+    pub idle_timeout_secs: u64,
     /// 最大连接数
     #[serde(default = "default_max_connections")]
     pub max_connections: usize,
@@ -248,6 +301,10 @@ fn default_heartbeat_timeout() -> u64 {
     60
 }
 
+fn default_max_missed_pongs() -> u32 {
+    2
+}
+
 fn default_max_connections() -> usize {
     100
 }
@@ -262,6 +319,8 @@ impl Default for WsConfig {
             enabled: default_enabled(),
             heartbeat_interval_secs: default_heartbeat_interval(),
             heartbeat_timeout_secs: default_heartbeat_timeout(),
+            max_missed_pongs: default_max_missed_pongs(),
+            idle_timeout_secs: 0,
             max_connections: default_max_connections(),
             max_message_size: default_max_message_size(),
         }
@@ -279,6 +338,14 @@ pub struct WsStats {
     pub total_messages: AtomicU64,
     /// 总错误数
     pub total_errors: AtomicU64,
+    /// 收到的总字节数
+    pub total_bytes_received: AtomicU64,
+    /// 发送的总字节数
+    pub total_bytes_sent: AtomicU64,
+    /// 因空闲超时断开的连接数
+    pub idle_disconnects: AtomicU64,
+    /// 因未响应 Ping 断开的连接数
+    pub ping_timeouts: AtomicU64,
 }
 
 impl WsStats {
@@ -308,6 +375,28 @@ impl WsStats {
         self.total_errors.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// 记录收到的字节数
+    pub fn on_bytes_received(&self, bytes: usize) {
+        self.total_bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// 记录发送的字节数
+    pub fn on_bytes_sent(&self, bytes: usize) {
+        self.total_bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// 记录空闲超时断开
+    pub fn on_idle_disconnect(&self) {
+        self.idle_disconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录 Ping 超时断开
+    pub fn on_ping_timeout(&self) {
+        self.ping_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// 获取活跃连接数
     pub fn active_count(&self) -> u64 {
         self.active_connections.load(Ordering::Relaxed)
@@ -320,17 +409,36 @@ impl WsStats {
             active_connections: self.active_connections.load(Ordering::Relaxed),
             total_messages: self.total_messages.load(Ordering::Relaxed),
             total_errors: self.total_errors.load(Ordering::Relaxed),
+            total_bytes_received: self.total_bytes_received.load(Ordering::Relaxed),
+            total_bytes_sent: self.total_bytes_sent.load(Ordering::Relaxed),
+            idle_disconnects: self.idle_disconnects.load(Ordering::Relaxed),
+            ping_timeouts: self.ping_timeouts.load(Ordering::Relaxed),
         }
     }
 }
 
 /// WebSocket 统计快照（可序列化）
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WsStatsSnapshot {
     pub total_connections: u64,
     pub active_connections: u64,
     pub total_messages: u64,
     pub total_errors: u64,
+    #[serde(default)]
+    pub total_bytes_received: u64,
+    #[serde(default)]
+    pub total_bytes_sent: u64,
+    #[serde(default)]
+    pub idle_disconnects: u64,
+    #[serde(default)]
+    pub ping_timeouts: u64,
+}
+
+/// WebSocket 统计报告（汇总统计和各连接详情）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsStatsReport {
+    pub stats: WsStatsSnapshot,
+    pub connections: Vec<WsConnection>,
 }
 
 /// WebSocket Flow 事件
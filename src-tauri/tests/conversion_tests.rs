@@ -0,0 +1,97 @@
+//! 请求 / 响应转换黄金文件测试
+//!
+//! 每个 Provider 一个目录：`tests/fixtures/conversion/<provider>/*.json`，每个文件一个用例：
+//! - 请求用例：`{"source": "openai" | "anthropic", "request": {...}, "expected": {...}}`
+//! - 响应用例：`{"model": "...", "response": ..., "expected": ...}`
+//!
+//! 转换逻辑有意变更时，设置 `PROXYCAST_BLESS=1` 运行测试，用当前输出重写 `expected`。
+
+use proxycast_lib::convert::{normalize, normalize_response, SourceFormat, TargetProvider};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// 重写黄金文件的环境变量
+const BLESS_ENV: &str = "PROXYCAST_BLESS";
+
+fn fixtures_dir(provider: TargetProvider) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/conversion")
+        .join(provider.as_str())
+}
+
+fn fixture_files(provider: TargetProvider) -> Vec<PathBuf> {
+    let dir = fixtures_dir(provider);
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("无法读取 {}: {}", dir.display(), e))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// 按用例类型执行转换
+fn run_case(provider: TargetProvider, case: &Value) -> Result<Value, String> {
+    if let Some(response) = case.get("response") {
+        let model = case["model"].as_str().ok_or("响应用例缺少 model")?;
+        normalize_response(provider, response, model)
+    } else {
+        let source: SourceFormat = case["source"]
+            .as_str()
+            .ok_or("请求用例缺少 source")?
+            .parse()?;
+        normalize(source, provider, &case["request"])
+    }
+}
+
+#[test]
+fn test_conversion_golden_files() {
+    let bless = std::env::var(BLESS_ENV).is_ok_and(|v| v == "1");
+    let mut failures = Vec::new();
+
+    for provider in TargetProvider::ALL {
+        let files = fixture_files(provider);
+        assert!(
+            !files.is_empty(),
+            "{} 没有黄金文件",
+            fixtures_dir(provider).display()
+        );
+
+        for path in files {
+            let name = path.display().to_string();
+            let mut case: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap())
+                .unwrap_or_else(|e| panic!("{name}: JSON 解析失败: {e}"));
+
+            let actual = match run_case(provider, &case) {
+                Ok(actual) => actual,
+                Err(e) => {
+                    failures.push(format!("{name}: 转换失败: {e}"));
+                    continue;
+                }
+            };
+            if case["expected"] == actual {
+                continue;
+            }
+
+            if bless {
+                case["expected"] = actual;
+                let mut content = serde_json::to_string_pretty(&case).unwrap();
+                content.push('\n');
+                std::fs::write(&path, content).unwrap();
+            } else {
+                failures.push(format!(
+                    "{name}: 输出与黄金文件不一致\nexpected: {}\nactual:   {}",
+                    case["expected"], actual
+                ));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} 个转换用例失败（确认变更符合预期后可用 {}=1 重写黄金文件）：\n\n{}",
+        failures.len(),
+        BLESS_ENV,
+        failures.join("\n\n")
+    );
+}
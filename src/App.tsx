@@ -32,6 +32,7 @@ import { ConnectConfirmDialog } from "./components/connect";
 import { showRegistryLoadError } from "./lib/utils/connectError";
 import { useDeepLink } from "./hooks/useDeepLink";
 import { useRelayRegistry } from "./hooks/useRelayRegistry";
+import { useAlertNotifications } from "./hooks/useAlertNotifications";
 import { ComponentDebugProvider } from "./contexts/ComponentDebugContext";
 import { SoundProvider } from "./contexts/SoundProvider";
 import { ComponentDebugOverlay } from "./components/dev";
@@ -103,6 +104,9 @@ function AppContent() {
     flowEventManager.subscribe();
   }, []);
 
+  // 告警桌面通知
+  useAlertNotifications();
+
   // 处理 Registry 加载失败
   // _Requirements: 7.2, 7.3_
   useEffect(() => {
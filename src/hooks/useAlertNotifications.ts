@@ -0,0 +1,50 @@
+/**
+ * 告警通知 Hook
+ *
+ * 监听后端告警事件（错误率、凭证不可用、磁盘空间、Token 预算），并发送桌面通知。
+ */
+
+import { useEffect } from "react";
+import { safeListen } from "@/lib/dev-bridge";
+import { notificationService } from "@/lib/notificationService";
+
+/** 后端告警事件 */
+export interface AlertEvent {
+  rule: string;
+  kind:
+    | "error_rate"
+    | "all_credentials_unhealthy"
+    | "low_disk_space"
+    | "token_budget";
+  message: string;
+  value: number;
+  threshold: number;
+  triggered_at: string;
+}
+
+export function useAlertNotifications() {
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let disposed = false;
+
+    safeListen<AlertEvent>("alert-triggered", (event) => {
+      notificationService.notify({
+        title: `ProxyCast 告警: ${event.payload.rule}`,
+        body: event.payload.message,
+        type: "warning",
+        tag: `alert-${event.payload.rule}`,
+      });
+    }).then((fn) => {
+      if (disposed) {
+        fn();
+      } else {
+        unlisten = fn;
+      }
+    });
+
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, []);
+}
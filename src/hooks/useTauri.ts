@@ -34,6 +34,7 @@ export interface QuotaExceededConfig {
 export interface AmpModelMapping {
   from: string;
   to: string;
+  provider?: string | null;
 }
 
 // Amp CLI Configuration
@@ -41,6 +42,8 @@ export interface AmpConfig {
   upstream_url: string | null;
   model_mappings: AmpModelMapping[];
   restrict_management_to_localhost: boolean;
+  upstream_timeout_secs: number;
+  upstream_max_retries: number;
 }
 
 // Gemini API Key Entry
@@ -129,12 +132,21 @@ export interface ExperimentalFeatures {
   screenshot_chat: ScreenshotChatConfig;
 }
 
+// 请求体大小限制（单位 MB）
+export interface BodyLimitsConfig {
+  api_mb: number;
+  management_mb: number;
+  amp_proxy_mb: number;
+  audio_mb: number;
+}
+
 export interface Config {
   server: {
     host: string;
     port: number;
     api_key: string;
     tls: TlsConfig;
+    body_limits?: BodyLimitsConfig;
   };
   providers: {
     kiro: {
@@ -179,6 +191,8 @@ export interface LogEntry {
   timestamp: string;
   level: string;
   message: string;
+  /** 所属请求 ID */
+  request_id?: string;
 }
 
 export async function startServer(): Promise<string> {
@@ -231,6 +245,25 @@ export async function updateProviderEnvVars(
   });
 }
 
+/**
+ * 验证响应签名（`x-proxycast-signature` 响应头）
+ *
+ * @param body 响应体原文
+ * @param signature 签名头的值
+ * @param key 可选的签名密钥，未指定时使用当前配置的密钥
+ */
+export async function verifyResponseSignature(
+  body: string,
+  signature: string,
+  key?: string,
+): Promise<boolean> {
+  return safeInvoke("verify_response_signature", {
+    body,
+    signature,
+    key: key || null,
+  });
+}
+
 export async function refreshKiroToken(): Promise<string> {
   return safeInvoke("refresh_kiro_token");
 }
@@ -255,6 +288,14 @@ export async function clearLogs(): Promise<void> {
   }
 }
 
+export async function getTracingFilter(): Promise<string | null> {
+  return safeInvoke("get_tracing_filter");
+}
+
+export async function setTracingFilter(directives: string): Promise<string> {
+  return safeInvoke("set_tracing_filter", { directives });
+}
+
 export interface TestResult {
   success: boolean;
   status: number;
@@ -3,6 +3,15 @@ import { safeInvoke } from "@/lib/dev-bridge";
 // Injection mode
 export type InjectionMode = "merge" | "override";
 
+// Injection rule conditions (all configured conditions must match)
+export interface InjectionConditions {
+  providers?: string[];
+  app_types?: string[];
+  paths?: string[];
+  headers?: Record<string, string>;
+  message_regex?: string;
+}
+
 // Injection rule
 export interface InjectionRule {
   id: string;
@@ -11,6 +20,9 @@ export interface InjectionRule {
   mode: InjectionMode;
   priority: number;
   enabled: boolean;
+  conditions?: InjectionConditions;
+  remove?: string[];
+  caps?: Record<string, number>;
 }
 
 // Injection configuration
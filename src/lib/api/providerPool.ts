@@ -1,4 +1,5 @@
 import { safeInvoke } from "@/lib/dev-bridge";
+import type { CredentialQuota } from "./usage";
 
 // Provider types supported by the pool
 export type PoolProviderType =
@@ -113,6 +114,23 @@ export interface ProviderCredential {
 // Credential source type
 export type CredentialSource = "manual" | "imported" | "private";
 
+// Credential priority tier
+export type CredentialTier = "primary" | "overflow";
+
+// Credential scheduling window (UTC, "HH:MM", end exclusive)
+export interface ScheduleWindow {
+  start: string;
+  end: string;
+}
+
+// Credential scheduling rules
+export interface CredentialSchedule {
+  windows: ScheduleWindow[];
+  daily_request_limit?: number;
+  // 每日计数重置时间（UTC，"HH:MM"）
+  reset_time: string;
+}
+
 // Credential display (for UI, hides sensitive data)
 export interface CredentialDisplay {
   uuid: string;
@@ -134,6 +152,7 @@ export interface CredentialDisplay {
   last_health_check_model?: string;
   oauth_status?: OAuthStatus;
   token_cache_status?: TokenCacheStatus;
+  expiry_status?: CredentialExpiryStatus;
   created_at: string;
   updated_at: string;
   // 凭证来源（手动添加/导入/私有）
@@ -144,6 +163,20 @@ export interface CredentialDisplay {
   api_key?: string;
   // 凭证级代理 URL（可覆盖全局代理设置）
   proxy_url?: string;
+  // 优先级分层（主力/溢出）
+  tier?: CredentialTier;
+  // 调度规则（时间窗口、每日请求次数）
+  schedule?: CredentialSchedule;
+  // 当日请求次数
+  daily_usage_count?: number;
+  // 根据上游错误自动加入不支持列表的模型及其到期时间（到期后重新尝试）
+  learned_unsupported_models?: Record<string, string>;
+  // 标签（用于 tag:<name> 路由选择器）
+  tags?: string[];
+  // 固定模型（设置后该凭证始终以此模型请求上游）
+  pinned_model?: string;
+  // 最近一次额度查询结果
+  quota?: CredentialQuota;
 }
 
 // Pool statistics
@@ -172,6 +205,31 @@ export interface HealthCheckResult {
   duration_ms: number;
 }
 
+// Progress of a batch health check, emitted as "provider-pool-health-progress"
+export interface HealthCheckProgress {
+  provider_type: string;
+  completed: number;
+  total: number;
+  result: HealthCheckResult;
+}
+
+// 本机 CLI 工具中扫描到的凭证
+export interface DetectedCredential {
+  provider_type: string;
+  source: string;
+  path: string;
+  fingerprint: string;
+  already_imported: boolean;
+}
+
+export interface CredentialImportResult {
+  provider_type: string;
+  path: string;
+  status: "imported" | "skipped" | "failed";
+  uuid?: string;
+  message?: string;
+}
+
 // OAuth status
 export interface OAuthStatus {
   has_access_token: boolean;
@@ -192,6 +250,16 @@ export interface TokenCacheStatus {
   last_refresh_error?: string;
 }
 
+// Credential expiry status (refresh token lifetime / refresh failures)
+export type ExpiryState = "ok" | "expiring_soon" | "expired" | "refresh_failing";
+
+export interface CredentialExpiryStatus {
+  state: ExpiryState;
+  last_successful_refresh?: string;
+  refresh_token_expiry?: string;
+  days_until_expiry?: number;
+}
+
 // Request types
 export interface AddCredentialRequest {
   provider_type: string;
@@ -217,6 +285,44 @@ export interface UpdateCredentialRequest {
   new_api_key?: string;
   /// 新的代理 URL（可覆盖全局代理设置）
   new_proxy_url?: string;
+  /// 新的优先级分层
+  tier?: CredentialTier;
+  /// 新的调度规则（无时间窗口且无次数上限表示清除）
+  schedule?: CredentialSchedule;
+  /// 新的标签列表（空列表表示清除）
+  tags?: string[];
+  /// 新的固定模型（空字符串表示清除）
+  pinned_model?: string;
+}
+
+// 复制凭证时覆盖的字段（未指定的字段沿用原凭证）
+export interface DuplicateCredentialRequest {
+  /// 副本名称（未指定时为"原名称 (copy)"）
+  name?: string;
+  is_disabled?: boolean;
+  check_health?: boolean;
+  check_model_name?: string;
+  not_supported_models?: string[];
+  proxy_url?: string;
+  tier?: CredentialTier;
+  schedule?: CredentialSchedule;
+  tags?: string[];
+  pinned_model?: string;
+}
+
+// 批量操作的凭证筛选条件（都不指定时匹配所有凭证）
+export interface BulkCredentialFilter {
+  provider_type?: PoolProviderType;
+  tag?: string;
+}
+
+// 批量操作中单个凭证的结果
+export interface BulkOperationResult {
+  uuid: string;
+  name?: string;
+  provider_type: string;
+  success: boolean;
+  message?: string;
 }
 
 export const providerPoolApi = {
@@ -284,8 +390,61 @@ export const providerPoolApi = {
   // Check health of all credentials of a type
   async checkTypeHealth(
     providerType: PoolProviderType,
+    concurrency?: number,
+  ): Promise<HealthCheckResult[]> {
+    return safeInvoke("check_provider_pool_type_health", {
+      providerType,
+      concurrency,
+    });
+  },
+
+  // Duplicate a credential with optional overrides
+  async duplicateCredential(
+    uuid: string,
+    overrides?: DuplicateCredentialRequest,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("duplicate_provider_pool_credential", {
+      uuid,
+      overrides,
+    });
+  },
+
+  // Bulk operations
+  async bulkSetDisabled(
+    filter: BulkCredentialFilter,
+    isDisabled: boolean,
+  ): Promise<BulkOperationResult[]> {
+    return safeInvoke("bulk_set_provider_pool_disabled", {
+      filter,
+      isDisabled,
+    });
+  },
+
+  async bulkDeleteUnhealthy(
+    filter?: BulkCredentialFilter,
+  ): Promise<BulkOperationResult[]> {
+    return safeInvoke("bulk_delete_unhealthy_provider_pool_credentials", {
+      filter,
+    });
+  },
+
+  async bulkCheckHealth(
+    filter?: BulkCredentialFilter,
+    concurrency?: number,
   ): Promise<HealthCheckResult[]> {
-    return safeInvoke("check_provider_pool_type_health", { providerType });
+    return safeInvoke("bulk_check_provider_pool_health", {
+      filter,
+      concurrency,
+    });
+  },
+
+  // Credential import
+  async detectImportable(): Promise<DetectedCredential[]> {
+    return safeInvoke("detect_importable_credentials");
+  },
+
+  async importDetected(paths?: string[]): Promise<CredentialImportResult[]> {
+    return safeInvoke("import_detected_credentials", { paths });
   },
 
   // Provider-specific add methods
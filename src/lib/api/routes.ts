@@ -26,6 +26,25 @@ export interface CurlExample {
   command: string;
 }
 
+export interface ClientConfig {
+  // claude_code | cline | zed | env
+  client: string;
+  target: string;
+  content: string;
+}
+
+export interface RouteClientConfigs {
+  selector: string;
+  provider_type: string;
+  configs: ClientConfig[];
+}
+
+export interface RouteBundle {
+  base_url: string;
+  default_provider: string;
+  routes: RouteClientConfigs[];
+}
+
 export const routesApi = {
   async getAvailableRoutes(): Promise<RouteListResponse> {
     return safeInvoke("get_available_routes");
@@ -34,4 +53,8 @@ export const routesApi = {
   async getCurlExamples(selector: string): Promise<CurlExample[]> {
     return safeInvoke("get_route_curl_examples", { selector });
   },
+
+  async getClientBundle(includeApiKey = false): Promise<RouteBundle> {
+    return safeInvoke("get_route_client_bundle", { includeApiKey });
+  },
 };
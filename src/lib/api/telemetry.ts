@@ -24,6 +24,10 @@ export interface RequestLog {
   is_streaming: boolean;
   credential_id?: string;
   retry_count: number;
+  /** 上下文窗口使用百分比（Kiro 非流式响应） */
+  context_usage_percentage?: number;
+  /** 下游终端用户 ID */
+  user_id?: string;
 }
 
 export interface StatsSummary {
@@ -105,6 +109,18 @@ export interface ModelTokenStats {
   avg_output_tokens: number;
 }
 
+export interface UserTokenStats {
+  user_id: string;
+  total_input_tokens: number;
+  total_output_tokens: number;
+  total_tokens: number;
+  record_count: number;
+  actual_count: number;
+  estimated_count: number;
+  avg_input_tokens: number;
+  avg_output_tokens: number;
+}
+
 export interface PeriodTokenStats {
   period_start?: string;
   period_end?: string;
@@ -124,6 +140,41 @@ export interface TimeRangeParam {
   preset?: "1h" | "24h" | "7d" | "30d";
 }
 
+export type RollupGranularity = "hour" | "day";
+
+export interface TelemetryRollup {
+  granularity: RollupGranularity;
+  /** 桶起始时间（Unix 秒） */
+  bucket_start: number;
+  provider: string;
+  model: string;
+  request_count: number;
+  success_count: number;
+  failed_count: number;
+  timeout_count: number;
+  total_duration_ms: number;
+  input_tokens: number;
+  output_tokens: number;
+}
+
+export interface UserUsage {
+  user_id: string;
+  request_count: number;
+  success_count: number;
+  failed_count: number;
+  input_tokens: number;
+  output_tokens: number;
+}
+
+export interface RollupQuery {
+  /** 起始时间（Unix 秒，含） */
+  start?: number;
+  /** 结束时间（Unix 秒，不含） */
+  end?: number;
+  provider?: string;
+  model?: string;
+}
+
 // ========== 请求日志 API ==========
 
 export async function getRequestLogs(params?: {
@@ -185,8 +236,37 @@ export async function getTokenStatsByModel(
   return safeInvoke("get_token_stats_by_model", { time_range: timeRange });
 }
 
+export async function getTokenStatsByUser(
+  timeRange?: TimeRangeParam,
+): Promise<Record<string, UserTokenStats>> {
+  return safeInvoke("get_token_stats_by_user", { time_range: timeRange });
+}
+
 export async function getTokenStatsByDay(
   days?: number,
 ): Promise<PeriodTokenStats[]> {
   return safeInvoke("get_token_stats_by_day", { days });
 }
+
+// ========== 历史统计 API（SQLite 持久化） ==========
+
+export async function getTelemetryHistory(
+  granularity: RollupGranularity,
+  query?: RollupQuery,
+): Promise<TelemetryRollup[]> {
+  return safeInvoke("get_telemetry_history", { granularity, query });
+}
+
+export async function getRequestLogHistory(
+  query?: RollupQuery,
+  limit?: number,
+): Promise<RequestLog[]> {
+  return safeInvoke("get_request_log_history", { query, limit });
+}
+
+export async function getUserUsageHistory(
+  query?: RollupQuery,
+  limit?: number,
+): Promise<UserUsage[]> {
+  return safeInvoke("get_user_usage_history", { query, limit });
+}
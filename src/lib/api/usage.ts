@@ -19,6 +19,37 @@ export interface UsageInfo {
   isLowBalance: boolean;
 }
 
+/** 分项额度（如 Gemini 按模型的额度） */
+export interface QuotaBucket {
+  name: string;
+  /** 剩余比例（0 ~ 1） */
+  remaining_fraction?: number;
+  reset_at?: string;
+}
+
+/** 额度信息 */
+export interface QuotaInfo {
+  /** 额度单位（credits、USD、CNY 等） */
+  unit?: string;
+  plan?: string;
+  limit?: number;
+  used?: number;
+  remaining?: number;
+  /** 剩余比例（0 ~ 1） */
+  remaining_fraction?: number;
+  reset_at?: string;
+  buckets?: QuotaBucket[];
+}
+
+/** 凭证额度查询结果 */
+export interface CredentialQuota {
+  credential_uuid: string;
+  provider_type: string;
+  quota?: QuotaInfo;
+  error?: string;
+  fetched_at: string;
+}
+
 /**
  * Usage API
  */
@@ -31,4 +62,16 @@ export const usageApi = {
    */
   getKiroUsage: (credentialUuid: string): Promise<UsageInfo> =>
     safeInvoke("get_kiro_usage", { credentialUuid }),
+
+  /**
+   * 查询凭证剩余额度
+   *
+   * @param credentialUuid - 凭证的 UUID
+   * @param refresh - 是否忽略缓存重新查询
+   */
+  getCredentialQuota: (
+    credentialUuid: string,
+    refresh = false,
+  ): Promise<CredentialQuota> =>
+    safeInvoke("get_credential_quota", { credentialUuid, refresh }),
 };
@@ -18,6 +18,12 @@ const defaultMocks: Record<string, any> = {
         cert_path: null,
         key_path: null,
       },
+      body_limits: {
+        api_mb: 100,
+        management_mb: 1,
+        amp_proxy_mb: 10,
+        audio_mb: 25,
+      },
     },
     providers: {
       kiro: {
@@ -59,6 +65,8 @@ const defaultMocks: Record<string, any> = {
       upstream_url: null,
       model_mappings: [],
       restrict_management_to_localhost: true,
+      upstream_timeout_secs: 30,
+      upstream_max_retries: 1,
     },
     credential_pool: {
       kiro: [],
@@ -397,11 +405,16 @@ const defaultMocks: Record<string, any> = {
   get_token_summary: () => ({ summary: {} }),
   get_token_stats_by_provider: () => ({ stats: [] }),
   get_token_stats_by_model: () => ({ stats: [] }),
+  get_token_stats_by_user: () => ({ stats: [] }),
   get_token_stats_by_day: () => ({ stats: [] }),
+  get_telemetry_history: () => [],
+  get_request_log_history: () => [],
+  get_user_usage_history: () => [],
 
   // Routes 相关
   get_available_routes: () => ({ routes: [] }),
   get_route_curl_examples: () => ({ examples: [] }),
+  get_route_client_bundle: () => ({ routes: [] }),
 
   // Prompts 相关
   get_prompts: () => [],
@@ -451,6 +464,7 @@ const defaultMocks: Record<string, any> = {
 
   // Usage 相关
   get_kiro_usage: () => ({ usage: {} }),
+  get_credential_quota: () => ({ quota: null }),
 
   // Resilience 相关
   get_retry_config: () => ({ config: {} }),
@@ -531,6 +545,8 @@ const defaultMocks: Record<string, any> = {
   // Log 相关
   get_logs: () => [],
   clear_logs: () => ({}),
+  get_tracing_filter: () => "info",
+  set_tracing_filter: (args: any) => args?.directives ?? "info",
 
   // Test 相关
   test_api: () => ({ success: true, status: 200, body: "", time_ms: 0 }),